@@ -52,14 +52,6 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                                 .takes_value(true)
                                 .help("The password file to unlock the eth1 account (see --index)"),
                         )
-                        .arg(
-                            Arg::with_name("testnet-dir")
-                                .long("testnet-dir")
-                                .value_name("DIRECTORY")
-                                .takes_value(true)
-                                .help("The directory from which to read the deposit contract /
-                                       address. Defaults to the current Lighthouse testnet."),
-                        )
                         .subcommand(
                             SubCommand::with_name("insecure")
                                 .about("Produce insecure, ephemeral validators. DO NOT USE TO STORE VALUE.")
@@ -94,4 +86,155 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                         )
                 )
         )
+        .subcommand(
+            SubCommand::with_name("wallet")
+                .about("Create or use EIP-2386 hierarchical deterministic wallets.")
+                .arg(
+                    Arg::with_name("wallets-dir")
+                        .long("wallets-dir")
+                        .value_name("DIRECTORY")
+                        .takes_value(true)
+                        .help("The directory containing wallets. Defaults to the `wallets` directory \
+                               beside the default `--datadir`."),
+                )
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create a new wallet from a new or existing BIP-39 mnemonic.")
+                        .arg(
+                            Arg::with_name("name")
+                                .long("name")
+                                .value_name("WALLET_NAME")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The name of the wallet. Must be unique within the wallets directory."),
+                        )
+                        .arg(
+                            Arg::with_name("password-file")
+                                .long("password-file")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .required(true)
+                                .help("A file containing the password used to encrypt the wallet."),
+                        )
+                        .arg(
+                            Arg::with_name("mnemonic")
+                                .long("mnemonic")
+                                .value_name("PHRASE")
+                                .takes_value(true)
+                                .help("Recover the wallet from an existing BIP-39 mnemonic. If not \
+                                       supplied, a new 24 word mnemonic is generated and printed."),
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("validator")
+                        .about("Derive new validators from a wallet, producing EIP-2335 keystores \
+                                and a deposit data file for the Eth2 launchpad.")
+                        .arg(
+                            Arg::with_name("wallet-name")
+                                .long("wallet-name")
+                                .value_name("WALLET_NAME")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The name of the wallet from which to derive validators."),
+                        )
+                        .arg(
+                            Arg::with_name("wallet-password")
+                                .long("wallet-password")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .required(true)
+                                .help("A file containing the password which unlocks the wallet."),
+                        )
+                        .arg(
+                            Arg::with_name("secrets-dir")
+                                .long("secrets-dir")
+                                .value_name("DIRECTORY")
+                                .takes_value(true)
+                                .help("The directory in which to store the keystore passwords. \
+                                       Defaults to the `secrets` directory beside the default \
+                                       `--datadir`."),
+                        )
+                        .arg(
+                            Arg::with_name("count")
+                                .long("count")
+                                .value_name("INTEGER")
+                                .takes_value(true)
+                                .default_value("1")
+                                .help("The number of validators to derive."),
+                        )
+                        .arg(
+                            Arg::with_name("deposit-value")
+                                .long("deposit-value")
+                                .value_name("GWEI")
+                                .takes_value(true)
+                                .default_value("32000000000")
+                                .help("The deposit amount in Gwei (not Wei). Default is 32 ETH."),
+                        )
+                        .arg(
+                            Arg::with_name("scrypt-n")
+                                .long("scrypt-n")
+                                .value_name("INTEGER")
+                                .takes_value(true)
+                                .help("The scrypt cost parameter used to encrypt the keystores. \
+                                       Must be a power of two. Lower values make the validator \
+                                       client start faster, but make the keystores easier to \
+                                       brute-force, so should only be used on testnets. \
+                                       Defaults to 262144."),
+                        )
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Submit the deposits in a deposit data file (e.g., from `wallet validator`) \
+                        to the deposit contract, then wait for them to be confirmed.")
+                .arg(
+                    Arg::with_name("deposit-data")
+                        .long("deposit-data")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The JSON deposit data file. The submitted transactions are recorded \
+                               beside it so that no deposit is submitted twice."),
+                )
+                .arg(
+                    Arg::with_name("eth1-endpoint")
+                        .long("eth1-endpoint")
+                        .value_name("HTTP_SERVER")
+                        .takes_value(true)
+                        .default_value("http://localhost:8545")
+                        .help("The URL to the eth1 JSON-RPC http API."),
+                )
+                .arg(
+                    Arg::with_name("eth1-private-key")
+                        .long("eth1-private-key")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required_unless("offline")
+                        .help("A file containing the hex-encoded private key of the eth1 account \
+                               which pays for the deposits. Transactions are signed locally."),
+                )
+                .arg(
+                    Arg::with_name("offline")
+                        .long("offline")
+                        .conflicts_with("eth1-private-key")
+                        .help("Print the unsigned deposit transactions as JSON, so they may be \
+                               signed offline, instead of submitting them."),
+                )
+                .arg(
+                    Arg::with_name("gas-price")
+                        .long("gas-price")
+                        .value_name("GWEI")
+                        .takes_value(true)
+                        .help("The gas price in Gwei. Defaults to the gas price of the eth1 node."),
+                )
+                .arg(
+                    Arg::with_name("confirmations")
+                        .long("confirmations")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("The number of blocks (including its own) which must contain or \
+                               follow each deposit before it is considered confirmed."),
+                )
+        )
 }
@@ -0,0 +1,557 @@
+//! Provides the `deposit` subcommand, which submits the deposits in a launchpad deposit data file
+//! to the deposit contract and waits for them to be confirmed.
+//!
+//! Transactions are signed locally with the private key of an eth1 account, so the eth1 node does
+//! not need to manage any accounts. Alternatively, the unsigned transactions can be printed so
+//! they may be signed offline.
+use crate::{from_gwei, wallet::LaunchpadDeposit};
+use clap::ArgMatches;
+use deposit_contract::{eth1_tx_data, DEPOSIT_GAS};
+use environment::Environment;
+use eth2_testnet_config::Eth2TestnetConfig;
+use serde_derive::Serialize;
+use slog::{info, Logger};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tree_hash::TreeHash;
+use types::{DepositData, EthSpec, Hash256, PublicKeyBytes, SignatureBytes};
+use web3::{
+    transports::Http,
+    types::{Address, BlockNumber, Bytes, CallRequest, H256, U256},
+    Web3,
+};
+
+/// The time between checks for the confirmation of the submitted transactions.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// The percentage added to the gas estimate of each deposit, in case the estimate is too low.
+const GAS_ESTIMATE_MARGIN_PERCENT: u64 = 20;
+
+/// A deposit transaction which has not been signed, for signing offline.
+#[derive(Serialize)]
+pub struct UnsignedDepositTransaction {
+    pub validator_pubkey: String,
+    pub to: String,
+    /// The value of the transaction in Wei, as a decimal string.
+    pub value: String,
+    pub gas: u64,
+    pub data: String,
+}
+
+/// Process the `deposit` subcommand.
+pub fn run_deposit_subcommand<T: EthSpec>(
+    matches: &ArgMatches,
+    mut env: Environment<T>,
+) -> Result<(), String> {
+    let context = env.core_context();
+    let log = context.log.clone();
+    let spec = &context.eth2_config.spec;
+
+    let deposit_data_path = matches
+        .value_of("deposit-data")
+        .map(PathBuf::from)
+        .ok_or_else(|| "No deposit-data supplied".to_string())?;
+    let deposits = load_deposits(&deposit_data_path, spec.genesis_fork.current_version)?;
+
+    let deposit_contract = deposit_contract_address::<T>(matches)?;
+
+    if matches.is_present("offline") {
+        let transactions = deposits
+            .iter()
+            .map(|deposit| {
+                Ok(UnsignedDepositTransaction {
+                    validator_pubkey: format!("0x{}", hex::encode(deposit.pubkey.as_bytes())),
+                    to: format!("{:?}", deposit_contract),
+                    value: from_gwei(deposit.amount).to_string(),
+                    gas: DEPOSIT_GAS as u64,
+                    data: format!("0x{}", hex::encode(deposit_tx_data(deposit)?)),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let json = serde_json::to_string_pretty(&transactions)
+            .map_err(|e| format!("Unable to encode transactions: {:?}", e))?;
+        println!("{}", json);
+
+        return Ok(());
+    }
+
+    let eth1_endpoint = matches
+        .value_of("eth1-endpoint")
+        .ok_or_else(|| "No eth1-endpoint".to_string())?;
+    let required_confirmations = matches
+        .value_of("confirmations")
+        .ok_or_else(|| "No confirmations".to_string())?
+        .parse::<u64>()
+        .map_err(|e| format!("Unable to parse confirmations: {}", e))?;
+    let secret_key = load_eth1_secret_key(
+        matches
+            .value_of("eth1-private-key")
+            .ok_or_else(|| "No eth1-private-key supplied".to_string())?,
+    )?;
+    let from = eth1_address(&secret_key);
+
+    let (_event_loop, transport) =
+        Http::new(eth1_endpoint).map_err(|e| format!("Failed to start web3 transport: {:?}", e))?;
+    let web3 = Web3::new(transport);
+
+    let chain_id = env
+        .runtime()
+        .block_on(web3.net().version())
+        .map_err(|e| format!("Unable to get eth1 chain id: {:?}", e))?
+        .parse::<u64>()
+        .map_err(|e| format!("Unable to parse eth1 chain id: {}", e))?;
+    let gas_price = if let Some(gwei) = matches.value_of("gas-price") {
+        gwei.parse::<u64>()
+            .map(from_gwei)
+            .map_err(|e| format!("Unable to parse gas-price: {}", e))?
+    } else {
+        env.runtime()
+            .block_on(web3.eth().gas_price())
+            .map_err(|e| format!("Unable to get gas price: {:?}", e))?
+    };
+    let mut nonce = env
+        .runtime()
+        .block_on(
+            web3.eth()
+                .transaction_count(from, Some(BlockNumber::Pending)),
+        )
+        .map_err(|e| format!("Unable to get account nonce: {:?}", e))?;
+
+    info!(
+        log,
+        "Submitting validator deposits";
+        "eth1_node_http_endpoint" => eth1_endpoint,
+        "from" => format!("{:?}", from),
+        "chain_id" => chain_id,
+        "gas_price_gwei" => (gas_price / U256::exp10(9)).as_u64(),
+        "count" => deposits.len(),
+    );
+
+    // The transactions are recorded as they are submitted, so running the command again will
+    // never submit a deposit twice.
+    let record_path = submitted_record_path(&deposit_data_path);
+    let mut submitted = load_submitted(&record_path)?;
+
+    for deposit in &deposits {
+        let pubkey = format!("0x{}", hex::encode(deposit.pubkey.as_bytes()));
+
+        if let Some(tx_hash) = submitted.get(&pubkey) {
+            info!(
+                log,
+                "Deposit already submitted";
+                "eth1_tx_hash" => tx_hash,
+                "validator_voting_pubkey" => &pubkey,
+            );
+            continue;
+        }
+
+        let data = deposit_tx_data(deposit)?;
+        let value = from_gwei(deposit.amount);
+
+        // Estimating the gas also checks that the deposit will not revert (e.g., if the account
+        // has insufficient funds).
+        let gas_estimate = env
+            .runtime()
+            .block_on(web3.eth().estimate_gas(
+                CallRequest {
+                    from: Some(from),
+                    to: deposit_contract,
+                    gas: None,
+                    gas_price: Some(gas_price),
+                    value: Some(value),
+                    data: Some(Bytes(data.clone())),
+                },
+                None,
+            ))
+            .map_err(|e| format!("Unable to estimate gas for deposit of {}: {:?}", pubkey, e))?;
+        let gas = gas_estimate + gas_estimate * GAS_ESTIMATE_MARGIN_PERCENT / 100;
+
+        let raw_tx = Eth1Transaction {
+            nonce,
+            gas_price,
+            gas,
+            to: deposit_contract,
+            value,
+            data,
+        }
+        .sign(&secret_key, chain_id);
+
+        let tx_hash = env
+            .runtime()
+            .block_on(web3.eth().send_raw_transaction(Bytes(raw_tx)))
+            .map_err(|e| format!("Failed to submit deposit of {}: {:?}", pubkey, e))?;
+
+        info!(
+            log,
+            "Submitted validator deposit";
+            "eth1_tx_hash" => format!("{:?}", tx_hash),
+            "validator_voting_pubkey" => &pubkey,
+        );
+
+        submitted.insert(pubkey, format!("{:?}", tx_hash));
+        save_submitted(&record_path, &submitted)?;
+        nonce += U256::one();
+    }
+
+    wait_for_confirmations(
+        &mut env,
+        &web3,
+        deposits
+            .iter()
+            .filter_map(|deposit| {
+                submitted.get(&format!("0x{}", hex::encode(deposit.pubkey.as_bytes())))
+            })
+            .map(|tx_hash| decode_hex(tx_hash).map(|bytes| H256::from_slice(&bytes)))
+            .collect::<Result<_, _>>()?,
+        required_confirmations,
+        &log,
+    )?;
+
+    info!(
+        log,
+        "Validator deposits complete";
+        "count" => deposits.len(),
+        "confirmations" => required_confirmations,
+    );
+
+    Ok(())
+}
+
+/// Polls the eth1 node until each of the `transactions` has been included in a block with at
+/// least `required_confirmations` blocks on top of it (including its own block).
+///
+/// Returns an error if any of the transactions reverted.
+fn wait_for_confirmations<T: EthSpec>(
+    env: &mut Environment<T>,
+    web3: &Web3<Http>,
+    mut transactions: Vec<H256>,
+    required_confirmations: u64,
+    log: &Logger,
+) -> Result<(), String> {
+    while !transactions.is_empty() {
+        let head = env
+            .runtime()
+            .block_on(web3.eth().block_number())
+            .map_err(|e| format!("Unable to get eth1 block number: {:?}", e))?
+            .as_u64();
+
+        let mut pending = vec![];
+        for tx_hash in transactions {
+            let receipt = env
+                .runtime()
+                .block_on(web3.eth().transaction_receipt(tx_hash))
+                .map_err(|e| format!("Unable to get receipt for {:?}: {:?}", tx_hash, e))?;
+
+            let block_number = match receipt {
+                Some(receipt) => {
+                    if receipt.status.map_or(false, |status| status.is_zero()) {
+                        return Err(format!("Deposit transaction {:?} reverted", tx_hash));
+                    }
+                    receipt.block_number.map(|number| number.as_u64())
+                }
+                None => None,
+            };
+
+            let confirmations = block_number.map_or(0, |number| head.saturating_sub(number) + 1);
+            if confirmations < required_confirmations {
+                pending.push(tx_hash);
+            } else {
+                info!(
+                    log,
+                    "Deposit confirmed";
+                    "eth1_tx_hash" => format!("{:?}", tx_hash),
+                    "confirmations" => confirmations,
+                );
+            }
+        }
+        transactions = pending;
+
+        if !transactions.is_empty() {
+            info!(
+                log,
+                "Waiting for deposit confirmations";
+                "pending" => transactions.len(),
+                "eth1_head" => head,
+            );
+            thread::sleep(CONFIRMATION_POLL_INTERVAL);
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the deposits in the launchpad deposit data file at `path`, checking that their deposit
+/// data roots are correct and that they were signed for the `fork_version` of this network.
+fn load_deposits(path: &Path, fork_version: [u8; 4]) -> Result<Vec<DepositData>, String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+    let deposits: Vec<LaunchpadDeposit> = serde_json::from_reader(file)
+        .map_err(|e| format!("Unable to parse deposit data file {:?}: {:?}", path, e))?;
+
+    deposits
+        .iter()
+        .map(|deposit| deposit_data(deposit, fork_version))
+        .collect()
+}
+
+/// Converts a `LaunchpadDeposit` into `DepositData`, checking it as described in `load_deposits`.
+fn deposit_data(deposit: &LaunchpadDeposit, fork_version: [u8; 4]) -> Result<DepositData, String> {
+    if decode_hex(&deposit.fork_version)? != fork_version {
+        return Err(format!(
+            "Deposit of {} is for fork version {}, not 0x{}. Check the --testnet-dir.",
+            deposit.pubkey,
+            deposit.fork_version,
+            hex::encode(fork_version)
+        ));
+    }
+
+    let withdrawal_credentials = decode_hex(&deposit.withdrawal_credentials)?;
+    if withdrawal_credentials.len() != 32 {
+        return Err(format!(
+            "Invalid withdrawal credentials for {}",
+            deposit.pubkey
+        ));
+    }
+
+    let deposit_data = DepositData {
+        pubkey: PublicKeyBytes::from_bytes(&decode_hex(&deposit.pubkey)?)
+            .map_err(|e| format!("Invalid pubkey {}: {:?}", deposit.pubkey, e))?,
+        withdrawal_credentials: Hash256::from_slice(&withdrawal_credentials),
+        amount: deposit.amount,
+        signature: SignatureBytes::from_bytes(&decode_hex(&deposit.signature)?)
+            .map_err(|e| format!("Invalid signature for {}: {:?}", deposit.pubkey, e))?,
+    };
+
+    if deposit_data.tree_hash_root() != decode_hex(&deposit.deposit_data_root)? {
+        return Err(format!(
+            "Deposit data root of {} does not match its deposit data",
+            deposit.pubkey
+        ));
+    }
+
+    Ok(deposit_data)
+}
+
+/// Returns the data of the deposit contract call for `deposit`.
+fn deposit_tx_data(deposit: &DepositData) -> Result<Vec<u8>, String> {
+    eth1_tx_data(deposit).map_err(|e| format!("Unable to encode deposit: {:?}", e))
+}
+
+/// Returns the deposit contract address of the `--testnet-dir`, or of the default testnet.
+fn deposit_contract_address<T: EthSpec>(matches: &ArgMatches) -> Result<Address, String> {
+    let eth2_testnet_config: Eth2TestnetConfig<T> =
+        if let Some(testnet_dir) = matches.value_of("testnet-dir").map(PathBuf::from) {
+            Eth2TestnetConfig::load(testnet_dir.clone())
+                .map_err(|e| format!("Failed to load testnet dir at {:?}: {}", testnet_dir, e))?
+        } else {
+            Eth2TestnetConfig::hard_coded()
+                .map_err(|e| format!("Failed to load hard_coded testnet dir: {}", e))?
+        };
+
+    // Convert from `types::Address` to `web3::types::Address`.
+    Ok(Address::from_slice(
+        eth2_testnet_config
+            .deposit_contract_address()?
+            .as_fixed_bytes(),
+    ))
+}
+
+/// Returns the path of the file which records the transactions submitted for the deposit data
+/// file at `deposit_data_path`.
+fn submitted_record_path(deposit_data_path: &Path) -> PathBuf {
+    let mut file_name = deposit_data_path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    file_name.push(".submitted");
+    deposit_data_path.with_file_name(file_name)
+}
+
+/// Loads the map of validator pubkey to transaction hash at `path`, if it exists.
+fn load_submitted(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+    serde_json::from_reader(file).map_err(|e| format!("Unable to parse {:?}: {:?}", path, e))
+}
+
+/// Writes the map of validator pubkey to transaction hash to `path`, replacing any existing file.
+fn save_submitted(path: &Path, submitted: &BTreeMap<String, String>) -> Result<(), String> {
+    let temp_path = path.with_extension("tmp");
+
+    let file =
+        File::create(&temp_path).map_err(|e| format!("Unable to create {:?}: {}", temp_path, e))?;
+    serde_json::to_writer_pretty(file, submitted)
+        .map_err(|e| format!("Unable to write {:?}: {:?}", temp_path, e))?;
+
+    fs::rename(&temp_path, path).map_err(|e| format!("Unable to save {:?}: {}", path, e))
+}
+
+/// Loads a hex-encoded secp256k1 private key from the file at `path`.
+fn load_eth1_secret_key(path: &str) -> Result<secp256k1::SecretKey, String> {
+    let hex = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read eth1 private key file {}: {}", path, e))?;
+    let bytes = decode_hex(hex.trim())?;
+
+    secp256k1::SecretKey::parse_slice(&bytes)
+        .map_err(|e| format!("Invalid eth1 private key in {}: {:?}", path, e))
+}
+
+/// Returns the eth1 address of the account with `secret_key`.
+fn eth1_address(secret_key: &secp256k1::SecretKey) -> Address {
+    let public_key = secp256k1::PublicKey::from_secret_key(secret_key).serialize();
+    // The address is the last 20 bytes of the hash of the uncompressed key, without its prefix.
+    Address::from_slice(&tiny_keccak::keccak256(&public_key[1..])[12..])
+}
+
+/// Decodes a hex string, with or without a `0x` prefix.
+fn decode_hex(string: &str) -> Result<Vec<u8>, String> {
+    let string = string.trim_start_matches("0x");
+    hex::decode(string).map_err(|e| format!("Invalid hex {}: {:?}", string, e))
+}
+
+/// A legacy eth1 transaction.
+struct Eth1Transaction {
+    nonce: U256,
+    gas_price: U256,
+    gas: U256,
+    to: Address,
+    value: U256,
+    data: Vec<u8>,
+}
+
+impl Eth1Transaction {
+    /// Returns the RLP-encoded transaction, signed with `secret_key` for the eth1 chain with
+    /// `chain_id` (as per EIP-155).
+    fn sign(&self, secret_key: &secp256k1::SecretKey, chain_id: u64) -> Vec<u8> {
+        let mut fields = self.fields();
+        fields.extend_from_slice(&[rlp_uint(chain_id.into()), rlp_bytes(&[]), rlp_bytes(&[])]);
+        let message = secp256k1::Message::parse(&tiny_keccak::keccak256(&rlp_list(&fields)));
+
+        let (signature, recovery_id) = secp256k1::sign(&message, secret_key);
+        let signature = signature.serialize();
+        let v = u64::from(recovery_id.serialize()) + chain_id * 2 + 35;
+
+        let mut fields = self.fields();
+        fields.extend_from_slice(&[
+            rlp_uint(v.into()),
+            rlp_uint(U256::from_big_endian(&signature[0..32])),
+            rlp_uint(U256::from_big_endian(&signature[32..64])),
+        ]);
+        rlp_list(&fields)
+    }
+
+    /// Returns the RLP encoding of each of the fields which are signed.
+    fn fields(&self) -> Vec<Vec<u8>> {
+        vec![
+            rlp_uint(self.nonce),
+            rlp_uint(self.gas_price),
+            rlp_uint(self.gas),
+            rlp_bytes(self.to.as_bytes()),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+        ]
+    }
+}
+
+/// RLP-encodes an integer as a big-endian byte string without leading zeros.
+fn rlp_uint(value: U256) -> Vec<u8> {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    let first_non_zero = bytes.iter().position(|byte| *byte != 0).unwrap_or(32);
+    rlp_bytes(&bytes[first_non_zero..])
+}
+
+/// RLP-encodes a byte string.
+fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        bytes.to_vec()
+    } else {
+        let mut encoded = rlp_length_prefix(bytes.len(), 0x80);
+        encoded.extend_from_slice(bytes);
+        encoded
+    }
+}
+
+/// RLP-encodes a list of already-encoded items.
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut encoded = rlp_length_prefix(payload.len(), 0xc0);
+    encoded.extend_from_slice(&payload);
+    encoded
+}
+
+/// Returns the RLP prefix of a string (`offset == 0x80`) or list (`offset == 0xc0`) of `len`
+/// bytes.
+fn rlp_length_prefix(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        vec![offset + len as u8]
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let first_non_zero = len_bytes.iter().position(|byte| *byte != 0).unwrap_or(7);
+        let mut prefix = vec![offset + 55 + (8 - first_non_zero) as u8];
+        prefix.extend_from_slice(&len_bytes[first_non_zero..]);
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example transaction of EIP-155.
+    fn eip_155_transaction() -> Eth1Transaction {
+        Eth1Transaction {
+            nonce: 9.into(),
+            gas_price: from_gwei(20),
+            gas: 21_000.into(),
+            to: Address::from_slice(&[0x35; 20]),
+            value: U256::exp10(18),
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn signs_eip_155_transaction() {
+        let secret_key = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+
+        assert_eq!(
+            hex::encode(rlp_list(&{
+                let mut fields = eip_155_transaction().fields();
+                fields.extend_from_slice(&[rlp_uint(1.into()), rlp_bytes(&[]), rlp_bytes(&[])]);
+                fields
+            })),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(eip_155_transaction().sign(&secret_key, 1)),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000\
+             8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f\
+             761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn rlp_encodes_long_items() {
+        let long = vec![0xaa; 60];
+        let encoded = rlp_bytes(&long);
+        assert_eq!(&encoded[0..2], &[0xb8, 60]);
+        assert_eq!(&encoded[2..], &long[..]);
+
+        assert_eq!(rlp_uint(0.into()), vec![0x80]);
+        assert_eq!(rlp_uint(0x7f.into()), vec![0x7f]);
+        assert_eq!(rlp_uint(0x400.into()), vec![0x82, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn submitted_record_is_beside_deposit_data() {
+        assert_eq!(
+            submitted_record_path(Path::new("/tmp/deposit_data-1.json")),
+            PathBuf::from("/tmp/deposit_data-1.json.submitted")
+        );
+    }
+}
@@ -1,4 +1,6 @@
 mod cli;
+mod deposit;
+mod wallet;
 
 use clap::ArgMatches;
 use deposit_contract::DEPOSIT_GAS;
@@ -7,7 +9,6 @@ use eth2_testnet_config::Eth2TestnetConfig;
 use futures::{future, Future, IntoFuture, Stream};
 use rayon::prelude::*;
 use slog::{crit, error, info, Logger};
-use std::fs;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
@@ -38,19 +39,15 @@ fn run_account_manager<T: EthSpec>(
     let context = env.core_context();
     let log = context.log.clone();
 
-    // If the `datadir` was not provided, default to the home directory. If the home directory is
-    // not known, use the current directory.
+    // If the `datadir` was not provided, default to the validators directory of the platform
+    // default (see `lighthouse_dirs`).
     let datadir = matches
         .value_of("datadir")
         .map(PathBuf::from)
-        .unwrap_or_else(|| {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".lighthouse")
-                .join("validators")
-        });
+        .unwrap_or_else(|| lighthouse_dirs::default_dir(lighthouse_dirs::VALIDATORS_DIR));
 
-    fs::create_dir_all(&datadir).map_err(|e| format!("Failed to create datadir: {}", e))?;
+    lighthouse_dirs::create_dir_restricted(&datadir)
+        .map_err(|e| format!("Failed to create datadir: {}", e))?;
 
     info!(
         log,
@@ -65,6 +62,8 @@ fn run_account_manager<T: EthSpec>(
                 return Err("Invalid 'validator new' command. See --help.".to_string());
             }
         },
+        ("wallet", Some(matches)) => wallet::run_wallet_subcommand(matches, datadir, env)?,
+        ("deposit", Some(matches)) => deposit::run_deposit_subcommand(matches, env)?,
         _ => {
             return Err("Invalid 'validator' command. See --help.".to_string());
         }
@@ -436,6 +435,6 @@ fn deposit_validator(
 }
 
 /// Converts gwei to wei.
-fn from_gwei(gwei: u64) -> U256 {
+pub(crate) fn from_gwei(gwei: u64) -> U256 {
     U256::from(gwei) * U256::exp10(9)
 }
@@ -0,0 +1,293 @@
+//! Provides the `wallet` subcommands, which manage EIP-2386 wallets and use them to derive
+//! validator keystores.
+use clap::ArgMatches;
+use environment::Environment;
+use eth2_wallet::{
+    bip39::{Language, Mnemonic, MnemonicType},
+    scrypt_kdf, Wallet, WalletBuilder, DEFAULT_SCRYPT_N,
+};
+use serde_derive::{Deserialize, Serialize};
+use slog::{info, warn};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tree_hash::{SignedRoot, TreeHash};
+use types::{ChainSpec, EthSpec};
+use validator_client::validator_directory::{
+    random_password, ValidatorDirectory, ValidatorDirectoryBuilder,
+};
+
+/// The file extension used for wallets stored in the wallets directory.
+const WALLET_FILE_EXTENSION: &str = "json";
+/// The prefix for the deposit data files produced for the Eth2 launchpad.
+const DEPOSIT_DATA_FILE_PREFIX: &str = "deposit_data";
+
+/// A single deposit, as it is represented in the JSON files consumed by the Eth2 launchpad.
+#[derive(Serialize, Deserialize)]
+pub struct LaunchpadDeposit {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: u64,
+    pub signature: String,
+    pub deposit_message_root: String,
+    pub deposit_data_root: String,
+    pub fork_version: String,
+}
+
+/// Process the `wallet` subcommands.
+pub fn run_wallet_subcommand<T: EthSpec>(
+    matches: &ArgMatches,
+    datadir: PathBuf,
+    mut env: Environment<T>,
+) -> Result<(), String> {
+    let context = env.core_context();
+    let log = context.log.clone();
+
+    let wallets_dir = dir_arg(matches, "wallets-dir", lighthouse_dirs::WALLETS_DIR)?;
+
+    match matches.subcommand() {
+        ("create", Some(matches)) => {
+            let name = matches
+                .value_of("name")
+                .ok_or_else(|| "No wallet name".to_string())?;
+            let password = read_password_file(matches, "password-file")?;
+
+            let (mnemonic, generated) = if let Some(phrase) = matches.value_of("mnemonic") {
+                let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+                    .map_err(|e| format!("Invalid mnemonic: {}", e))?;
+                (mnemonic, false)
+            } else {
+                (
+                    Mnemonic::new(MnemonicType::Words24, Language::English),
+                    true,
+                )
+            };
+
+            let path = wallet_path(&wallets_dir, name);
+            if path.exists() {
+                return Err(format!("Wallet already exists at {:?}", path));
+            }
+
+            let wallet = WalletBuilder::from_mnemonic(&mnemonic, &password, name.to_string())
+                .and_then(|builder| builder.build())
+                .map_err(|e| format!("Unable to create wallet: {:?}", e))?;
+
+            save_wallet(&wallet, &path)?;
+
+            if generated {
+                warn!(
+                    log,
+                    "Record the wallet mnemonic";
+                    "msg" => "this is the only time the mnemonic will be displayed"
+                );
+                println!("{}", mnemonic.phrase());
+            }
+
+            info!(
+                log,
+                "Created wallet";
+                "path" => format!("{:?}", path),
+                "uuid" => format!("{}", wallet.uuid()),
+            );
+        }
+        ("validator", Some(matches)) => {
+            let name = matches
+                .value_of("wallet-name")
+                .ok_or_else(|| "No wallet name".to_string())?;
+            let wallet_password = read_password_file(matches, "wallet-password")?;
+            let secrets_dir = dir_arg(matches, "secrets-dir", lighthouse_dirs::SECRETS_DIR)?;
+            let count = matches
+                .value_of("count")
+                .ok_or_else(|| "No validator count".to_string())?
+                .parse::<usize>()
+                .map_err(|e| format!("Unable to parse count: {}", e))?;
+            let deposit_value = matches
+                .value_of("deposit-value")
+                .ok_or_else(|| "No deposit-value".to_string())?
+                .parse::<u64>()
+                .map_err(|e| format!("Unable to parse deposit-value: {}", e))?;
+            let scrypt_n = match matches.value_of("scrypt-n") {
+                Some(n) => n
+                    .parse::<u32>()
+                    .map_err(|e| format!("Unable to parse scrypt-n: {}", e))?,
+                None => DEFAULT_SCRYPT_N,
+            };
+            if !scrypt_n.is_power_of_two() || scrypt_n < 2 {
+                return Err(format!("scrypt-n must be a power of two, not {}", scrypt_n));
+            }
+            if scrypt_n < DEFAULT_SCRYPT_N {
+                warn!(
+                    log,
+                    "Using reduced-cost keystore encryption";
+                    "msg" => "only use for testnets",
+                    "scrypt_n" => scrypt_n,
+                );
+            }
+
+            let path = wallet_path(&wallets_dir, name);
+            let mut wallet = Wallet::from_json_file(&path)
+                .map_err(|e| format!("Unable to load wallet at {:?}: {:?}", path, e))?;
+
+            let spec = &context.eth2_config.spec;
+            let mut validators = Vec::with_capacity(count);
+
+            for _ in 0..count {
+                let voting_password = random_password();
+                let withdrawal_password = random_password();
+
+                let keystores = wallet
+                    .next_validator_with_kdf(
+                        &wallet_password,
+                        &voting_password,
+                        &withdrawal_password,
+                        || scrypt_kdf(scrypt_n),
+                    )
+                    .map_err(|e| format!("Unable to derive validator keys: {:?}", e))?;
+
+                // Persist the wallet before creating the validator so the same keys are never
+                // derived twice, even if creating the validator directory fails.
+                save_wallet(&wallet, &path)?;
+
+                let validator = ValidatorDirectoryBuilder::default()
+                    .spec(spec.clone())
+                    .custom_deposit_amount(deposit_value)
+                    .keystores(
+                        (keystores.voting, voting_password),
+                        (keystores.withdrawal, withdrawal_password),
+                    )?
+                    .create_directory(datadir.clone())?
+                    .write_keystore_files(&secrets_dir)?
+                    .write_eth1_data_file()?
+                    .build()?;
+
+                info!(
+                    log,
+                    "Created validator";
+                    "wallet_index" => keystores.index,
+                    "voting_pubkey" => validator
+                        .voting_keypair
+                        .as_ref()
+                        .map(|keypair| format!("{:?}", keypair.pk))
+                        .unwrap_or_default(),
+                );
+
+                validators.push(validator);
+            }
+
+            let deposit_path =
+                write_launchpad_deposit_file(&datadir, &validators, deposit_value, spec)?;
+
+            info!(
+                log,
+                "Generated validator directories";
+                "base_path" => format!("{:?}", datadir),
+                "deposit_data" => format!("{:?}", deposit_path),
+                "count" => validators.len(),
+            );
+        }
+        _ => {
+            return Err("Invalid 'wallet' command. See --help.".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the directory at the `name` CLI flag, or the `default` directory (see
+/// `lighthouse_dirs`) if it was not supplied. Creates the directory if it does not exist.
+fn dir_arg(matches: &ArgMatches, name: &str, default: &str) -> Result<PathBuf, String> {
+    let dir = matches
+        .value_of(name)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| lighthouse_dirs::default_dir(default));
+
+    lighthouse_dirs::create_dir_restricted(&dir)?;
+
+    Ok(dir)
+}
+
+/// Reads the password in the file at the `name` CLI flag, trimming any trailing line feed.
+fn read_password_file(matches: &ArgMatches, name: &str) -> Result<Vec<u8>, String> {
+    let path = matches
+        .value_of(name)
+        .ok_or_else(|| format!("No {} supplied", name))?;
+
+    let mut password = String::new();
+    File::open(path)
+        .map_err(|e| format!("Unable to open password file: {:?}", e))?
+        .read_to_string(&mut password)
+        .map_err(|e| format!("Unable to read password file to string: {:?}", e))?;
+
+    let password = password.trim_end_matches(|c| c == '\n' || c == '\r');
+
+    if password.is_empty() {
+        Err(format!("Password file at {} is empty", path))
+    } else {
+        Ok(password.as_bytes().to_vec())
+    }
+}
+
+/// Returns the path of the wallet with the given `name`.
+fn wallet_path(wallets_dir: &Path, name: &str) -> PathBuf {
+    wallets_dir.join(format!("{}.{}", name, WALLET_FILE_EXTENSION))
+}
+
+/// Writes the `wallet` to `path`, replacing any existing file.
+fn save_wallet(wallet: &Wallet, path: &Path) -> Result<(), String> {
+    let temp_path = path.with_extension("tmp");
+
+    wallet
+        .to_json_file(&temp_path)
+        .map_err(|e| format!("Unable to write wallet to {:?}: {:?}", temp_path, e))?;
+
+    fs::rename(&temp_path, path).map_err(|e| format!("Unable to save wallet: {}", e))
+}
+
+/// Produces the deposit data for each of the `validators` in the JSON format expected by the Eth2
+/// launchpad.
+pub fn launchpad_deposits(
+    validators: &[ValidatorDirectory],
+    amount: u64,
+    spec: &ChainSpec,
+) -> Result<Vec<LaunchpadDeposit>, String> {
+    validators
+        .iter()
+        .map(|validator| {
+            let deposit_data = validator.deposit_data_struct(amount, spec)?;
+
+            Ok(LaunchpadDeposit {
+                pubkey: hex::encode(deposit_data.pubkey.as_bytes()),
+                withdrawal_credentials: hex::encode(deposit_data.withdrawal_credentials),
+                amount: deposit_data.amount,
+                signature: hex::encode(deposit_data.signature.as_bytes()),
+                deposit_message_root: hex::encode(deposit_data.signed_root()),
+                deposit_data_root: hex::encode(deposit_data.tree_hash_root()),
+                fork_version: hex::encode(spec.genesis_fork.current_version),
+            })
+        })
+        .collect()
+}
+
+/// Writes the launchpad deposit data for `validators` to a timestamped file in `datadir`,
+/// returning the path of the file.
+fn write_launchpad_deposit_file(
+    datadir: &Path,
+    validators: &[ValidatorDirectory],
+    amount: u64,
+    spec: &ChainSpec,
+) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("Unable to read system time: {:?}", e))?
+        .as_secs();
+    let path = datadir.join(format!("{}-{}.json", DEPOSIT_DATA_FILE_PREFIX, timestamp));
+
+    let deposits = launchpad_deposits(validators, amount, spec)?;
+
+    let file = File::create(&path).map_err(|e| format!("Unable to create {:?}: {}", path, e))?;
+    serde_json::to_writer(file, &deposits)
+        .map_err(|e| format!("Unable to write deposit data: {:?}", e))?;
+
+    Ok(path)
+}
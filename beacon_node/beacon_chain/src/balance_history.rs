@@ -0,0 +1,197 @@
+use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use store::{DBColumn, Error as StoreError, Store};
+use types::{BeaconState, Epoch, EthSpec, PublicKeyBytes};
+
+/// The maximum number of epochs which may be read from the history by a single call to `get`.
+pub const MAX_BALANCE_HISTORY_EPOCHS: u64 = 8_192;
+
+/// The balance of a validator at the start of some epoch.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct BalanceRecord {
+    pub epoch: Epoch,
+    pub balance: u64,
+    pub effective_balance: u64,
+}
+
+/// Records the balances of a set of monitored validators in the store at each epoch, so that
+/// stakers can chart their income without relying upon an external service.
+///
+/// Each record is stored under the validator's public key and the epoch, so a record for an epoch
+/// which was re-organised is overwritten when the epoch becomes canonical again.
+pub struct BalanceHistory {
+    /// Maps the public key of each monitored validator to its index, once it is known.
+    validators: RwLock<HashMap<PublicKeyBytes, Option<usize>>>,
+}
+
+impl BalanceHistory {
+    /// Instantiates a history which monitors the validators with the given `pubkeys`.
+    pub fn new(pubkeys: Vec<PublicKeyBytes>) -> Self {
+        Self {
+            validators: RwLock::new(pubkeys.into_iter().map(|pubkey| (pubkey, None)).collect()),
+        }
+    }
+
+    /// Returns `true` if the balances of the validator with `pubkey` are recorded.
+    pub fn is_monitored(&self, pubkey: &PublicKeyBytes) -> bool {
+        self.validators.read().contains_key(pubkey)
+    }
+
+    /// Stores the balances of all monitored validators in `state`, at the current epoch of
+    /// `state`. Validators which are not yet in `state` are ignored.
+    ///
+    /// Returns the number of records stored.
+    pub fn record<E: EthSpec, S: Store<E>>(
+        &self,
+        store: &S,
+        state: &BeaconState<E>,
+    ) -> Result<usize, StoreError> {
+        let mut validators = self.validators.write();
+
+        // Find the index of any validator which was not in previous states.
+        if validators.values().any(Option::is_none) {
+            for (i, validator) in state.validators.iter().enumerate() {
+                if let Some(index) = validators.get_mut(&validator.pubkey) {
+                    *index = Some(i);
+                }
+            }
+        }
+
+        let epoch = state.current_epoch();
+        let mut count = 0;
+
+        for (pubkey, index) in validators.iter() {
+            let index = match index {
+                Some(index) => *index,
+                None => continue,
+            };
+
+            if let (Some(validator), Some(balance)) =
+                (state.validators.get(index), state.balances.get(index))
+            {
+                let record = BalanceRecord {
+                    epoch,
+                    balance: *balance,
+                    effective_balance: validator.effective_balance,
+                };
+
+                store.put_bytes(
+                    DBColumn::ValidatorBalanceHistory.into(),
+                    &record_key(pubkey, epoch),
+                    &record.as_ssz_bytes(),
+                )?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the records for the validator with `pubkey` from `start_epoch` to `end_epoch`
+    /// (inclusive), skipping any epochs which were not recorded.
+    ///
+    /// No more than `MAX_BALANCE_HISTORY_EPOCHS` epochs are read, starting at `start_epoch`.
+    pub fn get<E: EthSpec, S: Store<E>>(
+        &self,
+        store: &S,
+        pubkey: &PublicKeyBytes,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+    ) -> Result<Vec<BalanceRecord>, StoreError> {
+        let end_epoch = std::cmp::min(end_epoch, start_epoch + (MAX_BALANCE_HISTORY_EPOCHS - 1));
+
+        (start_epoch.as_u64()..=end_epoch.as_u64())
+            .map(Epoch::new)
+            .filter_map(|epoch| {
+                store
+                    .get_bytes(
+                        DBColumn::ValidatorBalanceHistory.into(),
+                        &record_key(pubkey, epoch),
+                    )
+                    .transpose()
+            })
+            .map(|bytes| Ok(BalanceRecord::from_ssz_bytes(&bytes?)?))
+            .collect()
+    }
+}
+
+fn record_key(pubkey: &PublicKeyBytes, epoch: Epoch) -> Vec<u8> {
+    let mut key = pubkey.as_bytes();
+    key.extend_from_slice(&epoch.as_u64().to_be_bytes());
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+    use types::{test_utils::TestingBeaconStateBuilder, EthSpec, MinimalEthSpec, Slot, Validator};
+
+    type E = MinimalEthSpec;
+
+    fn get_state(validator_count: usize) -> BeaconState<E> {
+        let spec = E::default_spec();
+        let builder =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(validator_count, &spec);
+        let (state, _keypairs) = builder.build();
+        state
+    }
+
+    #[test]
+    fn records_monitored_validators() {
+        let store = MemoryStore::<E>::open();
+        let mut state = get_state(4);
+        let monitored = state.validators[1].pubkey.clone();
+        let history = BalanceHistory::new(vec![monitored.clone(), PublicKeyBytes::empty()]);
+
+        assert!(history.is_monitored(&monitored));
+        assert!(!history.is_monitored(&state.validators[0].pubkey));
+
+        for epoch in 0..3 {
+            state.slot = Slot::new(epoch * E::slots_per_epoch());
+            state.balances[1] = 32_000_000_000 + epoch;
+            assert_eq!(history.record(&store, &state), Ok(1));
+        }
+
+        let records = history
+            .get(&store, &monitored, Epoch::new(1), Epoch::new(5))
+            .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                BalanceRecord {
+                    epoch: Epoch::new(1),
+                    balance: 32_000_000_001,
+                    effective_balance: state.validators[1].effective_balance,
+                },
+                BalanceRecord {
+                    epoch: Epoch::new(2),
+                    balance: 32_000_000_002,
+                    effective_balance: state.validators[1].effective_balance,
+                },
+            ]
+        );
+
+        // A validator which joins later is recorded once it appears in the state.
+        let mut validator = Validator::default();
+        validator.pubkey = PublicKeyBytes::empty();
+        state.validators.push(validator).unwrap();
+        state.balances.push(42).unwrap();
+        assert_eq!(history.record(&store, &state), Ok(2));
+        assert_eq!(
+            history
+                .get(
+                    &store,
+                    &PublicKeyBytes::empty(),
+                    Epoch::new(0),
+                    Epoch::new(2)
+                )
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
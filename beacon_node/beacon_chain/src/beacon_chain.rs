@@ -1,37 +1,64 @@
+use crate::balance_history::{BalanceHistory, BalanceRecord};
+use crate::beacon_proposer_cache::{BeaconProposerCache, EpochProposers};
+use crate::block_equivocations::BlockEquivocations;
+use crate::block_packing::{AggregateArrivalTimes, BlockPackingDelay};
+use crate::cache_memory::{self, CacheMemoryReport, MemoryAccounted};
+use crate::chain_health::ChainHealth;
 use crate::checkpoint::CheckPoint;
 use crate::checkpoint_cache::CheckPointCache;
-use crate::errors::{BeaconChainError as Error, BlockProductionError};
-use crate::eth1_chain::{Eth1Chain, Eth1ChainBackend};
+use crate::checkpoint_state_cache::CheckPointStateCache;
+use crate::debug_dump::DebugDump;
+use crate::epoch_summary::EpochSummary;
+use crate::epoch_summary_cache::EpochSummaryCache;
+use crate::errors::{BeaconChainError as Error, BlockProductionError, HistoricalBlockError};
+use crate::eth1_chain::{DepositTreeStatus, Eth1Chain, Eth1ChainBackend, PendingDeposits};
 use crate::events::{EventHandler, EventKind};
-use crate::fork_choice::{Error as ForkChoiceError, ForkChoice};
+use crate::finality_update::FinalityUpdate;
+use crate::fork_choice::{Error as ForkChoiceError, ForkChoice, ForkChoiceNode};
+use crate::head_subscribers::{reorg_depth, HeadChange, HeadSubscribers};
 use crate::head_tracker::HeadTracker;
+use crate::local_proposals::{LocalProposals, ProposalOutcome};
 use crate::metrics;
+use crate::next_epoch_cache::{NextEpochCache, PrecomputedEpoch};
+use crate::observed_proposers::{ObservedProposers, ProposalObservation};
 use crate::persisted_beacon_chain::{PersistedBeaconChain, BEACON_CHAIN_DB_KEY};
+use crate::production_times::{LocalMessage, ProductionTimes};
+use crate::shuffling_cache::ShufflingCache;
+use crate::state_clones;
+use crate::validator_queue::{estimate_validator_queue, ValidatorQueueEstimate};
+use crate::validators_diff::{diff_validators, ValidatorsDiff};
+use futures::sync::mpsc::UnboundedReceiver;
 use lmd_ghost::LmdGhost;
 use operation_pool::{OperationPool, PersistedOperationPool};
 use parking_lot::RwLock;
-use slog::{debug, error, info, trace, warn, Logger};
+use slasher::Slasher;
+use slog::{crit, debug, error, info, trace, warn, Logger};
 use slot_clock::SlotClock;
 use ssz::Encode;
 use state_processing::per_block_processing::{
     errors::{
         AttestationValidationError, AttesterSlashingValidationError, ExitValidationError,
-        ProposerSlashingValidationError,
+        HeaderInvalid, ProposerSlashingValidationError,
     },
-    verify_attestation_for_state, VerifySignatures,
+    verify_attestation_for_state_with_pubkey_cache,
 };
 use state_processing::{
-    per_block_processing, per_slot_processing, BlockProcessingError, BlockSignatureStrategy,
+    common::get_indexed_attestation, per_block_processing, per_slot_processing, time_stage,
+    BlockProcessingError, BlockReplayError, BlockReplayer, BlockSignatureStrategy,
+    BlockSignatureVerifier, CommitteePubkeyCache, EpochProcessingSummary, TimingRecorder,
 };
+use std::collections::HashMap;
+use std::convert::TryInto;
 use std::fs;
 use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use store::iter::{
     BlockRootsIterator, ReverseBlockRootIterator, ReverseStateRootIterator, StateRootsIterator,
 };
-use store::{Error as DBError, Migrate, Store};
-use tree_hash::TreeHash;
+use store::{AnchorInfo, Error as DBError, Migrate, RootIndexInfo, Store};
+use tree_hash::{SignedRoot, TreeHash};
 use types::*;
 
 // Text included in blocks.
@@ -49,6 +76,13 @@ const WRITE_BLOCK_PROCESSING_SSZ: bool = cfg!(feature = "write_ssz_files");
 /// Maximum block slot number. Block with slots bigger than this constant will NOT be processed.
 const MAXIMUM_BLOCK_SLOT_NUMBER: u64 = 4_294_967_296; // 2^32
 
+/// The number of blocks between the progress logs of `BeaconChain::revalidate_from_slot`.
+const REVALIDATION_LOG_INTERVAL: usize = 1_024;
+
+/// The maximum difference between the clocks of two nodes which is tolerated when deciding whether
+/// a block is from a future slot.
+pub const MAXIMUM_GOSSIP_CLOCK_DISPARITY: Duration = Duration::from_millis(500);
+
 #[derive(Debug, PartialEq)]
 pub enum BlockProcessingOutcome {
     /// Block was valid and imported into the block graph.
@@ -77,6 +111,15 @@ pub enum BlockProcessingOutcome {
     PerBlockProcessingError(BlockProcessingError),
 }
 
+/// The outcome of checking a block with `BeaconChain::verify_block_for_gossip`.
+#[derive(Debug, PartialEq)]
+pub enum GossipBlockOutcome {
+    /// The block passed the checks and may be propagated. It has not been imported.
+    Valid { block_root: Hash256 },
+    /// The block failed the checks for the given reason and must not be propagated.
+    Invalid(BlockProcessingOutcome),
+}
+
 #[derive(Debug, PartialEq)]
 pub enum AttestationProcessingOutcome {
     Processed,
@@ -98,11 +141,49 @@ pub enum AttestationProcessingOutcome {
     Invalid(AttestationValidationError),
 }
 
+/// A summary of the head block and state, which is much cheaper to read than the `CheckPoint`
+/// returned by `BeaconChain::head`.
+#[derive(Debug, PartialEq, Clone)]
 pub struct HeadInfo {
     pub slot: Slot,
     pub block_root: Hash256,
     pub state_root: Hash256,
+    pub current_justified_checkpoint: types::Checkpoint,
+    pub previous_justified_checkpoint: types::Checkpoint,
     pub finalized_checkpoint: types::Checkpoint,
+    pub fork: Fork,
+    pub genesis_time: u64,
+}
+
+impl HeadInfo {
+    /// Summarizes the block and state of `head`.
+    pub fn from_checkpoint<E: EthSpec>(head: &CheckPoint<E>) -> Self {
+        Self {
+            slot: head.beacon_block.slot,
+            block_root: head.beacon_block_root,
+            state_root: head.beacon_state_root,
+            current_justified_checkpoint: head.beacon_state.current_justified_checkpoint.clone(),
+            previous_justified_checkpoint: head.beacon_state.previous_justified_checkpoint.clone(),
+            finalized_checkpoint: head.beacon_state.finalized_checkpoint.clone(),
+            fork: head.beacon_state.fork.clone(),
+            genesis_time: head.beacon_state.genesis_time,
+        }
+    }
+}
+
+/// The result of replaying the canonical chain through the full state transition, see
+/// `BeaconChain::revalidate_from_slot`.
+#[derive(Debug, PartialEq)]
+pub struct RevalidationSummary {
+    /// The slot of the first block which was replayed.
+    pub start_slot: Slot,
+    /// The slot of the head block.
+    pub head_slot: Slot,
+    /// The number of blocks which were applied without divergence.
+    pub blocks_verified: usize,
+    /// The first block which failed verification, or whose state root differs from that of the
+    /// replayed state.
+    pub divergence: Option<BlockReplayError>,
 }
 
 pub trait BeaconChainTypes: Send + Sync + 'static {
@@ -132,6 +213,9 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub eth1_chain: Option<Eth1Chain<T::Eth1Chain, T::EthSpec>>,
     /// Stores a "snapshot" of the chain at the time the head-of-the-chain block was received.
     pub(crate) canonical_head: RwLock<CheckPoint<T::EthSpec>>,
+    /// A summary of `canonical_head`, kept behind its own lock so that it may be read without
+    /// cloning the head state or waiting on a head update.
+    pub(crate) head_info: RwLock<HeadInfo>,
     /// The root of the genesis block.
     pub genesis_block_root: Hash256,
     /// A state-machine that is updated with information from the network and chooses a canonical
@@ -139,10 +223,54 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub fork_choice: ForkChoice<T>,
     /// A handler for events generated by the beacon chain.
     pub event_handler: T::EventHandler,
+    /// Checks valid attestations for slashable votes, if the slasher is enabled.
+    pub slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    /// Records the balances of monitored validators at each epoch, if any are monitored.
+    pub balance_history: Option<BalanceHistory>,
+    /// Determines how far into its slot a block is packed.
+    pub block_packing_delay: BlockPackingDelay,
+    /// Writes the blocks which fail the state transition to disk, if enabled.
+    pub(crate) debug_dump: Option<DebugDump>,
+    /// If true, the time spent in each stage of the state transition is logged for each block.
+    pub(crate) log_block_processing_breakdown: bool,
+    /// Records the arrival times of recent aggregates, used to learn an adaptive packing delay.
+    pub(crate) aggregate_arrival_times: AggregateArrivalTimes,
     /// Used to track the heads of the beacon chain.
     pub(crate) head_tracker: HeadTracker,
+    /// Records the blocks published by local validators, to prevent them from equivocating.
+    pub(crate) observed_proposers: ObservedProposers,
+    /// Records the blocks seen on gossip, to construct slashings for proposers which equivocate.
+    pub(crate) block_equivocations: BlockEquivocations,
+    /// Tracks whether the blocks produced by local validators become canonical.
+    pub(crate) local_proposals: LocalProposals,
+    /// Records when messages were produced for local validators, to time their publication.
+    pub(crate) production_times: ProductionTimes,
     /// Provides a small cache of `BeaconState` and `BeaconBlock`.
     pub(crate) checkpoint_cache: CheckPointCache<T::EthSpec>,
+    /// Stores the justified balances and the finalized checkpoint.
+    pub(crate) checkpoint_state_cache: CheckPointStateCache<T::EthSpec>,
+    /// Stores the summaries of validator participation produced by recent epoch transitions.
+    pub(crate) epoch_summary_cache: EpochSummaryCache,
+    /// Stores the next epoch, computed ahead of time from the head.
+    pub(crate) next_epoch_cache: NextEpochCache<T::EthSpec>,
+    /// Stores the proposers of recent epochs.
+    pub(crate) beacon_proposer_cache: BeaconProposerCache,
+    /// Stores the committees of recent epochs.
+    pub(crate) shuffling_cache: ShufflingCache,
+    /// Stores the aggregate public keys of the committees of recent epochs, for verifying
+    /// attestations from gossip and in blocks.
+    pub(crate) committee_pubkey_cache: CommitteePubkeyCache,
+    /// The budget for the total size of the caches above, in bytes, if there is one.
+    pub(crate) cache_memory_budget: Option<usize>,
+    /// The streams to which each change of the canonical head is sent.
+    pub(crate) head_subscribers: HeadSubscribers,
+    /// The latest assessment of the health of the node, if it has been assessed.
+    pub(crate) chain_health: RwLock<Option<ChainHealth>>,
+    /// Held for reading whilst a block is imported, so that `shutdown` can wait for any in-flight
+    /// imports to finish.
+    pub(crate) block_import_lock: RwLock<()>,
+    /// Set once `shutdown` has been called, after which no more blocks are imported.
+    pub(crate) shutting_down: AtomicBool,
     /// Logging to CLI, etc.
     pub(crate) log: Logger,
 }
@@ -156,25 +284,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         let canonical_head = self.head();
 
-        let finalized_checkpoint = {
-            let beacon_block_root = canonical_head.beacon_state.finalized_checkpoint.root;
-            let beacon_block = self
-                .store
-                .get::<BeaconBlock<_>>(&beacon_block_root)?
-                .ok_or_else(|| Error::MissingBeaconBlock(beacon_block_root))?;
-            let beacon_state_root = beacon_block.state_root;
-            let beacon_state = self
-                .store
-                .get_state(&beacon_state_root, Some(beacon_block.slot))?
-                .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?;
-
-            CheckPoint {
-                beacon_block_root,
-                beacon_block,
-                beacon_state_root,
-                beacon_state,
-            }
-        };
+        let finalized_checkpoint = (*self
+            .finalized_checkpoint(canonical_head.beacon_state.finalized_checkpoint.root)?)
+        .clone();
 
         let p: PersistedBeaconChain<T> = PersistedBeaconChain {
             canonical_head,
@@ -193,6 +305,226 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Prepares the chain for the process to exit.
+    ///
+    /// Stops any further blocks from being imported, waits for the blocks which are presently being
+    /// imported, then persists the head, fork choice and operation pool to `self.store`. The chain
+    /// is not persisted again when it is dropped.
+    ///
+    /// The eth1 caches are not persisted, they are re-downloaded from the eth1 node at startup.
+    pub fn shutdown(&self) -> Result<(), Error> {
+        if self.shutting_down.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        info!(self.log, "Waiting for block imports to finish");
+        let _import_lock = self.block_import_lock.write();
+
+        self.persist_on_shutdown()
+    }
+
+    /// Returns `true` if `shutdown` has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Persists the chain, logging the outcome.
+    fn persist_on_shutdown(&self) -> Result<(), Error> {
+        let result = self.persist();
+
+        if let Err(e) = &result {
+            error!(
+                self.log,
+                "Failed to persist BeaconChain on shutdown";
+                "error" => format!("{:?}", e)
+            )
+        } else {
+            info!(
+                self.log,
+                "Saved beacon chain state";
+            )
+        }
+
+        result
+    }
+
+    /// Returns the block with `block_root` and its post-state, or the head block and state if
+    /// `block_root` is `None`.
+    ///
+    /// Returns `Ok(None)` if the block is unknown.
+    pub fn export_checkpoint(
+        &self,
+        block_root: Option<Hash256>,
+    ) -> Result<Option<CheckPoint<T::EthSpec>>, Error> {
+        let beacon_block_root = match block_root {
+            Some(block_root) => block_root,
+            None => return Ok(Some(self.head())),
+        };
+
+        let beacon_block = match self
+            .store
+            .get::<BeaconBlock<T::EthSpec>>(&beacon_block_root)?
+        {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let beacon_state_root = beacon_block.state_root;
+        let beacon_state = self
+            .store
+            .get_state(&beacon_state_root, Some(beacon_block.slot))?
+            .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?;
+
+        Ok(Some(CheckPoint::new(
+            beacon_block,
+            beacon_block_root,
+            beacon_state,
+            beacon_state_root,
+        )))
+    }
+
+    /// Stores the block and state of `checkpoint` (e.g., a checkpoint exported from another node)
+    /// in `self.store`, after checking that the roots of each are consistent.
+    ///
+    /// The block is not imported into fork choice; it is only made available to be read from the
+    /// store.
+    pub fn import_checkpoint(&self, checkpoint: CheckPoint<T::EthSpec>) -> Result<(), Error> {
+        let block_root = checkpoint.beacon_block.canonical_root();
+        let state_root = Hash256::from_slice(&checkpoint.beacon_state.tree_hash_root());
+
+        if block_root != checkpoint.beacon_block_root {
+            return Err(Error::InvalidCheckPoint(format!(
+                "block root {} does not match block",
+                checkpoint.beacon_block_root
+            )));
+        }
+        if state_root != checkpoint.beacon_state_root
+            || state_root != checkpoint.beacon_block.state_root
+        {
+            return Err(Error::InvalidCheckPoint(format!(
+                "state root {} does not match state",
+                checkpoint.beacon_state_root
+            )));
+        }
+
+        self.store
+            .put_state(&state_root, &checkpoint.beacon_state)?;
+        self.store
+            .put_state_slot(&state_root, checkpoint.beacon_state.slot)?;
+        self.store.put(&block_root, &checkpoint.beacon_block)?;
+        self.store
+            .put_block_slot(&block_root, checkpoint.beacon_block.slot)?;
+
+        Ok(())
+    }
+
+    /// Returns a copy of the contents of `self.op_pool`, e.g., so that they may be loaded into
+    /// another node to reproduce the blocks it would produce.
+    pub fn export_op_pool(&self) -> PersistedOperationPool<T::EthSpec> {
+        PersistedOperationPool::from_operation_pool(&self.op_pool)
+    }
+
+    /// Replaces the contents of `self.op_pool` with `op_pool` (e.g., as exported from another
+    /// node with `Self::export_op_pool`).
+    ///
+    /// The operations are not verified against the head state.
+    pub fn import_op_pool(&self, op_pool: PersistedOperationPool<T::EthSpec>) {
+        let op_pool = self.with_head_state(|state| op_pool.into_operation_pool(state, &self.spec));
+        self.op_pool.replace(op_pool);
+    }
+
+    /// Stores `blocks`, which are older than the oldest block in the database (e.g., blocks
+    /// fetched out-of-band for a database initialised from a checkpoint), returning the updated
+    /// `AnchorInfo`.
+    ///
+    /// The blocks may be supplied in any order, but must form a chain ending at the parent of the
+    /// oldest block in the database. Their signatures are not verified, since their roots are
+    /// committed to by the blocks already held. No blocks are stored if any is invalid.
+    pub fn import_historical_blocks(
+        &self,
+        mut blocks: Vec<BeaconBlock<T::EthSpec>>,
+    ) -> Result<AnchorInfo, Error> {
+        let mut anchor_info = self
+            .store
+            .get_anchor_info()?
+            .ok_or_else(|| HistoricalBlockError::NoAnchor)?;
+        let oldest_block_slot = anchor_info.oldest_block_slot;
+
+        let (_, oldest_block) = self
+            .store
+            .get_block_at_preceeding_slot(anchor_info.anchor_root, oldest_block_slot)?
+            .ok_or_else(|| HistoricalBlockError::MissingOldestBlock { oldest_block_slot })?;
+
+        blocks.sort_by_key(|block| std::cmp::Reverse(block.slot));
+
+        let mut expected_root = oldest_block.parent_root;
+        let block_roots = blocks
+            .iter()
+            .map(|block| {
+                let block_root = block.canonical_root();
+                if block_root != expected_root {
+                    return Err(HistoricalBlockError::MismatchedBlockRoot {
+                        slot: block.slot,
+                        expected: expected_root,
+                        found: block_root,
+                    });
+                }
+
+                expected_root = block.parent_root;
+                Ok(block_root)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (block_root, block) in block_roots.iter().zip(blocks.iter()) {
+            self.store.put(block_root, block)?;
+            self.store.put_block_slot(block_root, block.slot)?;
+        }
+
+        if let Some(oldest_block) = blocks.last() {
+            anchor_info.oldest_block_slot = oldest_block.slot;
+            self.store.put_anchor_info(&anchor_info)?;
+
+            info!(
+                self.log,
+                "Imported historical blocks";
+                "count" => blocks.len(),
+                "oldest_block_slot" => oldest_block.slot,
+            );
+        }
+
+        Ok(anchor_info)
+    }
+
+    /// Returns the finalized block with `block_root` and its state, from the
+    /// `checkpoint_state_cache` if possible.
+    ///
+    /// The cache only holds the latest finalized checkpoint, so loading another one from the store
+    /// replaces it.
+    pub(crate) fn finalized_checkpoint(
+        &self,
+        block_root: Hash256,
+    ) -> Result<Arc<CheckPoint<T::EthSpec>>, Error> {
+        if let Some(finalized) = self.checkpoint_state_cache.get_finalized(block_root) {
+            return Ok(finalized);
+        }
+
+        let beacon_block = self
+            .store
+            .get::<BeaconBlock<_>>(&block_root)?
+            .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+        let beacon_state_root = beacon_block.state_root;
+        let beacon_state = self
+            .store
+            .get_state(&beacon_state_root, Some(beacon_block.slot))?
+            .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?;
+
+        Ok(self.checkpoint_state_cache.insert_finalized(CheckPoint {
+            beacon_block_root: block_root,
+            beacon_block,
+            beacon_state_root,
+            beacon_state,
+        }))
+    }
+
     /// Returns the slot _right now_ according to `self.slot_clock`. Returns `Err` if the slot is
     /// unavailable.
     ///
@@ -202,6 +534,14 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.slot_clock.now().ok_or_else(|| Error::UnableToReadSlot)
     }
 
+    /// Returns the present slot if the clock were `MAXIMUM_GOSSIP_CLOCK_DISPARITY` ahead, so that
+    /// blocks from nodes with a slightly faster clock are not rejected.
+    pub fn slot_with_future_tolerance(&self) -> Result<Slot, Error> {
+        self.slot_clock
+            .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+            .ok_or_else(|| Error::UnableToReadSlot)
+    }
+
     /// Returns the epoch _right now_ according to `self.slot_clock`. Returns `Err` if the epoch is
     /// unavailable.
     ///
@@ -212,6 +552,22 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .map(|slot| slot.epoch(T::EthSpec::slots_per_epoch()))
     }
 
+    /// Returns the fork ID which identifies the chain and fork of this node in its ENR, as of the
+    /// current epoch (or the head epoch if the slot clock cannot be read).
+    pub fn enr_fork_id(&self) -> EnrForkId {
+        let head_info = self.head_info();
+        let epoch = self
+            .epoch()
+            .unwrap_or_else(|_| head_info.slot.epoch(T::EthSpec::slots_per_epoch()));
+
+        EnrForkId::new(
+            &head_info.fork,
+            self.genesis_block_root,
+            epoch,
+            self.spec.far_future_epoch,
+        )
+    }
+
     /// Returns the beacon block body for each beacon block root in `roots`.
     ///
     /// Fails if any root in `roots` does not have a corresponding block.
@@ -334,6 +690,47 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         ReverseStateRootIterator::new((head.beacon_state_root, slot), iter)
     }
 
+    /// Adds the blocks and states of the canonical chain to the root indices of the store, if
+    /// they have not yet been backfilled.
+    ///
+    /// Blocks and states are indexed as they are imported, so this is only required once for a
+    /// database created before the indices were introduced. Blocks and states which are not in
+    /// the canonical chain are not backfilled.
+    pub fn backfill_root_index(&self) -> Result<(), Error> {
+        if self.store.get_root_index_info()?.is_some() {
+            return Ok(());
+        }
+
+        let head_slot = self.head_info().slot;
+        let mut indexed_blocks = 0;
+        let mut indexed_states = 0;
+
+        // Skipped slots repeat the root of the prior block, so the last write for each block is
+        // at its own slot.
+        for (block_root, slot) in self.rev_iter_block_roots() {
+            self.store.put_block_slot(&block_root, slot)?;
+            indexed_blocks += 1;
+        }
+        for (state_root, slot) in self.rev_iter_state_roots() {
+            self.store.put_state_slot(&state_root, slot)?;
+            indexed_states += 1;
+        }
+
+        self.store.put_root_index_info(&RootIndexInfo {
+            backfilled_to_slot: head_slot,
+        })?;
+
+        info!(
+            self.log,
+            "Backfilled root indices";
+            "state_roots" => indexed_states,
+            "block_roots" => indexed_blocks,
+            "head_slot" => head_slot,
+        );
+
+        Ok(())
+    }
+
     /// Returns the block at the given root, if any.
     ///
     /// ## Errors
@@ -439,22 +836,61 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// It is important to note that the `beacon_state` returned may not match the present slot. It
     /// is the state as it was when the head block was received, which could be some slots prior to
     /// now.
+    ///
+    /// The head state is cloned, which is expensive for a large validator set. Prefer `head_info`
+    /// or `with_head` wherever only a few fields are read.
     pub fn head(&self) -> CheckPoint<T::EthSpec> {
+        state_clones::record_state_clone();
         self.canonical_head.read().clone()
     }
 
-    /// Returns info representing the head block and state.
+    /// Returns a stream of the changes of the canonical head from now on, for applications which
+    /// embed the beacon chain.
     ///
-    /// A summarized version of `Self::head` that involves less cloning.
-    pub fn head_info(&self) -> HeadInfo {
-        let head = self.canonical_head.read();
+    /// The stream does not end whilst `self` exists. Dropping it unsubscribes.
+    pub fn subscribe_head(&self) -> UnboundedReceiver<HeadChange> {
+        self.head_subscribers.subscribe()
+    }
 
-        HeadInfo {
-            slot: head.beacon_block.slot,
-            block_root: head.beacon_block_root,
-            state_root: head.beacon_state_root,
-            finalized_checkpoint: head.beacon_state.finalized_checkpoint.clone(),
+    /// Calls `f` with a reference to the head block and state, without cloning them.
+    ///
+    /// A read lock is held on the head whilst `f` runs, so `f` should be quick and must not
+    /// update the head (e.g., by running fork choice).
+    pub fn with_head<R>(&self, f: impl FnOnce(&CheckPoint<T::EthSpec>) -> R) -> R {
+        f(&self.canonical_head.read())
+    }
+
+    /// Calls `f` with a reference to the head state, without cloning it (see `with_head`).
+    pub fn with_head_state<R>(&self, f: impl FnOnce(&BeaconState<T::EthSpec>) -> R) -> R {
+        self.with_head(|head| f(&head.beacon_state))
+    }
+
+    /// Calls `f` with a reference to the `BeaconState` at the given slot (see `state_at_slot`).
+    ///
+    /// The head state is borrowed rather than cloned if `slot` is the slot of the head (see
+    /// `with_head`), otherwise the state is loaded or computed as for `state_at_slot`.
+    pub fn with_state_at_slot<R>(
+        &self,
+        slot: Slot,
+        f: impl FnOnce(&BeaconState<T::EthSpec>) -> R,
+    ) -> Result<R, Error> {
+        {
+            let head = self.canonical_head.read();
+            if head.beacon_state.slot == slot {
+                return Ok(f(&head.beacon_state));
+            }
         }
+
+        let state = self.state_at_slot(slot)?;
+        Ok(f(&state))
+    }
+
+    /// Returns info representing the head block and state.
+    ///
+    /// A summarized version of `Self::head` that does not clone the head state. Prefer this
+    /// function wherever the full head state is not required.
+    pub fn head_info(&self) -> HeadInfo {
+        self.head_info.read().clone()
     }
 
     /// Returns the current heads of the `BeaconChain`. For the canonical head, see `Self::head`.
@@ -503,7 +939,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 }
 
                 match per_slot_processing(&mut state, &self.spec) {
-                    Ok(()) => (),
+                    Ok(_) => (),
                     Err(e) => {
                         warn!(
                             self.log,
@@ -525,90 +961,1199 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .map(|(root, _slot)| root)
                 .ok_or_else(|| Error::NoStateForSlot(slot))?;
 
-            Ok(self
-                .get_state_caching(&state_root, Some(slot))?
-                .ok_or_else(|| Error::NoStateForSlot(slot))?)
+            Ok(self
+                .get_state_caching(&state_root, Some(slot))?
+                .ok_or_else(|| Error::NoStateForSlot(slot))?)
+        }
+    }
+
+    /// Returns the `BeaconState` the current slot (viz., `self.slot()`).
+    ///
+    ///  - A reference to the head state (note: this keeps a read lock on the head, try to use
+    ///  sparingly).
+    ///  - The head state, but with skipped slots (for states later than the head).
+    ///
+    ///  Returns `None` when there is an error skipping to a future state or the slot clock cannot
+    ///  be read.
+    pub fn wall_clock_state(&self) -> Result<BeaconState<T::EthSpec>, Error> {
+        self.state_at_slot(self.slot()?)
+    }
+
+    /// Returns the slot of the highest block in the canonical chain.
+    pub fn best_slot(&self) -> Slot {
+        self.canonical_head.read().beacon_block.slot
+    }
+
+    /// Returns the validator index (if any) for the given public key.
+    ///
+    /// Information is retrieved from the present `beacon_state.validators`.
+    pub fn validator_index(&self, pubkey: &PublicKeyBytes) -> Option<usize> {
+        for (i, validator) in self.head().beacon_state.validators.iter().enumerate() {
+            if validator.pubkey == *pubkey {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Returns the block canonical root of the current canonical chain at a given slot.
+    ///
+    /// Returns None if a block doesn't exist at the slot.
+    pub fn root_at_slot(&self, target_slot: Slot) -> Option<Hash256> {
+        self.rev_iter_block_roots()
+            .find(|(_root, slot)| *slot == target_slot)
+            .map(|(root, _slot)| root)
+    }
+
+    /// Returns the root of the block at the last slot before `epoch` on the canonical chain, upon
+    /// which the proposers and committees of `epoch` depend.
+    ///
+    /// Returns `None` if the slot is older than the block roots held by the head state.
+    pub fn duties_dependent_root(&self, epoch: Epoch) -> Option<Hash256> {
+        // Taking advantage of saturating slot subtraction.
+        let slot = epoch.start_slot(T::EthSpec::slots_per_epoch()) - 1;
+        let head = self.canonical_head.read();
+
+        if slot >= head.beacon_block.slot {
+            Some(head.beacon_block_root)
+        } else {
+            head.beacon_state.get_block_root(slot).ok().copied()
+        }
+    }
+
+    /// Returns the summary of validator participation in `epoch` on the canonical chain.
+    ///
+    /// Returns `None` if the summary was not produced whilst importing a block in the last
+    /// `EPOCH_SUMMARY_CACHE_EPOCHS` epochs (e.g., if the epoch is too old, or no block has been
+    /// imported since the end of the following epoch).
+    pub fn epoch_summary(&self, epoch: Epoch) -> Option<Arc<EpochProcessingSummary>> {
+        // The attestations for `epoch` are summarised by the epoch transition at the end of the
+        // following epoch.
+        let transition_slot = (epoch + 2).start_slot(T::EthSpec::slots_per_epoch()) - 1;
+        let block_root = self.root_at_slot(transition_slot)?;
+
+        self.epoch_summary_cache.get(&block_root)
+    }
+
+    /// Replays the canonical chain to summarise the validator participation in each epoch from
+    /// `start_epoch` to `end_epoch` (inclusive), reading finalized states and blocks from the
+    /// freezer.
+    ///
+    /// Unlike `epoch_summary`, this does not depend on the summaries cached during block import,
+    /// but it may be slow for long ranges. Epochs whose attestations are yet to be summarised by
+    /// an epoch transition prior to the head are omitted.
+    pub fn replay_epoch_summaries(
+        &self,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+    ) -> Result<Vec<EpochProcessingSummary>, Error> {
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        // The attestations for an epoch are summarised by the epoch transition at the end of the
+        // following epoch, so the replay may start from the first slot of the following epoch.
+        let start_slot = (start_epoch + 1).start_slot(slots_per_epoch);
+        let end_slot = std::cmp::min(
+            (end_epoch + 2).start_slot(slots_per_epoch),
+            self.head_info().slot,
+        );
+
+        if start_slot >= end_slot {
+            return Ok(vec![]);
+        }
+
+        let state = self.state_at_slot(start_slot)?;
+
+        let mut blocks = vec![];
+        let mut previous_root = None;
+        for (block_root, _slot) in self
+            .forwards_iter_block_roots(start_slot)
+            .take_while(|(_root, slot)| *slot <= end_slot)
+        {
+            // Skipped slots repeat the root of the prior block, and the block at the start slot
+            // (if any) has already been applied to the state.
+            if previous_root.map_or(false, |root| root != block_root) {
+                blocks.push(
+                    self.get_block(&block_root)?
+                        .ok_or_else(|| Error::MissingBeaconBlock(block_root))?,
+                );
+            }
+            previous_root = Some(block_root);
+        }
+
+        let summaries = BlockReplayer::new(state, &self.spec)
+            .collect_epoch_summaries()
+            .stop_at_slot(end_slot)
+            .apply_blocks(blocks)?
+            .into_epoch_summaries();
+
+        Ok(summaries
+            .into_iter()
+            .filter(|summary| summary.epoch >= start_epoch && summary.epoch <= end_epoch)
+            .collect())
+    }
+
+    /// Replays the canonical chain from the block at (or after) `start_slot` to the head through
+    /// the full state transition, verifying the signatures of each block and that its state root
+    /// matches the state produced by applying it.
+    ///
+    /// Replaying stops at the first divergence, which is returned in the summary. Errors are only
+    /// returned if the chain cannot be read from the database.
+    ///
+    /// This is a recovery and debugging tool (e.g., after suspected database corruption), and
+    /// may take hours for long ranges.
+    pub fn revalidate_from_slot(&self, start_slot: Slot) -> Result<RevalidationSummary, Error> {
+        let head_slot = self.head_info().slot;
+        // The blocks are applied to the state at the slot prior to `start_slot`, or to the
+        // genesis state (whose block is not replayed). Taking advantage of saturating slot
+        // subtraction.
+        let pre_slot = std::cmp::min(start_slot - 1, head_slot);
+
+        let mut block_roots = vec![];
+        let mut previous_root = None;
+        for (block_root, _slot) in self.forwards_iter_block_roots(pre_slot) {
+            // Skipped slots repeat the root of the prior block, and the block at `pre_slot` (if
+            // any) is already applied to the pre-state.
+            if previous_root.map_or(false, |root| root != block_root) {
+                block_roots.push(block_root);
+            }
+            previous_root = Some(block_root);
+        }
+
+        info!(
+            self.log,
+            "Revalidating chain";
+            "blocks" => block_roots.len(),
+            "head_slot" => head_slot,
+            "start_slot" => start_slot,
+        );
+
+        let state = self.state_at_slot(pre_slot)?;
+        let mut replayer = BlockReplayer::new(state, &self.spec)
+            .block_signature_strategy(BlockSignatureStrategy::VerifyBulk)
+            .verify_state_roots();
+
+        let mut summary = RevalidationSummary {
+            start_slot,
+            head_slot,
+            blocks_verified: 0,
+            divergence: None,
+        };
+
+        for block_root in block_roots {
+            let block = self
+                .get_block(&block_root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+            let slot = block.slot;
+
+            replayer = match replayer.apply_blocks(vec![block]) {
+                Ok(replayer) => replayer,
+                Err(e) => {
+                    summary.divergence = Some(e);
+                    return Ok(summary);
+                }
+            };
+            summary.blocks_verified += 1;
+
+            if summary.blocks_verified % REVALIDATION_LOG_INTERVAL == 0 {
+                info!(
+                    self.log,
+                    "Revalidation progress";
+                    "blocks_verified" => summary.blocks_verified,
+                    "slot" => slot,
+                );
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Computes the state at the start of the next epoch (by the slot clock) from the head, along
+    /// with its committee cache and proposers, so that duties for the next epoch can be served
+    /// without an epoch transition for each request.
+    ///
+    /// Does nothing if the next epoch has already been computed from the present head, or if the
+    /// head is more than an epoch behind the slot clock (e.g., whilst syncing).
+    pub fn precompute_next_epoch(&self) -> Result<(), Error> {
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let next_epoch = self.epoch()? + 1;
+        let head_info = self.head_info();
+
+        if self
+            .next_epoch_cache
+            .contains(head_info.block_root, next_epoch)
+            || head_info.slot.epoch(slots_per_epoch) + 1 < next_epoch
+        {
+            return Ok(());
+        }
+
+        let timer = metrics::start_timer(&metrics::NEXT_EPOCH_PRECOMPUTE_TIMES);
+
+        let head = self.head();
+        let mut state = head.beacon_state;
+        while state.slot < next_epoch.start_slot(slots_per_epoch) {
+            per_slot_processing(&mut state, &self.spec)?;
+        }
+
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+
+        // The proposers are also added to the proposer cache, for verifying the blocks of the
+        // next epoch.
+        let decision_root =
+            self.proposer_shuffling_decision_root(head.beacon_block_root, next_epoch)?;
+        let epoch_proposers = self
+            .beacon_proposer_cache
+            .insert(decision_root, self.compute_epoch_proposers(&state)?);
+
+        self.next_epoch_cache.insert(PrecomputedEpoch {
+            head_block_root: head.beacon_block_root,
+            state,
+            proposers: epoch_proposers
+                .proposers
+                .iter()
+                .map(|(index, _)| *index)
+                .collect(),
+        });
+
+        metrics::stop_timer(timer);
+
+        debug!(
+            self.log,
+            "Precomputed next epoch";
+            "epoch" => next_epoch,
+            "head_block_root" => format!("{}", head.beacon_block_root),
+        );
+
+        Ok(())
+    }
+
+    /// Returns the state (and proposers) at the start of `epoch`, if it was precomputed from the
+    /// present head by `Self::precompute_next_epoch`.
+    pub fn precomputed_epoch(&self, epoch: Epoch) -> Option<Arc<PrecomputedEpoch<T::EthSpec>>> {
+        self.next_epoch_cache
+            .get(self.head_info().block_root, epoch)
+    }
+
+    /// Produces a `FinalityUpdate` proving the finalized checkpoint of the head against the head
+    /// state root.
+    ///
+    /// Returns `None` if no block after genesis has been finalized.
+    pub fn finality_update(&self) -> Result<Option<FinalityUpdate>, Error> {
+        let head = self.head();
+        let finalized_root = head.beacon_state.finalized_checkpoint.root;
+
+        // The root of the genesis checkpoint is zero, rather than the genesis block root.
+        if finalized_root == Hash256::zero() {
+            return Ok(None);
+        }
+
+        let finalized_block = self
+            .get_block_caching(&finalized_root)?
+            .ok_or_else(|| Error::MissingBeaconBlock(finalized_root))?;
+
+        Ok(Some(FinalityUpdate::new(
+            &head.beacon_block,
+            &head.beacon_state,
+            &finalized_block,
+        )?))
+    }
+
+    /// Publishes a `FinalityUpdate` for the finalized checkpoint of the head to the event
+    /// handler, if any block after genesis has been finalized.
+    pub fn publish_finality_update(&self) -> Result<(), Error> {
+        if let Some(update) = self.finality_update()? {
+            debug!(
+                self.log,
+                "Publishing finality update";
+                "finalized_root" => format!("{}", update.finalized_root()),
+                "finalized_slot" => update.finalized_header.slot,
+                "attested_slot" => update.attested_header.slot,
+            );
+
+            let _ = self
+                .event_handler
+                .register(EventKind::BeaconFinalityUpdate(Box::new(update)));
+        }
+
+        Ok(())
+    }
+
+    /// Records the arrival of an aggregate attestation for `attestation_slot`, to learn how long
+    /// to wait for aggregates before packing a block.
+    pub fn observe_aggregate_arrival(&self, attestation_slot: Slot) {
+        let delay = self
+            .slot_clock
+            .duration_since_start_of(attestation_slot + 1)
+            .unwrap_or_default();
+
+        metrics::observe(&metrics::AGGREGATE_ARRIVAL_DELAY_TIMES, delay.as_secs_f64());
+        self.aggregate_arrival_times.observe(delay);
+    }
+
+    /// Returns how long to wait before packing the block for `slot`, allowing late aggregates for
+    /// the previous slot to arrive.
+    ///
+    /// Returns zero if the packing deadline has passed or `slot` has not yet started (e.g., a
+    /// block produced ahead of time).
+    pub fn block_packing_wait(&self, slot: Slot) -> Duration {
+        let offset = self
+            .block_packing_delay
+            .offset(&self.aggregate_arrival_times);
+
+        let wait = self
+            .slot_clock
+            .duration_since_start_of(slot)
+            .and_then(|elapsed| offset.checked_sub(elapsed))
+            .unwrap_or_default();
+
+        metrics::observe(&metrics::BLOCK_PACKING_WAIT_TIMES, wait.as_secs_f64());
+
+        wait
+    }
+
+    /// Estimates the progress of the validator with `validator_index` through the activation and
+    /// exit queues of the head state.
+    pub fn validator_queue_estimate(
+        &self,
+        validator_index: usize,
+    ) -> Result<ValidatorQueueEstimate, Error> {
+        Ok(estimate_validator_queue(
+            &self.canonical_head.read().beacon_state,
+            validator_index,
+            &self.spec,
+        )?)
+    }
+
+    /// Returns the validators of the head state which have been added, or whose record or status
+    /// has changed, since the start of `since_epoch` on the canonical chain.
+    ///
+    /// This allows a consumer tracking many validators to fetch the registry once and then follow
+    /// the changes, rather than fetching the full registry each epoch.
+    pub fn validators_diff(&self, since_epoch: Epoch) -> Result<ValidatorsDiff, Error> {
+        let since_slot = since_epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let head_info = self.head_info();
+
+        let validators = if since_slot < head_info.slot {
+            let previous = self.state_at_slot(since_slot)?;
+            diff_validators(
+                &previous,
+                &self.canonical_head.read().beacon_state,
+                self.spec.far_future_epoch,
+            )
+        } else {
+            vec![]
+        };
+
+        Ok(ValidatorsDiff {
+            since_epoch,
+            epoch: head_info.slot.epoch(T::EthSpec::slots_per_epoch()),
+            validators,
+        })
+    }
+
+    /// Returns the deposits which have been observed in the deposit contract but which are not
+    /// yet included in the head state.
+    ///
+    /// Returns `None` if the chain was built without an eth1 backend.
+    pub fn pending_deposits(&self) -> Option<PendingDeposits> {
+        self.eth1_chain
+            .as_ref()
+            .map(|eth1_chain| eth1_chain.pending_deposits(&self.canonical_head.read().beacon_state))
+    }
+
+    /// Compares the local deposit tree against the `Eth1Data` of the finalized state, refusing to
+    /// produce blocks whilst they diverge (see `Eth1Chain::verify_deposit_tree`).
+    ///
+    /// Returns `None` if the chain was built without an eth1 backend.
+    pub fn check_deposit_tree(&self) -> Result<Option<DepositTreeStatus>, Error> {
+        let eth1_chain = match &self.eth1_chain {
+            Some(eth1_chain) => eth1_chain,
+            None => return Ok(None),
+        };
+
+        let finalized_root = self.head_info().finalized_checkpoint.root;
+        // The finalized checkpoint of the genesis state has a zero root.
+        let finalized_root = if finalized_root.is_zero() {
+            self.genesis_block_root
+        } else {
+            finalized_root
+        };
+        let finalized = self.finalized_checkpoint(finalized_root)?;
+
+        Ok(Some(eth1_chain.verify_deposit_tree(
+            &finalized.beacon_state.eth1_data,
+            &self.log,
+        )?))
+    }
+
+    /// Returns the timestamp of the latest block in the eth1 cache, if any.
+    pub fn latest_eth1_block_timestamp(&self) -> Option<u64> {
+        self.eth1_chain
+            .as_ref()
+            .and_then(|eth1_chain| eth1_chain.latest_block_timestamp())
+    }
+
+    /// Returns `true` if the eth1 backend is in sync with the eth1 chain.
+    ///
+    /// Returns `false` if the chain was built without an eth1 backend, since it cannot produce
+    /// blocks.
+    pub fn is_eth1_synced(&self) -> bool {
+        match (&self.eth1_chain, self.slot_clock.now_duration()) {
+            (Some(eth1_chain), Some(now)) => eth1_chain.is_synced(now.as_secs(), &self.spec),
+            _ => false,
+        }
+    }
+
+    /// Returns the latest `ChainHealth` assessment, or `None` if the node has not yet been
+    /// assessed.
+    pub fn chain_health(&self) -> Option<ChainHealth> {
+        *self.chain_health.read()
+    }
+
+    /// Records the latest `ChainHealth` assessment, which is made each slot by the client.
+    pub fn set_chain_health(&self, chain_health: ChainHealth) {
+        *self.chain_health.write() = Some(chain_health);
+    }
+
+    /// Returns the in-memory caches of the chain, for memory accounting.
+    fn memory_accounted_caches(&self) -> [&dyn MemoryAccounted; 8] {
+        [
+            &self.checkpoint_cache,
+            &self.checkpoint_state_cache,
+            &self.epoch_summary_cache,
+            &self.next_epoch_cache,
+            &self.beacon_proposer_cache,
+            &self.shuffling_cache,
+            &self.observed_proposers,
+            &self.block_equivocations,
+        ]
+    }
+
+    /// Returns an estimate of the memory used by each of the in-memory caches of the chain.
+    pub fn cache_memory_report(&self) -> CacheMemoryReport {
+        cache_memory::memory_report(&self.memory_accounted_caches(), self.cache_memory_budget)
+    }
+
+    /// Evicts cache entries until the caches are within the memory budget, if there is one.
+    fn enforce_cache_memory_budget(&self) {
+        if let Some(budget) = self.cache_memory_budget {
+            let evicted = cache_memory::enforce_budget(&self.memory_accounted_caches(), budget);
+
+            if evicted > 0 {
+                debug!(
+                    self.log,
+                    "Evicted cache entries";
+                    "reason" => "cache memory budget exceeded",
+                    "count" => evicted,
+                );
+            }
+        }
+    }
+
+    /// Returns the recorded balances of the validator with `pubkey` from `start_epoch` to
+    /// `end_epoch` (inclusive).
+    ///
+    /// Returns `None` if the balances of the validator are not recorded.
+    pub fn balance_history(
+        &self,
+        pubkey: &PublicKeyBytes,
+        start_epoch: Epoch,
+        end_epoch: Epoch,
+    ) -> Result<Option<Vec<BalanceRecord>>, Error> {
+        match &self.balance_history {
+            Some(history) if history.is_monitored(pubkey) => Ok(Some(history.get(
+                &*self.store,
+                pubkey,
+                start_epoch,
+                end_epoch,
+            )?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns a Merkle proof of `target` against the root of the state with `state_root`.
+    ///
+    /// Returns `None` if the state is not known.
+    pub fn state_proof(
+        &self,
+        state_root: &Hash256,
+        target: StateProofTarget,
+    ) -> Result<Option<StateProof>, Error> {
+        self.get_state_caching_only_with_committee_caches(state_root, None)?
+            .map(|state| state.compute_merkle_proof(target))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Returns a Merkle proof of the root of the block at `slot` against the root of the state
+    /// with `state_root`, via the `HistoricalBatch` for `slot` in the freezer database.
+    ///
+    /// This allows the block roots of historical slots to be verified, even once the states which
+    /// contain them have been pruned. Returns `None` if the state is not known.
+    pub fn historical_block_root_proof(
+        &self,
+        state_root: &Hash256,
+        slot: Slot,
+    ) -> Result<Option<StateProof>, Error> {
+        let state = match self.get_state_caching_only_with_committee_caches(state_root, None)? {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+
+        let index = slot.as_usize() / T::EthSpec::slots_per_historical_root();
+        if index >= state.historical_roots.len() {
+            return Err(BeaconStateError::SlotOutOfBounds.into());
+        }
+
+        let batch = self
+            .store
+            .get_historical_batch(index)?
+            .ok_or_else(|| Error::MissingHistoricalBatch(index))?;
+
+        state
+            .compute_historical_block_root_proof(slot, &batch)
+            .map(Some)
+            .map_err(Into::into)
+    }
+
+    /// Returns the nodes of the fork choice block tree, ordered by slot.
+    ///
+    /// Intended for debugging fork choice, since the post-state of each node's block is loaded.
+    pub fn fork_choice_nodes(&self) -> Result<Vec<ForkChoiceNode>, Error> {
+        let mut nodes = self
+            .fork_choice
+            .nodes()?
+            .into_iter()
+            .map(|node| {
+                let block = self
+                    .get_block(&node.root)?
+                    .ok_or_else(|| Error::MissingBeaconBlock(node.root))?;
+                let state = self
+                    .store
+                    .get_state(&block.state_root, Some(block.slot))?
+                    .ok_or_else(|| Error::MissingBeaconState(block.state_root))?;
+
+                Ok(ForkChoiceNode {
+                    root: node.root,
+                    parent: node.parent,
+                    slot: block.slot,
+                    weight: node.weight,
+                    justified_epoch: state.current_justified_checkpoint.epoch,
+                    finalized_epoch: state.finalized_checkpoint.epoch,
+                    best_descendant: node.best_descendant,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        nodes.sort_by_key(|node| (node.slot, node.root));
+
+        Ok(nodes)
+    }
+
+    /// Reads the slot clock (see `self.read_slot_clock()` and returns the number of slots since
+    /// genesis.
+    pub fn slots_since_genesis(&self) -> Option<SlotHeight> {
+        let now = self.slot().ok()?;
+        let genesis_slot = self.spec.genesis_slot;
+
+        if now < genesis_slot {
+            None
+        } else {
+            Some(SlotHeight::from(now.as_u64() - genesis_slot.as_u64()))
+        }
+    }
+
+    /// Returns the block proposer for a given slot.
+    ///
+    /// Proposers from the epoch of the head onwards are read from the proposer cache. Earlier
+    /// proposers are read from the state at `slot`.
+    pub fn block_proposer(&self, slot: Slot) -> Result<usize, Error> {
+        let epoch = |slot: Slot| slot.epoch(T::EthSpec::slots_per_epoch());
+        let head_info = self.head_info();
+
+        if epoch(slot) >= epoch(head_info.slot) {
+            return self
+                .epoch_proposers(head_info.block_root, epoch(slot))?
+                .proposer::<T::EthSpec>(slot)
+                .map(|(index, _)| *index)
+                .ok_or_else(|| Error::NoProposerForSlot(slot));
+        }
+
+        let mut state = self.state_at_slot(slot)?;
+
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+
+        if epoch(state.slot) != epoch(slot) {
+            return Err(Error::InvariantViolated(format!(
+                "Epochs in consistent in proposer lookup: state: {}, requested: {}",
+                epoch(state.slot),
+                epoch(slot)
+            )));
+        }
+
+        state
+            .get_beacon_proposer_index(slot, &self.spec)
+            .map_err(Into::into)
+    }
+
+    /// Returns the proposers of `epoch` on the chain of the block with `block_root`, which must be
+    /// from `epoch` or an earlier epoch.
+    ///
+    /// The proposers are read from the `beacon_proposer_cache` if possible. Otherwise, they are
+    /// computed from the state of the block (advanced to the start of `epoch`) and cached.
+    pub fn epoch_proposers(
+        &self,
+        block_root: Hash256,
+        epoch: Epoch,
+    ) -> Result<Arc<EpochProposers>, Error> {
+        let decision_root = self.proposer_shuffling_decision_root(block_root, epoch)?;
+
+        if let Some(proposers) = self.beacon_proposer_cache.get(epoch, decision_root) {
+            return Ok(proposers);
+        }
+
+        let head_state = {
+            let head = self.canonical_head.read();
+            if head.beacon_block_root == block_root {
+                Some(head.beacon_state.clone())
+            } else {
+                None
+            }
+        };
+        let mut state = if let Some(state) = head_state {
+            state
+        } else {
+            let block = self
+                .get_block_caching(&block_root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+            self.get_state_caching_only_with_committee_caches(&block.state_root, Some(block.slot))?
+                .ok_or_else(|| Error::MissingBeaconState(block.state_root))?
+        };
+
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        if state.current_epoch() > epoch {
+            return Err(Error::InvariantViolated(format!(
+                "Proposers for epoch {} requested from a block in epoch {}",
+                epoch,
+                state.current_epoch()
+            )));
+        }
+
+        while state.slot < epoch.start_slot(slots_per_epoch) {
+            per_slot_processing(&mut state, &self.spec)?;
+        }
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+
+        Ok(self
+            .beacon_proposer_cache
+            .insert(decision_root, self.compute_epoch_proposers(&state)?))
+    }
+
+    /// Computes the proposers of the current epoch of `state`, which must have its current
+    /// committee cache built.
+    fn compute_epoch_proposers(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<EpochProposers, Error> {
+        let epoch = state.current_epoch();
+        let proposers = epoch
+            .slot_iter(T::EthSpec::slots_per_epoch())
+            .map(|slot| {
+                let index = state.get_beacon_proposer_index(slot, &self.spec)?;
+                let pubkey = (&state.validators[index].pubkey).try_into().map_err(|e| {
+                    Error::InvariantViolated(format!(
+                        "Invalid pubkey for proposer {}: {:?}",
+                        index, e
+                    ))
+                })?;
+                Ok((index, pubkey))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(EpochProposers {
+            epoch,
+            fork: state.fork.clone(),
+            proposers,
+        })
+    }
+
+    /// Returns the committees of `epoch` on the chain of the block with `block_root`.
+    ///
+    /// The committees are read from the `shuffling_cache` if possible. Otherwise, they are
+    /// computed (from the head state without cloning it, if `block_root` is the head) and cached.
+    pub fn epoch_committees(
+        &self,
+        block_root: Hash256,
+        epoch: Epoch,
+    ) -> Result<Arc<CommitteeCache>, Error> {
+        let decision_root = self.attester_shuffling_decision_root(block_root, epoch)?;
+
+        if let Some(committees) = self.shuffling_cache.get(epoch, decision_root) {
+            return Ok(committees);
+        }
+
+        // The head state can provide the committees of its previous, current and next epochs,
+        // which have usually been built already.
+        let head_committees = {
+            let head = self.canonical_head.read();
+            let state = &head.beacon_state;
+
+            if head.beacon_block_root == block_root
+                && RelativeEpoch::from_epoch(state.current_epoch(), epoch).is_ok()
+            {
+                let committees = match state
+                    .committee_caches
+                    .iter()
+                    .find(|cache| cache.is_initialized_at(epoch))
+                {
+                    Some(cache) => cache.clone(),
+                    None => CommitteeCache::initialized(state, epoch, &self.spec)?,
+                };
+                Some(committees)
+            } else {
+                None
+            }
+        };
+
+        let committees = if let Some(committees) = head_committees {
+            committees
+        } else {
+            let block = self
+                .get_block_caching(&decision_root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(decision_root))?;
+            let mut state = self
+                .get_state_caching_only_with_committee_caches(&block.state_root, Some(block.slot))?
+                .ok_or_else(|| Error::MissingBeaconState(block.state_root))?;
+
+            // The decision block is at least two epochs prior to `epoch` (more, if slots were
+            // skipped), so its state must be advanced for the committees to be computed.
+            while state.current_epoch() + 1 < epoch {
+                per_slot_processing(&mut state, &self.spec)?;
+            }
+
+            CommitteeCache::initialized(&state, epoch, &self.spec)?
+        };
+
+        Ok(self
+            .shuffling_cache
+            .insert(epoch, decision_root, committees))
+    }
+
+    /// Returns the root of the block which decides the proposers of `epoch` on the chain of the
+    /// block with `block_root`: the latest block at or before the last slot of the previous epoch.
+    fn proposer_shuffling_decision_root(
+        &self,
+        block_root: Hash256,
+        epoch: Epoch,
+    ) -> Result<Hash256, Error> {
+        let decision_slot = epoch
+            .start_slot(T::EthSpec::slots_per_epoch())
+            .saturating_sub(1u64);
+
+        self.decision_root(block_root, decision_slot)
+    }
+
+    /// Returns the root of the block which decides the committees of `epoch` on the chain of the
+    /// block with `block_root`: the latest block at or before the last slot of the epoch two
+    /// epochs prior.
+    fn attester_shuffling_decision_root(
+        &self,
+        block_root: Hash256,
+        epoch: Epoch,
+    ) -> Result<Hash256, Error> {
+        let decision_slot = epoch
+            .saturating_sub(1u64)
+            .start_slot(T::EthSpec::slots_per_epoch())
+            .saturating_sub(1u64);
+
+        self.decision_root(block_root, decision_slot)
+    }
+
+    /// Returns the root of the latest block at or before `decision_slot` on the chain of the block
+    /// with `block_root`.
+    fn decision_root(&self, block_root: Hash256, decision_slot: Slot) -> Result<Hash256, Error> {
+        // Blocks usually build upon the head, in which case the decision root can be read from
+        // the head state without loading any blocks.
+        {
+            let head = self.canonical_head.read();
+            if head.beacon_block_root == block_root {
+                if head.beacon_block.slot <= decision_slot {
+                    return Ok(block_root);
+                } else if let Ok(root) = head.beacon_state.get_block_root(decision_slot) {
+                    return Ok(*root);
+                }
+            }
+        }
+
+        let mut root = block_root;
+        loop {
+            let block = self
+                .get_block_caching(&root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(root))?;
+
+            if block.slot <= decision_slot {
+                return Ok(root);
+            }
+            root = block.parent_root;
+        }
+    }
+
+    /// Writes `pre_state`, `block` and `error` to the debug dump directory, if blocks which fail the
+    /// state transition are being dumped.
+    fn dump_invalid_block<E: std::fmt::Debug>(
+        &self,
+        pre_state: Option<&BeaconState<T::EthSpec>>,
+        block: &BeaconBlock<T::EthSpec>,
+        block_root: Hash256,
+        error: &E,
+    ) {
+        let (debug_dump, pre_state) = match (&self.debug_dump, pre_state) {
+            (Some(debug_dump), Some(pre_state)) => (debug_dump, pre_state),
+            _ => return,
+        };
+
+        match debug_dump.dump(pre_state, block, block_root, &format!("{:?}", error)) {
+            Ok(Some(path)) => warn!(
+                self.log,
+                "Dumped block which failed the state transition";
+                "path" => format!("{:?}", path),
+                "slot" => block.slot,
+                "block_root" => format!("{}", block_root),
+            ),
+            Ok(None) => debug!(
+                self.log,
+                "Skipped dumping block which failed the state transition";
+                "slot" => block.slot,
+                "block_root" => format!("{}", block_root),
+            ),
+            Err(e) => error!(
+                self.log,
+                "Failed to dump block which failed the state transition";
+                "error" => e,
+            ),
+        }
+    }
+
+    /// Records that a block for `slot` was produced for a local validator, so that it may be
+    /// reported if it does not become canonical.
+    pub fn observe_local_block_production(&self, slot: Slot) {
+        let delay = self
+            .slot_clock
+            .duration_since_start_of(slot)
+            .unwrap_or_else(|| Duration::from_millis(0));
+
+        self.local_proposals.observe_production(slot, delay);
+        self.persist_local_proposals();
+        self.production_times
+            .observe_production(LocalMessage::Block, slot, delay);
+    }
+
+    /// Records that `block` from a local validator was published to the network, or that
+    /// publishing it failed.
+    pub fn observe_local_block_publication(
+        &self,
+        block: &BeaconBlock<T::EthSpec>,
+        published: bool,
+    ) {
+        self.local_proposals
+            .observe_publication(block.slot, block.canonical_root(), !published);
+        self.persist_local_proposals();
+
+        if published {
+            self.observe_local_publication(LocalMessage::Block, block.slot);
         }
     }
 
-    /// Returns the `BeaconState` the current slot (viz., `self.slot()`).
-    ///
-    ///  - A reference to the head state (note: this keeps a read lock on the head, try to use
-    ///  sparingly).
-    ///  - The head state, but with skipped slots (for states later than the head).
-    ///
-    ///  Returns `None` when there is an error skipping to a future state or the slot clock cannot
-    ///  be read.
-    pub fn wall_clock_state(&self) -> Result<BeaconState<T::EthSpec>, Error> {
-        self.state_at_slot(self.slot()?)
+    /// Records that a `message` for `slot` (other than a block, see
+    /// `observe_local_block_production`) was produced for a local validator.
+    pub fn observe_local_production(&self, message: LocalMessage, slot: Slot) {
+        let delay = self
+            .slot_clock
+            .duration_since_start_of(slot)
+            .unwrap_or_default();
+
+        self.production_times
+            .observe_production(message, slot, delay);
     }
 
-    /// Returns the slot of the highest block in the canonical chain.
-    pub fn best_slot(&self) -> Slot {
-        self.canonical_head.read().beacon_block.slot
+    /// Records that a `message` for `slot` from a local validator was published to the network,
+    /// observing the time since it was produced.
+    pub fn observe_local_publication(&self, message: LocalMessage, slot: Slot) {
+        let delay = self
+            .slot_clock
+            .duration_since_start_of(slot)
+            .unwrap_or_default();
+
+        if let Some(elapsed) = self
+            .production_times
+            .production_to_publication(message, slot, delay)
+        {
+            metrics::observe_vec(
+                &metrics::LOCAL_PRODUCTION_TO_PUBLISH_TIMES,
+                &[message.topic()],
+                elapsed.as_secs_f64(),
+            );
+        }
     }
 
-    /// Returns the validator index (if any) for the given public key.
-    ///
-    /// Information is retrieved from the present `beacon_state.validators`.
-    pub fn validator_index(&self, pubkey: &PublicKeyBytes) -> Option<usize> {
-        for (i, validator) in self.head().beacon_state.validators.iter().enumerate() {
-            if validator.pubkey == *pubkey {
-                return Some(i);
+    /// Reports the outcome of each local proposal at or before `finalized_slot`, warning about
+    /// those which did not become canonical.
+    fn resolve_local_proposals(&self, finalized_slot: Slot) {
+        let slots = self.local_proposals.finalized_slots(finalized_slot);
+        let lowest_slot = match slots.iter().min() {
+            Some(slot) => *slot,
+            None => return,
+        };
+
+        let canonical_roots = self
+            .rev_iter_block_roots()
+            .skip_while(|(_, slot)| *slot > finalized_slot)
+            .take_while(|(_, slot)| *slot >= lowest_slot)
+            .filter(|(_, slot)| slots.contains(slot))
+            .map(|(root, slot)| (slot, root))
+            .collect::<HashMap<_, _>>();
+
+        // Attesters vote for the head a third of the way into the slot.
+        let late_delay = self.slot_clock.slot_duration() / 3;
+
+        let resolved = self
+            .local_proposals
+            .resolve(finalized_slot, late_delay, |slot| {
+                canonical_roots.get(&slot).copied()
+            });
+
+        for (proposal, outcome) in resolved {
+            match outcome {
+                ProposalOutcome::Canonical => debug!(
+                    self.log,
+                    "Local block is canonical";
+                    "slot" => proposal.slot,
+                    "block_root" => format!("{}", proposal.block_root),
+                ),
+                ProposalOutcome::Orphaned(cause) => warn!(
+                    self.log,
+                    "Local block was orphaned";
+                    "probable_cause" => cause.as_str(),
+                    "production_delay_ms" => proposal.production_delay_millis,
+                    "slot" => proposal.slot,
+                    "block_root" => format!("{}", proposal.block_root),
+                ),
+                ProposalOutcome::Missed(cause) => warn!(
+                    self.log,
+                    "Local block proposal was missed";
+                    "probable_cause" => cause.as_str(),
+                    "production_delay_ms" => proposal.production_delay_millis,
+                    "slot" => proposal.slot,
+                ),
             }
         }
-        None
+
+        self.persist_local_proposals();
     }
 
-    /// Returns the block canonical root of the current canonical chain at a given slot.
+    fn persist_local_proposals(&self) {
+        if let Err(e) = self.local_proposals.persist(&*self.store) {
+            error!(
+                self.log,
+                "Failed to persist local proposals";
+                "error" => format!("{:?}", e),
+            );
+        }
+    }
+
+    /// Records that `block` was produced by a local validator and is about to be published,
+    /// returning `ProposalObservation::Equivocation` if a different block from the same proposer
+    /// at the same slot has already been observed.
     ///
-    /// Returns None if a block doesn't exist at the slot.
-    pub fn root_at_slot(&self, target_slot: Slot) -> Option<Hash256> {
-        self.rev_iter_block_roots()
-            .find(|(_root, slot)| *slot == target_slot)
-            .map(|(root, _slot)| root)
+    /// A block must not be published unless this function returns `New` or `Repeated`. New
+    /// observations are persisted immediately, so they are still known after a restart.
+    pub fn observe_local_block(
+        &self,
+        block: &BeaconBlock<T::EthSpec>,
+    ) -> Result<ProposalObservation, Error> {
+        let proposer_index = self.block_proposer(block.slot)? as u64;
+        let block_root = block.canonical_root();
+
+        let observation =
+            self.observed_proposers
+                .observe::<T::EthSpec>(proposer_index, block.slot, block_root);
+
+        match observation {
+            ProposalObservation::New => self.observed_proposers.persist(&*self.store)?,
+            ProposalObservation::Repeated => (),
+            ProposalObservation::Equivocation {
+                previous_block_root,
+            } => crit!(
+                self.log,
+                "Refusing to publish conflicting block";
+                "msg" => "check for redundant validator clients using the same keys",
+                "previous_block_root" => format!("{}", previous_block_root),
+                "block_root" => format!("{}", block_root),
+                "slot" => block.slot,
+                "proposer_index" => proposer_index,
+            ),
+        }
+
+        Ok(observation)
     }
 
-    /// Reads the slot clock (see `self.read_slot_clock()` and returns the number of slots since
-    /// genesis.
-    pub fn slots_since_genesis(&self) -> Option<SlotHeight> {
-        let now = self.slot().ok()?;
-        let genesis_slot = self.spec.genesis_slot;
+    /// Performs the cheap checks required before a block is propagated on gossip, without
+    /// importing it: the block must be from a slot between the finalized slot and the present
+    /// slot, must not already be known, must have a known parent and must be signed by the
+    /// expected proposer.
+    ///
+    /// Passing these checks does not imply the block is valid, only that it is safe to broadcast.
+    pub fn verify_block_for_gossip(
+        &self,
+        block: &BeaconBlock<T::EthSpec>,
+    ) -> Result<GossipBlockOutcome, Error> {
+        let invalid = |outcome| Ok(GossipBlockOutcome::Invalid(outcome));
 
-        if now < genesis_slot {
-            None
-        } else {
-            Some(SlotHeight::from(now.as_u64() - genesis_slot.as_u64()))
+        let finalized_slot = self
+            .head_info()
+            .finalized_checkpoint
+            .epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+
+        if block.slot == 0 {
+            return invalid(BlockProcessingOutcome::GenesisBlock);
+        }
+
+        if block.slot >= MAXIMUM_BLOCK_SLOT_NUMBER {
+            return invalid(BlockProcessingOutcome::BlockSlotLimitReached);
+        }
+
+        if block.slot <= finalized_slot {
+            return invalid(BlockProcessingOutcome::WouldRevertFinalizedSlot {
+                block_slot: block.slot,
+                finalized_slot,
+            });
+        }
+
+        let present_slot = self.slot_with_future_tolerance()?;
+
+        if block.slot > present_slot {
+            return invalid(BlockProcessingOutcome::FutureSlot {
+                present_slot,
+                block_slot: block.slot,
+            });
+        }
+
+        let block_root = block.canonical_root();
+
+        if self.store.exists::<BeaconBlock<T::EthSpec>>(&block_root)? {
+            return invalid(BlockProcessingOutcome::BlockIsAlreadyKnown);
+        }
+
+        if !self
+            .store
+            .exists::<BeaconBlock<T::EthSpec>>(&block.parent_root)?
+        {
+            return invalid(BlockProcessingOutcome::ParentUnknown {
+                parent: block.parent_root,
+            });
+        }
+
+        let epoch = block.slot.epoch(T::EthSpec::slots_per_epoch());
+        let proposers = self.epoch_proposers(block.parent_root, epoch)?;
+        let (proposer_index, proposer_pubkey) = proposers
+            .proposer::<T::EthSpec>(block.slot)
+            .ok_or_else(|| Error::NoProposerForSlot(block.slot))?;
+        let domain = self
+            .spec
+            .get_domain(epoch, Domain::BeaconProposer, &proposers.fork);
+
+        if !block
+            .signature
+            .verify(&block.signed_root(), domain, proposer_pubkey)
+        {
+            return invalid(BlockProcessingOutcome::PerBlockProcessingError(
+                BlockProcessingError::HeaderInvalid {
+                    reason: HeaderInvalid::ProposalSignatureInvalid,
+                },
+            ));
         }
+
+        self.observe_gossip_block(*proposer_index as u64, block, finalized_slot);
+
+        Ok(GossipBlockOutcome::Valid { block_root })
     }
 
-    /// Returns the block proposer for a given slot.
+    /// Records a block received on gossip, with a valid signature from `proposer_index`.
     ///
-    /// Information is read from the present `beacon_state` shuffling, only information from the
-    /// present epoch is available.
-    pub fn block_proposer(&self, slot: Slot) -> Result<usize, Error> {
-        let epoch = |slot: Slot| slot.epoch(T::EthSpec::slots_per_epoch());
-        let head_state = &self.head().beacon_state;
+    /// If the proposer has already signed a conflicting block at the same slot, a
+    /// `ProposerSlashing` is added to the op pool (and queued for publication, if the slasher is
+    /// enabled).
+    fn observe_gossip_block(
+        &self,
+        proposer_index: u64,
+        block: &BeaconBlock<T::EthSpec>,
+        finalized_slot: Slot,
+    ) {
+        let slashing = match self.block_equivocations.observe(
+            proposer_index,
+            block.block_header(),
+            finalized_slot,
+        ) {
+            Some(slashing) => slashing,
+            None => return,
+        };
 
-        let mut state = if epoch(slot) == epoch(head_state.slot) {
-            self.head().beacon_state.clone()
-        } else {
-            self.state_at_slot(slot)?
+        metrics::inc_counter(&metrics::GOSSIP_BLOCK_EQUIVOCATIONS);
+
+        let monitored = match (
+            self.balance_history.as_ref(),
+            self.head()
+                .beacon_state
+                .validators
+                .get(proposer_index as usize),
+        ) {
+            (Some(balance_history), Some(validator)) => {
+                balance_history.is_monitored(&validator.pubkey)
+            }
+            _ => false,
         };
 
-        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+        if monitored {
+            crit!(
+                self.log,
+                "Monitored validator equivocated";
+                "info" => "a slashing will be included in a block",
+                "proposer_index" => proposer_index,
+                "slot" => block.slot,
+            );
+        } else {
+            warn!(
+                self.log,
+                "Proposer equivocated on gossip";
+                "proposer_index" => proposer_index,
+                "slot" => block.slot,
+            );
+        }
 
-        if epoch(state.slot) != epoch(slot) {
-            return Err(Error::InvariantViolated(format!(
-                "Epochs in consistent in proposer lookup: state: {}, requested: {}",
-                epoch(state.slot),
-                epoch(slot)
-            )));
+        if let Err(e) = self.process_proposer_slashing(slashing.clone()) {
+            // The slashing may be invalid if the proposer has already been slashed.
+            warn!(
+                self.log,
+                "Proposer slashing rejected by op pool";
+                "proposer_index" => proposer_index,
+                "error" => format!("{:?}", e),
+            );
+            return;
         }
 
-        state
-            .get_beacon_proposer_index(slot, &self.spec)
-            .map_err(Into::into)
+        if self.slasher.is_some() {
+            self.block_equivocations.queue_for_publication(slashing);
+        }
+    }
+
+    /// Returns `true` if a conflicting block from `proposer_index` has been seen on gossip.
+    pub fn is_equivocating_proposer(&self, proposer_index: u64) -> bool {
+        self.block_equivocations.is_equivocator(proposer_index)
+    }
+
+    /// Returns the proposer slashings constructed from blocks seen on gossip since the last call,
+    /// to be published by the slasher service.
+    pub fn take_unpublished_proposer_slashings(&self) -> Vec<ProposerSlashing> {
+        self.block_equivocations.take_unpublished()
     }
 
     /// Returns the attestation slot and committee index for a given validator index.
@@ -675,6 +2220,38 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         })
     }
 
+    /// Produce an unsigned, unaggregated `Attestation` for each committee at `slot`, in order of
+    /// committee index.
+    ///
+    /// Equivalent to calling `produce_attestation` for each committee, except that the state at
+    /// `slot` is only loaded once.
+    pub fn produce_attestations_at_slot(
+        &self,
+        slot: Slot,
+    ) -> Result<Vec<Attestation<T::EthSpec>>, Error> {
+        let state = self.state_at_slot(slot)?;
+        let head = self.head();
+
+        state
+            .get_beacon_committees_at_slot(slot)?
+            .into_iter()
+            .map(|committee| {
+                let data = self.produce_attestation_data_for_block(
+                    committee.index,
+                    head.beacon_block_root,
+                    head.beacon_block.slot,
+                    &state,
+                )?;
+
+                Ok(Attestation {
+                    aggregation_bits: BitList::with_capacity(committee.committee.len())?,
+                    data,
+                    signature: AggregateSignature::new(),
+                })
+            })
+            .collect()
+    }
+
     /// Produce an `AttestationData` that is valid for the given `slot`, `index`.
     ///
     /// Always attests to the canonical chain.
@@ -967,10 +2544,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         );
 
         // A helper function to allow attestation processing to be metered.
-        let verify_attestation_for_state = |state, attestation, spec, verify_signatures| {
+        let verify_attestation_for_state = |state, attestation| {
             let timer = metrics::start_timer(&metrics::ATTESTATION_PROCESSING_CORE);
 
-            let result = verify_attestation_for_state(state, attestation, spec, verify_signatures);
+            let result = verify_attestation_for_state_with_pubkey_cache(
+                state,
+                attestation,
+                &self.committee_pubkey_cache,
+                &self.spec,
+            );
 
             metrics::stop_timer(timer);
             result
@@ -991,9 +2573,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 attestation: block.slot.epoch(T::EthSpec::slots_per_epoch()),
                 finalized: finalized_epoch,
             })
-        } else if let Err(e) =
-            verify_attestation_for_state(state, &attestation, VerifySignatures::True, &self.spec)
-        {
+        } else if let Err(e) = verify_attestation_for_state(state, &attestation) {
             warn!(
                 self.log,
                 "Invalid attestation";
@@ -1026,6 +2606,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 }
             }
 
+            // Provide the valid attestation to the slasher, which will check it for slashable
+            // votes.
+            if let Some(slasher) = self.slasher.as_ref() {
+                match get_indexed_attestation(state, &attestation) {
+                    Ok(indexed_attestation) => slasher.accept_attestation(indexed_attestation),
+                    Err(e) => error!(
+                        self.log,
+                        "Unable to index attestation for slasher";
+                        "error" => format!("{:?}", e),
+                    ),
+                }
+            }
+
             // Provide the valid attestation to op pool, which may choose to retain the
             // attestation for inclusion in a future block.
             self.op_pool
@@ -1156,6 +2749,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         block: BeaconBlock<T::EthSpec>,
     ) -> Result<BlockProcessingOutcome, Error> {
+        // Prevents `shutdown` from persisting the chain whilst this block is being imported.
+        let _import_lock = self.block_import_lock.read();
+        if self.is_shutting_down() {
+            return Err(Error::ShuttingDown);
+        }
+
         metrics::inc_counter(&metrics::BLOCK_PROCESSING_REQUESTS);
         let full_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_TIMES);
 
@@ -1190,7 +2789,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             return Ok(BlockProcessingOutcome::GenesisBlock);
         }
 
-        let present_slot = self.slot()?;
+        let present_slot = self.slot_with_future_tolerance()?;
 
         if block.slot > present_slot {
             return Ok(BlockProcessingOutcome::FutureSlot {
@@ -1238,6 +2837,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // slot and the block slot. These will need to be stored in the database.
         let mut intermediate_states = vec![];
 
+        // Keep the parent state if blocks which fail the state transition are being dumped.
+        let pre_state = self.debug_dump.as_ref().map(|_| parent_state.clone());
+
+        let timing_recorder = if self.log_block_processing_breakdown {
+            Some(TimingRecorder::start())
+        } else {
+            None
+        };
+
         // Transition the parent state to the block slot.
         let mut state: BeaconState<T::EthSpec> = parent_state;
         let distance = block.slot.as_u64().saturating_sub(state.slot.as_u64());
@@ -1245,7 +2853,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             if i > 0 {
                 intermediate_states.push(state.clone());
             }
-            per_slot_processing(&mut state, &self.spec)?;
+            let summary = per_slot_processing(&mut state, &self.spec).map_err(|e| {
+                self.dump_invalid_block(pre_state.as_ref(), &block, block_root, &e);
+                e
+            })?;
+            if let Some(summary) = summary {
+                // The latest block header has its state root filled in during slot processing, so
+                // its root is the root of the block preceding the epoch transition.
+                self.epoch_summary_cache
+                    .insert(state.latest_block_header.canonical_root(), summary);
+            }
         }
 
         metrics::stop_timer(catchup_timer);
@@ -1263,6 +2880,28 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             &self.log,
         );
 
+        let signature_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_SIGNATURE);
+
+        // Verify all the signatures in the block before processing it, so the time spent on
+        // signature verification is measured separately from the rest of the state transition.
+        let signatures_valid = time_stage("verify_block_signatures", || {
+            BlockSignatureVerifier::verify_entire_block_with_pubkey_cache(
+                &state,
+                &block,
+                &self.committee_pubkey_cache,
+                &self.spec,
+            )
+        })
+        .is_ok();
+
+        metrics::stop_timer(signature_timer);
+
+        if !signatures_valid {
+            let e = BlockProcessingError::BulkSignatureVerificationFailed;
+            self.dump_invalid_block(pre_state.as_ref(), &block, block_root, &e);
+            return Ok(BlockProcessingOutcome::PerBlockProcessingError(e));
+        }
+
         let core_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_CORE);
 
         // Apply the received block to its parent state (which has been transitioned into this
@@ -1271,13 +2910,17 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             &mut state,
             &block,
             Some(block_root),
-            BlockSignatureStrategy::VerifyIndividual,
+            BlockSignatureStrategy::NoVerification,
             &self.spec,
         ) {
             Err(BlockProcessingError::BeaconStateError(e)) => {
-                return Err(Error::BeaconStateError(e))
+                self.dump_invalid_block(pre_state.as_ref(), &block, block_root, &e);
+                return Err(Error::BeaconStateError(e));
+            }
+            Err(e) => {
+                self.dump_invalid_block(pre_state.as_ref(), &block, block_root, &e);
+                return Ok(BlockProcessingOutcome::PerBlockProcessingError(e));
             }
-            Err(e) => return Ok(BlockProcessingOutcome::PerBlockProcessingError(e)),
             _ => {}
         }
 
@@ -1285,7 +2928,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         let state_root_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_STATE_ROOT);
 
-        let state_root = state.update_tree_hash_cache()?;
+        let state_root = time_stage("update_tree_hash_cache", || state.update_tree_hash_cache())?;
 
         metrics::stop_timer(state_root_timer);
 
@@ -1296,10 +2939,22 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         );
 
         if block.state_root != state_root {
-            return Ok(BlockProcessingOutcome::StateRootMismatch {
+            let outcome = BlockProcessingOutcome::StateRootMismatch {
                 block: block.state_root,
                 local: state_root,
-            });
+            };
+            self.dump_invalid_block(pre_state.as_ref(), &block, block_root, &outcome);
+            return Ok(outcome);
+        }
+
+        if let Some(timing_recorder) = timing_recorder {
+            info!(
+                self.log,
+                "Block processing breakdown";
+                "stages" => timing_recorder.finish().to_string(),
+                "block_root" => format!("{}", block_root),
+                "slot" => block.slot,
+            );
         }
 
         let db_write_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_DB_WRITE);
@@ -1318,6 +2973,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
             self.store
                 .put_state(&intermediate_state_root, intermediate_state)?;
+            self.store
+                .put_state_slot(&intermediate_state_root, intermediate_state.slot)?;
         }
 
         // Store the block and state.
@@ -1327,7 +2984,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // settles down).
         // See: https://github.com/sigp/lighthouse/issues/692
         self.store.put_state(&state_root, &state)?;
+        self.store.put_state_slot(&state_root, state.slot)?;
         self.store.put(&block_root, &block)?;
+        self.store.put_block_slot(&block_root, block.slot)?;
 
         metrics::stop_timer(db_write_timer);
 
@@ -1368,6 +3027,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             beacon_state_root: state_root,
             beacon_state: state,
         });
+        self.enforce_cache_memory_budget();
 
         metrics::stop_timer(full_timer);
 
@@ -1382,12 +3042,23 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         randao_reveal: Signature,
         slot: Slot,
+    ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
+        self.produce_block_with_graffiti(randao_reveal, slot, None)
+    }
+
+    /// As per `self.produce_block()`, but includes the given `graffiti` in the block instead of
+    /// the default `GRAFFITI` (if it is not `None`).
+    pub fn produce_block_with_graffiti(
+        &self,
+        randao_reveal: Signature,
+        slot: Slot,
+        graffiti: Option<[u8; 32]>,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
         let state = self
             .state_at_slot(slot - 1)
             .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?;
 
-        self.produce_block_on_state(state, slot, randao_reveal)
+        self.produce_block_on_state(state, slot, randao_reveal, graffiti)
     }
 
     /// Produce a block for some `slot` upon the given `state`.
@@ -1397,12 +3068,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// non-current slots.
     ///
     /// The given state will be advanced to the given `produce_at_slot`, then a block will be
-    /// produced at that slot height.
+    /// produced at that slot height. If `graffiti` is `None`, the default `GRAFFITI` is used.
     pub fn produce_block_on_state(
         &self,
         mut state: BeaconState<T::EthSpec>,
         produce_at_slot: Slot,
         randao_reveal: Signature,
+        graffiti: Option<[u8; 32]>,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
         metrics::inc_counter(&metrics::BLOCK_PRODUCTION_REQUESTS);
         let timer = metrics::start_timer(&metrics::BLOCK_PRODUCTION_TIMES);
@@ -1427,8 +3099,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             state.latest_block_header.canonical_root()
         };
 
-        let mut graffiti: [u8; 32] = [0; 32];
-        graffiti.copy_from_slice(GRAFFITI.as_bytes());
+        let graffiti = graffiti.unwrap_or_else(|| {
+            let mut graffiti: [u8; 32] = [0; 32];
+            graffiti.copy_from_slice(GRAFFITI.as_bytes());
+            graffiti
+        });
 
         let (proposer_slashings, attester_slashings) =
             self.op_pool.get_slashings(&state, &self.spec);
@@ -1453,6 +3128,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 attestations: self.op_pool.get_attestations(&state, &self.spec).into(),
                 deposits,
                 voluntary_exits: self.op_pool.get_voluntary_exits(&state, &self.spec).into(),
+                extension: Default::default(),
             },
         };
 
@@ -1565,19 +3241,49 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
                 new_head.beacon_state.build_all_caches(&self.spec)?;
 
+                let reorg_depth = if is_reorg {
+                    reorg_depth(&self.canonical_head.read(), &new_head)
+                } else {
+                    0
+                };
+
                 let timer = metrics::start_timer(&metrics::UPDATE_HEAD_TIMES);
 
                 // Store the head in the checkpoint cache.
                 //
                 // The head block is likely to be referenced by the next imported block.
                 self.checkpoint_cache.insert(&new_head);
+                self.enforce_cache_memory_budget();
 
                 // Update the checkpoint that stores the head of the chain at the time it received the
                 // block.
+                let new_head_info = HeadInfo::from_checkpoint(&new_head);
                 *self.canonical_head.write() = new_head;
+                *self.head_info.write() = new_head_info;
 
                 metrics::stop_timer(timer);
 
+                if new_slot.epoch(T::EthSpec::slots_per_epoch())
+                    != previous_slot.epoch(T::EthSpec::slots_per_epoch())
+                {
+                    self.record_balance_history();
+                }
+
+                if new_slot.epoch(T::EthSpec::slots_per_epoch())
+                    > previous_slot.epoch(T::EthSpec::slots_per_epoch())
+                {
+                    self.publish_epoch_summary();
+                }
+
+                if self.slot_clock.now() == Some(new_slot) {
+                    if let Some(delay) = self.slot_clock.duration_since_start_of(new_slot) {
+                        metrics::observe(
+                            &metrics::BLOCK_HEAD_SLOT_START_DELAY_TIME,
+                            delay.as_secs_f64(),
+                        );
+                    }
+                }
+
                 // Save `self` to `self.store`.
                 self.persist()?;
 
@@ -1587,6 +3293,14 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     current_head_beacon_block_root,
                 });
 
+                self.head_subscribers.notify(HeadChange {
+                    block_root: current_head_beacon_block_root,
+                    slot: new_slot,
+                    previous_block_root: previous_head_beacon_block_root,
+                    previous_slot,
+                    reorg_depth,
+                });
+
                 if new_finalized_epoch != old_finalized_epoch {
                     self.after_finalization(old_finalized_epoch, finalized_root)?;
                 }
@@ -1607,6 +3321,39 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         result
     }
 
+    /// Stores the balances of the monitored validators in the head state, if any.
+    ///
+    /// Failures are logged rather than returned, since they do not affect the head.
+    fn record_balance_history(&self) {
+        if let Some(history) = &self.balance_history {
+            let head = self.canonical_head.read();
+            if let Err(e) = history.record(&*self.store, &head.beacon_state) {
+                warn!(
+                    self.log,
+                    "Failed to record validator balances";
+                    "error" => format!("{:?}", e),
+                    "epoch" => head.beacon_state.current_epoch(),
+                );
+            }
+        }
+    }
+
+    /// Publishes an `EpochSummary` of the head state to the event handler.
+    fn publish_epoch_summary(&self) {
+        let epoch = self.head_info().slot.epoch(T::EthSpec::slots_per_epoch());
+        // Read before the head, since `epoch_summary` also reads the head.
+        let participation = self.epoch_summary(epoch.saturating_sub(2u64));
+
+        let summary = EpochSummary::new(
+            &self.canonical_head.read().beacon_state,
+            participation.as_ref().map(|summary| &**summary),
+        );
+
+        let _ = self
+            .event_handler
+            .register(EventKind::EpochSummary(Box::new(summary)));
+    }
+
     /// Called after `self` has had a new block finalized.
     ///
     /// Performs pruning and finality-based optimizations.
@@ -1615,10 +3362,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         old_finalized_epoch: Epoch,
         finalized_block_root: Hash256,
     ) -> Result<(), Error> {
-        let finalized_block = self
-            .store
-            .get::<BeaconBlock<T::EthSpec>>(&finalized_block_root)?
-            .ok_or_else(|| Error::MissingBeaconBlock(finalized_block_root))?;
+        let finalized = self.finalized_checkpoint(finalized_block_root)?;
+        let finalized_block = &finalized.beacon_block;
 
         let new_finalized_epoch = finalized_block.slot.epoch(T::EthSpec::slots_per_epoch());
 
@@ -1629,23 +3374,20 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             })
         } else {
             self.fork_choice
-                .process_finalization(&finalized_block, finalized_block_root)?;
+                .process_finalization(finalized_block, finalized_block_root)?;
 
-            let finalized_state = self
-                .store
-                .get_state(&finalized_block.state_root, Some(finalized_block.slot))?
-                .ok_or_else(|| Error::MissingBeaconState(finalized_block.state_root))?;
-
-            self.op_pool.prune_all(&finalized_state, &self.spec);
+            self.op_pool.prune_all(&finalized.beacon_state, &self.spec);
 
             // TODO: configurable max finality distance
             let max_finality_distance = 0;
             self.store_migrator.freeze_to_state(
-                finalized_block.state_root,
-                finalized_state,
+                finalized.beacon_state_root,
+                finalized.beacon_state.clone(),
                 max_finality_distance,
             );
 
+            self.resolve_local_proposals(finalized_block.slot);
+
             let _ = self.event_handler.register(EventKind::BeaconFinalization {
                 epoch: new_finalized_epoch,
                 root: finalized_block_root,
@@ -1716,17 +3458,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
 impl<T: BeaconChainTypes> Drop for BeaconChain<T> {
     fn drop(&mut self) {
-        if let Err(e) = self.persist() {
-            error!(
-                self.log,
-                "Failed to persist BeaconChain on drop";
-                "error" => format!("{:?}", e)
-            )
-        } else {
-            info!(
-                self.log,
-                "Saved beacon chain state";
-            )
+        // The chain has already been persisted if it was shut down.
+        if !self.is_shutting_down() {
+            let _ = self.persist_on_shutdown();
         }
     }
 }
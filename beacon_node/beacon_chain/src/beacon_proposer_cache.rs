@@ -0,0 +1,164 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use crate::metrics;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{Epoch, EthSpec, Fork, Hash256, PublicKey, Slot};
+
+/// The maximum number of epochs (across all forks) for which proposers are kept.
+pub const BEACON_PROPOSER_CACHE_SIZE: usize = 16;
+
+/// The proposers for each slot of an epoch, on one chain.
+pub struct EpochProposers {
+    pub epoch: Epoch,
+    /// The fork of the state at the start of the epoch, required to verify proposer signatures.
+    pub fork: Fork,
+    /// The index and public key of the proposer of each slot of the epoch, in slot order.
+    pub proposers: Vec<(usize, PublicKey)>,
+}
+
+impl EpochProposers {
+    /// Returns the index and public key of the proposer of `slot`, if it is in `self.epoch`.
+    pub fn proposer<T: EthSpec>(&self, slot: Slot) -> Option<&(usize, PublicKey)> {
+        if slot.epoch(T::slots_per_epoch()) != self.epoch {
+            return None;
+        }
+
+        let start_slot = self.epoch.start_slot(T::slots_per_epoch());
+        self.proposers.get((slot - start_slot).as_usize())
+    }
+}
+
+/// Caches the proposers of recent epochs, so that a block proposer can be found without loading
+/// (and possibly advancing) a state.
+///
+/// The proposers of an epoch are determined by the chain up to the last slot of the previous
+/// epoch, so they are keyed by `(epoch, decision_root)` where `decision_root` is the root of the
+/// latest block at or before that slot. Every block of an epoch which descends from the same
+/// decision block shares the same proposers.
+#[derive(Default)]
+pub struct BeaconProposerCache {
+    cache: RwLock<HashMap<(Epoch, Hash256), Arc<EpochProposers>>>,
+}
+
+impl BeaconProposerCache {
+    /// Returns the proposers of `epoch` decided by the block with `decision_root`, if known.
+    pub fn get(&self, epoch: Epoch, decision_root: Hash256) -> Option<Arc<EpochProposers>> {
+        let proposers = self.cache.read().get(&(epoch, decision_root)).cloned();
+
+        if proposers.is_some() {
+            metrics::inc_counter(&metrics::BEACON_PROPOSER_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::BEACON_PROPOSER_CACHE_MISSES);
+        }
+
+        proposers
+    }
+
+    /// Adds the `proposers` decided by the block with `decision_root`, evicting the entries of
+    /// the lowest epochs if the cache is full.
+    pub fn insert(&self, decision_root: Hash256, proposers: EpochProposers) -> Arc<EpochProposers> {
+        let proposers = Arc::new(proposers);
+        let mut cache = self.cache.write();
+
+        cache.insert((proposers.epoch, decision_root), proposers.clone());
+
+        while cache.len() > BEACON_PROPOSER_CACHE_SIZE {
+            let lowest = cache.keys().min_by_key(|(epoch, _)| *epoch).copied();
+            match lowest {
+                Some(key) => cache.remove(&key),
+                None => break,
+            };
+        }
+
+        proposers
+    }
+}
+
+impl MemoryAccounted for BeaconProposerCache {
+    fn name(&self) -> &'static str {
+        "beacon_proposer"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Medium
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let cache = self.cache.read();
+
+        (
+            cache.len(),
+            cache
+                .values()
+                .map(|proposers| {
+                    std::mem::size_of::<EpochProposers>()
+                        + proposers.proposers.len() * std::mem::size_of::<(usize, PublicKey)>()
+                })
+                .sum(),
+        )
+    }
+
+    /// Evicts an entry of the lowest epoch.
+    fn evict_one(&self) -> bool {
+        let mut cache = self.cache.write();
+
+        let lowest = cache.keys().min_by_key(|(epoch, _)| *epoch).copied();
+        lowest.map_or(false, |key| cache.remove(&key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Keypair, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    fn epoch_proposers(epoch: u64) -> EpochProposers {
+        let keypair = Keypair::random();
+
+        EpochProposers {
+            epoch: Epoch::new(epoch),
+            fork: E::default_spec().genesis_fork,
+            proposers: (0..E::slots_per_epoch() as usize)
+                .map(|i| (i, keypair.pk.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn looks_up_proposer_by_slot() {
+        let proposers = epoch_proposers(2);
+        let start_slot = Epoch::new(2).start_slot(E::slots_per_epoch());
+
+        assert_eq!(proposers.proposer::<E>(start_slot).map(|p| p.0), Some(0));
+        assert_eq!(
+            proposers.proposer::<E>(start_slot + 3).map(|p| p.0),
+            Some(3)
+        );
+        assert!(proposers.proposer::<E>(start_slot - 1).is_none());
+        assert!(proposers
+            .proposer::<E>(start_slot + E::slots_per_epoch())
+            .is_none());
+    }
+
+    #[test]
+    fn evicts_lowest_epochs() {
+        let cache = BeaconProposerCache::default();
+        let root = |i: u64| Hash256::from_low_u64_be(i);
+
+        for epoch in 0..BEACON_PROPOSER_CACHE_SIZE as u64 + 2 {
+            cache.insert(root(epoch), epoch_proposers(epoch));
+        }
+
+        assert_eq!(cache.cache.read().len(), BEACON_PROPOSER_CACHE_SIZE);
+        assert!(cache.get(Epoch::new(0), root(0)).is_none());
+        assert!(cache.get(Epoch::new(1), root(1)).is_none());
+        assert!(cache.get(Epoch::new(2), root(2)).is_some());
+        assert!(
+            cache.get(Epoch::new(2), root(3)).is_none(),
+            "proposers are keyed by decision root"
+        );
+    }
+}
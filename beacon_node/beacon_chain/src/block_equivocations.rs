@@ -0,0 +1,138 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use types::{BeaconBlockHeader, ProposerSlashing, Slot};
+
+/// Records the header of the first valid block seen on gossip from each proposer at each slot,
+/// so that a conflicting block from the same proposer can be turned into a `ProposerSlashing`
+/// without running the slasher.
+///
+/// Only blocks from slots after the finalized slot are kept, since earlier blocks are rejected
+/// on gossip.
+#[derive(Default, Debug)]
+pub struct BlockEquivocations {
+    headers: RwLock<HashMap<(u64, Slot), BeaconBlockHeader>>,
+    /// The proposers for which a slashing has been constructed.
+    equivocators: RwLock<HashSet<u64>>,
+    /// Slashings which are waiting to be published by the slasher service.
+    unpublished: RwLock<Vec<ProposerSlashing>>,
+}
+
+impl BlockEquivocations {
+    /// Records that `proposer_index` signed the block with `header`, returning a slashing if a
+    /// conflicting block has already been seen from the same proposer at the same slot.
+    ///
+    /// At most one slashing is returned for each proposer. Observations from slots prior to or at
+    /// `finalized_slot` are pruned.
+    pub fn observe(
+        &self,
+        proposer_index: u64,
+        header: BeaconBlockHeader,
+        finalized_slot: Slot,
+    ) -> Option<ProposerSlashing> {
+        let mut headers = self.headers.write();
+
+        if let Some(previous) = headers.get(&(proposer_index, header.slot)) {
+            if *previous == header || !self.equivocators.write().insert(proposer_index) {
+                return None;
+            }
+
+            return Some(ProposerSlashing {
+                proposer_index,
+                header_1: previous.clone(),
+                header_2: header,
+            });
+        }
+
+        headers.retain(|(_, slot), _| *slot > finalized_slot);
+        headers.insert((proposer_index, header.slot), header);
+
+        None
+    }
+
+    /// Queues `slashing` to be published by the slasher service.
+    pub fn queue_for_publication(&self, slashing: ProposerSlashing) {
+        self.unpublished.write().push(slashing)
+    }
+
+    /// Returns all slashings queued for publication, removing them from the queue.
+    pub fn take_unpublished(&self) -> Vec<ProposerSlashing> {
+        std::mem::replace(&mut *self.unpublished.write(), vec![])
+    }
+
+    /// Returns `true` if a slashing has been constructed for `proposer_index`.
+    pub fn is_equivocator(&self, proposer_index: u64) -> bool {
+        self.equivocators.read().contains(&proposer_index)
+    }
+}
+
+impl MemoryAccounted for BlockEquivocations {
+    fn name(&self) -> &'static str {
+        "block_equivocations"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Required
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let len = self.headers.read().len();
+        (
+            len,
+            len * std::mem::size_of::<((u64, Slot), BeaconBlockHeader)>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Hash256, Signature};
+
+    fn header(slot: u64, state_root: u64) -> BeaconBlockHeader {
+        BeaconBlockHeader {
+            slot: Slot::new(slot),
+            parent_root: Hash256::zero(),
+            state_root: Hash256::from_low_u64_be(state_root),
+            body_root: Hash256::zero(),
+            signature: Signature::empty_signature(),
+        }
+    }
+
+    #[test]
+    fn constructs_slashing_for_conflicting_blocks() {
+        let equivocations = BlockEquivocations::default();
+        let finalized_slot = Slot::new(0);
+
+        assert_eq!(equivocations.observe(3, header(1, 1), finalized_slot), None);
+        assert_eq!(equivocations.observe(3, header(1, 1), finalized_slot), None);
+        assert_eq!(equivocations.observe(4, header(1, 2), finalized_slot), None);
+        assert_eq!(equivocations.observe(3, header(2, 2), finalized_slot), None);
+        assert!(!equivocations.is_equivocator(3));
+
+        assert_eq!(
+            equivocations.observe(3, header(1, 2), finalized_slot),
+            Some(ProposerSlashing {
+                proposer_index: 3,
+                header_1: header(1, 1),
+                header_2: header(1, 2),
+            })
+        );
+        assert!(equivocations.is_equivocator(3));
+        assert!(!equivocations.is_equivocator(4));
+
+        // Only one slashing is constructed for each proposer.
+        assert_eq!(equivocations.observe(3, header(1, 3), finalized_slot), None);
+        assert_eq!(equivocations.observe(3, header(2, 3), finalized_slot), None);
+    }
+
+    #[test]
+    fn prunes_finalized_slots() {
+        let equivocations = BlockEquivocations::default();
+
+        equivocations.observe(3, header(1, 1), Slot::new(0));
+        equivocations.observe(3, header(2, 1), Slot::new(1));
+        assert_eq!(equivocations.memory_usage().0, 1);
+        assert_eq!(equivocations.observe(3, header(1, 2), Slot::new(1)), None);
+    }
+}
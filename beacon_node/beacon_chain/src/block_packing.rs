@@ -0,0 +1,128 @@
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The number of recent aggregate arrivals from which the adaptive delay is learned.
+pub const AGGREGATE_ARRIVAL_WINDOW: usize = 512;
+/// The percentile of recent aggregate arrival delays which the adaptive delay waits for.
+pub const ADAPTIVE_DELAY_PERCENTILE: usize = 90;
+
+/// Determines how far into its slot a block is packed, allowing late aggregate attestations for
+/// the previous slot to be included.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlockPackingDelay {
+    /// Pack blocks `millis` after the start of their slot.
+    Fixed { millis: u64 },
+    /// Pack blocks once most aggregates for the previous slot have typically arrived (as
+    /// observed over recent slots), waiting no more than `max_millis` into the slot.
+    Adaptive { max_millis: u64 },
+}
+
+impl Default for BlockPackingDelay {
+    fn default() -> Self {
+        BlockPackingDelay::Fixed { millis: 0 }
+    }
+}
+
+impl BlockPackingDelay {
+    /// Returns the duration after the start of a slot at which its block should be packed.
+    pub fn offset(&self, arrival_times: &AggregateArrivalTimes) -> Duration {
+        match self {
+            BlockPackingDelay::Fixed { millis } => Duration::from_millis(*millis),
+            BlockPackingDelay::Adaptive { max_millis } => std::cmp::min(
+                arrival_times
+                    .percentile(ADAPTIVE_DELAY_PERCENTILE)
+                    .unwrap_or_default(),
+                Duration::from_millis(*max_millis),
+            ),
+        }
+    }
+}
+
+/// Records how long after the start of the following slot recent aggregate attestations arrived.
+///
+/// An aggregate which arrives before the following slot starts (as is typical) is recorded with a
+/// delay of zero, since it is available to any block in that slot.
+#[derive(Default)]
+pub struct AggregateArrivalTimes {
+    delays: Mutex<VecDeque<Duration>>,
+}
+
+impl AggregateArrivalTimes {
+    /// Records the arrival of an aggregate `delay` after the start of the following slot,
+    /// forgetting the oldest arrival if the window is full.
+    pub fn observe(&self, delay: Duration) {
+        let mut delays = self.delays.lock();
+
+        delays.push_back(delay);
+        while delays.len() > AGGREGATE_ARRIVAL_WINDOW {
+            delays.pop_front();
+        }
+    }
+
+    /// Returns the `percentile`th (0-100) of the recorded delays, or `None` if no aggregates
+    /// have arrived.
+    pub fn percentile(&self, percentile: usize) -> Option<Duration> {
+        let mut delays = self.delays.lock().iter().copied().collect::<Vec<_>>();
+
+        if delays.is_empty() {
+            return None;
+        }
+
+        delays.sort();
+        let index = std::cmp::min(delays.len() * percentile / 100, delays.len() - 1);
+
+        Some(delays[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn adaptive_delay_follows_arrivals() {
+        let arrival_times = AggregateArrivalTimes::default();
+        let adaptive = BlockPackingDelay::Adaptive { max_millis: 1_000 };
+
+        assert_eq!(
+            adaptive.offset(&arrival_times),
+            millis(0),
+            "no arrivals should not delay packing"
+        );
+
+        for i in 0..100 {
+            arrival_times.observe(millis(i * 5));
+        }
+
+        assert_eq!(adaptive.offset(&arrival_times), millis(450));
+        assert_eq!(
+            BlockPackingDelay::Adaptive { max_millis: 200 }.offset(&arrival_times),
+            millis(200),
+            "adaptive delay should be capped"
+        );
+        assert_eq!(
+            BlockPackingDelay::Fixed { millis: 300 }.offset(&arrival_times),
+            millis(300)
+        );
+    }
+
+    #[test]
+    fn forgets_old_arrivals() {
+        let arrival_times = AggregateArrivalTimes::default();
+
+        for _ in 0..AGGREGATE_ARRIVAL_WINDOW {
+            arrival_times.observe(millis(2_000));
+        }
+        for _ in 0..AGGREGATE_ARRIVAL_WINDOW {
+            arrival_times.observe(millis(10));
+        }
+
+        assert_eq!(arrival_times.percentile(100), Some(millis(10)));
+    }
+}
@@ -1,23 +1,41 @@
+use crate::balance_history::BalanceHistory;
+use crate::beacon_proposer_cache::BeaconProposerCache;
+use crate::block_equivocations::BlockEquivocations;
+use crate::block_packing::{AggregateArrivalTimes, BlockPackingDelay};
 use crate::checkpoint_cache::CheckPointCache;
+use crate::checkpoint_state_cache::CheckPointStateCache;
+use crate::debug_dump::DebugDump;
+use crate::epoch_summary_cache::EpochSummaryCache;
 use crate::eth1_chain::CachingEth1Backend;
 use crate::events::NullEventHandler;
+use crate::head_subscribers::HeadSubscribers;
 use crate::head_tracker::HeadTracker;
+use crate::local_proposals::LocalProposals;
+use crate::next_epoch_cache::NextEpochCache;
+use crate::observed_proposers::ObservedProposers;
 use crate::persisted_beacon_chain::{PersistedBeaconChain, BEACON_CHAIN_DB_KEY};
+use crate::production_times::ProductionTimes;
+use crate::request_id::CurrentRequestId;
+use crate::shuffling_cache::ShufflingCache;
 use crate::{
     BeaconChain, BeaconChainTypes, CheckPoint, Eth1Chain, Eth1ChainBackend, EventHandler,
-    ForkChoice,
+    ForkChoice, HeadInfo,
 };
 use eth1::Config as Eth1Config;
 use lmd_ghost::{LmdGhost, ThreadSafeReducedTree};
 use operation_pool::OperationPool;
 use parking_lot::RwLock;
-use slog::{info, Logger};
+use slasher::Slasher;
+use slog::{info, o, Logger};
 use slot_clock::{SlotClock, TestingSlotClock};
+use state_processing::CommitteePubkeyCache;
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
 use store::Store;
-use types::{BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256, Slot};
+use types::{BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256, PublicKeyBytes, Slot};
 
 /// An empty struct used to "witness" all the `BeaconChainTypes` traits. It has no user-facing
 /// functionality and only exists to satisfy the type system.
@@ -89,6 +107,12 @@ pub struct BeaconChainBuilder<T: BeaconChainTypes> {
     fork_choice: Option<ForkChoice<T>>,
     eth1_chain: Option<Eth1Chain<T::Eth1Chain, T::EthSpec>>,
     event_handler: Option<T::EventHandler>,
+    slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    balance_history: Option<BalanceHistory>,
+    block_packing_delay: BlockPackingDelay,
+    debug_dump: Option<DebugDump>,
+    log_block_processing_breakdown: bool,
+    cache_memory_budget: Option<usize>,
     slot_clock: Option<T::SlotClock>,
     persisted_beacon_chain: Option<PersistedBeaconChain<T>>,
     head_tracker: Option<HeadTracker>,
@@ -131,6 +155,12 @@ where
             fork_choice: None,
             eth1_chain: None,
             event_handler: None,
+            slasher: None,
+            balance_history: None,
+            block_packing_delay: BlockPackingDelay::default(),
+            debug_dump: None,
+            log_block_processing_breakdown: false,
+            cache_memory_budget: None,
             slot_clock: None,
             persisted_beacon_chain: None,
             head_tracker: None,
@@ -257,6 +287,10 @@ where
         store
             .put(&beacon_block_root, &beacon_block)
             .map_err(|e| format!("Failed to store genesis block: {:?}", e))?;
+        store
+            .put_state_slot(&beacon_state_root, beacon_state.slot)
+            .and_then(|()| store.put_block_slot(&beacon_block_root, beacon_block.slot))
+            .map_err(|e| format!("Failed to index genesis block and state: {:?}", e))?;
 
         // Store the genesis block under the `ZERO_HASH` key.
         store.put(&Hash256::zero(), &beacon_block).map_err(|e| {
@@ -284,12 +318,49 @@ where
 
     /// Sets the `BeaconChain` event handler backend.
     ///
-    /// For example, provide `WebSocketSender` as a `handler`.
+    /// For example, provide `EventBus` as a `handler`.
     pub fn event_handler(mut self, handler: TEventHandler) -> Self {
         self.event_handler = Some(handler);
         self
     }
 
+    /// Sets the `BeaconChain` slasher, which will be given all valid attestations.
+    pub fn slasher(mut self, slasher: Arc<Slasher<TEthSpec>>) -> Self {
+        self.slasher = Some(slasher);
+        self
+    }
+
+    /// Records the balances of the validators with `pubkeys` at each epoch.
+    pub fn balance_history(mut self, pubkeys: Vec<PublicKeyBytes>) -> Self {
+        self.balance_history = Some(BalanceHistory::new(pubkeys));
+        self
+    }
+
+    /// Sets how far into its slot a block is packed by `BeaconChain::block_packing_wait`.
+    pub fn block_packing_delay(mut self, delay: BlockPackingDelay) -> Self {
+        self.block_packing_delay = delay;
+        self
+    }
+
+    /// Writes the pre-state and block of each block which fails the state transition to `dir`.
+    pub fn debug_dump_dir(mut self, dir: PathBuf) -> Self {
+        self.debug_dump = Some(DebugDump::new(dir));
+        self
+    }
+
+    /// Logs the time spent in each stage of the state transition for each imported block.
+    pub fn log_block_processing_breakdown(mut self, enabled: bool) -> Self {
+        self.log_block_processing_breakdown = enabled;
+        self
+    }
+
+    /// Bounds the total size of the in-memory caches of the chain to `bytes`, evicting the least
+    /// valuable entries when it is exceeded.
+    pub fn cache_memory_budget(mut self, bytes: usize) -> Self {
+        self.cache_memory_budget = Some(bytes);
+        self
+    }
+
     /// Sets the `BeaconChain` slot clock.
     ///
     /// For example, provide `SystemTimeSlotClock` as a `clock`.
@@ -304,6 +375,121 @@ where
         self
     }
 
+    /// Checks that the configuration of `self` is consistent, both internally and with the
+    /// contents of the store, returning a description of the first inconsistency found.
+    ///
+    /// Checks that:
+    ///
+    /// - The genesis block is stored and its state is that of `self.spec` (e.g., the database was
+    ///   not created with a different spec).
+    /// - A chain started from a genesis state has the same genesis as any chain persisted in the
+    ///   store.
+    /// - The fork schedule of `self.spec` and of the head state is sane.
+    pub fn validate(&self) -> Result<(), String> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| "validate requires a store".to_string())?;
+        let genesis_block_root = self
+            .genesis_block_root
+            .ok_or_else(|| "validate requires a genesis block root".to_string())?;
+        let head_state = &self
+            .persisted_beacon_chain
+            .as_ref()
+            .map(|persisted| &persisted.canonical_head)
+            .or_else(|| self.finalized_checkpoint.as_ref())
+            .ok_or_else(|| "validate requires a head".to_string())?
+            .beacon_state;
+
+        let genesis_epoch = self.spec.genesis_slot.epoch(TEthSpec::slots_per_epoch());
+        let genesis_fork = &self.spec.genesis_fork;
+        if genesis_fork.epoch != genesis_epoch {
+            return Err(format!(
+                "Spec genesis fork epoch {} is not the genesis epoch {}",
+                genesis_fork.epoch, genesis_epoch
+            ));
+        }
+        if genesis_fork.previous_version != genesis_fork.current_version {
+            return Err("Spec genesis fork has distinct previous and current versions".into());
+        }
+
+        if self.persisted_beacon_chain.is_none() {
+            let persisted: Option<
+                PersistedBeaconChain<
+                    Witness<
+                        TStore,
+                        TStoreMigrator,
+                        TSlotClock,
+                        TLmdGhost,
+                        TEth1Backend,
+                        TEthSpec,
+                        TEventHandler,
+                    >,
+                >,
+            > = store
+                .get(&Hash256::from_slice(&BEACON_CHAIN_DB_KEY.as_bytes()))
+                .map_err(|e| format!("DB error when reading persisted beacon chain: {:?}", e))?;
+
+            if let Some(persisted) = persisted {
+                if persisted.genesis_block_root != genesis_block_root {
+                    return Err(format!(
+                        "Genesis block {} differs from the genesis block {} of the chain in the \
+                         database",
+                        genesis_block_root, persisted.genesis_block_root
+                    ));
+                }
+            }
+        }
+
+        let genesis_block = store
+            .get::<BeaconBlock<TEthSpec>>(&genesis_block_root)
+            .map_err(|e| format!("DB error when reading genesis block: {:?}", e))?
+            .ok_or_else(|| format!("Genesis block {} is not stored", genesis_block_root))?;
+        if genesis_block.slot != self.spec.genesis_slot {
+            return Err(format!(
+                "Genesis block slot {} is not the spec genesis slot {}",
+                genesis_block.slot, self.spec.genesis_slot
+            ));
+        }
+
+        // A database without history (e.g., initialised from a checkpoint) may not hold the
+        // genesis state.
+        match store.get_state(&genesis_block.state_root, Some(genesis_block.slot)) {
+            Ok(Some(genesis_state)) => {
+                if genesis_state.canonical_root() != genesis_block.state_root {
+                    return Err("Stored genesis state does not match its root".into());
+                }
+                if genesis_state.fork != *genesis_fork {
+                    return Err(format!(
+                        "Stored genesis state fork {:?} differs from the spec genesis fork {:?}",
+                        genesis_state.fork, genesis_fork
+                    ));
+                }
+                if genesis_state.genesis_time != head_state.genesis_time {
+                    return Err("Head state genesis time differs from the genesis state".into());
+                }
+            }
+            Ok(None) => {
+                return Err(format!(
+                    "Genesis state {} is not stored",
+                    genesis_block.state_root
+                ))
+            }
+            Err(store::Error::HistoryUnavailable { .. }) => (),
+            Err(e) => return Err(format!("DB error when reading genesis state: {:?}", e)),
+        }
+
+        if head_state.fork.epoch > head_state.current_epoch() {
+            return Err(format!(
+                "Head state fork epoch {} is after the head epoch {}",
+                head_state.fork.epoch,
+                head_state.current_epoch()
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Consumes `self`, returning a `BeaconChain` if all required parameters have been supplied.
     ///
     /// An error will be returned at runtime if all required parameters have not been configured.
@@ -349,11 +535,23 @@ where
             return Err("beacon_block.state_root != beacon_state".to_string());
         }
 
+        // Allow sensible eth1 votes to be cast whilst the eth1 caches are syncing.
+        if let Some(eth1_chain) = &self.eth1_chain {
+            eth1_chain.warm_start(&canonical_head.beacon_state, &log);
+        }
+
+        let store = self
+            .store
+            .ok_or_else(|| "Cannot build without store".to_string())?;
+
+        let observed_proposers = ObservedProposers::load(&*store)
+            .map_err(|e| format!("Unable to load observed proposers: {:?}", e))?;
+        let local_proposals = LocalProposals::load(&*store)
+            .map_err(|e| format!("Unable to load local proposals: {:?}", e))?;
+
         let beacon_chain = BeaconChain {
             spec: self.spec,
-            store: self
-                .store
-                .ok_or_else(|| "Cannot build without store".to_string())?,
+            store,
             store_migrator: self
                 .store_migrator
                 .ok_or_else(|| "Cannot build without store migrator".to_string())?,
@@ -364,6 +562,7 @@ where
                 .op_pool
                 .ok_or_else(|| "Cannot build without op pool".to_string())?,
             eth1_chain: self.eth1_chain,
+            head_info: RwLock::new(HeadInfo::from_checkpoint(&canonical_head)),
             canonical_head: RwLock::new(canonical_head),
             genesis_block_root: self
                 .genesis_block_root
@@ -374,11 +573,36 @@ where
             event_handler: self
                 .event_handler
                 .ok_or_else(|| "Cannot build without an event handler".to_string())?,
+            slasher: self.slasher,
+            balance_history: self.balance_history,
+            block_packing_delay: self.block_packing_delay,
+            debug_dump: self.debug_dump,
+            log_block_processing_breakdown: self.log_block_processing_breakdown,
+            aggregate_arrival_times: AggregateArrivalTimes::default(),
             head_tracker: self.head_tracker.unwrap_or_default(),
+            observed_proposers,
+            block_equivocations: BlockEquivocations::default(),
+            local_proposals,
+            production_times: ProductionTimes::default(),
             checkpoint_cache: CheckPointCache::default(),
-            log: log.clone(),
+            checkpoint_state_cache: CheckPointStateCache::default(),
+            epoch_summary_cache: EpochSummaryCache::default(),
+            head_subscribers: HeadSubscribers::default(),
+            next_epoch_cache: NextEpochCache::default(),
+            beacon_proposer_cache: BeaconProposerCache::default(),
+            shuffling_cache: ShufflingCache::default(),
+            committee_pubkey_cache: CommitteePubkeyCache::default(),
+            cache_memory_budget: self.cache_memory_budget,
+            chain_health: RwLock::new(None),
+            block_import_lock: RwLock::new(()),
+            shutting_down: AtomicBool::new(false),
+            log: log.new(o!("request_id" => CurrentRequestId)),
         };
 
+        beacon_chain
+            .backfill_root_index()
+            .map_err(|e| format!("Failed to backfill root indices: {:?}", e))?;
+
         info!(
             log,
             "Beacon chain initialized";
@@ -492,7 +716,8 @@ where
             .clone()
             .ok_or_else(|| "dummy_eth1_backend requires a store.".to_string())?;
 
-        let backend = CachingEth1Backend::new(Eth1Config::default(), log.clone(), store);
+        let backend =
+            CachingEth1Backend::new(Eth1Config::default(), log.clone(), store, self.spec.clone());
 
         let mut eth1_chain = Eth1Chain::new(backend);
         eth1_chain.use_dummy_backend = true;
@@ -588,7 +813,7 @@ mod test {
     use ssz::Encode;
     use std::time::Duration;
     use store::{migrate::NullMigrator, MemoryStore};
-    use types::{EthSpec, MinimalEthSpec, Slot};
+    use types::{Epoch, EthSpec, MinimalEthSpec, Slot};
 
     type TestEthSpec = MinimalEthSpec;
 
@@ -664,6 +889,47 @@ mod test {
         );
     }
 
+    #[test]
+    fn validate() {
+        let spec = MinimalEthSpec::default_spec();
+        let genesis_state = interop_genesis_state(&generate_deterministic_keypairs(8), 42, &spec)
+            .expect("should create interop genesis state");
+
+        let builder = |spec: ChainSpec| {
+            BeaconChainBuilder::new(MinimalEthSpec)
+                .logger(get_logger())
+                .custom_spec(spec)
+                .store(Arc::new(MemoryStore::open()))
+                .store_migrator(NullMigrator)
+                .genesis_state(genesis_state.clone())
+                .expect("should build state using genesis")
+                .dummy_eth1_backend()
+                .expect("should build the dummy eth1 backend")
+                .null_event_handler()
+                .testing_slot_clock(Duration::from_secs(1))
+                .expect("should configure testing slot clock")
+                .reduced_tree_fork_choice()
+                .expect("should add fork choice to builder")
+        };
+
+        assert_eq!(builder(spec.clone()).validate(), Ok(()));
+
+        let mut other_spec = spec.clone();
+        other_spec.genesis_fork.previous_version = [1, 0, 0, 0];
+        other_spec.genesis_fork.current_version = [1, 0, 0, 0];
+        assert!(
+            builder(other_spec).validate().is_err(),
+            "should not validate a spec with a different genesis fork"
+        );
+
+        let mut other_spec = spec;
+        other_spec.genesis_fork.epoch = Epoch::new(1);
+        assert!(
+            builder(other_spec).validate().is_err(),
+            "should not validate a spec with a genesis fork after genesis"
+        );
+    }
+
     #[test]
     fn interop_state() {
         let validator_count = 16;
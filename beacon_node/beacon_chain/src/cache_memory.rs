@@ -0,0 +1,217 @@
+//! Estimates of the memory used by the in-memory caches of the `BeaconChain`, and the eviction of
+//! cache entries when the caches exceed a global memory budget.
+//!
+//! The caches which hold states dominate the memory usage of the beacon node, and their size
+//! grows with the validator set. A budget bounds their total size, so that the memory usage of a
+//! node is predictable on small machines. When the budget is exceeded, entries are evicted from
+//! the caches of the lowest priority first, since they are the cheapest to recompute.
+use crate::metrics;
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// The order in which caches are evicted when the memory budget is exceeded, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePriority {
+    /// Only speeds up requests which are served infrequently.
+    Low,
+    /// Speeds up the verification of gossip and the production of duties.
+    Medium,
+    /// Speeds up block import and head updates.
+    High,
+    /// Required for correct operation, never evicted.
+    Required,
+}
+
+/// The estimated memory usage of a single cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheMemoryUsage {
+    pub name: String,
+    pub priority: CachePriority,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// The estimated memory usage of all the caches of the `BeaconChain`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheMemoryReport {
+    /// The budget for the total size of the caches, in bytes, if there is one.
+    pub budget_bytes: Option<usize>,
+    pub total_bytes: usize,
+    pub caches: Vec<CacheMemoryUsage>,
+}
+
+/// A cache whose memory usage can be estimated, and whose entries can be evicted.
+pub trait MemoryAccounted {
+    /// The name of the cache, used in reports and metrics.
+    fn name(&self) -> &'static str;
+
+    fn priority(&self) -> CachePriority;
+
+    /// Returns the number of entries in the cache and an estimate of their total size, in bytes.
+    fn memory_usage(&self) -> (usize, usize);
+
+    /// Evicts the entry which is the least likely to be used again, returning `false` if the
+    /// cache is empty or its entries cannot be evicted.
+    fn evict_one(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the estimated memory usage of each of `caches`.
+pub fn memory_report(
+    caches: &[&dyn MemoryAccounted],
+    budget_bytes: Option<usize>,
+) -> CacheMemoryReport {
+    let caches = caches
+        .iter()
+        .map(|cache| {
+            let (entries, bytes) = cache.memory_usage();
+            CacheMemoryUsage {
+                name: cache.name().to_string(),
+                priority: cache.priority(),
+                entries,
+                bytes,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    CacheMemoryReport {
+        budget_bytes,
+        total_bytes: caches.iter().map(|cache| cache.bytes).sum(),
+        caches,
+    }
+}
+
+/// Evicts entries from `caches` until their total size is within `budget_bytes`, returning the
+/// number of entries evicted.
+///
+/// Entries are evicted from the cache of the lowest priority first and, amongst caches of the
+/// same priority, from the largest. Caches with `CachePriority::Required` are never evicted, so
+/// the budget may still be exceeded when this function returns.
+pub fn enforce_budget(caches: &[&dyn MemoryAccounted], budget_bytes: usize) -> usize {
+    let mut evicted = 0;
+
+    loop {
+        let usages = caches
+            .iter()
+            .map(|cache| cache.memory_usage())
+            .collect::<Vec<_>>();
+
+        if usages.iter().map(|(_, bytes)| bytes).sum::<usize>() <= budget_bytes {
+            break;
+        }
+
+        let candidate = caches
+            .iter()
+            .zip(usages.iter())
+            .filter(|(cache, (entries, _))| {
+                cache.priority() != CachePriority::Required && *entries > 0
+            })
+            .min_by_key(|(cache, (_, bytes))| (cache.priority(), Reverse(*bytes)))
+            .map(|(cache, _)| cache);
+
+        match candidate {
+            Some(cache) if cache.evict_one() => {
+                metrics::inc_counter_vec(&metrics::CACHE_MEMORY_EVICTIONS, &[cache.name()]);
+                evicted += 1;
+            }
+            _ => break,
+        }
+    }
+
+    evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+
+    /// A cache of entries of a fixed size.
+    struct TestCache {
+        name: &'static str,
+        priority: CachePriority,
+        entry_bytes: usize,
+        entries: RwLock<usize>,
+    }
+
+    impl TestCache {
+        fn new(name: &'static str, priority: CachePriority, entries: usize) -> Self {
+            Self {
+                name,
+                priority,
+                entry_bytes: 100,
+                entries: RwLock::new(entries),
+            }
+        }
+
+        fn entries(&self) -> usize {
+            *self.entries.read()
+        }
+    }
+
+    impl MemoryAccounted for TestCache {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn priority(&self) -> CachePriority {
+            self.priority
+        }
+
+        fn memory_usage(&self) -> (usize, usize) {
+            (self.entries(), self.entries() * self.entry_bytes)
+        }
+
+        fn evict_one(&self) -> bool {
+            let mut entries = self.entries.write();
+            if *entries > 0 && self.priority != CachePriority::Required {
+                *entries -= 1;
+                true
+            } else {
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn evicts_lowest_priority_first() {
+        let low = TestCache::new("low", CachePriority::Low, 2);
+        let medium = TestCache::new("medium", CachePriority::Medium, 4);
+        let high = TestCache::new("high", CachePriority::High, 4);
+        let caches: [&dyn MemoryAccounted; 3] = [&high, &medium, &low];
+
+        assert_eq!(memory_report(&caches, None).total_bytes, 1_000);
+        assert_eq!(enforce_budget(&caches, 1_000), 0);
+
+        assert_eq!(enforce_budget(&caches, 700), 3);
+        assert_eq!(low.entries(), 0);
+        assert_eq!(medium.entries(), 3);
+        assert_eq!(high.entries(), 4);
+
+        let report = memory_report(&caches, Some(700));
+        assert_eq!(report.total_bytes, 700);
+        assert_eq!(
+            report.caches[1],
+            CacheMemoryUsage {
+                name: "medium".to_string(),
+                priority: CachePriority::Medium,
+                entries: 3,
+                bytes: 300,
+            }
+        );
+    }
+
+    #[test]
+    fn never_evicts_required_caches() {
+        let required = TestCache::new("required", CachePriority::Required, 5);
+        let high = TestCache::new("high", CachePriority::High, 1);
+        let caches: [&dyn MemoryAccounted; 2] = [&required, &high];
+
+        assert_eq!(enforce_budget(&caches, 0), 1);
+        assert_eq!(high.entries(), 0);
+        assert_eq!(required.entries(), 5);
+        assert_eq!(memory_report(&caches, Some(0)).total_bytes, 500);
+    }
+}
@@ -0,0 +1,153 @@
+use serde_derive::{Deserialize, Serialize};
+use types::Slot;
+
+/// The head may lag the current slot by up to this many epochs whilst still being synced.
+pub const SYNC_TOLERANCE_EPOCHS: u64 = 1;
+/// A node which is behind is stalled if its head has not advanced for this many epochs.
+pub const STALL_EPOCHS: u64 = 2;
+
+/// An assessment of whether the node is following the chain, as served to load balancers and
+/// validator clients.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ChainHealth {
+    /// The head is within `SYNC_TOLERANCE_EPOCHS` of the current slot.
+    Synced,
+    /// The head is synced, but the eth1 node is not, so blocks would be produced with stale eth1
+    /// data.
+    Eth1Optimistic,
+    /// The head is `distance` slots behind the current slot, but is advancing.
+    Syncing { distance: Slot },
+    /// The head is `distance` slots behind the current slot and there are no peers to sync from,
+    /// or it has not advanced for `STALL_EPOCHS`.
+    Stalled { distance: Slot },
+}
+
+/// The observations from which the `ChainHealth` is assessed each slot.
+pub struct ChainHealthObservation {
+    pub head_slot: Slot,
+    pub current_slot: Slot,
+    pub peer_count: usize,
+    pub eth1_synced: bool,
+}
+
+/// Assesses the `ChainHealth` each slot, remembering when the head last advanced.
+#[derive(Default)]
+pub struct ChainHealthTracker {
+    /// The head slot at the last assessment, and the slot at which it was first observed.
+    last_head: Option<(Slot, Slot)>,
+}
+
+impl ChainHealthTracker {
+    pub fn assess(
+        &mut self,
+        observation: &ChainHealthObservation,
+        slots_per_epoch: u64,
+    ) -> ChainHealth {
+        let head_slot = observation.head_slot;
+        let current_slot = observation.current_slot;
+
+        let advanced_at = match self.last_head {
+            Some((last_head_slot, advanced_at)) if last_head_slot == head_slot => advanced_at,
+            _ => current_slot,
+        };
+        self.last_head = Some((head_slot, advanced_at));
+
+        // Saturating subtraction, in case the head is ahead of the clock.
+        let distance = current_slot - head_slot;
+
+        if distance.as_u64() <= SYNC_TOLERANCE_EPOCHS * slots_per_epoch {
+            if observation.eth1_synced {
+                ChainHealth::Synced
+            } else {
+                ChainHealth::Eth1Optimistic
+            }
+        } else if observation.peer_count == 0
+            || (current_slot - advanced_at).as_u64() >= STALL_EPOCHS * slots_per_epoch
+        {
+            ChainHealth::Stalled { distance }
+        } else {
+            ChainHealth::Syncing { distance }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS_PER_EPOCH: u64 = 8;
+
+    fn observe(head_slot: u64, current_slot: u64, peer_count: usize) -> ChainHealthObservation {
+        ChainHealthObservation {
+            head_slot: Slot::new(head_slot),
+            current_slot: Slot::new(current_slot),
+            peer_count,
+            eth1_synced: true,
+        }
+    }
+
+    #[test]
+    fn assesses_sync_distance() {
+        let mut tracker = ChainHealthTracker::default();
+
+        assert_eq!(
+            tracker.assess(&observe(10, 18, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Synced
+        );
+        assert_eq!(
+            tracker.assess(
+                &ChainHealthObservation {
+                    eth1_synced: false,
+                    ..observe(18, 18, 1)
+                },
+                SLOTS_PER_EPOCH
+            ),
+            ChainHealth::Eth1Optimistic
+        );
+        assert_eq!(
+            tracker.assess(&observe(20, 40, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Syncing {
+                distance: Slot::new(20)
+            }
+        );
+        assert_eq!(
+            tracker.assess(&observe(20, 41, 0), SLOTS_PER_EPOCH),
+            ChainHealth::Stalled {
+                distance: Slot::new(21)
+            },
+            "a node without peers cannot sync"
+        );
+    }
+
+    #[test]
+    fn detects_stalled_head() {
+        let mut tracker = ChainHealthTracker::default();
+        let stall_slots = STALL_EPOCHS * SLOTS_PER_EPOCH;
+
+        assert_eq!(
+            tracker.assess(&observe(0, 100, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Syncing {
+                distance: Slot::new(100)
+            }
+        );
+        assert_eq!(
+            tracker.assess(&observe(0, 100 + stall_slots - 1, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Syncing {
+                distance: Slot::new(100 + stall_slots - 1)
+            }
+        );
+        assert_eq!(
+            tracker.assess(&observe(0, 100 + stall_slots, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Stalled {
+                distance: Slot::new(100 + stall_slots)
+            }
+        );
+        assert_eq!(
+            tracker.assess(&observe(1, 101 + stall_slots, 1), SLOTS_PER_EPOCH),
+            ChainHealth::Syncing {
+                distance: Slot::new(100 + stall_slots)
+            },
+            "an advancing head is no longer stalled"
+        );
+    }
+}
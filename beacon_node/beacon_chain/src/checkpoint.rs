@@ -1,4 +1,5 @@
 use serde_derive::Serialize;
+use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use types::{BeaconBlock, BeaconState, EthSpec, Hash256};
 
@@ -41,4 +42,28 @@ impl<E: EthSpec> CheckPoint<E> {
         self.beacon_state = beacon_state;
         self.beacon_state_root = beacon_state_root;
     }
+
+    /// An estimate of the memory used by the block and state, including the caches of the state,
+    /// in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.beacon_block.ssz_bytes_len() + self.beacon_state.memory_size()
+    }
+
+    /// Returns the SSZ encoding of `self`, compressed with (raw, unframed) snappy.
+    ///
+    /// This is the format in which checkpoints are exported to and imported from files.
+    pub fn as_snappy_ssz_bytes(&self) -> Result<Vec<u8>, String> {
+        snap::raw::Encoder::new()
+            .compress_vec(&self.as_ssz_bytes())
+            .map_err(|e| format!("Unable to compress checkpoint: {:?}", e))
+    }
+
+    /// Decodes a checkpoint from the output of `Self::as_snappy_ssz_bytes`.
+    pub fn from_snappy_ssz_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let ssz_bytes = snap::raw::Decoder::new()
+            .decompress_vec(bytes)
+            .map_err(|e| format!("Unable to decompress checkpoint: {:?}", e))?;
+
+        Self::from_ssz_bytes(&ssz_bytes).map_err(|e| format!("Invalid checkpoint SSZ: {:?}", e))
+    }
 }
@@ -1,3 +1,4 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
 use crate::checkpoint::CheckPoint;
 use crate::metrics;
 use parking_lot::RwLock;
@@ -122,3 +123,90 @@ impl<T: EthSpec> CheckPointCache<T> {
             })
     }
 }
+
+impl<T: EthSpec> MemoryAccounted for CheckPointCache<T> {
+    fn name(&self) -> &'static str {
+        "checkpoint"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::High
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let inner = self.inner.read();
+
+        (
+            inner.checkpoints.len(),
+            inner.checkpoints.iter().map(CheckPoint::memory_size).sum(),
+        )
+    }
+
+    /// Evicts the oldest checkpoint.
+    fn evict_one(&self) -> bool {
+        let mut inner = self.inner.write();
+
+        if inner.checkpoints.is_empty() {
+            return false;
+        }
+
+        // Order the checkpoints from oldest to newest, so that subsequent insertions (which push
+        // until the cache is full, then replace the oldest) preserve the order.
+        let oldest = inner.oldest;
+        inner.checkpoints.rotate_left(oldest);
+        inner.checkpoints.remove(0);
+        inner.oldest = 0;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    fn checkpoint(state: &BeaconState<E>, i: u64) -> CheckPoint<E> {
+        let block = BeaconBlock::empty(&E::default_spec());
+        let block_root = block.canonical_root();
+
+        CheckPoint::new(
+            block,
+            block_root,
+            state.clone(),
+            Hash256::from_low_u64_be(i),
+        )
+    }
+
+    #[test]
+    fn evicts_oldest() {
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(4, &spec).build();
+        let cache = CheckPointCache::<E>::default();
+        let root = |i: u64| Hash256::from_low_u64_be(i);
+
+        for i in 0..CACHE_SIZE as u64 + 1 {
+            cache.insert(&checkpoint(&state, i));
+        }
+
+        let (entries, bytes) = cache.memory_usage();
+        assert_eq!(entries, CACHE_SIZE);
+        assert_eq!(bytes, CACHE_SIZE * checkpoint(&state, 0).memory_size());
+
+        assert!(cache.evict_one());
+        assert!(cache.get_state(&root(1)).is_none());
+        assert!(cache.get_state(&root(2)).is_some());
+
+        cache.insert(&checkpoint(&state, 5));
+        cache.insert(&checkpoint(&state, 6));
+        assert!(cache.get_state(&root(2)).is_none());
+        assert!(cache.get_state(&root(3)).is_some());
+        assert!(cache.get_state(&root(6)).is_some());
+
+        while cache.evict_one() {}
+        assert_eq!(cache.memory_usage(), (0, 0));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use crate::checkpoint::CheckPoint;
+use crate::metrics;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use types::{BeaconState, Checkpoint, EthSpec, Hash256};
+
+/// The effective balances of all validators in the state at a justified checkpoint, which are
+/// the weights used by fork choice.
+#[derive(Debug, PartialEq)]
+pub struct JustifiedBalances {
+    pub checkpoint: Checkpoint,
+    pub balances: Vec<u64>,
+}
+
+impl JustifiedBalances {
+    /// Reads the balances from `state`, which must be the state at the start slot of the epoch
+    /// of `checkpoint`.
+    pub fn from_state<T: EthSpec>(checkpoint: Checkpoint, state: &BeaconState<T>) -> Self {
+        Self {
+            checkpoint,
+            balances: state
+                .validators
+                .iter()
+                .map(|validator| validator.effective_balance)
+                .collect(),
+        }
+    }
+}
+
+/// Stores the justified balances and the finalized checkpoint, so that fork choice and head
+/// updates do not load their states from the store.
+///
+/// Only the latest of each is kept, since they change at most once per epoch.
+pub struct CheckPointStateCache<T: EthSpec> {
+    justified_balances: RwLock<Option<Arc<JustifiedBalances>>>,
+    finalized: RwLock<Option<Arc<CheckPoint<T>>>>,
+}
+
+impl<T: EthSpec> Default for CheckPointStateCache<T> {
+    fn default() -> Self {
+        Self {
+            justified_balances: RwLock::new(None),
+            finalized: RwLock::new(None),
+        }
+    }
+}
+
+impl<T: EthSpec> CheckPointStateCache<T> {
+    /// Returns the balances at the justified `checkpoint`, if they are stored.
+    pub fn get_justified_balances(
+        &self,
+        checkpoint: &Checkpoint,
+    ) -> Option<Arc<JustifiedBalances>> {
+        let balances = self
+            .justified_balances
+            .read()
+            .as_ref()
+            .filter(|balances| balances.checkpoint == *checkpoint)
+            .cloned();
+
+        if balances.is_some() {
+            metrics::inc_counter(&metrics::JUSTIFIED_BALANCES_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::JUSTIFIED_BALANCES_CACHE_MISSES);
+        }
+
+        balances
+    }
+
+    /// Replaces the stored justified balances with `balances`.
+    pub fn insert_justified_balances(&self, balances: JustifiedBalances) -> Arc<JustifiedBalances> {
+        let balances = Arc::new(balances);
+        *self.justified_balances.write() = Some(balances.clone());
+        balances
+    }
+
+    /// Returns the finalized checkpoint with `block_root`, if it is stored.
+    pub fn get_finalized(&self, block_root: Hash256) -> Option<Arc<CheckPoint<T>>> {
+        let finalized = self
+            .finalized
+            .read()
+            .as_ref()
+            .filter(|finalized| finalized.beacon_block_root == block_root)
+            .cloned();
+
+        if finalized.is_some() {
+            metrics::inc_counter(&metrics::FINALIZED_STATE_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::FINALIZED_STATE_CACHE_MISSES);
+        }
+
+        finalized
+    }
+
+    /// Replaces the stored finalized checkpoint with `finalized`.
+    pub fn insert_finalized(&self, finalized: CheckPoint<T>) -> Arc<CheckPoint<T>> {
+        let finalized = Arc::new(finalized);
+        *self.finalized.write() = Some(finalized.clone());
+        finalized
+    }
+}
+
+impl<T: EthSpec> MemoryAccounted for CheckPointStateCache<T> {
+    fn name(&self) -> &'static str {
+        "checkpoint_state"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Required
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let balances = self.justified_balances.read().as_ref().map(|balances| {
+            std::mem::size_of::<JustifiedBalances>()
+                + balances.balances.len() * std::mem::size_of::<u64>()
+        });
+        let finalized = self
+            .finalized
+            .read()
+            .as_ref()
+            .map(|finalized| finalized.memory_size());
+
+        (
+            balances.iter().count() + finalized.iter().count(),
+            balances.unwrap_or(0) + finalized.unwrap_or(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, BeaconBlock, Epoch, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn keyed_by_checkpoint() {
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(4, &spec).build();
+        let checkpoint = Checkpoint {
+            epoch: Epoch::new(1),
+            root: Hash256::from_low_u64_be(1),
+        };
+        let other_checkpoint = Checkpoint {
+            epoch: Epoch::new(2),
+            root: Hash256::from_low_u64_be(2),
+        };
+        let cache = CheckPointStateCache::<E>::default();
+
+        assert!(cache.get_justified_balances(&checkpoint).is_none());
+        cache.insert_justified_balances(JustifiedBalances::from_state(checkpoint.clone(), &state));
+        assert_eq!(
+            cache
+                .get_justified_balances(&checkpoint)
+                .expect("should be cached")
+                .balances,
+            state
+                .validators
+                .iter()
+                .map(|validator| validator.effective_balance)
+                .collect::<Vec<_>>()
+        );
+        assert!(cache.get_justified_balances(&other_checkpoint).is_none());
+
+        let block = BeaconBlock::empty(&spec);
+        let block_root = block.canonical_root();
+        cache.insert_finalized(CheckPoint::new(
+            block,
+            block_root,
+            state.clone(),
+            state.canonical_root(),
+        ));
+        assert!(cache.get_finalized(block_root).is_some());
+        assert!(cache.get_finalized(Hash256::zero()).is_none());
+    }
+}
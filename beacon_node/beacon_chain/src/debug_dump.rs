@@ -0,0 +1,150 @@
+use parking_lot::Mutex;
+use ssz::Encode;
+use std::fs;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use types::{BeaconBlock, BeaconState, EthSpec, Hash256};
+
+/// At most one failure is dumped in each interval of this length, so that a flood of invalid
+/// blocks cannot fill the disk.
+pub const MIN_DUMP_INTERVAL: Duration = Duration::from_secs(60);
+
+pub const PRE_STATE_FILENAME: &str = "pre_state.ssz";
+pub const BLOCK_FILENAME: &str = "block.ssz";
+pub const ERROR_FILENAME: &str = "error.txt";
+
+/// Writes the pre-state and block of each block which fails the state transition to `dir`, so
+/// that consensus failures seen on a testnet may be replayed locally with
+/// `lcli transition-blocks`.
+///
+/// Each failure is written to its own sub-directory, which is renamed into place once all of its
+/// files are written, so a sub-directory is never seen partially written.
+pub struct DebugDump {
+    dir: PathBuf,
+    min_interval: Duration,
+    last_dump: Mutex<Option<Instant>>,
+}
+
+impl DebugDump {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            min_interval: MIN_DUMP_INTERVAL,
+            last_dump: Mutex::new(None),
+        }
+    }
+
+    /// Writes `pre_state`, `block` and a description of `error` to a sub-directory of the dump
+    /// directory named after the slot and root of the block.
+    ///
+    /// Returns `Ok(None)` if a failure was dumped within the last `MIN_DUMP_INTERVAL` or this block
+    /// has already been dumped.
+    pub fn dump<E: EthSpec>(
+        &self,
+        pre_state: &BeaconState<E>,
+        block: &BeaconBlock<E>,
+        block_root: Hash256,
+        error: &str,
+    ) -> Result<Option<PathBuf>, String> {
+        let mut last_dump = self.last_dump.lock();
+
+        if last_dump.map_or(false, |last_dump| last_dump.elapsed() < self.min_interval) {
+            return Ok(None);
+        }
+
+        let name = format!("slot_{}_root_{}", block.slot, block_root);
+        let path = self.dir.join(&name);
+        if path.exists() {
+            return Ok(None);
+        }
+
+        *last_dump = Some(Instant::now());
+
+        let tmp_path = self.dir.join(format!(".tmp_{}", name));
+        fs::create_dir_all(&tmp_path)
+            .map_err(|e| format!("Unable to create {:?}: {:?}", tmp_path, e))?;
+
+        write_file(
+            &tmp_path.join(PRE_STATE_FILENAME),
+            &pre_state.as_ssz_bytes(),
+        )?;
+        write_file(&tmp_path.join(BLOCK_FILENAME), &block.as_ssz_bytes())?;
+        write_file(
+            &tmp_path.join(ERROR_FILENAME),
+            format!(
+                "block_root: {:?}\nblock_slot: {}\npre_state_slot: {}\nerror: {}\n",
+                block_root, block.slot, pre_state.slot, error
+            )
+            .as_bytes(),
+        )?;
+
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| format!("Unable to rename {:?} to {:?}: {:?}", tmp_path, path, e))?;
+
+        Ok(Some(path))
+    }
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut file =
+        fs::File::create(path).map_err(|e| format!("Unable to create {:?}: {:?}", path, e))?;
+    file.write_all(bytes)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Unable to write {:?}: {:?}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::Decode;
+    use tempfile::tempdir;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec, Slot};
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn dumps_with_rate_limit() {
+        let dir = tempdir().unwrap();
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(4, &spec).build();
+        let mut block = BeaconBlock::empty(&spec);
+        block.slot = Slot::new(1);
+        let block_root = block.canonical_root();
+
+        let debug_dump = DebugDump::new(dir.path().to_path_buf());
+
+        let path = debug_dump
+            .dump(&state, &block, block_root, "invalid")
+            .unwrap()
+            .expect("should dump the first failure");
+        assert_eq!(
+            BeaconState::<E>::from_ssz_bytes(&fs::read(path.join(PRE_STATE_FILENAME)).unwrap()),
+            Ok(state.clone())
+        );
+        assert_eq!(
+            BeaconBlock::<E>::from_ssz_bytes(&fs::read(path.join(BLOCK_FILENAME)).unwrap()),
+            Ok(block.clone())
+        );
+        assert_eq!(
+            fs::read_dir(dir.path()).unwrap().count(),
+            1,
+            "the temporary directory should have been renamed"
+        );
+
+        block.slot = Slot::new(2);
+        let block_root = block.canonical_root();
+        assert_eq!(
+            debug_dump.dump(&state, &block, block_root, "invalid"),
+            Ok(None),
+            "should not dump within the interval"
+        );
+
+        *debug_dump.last_dump.lock() = None;
+        assert!(debug_dump
+            .dump(&state, &block, block_root, "invalid")
+            .unwrap()
+            .is_some());
+    }
+}
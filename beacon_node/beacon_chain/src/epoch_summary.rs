@@ -0,0 +1,104 @@
+use serde_derive::{Deserialize, Serialize};
+use state_processing::EpochProcessingSummary;
+use types::{BeaconState, Checkpoint, Epoch, EthSpec};
+
+/// The aggregates of the canonical chain at the start of an epoch, published to the event handler
+/// so that dashboards need not poll the API and recompute them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochSummary {
+    /// The epoch which the head has entered.
+    pub epoch: Epoch,
+    /// The epoch whose attestations are summarised by the participation rates.
+    ///
+    /// The attestations of an epoch are only summarised by the epoch transition at the end of the
+    /// following epoch, so this is two epochs prior to `epoch`.
+    pub participation_epoch: Epoch,
+    /// The fractions of the active balance which attested with a matching source, target and head,
+    /// or `None` if the participation in `participation_epoch` is not known.
+    pub source_participation_rate: Option<f64>,
+    pub target_participation_rate: Option<f64>,
+    pub head_participation_rate: Option<f64>,
+    pub current_justified_checkpoint: Checkpoint,
+    pub finalized_checkpoint: Checkpoint,
+    /// The summed effective balances of the validators active in `epoch`, in Gwei.
+    pub total_active_balance: u64,
+    pub active_validators: usize,
+    /// The number of validators which became active at the start of `epoch`.
+    pub activated_validators: usize,
+    /// The number of validators which exited at the start of `epoch`.
+    pub exited_validators: usize,
+}
+
+impl EpochSummary {
+    /// Summarises the head `state`, which must be in the first epoch of the summary, along with
+    /// the `participation` in the epoch two epochs prior (if known).
+    pub fn new<T: EthSpec>(
+        state: &BeaconState<T>,
+        participation: Option<&EpochProcessingSummary>,
+    ) -> Self {
+        let epoch = state.current_epoch();
+
+        let mut total_active_balance = 0;
+        let mut active_validators = 0;
+        let mut activated_validators = 0;
+        let mut exited_validators = 0;
+        for validator in state.validators.iter() {
+            if validator.is_active_at(epoch) {
+                total_active_balance += validator.effective_balance;
+                active_validators += 1;
+            }
+            if validator.activation_epoch == epoch {
+                activated_validators += 1;
+            }
+            if validator.exit_epoch == epoch {
+                exited_validators += 1;
+            }
+        }
+
+        Self {
+            epoch,
+            participation_epoch: epoch.saturating_sub(2u64),
+            source_participation_rate: participation
+                .map(EpochProcessingSummary::source_participation_rate),
+            target_participation_rate: participation
+                .map(EpochProcessingSummary::target_participation_rate),
+            head_participation_rate: participation
+                .map(EpochProcessingSummary::head_participation_rate),
+            current_justified_checkpoint: state.current_justified_checkpoint.clone(),
+            finalized_checkpoint: state.finalized_checkpoint.clone(),
+            total_active_balance,
+            active_validators,
+            activated_validators,
+            exited_validators,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn counts_validators() {
+        let spec = E::default_spec();
+        let (mut state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(8, &spec).build();
+        state.slot = Epoch::new(3).start_slot(E::slots_per_epoch());
+        state.validators[0].activation_epoch = Epoch::new(3);
+        state.validators[1].exit_epoch = Epoch::new(3);
+        state.validators[2].activation_epoch = Epoch::new(4);
+
+        let summary = EpochSummary::new(&state, None);
+
+        assert_eq!(summary.epoch, Epoch::new(3));
+        assert_eq!(summary.participation_epoch, Epoch::new(1));
+        assert_eq!(summary.active_validators, 6);
+        assert_eq!(summary.total_active_balance, 6 * spec.max_effective_balance);
+        assert_eq!(summary.activated_validators, 1);
+        assert_eq!(summary.exited_validators, 1);
+        assert_eq!(summary.target_participation_rate, None);
+    }
+}
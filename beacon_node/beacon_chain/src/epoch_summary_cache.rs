@@ -0,0 +1,135 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use parking_lot::RwLock;
+use state_processing::{EpochProcessingSummary, ValidatorParticipation};
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{Epoch, Hash256};
+
+/// The number of epochs for which summaries are kept.
+pub const EPOCH_SUMMARY_CACHE_EPOCHS: u64 = 8;
+
+/// Stores the `EpochProcessingSummary` produced by each epoch transition during block import, for
+/// the last `EPOCH_SUMMARY_CACHE_EPOCHS` epochs.
+///
+/// Summaries are keyed by the root of the latest block at the time of the epoch transition, so
+/// that a summary produced on one fork is never returned for another.
+#[derive(Default)]
+pub struct EpochSummaryCache {
+    summaries: RwLock<HashMap<Hash256, Arc<EpochProcessingSummary>>>,
+}
+
+impl EpochSummaryCache {
+    /// Adds the `summary` produced by the epoch transition after `block_root`, removing any
+    /// summaries which are too old to be kept.
+    pub fn insert(&self, block_root: Hash256, summary: EpochProcessingSummary) {
+        let mut summaries = self.summaries.write();
+
+        let highest_epoch = summaries
+            .values()
+            .map(|summary| summary.epoch)
+            .max()
+            .map_or(summary.epoch, |epoch| std::cmp::max(epoch, summary.epoch));
+        let lowest_epoch = highest_epoch.saturating_sub(EPOCH_SUMMARY_CACHE_EPOCHS - 1);
+
+        if summary.epoch < lowest_epoch {
+            return;
+        }
+
+        summaries.retain(|_, summary| summary.epoch >= lowest_epoch);
+        summaries.insert(block_root, Arc::new(summary));
+    }
+
+    /// Returns the summary produced by the epoch transition after `block_root`, if it is known.
+    pub fn get(&self, block_root: &Hash256) -> Option<Arc<EpochProcessingSummary>> {
+        self.summaries.read().get(block_root).cloned()
+    }
+
+    /// Returns the epochs of all known summaries.
+    pub fn epochs(&self) -> Vec<Epoch> {
+        let mut epochs = self
+            .summaries
+            .read()
+            .values()
+            .map(|summary| summary.epoch)
+            .collect::<Vec<_>>();
+        epochs.sort();
+        epochs.dedup();
+        epochs
+    }
+}
+
+impl MemoryAccounted for EpochSummaryCache {
+    fn name(&self) -> &'static str {
+        "epoch_summary"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Low
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let summaries = self.summaries.read();
+
+        (
+            summaries.len(),
+            summaries
+                .values()
+                .map(|summary| {
+                    std::mem::size_of::<EpochProcessingSummary>()
+                        + summary.validators.len() * std::mem::size_of::<ValidatorParticipation>()
+                })
+                .sum(),
+        )
+    }
+
+    /// Evicts a summary of the lowest epoch.
+    fn evict_one(&self) -> bool {
+        let mut summaries = self.summaries.write();
+
+        let lowest = summaries
+            .iter()
+            .min_by_key(|(_, summary)| summary.epoch)
+            .map(|(block_root, _)| *block_root);
+
+        lowest.map_or(false, |block_root| summaries.remove(&block_root).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_processing::per_epoch_processing::validator_statuses::TotalBalances;
+
+    fn summary(epoch: u64) -> EpochProcessingSummary {
+        EpochProcessingSummary {
+            epoch: Epoch::new(epoch),
+            total_balances: TotalBalances::default(),
+            validators: vec![],
+        }
+    }
+
+    #[test]
+    fn prunes_old_summaries() {
+        let cache = EpochSummaryCache::default();
+
+        for epoch in 0..EPOCH_SUMMARY_CACHE_EPOCHS * 2 {
+            cache.insert(Hash256::from_low_u64_be(epoch), summary(epoch));
+        }
+
+        let expected = (EPOCH_SUMMARY_CACHE_EPOCHS..EPOCH_SUMMARY_CACHE_EPOCHS * 2)
+            .map(Epoch::new)
+            .collect::<Vec<_>>();
+        assert_eq!(cache.epochs(), expected);
+        assert!(cache.get(&Hash256::from_low_u64_be(0)).is_none());
+
+        // A summary older than the cached epochs is not added.
+        cache.insert(Hash256::from_low_u64_be(100), summary(0));
+        assert!(cache.get(&Hash256::from_low_u64_be(100)).is_none());
+
+        let latest = EPOCH_SUMMARY_CACHE_EPOCHS * 2 - 1;
+        assert_eq!(
+            cache.get(&Hash256::from_low_u64_be(latest)),
+            Some(Arc::new(summary(latest)))
+        );
+    }
+}
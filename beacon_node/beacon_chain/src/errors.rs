@@ -1,8 +1,10 @@
 use crate::eth1_chain::Error as Eth1ChainError;
 use crate::fork_choice::Error as ForkChoiceError;
+use lighthouse_error::{CodedError, ErrorCode};
 use ssz_types::Error as SszTypesError;
 use state_processing::per_block_processing::errors::AttestationValidationError;
 use state_processing::BlockProcessingError;
+use state_processing::BlockReplayError;
 use state_processing::SlotProcessingError;
 use std::time::Duration;
 use types::*;
@@ -20,6 +22,7 @@ macro_rules! easy_from_to {
 #[derive(Debug, PartialEq)]
 pub enum BeaconChainError {
     InsufficientValidators,
+    ShuttingDown,
     UnableToReadSlot,
     RevertedFinalizedEpoch {
         previous_epoch: Epoch,
@@ -27,6 +30,7 @@ pub enum BeaconChainError {
     },
     SlotClockDidNotStart,
     NoStateForSlot(Slot),
+    NoProposerForSlot(Slot),
     UnableToFindTargetRoot(Slot),
     BeaconStateError(BeaconStateError),
     DBInconsistent(String),
@@ -34,6 +38,8 @@ pub enum BeaconChainError {
     ForkChoiceError(ForkChoiceError),
     MissingBeaconBlock(Hash256),
     MissingBeaconState(Hash256),
+    /// The `HistoricalBatch` with the given index in `historical_roots` is not in the database.
+    MissingHistoricalBatch(usize),
     SlotProcessingError(SlotProcessingError),
     UnableToAdvanceState(String),
     NoStateForAttestation {
@@ -47,12 +53,81 @@ pub enum BeaconChainError {
     },
     /// Returned when an internal check fails, indicating corrupt data.
     InvariantViolated(String),
+    /// The block and state of an imported checkpoint are inconsistent.
+    InvalidCheckPoint(String),
     SszTypesError(SszTypesError),
+    Eth1ChainError(Eth1ChainError),
+    BlockReplayError(BlockReplayError),
+    HistoricalBlockError(HistoricalBlockError),
+}
+
+impl CodedError for BeaconChainError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            // Errors from the database keep the code of the database.
+            BeaconChainError::DBError(e) => return e.error_code(),
+            BeaconChainError::InsufficientValidators => 2000,
+            BeaconChainError::ShuttingDown => 2001,
+            BeaconChainError::UnableToReadSlot => 2002,
+            BeaconChainError::RevertedFinalizedEpoch { .. } => 2003,
+            BeaconChainError::SlotClockDidNotStart => 2004,
+            BeaconChainError::NoStateForSlot(_) => 2005,
+            BeaconChainError::NoProposerForSlot(_) => 2006,
+            BeaconChainError::UnableToFindTargetRoot(_) => 2007,
+            BeaconChainError::BeaconStateError(_) => 2008,
+            BeaconChainError::DBInconsistent(_) => 2009,
+            BeaconChainError::ForkChoiceError(_) => 2010,
+            BeaconChainError::MissingBeaconBlock(_) => 2011,
+            BeaconChainError::MissingBeaconState(_) => 2012,
+            BeaconChainError::MissingHistoricalBatch(_) => 2013,
+            BeaconChainError::SlotProcessingError(_) => 2014,
+            BeaconChainError::UnableToAdvanceState(_) => 2015,
+            BeaconChainError::NoStateForAttestation { .. } => 2016,
+            BeaconChainError::AttestationValidationError(_) => 2017,
+            BeaconChainError::StateSkipTooLarge { .. } => 2018,
+            BeaconChainError::InvariantViolated(_) => 2019,
+            BeaconChainError::InvalidCheckPoint(_) => 2020,
+            BeaconChainError::SszTypesError(_) => 2021,
+            BeaconChainError::Eth1ChainError(_) => 2022,
+            BeaconChainError::BlockReplayError(_) => 2023,
+            BeaconChainError::HistoricalBlockError(e) => return e.error_code(),
+        })
+    }
 }
 
 easy_from_to!(SlotProcessingError, BeaconChainError);
 easy_from_to!(AttestationValidationError, BeaconChainError);
 easy_from_to!(SszTypesError, BeaconChainError);
+easy_from_to!(Eth1ChainError, BeaconChainError);
+easy_from_to!(BlockReplayError, BeaconChainError);
+easy_from_to!(HistoricalBlockError, BeaconChainError);
+
+/// The reason a batch of historical blocks could not be imported (see
+/// `BeaconChain::import_historical_blocks`).
+#[derive(Debug, PartialEq)]
+pub enum HistoricalBlockError {
+    /// The database holds the full history of the chain, so no blocks are missing.
+    NoAnchor,
+    /// The oldest block of the database could not be found by following the parent roots of the
+    /// anchor block.
+    MissingOldestBlock { oldest_block_slot: Slot },
+    /// The block at `slot` is not the parent of the block after it.
+    MismatchedBlockRoot {
+        slot: Slot,
+        expected: Hash256,
+        found: Hash256,
+    },
+}
+
+impl CodedError for HistoricalBlockError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            HistoricalBlockError::NoAnchor => 2200,
+            HistoricalBlockError::MissingOldestBlock { .. } => 2201,
+            HistoricalBlockError::MismatchedBlockRoot { .. } => 2202,
+        })
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub enum BlockProductionError {
@@ -68,6 +143,21 @@ pub enum BlockProductionError {
     NoEth1ChainConnection,
 }
 
+impl CodedError for BlockProductionError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            BlockProductionError::UnableToGetBlockRootFromState => 2100,
+            BlockProductionError::UnableToReadSlot => 2101,
+            BlockProductionError::UnableToProduceAtSlot(_) => 2102,
+            BlockProductionError::SlotProcessingError(_) => 2103,
+            BlockProductionError::BlockProcessingError(_) => 2104,
+            BlockProductionError::Eth1ChainError(_) => 2105,
+            BlockProductionError::BeaconStateError(_) => 2106,
+            BlockProductionError::NoEth1ChainConnection => 2107,
+        })
+    }
+}
+
 easy_from_to!(BlockProcessingError, BlockProductionError);
 easy_from_to!(BeaconStateError, BlockProductionError);
 easy_from_to!(SlotProcessingError, BlockProductionError);
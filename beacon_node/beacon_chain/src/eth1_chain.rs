@@ -1,11 +1,13 @@
 use crate::metrics;
-use eth1::{Config as Eth1Config, Eth1Block, Service as HttpService};
+use eth1::{Config as Eth1Config, DepositLog, Eth1Block, Service as HttpService};
 use eth2_hashing::hash;
 use exit_future::Exit;
 use futures::Future;
 use integer_sqrt::IntegerSquareRoot;
+use parking_lot::RwLock;
 use rand::prelude::*;
-use slog::{crit, debug, error, trace, Logger};
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, debug, error, info, trace, Logger};
 use state_processing::per_block_processing::get_new_eth1_data;
 use std::collections::HashMap;
 use std::iter::DoubleEndedIterator;
@@ -14,10 +16,12 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 use store::{Error as StoreError, Store};
 use types::{
-    BeaconState, BeaconStateError, ChainSpec, Deposit, Eth1Data, EthSpec, Hash256, Slot, Unsigned,
-    DEPOSIT_TREE_DEPTH,
+    BeaconState, BeaconStateError, ChainSpec, Deposit, Epoch, Eth1Data, EthSpec, Hash256,
+    PublicKeyBytes, Slot, Unsigned, DEPOSIT_TREE_DEPTH,
 };
 
+pub use eth1::SECONDS_PER_ETH1_BLOCK;
+
 type BlockNumber = u64;
 type Eth1DataBlockNumber = HashMap<Eth1Data, BlockNumber>;
 type Eth1DataVoteCount = HashMap<(Eth1Data, BlockNumber), u64>;
@@ -46,6 +50,68 @@ pub enum Error {
     ///
     /// The eth1 caches are stale, or a junk value was voted into the chain.
     UnknownPreviousEth1BlockHash,
+    /// The local deposit tree does not match the finalized `Eth1Data`, so any eth1 votes or
+    /// deposits produced from it would make a block invalid.
+    DepositTreeDiverged(DepositTreeDivergence),
+}
+
+/// A mismatch between the root of the local deposit tree and the `deposit_root` of a finalized
+/// `Eth1Data`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DepositTreeDivergence {
+    /// The number of deposits in the tree.
+    pub deposit_count: u64,
+    /// The eth1 block referenced by the finalized `Eth1Data`.
+    pub block_hash: Hash256,
+    /// The root of the first `deposit_count` deposits in the local deposit tree.
+    pub local_root: Hash256,
+    /// The `deposit_root` of the finalized `Eth1Data`.
+    pub finalized_root: Hash256,
+}
+
+/// The outcome of comparing the local deposit tree against a finalized `Eth1Data`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DepositTreeStatus {
+    /// The local deposit tree has the same root as the finalized `Eth1Data`.
+    Consistent,
+    /// The local deposit tree does not yet contain all of the deposits counted by the finalized
+    /// `Eth1Data` (or no eth1 node is used), so it cannot be checked.
+    Unknown,
+    /// The local deposit tree has a different root to the finalized `Eth1Data`.
+    Diverged(DepositTreeDivergence),
+}
+
+/// A deposit which has been observed in the deposit contract, but not yet included in the beacon
+/// chain.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PendingDeposit {
+    pub index: u64,
+    pub pubkey: PublicKeyBytes,
+    pub amount: u64,
+    /// The number of the eth1 block containing the deposit.
+    pub block_number: u64,
+    /// Set to `true` if the deposit is counted by `state.eth1_data`, so that it may be included in
+    /// the next blocks (subject to `MAX_DEPOSITS`).
+    pub is_voted_in: bool,
+    /// The epoch in which the deposit is expected to be included in a block.
+    pub estimated_inclusion_epoch: Epoch,
+    /// `Some(false)` if the deposit signature is invalid, in which case the deposit will not
+    /// create a validator and its amount is lost. `None` if the node was not configured to verify
+    /// deposit signatures.
+    pub signature_is_valid: Option<bool>,
+}
+
+/// The progress of the beacon chain through the deposits of the deposit contract.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PendingDeposits {
+    /// The number of deposits which have been included in the beacon chain.
+    pub included_count: u64,
+    /// The number of deposits counted by `state.eth1_data`.
+    pub voted_count: u64,
+    /// The number of deposits which have been observed in the deposit contract.
+    pub observed_count: u64,
+    /// All observed deposits which have not been included, ordered by index.
+    pub deposits: Vec<PendingDeposit>,
 }
 
 /// Holds an `Eth1ChainBackend` and serves requests from the `BeaconChain`.
@@ -58,6 +124,9 @@ where
     /// When `true`, the backend will be ignored and dummy data from the 2019 Canada interop method
     /// will be used instead.
     pub use_dummy_backend: bool,
+    /// Set whilst the local deposit tree is known to diverge from the finalized `Eth1Data`, during
+    /// which eth1 votes and deposits are not produced.
+    divergence: RwLock<Option<DepositTreeDivergence>>,
     _phantom: PhantomData<E>,
 }
 
@@ -70,10 +139,86 @@ where
         Self {
             backend,
             use_dummy_backend: false,
+            divergence: RwLock::new(None),
             _phantom: PhantomData,
         }
     }
 
+    /// Returns an error if the local deposit tree is known to diverge from the finalized
+    /// `Eth1Data`.
+    fn check_not_diverged(&self) -> Result<(), Error> {
+        match &*self.divergence.read() {
+            Some(divergence) => Err(Error::DepositTreeDiverged(divergence.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Compares the root of the local deposit tree against the `deposit_root` of the finalized
+    /// `finalized_eth1_data`.
+    ///
+    /// If they differ, eth1 votes and deposits are refused for block production until a later
+    /// check finds them consistent again (e.g., after the eth1 caches have been rebuilt).
+    pub fn verify_deposit_tree(
+        &self,
+        finalized_eth1_data: &Eth1Data,
+        log: &Logger,
+    ) -> Result<DepositTreeStatus, Error> {
+        if self.use_dummy_backend {
+            return Ok(DepositTreeStatus::Unknown);
+        }
+
+        let local_root = match self
+            .backend
+            .deposit_root(finalized_eth1_data.deposit_count)?
+        {
+            Some(local_root) => local_root,
+            None => return Ok(DepositTreeStatus::Unknown),
+        };
+
+        let status = if local_root == finalized_eth1_data.deposit_root {
+            DepositTreeStatus::Consistent
+        } else {
+            DepositTreeStatus::Diverged(DepositTreeDivergence {
+                deposit_count: finalized_eth1_data.deposit_count,
+                block_hash: finalized_eth1_data.block_hash,
+                local_root,
+                finalized_root: finalized_eth1_data.deposit_root,
+            })
+        };
+
+        let mut divergence = self.divergence.write();
+        match &status {
+            DepositTreeStatus::Diverged(new_divergence) => {
+                crit!(
+                    log,
+                    "Local deposit tree diverges from finalized eth1 data";
+                    "deposit_count" => new_divergence.deposit_count,
+                    "eth1_block_hash" => format!("{:?}", new_divergence.block_hash),
+                    "local_root" => format!("{:?}", new_divergence.local_root),
+                    "finalized_root" => format!("{:?}", new_divergence.finalized_root),
+                    "outcome" => "refusing to produce blocks, check the eth1 node"
+                );
+                *divergence = Some(new_divergence.clone());
+            }
+            _ => {
+                if divergence.take().is_some() {
+                    info!(
+                        log,
+                        "Local deposit tree matches finalized eth1 data";
+                        "deposit_count" => finalized_eth1_data.deposit_count,
+                        "outcome" => "resuming block production"
+                    );
+                }
+            }
+        }
+        metrics::set_gauge(
+            &metrics::ETH1_DEPOSIT_TREE_DIVERGED,
+            if divergence.is_some() { 1 } else { 0 },
+        );
+
+        Ok(status)
+    }
+
     /// Returns the `Eth1Data` that should be included in a block being produced for the given
     /// `state`.
     pub fn eth1_data_for_block_production(
@@ -81,6 +226,8 @@ where
         state: &BeaconState<E>,
         spec: &ChainSpec,
     ) -> Result<Eth1Data, Error> {
+        self.check_not_diverged()?;
+
         if self.use_dummy_backend {
             DummyEth1ChainBackend::default().eth1_data(state, spec)
         } else {
@@ -88,6 +235,56 @@ where
         }
     }
 
+    /// Seeds the backend with the `eth1_data` and `eth1_data_votes` of `head_state`, so that it
+    /// can cast sensible votes before its caches have synced with the eth1 chain.
+    pub fn warm_start(&self, head_state: &BeaconState<E>, log: &Logger) {
+        if self.use_dummy_backend {
+            return;
+        }
+
+        let votes = head_state
+            .eth1_data_votes
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        debug!(
+            log,
+            "Seeding eth1 votes from head state";
+            "votes" => votes.len(),
+            "deposit_count" => head_state.eth1_data.deposit_count,
+            "block_hash" => format!("{:?}", head_state.eth1_data.block_hash),
+        );
+
+        self.backend.warm_start(&head_state.eth1_data, votes);
+    }
+
+    /// Returns the timestamp of the latest eth1 block observed by the backend, if any.
+    pub fn latest_block_timestamp(&self) -> Option<u64> {
+        if self.use_dummy_backend {
+            None
+        } else {
+            self.backend.latest_block_timestamp()
+        }
+    }
+
+    /// Returns `true` if the backend has observed an eth1 block recently enough, at
+    /// `now_seconds`, to be considered in sync with the eth1 chain.
+    ///
+    /// The backend only follows blocks at least `spec.eth1_follow_distance` behind the eth1 head,
+    /// so it is allowed to lag up to twice that distance.
+    pub fn is_synced(&self, now_seconds: u64, spec: &ChainSpec) -> bool {
+        if self.use_dummy_backend {
+            return true;
+        }
+
+        let tolerance = 2 * spec.eth1_follow_distance * SECONDS_PER_ETH1_BLOCK;
+
+        self.backend
+            .latest_block_timestamp()
+            .map_or(false, |timestamp| timestamp + tolerance >= now_seconds)
+    }
+
     /// Returns a list of `Deposits` that may be included in a block.
     ///
     /// Including all of the returned `Deposits` in a block should _not_ cause it to become
@@ -102,12 +299,62 @@ where
         eth1_data_vote: &Eth1Data,
         spec: &ChainSpec,
     ) -> Result<Vec<Deposit>, Error> {
+        self.check_not_diverged()?;
+
         if self.use_dummy_backend {
             DummyEth1ChainBackend::default().queued_deposits(state, eth1_data_vote, spec)
         } else {
             self.backend.queued_deposits(state, eth1_data_vote, spec)
         }
     }
+
+    /// Returns the deposits which have been observed in the deposit contract but which are not
+    /// yet included in `state`.
+    pub fn pending_deposits(&self, state: &BeaconState<E>) -> PendingDeposits {
+        let logs = if self.use_dummy_backend {
+            DummyEth1ChainBackend::default().deposit_logs(state.eth1_deposit_index)
+        } else {
+            self.backend.deposit_logs(state.eth1_deposit_index)
+        };
+
+        let deposits = logs
+            .into_iter()
+            .map(|log| PendingDeposit {
+                index: log.index,
+                pubkey: log.deposit_data.pubkey,
+                amount: log.deposit_data.amount,
+                block_number: log.block_number,
+                is_voted_in: log.index < state.eth1_data.deposit_count,
+                estimated_inclusion_epoch: estimated_inclusion_epoch(state, log.index),
+                signature_is_valid: log.signature_is_valid,
+            })
+            .collect::<Vec<_>>();
+
+        PendingDeposits {
+            included_count: state.eth1_deposit_index,
+            voted_count: state.eth1_data.deposit_count,
+            observed_count: state.eth1_deposit_index + deposits.len() as u64,
+            deposits,
+        }
+    }
+}
+
+/// Estimates the epoch in which the deposit with `index` will be included in a block, assuming
+/// a block is produced at every slot and that each block includes `MAX_DEPOSITS` deposits.
+///
+/// A deposit which is not yet counted by `state.eth1_data` is assumed to be voted in at the end
+/// of the current eth1 voting period. The estimate does not account for the time taken for the
+/// eth1 block containing the deposit to become `ETH1_FOLLOW_DISTANCE` blocks deep.
+fn estimated_inclusion_epoch<T: EthSpec>(state: &BeaconState<T>, index: u64) -> Epoch {
+    let first_slot = if index < state.eth1_data.deposit_count {
+        state.slot
+    } else {
+        let period = T::SlotsPerEth1VotingPeriod::to_u64();
+        (state.slot / period + 1) * period
+    };
+    let position = index.saturating_sub(state.eth1_deposit_index);
+
+    (first_slot + position / T::MaxDeposits::to_u64() + 1).epoch(T::slots_per_epoch())
 }
 
 pub trait Eth1ChainBackend<T: EthSpec>: Sized + Send + Sync {
@@ -129,6 +376,21 @@ pub trait Eth1ChainBackend<T: EthSpec>: Sized + Send + Sync {
         eth1_data_vote: &Eth1Data,
         spec: &ChainSpec,
     ) -> Result<Vec<Deposit>, Error>;
+
+    /// Returns all deposits observed in the deposit contract with an index of at least
+    /// `from_index`, ordered by index.
+    fn deposit_logs(&self, from_index: u64) -> Vec<DepositLog>;
+
+    /// Returns the root of the tree of the first `deposit_count` deposits, or `None` if fewer
+    /// deposits have been observed.
+    fn deposit_root(&self, deposit_count: u64) -> Result<Option<Hash256>, Error>;
+
+    /// Returns the timestamp of the latest eth1 block observed by the backend, if any.
+    fn latest_block_timestamp(&self) -> Option<u64>;
+
+    /// Seeds the backend with the `eth1_data` and `eth1_data_votes` of the head state, to be
+    /// used whilst the backend cannot determine votes from the eth1 chain.
+    fn warm_start(&self, eth1_data: &Eth1Data, votes: Vec<Eth1Data>);
 }
 
 /// Provides a simple, testing-only backend that generates deterministic, meaningless eth1 data.
@@ -164,6 +426,23 @@ impl<T: EthSpec> Eth1ChainBackend<T> for DummyEth1ChainBackend<T> {
     ) -> Result<Vec<Deposit>, Error> {
         Ok(vec![])
     }
+
+    /// The dummy back-end never observes deposits.
+    fn deposit_logs(&self, _: u64) -> Vec<DepositLog> {
+        vec![]
+    }
+
+    fn deposit_root(&self, _: u64) -> Result<Option<Hash256>, Error> {
+        Ok(None)
+    }
+
+    /// The dummy back-end never observes eth1 blocks.
+    fn latest_block_timestamp(&self) -> Option<u64> {
+        None
+    }
+
+    /// The dummy back-end does not use the votes of other nodes.
+    fn warm_start(&self, _: &Eth1Data, _: Vec<Eth1Data>) {}
 }
 
 impl<T: EthSpec> Default for DummyEth1ChainBackend<T> {
@@ -181,18 +460,29 @@ impl<T: EthSpec> Default for DummyEth1ChainBackend<T> {
 pub struct CachingEth1Backend<T: EthSpec, S> {
     pub core: HttpService,
     store: Arc<S>,
+    /// The votes of the head state at startup, used until the caches have synced.
+    warm_start: Arc<RwLock<Option<Eth1WarmStart>>>,
     log: Logger,
     _phantom: PhantomData<T>,
 }
 
+/// The `eth1_data` and `eth1_data_votes` of a state, used to vote whilst the eth1 caches are
+/// incomplete.
+#[derive(Debug, Clone, PartialEq)]
+struct Eth1WarmStart {
+    eth1_data: Eth1Data,
+    votes: Vec<Eth1Data>,
+}
+
 impl<T: EthSpec, S: Store<T>> CachingEth1Backend<T, S> {
     /// Instantiates `self` with empty caches.
     ///
     /// Does not connect to the eth1 node or start any tasks to keep the cache updated.
-    pub fn new(config: Eth1Config, log: Logger, store: Arc<S>) -> Self {
+    pub fn new(config: Eth1Config, log: Logger, store: Arc<S>, spec: ChainSpec) -> Self {
         Self {
-            core: HttpService::new(config, log.clone()),
+            core: HttpService::new(config, log.clone(), spec),
             store,
+            warm_start: Arc::new(RwLock::new(None)),
             log,
             _phantom: PhantomData,
         }
@@ -209,11 +499,45 @@ impl<T: EthSpec, S: Store<T>> CachingEth1Backend<T, S> {
             log: service.log.clone(),
             core: service,
             store,
+            warm_start: Arc::new(RwLock::new(None)),
             _phantom: PhantomData,
         }
     }
 }
 
+impl<T: EthSpec, S: Store<T>> CachingEth1Backend<T, S> {
+    /// Returns the vote to cast for `state` whilst the eth1 caches are incomplete, if the backend
+    /// was seeded with the votes of the head state.
+    ///
+    /// Follows the most popular vote of `state` or, if it has no votes (e.g., at the start of a
+    /// voting period), of the head state. Votes for fewer deposits than are already known are
+    /// ignored. If there are no such votes, `state.eth1_data` is repeated, which never changes
+    /// the eth1 data of the chain.
+    fn warm_start_vote(&self, state: &BeaconState<T>) -> Option<Eth1Data> {
+        let warm_start = self.warm_start.read();
+        let warm_start = warm_start.as_ref()?;
+
+        let min_deposit_count = std::cmp::max(
+            state.eth1_data.deposit_count,
+            warm_start.eth1_data.deposit_count,
+        );
+        let votes = if state.eth1_data_votes.is_empty() {
+            &warm_start.votes[..]
+        } else {
+            &state.eth1_data_votes[..]
+        };
+
+        let vote = most_popular_vote(
+            votes
+                .iter()
+                .filter(|vote| vote.deposit_count >= min_deposit_count),
+        )
+        .unwrap_or_else(|| state.eth1_data.clone());
+
+        Some(vote)
+    }
+}
+
 impl<T: EthSpec, S: Store<T>> Eth1ChainBackend<T> for CachingEth1Backend<T, S> {
     fn eth1_data(&self, state: &BeaconState<T>, spec: &ChainSpec) -> Result<Eth1Data, Error> {
         // Note: we do not return random junk if this function call fails as it would be caused by
@@ -248,6 +572,18 @@ impl<T: EthSpec, S: Store<T>> Eth1ChainBackend<T> for CachingEth1Backend<T, S> {
             // This situation can also be caused when a testnet does not have an adequate delay
             // between the eth1 genesis block and the eth2 genesis block. This delay needs to be at
             // least `2 * ETH1_FOLLOW_DISTANCE`.
+            if let Some(eth1_data) = self.warm_start_vote(state) {
+                debug!(
+                    self.log,
+                    "Unable to find eth1 data sets";
+                    "lowest_block_number" => self.core.lowest_block_number(),
+                    "earliest_block_timestamp" => self.core.earliest_block_timestamp(),
+                    "outcome" => "casting eth1 vote from head state"
+                );
+
+                return Ok(eth1_data);
+            }
+
             crit!(
                 self.log,
                 "Unable to find eth1 data sets";
@@ -293,6 +629,7 @@ impl<T: EthSpec, S: Store<T>> Eth1ChainBackend<T> for CachingEth1Backend<T, S> {
                     block
                 })
                 .and_then(|block| block.clone().eth1_data())
+                .or_else(|| self.warm_start_vote(state))
                 .unwrap_or_else(|| {
                     crit!(
                         self.log,
@@ -346,6 +683,42 @@ impl<T: EthSpec, S: Store<T>> Eth1ChainBackend<T> for CachingEth1Backend<T, S> {
                 .map(|(_deposit_root, deposits)| deposits)
         }
     }
+
+    fn deposit_logs(&self, from_index: u64) -> Vec<DepositLog> {
+        self.core
+            .deposits()
+            .read()
+            .cache
+            .iter()
+            .skip_while(|log| log.index < from_index)
+            .cloned()
+            .collect()
+    }
+
+    fn deposit_root(&self, deposit_count: u64) -> Result<Option<Hash256>, Error> {
+        let deposits = self.core.deposits().read();
+
+        if (deposits.cache.len() as u64) < deposit_count {
+            return Ok(None);
+        }
+
+        deposits
+            .cache
+            .get_deposit_root(deposit_count, DEPOSIT_TREE_DEPTH)
+            .map(Some)
+            .map_err(|e| Error::BackendError(format!("Failed to get deposit root: {:?}", e)))
+    }
+
+    fn latest_block_timestamp(&self) -> Option<u64> {
+        self.core.latest_block_timestamp()
+    }
+
+    fn warm_start(&self, eth1_data: &Eth1Data, votes: Vec<Eth1Data>) {
+        *self.warm_start.write() = Some(Eth1WarmStart {
+            eth1_data: eth1_data.clone(),
+            votes,
+        });
+    }
 }
 
 /// Produces an `Eth1Data` with all fields sourced from `rand::thread_rng()`.
@@ -501,6 +874,20 @@ fn is_period_tail<E: EthSpec>(state: &BeaconState<E>) -> bool {
     slot >= slots_per_eth1_voting_period.integer_sqrt()
 }
 
+/// Returns the vote which appears most often in `votes`, preferring the vote for the most deposits
+/// (then the highest block hash) when there is a tie.
+fn most_popular_vote<'a, I: Iterator<Item = &'a Eth1Data>>(votes: I) -> Option<Eth1Data> {
+    let mut counts: HashMap<&Eth1Data, u64> = HashMap::new();
+    for vote in votes {
+        *counts.entry(vote).or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(vote, count)| (*count, vote.deposit_count, vote.block_hash))
+        .map(|(vote, _)| vote.clone())
+}
+
 /// Selects the winning vote from `valid_votes`.
 fn find_winning_vote(valid_votes: Eth1DataVoteCount) -> Option<Eth1Data> {
     valid_votes
@@ -529,7 +916,7 @@ fn slot_start_seconds<T: EthSpec>(
 mod test {
     use super::*;
     use environment::null_logger;
-    use types::{test_utils::DepositTestTask, MinimalEthSpec};
+    use types::{test_utils::DepositTestTask, MinimalEthSpec, VariableList};
 
     type E = MinimalEthSpec;
 
@@ -591,7 +978,12 @@ mod test {
 
             let log = null_logger().unwrap();
             let store = Arc::new(MemoryStore::open());
-            Eth1Chain::new(CachingEth1Backend::new(eth1_config, log, store))
+            Eth1Chain::new(CachingEth1Backend::new(
+                eth1_config,
+                log,
+                store,
+                E::default_spec(),
+            ))
         }
 
         fn get_deposit_log(i: u64, spec: &ChainSpec) -> DepositLog {
@@ -605,6 +997,7 @@ mod test {
                 deposit_data,
                 block_number: i,
                 index: i,
+                signature_is_valid: Some(true),
             }
         }
 
@@ -640,6 +1033,136 @@ mod test {
             );
         }
 
+        #[test]
+        fn pending_deposits() {
+            let spec = &E::default_spec();
+
+            let eth1_chain = get_eth1_chain();
+            let max_deposits = <E as EthSpec>::MaxDeposits::to_u64();
+            let period = <E as EthSpec>::SlotsPerEth1VotingPeriod::to_u64();
+
+            for i in 0..max_deposits + 2 {
+                eth1_chain
+                    .backend
+                    .core
+                    .deposits()
+                    .write()
+                    .cache
+                    .insert_log(get_deposit_log(i, spec))
+                    .expect("should insert log");
+            }
+
+            let mut state: BeaconState<E> = BeaconState::new(0, get_eth1_data(0), &spec);
+            state.slot = Slot::new(1);
+            state.eth1_deposit_index = 2;
+            state.eth1_data.deposit_count = max_deposits + 1;
+
+            let pending = eth1_chain.pending_deposits(&state);
+
+            assert_eq!(pending.included_count, 2);
+            assert_eq!(pending.voted_count, max_deposits + 1);
+            assert_eq!(pending.observed_count, max_deposits + 2);
+            assert_eq!(
+                pending
+                    .deposits
+                    .iter()
+                    .map(|deposit| deposit.index)
+                    .collect::<Vec<_>>(),
+                (2..max_deposits + 2).collect::<Vec<_>>(),
+                "should only return deposits which are not included"
+            );
+
+            let first = &pending.deposits[0];
+            assert!(first.is_voted_in, "first deposit should be voted in");
+            assert_eq!(first.signature_is_valid, Some(true));
+            assert_eq!(
+                first.estimated_inclusion_epoch,
+                Slot::new(2).epoch(E::slots_per_epoch()),
+                "first deposit should be included in the next block"
+            );
+
+            let last = pending.deposits.last().expect("should have deposits");
+            assert!(!last.is_voted_in, "last deposit should not be voted in");
+            assert_eq!(
+                last.estimated_inclusion_epoch,
+                Slot::new(period + 1).epoch(E::slots_per_epoch()),
+                "last deposit should be included after the voting period"
+            );
+        }
+
+        #[test]
+        fn deposit_tree_divergence() {
+            let spec = &E::default_spec();
+            let log = null_logger().unwrap();
+
+            let eth1_chain = get_eth1_chain();
+
+            let mut state: BeaconState<E> = BeaconState::new(0, get_eth1_data(0), &spec);
+            state.eth1_data.deposit_count = 2;
+
+            assert_eq!(
+                eth1_chain.verify_deposit_tree(&state.eth1_data, &log),
+                Ok(DepositTreeStatus::Unknown),
+                "should not check the tree without the finalized deposits"
+            );
+
+            for i in 0..3 {
+                eth1_chain
+                    .backend
+                    .core
+                    .deposits()
+                    .write()
+                    .cache
+                    .insert_log(get_deposit_log(i, spec))
+                    .expect("should insert log");
+            }
+
+            let deposit_root = eth1_chain
+                .backend
+                .deposit_root(2)
+                .expect("should get deposit root")
+                .expect("should know deposit root");
+            let finalized_eth1_data = Eth1Data {
+                deposit_root,
+                ..state.eth1_data.clone()
+            };
+            let junk_eth1_data = Eth1Data {
+                deposit_root: Hash256::from_low_u64_be(42),
+                ..state.eth1_data.clone()
+            };
+
+            match eth1_chain.verify_deposit_tree(&junk_eth1_data, &log) {
+                Ok(DepositTreeStatus::Diverged(divergence)) => {
+                    assert_eq!(divergence.local_root, deposit_root);
+                    assert_eq!(divergence.finalized_root, junk_eth1_data.deposit_root);
+                }
+                other => panic!("should diverge, got {:?}", other),
+            }
+            assert!(
+                eth1_chain
+                    .eth1_data_for_block_production(&state, spec)
+                    .is_err(),
+                "should not vote whilst diverged"
+            );
+            assert!(
+                eth1_chain
+                    .deposits_for_block_inclusion(&state, &state.eth1_data, spec)
+                    .is_err(),
+                "should not produce deposits whilst diverged"
+            );
+
+            assert_eq!(
+                eth1_chain.verify_deposit_tree(&finalized_eth1_data, &log),
+                Ok(DepositTreeStatus::Consistent)
+            );
+            assert!(
+                eth1_chain
+                    .deposits_for_block_inclusion(&state, &state.eth1_data, spec)
+                    .is_ok(),
+                "should produce deposits once consistent"
+            );
+        }
+
         #[test]
         fn deposits_with_cache() {
             let spec = &E::default_spec();
@@ -815,6 +1338,44 @@ mod test {
                 "random votes should be returned if the previous eth1 data block hash is unknown"
             );
         }
+
+        #[test]
+        fn eth1_data_warm_start() {
+            let spec = &E::default_spec();
+            let log = null_logger().unwrap();
+
+            let eth1_chain = get_eth1_chain();
+
+            let mut head_state: BeaconState<E> = BeaconState::new(0, get_eth1_data(1), &spec);
+            for i in &[0, 2, 3, 3] {
+                head_state
+                    .eth1_data_votes
+                    .push(get_eth1_data(*i))
+                    .expect("should push eth1 vote");
+            }
+            eth1_chain.warm_start(&head_state, &log);
+
+            assert_eq!(
+                eth1_chain.eth1_data_for_block_production(&head_state, &spec),
+                Ok(get_eth1_data(3)),
+                "the most popular vote should be followed with an empty cache"
+            );
+
+            let mut state = head_state.clone();
+            state.eth1_data_votes = VariableList::empty();
+            assert_eq!(
+                eth1_chain.eth1_data_for_block_production(&state, &spec),
+                Ok(get_eth1_data(3)),
+                "the votes of the head state should be used if the state has none"
+            );
+
+            state.eth1_data = get_eth1_data(4);
+            assert_eq!(
+                eth1_chain.eth1_data_for_block_production(&state, &spec),
+                Ok(get_eth1_data(4)),
+                "votes for fewer deposits than known should be ignored"
+            );
+        }
     }
 
     mod prev_block_hash {
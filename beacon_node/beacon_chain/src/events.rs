@@ -1,7 +1,12 @@
+use crate::epoch_summary::EpochSummary;
+use crate::finality_update::FinalityUpdate;
+use crate::metrics;
+use futures::sync::mpsc;
+use parking_lot::Mutex;
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
+use std::sync::Arc;
 use types::{Attestation, BeaconBlock, Epoch, EthSpec, Hash256};
-pub use websocket_server::WebSocketSender;
 
 pub trait EventHandler<T: EthSpec>: Sized + Send + Sync {
     fn register(&self, kind: EventKind<T>) -> Result<(), String>;
@@ -9,15 +14,6 @@ pub trait EventHandler<T: EthSpec>: Sized + Send + Sync {
 
 pub struct NullEventHandler<T: EthSpec>(PhantomData<T>);
 
-impl<T: EthSpec> EventHandler<T> for WebSocketSender<T> {
-    fn register(&self, kind: EventKind<T>) -> Result<(), String> {
-        self.send_string(
-            serde_json::to_string(&kind)
-                .map_err(|e| format!("Unable to serialize event: {:?}", e))?,
-        )
-    }
-}
-
 impl<T: EthSpec> EventHandler<T> for NullEventHandler<T> {
     fn register(&self, _kind: EventKind<T>) -> Result<(), String> {
         Ok(())
@@ -30,6 +26,58 @@ impl<T: EthSpec> Default for NullEventHandler<T> {
     }
 }
 
+/// Publishes the events of the `BeaconChain` to the services of the client which subscribe to
+/// them (e.g., the websocket server), so that the chain is not coupled to any of them.
+///
+/// Each subscriber receives every event published after it subscribed, in order. A subscriber is
+/// removed when the next event is published after it drops its receiver.
+pub struct EventBus<T: EthSpec> {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<Arc<EventKind<T>>>>>>,
+}
+
+impl<T: EthSpec> EventBus<T> {
+    /// Returns a stream of the events published from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Arc<EventKind<T>>> {
+        let (sender, receiver) = mpsc::unbounded();
+
+        let mut subscribers = self.subscribers.lock();
+        subscribers.push(sender);
+        metrics::set_gauge(&metrics::EVENT_BUS_SUBSCRIBERS, subscribers.len() as i64);
+
+        receiver
+    }
+}
+
+impl<T: EthSpec> EventHandler<T> for EventBus<T> {
+    fn register(&self, kind: EventKind<T>) -> Result<(), String> {
+        metrics::inc_counter_vec(&metrics::EVENTS_PUBLISHED, &[kind.name()]);
+
+        let event = Arc::new(kind);
+
+        let mut subscribers = self.subscribers.lock();
+        subscribers.retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+        metrics::set_gauge(&metrics::EVENT_BUS_SUBSCRIBERS, subscribers.len() as i64);
+
+        Ok(())
+    }
+}
+
+impl<T: EthSpec> Clone for EventBus<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T: EthSpec> Default for EventBus<T> {
+    fn default() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(
     bound = "T: EthSpec",
@@ -47,6 +95,10 @@ pub enum EventKind<T: EthSpec> {
         epoch: Epoch,
         root: Hash256,
     },
+    /// Published at each finalization, if enabled (see `BeaconChain::publish_finality_update`).
+    BeaconFinalityUpdate(Box<FinalityUpdate>),
+    /// Published when the canonical head enters a new epoch.
+    EpochSummary(Box<EpochSummary>),
     BeaconBlockImported {
         block_root: Hash256,
         block: Box<BeaconBlock<T>>,
@@ -62,4 +114,74 @@ pub enum EventKind<T: EthSpec> {
         reason: String,
         attestation: Box<Attestation<T>>,
     },
+    /// Published when the latest block in the eth1 cache changes.
+    Eth1Updated {
+        latest_block_timestamp: u64,
+        synced: bool,
+    },
+}
+
+impl<T: EthSpec> EventKind<T> {
+    /// Returns the name of the event, as it is serialized.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::BeaconHeadChanged { .. } => "beacon_head_changed",
+            EventKind::BeaconFinalization { .. } => "beacon_finalization",
+            EventKind::BeaconFinalityUpdate(_) => "beacon_finality_update",
+            EventKind::EpochSummary(_) => "epoch_summary",
+            EventKind::BeaconBlockImported { .. } => "beacon_block_imported",
+            EventKind::BeaconBlockRejected { .. } => "beacon_block_rejected",
+            EventKind::BeaconAttestationImported { .. } => "beacon_attestation_imported",
+            EventKind::BeaconAttestationRejected { .. } => "beacon_attestation_rejected",
+            EventKind::Eth1Updated { .. } => "eth1_updated",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{Future, Stream};
+    use types::MinimalEthSpec;
+
+    type E = MinimalEthSpec;
+
+    fn finalization(epoch: u64) -> EventKind<E> {
+        EventKind::BeaconFinalization {
+            epoch: Epoch::new(epoch),
+            root: Hash256::zero(),
+        }
+    }
+
+    #[test]
+    fn publishes_to_subscribers() {
+        let bus = EventBus::<E>::default();
+
+        bus.register(finalization(0)).unwrap();
+        let first = bus.subscribe();
+        bus.register(finalization(1)).unwrap();
+        let second = bus.subscribe();
+        bus.register(finalization(2)).unwrap();
+
+        let epochs = |receiver: mpsc::UnboundedReceiver<Arc<EventKind<E>>>, n| {
+            receiver
+                .take(n)
+                .map(|event| match *event {
+                    EventKind::BeaconFinalization { epoch, .. } => epoch.as_u64(),
+                    _ => panic!("unexpected event"),
+                })
+                .collect()
+                .wait()
+                .unwrap()
+        };
+
+        assert_eq!(epochs(first, 2), vec![1, 2]);
+        assert_eq!(epochs(second, 1), vec![2]);
+
+        bus.register(finalization(3)).unwrap();
+        assert!(
+            bus.subscribers.lock().is_empty(),
+            "dropped subscribers should be removed"
+        );
+    }
 }
@@ -0,0 +1,56 @@
+use serde_derive::{Deserialize, Serialize};
+use types::{
+    BeaconBlock, BeaconBlockHeader, BeaconState, BeaconStateError, EthSpec, Hash256, StateField,
+    StateProof, StateProofTarget,
+};
+
+/// A compact proof that a block has been finalized, allowing a client which does not sync the
+/// chain to follow finality.
+///
+/// The `finality_branch` proves the root of `finalized_header` within the state of
+/// `attested_header` (i.e., within its `finalized_checkpoint`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinalityUpdate {
+    /// The header of a block whose state finalizes `finalized_header`.
+    pub attested_header: BeaconBlockHeader,
+    /// The header of the finalized block.
+    pub finalized_header: BeaconBlockHeader,
+    /// A proof of the root of `finalized_header` against the state root of `attested_header`.
+    pub finality_branch: StateProof,
+}
+
+impl FinalityUpdate {
+    /// Produces the update for the `finalized_block` of the `attested_state` of `attested_block`.
+    ///
+    /// The proof is computed from scratch, so this function is expensive for large states.
+    pub fn new<T: EthSpec>(
+        attested_block: &BeaconBlock<T>,
+        attested_state: &BeaconState<T>,
+        finalized_block: &BeaconBlock<T>,
+    ) -> Result<Self, BeaconStateError> {
+        Ok(Self {
+            attested_header: attested_block.block_header(),
+            finalized_header: finalized_block.block_header(),
+            finality_branch: attested_state
+                .compute_merkle_proof(StateProofTarget::FinalizedCheckpointRoot)?,
+        })
+    }
+
+    /// Returns the root of the finalized block.
+    pub fn finalized_root(&self) -> Hash256 {
+        self.finalized_header.canonical_root()
+    }
+
+    /// Returns `true` if the `finality_branch` shows that the `finalized_header` is finalized by
+    /// the state of the `attested_header`.
+    ///
+    /// This does not verify that the `attested_header` is part of the canonical chain.
+    pub fn verify(&self) -> bool {
+        // The root is the second field of the `finalized_checkpoint`.
+        let finalized_root_index = (StateField::FinalizedCheckpoint.index() as u64) << 1 | 1;
+
+        self.finality_branch.leaf == self.finalized_root()
+            && self.finality_branch.index == finalized_root_index
+            && self.finality_branch.verify(self.attested_header.state_root)
+    }
+}
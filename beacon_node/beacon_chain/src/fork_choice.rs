@@ -1,12 +1,15 @@
+use crate::checkpoint_state_cache::JustifiedBalances;
 use crate::{errors::BeaconChainError, metrics, BeaconChain, BeaconChainTypes};
-use lmd_ghost::LmdGhost;
-use parking_lot::RwLock;
+use lmd_ghost::{LmdGhost, TreeNode};
+use parking_lot::{Mutex, RwLock};
+use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use state_processing::{common::get_attesting_indices, per_slot_processing};
 use std::sync::Arc;
 use store::{Error as StoreError, Store};
 use types::{
-    Attestation, BeaconBlock, BeaconState, BeaconStateError, Checkpoint, EthSpec, Hash256, Slot,
+    Attestation, BeaconBlock, BeaconState, BeaconStateError, Checkpoint, Epoch, EthSpec, Hash256,
+    Slot,
 };
 
 type Result<T> = std::result::Result<T, Error>;
@@ -21,6 +24,32 @@ pub enum Error {
     BeaconChainError(Box<BeaconChainError>),
 }
 
+/// A node of the block tree used by fork choice, as returned by `BeaconChain::fork_choice_nodes`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ForkChoiceNode {
+    pub root: Hash256,
+    /// The root of the closest ancestor of the block which is also in the tree.
+    pub parent: Option<Hash256>,
+    pub slot: Slot,
+    /// The weight of the node, as of the last run of fork choice.
+    pub weight: u64,
+    /// The current justified epoch of the post-state of the block.
+    pub justified_epoch: Epoch,
+    /// The finalized epoch of the post-state of the block.
+    pub finalized_epoch: Epoch,
+    /// The head that fork choice would select if it started from this node.
+    pub best_descendant: Hash256,
+}
+
+/// The votes of an attestation which have not yet been applied to the fork choice backend.
+struct QueuedAttestation {
+    /// The slot of the attestation. Its votes may be applied from the next slot onwards.
+    slot: Slot,
+    validator_indices: Vec<usize>,
+    block_hash: Hash256,
+    block_slot: Slot,
+}
+
 pub struct ForkChoice<T: BeaconChainTypes> {
     store: Arc<T::Store>,
     backend: T::LmdGhost,
@@ -33,10 +62,14 @@ pub struct ForkChoice<T: BeaconChainTypes> {
     justified_checkpoint: RwLock<Checkpoint>,
     /// The best justified checkpoint we've seen, which may be ahead of `justified_checkpoint`.
     best_justified_checkpoint: RwLock<Checkpoint>,
+    /// Attestations which are applied to `backend` in a single batch, once their slot has passed.
+    ///
+    /// These are not persisted, they are lost if the node restarts before they are applied.
+    queued_attestations: Mutex<Vec<QueuedAttestation>>,
 }
 
 impl<T: BeaconChainTypes> PartialEq for ForkChoice<T> {
-    /// This implementation ignores the `store`.
+    /// This implementation ignores the `store` and any queued attestations.
     fn eq(&self, other: &Self) -> bool {
         self.backend == other.backend
             && self.genesis_block_root == other.genesis_block_root
@@ -66,6 +99,7 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
             genesis_block_root,
             justified_checkpoint: RwLock::new(justified_checkpoint.clone()),
             best_justified_checkpoint: RwLock::new(justified_checkpoint),
+            queued_attestations: Mutex::new(vec![]),
         }
     }
 
@@ -114,10 +148,14 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
     }
 
     /// Run the fork choice rule to determine the head.
+    ///
+    /// Any queued attestations from prior slots are applied first.
     pub fn find_head(&self, chain: &BeaconChain<T>) -> Result<Hash256> {
         let timer = metrics::start_timer(&metrics::FORK_CHOICE_FIND_HEAD_TIMES);
 
-        let (start_state, start_block_root, start_block_slot) = {
+        self.apply_queued_attestations(chain.slot()?)?;
+
+        let (justified_balances, start_block_root, start_block_slot) = {
             // Check if we should update our view of the justified checkpoint.
             // Doing this check here should be quasi-equivalent to the update in the `on_tick`
             // function of the spec, so long as `find_head` is called at least once during the first
@@ -136,38 +174,49 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
                     .start_slot(T::EthSpec::slots_per_epoch()),
             );
 
-            let block = chain
-                .store
-                .get::<BeaconBlock<T::EthSpec>>(&block_root)?
-                .ok_or_else(|| Error::MissingBlock(block_root))?;
-
             // Resolve the `0x00.. 00` alias back to genesis
-            let block_root = if block_root == Hash256::zero() {
+            let start_block_root = if block_root == Hash256::zero() {
                 self.genesis_block_root
             } else {
                 block_root
             };
 
-            let mut state: BeaconState<T::EthSpec> = chain
-                .store
-                .get_state(&block.state_root, Some(block.slot))?
-                .ok_or_else(|| Error::MissingState(block.state_root))?;
-
-            // Fast-forward the state to the start slot of the epoch where it was justified.
-            for _ in block.slot.as_u64()..block_justified_slot.as_u64() {
-                per_slot_processing(&mut state, &chain.spec)
-                    .map_err(BeaconChainError::SlotProcessingError)?
-            }
+            // The balances only change with the justified checkpoint, so its state is only loaded
+            // from the store when the checkpoint changes.
+            let justified_balances = match chain
+                .checkpoint_state_cache
+                .get_justified_balances(&current_justified_checkpoint)
+            {
+                Some(justified_balances) => justified_balances,
+                None => {
+                    let block = chain
+                        .store
+                        .get::<BeaconBlock<T::EthSpec>>(&block_root)?
+                        .ok_or_else(|| Error::MissingBlock(block_root))?;
+
+                    let mut state: BeaconState<T::EthSpec> = chain
+                        .store
+                        .get_state(&block.state_root, Some(block.slot))?
+                        .ok_or_else(|| Error::MissingState(block.state_root))?;
+
+                    // Fast-forward the state to the start slot of the epoch where it was justified.
+                    for _ in block.slot.as_u64()..block_justified_slot.as_u64() {
+                        per_slot_processing(&mut state, &chain.spec)
+                            .map_err(BeaconChainError::SlotProcessingError)?;
+                    }
+
+                    chain.checkpoint_state_cache.insert_justified_balances(
+                        JustifiedBalances::from_state(current_justified_checkpoint, &state),
+                    )
+                }
+            };
 
-            (state, block_root, block_justified_slot)
+            (justified_balances, start_block_root, block_justified_slot)
         };
 
         // A function that returns the weight for some validator index.
         let weight = |validator_index: usize| -> Option<u64> {
-            start_state
-                .validators
-                .get(validator_index)
-                .map(|v| v.effective_balance)
+            justified_balances.balances.get(validator_index).copied()
         };
 
         let result = self
@@ -231,7 +280,8 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         Ok(())
     }
 
-    /// Process an attestation which references `block` in `attestation.data.beacon_block_root`.
+    /// Queue an attestation which references `block` in `attestation.data.beacon_block_root`, to be
+    /// applied by `apply_queued_attestations` once the slot of the attestation has passed.
     ///
     /// Assumes the attestation is valid.
     pub fn process_attestation(
@@ -263,10 +313,12 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
             let validator_indices =
                 get_attesting_indices(state, &attestation.data, &attestation.aggregation_bits)?;
 
-            for validator_index in validator_indices {
-                self.backend
-                    .process_attestation(validator_index, block_hash, block.slot)?;
-            }
+            self.queued_attestations.lock().push(QueuedAttestation {
+                slot: attestation.data.slot,
+                validator_indices,
+                block_hash,
+                block_slot: block.slot,
+            });
         }
 
         metrics::stop_timer(timer);
@@ -274,6 +326,53 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         Ok(())
     }
 
+    /// Applies the votes of all queued attestations from slots prior to `current_slot` to the
+    /// backend, in a single batch.
+    ///
+    /// As in the spec, an attestation only influences fork choice from the slot after its own.
+    /// Batching the votes means the lock on the backend is taken once per call, rather than once
+    /// per attestation.
+    pub fn apply_queued_attestations(&self, current_slot: Slot) -> Result<()> {
+        let timer = metrics::start_timer(&metrics::FORK_CHOICE_APPLY_QUEUED_ATTESTATIONS_TIMES);
+
+        let ready = {
+            let mut queued_attestations = self.queued_attestations.lock();
+            let (ready, pending): (Vec<_>, Vec<_>) =
+                std::mem::replace(&mut *queued_attestations, vec![])
+                    .into_iter()
+                    .partition(|attestation| attestation.slot < current_slot);
+            *queued_attestations = pending;
+            ready
+        };
+
+        let messages = ready
+            .iter()
+            .flat_map(|attestation| {
+                attestation
+                    .validator_indices
+                    .iter()
+                    .map(move |validator_index| {
+                        (
+                            *validator_index,
+                            attestation.block_hash,
+                            attestation.block_slot,
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        self.backend.process_attestations(&messages)?;
+
+        metrics::stop_timer(timer);
+
+        Ok(())
+    }
+
+    /// Returns the number of attestations which are queued and not yet applied to the backend.
+    pub fn queued_attestations_count(&self) -> usize {
+        self.queued_attestations.lock().len()
+    }
+
     /// Returns the latest message for a given validator, if any.
     ///
     /// Returns `(block_root, block_slot)`.
@@ -281,6 +380,11 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
         self.backend.latest_message(validator_index)
     }
 
+    /// Returns the nodes of the block tree of the underlying fork choice algorithm.
+    pub fn nodes(&self) -> Result<Vec<TreeNode>> {
+        self.backend.nodes().map_err(Into::into)
+    }
+
     /// Runs an integrity verification function on the underlying fork choice algorithm.
     ///
     /// Returns `Ok(())` if the underlying fork choice has maintained it's integrity,
@@ -325,6 +429,7 @@ impl<T: BeaconChainTypes> ForkChoice<T> {
             genesis_block_root: ssz_container.genesis_block_root,
             justified_checkpoint: RwLock::new(ssz_container.justified_checkpoint),
             best_justified_checkpoint: RwLock::new(ssz_container.best_justified_checkpoint),
+            queued_attestations: Mutex::new(vec![]),
         })
     }
 }
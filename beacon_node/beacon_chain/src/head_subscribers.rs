@@ -0,0 +1,85 @@
+use crate::checkpoint::CheckPoint;
+use futures::sync::mpsc;
+use parking_lot::Mutex;
+use types::{EthSpec, Hash256, Slot};
+
+/// A change of the canonical head, as sent to the streams returned by
+/// `BeaconChain::subscribe_head`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadChange {
+    pub block_root: Hash256,
+    pub slot: Slot,
+    pub previous_block_root: Hash256,
+    pub previous_slot: Slot,
+    /// The number of slots of the previous canonical chain which are no longer canonical, or zero
+    /// if the new head descends from the previous head.
+    pub reorg_depth: u64,
+}
+
+impl HeadChange {
+    /// Returns true if the new head does not descend from the previous head.
+    pub fn is_reorg(&self) -> bool {
+        self.reorg_depth > 0
+    }
+}
+
+/// The streams to which each change of the canonical head is sent, so that applications embedding
+/// the beacon chain may follow it without polling.
+///
+/// A subscriber is removed when the next change is sent after it drops its receiver.
+#[derive(Default)]
+pub struct HeadSubscribers {
+    senders: Mutex<Vec<mpsc::UnboundedSender<HeadChange>>>,
+}
+
+impl HeadSubscribers {
+    /// Returns a stream of the head changes from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<HeadChange> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.senders.lock().push(sender);
+        receiver
+    }
+
+    /// Sends `change` to each subscriber.
+    pub fn notify(&self, change: HeadChange) {
+        self.senders
+            .lock()
+            .retain(|sender| sender.unbounded_send(change.clone()).is_ok());
+    }
+}
+
+/// Returns the number of slots between `previous_head` and the last block it has in common with
+/// `new_head`.
+///
+/// The common block is found using the block roots of the head states, so if the chains diverged
+/// more than `SLOTS_PER_HISTORICAL_ROOT` slots ago the depth is only a lower bound.
+pub fn reorg_depth<E: EthSpec>(previous_head: &CheckPoint<E>, new_head: &CheckPoint<E>) -> u64 {
+    let previous_slot = previous_head.beacon_block.slot;
+    let highest_common_slot = std::cmp::min(previous_slot, new_head.beacon_block.slot);
+    let lowest_common_slot =
+        highest_common_slot.saturating_sub(E::slots_per_historical_root() as u64 - 1);
+
+    let mut slot = highest_common_slot;
+    loop {
+        let previous_root = block_root_at_slot(previous_head, slot);
+        if previous_root.is_some() && previous_root == block_root_at_slot(new_head, slot) {
+            break;
+        }
+        if slot <= lowest_common_slot {
+            break;
+        }
+        slot -= 1;
+    }
+
+    (previous_slot - slot).as_u64()
+}
+
+/// Returns the root of the block at `slot` on the chain of `head` (or of the block prior to `slot`
+/// if `slot` was skipped).
+fn block_root_at_slot<E: EthSpec>(head: &CheckPoint<E>, slot: Slot) -> Option<Hash256> {
+    if slot == head.beacon_block.slot {
+        Some(head.beacon_block_root)
+    } else {
+        head.beacon_state.get_block_root(slot).ok().copied()
+    }
+}
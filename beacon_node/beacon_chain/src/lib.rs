@@ -2,34 +2,78 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod balance_history;
 mod beacon_chain;
+mod beacon_proposer_cache;
+mod block_equivocations;
+mod block_packing;
 pub mod builder;
+mod cache_memory;
+mod chain_health;
 mod checkpoint;
 mod checkpoint_cache;
+mod checkpoint_state_cache;
+mod debug_dump;
+mod epoch_summary;
+mod epoch_summary_cache;
 mod errors;
 pub mod eth1_chain;
 pub mod events;
+mod finality_update;
 mod fork_choice;
+mod head_subscribers;
 mod head_tracker;
+mod local_proposals;
 mod metrics;
+mod next_epoch_cache;
+mod observed_proposers;
 mod persisted_beacon_chain;
+mod production_times;
+mod request_id;
+mod shuffling_cache;
+mod state_clones;
 pub mod test_utils;
+mod validator_queue;
+mod validators_diff;
 
+pub use self::balance_history::{BalanceRecord, MAX_BALANCE_HISTORY_EPOCHS};
 pub use self::beacon_chain::{
     AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BlockProcessingOutcome,
+    GossipBlockOutcome, HeadInfo, RevalidationSummary, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
 };
 pub use self::checkpoint::CheckPoint;
-pub use self::errors::{BeaconChainError, BlockProductionError};
-pub use eth1_chain::{Eth1Chain, Eth1ChainBackend};
-pub use events::EventHandler;
-pub use fork_choice::ForkChoice;
+pub use self::errors::{BeaconChainError, BlockProductionError, HistoricalBlockError};
+pub use beacon_proposer_cache::EpochProposers;
+pub use block_packing::BlockPackingDelay;
+pub use cache_memory::{CacheMemoryReport, CacheMemoryUsage, CachePriority};
+pub use chain_health::{ChainHealth, ChainHealthObservation, ChainHealthTracker};
+pub use epoch_summary::EpochSummary;
+pub use epoch_summary_cache::EPOCH_SUMMARY_CACHE_EPOCHS;
+pub use eth1_chain::{
+    DepositTreeDivergence, DepositTreeStatus, Eth1Chain, Eth1ChainBackend, PendingDeposit,
+    PendingDeposits,
+};
+pub use events::{EventBus, EventHandler};
+pub use finality_update::FinalityUpdate;
+pub use fork_choice::{ForkChoice, ForkChoiceNode};
+pub use head_subscribers::HeadChange;
 pub use lmd_ghost;
+pub use local_proposals::{ProposalFailureCause, ProposalOutcome};
 pub use metrics::scrape_for_metrics;
+pub use next_epoch_cache::PrecomputedEpoch;
+pub use observed_proposers::ProposalObservation;
+pub use operation_pool::PersistedOperationPool;
 pub use parking_lot;
+pub use production_times::LocalMessage;
+pub use request_id::{current_request_id, with_request_id, RequestId};
+pub use slasher;
 pub use slot_clock;
+pub use state_clones::take_state_clones;
 pub use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, DepositValidationError,
     ExitValidationError, ProposerSlashingValidationError,
 };
 pub use store;
 pub use types;
+pub use validator_queue::ValidatorQueueEstimate;
+pub use validators_diff::{ValidatorDiff, ValidatorStatus, ValidatorsDiff};
@@ -0,0 +1,355 @@
+use crate::metrics;
+use parking_lot::RwLock;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::time::Duration;
+use store::{DBColumn, Error as StoreError, SimpleStoreItem, Store};
+use types::{EthSpec, Hash256, Slot};
+
+/// 32-byte key for accessing the `SszLocalProposals`.
+pub const LOCAL_PROPOSALS_DB_KEY: &str = "LOCALPROPOSALSLOCALPROPOSALSLOCA";
+
+/// The most probable reason for a local proposal not becoming canonical.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProposalFailureCause {
+    /// The block was produced after attesters were due to vote for the head of its slot.
+    LateProduction,
+    /// The block was produced and published on time, but another chain was chosen.
+    LostForkChoice,
+    /// The block was not published to the network.
+    PublishFailure,
+}
+
+impl ProposalFailureCause {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProposalFailureCause::LateProduction => "late_production",
+            ProposalFailureCause::LostForkChoice => "lost_fork_choice",
+            ProposalFailureCause::PublishFailure => "publish_failure",
+        }
+    }
+}
+
+/// Whether a local proposal became canonical, once its slot has been finalized.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProposalOutcome {
+    Canonical,
+    /// The block was published, but is not in the canonical chain.
+    Orphaned(ProposalFailureCause),
+    /// The block was produced, but never published.
+    Missed(ProposalFailureCause),
+}
+
+/// A block produced by a local validator whose outcome is not yet known.
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct LocalProposal {
+    pub slot: Slot,
+    /// The time after the start of the slot at which the block was produced.
+    pub production_delay_millis: u64,
+    /// The root of the block once it has been published, otherwise zero.
+    pub block_root: Hash256,
+    /// Set if publishing the block to the network failed.
+    pub publish_failed: bool,
+}
+
+impl LocalProposal {
+    /// Determines the outcome of the proposal, given the root of the block at its slot in the
+    /// canonical chain (the root of an earlier block if the slot was skipped).
+    ///
+    /// A block produced later than `late_delay` into its slot which is not canonical is assumed
+    /// to have been orphaned because it was late.
+    pub fn outcome(
+        &self,
+        canonical_root: Option<Hash256>,
+        late_delay: Duration,
+    ) -> ProposalOutcome {
+        if self.block_root == Hash256::zero() {
+            ProposalOutcome::Missed(ProposalFailureCause::PublishFailure)
+        } else if canonical_root == Some(self.block_root) {
+            ProposalOutcome::Canonical
+        } else if self.publish_failed {
+            ProposalOutcome::Orphaned(ProposalFailureCause::PublishFailure)
+        } else if Duration::from_millis(self.production_delay_millis) > late_delay {
+            ProposalOutcome::Orphaned(ProposalFailureCause::LateProduction)
+        } else {
+            ProposalOutcome::Orphaned(ProposalFailureCause::LostForkChoice)
+        }
+    }
+}
+
+/// Tracks the blocks produced by local validators until their slots are finalized, and counts
+/// how many became canonical, were orphaned or were missed.
+///
+/// The pending proposals and the counts are persisted to the store as they change, so that they
+/// survive a restart of the beacon node.
+#[derive(Default)]
+pub struct LocalProposals(RwLock<SszLocalProposals>);
+
+impl LocalProposals {
+    /// Loads the proposals persisted in `store`, returning an empty set if there are none.
+    pub fn load<E: EthSpec, S: Store<E>>(store: &S) -> Result<Self, StoreError> {
+        let key = Hash256::from_slice(LOCAL_PROPOSALS_DB_KEY.as_bytes());
+
+        let ssz_container = store.get::<SszLocalProposals>(&key)?.unwrap_or_default();
+        ssz_container.update_metrics();
+
+        Ok(Self(RwLock::new(ssz_container)))
+    }
+
+    /// Stores the proposals in `store`, replacing any which were persisted previously.
+    pub fn persist<E: EthSpec, S: Store<E>>(&self, store: &S) -> Result<(), StoreError> {
+        let key = Hash256::from_slice(LOCAL_PROPOSALS_DB_KEY.as_bytes());
+        store.put(&key, &*self.0.read())
+    }
+
+    /// Records that a block for `slot` was produced `production_delay` after the start of the
+    /// slot.
+    pub fn observe_production(&self, slot: Slot, production_delay: Duration) {
+        let mut inner = self.0.write();
+
+        if inner.pending.iter().all(|proposal| proposal.slot != slot) {
+            inner.pending.push(LocalProposal {
+                slot,
+                production_delay_millis: production_delay.as_millis() as u64,
+                block_root: Hash256::zero(),
+                publish_failed: false,
+            });
+        }
+    }
+
+    /// Records that the block with `block_root` at `slot` was published, or that publishing it
+    /// failed.
+    pub fn observe_publication(&self, slot: Slot, block_root: Hash256, publish_failed: bool) {
+        let mut inner = self.0.write();
+
+        match inner
+            .pending
+            .iter_mut()
+            .find(|proposal| proposal.slot == slot)
+        {
+            Some(proposal) => {
+                proposal.block_root = block_root;
+                proposal.publish_failed = publish_failed;
+            }
+            // The block was produced by another beacon node.
+            None => inner.pending.push(LocalProposal {
+                slot,
+                production_delay_millis: 0,
+                block_root,
+                publish_failed,
+            }),
+        }
+    }
+
+    /// Returns the slots of the pending proposals at or before `finalized_slot`.
+    pub fn finalized_slots(&self, finalized_slot: Slot) -> Vec<Slot> {
+        self.0
+            .read()
+            .pending
+            .iter()
+            .map(|proposal| proposal.slot)
+            .filter(|slot| *slot <= finalized_slot)
+            .collect()
+    }
+
+    /// Determines the outcomes of the pending proposals at or before `finalized_slot`, removing
+    /// them and adding them to the counts.
+    ///
+    /// `canonical_root` returns the root of the block at a slot in the canonical chain.
+    pub fn resolve<F>(
+        &self,
+        finalized_slot: Slot,
+        late_delay: Duration,
+        canonical_root: F,
+    ) -> Vec<(LocalProposal, ProposalOutcome)>
+    where
+        F: Fn(Slot) -> Option<Hash256>,
+    {
+        let mut inner = self.0.write();
+
+        let (finalized, pending) = inner
+            .pending
+            .drain(..)
+            .partition::<Vec<_>, _>(|proposal| proposal.slot <= finalized_slot);
+        inner.pending = pending;
+
+        let resolved = finalized
+            .into_iter()
+            .map(|proposal| {
+                let outcome = proposal.outcome(canonical_root(proposal.slot), late_delay);
+                inner.count(outcome);
+                (proposal, outcome)
+            })
+            .collect::<Vec<_>>();
+
+        inner.update_metrics();
+
+        resolved
+    }
+}
+
+/// Helper struct that is used to encode/decode the state of the `LocalProposals` as SSZ bytes.
+#[derive(Encode, Decode, Clone, Default)]
+pub struct SszLocalProposals {
+    pending: Vec<LocalProposal>,
+    canonical: u64,
+    orphaned: u64,
+    missed: u64,
+    late_production: u64,
+    lost_fork_choice: u64,
+    publish_failure: u64,
+}
+
+impl SszLocalProposals {
+    fn count(&mut self, outcome: ProposalOutcome) {
+        let cause = match outcome {
+            ProposalOutcome::Canonical => {
+                self.canonical += 1;
+                return;
+            }
+            ProposalOutcome::Orphaned(cause) => {
+                self.orphaned += 1;
+                cause
+            }
+            ProposalOutcome::Missed(cause) => {
+                self.missed += 1;
+                cause
+            }
+        };
+
+        match cause {
+            ProposalFailureCause::LateProduction => self.late_production += 1,
+            ProposalFailureCause::LostForkChoice => self.lost_fork_choice += 1,
+            ProposalFailureCause::PublishFailure => self.publish_failure += 1,
+        }
+    }
+
+    fn update_metrics(&self) {
+        for (outcome, count) in &[
+            ("canonical", self.canonical),
+            ("orphaned", self.orphaned),
+            ("missed", self.missed),
+        ] {
+            metrics::set_gauge_vec(&metrics::LOCAL_PROPOSALS, &[*outcome], *count as i64);
+        }
+        for (cause, count) in &[
+            (ProposalFailureCause::LateProduction, self.late_production),
+            (ProposalFailureCause::LostForkChoice, self.lost_fork_choice),
+            (ProposalFailureCause::PublishFailure, self.publish_failure),
+        ] {
+            metrics::set_gauge_vec(
+                &metrics::LOCAL_PROPOSAL_FAILURES,
+                &[cause.as_str()],
+                *count as i64,
+            );
+        }
+    }
+}
+
+impl SimpleStoreItem for SszLocalProposals {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+    use types::MinimalEthSpec;
+
+    type E = MinimalEthSpec;
+
+    const LATE_DELAY: Duration = Duration::from_millis(2_000);
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn determines_outcomes() {
+        let proposals = LocalProposals::default();
+
+        // Canonical.
+        proposals.observe_production(Slot::new(1), Duration::from_millis(100));
+        proposals.observe_publication(Slot::new(1), root(1), false);
+        // Orphaned after losing fork choice.
+        proposals.observe_production(Slot::new(2), Duration::from_millis(100));
+        proposals.observe_publication(Slot::new(2), root(2), false);
+        // Orphaned after being produced late.
+        proposals.observe_production(Slot::new(3), Duration::from_millis(3_000));
+        proposals.observe_publication(Slot::new(3), root(3), false);
+        // Orphaned after failing to publish.
+        proposals.observe_production(Slot::new(4), Duration::from_millis(100));
+        proposals.observe_publication(Slot::new(4), root(4), true);
+        // Missed, never published.
+        proposals.observe_production(Slot::new(5), Duration::from_millis(100));
+        // Not yet finalized.
+        proposals.observe_production(Slot::new(9), Duration::from_millis(100));
+
+        assert_eq!(
+            proposals.finalized_slots(Slot::new(8)),
+            (1..=5).map(Slot::new).collect::<Vec<_>>()
+        );
+
+        let outcomes = proposals
+            .resolve(Slot::new(8), LATE_DELAY, |slot| {
+                if slot == Slot::new(1) {
+                    Some(root(1))
+                } else {
+                    Some(root(0))
+                }
+            })
+            .into_iter()
+            .map(|(proposal, outcome)| (proposal.slot.as_u64(), outcome))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                (1, ProposalOutcome::Canonical),
+                (
+                    2,
+                    ProposalOutcome::Orphaned(ProposalFailureCause::LostForkChoice)
+                ),
+                (
+                    3,
+                    ProposalOutcome::Orphaned(ProposalFailureCause::LateProduction)
+                ),
+                (
+                    4,
+                    ProposalOutcome::Orphaned(ProposalFailureCause::PublishFailure)
+                ),
+                (
+                    5,
+                    ProposalOutcome::Missed(ProposalFailureCause::PublishFailure)
+                ),
+            ]
+        );
+        assert_eq!(proposals.finalized_slots(Slot::new(9)), vec![Slot::new(9)]);
+    }
+
+    #[test]
+    fn persists_to_store() {
+        let store = MemoryStore::<E>::open();
+
+        let proposals = LocalProposals::default();
+        proposals.observe_production(Slot::new(1), Duration::from_millis(100));
+        proposals.observe_publication(Slot::new(1), root(1), false);
+        proposals.observe_production(Slot::new(2), Duration::from_millis(100));
+        proposals.resolve(Slot::new(1), LATE_DELAY, |_| Some(root(1)));
+        proposals.persist(&store).unwrap();
+
+        let loaded = LocalProposals::load(&store).unwrap();
+        assert_eq!(loaded.finalized_slots(Slot::new(2)), vec![Slot::new(2)]);
+        assert_eq!(loaded.0.read().canonical, 1);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{BeaconChain, BeaconChainTypes};
+use crate::{BeaconChain, BeaconChainTypes, HeadInfo};
 pub use lighthouse_metrics::*;
 use types::{BeaconState, Epoch, Hash256, Slot};
 
@@ -32,6 +32,10 @@ lazy_static! {
         "beacon_block_processing_committee_building_seconds",
         "Time spent building/obtaining committees for block processing."
     );
+    pub static ref BLOCK_PROCESSING_SIGNATURE: Result<Histogram> = try_create_histogram(
+        "beacon_block_processing_signature_seconds",
+        "Time spent verifying all the signatures in a block, in bulk."
+    );
     pub static ref BLOCK_PROCESSING_CORE: Result<Histogram> = try_create_histogram(
         "beacon_block_processing_core_seconds",
         "Time spent doing the core per_block_processing state processing."
@@ -49,6 +53,12 @@ lazy_static! {
         "Time spent registering the new block with fork choice (but not finding head)"
     );
 
+    pub static ref BLOCK_HEAD_SLOT_START_DELAY_TIME: Result<Histogram> = try_create_histogram(
+        "beacon_block_head_slot_start_delay_seconds",
+        "Duration between the start of the block's slot and the block becoming the head. Only \
+        observed for blocks which become the head during their own slot."
+    );
+
     /*
      * Block Production
      */
@@ -63,6 +73,14 @@ lazy_static! {
     pub static ref BLOCK_PRODUCTION_TIMES: Result<Histogram> =
         try_create_histogram("beacon_block_production_seconds", "Full runtime of block production");
 
+    /*
+     * Block Gossip
+     */
+    pub static ref GOSSIP_BLOCK_EQUIVOCATIONS: Result<IntCounter> = try_create_int_counter(
+        "beacon_gossip_block_equivocations_total",
+        "Count of proposers found to have signed conflicting blocks on gossip"
+    );
+
     /*
      * Block Statistics
      */
@@ -138,6 +156,10 @@ lazy_static! {
         "beacon_fork_choice_process_attestation_seconds",
         "Time taken to add an attestation to fork choice"
     );
+    pub static ref FORK_CHOICE_APPLY_QUEUED_ATTESTATIONS_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_fork_choice_apply_queued_attestations_seconds",
+        "Time taken to apply a batch of queued attestations to fork choice"
+    );
 
     /*
      * Persisting BeaconChain to disk
@@ -153,11 +175,117 @@ lazy_static! {
     pub static ref CHECKPOINT_CACHE_MISSES: Result<IntCounter> =
         try_create_int_counter("beacon_checkpoint_cache_misses_total", "Count of times checkpoint cache fulfils request");
 
+    /*
+     * Checkpoint state cache
+     */
+    pub static ref JUSTIFIED_BALANCES_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_justified_balances_cache_hits_total", "Count of times the justified balances cache fulfils a request");
+    pub static ref JUSTIFIED_BALANCES_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_justified_balances_cache_misses_total", "Count of times the justified balances cache does not fulfil a request");
+    pub static ref FINALIZED_STATE_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_finalized_state_cache_hits_total", "Count of times the finalized state cache fulfils a request");
+    pub static ref FINALIZED_STATE_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_finalized_state_cache_misses_total", "Count of times the finalized state cache does not fulfil a request");
+
+    /*
+     * Beacon proposer cache
+     */
+    pub static ref BEACON_PROPOSER_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_proposer_cache_hits_total", "Count of times the proposer cache fulfils a request");
+    pub static ref BEACON_PROPOSER_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_proposer_cache_misses_total", "Count of times the proposer cache does not fulfil a request");
+
+    /*
+     * Shuffling cache
+     */
+    pub static ref SHUFFLING_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_shuffling_cache_hits_total", "Count of times the shuffling cache fulfils a request");
+    pub static ref SHUFFLING_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_shuffling_cache_misses_total", "Count of times the shuffling cache does not fulfil a request");
+
+    /*
+     * Next epoch cache
+     */
+    pub static ref NEXT_EPOCH_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_next_epoch_cache_hits_total", "Count of times the precomputed next epoch fulfils a request");
+    pub static ref NEXT_EPOCH_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_next_epoch_cache_misses_total", "Count of times the precomputed next epoch does not fulfil a request");
+    pub static ref NEXT_EPOCH_PRECOMPUTE_TIMES: Result<Histogram> =
+        try_create_histogram("beacon_next_epoch_precompute_seconds", "Time taken to precompute the next epoch");
+
+    /*
+     * Cache memory
+     */
+    pub static ref CACHE_MEMORY_BYTES: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_cache_memory_bytes",
+        "Estimated memory used by each in-memory cache of the beacon chain",
+        &["cache"]
+    );
+    pub static ref CACHE_MEMORY_EVICTIONS: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_cache_memory_evictions_total",
+        "Count of cache entries evicted to keep the caches within the memory budget, by cache",
+        &["cache"]
+    );
+
+    /*
+     * Block packing
+     */
+    pub static ref AGGREGATE_ARRIVAL_DELAY_TIMES: Result<Histogram> =
+        try_create_histogram("beacon_aggregate_arrival_delay_seconds", "Time after the start of the following slot at which aggregates arrive (zero if before)");
+    pub static ref BLOCK_PACKING_WAIT_TIMES: Result<Histogram> =
+        try_create_histogram("beacon_block_packing_wait_seconds", "Time waited for aggregates before packing a block");
+
+    /*
+     * State clones
+     */
+    pub static ref HEAD_STATE_CLONES: Result<IntCounter> = try_create_int_counter(
+        "beacon_head_state_clones_total",
+        "Count of full clones of the head state"
+    );
+
+    /*
+     * Local publication
+     */
+    pub static ref LOCAL_PRODUCTION_TO_PUBLISH_TIMES: Result<HistogramVec> = try_create_histogram_vec(
+        "beacon_local_production_to_publish_seconds",
+        "Time between a message being produced for a local validator and it being published, by gossip topic",
+        &["topic"]
+    );
+
+    /*
+     * Events
+     */
+    pub static ref EVENTS_PUBLISHED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_events_published_total",
+        "Count of events published to the event bus, by kind",
+        &["kind"]
+    );
+    pub static ref EVENT_BUS_SUBSCRIBERS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_event_bus_subscribers",
+        "Number of subscribers to the event bus"
+    );
+
+    /*
+     * Local proposals
+     */
+    pub static ref LOCAL_PROPOSALS: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_local_proposals_total",
+        "Count of finalized slots proposed by local validators, by outcome (persisted across restarts)",
+        &["outcome"]
+    );
+    pub static ref LOCAL_PROPOSAL_FAILURES: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_local_proposal_failures_total",
+        "Count of orphaned or missed local proposals, by probable cause (persisted across restarts)",
+        &["cause"]
+    );
+
     /*
      * Eth1
      */
     pub static ref JUNK_ETH1_VOTES: Result<IntCounter> =
         try_create_int_counter("beacon_eth1_junk_votes", "Count of times we have voted junk for eth1 dat");
+    pub static ref ETH1_DEPOSIT_TREE_DIVERGED: Result<IntGauge> =
+        try_create_int_gauge("beacon_eth1_deposit_tree_diverged", "Set to 1 if the local deposit tree does not match the finalized eth1 data");
 
     /*
      * Chain Head
@@ -199,40 +327,51 @@ lazy_static! {
 /// Scrape the `beacon_chain` for metrics that are not constantly updated (e.g., the present slot,
 /// head state info, etc) and update the Prometheus `DEFAULT_REGISTRY`.
 pub fn scrape_for_metrics<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) {
-    scrape_head_state::<T>(
-        &beacon_chain.head().beacon_state,
-        beacon_chain.head().beacon_state_root,
-    );
+    scrape_head_info(&beacon_chain.head_info());
+    scrape_head_state::<T>(&beacon_chain.canonical_head.read().beacon_state);
+
+    for cache in beacon_chain.cache_memory_report().caches {
+        set_gauge_vec(&CACHE_MEMORY_BYTES, &[&cache.name], cache.bytes as i64);
+    }
 }
 
-/// Scrape the given `state` assuming it's the head state, updating the `DEFAULT_REGISTRY`.
-fn scrape_head_state<T: BeaconChainTypes>(state: &BeaconState<T::EthSpec>, state_root: Hash256) {
-    set_gauge_by_slot(&HEAD_STATE_SLOT, state.slot);
-    set_gauge_by_hash(&HEAD_STATE_ROOT, state_root);
-    set_gauge_by_slot(
-        &HEAD_STATE_LATEST_BLOCK_SLOT,
-        state.latest_block_header.slot,
-    );
+/// Scrape the summary of the head, updating the `DEFAULT_REGISTRY`.
+fn scrape_head_info(head_info: &HeadInfo) {
+    set_gauge_by_slot(&HEAD_STATE_SLOT, head_info.slot);
+    set_gauge_by_hash(&HEAD_STATE_ROOT, head_info.state_root);
     set_gauge_by_hash(
         &HEAD_STATE_CURRENT_JUSTIFIED_ROOT,
-        state.current_justified_checkpoint.root,
+        head_info.current_justified_checkpoint.root,
     );
     set_gauge_by_epoch(
         &HEAD_STATE_CURRENT_JUSTIFIED_EPOCH,
-        state.current_justified_checkpoint.epoch,
+        head_info.current_justified_checkpoint.epoch,
     );
     set_gauge_by_hash(
         &HEAD_STATE_PREVIOUS_JUSTIFIED_ROOT,
-        state.previous_justified_checkpoint.root,
+        head_info.previous_justified_checkpoint.root,
     );
     set_gauge_by_epoch(
         &HEAD_STATE_PREVIOUS_JUSTIFIED_EPOCH,
-        state.previous_justified_checkpoint.epoch,
+        head_info.previous_justified_checkpoint.epoch,
+    );
+    set_gauge_by_hash(
+        &HEAD_STATE_FINALIZED_ROOT,
+        head_info.finalized_checkpoint.root,
     );
-    set_gauge_by_hash(&HEAD_STATE_FINALIZED_ROOT, state.finalized_checkpoint.root);
     set_gauge_by_epoch(
         &HEAD_STATE_FINALIZED_EPOCH,
-        state.finalized_checkpoint.epoch,
+        head_info.finalized_checkpoint.epoch,
+    );
+}
+
+/// Scrape the given `state` assuming it's the head state, updating the `DEFAULT_REGISTRY`.
+///
+/// Only values which are not summarized by `HeadInfo` are scraped.
+fn scrape_head_state<T: BeaconChainTypes>(state: &BeaconState<T::EthSpec>) {
+    set_gauge_by_slot(
+        &HEAD_STATE_LATEST_BLOCK_SLOT,
+        state.latest_block_header.slot,
     );
     set_gauge_by_usize(&HEAD_STATE_TOTAL_VALIDATORS, state.validators.len());
     set_gauge_by_u64(&HEAD_STATE_VALIDATOR_BALANCES, state.balances.iter().sum());
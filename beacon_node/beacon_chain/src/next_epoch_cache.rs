@@ -0,0 +1,108 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use crate::metrics;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use types::{BeaconState, Epoch, EthSpec, Hash256};
+
+/// The state at the start of an epoch, computed ahead of time from the head.
+pub struct PrecomputedEpoch<T: EthSpec> {
+    /// The root of the head block from which `state` was computed.
+    pub head_block_root: Hash256,
+    /// The state at the first slot of the epoch, with the committee cache built for its current
+    /// epoch.
+    pub state: BeaconState<T>,
+    /// The index of the proposer for each slot of the epoch.
+    pub proposers: Vec<usize>,
+}
+
+impl<T: EthSpec> PrecomputedEpoch<T> {
+    pub fn epoch(&self) -> Epoch {
+        self.state.current_epoch()
+    }
+
+    fn is_for(&self, head_block_root: Hash256, epoch: Epoch) -> bool {
+        self.head_block_root == head_block_root && self.epoch() == epoch
+    }
+}
+
+/// Stores the `PrecomputedEpoch` for the next epoch, so that the spike of duties requests from
+/// validator clients at the epoch boundary does not require an epoch transition for each request.
+///
+/// Only the most recent precomputation is kept.
+pub struct NextEpochCache<T: EthSpec> {
+    precomputed: RwLock<Option<Arc<PrecomputedEpoch<T>>>>,
+}
+
+impl<T: EthSpec> Default for NextEpochCache<T> {
+    fn default() -> Self {
+        Self {
+            precomputed: RwLock::new(None),
+        }
+    }
+}
+
+impl<T: EthSpec> NextEpochCache<T> {
+    /// Replaces the stored precomputation with `precomputed`.
+    pub fn insert(&self, precomputed: PrecomputedEpoch<T>) {
+        *self.precomputed.write() = Some(Arc::new(precomputed));
+    }
+
+    /// Returns `true` if a precomputation for `epoch` from `head_block_root` is stored.
+    pub fn contains(&self, head_block_root: Hash256, epoch: Epoch) -> bool {
+        self.precomputed
+            .read()
+            .as_ref()
+            .map_or(false, |precomputed| {
+                precomputed.is_for(head_block_root, epoch)
+            })
+    }
+
+    /// Returns the precomputation for `epoch`, if it was computed from `head_block_root`.
+    ///
+    /// A precomputation from any other head is stale (e.g., a block arrived after it was
+    /// computed) and is not returned.
+    pub fn get(&self, head_block_root: Hash256, epoch: Epoch) -> Option<Arc<PrecomputedEpoch<T>>> {
+        let precomputed = self
+            .precomputed
+            .read()
+            .as_ref()
+            .filter(|precomputed| precomputed.is_for(head_block_root, epoch))
+            .cloned();
+
+        if precomputed.is_some() {
+            metrics::inc_counter(&metrics::NEXT_EPOCH_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::NEXT_EPOCH_CACHE_MISSES);
+        }
+
+        precomputed
+    }
+}
+
+impl<T: EthSpec> MemoryAccounted for NextEpochCache<T> {
+    fn name(&self) -> &'static str {
+        "next_epoch"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Low
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        self.precomputed
+            .read()
+            .as_ref()
+            .map_or((0, 0), |precomputed| {
+                (
+                    1,
+                    precomputed.state.memory_size()
+                        + precomputed.proposers.len() * std::mem::size_of::<usize>(),
+                )
+            })
+    }
+
+    /// Drops the precomputation, which is recomputed before the next epoch if possible.
+    fn evict_one(&self) -> bool {
+        self.precomputed.write().take().is_some()
+    }
+}
@@ -0,0 +1,218 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use parking_lot::RwLock;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use store::{DBColumn, Error as StoreError, SimpleStoreItem, Store};
+use types::{EthSpec, Hash256, Slot};
+
+/// 32-byte key for accessing the `SszObservedProposers`.
+pub const OBSERVED_PROPOSERS_DB_KEY: &str = "OBSERVEDPROPOSERSOBSERVEDPROPOSE";
+
+/// The result of observing a block proposal with `ObservedProposers::observe`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProposalObservation {
+    /// No block has been observed from this proposer at this slot.
+    New,
+    /// The same block has already been observed from this proposer at this slot.
+    Repeated,
+    /// A different block has already been observed from this proposer at this slot. Publishing
+    /// this block would make the proposer slashable.
+    Equivocation { previous_block_root: Hash256 },
+}
+
+/// Records the roots of the blocks produced by local validators, keyed by proposer and slot, so
+/// that a second, conflicting block is never published (e.g., by a redundant validator client).
+///
+/// Only proposals from the current epoch onwards are kept. The observations are persisted to the
+/// store as they are made, so that they survive a restart of the beacon node.
+#[derive(Default, Debug)]
+pub struct ObservedProposers(RwLock<HashMap<(u64, Slot), Hash256>>);
+
+impl ObservedProposers {
+    /// Loads the observations persisted in `store`, returning an empty set if there are none.
+    pub fn load<E: EthSpec, S: Store<E>>(store: &S) -> Result<Self, StoreError> {
+        let key = Hash256::from_slice(OBSERVED_PROPOSERS_DB_KEY.as_bytes());
+
+        Ok(store
+            .get::<SszObservedProposers>(&key)?
+            .map(|ssz_container| Self::from_ssz_container(&ssz_container))
+            .unwrap_or_default())
+    }
+
+    /// Stores the observations in `store`, replacing any which were persisted previously.
+    pub fn persist<E: EthSpec, S: Store<E>>(&self, store: &S) -> Result<(), StoreError> {
+        let key = Hash256::from_slice(OBSERVED_PROPOSERS_DB_KEY.as_bytes());
+        store.put(&key, &self.to_ssz_container())
+    }
+
+    /// Records that `proposer_index` produced the block with `block_root` at `slot`, unless some
+    /// other block has already been observed for the same proposer and slot.
+    ///
+    /// Observations from slots prior to the epoch of `slot` are pruned.
+    pub fn observe<E: EthSpec>(
+        &self,
+        proposer_index: u64,
+        slot: Slot,
+        block_root: Hash256,
+    ) -> ProposalObservation {
+        let mut map = self.0.write();
+
+        if let Some(previous_block_root) = map.get(&(proposer_index, slot)) {
+            return if *previous_block_root == block_root {
+                ProposalObservation::Repeated
+            } else {
+                ProposalObservation::Equivocation {
+                    previous_block_root: *previous_block_root,
+                }
+            };
+        }
+
+        let epoch_start_slot = slot
+            .epoch(E::slots_per_epoch())
+            .start_slot(E::slots_per_epoch());
+        map.retain(|(_, observed_slot), _| *observed_slot >= epoch_start_slot);
+        map.insert((proposer_index, slot), block_root);
+
+        ProposalObservation::New
+    }
+
+    /// Returns a `SszObservedProposers`, which contains all necessary information to restore the
+    /// state of `Self` at some later point.
+    pub fn to_ssz_container(&self) -> SszObservedProposers {
+        let map = self.0.read();
+
+        SszObservedProposers {
+            proposer_indices: map.keys().map(|(index, _)| *index).collect(),
+            slots: map.keys().map(|(_, slot)| *slot).collect(),
+            block_roots: map.values().copied().collect(),
+        }
+    }
+
+    /// Creates a new `Self` from the given `SszObservedProposers`.
+    pub fn from_ssz_container(ssz_container: &SszObservedProposers) -> Self {
+        let map = ssz_container
+            .proposer_indices
+            .iter()
+            .zip(ssz_container.slots.iter())
+            .zip(ssz_container.block_roots.iter())
+            .map(|((index, slot), root)| ((*index, *slot), *root))
+            .collect();
+
+        Self(RwLock::new(map))
+    }
+}
+
+impl MemoryAccounted for ObservedProposers {
+    fn name(&self) -> &'static str {
+        "observed_proposers"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Required
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let len = self.0.read().len();
+        (len, len * std::mem::size_of::<((u64, Slot), Hash256)>())
+    }
+}
+
+/// Helper struct that is used to encode/decode the state of the `ObservedProposers` as SSZ bytes.
+#[derive(Encode, Decode, Clone)]
+pub struct SszObservedProposers {
+    proposer_indices: Vec<u64>,
+    slots: Vec<Slot>,
+    block_roots: Vec<Hash256>,
+}
+
+impl SimpleStoreItem for SszObservedProposers {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+    use types::MinimalEthSpec;
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn detects_equivocation() {
+        let observed = ObservedProposers::default();
+        let slot = Slot::new(E::slots_per_epoch() + 1);
+        let root_a = Hash256::from_low_u64_be(1);
+        let root_b = Hash256::from_low_u64_be(2);
+
+        assert_eq!(
+            observed.observe::<E>(3, slot, root_a),
+            ProposalObservation::New
+        );
+        assert_eq!(
+            observed.observe::<E>(3, slot, root_a),
+            ProposalObservation::Repeated
+        );
+        assert_eq!(
+            observed.observe::<E>(3, slot, root_b),
+            ProposalObservation::Equivocation {
+                previous_block_root: root_a
+            }
+        );
+        assert_eq!(
+            observed.observe::<E>(4, slot, root_b),
+            ProposalObservation::New
+        );
+        assert_eq!(
+            observed.observe::<E>(3, slot + 1, root_b),
+            ProposalObservation::New
+        );
+    }
+
+    #[test]
+    fn prunes_previous_epochs() {
+        let observed = ObservedProposers::default();
+        let root = Hash256::from_low_u64_be(1);
+
+        observed.observe::<E>(3, Slot::new(1), root);
+        observed.observe::<E>(3, Slot::new(E::slots_per_epoch()), root);
+        assert_eq!(observed.to_ssz_container().slots.len(), 1);
+    }
+
+    #[test]
+    fn persists_to_store() {
+        let store = MemoryStore::<E>::open();
+        let slot = Slot::new(1);
+        let root = Hash256::from_low_u64_be(1);
+
+        assert_eq!(
+            ObservedProposers::load(&store)
+                .unwrap()
+                .to_ssz_container()
+                .slots,
+            vec![]
+        );
+
+        let observed = ObservedProposers::default();
+        observed.observe::<E>(3, slot, root);
+        observed.persist(&store).unwrap();
+
+        let loaded = ObservedProposers::load(&store).unwrap();
+        assert_eq!(
+            loaded.observe::<E>(3, slot, Hash256::zero()),
+            ProposalObservation::Equivocation {
+                previous_block_root: root
+            }
+        );
+    }
+}
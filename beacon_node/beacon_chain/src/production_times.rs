@@ -0,0 +1,102 @@
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::time::Duration;
+use types::Slot;
+
+/// The number of slots for which production times are retained, after which a message which has
+/// not been published is forgotten.
+pub const PRODUCTION_TIMES_RETAINED_SLOTS: u64 = 64;
+
+/// The kinds of message which are produced for local validators and published over gossip.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum LocalMessage {
+    Block,
+    Attestation,
+    AggregateAndProof,
+}
+
+impl LocalMessage {
+    /// The name of the gossip topic on which the message is published.
+    pub fn topic(self) -> &'static str {
+        match self {
+            LocalMessage::Block => "beacon_block",
+            LocalMessage::Attestation => "beacon_attestation",
+            LocalMessage::AggregateAndProof => "beacon_aggregate_and_proof",
+        }
+    }
+}
+
+/// Records when messages for local validators were produced, so that the delay until they are
+/// published can be measured.
+///
+/// Times are recorded per slot rather than per message: the validator client produces all the
+/// messages of a kind for a slot at about the same time, and the earliest production is kept.
+#[derive(Default)]
+pub struct ProductionTimes(RwLock<HashMap<(LocalMessage, Slot), Duration>>);
+
+impl ProductionTimes {
+    /// Records that a `message` for `slot` was produced `delay` after the start of `slot`,
+    /// forgetting any messages for slots which are too old to be published.
+    pub fn observe_production(&self, message: LocalMessage, slot: Slot, delay: Duration) {
+        let mut times = self.0.write();
+
+        let oldest_slot = slot.saturating_sub(PRODUCTION_TIMES_RETAINED_SLOTS);
+        times.retain(|(_, time_slot), _| *time_slot >= oldest_slot);
+
+        times.entry((message, slot)).or_insert(delay);
+    }
+
+    /// Returns the time between the production of a `message` for `slot` and its publication
+    /// `delay` after the start of `slot`, if the production was recorded.
+    pub fn production_to_publication(
+        &self,
+        message: LocalMessage,
+        slot: Slot,
+        delay: Duration,
+    ) -> Option<Duration> {
+        self.0
+            .read()
+            .get(&(message, slot))
+            .map(|production| delay.checked_sub(*production).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_production_to_publication() {
+        let times = ProductionTimes::default();
+        let slot = Slot::new(100);
+        let ms = Duration::from_millis;
+
+        times.observe_production(LocalMessage::Attestation, slot, ms(2_000));
+        times.observe_production(LocalMessage::Attestation, slot, ms(2_500));
+
+        assert_eq!(
+            times.production_to_publication(LocalMessage::Attestation, slot, ms(2_600)),
+            Some(ms(600)),
+            "the earliest production should be kept"
+        );
+        assert_eq!(
+            times.production_to_publication(LocalMessage::Attestation, slot, ms(1_000)),
+            Some(ms(0))
+        );
+        assert_eq!(
+            times.production_to_publication(LocalMessage::Block, slot, ms(2_600)),
+            None
+        );
+
+        times.observe_production(
+            LocalMessage::Block,
+            slot + PRODUCTION_TIMES_RETAINED_SLOTS + 1,
+            ms(0),
+        );
+        assert_eq!(
+            times.production_to_publication(LocalMessage::Attestation, slot, ms(2_600)),
+            None,
+            "old slots should be forgotten"
+        );
+    }
+}
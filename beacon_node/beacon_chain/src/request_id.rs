@@ -0,0 +1,111 @@
+//! Identifies the unit of work (e.g., an HTTP API request or a gossip message) being performed by
+//! the present thread.
+//!
+//! The logger of the `BeaconChain` includes the ID of the present request in each line, so that
+//! the lines logged whilst (e.g.) producing a block can be correlated with the request which
+//! triggered them. Lines logged outside of a request have no `request_id` key.
+use slog::{Key, Record, Serializer, Value};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static CURRENT_REQUEST_ID: Cell<Option<RequestId>> = Cell::new(None);
+}
+
+/// A process-wide unique identifier of a unit of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Returns an ID which has not been returned before.
+    pub fn next() -> Self {
+        RequestId(NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Value for RequestId {
+    fn serialize(
+        &self,
+        _record: &Record,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        serializer.emit_u64(key, self.0)
+    }
+}
+
+/// Returns the ID of the request being performed by the present thread, if any.
+pub fn current_request_id() -> Option<RequestId> {
+    CURRENT_REQUEST_ID.with(Cell::get)
+}
+
+/// Runs `f` as part of the request `id`, restoring the previous request of the present thread
+/// afterwards (even if `f` panics).
+pub fn with_request_id<F: FnOnce() -> R, R>(id: RequestId, f: F) -> R {
+    struct Restore(Option<RequestId>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT_REQUEST_ID.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CURRENT_REQUEST_ID.with(|current| current.replace(Some(id))));
+    f()
+}
+
+/// A log value which is the ID of the request being performed by the thread which logs the line.
+///
+/// The key is omitted from lines logged outside of a request.
+pub struct CurrentRequestId;
+
+impl Value for CurrentRequestId {
+    fn serialize(
+        &self,
+        record: &Record,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        match current_request_id() {
+            Some(id) => id.serialize(record, key, serializer),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_request_ids() {
+        let outer = RequestId::next();
+        let inner = RequestId::next();
+        assert_ne!(outer, inner);
+
+        assert_eq!(current_request_id(), None);
+        with_request_id(outer, || {
+            assert_eq!(current_request_id(), Some(outer));
+            with_request_id(inner, || assert_eq!(current_request_id(), Some(inner)));
+            assert_eq!(current_request_id(), Some(outer));
+
+            std::thread::spawn(|| assert_eq!(current_request_id(), None))
+                .join()
+                .expect("thread should not panic");
+        });
+        assert_eq!(current_request_id(), None);
+    }
+}
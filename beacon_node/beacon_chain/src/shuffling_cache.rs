@@ -0,0 +1,112 @@
+use crate::cache_memory::{CachePriority, MemoryAccounted};
+use crate::metrics;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{CommitteeCache, Epoch, Hash256};
+
+/// The maximum number of epochs (across all forks) for which committees are kept.
+pub const SHUFFLING_CACHE_SIZE: usize = 16;
+
+/// Caches the committees of recent epochs, so that they can be served without loading (or
+/// cloning) a state.
+///
+/// The committees of an epoch are determined by the chain up to the last slot of the epoch two
+/// epochs prior, so they are keyed by `(epoch, decision_root)` where `decision_root` is the root
+/// of the latest block at or before that slot.
+#[derive(Default)]
+pub struct ShufflingCache {
+    cache: RwLock<HashMap<(Epoch, Hash256), Arc<CommitteeCache>>>,
+}
+
+impl ShufflingCache {
+    /// Returns the committees of `epoch` decided by the block with `decision_root`, if known.
+    pub fn get(&self, epoch: Epoch, decision_root: Hash256) -> Option<Arc<CommitteeCache>> {
+        let committees = self.cache.read().get(&(epoch, decision_root)).cloned();
+
+        if committees.is_some() {
+            metrics::inc_counter(&metrics::SHUFFLING_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::SHUFFLING_CACHE_MISSES);
+        }
+
+        committees
+    }
+
+    /// Adds the `committees` of `epoch` decided by the block with `decision_root`, evicting the
+    /// entries of the lowest epochs if the cache is full.
+    pub fn insert(
+        &self,
+        epoch: Epoch,
+        decision_root: Hash256,
+        committees: CommitteeCache,
+    ) -> Arc<CommitteeCache> {
+        let committees = Arc::new(committees);
+        let mut cache = self.cache.write();
+
+        cache.insert((epoch, decision_root), committees.clone());
+
+        while cache.len() > SHUFFLING_CACHE_SIZE {
+            let lowest = cache.keys().min_by_key(|(epoch, _)| *epoch).copied();
+            match lowest {
+                Some(key) => cache.remove(&key),
+                None => break,
+            };
+        }
+
+        committees
+    }
+}
+
+impl MemoryAccounted for ShufflingCache {
+    fn name(&self) -> &'static str {
+        "shuffling"
+    }
+
+    fn priority(&self) -> CachePriority {
+        CachePriority::Medium
+    }
+
+    fn memory_usage(&self) -> (usize, usize) {
+        let cache = self.cache.read();
+
+        (
+            cache.len(),
+            cache
+                .values()
+                .map(|committees| committees.memory_size())
+                .sum(),
+        )
+    }
+
+    /// Evicts an entry of the lowest epoch.
+    fn evict_one(&self) -> bool {
+        let mut cache = self.cache.write();
+
+        let lowest = cache.keys().min_by_key(|(epoch, _)| *epoch).copied();
+        lowest.map_or(false, |key| cache.remove(&key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_lowest_epochs() {
+        let cache = ShufflingCache::default();
+        let root = |i: u64| Hash256::from_low_u64_be(i);
+
+        for epoch in 0..SHUFFLING_CACHE_SIZE as u64 + 2 {
+            cache.insert(Epoch::new(epoch), root(epoch), CommitteeCache::default());
+        }
+
+        assert_eq!(cache.cache.read().len(), SHUFFLING_CACHE_SIZE);
+        assert!(cache.get(Epoch::new(1), root(1)).is_none());
+        assert!(cache.get(Epoch::new(2), root(2)).is_some());
+        assert!(
+            cache.get(Epoch::new(2), root(3)).is_none(),
+            "committees are keyed by decision root"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Counts the clones of the head state made by the present thread.
+//!
+//! Cloning a `BeaconState` copies hundreds of megabytes with a large validator set, so code which
+//! only reads a few fields (e.g., the handlers of the HTTP API) should borrow the state with
+//! `BeaconChain::with_head_state` instead. The count allows such code to check that it does not
+//! clone states unexpectedly.
+use crate::metrics;
+use std::cell::Cell;
+
+thread_local! {
+    static STATE_CLONES: Cell<usize> = Cell::new(0);
+}
+
+/// Records that the head state was cloned by the present thread.
+pub fn record_state_clone() {
+    metrics::inc_counter(&metrics::HEAD_STATE_CLONES);
+    STATE_CLONES.with(|clones| clones.set(clones.get() + 1));
+}
+
+/// Returns the number of clones of the head state made by the present thread since the last call,
+/// resetting the count.
+pub fn take_state_clones() -> usize {
+    STATE_CLONES.with(|clones| clones.replace(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_clones_per_thread() {
+        take_state_clones();
+
+        record_state_clone();
+        record_state_clone();
+        std::thread::spawn(record_state_clone)
+            .join()
+            .expect("thread should not panic");
+
+        assert_eq!(take_state_clones(), 2);
+        assert_eq!(take_state_clones(), 0);
+    }
+}
@@ -18,8 +18,12 @@ use store::{
 };
 use tree_hash::{SignedRoot, TreeHash};
 use types::{
-    AggregateSignature, Attestation, BeaconBlock, BeaconState, BitList, ChainSpec, Domain, EthSpec,
-    Hash256, Keypair, SecretKey, Signature, Slot,
+    test_utils::{
+        AttesterSlashingTestTask, ProposerSlashingTestTask, TestingAttesterSlashingBuilder,
+        TestingProposerSlashingBuilder,
+    },
+    AggregateSignature, Attestation, AttesterSlashing, BeaconBlock, BeaconState, BitList,
+    ChainSpec, Domain, EthSpec, Hash256, Keypair, ProposerSlashing, SecretKey, Signature, Slot,
 };
 
 pub use crate::persisted_beacon_chain::{PersistedBeaconChain, BEACON_CHAIN_DB_KEY};
@@ -68,6 +72,24 @@ pub enum AttestationStrategy {
     SomeValidators(Vec<usize>),
 }
 
+/// Describes one of two competing forks built by `BeaconChainHarness::build_competing_forks`.
+#[derive(Clone, Debug)]
+pub struct ForkSpec {
+    /// The number of blocks on the fork.
+    pub blocks: usize,
+    /// The validators who attest to the blocks of the fork.
+    ///
+    /// Each validator only attests in the slot of its committee, so a fork of fewer than
+    /// `slots_per_epoch` blocks may not receive a vote from each of them.
+    pub attesters: Vec<usize>,
+}
+
+impl ForkSpec {
+    pub fn new(blocks: usize, attesters: Vec<usize>) -> Self {
+        Self { blocks, attesters }
+    }
+}
+
 /// A testing harness which can instantiate a `BeaconChain` and populate it with blocks and
 /// attestations.
 ///
@@ -247,27 +269,11 @@ where
         let mut head_block_root = None;
 
         for _ in 0..num_blocks {
-            while self.chain.slot().expect("should have a slot") < slot {
-                self.advance_slot();
-            }
+            let (block_root, new_state) = self.add_block_at_slot(slot, state);
 
-            let (block, new_state) = self.build_block(state.clone(), slot, block_strategy);
-
-            let outcome = self
-                .chain
-                .process_block(block)
-                .expect("should not error during block processing");
-
-            self.chain.fork_choice().expect("should find head");
-
-            if let BlockProcessingOutcome::Processed { block_root } = outcome {
-                head_block_root = Some(block_root);
-
-                self.add_free_attestations(&attestation_strategy, &new_state, block_root, slot);
-            } else {
-                panic!("block should be successfully processed: {:?}", outcome);
-            }
+            self.add_free_attestations(&attestation_strategy, &new_state, block_root, slot);
 
+            head_block_root = Some(block_root);
             state = new_state;
             slot += 1;
         }
@@ -275,12 +281,44 @@ where
         head_block_root.expect("did not produce any blocks")
     }
 
-    /// Returns a newly created block, signed by the proposer for the given slot.
-    fn build_block(
+    /// Produces a block at `slot` upon `state`, imports it into the `BeaconChain` and runs fork
+    /// choice. Returns the root of the block and the state after the block.
+    ///
+    /// The slot clock is advanced to `slot`, if required. Since `state` may be from any fork,
+    /// this is the building block for scenarios which require competing chains.
+    pub fn add_block_at_slot(
+        &self,
+        slot: Slot,
+        state: BeaconState<E>,
+    ) -> (Hash256, BeaconState<E>) {
+        while self.chain.slot().expect("should have a slot") < slot {
+            self.advance_slot();
+        }
+
+        let (block, new_state) = self.make_block(state, slot);
+
+        let outcome = self
+            .chain
+            .process_block(block)
+            .expect("should not error during block processing");
+
+        self.chain.fork_choice().expect("should find head");
+
+        if let BlockProcessingOutcome::Processed { block_root } = outcome {
+            (block_root, new_state)
+        } else {
+            panic!("block should be successfully processed: {:?}", outcome);
+        }
+    }
+
+    /// Returns a newly created block upon `state`, signed by the proposer for the given slot, and
+    /// the state after the block.
+    ///
+    /// The block is not imported into the `BeaconChain`.
+    pub fn make_block(
         &self,
         mut state: BeaconState<E>,
         slot: Slot,
-        block_strategy: BlockStrategy,
     ) -> (BeaconBlock<E>, BeaconState<E>) {
         if slot < state.slot {
             panic!("produce slot cannot be prior to the state slot");
@@ -295,15 +333,9 @@ where
             .build_all_caches(&self.spec)
             .expect("should build caches");
 
-        let proposer_index = match block_strategy {
-            BlockStrategy::OnCanonicalHead => self
-                .chain
-                .block_proposer(slot)
-                .expect("should get block proposer from chain"),
-            _ => state
-                .get_beacon_proposer_index(slot, &self.spec)
-                .expect("should get block proposer from state"),
-        };
+        let proposer_index = state
+            .get_beacon_proposer_index(slot, &self.spec)
+            .expect("should get block proposer from state");
 
         let sk = &self.keypairs[proposer_index].sk;
         let fork = &state.fork.clone();
@@ -317,7 +349,7 @@ where
 
         let (mut block, state) = self
             .chain
-            .produce_block_on_state(state, slot, randao_reveal)
+            .produce_block_on_state(state, slot, randao_reveal, None)
             .expect("should produce block");
 
         block.signature = {
@@ -340,7 +372,7 @@ where
         head_block_root: Hash256,
         head_block_slot: Slot,
     ) {
-        self.get_free_attestations(
+        self.make_attestations(
             attestation_strategy,
             state,
             head_block_root,
@@ -360,7 +392,9 @@ where
     }
 
     /// Generates a `Vec<Attestation>` for some attestation strategy and head_block.
-    pub fn get_free_attestations(
+    ///
+    /// The attestations are not imported into the `BeaconChain`.
+    pub fn make_attestations(
         &self,
         attestation_strategy: &AttestationStrategy,
         state: &BeaconState<E>,
@@ -461,33 +495,136 @@ where
         honest_fork_blocks: usize,
         faulty_fork_blocks: usize,
     ) -> (Hash256, Hash256) {
+        self.build_competing_forks(
+            &ForkSpec::new(honest_fork_blocks, honest_validators.to_vec()),
+            &ForkSpec::new(faulty_fork_blocks, faulty_validators.to_vec()),
+        )
+    }
+
+    /// Builds two competing forks upon the present head, each as described by its `ForkSpec`.
+    ///
+    /// The `first` fork is built upon the head in the next slot. The `second` fork is built upon
+    /// the same block after skipping a slot (so that its first block differs from the first block
+    /// of the `first` fork), once all the blocks of the `first` fork have been imported.
+    ///
+    /// Returns the roots of the blocks at the top of the `first` and `second` forks. Use
+    /// `Self::find_head` to determine which of them fork choice selects.
+    pub fn build_competing_forks(&self, first: &ForkSpec, second: &ForkSpec) -> (Hash256, Hash256) {
         let initial_head_slot = self.chain.head().beacon_block.slot;
 
         // Move to the next slot so we may produce some more blocks on the head.
         self.advance_slot();
 
-        // Extend the chain with blocks where only honest validators agree.
-        let honest_head = self.extend_chain(
-            honest_fork_blocks,
+        let first_head = self.extend_chain(
+            first.blocks,
             BlockStrategy::OnCanonicalHead,
-            AttestationStrategy::SomeValidators(honest_validators.to_vec()),
+            AttestationStrategy::SomeValidators(first.attesters.clone()),
         );
 
-        // Go back to the last block where all agreed, and build blocks upon it where only faulty nodes
-        // agree.
-        let faulty_head = self.extend_chain(
-            faulty_fork_blocks,
+        // Go back to the last block common to both forks, and build the second fork upon it.
+        let second_head = self.extend_chain(
+            second.blocks,
             BlockStrategy::ForkCanonicalChainAt {
                 previous_slot: initial_head_slot,
                 // `initial_head_slot + 2` means one slot is skipped.
                 first_slot: initial_head_slot + 2,
             },
-            AttestationStrategy::SomeValidators(faulty_validators.to_vec()),
+            AttestationStrategy::SomeValidators(second.attesters.clone()),
+        );
+
+        assert!(first_head != second_head, "forks should be distinct");
+
+        (first_head, second_head)
+    }
+
+    /// Makes those of the `attesters` in the committees of the present slot attest to the block
+    /// with `block_root`, which need not be the head (e.g., to shift the weight between forks).
+    pub fn attest_to_block(&self, block_root: Hash256, attesters: &[usize]) {
+        let block = self
+            .chain
+            .get_block(&block_root)
+            .expect("should read block")
+            .expect("block should exist");
+        let mut state = self
+            .chain
+            .store
+            .get_state(&block.state_root, Some(block.slot))
+            .expect("should read state")
+            .expect("state should exist");
+
+        let slot = self.chain.slot().expect("should have a slot");
+        while state.slot < slot {
+            per_slot_processing(&mut state, &self.spec)
+                .expect("should be able to advance state to slot");
+        }
+        state
+            .build_all_caches(&self.spec)
+            .expect("should build caches");
+
+        self.add_free_attestations(
+            &AttestationStrategy::SomeValidators(attesters.to_vec()),
+            &state,
+            block_root,
+            block.slot,
         );
+    }
 
-        assert!(honest_head != faulty_head, "forks should be distinct");
+    /// Advances to the next slot, so that any attestations queued by fork choice are applied,
+    /// then runs fork choice and returns the root of the head block.
+    pub fn find_head(&self) -> Hash256 {
+        self.advance_slot();
+        self.chain.fork_choice().expect("should find head");
+        self.chain.head_info().block_root
+    }
+
+    /// Returns an `AttesterSlashing` of the validators with `validator_indices`, who are made to
+    /// cast a double vote.
+    pub fn make_attester_slashing(&self, validator_indices: &[u64]) -> AttesterSlashing<E> {
+        let fork = self.chain.head().beacon_state.fork;
+
+        TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            validator_indices,
+            |validator_index, message, epoch, domain| {
+                let domain = self.spec.get_domain(epoch, domain, &fork);
+                Signature::new(message, domain, self.get_sk(validator_index as usize))
+            },
+        )
+    }
 
-        (honest_head, faulty_head)
+    /// Returns a `ProposerSlashing` of the validator with `validator_index`, who is made to
+    /// propose two different blocks in the same slot.
+    pub fn make_proposer_slashing(&self, validator_index: u64) -> ProposerSlashing {
+        let fork = self.chain.head().beacon_state.fork;
+
+        TestingProposerSlashingBuilder::double_vote::<E, _>(
+            ProposerSlashingTestTask::Valid,
+            validator_index,
+            |validator_index, message, epoch, domain| {
+                let domain = self.spec.get_domain(epoch, domain, &fork);
+                Signature::new(message, domain, self.get_sk(validator_index as usize))
+            },
+        )
+    }
+
+    /// Adds `slashing` to the operation pool of the `BeaconChain`, so that it is included in the
+    /// next block produced.
+    ///
+    /// Panics if the slashing is invalid.
+    pub fn process_attester_slashing(&self, slashing: AttesterSlashing<E>) {
+        self.chain
+            .process_attester_slashing(slashing)
+            .expect("should process attester slashing");
+    }
+
+    /// Adds `slashing` to the operation pool of the `BeaconChain`, so that it is included in the
+    /// next block produced.
+    ///
+    /// Panics if the slashing is invalid.
+    pub fn process_proposer_slashing(&self, slashing: ProposerSlashing) {
+        self.chain
+            .process_proposer_slashing(slashing)
+            .expect("should process proposer slashing");
     }
 
     /// Returns the secret key for the given validator index.
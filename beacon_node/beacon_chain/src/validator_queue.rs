@@ -0,0 +1,226 @@
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::max;
+use types::{BeaconState, BeaconStateError, ChainSpec, Epoch, EthSpec};
+
+/// The progress of a validator through the activation and exit queues, as estimated from some
+/// `BeaconState`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ValidatorQueueEstimate {
+    pub validator_index: u64,
+    /// The number of validators ahead of this validator in the activation queue, if it is waiting
+    /// to be activated.
+    pub activation_queue_position: Option<u64>,
+    /// The epoch in which the validator is (or is expected to be) activated.
+    ///
+    /// `None` if the validator is not yet eligible for activation.
+    pub activation_epoch: Option<Epoch>,
+    /// Set to `true` if the validator has initiated an exit.
+    pub is_exiting: bool,
+    /// The epoch in which the validator exits.
+    ///
+    /// For an active validator which has not initiated an exit, this is the epoch in which it
+    /// would exit if it were to initiate an exit now.
+    pub exit_epoch: Option<Epoch>,
+    /// The epoch from which the balance of the validator may be withdrawn, following `exit_epoch`.
+    pub withdrawable_epoch: Option<Epoch>,
+}
+
+/// Estimates the progress of the validator with `validator_index` through the activation and exit
+/// queues of `state`.
+///
+/// The estimate assumes that the churn limit does not change and that each validator is dequeued
+/// for activation as soon as it reaches the front of the queue (i.e., that the chain is
+/// finalizing).
+pub fn estimate_validator_queue<T: EthSpec>(
+    state: &BeaconState<T>,
+    validator_index: usize,
+    spec: &ChainSpec,
+) -> Result<ValidatorQueueEstimate, BeaconStateError> {
+    let validator = state
+        .validators
+        .get(validator_index)
+        .ok_or(BeaconStateError::UnknownValidator)?;
+
+    let current_epoch = state.current_epoch();
+    let churn_limit = churn_limit(state, spec);
+
+    let (activation_queue_position, activation_epoch) =
+        if validator.activation_epoch != spec.far_future_epoch {
+            (None, Some(validator.activation_epoch))
+        } else if validator.activation_eligibility_epoch != spec.far_future_epoch {
+            // Validators are dequeued in order of eligibility, with ties broken by index.
+            let queue_key = (validator.activation_eligibility_epoch, validator_index);
+            let position = state
+                .validators
+                .iter()
+                .enumerate()
+                .filter(|(i, other)| {
+                    other.activation_eligibility_epoch != spec.far_future_epoch
+                        && other.activation_epoch == spec.far_future_epoch
+                        && (other.activation_eligibility_epoch, *i) < queue_key
+                })
+                .count() as u64;
+
+            // The queue is processed at the end of the current epoch, and at each epoch after.
+            let dequeue_epoch = current_epoch + position / churn_limit;
+
+            (
+                Some(position),
+                Some(state.compute_activation_exit_epoch(dequeue_epoch, spec)),
+            )
+        } else {
+            (None, None)
+        };
+
+    let is_exiting = validator.exit_epoch != spec.far_future_epoch;
+    let exit_epoch = if is_exiting {
+        Some(validator.exit_epoch)
+    } else if validator.is_active_at(current_epoch) {
+        Some(exit_queue_epoch(state, churn_limit, spec))
+    } else {
+        None
+    };
+
+    Ok(ValidatorQueueEstimate {
+        validator_index: validator_index as u64,
+        activation_queue_position,
+        activation_epoch,
+        is_exiting,
+        exit_epoch,
+        withdrawable_epoch: if is_exiting {
+            Some(validator.withdrawable_epoch)
+        } else {
+            exit_epoch.map(|epoch| epoch + spec.min_validator_withdrawability_delay)
+        },
+    })
+}
+
+/// Returns the churn limit of the current epoch of `state`.
+///
+/// Equivalent to `BeaconState::get_churn_limit`, but does not require the committee cache.
+fn churn_limit<T: EthSpec>(state: &BeaconState<T>, spec: &ChainSpec) -> u64 {
+    let active_validator_count = state
+        .validators
+        .iter()
+        .filter(|validator| validator.is_active_at(state.current_epoch()))
+        .count() as u64;
+
+    max(
+        spec.min_per_epoch_churn_limit,
+        active_validator_count / spec.churn_limit_quotient,
+    )
+}
+
+/// Returns the epoch which would be assigned to a validator initiating an exit in `state`.
+///
+/// Mirrors `initiate_validator_exit`, without requiring the exit cache.
+fn exit_queue_epoch<T: EthSpec>(
+    state: &BeaconState<T>,
+    churn_limit: u64,
+    spec: &ChainSpec,
+) -> Epoch {
+    let delayed_epoch = state.compute_activation_exit_epoch(state.current_epoch(), spec);
+    let exit_epochs = state
+        .validators
+        .iter()
+        .map(|validator| validator.exit_epoch)
+        .filter(|epoch| *epoch != spec.far_future_epoch);
+
+    let exit_queue_epoch = exit_epochs
+        .clone()
+        .max()
+        .map_or(delayed_epoch, |epoch| max(epoch, delayed_epoch));
+    let exit_queue_churn = exit_epochs
+        .filter(|epoch| *epoch == exit_queue_epoch)
+        .count() as u64;
+
+    if exit_queue_churn >= churn_limit {
+        exit_queue_epoch + 1
+    } else {
+        exit_queue_epoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    fn get_state(validator_count: usize, spec: &ChainSpec) -> BeaconState<E> {
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(validator_count, spec);
+        let (state, _keypairs) = builder.build();
+        state
+    }
+
+    #[test]
+    fn estimates_activation_queue() {
+        let spec = E::default_spec();
+        let mut state = get_state(16, &spec);
+        let churn_limit = spec.min_per_epoch_churn_limit as usize;
+
+        // Queue twice the churn limit of validators, with the last validator eligible first.
+        let queued = (16 - churn_limit * 2..16).collect::<Vec<_>>();
+        for (i, index) in queued.iter().enumerate() {
+            let validator = &mut state.validators[*index];
+            validator.activation_epoch = spec.far_future_epoch;
+            validator.activation_eligibility_epoch = Epoch::new(1 + i as u64 % 4);
+        }
+        state.validators[15].activation_eligibility_epoch = Epoch::new(0);
+
+        let current_epoch = state.current_epoch();
+        let first_activation = state.compute_activation_exit_epoch(current_epoch, &spec);
+
+        let estimate = estimate_validator_queue(&state, 15, &spec).unwrap();
+        assert_eq!(estimate.activation_queue_position, Some(0));
+        assert_eq!(estimate.activation_epoch, Some(first_activation));
+
+        // The queued validator with the latest eligibility epoch is at the back of the queue.
+        let last = queued[3];
+        let estimate = estimate_validator_queue(&state, last, &spec).unwrap();
+        assert_eq!(
+            estimate.activation_queue_position,
+            Some(churn_limit as u64 * 2 - 1)
+        );
+        assert_eq!(estimate.activation_epoch, Some(first_activation + 1));
+
+        let estimate = estimate_validator_queue(&state, 0, &spec).unwrap();
+        assert_eq!(estimate.activation_queue_position, None);
+        assert_eq!(estimate.activation_epoch, Some(Epoch::new(0)));
+    }
+
+    #[test]
+    fn estimates_exit_queue() {
+        let spec = E::default_spec();
+        let mut state = get_state(16, &spec);
+        let delayed_epoch = state.compute_activation_exit_epoch(state.current_epoch(), &spec);
+
+        let estimate = estimate_validator_queue(&state, 0, &spec).unwrap();
+        assert!(!estimate.is_exiting);
+        assert_eq!(estimate.exit_epoch, Some(delayed_epoch));
+        assert_eq!(
+            estimate.withdrawable_epoch,
+            Some(delayed_epoch + spec.min_validator_withdrawability_delay)
+        );
+
+        // Fill the exit queue at the earliest epoch.
+        for index in 1..=spec.min_per_epoch_churn_limit as usize {
+            state.validators[index].exit_epoch = delayed_epoch;
+            state.validators[index].withdrawable_epoch = delayed_epoch + 7;
+        }
+
+        let estimate = estimate_validator_queue(&state, 0, &spec).unwrap();
+        assert_eq!(estimate.exit_epoch, Some(delayed_epoch + 1));
+
+        let estimate = estimate_validator_queue(&state, 1, &spec).unwrap();
+        assert!(estimate.is_exiting);
+        assert_eq!(estimate.exit_epoch, Some(delayed_epoch));
+        assert_eq!(estimate.withdrawable_epoch, Some(delayed_epoch + 7));
+
+        assert_eq!(
+            estimate_validator_queue(&state, 16, &spec),
+            Err(BeaconStateError::UnknownValidator)
+        );
+    }
+}
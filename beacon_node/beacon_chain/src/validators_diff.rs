@@ -0,0 +1,152 @@
+use serde_derive::{Deserialize, Serialize};
+use types::{BeaconState, Epoch, EthSpec, Validator};
+
+/// The stage of the lifecycle of a validator at some epoch.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorStatus {
+    /// Waiting to become eligible for activation, or waiting in the activation queue.
+    Pending,
+    Active,
+    /// Active, but an exit has been initiated (either voluntarily or by a slashing).
+    Exiting,
+    /// Exited, but not yet able to withdraw.
+    Exited,
+    Withdrawable,
+}
+
+impl ValidatorStatus {
+    /// Returns the status of `validator` at `epoch`.
+    pub fn at(validator: &Validator, epoch: Epoch, far_future_epoch: Epoch) -> Self {
+        if validator.is_withdrawable_at(epoch) {
+            ValidatorStatus::Withdrawable
+        } else if validator.is_exited_at(epoch) {
+            ValidatorStatus::Exited
+        } else if !validator.is_active_at(epoch) {
+            ValidatorStatus::Pending
+        } else if validator.exit_epoch != far_future_epoch {
+            ValidatorStatus::Exiting
+        } else {
+            ValidatorStatus::Active
+        }
+    }
+}
+
+/// A validator whose record or status differs between two states.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ValidatorDiff {
+    pub validator_index: u64,
+    /// The status of the validator at the current epoch of the later state.
+    pub status: ValidatorStatus,
+    /// The status of the validator at the current epoch of the earlier state, or `None` if the
+    /// validator has been added to the registry since.
+    pub previous_status: Option<ValidatorStatus>,
+    /// The record of the validator in the later state.
+    pub validator: Validator,
+    /// The balance of the validator in the later state, in Gwei.
+    pub balance: u64,
+}
+
+/// The validators which have changed since some epoch.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ValidatorsDiff {
+    /// The epoch of the earlier state.
+    pub since_epoch: Epoch,
+    /// The epoch of the later state, which may be supplied as `since_epoch` to fetch the
+    /// subsequent changes.
+    pub epoch: Epoch,
+    pub validators: Vec<ValidatorDiff>,
+}
+
+/// Returns each validator of `current` which is new, or whose record or status differs from that
+/// in `previous`, an earlier state of the same chain.
+///
+/// Changes to the balance of a validator are ignored, unless they change its effective balance.
+pub fn diff_validators<T: EthSpec>(
+    previous: &BeaconState<T>,
+    current: &BeaconState<T>,
+    far_future_epoch: Epoch,
+) -> Vec<ValidatorDiff> {
+    let previous_epoch = previous.current_epoch();
+    let current_epoch = current.current_epoch();
+
+    current
+        .validators
+        .iter()
+        .zip(current.balances.iter())
+        .enumerate()
+        .filter_map(|(validator_index, (validator, balance))| {
+            let status = ValidatorStatus::at(validator, current_epoch, far_future_epoch);
+            let previous_validator = previous.validators.get(validator_index);
+            let previous_status = previous_validator
+                .map(|previous| ValidatorStatus::at(previous, previous_epoch, far_future_epoch));
+
+            if previous_validator == Some(validator) && previous_status == Some(status) {
+                None
+            } else {
+                Some(ValidatorDiff {
+                    validator_index: validator_index as u64,
+                    status,
+                    previous_status,
+                    validator: validator.clone(),
+                    balance: *balance,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, ChainSpec, MinimalEthSpec, Slot};
+
+    type E = MinimalEthSpec;
+
+    fn get_state(validator_count: usize, spec: &ChainSpec) -> BeaconState<E> {
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(validator_count, spec);
+        let (state, _keypairs) = builder.build();
+        state
+    }
+
+    #[test]
+    fn diffs_validators() {
+        let spec = E::default_spec();
+        let far_future_epoch = spec.far_future_epoch;
+        let mut previous = get_state(8, &spec);
+        previous.validators[1].activation_epoch = Epoch::new(1);
+        previous.validators[2].activation_epoch = spec.far_future_epoch;
+
+        let mut current = previous.clone();
+        current.slot = Slot::new(E::slots_per_epoch());
+        current.balances[0] += 1;
+        current.validators[2].activation_eligibility_epoch = Epoch::new(1);
+        current.validators[3].exit_epoch = Epoch::new(4);
+        current.validators[4].effective_balance -= spec.effective_balance_increment;
+        current.validators.push(Validator::default()).unwrap();
+        current.balances.push(1).unwrap();
+
+        let diff = diff_validators(&previous, &current, far_future_epoch);
+        let statuses = diff
+            .iter()
+            .map(|diff| (diff.validator_index, diff.previous_status, diff.status))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            vec![
+                // Activated by the passing of an epoch.
+                (1, Some(ValidatorStatus::Pending), ValidatorStatus::Active),
+                // Eligible for activation, but still pending.
+                (2, Some(ValidatorStatus::Pending), ValidatorStatus::Pending),
+                (3, Some(ValidatorStatus::Active), ValidatorStatus::Exiting),
+                // Changed effective balance.
+                (4, Some(ValidatorStatus::Active), ValidatorStatus::Active),
+                (8, None, ValidatorStatus::Pending),
+            ]
+        );
+        assert_eq!(diff[4].balance, 1);
+
+        assert!(diff_validators(&current, &current, far_future_epoch).is_empty());
+    }
+}
@@ -6,10 +6,15 @@ extern crate lazy_static;
 use beacon_chain::test_utils::{
     AttestationStrategy, BeaconChainHarness, BlockStrategy, DiskHarnessType,
 };
+use beacon_chain::{BeaconChainError, HistoricalBlockError};
 use rand::Rng;
 use sloggers::{null::NullLoggerBuilder, Build};
+use state_processing::BlockReplayError;
 use std::sync::Arc;
-use store::{DiskStore, Store};
+use store::{
+    AnchorInfo, BlockRootSlot, DiskStore, RootIndexInfo, StateRootSlot, Store, StoreConfig,
+    ROOT_INDEX_INFO_KEY,
+};
 use tempfile::{tempdir, TempDir};
 use tree_hash::TreeHash;
 use types::test_utils::{SeedableRng, XorShiftRng};
@@ -27,13 +32,16 @@ type E = MinimalEthSpec;
 type TestHarness = BeaconChainHarness<DiskHarnessType<E>>;
 
 fn get_store(db_path: &TempDir) -> Arc<DiskStore<E>> {
+    get_store_with_config(db_path, StoreConfig::default())
+}
+
+fn get_store_with_config(db_path: &TempDir, config: StoreConfig) -> Arc<DiskStore<E>> {
     let spec = MinimalEthSpec::default_spec();
     let hot_path = db_path.path().join("hot_db");
     let cold_path = db_path.path().join("cold_db");
-    let slots_per_restore_point = MinimalEthSpec::slots_per_historical_root() as u64;
     let log = NullLoggerBuilder.build().expect("logger should build");
     Arc::new(
-        DiskStore::open(&hot_path, &cold_path, slots_per_restore_point, spec, log)
+        DiskStore::open(&hot_path, &cold_path, &config, spec, log)
             .expect("disk store should initialize"),
     )
 }
@@ -145,6 +153,40 @@ fn long_skip() {
     check_split_slot(&harness, store);
     check_chain_dump(&harness, initial_blocks + final_blocks + 1);
     check_iterators(&harness);
+    check_historical_block_root_proofs(&harness);
+}
+
+#[test]
+fn unfinalized_hot_state_summaries() {
+    let num_blocks_produced = E::slots_per_epoch() * 6;
+    let db_path = tempdir().unwrap();
+    // Disable the state cache so that every summarised state is replayed when it is read.
+    let config = StoreConfig {
+        state_cache_max_states: 0,
+        hot_full_state_epochs: 1,
+        ..StoreConfig::default()
+    };
+    let store = get_store_with_config(&db_path, config);
+    let harness = get_harness(store.clone(), VALIDATOR_COUNT);
+
+    // Without attestations the chain does not finalize, so every state stays in the hot DB.
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::SomeValidators(vec![]),
+    );
+
+    check_slot(&harness, num_blocks_produced);
+    assert_eq!(
+        harness.chain.head().beacon_state.finalized_checkpoint.epoch,
+        0,
+        "the chain should not finalize"
+    );
+    assert_eq!(store.get_split_slot(), 0);
+
+    // The states are checked against their roots, including those replayed from a summary.
+    check_chain_dump(&harness, num_blocks_produced + 1);
+    check_iterators(&harness);
 }
 
 /// Go forward to the point where the genesis randao value is no longer part of the vector.
@@ -236,6 +278,197 @@ fn split_slot_restore() {
     assert_eq!(store.get_split_slot(), split_slot);
 }
 
+// Check that the root indices of a database which predates them are backfilled.
+#[test]
+fn root_index_backfill() {
+    let num_blocks_produced = E::slots_per_epoch() * 4;
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    assert_eq!(
+        store.get_root_index_info(),
+        Ok(Some(RootIndexInfo {
+            backfilled_to_slot: Slot::new(0)
+        })),
+        "a new database should be marked as backfilled at genesis"
+    );
+
+    // Remove the indices, as if the database was created before they were introduced.
+    let chain_dump = harness.chain.chain_dump().unwrap();
+    for checkpoint in &chain_dump {
+        store
+            .delete::<BlockRootSlot>(&checkpoint.beacon_block_root)
+            .unwrap();
+        store
+            .delete::<StateRootSlot>(&checkpoint.beacon_state_root)
+            .unwrap();
+    }
+    store
+        .delete::<RootIndexInfo>(&Hash256::from_slice(ROOT_INDEX_INFO_KEY.as_bytes()))
+        .unwrap();
+    assert_eq!(store.get_block_slot(&head.beacon_block_root), Ok(None));
+
+    harness.chain.backfill_root_index().unwrap();
+
+    assert_eq!(
+        store.get_root_index_info(),
+        Ok(Some(RootIndexInfo {
+            backfilled_to_slot: head.beacon_block.slot
+        }))
+    );
+    check_chain_dump(&harness, num_blocks_produced + 1);
+}
+
+// Check that blocks older than the anchor of a database can be imported out-of-band.
+#[test]
+fn historical_block_import() {
+    let num_blocks_produced = E::slots_per_epoch() * 4;
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let mut blocks = harness
+        .chain
+        .chain_dump()
+        .unwrap()
+        .into_iter()
+        .map(|checkpoint| (checkpoint.beacon_block_root, checkpoint.beacon_block))
+        .collect::<Vec<_>>();
+    blocks.sort_by_key(|(_, block)| block.slot);
+
+    assert_eq!(
+        harness.chain.import_historical_blocks(vec![]),
+        Err(BeaconChainError::HistoricalBlockError(
+            HistoricalBlockError::NoAnchor
+        )),
+        "a database with the full history should not import historical blocks"
+    );
+
+    // Remove the blocks prior to the anchor, as if the database was initialised from it.
+    let anchor_index = blocks.len() / 2;
+    let (anchor_root, anchor_block) = blocks[anchor_index].clone();
+    let anchor_info = AnchorInfo {
+        anchor_root,
+        oldest_block_slot: anchor_block.slot,
+        oldest_state_slot: anchor_block.slot,
+    };
+    store.put_anchor_info(&anchor_info).unwrap();
+    for (block_root, _) in &blocks[..anchor_index] {
+        store.delete::<BeaconBlock<E>>(block_root).unwrap();
+    }
+
+    let mut missing = blocks[..anchor_index]
+        .iter()
+        .map(|(_, block)| block.clone())
+        .collect::<Vec<_>>();
+
+    // A batch with a gap is rejected without storing any blocks.
+    let gap = missing.remove(1);
+    assert!(harness
+        .chain
+        .import_historical_blocks(missing.clone())
+        .is_err());
+    assert_eq!(store.get_anchor_info(), Ok(Some(anchor_info)));
+    assert_eq!(
+        store.get::<BeaconBlock<E>>(&blocks[anchor_index - 1].0),
+        Ok(None)
+    );
+    missing.insert(1, gap);
+
+    // Blocks may be imported in several batches, in any order.
+    let newer = missing.split_off(missing.len() / 2);
+    assert_eq!(
+        harness.chain.import_historical_blocks(newer.clone()),
+        Ok(AnchorInfo {
+            oldest_block_slot: newer[0].slot,
+            ..anchor_info
+        })
+    );
+    missing.reverse();
+    assert_eq!(
+        harness.chain.import_historical_blocks(missing),
+        Ok(AnchorInfo {
+            oldest_block_slot: Slot::new(0),
+            ..anchor_info
+        })
+    );
+
+    for (block_root, block) in &blocks {
+        assert_eq!(
+            store.get::<BeaconBlock<E>>(block_root),
+            Ok(Some(block.clone()))
+        );
+        assert_eq!(store.get_block_slot(block_root), Ok(Some(block.slot)));
+    }
+    assert_eq!(
+        store.get_database_info().unwrap().oldest_block_slot,
+        Slot::new(0)
+    );
+}
+
+#[test]
+fn revalidate_from_slot() {
+    let num_blocks_produced = E::slots_per_epoch() * 5;
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    // Start from a finalized slot, so that the pre-state is read from the freezer.
+    let start_slot = Slot::new(E::slots_per_epoch() + 3);
+    let summary = harness.chain.revalidate_from_slot(start_slot).unwrap();
+    assert_eq!(summary.divergence, None);
+    assert_eq!(summary.head_slot, Slot::new(num_blocks_produced));
+    assert_eq!(
+        summary.blocks_verified as u64,
+        num_blocks_produced - start_slot.as_u64() + 1
+    );
+
+    // Replace a block with one committing to a different state root.
+    let corrupt_slot = Slot::new(E::slots_per_epoch() * 3 + 1);
+    let corrupt_root = harness.chain.root_at_slot(corrupt_slot).unwrap();
+    let mut corrupt_block = harness.chain.get_block(&corrupt_root).unwrap().unwrap();
+    corrupt_block.state_root = Hash256::repeat_byte(42);
+    store.put(&corrupt_root, &corrupt_block).unwrap();
+
+    let summary = harness.chain.revalidate_from_slot(start_slot).unwrap();
+    assert_eq!(
+        summary.blocks_verified as u64,
+        (corrupt_slot - start_slot).as_u64()
+    );
+    // The proposer signature commits to the state root, so the block may fail either check.
+    match summary.divergence {
+        Some(BlockReplayError::BlockProcessingError { slot, .. })
+        | Some(BlockReplayError::StateRootMismatch { slot, .. }) => {
+            assert_eq!(slot, corrupt_slot)
+        }
+        other => panic!("should diverge at the corrupt block, got {:?}", other),
+    }
+
+    // Blocks prior to the corruption still verify.
+    let summary = harness.chain.revalidate_from_slot(Slot::new(0)).unwrap();
+    assert_eq!(summary.blocks_verified as u64, corrupt_slot.as_u64() - 1);
+}
+
 /// Check that the head state's slot matches `expected_slot`.
 fn check_slot(harness: &TestHarness, expected_slot: u64) {
     let state = &harness.chain.head().beacon_state;
@@ -306,6 +539,22 @@ fn check_chain_dump(harness: &TestHarness, expected_len: u64) {
                 .slot,
             checkpoint.beacon_state.slot
         );
+
+        // Check that the block and state were indexed on import.
+        assert_eq!(
+            harness
+                .chain
+                .store
+                .get_block_slot(&checkpoint.beacon_block_root),
+            Ok(Some(checkpoint.beacon_block.slot))
+        );
+        assert_eq!(
+            harness
+                .chain
+                .store
+                .get_state_slot(&checkpoint.beacon_state_root),
+            Ok(Some(checkpoint.beacon_state.slot))
+        );
     }
 
     // Check the forwards block roots iterator against the chain dump
@@ -356,3 +605,44 @@ fn check_iterators(harness: &TestHarness) {
         Some(Slot::new(0))
     );
 }
+
+/// Check that the block roots accumulated into `historical_roots` can be proven against the head
+/// state root, using the historical batches in the freezer.
+fn check_historical_block_root_proofs(harness: &TestHarness) {
+    let head = harness.chain.head();
+    let state_root = head.beacon_state_root;
+    let slots_per_historical_root = E::slots_per_historical_root() as u64;
+
+    // Only the batches accumulated before the split slot have been moved to the freezer.
+    let split_slot = harness.chain.store.get_split_slot().as_u64();
+    let frozen_batches = split_slot.saturating_sub(1) / slots_per_historical_root;
+    assert!(frozen_batches > 0, "chain should span a historical root");
+
+    let block_roots = harness
+        .chain
+        .rev_iter_block_roots()
+        .map(|(root, slot)| (slot, root))
+        .collect::<std::collections::HashMap<_, _>>();
+
+    for index in 0..frozen_batches {
+        for &offset in &[
+            0,
+            slots_per_historical_root / 2,
+            slots_per_historical_root - 1,
+        ] {
+            let slot = Slot::new(index * slots_per_historical_root + offset);
+            let proof = harness
+                .chain
+                .historical_block_root_proof(&state_root, slot)
+                .expect("should compute proof")
+                .expect("head state should exist");
+
+            assert_eq!(Some(&proof.leaf), block_roots.get(&slot), "slot {}", slot);
+            assert!(
+                proof.verify(state_root),
+                "proof for slot {} should verify",
+                slot
+            );
+        }
+    }
+}
@@ -5,17 +5,24 @@ extern crate lazy_static;
 
 use beacon_chain::AttestationProcessingOutcome;
 use beacon_chain::{
+    take_state_clones,
     test_utils::{
-        AttestationStrategy, BeaconChainHarness, BlockStrategy, HarnessType, PersistedBeaconChain,
-        BEACON_CHAIN_DB_KEY,
+        AttestationStrategy, BeaconChainHarness, BlockStrategy, ForkSpec, HarnessType,
+        PersistedBeaconChain, BEACON_CHAIN_DB_KEY,
     },
-    BlockProcessingOutcome,
+    BeaconChainError, BlockProcessingOutcome, CheckPoint, HeadChange, HeadInfo,
+    ProposalObservation,
 };
+use futures::{Future, Stream};
 use state_processing::{
     per_slot_processing, per_slot_processing::Error as SlotProcessingError, EpochProcessingError,
 };
 use store::Store;
-use types::{BeaconStateError, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Slot};
+use tree_hash::TreeHash;
+use types::{
+    BeaconStateError, Epoch, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Slot,
+    StateField, StateProofTarget,
+};
 
 // Should ideally be divisible by 3.
 pub const VALIDATOR_COUNT: usize = 24;
@@ -169,6 +176,101 @@ fn chooses_fork() {
     );
 }
 
+#[test]
+fn chooses_fork_with_more_attestation_weight() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch() as usize;
+
+    harness.extend_chain(
+        slots_per_epoch,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    // Neither fork has the votes of two thirds of the validators, so neither is justified. Not
+    // every attester is in a committee of a slot with a block, but the heavy fork has more votes
+    // regardless of the shuffling.
+    let light_attesters: Vec<usize> = (0..VALIDATOR_COUNT / 6).collect();
+    let heavy_attesters: Vec<usize> = (VALIDATOR_COUNT / 6..VALIDATOR_COUNT / 6 * 4).collect();
+
+    let (long_head, short_head) = harness.build_competing_forks(
+        &ForkSpec::new(slots_per_epoch + 2, light_attesters),
+        &ForkSpec::new(slots_per_epoch + 1, heavy_attesters.clone()),
+    );
+
+    assert_eq!(
+        harness.find_head(),
+        short_head,
+        "the fork with more votes should be the head, despite having fewer blocks"
+    );
+
+    // Any two consecutive epochs of slots contain a whole epoch, in which each of the heavy
+    // attesters votes for the long fork instead.
+    for _ in 0..slots_per_epoch * 2 {
+        harness.attest_to_block(long_head, &heavy_attesters);
+        harness.advance_slot();
+    }
+
+    assert_eq!(
+        harness.find_head(),
+        long_head,
+        "should re-org to the fork which gained the votes"
+    );
+}
+
+#[test]
+fn head_stream_reports_reorg_depth() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch() as usize;
+
+    let heads = harness.chain.subscribe_head();
+    harness.extend_chain(
+        slots_per_epoch,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let common_slot = harness.chain.head_info().slot;
+
+    let changes: Vec<HeadChange> = heads
+        .take(slots_per_epoch as u64)
+        .collect()
+        .wait()
+        .expect("should receive head changes");
+    assert!(
+        changes.iter().all(|change| !change.is_reorg()),
+        "extending the chain should not re-org"
+    );
+    assert_eq!(changes.last().map(|change| change.slot), Some(common_slot));
+
+    let light_attesters: Vec<usize> = (0..VALIDATOR_COUNT / 6).collect();
+    let heavy_attesters: Vec<usize> = (VALIDATOR_COUNT / 6..VALIDATOR_COUNT / 6 * 4).collect();
+    let (long_head, short_head) = harness.build_competing_forks(
+        &ForkSpec::new(slots_per_epoch + 2, light_attesters),
+        &ForkSpec::new(slots_per_epoch + 1, heavy_attesters.clone()),
+    );
+    assert_eq!(harness.find_head(), short_head);
+    let short_slot = harness.chain.head_info().slot;
+
+    for _ in 0..slots_per_epoch * 2 {
+        harness.attest_to_block(long_head, &heavy_attesters);
+        harness.advance_slot();
+    }
+
+    let heads = harness.chain.subscribe_head();
+    assert_eq!(harness.find_head(), long_head);
+
+    let change = heads
+        .take(1)
+        .collect()
+        .wait()
+        .expect("should receive head change")
+        .pop()
+        .expect("head should have changed");
+    assert_eq!(change.previous_block_root, short_head);
+    assert_eq!(change.block_root, long_head);
+    assert_eq!(change.reorg_depth, (short_slot - common_slot).as_u64());
+}
+
 #[test]
 fn finalizes_with_full_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -204,6 +306,522 @@ fn finalizes_with_full_participation() {
     );
 }
 
+#[test]
+fn head_info_matches_head() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    assert_eq!(
+        harness.chain.head_info(),
+        HeadInfo::from_checkpoint(&harness.chain.head()),
+        "head info should be initialized with the genesis head"
+    );
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize * 4,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    let head_info = harness.chain.head_info();
+
+    assert_eq!(head_info, HeadInfo::from_checkpoint(&head));
+    assert_eq!(head_info.slot, head.beacon_state.slot);
+    assert!(
+        head_info.finalized_checkpoint.epoch > 0,
+        "head info should follow finalization"
+    );
+}
+
+#[test]
+fn borrows_head_state_without_cloning() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    let head_slot = head.beacon_state.slot;
+    take_state_clones();
+
+    assert_eq!(
+        harness
+            .chain
+            .with_head_state(|state| state.tree_hash_root()),
+        head.beacon_state.tree_hash_root()
+    );
+    assert_eq!(
+        harness
+            .chain
+            .with_state_at_slot(head_slot, |state| state.slot)
+            .expect("should read head state"),
+        head_slot
+    );
+    assert_eq!(
+        take_state_clones(),
+        0,
+        "the head state should not be cloned"
+    );
+
+    let earlier_slot = head_slot - 2;
+    assert_eq!(
+        harness
+            .chain
+            .with_state_at_slot(earlier_slot, |state| state.tree_hash_root())
+            .expect("should read earlier state"),
+        harness
+            .chain
+            .state_at_slot(earlier_slot)
+            .expect("should load earlier state")
+            .tree_hash_root()
+    );
+}
+
+#[test]
+fn summarises_epochs_with_full_participation() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let epoch = Epoch::new(2);
+    let summary = harness
+        .chain
+        .epoch_summary(epoch)
+        .expect("should have a summary for the epoch");
+
+    assert_eq!(summary.epoch, epoch);
+    assert_eq!(summary.validators.len(), VALIDATOR_COUNT);
+    assert!(
+        summary
+            .validators
+            .iter()
+            .all(|validator| validator.is_target_attester && validator.inclusion_delay.is_some()),
+        "all validators should have attested to the target"
+    );
+    assert_eq!(summary.target_participation_rate(), 1.0);
+
+    // The summary for the current epoch is not yet known.
+    assert!(harness
+        .chain
+        .epoch_summary(harness.chain.head().beacon_state.current_epoch())
+        .is_none());
+}
+
+#[test]
+fn precomputes_next_epoch_from_head() {
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let harness = get_harness(VALIDATOR_COUNT);
+    let spec = &harness.chain.spec;
+
+    harness.extend_chain(
+        slots_per_epoch as usize * 2 - 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let next_epoch = harness.chain.epoch().expect("should read epoch") + 1;
+    assert!(harness.chain.precomputed_epoch(next_epoch).is_none());
+
+    harness
+        .chain
+        .precompute_next_epoch()
+        .expect("should precompute next epoch");
+
+    let precomputed = harness
+        .chain
+        .precomputed_epoch(next_epoch)
+        .expect("should have precomputed the next epoch");
+    assert_eq!(precomputed.epoch(), next_epoch);
+    assert_eq!(
+        precomputed.head_block_root,
+        harness.chain.head_info().block_root
+    );
+
+    let mut state = harness
+        .chain
+        .state_at_slot(next_epoch.start_slot(slots_per_epoch))
+        .expect("should skip to next epoch");
+    state
+        .build_committee_cache(RelativeEpoch::Current, spec)
+        .expect("should build committee cache");
+    let proposers = next_epoch
+        .slot_iter(slots_per_epoch)
+        .map(|slot| state.get_beacon_proposer_index(slot, spec))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("should get proposers");
+    assert_eq!(precomputed.proposers, proposers);
+    assert_eq!(precomputed.state.tree_hash_root(), state.tree_hash_root());
+
+    harness.extend_chain(
+        1,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    assert!(
+        harness.chain.precomputed_epoch(next_epoch).is_none(),
+        "precomputation from an old head should not be used"
+    );
+}
+
+#[test]
+fn epoch_proposers_match_state() {
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let harness = get_harness(VALIDATOR_COUNT);
+    let spec = &harness.chain.spec;
+
+    harness.extend_chain(
+        slots_per_epoch as usize * 2 + 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head_info = harness.chain.head_info();
+    let head_epoch = head_info.slot.epoch(slots_per_epoch);
+
+    for epoch in vec![head_epoch, head_epoch + 1] {
+        let proposers = harness
+            .chain
+            .epoch_proposers(head_info.block_root, epoch)
+            .expect("should get proposers");
+
+        let mut state = harness
+            .chain
+            .state_at_slot(std::cmp::max(
+                epoch.start_slot(slots_per_epoch),
+                head_info.slot,
+            ))
+            .expect("should get state");
+        state
+            .build_committee_cache(RelativeEpoch::Current, spec)
+            .expect("should build committee cache");
+
+        for slot in epoch.slot_iter(slots_per_epoch) {
+            let expected = state
+                .get_beacon_proposer_index(slot, spec)
+                .expect("should get proposer");
+            assert_eq!(
+                proposers
+                    .proposer::<MinimalEthSpec>(slot)
+                    .map(|(index, _)| *index),
+                Some(expected)
+            );
+            assert_eq!(harness.chain.block_proposer(slot), Ok(expected));
+        }
+    }
+}
+
+#[test]
+fn epoch_committees_match_state() {
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let harness = get_harness(VALIDATOR_COUNT);
+    let spec = &harness.chain.spec;
+
+    harness.extend_chain(
+        slots_per_epoch as usize * 4 + 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head_info = harness.chain.head_info();
+    let head_epoch = head_info.slot.epoch(slots_per_epoch);
+
+    for epoch in (0..=head_epoch.as_u64() + 1).map(Epoch::new) {
+        let committees = harness
+            .chain
+            .epoch_committees(head_info.block_root, epoch)
+            .expect("should get committees");
+
+        let mut state = harness
+            .chain
+            .state_at_slot(epoch.start_slot(slots_per_epoch))
+            .expect("should get state");
+        state
+            .build_committee_cache(RelativeEpoch::Current, spec)
+            .expect("should build committee cache");
+
+        for slot in epoch.slot_iter(slots_per_epoch) {
+            assert_eq!(
+                committees
+                    .get_beacon_committees_at_slot(slot)
+                    .expect("should get committees"),
+                state
+                    .get_beacon_committees_at_slot(slot)
+                    .expect("should get committees"),
+                "epoch {}, slot {}",
+                epoch,
+                slot
+            );
+        }
+    }
+}
+
+#[test]
+fn produces_attestations_for_every_committee() {
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        slots_per_epoch as usize + 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let slot = harness.chain.head_info().slot;
+    let attestations = harness
+        .chain
+        .produce_attestations_at_slot(slot)
+        .expect("should produce attestations");
+
+    let committee_count = harness
+        .chain
+        .head()
+        .beacon_state
+        .get_committee_count_at_slot(slot)
+        .expect("should get committee count");
+    assert_eq!(attestations.len() as u64, committee_count);
+
+    for (index, attestation) in attestations.into_iter().enumerate() {
+        assert_eq!(
+            attestation,
+            harness
+                .chain
+                .produce_attestation(slot, index as u64)
+                .expect("should produce attestation"),
+            "committee {}",
+            index
+        );
+    }
+}
+
+#[test]
+fn proves_finalized_checkpoint_against_head_state_root() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        num_blocks_produced as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    let state_root = head.beacon_state_root;
+
+    let proof = harness
+        .chain
+        .state_proof(
+            &state_root,
+            StateProofTarget::Field(StateField::FinalizedCheckpoint),
+        )
+        .expect("should compute proof")
+        .expect("should find head state");
+
+    assert_eq!(
+        proof.leaf,
+        Hash256::from_slice(&head.beacon_state.finalized_checkpoint.tree_hash_root())
+    );
+    assert!(proof.verify(state_root), "proof should verify");
+
+    assert_eq!(
+        harness
+            .chain
+            .state_proof(&Hash256::zero(), StateProofTarget::Validator(0))
+            .expect("should not error for unknown state"),
+        None
+    );
+}
+
+#[test]
+fn finality_update_verifies() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    assert_eq!(
+        harness
+            .chain
+            .finality_update()
+            .expect("should not error before finalization"),
+        None,
+        "genesis should not produce an update"
+    );
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize * 5,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    let mut update = harness
+        .chain
+        .finality_update()
+        .expect("should produce update")
+        .expect("should have finalized a block");
+
+    assert_eq!(
+        update.attested_header.canonical_root(),
+        head.beacon_block_root
+    );
+    assert_eq!(
+        update.finalized_root(),
+        head.beacon_state.finalized_checkpoint.root
+    );
+    assert!(update.verify(), "update should verify");
+
+    update.finalized_header.slot += 1;
+    assert!(!update.verify(), "altered header should not verify");
+}
+
+#[test]
+fn includes_slashings_in_blocks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    harness.process_attester_slashing(harness.make_attester_slashing(&[1, 2]));
+    harness.process_proposer_slashing(harness.make_proposer_slashing(3));
+
+    harness.advance_slot();
+    harness.extend_chain(
+        1,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    assert_eq!(head.beacon_block.body.attester_slashings.len(), 1);
+    assert_eq!(head.beacon_block.body.proposer_slashings.len(), 1);
+
+    for (i, validator) in head.beacon_state.validators.iter().enumerate() {
+        assert_eq!(
+            validator.slashed,
+            [1, 2, 3].contains(&i),
+            "validator {} should be slashed iff included in a slashing",
+            i
+        );
+    }
+}
+
+#[test]
+fn refuses_to_observe_conflicting_local_blocks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.advance_slot();
+    let slot = harness.chain.slot().expect("should get slot");
+    let (block, _state) = harness.make_block(harness.chain.head().beacon_state, slot);
+
+    let mut conflicting_block = block.clone();
+    conflicting_block.body.graffiti = [42; 32];
+
+    assert_eq!(
+        harness.chain.observe_local_block(&block),
+        Ok(ProposalObservation::New)
+    );
+    assert_eq!(
+        harness.chain.observe_local_block(&block),
+        Ok(ProposalObservation::Repeated)
+    );
+    assert_eq!(
+        harness.chain.observe_local_block(&conflicting_block),
+        Ok(ProposalObservation::Equivocation {
+            previous_block_root: block.canonical_root()
+        })
+    );
+}
+
+#[test]
+fn exports_and_imports_checkpoints() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize * 2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let head = harness.chain.head();
+    let bytes = harness
+        .chain
+        .export_checkpoint(None)
+        .expect("should export head")
+        .expect("head should exist")
+        .as_snappy_ssz_bytes()
+        .expect("should encode checkpoint");
+
+    let other_harness = get_harness(VALIDATOR_COUNT);
+    let checkpoint = CheckPoint::from_snappy_ssz_bytes(&bytes).expect("should decode checkpoint");
+    assert_eq!(checkpoint.beacon_block, head.beacon_block);
+    assert_eq!(
+        checkpoint.beacon_state.tree_hash_root(),
+        head.beacon_state.tree_hash_root()
+    );
+    other_harness
+        .chain
+        .import_checkpoint(checkpoint.clone())
+        .expect("should import checkpoint");
+
+    let imported = other_harness
+        .chain
+        .export_checkpoint(Some(head.beacon_block_root))
+        .expect("should export imported block")
+        .expect("imported block should exist");
+    assert_eq!(imported.beacon_block, head.beacon_block);
+    assert_eq!(imported.beacon_state_root, head.beacon_state_root);
+
+    let mut invalid_checkpoint = checkpoint;
+    invalid_checkpoint.beacon_state.slot += 1;
+    match other_harness.chain.import_checkpoint(invalid_checkpoint) {
+        Err(BeaconChainError::InvalidCheckPoint(_)) => (),
+        other => panic!("should reject inconsistent checkpoint, got {:?}", other),
+    }
+
+    assert_eq!(
+        harness.chain.export_checkpoint(Some(Hash256::zero())),
+        Ok(None)
+    );
+}
+
+#[test]
+fn imports_blocks_on_competing_forks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        MinimalEthSpec::slots_per_epoch() as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    let fork_state = harness.chain.head().beacon_state;
+    let fork_slot = fork_state.slot;
+
+    let (root_a, _) = harness.add_block_at_slot(fork_slot + 1, fork_state.clone());
+    let (root_b, state_b) = harness.add_block_at_slot(fork_slot + 2, fork_state);
+
+    assert_ne!(root_a, root_b, "forks should be distinct");
+    assert_eq!(
+        state_b.get_block_root(fork_slot + 1),
+        Ok(&state_b.latest_block_header.parent_root),
+        "the second fork should skip the slot of the first"
+    );
+
+    let heads = harness.chain.heads();
+    assert!(heads.iter().any(|(root, _)| *root == root_a));
+    assert!(heads.iter().any(|(root, _)| *root == root_b));
+}
+
 #[test]
 fn finalizes_with_two_thirds_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -357,6 +975,10 @@ fn free_attestations_added_to_fork_choice_some_none() {
         AttestationStrategy::AllValidators,
     );
 
+    // Attestations are only applied to fork choice once their slot has passed.
+    harness.advance_slot();
+    harness.chain.fork_choice().expect("should find head");
+
     let state = &harness.chain.head().beacon_state;
     let fork_choice = &harness.chain.fork_choice;
 
@@ -409,7 +1031,7 @@ fn attestations_with_increasing_slots() {
             AttestationStrategy::SomeValidators(vec![]),
         );
 
-        attestations.append(&mut harness.get_free_attestations(
+        attestations.append(&mut harness.make_attestations(
             &AttestationStrategy::AllValidators,
             &harness.chain.head().beacon_state,
             harness.chain.head().beacon_block_root,
@@ -427,6 +1049,55 @@ fn attestations_with_increasing_slots() {
     }
 }
 
+#[test]
+fn attestations_applied_to_fork_choice_after_their_slot() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness.extend_chain(
+        1,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::SomeValidators(vec![]),
+    );
+
+    let head = harness.chain.head();
+    let attestations = harness.make_attestations(
+        &AttestationStrategy::AllValidators,
+        &head.beacon_state,
+        head.beacon_block_root,
+        head.beacon_block.slot,
+    );
+    assert!(!attestations.is_empty(), "should make attestations");
+
+    for attestation in attestations {
+        assert_eq!(
+            harness.chain.process_attestation(attestation),
+            Ok(AttestationProcessingOutcome::Processed)
+        );
+    }
+
+    let fork_choice = &harness.chain.fork_choice;
+    let latest_messages = || {
+        (0..VALIDATOR_COUNT)
+            .filter_map(|validator| fork_choice.latest_message(validator))
+            .collect::<Vec<_>>()
+    };
+
+    // The attestations are queued until the end of their slot.
+    harness.chain.fork_choice().expect("should find head");
+    assert!(fork_choice.queued_attestations_count() > 0);
+    assert_eq!(latest_messages(), vec![]);
+
+    harness.advance_slot();
+    harness.chain.fork_choice().expect("should find head");
+    assert_eq!(fork_choice.queued_attestations_count(), 0);
+
+    let latest_messages = latest_messages();
+    assert!(!latest_messages.is_empty(), "should apply the attestations");
+    assert!(latest_messages
+        .iter()
+        .all(|message| *message == (head.beacon_block_root, head.beacon_block.slot)));
+}
+
 #[test]
 fn free_attestations_added_to_fork_choice_all_updated() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 2 - 1;
@@ -439,6 +1110,10 @@ fn free_attestations_added_to_fork_choice_all_updated() {
         AttestationStrategy::AllValidators,
     );
 
+    // Attestations are only applied to fork choice once their slot has passed.
+    harness.advance_slot();
+    harness.chain.fork_choice().expect("should find head");
+
     let state = &harness.chain.head().beacon_state;
     let fork_choice = &harness.chain.fork_choice;
 
@@ -1,5 +1,12 @@
 use crate::config::{ClientGenesis, Config as ClientConfig};
+use crate::deposit_tree_service::spawn_deposit_tree_service;
+use crate::finality_update_service::spawn_finality_update_service;
+use crate::fork_choice_service::spawn_fork_choice_service;
+use crate::monitoring_service::spawn_monitoring_service;
+use crate::next_epoch_service::spawn_next_epoch_service;
 use crate::notifier::spawn_notifier;
+use crate::slasher_service::spawn_slasher_service;
+use crate::startup_report::{log_startup_report, startup_report};
 use crate::Client;
 use beacon_chain::{
     builder::{BeaconChainBuilder, Witness},
@@ -8,33 +15,38 @@ use beacon_chain::{
     slot_clock::{SlotClock, SystemTimeSlotClock},
     store::{
         migrate::{BackgroundMigrator, Migrate, NullMigrator},
-        DiskStore, MemoryStore, SimpleDiskStore, Store,
+        DiskStore, MemoryStore, SimpleDiskStore, Store, StoreConfig,
     },
-    BeaconChain, BeaconChainTypes, Eth1ChainBackend, EventHandler,
+    BeaconChain, BeaconChainTypes, BlockPackingDelay, Eth1ChainBackend, EventBus, EventHandler,
 };
 use environment::RuntimeContext;
-use eth1::{Config as Eth1Config, Service as Eth1Service};
+use eth1::{Config as Eth1Config, Error as Eth1Error, Service as Eth1Service};
 use eth2_config::Eth2Config;
 use exit_future::Signal;
-use futures::{future, Future, IntoFuture};
+use futures::{future, future::Either, Future, IntoFuture, Stream};
 use genesis::{
-    generate_deterministic_keypairs, interop_genesis_state, state_from_ssz_file, Eth1GenesisService,
+    generate_deterministic_keypairs, interop_genesis_state, start_ceremony_server,
+    state_from_ssz_file, wait_for_quorum, CandidateGenesis, CeremonyConfig, Eth1GenesisService,
 };
 use lighthouse_bootstrap::Bootstrapper;
 use lmd_ghost::LmdGhost;
 use network::{NetworkConfig, NetworkMessage, Service as NetworkService};
-use slog::info;
+use rest_api::StartupReport;
+use slasher::Slasher;
+use slog::{debug, info, warn};
 use ssz::Decode;
 use std::net::SocketAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc::UnboundedSender;
-use types::{BeaconState, ChainSpec, EthSpec};
+use types::{BeaconState, ChainSpec, EthSpec, PublicKeyBytes};
 use websocket_server::{Config as WebSocketConfig, WebSocketSender};
 
 /// Interval between polling the eth1 node for genesis information.
 pub const ETH1_GENESIS_UPDATE_INTERVAL_MILLIS: u64 = 7_000;
+/// Interval between polling the peers of a genesis ceremony for their genesis state.
+pub const GENESIS_CEREMONY_POLL_INTERVAL_MILLIS: u64 = 7_000;
 
 /// Builds a `Client` instance.
 ///
@@ -58,12 +70,16 @@ pub struct ClientBuilder<T: BeaconChainTypes> {
     beacon_chain_builder: Option<BeaconChainBuilder<T>>,
     beacon_chain: Option<Arc<BeaconChain<T>>>,
     eth1_service: Option<Eth1Service>,
+    genesis_ceremony: Option<CeremonyConfig>,
     exit_signals: Vec<Signal>,
     event_handler: Option<T::EventHandler>,
     libp2p_network: Option<Arc<NetworkService<T>>>,
     libp2p_network_send: Option<UnboundedSender<NetworkMessage>>,
+    slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    startup_report: Option<StartupReport>,
     http_listen_addr: Option<SocketAddr>,
     websocket_listen_addr: Option<SocketAddr>,
+    http_metrics_listen_addr: Option<SocketAddr>,
     eth_spec_instance: T::EthSpec,
 }
 
@@ -101,12 +117,16 @@ where
             beacon_chain_builder: None,
             beacon_chain: None,
             eth1_service: None,
+            genesis_ceremony: None,
             exit_signals: vec![],
             event_handler: None,
             libp2p_network: None,
             libp2p_network_send: None,
+            slasher: None,
+            startup_report: None,
             http_listen_addr: None,
             websocket_listen_addr: None,
+            http_metrics_listen_addr: None,
             eth_spec_instance,
         }
     }
@@ -123,6 +143,66 @@ where
         self
     }
 
+    /// Checks that the eth1 node of `config` is on the eth1 chain and network given by its
+    /// `chain_id` and `network_id`, so that a node pointed at another eth1 network (e.g., mainnet
+    /// rather than Goerli) fails to start instead of following the wrong deposit contract.
+    ///
+    /// An eth1 node which cannot be reached is not an error here; the eth1 service will not import
+    /// anything from it until it has been verified.
+    pub fn verify_eth1_network(
+        self,
+        config: Eth1Config,
+    ) -> impl Future<Item = Self, Error = String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .map(|context| context.service_context("eth1_rpc".into()));
+
+        context
+            .ok_or_else(|| "verify_eth1_network requires a runtime_context".to_string())
+            .into_future()
+            .and_then(move |context| {
+                let service = Eth1Service::new(
+                    config,
+                    context.log.clone(),
+                    context.eth2_config().spec.clone(),
+                );
+
+                service.verify_network().then(move |result| match result {
+                    Ok(()) => Ok(()),
+                    Err(Eth1Error::IncorrectChainId { expected, found }) => Err(format!(
+                        "The eth1 node has chain id {}, but the testnet expects {}. Ensure the \
+                         eth1 node is on the network of the deposit contract.",
+                        found, expected
+                    )),
+                    Err(Eth1Error::IncorrectNetworkId { expected, found }) => Err(format!(
+                        "The eth1 node has network id {}, but the testnet expects {}. Ensure the \
+                         eth1 node is on the network of the deposit contract.",
+                        found, expected
+                    )),
+                    Err(e) => {
+                        warn!(
+                            context.log,
+                            "Unable to verify eth1 network";
+                            "error" => format!("{:?}", e),
+                        );
+                        Ok(())
+                    }
+                })
+            })
+            .map(move |()| self)
+    }
+
+    /// Takes part in a genesis ceremony with other nodes when the genesis state is computed from
+    /// the deposit contract, waiting for a quorum of them to compute the same state before the
+    /// chain is started.
+    ///
+    /// Must be called before `beacon_chain_builder`.
+    pub fn genesis_ceremony(mut self, config: CeremonyConfig) -> Self {
+        self.genesis_ceremony = Some(config);
+        self
+    }
+
     /// Initializes the `BeaconChainBuilder`. The `build_beacon_chain` method will need to be
     /// called later in order to actually instantiate the `BeaconChain`.
     pub fn beacon_chain_builder(
@@ -135,6 +215,7 @@ where
         let chain_spec = self.chain_spec.clone();
         let runtime_context = self.runtime_context.clone();
         let eth_spec_instance = self.eth_spec_instance.clone();
+        let genesis_ceremony = self.genesis_ceremony.clone();
 
         future::ok(())
             .and_then(move |()| {
@@ -170,7 +251,7 @@ where
                             let future = result
                                 .and_then(move |genesis_state| builder.genesis_state(genesis_state))
                                 .into_future()
-                                .map(|v| (v, None));
+                                .map(|v| (v, None, None));
 
                             Box::new(future)
                         }
@@ -180,7 +261,7 @@ where
                             let future = result
                                 .and_then(move |genesis_state| builder.genesis_state(genesis_state))
                                 .into_future()
-                                .map(|v| (v, None));
+                                .map(|v| (v, None, None));
 
                             Box::new(future)
                         }
@@ -198,7 +279,7 @@ where
                             let future = result
                                 .and_then(move |genesis_state| builder.genesis_state(genesis_state))
                                 .into_future()
-                                .map(|v| (v, None));
+                                .map(|v| (v, None, None));
 
                             Box::new(future)
                         }
@@ -209,39 +290,69 @@ where
                                 "eth1_node" => &config.endpoint
                             );
 
-                            let genesis_service =
-                                Eth1GenesisService::new(config, context.log.clone());
+                            let genesis_service = Eth1GenesisService::new(
+                                config,
+                                context.log.clone(),
+                                context.eth2_config().spec.clone(),
+                            );
 
                             let future = genesis_service
                                 .wait_for_genesis_state(
                                     Duration::from_millis(ETH1_GENESIS_UPDATE_INTERVAL_MILLIS),
                                     context.eth2_config().spec.clone(),
                                 )
-                                .and_then(move |genesis_state| builder.genesis_state(genesis_state))
-                                .map(|v| (v, Some(genesis_service.into_core_service())));
+                                .and_then(move |genesis_state| {
+                                    let ceremony_future = match genesis_ceremony {
+                                        Some(config) => Either::A(genesis_ceremony_quorum(
+                                            &context,
+                                            &config,
+                                            &genesis_state,
+                                        )),
+                                        None => Either::B(future::ok(None)),
+                                    };
+
+                                    ceremony_future.map(|signal| (genesis_state, signal))
+                                })
+                                .and_then(move |(genesis_state, ceremony_signal)| {
+                                    let builder = builder.genesis_state(genesis_state)?;
+                                    Ok((
+                                        builder,
+                                        Some(genesis_service.into_core_service()),
+                                        ceremony_signal,
+                                    ))
+                                });
 
                             Box::new(future)
                         }
-                        ClientGenesis::RemoteNode { server, .. } => {
+                        ClientGenesis::RemoteNode {
+                            server,
+                            verification,
+                            ..
+                        } => {
+                            let log = context.log.clone();
                             let future = Bootstrapper::connect(server.to_string(), &context.log)
                                 .map_err(|e| {
                                     format!("Failed to initialize bootstrap client: {}", e)
                                 })
                                 .into_future()
-                                .and_then(|bootstrapper| {
-                                    let (genesis_state, _genesis_block) =
-                                        bootstrapper.genesis().map_err(|e| {
+                                .and_then(move |bootstrapper| {
+                                    let (genesis_state, _genesis_block) = bootstrapper
+                                        .verified_genesis(&verification, &log)
+                                        .map_err(|e| {
                                             format!("Failed to bootstrap genesis state: {}", e)
                                         })?;
 
                                     builder.genesis_state(genesis_state)
                                 })
-                                .map(|v| (v, None));
+                                .map(|v| (v, None, None));
 
                             Box::new(future)
                         }
                         ClientGenesis::Resume => {
-                            let future = builder.resume_from_db().into_future().map(|v| (v, None));
+                            let future = builder
+                                .resume_from_db()
+                                .into_future()
+                                .map(|v| (v, None, None));
 
                             Box::new(future)
                         }
@@ -249,11 +360,14 @@ where
 
                 genesis_state_future
             })
-            .map(move |(beacon_chain_builder, eth1_service_option)| {
-                self.eth1_service = eth1_service_option;
-                self.beacon_chain_builder = Some(beacon_chain_builder);
-                self
-            })
+            .map(
+                move |(beacon_chain_builder, eth1_service_option, ceremony_signal)| {
+                    self.eth1_service = eth1_service_option;
+                    self.exit_signals.extend(ceremony_signal);
+                    self.beacon_chain_builder = Some(beacon_chain_builder);
+                    self
+                },
+            )
     }
 
     /// Immediately starts the libp2p networking stack.
@@ -268,9 +382,14 @@ where
             .ok_or_else(|| "libp2p_network requires a runtime_context")?
             .service_context("network".into());
 
-        let (network, network_send) =
-            NetworkService::new(beacon_chain, config, &context.executor, context.log)
-                .map_err(|e| format!("Failed to start libp2p network: {:?}", e))?;
+        let (network, network_send) = NetworkService::new(
+            beacon_chain,
+            config,
+            &context.executor,
+            context.shutdown_sender,
+            context.log,
+        )
+        .map_err(|e| format!("Failed to start libp2p network: {:?}", e))?;
 
         self.libp2p_network = Some(network);
         self.libp2p_network_send = Some(network_send);
@@ -278,6 +397,31 @@ where
         Ok(self)
     }
 
+    /// Logs the effective configuration of the node, which is later served by the HTTP API.
+    ///
+    /// Should be called after `libp2p_network`, so that the report includes the local ENR.
+    pub fn startup_report(
+        mut self,
+        client_config: &ClientConfig,
+        eth2_config: &Eth2Config,
+    ) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "startup_report requires a runtime_context")?;
+
+        let enr = self
+            .libp2p_network
+            .as_ref()
+            .map(|network| network.local_enr());
+        let report = startup_report(client_config, eth2_config, enr.as_ref());
+        log_startup_report(&report, &context.log);
+
+        self.startup_report = Some(report);
+
+        Ok(self)
+    }
+
     /// Immediately starts the beacon node REST API http server.
     pub fn http_server(
         mut self,
@@ -301,6 +445,10 @@ where
             .libp2p_network_send
             .clone()
             .ok_or_else(|| "http_server requires a libp2p network sender")?;
+        let startup_report = self
+            .startup_report
+            .clone()
+            .ok_or_else(|| "http_server requires a startup report")?;
 
         let network_info = rest_api::NetworkInfo {
             network_service: network.clone(),
@@ -319,6 +467,7 @@ where
                 .create_freezer_db_path()
                 .map_err(|_| "unable to read freezer DB dir")?,
             eth2_config.clone(),
+            startup_report,
             context.log,
         )
         .map_err(|e| format!("Failed to start HTTP API: {:?}", e))?;
@@ -329,6 +478,307 @@ where
         Ok(self)
     }
 
+    /// Immediately starts the HTTP server which serves the Prometheus metrics.
+    pub fn http_metrics_server(mut self, client_config: &ClientConfig) -> Result<Self, String> {
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "http_metrics_server requires a beacon chain")?;
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "http_metrics_server requires a runtime_context")?
+            .service_context("http_metrics".into());
+
+        let (exit_signal, listening_addr) = http_metrics::start_server(
+            &client_config.http_metrics,
+            &context.executor,
+            beacon_chain,
+            client_config
+                .create_db_path()
+                .map_err(|_| "unable to read data dir")?,
+            client_config
+                .create_freezer_db_path()
+                .map_err(|_| "unable to read freezer DB dir")?,
+            context.log,
+        )
+        .map_err(|e| format!("Failed to start metrics HTTP server: {:?}", e))?;
+
+        self.exit_signals.push(exit_signal);
+        self.http_metrics_listen_addr = Some(listening_addr);
+
+        Ok(self)
+    }
+
+    /// Opens the slasher database, so that the slasher is given all valid attestations once the
+    /// beacon chain is built.
+    ///
+    /// Must be called before `build_beacon_chain`.
+    pub fn slasher(mut self, client_config: &ClientConfig) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "slasher requires a runtime_context")?
+            .service_context("slasher".into());
+
+        let slasher = Slasher::open(
+            client_config.slasher.clone(),
+            &client_config.create_slasher_db_path()?,
+            context.log,
+        )?;
+
+        self.slasher = Some(Arc::new(slasher));
+
+        Ok(self)
+    }
+
+    /// Records the balances of the validators with `pubkeys` at each epoch.
+    ///
+    /// Must be called after `beacon_chain_builder` and before `build_beacon_chain`.
+    pub fn balance_history(mut self, pubkeys: Vec<PublicKeyBytes>) -> Result<Self, String> {
+        let chain_builder = self
+            .beacon_chain_builder
+            .ok_or_else(|| "balance_history requires a beacon_chain_builder")?;
+
+        self.beacon_chain_builder = Some(chain_builder.balance_history(pubkeys));
+
+        Ok(self)
+    }
+
+    /// Sets how far into its slot a block is packed by the beacon chain.
+    ///
+    /// Must be called after `beacon_chain_builder` and before `build_beacon_chain`.
+    pub fn block_packing_delay(mut self, delay: BlockPackingDelay) -> Result<Self, String> {
+        let chain_builder = self
+            .beacon_chain_builder
+            .ok_or_else(|| "block_packing_delay requires a beacon_chain_builder")?;
+
+        self.beacon_chain_builder = Some(chain_builder.block_packing_delay(delay));
+
+        Ok(self)
+    }
+
+    /// Writes the pre-state and block of each block which fails the state transition to `dir`.
+    ///
+    /// Must be called after `beacon_chain_builder` and before `build_beacon_chain`.
+    pub fn debug_dump_dir(mut self, dir: PathBuf) -> Result<Self, String> {
+        let chain_builder = self
+            .beacon_chain_builder
+            .ok_or_else(|| "debug_dump_dir requires a beacon_chain_builder")?;
+
+        self.beacon_chain_builder = Some(chain_builder.debug_dump_dir(dir));
+
+        Ok(self)
+    }
+
+    /// Logs the time spent in each stage of the state transition for each imported block.
+    ///
+    /// Must be called after `beacon_chain_builder` and before `build_beacon_chain`.
+    pub fn log_block_processing_breakdown(mut self, enabled: bool) -> Result<Self, String> {
+        let chain_builder = self
+            .beacon_chain_builder
+            .ok_or_else(|| "log_block_processing_breakdown requires a beacon_chain_builder")?;
+
+        self.beacon_chain_builder = Some(chain_builder.log_block_processing_breakdown(enabled));
+
+        Ok(self)
+    }
+
+    /// Bounds the total size of the in-memory caches of the beacon chain to `bytes`.
+    ///
+    /// Must be called after `beacon_chain_builder` and before `build_beacon_chain`.
+    pub fn cache_memory_budget(mut self, bytes: usize) -> Result<Self, String> {
+        let chain_builder = self
+            .beacon_chain_builder
+            .ok_or_else(|| "cache_memory_budget requires a beacon_chain_builder")?;
+
+        self.beacon_chain_builder = Some(chain_builder.cache_memory_budget(bytes));
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service that checks the attestations queued in the slasher each
+    /// slot, publishing any resulting slashings.
+    ///
+    /// Does nothing if `slasher` has not been called.
+    pub fn slasher_service(mut self) -> Result<Self, String> {
+        let slasher = if let Some(slasher) = self.slasher.clone() {
+            slasher
+        } else {
+            return Ok(self);
+        };
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "slasher_service requires a runtime_context")?
+            .service_context("slasher".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "slasher_service requires a beacon chain")?;
+        let network_send = self
+            .libp2p_network_send
+            .clone()
+            .ok_or_else(|| "slasher_service requires a libp2p network sender")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "slasher_service requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        let exit_signal = spawn_slasher_service(
+            context,
+            beacon_chain,
+            slasher,
+            network_send,
+            milliseconds_per_slot,
+        )
+        .map_err(|e| format!("Unable to start slasher service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service that runs fork choice at the start of each slot.
+    pub fn fork_choice_service(mut self) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "fork_choice_service requires a runtime_context")?
+            .service_context("fork_choice".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "fork_choice_service requires a beacon chain")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "fork_choice_service requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        let exit_signal = spawn_fork_choice_service(context, beacon_chain, milliseconds_per_slot)
+            .map_err(|e| format!("Unable to start fork choice service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service that precomputes the next epoch towards the end of each
+    /// epoch.
+    pub fn next_epoch_service(mut self) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "next_epoch_service requires a runtime_context")?
+            .service_context("next_epoch".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "next_epoch_service requires a beacon chain")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "next_epoch_service requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        let exit_signal = spawn_next_epoch_service(context, beacon_chain, milliseconds_per_slot)
+            .map_err(|e| format!("Unable to start next epoch service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service that checks the deposit tree against the finalized eth1
+    /// data once per epoch.
+    pub fn deposit_tree_service(mut self) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "deposit_tree_service requires a runtime_context")?
+            .service_context("deposit_tree".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "deposit_tree_service requires a beacon chain")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "deposit_tree_service requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        let exit_signal = spawn_deposit_tree_service(context, beacon_chain, milliseconds_per_slot)
+            .map_err(|e| format!("Unable to start deposit tree service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service that publishes a finality update to the event handler at
+    /// each finalization.
+    pub fn finality_update_service(mut self) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "finality_update_service requires a runtime_context")?
+            .service_context("finality_update".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "finality_update_service requires a beacon chain")?;
+        let milliseconds_per_slot = self
+            .chain_spec
+            .as_ref()
+            .ok_or_else(|| "finality_update_service requires a chain spec".to_string())?
+            .milliseconds_per_slot;
+
+        let exit_signal =
+            spawn_finality_update_service(context, beacon_chain, milliseconds_per_slot)
+                .map_err(|e| format!("Unable to start finality update service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
+    /// Immediately starts the service which periodically sends a summary of the state of the node
+    /// to the `monitoring_endpoint` of the `client_config`.
+    pub fn monitoring_service(mut self, client_config: &ClientConfig) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "monitoring_service requires a runtime_context")?
+            .service_context("monitoring".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "monitoring_service requires a beacon chain")?;
+        let network = self
+            .libp2p_network
+            .clone()
+            .ok_or_else(|| "monitoring_service requires a libp2p network")?;
+        let endpoint = client_config
+            .monitoring_endpoint
+            .as_ref()
+            .ok_or_else(|| "monitoring_service requires a monitoring endpoint")?;
+
+        let exit_signal = spawn_monitoring_service(
+            context,
+            beacon_chain,
+            network,
+            endpoint,
+            client_config.network.client_version.clone(),
+        )
+        .map_err(|e| format!("Unable to start monitoring service: {}", e))?;
+
+        self.exit_signals.push(exit_signal);
+
+        Ok(self)
+    }
+
     /// Immediately starts the service that periodically logs information each slot.
     pub fn notifier(mut self) -> Result<Self, String> {
         let context = self
@@ -380,6 +830,7 @@ where
             libp2p_network: self.libp2p_network,
             http_listen_addr: self.http_listen_addr,
             websocket_listen_addr: self.websocket_listen_addr,
+            http_metrics_listen_addr: self.http_metrics_listen_addr,
             _exit_signals: self.exit_signals,
         }
     }
@@ -405,11 +856,31 @@ where
     TEthSpec: EthSpec + 'static,
     TEventHandler: EventHandler<TEthSpec> + 'static,
 {
+    /// Checks the configuration of the internal `BeaconChainBuilder` against the store (see
+    /// `BeaconChainBuilder::validate`), without building the `BeaconChain`.
+    pub fn validate_beacon_chain(self) -> Result<Self, String> {
+        self.beacon_chain_builder
+            .as_ref()
+            .ok_or_else(|| "validate_beacon_chain requires a beacon_chain_builder")?
+            .validate()
+            .map_err(|e| format!("Invalid beacon chain configuration: {}", e))?;
+
+        Ok(self)
+    }
+
     /// Consumes the internal `BeaconChainBuilder`, attaching the resulting `BeaconChain` to self.
     pub fn build_beacon_chain(mut self) -> Result<Self, String> {
-        let chain = self
+        self = self.validate_beacon_chain()?;
+
+        let mut chain_builder = self
             .beacon_chain_builder
-            .ok_or_else(|| "beacon_chain requires a beacon_chain_builder")?
+            .ok_or_else(|| "beacon_chain requires a beacon_chain_builder")?;
+
+        if let Some(slasher) = self.slasher.clone() {
+            chain_builder = chain_builder.slasher(slasher);
+        }
+
+        let chain = chain_builder
             .event_handler(
                 self.event_handler
                     .ok_or_else(|| "beacon_chain requires an event handler")?,
@@ -441,7 +912,7 @@ impl<TStore, TStoreMigrator, TSlotClock, TLmdGhost, TEth1Backend, TEthSpec>
             TLmdGhost,
             TEth1Backend,
             TEthSpec,
-            WebSocketSender<TEthSpec>,
+            EventBus<TEthSpec>,
         >,
     >
 where
@@ -452,31 +923,53 @@ where
     TEth1Backend: Eth1ChainBackend<TEthSpec> + 'static,
     TEthSpec: EthSpec + 'static,
 {
-    /// Specifies that the `BeaconChain` should publish events using the WebSocket server.
-    pub fn websocket_event_handler(mut self, config: WebSocketConfig) -> Result<Self, String> {
+    /// Specifies that the `BeaconChain` should publish its events to an `EventBus`, to which the
+    /// services of the client may subscribe.
+    ///
+    /// Must be called before `build_beacon_chain`.
+    pub fn event_bus(mut self) -> Result<Self, String> {
+        self.event_handler = Some(EventBus::default());
+
+        Ok(self)
+    }
+
+    /// Immediately starts the WebSocket server, if it is enabled, which publishes the events of the
+    /// `BeaconChain` to its clients.
+    ///
+    /// Must be called after `build_beacon_chain`.
+    pub fn websocket_server(mut self, config: WebSocketConfig) -> Result<Self, String> {
+        if !config.enabled {
+            return Ok(self);
+        }
+
         let context = self
             .runtime_context
             .as_ref()
-            .ok_or_else(|| "websocket_event_handler requires a runtime_context")?
+            .ok_or_else(|| "websocket_server requires a runtime_context")?
             .service_context("ws".into());
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or_else(|| "websocket_server requires a beacon chain")?;
 
-        let (sender, exit_signal, listening_addr): (
-            WebSocketSender<TEthSpec>,
-            Option<_>,
-            Option<_>,
-        ) = if config.enabled {
-            let (sender, exit, listening_addr) =
-                websocket_server::start_server(&config, &context.executor, &context.log)?;
-            (sender, Some(exit), Some(listening_addr))
-        } else {
-            (WebSocketSender::dummy(), None, None)
-        };
+        let (sender, exit_signal, listening_addr): (WebSocketSender<TEthSpec>, _, _) =
+            websocket_server::start_server(&config, &context.executor, &context.log)?;
 
-        if let Some(signal) = exit_signal {
-            self.exit_signals.push(signal);
-        }
-        self.event_handler = Some(sender);
-        self.websocket_listen_addr = listening_addr;
+        let log = context.log.clone();
+        let forward_future = beacon_chain
+            .event_handler
+            .subscribe()
+            .for_each(move |event| {
+                if let Err(e) = sender.send_json(&*event) {
+                    debug!(log, "Unable to publish event to websocket clients"; "error" => e);
+                }
+
+                Ok(())
+            });
+        context.executor.spawn(forward_future, "websocket_events");
+
+        self.exit_signals.push(exit_signal);
+        self.websocket_listen_addr = Some(listening_addr);
 
         Ok(self)
     }
@@ -507,7 +1000,7 @@ where
         mut self,
         hot_path: &Path,
         cold_path: &Path,
-        slots_per_restore_point: u64,
+        config: &StoreConfig,
     ) -> Result<Self, String> {
         let context = self
             .runtime_context
@@ -519,14 +1012,8 @@ where
             .clone()
             .ok_or_else(|| "disk_store requires a chain spec".to_string())?;
 
-        let store = DiskStore::open(
-            hot_path,
-            cold_path,
-            slots_per_restore_point,
-            spec,
-            context.log,
-        )
-        .map_err(|e| format!("Unable to open database: {:?}", e).to_string())?;
+        let store = DiskStore::open(hot_path, cold_path, config, spec, context.log)
+            .map_err(|e| format!("Unable to open database: {:?}", e).to_string())?;
         self.store = Some(Arc::new(store));
         Ok(self)
     }
@@ -671,7 +1158,8 @@ where
 
             CachingEth1Backend::from_service(eth1_service_from_genesis, store)
         } else {
-            CachingEth1Backend::new(config, context.log, store)
+            let spec = context.eth2_config().spec.clone();
+            CachingEth1Backend::new(config, context.log, store, spec)
         };
 
         self.eth1_service = None;
@@ -683,7 +1171,7 @@ where
         };
 
         // Starts the service that connects to an eth1 node and periodically updates caches.
-        context.executor.spawn(backend.start(exit));
+        context.executor.spawn(backend.start(exit), "eth1_service");
 
         self.beacon_chain_builder = Some(beacon_chain_builder.eth1_backend(Some(backend)));
 
@@ -760,13 +1248,50 @@ where
             .clone()
             .ok_or_else(|| "system_time_slot_clock requires a chain spec".to_string())?;
 
+        let time_source = self
+            .runtime_context
+            .as_ref()
+            .ok_or_else(|| "system_time_slot_clock requires a runtime_context")?
+            .time_source
+            .clone();
+
         let slot_clock = SystemTimeSlotClock::new(
             spec.genesis_slot,
             Duration::from_secs(genesis_time),
             Duration::from_millis(spec.milliseconds_per_slot),
-        );
+        )
+        .with_time_source(time_source);
 
         self.slot_clock = Some(slot_clock);
         Ok(self)
     }
 }
+
+/// Serves the root of `genesis_state` to the peers of the genesis ceremony and waits until a
+/// quorum of them have computed the same state, returning a `Signal` which stops the server.
+fn genesis_ceremony_quorum<E: EthSpec>(
+    context: &RuntimeContext<E>,
+    config: &CeremonyConfig,
+    genesis_state: &BeaconState<E>,
+) -> impl Future<Item = Option<Signal>, Error = String> {
+    let context = context.service_context("genesis_ceremony".into());
+    let candidate = CandidateGenesis::from_state(genesis_state);
+    let config = config.clone();
+
+    start_ceremony_server(
+        &config,
+        candidate.clone(),
+        &context.executor,
+        context.log.clone(),
+    )
+    .into_future()
+    .and_then(move |(exit_signal, _listen_addr)| {
+        wait_for_quorum(
+            &config,
+            candidate,
+            Duration::from_millis(GENESIS_CEREMONY_POLL_INTERVAL_MILLIS),
+            context.log.clone(),
+        )
+        .map(|()| Some(exit_signal))
+    })
+}
@@ -1,7 +1,9 @@
+use beacon_chain::BlockPackingDelay;
+use lighthouse_bootstrap::StateVerification;
 use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
+use types::PublicKeyBytes;
 
 /// The number initial validators when starting the `Minimal`.
 const TESTNET_SPEC_CONSTANTS: &str = "minimal";
@@ -28,7 +30,28 @@ pub enum ClientGenesis {
     SszBytes { genesis_state_bytes: Vec<u8> },
     /// Connects to another Lighthouse instance and reads the genesis state and other data via the
     /// HTTP API.
-    RemoteNode { server: String, port: Option<u16> },
+    ///
+    /// The server is not trusted: the state is refused unless it passes `verification`.
+    RemoteNode {
+        server: String,
+        port: Option<u16>,
+        #[serde(default)]
+        verification: StateVerification,
+    },
+}
+
+impl ClientGenesis {
+    /// Returns the name of the method by which the genesis state is obtained, e.g. for logging.
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            ClientGenesis::Resume => "resume",
+            ClientGenesis::Interop { .. } => "interop",
+            ClientGenesis::DepositContract => "deposit_contract",
+            ClientGenesis::SszFile { .. } => "ssz_file",
+            ClientGenesis::SszBytes { .. } => "ssz_bytes",
+            ClientGenesis::RemoteNode { .. } => "remote_node",
+        }
+    }
 }
 
 impl Default for ClientGenesis {
@@ -57,13 +80,40 @@ pub struct Config {
     pub network: network::NetworkConfig,
     pub rest_api: rest_api::Config,
     pub websocket_server: websocket_server::Config,
+    pub http_metrics: http_metrics::Config,
+    pub slasher: slasher::Config,
+    /// The public keys of the validators whose balances are recorded at each epoch.
+    pub balance_history: Vec<PublicKeyBytes>,
+    /// Determines how far into its slot a block is packed, to include late aggregates.
+    pub block_packing_delay: BlockPackingDelay,
+    /// If set, the blocks which fail the state transition are written to this directory with
+    /// their pre-states.
+    #[serde(default)]
+    pub debug_dump_dir: Option<PathBuf>,
+    /// If true, the time spent in each stage of the state transition is logged for each block.
+    #[serde(default)]
+    pub log_block_processing_breakdown: bool,
+    /// If set, the budget for the total size of the in-memory caches of the beacon chain, in
+    /// bytes.
+    #[serde(default)]
+    pub cache_memory_budget: Option<usize>,
+    /// If true, a finality update is published to the websocket server at each finalization.
+    #[serde(default)]
+    pub finality_updates: bool,
+    /// If set, a summary of the state of the node is periodically sent to this URL.
+    #[serde(default)]
+    pub monitoring_endpoint: Option<String>,
+    /// If set, the node takes part in a genesis ceremony when computing the genesis state from the
+    /// deposit contract.
+    #[serde(default)]
+    pub genesis_ceremony: Option<genesis::CeremonyConfig>,
     pub eth1: eth1::Config,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            data_dir: PathBuf::from(".lighthouse"),
+            data_dir: lighthouse_dirs::default_dir(lighthouse_dirs::BEACON_DIR),
             testnet_dir: None,
             log_file: PathBuf::from(""),
             genesis: <_>::default(),
@@ -71,6 +121,16 @@ impl Default for Config {
             network: NetworkConfig::default(),
             rest_api: <_>::default(),
             websocket_server: <_>::default(),
+            http_metrics: <_>::default(),
+            slasher: <_>::default(),
+            balance_history: vec![],
+            block_packing_delay: <_>::default(),
+            debug_dump_dir: None,
+            log_block_processing_breakdown: false,
+            cache_memory_budget: None,
+            finality_updates: false,
+            monitoring_endpoint: None,
+            genesis_ceremony: None,
             spec_constants: TESTNET_SPEC_CONSTANTS.into(),
             dummy_eth1_backend: false,
             sync_eth1_chain: false,
@@ -119,6 +179,20 @@ impl Config {
         ensure_dir_exists(freezer_db_path)
     }
 
+    /// Get the slasher database path without initialising it.
+    pub fn get_slasher_db_path(&self) -> Option<PathBuf> {
+        self.get_data_dir()
+            .map(|data_dir| data_dir.join(&self.slasher.db_name))
+    }
+
+    /// Get the slasher database path, creating it if necessary.
+    pub fn create_slasher_db_path(&self) -> Result<PathBuf, String> {
+        let db_path = self
+            .get_slasher_db_path()
+            .ok_or_else(|| "Unable to locate user home directory")?;
+        ensure_dir_exists(db_path)
+    }
+
     /// Returns the core path for the client.
     ///
     /// Will not create any directories.
@@ -139,7 +213,7 @@ impl Config {
 
 /// Ensure that the directory at `path` exists, by creating it and all parents if necessary.
 fn ensure_dir_exists(path: PathBuf) -> Result<PathBuf, String> {
-    fs::create_dir_all(&path).map_err(|e| format!("Unable to create {}: {}", path.display(), e))?;
+    lighthouse_dirs::create_dir_restricted(&path)?;
     Ok(path)
 }
 
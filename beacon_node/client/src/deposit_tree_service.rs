@@ -0,0 +1,68 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use slog::error;
+use slot_clock::SlotClock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::EthSpec;
+
+/// Spawns a service which, once per epoch, compares the local deposit tree against the
+/// `Eth1Data` of the finalized state.
+///
+/// A divergence means that the eth1 caches are corrupt (or the eth1 node is following another
+/// chain), and would cause the node to produce invalid blocks. It is logged prominently and block
+/// production is refused until a later check finds the tree consistent again.
+pub fn spawn_deposit_tree_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    milliseconds_per_slot: u64,
+) -> Result<Signal, String> {
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+    let executor = context.executor.clone();
+
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let epoch_duration = slot_duration * T::EthSpec::slots_per_epoch() as u32;
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "deposit tree service unable to determine time to next slot")?;
+
+    let start_instant = Instant::now() + duration_to_next_slot + slot_duration / 2;
+
+    let interval_future = Interval::new(start_instant, epoch_duration)
+        .map_err(move |e| error!(log_1, "Deposit tree timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let log = log_2.clone();
+            let beacon_chain = beacon_chain.clone();
+
+            executor
+                .spawn_blocking(
+                    move || beacon_chain.check_deposit_tree(),
+                    "check_deposit_tree",
+                )
+                .then(move |result| {
+                    match result {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => error!(
+                            log,
+                            "Failed to check deposit tree";
+                            "error" => format!("{:?}", e)
+                        ),
+                        Err(e) => error!(log, "Failed to check deposit tree"; "error" => e),
+                    }
+                    Ok(())
+                })
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context.executor.spawn(
+        exit.until(interval_future).map(|_| ()),
+        "deposit_tree_service",
+    );
+
+    Ok(exit_signal)
+}
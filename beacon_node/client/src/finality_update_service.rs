@@ -0,0 +1,87 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use slog::error;
+use slot_clock::SlotClock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+
+/// Spawns a service which, halfway through each slot, publishes a finality update to the event
+/// handler if the finalized checkpoint of the head has changed since the previous update.
+///
+/// Computing the update requires a Merkle proof against the head state, so it is done here rather
+/// than whilst importing the block which caused finalization.
+pub fn spawn_finality_update_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    milliseconds_per_slot: u64,
+) -> Result<Signal, String> {
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+    let executor = context.executor.clone();
+
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "finality update service unable to determine time to next slot")?;
+
+    let start_instant = Instant::now() + duration_to_next_slot + slot_duration / 2;
+
+    // Only finalizations which happen whilst the service is running are published.
+    let mut published_epoch = beacon_chain.head_info().finalized_checkpoint.epoch;
+
+    let interval_future = Interval::new(start_instant, slot_duration)
+        .map_err(
+            move |e| error!(log_1, "Finality update timer failed"; "error" => format!("{:?}", e)),
+        )
+        .for_each(move |_| {
+            let log = log_2.clone();
+            let beacon_chain = beacon_chain.clone();
+
+            let finalized_epoch = beacon_chain.head_info().finalized_checkpoint.epoch;
+
+            let future: Box<dyn Future<Item = (), Error = ()> + Send> =
+                if finalized_epoch > published_epoch {
+                    published_epoch = finalized_epoch;
+
+                    Box::new(
+                        executor
+                            .spawn_blocking(
+                                move || beacon_chain.publish_finality_update(),
+                                "publish_finality_update",
+                            )
+                            .then(move |result| {
+                                match result {
+                                    Ok(Ok(())) => {}
+                                    Ok(Err(e)) => error!(
+                                        log,
+                                        "Failed to publish finality update";
+                                        "error" => format!("{:?}", e)
+                                    ),
+                                    Err(e) => error!(
+                                        log,
+                                        "Failed to publish finality update";
+                                        "error" => e
+                                    ),
+                                }
+                                Ok(())
+                            }),
+                    )
+                } else {
+                    Box::new(futures::future::ok(()))
+                };
+
+            future
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context.executor.spawn(
+        exit.until(interval_future).map(|_| ()),
+        "finality_update_service",
+    );
+
+    Ok(exit_signal)
+}
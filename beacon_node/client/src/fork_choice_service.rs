@@ -0,0 +1,57 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use slog::error;
+use slot_clock::SlotClock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+
+/// Spawns a service which runs fork choice at the start of each slot, applying the attestations
+/// from the previous slot and updating the head even if no block is received.
+pub fn spawn_fork_choice_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    milliseconds_per_slot: u64,
+) -> Result<Signal, String> {
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+    let executor = context.executor.clone();
+
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "fork choice service unable to determine time to next slot")?;
+
+    let start_instant = Instant::now() + duration_to_next_slot;
+
+    let interval_future = Interval::new(start_instant, slot_duration)
+        .map_err(move |e| error!(log_1, "Fork choice timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let log = log_2.clone();
+            let beacon_chain = beacon_chain.clone();
+
+            executor
+                .spawn_blocking(move || beacon_chain.fork_choice(), "fork_choice")
+                .then(move |result| {
+                    match result {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            error!(log, "Fork choice failed"; "error" => format!("{:?}", e))
+                        }
+                        Err(e) => error!(log, "Fork choice failed"; "error" => e),
+                    }
+                    Ok(())
+                })
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context.executor.spawn(
+        exit.until(interval_future).map(|_| ()),
+        "fork_choice_service",
+    );
+
+    Ok(exit_signal)
+}
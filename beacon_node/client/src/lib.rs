@@ -1,15 +1,22 @@
 extern crate slog;
 
 mod config;
+mod deposit_tree_service;
+mod finality_update_service;
+mod fork_choice_service;
+mod monitoring_service;
+mod next_epoch_service;
 mod notifier;
+mod slasher_service;
+mod startup_report;
 
 pub mod builder;
 pub mod error;
 
 use beacon_chain::BeaconChain;
-use eth2_libp2p::{Enr, Multiaddr};
+use eth2_libp2p::{Enr, Multiaddr, PeerId};
 use exit_future::Signal;
-use network::Service as NetworkService;
+use network::{FaultInjector, Service as NetworkService};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
@@ -26,6 +33,7 @@ pub struct Client<T: BeaconChainTypes> {
     libp2p_network: Option<Arc<NetworkService<T>>>,
     http_listen_addr: Option<SocketAddr>,
     websocket_listen_addr: Option<SocketAddr>,
+    http_metrics_listen_addr: Option<SocketAddr>,
     /// Exit signals will "fire" when dropped, causing each service to exit gracefully.
     _exit_signals: Vec<Signal>,
 }
@@ -46,6 +54,11 @@ impl<T: BeaconChainTypes> Client<T> {
         self.websocket_listen_addr
     }
 
+    /// Returns the address of the client's metrics HTTP server, if it was started.
+    pub fn http_metrics_listen_addr(&self) -> Option<SocketAddr> {
+        self.http_metrics_listen_addr
+    }
+
     /// Returns the port of the client's libp2p stack, if it was started.
     pub fn libp2p_listen_port(&self) -> Option<u16> {
         self.libp2p_network.as_ref().map(|n| n.listen_port())
@@ -60,12 +73,31 @@ impl<T: BeaconChainTypes> Client<T> {
     pub fn enr(&self) -> Option<Enr> {
         self.libp2p_network.as_ref().map(|n| n.local_enr())
     }
+
+    /// Returns the local libp2p `PeerId` of this node.
+    pub fn libp2p_peer_id(&self) -> Option<PeerId> {
+        self.libp2p_network.as_ref().map(|n| n.local_peer_id())
+    }
+
+    /// Returns the faults applied to the client's libp2p network, for testing purposes.
+    pub fn network_fault_injector(&self) -> Option<Arc<FaultInjector>> {
+        self.libp2p_network.as_ref().map(|n| n.fault_injector())
+    }
 }
 
 impl<T: BeaconChainTypes> Drop for Client<T> {
     fn drop(&mut self) {
+        // Stop receiving gossip and RPC messages from peers, so no new work arrives.
+        if let Some(network) = &self.libp2p_network {
+            network.shutdown();
+        }
+
+        // Wait for in-flight block imports, then persist the chain. Errors are logged by the chain.
         if let Some(beacon_chain) = &self.beacon_chain {
-            let _result = beacon_chain.persist();
+            let _result = beacon_chain.shutdown();
         }
+
+        // The remaining services exit when `_exit_signals` is dropped. The database is closed once
+        // the last reference to the chain is dropped.
     }
 }
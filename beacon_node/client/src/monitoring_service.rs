@@ -0,0 +1,115 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes, ChainHealth};
+use environment::{HostStats, RuntimeContext};
+use exit_future::Signal;
+use futures::{Future, Stream};
+use network::Service as NetworkService;
+use reqwest::r#async::ClientBuilder;
+use serde_derive::Serialize;
+use slog::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::timer::Interval;
+use types::{Epoch, EthSpec, Slot};
+use url::Url;
+
+/// The interval between the summaries sent to the monitoring endpoint.
+pub const MONITORING_INTERVAL: Duration = Duration::from_secs(60);
+/// The timeout of each request to the monitoring endpoint.
+const MONITORING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The summary of the state of the node which is sent to the monitoring endpoint.
+#[derive(Debug, Serialize)]
+pub struct BeaconNodeSummary {
+    /// Always `beacon_node`, to distinguish this summary from those of validator clients.
+    pub process: &'static str,
+    /// The time at which the summary was taken, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub version: String,
+    /// The latest assessment of the sync status of the node, if it has been assessed.
+    pub chain_health: Option<ChainHealth>,
+    pub head_slot: Slot,
+    pub current_slot: Option<Slot>,
+    pub finalized_epoch: Epoch,
+    pub peer_count: usize,
+    pub load_average_1m: Option<f64>,
+    pub memory_total_bytes: Option<u64>,
+    pub memory_available_bytes: Option<u64>,
+    pub process_resident_memory_bytes: Option<u64>,
+}
+
+/// Spawns a service which sends a `BeaconNodeSummary` to `endpoint` with a `POST` request every
+/// `MONITORING_INTERVAL`.
+///
+/// A failed request is logged and not retried; the next summary is sent at the next interval.
+pub fn spawn_monitoring_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network: Arc<NetworkService<T>>,
+    endpoint: &str,
+    version: String,
+) -> Result<Signal, String> {
+    let endpoint = Url::parse(endpoint)
+        .map_err(|e| format!("Invalid monitoring endpoint {}: {}", endpoint, e))?;
+    let http = ClientBuilder::new()
+        .timeout(MONITORING_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+
+    info!(
+        context.log,
+        "Monitoring enabled";
+        "interval_secs" => MONITORING_INTERVAL.as_secs(),
+        "endpoint" => endpoint.as_str(),
+    );
+
+    let interval_future = Interval::new(Instant::now() + MONITORING_INTERVAL, MONITORING_INTERVAL)
+        .map_err(move |e| error!(log_1, "Monitoring timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let head_info = beacon_chain.head_info();
+            let host = HostStats::observe();
+            let summary = BeaconNodeSummary {
+                process: "beacon_node",
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0),
+                version: version.clone(),
+                chain_health: beacon_chain.chain_health(),
+                head_slot: head_info.slot,
+                current_slot: beacon_chain.slot().ok(),
+                finalized_epoch: head_info.finalized_checkpoint.epoch,
+                peer_count: network.connected_peers(),
+                load_average_1m: host.load_average_1m,
+                memory_total_bytes: host.memory_total_bytes,
+                memory_available_bytes: host.memory_available_bytes,
+                process_resident_memory_bytes: host.process_resident_memory_bytes,
+            };
+
+            let log = log_2.clone();
+            http.post(endpoint.clone())
+                .json(&summary)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .then(move |result| {
+                    match result {
+                        Ok(_) => debug!(log, "Sent monitoring summary"),
+                        Err(e) => warn!(
+                            log,
+                            "Unable to send monitoring summary";
+                            "error" => format!("{}", e),
+                        ),
+                    }
+                    Ok(())
+                })
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context
+        .executor
+        .spawn(exit.until(interval_future).map(|_| ()), "monitoring");
+
+    Ok(exit_signal)
+}
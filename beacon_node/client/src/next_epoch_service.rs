@@ -0,0 +1,81 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use slog::error;
+use slot_clock::SlotClock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::EthSpec;
+
+/// Spawns a service which, three quarters of the way through the last slot of each epoch (once
+/// the block and attestations for the slot have been processed), precomputes the committees and
+/// proposers of the next epoch from the head.
+///
+/// Validator clients request their duties for the next epoch at the epoch boundary, so this
+/// avoids an epoch transition for each of their requests.
+pub fn spawn_next_epoch_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    milliseconds_per_slot: u64,
+) -> Result<Signal, String> {
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+    let executor = context.executor.clone();
+
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "next epoch service unable to determine time to next slot")?;
+
+    let start_instant = Instant::now() + duration_to_next_slot + slot_duration * 3 / 4;
+
+    let interval_future = Interval::new(start_instant, slot_duration)
+        .map_err(move |e| error!(log_1, "Next epoch timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let log = log_2.clone();
+            let beacon_chain = beacon_chain.clone();
+
+            let is_last_slot_of_epoch = beacon_chain.slot().ok().map_or(false, |slot| {
+                (slot + 1).as_u64() % T::EthSpec::slots_per_epoch() == 0
+            });
+
+            let future: Box<dyn Future<Item = (), Error = ()> + Send> = if is_last_slot_of_epoch {
+                Box::new(
+                    executor
+                        .spawn_blocking(
+                            move || beacon_chain.precompute_next_epoch(),
+                            "precompute_next_epoch",
+                        )
+                        .then(move |result| {
+                            match result {
+                                Ok(Ok(())) => {}
+                                Ok(Err(e)) => error!(
+                                    log,
+                                    "Failed to precompute next epoch";
+                                    "error" => format!("{:?}", e)
+                                ),
+                                Err(e) => {
+                                    error!(log, "Failed to precompute next epoch"; "error" => e)
+                                }
+                            }
+                            Ok(())
+                        }),
+                )
+            } else {
+                Box::new(futures::future::ok(()))
+            };
+
+            future
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context.executor.spawn(
+        exit.until(interval_future).map(|_| ()),
+        "next_epoch_service",
+    );
+
+    Ok(exit_signal)
+}
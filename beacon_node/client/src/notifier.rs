@@ -1,4 +1,7 @@
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{
+    events::EventKind, BeaconChain, BeaconChainTypes, ChainHealth, ChainHealthObservation,
+    ChainHealthTracker, EventHandler,
+};
 use environment::RuntimeContext;
 use exit_future::Signal;
 use futures::{Future, Stream};
@@ -51,6 +54,8 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
     let interval_duration = slot_duration;
 
     let speedo = Mutex::new(Speedo::default());
+    let health_tracker = Mutex::new(ChainHealthTracker::default());
+    let last_eth1_block_timestamp = Mutex::new(None);
 
     let interval_future = Interval::new(start_instant, interval_duration)
         .map_err(
@@ -69,9 +74,9 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
                 usize::max_value()
             };
 
-            let head = beacon_chain.head();
+            let head_info = beacon_chain.head_info();
 
-            let head_slot = head.beacon_block.slot;
+            let head_slot = head_info.slot;
             let head_epoch = head_slot.epoch(T::EthSpec::slots_per_epoch());
             let current_slot = beacon_chain.slot().map_err(|e| {
                 error!(
@@ -81,9 +86,9 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
                 )
             })?;
             let current_epoch = current_slot.epoch(T::EthSpec::slots_per_epoch());
-            let finalized_epoch = head.beacon_state.finalized_checkpoint.epoch;
-            let finalized_root = head.beacon_state.finalized_checkpoint.root;
-            let head_root = head.beacon_block_root;
+            let finalized_epoch = head_info.finalized_checkpoint.epoch;
+            let finalized_root = head_info.finalized_checkpoint.root;
+            let head_root = head_info.block_root;
 
             let mut speedo = speedo.lock();
             speedo.observe(head_slot, Instant::now());
@@ -91,6 +96,39 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
             // The next two lines take advantage of saturating subtraction on `Slot`.
             let head_distance = current_slot - head_slot;
 
+            let eth1_synced = beacon_chain.is_eth1_synced();
+            let eth1_block_timestamp = beacon_chain.latest_eth1_block_timestamp();
+            let mut last_eth1_block_timestamp = last_eth1_block_timestamp.lock();
+            if eth1_block_timestamp != *last_eth1_block_timestamp {
+                if let Some(latest_block_timestamp) = eth1_block_timestamp {
+                    let _ = beacon_chain.event_handler.register(EventKind::Eth1Updated {
+                        latest_block_timestamp,
+                        synced: eth1_synced,
+                    });
+                }
+                *last_eth1_block_timestamp = eth1_block_timestamp;
+            }
+
+            let chain_health = health_tracker.lock().assess(
+                &ChainHealthObservation {
+                    head_slot,
+                    current_slot,
+                    peer_count: connected_peer_count,
+                    eth1_synced,
+                },
+                T::EthSpec::slots_per_epoch(),
+            );
+            beacon_chain.set_chain_health(chain_health);
+
+            if let ChainHealth::Stalled { distance } = chain_health {
+                warn!(
+                    log,
+                    "Head is not advancing";
+                    "peers" => peer_count_pretty(connected_peer_count),
+                    "distance" => distance,
+                );
+            }
+
             if connected_peer_count <= WARN_PEER_COUNT {
                 warn!(log, "Low peer count"; "peer_count" => peer_count_pretty(connected_peer_count));
             }
@@ -161,7 +199,7 @@ pub fn spawn_notifier<T: BeaconChainTypes>(
     let (exit_signal, exit) = exit_future::signal();
     context
         .executor
-        .spawn(exit.until(interval_future).map(|_| ()));
+        .spawn(exit.until(interval_future).map(|_| ()), "notifier");
 
     Ok(exit_signal)
 }
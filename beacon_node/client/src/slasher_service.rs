@@ -0,0 +1,174 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::RuntimeContext;
+use eth2_libp2p::{
+    PubsubMessage, Topic, ATTESTER_SLASHING_TOPIC, PROPOSER_SLASHING_TOPIC, TOPIC_ENCODING_POSTFIX,
+    TOPIC_PREFIX,
+};
+use exit_future::Signal;
+use futures::{Future, Stream};
+use network::NetworkMessage;
+use slasher::Slasher;
+use slog::{error, info, warn, Logger};
+use slot_clock::SlotClock;
+use ssz::Encode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::timer::Interval;
+use types::{AttesterSlashing, ProposerSlashing};
+
+/// Spawns a service which checks the attestations queued in the `slasher` each slot, adding any
+/// resulting slashings to the op pool and publishing them to the network.
+///
+/// The proposer slashings which the `beacon_chain` constructs from conflicting blocks on gossip
+/// are published alongside them.
+pub fn spawn_slasher_service<T: BeaconChainTypes>(
+    context: RuntimeContext<T::EthSpec>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    slasher: Arc<Slasher<T::EthSpec>>,
+    network_send: UnboundedSender<NetworkMessage>,
+    milliseconds_per_slot: u64,
+) -> Result<Signal, String> {
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+    let executor = context.executor.clone();
+
+    let slot_duration = Duration::from_millis(milliseconds_per_slot);
+    let duration_to_next_slot = beacon_chain
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "slasher unable to determine time to next slot")?;
+
+    // Run this three quarters of the way through each slot, once most of the attestations for the
+    // slot have been received.
+    let start_instant = Instant::now() + duration_to_next_slot + (slot_duration * 3 / 4);
+
+    let interval_future = Interval::new(start_instant, slot_duration)
+        .map_err(move |e| error!(log_1, "Slasher timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let log = log_2.clone();
+            let beacon_chain = beacon_chain.clone();
+            let slasher = slasher.clone();
+            let mut network_send = network_send.clone();
+
+            let process_chain = beacon_chain.clone();
+            executor
+                .spawn_blocking(
+                    move || {
+                        let current_epoch = process_chain
+                            .epoch()
+                            .map_err(|e| format!("Unable to read current epoch: {:?}", e))?;
+                        slasher
+                            .process_queued(current_epoch)
+                            .map_err(|e| format!("Unable to process attestations: {:?}", e))
+                    },
+                    "slasher",
+                )
+                .and_then(|result| result)
+                .then(move |result| {
+                    match result {
+                        Ok(slashings) => {
+                            for slashing in slashings {
+                                handle_slashing(&beacon_chain, &mut network_send, slashing, &log);
+                            }
+                        }
+                        Err(e) => error!(log, "Slasher failed"; "error" => e),
+                    }
+
+                    // These slashings have already been added to the op pool.
+                    for slashing in beacon_chain.take_unpublished_proposer_slashings() {
+                        publish_proposer_slashing(&mut network_send, slashing, &log);
+                    }
+                    Ok(())
+                })
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    context
+        .executor
+        .spawn(exit.until(interval_future).map(|_| ()), "slasher_service");
+
+    Ok(exit_signal)
+}
+
+/// Adds `slashing` to the op pool and, if it is valid, publishes it to the network.
+fn handle_slashing<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    network_send: &mut UnboundedSender<NetworkMessage>,
+    slashing: AttesterSlashing<T::EthSpec>,
+    log: &Logger,
+) {
+    let validator_count = slashing
+        .attestation_1
+        .attesting_indices
+        .iter()
+        .filter(|index| slashing.attestation_2.attesting_indices.contains(*index))
+        .count();
+
+    if let Err(e) = beacon_chain.process_attester_slashing(slashing.clone()) {
+        // The slashing may be invalid if all of the validators have already been slashed.
+        warn!(
+            log,
+            "Slashing rejected by op pool";
+            "validator_count" => validator_count,
+            "error" => format!("{:?}", e),
+        );
+        return;
+    }
+
+    info!(
+        log,
+        "Slasher found attester slashing";
+        "validator_count" => validator_count,
+        "target_epoch_1" => slashing.attestation_1.data.target.epoch,
+        "target_epoch_2" => slashing.attestation_2.data.target.epoch,
+    );
+
+    let topic = Topic::new(format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, ATTESTER_SLASHING_TOPIC, TOPIC_ENCODING_POSTFIX
+    ));
+    let message = PubsubMessage::AttesterSlashing(slashing.as_ssz_bytes());
+
+    if let Err(e) = network_send.try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        error!(
+            log,
+            "Unable to publish attester slashing";
+            "error" => format!("{:?}", e),
+        );
+    }
+}
+
+/// Publishes `slashing`, which has already been added to the op pool, to the network.
+fn publish_proposer_slashing(
+    network_send: &mut UnboundedSender<NetworkMessage>,
+    slashing: ProposerSlashing,
+    log: &Logger,
+) {
+    info!(
+        log,
+        "Publishing proposer slashing";
+        "proposer_index" => slashing.proposer_index,
+        "slot" => slashing.header_1.slot,
+    );
+
+    let topic = Topic::new(format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, PROPOSER_SLASHING_TOPIC, TOPIC_ENCODING_POSTFIX
+    ));
+    let message = PubsubMessage::ProposerSlashing(slashing.as_ssz_bytes());
+
+    if let Err(e) = network_send.try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        error!(
+            log,
+            "Unable to publish proposer slashing";
+            "error" => format!("{:?}", e),
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use std::ops::RangeInclusive;
-use types::{Eth1Data, Hash256};
+use types::{BeaconState, ChainSpec, Eth1Data, EthSpec, Hash256, Unsigned};
+
+/// The expected time between eth1 blocks.
+pub const SECONDS_PER_ETH1_BLOCK: u64 = 14;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
@@ -34,6 +37,39 @@ impl Eth1Block {
             block_hash: self.hash,
         })
     }
+
+    /// Returns `true` if the block may be voted for during the eth1 voting period starting at
+    /// `period_start_seconds`.
+    ///
+    /// A candidate block is at least `ETH1_FOLLOW_DISTANCE` and at most `2 *
+    /// ETH1_FOLLOW_DISTANCE` blocks older than the start of the period, as estimated from its
+    /// timestamp.
+    pub fn is_candidate(&self, period_start_seconds: u64, spec: &ChainSpec) -> bool {
+        candidate_timestamp_bounds(period_start_seconds, spec)
+            .map_or(false, |(earliest, latest)| {
+                earliest <= self.timestamp && self.timestamp <= latest
+            })
+    }
+}
+
+/// Returns the unix-epoch seconds at the start of the eth1 voting period containing `state.slot`.
+pub fn voting_period_start_seconds<E: EthSpec>(state: &BeaconState<E>, spec: &ChainSpec) -> u64 {
+    let period = E::SlotsPerEth1VotingPeriod::to_u64();
+    let period_start_slot = (state.slot / period) * period;
+
+    state.genesis_time + period_start_slot.as_u64() * spec.milliseconds_per_slot / 1_000
+}
+
+/// Returns the inclusive range of timestamps of the candidate blocks for the eth1 voting period
+/// starting at `period_start_seconds`, or `None` if no block can be a candidate (i.e., the period
+/// starts within `ETH1_FOLLOW_DISTANCE` blocks of the unix epoch).
+fn candidate_timestamp_bounds(period_start_seconds: u64, spec: &ChainSpec) -> Option<(u64, u64)> {
+    let follow_seconds = SECONDS_PER_ETH1_BLOCK * spec.eth1_follow_distance;
+
+    Some((
+        period_start_seconds.saturating_sub(2 * follow_seconds),
+        period_start_seconds.checked_sub(follow_seconds)?,
+    ))
 }
 
 /// Stores block and deposit contract information and provides queries based upon the block
@@ -84,6 +120,38 @@ impl BlockCache {
         self.blocks.iter()
     }
 
+    /// Returns an iterator over the blocks which may be voted for during the eth1 voting period
+    /// containing `state.slot` (see `Eth1Block::is_candidate`), ordered by block number.
+    ///
+    /// Blocks are selected by their timestamps, rather than their distance from the eth1 head, so
+    /// that all honest voters consider the same blocks regardless of when they last updated their
+    /// caches.
+    pub fn blocks_in_voting_period<E: EthSpec>(
+        &self,
+        state: &BeaconState<E>,
+        spec: &ChainSpec,
+    ) -> impl DoubleEndedIterator<Item = &Eth1Block> + Clone {
+        let candidates = candidate_timestamp_bounds(voting_period_start_seconds(state, spec), spec)
+            .map(|(earliest, latest)| {
+                // Timestamps are non-decreasing, so the candidates are contiguous.
+                let start = self
+                    .blocks
+                    .iter()
+                    .position(|block| block.timestamp >= earliest)
+                    .unwrap_or_else(|| self.blocks.len());
+                let end = self
+                    .blocks
+                    .iter()
+                    .rposition(|block| block.timestamp <= latest)
+                    .map_or(start, |index| (index + 1).max(start));
+
+                &self.blocks[start..end]
+            })
+            .unwrap_or(&[]);
+
+        candidates.iter()
+    }
+
     /// Shortens the cache, keeping the latest (by block number) `len` blocks while dropping the
     /// rest.
     ///
@@ -180,6 +248,7 @@ impl BlockCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, MinimalEthSpec, Slot};
 
     fn get_block(i: u64, interval_secs: u64) -> Eth1Block {
         Eth1Block {
@@ -199,6 +268,56 @@ mod tests {
         cache.insert_root_or_child(s)
     }
 
+    #[test]
+    fn blocks_in_voting_period() {
+        type E = MinimalEthSpec;
+
+        let mut spec = E::default_spec();
+        spec.eth1_follow_distance = 4;
+        spec.milliseconds_per_slot = 1_000;
+        let follow_seconds = SECONDS_PER_ETH1_BLOCK * spec.eth1_follow_distance;
+
+        let mut cache = BlockCache::default();
+        for block in get_blocks(32, SECONDS_PER_ETH1_BLOCK) {
+            insert(&mut cache, block).expect("should add consecutive blocks");
+        }
+
+        let (mut state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(1, &spec).build();
+        state.genesis_time = 10 * SECONDS_PER_ETH1_BLOCK + 3;
+
+        // The middle of the voting period has the same candidates as its start.
+        let period = <E as EthSpec>::SlotsPerEth1VotingPeriod::to_u64();
+        state.slot = Slot::new(period + period / 2);
+        let period_start = voting_period_start_seconds(&state, &spec);
+        assert_eq!(period_start, state.genesis_time + period);
+
+        let numbers = cache
+            .blocks_in_voting_period(&state, &spec)
+            .map(|block| block.number)
+            .collect::<Vec<_>>();
+        let expected = cache
+            .iter()
+            .filter(|block| {
+                block.timestamp + follow_seconds <= period_start
+                    && block.timestamp + 2 * follow_seconds >= period_start
+            })
+            .map(|block| block.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, expected);
+        assert_eq!(numbers.len(), spec.eth1_follow_distance as usize);
+        assert!(cache
+            .blocks_in_voting_period(&state, &spec)
+            .all(|block| block.is_candidate(period_start, &spec)));
+
+        // No blocks are candidates before the cache begins, or after it ends.
+        state.genesis_time = 0;
+        state.slot = Slot::new(0);
+        assert_eq!(cache.blocks_in_voting_period(&state, &spec).count(), 0);
+        state.genesis_time = 1_000 * SECONDS_PER_ETH1_BLOCK;
+        assert_eq!(cache.blocks_in_voting_period(&state, &spec).count(), 0);
+    }
+
     #[test]
     fn truncate() {
         let n = 16;
@@ -203,6 +203,21 @@ impl DepositCache {
             Ok((tree.root(), deposits))
         }
     }
+
+    /// Returns the root of the deposit tree containing the first `deposit_count` deposits, i.e.,
+    /// the `deposit_root` of the deposit contract once it had received them.
+    ///
+    /// ## Errors
+    ///
+    /// - There are fewer than `deposit_count` deposits in the cache.
+    pub fn get_deposit_root(
+        &self,
+        deposit_count: u64,
+        tree_depth: usize,
+    ) -> Result<Hash256, Error> {
+        self.get_deposits(deposit_count, deposit_count, deposit_count, tree_depth)
+            .map(|(deposit_root, _deposits)| deposit_root)
+    }
 }
 
 /// Returns `int` as little-endian bytes with a length of 32.
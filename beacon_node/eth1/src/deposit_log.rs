@@ -1,6 +1,7 @@
 use super::http::Log;
 use ssz::Decode;
-use types::{DepositData, Hash256, PublicKeyBytes, SignatureBytes};
+use state_processing::per_block_processing::verify_deposit_signature;
+use types::{ChainSpec, DepositData, Hash256, PublicKeyBytes, SignatureBytes};
 
 /// The following constants define the layout of bytes in the deposit contract `DepositEvent`. The
 /// event bytes are formatted according to the  Ethereum ABI.
@@ -23,6 +24,12 @@ pub struct DepositLog {
     pub block_number: u64,
     /// The index included with the deposit log.
     pub index: u64,
+    /// `Some(true)` if the signature of `deposit_data` is valid and `None` if it has not been
+    /// verified.
+    ///
+    /// A deposit with an invalid signature still consumes an index, however it is ignored by the
+    /// beacon chain (unless it tops up an existing validator) and its amount is lost.
+    pub signature_is_valid: Option<bool>,
 }
 
 impl DepositLog {
@@ -61,14 +68,21 @@ impl DepositLog {
             deposit_data,
             block_number: log.block_number,
             index: u64::from_ssz_bytes(index).map_err(|e| format!("Invalid index ssz: {:?}", e))?,
+            signature_is_valid: None,
         })
     }
+
+    /// Verifies the signature of `self.deposit_data`, setting `self.signature_is_valid`.
+    pub fn verify_signature(&mut self, spec: &ChainSpec) {
+        self.signature_is_valid = Some(verify_deposit_signature(&self.deposit_data, spec).is_ok());
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
     use crate::http::Log;
+    use types::{Keypair, Signature};
 
     /// The data from a deposit event, using the v0.8.3 version of the deposit contract.
     pub const EXAMPLE_LOG: &[u8] = &[
@@ -104,4 +118,32 @@ pub mod tests {
         };
         DepositLog::from_log(&log).expect("should decode log");
     }
+
+    #[test]
+    fn verifies_signature() {
+        let spec = ChainSpec::mainnet();
+        let keypair = Keypair::random();
+
+        let mut deposit_data = DepositData {
+            pubkey: keypair.pk.into(),
+            withdrawal_credentials: Hash256::zero(),
+            amount: 32_000_000_000,
+            signature: Signature::empty_signature().into(),
+        };
+        deposit_data.signature = deposit_data.create_signature(&keypair.sk, &spec);
+
+        let mut deposit_log = DepositLog {
+            deposit_data,
+            block_number: 42,
+            index: 0,
+            signature_is_valid: None,
+        };
+
+        deposit_log.verify_signature(&spec);
+        assert_eq!(deposit_log.signature_is_valid, Some(true));
+
+        deposit_log.deposit_data.amount += 1;
+        deposit_log.verify_signature(&spec);
+        assert_eq!(deposit_log.signature_is_valid, Some(false));
+    }
 }
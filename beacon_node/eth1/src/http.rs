@@ -1,22 +1,45 @@
 //! Provides a very minimal set of functions for interfacing with the eth2 deposit contract via an
-//! eth1 HTTP JSON-RPC endpoint.
+//! eth1 JSON-RPC endpoint.
 //!
-//! All remote functions return a future (i.e., are async).
+//! All remote functions are methods of an `Eth1Client`, which pools connections, and return a
+//! future (i.e., are async).
 //!
 //! Does not use a web3 library, instead it uses `reqwest` (`hyper`) to call the remote endpoint
 //! and `serde` to decode the response.
 //!
+//! An endpoint with the `ipc://` scheme (e.g., `ipc:///home/user/.ethereum/geth.ipc`) is instead
+//! called over the Unix domain socket at the given path.
+//!
 //! ## Note
 //!
 //! There is no ABI parsing here, all function signatures and topics are hard-coded as constants.
 
-use futures::{Future, Stream};
-use reqwest::{header::CONTENT_TYPE, r#async::ClientBuilder, StatusCode};
+use futures::{
+    future::{self, Either, Loop},
+    task::{self, Task},
+    Async, Future, Poll, Stream,
+};
+use parking_lot::Mutex;
+use reqwest::{
+    header::CONTENT_TYPE,
+    r#async::{Client, ClientBuilder},
+    StatusCode,
+};
 use serde_json::{json, Value};
 use std::ops::Range;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::net::UnixStream;
+use tokio::timer::Timeout;
 use types::Hash256;
 
+/// The scheme of endpoints which are called over IPC, rather than HTTP.
+pub const IPC_SCHEME: &str = "ipc://";
+/// The number of bytes read from an IPC socket at a time.
+const IPC_READ_CHUNK_BYTES: usize = 4_096;
+/// The default maximum number of requests which an `Eth1Client` has in flight at once.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 16;
+
 /// `keccak("DepositEvent(bytes,bytes,bytes,bytes,bytes)")`
 pub const DEPOSIT_EVENT_TOPIC: &str =
     "0x649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5";
@@ -37,276 +60,495 @@ pub struct Block {
     pub number: u64,
 }
 
-/// Returns the current block number.
-///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-pub fn get_block_number(
-    endpoint: &str,
-    timeout: Duration,
-) -> impl Future<Item = u64, Error = String> {
-    send_rpc_request(endpoint, "eth_blockNumber", json!([]), timeout)
-        .and_then(|response_body| {
-            hex_to_u64_be(
-                response_result(&response_body)?
-                    .ok_or_else(|| "No result field was returned for block number".to_string())?
-                    .as_str()
-                    .ok_or_else(|| "Data was not string")?,
-            )
-        })
-        .map_err(|e| format!("Failed to get block number: {}", e))
+/// A reduced set of fields from an Eth1 contract log.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Log {
+    pub(crate) block_number: u64,
+    pub(crate) data: Vec<u8>,
 }
 
-/// Gets a block hash by block number.
+/// A client for the JSON-RPC endpoints of eth1 nodes.
 ///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-pub fn get_block(
-    endpoint: &str,
-    block_number: u64,
-    timeout: Duration,
-) -> impl Future<Item = Block, Error = String> {
-    let params = json!([
-        format!("0x{:x}", block_number),
-        false // do not return full tx objects.
-    ]);
-
-    send_rpc_request(endpoint, "eth_getBlockByNumber", params, timeout)
-        .and_then(|response_body| {
-            let hash = hex_to_bytes(
-                response_result(&response_body)?
-                    .ok_or_else(|| "No result field was returned for block".to_string())?
-                    .get("hash")
-                    .ok_or_else(|| "No hash for block")?
-                    .as_str()
-                    .ok_or_else(|| "Block hash was not string")?,
-            )?;
-            let hash = if hash.len() == 32 {
-                Ok(Hash256::from_slice(&hash))
-            } else {
-                Err(format!("Block has was not 32 bytes: {:?}", hash))
-            }?;
+/// The client is shared between requests (it is cheap to clone), so that HTTP connections are
+/// pooled and kept alive between requests rather than opened for each. Endpoints with the
+/// `https` scheme are supported (e.g., those of hosted providers).
+#[derive(Clone)]
+pub struct Eth1Client {
+    http: Client,
+    limiter: Arc<RequestLimiter>,
+}
 
-            let timestamp = hex_to_u64_be(
+impl Default for Eth1Client {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_REQUESTS).expect("The default client should always build")
+    }
+}
+
+impl Eth1Client {
+    /// Creates a client which has at most `max_concurrent_requests` requests in flight at once.
+    pub fn new(max_concurrent_requests: usize) -> Result<Self, String> {
+        let max_concurrent_requests = std::cmp::max(max_concurrent_requests, 1);
+
+        let http = ClientBuilder::new()
+            .max_idle_per_host(max_concurrent_requests)
+            .build()
+            .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+
+        Ok(Self {
+            http,
+            limiter: Arc::new(RequestLimiter {
+                max_in_flight: max_concurrent_requests,
+                state: Mutex::new(LimiterState::default()),
+            }),
+        })
+    }
+
+    /// Returns the current block number.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_block_number(
+        &self,
+        endpoint: &str,
+        timeout: Duration,
+    ) -> impl Future<Item = u64, Error = String> {
+        self.send_rpc_request(endpoint, "eth_blockNumber", json!([]), timeout)
+            .and_then(|response_body| {
+                hex_to_u64_be(
+                    response_result(&response_body)?
+                        .ok_or_else(|| "No result field was returned for block number".to_string())?
+                        .as_str()
+                        .ok_or_else(|| "Data was not string")?,
+                )
+            })
+            .map_err(|e| format!("Failed to get block number: {}", e))
+    }
+
+    /// Returns the chain ID of the eth1 node, as per EIP-695.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_chain_id(
+        &self,
+        endpoint: &str,
+        timeout: Duration,
+    ) -> impl Future<Item = u64, Error = String> {
+        self.send_rpc_request(endpoint, "eth_chainId", json!([]), timeout)
+            .and_then(|response_body| {
+                hex_to_u64_be(
+                    response_result(&response_body)?
+                        .ok_or_else(|| "No result field was returned for chain id".to_string())?
+                        .as_str()
+                        .ok_or_else(|| "Data was not string")?,
+                )
+            })
+            .map_err(|e| format!("Failed to get chain id: {}", e))
+    }
+
+    /// Returns the network ID of the eth1 node.
+    ///
+    /// Unlike the chain ID, the network ID is returned as a decimal string.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_network_id(
+        &self,
+        endpoint: &str,
+        timeout: Duration,
+    ) -> impl Future<Item = u64, Error = String> {
+        self.send_rpc_request(endpoint, "net_version", json!([]), timeout)
+            .and_then(|response_body| {
                 response_result(&response_body)?
-                    .ok_or_else(|| "No result field was returned for timestamp".to_string())?
-                    .get("timestamp")
-                    .ok_or_else(|| "No timestamp for block")?
+                    .ok_or_else(|| "No result field was returned for network id".to_string())?
                     .as_str()
-                    .ok_or_else(|| "Block timestamp was not string")?,
-            )?;
+                    .ok_or_else(|| "Data was not string")?
+                    .parse::<u64>()
+                    .map_err(|e| format!("Failed to parse network id: {:?}", e))
+            })
+            .map_err(|e| format!("Failed to get network id: {}", e))
+    }
+
+    /// Gets a block hash by block number.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_block(
+        &self,
+        endpoint: &str,
+        block_number: u64,
+        timeout: Duration,
+    ) -> impl Future<Item = Block, Error = String> {
+        let params = json!([
+            format!("0x{:x}", block_number),
+            false // do not return full tx objects.
+        ]);
+
+        self.send_rpc_request(endpoint, "eth_getBlockByNumber", params, timeout)
+            .and_then(|response_body| {
+                let hash = hex_to_bytes(
+                    response_result(&response_body)?
+                        .ok_or_else(|| "No result field was returned for block".to_string())?
+                        .get("hash")
+                        .ok_or_else(|| "No hash for block")?
+                        .as_str()
+                        .ok_or_else(|| "Block hash was not string")?,
+                )?;
+                let hash = if hash.len() == 32 {
+                    Ok(Hash256::from_slice(&hash))
+                } else {
+                    Err(format!("Block has was not 32 bytes: {:?}", hash))
+                }?;
 
-            let number = hex_to_u64_be(
+                let timestamp = hex_to_u64_be(
+                    response_result(&response_body)?
+                        .ok_or_else(|| "No result field was returned for timestamp".to_string())?
+                        .get("timestamp")
+                        .ok_or_else(|| "No timestamp for block")?
+                        .as_str()
+                        .ok_or_else(|| "Block timestamp was not string")?,
+                )?;
+
+                let number = hex_to_u64_be(
+                    response_result(&response_body)?
+                        .ok_or_else(|| "No result field was returned for number".to_string())?
+                        .get("number")
+                        .ok_or_else(|| "No number for block")?
+                        .as_str()
+                        .ok_or_else(|| "Block number was not string")?,
+                )?;
+
+                if number <= usize::max_value() as u64 {
+                    Ok(Block {
+                        hash,
+                        timestamp,
+                        number,
+                    })
+                } else {
+                    Err(format!("Block number {} is larger than a usize", number))
+                }
+            })
+            .map_err(|e| format!("Failed to get block number: {}", e))
+    }
+
+    /// Returns the value of the `get_deposit_count()` call at the given `address` for the given
+    /// `block_number`.
+    ///
+    /// Assumes that the `address` has the same ABI as the eth2 deposit contract.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_deposit_count(
+        &self,
+        endpoint: &str,
+        address: &str,
+        block_number: u64,
+        timeout: Duration,
+    ) -> impl Future<Item = Option<u64>, Error = String> {
+        self.call(
+            endpoint,
+            address,
+            DEPOSIT_COUNT_FN_SIGNATURE,
+            block_number,
+            timeout,
+        )
+        .and_then(|result| match result {
+            None => Err(format!("Deposit root response was none")),
+            Some(bytes) => {
+                if bytes.is_empty() {
+                    Ok(None)
+                } else if bytes.len() == DEPOSIT_COUNT_RESPONSE_BYTES {
+                    let mut array = [0; 8];
+                    array.copy_from_slice(&bytes[32 + 32..32 + 32 + 8]);
+                    Ok(Some(u64::from_le_bytes(array)))
+                } else {
+                    Err(format!(
+                        "Deposit count response was not {} bytes: {:?}",
+                        DEPOSIT_COUNT_RESPONSE_BYTES, bytes
+                    ))
+                }
+            }
+        })
+    }
+
+    /// Returns the value of the `get_hash_tree_root()` call at the given `block_number`.
+    ///
+    /// Assumes that the `address` has the same ABI as the eth2 deposit contract.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_deposit_root(
+        &self,
+        endpoint: &str,
+        address: &str,
+        block_number: u64,
+        timeout: Duration,
+    ) -> impl Future<Item = Option<Hash256>, Error = String> {
+        self.call(
+            endpoint,
+            address,
+            DEPOSIT_ROOT_FN_SIGNATURE,
+            block_number,
+            timeout,
+        )
+        .and_then(|result| match result {
+            None => Err(format!("Deposit root response was none")),
+            Some(bytes) => {
+                if bytes.is_empty() {
+                    Ok(None)
+                } else if bytes.len() == DEPOSIT_ROOT_BYTES {
+                    Ok(Some(Hash256::from_slice(&bytes)))
+                } else {
+                    Err(format!(
+                        "Deposit root response was not {} bytes: {:?}",
+                        DEPOSIT_ROOT_BYTES, bytes
+                    ))
+                }
+            }
+        })
+    }
+
+    /// Performs a instant, no-transaction call to the contract `address` with the given `0x`-prefixed
+    /// `hex_data`.
+    ///
+    /// Returns bytes, if any.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    fn call(
+        &self,
+        endpoint: &str,
+        address: &str,
+        hex_data: &str,
+        block_number: u64,
+        timeout: Duration,
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = String> {
+        let params = json! ([
+            {
+                "to": address,
+                "data": hex_data,
+            },
+            format!("0x{:x}", block_number)
+        ]);
+
+        self.send_rpc_request(endpoint, "eth_call", params, timeout)
+            .and_then(|response_body| match response_result(&response_body)? {
+                None => Ok(None),
+                Some(result) => {
+                    let hex = result
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .ok_or_else(|| "'result' value was not a string".to_string())?;
+
+                    Ok(Some(hex_to_bytes(&hex)?))
+                }
+            })
+    }
+
+    /// Returns logs for the `DEPOSIT_EVENT_TOPIC`, for the given `address` in the given
+    /// `block_height_range`.
+    ///
+    /// It's not clear from the Ethereum JSON-RPC docs if this range is inclusive or not.
+    ///
+    /// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
+    pub fn get_deposit_logs_in_range(
+        &self,
+        endpoint: &str,
+        address: &str,
+        block_height_range: Range<u64>,
+        timeout: Duration,
+    ) -> impl Future<Item = Vec<Log>, Error = String> {
+        let params = json! ([{
+            "address": address,
+            "topics": [DEPOSIT_EVENT_TOPIC],
+            "fromBlock": format!("0x{:x}", block_height_range.start),
+            "toBlock": format!("0x{:x}", block_height_range.end),
+        }]);
+
+        self.send_rpc_request(endpoint, "eth_getLogs", params, timeout)
+            .and_then(|response_body| {
                 response_result(&response_body)?
-                    .ok_or_else(|| "No result field was returned for number".to_string())?
-                    .get("number")
-                    .ok_or_else(|| "No number for block")?
-                    .as_str()
-                    .ok_or_else(|| "Block number was not string")?,
-            )?;
-
-            if number <= usize::max_value() as u64 {
-                Ok(Block {
-                    hash,
-                    timestamp,
-                    number,
-                })
+                    .ok_or_else(|| "No result field was returned for deposit logs".to_string())?
+                    .as_array()
+                    .cloned()
+                    .ok_or_else(|| "'result' value was not an array".to_string())?
+                    .into_iter()
+                    .map(|value| {
+                        let block_number = value
+                            .get("blockNumber")
+                            .ok_or_else(|| "No block number field in log")?
+                            .as_str()
+                            .ok_or_else(|| "Block number was not string")?;
+
+                        let data = value
+                            .get("data")
+                            .ok_or_else(|| "No block number field in log")?
+                            .as_str()
+                            .ok_or_else(|| "Data was not string")?;
+
+                        Ok(Log {
+                            block_number: hex_to_u64_be(&block_number)?,
+                            data: hex_to_bytes(data)?,
+                        })
+                    })
+                    .collect::<Result<Vec<Log>, String>>()
+            })
+            .map_err(|e| format!("Failed to get logs in range: {}", e))
+    }
+
+    /// Sends an RPC request to `endpoint`, over IPC if it has the `IPC_SCHEME` or otherwise using a
+    /// HTTP POST.
+    ///
+    /// Waits until fewer than the maximum number of requests are in flight before sending, then
+    /// tries to receive the response and parse the body as a `String`.
+    pub fn send_rpc_request(
+        &self,
+        endpoint: &str,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> impl Future<Item = String, Error = String> {
+        let body = json! ({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 1
+        })
+        .to_string();
+
+        let http = self.http.clone();
+        let endpoint = endpoint.to_string();
+
+        Acquire(Some(self.limiter.clone())).and_then(move |permit| {
+            let request = if endpoint.starts_with(IPC_SCHEME) {
+                Either::A(send_ipc_request(
+                    &endpoint[IPC_SCHEME.len()..],
+                    body,
+                    timeout,
+                ))
             } else {
-                Err(format!("Block number {} is larger than a usize", number))
-            }
+                Either::B(send_http_request(&http, &endpoint, body, timeout))
+            };
+
+            // The permit is released once the response is received, or the request fails.
+            request.then(move |result| {
+                drop(permit);
+                result
+            })
         })
-        .map_err(|e| format!("Failed to get block number: {}", e))
+    }
 }
 
-/// Returns the value of the `get_deposit_count()` call at the given `address` for the given
-/// `block_number`.
-///
-/// Assumes that the `address` has the same ABI as the eth2 deposit contract.
-///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-pub fn get_deposit_count(
-    endpoint: &str,
-    address: &str,
-    block_number: u64,
-    timeout: Duration,
-) -> impl Future<Item = Option<u64>, Error = String> {
-    call(
-        endpoint,
-        address,
-        DEPOSIT_COUNT_FN_SIGNATURE,
-        block_number,
-        timeout,
-    )
-    .and_then(|result| match result {
-        None => Err(format!("Deposit root response was none")),
-        Some(bytes) => {
-            if bytes.is_empty() {
-                Ok(None)
-            } else if bytes.len() == DEPOSIT_COUNT_RESPONSE_BYTES {
-                let mut array = [0; 8];
-                array.copy_from_slice(&bytes[32 + 32..32 + 32 + 8]);
-                Ok(Some(u64::from_le_bytes(array)))
-            } else {
-                Err(format!(
-                    "Deposit count response was not {} bytes: {:?}",
-                    DEPOSIT_COUNT_RESPONSE_BYTES, bytes
-                ))
-            }
-        }
-    })
+/// Limits the number of requests which are in flight at once.
+struct RequestLimiter {
+    max_in_flight: usize,
+    state: Mutex<LimiterState>,
 }
 
-/// Returns the value of the `get_hash_tree_root()` call at the given `block_number`.
-///
-/// Assumes that the `address` has the same ABI as the eth2 deposit contract.
-///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-pub fn get_deposit_root(
-    endpoint: &str,
-    address: &str,
-    block_number: u64,
-    timeout: Duration,
-) -> impl Future<Item = Option<Hash256>, Error = String> {
-    call(
-        endpoint,
-        address,
-        DEPOSIT_ROOT_FN_SIGNATURE,
-        block_number,
-        timeout,
-    )
-    .and_then(|result| match result {
-        None => Err(format!("Deposit root response was none")),
-        Some(bytes) => {
-            if bytes.is_empty() {
-                Ok(None)
-            } else if bytes.len() == DEPOSIT_ROOT_BYTES {
-                Ok(Some(Hash256::from_slice(&bytes)))
-            } else {
-                Err(format!(
-                    "Deposit root response was not {} bytes: {:?}",
-                    DEPOSIT_ROOT_BYTES, bytes
-                ))
-            }
-        }
-    })
+#[derive(Default)]
+struct LimiterState {
+    in_flight: usize,
+    /// The tasks waiting to send a request, which are notified when a request completes.
+    waiting: Vec<Task>,
 }
 
-/// Performs a instant, no-transaction call to the contract `address` with the given `0x`-prefixed
-/// `hex_data`.
-///
-/// Returns bytes, if any.
-///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-fn call(
-    endpoint: &str,
-    address: &str,
-    hex_data: &str,
-    block_number: u64,
-    timeout: Duration,
-) -> impl Future<Item = Option<Vec<u8>>, Error = String> {
-    let params = json! ([
-        {
-            "to": address,
-            "data": hex_data,
-        },
-        format!("0x{:x}", block_number)
-    ]);
-
-    send_rpc_request(endpoint, "eth_call", params, timeout).and_then(|response_body| {
-        match response_result(&response_body)? {
-            None => Ok(None),
-            Some(result) => {
-                let hex = result
-                    .as_str()
-                    .map(|s| s.to_string())
-                    .ok_or_else(|| "'result' value was not a string".to_string())?;
+/// A future which resolves once a request may be sent.
+struct Acquire(Option<Arc<RequestLimiter>>);
+
+impl Future for Acquire {
+    type Item = Permit;
+    type Error = String;
 
-                Ok(Some(hex_to_bytes(&hex)?))
+    fn poll(&mut self) -> Poll<Permit, String> {
+        let limiter = self
+            .0
+            .take()
+            .ok_or_else(|| "Polled request limiter after completion".to_string())?;
+
+        let acquired = {
+            let mut state = limiter.state.lock();
+            if state.in_flight < limiter.max_in_flight {
+                state.in_flight += 1;
+                true
+            } else {
+                state.waiting.push(task::current());
+                false
             }
+        };
+
+        if acquired {
+            Ok(Async::Ready(Permit(limiter)))
+        } else {
+            self.0 = Some(limiter);
+            Ok(Async::NotReady)
         }
-    })
+    }
 }
 
-/// A reduced set of fields from an Eth1 contract log.
-#[derive(Debug, PartialEq, Clone)]
-pub struct Log {
-    pub(crate) block_number: u64,
-    pub(crate) data: Vec<u8>,
+/// Allows a request to be in flight, until it is dropped.
+struct Permit(Arc<RequestLimiter>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.in_flight -= 1;
+
+        // All waiting tasks are notified, since a notified task may no longer want to send.
+        for task in state.waiting.drain(..) {
+            task.notify();
+        }
+    }
 }
 
-/// Returns logs for the `DEPOSIT_EVENT_TOPIC`, for the given `address` in the given
-/// `block_height_range`.
-///
-/// It's not clear from the Ethereum JSON-RPC docs if this range is inclusive or not.
-///
-/// Uses HTTP JSON RPC at `endpoint`. E.g., `http://localhost:8545`.
-pub fn get_deposit_logs_in_range(
-    endpoint: &str,
-    address: &str,
-    block_height_range: Range<u64>,
+/// Sends the `body` to the Unix domain socket at `path`, reading until a complete JSON value has
+/// been received.
+fn send_ipc_request(
+    path: &str,
+    body: String,
     timeout: Duration,
-) -> impl Future<Item = Vec<Log>, Error = String> {
-    let params = json! ([{
-        "address": address,
-        "topics": [DEPOSIT_EVENT_TOPIC],
-        "fromBlock": format!("0x{:x}", block_height_range.start),
-        "toBlock": format!("0x{:x}", block_height_range.end),
-    }]);
-
-    send_rpc_request(endpoint, "eth_getLogs", params, timeout)
-        .and_then(|response_body| {
-            response_result(&response_body)?
-                .ok_or_else(|| "No result field was returned for deposit logs".to_string())?
-                .as_array()
-                .cloned()
-                .ok_or_else(|| "'result' value was not an array".to_string())?
-                .into_iter()
-                .map(|value| {
-                    let block_number = value
-                        .get("blockNumber")
-                        .ok_or_else(|| "No block number field in log")?
-                        .as_str()
-                        .ok_or_else(|| "Block number was not string")?;
+) -> impl Future<Item = String, Error = String> {
+    let request = UnixStream::connect(path)
+        .map_err(|e| format!("Failed to connect to IPC socket: {:?}", e))
+        .and_then(move |stream| {
+            tokio::io::write_all(stream, body.into_bytes())
+                .map_err(|e| format!("IPC request failed: {:?}", e))
+        })
+        .and_then(|(stream, _)| {
+            future::loop_fn((stream, vec![]), |(stream, mut bytes)| {
+                tokio::io::read(stream, vec![0; IPC_READ_CHUNK_BYTES])
+                    .map_err(|e| format!("Failed to receive IPC response: {:?}", e))
+                    .and_then(move |(stream, chunk, len)| {
+                        if len == 0 {
+                            return Err(
+                                "IPC socket closed before a response was received".to_string()
+                            );
+                        }
 
-                    let data = value
-                        .get("data")
-                        .ok_or_else(|| "No block number field in log")?
-                        .as_str()
-                        .ok_or_else(|| "Data was not string")?;
+                        bytes.extend_from_slice(&chunk[0..len]);
 
-                    Ok(Log {
-                        block_number: hex_to_u64_be(&block_number)?,
-                        data: hex_to_bytes(data)?,
+                        // The response is not delimited, so it is complete once it parses.
+                        if serde_json::from_slice::<Value>(&bytes).is_ok() {
+                            Ok(Loop::Break(bytes))
+                        } else {
+                            Ok(Loop::Continue((stream, bytes)))
+                        }
                     })
-                })
-                .collect::<Result<Vec<Log>, String>>()
+            })
         })
-        .map_err(|e| format!("Failed to get logs in range: {}", e))
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+    Timeout::new(request, timeout).map_err(|e| {
+        if e.is_elapsed() {
+            "IPC request timed out".to_string()
+        } else {
+            e.into_inner()
+                .unwrap_or_else(|| "IPC request timer failed".to_string())
+        }
+    })
 }
 
-/// Sends an RPC request to `endpoint`, using a POST with the given `body`.
+/// Sends the `body` to `endpoint` using a HTTP POST.
 ///
-/// Tries to receive the response and parse the body as a `String`.
-pub fn send_rpc_request(
+/// Connections are reused from the pool of `http`, which is shared between requests.
+fn send_http_request(
+    http: &Client,
     endpoint: &str,
-    method: &str,
-    params: Value,
+    body: String,
     timeout: Duration,
 ) -> impl Future<Item = String, Error = String> {
-    let body = json! ({
-        "jsonrpc": "2.0",
-        "method": method,
-        "params": params,
-        "id": 1
-    })
-    .to_string();
-
-    // Note: it is not ideal to create a new client for each request.
-    //
-    // A better solution would be to create some struct that contains a built client and pass it
-    // around (similar to the `web3` crate's `Transport` structs).
-    ClientBuilder::new()
-        .timeout(timeout)
-        .build()
-        .expect("The builder should always build a client")
+    let request = http
         .post(endpoint)
         .header(CONTENT_TYPE, "application/json")
         .body(body)
@@ -348,7 +590,16 @@ pub fn send_rpc_request(
                 })
                 .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
                 .map_err(|e| format!("Failed to receive body: {:?}", e))
-        })
+        });
+
+    Timeout::new(request, timeout).map_err(|e| {
+        if e.is_elapsed() {
+            "HTTP request timed out".to_string()
+        } else {
+            e.into_inner()
+                .unwrap_or_else(|| "HTTP request timer failed".to_string())
+        }
+    })
 }
 
 /// Accepts an entire HTTP body (as a string) and returns the `result` field, as a serde `Value`.
@@ -393,3 +644,83 @@ fn strip_prefix(hex: &str) -> Result<&str, String> {
         Err("Hex string did not start with `0x`".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::{self, Notify};
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn ipc_block_number() {
+        let dir = std::env::temp_dir().join(format!("eth1_ipc_test_{}", std::process::id()));
+        let _ = std::fs::remove_file(&dir);
+        let listener = UnixListener::bind(&dir).expect("should bind socket");
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("should accept connection");
+            let mut request = [0; 1_024];
+            let len = stream.read(&mut request).expect("should read request");
+            let request: Value =
+                serde_json::from_slice(&request[0..len]).expect("request should be json");
+            assert_eq!(request["method"], "eth_blockNumber");
+
+            // Write the response in two parts, to ensure partial responses are buffered.
+            stream
+                .write_all(br#"{"jsonrpc":"2.0","id":1,"#)
+                .expect("should write response");
+            thread::sleep(Duration::from_millis(50));
+            stream
+                .write_all(br#""result":"0x2a"}"#)
+                .expect("should write response");
+        });
+
+        let endpoint = format!("{}{}", IPC_SCHEME, dir.display());
+        let block_number = Runtime::new()
+            .expect("should create runtime")
+            .block_on(Eth1Client::default().get_block_number(&endpoint, Duration::from_secs(5)))
+            .expect("should get block number over ipc");
+
+        server.join().expect("server should not panic");
+        let _ = std::fs::remove_file(&dir);
+
+        assert_eq!(block_number, 42);
+    }
+
+    #[test]
+    fn limits_concurrent_requests() {
+        struct NoopNotify;
+
+        impl Notify for NoopNotify {
+            fn notify(&self, _: usize) {}
+        }
+
+        let notify = Arc::new(NoopNotify);
+        let client = Eth1Client::new(1).expect("should build client");
+
+        let first = Acquire(Some(client.limiter.clone()))
+            .wait()
+            .expect("should allow first request");
+
+        let mut second = executor::spawn(Acquire(Some(client.limiter.clone())));
+        assert_eq!(
+            second
+                .poll_future_notify(&notify, 0)
+                .map(|async_| async_.is_ready()),
+            Ok(false),
+            "second request should wait for the first"
+        );
+
+        drop(first);
+        assert_eq!(
+            second
+                .poll_future_notify(&notify, 0)
+                .map(|async_| async_.is_ready()),
+            Ok(true),
+            "second request should be sent once the first completes"
+        );
+    }
+}
@@ -1,6 +1,9 @@
+use crate::http::Eth1Client;
 use crate::Config;
 use crate::{block_cache::BlockCache, deposit_cache::DepositCache};
 use parking_lot::RwLock;
+use std::time::Instant;
+use types::ChainSpec;
 
 #[derive(Default)]
 pub struct DepositUpdater {
@@ -10,9 +13,14 @@ pub struct DepositUpdater {
 
 #[derive(Default)]
 pub struct Inner {
+    /// The client shared by all requests to the eth1 node.
+    pub client: Eth1Client,
     pub block_cache: RwLock<BlockCache>,
     pub deposit_cache: RwLock<DepositUpdater>,
     pub config: RwLock<Config>,
+    pub spec: ChainSpec,
+    /// The time at which the eth1 node was last found to be on the expected network.
+    pub network_verified_at: RwLock<Option<Instant>>,
 }
 
 impl Inner {
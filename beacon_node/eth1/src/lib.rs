@@ -9,7 +9,7 @@ mod inner;
 mod metrics;
 mod service;
 
-pub use block_cache::{BlockCache, Eth1Block};
+pub use block_cache::{voting_period_start_seconds, BlockCache, Eth1Block, SECONDS_PER_ETH1_BLOCK};
 pub use deposit_cache::DepositCache;
 pub use deposit_log::DepositLog;
 pub use service::{BlockCacheUpdateOutcome, Config, DepositCacheUpdateOutcome, Error, Service};
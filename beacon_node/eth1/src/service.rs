@@ -2,24 +2,23 @@ use crate::metrics;
 use crate::{
     block_cache::{BlockCache, Error as BlockCacheError, Eth1Block},
     deposit_cache::Error as DepositCacheError,
-    http::{
-        get_block, get_block_number, get_deposit_count, get_deposit_logs_in_range, get_deposit_root,
-    },
+    http::{Eth1Client, DEFAULT_MAX_CONCURRENT_REQUESTS},
     inner::{DepositUpdater, Inner},
     DepositLog,
 };
 use exit_future::Exit;
 use futures::{
-    future::{loop_fn, Loop},
+    future::{self, loop_fn, Either, Loop},
     stream, Future, Stream,
 };
 use parking_lot::{RwLock, RwLockReadGuard};
 use serde::{Deserialize, Serialize};
-use slog::{debug, error, trace, Logger};
+use slog::{debug, error, trace, warn, Logger};
 use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::timer::Delay;
+use types::ChainSpec;
 
 const STANDARD_TIMEOUT_MILLIS: u64 = 15_000;
 
@@ -33,6 +32,11 @@ const GET_DEPOSIT_ROOT_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 const GET_DEPOSIT_COUNT_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 /// Timeout when doing an eth_getLogs to read the deposit contract logs.
 const GET_DEPOSIT_LOG_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+/// Timeout when doing an eth_chainId or net_version call.
+const NETWORK_ID_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
+
+/// The interval between checks that the eth1 node is still on the expected chain and network.
+const NETWORK_RECHECK_INTERVAL: Duration = Duration::from_secs(300);
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Error {
@@ -47,6 +51,15 @@ pub enum Error {
     BlockDownloadFailed(String),
     /// Failed to get the current block number from the eth1 node.
     GetBlockNumberFailed(String),
+    /// Failed to get the chain ID from the eth1 node.
+    GetChainIdFailed(String),
+    /// Failed to get the network ID from the eth1 node.
+    GetNetworkIdFailed(String),
+    /// The eth1 node is on a different chain to the one configured (e.g., mainnet rather than
+    /// Goerli). No blocks or deposits are imported from it.
+    IncorrectChainId { expected: u64, found: u64 },
+    /// The eth1 node is on a different network to the one configured.
+    IncorrectNetworkId { expected: u64, found: u64 },
     /// Failed to read the deposit contract root from the eth1 node.
     GetDepositRootFailed(String),
     /// Failed to read the deposit contract deposit count from the eth1 node.
@@ -85,7 +98,8 @@ pub enum DepositCacheUpdateOutcome {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// An Eth1 node (e.g., Geth) running a HTTP JSON-RPC endpoint.
+    /// An Eth1 node (e.g., Geth) running a HTTP JSON-RPC endpoint, or an IPC endpoint of the form
+    /// `ipc://<socket-path>`.
     pub endpoint: String,
     /// The address the `BlockCache` and `DepositCache` should assume is the canonical deposit contract.
     pub deposit_contract_address: String,
@@ -111,6 +125,24 @@ pub struct Config {
     pub max_log_requests_per_update: Option<usize>,
     /// The maximum number of log requests per update.
     pub max_blocks_per_update: Option<usize>,
+    /// If `true`, the signature of each deposit is verified as it is imported, so that deposits
+    /// which will not create a validator can be reported.
+    #[serde(default)]
+    pub verify_deposit_signatures: bool,
+    /// The maximum number of requests in flight to the eth1 node at once. Read when the service is
+    /// created.
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// The chain ID which the eth1 node must have, if any.
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    /// The network ID which the eth1 node must have, if any.
+    #[serde(default)]
+    pub network_id: Option<u64>,
+}
+
+fn default_max_concurrent_requests() -> usize {
+    DEFAULT_MAX_CONCURRENT_REQUESTS
 }
 
 impl Default for Config {
@@ -126,6 +158,10 @@ impl Default for Config {
             blocks_per_log_query: 1_000,
             max_log_requests_per_update: None,
             max_blocks_per_update: None,
+            verify_deposit_signatures: false,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            chain_id: None,
+            network_id: None,
         }
     }
 }
@@ -144,10 +180,15 @@ pub struct Service {
 
 impl Service {
     /// Creates a new service. Does not attempt to connect to the eth1 node.
-    pub fn new(config: Config, log: Logger) -> Self {
+    pub fn new(config: Config, log: Logger, spec: ChainSpec) -> Self {
+        let client = Eth1Client::new(config.max_concurrent_requests)
+            .expect("The eth1 client should always build");
+
         Self {
             inner: Arc::new(Inner {
+                client,
                 config: RwLock::new(config),
+                spec,
                 ..Inner::default()
             }),
             log,
@@ -227,6 +268,73 @@ impl Service {
         self.inner.config.write().lowest_cached_block_number = block_number;
     }
 
+    /// Checks that the eth1 node is on the chain and network given by the `chain_id` and
+    /// `network_id` of the config, if they are set.
+    ///
+    /// Resolves with an error if the node is on another chain or network, or if it cannot be
+    /// reached.
+    pub fn verify_network(&self) -> impl Future<Item = (), Error = Error> {
+        let config = self.config();
+        let client = &self.inner.client;
+        let timeout = Duration::from_millis(NETWORK_ID_TIMEOUT_MILLIS);
+
+        let chain_id_future = match config.chain_id {
+            Some(expected) => Either::A(
+                client
+                    .get_chain_id(&config.endpoint, timeout)
+                    .map_err(Error::GetChainIdFailed)
+                    .and_then(move |found| {
+                        if found == expected {
+                            Ok(())
+                        } else {
+                            Err(Error::IncorrectChainId { expected, found })
+                        }
+                    }),
+            ),
+            None => Either::B(future::ok(())),
+        };
+
+        let network_id_future = match config.network_id {
+            Some(expected) => Either::A(
+                client
+                    .get_network_id(&config.endpoint, timeout)
+                    .map_err(Error::GetNetworkIdFailed)
+                    .and_then(move |found| {
+                        if found == expected {
+                            Ok(())
+                        } else {
+                            Err(Error::IncorrectNetworkId { expected, found })
+                        }
+                    }),
+            ),
+            None => Either::B(future::ok(())),
+        };
+
+        chain_id_future.join(network_id_future).map(|_| ())
+    }
+
+    /// Runs `Self::verify_network` if it has not succeeded in the last
+    /// `NETWORK_RECHECK_INTERVAL`, so that an eth1 node which is switched to another network
+    /// whilst running is detected.
+    fn verify_network_if_due(&self) -> impl Future<Item = (), Error = Error> {
+        let due = self
+            .inner
+            .network_verified_at
+            .read()
+            .map_or(true, |verified_at| {
+                verified_at.elapsed() >= NETWORK_RECHECK_INTERVAL
+            });
+
+        if due {
+            let inner = self.inner.clone();
+            Either::A(self.verify_network().map(move |()| {
+                *inner.network_verified_at.write() = Some(Instant::now());
+            }))
+        } else {
+            Either::B(future::ok(()))
+        }
+    }
+
     /// Update the deposit and block cache, returning an error if either fail.
     ///
     /// ## Returns
@@ -381,52 +489,52 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| self.config().deposit_contract_deploy_block);
 
-        get_new_block_numbers(
-            &self.config().endpoint,
-            next_required_block,
-            self.config().follow_distance,
-        )
-        .map(move |range| {
-            range
-                .map(|range| {
-                    range
-                        .collect::<Vec<u64>>()
-                        .chunks(blocks_per_log_query)
-                        .take(max_log_requests_per_update)
-                        .map(|vec| {
-                            let first = vec.first().cloned().unwrap_or_else(|| 0);
-                            let last = vec.last().map(|n| n + 1).unwrap_or_else(|| 0);
-                            (first..last)
-                        })
-                        .collect::<Vec<Range<u64>>>()
-                })
-                .unwrap_or_else(|| vec![])
-        })
-        .and_then(move |block_number_chunks| {
-            stream::unfold(
-                block_number_chunks.into_iter(),
-                move |mut chunks| match chunks.next() {
-                    Some(chunk) => {
-                        let chunk_1 = chunk.clone();
-                        Some(
-                            get_deposit_logs_in_range(
-                                &service_1.config().endpoint,
-                                &service_1.config().deposit_contract_address,
-                                chunk,
-                                Duration::from_millis(GET_DEPOSIT_LOG_TIMEOUT_MILLIS),
+        get_new_block_numbers(self, next_required_block, self.config().follow_distance)
+            .map(move |range| {
+                range
+                    .map(|range| {
+                        range
+                            .collect::<Vec<u64>>()
+                            .chunks(blocks_per_log_query)
+                            .take(max_log_requests_per_update)
+                            .map(|vec| {
+                                let first = vec.first().cloned().unwrap_or_else(|| 0);
+                                let last = vec.last().map(|n| n + 1).unwrap_or_else(|| 0);
+                                (first..last)
+                            })
+                            .collect::<Vec<Range<u64>>>()
+                    })
+                    .unwrap_or_else(|| vec![])
+            })
+            .and_then(move |block_number_chunks| {
+                stream::unfold(
+                    block_number_chunks.into_iter(),
+                    move |mut chunks| match chunks.next() {
+                        Some(chunk) => {
+                            let chunk_1 = chunk.clone();
+                            Some(
+                                service_1
+                                    .inner
+                                    .client
+                                    .get_deposit_logs_in_range(
+                                        &service_1.config().endpoint,
+                                        &service_1.config().deposit_contract_address,
+                                        chunk,
+                                        Duration::from_millis(GET_DEPOSIT_LOG_TIMEOUT_MILLIS),
+                                    )
+                                    .map_err(Error::GetDepositLogsFailed)
+                                    .map(|logs| (chunk_1, logs))
+                                    .map(|logs| (logs, chunks)),
                             )
-                            .map_err(Error::GetDepositLogsFailed)
-                            .map(|logs| (chunk_1, logs))
-                            .map(|logs| (logs, chunks)),
-                        )
-                    }
-                    None => None,
-                },
-            )
-            .fold(0, move |mut sum, (block_range, log_chunk)| {
-                let mut cache = service_2.deposits().write();
-
-                log_chunk
+                        }
+                        None => None,
+                    },
+                )
+                .fold(0, move |mut sum, (block_range, log_chunk)| {
+                    let verify_deposit_signatures = service_2.config().verify_deposit_signatures;
+                    let mut cache = service_2.deposits().write();
+
+                    log_chunk
                     .into_iter()
                     .map(|raw_log| {
                         DepositLog::from_log(&raw_log).map_err(|error| {
@@ -442,7 +550,21 @@ impl Service {
                     // imported if any one of them cannot be parsed.
                     .collect::<Result<Vec<_>, _>>()?
                     .into_iter()
-                    .map(|deposit_log| {
+                    .map(|mut deposit_log| {
+                        if verify_deposit_signatures {
+                            deposit_log.verify_signature(&service_2.inner.spec);
+
+                            if deposit_log.signature_is_valid == Some(false) {
+                                warn!(
+                                    service_2.log,
+                                    "Deposit with invalid signature";
+                                    "msg" => "the deposit will not create a validator",
+                                    "pubkey" => format!("{:?}", deposit_log.deposit_data.pubkey),
+                                    "index" => deposit_log.index,
+                                );
+                            }
+                        }
+
                         cache
                             .cache
                             .insert_log(deposit_log)
@@ -460,18 +582,18 @@ impl Service {
                     // node to choose an invalid genesis state or propose an invalid block.
                     .collect::<Result<_, _>>()?;
 
-                cache.last_processed_block = Some(block_range.end.saturating_sub(1));
+                    cache.last_processed_block = Some(block_range.end.saturating_sub(1));
 
-                metrics::set_gauge(&metrics::DEPOSIT_CACHE_LEN, cache.cache.len() as i64);
-                metrics::set_gauge(
-                    &metrics::HIGHEST_PROCESSED_DEPOSIT_BLOCK,
-                    cache.last_processed_block.unwrap_or_else(|| 0) as i64,
-                );
+                    metrics::set_gauge(&metrics::DEPOSIT_CACHE_LEN, cache.cache.len() as i64);
+                    metrics::set_gauge(
+                        &metrics::HIGHEST_PROCESSED_DEPOSIT_BLOCK,
+                        cache.last_processed_block.unwrap_or_else(|| 0) as i64,
+                    );
 
-                Ok(sum)
+                    Ok(sum)
+                })
+                .map(|logs_imported| DepositCacheUpdateOutcome::Success { logs_imported })
             })
-            .map(|logs_imported| DepositCacheUpdateOutcome::Success { logs_imported })
-        })
     }
 
     /// Contacts the remote eth1 node and attempts to import all blocks up to the configured
@@ -505,109 +627,119 @@ impl Service {
             .map(|n| n + 1)
             .unwrap_or_else(|| self.config().lowest_cached_block_number);
 
-        get_new_block_numbers(
-            &self.config().endpoint,
-            next_required_block,
-            self.config().follow_distance,
-        )
-        // Map the range of required blocks into a Vec.
-        //
-        // If the required range is larger than the size of the cache, drop the exiting cache
-        // because it's exipred and just download enough blocks to fill the cache.
-        .and_then(move |range| {
-            range
-                .map(|range| {
-                    if range.start() > range.end() {
-                        // Note: this check is not strictly necessary, however it remains to safe
-                        // guard against any regression which may cause an underflow in a following
-                        // subtraction operation.
-                        Err(Error::Internal("Range was not increasing".into()))
-                    } else {
-                        let range_size = range.end() - range.start();
-                        let max_size = block_cache_truncation
-                            .map(|n| n as u64)
-                            .unwrap_or_else(u64::max_value);
-
-                        if range_size > max_size {
-                            // If the range of required blocks is larger than `max_size`, drop all
-                            // existing blocks and download `max_size` count of blocks.
-                            let first_block = range.end() - max_size;
-                            (*cache_5.block_cache.write()) = BlockCache::default();
-                            Ok((first_block..=*range.end()).collect::<Vec<u64>>())
+        get_new_block_numbers(self, next_required_block, self.config().follow_distance)
+            // Map the range of required blocks into a Vec.
+            //
+            // If the required range is larger than the size of the cache, drop the exiting cache
+            // because it's exipred and just download enough blocks to fill the cache.
+            .and_then(move |range| {
+                range
+                    .map(|range| {
+                        if range.start() > range.end() {
+                            // Note: this check is not strictly necessary, however it remains to safe
+                            // guard against any regression which may cause an underflow in a following
+                            // subtraction operation.
+                            Err(Error::Internal("Range was not increasing".into()))
                         } else {
-                            Ok(range.collect::<Vec<u64>>())
+                            let range_size = range.end() - range.start();
+                            let max_size = block_cache_truncation
+                                .map(|n| n as u64)
+                                .unwrap_or_else(u64::max_value);
+
+                            if range_size > max_size {
+                                // If the range of required blocks is larger than `max_size`, drop all
+                                // existing blocks and download `max_size` count of blocks.
+                                let first_block = range.end() - max_size;
+                                (*cache_5.block_cache.write()) = BlockCache::default();
+                                Ok((first_block..=*range.end()).collect::<Vec<u64>>())
+                            } else {
+                                Ok(range.collect::<Vec<u64>>())
+                            }
                         }
-                    }
+                    })
+                    .unwrap_or_else(|| Ok(vec![]))
+            })
+            // Download the range of blocks and sequentially import them into the cache.
+            .and_then(move |required_block_numbers| {
+                let required_block_numbers = required_block_numbers
+                    .into_iter()
+                    .take(max_blocks_per_update);
+
+                // Produce a stream from the list of required block numbers and return a future that
+                // consumes the it.
+                stream::unfold(
+                    required_block_numbers,
+                    move |mut block_numbers| match block_numbers.next() {
+                        Some(block_number) => Some(
+                            download_eth1_block(cache_2.clone(), block_number)
+                                .map(|v| (v, block_numbers)),
+                        ),
+                        None => None,
+                    },
+                )
+                .fold(0, move |sum, eth1_block| {
+                    cache_3
+                        .block_cache
+                        .write()
+                        .insert_root_or_child(eth1_block)
+                        .map_err(Error::FailedToInsertEth1Block)?;
+
+                    metrics::set_gauge(
+                        &metrics::BLOCK_CACHE_LEN,
+                        cache_3.block_cache.read().len() as i64,
+                    );
+                    metrics::set_gauge(
+                        &metrics::LATEST_CACHED_BLOCK_TIMESTAMP,
+                        cache_3
+                            .block_cache
+                            .read()
+                            .latest_block_timestamp()
+                            .unwrap_or_else(|| 0) as i64,
+                    );
+
+                    Ok(sum + 1)
                 })
-                .unwrap_or_else(|| Ok(vec![]))
-        })
-        // Download the range of blocks and sequentially import them into the cache.
-        .and_then(move |required_block_numbers| {
-            let required_block_numbers = required_block_numbers
-                .into_iter()
-                .take(max_blocks_per_update);
-
-            // Produce a stream from the list of required block numbers and return a future that
-            // consumes the it.
-            stream::unfold(
-                required_block_numbers,
-                move |mut block_numbers| match block_numbers.next() {
-                    Some(block_number) => Some(
-                        download_eth1_block(cache_2.clone(), block_number)
-                            .map(|v| (v, block_numbers)),
-                    ),
-                    None => None,
-                },
-            )
-            .fold(0, move |sum, eth1_block| {
-                cache_3
-                    .block_cache
-                    .write()
-                    .insert_root_or_child(eth1_block)
-                    .map_err(Error::FailedToInsertEth1Block)?;
+            })
+            .and_then(move |blocks_imported| {
+                // Prune the block cache, preventing it from growing too large.
+                cache_4.prune_blocks();
 
                 metrics::set_gauge(
                     &metrics::BLOCK_CACHE_LEN,
-                    cache_3.block_cache.read().len() as i64,
-                );
-                metrics::set_gauge(
-                    &metrics::LATEST_CACHED_BLOCK_TIMESTAMP,
-                    cache_3
-                        .block_cache
-                        .read()
-                        .latest_block_timestamp()
-                        .unwrap_or_else(|| 0) as i64,
+                    cache_4.block_cache.read().len() as i64,
                 );
 
-                Ok(sum + 1)
-            })
-        })
-        .and_then(move |blocks_imported| {
-            // Prune the block cache, preventing it from growing too large.
-            cache_4.prune_blocks();
-
-            metrics::set_gauge(
-                &metrics::BLOCK_CACHE_LEN,
-                cache_4.block_cache.read().len() as i64,
-            );
-
-            Ok(BlockCacheUpdateOutcome::Success {
-                blocks_imported,
-                head_block_number: cache_4.clone().block_cache.read().highest_block_number(),
+                Ok(BlockCacheUpdateOutcome::Success {
+                    blocks_imported,
+                    head_block_number: cache_4.clone().block_cache.read().highest_block_number(),
+                })
             })
-        })
     }
 }
 
 /// Determine the range of blocks that need to be downloaded, given the remotes best block and
 /// the locally stored best block.
+///
+/// Resolves with an error, without requesting any blocks, if the remote is not on the expected
+/// eth1 network.
 fn get_new_block_numbers<'a>(
-    endpoint: &str,
+    service: &Service,
     next_required_block: u64,
     follow_distance: u64,
 ) -> impl Future<Item = Option<RangeInclusive<u64>>, Error = Error> + 'a {
-    get_block_number(endpoint, Duration::from_millis(BLOCK_NUMBER_TIMEOUT_MILLIS))
-        .map_err(Error::GetBlockNumberFailed)
+    let client = service.inner.client.clone();
+    let endpoint = service.config().endpoint.clone();
+
+    service
+        .verify_network_if_due()
+        .and_then(move |()| {
+            client
+                .get_block_number(
+                    &endpoint,
+                    Duration::from_millis(BLOCK_NUMBER_TIMEOUT_MILLIS),
+                )
+                .map_err(Error::GetBlockNumberFailed)
+        })
         .and_then(move |remote_highest_block| {
             let remote_follow_block = remote_highest_block.saturating_sub(follow_distance);
 
@@ -640,36 +772,42 @@ fn download_eth1_block<'a>(
     block_number: u64,
 ) -> impl Future<Item = Eth1Block, Error = Error> + 'a {
     // Performs a `get_blockByNumber` call to an eth1 node.
-    get_block(
-        &cache.config.read().endpoint,
-        block_number,
-        Duration::from_millis(GET_BLOCK_TIMEOUT_MILLIS),
-    )
-    .map_err(Error::BlockDownloadFailed)
-    .join3(
-        // Perform 2x `eth_call` via an eth1 node to read the deposit contract root and count.
-        get_deposit_root(
+    cache
+        .client
+        .get_block(
             &cache.config.read().endpoint,
-            &cache.config.read().deposit_contract_address,
             block_number,
-            Duration::from_millis(GET_DEPOSIT_ROOT_TIMEOUT_MILLIS),
+            Duration::from_millis(GET_BLOCK_TIMEOUT_MILLIS),
         )
-        .map_err(Error::GetDepositRootFailed),
-        get_deposit_count(
-            &cache.config.read().endpoint,
-            &cache.config.read().deposit_contract_address,
-            block_number,
-            Duration::from_millis(GET_DEPOSIT_COUNT_TIMEOUT_MILLIS),
+        .map_err(Error::BlockDownloadFailed)
+        .join3(
+            // Perform 2x `eth_call` via an eth1 node to read the deposit contract root and count.
+            cache
+                .client
+                .get_deposit_root(
+                    &cache.config.read().endpoint,
+                    &cache.config.read().deposit_contract_address,
+                    block_number,
+                    Duration::from_millis(GET_DEPOSIT_ROOT_TIMEOUT_MILLIS),
+                )
+                .map_err(Error::GetDepositRootFailed),
+            cache
+                .client
+                .get_deposit_count(
+                    &cache.config.read().endpoint,
+                    &cache.config.read().deposit_contract_address,
+                    block_number,
+                    Duration::from_millis(GET_DEPOSIT_COUNT_TIMEOUT_MILLIS),
+                )
+                .map_err(Error::GetDepositCountFailed),
         )
-        .map_err(Error::GetDepositCountFailed),
-    )
-    .map(|(http_block, deposit_root, deposit_count)| Eth1Block {
-        hash: http_block.hash,
-        number: http_block.number,
-        timestamp: http_block.timestamp,
-        deposit_root,
-        deposit_count,
-    })
+        .map(|(http_block, deposit_root, deposit_count)| Eth1Block {
+            hash: http_block.hash,
+            number: http_block.number,
+            timestamp: http_block.timestamp,
+            deposit_root,
+            deposit_count,
+        })
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 #![cfg(test)]
 use environment::{Environment, EnvironmentBuilder};
-use eth1::http::{get_deposit_count, get_deposit_logs_in_range, get_deposit_root, Block, Log};
+use eth1::http::{Block, Eth1Client, Log};
 use eth1::{Config, Service};
 use eth1::{DepositCache, DepositLog};
 use eth1_test_rig::GanacheEth1Instance;
@@ -54,7 +54,7 @@ fn blocking_deposit_logs(
     range: Range<u64>,
 ) -> Vec<Log> {
     runtime
-        .block_on(get_deposit_logs_in_range(
+        .block_on(Eth1Client::default().get_deposit_logs_in_range(
             &eth1.endpoint(),
             &eth1.deposit_contract.address(),
             range,
@@ -70,7 +70,7 @@ fn blocking_deposit_root(
     block_number: u64,
 ) -> Option<Hash256> {
     runtime
-        .block_on(get_deposit_root(
+        .block_on(Eth1Client::default().get_deposit_root(
             &eth1.endpoint(),
             &eth1.deposit_contract.address(),
             block_number,
@@ -86,7 +86,7 @@ fn blocking_deposit_count(
     block_number: u64,
 ) -> Option<u64> {
     runtime
-        .block_on(get_deposit_count(
+        .block_on(Eth1Client::default().get_deposit_count(
             &eth1.endpoint(),
             &eth1.deposit_contract.address(),
             block_number,
@@ -129,6 +129,7 @@ mod auto_update {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         // NOTE: this test is sensitive to the response speed of the external web3 server. If
@@ -212,6 +213,7 @@ mod eth1_cache {
                     ..Config::default()
                 },
                 log.clone(),
+                MainnetEthSpec::default_spec(),
             );
 
             // Create some blocks and then consume them, performing the test `rounds` times.
@@ -284,6 +286,7 @@ mod eth1_cache {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         let blocks = cache_len * 2;
@@ -331,6 +334,7 @@ mod eth1_cache {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         for _ in 0..4 {
@@ -374,6 +378,7 @@ mod eth1_cache {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         for _ in 0..n {
@@ -422,6 +427,7 @@ mod deposit_tree {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         for round in 0..3 {
@@ -496,6 +502,7 @@ mod deposit_tree {
                 ..Config::default()
             },
             log,
+            MainnetEthSpec::default_spec(),
         );
 
         let deposits: Vec<_> = (0..n).map(|_| random_deposit_data()).collect();
@@ -618,11 +625,7 @@ mod http {
 
     fn get_block(runtime: &mut Runtime, eth1: &GanacheEth1Instance, block_number: u64) -> Block {
         runtime
-            .block_on(eth1::http::get_block(
-                &eth1.endpoint(),
-                block_number,
-                timeout(),
-            ))
+            .block_on(Eth1Client::default().get_block(&eth1.endpoint(), block_number, timeout()))
             .expect("should get block number")
     }
 
@@ -711,3 +714,75 @@ mod http {
         }
     }
 }
+
+mod network_id {
+    use super::*;
+    use eth1::Error;
+
+    #[test]
+    fn rejects_other_network() {
+        let mut env = new_env();
+        let log = env.core_context().log;
+        let runtime = env.runtime();
+
+        let eth1 = runtime
+            .block_on(GanacheEth1Instance::new())
+            .expect("should start eth1 environment");
+        let web3 = eth1.web3();
+
+        let network_id = runtime
+            .block_on(Eth1Client::default().get_network_id(&eth1.endpoint(), timeout()))
+            .expect("should get network id");
+        let now = get_block_number(runtime, &web3);
+
+        let service = |network_id| {
+            Service::new(
+                Config {
+                    endpoint: eth1.endpoint(),
+                    deposit_contract_address: eth1.deposit_contract.address(),
+                    lowest_cached_block_number: now,
+                    follow_distance: 0,
+                    network_id: Some(network_id),
+                    ..Config::default()
+                },
+                log.clone(),
+                MainnetEthSpec::default_spec(),
+            )
+        };
+
+        let correct = service(network_id);
+        runtime
+            .block_on(correct.verify_network())
+            .expect("should verify the network of the node");
+
+        let incorrect = service(network_id + 1);
+        match runtime.block_on(incorrect.verify_network()) {
+            Err(Error::IncorrectNetworkId { expected, found }) => {
+                assert_eq!(expected, network_id + 1);
+                assert_eq!(found, network_id);
+            }
+            other => panic!("should detect the wrong network, got {:?}", other),
+        }
+
+        runtime
+            .block_on(eth1.ganache.evm_mine())
+            .expect("should mine block");
+
+        runtime
+            .block_on(correct.update_block_cache())
+            .expect("should update cache");
+        assert!(correct.block_cache_len() > 0, "should import blocks");
+
+        runtime
+            .block_on(incorrect.update_block_cache())
+            .expect_err("should not update cache from another network");
+        runtime
+            .block_on(incorrect.update_deposit_cache())
+            .expect_err("should not update deposits from another network");
+        assert_eq!(
+            incorrect.block_cache_len(),
+            0,
+            "should not import blocks from another network"
+        );
+    }
+}
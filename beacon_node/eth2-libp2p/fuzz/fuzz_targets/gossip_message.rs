@@ -0,0 +1,27 @@
+#![no_main]
+use eth2_libp2p::fuzz::decode_gossip_message;
+use eth2_libp2p::*;
+use libfuzzer_sys::fuzz_target;
+use types::MainnetEthSpec;
+
+const TOPICS: &[&str] = &[
+    BEACON_BLOCK_TOPIC,
+    BEACON_ATTESTATION_TOPIC,
+    BEACON_AGGREGATE_AND_PROOF_TOPIC,
+    VOLUNTARY_EXIT_TOPIC,
+    PROPOSER_SLASHING_TOPIC,
+    ATTESTER_SLASHING_TOPIC,
+];
+
+// The first byte selects the topic on which the remaining bytes are received.
+fuzz_target!(|data: &[u8]| {
+    if let Some((selector, message)) = data.split_first() {
+        let topic = format!(
+            "/{}/{}/{}",
+            TOPIC_PREFIX,
+            TOPICS[*selector as usize % TOPICS.len()],
+            TOPIC_ENCODING_POSTFIX
+        );
+        let _ = decode_gossip_message::<MainnetEthSpec>(&topic, message);
+    }
+});
@@ -0,0 +1,7 @@
+#![no_main]
+use eth2_libp2p::fuzz::decode_rpc_request;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_rpc_request(data);
+});
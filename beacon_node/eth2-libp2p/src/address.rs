@@ -0,0 +1,256 @@
+//! Parsing and formatting of the ENRs and multiaddrs which identify nodes on the network.
+//!
+//! These are often supplied by hand (e.g., on the command line or in a testnet directory), so
+//! errors name the offending item of a list and, for multiaddrs, the offending component.
+use crate::multiaddr::Protocol;
+use crate::{Enr, Multiaddr, PeerId};
+use ssz::Decode;
+use std::str::FromStr;
+use types::EnrForkId;
+
+/// The ENR key under which a node advertises its SSZ-encoded `EnrForkId`.
+pub const ETH2_ENR_KEY: &str = "eth2";
+
+/// Returns the `EnrForkId` advertised in `enr`, or `None` if it does not advertise one.
+pub fn enr_fork_id(enr: &Enr) -> Result<Option<EnrForkId>, String> {
+    enr.get(ETH2_ENR_KEY)
+        .map(|bytes| {
+            EnrForkId::from_ssz_bytes(bytes)
+                .map_err(|e| format!("Invalid {} field in ENR: {:?}", ETH2_ENR_KEY, e))
+        })
+        .transpose()
+}
+
+/// Parses a base64-encoded ENR, with or without the `enr:` prefix.
+pub fn parse_enr(enr: &str) -> Result<Enr, String> {
+    let enr = enr.trim();
+
+    if enr.is_empty() {
+        return Err("Invalid ENR: the ENR is empty".to_string());
+    }
+
+    Enr::from_str(enr).map_err(|e| format!("Invalid ENR {}: {}", enr, e))
+}
+
+/// Parses a comma-delimited list of ENRs.
+pub fn parse_enr_list(enrs: &str) -> Result<Vec<Enr>, String> {
+    parse_list(enrs, parse_enr)
+}
+
+/// Parses a multiaddr, e.g. `/ip4/127.0.0.1/tcp/9000`.
+pub fn parse_multiaddr(multiaddr: &str) -> Result<Multiaddr, String> {
+    let multiaddr_str = multiaddr.trim();
+    let mut parts = multiaddr_str.split('/').peekable();
+
+    if parts.next() != Some("") {
+        return Err(format!(
+            "Invalid multiaddr {}: it must begin with a '/'",
+            multiaddr_str
+        ));
+    }
+
+    let mut multiaddr = Multiaddr::empty();
+    while let Some(&name) = parts.peek() {
+        if name.is_empty() {
+            return Err(format!(
+                "Invalid multiaddr {}: it has an empty component",
+                multiaddr_str
+            ));
+        }
+
+        let protocol = Protocol::from_str_parts(&mut parts).map_err(|e| {
+            format!(
+                "Invalid multiaddr {}: unable to parse the /{} component: {}",
+                multiaddr_str, name, e
+            )
+        })?;
+        multiaddr.push(protocol);
+    }
+
+    if multiaddr.iter().next().is_none() {
+        return Err(format!(
+            "Invalid multiaddr {}: it has no components",
+            multiaddr_str
+        ));
+    }
+
+    Ok(multiaddr)
+}
+
+/// Parses a comma-delimited list of multiaddrs.
+pub fn parse_multiaddr_list(multiaddrs: &str) -> Result<Vec<Multiaddr>, String> {
+    parse_list(multiaddrs, parse_multiaddr)
+}
+
+/// Parses a base58-encoded peer id, e.g. `QmeMFRTWfo3KbVG7dEBXGhyRMa29yfmnJBXW84rKuGEhuL`.
+pub fn parse_peer_id(peer_id: &str) -> Result<PeerId, String> {
+    let peer_id = peer_id.trim();
+
+    PeerId::from_str(peer_id).map_err(|e| format!("Invalid peer id {}: {:?}", peer_id, e))
+}
+
+/// Parses a comma-delimited list of peer ids.
+pub fn parse_peer_id_list(peer_ids: &str) -> Result<Vec<PeerId>, String> {
+    parse_list(peer_ids, parse_peer_id)
+}
+
+/// Returns a human-readable, multi-line description of `enr`, for display to a node operator.
+pub fn describe_enr(enr: &Enr) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+
+    let mut description = vec![
+        format!("ENR:        {}", enr.to_base64()),
+        format!("Sequence:   {}", enr.seq()),
+        format!("Node ID:    {}", enr.node_id()),
+        format!("Peer ID:    {}", enr.peer_id()),
+        format!(
+            "IP:         {}",
+            optional(enr.ip().map(|ip| ip.to_string()))
+        ),
+        format!(
+            "TCP port:   {}",
+            optional(enr.tcp().map(|port| port.to_string()))
+        ),
+        format!(
+            "UDP port:   {}",
+            optional(enr.udp().map(|port| port.to_string()))
+        ),
+        format!(
+            "Fork ID:    {}",
+            match enr_fork_id(enr) {
+                Ok(Some(fork_id)) => format!(
+                    "digest 0x{}, next version 0x{} at epoch {}",
+                    hex::encode(fork_id.fork_digest),
+                    hex::encode(fork_id.next_fork_version),
+                    fork_id.next_fork_epoch
+                ),
+                Ok(None) => "none".to_string(),
+                Err(e) => e,
+            }
+        ),
+    ];
+    description.extend(
+        enr.multiaddr()
+            .iter()
+            .map(|multiaddr| format!("Multiaddr:  {}", multiaddr)),
+    );
+
+    description.join("\n")
+}
+
+/// Parses each item of the comma-delimited `list` with `parse`, prefixing any error with the
+/// position of the offending item.
+fn parse_list<T, F>(list: &str, parse: F) -> Result<Vec<T>, String>
+where
+    F: Fn(&str) -> Result<T, String>,
+{
+    list.split(',')
+        .enumerate()
+        .map(|(i, item)| parse(item).map_err(|e| format!("Item {} of the list: {}", i + 1, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::core::identity::Keypair;
+    use libp2p::enr::EnrBuilder;
+    use ssz::Encode;
+
+    fn get_enr(port: u16) -> Enr {
+        EnrBuilder::new("v4")
+            .ip("127.0.0.1".parse().unwrap())
+            .tcp(port)
+            .udp(port)
+            .build(&Keypair::generate_secp256k1())
+            .unwrap()
+    }
+
+    #[test]
+    fn enrs() {
+        let (a, b) = (get_enr(1), get_enr(2));
+
+        assert_eq!(parse_enr(&a.to_base64()), Ok(a.clone()));
+        assert_eq!(
+            parse_enr_list(&format!("{}, {}", a.to_base64(), b.to_base64())),
+            Ok(vec![a.clone(), b])
+        );
+
+        assert!(parse_enr("").is_err());
+        let error = parse_enr_list(&format!("{},enr:-Iu4", a.to_base64())).unwrap_err();
+        assert!(error.starts_with("Item 2 of the list: Invalid ENR enr:-Iu4"));
+    }
+
+    #[test]
+    fn fork_ids() {
+        let fork_id = EnrForkId {
+            fork_digest: [1, 2, 3, 4],
+            next_fork_version: [0, 0, 0, 1],
+            next_fork_epoch: types::Epoch::new(42),
+        };
+        let enr = EnrBuilder::new("v4")
+            .ip("127.0.0.1".parse().unwrap())
+            .tcp(9000)
+            .add_value(ETH2_ENR_KEY.into(), fork_id.as_ssz_bytes())
+            .build(&Keypair::generate_secp256k1())
+            .unwrap();
+
+        assert_eq!(enr_fork_id(&enr), Ok(Some(fork_id)));
+        assert_eq!(enr_fork_id(&get_enr(9000)), Ok(None));
+        assert!(describe_enr(&enr).contains("digest 0x01020304"));
+    }
+
+    #[test]
+    fn multiaddrs() {
+        let expected = "/ip4/127.0.0.1/tcp/9000".parse::<Multiaddr>().unwrap();
+        assert_eq!(parse_multiaddr(" /ip4/127.0.0.1/tcp/9000 "), Ok(expected));
+        assert_eq!(
+            parse_multiaddr_list("/ip4/127.0.0.1/tcp/9000,/ip4/10.0.0.1/tcp/9001")
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let error = parse_multiaddr("/ip4/127.0.0/tcp/9000").unwrap_err();
+        assert!(error.contains("/ip4 component"), "{}", error);
+        let error = parse_multiaddr("/ip4/127.0.0.1/tcp/port").unwrap_err();
+        assert!(error.contains("/tcp component"), "{}", error);
+        let error = parse_multiaddr("/ip4/127.0.0.1/udpp/9000").unwrap_err();
+        assert!(error.contains("/udpp component"), "{}", error);
+        let error = parse_multiaddr("ip4/127.0.0.1").unwrap_err();
+        assert!(error.contains("must begin with a '/'"), "{}", error);
+        assert!(parse_multiaddr("/").is_err());
+        assert!(parse_multiaddr("/ip4/127.0.0.1/").is_err());
+
+        let error = parse_multiaddr_list("/ip4/127.0.0.1/tcp/9000,").unwrap_err();
+        assert!(error.starts_with("Item 2 of the list"), "{}", error);
+    }
+
+    #[test]
+    fn peer_ids() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+
+        assert_eq!(parse_peer_id(&a.to_base58()), Ok(a.clone()));
+        assert_eq!(
+            parse_peer_id_list(&format!("{}, {}", a.to_base58(), b.to_base58())),
+            Ok(vec![a.clone(), b])
+        );
+
+        let error = parse_peer_id_list(&format!("{},Qm", a.to_base58())).unwrap_err();
+        assert!(
+            error.starts_with("Item 2 of the list: Invalid peer id Qm"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn describes_enr() {
+        let enr = get_enr(9000);
+        let description = describe_enr(&enr);
+
+        assert!(description.contains(&enr.to_base64()));
+        assert!(description.contains("TCP port:   9000"));
+        assert!(description.contains("IP:         127.0.0.1"));
+    }
+}
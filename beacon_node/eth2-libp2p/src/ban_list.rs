@@ -0,0 +1,304 @@
+/// The bans imposed by the node operator, either on the command line or through the HTTP API, as
+/// opposed to the bans of misbehaving peers recorded in the `PeerDB`.
+///
+/// A ban applies to a peer id or to every peer connecting from (or discovered at) an IP address,
+/// and lasts until it expires or is removed. The list is persisted to disk, so that bans survive a
+/// restart.
+use crate::address::parse_peer_id;
+use libp2p::PeerId;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use slog::{debug, warn};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Ban list storage filename.
+pub const BAN_LIST_FILENAME: &str = "bans.dat";
+
+/// The subject of a ban.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BanTarget {
+    Peer(PeerId),
+    Ip(IpAddr),
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BanTarget::Peer(peer_id) => write!(f, "{}", peer_id.to_base58()),
+            BanTarget::Ip(ip) => write!(f, "{}", ip),
+        }
+    }
+}
+
+impl FromStr for BanTarget {
+    type Err = String;
+
+    /// Parses an IP address (e.g., `10.0.0.1`) or a base58-encoded peer id.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+
+        match IpAddr::from_str(s) {
+            Ok(ip) => Ok(BanTarget::Ip(ip)),
+            Err(_) => parse_peer_id(s)
+                .map(BanTarget::Peer)
+                .map_err(|_| format!("Invalid ban target {}: not a peer id or IP address", s)),
+        }
+    }
+}
+
+impl Serialize for BanTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for BanTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BanTarget::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+/// A ban, as presented to the node operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ban {
+    pub target: BanTarget,
+    /// The unix time (in seconds) at which the ban expires, or `None` if it is permanent.
+    pub expires_at: Option<u64>,
+}
+
+/// The bans imposed by the node operator.
+#[derive(Debug, Default)]
+pub struct BanList {
+    /// The unix time (in seconds) at which each ban expires, or `None` if it is permanent.
+    bans: HashMap<BanTarget, Option<u64>>,
+}
+
+impl BanList {
+    /// Loads the list stored in `dir`, returning an empty list if there is none or it cannot be
+    /// decoded.
+    pub fn open(dir: &Path, log: &slog::Logger) -> Self {
+        let path = dir.join(BAN_LIST_FILENAME);
+
+        let mut bytes = vec![];
+        match File::open(&path).and_then(|mut f| f.read_to_end(&mut bytes)) {
+            Ok(_) => match SszBanList::from_ssz_bytes(&bytes) {
+                Ok(ssz_container) => {
+                    let list = Self::from_ssz_container(ssz_container);
+                    debug!(log, "Ban list loaded"; "file" => format!("{:?}", path), "bans" => list.len());
+                    list
+                }
+                Err(e) => {
+                    warn!(log, "Ban list could not be decoded"; "error" => format!("{:?}", e));
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Writes the list to `dir`, replacing any list stored previously. Expired bans are dropped.
+    pub fn save(&mut self, dir: &Path, now: u64) -> Result<(), String> {
+        self.bans
+            .retain(|_, expires_at| expires_at.map_or(true, |expires_at| expires_at > now));
+
+        let _ = std::fs::create_dir_all(dir);
+        File::create(dir.join(BAN_LIST_FILENAME))
+            .and_then(|mut f| f.write_all(&self.to_ssz_container().as_ssz_bytes()))
+            .map_err(|e| format!("Could not write ban list: {}", e))
+    }
+
+    /// The number of bans in the list, including any which have expired but not yet been dropped.
+    pub fn len(&self) -> usize {
+        self.bans.len()
+    }
+
+    /// Returns `true` if the list contains no bans.
+    pub fn is_empty(&self) -> bool {
+        self.bans.is_empty()
+    }
+
+    /// Bans `target` until the unix time `expires_at`, or permanently if it is `None`, replacing
+    /// any previous ban of `target`.
+    pub fn insert(&mut self, target: BanTarget, expires_at: Option<u64>) {
+        self.bans.insert(target, expires_at);
+    }
+
+    /// Lifts the ban of `target`, returning `false` if it was not banned.
+    pub fn remove(&mut self, target: &BanTarget) -> bool {
+        self.bans.remove(target).is_some()
+    }
+
+    /// Returns `true` if `target` is banned at the unix time `now`.
+    pub fn is_banned(&self, target: &BanTarget, now: u64) -> bool {
+        self.bans
+            .get(target)
+            .map_or(false, |expires_at| expires_at.map_or(true, |t| t > now))
+    }
+
+    /// Returns `true` if `peer_id`, or the IP address `ip` (if known), is banned at `now`.
+    pub fn is_peer_banned(&self, peer_id: &PeerId, ip: Option<IpAddr>, now: u64) -> bool {
+        self.is_banned(&BanTarget::Peer(peer_id.clone()), now)
+            || ip.map_or(false, |ip| self.is_banned(&BanTarget::Ip(ip), now))
+    }
+
+    /// Returns the remaining duration of the ban of `target` at `now`: `None` if it is not
+    /// banned, `Some(None)` if the ban is permanent.
+    pub fn remaining(&self, target: &BanTarget, now: u64) -> Option<Option<Duration>> {
+        if !self.is_banned(target, now) {
+            return None;
+        }
+
+        self.bans
+            .get(target)
+            .map(|expires_at| expires_at.map(|t| Duration::from_secs(t - now)))
+    }
+
+    /// Returns the bans which have not expired at `now`.
+    pub fn bans(&self, now: u64) -> Vec<Ban> {
+        self.bans
+            .iter()
+            .filter(|(target, _)| self.is_banned(target, now))
+            .map(|(target, expires_at)| Ban {
+                target: target.clone(),
+                expires_at: *expires_at,
+            })
+            .collect()
+    }
+
+    /// Returns a `SszBanList`, which contains all necessary information to restore the state of
+    /// `Self` at some later point.
+    fn to_ssz_container(&self) -> SszBanList {
+        SszBanList {
+            bans: self
+                .bans
+                .iter()
+                .map(|(target, expires_at)| SszBan {
+                    target: target.to_string().into_bytes(),
+                    expires_at: expires_at.unwrap_or(0),
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates a new `Self` from the given `SszBanList`, skipping any bans which cannot be
+    /// decoded.
+    fn from_ssz_container(ssz_container: SszBanList) -> Self {
+        let bans = ssz_container
+            .bans
+            .into_iter()
+            .filter_map(|ssz_ban| {
+                let target = BanTarget::from_str(&String::from_utf8(ssz_ban.target).ok()?).ok()?;
+                let expires_at = Some(ssz_ban.expires_at).filter(|&t| t != 0);
+                Some((target, expires_at))
+            })
+            .collect();
+
+        Self { bans }
+    }
+}
+
+/// Helper struct that is used to encode/decode the `BanList` as SSZ bytes.
+#[derive(Encode, Decode)]
+struct SszBanList {
+    bans: Vec<SszBan>,
+}
+
+/// A ban as SSZ. The target is encoded as a string, and a permanent ban expires at zero.
+#[derive(Encode, Decode)]
+struct SszBan {
+    target: Vec<u8>,
+    expires_at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peer_db::unix_now;
+    use slog::{o, Drain};
+
+    #[test]
+    fn parses_targets() {
+        let peer_id = PeerId::random();
+
+        assert_eq!(
+            BanTarget::from_str(&peer_id.to_base58()),
+            Ok(BanTarget::Peer(peer_id.clone()))
+        );
+        assert_eq!(
+            BanTarget::from_str(" 10.0.0.1"),
+            Ok(BanTarget::Ip("10.0.0.1".parse().unwrap()))
+        );
+        assert_eq!(
+            BanTarget::from_str("::1"),
+            Ok(BanTarget::Ip("::1".parse().unwrap()))
+        );
+        assert!(BanTarget::from_str("10.0.0").is_err());
+
+        let target = BanTarget::Peer(peer_id);
+        assert_eq!(BanTarget::from_str(&target.to_string()), Ok(target));
+    }
+
+    #[test]
+    fn bans_expire() {
+        let mut list = BanList::default();
+        let (peer, other_peer) = (PeerId::random(), PeerId::random());
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        list.insert(BanTarget::Peer(peer.clone()), Some(100));
+        list.insert(BanTarget::Ip(ip), None);
+
+        assert!(list.is_peer_banned(&peer, None, 50));
+        assert!(!list.is_peer_banned(&peer, None, 100));
+        assert!(list.is_peer_banned(&other_peer, Some(ip), 1_000));
+        assert!(!list.is_peer_banned(&other_peer, None, 50));
+        assert_eq!(
+            list.remaining(&BanTarget::Peer(peer.clone()), 40),
+            Some(Some(Duration::from_secs(60)))
+        );
+        assert_eq!(list.remaining(&BanTarget::Ip(ip), 40), Some(None));
+        assert_eq!(list.bans(50).len(), 2);
+        assert_eq!(
+            list.bans(100),
+            vec![Ban {
+                target: BanTarget::Ip(ip),
+                expires_at: None
+            }]
+        );
+
+        assert!(list.remove(&BanTarget::Ip(ip)));
+        assert!(!list.remove(&BanTarget::Ip(ip)));
+        assert!(!list.is_peer_banned(&other_peer, Some(ip), 50));
+    }
+
+    #[test]
+    fn persists_to_disk() {
+        let dir = std::env::temp_dir().join(format!("ban_list_test_{}", unix_now()));
+        let log = slog::Logger::root(slog::Discard.fuse(), o!());
+        let peer = PeerId::random();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let mut list = BanList::default();
+        list.insert(BanTarget::Peer(peer.clone()), Some(100));
+        list.insert(BanTarget::Ip(ip), None);
+        list.insert(BanTarget::Peer(PeerId::random()), Some(20));
+        list.save(&dir, 30).unwrap();
+
+        let loaded = BanList::open(&dir, &log);
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.is_banned(&BanTarget::Peer(peer), 50));
+        assert_eq!(loaded.remaining(&BanTarget::Ip(ip), 50), Some(None));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
+use crate::ban_list::{Ban, BanTarget};
 use crate::config::*;
-use crate::discovery::Discovery;
+use crate::discovery::{Discovery, PeerCounts};
+use crate::gossip::{gossip_message_id, SeenMessages};
+use crate::metrics;
 use crate::rpc::{RPCEvent, RPCMessage, RPC};
 use crate::{error, NetworkConfig};
 use crate::{Topic, TopicHash};
@@ -15,9 +18,9 @@ use libp2p::{
     tokio_io::{AsyncRead, AsyncWrite},
     NetworkBehaviour, PeerId,
 };
-use slog::{debug, o};
+use slog::{debug, o, trace};
 use std::num::NonZeroU32;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const MAX_IDENTIFY_ADDRESSES: usize = 20;
 
@@ -40,6 +43,9 @@ pub struct Behaviour<TSubstream: AsyncRead + AsyncWrite> {
     /// Discovery behaviour.
     discovery: Discovery<TSubstream>,
     #[behaviour(ignore)]
+    /// The spec message-ids of recently received gossip messages, used to drop duplicates.
+    seen_gossip_messages: SeenMessages,
+    #[behaviour(ignore)]
     /// The events generated by this behaviour to be consumed in the swarm poll.
     events: Vec<BehaviourEvent>,
     /// Logger for behaviour actions.
@@ -74,6 +80,10 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
             discovery: Discovery::new(local_key, net_conf, log)?,
             ping: Ping::new(ping_config),
             identify,
+            // Duplicates are suppressed for as long as gossipsub caches messages.
+            seen_gossip_messages: SeenMessages::new(
+                net_conf.gs_config.heartbeat_interval * net_conf.gs_config.history_length as u32,
+            ),
             events: Vec::new(),
             log: behaviour_log,
         })
@@ -95,6 +105,18 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<GossipsubE
     fn inject_event(&mut self, event: GossipsubEvent) {
         match event {
             GossipsubEvent::Message(propagation_source, gs_msg) => {
+                // Gossipsub identifies messages by their source and sequence number, which
+                // differ when the same payload is published by several peers.
+                let message_id = gossip_message_id(&gs_msg.topics, &gs_msg.data);
+                if !self
+                    .seen_gossip_messages
+                    .observe(message_id.clone(), Instant::now())
+                {
+                    trace!(self.log, "Dropping duplicate gossip message"; "message_id" => message_id);
+                    metrics::inc_counter(&metrics::GOSSIP_DUPLICATE_MESSAGES);
+                    return;
+                }
+
                 let id = gs_msg.id();
                 let msg = PubsubMessage::from_topics(&gs_msg.topics, gs_msg.data);
 
@@ -236,11 +258,59 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
         self.discovery.connected_peers()
     }
 
+    /// The number of connected peers in each direction, and the limits on them.
+    pub fn peer_counts(&self) -> PeerCounts {
+        self.discovery.peer_counts()
+    }
+
+    /// Returns the peers which should be disconnected to respect the peer limits.
+    pub fn take_peers_to_prune(&mut self) -> Vec<PeerId> {
+        self.discovery.take_peers_to_prune()
+    }
+
     /// Notify discovery that the peer has been banned.
     pub fn peer_banned(&mut self, peer_id: PeerId) {
         self.discovery.peer_banned(peer_id);
     }
 
+    /// Record that the peer is to be banned for `timeout`, so that the ban persists across
+    /// restarts.
+    pub fn record_ban(&mut self, peer_id: &PeerId, timeout: Duration) {
+        self.discovery.record_ban(peer_id, timeout);
+    }
+
+    /// Returns the peers which were banned in a previous run or on the command line, with the
+    /// remaining duration of each ban (`None` if it is permanent).
+    pub fn persisted_bans(&self) -> Vec<(PeerId, Option<Duration>)> {
+        self.discovery.persisted_bans()
+    }
+
+    /// Returns the peers which should be disconnected because they or their IP address are
+    /// banned.
+    pub fn take_banned_connections(&mut self) -> Vec<PeerId> {
+        self.discovery.take_banned_connections()
+    }
+
+    /// Returns `true` if the peer is banned, either for misbehaving or by the node operator.
+    pub fn is_peer_banned(&self, peer_id: &PeerId) -> bool {
+        self.discovery.is_peer_banned(peer_id)
+    }
+
+    /// Adds `target` to the ban list, returning the connected peers which it applies to.
+    pub fn add_ban(&mut self, target: BanTarget, expires_at: Option<u64>) -> Vec<PeerId> {
+        self.discovery.add_ban(target, expires_at)
+    }
+
+    /// Lifts the ban of `target`, returning `false` if it was not banned.
+    pub fn remove_ban(&mut self, target: &BanTarget) -> bool {
+        self.discovery.remove_ban(target)
+    }
+
+    /// Returns the bans which have not expired.
+    pub fn bans(&self) -> Vec<Ban> {
+        self.discovery.bans()
+    }
+
     /// Notify discovery that the peer has been unbanned.
     pub fn peer_unbanned(&mut self, peer_id: &PeerId) {
         self.discovery.peer_unbanned(peer_id);
@@ -281,8 +351,13 @@ pub enum BehaviourEvent {
 pub enum PubsubMessage {
     /// Gossipsub message providing notification of a new block.
     Block(Vec<u8>),
+    /// Gossipsub message providing notification of a new block which carries an execution
+    /// witness.
+    BlockAndWitness(Vec<u8>),
     /// Gossipsub message providing notification of a new attestation.
     Attestation(Vec<u8>),
+    /// Gossipsub message providing notification of a new aggregate attestation and its proof.
+    AggregateAndProof(Vec<u8>),
     /// Gossipsub message providing notification of a voluntary exit.
     VoluntaryExit(Vec<u8>),
     /// Gossipsub message providing notification of a new proposer slashing.
@@ -300,7 +375,7 @@ impl PubsubMessage {
      * Also note that a message can be associated with many topics. As soon as one of the topics is
      * known we match. If none of the topics are known we return an unknown state.
      */
-    fn from_topics(topics: &[TopicHash], data: Vec<u8>) -> Self {
+    pub(crate) fn from_topics(topics: &[TopicHash], data: Vec<u8>) -> Self {
         for topic in topics {
             // compare the prefix and postfix, then match on the topic
             let topic_parts: Vec<&str> = topic.as_str().split('/').collect();
@@ -310,7 +385,11 @@ impl PubsubMessage {
             {
                 match topic_parts[2] {
                     BEACON_BLOCK_TOPIC => return PubsubMessage::Block(data),
+                    BEACON_BLOCK_AND_WITNESS_TOPIC => return PubsubMessage::BlockAndWitness(data),
                     BEACON_ATTESTATION_TOPIC => return PubsubMessage::Attestation(data),
+                    BEACON_AGGREGATE_AND_PROOF_TOPIC => {
+                        return PubsubMessage::AggregateAndProof(data)
+                    }
                     VOLUNTARY_EXIT_TOPIC => return PubsubMessage::VoluntaryExit(data),
                     PROPOSER_SLASHING_TOPIC => return PubsubMessage::ProposerSlashing(data),
                     ATTESTER_SLASHING_TOPIC => return PubsubMessage::AttesterSlashing(data),
@@ -324,7 +403,9 @@ impl PubsubMessage {
     fn into_data(self) -> Vec<u8> {
         match self {
             PubsubMessage::Block(data)
+            | PubsubMessage::BlockAndWitness(data)
             | PubsubMessage::Attestation(data)
+            | PubsubMessage::AggregateAndProof(data)
             | PubsubMessage::VoluntaryExit(data)
             | PubsubMessage::ProposerSlashing(data)
             | PubsubMessage::AttesterSlashing(data)
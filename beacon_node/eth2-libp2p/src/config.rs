@@ -1,20 +1,27 @@
+use crate::ban_list::BanTarget;
 use enr::Enr;
 use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder};
-use libp2p::Multiaddr;
+use libp2p::{Multiaddr, PeerId};
 use serde_derive::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::Duration;
+use types::EnrForkId;
 
 /// The gossipsub topic names.
 // These constants form a topic name of the form /TOPIC_PREFIX/TOPIC/ENCODING_POSTFIX
 // For example /eth2/beacon_block/ssz
 pub const TOPIC_PREFIX: &str = "eth2";
 pub const TOPIC_ENCODING_POSTFIX: &str = "ssz";
+/// The postfix of topics whose messages are snappy-compressed SSZ.
+pub const GOSSIP_SNAPPY_ENCODING_POSTFIX: &str = "ssz_snappy";
 pub const BEACON_BLOCK_TOPIC: &str = "beacon_block";
 pub const BEACON_ATTESTATION_TOPIC: &str = "beacon_attestation";
+pub const BEACON_AGGREGATE_AND_PROOF_TOPIC: &str = "beacon_aggregate_and_proof";
 pub const VOLUNTARY_EXIT_TOPIC: &str = "voluntary_exit";
 pub const PROPOSER_SLASHING_TOPIC: &str = "proposer_slashing";
 pub const ATTESTER_SLASHING_TOPIC: &str = "attester_slashing";
+/// The experimental topic of blocks which carry an `ExecutionWitness`.
+pub const BEACON_BLOCK_AND_WITNESS_TOPIC: &str = "beacon_block_and_witness";
 pub const SHARD_TOPIC_PREFIX: &str = "shard";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -36,15 +43,37 @@ pub struct Config {
     /// UDP port that discovery listens on.
     pub discovery_port: u16,
 
-    /// Target number of connected peers.
+    /// Maximum number of connected peers.
     pub max_peers: usize,
 
+    /// Maximum number of peers which connected to us. If `None`, only `max_peers` applies.
+    pub max_inbound_peers: Option<usize>,
+
+    /// Maximum number of peers which we dialed. If `None`, only `max_peers` applies.
+    pub max_outbound_peers: Option<usize>,
+
+    /// Number of peers which we aim to have dialed. Whilst there are fewer, discovery is queried
+    /// for more peers. If `None`, peers are dialed until `max_peers` are connected.
+    pub target_outbound_peers: Option<usize>,
+
+    /// Peers which are never disconnected to respect the peer limits. They still count towards
+    /// the limits.
+    #[serde(skip)]
+    pub trusted_peers: Vec<PeerId>,
+
+    /// Peer ids and IP addresses which are banned until the ban is removed through the HTTP API.
+    #[serde(skip)]
+    pub banned_peers: Vec<BanTarget>,
+
     /// A secp256k1 secret key, as bytes in ASCII-encoded hex.
     ///
     /// With or without `0x` prefix.
     #[serde(skip)]
     pub secret_key_hex: Option<String>,
 
+    /// The tunable gossipsub parameters, from which `gs_config` is built.
+    pub gossipsub: GossipsubParams,
+
     /// Gossipsub configuration parameters.
     #[serde(skip)]
     pub gs_config: GossipsubConfig,
@@ -52,6 +81,12 @@ pub struct Config {
     /// List of nodes to initially connect to.
     pub boot_nodes: Vec<Enr>,
 
+    /// The fork ID advertised in the local ENR, which is set from the beacon chain when the
+    /// network is started. Discovered peers which advertise an incompatible fork ID are not
+    /// dialed. If `None`, no fork ID is advertised and discovered peers are not filtered.
+    #[serde(skip)]
+    pub enr_fork_id: Option<EnrForkId>,
+
     /// List of libp2p nodes to initially connect to.
     pub libp2p_nodes: Vec<Multiaddr>,
 
@@ -61,18 +96,23 @@ pub struct Config {
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<String>,
 
+    /// Subscribe to the experimental topic of blocks which carry an execution witness.
+    pub execution_witness_topic: bool,
+
     /// Introduces randomization in network propagation of messages. This should only be set for
     /// testing purposes and will likely be removed in future versions.
     // TODO: Remove this functionality for mainnet
     pub propagation_percentage: Option<u8>,
+
+    /// If set, each gossip message received is appended to a log in this directory, so that it
+    /// may be replayed with `lcli replay-gossip`.
+    pub record_gossip_dir: Option<PathBuf>,
 }
 
 impl Default for Config {
     /// Generate a default network configuration.
     fn default() -> Self {
-        let mut network_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        network_dir.push(".lighthouse");
-        network_dir.push("network");
+        let network_dir = lighthouse_dirs::default_dir(lighthouse_dirs::BEACON_DIR).join("network");
         Config {
             network_dir,
             listen_address: "127.0.0.1".parse().expect("valid ip address"),
@@ -80,19 +120,135 @@ impl Default for Config {
             discovery_address: "127.0.0.1".parse().expect("valid ip address"),
             discovery_port: 9000,
             max_peers: 10,
+            max_inbound_peers: None,
+            max_outbound_peers: None,
+            target_outbound_peers: None,
+            trusted_peers: vec![],
+            banned_peers: vec![],
             secret_key_hex: None,
-            // Note: The topics by default are sent as plain strings. Hashes are an optional
-            // parameter.
-            gs_config: GossipsubConfigBuilder::new()
-                .max_transmit_size(1_048_576)
-                .heartbeat_interval(Duration::from_secs(20)) // TODO: Reduce for mainnet
-                .manual_propagation(true) // require validation before propagation
-                .build(),
+            gossipsub: GossipsubParams::default(),
+            gs_config: GossipsubParams::default().gs_config(),
             boot_nodes: vec![],
+            enr_fork_id: None,
             libp2p_nodes: vec![],
             client_version: version::version(),
             topics: Vec::new(),
+            execution_witness_topic: false,
             propagation_percentage: None,
+            record_gossip_dir: None,
+        }
+    }
+}
+
+/// The gossipsub parameters which may be tuned in the `[network.gossipsub]` section of the
+/// config file, e.g. to speed up message propagation on a small devnet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GossipsubParams {
+    /// The target number of peers in the mesh of each topic.
+    pub mesh_n: usize,
+    /// Peers are grafted into the mesh when it has fewer than this many peers.
+    pub mesh_n_low: usize,
+    /// Peers are pruned from the mesh when it has more than this many peers.
+    pub mesh_n_high: usize,
+    /// The number of heartbeats for which messages are kept in the message cache, which is also
+    /// the period for which duplicate messages are suppressed.
+    pub history_length: usize,
+    /// The number of heartbeats of history which are advertised to peers outside the mesh.
+    pub history_gossip: usize,
+    /// The time between heartbeats, in milliseconds.
+    pub heartbeat_interval_millis: u64,
+}
+
+impl Default for GossipsubParams {
+    fn default() -> Self {
+        Self {
+            mesh_n: 6,
+            mesh_n_low: 4,
+            mesh_n_high: 12,
+            history_length: 5,
+            history_gossip: 3,
+            // TODO: Reduce for mainnet
+            heartbeat_interval_millis: 20_000,
+        }
+    }
+}
+
+impl GossipsubParams {
+    /// Returns an error if the parameters are inconsistent.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.mesh_n_low <= self.mesh_n && self.mesh_n <= self.mesh_n_high) {
+            return Err(format!(
+                "Gossipsub mesh sizes must satisfy mesh_n_low ({}) <= mesh_n ({}) <= mesh_n_high ({})",
+                self.mesh_n_low, self.mesh_n, self.mesh_n_high
+            ));
         }
+
+        if self.history_gossip > self.history_length {
+            return Err(format!(
+                "Gossipsub history_gossip ({}) must not exceed history_length ({})",
+                self.history_gossip, self.history_length
+            ));
+        }
+
+        if self.history_length == 0 || self.heartbeat_interval_millis == 0 {
+            return Err(
+                "Gossipsub history_length and heartbeat_interval_millis must be non-zero"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the `GossipsubConfig` with these parameters.
+    pub fn gs_config(&self) -> GossipsubConfig {
+        // Note: The topics by default are sent as plain strings. Hashes are an optional
+        // parameter.
+        GossipsubConfigBuilder::new()
+            .max_transmit_size(1_048_576)
+            .mesh_n(self.mesh_n)
+            .mesh_n_low(self.mesh_n_low)
+            .mesh_n_high(self.mesh_n_high)
+            .history_length(self.history_length)
+            .history_gossip(self.history_gossip)
+            .heartbeat_interval(Duration::from_millis(self.heartbeat_interval_millis))
+            .manual_propagation(true) // require validation before propagation
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_gossipsub_params() {
+        assert_eq!(GossipsubParams::default().validate(), Ok(()));
+
+        let small_devnet = GossipsubParams {
+            mesh_n: 2,
+            mesh_n_low: 1,
+            mesh_n_high: 3,
+            heartbeat_interval_millis: 700,
+            ..GossipsubParams::default()
+        };
+        assert_eq!(small_devnet.validate(), Ok(()));
+        assert_eq!(
+            small_devnet.gs_config().heartbeat_interval,
+            Duration::from_millis(700)
+        );
+
+        let bad_mesh = GossipsubParams {
+            mesh_n_low: 7,
+            ..GossipsubParams::default()
+        };
+        assert!(bad_mesh.validate().is_err());
+
+        let bad_history = GossipsubParams {
+            history_gossip: 6,
+            ..GossipsubParams::default()
+        };
+        assert!(bad_history.validate().is_err());
     }
 }
@@ -1,4 +1,7 @@
+use crate::address::{enr_fork_id, ETH2_ENR_KEY};
+use crate::ban_list::{Ban, BanList, BanTarget};
 use crate::metrics;
+use crate::peer_db::{unix_now, PeerDB};
 use crate::{error, NetworkConfig};
 /// This manages the discovery and management of peers.
 ///
@@ -10,15 +13,19 @@ use libp2p::discv5::{Discv5, Discv5Event};
 use libp2p::enr::{Enr, EnrBuilder, NodeId};
 use libp2p::multiaddr::Protocol;
 use libp2p::swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParameters, ProtocolsHandler};
+use serde_derive::Serialize;
 use slog::{debug, info, warn};
-use std::collections::HashSet;
+use ssz::Encode;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
+use std::net::IpAddr;
 use std::path::Path;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::timer::Delay;
+use types::EnrForkId;
 
 /// Maximum seconds before searching for extra peers.
 const MAX_TIME_BETWEEN_PEER_SEARCHES: u64 = 120;
@@ -26,6 +33,8 @@ const MAX_TIME_BETWEEN_PEER_SEARCHES: u64 = 120;
 const INITIAL_SEARCH_DELAY: u64 = 5;
 /// Local ENR storage filename.
 const ENR_FILENAME: &str = "enr.dat";
+/// Seconds between writes of the peer database to disk.
+const PEER_DB_SAVE_INTERVAL: u64 = 60;
 
 /// Lighthouse discovery behaviour. This provides peer management and discovery using the Discv5
 /// libp2p protocol.
@@ -33,11 +42,42 @@ pub struct Discovery<TSubstream> {
     /// The peers currently connected to libp2p streams.
     connected_peers: HashSet<PeerId>,
 
+    /// The connected peers which we dialed, as opposed to those which connected to us.
+    outbound_peers: HashSet<PeerId>,
+
+    /// Peers which are never pruned.
+    trusted_peers: HashSet<PeerId>,
+
+    /// Connected peers which should be disconnected to respect `limits`.
+    peers_to_prune: Vec<PeerId>,
+
     /// The currently banned peers.
     banned_peers: HashSet<PeerId>,
 
-    /// The target number of connected peers on the libp2p interface.
-    max_peers: usize,
+    /// The IP address of each connected peer, if it is known.
+    peer_ips: HashMap<PeerId, IpAddr>,
+
+    /// Connected peers which should be disconnected because they or their IP address are banned.
+    banned_connections: Vec<PeerId>,
+
+    /// The known peers, with their dial history and bans, which is persisted across restarts.
+    peer_db: PeerDB,
+
+    /// The bans imposed by the node operator, which are persisted across restarts.
+    ban_list: BanList,
+
+    /// The fork ID advertised in the local ENR, if any. Peers which advertise an incompatible
+    /// fork ID (e.g., those on another testnet) are not dialed.
+    local_fork_id: Option<EnrForkId>,
+
+    /// Good peers from the peer database, which are dialed at startup.
+    peers_to_dial: Vec<PeerId>,
+
+    /// The delay between writes of the peer database to disk.
+    peer_db_save_delay: Delay,
+
+    /// The limits on the number of connected peers on the libp2p interface.
+    limits: PeerLimits,
 
     /// The directory where the ENR is stored.
     enr_dir: String,
@@ -97,10 +137,52 @@ impl<TSubstream> Discovery<TSubstream> {
             discovery.add_enr(bootnode_enr);
         }
 
+        // The bans given on the command line last until they are removed.
+        let mut ban_list = BanList::open(&config.network_dir, &log);
+        for target in &config.banned_peers {
+            ban_list.insert(target.clone(), None);
+        }
+
+        // Add the best peers from previous runs to the routing table and dial them, rather than
+        // waiting for them to be rediscovered.
+        let peer_db = PeerDB::open(&config.network_dir, &log);
+        let now = unix_now();
+        let mut peers_to_dial = vec![];
+        let limits = PeerLimits::from_config(config);
+        for enr in peer_db.good_peers(now, limits.target_outbound_peers) {
+            if ban_list.is_peer_banned(&enr.peer_id(), enr.ip().map(IpAddr::V4), now) {
+                continue;
+            }
+            peers_to_dial.push(enr.peer_id());
+            discovery.add_enr(enr);
+        }
+        // Dial the best peers first.
+        peers_to_dial.reverse();
+
+        let banned_peers = peer_db
+            .banned_peers(now)
+            .into_iter()
+            .map(|(peer_id, _)| peer_id)
+            .collect();
+
+        info!(log, "Peer database loaded"; "peers" => peer_db.len(), "peers_to_dial" => peers_to_dial.len(), "bans" => ban_list.len());
+
         Ok(Self {
             connected_peers: HashSet::new(),
-            banned_peers: HashSet::new(),
-            max_peers: config.max_peers,
+            outbound_peers: HashSet::new(),
+            trusted_peers: config.trusted_peers.iter().cloned().collect(),
+            peers_to_prune: vec![],
+            banned_peers,
+            peer_ips: HashMap::new(),
+            banned_connections: vec![],
+            peer_db,
+            ban_list,
+            local_fork_id: config.enr_fork_id.clone(),
+            peers_to_dial,
+            peer_db_save_delay: Delay::new(
+                Instant::now() + Duration::from_secs(PEER_DB_SAVE_INTERVAL),
+            ),
+            limits,
             peer_discovery_delay: Delay::new(Instant::now()),
             past_discovery_delay: INITIAL_SEARCH_DELAY,
             tcp_port: config.libp2p_port,
@@ -155,6 +237,139 @@ impl<TSubstream> Discovery<TSubstream> {
         &self.connected_peers
     }
 
+    /// The number of connected peers in each direction, and the limits on them.
+    pub fn peer_counts(&self) -> PeerCounts {
+        PeerCounts {
+            connected: self.connected_peers.len(),
+            inbound: self.connected_peers.len() - self.outbound_peers.len(),
+            outbound: self.outbound_peers.len(),
+            trusted: self
+                .connected_peers
+                .intersection(&self.trusted_peers)
+                .count(),
+            max_peers: self.limits.max_peers,
+            max_inbound_peers: self.limits.max_inbound_peers,
+            max_outbound_peers: self.limits.max_outbound_peers,
+            target_outbound_peers: self.limits.target_outbound_peers,
+        }
+    }
+
+    /// Returns the peers which should be disconnected to respect the peer limits, emptying the
+    /// list.
+    pub fn take_peers_to_prune(&mut self) -> Vec<PeerId> {
+        std::mem::replace(&mut self.peers_to_prune, vec![])
+    }
+
+    /// Returns `true` if another peer should be dialed.
+    fn needs_outbound_peers(&self) -> bool {
+        self.outbound_peers.len() < self.limits.target_outbound_peers
+            && self.connected_peers.len() < self.limits.max_peers
+    }
+
+    /// Returns the peers which should be disconnected because they or their IP address are
+    /// banned, emptying the list.
+    pub fn take_banned_connections(&mut self) -> Vec<PeerId> {
+        std::mem::replace(&mut self.banned_connections, vec![])
+    }
+
+    /// Returns `true` if `peer_id` may be dialed.
+    fn should_dial(&self, peer_id: &PeerId) -> bool {
+        self.needs_outbound_peers()
+            && !self.connected_peers.contains(peer_id)
+            && !self.banned_peers.contains(peer_id)
+            && !self.is_operator_banned(peer_id, self.known_ip(peer_id))
+            && self.is_fork_compatible(peer_id)
+    }
+
+    /// Returns `false` if the ENR of `peer_id` advertises a fork ID which is incompatible with the
+    /// local one, or a fork ID which cannot be decoded.
+    ///
+    /// Peers whose ENR is unknown or does not advertise a fork ID are assumed to be compatible,
+    /// since they will be disconnected after the handshake if they are not.
+    fn is_fork_compatible(&self, peer_id: &PeerId) -> bool {
+        let local_fork_id = match &self.local_fork_id {
+            Some(fork_id) => fork_id,
+            None => return true,
+        };
+
+        match self
+            .peer_db
+            .get(peer_id)
+            .and_then(|record| record.enr.as_ref())
+            .map(enr_fork_id)
+        {
+            Some(Ok(Some(fork_id))) => local_fork_id.is_compatible_with(&fork_id),
+            Some(Ok(None)) | None => true,
+            Some(Err(_)) => false,
+        }
+    }
+
+    /// Returns the IP address of `peer_id`, if it is connected or its ENR is known.
+    fn known_ip(&self, peer_id: &PeerId) -> Option<IpAddr> {
+        self.peer_ips.get(peer_id).cloned().or_else(|| {
+            self.peer_db
+                .get(peer_id)
+                .and_then(|record| record.enr.as_ref())
+                .and_then(|enr| enr.ip())
+                .map(IpAddr::V4)
+        })
+    }
+
+    /// Returns `true` if `peer_id`, or its IP address `ip`, is in the ban list.
+    fn is_operator_banned(&self, peer_id: &PeerId, ip: Option<IpAddr>) -> bool {
+        self.ban_list.is_peer_banned(peer_id, ip, unix_now())
+    }
+
+    /// Selects the lowest-scored, untrusted peers to disconnect until no limit is exceeded.
+    fn prune_excess_peers(&mut self) {
+        let candidates = self
+            .connected_peers
+            .iter()
+            .filter(|peer_id| !self.trusted_peers.contains(peer_id))
+            .filter(|peer_id| !self.is_disconnecting(peer_id))
+            .map(|peer_id| {
+                let score = self.peer_db.get(peer_id).map_or(0, |record| record.score());
+                (
+                    peer_id.clone(),
+                    self.outbound_peers.contains(peer_id),
+                    score,
+                )
+            })
+            .collect();
+
+        // peers which are already being disconnected do not count towards the limits
+        let outbound = self
+            .outbound_peers
+            .iter()
+            .filter(|peer_id| !self.is_disconnecting(peer_id))
+            .count();
+        let disconnecting = self
+            .connected_peers
+            .iter()
+            .filter(|peer_id| self.is_disconnecting(peer_id))
+            .count();
+        let inbound = self.connected_peers.len() - disconnecting - outbound;
+
+        let pruned = select_peers_to_prune(candidates, inbound, outbound, &self.limits);
+        metrics::inc_counter_by(&metrics::PEERS_PRUNED_COUNT, pruned.len() as i64);
+        self.peers_to_prune.extend(pruned);
+    }
+
+    /// Returns `true` if `peer_id` is about to be disconnected, having been pruned or banned.
+    fn is_disconnecting(&self, peer_id: &PeerId) -> bool {
+        self.peers_to_prune.contains(peer_id) || self.banned_connections.contains(peer_id)
+    }
+
+    fn update_peer_metrics(&self) {
+        let outbound = self.outbound_peers.len();
+        metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
+        metrics::set_gauge(&metrics::OUTBOUND_PEERS_CONNECTED, outbound as i64);
+        metrics::set_gauge(
+            &metrics::INBOUND_PEERS_CONNECTED,
+            (self.connected_peers() - outbound) as i64,
+        );
+    }
+
     /// The peer has been banned. Add this peer to the banned list to prevent any future
     /// re-connections.
     // TODO: Remove the peer from the DHT if present
@@ -164,6 +379,124 @@ impl<TSubstream> Discovery<TSubstream> {
 
     pub fn peer_unbanned(&mut self, peer_id: &PeerId) {
         self.banned_peers.remove(peer_id);
+        self.peer_db.unban(peer_id);
+    }
+
+    /// Records in the peer database that the peer is banned for `timeout`, so that the ban
+    /// survives a restart.
+    pub fn record_ban(&mut self, peer_id: &PeerId, timeout: Duration) {
+        let now = unix_now();
+        self.peer_db.ban(peer_id, now + timeout.as_secs(), now);
+    }
+
+    /// Returns the peers banned in the peer database or the ban list, with the remaining
+    /// duration of each ban (`None` if it is permanent).
+    pub fn persisted_bans(&self) -> Vec<(PeerId, Option<Duration>)> {
+        let now = unix_now();
+        let mut bans = self
+            .peer_db
+            .banned_peers(now)
+            .into_iter()
+            .map(|(peer_id, timeout)| (peer_id, Some(timeout)))
+            .collect::<Vec<_>>();
+
+        for ban in self.ban_list.bans(now) {
+            if let BanTarget::Peer(peer_id) = ban.target {
+                let remaining = ban.expires_at.map(|t| Duration::from_secs(t - now));
+                bans.push((peer_id, remaining));
+            }
+        }
+
+        bans
+    }
+
+    /// Returns `true` if `peer_id` is banned, either for misbehaving or by the node operator.
+    pub fn is_peer_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned_peers.contains(peer_id) || self.is_operator_banned(peer_id, None)
+    }
+
+    /// Adds `target` to the ban list until `expires_at` (or permanently), returning the connected
+    /// peers which it applies to.
+    pub fn add_ban(&mut self, target: BanTarget, expires_at: Option<u64>) -> Vec<PeerId> {
+        let peers = match &target {
+            BanTarget::Peer(peer_id) => self
+                .connected_peers
+                .iter()
+                .filter(|connected| *connected == peer_id)
+                .cloned()
+                .collect(),
+            BanTarget::Ip(ip) => self
+                .peer_ips
+                .iter()
+                .filter(|(_, peer_ip)| *peer_ip == ip)
+                .map(|(peer_id, _)| peer_id.clone())
+                .collect(),
+        };
+
+        info!(self.log, "Ban added"; "target" => target.to_string(), "expires_at" => format!("{:?}", expires_at));
+        self.ban_list.insert(target, expires_at);
+        self.save_ban_list();
+
+        peers
+    }
+
+    /// Lifts the ban of `target`, whether it was imposed by the node operator or, for a peer, for
+    /// misbehaving. Returns `false` if it was not banned.
+    pub fn remove_ban(&mut self, target: &BanTarget) -> bool {
+        let now = unix_now();
+        let mut removed = self.ban_list.remove(target);
+
+        if let BanTarget::Peer(peer_id) = target {
+            let misbehaved = self
+                .peer_db
+                .get(peer_id)
+                .map_or(false, |record| record.is_banned(now));
+            removed |= misbehaved || self.banned_peers.contains(peer_id);
+            self.peer_unbanned(peer_id);
+        }
+
+        if removed {
+            info!(self.log, "Ban removed"; "target" => target.to_string());
+            self.save_ban_list();
+        }
+
+        removed
+    }
+
+    /// Returns the bans which have not expired, whether they were imposed by the node operator or,
+    /// for a peer, for misbehaving.
+    pub fn bans(&self) -> Vec<Ban> {
+        let now = unix_now();
+        let mut bans = self.ban_list.bans(now);
+
+        for (peer_id, timeout) in self.peer_db.banned_peers(now) {
+            let target = BanTarget::Peer(peer_id);
+            if !self.ban_list.is_banned(&target, now) {
+                bans.push(Ban {
+                    target,
+                    expires_at: Some(now + timeout.as_secs()),
+                });
+            }
+        }
+
+        bans
+    }
+
+    /// Writes the ban list to disk.
+    fn save_ban_list(&mut self) {
+        if let Err(e) = self.ban_list.save(Path::new(&self.enr_dir), unix_now()) {
+            warn!(self.log, "Could not write ban list"; "error" => e);
+        }
+    }
+
+    /// Writes the peer database to disk.
+    fn save_peer_db(&mut self) {
+        match self.peer_db.save(Path::new(&self.enr_dir), unix_now()) {
+            Ok(()) => {
+                debug!(self.log, "Peer database written to disk"; "peers" => self.peer_db.len())
+            }
+            Err(e) => warn!(self.log, "Could not write peer database"; "error" => e),
+        }
     }
 
     /// Search for new peers using the underlying discovery mechanism.
@@ -192,19 +525,54 @@ where
         self.discovery.addresses_of_peer(peer_id)
     }
 
-    fn inject_connected(&mut self, peer_id: PeerId, _endpoint: ConnectedPoint) {
-        self.connected_peers.insert(peer_id);
-        // TODO: Drop peers if over max_peer limit
+    fn inject_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
+        let address = match &endpoint {
+            ConnectedPoint::Dialer { address } => address,
+            ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+        };
+        let ip = multiaddr_ip(address);
+        if let Some(ip) = ip {
+            self.peer_ips.insert(peer_id.clone(), ip);
+        }
+
+        if let ConnectedPoint::Dialer { .. } = endpoint {
+            self.outbound_peers.insert(peer_id.clone());
+        }
+        self.connected_peers.insert(peer_id.clone());
+
+        // banned peers are disconnected without counting towards the peer limits
+        if self.is_operator_banned(&peer_id, ip) {
+            debug!(self.log, "Disconnecting banned peer"; "peer_id" => format!("{:?}", peer_id), "ip" => format!("{:?}", ip));
+            metrics::inc_counter(&metrics::BANNED_PEER_CONNECTIONS_REJECTED);
+            self.banned_connections.push(peer_id);
+        } else {
+            self.peer_db.on_connected(&peer_id, unix_now());
+            self.prune_excess_peers();
+        }
 
         metrics::inc_counter(&metrics::PEER_CONNECT_EVENT_COUNT);
-        metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
+        self.update_peer_metrics();
     }
 
     fn inject_disconnected(&mut self, peer_id: &PeerId, _endpoint: ConnectedPoint) {
         self.connected_peers.remove(peer_id);
+        self.peer_ips.remove(peer_id);
+        let was_outbound = self.outbound_peers.remove(peer_id);
+        self.peers_to_prune.retain(|pruned| pruned != peer_id);
+        self.banned_connections.retain(|banned| banned != peer_id);
+
+        // replace the lost outbound peer without waiting for the next scheduled search
+        if was_outbound && self.needs_outbound_peers() {
+            self.past_discovery_delay = INITIAL_SEARCH_DELAY;
+            self.peer_discovery_delay.reset(Instant::now());
+        }
 
         metrics::inc_counter(&metrics::PEER_DISCONNECT_EVENT_COUNT);
-        metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
+        self.update_peer_metrics();
+    }
+
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        self.peer_db.on_dial_failure(peer_id, unix_now());
     }
 
     fn inject_replaced(
@@ -237,7 +605,7 @@ where
         loop {
             match self.peer_discovery_delay.poll() {
                 Ok(Async::Ready(_)) => {
-                    if self.connected_peers.len() < self.max_peers {
+                    if self.needs_outbound_peers() {
                         self.find_peers();
                     }
                     // Set to maximum, and update to earlier, once we get our results back.
@@ -252,14 +620,46 @@ where
             }
         }
 
+        // persist the peer database if it is time
+        loop {
+            match self.peer_db_save_delay.poll() {
+                Ok(Async::Ready(_)) => {
+                    self.save_peer_db();
+                    self.peer_db_save_delay
+                        .reset(Instant::now() + Duration::from_secs(PEER_DB_SAVE_INTERVAL));
+                }
+                Ok(Async::NotReady) => break,
+                Err(e) => {
+                    warn!(self.log, "Peer database save delay failed"; "error" => format!("{:?}", e));
+                }
+            }
+        }
+
+        // dial the peers loaded from the peer database
+        while let Some(peer_id) = self.peers_to_dial.pop() {
+            if self.should_dial(&peer_id) {
+                debug!(self.log, "Dialing known peer"; "peer_id" => format!("{:?}", peer_id));
+                return Async::Ready(NetworkBehaviourAction::DialPeer { peer_id });
+            }
+        }
+
         // Poll discovery
         loop {
             match self.discovery.poll(params) {
                 Async::Ready(NetworkBehaviourAction::GenerateEvent(event)) => {
                     match event {
-                        Discv5Event::Discovered(_enr) => {
-                            // not concerned about FINDNODE results, rather the result of an entire
-                            // query.
+                        Discv5Event::Discovered(enr) => {
+                            let peer_id = enr.peer_id();
+                            // record the peer for future runs, but otherwise wait for the result
+                            // of the entire query. The ENR is recorded even if the peer is banned
+                            // or on another fork, since it is needed to exclude the peer from the
+                            // result.
+                            self.peer_db.insert_enr(enr, unix_now());
+
+                            if !self.is_fork_compatible(&peer_id) {
+                                metrics::inc_counter(&metrics::DISCOVERED_INCOMPATIBLE_PEERS);
+                                debug!(self.log, "Discovered peer on an incompatible fork"; "peer_id" => format!("{:?}", peer_id));
+                            }
                         }
                         Discv5Event::SocketUpdated(socket) => {
                             info!(self.log, "Address updated"; "ip" => format!("{}",socket.ip()), "udp_port" => format!("{}", socket.port()));
@@ -289,12 +689,10 @@ where
                             if closer_peers.is_empty() {
                                 debug!(self.log, "Discovery random query found no peers");
                             }
+                            // banned peers are skipped by `should_dial`
                             for peer_id in closer_peers {
                                 // if we need more peers, attempt a connection
-                                if self.connected_peers.len() < self.max_peers
-                                    && self.connected_peers.get(&peer_id).is_none()
-                                    && !self.banned_peers.contains(&peer_id)
-                                {
+                                if self.should_dial(&peer_id) {
                                     debug!(self.log, "Peer discovered"; "peer_id"=> format!("{:?}", peer_id));
                                     return Async::Ready(NetworkBehaviourAction::DialPeer {
                                         peer_id,
@@ -314,12 +712,19 @@ where
     }
 }
 
+impl<TSubstream> Drop for Discovery<TSubstream> {
+    fn drop(&mut self) {
+        self.save_peer_db();
+        self.save_ban_list();
+    }
+}
+
 /// Loads an ENR from file if it exists and matches the current NodeId and sequence number. If none
 /// exists, generates a new one.
 ///
 /// If an ENR exists, with the same NodeId and IP address, we use the disk-generated one as its
 /// ENR sequence will be equal or higher than a newly generated one.
-fn load_enr(
+pub(crate) fn load_enr(
     local_key: &Keypair,
     config: &NetworkConfig,
     log: &slog::Logger,
@@ -327,10 +732,15 @@ fn load_enr(
     // Build the local ENR.
     // Note: Discovery should update the ENR record's IP to the external IP as seen by the
     // majority of our peers.
-    let mut local_enr = EnrBuilder::new("v4")
+    let mut builder = EnrBuilder::new("v4");
+    builder
         .ip(config.discovery_address)
         .tcp(config.libp2p_port)
-        .udp(config.discovery_port)
+        .udp(config.discovery_port);
+    if let Some(fork_id) = &config.enr_fork_id {
+        builder.add_value(ETH2_ENR_KEY.into(), fork_id.as_ssz_bytes());
+    }
+    let mut local_enr = builder
         .build(&local_key)
         .map_err(|e| format!("Could not build Local ENR: {:?}", e))?;
 
@@ -346,6 +756,7 @@ fn load_enr(
                             if enr.ip().map(Into::into) == Some(config.discovery_address)
                                 && enr.tcp() == Some(config.libp2p_port)
                                 && enr.udp() == Some(config.discovery_port)
+                                && enr.get(ETH2_ENR_KEY) == local_enr.get(ETH2_ENR_KEY)
                             {
                                 debug!(log, "ENR loaded from file"; "file" => format!("{:?}", enr_f));
                                 // the stored ENR has the same configuration, use it
@@ -389,3 +800,168 @@ fn save_enr_to_disc(dir: &Path, enr: &Enr, log: &slog::Logger) {
         }
     }
 }
+
+/// Returns the IP address of a `/ip4` or `/ip6` multiaddr.
+fn multiaddr_ip(multiaddr: &Multiaddr) -> Option<IpAddr> {
+    match multiaddr.iter().next() {
+        Some(Protocol::Ip4(ip)) => Some(ip.into()),
+        Some(Protocol::Ip6(ip)) => Some(ip.into()),
+        _ => None,
+    }
+}
+
+/// The limits on the number of connected peers, derived from the `NetworkConfig`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerLimits {
+    pub max_peers: usize,
+    pub max_inbound_peers: usize,
+    pub max_outbound_peers: usize,
+    pub target_outbound_peers: usize,
+}
+
+impl PeerLimits {
+    fn from_config(config: &NetworkConfig) -> Self {
+        let max_peers = config.max_peers;
+        let max_outbound_peers = config.max_outbound_peers.unwrap_or(max_peers);
+
+        Self {
+            max_peers,
+            max_inbound_peers: config.max_inbound_peers.unwrap_or(max_peers),
+            max_outbound_peers,
+            target_outbound_peers: std::cmp::min(
+                config.target_outbound_peers.unwrap_or(max_peers),
+                max_outbound_peers,
+            ),
+        }
+    }
+}
+
+/// The number of connected peers in each direction, and the limits on them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerCounts {
+    pub connected: usize,
+    /// Peers which connected to us.
+    pub inbound: usize,
+    /// Peers which we dialed.
+    pub outbound: usize,
+    /// Connected peers which are never pruned.
+    pub trusted: usize,
+    pub max_peers: usize,
+    pub max_inbound_peers: usize,
+    pub max_outbound_peers: usize,
+    pub target_outbound_peers: usize,
+}
+
+/// Returns the `candidates` (tuples of peer id, whether it was dialed by us and its score) which
+/// should be disconnected so that no limit is exceeded.
+///
+/// `inbound` and `outbound` count all connected peers, including those which are not candidates
+/// (i.e., trusted peers). The lowest-scored peers in a direction which exceeds its limit are
+/// pruned first, then the lowest-scored peers overall until `max_peers` is respected.
+fn select_peers_to_prune(
+    mut candidates: Vec<(PeerId, bool, i64)>,
+    inbound: usize,
+    outbound: usize,
+    limits: &PeerLimits,
+) -> Vec<PeerId> {
+    let mut excess_inbound = inbound.saturating_sub(limits.max_inbound_peers);
+    let mut excess_outbound = outbound.saturating_sub(limits.max_outbound_peers);
+    let mut excess = (inbound + outbound).saturating_sub(limits.max_peers);
+
+    candidates.sort_by_key(|(_, _, score)| *score);
+
+    let mut pruned = vec![];
+    candidates.retain(|(peer_id, is_outbound, _)| {
+        let excess_in_direction = if *is_outbound {
+            &mut excess_outbound
+        } else {
+            &mut excess_inbound
+        };
+
+        if *excess_in_direction > 0 {
+            *excess_in_direction -= 1;
+            excess = excess.saturating_sub(1);
+            pruned.push(peer_id.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    pruned.extend(
+        candidates
+            .into_iter()
+            .take(excess)
+            .map(|(peer_id, _, _)| peer_id),
+    );
+
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> PeerLimits {
+        PeerLimits {
+            max_peers: 4,
+            max_inbound_peers: 3,
+            max_outbound_peers: 2,
+            target_outbound_peers: 2,
+        }
+    }
+
+    #[test]
+    fn prunes_nothing_within_limits() {
+        let candidates = vec![(PeerId::random(), false, 0), (PeerId::random(), true, 0)];
+        assert!(select_peers_to_prune(candidates, 2, 2, &limits()).is_empty());
+    }
+
+    #[test]
+    fn prunes_lowest_scored_peers() {
+        let (worst, bad, good, outbound) = (
+            PeerId::random(),
+            PeerId::random(),
+            PeerId::random(),
+            PeerId::random(),
+        );
+        let candidates = vec![
+            (good.clone(), false, 5),
+            (outbound.clone(), true, -10),
+            (bad.clone(), false, 1),
+            (worst.clone(), false, -1),
+        ];
+
+        // Too many inbound peers: the outbound peer is kept despite its low score.
+        assert_eq!(
+            select_peers_to_prune(candidates.clone(), 4, 0, &limits()),
+            vec![worst.clone()]
+        );
+
+        // Too many inbound peers and too many peers: pruning an inbound peer satisfies both.
+        assert_eq!(
+            select_peers_to_prune(candidates.clone(), 4, 1, &limits()),
+            vec![worst.clone()]
+        );
+
+        // Too many peers: the lowest-scored peers are pruned regardless of their direction.
+        assert_eq!(
+            select_peers_to_prune(candidates, 3, 2, &limits()),
+            vec![outbound]
+        );
+    }
+
+    #[test]
+    fn never_prunes_trusted_peers() {
+        // The other inbound peers are trusted, so they are not candidates but they count
+        // towards the limits.
+        let untrusted = PeerId::random();
+        let candidates = vec![(untrusted.clone(), false, 10)];
+
+        assert_eq!(
+            select_peers_to_prune(candidates, 4, 0, &limits()),
+            vec![untrusted]
+        );
+        assert!(select_peers_to_prune(vec![], 5, 0, &limits()).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+//! A service which runs only the discv5 discovery protocol, without any of the eth2 protocols.
+//!
+//! Used by boot nodes, which only help other nodes to find peers.
+use crate::service::{build_transport, load_keypair, Libp2pStream};
+use crate::{error, Enr, NetworkConfig};
+use futures::prelude::*;
+use libp2p::core::{muxing::StreamMuxerBox, nodes::Substream};
+use libp2p::discv5::{Discv5, Discv5Event};
+use libp2p::enr::{EnrBuilder, NodeId};
+use libp2p::{PeerId, Swarm};
+use slog::{debug, info};
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+/// Seconds between the random queries which keep the routing table fresh.
+const QUERY_INTERVAL: u64 = 60;
+
+/// Runs discv5 with a static ENR, answering the queries of other nodes.
+///
+/// The service never completes; drop it to stop discovery.
+pub struct Discv5Service {
+    swarm: Swarm<Libp2pStream, Discv5<Substream<StreamMuxerBox>>>,
+    /// The delay until the next random query.
+    query_delay: Delay,
+    log: slog::Logger,
+}
+
+impl Discv5Service {
+    /// Starts discv5, listening on the `listen_address` and `discovery_port` of `config`.
+    ///
+    /// The local ENR advertises the `discovery_address` and `discovery_port` and is not updated
+    /// with the address observed by other nodes. It has no TCP port, since the service does not
+    /// accept libp2p connections. The `boot_nodes` of `config` are added to the routing table.
+    pub fn new(config: &NetworkConfig, log: slog::Logger) -> error::Result<Self> {
+        let local_keypair = load_keypair(config, &log)?;
+
+        let local_enr = EnrBuilder::new("v4")
+            .ip(config.discovery_address)
+            .udp(config.discovery_port)
+            .build(&local_keypair)
+            .map_err(|e| format!("Could not build local ENR: {:?}", e))?;
+
+        info!(
+            log,
+            "ENR Initialised";
+            "enr" => local_enr.to_base64(),
+            "node_id" => format!("{}", local_enr.node_id()),
+            "ip" => format!("{}", config.discovery_address),
+            "udp_port" => config.discovery_port,
+        );
+
+        let mut discovery = Discv5::new(
+            local_enr,
+            local_keypair.clone(),
+            config.listen_address,
+            false,
+        )
+        .map_err(|e| format!("Discv5 service failed. Error: {:?}", e))?;
+
+        for enr in config.boot_nodes.clone() {
+            debug!(log, "Adding node to routing table"; "node_id" => format!("{}", enr.node_id()));
+            discovery.add_enr(enr);
+        }
+
+        // The transport is required by the swarm, but is never used since discv5 communicates
+        // over its own UDP socket.
+        let local_peer_id = PeerId::from(local_keypair.public());
+        let swarm = Swarm::new(build_transport(local_keypair), discovery, local_peer_id);
+
+        Ok(Self {
+            swarm,
+            query_delay: Delay::new(Instant::now()),
+            log,
+        })
+    }
+
+    /// Returns the ENR advertised by the service.
+    pub fn local_enr(&self) -> &Enr {
+        self.swarm.local_enr()
+    }
+}
+
+impl Future for Discv5Service {
+    type Item = ();
+    type Error = error::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        while let Async::Ready(()) = self
+            .query_delay
+            .poll()
+            .map_err(|e| format!("Discovery query delay failed: {:?}", e))?
+        {
+            debug!(self.log, "Searching for peers");
+            self.swarm.find_node(NodeId::random());
+            self.query_delay
+                .reset(Instant::now() + Duration::from_secs(QUERY_INTERVAL));
+        }
+
+        loop {
+            match self
+                .swarm
+                .poll()
+                .map_err(|e| format!("Discovery failed: {:?}", e))?
+            {
+                Async::Ready(Some(event)) => match event {
+                    Discv5Event::Discovered(enr) => {
+                        debug!(self.log, "Node discovered"; "node_id" => format!("{}", enr.node_id()));
+                    }
+                    Discv5Event::SocketUpdated(socket) => {
+                        debug!(
+                            self.log,
+                            "Ignoring observed address, the ENR is static";
+                            "address" => format!("{}", socket)
+                        );
+                    }
+                    Discv5Event::FindNodeResult { closer_peers, .. } => {
+                        debug!(self.log, "Discovery query completed"; "peers_found" => closer_peers.len());
+                    }
+                    _ => {}
+                },
+                Async::Ready(None) => {
+                    return Err("Discovery stream ended".into());
+                }
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
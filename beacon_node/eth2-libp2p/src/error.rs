@@ -1,5 +1,12 @@
 // generates error types
 
 use error_chain::error_chain;
+use lighthouse_error::{CodedError, ErrorCode};
 
 error_chain! {}
+
+impl CodedError for Error {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(3000)
+    }
+}
@@ -0,0 +1,103 @@
+//! Deterministic entrypoints for decoding untrusted network messages, for use by fuzzers.
+//!
+//! Only available with the `fuzz` feature.
+use crate::{PubsubMessage, TopicHash};
+use ssz::{Decode, DecodeError};
+use types::{
+    Attestation, AttesterSlashing, BeaconBlock, EthSpec, ProposerSlashing, SignedAggregateAndProof,
+    VoluntaryExit,
+};
+
+pub use crate::rpc::decode_rpc_request;
+
+/// A gossip message decoded by `decode_gossip_message`.
+#[derive(Debug)]
+pub enum GossipMessage<E: EthSpec> {
+    Block(Box<BeaconBlock<E>>),
+    BlockAndWitness(Box<BeaconBlock<E>>),
+    Attestation(Box<Attestation<E>>),
+    AggregateAndProof(Box<SignedAggregateAndProof<E>>),
+    VoluntaryExit(Box<VoluntaryExit>),
+    ProposerSlashing(Box<ProposerSlashing>),
+    AttesterSlashing(Box<AttesterSlashing<E>>),
+    /// The message was received on a topic which is not known.
+    Unknown,
+}
+
+/// Decodes the `bytes` of a gossip message received on `topic` (e.g.,
+/// `/eth2/beacon_block/ssz`), as done by the network service for messages received from peers.
+pub fn decode_gossip_message<E: EthSpec>(
+    topic: &str,
+    bytes: &[u8],
+) -> Result<GossipMessage<E>, DecodeError> {
+    let topics = [TopicHash::from_raw(topic)];
+
+    Ok(match PubsubMessage::from_topics(&topics, bytes.to_vec()) {
+        PubsubMessage::Block(data) => {
+            GossipMessage::Block(Box::new(BeaconBlock::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::BlockAndWitness(data) => {
+            GossipMessage::BlockAndWitness(Box::new(BeaconBlock::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::Attestation(data) => {
+            GossipMessage::Attestation(Box::new(Attestation::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::AggregateAndProof(data) => GossipMessage::AggregateAndProof(Box::new(
+            SignedAggregateAndProof::from_ssz_bytes(&data)?,
+        )),
+        PubsubMessage::VoluntaryExit(data) => {
+            GossipMessage::VoluntaryExit(Box::new(VoluntaryExit::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::ProposerSlashing(data) => {
+            GossipMessage::ProposerSlashing(Box::new(ProposerSlashing::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::AttesterSlashing(data) => {
+            GossipMessage::AttesterSlashing(Box::new(AttesterSlashing::from_ssz_bytes(&data)?))
+        }
+        PubsubMessage::Unknown(_) => GossipMessage::Unknown,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::Encode;
+    use types::{Epoch, EthSpec, MinimalEthSpec, Signature};
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn decodes_gossip_by_topic() {
+        let exit = VoluntaryExit {
+            epoch: Epoch::new(1),
+            validator_index: 2,
+            signature: Signature::empty_signature(),
+        };
+        let bytes = exit.as_ssz_bytes();
+
+        match decode_gossip_message::<E>("/eth2/voluntary_exit/ssz", &bytes) {
+            Ok(GossipMessage::VoluntaryExit(decoded)) => assert_eq!(*decoded, exit),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(decode_gossip_message::<E>("/eth2/beacon_block/ssz", &bytes).is_err());
+        match decode_gossip_message::<E>("/eth2/unknown/ssz", &bytes) {
+            Ok(GossipMessage::Unknown) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let block = BeaconBlock::<E>::empty(&E::default_spec());
+        match decode_gossip_message::<E>(
+            "/eth2/beacon_block_and_witness/ssz",
+            &block.as_ssz_bytes(),
+        ) {
+            Ok(GossipMessage::BlockAndWitness(decoded)) => assert_eq!(*decoded, block),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_rpc_request() {
+        assert!(decode_rpc_request(&[]).expect("should decode").is_none());
+        assert!(decode_rpc_request(&[0, 1, 0xff]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! The eth2 gossip message-id and the suppression of duplicate gossip messages.
+//!
+//! Gossipsub identifies a message by its source and sequence number, so the same payload
+//! published by two peers (or republished by another client) is seen as two messages. The eth2
+//! spec instead identifies a message by the hash of its payload, which is used here to drop
+//! duplicates before they reach the beacon chain.
+use crate::config::GOSSIP_SNAPPY_ENCODING_POSTFIX;
+use crate::TopicHash;
+use eth2_hashing::hash;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Returns the spec's message-id of a gossip message: the URL-safe base64 encoding of the
+/// SHA256 hash of its payload.
+///
+/// The payload of a message on a snappy-encoded topic is decompressed before it is hashed, so
+/// that the id does not depend on the compression. If it cannot be decompressed, the raw data is
+/// hashed instead.
+pub fn gossip_message_id(topics: &[TopicHash], data: &[u8]) -> String {
+    let is_snappy = topics.iter().any(|topic| {
+        topic
+            .as_str()
+            .ends_with(&format!("/{}", GOSSIP_SNAPPY_ENCODING_POSTFIX))
+    });
+
+    let digest = if is_snappy {
+        match snap::raw::Decoder::new().decompress_vec(data) {
+            Ok(payload) => hash(&payload),
+            Err(_) => hash(data),
+        }
+    } else {
+        hash(data)
+    };
+
+    base64::encode_config(&digest, base64::URL_SAFE)
+}
+
+/// The message-ids of the gossip messages received within the last `ttl`.
+pub struct SeenMessages {
+    ttl: Duration,
+    ids: HashSet<String>,
+    /// The ids in `ids`, in the order in which they were received.
+    expiries: VecDeque<(Instant, String)>,
+}
+
+impl SeenMessages {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            ids: HashSet::new(),
+            expiries: VecDeque::new(),
+        }
+    }
+
+    /// Records that the message with `id` was received at `now`, returning `false` if it was
+    /// already received within the last `ttl`.
+    pub fn observe(&mut self, id: String, now: Instant) -> bool {
+        while let Some((expiry, _)) = self.expiries.front() {
+            if *expiry > now {
+                break;
+            }
+            if let Some((_, expired_id)) = self.expiries.pop_front() {
+                self.ids.remove(&expired_id);
+            }
+        }
+
+        if self.ids.contains(&id) {
+            return false;
+        }
+
+        self.ids.insert(id.clone());
+        self.expiries.push_back((now + self.ttl, id));
+        true
+    }
+
+    /// The number of messages received within the last `ttl`.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if no messages were received within the last `ttl`.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TOPIC_ENCODING_POSTFIX;
+    use crate::Topic;
+
+    fn topic_hash(encoding: &str) -> TopicHash {
+        Topic::new(format!("/eth2/beacon_block/{}", encoding)).no_hash()
+    }
+
+    #[test]
+    fn message_id_is_hash_of_payload() {
+        let payload = vec![42; 100];
+        let ssz = [topic_hash(TOPIC_ENCODING_POSTFIX)];
+        let snappy = [topic_hash(GOSSIP_SNAPPY_ENCODING_POSTFIX)];
+        let compressed = snap::raw::Encoder::new()
+            .compress_vec(&payload)
+            .expect("should compress");
+
+        let id = gossip_message_id(&ssz, &payload);
+        assert_eq!(id, base64::encode_config(&hash(&payload), base64::URL_SAFE));
+        assert_eq!(gossip_message_id(&snappy, &compressed), id);
+        assert_ne!(gossip_message_id(&ssz, &compressed), id);
+        assert_ne!(gossip_message_id(&ssz, &[42; 99]), id);
+    }
+
+    #[test]
+    fn suppresses_duplicates_until_expiry() {
+        let ttl = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut seen = SeenMessages::new(ttl);
+
+        assert!(seen.observe("a".into(), start));
+        assert!(seen.observe("b".into(), start + Duration::from_secs(5)));
+        assert!(!seen.observe("a".into(), start + Duration::from_secs(9)));
+        assert_eq!(seen.len(), 2);
+
+        assert!(seen.observe("a".into(), start + ttl));
+        assert!(!seen.observe("b".into(), start + ttl));
+        assert_eq!(seen.len(), 2);
+    }
+}
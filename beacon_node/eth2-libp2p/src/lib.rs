@@ -5,19 +5,32 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod address;
+mod ban_list;
 pub mod behaviour;
 mod config;
 mod discovery;
+mod discv5_service;
 pub mod error;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+mod gossip;
 mod metrics;
+mod peer_db;
 pub mod rpc;
 mod service;
 
+pub use ban_list::{Ban, BanTarget};
 pub use behaviour::PubsubMessage;
 pub use config::{
-    Config as NetworkConfig, BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC, SHARD_TOPIC_PREFIX,
-    TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
+    Config as NetworkConfig, GossipsubParams, ATTESTER_SLASHING_TOPIC,
+    BEACON_AGGREGATE_AND_PROOF_TOPIC, BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_AND_WITNESS_TOPIC,
+    BEACON_BLOCK_TOPIC, PROPOSER_SLASHING_TOPIC, SHARD_TOPIC_PREFIX, TOPIC_ENCODING_POSTFIX,
+    TOPIC_PREFIX, VOLUNTARY_EXIT_TOPIC,
 };
+pub use discovery::PeerCounts;
+pub use discv5_service::Discv5Service;
+pub use gossip::gossip_message_id;
 pub use libp2p::enr::Enr;
 pub use libp2p::gossipsub::{Topic, TopicHash};
 pub use libp2p::multiaddr;
@@ -28,4 +41,4 @@ pub use libp2p::{
 };
 pub use rpc::RPCEvent;
 pub use service::Libp2pEvent;
-pub use service::Service;
+pub use service::{load_local_enr, Service};
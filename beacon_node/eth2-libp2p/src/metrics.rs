@@ -9,6 +9,38 @@ lazy_static! {
         "libp2p_peer_connected_peers_total",
         "Count of libp2p peers currently connected"
     );
+    pub static ref INBOUND_PEERS_CONNECTED: Result<IntGauge> = try_create_int_gauge(
+        "libp2p_peer_connected_inbound_peers_total",
+        "Count of libp2p peers currently connected which connected to us"
+    );
+    pub static ref OUTBOUND_PEERS_CONNECTED: Result<IntGauge> = try_create_int_gauge(
+        "libp2p_peer_connected_outbound_peers_total",
+        "Count of libp2p peers currently connected which we dialed"
+    );
+    pub static ref PEERS_PRUNED_COUNT: Result<IntCounter> = try_create_int_counter(
+        "libp2p_peer_pruned_total",
+        "Count of libp2p peers disconnected to respect the peer limits"
+    );
+    pub static ref DISCOVERED_INCOMPATIBLE_PEERS: Result<IntCounter> = try_create_int_counter(
+        "libp2p_discovered_incompatible_peers_total",
+        "Count of peers discovered with an ENR fork ID incompatible with the local one, which are not dialed"
+    );
+    pub static ref BANNED_PEER_CONNECTIONS_REJECTED: Result<IntCounter> = try_create_int_counter(
+        "libp2p_banned_peer_connections_rejected_total",
+        "Count of libp2p connections closed because the peer or its IP address is banned"
+    );
+    pub static ref GOSSIP_DUPLICATE_MESSAGES: Result<IntCounter> = try_create_int_counter(
+        "libp2p_gossip_duplicate_messages_total",
+        "Count of gossip messages dropped because a message with the same message-id was received"
+    );
+    pub static ref RPC_INBOUND_STREAMS_REJECTED: Result<IntCounter> = try_create_int_counter(
+        "libp2p_rpc_inbound_streams_rejected_total",
+        "Count of inbound RPC requests refused because the peer had too many streams open"
+    );
+    pub static ref RPC_INBOUND_STREAMS_TIMED_OUT: Result<IntCounter> = try_create_int_counter(
+        "libp2p_rpc_inbound_streams_timed_out_total",
+        "Count of inbound RPC streams dropped because a response chunk was not sent in time"
+    );
     pub static ref PEER_CONNECT_EVENT_COUNT: Result<IntCounter> = try_create_int_counter(
         "libp2p_peer_connect_event_total",
         "Count of libp2p peer connect events (not the current number of connected peers)"
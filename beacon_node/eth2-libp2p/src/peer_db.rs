@@ -0,0 +1,392 @@
+/// A database of the peers known to the node, persisted to disk so that the node can reconnect to
+/// good peers quickly after a restart, instead of rediscovering them from the boot nodes.
+///
+/// For each peer, the database records its ENR (if known), when it was last seen, how many times
+/// it has been dialed successfully or unsuccessfully and until when it is banned.
+use libp2p::enr::Enr;
+use libp2p::PeerId;
+use slog::{debug, warn};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Peer database storage filename.
+pub const PEER_DB_FILENAME: &str = "peers.dat";
+/// The maximum number of peers retained in the database. The worst peers are dropped first.
+pub const MAX_PEER_DB_SIZE: usize = 1_000;
+
+/// The history of a single peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerRecord {
+    /// The ENR of the peer, if it has been discovered. Peers without an ENR cannot be dialed.
+    pub enr: Option<Enr>,
+    /// The unix time (in seconds) at which the peer was last discovered or connected.
+    pub last_seen: u64,
+    /// The number of connections established with the peer.
+    pub successful_dials: u64,
+    /// The number of times dialing the peer has failed.
+    pub failed_dials: u64,
+    /// The unix time (in seconds) until which the peer is banned. Zero if it has never been
+    /// banned.
+    pub banned_until: u64,
+}
+
+impl PeerRecord {
+    fn new(now: u64) -> Self {
+        Self {
+            enr: None,
+            last_seen: now,
+            successful_dials: 0,
+            failed_dials: 0,
+            banned_until: 0,
+        }
+    }
+
+    /// The score of the peer. Peers with a higher score are dialed first.
+    pub fn score(&self) -> i64 {
+        self.successful_dials as i64 - self.failed_dials as i64
+    }
+
+    /// Returns `true` if the peer is banned at the unix time `now`.
+    pub fn is_banned(&self, now: u64) -> bool {
+        self.banned_until > now
+    }
+}
+
+/// The peers known to the node, keyed by `PeerId`.
+#[derive(Debug, Default)]
+pub struct PeerDB {
+    peers: HashMap<PeerId, PeerRecord>,
+}
+
+impl PeerDB {
+    /// Loads the database stored in `dir`, returning an empty database if there is none or it
+    /// cannot be decoded.
+    pub fn open(dir: &Path, log: &slog::Logger) -> Self {
+        let path = dir.join(PEER_DB_FILENAME);
+
+        let mut bytes = vec![];
+        match File::open(&path).and_then(|mut f| f.read_to_end(&mut bytes)) {
+            Ok(_) => match SszPeerDB::from_ssz_bytes(&bytes) {
+                Ok(ssz_container) => {
+                    let db = Self::from_ssz_container(ssz_container);
+                    debug!(log, "Peer database loaded"; "file" => format!("{:?}", path), "peers" => db.len());
+                    db
+                }
+                Err(e) => {
+                    warn!(log, "Peer database could not be decoded"; "error" => format!("{:?}", e));
+                    Self::default()
+                }
+            },
+            Err(_) => {
+                debug!(log, "No peer database found"; "file" => format!("{:?}", path));
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the database to `dir`, replacing any database stored previously.
+    ///
+    /// Expired bans of peers without an ENR are dropped, and only the best `MAX_PEER_DB_SIZE`
+    /// peers are retained.
+    pub fn save(&mut self, dir: &Path, now: u64) -> Result<(), String> {
+        self.prune(now);
+
+        let _ = std::fs::create_dir_all(dir);
+        File::create(dir.join(PEER_DB_FILENAME))
+            .and_then(|mut f| f.write_all(&self.to_ssz_container().as_ssz_bytes()))
+            .map_err(|e| format!("Could not write peer database: {}", e))
+    }
+
+    /// The number of peers in the database.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Returns `true` if the database contains no peers.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Returns the record of the peer with `peer_id`, if it is known.
+    pub fn get(&self, peer_id: &PeerId) -> Option<&PeerRecord> {
+        self.peers.get(peer_id)
+    }
+
+    /// Records that `enr` was discovered at `now`, replacing any older ENR of the same peer.
+    pub fn insert_enr(&mut self, enr: Enr, now: u64) {
+        let peer_id = enr.peer_id();
+        let record = self
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| PeerRecord::new(now));
+
+        if record
+            .enr
+            .as_ref()
+            .map_or(true, |old| old.seq() <= enr.seq())
+        {
+            record.enr = Some(enr);
+        }
+        record.last_seen = now;
+    }
+
+    /// Records that a connection was established with `peer_id` at `now`.
+    pub fn on_connected(&mut self, peer_id: &PeerId, now: u64) {
+        let record = self.record_mut(peer_id, now);
+        record.successful_dials += 1;
+        record.last_seen = now;
+    }
+
+    /// Records that dialing `peer_id` failed.
+    pub fn on_dial_failure(&mut self, peer_id: &PeerId, now: u64) {
+        self.record_mut(peer_id, now).failed_dials += 1;
+    }
+
+    /// Records that `peer_id` is banned until the unix time `until`.
+    pub fn ban(&mut self, peer_id: &PeerId, until: u64, now: u64) {
+        self.record_mut(peer_id, now).banned_until = until;
+    }
+
+    /// Records that the ban of `peer_id` has been lifted.
+    pub fn unban(&mut self, peer_id: &PeerId) {
+        if let Some(record) = self.peers.get_mut(peer_id) {
+            record.banned_until = 0;
+        }
+    }
+
+    /// Returns the ENRs of at most `count` peers which are not banned at `now` and have not failed
+    /// more dials than they have succeeded, best first.
+    pub fn good_peers(&self, now: u64, count: usize) -> Vec<Enr> {
+        let mut peers = self
+            .peers
+            .values()
+            .filter(|record| !record.is_banned(now) && record.score() >= 0)
+            .filter_map(|record| record.enr.as_ref().map(|enr| (record, enr)))
+            .collect::<Vec<_>>();
+
+        peers.sort_by(|(a, _), (b, _)| (b.score(), b.last_seen).cmp(&(a.score(), a.last_seen)));
+
+        peers
+            .into_iter()
+            .take(count)
+            .map(|(_, enr)| enr.clone())
+            .collect()
+    }
+
+    /// Returns the peers which are banned at `now`, with the remaining duration of each ban.
+    pub fn banned_peers(&self, now: u64) -> Vec<(PeerId, Duration)> {
+        self.peers
+            .iter()
+            .filter(|(_, record)| record.is_banned(now))
+            .map(|(peer_id, record)| {
+                (
+                    peer_id.clone(),
+                    Duration::from_secs(record.banned_until - now),
+                )
+            })
+            .collect()
+    }
+
+    fn record_mut(&mut self, peer_id: &PeerId, now: u64) -> &mut PeerRecord {
+        self.peers
+            .entry(peer_id.clone())
+            .or_insert_with(|| PeerRecord::new(now))
+    }
+
+    /// Drops peers which can neither be dialed nor are banned, then the worst peers until no
+    /// more than `MAX_PEER_DB_SIZE` remain. Banned peers are dropped last.
+    fn prune(&mut self, now: u64) {
+        self.peers
+            .retain(|_, record| record.enr.is_some() || record.is_banned(now));
+
+        if self.peers.len() > MAX_PEER_DB_SIZE {
+            let mut ranked = self
+                .peers
+                .iter()
+                .map(|(peer_id, record)| {
+                    (
+                        (record.is_banned(now), record.score(), record.last_seen),
+                        peer_id.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+            for (_, peer_id) in ranked.into_iter().skip(MAX_PEER_DB_SIZE) {
+                self.peers.remove(&peer_id);
+            }
+        }
+    }
+
+    /// Returns a `SszPeerDB`, which contains all necessary information to restore the state of
+    /// `Self` at some later point.
+    fn to_ssz_container(&self) -> SszPeerDB {
+        SszPeerDB {
+            peers: self
+                .peers
+                .iter()
+                .map(|(peer_id, record)| SszPeerRecord {
+                    peer_id: peer_id.clone().into_bytes(),
+                    enr: record
+                        .enr
+                        .as_ref()
+                        .map(|enr| enr.to_base64().into_bytes())
+                        .unwrap_or_default(),
+                    last_seen: record.last_seen,
+                    successful_dials: record.successful_dials,
+                    failed_dials: record.failed_dials,
+                    banned_until: record.banned_until,
+                })
+                .collect(),
+        }
+    }
+
+    /// Creates a new `Self` from the given `SszPeerDB`, skipping any peers which cannot be
+    /// decoded.
+    fn from_ssz_container(ssz_container: SszPeerDB) -> Self {
+        let peers = ssz_container
+            .peers
+            .into_iter()
+            .filter_map(|ssz_record| {
+                let peer_id = PeerId::from_bytes(ssz_record.peer_id).ok()?;
+                let enr = if ssz_record.enr.is_empty() {
+                    None
+                } else {
+                    let enr = Enr::from_str(&String::from_utf8(ssz_record.enr).ok()?).ok()?;
+                    Some(enr).filter(|enr| enr.peer_id() == peer_id)
+                };
+
+                Some((
+                    peer_id,
+                    PeerRecord {
+                        enr,
+                        last_seen: ssz_record.last_seen,
+                        successful_dials: ssz_record.successful_dials,
+                        failed_dials: ssz_record.failed_dials,
+                        banned_until: ssz_record.banned_until,
+                    },
+                ))
+            })
+            .collect();
+
+        Self { peers }
+    }
+}
+
+/// Returns the current unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Helper struct that is used to encode/decode the `PeerDB` as SSZ bytes.
+#[derive(Encode, Decode)]
+struct SszPeerDB {
+    peers: Vec<SszPeerRecord>,
+}
+
+/// A `PeerRecord` as SSZ. The ENR is base64 encoded, or empty if it is not known.
+#[derive(Encode, Decode)]
+struct SszPeerRecord {
+    peer_id: Vec<u8>,
+    enr: Vec<u8>,
+    last_seen: u64,
+    successful_dials: u64,
+    failed_dials: u64,
+    banned_until: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::core::identity::Keypair;
+    use libp2p::enr::EnrBuilder;
+    use slog::{o, Drain};
+
+    fn get_logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard.fuse(), o!())
+    }
+
+    fn get_enr(port: u16) -> Enr {
+        EnrBuilder::new("v4")
+            .ip("127.0.0.1".parse().unwrap())
+            .tcp(port)
+            .udp(port)
+            .build(&Keypair::generate_secp256k1())
+            .unwrap()
+    }
+
+    #[test]
+    fn ranks_good_peers() {
+        let mut db = PeerDB::default();
+        let (good, new, bad, banned) = (get_enr(1), get_enr(2), get_enr(3), get_enr(4));
+
+        for enr in &[good.clone(), new.clone(), bad.clone(), banned.clone()] {
+            db.insert_enr(enr.clone(), 10);
+        }
+        db.on_connected(&good.peer_id(), 20);
+        db.on_connected(&banned.peer_id(), 15);
+        db.on_dial_failure(&bad.peer_id(), 20);
+        db.ban(&banned.peer_id(), 100, 15);
+        // Peers without an ENR cannot be dialed.
+        db.on_connected(&PeerId::random(), 20);
+
+        assert_eq!(db.good_peers(30, 10), vec![good.clone(), new.clone()]);
+        assert_eq!(db.good_peers(30, 1), vec![good.clone()]);
+        assert_eq!(
+            db.banned_peers(30),
+            vec![(banned.peer_id(), Duration::from_secs(70))]
+        );
+
+        // The ban expires.
+        assert_eq!(db.good_peers(100, 10), vec![good, banned, new]);
+        assert!(db.banned_peers(100).is_empty());
+    }
+
+    #[test]
+    fn unbans_peers() {
+        let mut db = PeerDB::default();
+        let enr = get_enr(1);
+
+        db.insert_enr(enr.clone(), 10);
+        db.ban(&enr.peer_id(), 100, 10);
+        assert!(db.good_peers(10, 10).is_empty());
+
+        db.unban(&enr.peer_id());
+        assert_eq!(db.good_peers(10, 10), vec![enr]);
+    }
+
+    #[test]
+    fn persists_to_disk() {
+        let dir = std::env::temp_dir().join(format!("peer_db_test_{}", unix_now()));
+        let log = get_logger();
+        let enr = get_enr(1);
+        let banned_peer = PeerId::random();
+        let forgotten_peer = PeerId::random();
+
+        assert!(PeerDB::open(&dir, &log).is_empty());
+
+        let mut db = PeerDB::default();
+        db.insert_enr(enr.clone(), 10);
+        db.on_connected(&enr.peer_id(), 20);
+        db.ban(&banned_peer, 100, 20);
+        db.on_connected(&forgotten_peer, 20);
+        db.save(&dir, 30).unwrap();
+
+        let loaded = PeerDB::open(&dir, &log);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(&enr.peer_id()), db.get(&enr.peer_id()));
+        assert_eq!(loaded.get(&banned_peer), db.get(&banned_peer));
+        assert_eq!(loaded.get(&forgotten_peer), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
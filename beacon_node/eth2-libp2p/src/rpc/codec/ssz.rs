@@ -2,7 +2,8 @@ use crate::rpc::methods::*;
 use crate::rpc::{
     codec::base::OutboundCodec,
     protocol::{
-        ProtocolId, RPCError, RPC_BLOCKS_BY_RANGE, RPC_BLOCKS_BY_ROOT, RPC_GOODBYE, RPC_STATUS,
+        ProtocolId, RPCError, Version, RPC_BLOCKS_BY_RANGE, RPC_BLOCKS_BY_ROOT, RPC_GOODBYE,
+        RPC_STATUS,
     },
 };
 use crate::rpc::{ErrorMessage, RPCErrorResponse, RPCRequest, RPCResponse};
@@ -11,6 +12,10 @@ use ssz::{Decode, Encode};
 use tokio::codec::{Decoder, Encoder};
 use unsigned_varint::codec::UviBytes;
 
+/// The length of the fork version which prefixes each block sent over version 2 of the block
+/// protocols.
+const FORK_VERSION_LEN: usize = 4;
+
 /* Inbound Codec */
 
 pub struct SSZInboundCodec {
@@ -40,15 +45,15 @@ impl Encoder for SSZInboundCodec {
 
     fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
         let bytes = match item {
-            RPCErrorResponse::Success(resp) => {
-                match resp {
-                    RPCResponse::Status(res) => res.as_ssz_bytes(),
-                    RPCResponse::BlocksByRange(res) => res, // already raw bytes
-                    RPCResponse::BlocksByRoot(res) => res,  // already raw bytes
-                }
-            }
+            RPCErrorResponse::Success(resp) => match resp {
+                RPCResponse::Status(res) => res.as_ssz_bytes(),
+                RPCResponse::BlocksByRange(res) => encode_block_response(&self.protocol, res)?,
+                RPCResponse::BlocksByRoot(res) => encode_block_response(&self.protocol, res)?,
+            },
             RPCErrorResponse::InvalidRequest(err) => err.as_ssz_bytes(),
             RPCErrorResponse::ServerError(err) => err.as_ssz_bytes(),
+            RPCErrorResponse::ResourceUnavailable(err) => err.as_ssz_bytes(),
+            RPCErrorResponse::RateLimited(err) => err.as_ssz_bytes(),
             RPCErrorResponse::Unknown(err) => err.as_ssz_bytes(),
             RPCErrorResponse::StreamTermination(_) => {
                 unreachable!("Code error - attempting to encode a stream termination")
@@ -77,29 +82,30 @@ impl Decoder for SSZInboundCodec {
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         match self.inner.decode(src).map_err(RPCError::from) {
             Ok(Some(packet)) => match self.protocol.message_name.as_str() {
-                RPC_STATUS => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
+                RPC_STATUS => match self.protocol.version {
+                    Version::V1 => Ok(Some(RPCRequest::Status(StatusMessage::from_ssz_bytes(
                         &packet,
                     )?))),
                     _ => unreachable!("Cannot negotiate an unknown version"),
                 },
-                RPC_GOODBYE => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCRequest::Goodbye(GoodbyeReason::from_ssz_bytes(
+                RPC_GOODBYE => match self.protocol.version {
+                    Version::V1 => Ok(Some(RPCRequest::Goodbye(GoodbyeReason::from_ssz_bytes(
                         &packet,
                     )?))),
                     _ => unreachable!("Cannot negotiate an unknown version"),
                 },
-                RPC_BLOCKS_BY_RANGE => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCRequest::BlocksByRange(
+                // The block requests are the same in both versions.
+                RPC_BLOCKS_BY_RANGE => match self.protocol.version {
+                    Version::V1 | Version::V2 => Ok(Some(RPCRequest::BlocksByRange(
                         BlocksByRangeRequest::from_ssz_bytes(&packet)?,
                     ))),
-                    _ => unreachable!("Cannot negotiate an unknown version"),
                 },
-                RPC_BLOCKS_BY_ROOT => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
-                        block_roots: Vec::from_ssz_bytes(&packet)?,
-                    }))),
-                    _ => unreachable!("Cannot negotiate an unknown version"),
+                RPC_BLOCKS_BY_ROOT => match self.protocol.version {
+                    Version::V1 | Version::V2 => {
+                        Ok(Some(RPCRequest::BlocksByRoot(BlocksByRootRequest {
+                            block_roots: Vec::from_ssz_bytes(&packet)?,
+                        })))
+                    }
                 },
                 _ => unreachable!("Cannot negotiate an unknown protocol"),
             },
@@ -165,21 +171,15 @@ impl Decoder for SSZOutboundCodec {
             // clear the buffer and return an empty object
             src.clear();
             match self.protocol.message_name.as_str() {
-                RPC_STATUS => match self.protocol.version.as_str() {
-                    "1" => Err(RPCError::Custom(
+                RPC_STATUS => match self.protocol.version {
+                    Version::V1 => Err(RPCError::Custom(
                         "Status stream terminated unexpectedly".into(),
                     )), // cannot have an empty HELLO message. The stream has terminated unexpectedly
                     _ => unreachable!("Cannot negotiate an unknown version"),
                 },
                 RPC_GOODBYE => Err(RPCError::InvalidProtocol("GOODBYE doesn't have a response")),
-                RPC_BLOCKS_BY_RANGE => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCResponse::BlocksByRange(Vec::new()))),
-                    _ => unreachable!("Cannot negotiate an unknown version"),
-                },
-                RPC_BLOCKS_BY_ROOT => match self.protocol.version.as_str() {
-                    "1" => Ok(Some(RPCResponse::BlocksByRoot(Vec::new()))),
-                    _ => unreachable!("Cannot negotiate an unknown version"),
-                },
+                RPC_BLOCKS_BY_RANGE => Ok(Some(RPCResponse::BlocksByRange(BlockResponse::empty()))),
+                RPC_BLOCKS_BY_ROOT => Ok(Some(RPCResponse::BlocksByRoot(BlockResponse::empty()))),
                 _ => unreachable!("Cannot negotiate an unknown protocol"),
             }
         } else {
@@ -189,23 +189,21 @@ impl Decoder for SSZOutboundCodec {
                     let raw_bytes = packet.take();
 
                     match self.protocol.message_name.as_str() {
-                        RPC_STATUS => match self.protocol.version.as_str() {
-                            "1" => Ok(Some(RPCResponse::Status(StatusMessage::from_ssz_bytes(
-                                &raw_bytes,
-                            )?))),
+                        RPC_STATUS => match self.protocol.version {
+                            Version::V1 => Ok(Some(RPCResponse::Status(
+                                StatusMessage::from_ssz_bytes(&raw_bytes)?,
+                            ))),
                             _ => unreachable!("Cannot negotiate an unknown version"),
                         },
                         RPC_GOODBYE => {
                             Err(RPCError::InvalidProtocol("GOODBYE doesn't have a response"))
                         }
-                        RPC_BLOCKS_BY_RANGE => match self.protocol.version.as_str() {
-                            "1" => Ok(Some(RPCResponse::BlocksByRange(raw_bytes.to_vec()))),
-                            _ => unreachable!("Cannot negotiate an unknown version"),
-                        },
-                        RPC_BLOCKS_BY_ROOT => match self.protocol.version.as_str() {
-                            "1" => Ok(Some(RPCResponse::BlocksByRoot(raw_bytes.to_vec()))),
-                            _ => unreachable!("Cannot negotiate an unknown version"),
-                        },
+                        RPC_BLOCKS_BY_RANGE => Ok(Some(RPCResponse::BlocksByRange(
+                            decode_block_response(&self.protocol, &raw_bytes)?,
+                        ))),
+                        RPC_BLOCKS_BY_ROOT => Ok(Some(RPCResponse::BlocksByRoot(
+                            decode_block_response(&self.protocol, &raw_bytes)?,
+                        ))),
                         _ => unreachable!("Cannot negotiate an unknown protocol"),
                     }
                 }
@@ -216,6 +214,60 @@ impl Decoder for SSZOutboundCodec {
     }
 }
 
+/// Returns the bytes of `response` to be sent over `protocol`.
+///
+/// The block is already SSZ encoded. For version 2, it is prefixed by its fork version. An empty
+/// response is sent as an empty payload in both versions.
+fn encode_block_response(
+    protocol: &ProtocolId,
+    response: BlockResponse,
+) -> Result<Vec<u8>, RPCError> {
+    if response.block_bytes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match protocol.version {
+        Version::V1 => Ok(response.block_bytes),
+        Version::V2 => {
+            let fork_version = response.fork_version.ok_or_else(|| {
+                RPCError::Custom("Cannot send a block without its fork version".into())
+            })?;
+
+            let mut bytes = fork_version.to_vec();
+            bytes.extend_from_slice(&response.block_bytes);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Decodes the bytes of a block response received over `protocol`, the inverse of
+/// `encode_block_response`.
+///
+/// The block itself is decoded upstream, once its container is selected by fork version.
+fn decode_block_response(protocol: &ProtocolId, bytes: &[u8]) -> Result<BlockResponse, RPCError> {
+    match protocol.version {
+        Version::V1 => Ok(BlockResponse {
+            fork_version: None,
+            block_bytes: bytes.to_vec(),
+        }),
+        Version::V2 => {
+            if bytes.len() < FORK_VERSION_LEN {
+                return Err(RPCError::Custom(
+                    "Block response is missing its fork version".into(),
+                ));
+            }
+
+            let mut fork_version = [0; FORK_VERSION_LEN];
+            fork_version.copy_from_slice(&bytes[0..FORK_VERSION_LEN]);
+
+            Ok(BlockResponse::new(
+                fork_version,
+                bytes[FORK_VERSION_LEN..].to_vec(),
+            ))
+        }
+    }
+}
+
 impl OutboundCodec for SSZOutboundCodec {
     type ErrorType = ErrorMessage;
 
@@ -227,3 +279,173 @@ impl OutboundCodec for SSZOutboundCodec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codecs(message_name: &str, version: Version) -> (SSZInboundCodec, SSZOutboundCodec) {
+        let protocol = ProtocolId::new(message_name, version, "ssz");
+        (
+            SSZInboundCodec::new(protocol.clone(), 1_024),
+            SSZOutboundCodec::new(protocol, 1_024),
+        )
+    }
+
+    fn round_trip(message_name: &str, version: Version, response: RPCResponse) -> RPCResponse {
+        let (mut inbound, mut outbound) = codecs(message_name, version);
+        let mut buf = BytesMut::new();
+
+        inbound
+            .encode(RPCErrorResponse::Success(response), &mut buf)
+            .expect("should encode response");
+        outbound
+            .decode(&mut buf)
+            .expect("should decode response")
+            .expect("should decode a complete response")
+    }
+
+    #[test]
+    fn block_responses_v1() {
+        let response = BlockResponse::new([1, 2, 3, 4], vec![13, 13, 13]);
+
+        // The fork version is not sent over version 1.
+        let expected = BlockResponse {
+            fork_version: None,
+            block_bytes: vec![13, 13, 13],
+        };
+
+        assert_eq!(
+            round_trip(
+                RPC_BLOCKS_BY_RANGE,
+                Version::V1,
+                RPCResponse::BlocksByRange(response.clone())
+            ),
+            RPCResponse::BlocksByRange(expected.clone())
+        );
+        assert_eq!(
+            round_trip(
+                RPC_BLOCKS_BY_ROOT,
+                Version::V1,
+                RPCResponse::BlocksByRoot(response)
+            ),
+            RPCResponse::BlocksByRoot(expected)
+        );
+    }
+
+    #[test]
+    fn block_responses_v2() {
+        let response = BlockResponse::new([1, 2, 3, 4], vec![13, 13, 13]);
+
+        assert_eq!(
+            round_trip(
+                RPC_BLOCKS_BY_RANGE,
+                Version::V2,
+                RPCResponse::BlocksByRange(response.clone())
+            ),
+            RPCResponse::BlocksByRange(response.clone())
+        );
+        assert_eq!(
+            round_trip(
+                RPC_BLOCKS_BY_ROOT,
+                Version::V2,
+                RPCResponse::BlocksByRoot(response.clone())
+            ),
+            RPCResponse::BlocksByRoot(response)
+        );
+    }
+
+    #[test]
+    fn empty_block_responses() {
+        for version in &[Version::V1, Version::V2] {
+            assert_eq!(
+                round_trip(
+                    RPC_BLOCKS_BY_RANGE,
+                    *version,
+                    RPCResponse::BlocksByRange(BlockResponse::empty())
+                ),
+                RPCResponse::BlocksByRange(BlockResponse::empty())
+            );
+        }
+    }
+
+    #[test]
+    fn error_responses() {
+        let (mut inbound, mut outbound) = codecs(RPC_BLOCKS_BY_RANGE, Version::V2);
+        let mut buf = BytesMut::new();
+
+        inbound
+            .encode(
+                RPCErrorResponse::error(RPCResponseErrorCode::RateLimited, "Slow down"),
+                &mut buf,
+            )
+            .expect("should encode error");
+        assert_eq!(
+            outbound
+                .decode_error(&mut buf)
+                .expect("should decode error"),
+            Some(ErrorMessage::new("Slow down"))
+        );
+
+        // An error need not carry a message.
+        inbound
+            .encode(
+                RPCErrorResponse::error(RPCResponseErrorCode::ResourceUnavailable, ""),
+                &mut buf,
+            )
+            .expect("should encode error");
+        assert_eq!(
+            outbound
+                .decode_error(&mut buf)
+                .expect("should decode error"),
+            Some(ErrorMessage::new(""))
+        );
+    }
+
+    #[test]
+    fn block_responses_v2_require_fork_version() {
+        let (mut inbound, mut outbound) = codecs(RPC_BLOCKS_BY_RANGE, Version::V2);
+        let response = BlockResponse {
+            fork_version: None,
+            block_bytes: vec![13, 13, 13],
+        };
+
+        assert!(inbound
+            .encode(
+                RPCErrorResponse::Success(RPCResponse::BlocksByRange(response)),
+                &mut BytesMut::new()
+            )
+            .is_err());
+
+        // A response which is too short to contain a fork version is rejected.
+        let mut buf = BytesMut::new();
+        let mut uvi_codec: UviBytes = UviBytes::default();
+        uvi_codec
+            .encode(Bytes::from(vec![1, 2, 3]), &mut buf)
+            .unwrap();
+        assert!(outbound.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn block_requests_are_version_independent() {
+        let request = BlocksByRangeRequest {
+            head_block_root: Default::default(),
+            start_slot: 2,
+            count: 8,
+            step: 1,
+        };
+
+        for version in &[Version::V1, Version::V2] {
+            let (mut inbound, mut outbound) = codecs(RPC_BLOCKS_BY_RANGE, *version);
+            let mut buf = BytesMut::new();
+
+            outbound
+                .encode(RPCRequest::BlocksByRange(request.clone()), &mut buf)
+                .unwrap();
+            assert_eq!(
+                inbound.decode(&mut buf).unwrap(),
+                Some(RPCRequest::BlocksByRange(request.clone()))
+            );
+        }
+    }
+}
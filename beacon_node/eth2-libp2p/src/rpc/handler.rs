@@ -1,9 +1,10 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::cognitive_complexity)]
 
-use super::methods::{RPCErrorResponse, RequestId};
+use super::methods::{RPCErrorResponse, RPCResponseErrorCode, RequestId};
 use super::protocol::{RPCError, RPCProtocol, RPCRequest};
 use super::RPCEvent;
+use crate::metrics;
 use crate::rpc::protocol::{InboundFramed, OutboundFramed};
 use core::marker::PhantomData;
 use fnv::FnvHashMap;
@@ -23,8 +24,15 @@ use tokio::timer::{delay_queue, DelayQueue};
 //TODO: Implement check_timeout() on the substream types
 
 /// The time (in seconds) before a substream that is awaiting a response from the user times out.
+///
+/// For a chunked response this applies to each chunk, so a long response is not cut short whilst
+/// it is making progress, but a peer which stops reading it cannot hold the stream open.
 pub const RESPONSE_TIMEOUT: u64 = 10;
 
+/// The maximum number of inbound substreams a peer may have open at once. Further requests are
+/// answered with a server error until some of the open substreams have completed.
+const MAX_INBOUND_SUBSTREAMS: usize = 8;
+
 /// The number of times to retry an outbound upgrade in the case of IO errors.
 const IO_ERROR_RETRIES: u8 = 3;
 
@@ -224,11 +232,30 @@ where
             return;
         }
 
-        // New inbound request. Store the stream and tag the output.
         let delay_key = self.inbound_substreams_delay.insert(
             self.current_substream_id,
             Duration::from_secs(RESPONSE_TIMEOUT),
         );
+
+        // Refuse the request without passing it to the application if the peer already has too
+        // many streams open.
+        if self.inbound_substreams.len() >= MAX_INBOUND_SUBSTREAMS {
+            debug!(self.log, "Too many inbound streams. Refusing request"; "request" => format!("{}", req));
+            metrics::inc_counter(&metrics::RPC_INBOUND_STREAMS_REJECTED);
+            let refusing_stream = InboundSubstreamState::ResponsePendingSend {
+                substream: substream.send(RPCErrorResponse::error(
+                    RPCResponseErrorCode::RateLimited,
+                    "Too many concurrent requests",
+                )),
+                closing: true,
+            };
+            self.inbound_substreams
+                .insert(self.current_substream_id, (refusing_stream, delay_key));
+            self.current_substream_id += 1;
+            return;
+        }
+
+        // New inbound request. Store the stream and tag the output.
         let awaiting_stream = InboundSubstreamState::ResponseIdle(substream);
         self.inbound_substreams
             .insert(self.current_substream_id, (awaiting_stream, delay_key));
@@ -437,7 +464,33 @@ where
             .poll()
             .map_err(|_| ProtocolsHandlerUpgrErr::Timer)?
         {
-            self.inbound_substreams.remove(stream_id.get_ref());
+            let request_id = stream_id.into_inner();
+            metrics::inc_counter(&metrics::RPC_INBOUND_STREAMS_TIMED_OUT);
+            match self.inbound_substreams.remove(&request_id) {
+                // The application did not provide the next chunk in time. Terminate the response
+                // with an error so the peer knows it is incomplete.
+                Some((InboundSubstreamState::ResponseIdle(substream), _)) => {
+                    debug!(self.log, "Inbound stream timed out. Terminating partial response"; "request_id" => request_id);
+                    let delay_key = self
+                        .inbound_substreams_delay
+                        .insert(request_id, Duration::from_secs(RESPONSE_TIMEOUT));
+                    let terminating_stream = InboundSubstreamState::ResponsePendingSend {
+                        substream: substream.send(RPCErrorResponse::error(
+                            RPCResponseErrorCode::ServerError,
+                            "Response timed out",
+                        )),
+                        closing: true,
+                    };
+                    self.inbound_substreams
+                        .insert(request_id, (terminating_stream, delay_key));
+                }
+                // The peer is not reading the response, or not closing the stream. Drop it.
+                Some(_) => {
+                    debug!(self.log, "Inbound stream timed out. Dropping stream"; "request_id" => request_id);
+                    self.queued_outbound_items.remove(&request_id);
+                }
+                None => {}
+            }
         }
 
         // purge expired outbound substreams
@@ -475,7 +528,12 @@ where
                             } => {
                                 match substream.poll() {
                                     Ok(Async::Ready(raw_substream)) => {
-                                        // completed the send
+                                        // completed the send, allow another timeout period for
+                                        // the next chunk
+                                        self.inbound_substreams_delay.reset(
+                                            &entry.get().1,
+                                            Duration::from_secs(RESPONSE_TIMEOUT),
+                                        );
 
                                         // close the stream if required
                                         if closing {
@@ -503,6 +561,7 @@ where
                                         let delay_key = &entry.get().1;
                                         self.inbound_substreams_delay.remove(delay_key);
                                         entry.remove_entry();
+                                        self.queued_outbound_items.remove(&request_id);
                                         return Ok(Async::Ready(ProtocolsHandlerEvent::Custom(
                                             RPCEvent::Error(0, e),
                                         )));
@@ -555,7 +614,8 @@ where
                             request,
                         } => match substream.poll() {
                             Ok(Async::Ready(Some(response))) => {
-                                if request.multiple_responses() {
+                                // an error response terminates a chunked response early
+                                if request.multiple_responses() && !response.is_error() {
                                     entry.get_mut().0 =
                                         OutboundSubstreamState::RequestPendingResponse {
                                             substream,
@@ -565,8 +625,8 @@ where
                                     self.outbound_substreams_delay
                                         .reset(delay_key, Duration::from_secs(RESPONSE_TIMEOUT));
                                 } else {
-                                    trace!(self.log, "Closing single stream request");
-                                    // only expect a single response, close the stream
+                                    trace!(self.log, "Closing stream, no more responses");
+                                    // no further responses are expected, close the stream
                                     entry.get_mut().0 = OutboundSubstreamState::Closing(substream);
                                 }
                                 return Ok(Async::Ready(ProtocolsHandlerEvent::Custom(
@@ -677,10 +737,14 @@ fn apply_queued_responses<TSubstream: AsyncRead + AsyncWrite>(
                     // close the stream if this is a stream termination
                     InboundSubstreamState::Closing(raw_substream)
                 }
-                chunk => InboundSubstreamState::ResponsePendingSend {
-                    substream: raw_substream.send(chunk),
-                    closing: false,
-                },
+                chunk => {
+                    // an error ends the response, so close the stream once it is sent
+                    let closing = chunk.is_error();
+                    InboundSubstreamState::ResponsePendingSend {
+                        substream: raw_substream.send(chunk),
+                        closing,
+                    }
+                }
             }
         }
         _ => {
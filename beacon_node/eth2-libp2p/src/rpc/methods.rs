@@ -5,6 +5,12 @@ use types::{Epoch, Hash256, Slot};
 
 /* Request/Response data structures for RPC methods */
 
+/// The maximum number of blocks that may be requested in a single `BlocksByRange` request.
+pub const MAX_REQUEST_BLOCKS: u64 = 1024;
+
+/// The maximum length of the message in an `ErrorMessage`, in bytes.
+pub const MAX_ERROR_MESSAGE_LEN: usize = 256;
+
 /* Requests */
 
 pub type RequestId = usize;
@@ -125,6 +131,40 @@ pub struct BlocksByRootRequest {
     pub block_roots: Vec<Hash256>,
 }
 
+/* Responses */
+
+/// A block sent in response to a `BlocksByRange` or `BlocksByRoot` request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockResponse {
+    /// The fork version at the epoch of the block, which determines the SSZ container used to
+    /// encode it.
+    ///
+    /// Only sent over version 2 of the block protocols. Always `None` for blocks received over
+    /// version 1.
+    pub fork_version: Option<[u8; 4]>,
+
+    /// The SSZ bytes of the block.
+    pub block_bytes: Vec<u8>,
+}
+
+impl BlockResponse {
+    /// Instantiates a response for a block from the fork with `fork_version`.
+    pub fn new(fork_version: [u8; 4], block_bytes: Vec<u8>) -> Self {
+        Self {
+            fork_version: Some(fork_version),
+            block_bytes,
+        }
+    }
+
+    /// Instantiates an empty response, as is received when a peer terminates a stream early.
+    pub fn empty() -> Self {
+        Self {
+            fork_version: None,
+            block_bytes: vec![],
+        }
+    }
+}
+
 /* RPC Handling and Grouping */
 // Collection of enums and structs used by the Codecs to encode/decode RPC messages
 
@@ -135,10 +175,10 @@ pub enum RPCResponse {
 
     /// A response to a get BLOCKS_BY_RANGE request. A None response signifies the end of the
     /// batch.
-    BlocksByRange(Vec<u8>),
+    BlocksByRange(BlockResponse),
 
     /// A response to a get BLOCKS_BY_ROOT request.
-    BlocksByRoot(Vec<u8>),
+    BlocksByRoot(BlockResponse),
 }
 
 /// Indicates which response is being terminated by a stream termination response.
@@ -162,6 +202,14 @@ pub enum RPCErrorResponse {
     /// The response indicates a server error.
     ServerError(ErrorMessage),
 
+    /// The response indicates that the requested data is not available (e.g., it has been
+    /// pruned).
+    ResourceUnavailable(ErrorMessage),
+
+    /// The response indicates that the request was refused because the peer has made too many
+    /// requests.
+    RateLimited(ErrorMessage),
+
     /// There was an unknown response.
     Unknown(ErrorMessage),
 
@@ -176,6 +224,8 @@ impl RPCErrorResponse {
             RPCErrorResponse::Success(_) => Some(0),
             RPCErrorResponse::InvalidRequest(_) => Some(1),
             RPCErrorResponse::ServerError(_) => Some(2),
+            RPCErrorResponse::ResourceUnavailable(_) => Some(3),
+            RPCErrorResponse::RateLimited(_) => Some(139),
             RPCErrorResponse::Unknown(_) => Some(255),
             RPCErrorResponse::StreamTermination(_) => None,
         }
@@ -194,10 +244,41 @@ impl RPCErrorResponse {
         match response_code {
             1 => RPCErrorResponse::InvalidRequest(err),
             2 => RPCErrorResponse::ServerError(err),
+            3 => RPCErrorResponse::ResourceUnavailable(err),
+            139 => RPCErrorResponse::RateLimited(err),
             _ => RPCErrorResponse::Unknown(err),
         }
     }
 
+    /// Builds an error response with the given `code`, carrying `message`.
+    pub fn error(code: RPCResponseErrorCode, message: &str) -> Self {
+        let err = ErrorMessage::new(message);
+
+        match code {
+            RPCResponseErrorCode::InvalidRequest => RPCErrorResponse::InvalidRequest(err),
+            RPCResponseErrorCode::ServerError => RPCErrorResponse::ServerError(err),
+            RPCResponseErrorCode::ResourceUnavailable => RPCErrorResponse::ResourceUnavailable(err),
+            RPCResponseErrorCode::RateLimited => RPCErrorResponse::RateLimited(err),
+            RPCResponseErrorCode::Unknown => RPCErrorResponse::Unknown(err),
+        }
+    }
+
+    /// Returns the code and message of an error response, or `None` if `self` is not an error.
+    pub fn as_error(&self) -> Option<(RPCResponseErrorCode, &ErrorMessage)> {
+        match self {
+            RPCErrorResponse::InvalidRequest(err) => {
+                Some((RPCResponseErrorCode::InvalidRequest, err))
+            }
+            RPCErrorResponse::ServerError(err) => Some((RPCResponseErrorCode::ServerError, err)),
+            RPCErrorResponse::ResourceUnavailable(err) => {
+                Some((RPCResponseErrorCode::ResourceUnavailable, err))
+            }
+            RPCErrorResponse::RateLimited(err) => Some((RPCResponseErrorCode::RateLimited, err)),
+            RPCErrorResponse::Unknown(err) => Some((RPCResponseErrorCode::Unknown, err)),
+            RPCErrorResponse::Success(_) | RPCErrorResponse::StreamTermination(_) => None,
+        }
+    }
+
     /// Specifies which response allows for multiple chunks for the stream handler.
     pub fn multiple_responses(&self) -> bool {
         match self {
@@ -208,6 +289,8 @@ impl RPCErrorResponse {
             },
             RPCErrorResponse::InvalidRequest(_) => true,
             RPCErrorResponse::ServerError(_) => true,
+            RPCErrorResponse::ResourceUnavailable(_) => true,
+            RPCErrorResponse::RateLimited(_) => true,
             RPCErrorResponse::Unknown(_) => true,
             // Stream terminations are part of responses that have chunks
             RPCErrorResponse::StreamTermination(_) => true,
@@ -224,13 +307,68 @@ impl RPCErrorResponse {
     }
 }
 
-#[derive(Encode, Decode, Debug)]
+/// The kind of error indicated by an error response, which determines how the requester should
+/// react to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RPCResponseErrorCode {
+    /// The request was malformed or is not permitted.
+    InvalidRequest,
+    /// The responder failed to process a valid request.
+    ServerError,
+    /// The responder does not have the requested data.
+    ResourceUnavailable,
+    /// The responder refused the request because the requester has made too many requests.
+    RateLimited,
+    /// The responder used a code which is not known to this client.
+    Unknown,
+}
+
+impl RPCResponseErrorCode {
+    /// Returns `true` if the request may succeed if it is sent again, later or to another peer.
+    ///
+    /// Otherwise, the error indicates that the request or the responder is faulty.
+    pub fn is_retryable(self) -> bool {
+        match self {
+            RPCResponseErrorCode::ServerError
+            | RPCResponseErrorCode::ResourceUnavailable
+            | RPCResponseErrorCode::RateLimited => true,
+            RPCResponseErrorCode::InvalidRequest | RPCResponseErrorCode::Unknown => false,
+        }
+    }
+}
+
+impl std::fmt::Display for RPCResponseErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RPCResponseErrorCode::InvalidRequest => write!(f, "Invalid Request"),
+            RPCResponseErrorCode::ServerError => write!(f, "Server Error"),
+            RPCResponseErrorCode::ResourceUnavailable => write!(f, "Resource Unavailable"),
+            RPCResponseErrorCode::RateLimited => write!(f, "Rate Limited"),
+            RPCResponseErrorCode::Unknown => write!(f, "Unknown Error"),
+        }
+    }
+}
+
+/// The optional message describing an error response.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
 pub struct ErrorMessage {
-    /// The UTF-8 encoded Error message string.
+    /// The UTF-8 encoded Error message string, which may be empty.
     pub error_message: Vec<u8>,
 }
 
 impl ErrorMessage {
+    /// Returns a message carrying `message`, truncated to `MAX_ERROR_MESSAGE_LEN` bytes.
+    pub fn new(message: &str) -> Self {
+        let mut len = std::cmp::min(message.len(), MAX_ERROR_MESSAGE_LEN);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        Self {
+            error_message: message.as_bytes()[..len].to_vec(),
+        }
+    }
+
     pub fn as_string(&self) -> String {
         String::from_utf8(self.error_message.clone()).unwrap_or_else(|_| "".into())
     }
@@ -258,6 +396,10 @@ impl std::fmt::Display for RPCErrorResponse {
             RPCErrorResponse::Success(res) => write!(f, "{}", res),
             RPCErrorResponse::InvalidRequest(err) => write!(f, "Invalid Request: {:?}", err),
             RPCErrorResponse::ServerError(err) => write!(f, "Server Error: {:?}", err),
+            RPCErrorResponse::ResourceUnavailable(err) => {
+                write!(f, "Resource Unavailable: {:?}", err)
+            }
+            RPCErrorResponse::RateLimited(err) => write!(f, "Rate Limited: {:?}", err),
             RPCErrorResponse::Unknown(err) => write!(f, "Unknown Error: {:?}", err),
             RPCErrorResponse::StreamTermination(_) => write!(f, "Stream Termination"),
         }
@@ -284,3 +426,48 @@ impl std::fmt::Display for BlocksByRangeRequest {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_round_trip() {
+        let codes = [
+            RPCResponseErrorCode::InvalidRequest,
+            RPCResponseErrorCode::ServerError,
+            RPCResponseErrorCode::ResourceUnavailable,
+            RPCResponseErrorCode::RateLimited,
+            RPCResponseErrorCode::Unknown,
+        ];
+
+        for code in codes.iter() {
+            let response = RPCErrorResponse::error(*code, "message");
+            let response_code = response
+                .as_u8()
+                .expect("errors should have a response code");
+
+            assert!(!RPCErrorResponse::is_response(response_code));
+            assert_eq!(
+                RPCErrorResponse::from_error(response_code, ErrorMessage::new("message"))
+                    .as_error(),
+                Some((*code, &ErrorMessage::new("message")))
+            );
+        }
+
+        assert_eq!(
+            RPCErrorResponse::from_error(42, ErrorMessage::new("")).as_error(),
+            Some((RPCResponseErrorCode::Unknown, &ErrorMessage::new("")))
+        );
+    }
+
+    #[test]
+    fn error_message_is_truncated() {
+        let message = "é".repeat(MAX_ERROR_MESSAGE_LEN);
+        let truncated = ErrorMessage::new(&message);
+
+        assert_eq!(truncated.error_message.len(), MAX_ERROR_MESSAGE_LEN);
+        assert_eq!(truncated.as_string(), "é".repeat(MAX_ERROR_MESSAGE_LEN / 2));
+        assert_eq!(ErrorMessage::new("").as_string(), "");
+    }
+}
@@ -13,9 +13,12 @@ use libp2p::swarm::{
 };
 use libp2p::{Multiaddr, PeerId};
 pub use methods::{
-    ErrorMessage, RPCErrorResponse, RPCResponse, RequestId, ResponseTermination, StatusMessage,
+    BlockResponse, ErrorMessage, RPCErrorResponse, RPCResponse, RPCResponseErrorCode, RequestId,
+    ResponseTermination, StatusMessage,
 };
-pub use protocol::{RPCError, RPCProtocol, RPCRequest};
+#[cfg(feature = "fuzz")]
+pub use protocol::decode_rpc_request;
+pub use protocol::{RPCError, RPCProtocol, RPCRequest, Version};
 use slog::o;
 use std::marker::PhantomData;
 use std::time::Duration;
@@ -14,6 +14,7 @@ use futures::{
     sink, stream, Sink, Stream,
 };
 use libp2p::core::{upgrade, InboundUpgrade, OutboundUpgrade, ProtocolName, UpgradeInfo};
+use lighthouse_error::{CodedError, ErrorCode};
 use std::io;
 use std::time::Duration;
 use tokio::codec::Framed;
@@ -43,6 +44,27 @@ pub const RPC_BLOCKS_BY_RANGE: &str = "beacon_blocks_by_range";
 /// The `BlocksByRoot` protocol name.
 pub const RPC_BLOCKS_BY_ROOT: &str = "beacon_blocks_by_root";
 
+/// The version of an RPC protocol, which determines the SSZ containers sent over it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Version {
+    /// The original containers.
+    V1,
+    /// Each block sent in response to `BlocksByRange` and `BlocksByRoot` is prefixed by the fork
+    /// version at the epoch of the block, so that the receiver may select the container with
+    /// which to decode the block.
+    V2,
+}
+
+impl Version {
+    /// Returns the version as it appears in a protocol id.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Version::V1 => "1",
+            Version::V2 => "2",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RPCProtocol;
 
@@ -50,12 +72,16 @@ impl UpgradeInfo for RPCProtocol {
     type Info = ProtocolId;
     type InfoIter = Vec<Self::Info>;
 
+    /// Protocols are listed in order of preference, so that the latest version supported by both
+    /// peers is negotiated.
     fn protocol_info(&self) -> Self::InfoIter {
         vec![
-            ProtocolId::new(RPC_STATUS, "1", "ssz"),
-            ProtocolId::new(RPC_GOODBYE, "1", "ssz"),
-            ProtocolId::new(RPC_BLOCKS_BY_RANGE, "1", "ssz"),
-            ProtocolId::new(RPC_BLOCKS_BY_ROOT, "1", "ssz"),
+            ProtocolId::new(RPC_STATUS, Version::V1, "ssz"),
+            ProtocolId::new(RPC_GOODBYE, Version::V1, "ssz"),
+            ProtocolId::new(RPC_BLOCKS_BY_RANGE, Version::V2, "ssz"),
+            ProtocolId::new(RPC_BLOCKS_BY_RANGE, Version::V1, "ssz"),
+            ProtocolId::new(RPC_BLOCKS_BY_ROOT, Version::V2, "ssz"),
+            ProtocolId::new(RPC_BLOCKS_BY_ROOT, Version::V1, "ssz"),
         ]
     }
 }
@@ -67,7 +93,7 @@ pub struct ProtocolId {
     pub message_name: String,
 
     /// The version of the RPC.
-    pub version: String,
+    pub version: Version,
 
     /// The encoding of the RPC.
     pub encoding: String,
@@ -78,15 +104,18 @@ pub struct ProtocolId {
 
 /// An RPC protocol ID.
 impl ProtocolId {
-    pub fn new(message_name: &str, version: &str, encoding: &str) -> Self {
+    pub fn new(message_name: &str, version: Version, encoding: &str) -> Self {
         let protocol_id = format!(
             "{}/{}/{}/{}",
-            PROTOCOL_PREFIX, message_name, version, encoding
+            PROTOCOL_PREFIX,
+            message_name,
+            version.as_str(),
+            encoding
         );
 
         ProtocolId {
             message_name: message_name.into(),
-            version: version.into(),
+            version,
             encoding: encoding.into(),
             protocol_id,
         }
@@ -155,6 +184,27 @@ where
     }
 }
 
+/// Decodes an inbound RPC request from untrusted `bytes`, as done by `upgrade_inbound`.
+///
+/// The first byte selects one of the protocols of `RPCProtocol` (modulo their number), and the
+/// remaining bytes are the length-prefixed request. Intended as a fuzzing entrypoint.
+#[cfg(feature = "fuzz")]
+pub fn decode_rpc_request(bytes: &[u8]) -> Result<Option<RPCRequest>, RPCError> {
+    use libp2p::bytes::BytesMut;
+    use tokio::codec::Decoder;
+
+    let (selector, packet) = match bytes.split_first() {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+
+    let protocols = RPCProtocol.protocol_info();
+    let protocol = protocols[*selector as usize % protocols.len()].clone();
+
+    BaseInboundCodec::new(SSZInboundCodec::new(protocol, MAX_RPC_SIZE))
+        .decode(&mut BytesMut::from(packet))
+}
+
 /* Outbound request */
 
 // Combines all the RPC requests into a single enum to implement `UpgradeInfo` and
@@ -180,13 +230,23 @@ impl UpgradeInfo for RPCRequest {
 
 /// Implements the encoding per supported protocol for RPCRequest.
 impl RPCRequest {
+    /// Returns the protocols over which `self` may be sent.
+    ///
+    /// Protocols are listed in order of preference. Version 1 of the block protocols is retained
+    /// for peers which do not support version 2.
     pub fn supported_protocols(&self) -> Vec<ProtocolId> {
         match self {
             // add more protocols when versions/encodings are supported
-            RPCRequest::Status(_) => vec![ProtocolId::new(RPC_STATUS, "1", "ssz")],
-            RPCRequest::Goodbye(_) => vec![ProtocolId::new(RPC_GOODBYE, "1", "ssz")],
-            RPCRequest::BlocksByRange(_) => vec![ProtocolId::new(RPC_BLOCKS_BY_RANGE, "1", "ssz")],
-            RPCRequest::BlocksByRoot(_) => vec![ProtocolId::new(RPC_BLOCKS_BY_ROOT, "1", "ssz")],
+            RPCRequest::Status(_) => vec![ProtocolId::new(RPC_STATUS, Version::V1, "ssz")],
+            RPCRequest::Goodbye(_) => vec![ProtocolId::new(RPC_GOODBYE, Version::V1, "ssz")],
+            RPCRequest::BlocksByRange(_) => vec![
+                ProtocolId::new(RPC_BLOCKS_BY_RANGE, Version::V2, "ssz"),
+                ProtocolId::new(RPC_BLOCKS_BY_RANGE, Version::V1, "ssz"),
+            ],
+            RPCRequest::BlocksByRoot(_) => vec![
+                ProtocolId::new(RPC_BLOCKS_BY_ROOT, Version::V2, "ssz"),
+                ProtocolId::new(RPC_BLOCKS_BY_ROOT, Version::V1, "ssz"),
+            ],
         }
     }
 
@@ -276,6 +336,20 @@ pub enum RPCError {
     Custom(String),
 }
 
+impl CodedError for RPCError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            RPCError::ReadError(_) => 3100,
+            RPCError::SSZDecodeError(_) => 3101,
+            RPCError::InvalidProtocol(_) => 3102,
+            RPCError::IoError(_) => 3103,
+            RPCError::StreamTimeout => 3104,
+            RPCError::RPCErrorResponse => 3105,
+            RPCError::Custom(_) => 3106,
+        })
+    }
+}
+
 impl From<upgrade::ReadOneError> for RPCError {
     #[inline]
     fn from(err: upgrade::ReadOneError) -> Self {
@@ -1,9 +1,12 @@
+use crate::ban_list::{Ban, BanTarget};
 use crate::behaviour::{Behaviour, BehaviourEvent, PubsubMessage};
 use crate::config::*;
+use crate::discovery::load_enr;
 use crate::error;
 use crate::multiaddr::Protocol;
+use crate::peer_db::unix_now;
 use crate::rpc::RPCEvent;
-use crate::NetworkConfig;
+use crate::{Enr, NetworkConfig};
 use crate::{Topic, TopicHash};
 use futures::prelude::*;
 use futures::Stream;
@@ -19,7 +22,7 @@ use std::io::{Error, ErrorKind};
 use std::time::Duration;
 use tokio::timer::DelayQueue;
 
-type Libp2pStream = Boxed<(PeerId, StreamMuxerBox), Error>;
+pub(crate) type Libp2pStream = Boxed<(PeerId, StreamMuxerBox), Error>;
 type Libp2pBehaviour = Behaviour<Substream<StreamMuxerBox>>;
 
 const NETWORK_KEY_FILENAME: &str = "key";
@@ -53,13 +56,8 @@ impl Service {
     pub fn new(config: NetworkConfig, log: slog::Logger) -> error::Result<Self> {
         trace!(log, "Libp2p Service starting");
 
-        let local_keypair = if let Some(hex_bytes) = &config.secret_key_hex {
-            keypair_from_hex(hex_bytes)?
-        } else {
-            load_private_key(&config, &log)
-        };
-
         // load the private key from CLI flag, disk or generate a new one
+        let local_keypair = load_keypair(&config, &log)?;
         let local_peer_id = PeerId::from(local_keypair.public());
         info!(log, "Libp2p Service"; "peer_id" => format!("{:?}", local_peer_id));
 
@@ -138,9 +136,13 @@ impl Service {
         };
         topics.push(topic_builder(BEACON_BLOCK_TOPIC));
         topics.push(topic_builder(BEACON_ATTESTATION_TOPIC));
+        topics.push(topic_builder(BEACON_AGGREGATE_AND_PROOF_TOPIC));
         topics.push(topic_builder(VOLUNTARY_EXIT_TOPIC));
         topics.push(topic_builder(PROPOSER_SLASHING_TOPIC));
         topics.push(topic_builder(ATTESTER_SLASHING_TOPIC));
+        if config.execution_witness_topic {
+            topics.push(topic_builder(BEACON_BLOCK_AND_WITNESS_TOPIC));
+        }
 
         // Add any topics specified by the user
         topics.append(&mut config.topics.iter().cloned().map(Topic::new).collect());
@@ -156,11 +158,22 @@ impl Service {
         }
         info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics.iter().map(|t| format!("{}", t)).collect::<Vec<String>>()));
 
+        // re-apply the bans which had not expired when the node was last stopped, and those given
+        // on the command line
+        let mut peer_ban_timeout = DelayQueue::new();
+        for (peer_id, timeout) in swarm.persisted_bans() {
+            debug!(log, "Restoring peer ban"; "peer_id" => format!("{:?}", peer_id), "timeout" => format!("{:?}", timeout));
+            Swarm::ban_peer_id(&mut swarm, peer_id.clone());
+            if let Some(timeout) = timeout {
+                peer_ban_timeout.insert(peer_id, timeout);
+            }
+        }
+
         Ok(Service {
             local_peer_id,
             swarm,
             peers_to_ban: DelayQueue::new(),
-            peer_ban_timeout: DelayQueue::new(),
+            peer_ban_timeout,
             verified_listen_address: false,
             log,
         })
@@ -173,8 +186,69 @@ impl Service {
             peer_id.clone(),
             Duration::from_millis(BAN_PEER_WAIT_TIMEOUT),
         );
+        self.swarm.record_ban(&peer_id, timeout);
         self.peer_ban_timeout.insert(peer_id, timeout);
     }
+
+    /// Bans `target` for `duration`, or until the ban is removed if it is `None`, disconnecting
+    /// the connected peers which it applies to. The ban is persisted across restarts.
+    pub fn ban(&mut self, target: BanTarget, duration: Option<Duration>) -> Ban {
+        let expires_at = duration.map(|duration| unix_now() + duration.as_secs());
+
+        if let BanTarget::Peer(peer_id) = &target {
+            Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+            if let Some(duration) = duration {
+                self.peer_ban_timeout.insert(peer_id.clone(), duration);
+            }
+        }
+
+        for peer_id in self.swarm.add_ban(target.clone(), expires_at) {
+            self.disconnect_banned(peer_id);
+        }
+
+        Ban { target, expires_at }
+    }
+
+    /// Lifts the ban of `target`, whether it was imposed with `ban` or for misbehaving. Returns
+    /// `false` if it was not banned.
+    pub fn unban(&mut self, target: &BanTarget) -> bool {
+        let removed = self.swarm.remove_ban(target);
+        if let BanTarget::Peer(peer_id) = target {
+            Swarm::unban_peer_id(&mut self.swarm, peer_id.clone());
+        }
+        removed
+    }
+
+    /// Returns the bans which have not expired, whether they were imposed with `ban` or for
+    /// misbehaving.
+    pub fn bans(&self) -> Vec<Ban> {
+        self.swarm.bans()
+    }
+
+    /// Disconnects a peer which is banned by its peer id or IP address. Peers which are only
+    /// banned by their IP address may reconnect from another address.
+    fn disconnect_banned(&mut self, peer_id: PeerId) {
+        debug!(self.log, "Disconnecting peer"; "peer_id" => format!("{:?}", peer_id), "reason" => "banned");
+        self.disconnect(&peer_id);
+        if !self.swarm.is_peer_banned(&peer_id) {
+            Swarm::unban_peer_id(&mut self.swarm, peer_id);
+        }
+    }
+
+    /// Closes the connection to `peer_id` by banning it. The caller is responsible for lifting
+    /// the ban.
+    fn disconnect(&mut self, peer_id: &PeerId) {
+        Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+        // TODO: Correctly notify protocols of the disconnect
+        // TODO: Also remove peer from the DHT: https://github.com/sigp/lighthouse/issues/629
+        let dummy_connected_point = ConnectedPoint::Dialer {
+            address: "/ip4/0.0.0.0"
+                .parse::<Multiaddr>()
+                .expect("valid multiaddr"),
+        };
+        self.swarm
+            .inject_disconnected(peer_id, dummy_connected_point);
+    }
 }
 
 impl Stream for Service {
@@ -220,21 +294,24 @@ impl Stream for Service {
             }
         }
 
+        // disconnect peers in excess of the peer limits, without banning them
+        for peer_id in self.swarm.take_peers_to_prune() {
+            debug!(self.log, "Disconnecting peer"; "peer_id" => format!("{:?}", peer_id), "reason" => "too many peers");
+            self.disconnect(&peer_id);
+            Swarm::unban_peer_id(&mut self.swarm, peer_id);
+        }
+
+        // disconnect peers which connected from a banned IP address
+        for peer_id in self.swarm.take_banned_connections() {
+            self.disconnect_banned(peer_id);
+        }
+
         // check if peers need to be banned
         loop {
             match self.peers_to_ban.poll() {
                 Ok(Async::Ready(Some(peer_id))) => {
                     let peer_id = peer_id.into_inner();
-                    Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
-                    // TODO: Correctly notify protocols of the disconnect
-                    // TODO: Also remove peer from the DHT: https://github.com/sigp/lighthouse/issues/629
-                    let dummy_connected_point = ConnectedPoint::Dialer {
-                        address: "/ip4/0.0.0.0"
-                            .parse::<Multiaddr>()
-                            .expect("valid multiaddr"),
-                    };
-                    self.swarm
-                        .inject_disconnected(&peer_id, dummy_connected_point);
+                    self.disconnect(&peer_id);
                     // inform the behaviour that the peer has been banned
                     self.swarm.peer_banned(peer_id);
                 }
@@ -250,9 +327,12 @@ impl Stream for Service {
             match self.peer_ban_timeout.poll() {
                 Ok(Async::Ready(Some(peer_id))) => {
                     let peer_id = peer_id.into_inner();
-                    debug!(self.log, "Peer has been unbanned"; "peer" => format!("{:?}", peer_id));
                     self.swarm.peer_unbanned(&peer_id);
-                    Swarm::unban_peer_id(&mut self.swarm, peer_id);
+                    // the peer may also be banned by the node operator, for longer
+                    if !self.swarm.is_peer_banned(&peer_id) {
+                        debug!(self.log, "Peer has been unbanned"; "peer" => format!("{:?}", peer_id));
+                        Swarm::unban_peer_id(&mut self.swarm, peer_id);
+                    }
                 }
                 Ok(Async::NotReady) | Ok(Async::Ready(None)) => break,
                 Err(e) => {
@@ -303,7 +383,9 @@ fn multiaddr_to_socket_addr(multiaddr: &Multiaddr) -> Option<std::net::SocketAdd
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, secio as the encryption layer, and
 /// mplex or yamux as the multiplexing layer.
-fn build_transport(local_private_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox), Error> {
+pub(crate) fn build_transport(
+    local_private_key: Keypair,
+) -> Boxed<(PeerId, StreamMuxerBox), Error> {
     // TODO: The Wire protocol currently doesn't specify encryption and this will need to be customised
     // in the future.
     let transport = libp2p::tcp::TcpConfig::new().nodelay(true);
@@ -367,6 +449,25 @@ fn keypair_from_bytes(mut bytes: Vec<u8>) -> error::Result<Keypair> {
         .map_err(|e| format!("Unable to parse p2p secret key: {:?}", e).into())
 }
 
+/// Returns the ENR that the node would advertise with the given `config`, without starting any
+/// network services.
+///
+/// As when starting the network, the network key and ENR are generated and stored on disk if they
+/// do not already exist.
+pub fn load_local_enr(config: &NetworkConfig, log: &slog::Logger) -> error::Result<Enr> {
+    let local_keypair = load_keypair(config, log)?;
+    load_enr(&local_keypair, config, log).map_err(Into::into)
+}
+
+/// Loads the private key from the CLI flag or from disk, or generates a new one.
+pub(crate) fn load_keypair(config: &NetworkConfig, log: &slog::Logger) -> error::Result<Keypair> {
+    if let Some(hex_bytes) = &config.secret_key_hex {
+        keypair_from_hex(hex_bytes)
+    } else {
+        Ok(load_private_key(config, log))
+    }
+}
+
 /// Loads a private key from disk. If this fails, a new key is
 /// generated and is then saved to disk.
 ///
@@ -145,7 +145,7 @@ fn test_blocks_by_range_chunked_rpc() {
     });
 
     // BlocksByRange Response
-    let rpc_response = RPCResponse::BlocksByRange(vec![13, 13, 13]);
+    let rpc_response = RPCResponse::BlocksByRange(BlockResponse::new([0; 4], vec![13, 13, 13]));
 
     let sender_request = rpc_request.clone();
     let sender_log = log.clone();
@@ -273,7 +273,7 @@ fn test_blocks_by_range_single_empty_rpc() {
     });
 
     // BlocksByRange Response
-    let rpc_response = RPCResponse::BlocksByRange(vec![]);
+    let rpc_response = RPCResponse::BlocksByRange(BlockResponse::empty());
 
     let sender_request = rpc_request.clone();
     let sender_log = log.clone();
@@ -375,6 +375,135 @@ fn test_blocks_by_range_single_empty_rpc() {
     assert!(*test_result.lock().unwrap());
 }
 
+#[test]
+// Tests a BlocksByRange response which is terminated early with an error
+fn test_blocks_by_range_partial_rpc() {
+    // set up the logging. The level and enabled logging or not
+    let log_level = Level::Trace;
+    let enable_logging = false;
+
+    let messages_to_send = 3;
+
+    let log = common::build_log(log_level, enable_logging);
+
+    // get sender/receiver
+    let (mut sender, mut receiver) = common::build_node_pair(&log, 10525);
+
+    // BlocksByRange Request
+    let rpc_request = RPCRequest::BlocksByRange(BlocksByRangeRequest {
+        head_block_root: Hash256::from_low_u64_be(0),
+        start_slot: 0,
+        count: 10,
+        step: 1,
+    });
+
+    // BlocksByRange Response
+    let rpc_response = RPCResponse::BlocksByRange(BlockResponse::new([0; 4], vec![13, 13, 13]));
+
+    let sender_request = rpc_request.clone();
+    let sender_log = log.clone();
+    let sender_response = rpc_response.clone();
+
+    // keep count of the number of messages received
+    let messages_received = Arc::new(Mutex::new(0));
+    // build the sender future
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    // Send a BlocksByRange request
+                    warn!(sender_log, "Sender sending RPC request");
+                    sender
+                        .swarm
+                        .send_rpc(peer_id, RPCEvent::Request(1, sender_request.clone()));
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(_, event))) => match event {
+                    // Should receive the RPC response
+                    RPCEvent::Response(id, response) => {
+                        warn!(sender_log, "Sender received a response");
+                        assert_eq!(id, 1);
+                        match response {
+                            RPCErrorResponse::Success(res) => {
+                                assert_eq!(res, sender_response.clone());
+                                *messages_received.lock().unwrap() += 1;
+                                warn!(sender_log, "Chunk received");
+                            }
+                            RPCErrorResponse::ServerError(err) => {
+                                // the chunks sent before the error should have been received
+                                assert_eq!(*messages_received.lock().unwrap(), messages_to_send);
+                                assert_eq!(err.as_string(), "Partial response");
+                                // end the test
+                                return Ok(Async::Ready(true));
+                            }
+                            _ => panic!("Invalid RPC received"),
+                        }
+                    }
+                    _ => panic!("Received invalid RPC message"),
+                },
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    // build the receiver future
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::RPC(peer_id, event))) => match event {
+                    // Should receive the sent RPC request
+                    RPCEvent::Request(id, request) => {
+                        assert_eq!(id, 1);
+                        assert_eq!(rpc_request.clone(), request);
+
+                        // send the response
+                        warn!(log, "Receiver got request");
+
+                        for _ in 1..=messages_to_send {
+                            receiver.swarm.send_rpc(
+                                peer_id.clone(),
+                                RPCEvent::Response(
+                                    id,
+                                    RPCErrorResponse::Success(rpc_response.clone()),
+                                ),
+                            );
+                        }
+                        // terminate the stream with an error
+                        receiver.swarm.send_rpc(
+                            peer_id,
+                            RPCEvent::Response(
+                                id,
+                                RPCErrorResponse::ServerError(ErrorMessage {
+                                    error_message: b"Partial response".to_vec(),
+                                }),
+                            ),
+                        );
+                    }
+                    _ => panic!("Received invalid RPC message"),
+                },
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(1000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+                ()
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
 #[test]
 // Tests a streamed, chunked BlocksByRoot RPC Message
 fn test_blocks_by_root_chunked_rpc() {
@@ -395,7 +524,7 @@ fn test_blocks_by_root_chunked_rpc() {
     });
 
     // BlocksByRoot Response
-    let rpc_response = RPCResponse::BlocksByRoot(vec![13, 13, 13]);
+    let rpc_response = RPCResponse::BlocksByRoot(BlockResponse::new([0; 4], vec![13, 13, 13]));
 
     let sender_request = rpc_request.clone();
     let sender_log = log.clone();
@@ -0,0 +1,320 @@
+//! Provides a genesis ceremony, in which each node of a new testnet computes the genesis state
+//! from the deposit contract independently and refuses to start the chain until a quorum of the
+//! other nodes have computed the same state.
+//!
+//! Each node serves its `CandidateGenesis` at `GET /genesis` and polls the same path of each of
+//! its peers, so that divergent deposit histories are caught before the testnet launches.
+use environment::TaskExecutor;
+use exit_future::Signal;
+use futures::{
+    future::{self, loop_fn, Loop},
+    Future, IntoFuture,
+};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use reqwest::r#async::ClientBuilder;
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, info, warn, Logger};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use types::{BeaconState, EthSpec, Hash256};
+
+/// The path at which the candidate genesis is served.
+pub const GENESIS_PATH: &str = "/genesis";
+/// The timeout of each request to a peer.
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Genesis ceremony configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CeremonyConfig {
+    /// The IPv4 address the ceremony HTTP server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the ceremony HTTP server will listen on.
+    pub port: u16,
+    /// The URLs of the ceremony HTTP servers of the other nodes, e.g. `http://10.0.0.2:5055`.
+    pub peers: Vec<String>,
+    /// The number of `peers` which must have computed the same genesis state as this node before
+    /// it starts the chain.
+    pub quorum: usize,
+}
+
+impl Default for CeremonyConfig {
+    fn default() -> Self {
+        Self {
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5055,
+            peers: vec![],
+            quorum: 0,
+        }
+    }
+}
+
+/// The genesis state computed by a node, as served to its peers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateGenesis {
+    pub genesis_root: Hash256,
+    pub genesis_time: u64,
+    /// The number of deposits included in the genesis state.
+    pub deposit_count: u64,
+    /// The hash of the eth1 block which triggered genesis.
+    pub eth1_block_hash: Hash256,
+}
+
+impl CandidateGenesis {
+    pub fn from_state<E: EthSpec>(state: &BeaconState<E>) -> Self {
+        Self {
+            genesis_root: state.canonical_root(),
+            genesis_time: state.genesis_time,
+            deposit_count: state.eth1_data.deposit_count,
+            eth1_block_hash: state.eth1_data.block_hash,
+        }
+    }
+}
+
+/// The candidates reported by the peers in one round of polling.
+#[derive(Debug, Default, PartialEq)]
+pub struct Tally {
+    /// The peers which reported the same genesis root as this node.
+    pub agreeing: Vec<String>,
+    /// The peers which reported a different genesis, with the genesis they reported.
+    pub divergent: Vec<(String, CandidateGenesis)>,
+    /// The peers which could not be reached (or have not yet computed a genesis), with the error.
+    pub unreachable: Vec<(String, String)>,
+}
+
+impl Tally {
+    /// Compares the candidate reported by each peer to `ours`.
+    pub fn new(
+        ours: &CandidateGenesis,
+        responses: Vec<(String, Result<CandidateGenesis, String>)>,
+    ) -> Self {
+        let mut tally = Self::default();
+
+        for (peer, response) in responses {
+            match response {
+                Ok(candidate) if candidate.genesis_root == ours.genesis_root => {
+                    tally.agreeing.push(peer)
+                }
+                Ok(candidate) => tally.divergent.push((peer, candidate)),
+                Err(e) => tally.unreachable.push((peer, e)),
+            }
+        }
+
+        tally
+    }
+}
+
+/// Starts the ceremony HTTP server, serving `candidate`, returning a `Signal` which stops it.
+///
+/// The server should be kept running once the chain has started, so that peers which are still
+/// polling can reach a quorum.
+pub fn start_server(
+    config: &CeremonyConfig,
+    candidate: CandidateGenesis,
+    executor: &TaskExecutor,
+    log: Logger,
+) -> Result<(Signal, SocketAddr), String> {
+    let body = Arc::new(
+        serde_json::to_string(&candidate)
+            .map_err(|e| format!("Unable to serialize candidate genesis: {:?}", e))?,
+    );
+
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let body = body.clone();
+
+        service_fn(move |req: Request<Body>| future::ok::<_, hyper::Error>(route(req, &body)))
+    });
+
+    let bind_addr = (config.listen_address, config.port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind ceremony server to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    let actual_listen_addr = server.local_addr();
+
+    let (exit_signal, exit) = exit_future::signal();
+    let inner_log = log.clone();
+    let server_exit = exit.and_then(move |_| {
+        info!(inner_log, "Genesis ceremony HTTP server shutdown");
+        Ok(())
+    });
+    let inner_log = log.clone();
+    let server_future = server.with_graceful_shutdown(server_exit).map_err(move |e| {
+        warn!(inner_log, "Genesis ceremony HTTP server failed"; "error" => format!("{:?}", e))
+    });
+
+    info!(
+        log,
+        "Genesis ceremony HTTP server started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    executor.spawn(server_future, "genesis_ceremony");
+
+    Ok((exit_signal, actual_listen_addr))
+}
+
+fn route(req: Request<Body>, body: &str) -> Response<Body> {
+    let (status, content_type, body) = match (req.method(), req.uri().path()) {
+        (&Method::GET, GENESIS_PATH) => (StatusCode::OK, "application/json", body.to_string()),
+        _ => (
+            StatusCode::NOT_FOUND,
+            "text/plain; charset=utf-8",
+            "Request path and/or method not found.".to_string(),
+        ),
+    };
+
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    if let Ok(content_type) = content_type.parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+/// Returns a future which polls each of the `peers` in `config` every `poll_interval`, resolving
+/// once `quorum` of them report the same genesis root as `candidate`.
+///
+/// Peers which report a different genesis are logged, but do not cause the ceremony to fail; it
+/// is left to the operators to decide which deposit history is correct.
+pub fn wait_for_quorum(
+    config: &CeremonyConfig,
+    candidate: CandidateGenesis,
+    poll_interval: Duration,
+    log: Logger,
+) -> impl Future<Item = (), Error = String> {
+    let peers = config.peers.clone();
+    let quorum = config.quorum;
+
+    info!(
+        log,
+        "Waiting for genesis ceremony quorum";
+        "genesis_root" => format!("{:?}", candidate.genesis_root),
+        "peers" => peers.len(),
+        "quorum" => quorum,
+    );
+
+    ClientBuilder::new()
+        .timeout(PEER_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Unable to build HTTP client: {:?}", e))
+        .into_future()
+        .and_then(move |client| {
+            loop_fn((), move |()| {
+                let requests = peers
+                    .iter()
+                    .map(|peer| {
+                        let peer = peer.clone();
+                        let url = format!("{}{}", peer.trim_end_matches('/'), GENESIS_PATH);
+
+                        client
+                            .get(&url)
+                            .send()
+                            .and_then(|response| response.error_for_status())
+                            .and_then(|mut response| response.json::<CandidateGenesis>())
+                            .then(move |result| Ok((peer, result.map_err(|e| format!("{}", e)))))
+                    })
+                    .collect::<Vec<_>>();
+
+                let candidate = candidate.clone();
+                let log = log.clone();
+                future::join_all(requests).and_then(move |responses| {
+                    let tally = Tally::new(&candidate, responses);
+                    log_tally(&tally, &candidate, quorum, &log);
+
+                    if tally.agreeing.len() >= quorum {
+                        Box::new(future::ok(Loop::Break(())))
+                            as Box<dyn Future<Item = _, Error = _> + Send>
+                    } else {
+                        Box::new(
+                            Delay::new(Instant::now() + poll_interval)
+                                .map_err(|e| format!("Genesis ceremony delay failed: {:?}", e))
+                                .map(|()| Loop::Continue(())),
+                        )
+                    }
+                })
+            })
+        })
+}
+
+fn log_tally(tally: &Tally, candidate: &CandidateGenesis, quorum: usize, log: &Logger) {
+    for (peer, theirs) in &tally.divergent {
+        crit!(
+            log,
+            "Peer computed a different genesis state";
+            "peer" => peer,
+            "peer_genesis_root" => format!("{:?}", theirs.genesis_root),
+            "peer_deposit_count" => theirs.deposit_count,
+            "peer_eth1_block_hash" => format!("{:?}", theirs.eth1_block_hash),
+            "genesis_root" => format!("{:?}", candidate.genesis_root),
+            "deposit_count" => candidate.deposit_count,
+            "eth1_block_hash" => format!("{:?}", candidate.eth1_block_hash),
+        );
+    }
+
+    for (peer, error) in &tally.unreachable {
+        warn!(
+            log,
+            "Unable to read genesis from peer";
+            "peer" => peer,
+            "error" => error,
+        );
+    }
+
+    if tally.agreeing.len() >= quorum {
+        info!(
+            log,
+            "Genesis ceremony quorum reached";
+            "agreeing_peers" => tally.agreeing.len(),
+            "quorum" => quorum,
+        );
+    } else {
+        info!(
+            log,
+            "Genesis ceremony quorum not reached";
+            "agreeing_peers" => tally.agreeing.len(),
+            "divergent_peers" => tally.divergent.len(),
+            "unreachable_peers" => tally.unreachable.len(),
+            "quorum" => quorum,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(root: u64) -> CandidateGenesis {
+        CandidateGenesis {
+            genesis_root: Hash256::from_low_u64_be(root),
+            genesis_time: 42,
+            deposit_count: 16,
+            eth1_block_hash: Hash256::from_low_u64_be(7),
+        }
+    }
+
+    #[test]
+    fn tally_compares_genesis_roots() {
+        let ours = candidate(1);
+        let responses = vec![
+            ("a".to_string(), Ok(candidate(1))),
+            ("b".to_string(), Ok(candidate(2))),
+            ("c".to_string(), Err("connection refused".to_string())),
+            ("d".to_string(), Ok(candidate(1))),
+        ];
+
+        assert_eq!(
+            Tally::new(&ours, responses),
+            Tally {
+                agreeing: vec!["a".to_string(), "d".to_string()],
+                divergent: vec![("b".to_string(), candidate(2))],
+                unreachable: vec![("c".to_string(), "connection refused".to_string())],
+            }
+        );
+    }
+}
@@ -39,7 +39,7 @@ impl Eth1GenesisService {
     /// Creates a new service. Does not attempt to connect to the Eth1 node.
     ///
     /// Modifies the given `config` to make it more suitable to the task of listening to genesis.
-    pub fn new(config: Eth1Config, log: Logger) -> Self {
+    pub fn new(config: Eth1Config, log: Logger, spec: ChainSpec) -> Self {
         let config = Eth1Config {
             // Truncating the block cache makes searching for genesis more
             // complicated.
@@ -61,7 +61,7 @@ impl Eth1GenesisService {
         };
 
         Self {
-            core: Service::new(config, log),
+            core: Service::new(config, log, spec),
             highest_processed_block: Arc::new(Mutex::new(None)),
             sync_blocks: Arc::new(Mutex::new(false)),
         }
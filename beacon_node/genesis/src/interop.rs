@@ -6,8 +6,7 @@ use state_processing::initialize_beacon_state_from_eth1;
 use std::time::SystemTime;
 use tree_hash::SignedRoot;
 use types::{
-    BeaconState, ChainSpec, DepositData, Domain, EthSpec, Fork, Hash256, Keypair, PublicKey,
-    Signature,
+    BeaconState, ChainSpec, DepositData, Domain, EthSpec, Fork, Hash256, Keypair, Signature,
 };
 
 /// Builds a genesis state as defined by the Eth2 interop procedure (see below).
@@ -19,51 +18,157 @@ pub fn interop_genesis_state<T: EthSpec>(
     genesis_time: u64,
     spec: &ChainSpec,
 ) -> Result<BeaconState<T>, String> {
-    let eth1_block_hash = Hash256::from_slice(&[0x42; 32]);
-    let eth1_timestamp = 2_u64.pow(40);
-    let amount = spec.max_effective_balance;
-
-    let withdrawal_credentials = |pubkey: &PublicKey| {
-        let mut credentials = hash(&pubkey.as_ssz_bytes());
-        credentials[0] = spec.bls_withdrawal_prefix_byte;
-        Hash256::from_slice(&credentials)
-    };
-
-    let datas = keypairs
-        .into_par_iter()
-        .map(|keypair| {
-            let mut data = DepositData {
-                withdrawal_credentials: withdrawal_credentials(&keypair.pk),
-                pubkey: keypair.pk.clone().into(),
-                amount,
-                signature: Signature::empty_signature().into(),
-            };
-
-            let domain = spec.get_domain(
-                spec.genesis_slot.epoch(T::slots_per_epoch()),
-                Domain::Deposit,
-                &Fork::default(),
-            );
-            data.signature = Signature::new(&data.signed_root()[..], domain, &keypair.sk).into();
+    GenesisStateBuilder::new(keypairs, genesis_time).build(spec)
+}
 
-            data
-        })
-        .collect::<Vec<_>>();
+/// Builds a genesis state from the deposits of the given keypairs.
+///
+/// By default, the state is as described by the Eth2 interop procedure (see
+/// `interop_genesis_state`): each validator deposits `spec.max_effective_balance` (and is
+/// therefore active at genesis) with withdrawal credentials derived from its public key. Each of
+/// these may be overridden, so that tests can start from unusual genesis states.
+pub struct GenesisStateBuilder {
+    keypairs: Vec<Keypair>,
+    genesis_time: u64,
+    balances: Option<Vec<u64>>,
+    withdrawal_credentials: Vec<(usize, Hash256)>,
+    pending: Vec<usize>,
+    eth1_block_hash: Hash256,
+    eth1_timestamp: u64,
+}
 
-    let mut state = initialize_beacon_state_from_eth1(
-        eth1_block_hash,
-        eth1_timestamp,
-        genesis_deposits(datas, spec)?,
-        spec,
-    )
-    .map_err(|e| format!("Unable to initialize genesis state: {:?}", e))?;
+impl GenesisStateBuilder {
+    /// Starts building a state with a validator for each of `keypairs`.
+    pub fn new(keypairs: &[Keypair], genesis_time: u64) -> Self {
+        Self {
+            keypairs: keypairs.to_vec(),
+            genesis_time,
+            balances: None,
+            withdrawal_credentials: vec![],
+            pending: vec![],
+            eth1_block_hash: Hash256::from_slice(&[0x42; 32]),
+            eth1_timestamp: 2_u64.pow(40),
+        }
+    }
+
+    /// Sets the deposit amount (in Gwei) of each validator, in the order of the keypairs.
+    ///
+    /// Validators which deposit less than `spec.max_effective_balance` are not active at genesis.
+    pub fn balances(mut self, balances: Vec<u64>) -> Self {
+        self.balances = Some(balances);
+        self
+    }
+
+    /// Sets the withdrawal credentials of the validator at `index`.
+    pub fn withdrawal_credentials(mut self, index: usize, credentials: Hash256) -> Self {
+        self.withdrawal_credentials.push((index, credentials));
+        self
+    }
+
+    /// Leaves the validators at `indices` in the activation queue at genesis, even if they have
+    /// deposited `spec.max_effective_balance`. They are eligible for activation, but not active.
+    pub fn pending_validators(mut self, indices: &[usize]) -> Self {
+        self.pending.extend_from_slice(indices);
+        self
+    }
+
+    /// Sets the hash of the eth1 block from which the genesis state is produced.
+    pub fn eth1_block_hash(mut self, eth1_block_hash: Hash256) -> Self {
+        self.eth1_block_hash = eth1_block_hash;
+        self
+    }
+
+    /// Sets the timestamp of the eth1 block from which the genesis state is produced.
+    pub fn eth1_timestamp(mut self, eth1_timestamp: u64) -> Self {
+        self.eth1_timestamp = eth1_timestamp;
+        self
+    }
+
+    /// Produces the genesis state.
+    pub fn build<T: EthSpec>(self, spec: &ChainSpec) -> Result<BeaconState<T>, String> {
+        let validator_count = self.keypairs.len();
+        let check_index = |index: usize| {
+            if index < validator_count {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Validator index {} is out of bounds for {} validators",
+                    index, validator_count
+                ))
+            }
+        };
+
+        let balances = match self.balances {
+            Some(balances) if balances.len() != validator_count => {
+                return Err(format!(
+                    "Expected {} balances, got {}",
+                    validator_count,
+                    balances.len()
+                ))
+            }
+            Some(balances) => balances,
+            None => vec![spec.max_effective_balance; validator_count],
+        };
+
+        let mut withdrawal_credentials = self
+            .keypairs
+            .iter()
+            .map(|keypair| {
+                let mut credentials = hash(&keypair.pk.as_ssz_bytes());
+                credentials[0] = spec.bls_withdrawal_prefix_byte;
+                Hash256::from_slice(&credentials)
+            })
+            .collect::<Vec<_>>();
+        for (index, credentials) in self.withdrawal_credentials {
+            check_index(index)?;
+            withdrawal_credentials[index] = credentials;
+        }
 
-    state.genesis_time = genesis_time;
+        let datas = self
+            .keypairs
+            .par_iter()
+            .zip(balances.into_par_iter())
+            .zip(withdrawal_credentials.into_par_iter())
+            .map(|((keypair, amount), withdrawal_credentials)| {
+                let mut data = DepositData {
+                    withdrawal_credentials,
+                    pubkey: keypair.pk.clone().into(),
+                    amount,
+                    signature: Signature::empty_signature().into(),
+                };
 
-    // Invalid all the caches after all the manual state surgery.
-    state.drop_all_caches();
+                let domain = spec.get_domain(
+                    spec.genesis_slot.epoch(T::slots_per_epoch()),
+                    Domain::Deposit,
+                    &Fork::default(),
+                );
+                data.signature =
+                    Signature::new(&data.signed_root()[..], domain, &keypair.sk).into();
 
-    Ok(state)
+                data
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = initialize_beacon_state_from_eth1(
+            self.eth1_block_hash,
+            self.eth1_timestamp,
+            genesis_deposits(datas, spec)?,
+            spec,
+        )
+        .map_err(|e| format!("Unable to initialize genesis state: {:?}", e))?;
+
+        state.genesis_time = self.genesis_time;
+
+        for index in self.pending {
+            check_index(index)?;
+            state.validators[index].activation_epoch = spec.far_future_epoch;
+        }
+
+        // Invalid all the caches after all the manual state surgery.
+        state.drop_all_caches();
+
+        Ok(state)
+    }
 }
 
 /// Returns the system time, mod 30 minutes.
@@ -139,4 +244,56 @@ mod test {
             "validator count should be correct"
         );
     }
+
+    #[test]
+    fn configurable_state() {
+        let spec = &TestEthSpec::default_spec();
+        let keypairs = generate_deterministic_keypairs(4);
+        let half_balance = spec.max_effective_balance / 2;
+        let credentials = Hash256::from_low_u64_be(7);
+        let eth1_block_hash = Hash256::from_low_u64_be(42);
+
+        let state = GenesisStateBuilder::new(&keypairs, 42)
+            .balances(vec![
+                spec.max_effective_balance,
+                half_balance,
+                spec.max_effective_balance,
+                spec.max_effective_balance + 1,
+            ])
+            .withdrawal_credentials(3, credentials)
+            .pending_validators(&[2])
+            .eth1_block_hash(eth1_block_hash)
+            .eth1_timestamp(1_000)
+            .build::<TestEthSpec>(spec)
+            .expect("should build state");
+
+        let genesis_epoch = TestEthSpec::genesis_epoch();
+        let is_active = |i: usize| state.validators[i].is_active_at(genesis_epoch);
+
+        assert!(is_active(0));
+        assert!(!is_active(1), "partial deposits are not activated");
+        assert_eq!(state.validators[1].effective_balance, half_balance);
+        assert!(!is_active(2), "pending validators are not activated");
+        assert_eq!(
+            state.validators[2].activation_eligibility_epoch,
+            genesis_epoch
+        );
+        assert!(is_active(3));
+        assert_eq!(state.balances[3], spec.max_effective_balance + 1);
+        assert_eq!(
+            state.validators[3].effective_balance,
+            spec.max_effective_balance
+        );
+        assert_eq!(state.validators[3].withdrawal_credentials, credentials);
+        assert_eq!(state.eth1_data.block_hash, eth1_block_hash);
+
+        assert!(GenesisStateBuilder::new(&keypairs, 42)
+            .balances(vec![spec.max_effective_balance])
+            .build::<TestEthSpec>(spec)
+            .is_err());
+        assert!(GenesisStateBuilder::new(&keypairs, 42)
+            .pending_validators(&[4])
+            .build::<TestEthSpec>(spec)
+            .is_err());
+    }
 }
@@ -1,10 +1,14 @@
+mod ceremony;
 mod common;
 mod eth1_genesis_service;
 mod interop;
 
+pub use ceremony::{
+    start_server as start_ceremony_server, wait_for_quorum, CandidateGenesis, CeremonyConfig, Tally,
+};
 pub use eth1::Config as Eth1Config;
 pub use eth1_genesis_service::Eth1GenesisService;
-pub use interop::{interop_genesis_state, recent_genesis_time};
+pub use interop::{interop_genesis_state, recent_genesis_time, GenesisStateBuilder};
 pub use types::test_utils::generate_deterministic_keypairs;
 
 use ssz::Decode;
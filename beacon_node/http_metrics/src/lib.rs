@@ -0,0 +1,149 @@
+//! Provides an unauthenticated HTTP server which serves the beacon node's Prometheus metrics at
+//! `GET /metrics`.
+//!
+//! The metrics are kept in the global `lighthouse_metrics` registry, to which the store, beacon
+//! chain, network, sync and eth1 crates each contribute. Most metrics are updated as events occur,
+//! however some (e.g., the head state and the process metrics) are only read at the time of the
+//! scrape.
+#[macro_use]
+extern crate lazy_static;
+
+mod process;
+
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
+use exit_future::Signal;
+use futures::{future, Future};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use lighthouse_metrics::{Encoder, TextEncoder};
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The path at which metrics are served.
+const METRICS_PATH: &str = "/metrics";
+
+/// Metrics HTTP server configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the metrics HTTP server.
+    pub enabled: bool,
+    /// The IPv4 address the metrics HTTP server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the metrics HTTP server will listen on.
+    pub port: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5054,
+        }
+    }
+}
+
+/// The components which are read each time the metrics are scraped.
+struct Shared<T: BeaconChainTypes> {
+    beacon_chain: Arc<BeaconChain<T>>,
+    db_path: PathBuf,
+    freezer_db_path: PathBuf,
+}
+
+/// Starts the metrics HTTP server, returning a `Signal` which stops it.
+pub fn start_server<T: BeaconChainTypes>(
+    config: &Config,
+    executor: &TaskExecutor,
+    beacon_chain: Arc<BeaconChain<T>>,
+    db_path: PathBuf,
+    freezer_db_path: PathBuf,
+    log: Logger,
+) -> Result<(Signal, SocketAddr), String> {
+    let shared = Arc::new(Shared {
+        beacon_chain,
+        db_path,
+        freezer_db_path,
+    });
+
+    // Define the function that will build the request handler.
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let shared = shared.clone();
+
+        service_fn(move |req: Request<Body>| future::ok::<_, hyper::Error>(route(req, &shared)))
+    });
+
+    let bind_addr = (config.listen_address, config.port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind metrics server to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    // Determine the address the server is actually listening on.
+    //
+    // This may be different to `bind_addr` if bind port was 0 (this allows the OS to choose a free
+    // port).
+    let actual_listen_addr = server.local_addr();
+
+    // Build a channel to kill the HTTP server.
+    let (exit_signal, exit) = exit_future::signal();
+    let inner_log = log.clone();
+    let server_exit = exit.and_then(move |_| {
+        info!(inner_log, "Metrics HTTP server shutdown");
+        Ok(())
+    });
+    // Configure the `hyper` server to gracefully shutdown when the shutdown channel is triggered.
+    let inner_log = log.clone();
+    let server_future = server.with_graceful_shutdown(server_exit).map_err(
+        move |e| warn!(inner_log, "Metrics HTTP server failed"; "error" => format!("{:?}", e)),
+    );
+
+    info!(
+        log,
+        "Metrics HTTP server started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    executor.spawn(server_future, "http_metrics");
+
+    Ok((exit_signal, actual_listen_addr))
+}
+
+fn route<T: BeaconChainTypes>(req: Request<Body>, shared: &Shared<T>) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, METRICS_PATH) => match gather_metrics(shared) {
+            Ok(body) => response(StatusCode::OK, body),
+            Err(e) => response(StatusCode::INTERNAL_SERVER_ERROR, e),
+        },
+        _ => response(StatusCode::NOT_FOUND, "Request path not found".to_string()),
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(body))
+        .expect("Response should always be created.")
+}
+
+/// Updates the metrics which are only read at the time of the scrape, then returns all metrics
+/// in the Prometheus text format.
+fn gather_metrics<T: BeaconChainTypes>(shared: &Shared<T>) -> Result<String, String> {
+    slot_clock::scrape_for_metrics::<T::EthSpec, T::SlotClock>(&shared.beacon_chain.slot_clock);
+    store::scrape_for_metrics(&shared.db_path, &shared.freezer_db_path);
+    beacon_chain::scrape_for_metrics(&shared.beacon_chain);
+    process::scrape_for_metrics();
+
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&lighthouse_metrics::gather(), &mut buffer)
+        .map_err(|e| format!("Unable to encode metrics: {:?}", e))?;
+
+    String::from_utf8(buffer).map_err(|e| format!("Unable to encode metrics as UTF-8: {:?}", e))
+}
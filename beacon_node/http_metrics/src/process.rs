@@ -0,0 +1,96 @@
+//! Metrics describing the resources used by this process.
+//!
+//! These are read from `/proc/self` and are only available on Linux. On other platforms the
+//! metrics are registered but never updated.
+use lighthouse_metrics::*;
+
+lazy_static! {
+    pub static ref PROCESS_OPEN_FDS: Result<IntGauge> =
+        try_create_int_gauge("process_open_fds", "Number of open file descriptors");
+    pub static ref PROCESS_RESIDENT_MEMORY: Result<IntGauge> = try_create_int_gauge(
+        "process_resident_memory_bytes",
+        "Resident memory size in bytes"
+    );
+    pub static ref PROCESS_CPU_SECONDS: Result<IntGauge> = try_create_int_gauge(
+        "process_cpu_seconds_total",
+        "Total user and system CPU time spent, in seconds"
+    );
+}
+
+/// Updates the process metrics with the present values from `/proc/self`.
+#[cfg(target_os = "linux")]
+pub fn scrape_for_metrics() {
+    if let Some(open_fds) = open_fds() {
+        set_gauge(&PROCESS_OPEN_FDS, open_fds as i64);
+    }
+    if let Some(resident_memory) = resident_memory_bytes() {
+        set_gauge(&PROCESS_RESIDENT_MEMORY, resident_memory as i64);
+    }
+    if let Some(cpu_seconds) = cpu_seconds() {
+        set_gauge(&PROCESS_CPU_SECONDS, cpu_seconds as i64);
+    }
+}
+
+/// Process metrics are not supported on this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn scrape_for_metrics() {}
+
+#[cfg(target_os = "linux")]
+fn open_fds() -> Option<usize> {
+    std::fs::read_dir("/proc/self/fd")
+        .ok()
+        .map(|entries| entries.count())
+}
+
+/// Reads the `VmRSS` line (in kB) of `/proc/self/status`.
+#[cfg(target_os = "linux")]
+fn resident_memory_bytes() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u64>()
+        .ok()
+        .map(|kb| kb * 1024)
+}
+
+/// Reads the `utime` and `stime` fields (in clock ticks) of `/proc/self/stat`.
+#[cfg(target_os = "linux")]
+fn cpu_seconds() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The second field is the executable name in parentheses, which may contain spaces.
+    let fields = stat
+        .get(stat.rfind(')')? + 1..)?
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    // `utime` and `stime` are the 14th and 15th fields, counting the two fields skipped above.
+    let utime = fields.get(11)?.parse::<u64>().ok()?;
+    let stime = fields.get(12)?.parse::<u64>().ok()?;
+
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second > 0 {
+        Some((utime + stime) / ticks_per_second as u64)
+    } else {
+        None
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_proc_self() {
+        assert!(
+            open_fds().unwrap() > 0,
+            "should have stdin/stdout/stderr open"
+        );
+        assert!(
+            resident_memory_bytes().unwrap() > 0,
+            "should use some memory"
+        );
+        assert!(cpu_seconds().is_some(), "should read cpu time");
+    }
+}
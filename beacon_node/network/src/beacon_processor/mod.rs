@@ -0,0 +1,625 @@
+//! Provides the `BeaconProcessor`, which receives work from the network (gossip blocks, gossip
+//! attestations, gossip exits and slashings and RPC block responses) and processes it on a
+//! bounded pool of workers.
+//!
+//! Work which arrives whilst all workers are busy is held in a queue for its gossip topic (or for
+//! RPC blocks). Each time a worker becomes idle, it is given work from the highest-priority
+//! non-empty queue:
+//!
+//! 1. Gossip blocks.
+//! 2. RPC block responses.
+//! 3. Gossip voluntary exits, proposer slashings and attester slashings (one queue each).
+//! 4. Gossip aggregate attestations.
+//! 5. Gossip unaggregated attestations.
+//!
+//! Each queue has a drop policy:
+//!
+//! - Blocks are never dropped, since a missed block must be fetched again by sync.
+//! - Operations are dropped when their queue is full.
+//! - Attestations are processed newest-first; the oldest attestations are dropped when their queue
+//!   is full, and any attestation which is no longer useful by the time it reaches the front of its
+//!   queue is dropped without being processed.
+//!
+//! Drops are counted per queue in the `beacon_processor_work_dropped_total` metric. A warning is
+//! logged if work is dropped for several consecutive intervals, which indicates that the node
+//! cannot keep up with the network.
+use crate::metrics;
+use crate::service::NetworkMessage;
+use crate::sync::SyncMessage;
+use beacon_chain::slot_clock::SlotClock;
+use beacon_chain::{with_request_id, BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
+use eth2_libp2p::rpc::RequestId;
+use eth2_libp2p::PeerId;
+use futures::{Future, Stream};
+use slog::{crit, debug, o, trace, warn};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use types::{
+    Attestation, AttesterSlashing, BeaconBlock, EthSpec, ProposerSlashing, SignedAggregateAndProof,
+    Slot, VoluntaryExit,
+};
+pub use worker::Worker;
+
+mod worker;
+
+/// The maximum number of workers processing work concurrently.
+pub const MAX_WORKERS: usize = 4;
+
+/// The maximum length of each of the queues of gossip exits and slashings.
+const MAX_GOSSIP_OPERATION_QUEUE_LEN: usize = 4_096;
+/// The maximum length of the queue of gossip aggregate attestations.
+const MAX_AGGREGATED_ATTESTATION_QUEUE_LEN: usize = 1_024;
+/// The maximum length of the queue of gossip unaggregated attestations.
+const MAX_UNAGGREGATED_ATTESTATION_QUEUE_LEN: usize = 16_384;
+
+/// The labels of the queues in the `beacon_processor_*` metrics.
+const GOSSIP_BLOCK_QUEUE: &str = "gossip_block";
+const RPC_BLOCK_QUEUE: &str = "rpc_block";
+const GOSSIP_EXIT_QUEUE: &str = "gossip_voluntary_exit";
+const GOSSIP_PROPOSER_SLASHING_QUEUE: &str = "gossip_proposer_slashing";
+const GOSSIP_ATTESTER_SLASHING_QUEUE: &str = "gossip_attester_slashing";
+const AGGREGATED_ATTESTATION_QUEUE: &str = "gossip_aggregate";
+const UNAGGREGATED_ATTESTATION_QUEUE: &str = "gossip_attestation";
+
+/// The length of the intervals over which dropped work is counted.
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+/// The number of consecutive intervals in which work must be dropped before a warning is logged.
+const SUSTAINED_DROP_INTERVALS: usize = 3;
+
+/// A unit of work to be processed by the `BeaconProcessor`.
+#[derive(Debug)]
+pub enum WorkEvent<E: EthSpec> {
+    /// A block received via gossip, which is propagated if it is valid.
+    GossipBlock {
+        message_id: String,
+        peer_id: PeerId,
+        block: Box<BeaconBlock<E>>,
+    },
+    /// A block received via the experimental gossip topic of blocks which carry an execution
+    /// witness. It is propagated if both the block and its witness are valid.
+    GossipBlockAndWitness {
+        message_id: String,
+        peer_id: PeerId,
+        block: Box<BeaconBlock<E>>,
+    },
+    /// An unaggregated attestation received via gossip.
+    GossipAttestation {
+        peer_id: PeerId,
+        attestation: Box<Attestation<E>>,
+    },
+    /// An aggregate attestation received via gossip.
+    GossipAggregate {
+        peer_id: PeerId,
+        aggregate: Box<SignedAggregateAndProof<E>>,
+    },
+    /// A voluntary exit received via gossip, which is propagated if it is valid.
+    GossipVoluntaryExit {
+        message_id: String,
+        peer_id: PeerId,
+        exit: Box<VoluntaryExit>,
+    },
+    /// A proposer slashing received via gossip, which is propagated if it is valid.
+    GossipProposerSlashing {
+        message_id: String,
+        peer_id: PeerId,
+        slashing: Box<ProposerSlashing>,
+    },
+    /// An attester slashing received via gossip, which is propagated if it is valid.
+    GossipAttesterSlashing {
+        message_id: String,
+        peer_id: PeerId,
+        slashing: Box<AttesterSlashing<E>>,
+    },
+    /// A block in response to a `BlocksByRange` request, or `None` at the end of the stream.
+    BlocksByRangeResponse {
+        peer_id: PeerId,
+        request_id: RequestId,
+        beacon_block: Option<Box<BeaconBlock<E>>>,
+    },
+    /// A block in response to a `BlocksByRoot` request, or `None` at the end of the stream.
+    BlocksByRootResponse {
+        peer_id: PeerId,
+        request_id: RequestId,
+        beacon_block: Option<Box<BeaconBlock<E>>>,
+    },
+}
+
+impl<E: EthSpec> WorkEvent<E> {
+    /// Returns the slot of the attestation, if this event is an attestation.
+    fn attestation_slot(&self) -> Option<Slot> {
+        match self {
+            WorkEvent::GossipAttestation { attestation, .. } => Some(attestation.data.slot),
+            WorkEvent::GossipAggregate { aggregate, .. } => {
+                Some(aggregate.message.aggregate.data.slot)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The events received by the manager of the `BeaconProcessor`.
+enum InboundEvent<E: EthSpec> {
+    /// Work received from the network.
+    Work(WorkEvent<E>),
+    /// A worker has finished its work.
+    WorkerIdle,
+}
+
+/// A queue which processes the oldest work first and drops new work when full.
+struct FifoQueue<T> {
+    queue: VecDeque<T>,
+    /// The maximum length of the queue, or `None` if work is never dropped.
+    max_length: Option<usize>,
+}
+
+impl<T> FifoQueue<T> {
+    fn new(max_length: usize) -> Self {
+        Self {
+            queue: VecDeque::default(),
+            max_length: Some(max_length),
+        }
+    }
+
+    /// Instantiates a queue which is never full.
+    fn unbounded() -> Self {
+        Self {
+            queue: VecDeque::default(),
+            max_length: None,
+        }
+    }
+
+    /// Adds `item` to the back of the queue, returning `false` if it was dropped because the queue
+    /// is full.
+    fn push(&mut self, item: T) -> bool {
+        if Some(self.queue.len()) == self.max_length {
+            false
+        } else {
+            self.queue.push_back(item);
+            true
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.queue.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+/// A queue which processes the newest work first and drops the oldest work when full.
+struct LifoQueue<T> {
+    queue: VecDeque<T>,
+    max_length: usize,
+}
+
+impl<T> LifoQueue<T> {
+    fn new(max_length: usize) -> Self {
+        Self {
+            queue: VecDeque::default(),
+            max_length,
+        }
+    }
+
+    /// Adds `item` to the front of the queue, returning `false` if the oldest item was dropped to
+    /// make room for it.
+    fn push(&mut self, item: T) -> bool {
+        let dropped = if self.queue.len() == self.max_length {
+            self.queue.pop_back();
+            true
+        } else {
+            false
+        };
+        self.queue.push_front(item);
+        !dropped
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.queue.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+/// Counts the work dropped by each queue, to detect sustained drops.
+struct DropTracker {
+    interval_start: Instant,
+    /// The work dropped in the present interval, by queue.
+    dropped: BTreeMap<&'static str, u64>,
+    /// The number of consecutive intervals (prior to the present one) in which work was dropped.
+    consecutive_intervals: usize,
+}
+
+impl DropTracker {
+    fn new(now: Instant) -> Self {
+        Self {
+            interval_start: now,
+            dropped: BTreeMap::new(),
+            consecutive_intervals: 0,
+        }
+    }
+
+    fn record(&mut self, queue: &'static str) {
+        *self.dropped.entry(queue).or_insert(0) += 1;
+    }
+
+    /// Ends the present interval if it has elapsed at `now`, returning the work dropped in it if
+    /// work has been dropped in at least `SUSTAINED_DROP_INTERVALS` consecutive intervals.
+    fn end_interval(&mut self, now: Instant) -> Option<BTreeMap<&'static str, u64>> {
+        if now.duration_since(self.interval_start) < DROP_REPORT_INTERVAL {
+            return None;
+        }
+
+        self.interval_start = now;
+        let dropped = std::mem::replace(&mut self.dropped, BTreeMap::new());
+
+        if dropped.is_empty() {
+            self.consecutive_intervals = 0;
+            None
+        } else {
+            self.consecutive_intervals += 1;
+            if self.consecutive_intervals >= SUSTAINED_DROP_INTERVALS {
+                Some(dropped)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Queues work from the network and dispatches it to a bounded pool of workers, in order of
+/// priority.
+pub struct BeaconProcessor<T: BeaconChainTypes> {
+    chain: Arc<BeaconChain<T>>,
+    network_send: mpsc::UnboundedSender<NetworkMessage>,
+    sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+    executor: TaskExecutor,
+    /// Used by workers to signal that they have finished their work.
+    idle_send: mpsc::UnboundedSender<()>,
+    max_workers: usize,
+    current_workers: usize,
+    gossip_block_queue: FifoQueue<WorkEvent<T::EthSpec>>,
+    rpc_block_queue: FifoQueue<WorkEvent<T::EthSpec>>,
+    exit_queue: FifoQueue<WorkEvent<T::EthSpec>>,
+    proposer_slashing_queue: FifoQueue<WorkEvent<T::EthSpec>>,
+    attester_slashing_queue: FifoQueue<WorkEvent<T::EthSpec>>,
+    aggregate_queue: LifoQueue<WorkEvent<T::EthSpec>>,
+    attestation_queue: LifoQueue<WorkEvent<T::EthSpec>>,
+    drop_tracker: DropTracker,
+    log: slog::Logger,
+}
+
+impl<T: BeaconChainTypes> BeaconProcessor<T> {
+    /// Spawns a `BeaconProcessor` with `MAX_WORKERS` workers, returning the channel on which it
+    /// receives work.
+    pub fn spawn(
+        executor: &TaskExecutor,
+        chain: Arc<BeaconChain<T>>,
+        network_send: mpsc::UnboundedSender<NetworkMessage>,
+        sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        log: &slog::Logger,
+    ) -> mpsc::UnboundedSender<WorkEvent<T::EthSpec>> {
+        let log = log.new(o!("service" => "beacon_processor"));
+        let (work_send, work_recv) = mpsc::unbounded_channel();
+        let (idle_send, idle_recv) = mpsc::unbounded_channel();
+
+        let mut processor = Self {
+            chain,
+            network_send,
+            sync_send,
+            executor: executor.clone(),
+            idle_send,
+            max_workers: MAX_WORKERS,
+            current_workers: 0,
+            gossip_block_queue: FifoQueue::unbounded(),
+            rpc_block_queue: FifoQueue::unbounded(),
+            exit_queue: FifoQueue::new(MAX_GOSSIP_OPERATION_QUEUE_LEN),
+            proposer_slashing_queue: FifoQueue::new(MAX_GOSSIP_OPERATION_QUEUE_LEN),
+            attester_slashing_queue: FifoQueue::new(MAX_GOSSIP_OPERATION_QUEUE_LEN),
+            aggregate_queue: LifoQueue::new(MAX_AGGREGATED_ATTESTATION_QUEUE_LEN),
+            attestation_queue: LifoQueue::new(MAX_UNAGGREGATED_ATTESTATION_QUEUE_LEN),
+            drop_tracker: DropTracker::new(Instant::now()),
+            log: log.clone(),
+        };
+
+        executor.spawn(
+            work_recv
+                .map(InboundEvent::Work)
+                .select(idle_recv.map(|()| InboundEvent::WorkerIdle))
+                .for_each(move |event| {
+                    processor.handle_event(event);
+                    Ok(())
+                })
+                .map_err(move |e| {
+                    debug!(log, "Beacon processor terminated"; "error" => format!("{:?}", e));
+                }),
+            "beacon_processor",
+        );
+
+        work_send
+    }
+
+    /// Starts a worker for new work if one is available, otherwise queues the work. When a worker
+    /// becomes idle, it is given the highest-priority queued work.
+    fn handle_event(&mut self, event: InboundEvent<T::EthSpec>) {
+        let work = match event {
+            InboundEvent::Work(work) if self.current_workers < self.max_workers => Some(work),
+            InboundEvent::Work(work) => {
+                self.enqueue(work);
+                None
+            }
+            InboundEvent::WorkerIdle => {
+                self.current_workers = self.current_workers.saturating_sub(1);
+                self.next_queued_work()
+            }
+        };
+
+        if let Some(work) = work {
+            self.spawn_worker(work);
+        }
+
+        metrics::set_gauge(
+            &metrics::BEACON_PROCESSOR_WORKERS_ACTIVE,
+            self.current_workers as i64,
+        );
+        for (queue, len) in &[
+            (GOSSIP_BLOCK_QUEUE, self.gossip_block_queue.len()),
+            (RPC_BLOCK_QUEUE, self.rpc_block_queue.len()),
+            (GOSSIP_EXIT_QUEUE, self.exit_queue.len()),
+            (
+                GOSSIP_PROPOSER_SLASHING_QUEUE,
+                self.proposer_slashing_queue.len(),
+            ),
+            (
+                GOSSIP_ATTESTER_SLASHING_QUEUE,
+                self.attester_slashing_queue.len(),
+            ),
+            (AGGREGATED_ATTESTATION_QUEUE, self.aggregate_queue.len()),
+            (UNAGGREGATED_ATTESTATION_QUEUE, self.attestation_queue.len()),
+        ] {
+            metrics::set_gauge_vec(
+                &metrics::BEACON_PROCESSOR_QUEUE_LENGTH,
+                &[*queue],
+                *len as i64,
+            );
+        }
+
+        if let Some(dropped) = self.drop_tracker.end_interval(Instant::now()) {
+            warn!(
+                self.log,
+                "Beacon processor is dropping work";
+                "info" => "the node may be under-provisioned for the present network load",
+                "interval_secs" => DROP_REPORT_INTERVAL.as_secs(),
+                "dropped" => format!("{:?}", dropped),
+            );
+        }
+    }
+
+    /// Adds `work` to the queue for its kind.
+    fn enqueue(&mut self, work: WorkEvent<T::EthSpec>) {
+        let (queue, accepted) = match work {
+            WorkEvent::GossipBlock { .. } | WorkEvent::GossipBlockAndWitness { .. } => {
+                (GOSSIP_BLOCK_QUEUE, self.gossip_block_queue.push(work))
+            }
+            WorkEvent::BlocksByRangeResponse { .. } | WorkEvent::BlocksByRootResponse { .. } => {
+                (RPC_BLOCK_QUEUE, self.rpc_block_queue.push(work))
+            }
+            WorkEvent::GossipVoluntaryExit { .. } => {
+                (GOSSIP_EXIT_QUEUE, self.exit_queue.push(work))
+            }
+            WorkEvent::GossipProposerSlashing { .. } => (
+                GOSSIP_PROPOSER_SLASHING_QUEUE,
+                self.proposer_slashing_queue.push(work),
+            ),
+            WorkEvent::GossipAttesterSlashing { .. } => (
+                GOSSIP_ATTESTER_SLASHING_QUEUE,
+                self.attester_slashing_queue.push(work),
+            ),
+            WorkEvent::GossipAggregate { .. } => (
+                AGGREGATED_ATTESTATION_QUEUE,
+                self.aggregate_queue.push(work),
+            ),
+            WorkEvent::GossipAttestation { .. } => (
+                UNAGGREGATED_ATTESTATION_QUEUE,
+                self.attestation_queue.push(work),
+            ),
+        };
+
+        if !accepted {
+            metrics::inc_counter_vec(&metrics::BEACON_PROCESSOR_WORK_DROPPED, &[queue]);
+            self.drop_tracker.record(queue);
+            trace!(self.log, "Beacon processor queue full"; "queue" => queue);
+        }
+    }
+
+    /// Returns the queued work with the highest priority, dropping any stale attestations.
+    fn next_queued_work(&mut self) -> Option<WorkEvent<T::EthSpec>> {
+        if let Some(work) = self.gossip_block_queue.pop() {
+            return Some(work);
+        }
+        if let Some(work) = self.rpc_block_queue.pop() {
+            return Some(work);
+        }
+        if let Some(work) = self.exit_queue.pop() {
+            return Some(work);
+        }
+        if let Some(work) = self.proposer_slashing_queue.pop() {
+            return Some(work);
+        }
+        if let Some(work) = self.attester_slashing_queue.pop() {
+            return Some(work);
+        }
+
+        let current_slot = self.chain.slot_clock.now();
+        let attestation_queue = &mut self.attestation_queue;
+        let drop_tracker = &mut self.drop_tracker;
+        pop_fresh_attestation(
+            &mut self.aggregate_queue,
+            AGGREGATED_ATTESTATION_QUEUE,
+            current_slot,
+            drop_tracker,
+        )
+        .or_else(|| {
+            pop_fresh_attestation(
+                attestation_queue,
+                UNAGGREGATED_ATTESTATION_QUEUE,
+                current_slot,
+                drop_tracker,
+            )
+        })
+    }
+
+    /// Processes `work` on the blocking thread pool, signalling when the worker is idle again.
+    ///
+    /// The work is assigned a request ID, which is included in the lines logged by the worker and
+    /// the `BeaconChain` whilst it is processed.
+    fn spawn_worker(&mut self, work: WorkEvent<T::EthSpec>) {
+        self.current_workers += 1;
+
+        let request_id = beacon_chain::RequestId::next();
+        let worker = Worker::new(
+            self.chain.clone(),
+            self.network_send.clone(),
+            self.sync_send.clone(),
+            self.log.new(o!("request_id" => request_id)),
+        );
+        let mut idle_send = self.idle_send.clone();
+        let log = self.log.clone();
+
+        self.executor.spawn(
+            self.executor
+                .spawn_blocking(
+                    move || with_request_id(request_id, || worker.process(work)),
+                    "beacon_processor_worker",
+                )
+                .then(move |result| {
+                    if let Err(e) = result {
+                        crit!(log, "Beacon processor worker failed"; "error" => e);
+                    }
+                    // The manager must be informed even if the worker failed, otherwise it would
+                    // never be replaced.
+                    idle_send.try_send(()).unwrap_or_else(|_| {
+                        warn!(
+                            log,
+                            "Could not inform the beacon processor of an idle worker"
+                        )
+                    });
+                    Ok(())
+                }),
+            "beacon_processor_worker",
+        );
+    }
+}
+
+/// Pops work from `queue` until an attestation which is not stale is found.
+fn pop_fresh_attestation<E: EthSpec>(
+    queue: &mut LifoQueue<WorkEvent<E>>,
+    label: &'static str,
+    current_slot: Option<Slot>,
+    drop_tracker: &mut DropTracker,
+) -> Option<WorkEvent<E>> {
+    while let Some(work) = queue.pop() {
+        if is_stale(&work, current_slot) {
+            metrics::inc_counter_vec(&metrics::BEACON_PROCESSOR_WORK_DROPPED, &[label]);
+            drop_tracker.record(label);
+        } else {
+            return Some(work);
+        }
+    }
+
+    None
+}
+
+/// Returns `true` if `work` is an attestation from a slot which is more than an epoch prior to
+/// `current_slot`, and so is too old to be useful to fork choice or to block producers.
+fn is_stale<E: EthSpec>(work: &WorkEvent<E>, current_slot: Option<Slot>) -> bool {
+    match (work.attestation_slot(), current_slot) {
+        (Some(slot), Some(current_slot)) => slot + E::slots_per_epoch() < current_slot,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_queue_drops_new_items() {
+        let mut queue = FifoQueue::new(2);
+
+        assert!(queue.push(1));
+        assert!(queue.push(2));
+        assert!(!queue.push(3));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn unbounded_fifo_queue_never_drops_items() {
+        let mut queue = FifoQueue::unbounded();
+
+        assert!((0..10_000).all(|i| queue.push(i)));
+        assert_eq!(queue.len(), 10_000);
+        assert_eq!(queue.pop(), Some(0));
+    }
+
+    #[test]
+    fn lifo_queue_drops_old_items() {
+        let mut queue = LifoQueue::new(2);
+
+        assert!(queue.push(1));
+        assert!(queue.push(2));
+        assert!(!queue.push(3));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn drop_tracker_reports_sustained_drops() {
+        let start = Instant::now();
+        let interval = |n: u32| start + DROP_REPORT_INTERVAL * n;
+        let mut tracker = DropTracker::new(start);
+
+        // Drops in fewer than `SUSTAINED_DROP_INTERVALS` consecutive intervals are not reported.
+        for n in 1..SUSTAINED_DROP_INTERVALS as u32 {
+            tracker.record(UNAGGREGATED_ATTESTATION_QUEUE);
+            assert_eq!(tracker.end_interval(interval(n)), None);
+        }
+        assert_eq!(
+            tracker.end_interval(interval(SUSTAINED_DROP_INTERVALS as u32)),
+            None
+        );
+
+        for n in 1..=SUSTAINED_DROP_INTERVALS as u32 {
+            tracker.record(UNAGGREGATED_ATTESTATION_QUEUE);
+            tracker.record(GOSSIP_EXIT_QUEUE);
+            // The interval has not yet elapsed.
+            assert_eq!(
+                tracker.end_interval(
+                    interval(SUSTAINED_DROP_INTERVALS as u32 + n) - Duration::from_secs(1)
+                ),
+                None
+            );
+            tracker.record(UNAGGREGATED_ATTESTATION_QUEUE);
+
+            let report = tracker.end_interval(interval(SUSTAINED_DROP_INTERVALS as u32 + n));
+            if n < SUSTAINED_DROP_INTERVALS as u32 {
+                assert_eq!(report, None);
+            } else {
+                let report = report.expect("should report sustained drops");
+                assert_eq!(report.get(UNAGGREGATED_ATTESTATION_QUEUE), Some(&2));
+                assert_eq!(report.get(GOSSIP_EXIT_QUEUE), Some(&1));
+            }
+        }
+    }
+}
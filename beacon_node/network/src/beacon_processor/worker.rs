@@ -0,0 +1,433 @@
+use super::WorkEvent;
+use crate::service::NetworkMessage;
+use crate::sync::{HandlerNetworkContext, SyncMessage, FUTURE_SLOT_TOLERANCE};
+use beacon_chain::slot_clock::{check_block_arrival, CLOCK_DRIFT_WARNING_THRESHOLD};
+use beacon_chain::{
+    AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BlockProcessingOutcome,
+};
+use eth2_libp2p::rpc::methods::GoodbyeReason;
+use eth2_libp2p::PeerId;
+use slog::{debug, error, trace, warn};
+use ssz::Encode;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tree_hash::SignedRoot;
+use types::{
+    Attestation, AttesterSlashing, BeaconBlock, BlockBodyExtension, Hash256, ProposerSlashing,
+    Slot, VoluntaryExit,
+};
+
+/// Processes a single `WorkEvent` on behalf of the `BeaconProcessor`.
+pub struct Worker<T: BeaconChainTypes> {
+    chain: Arc<BeaconChain<T>>,
+    network_send: mpsc::UnboundedSender<NetworkMessage>,
+    sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+    network: HandlerNetworkContext,
+    log: slog::Logger,
+}
+
+impl<T: BeaconChainTypes> Worker<T> {
+    pub fn new(
+        chain: Arc<BeaconChain<T>>,
+        network_send: mpsc::UnboundedSender<NetworkMessage>,
+        sync_send: mpsc::UnboundedSender<SyncMessage<T::EthSpec>>,
+        log: slog::Logger,
+    ) -> Self {
+        Self {
+            chain,
+            network: HandlerNetworkContext::new(network_send.clone(), log.clone()),
+            network_send,
+            sync_send,
+            log,
+        }
+    }
+
+    /// Processes `work`, consuming the worker.
+    pub fn process(mut self, work: WorkEvent<T::EthSpec>) {
+        match work {
+            WorkEvent::GossipBlock {
+                message_id,
+                peer_id,
+                block,
+            } => {
+                if self.verify_execution_witness(&peer_id, &block, false)
+                    && self.process_gossip_block(peer_id.clone(), *block)
+                {
+                    self.propagate_message(message_id, peer_id);
+                }
+            }
+            WorkEvent::GossipBlockAndWitness {
+                message_id,
+                peer_id,
+                block,
+            } => {
+                if self.verify_execution_witness(&peer_id, &block, true)
+                    && self.process_gossip_block(peer_id.clone(), *block)
+                {
+                    self.propagate_message(message_id, peer_id);
+                }
+            }
+            WorkEvent::GossipAttestation {
+                peer_id,
+                attestation,
+            } => self.process_gossip_attestation(peer_id, *attestation),
+            WorkEvent::GossipAggregate { peer_id, aggregate } => {
+                self.chain
+                    .observe_aggregate_arrival(aggregate.message.aggregate.data.slot);
+                // TODO: Verify the selection proof and aggregator signature
+                self.process_gossip_attestation(peer_id, aggregate.message.aggregate)
+            }
+            WorkEvent::GossipVoluntaryExit {
+                message_id,
+                peer_id,
+                exit,
+            } => {
+                if self.process_gossip_voluntary_exit(&peer_id, *exit) {
+                    self.propagate_message(message_id, peer_id);
+                }
+            }
+            WorkEvent::GossipProposerSlashing {
+                message_id,
+                peer_id,
+                slashing,
+            } => {
+                if self.process_gossip_proposer_slashing(&peer_id, *slashing) {
+                    self.propagate_message(message_id, peer_id);
+                }
+            }
+            WorkEvent::GossipAttesterSlashing {
+                message_id,
+                peer_id,
+                slashing,
+            } => {
+                if self.process_gossip_attester_slashing(&peer_id, *slashing) {
+                    self.propagate_message(message_id, peer_id);
+                }
+            }
+            WorkEvent::BlocksByRangeResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                trace!(
+                    self.log,
+                    "Received BlocksByRange Response";
+                    "peer" => format!("{:?}", peer_id),
+                );
+                self.send_to_sync(SyncMessage::BlocksByRangeResponse {
+                    peer_id,
+                    request_id,
+                    beacon_block,
+                });
+            }
+            WorkEvent::BlocksByRootResponse {
+                peer_id,
+                request_id,
+                beacon_block,
+            } => {
+                trace!(
+                    self.log,
+                    "Received BlocksByRoot Response";
+                    "peer" => format!("{:?}", peer_id),
+                );
+                self.send_to_sync(SyncMessage::BlocksByRootResponse {
+                    peer_id,
+                    request_id,
+                    beacon_block,
+                });
+            }
+        }
+    }
+
+    /// Verifies the execution witness carried by a gossip `block`, returning `false` if it is
+    /// invalid, or if the block carries no witness and one is `required`.
+    ///
+    /// Only the structure of the witness is verified. This is where it would be verified against
+    /// the state root of the parent block, once the Verkle commitment scheme is available.
+    fn verify_execution_witness(
+        &self,
+        peer_id: &PeerId,
+        block: &BeaconBlock<T::EthSpec>,
+        required: bool,
+    ) -> bool {
+        match block.body.extension.execution_witness() {
+            Some(witness) => match witness.verify_structure() {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!(
+                        self.log,
+                        "Invalid execution witness";
+                        "peer_id" => format!("{:?}", peer_id),
+                        "block_slot" => block.slot,
+                        "error" => format!("{:?}", e),
+                    );
+                    false
+                }
+            },
+            None if required => {
+                debug!(
+                    self.log,
+                    "Gossip block is missing its execution witness";
+                    "peer_id" => format!("{:?}", peer_id),
+                    "block_slot" => block.slot,
+                );
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Process a gossip message declaring a new block.
+    ///
+    /// Attempts to apply to block to the beacon chain. May queue the block for later processing.
+    ///
+    /// Returns a `bool` which, if `true`, indicates we should forward the block to our peers.
+    fn process_gossip_block(&mut self, peer_id: PeerId, block: BeaconBlock<T::EthSpec>) -> bool {
+        match self.chain.process_block(block.clone()) {
+            Ok(outcome) => match outcome {
+                BlockProcessingOutcome::Processed { .. } => {
+                    trace!(self.log, "Gossipsub block processed";
+                            "peer_id" => format!("{:?}",peer_id));
+                    self.check_clock_drift(block.slot);
+
+                    // TODO: It would be better if we can run this _after_ we publish the block to
+                    // reduce block propagation latency.
+                    match self.chain.fork_choice() {
+                        Ok(()) => trace!(
+                            self.log,
+                            "Fork choice success";
+                            "location" => "block gossip"
+                        ),
+                        Err(e) => error!(
+                            self.log,
+                            "Fork choice failed";
+                            "error" => format!("{:?}", e),
+                            "location" => "block gossip"
+                        ),
+                    }
+
+                    true
+                }
+                BlockProcessingOutcome::ParentUnknown { .. } => {
+                    // Inform the sync manager to find parents for this block
+                    trace!(self.log, "Block with unknown parent received";
+                            "peer_id" => format!("{:?}",peer_id));
+                    self.send_to_sync(SyncMessage::UnknownBlock(peer_id, Box::new(block)));
+                    true
+                }
+                BlockProcessingOutcome::FutureSlot {
+                    present_slot,
+                    block_slot,
+                } if present_slot + FUTURE_SLOT_TOLERANCE >= block_slot => {
+                    self.check_clock_drift(block_slot);
+                    //TODO: Decide the logic here
+                    true
+                }
+                BlockProcessingOutcome::BlockIsAlreadyKnown => true,
+                other => {
+                    warn!(
+                        self.log,
+                        "Invalid gossip beacon block";
+                        "outcome" => format!("{:?}", other),
+                        "block root" => format!("{}", Hash256::from_slice(&block.signed_root()[..])),
+                        "block slot" => block.slot
+                    );
+                    trace!(
+                        self.log,
+                        "Invalid gossip beacon block ssz";
+                        "ssz" => format!("0x{}", hex::encode(block.as_ssz_bytes())),
+                    );
+                    false //TODO: Decide if we want to forward these
+                }
+            },
+            Err(_) => {
+                // error is logged during the processing therefore no error is logged here
+                trace!(
+                    self.log,
+                    "Erroneous gossip beacon block ssz";
+                    "ssz" => format!("0x{}", hex::encode(block.as_ssz_bytes())),
+                );
+                false
+            }
+        }
+    }
+
+    /// Process a gossip message declaring a new attestation.
+    fn process_gossip_attestation(&mut self, peer_id: PeerId, msg: Attestation<T::EthSpec>) {
+        match self.chain.process_attestation(msg.clone()) {
+            Ok(outcome) => match outcome {
+                AttestationProcessingOutcome::Processed => {
+                    debug!(
+                        self.log,
+                        "Processed attestation";
+                        "source" => "gossip",
+                        "outcome" => format!("{:?}", outcome)
+                    );
+                }
+                AttestationProcessingOutcome::UnknownHeadBlock { beacon_block_root } => {
+                    // TODO: Maintain this attestation and re-process once sync completes
+                    debug!(
+                    self.log,
+                    "Attestation for unknown block";
+                    "peer_id" => format!("{:?}", peer_id),
+                    "block" => format!("{}", beacon_block_root)
+                    );
+                    // we don't know the block, get the sync manager to handle the block lookup
+                    self.send_to_sync(SyncMessage::UnknownBlockHash(peer_id, beacon_block_root));
+                }
+                AttestationProcessingOutcome::AttestsToFutureState { .. }
+                | AttestationProcessingOutcome::FinalizedSlot { .. } => {} // ignore the attestation
+                AttestationProcessingOutcome::Invalid { .. }
+                | AttestationProcessingOutcome::EmptyAggregationBitfield { .. } => {
+                    // the peer has sent a bad attestation. Remove them.
+                    self.network.disconnect(peer_id, GoodbyeReason::Fault);
+                }
+            },
+            Err(_) => {
+                // error is logged during the processing therefore no error is logged here
+                trace!(
+                    self.log,
+                    "Erroneous gossip attestation ssz";
+                    "ssz" => format!("0x{}", hex::encode(msg.as_ssz_bytes())),
+                );
+            }
+        }
+    }
+
+    /// Process a gossip message declaring a voluntary exit, adding it to the op pool if it is
+    /// valid.
+    ///
+    /// Returns a `bool` which, if `true`, indicates we should forward the exit to our peers.
+    fn process_gossip_voluntary_exit(&mut self, peer_id: &PeerId, exit: VoluntaryExit) -> bool {
+        let validator_index = exit.validator_index;
+        match self.chain.process_voluntary_exit(exit) {
+            Ok(()) => {
+                debug!(
+                    self.log,
+                    "Processed voluntary exit";
+                    "source" => "gossip",
+                    "validator_index" => validator_index,
+                );
+                true
+            }
+            Err(e) => {
+                debug!(
+                    self.log,
+                    "Invalid gossip voluntary exit";
+                    "peer_id" => format!("{:?}", peer_id),
+                    "validator_index" => validator_index,
+                    "error" => format!("{:?}", e),
+                );
+                false
+            }
+        }
+    }
+
+    /// Process a gossip message declaring a proposer slashing, adding it to the op pool if it is
+    /// valid.
+    ///
+    /// Returns a `bool` which, if `true`, indicates we should forward the slashing to our peers.
+    fn process_gossip_proposer_slashing(
+        &mut self,
+        peer_id: &PeerId,
+        slashing: ProposerSlashing,
+    ) -> bool {
+        let proposer_index = slashing.proposer_index;
+        match self.chain.process_proposer_slashing(slashing) {
+            Ok(()) => {
+                debug!(
+                    self.log,
+                    "Processed proposer slashing";
+                    "source" => "gossip",
+                    "proposer_index" => proposer_index,
+                );
+                true
+            }
+            Err(e) => {
+                debug!(
+                    self.log,
+                    "Invalid gossip proposer slashing";
+                    "peer_id" => format!("{:?}", peer_id),
+                    "proposer_index" => proposer_index,
+                    "error" => format!("{:?}", e),
+                );
+                false
+            }
+        }
+    }
+
+    /// Process a gossip message declaring an attester slashing, adding it to the op pool if it is
+    /// valid.
+    ///
+    /// Returns a `bool` which, if `true`, indicates we should forward the slashing to our peers.
+    fn process_gossip_attester_slashing(
+        &mut self,
+        peer_id: &PeerId,
+        slashing: AttesterSlashing<T::EthSpec>,
+    ) -> bool {
+        match self.chain.process_attester_slashing(slashing) {
+            Ok(()) => {
+                debug!(
+                    self.log,
+                    "Processed attester slashing";
+                    "source" => "gossip",
+                );
+                true
+            }
+            Err(e) => {
+                debug!(
+                    self.log,
+                    "Invalid gossip attester slashing";
+                    "peer_id" => format!("{:?}", peer_id),
+                    "error" => format!("{:?}", e),
+                );
+                false
+            }
+        }
+    }
+
+    /// Warns the user if a block arrived so long before the start of its slot that the local clock
+    /// is probably behind.
+    ///
+    /// Only blocks which were processed, or which are at most `FUTURE_SLOT_TOLERANCE` slots ahead,
+    /// are checked, so that a peer cannot trigger a warning with a block from the distant future.
+    fn check_clock_drift(&self, block_slot: Slot) {
+        if let Some(early_by) = check_block_arrival(
+            &self.chain.slot_clock,
+            block_slot,
+            CLOCK_DRIFT_WARNING_THRESHOLD,
+        ) {
+            warn!(
+                self.log,
+                "System clock may be behind";
+                "msg" => "ensure the system time is synchronized (e.g., using NTP)",
+                "block_slot" => block_slot,
+                "block_early_by_ms" => early_by.as_millis() as u64,
+            );
+        }
+    }
+
+    fn send_to_sync(&mut self, message: SyncMessage<T::EthSpec>) {
+        self.sync_send.try_send(message).unwrap_or_else(|_| {
+            warn!(
+                self.log,
+                "Could not send message to the sync service";
+            )
+        });
+    }
+
+    /// Informs the network service that the message should be forwarded to other peers.
+    fn propagate_message(&mut self, message_id: String, propagation_source: PeerId) {
+        self.network_send
+            .try_send(NetworkMessage::Propagate {
+                propagation_source,
+                message_id,
+            })
+            .unwrap_or_else(|_| {
+                warn!(
+                    self.log,
+                    "Could not send propagation request to the network service"
+                )
+            });
+    }
+}
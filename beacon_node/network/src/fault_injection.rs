@@ -0,0 +1,126 @@
+//! Provides the `FaultInjector`, which allows faults to be injected into the network service of a
+//! running node.
+//!
+//! Intended for testing sync and fork choice recovery. Not for production: a node without any
+//! faults set behaves exactly as it would otherwise.
+use eth2_libp2p::PeerId;
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// The faults which are presently applied to a network service.
+///
+/// Faults only affect messages as they enter or leave this node. To fully partition two nodes,
+/// each must be partitioned from the other.
+#[derive(Default)]
+pub struct FaultInjector {
+    /// The percentage of received gossip messages which are dropped.
+    gossip_drop_percentage: AtomicU8,
+    /// The time for which each gossip block is held before it is passed to the message handler.
+    block_import_delay: RwLock<Duration>,
+    /// The time for which each attestation published by this node is held before it is sent.
+    attestation_publish_delay: RwLock<Duration>,
+    /// Peers from which all messages are dropped, and to which no RPC requests are sent.
+    partitioned_peers: RwLock<HashSet<PeerId>>,
+}
+
+impl FaultInjector {
+    /// Drops `percentage` percent of the gossip messages received from peers.
+    ///
+    /// Values above `100` are treated as `100`.
+    pub fn set_gossip_drop_percentage(&self, percentage: u8) {
+        self.gossip_drop_percentage
+            .store(std::cmp::min(percentage, 100), Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a received gossip message should be dropped.
+    pub fn should_drop_gossip(&self) -> bool {
+        let percentage = self.gossip_drop_percentage.load(Ordering::Relaxed);
+        percentage > 0 && rand::random::<u8>() % 100 < percentage
+    }
+
+    /// Holds each gossip block for `delay` before it is imported.
+    pub fn set_block_import_delay(&self, delay: Duration) {
+        *self.block_import_delay.write() = delay;
+    }
+
+    /// Returns the time for which each gossip block should be held before it is imported.
+    pub fn block_import_delay(&self) -> Duration {
+        *self.block_import_delay.read()
+    }
+
+    /// Holds each attestation published by this node for `delay` before it is sent to peers.
+    pub fn set_attestation_publish_delay(&self, delay: Duration) {
+        *self.attestation_publish_delay.write() = delay;
+    }
+
+    /// Returns the time for which each published attestation should be held before it is sent.
+    pub fn attestation_publish_delay(&self) -> Duration {
+        *self.attestation_publish_delay.read()
+    }
+
+    /// Ignores all messages from `peer_id` and stops sending RPC requests to it.
+    pub fn partition(&self, peer_id: PeerId) {
+        self.partitioned_peers.write().insert(peer_id);
+    }
+
+    /// Reverses a previous call to `partition`.
+    pub fn heal(&self, peer_id: &PeerId) {
+        self.partitioned_peers.write().remove(peer_id);
+    }
+
+    /// Returns `true` if `peer_id` has been partitioned from this node.
+    pub fn is_partitioned(&self, peer_id: &PeerId) -> bool {
+        self.partitioned_peers.read().contains(peer_id)
+    }
+
+    /// Removes all faults.
+    pub fn clear(&self) {
+        self.set_gossip_drop_percentage(0);
+        self.set_block_import_delay(Duration::from_secs(0));
+        self.set_attestation_publish_delay(Duration::from_secs(0));
+        self.partitioned_peers.write().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gossip_drop_percentage() {
+        let faults = FaultInjector::default();
+        assert!((0..1_000).all(|_| !faults.should_drop_gossip()));
+
+        faults.set_gossip_drop_percentage(200);
+        assert!((0..1_000).all(|_| faults.should_drop_gossip()));
+
+        faults.clear();
+        assert!((0..1_000).all(|_| !faults.should_drop_gossip()));
+    }
+
+    #[test]
+    fn attestation_publish_delay() {
+        let faults = FaultInjector::default();
+        assert_eq!(faults.attestation_publish_delay(), Duration::from_secs(0));
+
+        faults.set_attestation_publish_delay(Duration::from_secs(3));
+        assert_eq!(faults.attestation_publish_delay(), Duration::from_secs(3));
+
+        faults.clear();
+        assert_eq!(faults.attestation_publish_delay(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn partition_and_heal() {
+        let faults = FaultInjector::default();
+        let peer_id = PeerId::random();
+
+        assert!(!faults.is_partitioned(&peer_id));
+        faults.partition(peer_id.clone());
+        assert!(faults.is_partitioned(&peer_id));
+        faults.heal(&peer_id);
+        assert!(!faults.is_partitioned(&peer_id));
+    }
+}
@@ -0,0 +1,243 @@
+//! Provides a log of the gossip messages received by a node, so that they may later be replayed
+//! against a fresh chain (see `lcli replay-gossip`) to reproduce networking-triggered consensus
+//! issues.
+//!
+//! A log is a sequence of records, each encoded as:
+//!
+//! - The time at which the message was received, in milliseconds since the UNIX epoch (`u64`).
+//! - The length of the topic (`u16`), followed by the topic (e.g., `beacon_block`).
+//! - The length of the message (`u32`), followed by the SSZ bytes of the message.
+//!
+//! All integers are little-endian.
+use eth2_libp2p::{
+    PubsubMessage, ATTESTER_SLASHING_TOPIC, BEACON_AGGREGATE_AND_PROOF_TOPIC,
+    BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_AND_WITNESS_TOPIC, BEACON_BLOCK_TOPIC,
+    PROPOSER_SLASHING_TOPIC, VOLUNTARY_EXIT_TOPIC,
+};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A gossip message, as received from the network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GossipRecord {
+    /// The time at which the message was received, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub topic: String,
+    pub data: Vec<u8>,
+}
+
+impl GossipRecord {
+    /// Returns a record of `message`, received at `timestamp`, or `None` if the message is from an
+    /// unknown topic.
+    pub fn from_message(timestamp: u64, message: &PubsubMessage) -> Option<Self> {
+        let (topic, data) = match message {
+            PubsubMessage::Block(data) => (BEACON_BLOCK_TOPIC, data),
+            PubsubMessage::BlockAndWitness(data) => (BEACON_BLOCK_AND_WITNESS_TOPIC, data),
+            PubsubMessage::Attestation(data) => (BEACON_ATTESTATION_TOPIC, data),
+            PubsubMessage::AggregateAndProof(data) => (BEACON_AGGREGATE_AND_PROOF_TOPIC, data),
+            PubsubMessage::VoluntaryExit(data) => (VOLUNTARY_EXIT_TOPIC, data),
+            PubsubMessage::ProposerSlashing(data) => (PROPOSER_SLASHING_TOPIC, data),
+            PubsubMessage::AttesterSlashing(data) => (ATTESTER_SLASHING_TOPIC, data),
+            PubsubMessage::Unknown(_) => return None,
+        };
+
+        Some(Self {
+            timestamp,
+            topic: topic.to_string(),
+            data: data.clone(),
+        })
+    }
+
+    /// Returns the message which was recorded, or `None` if the topic is unknown.
+    pub fn to_message(&self) -> Option<PubsubMessage> {
+        let data = self.data.clone();
+        let message = match self.topic.as_str() {
+            BEACON_BLOCK_TOPIC => PubsubMessage::Block(data),
+            BEACON_BLOCK_AND_WITNESS_TOPIC => PubsubMessage::BlockAndWitness(data),
+            BEACON_ATTESTATION_TOPIC => PubsubMessage::Attestation(data),
+            BEACON_AGGREGATE_AND_PROOF_TOPIC => PubsubMessage::AggregateAndProof(data),
+            VOLUNTARY_EXIT_TOPIC => PubsubMessage::VoluntaryExit(data),
+            PROPOSER_SLASHING_TOPIC => PubsubMessage::ProposerSlashing(data),
+            ATTESTER_SLASHING_TOPIC => PubsubMessage::AttesterSlashing(data),
+            _ => return None,
+        };
+
+        Some(message)
+    }
+
+    /// Writes the record to `writer` in the log format.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.topic.len() > u16::max_value() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Topic too long",
+            ));
+        }
+        if self.data.len() > u32::max_value() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Message too long",
+            ));
+        }
+
+        writer.write_all(&self.timestamp.to_le_bytes())?;
+        writer.write_all(&(self.topic.len() as u16).to_le_bytes())?;
+        writer.write_all(self.topic.as_bytes())?;
+        writer.write_all(&(self.data.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.data)
+    }
+
+    /// Reads the next record from `reader`, returning `None` if the end of the log has been
+    /// reached.
+    ///
+    /// A log which ends part-way through a record (e.g., because the node was killed whilst
+    /// writing it) returns an error.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut timestamp = [0; 8];
+        match reader.read_exact(&mut timestamp) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let mut topic_len = [0; 2];
+        reader.read_exact(&mut topic_len)?;
+        let mut topic = vec![0; u16::from_le_bytes(topic_len) as usize];
+        reader.read_exact(&mut topic)?;
+        let topic =
+            String::from_utf8(topic).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut data_len = [0; 4];
+        reader.read_exact(&mut data_len)?;
+        let mut data = vec![0; u32::from_le_bytes(data_len) as usize];
+        reader.read_exact(&mut data)?;
+
+        Ok(Some(Self {
+            timestamp: u64::from_le_bytes(timestamp),
+            topic,
+            data,
+        }))
+    }
+}
+
+/// Reads all the records of the log at `path`, in the order in which they were received.
+pub fn read_gossip_log(path: &Path) -> Result<Vec<GossipRecord>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Unable to open gossip log {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    let mut records = vec![];
+    while let Some(record) = GossipRecord::read_from(&mut reader)
+        .map_err(|e| format!("Unable to read gossip log {}: {}", path.display(), e))?
+    {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Appends each gossip message received by the node to a log.
+pub struct GossipRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl GossipRecorder {
+    /// Creates a new log in `dir`, named after the present time, creating `dir` if it does not
+    /// exist.
+    pub fn create(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Unable to create {}: {}", dir.display(), e))?;
+
+        let path = dir.join(format!("gossip_{}.log", timestamp_now()));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Returns the path of the log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `message` to the log, timestamped with the present time.
+    ///
+    /// Messages from unknown topics are not recorded, since they cannot be replayed. The log is
+    /// flushed after each message, so that it is complete if the node crashes.
+    pub fn record(&mut self, message: &PubsubMessage) -> io::Result<()> {
+        if let Some(record) = GossipRecord::from_message(timestamp_now(), message) {
+            record.write_to(&mut self.writer)?;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the present time, in milliseconds since the UNIX epoch.
+fn timestamp_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let records = vec![
+            GossipRecord::from_message(1, &PubsubMessage::Block(vec![1, 2, 3])).unwrap(),
+            GossipRecord::from_message(2, &PubsubMessage::Attestation(vec![])).unwrap(),
+            GossipRecord::from_message(3, &PubsubMessage::AttesterSlashing(vec![42; 1024]))
+                .unwrap(),
+        ];
+
+        let mut log = vec![];
+        for record in &records {
+            record.write_to(&mut log).unwrap();
+        }
+
+        let mut reader = &log[..];
+        let mut decoded = vec![];
+        while let Some(record) = GossipRecord::read_from(&mut reader).unwrap() {
+            decoded.push(record);
+        }
+
+        assert_eq!(decoded, records);
+        assert_eq!(
+            decoded[0].to_message(),
+            Some(PubsubMessage::Block(vec![1, 2, 3]))
+        );
+    }
+
+    #[test]
+    fn unknown_topic_is_not_recorded() {
+        assert_eq!(
+            GossipRecord::from_message(1, &PubsubMessage::Unknown(vec![1])),
+            None
+        );
+    }
+
+    #[test]
+    fn truncated_record_is_an_error() {
+        let mut log = vec![];
+        GossipRecord::from_message(1, &PubsubMessage::Block(vec![1, 2, 3]))
+            .unwrap()
+            .write_to(&mut log)
+            .unwrap();
+        log.pop();
+
+        assert!(GossipRecord::read_from(&mut &log[..]).is_err());
+    }
+}
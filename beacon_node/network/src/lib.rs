@@ -1,9 +1,19 @@
 /// This crate provides the network server for Lighthouse.
+#[macro_use]
+extern crate lazy_static;
+
+mod beacon_processor;
 pub mod error;
+pub mod fault_injection;
+pub mod gossip_record;
 pub mod message_handler;
+mod metrics;
 pub mod service;
 pub mod sync;
 
 pub use eth2_libp2p::NetworkConfig;
+pub use fault_injection::FaultInjector;
+pub use gossip_record::{read_gossip_log, GossipRecord, GossipRecorder};
+pub use message_handler::replay_gossip;
 pub use service::NetworkMessage;
 pub use service::Service;
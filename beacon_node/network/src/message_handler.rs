@@ -1,12 +1,21 @@
 #![allow(clippy::unit_arg)]
+use crate::beacon_processor::{BeaconProcessor, WorkEvent, Worker};
 use crate::error;
+use crate::gossip_record::{GossipRecord, GossipRecorder};
+use crate::metrics;
 use crate::service::NetworkMessage;
 use crate::sync::MessageProcessor;
+use beacon_chain::slot_clock::SlotClock;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
 use eth2_libp2p::{
     behaviour::PubsubMessage,
-    rpc::{RPCError, RPCErrorResponse, RPCRequest, RPCResponse, RequestId, ResponseTermination},
-    PeerId, RPCEvent,
+    rpc::{
+        BlockResponse, ErrorMessage, RPCError, RPCErrorResponse, RPCRequest, RPCResponse,
+        RPCResponseErrorCode, RequestId, ResponseTermination,
+    },
+    PeerId, RPCEvent, BEACON_AGGREGATE_AND_PROOF_TOPIC, BEACON_ATTESTATION_TOPIC,
+    BEACON_BLOCK_AND_WITNESS_TOPIC, BEACON_BLOCK_TOPIC,
 };
 use futures::future::Future;
 use futures::stream::Stream;
@@ -14,7 +23,10 @@ use slog::{debug, o, trace, warn};
 use ssz::{Decode, DecodeError};
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use types::{Attestation, AttesterSlashing, BeaconBlock, ProposerSlashing, VoluntaryExit};
+use types::{
+    Attestation, AttesterSlashing, BeaconBlock, ProposerSlashing, SignedAggregateAndProof, Slot,
+    VoluntaryExit,
+};
 
 /// Handles messages received from the network and client and organises syncing. This
 /// functionality of this struct is to validate an decode messages from the network before
@@ -26,6 +38,13 @@ pub struct MessageHandler<T: BeaconChainTypes> {
     /// Processes validated and decoded messages from the network. Has direct access to the
     /// sync manager.
     message_processor: MessageProcessor<T>,
+    /// A channel to the beacon processor, which processes gossip blocks, gossip attestations and
+    /// RPC block responses on a pool of workers.
+    beacon_processor_send: mpsc::UnboundedSender<WorkEvent<T::EthSpec>>,
+    /// Used to determine the forks known to this node when decoding blocks.
+    beacon_chain: Arc<BeaconChain<T>>,
+    /// Appends each gossip message to a log, if gossip is being recorded.
+    gossip_recorder: Option<GossipRecorder>,
     /// The `MessageHandler` logger.
     log: slog::Logger,
 }
@@ -49,7 +68,8 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
     pub fn spawn(
         beacon_chain: Arc<BeaconChain<T>>,
         network_send: mpsc::UnboundedSender<NetworkMessage>,
-        executor: &tokio::runtime::TaskExecutor,
+        gossip_recorder: Option<GossipRecorder>,
+        executor: &TaskExecutor,
         log: slog::Logger,
     ) -> error::Result<mpsc::UnboundedSender<HandlerMessage>> {
         let message_handler_log = log.new(o!("service"=> "msg_handler"));
@@ -59,12 +79,23 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
 
         // Initialise a message instance, which itself spawns the syncing thread.
         let message_processor =
-            MessageProcessor::new(executor, beacon_chain, network_send.clone(), &log);
+            MessageProcessor::new(executor, beacon_chain.clone(), network_send.clone(), &log);
+
+        let beacon_processor_send = BeaconProcessor::spawn(
+            executor,
+            beacon_chain.clone(),
+            network_send.clone(),
+            message_processor.sync_send(),
+            &log,
+        );
 
         // generate the Message handler
         let mut handler = MessageHandler {
             network_send,
             message_processor,
+            beacon_processor_send,
+            beacon_chain,
+            gossip_recorder,
             log: message_handler_log,
         };
 
@@ -75,6 +106,7 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
                 .map_err(move |_| {
                     debug!(log, "Network message handler terminated.");
                 }),
+            "message_handler",
         );
 
         Ok(handler_send)
@@ -147,18 +179,36 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
     ) {
         // an error could have occurred.
         match error_response {
-            RPCErrorResponse::InvalidRequest(error) => {
-                warn!(self.log, "Peer indicated invalid request";"peer_id" => format!("{:?}", peer_id), "error" => error.as_string());
-                self.handle_rpc_error(peer_id, request_id, RPCError::RPCErrorResponse);
-            }
-            RPCErrorResponse::ServerError(error) => {
-                warn!(self.log, "Peer internal server error";"peer_id" => format!("{:?}", peer_id), "error" => error.as_string());
-                self.handle_rpc_error(peer_id, request_id, RPCError::RPCErrorResponse);
-            }
-            RPCErrorResponse::Unknown(error) => {
-                warn!(self.log, "Unknown peer error";"peer" => format!("{:?}", peer_id), "error" => error.as_string());
-                self.handle_rpc_error(peer_id, request_id, RPCError::RPCErrorResponse);
-            }
+            RPCErrorResponse::InvalidRequest(error) => self.handle_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCResponseErrorCode::InvalidRequest,
+                error,
+            ),
+            RPCErrorResponse::ServerError(error) => self.handle_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCResponseErrorCode::ServerError,
+                error,
+            ),
+            RPCErrorResponse::ResourceUnavailable(error) => self.handle_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCResponseErrorCode::ResourceUnavailable,
+                error,
+            ),
+            RPCErrorResponse::RateLimited(error) => self.handle_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCResponseErrorCode::RateLimited,
+                error,
+            ),
+            RPCErrorResponse::Unknown(error) => self.handle_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCResponseErrorCode::Unknown,
+                error,
+            ),
             RPCErrorResponse::Success(response) => {
                 match response {
                     RPCResponse::Status(status_message) => {
@@ -168,11 +218,11 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
                     RPCResponse::BlocksByRange(response) => {
                         match self.decode_beacon_block(response) {
                             Ok(beacon_block) => {
-                                self.message_processor.on_blocks_by_range_response(
+                                self.send_to_beacon_processor(WorkEvent::BlocksByRangeResponse {
                                     peer_id,
                                     request_id,
-                                    Some(beacon_block),
-                                );
+                                    beacon_block: Some(Box::new(beacon_block)),
+                                });
                             }
                             Err(e) => {
                                 // TODO: Down-vote Peer
@@ -183,11 +233,11 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
                     RPCResponse::BlocksByRoot(response) => {
                         match self.decode_beacon_block(response) {
                             Ok(beacon_block) => {
-                                self.message_processor.on_blocks_by_root_response(
+                                self.send_to_beacon_processor(WorkEvent::BlocksByRootResponse {
                                     peer_id,
                                     request_id,
-                                    Some(beacon_block),
-                                );
+                                    beacon_block: Some(Box::new(beacon_block)),
+                                });
                             }
                             Err(e) => {
                                 // TODO: Down-vote Peer
@@ -201,12 +251,18 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
                 // have received a stream termination, notify the processing functions
                 match response_type {
                     ResponseTermination::BlocksByRange => {
-                        self.message_processor
-                            .on_blocks_by_range_response(peer_id, request_id, None);
+                        self.send_to_beacon_processor(WorkEvent::BlocksByRangeResponse {
+                            peer_id,
+                            request_id,
+                            beacon_block: None,
+                        });
                     }
                     ResponseTermination::BlocksByRoot => {
-                        self.message_processor
-                            .on_blocks_by_root_response(peer_id, request_id, None);
+                        self.send_to_beacon_processor(WorkEvent::BlocksByRootResponse {
+                            peer_id,
+                            request_id,
+                            beacon_block: None,
+                        });
                     }
                 }
             }
@@ -214,83 +270,222 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
     }
 
     /// Handle various RPC errors
+    /// A peer responded to one of our requests with an error.
+    ///
+    /// The request is retried with another peer either way. If the error indicates that the
+    /// request may not succeed if retried, the peer is also disconnected.
+    fn handle_rpc_error_response(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        code: RPCResponseErrorCode,
+        error: ErrorMessage,
+    ) {
+        warn!(self.log, "Peer responded with an error"; "peer_id" => format!("{:?}", peer_id), "code" => code.to_string(), "error" => error.as_string());
+        metrics::inc_counter_vec(&metrics::RPC_ERROR_RESPONSES_RECEIVED, &[&code.to_string()]);
+
+        if !code.is_retryable() {
+            self.message_processor.on_faulty_peer(peer_id.clone());
+        }
+
+        self.handle_rpc_error(peer_id, request_id, RPCError::RPCErrorResponse);
+    }
+
     fn handle_rpc_error(&mut self, peer_id: PeerId, request_id: RequestId, error: RPCError) {
         warn!(self.log, "RPC Error"; "Peer" => format!("{:?}", peer_id), "request_id" => format!("{}", request_id), "Error" => format!("{:?}", error));
         self.message_processor.on_rpc_error(peer_id, request_id);
     }
 
-    /// Handle RPC messages
+    /// Records and handles a gossip message.
     fn handle_gossip(&mut self, id: String, peer_id: PeerId, gossip_message: PubsubMessage) {
+        if let Some(recorder) = &mut self.gossip_recorder {
+            if let Err(e) = recorder.record(&gossip_message) {
+                warn!(self.log, "Unable to record gossip message"; "error" => format!("{}", e));
+            }
+        }
+
+        if let Some(work) = self.gossip_work(id, peer_id, gossip_message) {
+            self.send_to_beacon_processor(work);
+        }
+    }
+
+    /// Decodes `gossip_message`, returning the work to be done by the beacon processor, or `None`
+    /// if the message is invalid.
+    fn gossip_work(
+        &mut self,
+        id: String,
+        peer_id: PeerId,
+        gossip_message: PubsubMessage,
+    ) -> Option<WorkEvent<T::EthSpec>> {
+        let kind = gossip_kind(&gossip_message);
+        metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_RECEIVED, &[kind]);
+
         match gossip_message {
             PubsubMessage::Block(message) => match self.decode_gossip_block(message) {
                 Ok(block) => {
-                    let should_forward_on = self
-                        .message_processor
-                        .on_block_gossip(peer_id.clone(), block);
+                    self.observe_block_arrival(&block);
+                    self.observe_arrival(BEACON_BLOCK_TOPIC, block.slot);
+                    // The block is propagated by the beacon processor once it has been verified.
                     // TODO: Apply more sophisticated validation and decoding logic
-                    if should_forward_on {
-                        self.propagate_message(id, peer_id.clone());
-                    }
+                    Some(WorkEvent::GossipBlock {
+                        message_id: id,
+                        peer_id,
+                        block: Box::new(block),
+                    })
                 }
                 Err(e) => {
+                    metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
                     debug!(self.log, "Invalid gossiped beacon block"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                    None
+                }
+            },
+            PubsubMessage::BlockAndWitness(message) => match self.decode_gossip_block(message) {
+                Ok(block) => {
+                    self.observe_arrival(BEACON_BLOCK_AND_WITNESS_TOPIC, block.slot);
+                    // The block is propagated by the beacon processor once it and its witness
+                    // have been verified.
+                    Some(WorkEvent::GossipBlockAndWitness {
+                        message_id: id,
+                        peer_id,
+                        block: Box::new(block),
+                    })
+                }
+                Err(e) => {
+                    metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
+                    debug!(self.log, "Invalid gossiped beacon block and witness"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                    None
                 }
             },
             PubsubMessage::Attestation(message) => match self.decode_gossip_attestation(message) {
                 Ok(attestation) => {
+                    self.observe_arrival(BEACON_ATTESTATION_TOPIC, attestation.data.slot);
                     // TODO: Apply more sophisticated validation and decoding logic
                     self.propagate_message(id, peer_id.clone());
-                    self.message_processor
-                        .on_attestation_gossip(peer_id, attestation);
+                    Some(WorkEvent::GossipAttestation {
+                        peer_id,
+                        attestation: Box::new(attestation),
+                    })
                 }
                 Err(e) => {
+                    metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
                     debug!(self.log, "Invalid gossiped attestation"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                    None
                 }
             },
+            PubsubMessage::AggregateAndProof(message) => {
+                match self.decode_gossip_aggregate_and_proof(message) {
+                    Ok(aggregate_and_proof) => {
+                        self.observe_arrival(
+                            BEACON_AGGREGATE_AND_PROOF_TOPIC,
+                            aggregate_and_proof.message.aggregate.data.slot,
+                        );
+                        // TODO: Verify the selection proof and aggregator signature
+                        self.propagate_message(id, peer_id.clone());
+                        Some(WorkEvent::GossipAggregate {
+                            peer_id,
+                            aggregate: Box::new(aggregate_and_proof),
+                        })
+                    }
+                    Err(e) => {
+                        metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
+                        debug!(self.log, "Invalid gossiped aggregate and proof"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                        None
+                    }
+                }
+            }
             PubsubMessage::VoluntaryExit(message) => match self.decode_gossip_exit(message) {
-                Ok(_exit) => {
-                    // TODO: Apply more sophisticated validation and decoding logic
-                    self.propagate_message(id, peer_id.clone());
-                    // TODO: Handle exits
-                    debug!(self.log, "Received a voluntary exit"; "peer_id" => format!("{}", peer_id) );
+                Ok(exit) => {
+                    // The exit is propagated by the beacon processor once it has been verified.
+                    Some(WorkEvent::GossipVoluntaryExit {
+                        message_id: id,
+                        peer_id,
+                        exit: Box::new(exit),
+                    })
                 }
                 Err(e) => {
+                    metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
                     debug!(self.log, "Invalid gossiped exit"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                    None
                 }
             },
             PubsubMessage::ProposerSlashing(message) => {
                 match self.decode_gossip_proposer_slashing(message) {
-                    Ok(_slashing) => {
-                        // TODO: Apply more sophisticated validation and decoding logic
-                        self.propagate_message(id, peer_id.clone());
-                        // TODO: Handle proposer slashings
-                        debug!(self.log, "Received a proposer slashing"; "peer_id" => format!("{}", peer_id) );
+                    Ok(slashing) => {
+                        // The slashing is propagated by the beacon processor once it has been
+                        // verified.
+                        Some(WorkEvent::GossipProposerSlashing {
+                            message_id: id,
+                            peer_id,
+                            slashing: Box::new(slashing),
+                        })
                     }
                     Err(e) => {
+                        metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
                         debug!(self.log, "Invalid gossiped proposer slashing"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                        None
                     }
                 }
             }
             PubsubMessage::AttesterSlashing(message) => {
                 match self.decode_gossip_attestation_slashing(message) {
-                    Ok(_slashing) => {
-                        // TODO: Apply more sophisticated validation and decoding logic
-                        self.propagate_message(id, peer_id.clone());
-                        // TODO: Handle attester slashings
-                        debug!(self.log, "Received an attester slashing"; "peer_id" => format!("{}", peer_id) );
+                    Ok(slashing) => {
+                        // The slashing is propagated by the beacon processor once it has been
+                        // verified.
+                        Some(WorkEvent::GossipAttesterSlashing {
+                            message_id: id,
+                            peer_id,
+                            slashing: Box::new(slashing),
+                        })
                     }
                     Err(e) => {
+                        metrics::inc_counter_vec(&metrics::GOSSIP_MESSAGES_INVALID, &[kind]);
                         debug!(self.log, "Invalid gossiped attester slashing"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
+                        None
                     }
                 }
             }
             PubsubMessage::Unknown(message) => {
                 // Received a message from an unknown topic. Ignore for now
                 debug!(self.log, "Unknown Gossip Message"; "peer_id" => format!("{}", peer_id), "Message" => format!("{:?}", message));
+                None
+            }
+        }
+    }
+
+    /// Records the delay between the start of the slot of `block` and its arrival, if it arrived
+    /// during its own slot.
+    fn observe_block_arrival(&self, block: &BeaconBlock<T::EthSpec>) {
+        let slot_clock = &self.beacon_chain.slot_clock;
+
+        if slot_clock.now() == Some(block.slot) {
+            if let Some(delay) = slot_clock.duration_since_start_of(block.slot) {
+                metrics::observe(
+                    &metrics::GOSSIP_BLOCK_ARRIVAL_DELAY_TIME,
+                    delay.as_secs_f64(),
+                );
             }
         }
     }
 
+    /// Records the delay between the start of `slot` and the arrival of a message for `slot` on
+    /// the gossip `topic`, if `slot` has started.
+    fn observe_arrival(&self, topic: &str, slot: Slot) {
+        if let Some(delay) = self.beacon_chain.slot_clock.duration_since_start_of(slot) {
+            metrics::observe_vec(
+                &metrics::GOSSIP_ARRIVAL_DELAY_TIME,
+                &[topic],
+                delay.as_secs_f64(),
+            );
+        }
+    }
+
+    /// Queues `work` to be processed by the beacon processor.
+    fn send_to_beacon_processor(&mut self, work: WorkEvent<T::EthSpec>) {
+        self.beacon_processor_send
+            .try_send(work)
+            .unwrap_or_else(|_| warn!(self.log, "Could not send work to the beacon processor"));
+    }
+
     /// Informs the network service that the message should be forwarded to other peers.
     fn propagate_message(&mut self, message_id: String, propagation_source: PeerId) {
         self.network_send
@@ -320,6 +515,7 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
         &self,
         beacon_block: Vec<u8>,
     ) -> Result<BeaconBlock<T::EthSpec>, DecodeError> {
+        let _timer = metrics::start_timer(&metrics::GOSSIP_BLOCK_DECODE_TIME);
         //TODO: Apply verification before decoding.
         BeaconBlock::from_ssz_bytes(&beacon_block)
     }
@@ -332,6 +528,14 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
         Attestation::from_ssz_bytes(&beacon_block)
     }
 
+    fn decode_gossip_aggregate_and_proof(
+        &self,
+        aggregate_and_proof: Vec<u8>,
+    ) -> Result<SignedAggregateAndProof<T::EthSpec>, DecodeError> {
+        //TODO: Apply verification before decoding.
+        SignedAggregateAndProof::from_ssz_bytes(&aggregate_and_proof)
+    }
+
     fn decode_gossip_exit(&self, voluntary_exit: Vec<u8>) -> Result<VoluntaryExit, DecodeError> {
         //TODO: Apply verification before decoding.
         VoluntaryExit::from_ssz_bytes(&voluntary_exit)
@@ -355,13 +559,103 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
 
     /* Req/Resp Domain Decoding  */
 
-    /// Verifies and decodes an ssz-encoded `BeaconBlock`. If `None` is passed, this represents a
-    /// stream termination.
+    /// Verifies and decodes an ssz-encoded `BeaconBlock`, selecting the container by the fork
+    /// version of the response (if the peer sent one).
     fn decode_beacon_block(
         &self,
-        beacon_block: Vec<u8>,
+        response: BlockResponse,
     ) -> Result<BeaconBlock<T::EthSpec>, DecodeError> {
+        // All known forks share the same `BeaconBlock` container, so a block from any of them is
+        // decoded in the same way. A block from an unknown fork cannot be decoded.
+        if let Some(fork_version) = response.fork_version {
+            let fork = self.beacon_chain.head_info().fork;
+
+            if fork_version != fork.current_version && fork_version != fork.previous_version {
+                return Err(DecodeError::BytesInvalid(format!(
+                    "Block from unknown fork version: {:?}",
+                    fork_version
+                )));
+            }
+        }
+
         //TODO: Implement faster block verification before decoding entirely
-        BeaconBlock::from_ssz_bytes(&beacon_block)
+        BeaconBlock::from_ssz_bytes(&response.block_bytes)
+    }
+}
+
+/// Processes the gossip messages in `records` against `beacon_chain` in order, as if they had been
+/// received from a single peer, returning the number of messages which were decoded and processed.
+///
+/// Unlike a spawned `MessageHandler`, each message is processed by a beacon processor worker on
+/// the calling thread before the next message is decoded, so that a replay is deterministic.
+/// Messages to the network service (e.g., requests to propagate a message) are discarded.
+pub fn replay_gossip<T, I>(
+    beacon_chain: Arc<BeaconChain<T>>,
+    records: I,
+    executor: &TaskExecutor,
+    log: slog::Logger,
+) -> usize
+where
+    T: BeaconChainTypes,
+    I: IntoIterator<Item = GossipRecord>,
+{
+    let (network_send, network_recv) = mpsc::unbounded_channel();
+    executor.spawn(
+        network_recv.for_each(|_| Ok(())).map_err(|_| ()),
+        "gossip_replay_network",
+    );
+
+    let message_processor =
+        MessageProcessor::new(executor, beacon_chain.clone(), network_send.clone(), &log);
+    let sync_send = message_processor.sync_send();
+    // The work is processed on this thread, so nothing is sent to a beacon processor.
+    let (beacon_processor_send, _) = mpsc::unbounded_channel();
+
+    let mut handler = MessageHandler {
+        network_send: network_send.clone(),
+        message_processor,
+        beacon_processor_send,
+        beacon_chain: beacon_chain.clone(),
+        gossip_recorder: None,
+        log: log.new(o!("service" => "gossip_replay")),
+    };
+
+    let peer_id = PeerId::random();
+    let mut processed = 0;
+    for (index, record) in records.into_iter().enumerate() {
+        let message = match record.to_message() {
+            Some(message) => message,
+            None => {
+                warn!(log, "Skipping gossip message from unknown topic"; "topic" => &record.topic);
+                continue;
+            }
+        };
+
+        if let Some(work) = handler.gossip_work(index.to_string(), peer_id.clone(), message) {
+            Worker::new(
+                beacon_chain.clone(),
+                network_send.clone(),
+                sync_send.clone(),
+                log.clone(),
+            )
+            .process(work);
+            processed += 1;
+        }
+    }
+
+    processed
+}
+
+/// Returns the label used for `message` in the gossip metrics.
+fn gossip_kind(message: &PubsubMessage) -> &'static str {
+    match message {
+        PubsubMessage::Block(_) => "beacon_block",
+        PubsubMessage::BlockAndWitness(_) => "beacon_block_and_witness",
+        PubsubMessage::Attestation(_) => "attestation",
+        PubsubMessage::AggregateAndProof(_) => "aggregate_and_proof",
+        PubsubMessage::VoluntaryExit(_) => "voluntary_exit",
+        PubsubMessage::ProposerSlashing(_) => "proposer_slashing",
+        PubsubMessage::AttesterSlashing(_) => "attester_slashing",
+        PubsubMessage::Unknown(_) => "unknown",
     }
 }
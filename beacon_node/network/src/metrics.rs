@@ -0,0 +1,72 @@
+pub use lighthouse_metrics::*;
+
+lazy_static! {
+    /*
+     * Gossip
+     */
+    pub static ref GOSSIP_MESSAGES_RECEIVED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "network_gossip_messages_received_total",
+        "Count of gossip messages received from peers, by kind",
+        &["kind"]
+    );
+    pub static ref GOSSIP_BLOCK_DECODE_TIME: Result<Histogram> = try_create_histogram(
+        "network_gossip_block_decode_seconds",
+        "Time spent decoding a block received via gossip"
+    );
+    pub static ref GOSSIP_BLOCK_ARRIVAL_DELAY_TIME: Result<Histogram> = try_create_histogram(
+        "network_gossip_block_arrival_delay_seconds",
+        "Duration between the start of the block's slot and the block arriving via gossip. Only \
+        observed for blocks which arrive during their own slot."
+    );
+    pub static ref GOSSIP_ARRIVAL_DELAY_TIME: Result<HistogramVec> = try_create_histogram_vec(
+        "network_gossip_arrival_delay_seconds",
+        "Duration between the start of a message's slot and the message arriving via gossip, by \
+        topic",
+        &["topic"]
+    );
+    pub static ref GOSSIP_MESSAGES_INVALID: Result<IntCounterVec> = try_create_int_counter_vec(
+        "network_gossip_messages_invalid_total",
+        "Count of gossip messages received from peers which could not be decoded, by kind",
+        &["kind"]
+    );
+
+    /*
+     * RPC
+     */
+    pub static ref RPC_ERROR_RESPONSES_RECEIVED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "network_rpc_error_responses_received_total",
+        "Count of error responses received from peers, by error code",
+        &["code"]
+    );
+
+    /*
+     * Beacon processor
+     */
+    pub static ref BEACON_PROCESSOR_QUEUE_LENGTH: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_processor_queue_length",
+        "Count of work events waiting for a worker, by queue",
+        &["queue"]
+    );
+    pub static ref BEACON_PROCESSOR_WORK_DROPPED: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_processor_work_dropped_total",
+        "Count of work events dropped because their queue was full or they became stale, by queue",
+        &["queue"]
+    );
+    pub static ref BEACON_PROCESSOR_WORKERS_ACTIVE: Result<IntGauge> = try_create_int_gauge(
+        "beacon_processor_workers_active",
+        "Number of workers presently processing work"
+    );
+
+    /*
+     * Sync
+     */
+    pub static ref SYNC_STATE: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "sync_state",
+        "Set to 1 for the present state of the sync manager, 0 for the others",
+        &["state"]
+    );
+    pub static ref SYNC_FULL_PEERS: Result<IntGauge> = try_create_int_gauge(
+        "sync_full_peers_count",
+        "Number of connected peers which are synced with this node"
+    );
+}
@@ -1,18 +1,24 @@
 use crate::error;
+use crate::fault_injection::FaultInjector;
+use crate::gossip_record::GossipRecorder;
 use crate::message_handler::{HandlerMessage, MessageHandler};
 use crate::NetworkConfig;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use core::marker::PhantomData;
+use environment::{ShutdownReason, TaskExecutor};
 use eth2_libp2p::Service as LibP2PService;
-use eth2_libp2p::{rpc::RPCRequest, Enr, Libp2pEvent, Multiaddr, PeerId, Swarm, Topic};
+use eth2_libp2p::{
+    rpc::RPCRequest, Ban, BanTarget, Enr, Libp2pEvent, Multiaddr, PeerCounts, PeerId, Swarm, Topic,
+};
 use eth2_libp2p::{PubsubMessage, RPCEvent};
 use futures::prelude::*;
 use futures::Stream;
 use parking_lot::Mutex;
-use slog::{debug, info, trace};
+use slog::{crit, debug, info, trace};
 use std::sync::Arc;
-use tokio::runtime::TaskExecutor;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
+use tokio::timer::Delay;
 
 /// The time in seconds that a peer will be banned and prevented from reconnecting.
 const BAN_PEER_TIMEOUT: u64 = 30;
@@ -21,7 +27,9 @@ const BAN_PEER_TIMEOUT: u64 = 30;
 pub struct Service<T: BeaconChainTypes> {
     libp2p_service: Arc<Mutex<LibP2PService>>,
     libp2p_port: u16,
-    _libp2p_exit: oneshot::Sender<()>,
+    fault_injector: Arc<FaultInjector>,
+    /// Stops the libp2p service when sent or dropped. `None` once `shutdown` has been called.
+    libp2p_exit: Mutex<Option<oneshot::Sender<()>>>,
     _network_send: mpsc::UnboundedSender<NetworkMessage>,
     _phantom: PhantomData<T>,
 }
@@ -31,14 +39,33 @@ impl<T: BeaconChainTypes> Service<T> {
         beacon_chain: Arc<BeaconChain<T>>,
         config: &NetworkConfig,
         executor: &TaskExecutor,
+        shutdown_sender: futures::sync::mpsc::Sender<ShutdownReason>,
         network_log: slog::Logger,
     ) -> error::Result<(Arc<Self>, mpsc::UnboundedSender<NetworkMessage>)> {
+        // advertise the chain and fork of this node, so that peers on other chains are not dialed
+        let mut config = config.clone();
+        config.enr_fork_id = Some(beacon_chain.enr_fork_id());
+
+        let gossip_recorder = match &config.record_gossip_dir {
+            Some(dir) => {
+                let recorder = GossipRecorder::create(dir)?;
+                info!(
+                    network_log,
+                    "Recording gossip";
+                    "path" => format!("{}", recorder.path().display())
+                );
+                Some(recorder)
+            }
+            None => None,
+        };
+
         // build the network channel
         let (network_send, network_recv) = mpsc::unbounded_channel::<NetworkMessage>();
         // launch message handler thread
         let message_handler_send = MessageHandler::spawn(
             beacon_chain,
             network_send.clone(),
+            gossip_recorder,
             executor,
             network_log.clone(),
         )?;
@@ -49,18 +76,23 @@ impl<T: BeaconChainTypes> Service<T> {
             network_log.clone(),
         )?));
 
+        let fault_injector = Arc::new(FaultInjector::default());
+
         let libp2p_exit = spawn_service(
             libp2p_service.clone(),
             network_recv,
             message_handler_send,
             executor,
+            shutdown_sender,
             network_log,
             config.propagation_percentage,
+            fault_injector.clone(),
         )?;
         let network_service = Service {
             libp2p_service,
             libp2p_port: config.libp2p_port,
-            _libp2p_exit: libp2p_exit,
+            fault_injector,
+            libp2p_exit: Mutex::new(Some(libp2p_exit)),
             _network_send: network_send.clone(),
             _phantom: PhantomData,
         };
@@ -68,6 +100,16 @@ impl<T: BeaconChainTypes> Service<T> {
         Ok((Arc::new(network_service), network_send))
     }
 
+    /// Stops the libp2p service, so that no further gossip or RPC messages are received from (or
+    /// sent to) peers.
+    ///
+    /// Has no effect if the service has already been shut down.
+    pub fn shutdown(&self) {
+        if let Some(libp2p_exit) = self.libp2p_exit.lock().take() {
+            let _ = libp2p_exit.send(());
+        }
+    }
+
     /// Returns the local ENR from the underlying Discv5 behaviour that external peers may connect
     /// to.
     pub fn local_enr(&self) -> Enr {
@@ -113,10 +155,36 @@ impl<T: BeaconChainTypes> Service<T> {
             .collect()
     }
 
+    /// Returns the number of connected peers in each direction, and the limits on them.
+    pub fn peer_counts(&self) -> PeerCounts {
+        self.libp2p_service.lock().swarm.peer_counts()
+    }
+
+    /// Returns the bans which have not expired, including those of misbehaving peers.
+    pub fn bans(&self) -> Vec<Ban> {
+        self.libp2p_service.lock().bans()
+    }
+
+    /// Bans `target` for `duration`, or until the ban is removed if it is `None`, disconnecting
+    /// the connected peers which it applies to.
+    pub fn ban(&self, target: BanTarget, duration: Option<Duration>) -> Ban {
+        self.libp2p_service.lock().ban(target, duration)
+    }
+
+    /// Lifts the ban of `target`, returning `false` if it was not banned.
+    pub fn unban(&self, target: &BanTarget) -> bool {
+        self.libp2p_service.lock().unban(target)
+    }
+
     /// Provides a reference to the underlying libp2p service.
     pub fn libp2p_service(&self) -> Arc<Mutex<LibP2PService>> {
         self.libp2p_service.clone()
     }
+
+    /// Provides a reference to the faults applied to this service, for testing purposes.
+    pub fn fault_injector(&self) -> Arc<FaultInjector> {
+        self.fault_injector.clone()
+    }
 }
 
 fn spawn_service(
@@ -124,8 +192,10 @@ fn spawn_service(
     network_recv: mpsc::UnboundedReceiver<NetworkMessage>,
     message_handler_send: mpsc::UnboundedSender<HandlerMessage>,
     executor: &TaskExecutor,
+    mut shutdown_sender: futures::sync::mpsc::Sender<ShutdownReason>,
     log: slog::Logger,
     propagation_percentage: Option<u8>,
+    fault_injector: Arc<FaultInjector>,
 ) -> error::Result<tokio::sync::oneshot::Sender<()>> {
     let (network_exit, exit_rx) = tokio::sync::oneshot::channel();
 
@@ -135,15 +205,26 @@ fn spawn_service(
             libp2p_service,
             network_recv,
             message_handler_send,
+            executor.clone(),
             log.clone(),
             propagation_percentage,
+            fault_injector,
         )
         // allow for manual termination
         .select(exit_rx.then(|_| Ok(())))
-        .then(move |_| {
-            info!(log.clone(), "Network service shutdown");
+        .then(move |result| {
+            match result {
+                Ok(_) => info!(log, "Network service shutdown"),
+                Err((e, _)) => {
+                    crit!(log, "Network service failed"; "error" => format!("{:?}", e));
+                    // The node is of no use without the network, request that it shuts down.
+                    let _ =
+                        shutdown_sender.try_send(ShutdownReason::Failure("Network service failed"));
+                }
+            }
             Ok(())
         }),
+        "network",
     );
 
     Ok(network_exit)
@@ -154,8 +235,10 @@ fn network_service(
     libp2p_service: Arc<Mutex<LibP2PService>>,
     mut network_recv: mpsc::UnboundedReceiver<NetworkMessage>,
     mut message_handler_send: mpsc::UnboundedSender<HandlerMessage>,
+    executor: TaskExecutor,
     log: slog::Logger,
     propagation_percentage: Option<u8>,
+    fault_injector: Arc<FaultInjector>,
 ) -> impl futures::Future<Item = (), Error = eth2_libp2p::error::Error> {
     futures::future::poll_fn(move || -> Result<_, eth2_libp2p::error::Error> {
         // processes the network channel before processing the libp2p swarm
@@ -163,6 +246,9 @@ fn network_service(
             // poll the network channel
             match network_recv.poll() {
                 Ok(Async::Ready(Some(message))) => match message {
+                    NetworkMessage::RPC(peer_id, _) if fault_injector.is_partitioned(&peer_id) => {
+                        trace!(log, "Fault injection dropped outbound RPC"; "peer_id" => format!("{:?}", peer_id));
+                    }
                     NetworkMessage::RPC(peer_id, rpc_event) => {
                         trace!(log, "Sending RPC"; "rpc" => format!("{}", rpc_event));
                         libp2p_service.lock().swarm.send_rpc(peer_id, rpc_event);
@@ -195,6 +281,21 @@ fn network_service(
                                 .propagate_message(&propagation_source, message_id);
                         }
                     }
+                    NetworkMessage::Publish {
+                        topics,
+                        message: message @ PubsubMessage::Attestation(_),
+                    } if fault_injector.attestation_publish_delay() > Duration::from_secs(0) => {
+                        let libp2p_service = libp2p_service.clone();
+                        let delay = fault_injector.attestation_publish_delay();
+                        trace!(log, "Fault injection delayed published attestation"; "delay_ms" => delay.as_millis() as u64);
+                        executor.spawn(
+                            Delay::new(Instant::now() + delay).then(move |_| {
+                                libp2p_service.lock().swarm.publish(&topics, message);
+                                Ok(())
+                            }),
+                            "network_delayed_attestation",
+                        );
+                    }
                     NetworkMessage::Publish { topics, message } => {
                         // TODO: Remove this for mainnet
                         // randomly prevents propagation
@@ -236,6 +337,11 @@ fn network_service(
         loop {
             match libp2p_service.lock().poll() {
                 Ok(Async::Ready(Some(event))) => match event {
+                    Libp2pEvent::RPC(peer_id, _) | Libp2pEvent::PeerDialed(peer_id)
+                        if fault_injector.is_partitioned(&peer_id) =>
+                    {
+                        trace!(log, "Fault injection dropped peer event"; "peer_id" => format!("{:?}", peer_id));
+                    }
                     Libp2pEvent::RPC(peer_id, rpc_event) => {
                         trace!(log, "Received RPC"; "rpc" => format!("{}", rpc_event));
 
@@ -259,6 +365,30 @@ fn network_service(
                             .try_send(HandlerMessage::PeerDisconnected(peer_id))
                             .map_err(|_| "Failed to send PeerDisconnected to handler")?;
                     }
+                    Libp2pEvent::PubsubMessage { source, .. }
+                        if fault_injector.is_partitioned(&source)
+                            || fault_injector.should_drop_gossip() =>
+                    {
+                        trace!(log, "Fault injection dropped gossip"; "peer_id" => format!("{:?}", source));
+                    }
+                    Libp2pEvent::PubsubMessage {
+                        id,
+                        source,
+                        message: message @ PubsubMessage::Block(_),
+                        ..
+                    } if fault_injector.block_import_delay() > Duration::from_secs(0) => {
+                        let mut message_handler_send = message_handler_send.clone();
+                        let delay = fault_injector.block_import_delay();
+                        trace!(log, "Fault injection delayed gossip block"; "delay_ms" => delay.as_millis() as u64);
+                        executor.spawn(
+                            Delay::new(Instant::now() + delay).then(move |_| {
+                                message_handler_send
+                                    .try_send(HandlerMessage::PubsubMessage(id, source, message))
+                                    .map_err(|_| ())
+                            }),
+                            "network_delayed_block",
+                        );
+                    }
                     Libp2pEvent::PubsubMessage {
                         id,
                         source,
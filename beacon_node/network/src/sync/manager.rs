@@ -61,8 +61,10 @@
 use super::message_processor::PeerSyncInfo;
 use super::network_context::SyncNetworkContext;
 use super::range_sync::RangeSync;
+use crate::metrics;
 use crate::service::NetworkMessage;
 use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
+use environment::TaskExecutor;
 use eth2_libp2p::rpc::methods::*;
 use eth2_libp2p::rpc::RequestId;
 use eth2_libp2p::PeerId;
@@ -154,6 +156,17 @@ enum ManagerState {
     Stalled,
 }
 
+impl ManagerState {
+    /// Returns the label used for this state in the sync metrics.
+    fn as_str(&self) -> &'static str {
+        match self {
+            ManagerState::Syncing => "syncing",
+            ManagerState::Regular => "regular",
+            ManagerState::Stalled => "stalled",
+        }
+    }
+}
+
 /// The primary object for handling and driving all the current syncing logic. It maintains the
 /// current state of the syncing process, the number of useful peers, downloaded blocks and
 /// controls the logic behind both the long-range (batch) sync and the on-going potential parent
@@ -191,7 +204,7 @@ pub struct SyncManager<T: BeaconChainTypes> {
 /// chain. This allows the chain to be
 /// dropped during the syncing process which will gracefully end the `SyncManager`.
 pub fn spawn<T: BeaconChainTypes>(
-    executor: &tokio::runtime::TaskExecutor,
+    executor: &TaskExecutor,
     beacon_chain: Weak<BeaconChain<T>>,
     network_send: mpsc::UnboundedSender<NetworkMessage>,
     log: slog::Logger,
@@ -226,6 +239,7 @@ pub fn spawn<T: BeaconChainTypes>(
                 info!(log.clone(), "Sync Manager shutdown");
                 Ok(())
             }),
+        "sync_manager",
     );
     (sync_send, sync_exit)
 }
@@ -504,7 +518,16 @@ impl<T: BeaconChainTypes> SyncManager<T> {
                 ManagerState::Stalled
             }
         };
+        metrics::set_gauge(&metrics::SYNC_FULL_PEERS, self.full_peers.len() as i64);
         if self.state != previous_state {
+            for state in &[
+                ManagerState::Syncing,
+                ManagerState::Regular,
+                ManagerState::Stalled,
+            ] {
+                let value = if *state == self.state { 1 } else { 0 };
+                metrics::set_gauge_vec(&metrics::SYNC_STATE, &[state.as_str()], value);
+            }
             info!(self.log, "Syncing state updated";
                 "old_state" => format!("{:?}", previous_state),
                 "new_state" => format!("{:?}", self.state),
@@ -1,18 +1,16 @@
 use super::manager::SyncMessage;
 use crate::service::NetworkMessage;
-use beacon_chain::{
-    AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BlockProcessingOutcome,
-};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use environment::TaskExecutor;
 use eth2_libp2p::rpc::methods::*;
-use eth2_libp2p::rpc::{RPCEvent, RPCRequest, RPCResponse, RequestId};
+use eth2_libp2p::rpc::{RPCEvent, RPCRequest, RPCResponse, RPCResponseErrorCode, RequestId};
 use eth2_libp2p::PeerId;
 use slog::{debug, error, o, trace, warn};
 use ssz::Encode;
 use std::sync::Arc;
-use store::Store;
+use store::{Error as StoreError, Store};
 use tokio::sync::{mpsc, oneshot};
-use tree_hash::SignedRoot;
-use types::{Attestation, BeaconBlock, Epoch, EthSpec, Hash256, Slot};
+use types::{BeaconBlock, Epoch, EthSpec, Fork, Hash256, Slot};
 
 //TODO: Rate limit requests
 
@@ -20,9 +18,6 @@ use types::{Attestation, BeaconBlock, Epoch, EthSpec, Hash256, Slot};
 /// Otherwise we queue it.
 pub(crate) const FUTURE_SLOT_TOLERANCE: u64 = 1;
 
-const SHOULD_FORWARD_GOSSIP_BLOCK: bool = true;
-const SHOULD_NOT_FORWARD_GOSSIP_BLOCK: bool = false;
-
 /// Keeps track of syncing information for known connected peers.
 #[derive(Clone, Copy, Debug)]
 pub struct PeerSyncInfo {
@@ -69,7 +64,7 @@ pub struct MessageProcessor<T: BeaconChainTypes> {
 impl<T: BeaconChainTypes> MessageProcessor<T> {
     /// Instantiate a `MessageProcessor` instance
     pub fn new(
-        executor: &tokio::runtime::TaskExecutor,
+        executor: &TaskExecutor,
         beacon_chain: Arc<BeaconChain<T>>,
         network_send: mpsc::UnboundedSender<NetworkMessage>,
         log: &slog::Logger,
@@ -93,6 +88,11 @@ impl<T: BeaconChainTypes> MessageProcessor<T> {
         }
     }
 
+    /// Returns a channel to the syncing thread.
+    pub fn sync_send(&self) -> mpsc::UnboundedSender<SyncMessage<T::EthSpec>> {
+        self.sync_send.clone()
+    }
+
     fn send_to_sync(&mut self, message: SyncMessage<T::EthSpec>) {
         self.sync_send.try_send(message).unwrap_or_else(|_| {
             warn!(
@@ -115,6 +115,11 @@ impl<T: BeaconChainTypes> MessageProcessor<T> {
         self.send_to_sync(SyncMessage::RPCError(peer_id, request_id));
     }
 
+    /// A peer responded to a request with an error which indicates that it is faulty.
+    pub fn on_faulty_peer(&mut self, peer_id: PeerId) {
+        self.network.disconnect(peer_id, GoodbyeReason::Fault);
+    }
+
     /// Handle the connection of a new peer.
     ///
     /// Sends a `Status` message to the peer.
@@ -263,22 +268,43 @@ impl<T: BeaconChainTypes> MessageProcessor<T> {
         request_id: RequestId,
         request: BlocksByRootRequest,
     ) {
+        let fork = self.chain.head_info().fork;
+
         let mut send_block_count = 0;
         for root in request.block_roots.iter() {
-            if let Ok(Some(block)) = self.chain.store.get::<BeaconBlock<T::EthSpec>>(root) {
-                self.network.send_rpc_response(
-                    peer_id.clone(),
-                    request_id,
-                    RPCResponse::BlocksByRoot(block.as_ssz_bytes()),
-                );
-                send_block_count += 1;
-            } else {
-                debug!(
-                    self.log,
-                    "Peer requested unknown block";
-                    "peer" => format!("{:?}", peer_id),
-                    "request_root" => format!("{:}", root),
-                );
+            match self.chain.store.get::<BeaconBlock<T::EthSpec>>(root) {
+                Ok(Some(block)) => {
+                    self.network.send_rpc_response(
+                        peer_id.clone(),
+                        request_id,
+                        RPCResponse::BlocksByRoot(block_response(&fork, &block)),
+                    );
+                    send_block_count += 1;
+                }
+                Ok(None) => {
+                    debug!(
+                        self.log,
+                        "Peer requested unknown block";
+                        "peer" => format!("{:?}", peer_id),
+                        "request_root" => format!("{:}", root),
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        self.log,
+                        "Unable to read block from the store";
+                        "request_root" => format!("{:}", root),
+                        "error" => format!("{:?}", e),
+                    );
+                    // Terminate the response with an error, so the peer knows the response is
+                    // incomplete.
+                    self.network.send_rpc_error_response(
+                        peer_id,
+                        request_id,
+                        RPCErrorResponse::error(store_error_code(&e), "Unable to read block"),
+                    );
+                    return;
+                }
             }
         }
         debug!(
@@ -312,35 +338,73 @@ impl<T: BeaconChainTypes> MessageProcessor<T> {
             "start_slot" => req.start_slot,
         );
 
+        if req.count > MAX_REQUEST_BLOCKS || req.step == 0 {
+            debug!(
+                self.log,
+                "Invalid BlocksByRange Request";
+                "peer" => format!("{:?}", peer_id),
+                "count" => req.count,
+                "step" => req.step,
+            );
+            self.network.send_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCErrorResponse::error(
+                    RPCResponseErrorCode::InvalidRequest,
+                    &format!(
+                        "Count must be at most {} and step must be non-zero",
+                        MAX_REQUEST_BLOCKS
+                    ),
+                ),
+            );
+            return;
+        }
+
+        let end_slot = req.start_slot.saturating_add(req.count);
         let mut block_roots = self
             .chain
             .forwards_iter_block_roots(Slot::from(req.start_slot))
-            .take_while(|(_root, slot)| slot.as_u64() < req.start_slot + req.count)
+            .take_while(|(_root, slot)| slot.as_u64() < end_slot)
             .map(|(root, _slot)| root)
             .collect::<Vec<_>>();
 
         block_roots.dedup();
 
+        let fork = self.chain.head_info().fork;
+
         let mut blocks_sent = 0;
         for root in block_roots {
-            if let Ok(Some(block)) = self.chain.store.get::<BeaconBlock<T::EthSpec>>(&root) {
-                // Due to skip slots, blocks could be out of the range, we ensure they are in the
-                // range before sending
-                if block.slot >= req.start_slot && block.slot < req.start_slot + req.count {
-                    blocks_sent += 1;
-                    self.network.send_rpc_response(
-                        peer_id.clone(),
-                        request_id,
-                        RPCResponse::BlocksByRange(block.as_ssz_bytes()),
-                    );
+            let code = match self.chain.store.get::<BeaconBlock<T::EthSpec>>(&root) {
+                Ok(Some(block)) => {
+                    // Due to skip slots, blocks could be out of the range, we ensure they are in
+                    // the range before sending
+                    if block.slot >= req.start_slot && block.slot < end_slot {
+                        blocks_sent += 1;
+                        self.network.send_rpc_response(
+                            peer_id.clone(),
+                            request_id,
+                            RPCResponse::BlocksByRange(block_response(&fork, &block)),
+                        );
+                    }
+                    continue;
                 }
-            } else {
-                error!(
-                    self.log,
-                    "Block in the chain is not in the store";
-                    "request_root" => format!("{:}", root),
-                );
-            }
+                Ok(None) => RPCResponseErrorCode::ResourceUnavailable,
+                Err(e) => store_error_code(&e),
+            };
+
+            error!(
+                self.log,
+                "Block in the chain is not in the store";
+                "request_root" => format!("{:}", root),
+            );
+            // Terminate the response with an error, rather than skipping the block, so the
+            // peer knows the response is incomplete.
+            self.network.send_rpc_error_response(
+                peer_id,
+                request_id,
+                RPCErrorResponse::error(code, "Partial response: missing block"),
+            );
+            return;
         }
 
         if blocks_sent < (req.count as usize) {
@@ -371,185 +435,38 @@ impl<T: BeaconChainTypes> MessageProcessor<T> {
             RPCErrorResponse::StreamTermination(ResponseTermination::BlocksByRange),
         );
     }
-
-    /// Handle a `BlocksByRange` response from the peer.
-    /// A `beacon_block` behaves as a stream which is terminated on a `None` response.
-    pub fn on_blocks_by_range_response(
-        &mut self,
-        peer_id: PeerId,
-        request_id: RequestId,
-        beacon_block: Option<BeaconBlock<T::EthSpec>>,
-    ) {
-        let beacon_block = beacon_block.map(Box::new);
-        trace!(
-            self.log,
-            "Received BlocksByRange Response";
-            "peer" => format!("{:?}", peer_id),
-        );
-
-        self.send_to_sync(SyncMessage::BlocksByRangeResponse {
-            peer_id,
-            request_id,
-            beacon_block,
-        });
-    }
-
-    /// Handle a `BlocksByRoot` response from the peer.
-    pub fn on_blocks_by_root_response(
-        &mut self,
-        peer_id: PeerId,
-        request_id: RequestId,
-        beacon_block: Option<BeaconBlock<T::EthSpec>>,
-    ) {
-        let beacon_block = beacon_block.map(Box::new);
-        trace!(
-            self.log,
-            "Received BlocksByRoot Response";
-            "peer" => format!("{:?}", peer_id),
-        );
-
-        self.send_to_sync(SyncMessage::BlocksByRootResponse {
-            peer_id,
-            request_id,
-            beacon_block,
-        });
-    }
-
-    /// Process a gossip message declaring a new block.
-    ///
-    /// Attempts to apply to block to the beacon chain. May queue the block for later processing.
-    ///
-    /// Returns a `bool` which, if `true`, indicates we should forward the block to our peers.
-    pub fn on_block_gossip(&mut self, peer_id: PeerId, block: BeaconBlock<T::EthSpec>) -> bool {
-        match self.chain.process_block(block.clone()) {
-            Ok(outcome) => match outcome {
-                BlockProcessingOutcome::Processed { .. } => {
-                    trace!(self.log, "Gossipsub block processed";
-                            "peer_id" => format!("{:?}",peer_id));
-
-                    // TODO: It would be better if we can run this _after_ we publish the block to
-                    // reduce block propagation latency.
-                    //
-                    // The `MessageHandler` would be the place to put this, however it doesn't seem
-                    // to have a reference to the `BeaconChain`. I will leave this for future
-                    // works.
-                    match self.chain.fork_choice() {
-                        Ok(()) => trace!(
-                            self.log,
-                            "Fork choice success";
-                            "location" => "block gossip"
-                        ),
-                        Err(e) => error!(
-                            self.log,
-                            "Fork choice failed";
-                            "error" => format!("{:?}", e),
-                            "location" => "block gossip"
-                        ),
-                    }
-
-                    SHOULD_FORWARD_GOSSIP_BLOCK
-                }
-                BlockProcessingOutcome::ParentUnknown { .. } => {
-                    // Inform the sync manager to find parents for this block
-                    trace!(self.log, "Block with unknown parent received";
-                            "peer_id" => format!("{:?}",peer_id));
-                    self.send_to_sync(SyncMessage::UnknownBlock(peer_id, Box::new(block.clone())));
-                    SHOULD_FORWARD_GOSSIP_BLOCK
-                }
-                BlockProcessingOutcome::FutureSlot {
-                    present_slot,
-                    block_slot,
-                } if present_slot + FUTURE_SLOT_TOLERANCE >= block_slot => {
-                    //TODO: Decide the logic here
-                    SHOULD_FORWARD_GOSSIP_BLOCK
-                }
-                BlockProcessingOutcome::BlockIsAlreadyKnown => SHOULD_FORWARD_GOSSIP_BLOCK,
-                other => {
-                    warn!(
-                        self.log,
-                        "Invalid gossip beacon block";
-                        "outcome" => format!("{:?}", other),
-                        "block root" => format!("{}", Hash256::from_slice(&block.signed_root()[..])),
-                        "block slot" => block.slot
-                    );
-                    trace!(
-                        self.log,
-                        "Invalid gossip beacon block ssz";
-                        "ssz" => format!("0x{}", hex::encode(block.as_ssz_bytes())),
-                    );
-                    SHOULD_NOT_FORWARD_GOSSIP_BLOCK //TODO: Decide if we want to forward these
-                }
-            },
-            Err(_) => {
-                // error is logged during the processing therefore no error is logged here
-                trace!(
-                    self.log,
-                    "Erroneous gossip beacon block ssz";
-                    "ssz" => format!("0x{}", hex::encode(block.as_ssz_bytes())),
-                );
-                SHOULD_NOT_FORWARD_GOSSIP_BLOCK
-            }
-        }
-    }
-
-    /// Process a gossip message declaring a new attestation.
-    ///
-    /// Not currently implemented.
-    pub fn on_attestation_gossip(&mut self, peer_id: PeerId, msg: Attestation<T::EthSpec>) {
-        match self.chain.process_attestation(msg.clone()) {
-            Ok(outcome) => match outcome {
-                AttestationProcessingOutcome::Processed => {
-                    debug!(
-                        self.log,
-                        "Processed attestation";
-                        "source" => "gossip",
-                        "outcome" => format!("{:?}", outcome)
-                    );
-                }
-                AttestationProcessingOutcome::UnknownHeadBlock { beacon_block_root } => {
-                    // TODO: Maintain this attestation and re-process once sync completes
-                    debug!(
-                    self.log,
-                    "Attestation for unknown block";
-                    "peer_id" => format!("{:?}", peer_id),
-                    "block" => format!("{}", beacon_block_root)
-                    );
-                    // we don't know the block, get the sync manager to handle the block lookup
-                    self.send_to_sync(SyncMessage::UnknownBlockHash(peer_id, beacon_block_root));
-                }
-                AttestationProcessingOutcome::AttestsToFutureState { .. }
-                | AttestationProcessingOutcome::FinalizedSlot { .. } => {} // ignore the attestation
-                AttestationProcessingOutcome::Invalid { .. }
-                | AttestationProcessingOutcome::EmptyAggregationBitfield { .. } => {
-                    // the peer has sent a bad attestation. Remove them.
-                    self.network.disconnect(peer_id, GoodbyeReason::Fault);
-                }
-            },
-            Err(_) => {
-                // error is logged during the processing therefore no error is logged here
-                trace!(
-                    self.log,
-                    "Erroneous gossip attestation ssz";
-                    "ssz" => format!("0x{}", hex::encode(msg.as_ssz_bytes())),
-                );
-            }
-        }
-    }
 }
 
 /// Build a `StatusMessage` representing the state of the given `beacon_chain`.
 pub(crate) fn status_message<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) -> StatusMessage {
-    let state = &beacon_chain.head().beacon_state;
+    let head_info = beacon_chain.head_info();
 
     StatusMessage {
-        fork_version: state.fork.current_version,
-        finalized_root: state.finalized_checkpoint.root,
-        finalized_epoch: state.finalized_checkpoint.epoch,
-        head_root: beacon_chain.head().beacon_block_root,
-        head_slot: state.slot,
+        fork_version: head_info.fork.current_version,
+        finalized_root: head_info.finalized_checkpoint.root,
+        finalized_epoch: head_info.finalized_checkpoint.epoch,
+        head_root: head_info.block_root,
+        head_slot: head_info.slot,
+    }
+}
+
+/// Returns the code of the error response to a request which failed to read from the store.
+///
+/// Data which has been pruned may be available from other peers, whilst other errors indicate a
+/// fault in this node.
+fn store_error_code(error: &StoreError) -> RPCResponseErrorCode {
+    match error {
+        StoreError::HistoryUnavailable { .. } => RPCResponseErrorCode::ResourceUnavailable,
+        _ => RPCResponseErrorCode::ServerError,
     }
 }
 
+/// Returns a response containing `block`, labelled with the version of `fork` at the epoch of the
+/// block.
+fn block_response<E: EthSpec>(fork: &Fork, block: &BeaconBlock<E>) -> BlockResponse {
+    BlockResponse::new(fork.get_fork_version(block.epoch()), block.as_ssz_bytes())
+}
+
 /// Wraps a Network Channel to employ various RPC related network functionality for the message
 /// handler. The handler doesn't manage it's own request Id's and can therefore only send
 /// responses or requests with 0 request Ids.
@@ -6,7 +6,9 @@ mod message_processor;
 mod network_context;
 mod range_sync;
 
+pub(crate) use manager::SyncMessage;
 pub use message_processor::MessageProcessor;
+pub(crate) use message_processor::{HandlerNetworkContext, FUTURE_SLOT_TOLERANCE};
 
 /// Currently implemented sync methods.
 pub enum SyncMethod {
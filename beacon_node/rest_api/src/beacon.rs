@@ -1,13 +1,20 @@
 use crate::helpers::*;
 use crate::response_builder::ResponseBuilder;
-use crate::{ApiError, ApiResult, UrlQuery};
+use crate::{ApiError, ApiResult, BoxFut, NetworkChannel, UrlQuery};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
+use bls::PublicKeyBytes;
+use futures::future::Future;
+use futures::stream::Stream;
 use hyper::{Body, Request};
 use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
 use ssz_derive::Encode;
 use std::sync::Arc;
 use store::Store;
-use types::{BeaconBlock, BeaconState, Epoch, EthSpec, Hash256, Slot, Validator};
+use types::{
+    AttesterSlashing, BeaconBlock, BeaconState, Checkpoint, CommitteeIndex, Epoch, EthSpec,
+    Hash256, ProposerSlashing, Slot, StateField, StateProof, StateProofTarget, Validator,
+};
 
 #[derive(Serialize, Deserialize, Encode)]
 pub struct HeadResponse {
@@ -20,6 +27,10 @@ pub struct HeadResponse {
     pub justified_block_root: Hash256,
     pub previous_justified_slot: Slot,
     pub previous_justified_block_root: Hash256,
+    /// The root upon which the proposers and committees of the head's epoch depend (see
+    /// `ValidatorDuty::dependent_root`).
+    #[serde(default)]
+    pub duty_dependent_root: Option<Hash256>,
 }
 
 /// HTTP handler to return a `BeaconBlock` at a given `root` or `slot`.
@@ -27,30 +38,29 @@ pub fn get_head<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
 ) -> ApiResult {
-    let chain_head = beacon_chain.head();
+    let head_info = beacon_chain.head_info();
 
     let head = HeadResponse {
-        slot: chain_head.beacon_state.slot,
-        block_root: chain_head.beacon_block_root,
-        state_root: chain_head.beacon_state_root,
-        finalized_slot: chain_head
-            .beacon_state
+        slot: head_info.slot,
+        block_root: head_info.block_root,
+        state_root: head_info.state_root,
+        finalized_slot: head_info
             .finalized_checkpoint
             .epoch
             .start_slot(T::EthSpec::slots_per_epoch()),
-        finalized_block_root: chain_head.beacon_state.finalized_checkpoint.root,
-        justified_slot: chain_head
-            .beacon_state
+        finalized_block_root: head_info.finalized_checkpoint.root,
+        justified_slot: head_info
             .current_justified_checkpoint
             .epoch
             .start_slot(T::EthSpec::slots_per_epoch()),
-        justified_block_root: chain_head.beacon_state.current_justified_checkpoint.root,
-        previous_justified_slot: chain_head
-            .beacon_state
+        justified_block_root: head_info.current_justified_checkpoint.root,
+        previous_justified_slot: head_info
             .previous_justified_checkpoint
             .epoch
             .start_slot(T::EthSpec::slots_per_epoch()),
-        previous_justified_block_root: chain_head.beacon_state.previous_justified_checkpoint.root,
+        previous_justified_block_root: head_info.previous_justified_checkpoint.root,
+        duty_dependent_root: beacon_chain
+            .duties_dependent_root(head_info.slot.epoch(T::EthSpec::slots_per_epoch())),
     };
 
     ResponseBuilder::new(&req)?.body(&head)
@@ -98,7 +108,7 @@ pub fn get_block<T: BeaconChainTypes>(
         ("slot", value) => {
             let target = parse_slot(&value)?;
 
-            block_root_at_slot(&beacon_chain, target).ok_or_else(|| {
+            block_root_at_slot(&beacon_chain, target)?.ok_or_else(|| {
                 ApiError::NotFound(format!("Unable to find BeaconBlock for slot {:?}", target))
             })?
         }
@@ -132,7 +142,7 @@ pub fn get_block_root<T: BeaconChainTypes>(
     let slot_string = UrlQuery::from_request(&req)?.only_one("slot")?;
     let target = parse_slot(&slot_string)?;
 
-    let root = block_root_at_slot(&beacon_chain, target).ok_or_else(|| {
+    let root = block_root_at_slot(&beacon_chain, target)?.ok_or_else(|| {
         ApiError::NotFound(format!("Unable to find BeaconBlock for slot {:?}", target))
     })?;
 
@@ -144,7 +154,7 @@ pub fn get_fork<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
 ) -> ApiResult {
-    ResponseBuilder::new(&req)?.body(&beacon_chain.head().beacon_state.fork)
+    ResponseBuilder::new(&req)?.body(&beacon_chain.head_info().fork)
 }
 
 /// HTTP handler to return the set of validators for an `Epoch`
@@ -170,16 +180,192 @@ pub fn get_validators<T: BeaconChainTypes>(
         })?,
     };
 
-    let all_validators = &beacon_chain.head().beacon_state.validators;
-    let active_vals: Vec<Validator> = all_validators
-        .iter()
-        .filter(|v| v.is_active_at(epoch))
-        .cloned()
-        .collect();
+    let active_vals: Vec<Validator> = beacon_chain.with_head_state(|state| {
+        state
+            .validators
+            .iter()
+            .filter(|v| v.is_active_at(epoch))
+            .cloned()
+            .collect()
+    });
 
     ResponseBuilder::new(&req)?.body(&active_vals)
 }
 
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode)]
+pub struct ValidatorRequest {
+    pub pubkeys: Vec<PublicKeyBytes>,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorResponse {
+    /// The validator's BLS public key.
+    pub pubkey: PublicKeyBytes,
+    /// The validator's index in `state.validators`, if it is known to the beacon chain.
+    pub validator_index: Option<usize>,
+    /// The validator's balance in the head state, in Gwei.
+    pub balance: Option<u64>,
+    pub validator: Option<Validator>,
+}
+
+/// HTTP handler to return the balance and status of a set of validators in the head state.
+///
+/// A response is returned for each requested pubkey, in the same order as the request. Unknown
+/// validators have all fields (except `pubkey`) set to `None`.
+pub fn post_validators<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> BoxFut {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let future = req
+        .into_body()
+        .concat2()
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+        .and_then(|chunks| {
+            serde_json::from_slice::<ValidatorRequest>(&chunks).map_err(|e| {
+                ApiError::BadRequest(format!(
+                    "Unable to parse JSON into ValidatorRequest: {:?}",
+                    e
+                ))
+            })
+        })
+        .and_then(|request| validator_responses(beacon_chain, request.pubkeys))
+        .and_then(|validators| response_builder?.body_no_ssz(&validators));
+
+    Box::new(future)
+}
+
+/// HTTP Handler to accept a `ProposerSlashing`, adding it to the operation pool and publishing it
+/// to the network.
+pub fn post_proposer_slashing<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel,
+    log: Logger,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                serde_json::from_slice::<ProposerSlashing>(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to deserialize JSON into a ProposerSlashing: {:?}",
+                        e
+                    ))
+                })
+            })
+            .and_then(move |slashing| {
+                match beacon_chain.process_proposer_slashing(slashing.clone()) {
+                    Ok(()) => {
+                        info!(
+                            log,
+                            "Proposer slashing from the HTTP API";
+                            "proposer_index" => slashing.proposer_index,
+                        );
+                        publish_proposer_slashing_to_network(network_chan, slashing)
+                    }
+                    Err(e) => {
+                        warn!(
+                            log,
+                            "Invalid proposer slashing from the HTTP API";
+                            "proposer_index" => slashing.proposer_index,
+                            "error" => format!("{:?}", e)
+                        );
+
+                        Err(ApiError::ProcessingError(format!(
+                            "The ProposerSlashing could not be processed and has not been published: {:?}",
+                            e
+                        )))
+                    }
+                }
+            })
+            .and_then(|_| response_builder?.body_no_ssz(&())),
+    )
+}
+
+/// HTTP Handler to accept an `AttesterSlashing`, adding it to the operation pool and publishing it
+/// to the network.
+pub fn post_attester_slashing<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel,
+    log: Logger,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                serde_json::from_slice::<AttesterSlashing<T::EthSpec>>(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to deserialize JSON into an AttesterSlashing: {:?}",
+                        e
+                    ))
+                })
+            })
+            .and_then(move |slashing| {
+                match beacon_chain.process_attester_slashing(slashing.clone()) {
+                    Ok(()) => {
+                        info!(
+                            log,
+                            "Attester slashing from the HTTP API";
+                            "target_epoch" => slashing.attestation_1.data.target.epoch,
+                        );
+                        publish_attester_slashing_to_network::<T>(network_chan, slashing)
+                    }
+                    Err(e) => {
+                        warn!(
+                            log,
+                            "Invalid attester slashing from the HTTP API";
+                            "error" => format!("{:?}", e)
+                        );
+
+                        Err(ApiError::ProcessingError(format!(
+                            "The AttesterSlashing could not be processed and has not been published: {:?}",
+                            e
+                        )))
+                    }
+                }
+            })
+            .and_then(|_| response_builder?.body_no_ssz(&())),
+    )
+}
+
+fn validator_responses<T: BeaconChainTypes>(
+    beacon_chain: Arc<BeaconChain<T>>,
+    validator_pubkeys: Vec<PublicKeyBytes>,
+) -> Result<Vec<ValidatorResponse>, ApiError> {
+    let mut state = beacon_chain.head().beacon_state;
+
+    state
+        .update_pubkey_cache()
+        .map_err(|e| ApiError::ServerError(format!("Unable to build pubkey cache: {:?}", e)))?;
+
+    validator_pubkeys
+        .into_iter()
+        .map(|pubkey| {
+            let validator_index = state.get_validator_index(&pubkey).map_err(|e| {
+                ApiError::ServerError(format!("Unable to read pubkey cache: {:?}", e))
+            })?;
+
+            Ok(ValidatorResponse {
+                pubkey,
+                validator_index,
+                balance: validator_index.and_then(|i| state.balances.get(i).copied()),
+                validator: validator_index.and_then(|i| state.validators.get(i).cloned()),
+            })
+        })
+        .collect()
+}
+
 #[derive(Serialize, Encode)]
 #[serde(bound = "T: EthSpec")]
 pub struct StateResponse<T: EthSpec> {
@@ -195,8 +381,6 @@ pub fn get_state<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
 ) -> ApiResult {
-    let head_state = beacon_chain.head().beacon_state;
-
     let (key, value) = match UrlQuery::from_request(&req) {
         Ok(query) => {
             // We have *some* parameters, just check them.
@@ -205,7 +389,8 @@ pub fn get_state<T: BeaconChainTypes>(
         }
         Err(ApiError::BadRequest(_)) => {
             // No parameters provided at all, use current slot.
-            (String::from("slot"), head_state.slot.to_string())
+            let head_slot = beacon_chain.with_head_state(|state| state.slot);
+            (String::from("slot"), head_slot.to_string())
         }
         Err(e) => {
             return Err(e);
@@ -216,10 +401,11 @@ pub fn get_state<T: BeaconChainTypes>(
         ("slot", value) => state_at_slot(&beacon_chain, parse_slot(&value)?)?,
         ("root", value) => {
             let root = &parse_root(&value)?;
+            let slot = beacon_chain.store.get_state_slot(root)?;
 
             let state = beacon_chain
                 .store
-                .get_state(root, None)?
+                .get_state(root, slot)?
                 .ok_or_else(|| ApiError::NotFound(format!("No state for root: {:?}", root)))?;
 
             (*root, state)
@@ -251,14 +437,92 @@ pub fn get_state_root<T: BeaconChainTypes>(
     ResponseBuilder::new(&req)?.body(&root)
 }
 
+#[derive(Serialize, Deserialize, Encode)]
+pub struct StateProofResponse {
+    pub state_root: Hash256,
+    pub proof: StateProof,
+}
+
+/// HTTP handler to return a Merkle proof of a value within a `BeaconState`.
+///
+/// The state is given by its `root`, or is the head state if no `root` is supplied. The value is
+/// given by exactly one of:
+///
+/// - `field`: the name of a top-level field of the state (e.g., `finalized_checkpoint`).
+/// - `block_root_slot`: the slot of a block root within `block_roots`.
+/// - `state_root_slot`: the slot of a state root within `state_roots`.
+/// - `validator_index`: the index of a validator record within `validators`.
+/// - `historical_block_root_slot`: the slot of a block root within a `HistoricalBatch` which has
+///   been accumulated into `historical_roots`.
+pub fn get_state_proof<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = UrlQuery::from_request(&req)?;
+
+    let state_root = match query.first_of(&["root"]) {
+        Ok((_key, value)) => parse_root(&value)?,
+        Err(_) => beacon_chain.head().beacon_state_root,
+    };
+
+    let (key, value) = query.first_of(&[
+        "field",
+        "block_root_slot",
+        "state_root_slot",
+        "validator_index",
+        "historical_block_root_slot",
+    ])?;
+
+    let proof = if key == "historical_block_root_slot" {
+        let slot = parse_slot(&value)?;
+        beacon_chain
+            .historical_block_root_proof(&state_root, slot)
+            .map_err(|e| proof_error(format!("historical block root at {}", slot), e))?
+    } else {
+        let target = match (key, value) {
+            (ref key, ref value) if key == "field" => StateField::from_name(value)
+                .map(StateProofTarget::Field)
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown state field: {}", value)))?,
+            (ref key, ref value) if key == "block_root_slot" => {
+                StateProofTarget::BlockRoot(parse_slot(value)?)
+            }
+            (ref key, ref value) if key == "state_root_slot" => {
+                StateProofTarget::StateRoot(parse_slot(value)?)
+            }
+            (_, value) => StateProofTarget::Validator(value.parse::<usize>().map_err(|e| {
+                ApiError::BadRequest(format!("Unable to parse validator index: {:?}", e))
+            })?),
+        };
+
+        beacon_chain
+            .state_proof(&state_root, target)
+            .map_err(|e| proof_error(format!("{:?}", target), e))?
+    }
+    .ok_or_else(|| ApiError::NotFound(format!("No state for root: {:?}", state_root)))?;
+
+    ResponseBuilder::new(&req)?.body(&StateProofResponse { state_root, proof })
+}
+
+/// Maps an error from computing a proof of `target` into an `ApiError`, blaming the request for
+/// values which are not in the state.
+fn proof_error(target: String, e: beacon_chain::BeaconChainError) -> ApiError {
+    match e {
+        beacon_chain::BeaconChainError::BeaconStateError(e) => {
+            ApiError::BadRequest(format!("Unable to prove {}: {:?}", target, e))
+        }
+        beacon_chain::BeaconChainError::MissingHistoricalBatch(index) => ApiError::NotFound(
+            format!("No historical batch for historical_roots index {}", index),
+        ),
+        e => ApiError::ServerError(format!("Unable to compute state proof: {:?}", e)),
+    }
+}
+
 /// HTTP handler to return the highest finalized slot.
 pub fn get_current_finalized_checkpoint<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
 ) -> ApiResult {
-    let head_state = beacon_chain.head().beacon_state;
-
-    let checkpoint = head_state.finalized_checkpoint.clone();
+    let checkpoint = beacon_chain.head_info().finalized_checkpoint;
 
     ResponseBuilder::new(&req)?.body(&checkpoint)
 }
@@ -278,5 +542,170 @@ pub fn get_genesis_time<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
 ) -> ApiResult {
-    ResponseBuilder::new(&req)?.body(&beacon_chain.head().beacon_state.genesis_time)
+    ResponseBuilder::new(&req)?.body(&beacon_chain.head_info().genesis_time)
+}
+
+/// The prefix of the path of the committees endpoint.
+pub const STATES_PATH_PREFIX: &str = "/eth/v1/beacon/states/";
+/// The suffix of the path of the committees endpoint.
+pub const COMMITTEES_PATH_SUFFIX: &str = "/committees";
+
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+pub struct Committee {
+    pub index: CommitteeIndex,
+    pub slot: Slot,
+    pub validators: Vec<usize>,
+}
+
+/// HTTP handler to return the committees of the state identified in the path (see
+/// `state_id_block_root`), e.g. `/eth/v1/beacon/states/head/committees?epoch=3&index=0&slot=25`.
+///
+/// The `epoch` defaults to the epoch of the state and may be up to one epoch after it. The
+/// committees may be filtered by `index` and `slot`.
+///
+/// Committees are served from the shuffling cache of the beacon chain, without loading or cloning
+/// a state where possible.
+pub fn get_state_committees<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    let (block_root, state_slot) = state_id_block_root(
+        &beacon_chain,
+        req.uri()
+            .path()
+            .trim_start_matches(STATES_PATH_PREFIX)
+            .trim_end_matches(COMMITTEES_PATH_SUFFIX),
+    )?;
+    let state_epoch = state_slot.epoch(slots_per_epoch);
+
+    let mut epoch = state_epoch;
+    let mut index = None;
+    let mut slot = None;
+
+    if let Some(query) = req.uri().query() {
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "epoch" => epoch = parse_epoch(&value)?,
+                "index" => index = Some(parse_committee_index(&value)?),
+                "slot" => slot = Some(parse_slot(&value)?),
+                _ => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Unknown query parameter: {}",
+                        key
+                    )))
+                }
+            }
+        }
+    }
+
+    if epoch > state_epoch + 1 {
+        return Err(ApiError::BadRequest(format!(
+            "Epoch {} is more than one epoch after the state at slot {}",
+            epoch, state_slot
+        )));
+    }
+    if let Some(slot) = slot {
+        if slot.epoch(slots_per_epoch) != epoch {
+            return Err(ApiError::BadRequest(format!(
+                "Slot {} is not in epoch {}",
+                slot, epoch
+            )));
+        }
+    }
+
+    let committee_cache = beacon_chain
+        .epoch_committees(block_root, epoch)
+        .map_err(|e| ApiError::ServerError(format!("Unable to get committees: {:?}", e)))?;
+
+    let mut committees = vec![];
+    for committee_slot in epoch.slot_iter(slots_per_epoch) {
+        if slot.map_or(false, |slot| slot != committee_slot) {
+            continue;
+        }
+
+        for committee in committee_cache.get_beacon_committees_at_slot(committee_slot)? {
+            if index.map_or(true, |index| index == committee.index) {
+                committees.push(Committee {
+                    index: committee.index,
+                    slot: committee.slot,
+                    validators: committee.committee.to_vec(),
+                });
+            }
+        }
+    }
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&committees)
+}
+
+/// Returns the root of the block on which the state identified by `state_id` is built, and the
+/// slot of that state.
+///
+/// The `state_id` is one of `head`, `genesis`, `finalized`, `justified`, a slot or a
+/// `0x`-prefixed state root. Slots after the head refer to the head advanced through skip slots.
+fn state_id_block_root<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    state_id: &str,
+) -> Result<(Hash256, Slot), ApiError> {
+    let head_info = beacon_chain.head_info();
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+
+    // The root of the genesis checkpoint is zero, rather than the genesis block root.
+    let checkpoint_block_root = |checkpoint: Checkpoint| {
+        let root = if checkpoint.root == Hash256::zero() {
+            beacon_chain.genesis_block_root
+        } else {
+            checkpoint.root
+        };
+        (root, checkpoint.epoch.start_slot(slots_per_epoch))
+    };
+
+    match state_id {
+        "head" => Ok((head_info.block_root, head_info.slot)),
+        "genesis" => Ok((
+            beacon_chain.genesis_block_root,
+            beacon_chain.spec.genesis_slot,
+        )),
+        "finalized" => Ok(checkpoint_block_root(head_info.finalized_checkpoint)),
+        "justified" => Ok(checkpoint_block_root(
+            head_info.current_justified_checkpoint,
+        )),
+        state_id if state_id.starts_with("0x") => {
+            let state_root = parse_root(state_id)?;
+            let slot = beacon_chain
+                .store
+                .get_state_slot(&state_root)?
+                .ok_or_else(|| {
+                    ApiError::NotFound(format!("Unknown state root: {:?}", state_root))
+                })?;
+
+            Ok((state_id_slot_block_root(beacon_chain, slot)?, slot))
+        }
+        state_id => {
+            let slot = parse_slot(state_id)?;
+            let current_slot = beacon_chain
+                .slot()
+                .map_err(|_| ApiError::ServerError("Unable to read slot clock".to_string()))?;
+
+            if slot > current_slot {
+                Err(ApiError::BadRequest(format!(
+                    "Requested slot {} is past the current slot {}",
+                    slot, current_slot
+                )))
+            } else if slot >= head_info.slot {
+                Ok((head_info.block_root, slot))
+            } else {
+                Ok((state_id_slot_block_root(beacon_chain, slot)?, slot))
+            }
+        }
+    }
+}
+
+/// Returns the root of the latest block at or before `slot` in the canonical chain.
+fn state_id_slot_block_root<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    slot: Slot,
+) -> Result<Hash256, ApiError> {
+    block_root_at_slot(beacon_chain, slot)?
+        .ok_or_else(|| ApiError::NotFound(format!("Unable to find block at slot {}", slot)))
 }
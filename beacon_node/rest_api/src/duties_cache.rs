@@ -0,0 +1,93 @@
+use crate::metrics;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::{BeaconState, Epoch, EthSpec, Hash256, RelativeEpoch, Slot};
+
+/// The maximum number of epochs for which duties are cached.
+pub const MAX_CACHED_EPOCHS: usize = 4;
+
+/// The state and proposers from which the duties of any validator in `epoch` are read.
+pub struct EpochDuties<T: EthSpec> {
+    /// A state with its committee and pubkey caches built for `relative_epoch`.
+    pub state: BeaconState<T>,
+    pub relative_epoch: RelativeEpoch,
+    /// The index of the proposer for each slot of the epoch.
+    pub proposers: Vec<(usize, Slot)>,
+}
+
+struct CacheEntry<T: EthSpec> {
+    epoch: Epoch,
+    dependent_root: Hash256,
+    inserted: Instant,
+    duties: Arc<EpochDuties<T>>,
+}
+
+/// Caches the `EpochDuties` of recent epochs, so that the duties requests made by every validator
+/// client at an epoch boundary do not each load a state and build its committees.
+///
+/// Entries are keyed by the epoch and the root of the block upon which its shuffling depends (see
+/// `BeaconChain::duties_dependent_root`), so an entry is never served once a re-org has replaced
+/// that block. Entries also expire after `ttl`, so that the indices of newly deposited validators
+/// are found.
+pub struct DutiesCache<T: EthSpec> {
+    ttl: Duration,
+    entries: RwLock<Vec<CacheEntry<T>>>,
+}
+
+impl<T: EthSpec> DutiesCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(vec![]),
+        }
+    }
+
+    /// Returns the duties of `epoch` with `dependent_root`, if they are cached and have not
+    /// expired.
+    pub fn get(&self, epoch: Epoch, dependent_root: Hash256) -> Option<Arc<EpochDuties<T>>> {
+        let duties = self
+            .entries
+            .read()
+            .iter()
+            .find(|entry| {
+                entry.epoch == epoch
+                    && entry.dependent_root == dependent_root
+                    && entry.inserted.elapsed() < self.ttl
+            })
+            .map(|entry| entry.duties.clone());
+
+        if duties.is_some() {
+            metrics::inc_counter(&metrics::DUTIES_CACHE_HITS);
+        } else {
+            metrics::inc_counter(&metrics::DUTIES_CACHE_MISSES);
+        }
+
+        duties
+    }
+
+    /// Caches the `duties` of `epoch` with `dependent_root`, replacing any other duties of `epoch`
+    /// and evicting the oldest epoch if the cache is full.
+    pub fn insert(&self, epoch: Epoch, dependent_root: Hash256, duties: Arc<EpochDuties<T>>) {
+        let mut entries = self.entries.write();
+
+        entries.retain(|entry| entry.epoch != epoch);
+        if entries.len() >= MAX_CACHED_EPOCHS {
+            if let Some(oldest) = entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.epoch)
+                .map(|(i, _)| i)
+            {
+                entries.remove(oldest);
+            }
+        }
+
+        entries.push(CacheEntry {
+            epoch,
+            dependent_root,
+            inserted: Instant::now(),
+            duties,
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use crate::BoxFut;
 use hyper::{Body, Response, StatusCode};
+use lighthouse_error::{CodedError, ErrorCode};
 use std::error::Error as StdError;
 
 #[derive(PartialEq, Debug, Clone)]
@@ -31,12 +32,29 @@ impl ApiError {
     }
 }
 
+impl CodedError for ApiError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            ApiError::MethodNotAllowed(_) => 4000,
+            ApiError::ServerError(_) => 4001,
+            ApiError::NotImplemented(_) => 4002,
+            ApiError::BadRequest(_) => 4003,
+            ApiError::NotFound(_) => 4004,
+            ApiError::UnsupportedType(_) => 4005,
+            ApiError::ImATeapot(_) => 4006,
+            ApiError::ProcessingError(_) => 4007,
+        })
+    }
+}
+
 impl Into<Response<Body>> for ApiError {
     fn into(self) -> Response<Body> {
+        let code = self.error_code();
         let status_code = self.status_code();
         Response::builder()
             .status(status_code.0)
             .header("content-type", "text/plain; charset=utf-8")
+            .header(lighthouse_error::HTTP_HEADER, code.to_string())
             .body(Body::from(status_code.1))
             .expect("Response should always be created.")
     }
@@ -50,7 +68,16 @@ impl Into<BoxFut> for ApiError {
 
 impl From<store::Error> for ApiError {
     fn from(e: store::Error) -> ApiError {
-        ApiError::ServerError(format!("Database error: {:?}", e))
+        match e {
+            store::Error::HistoryUnavailable {
+                requested_slot,
+                oldest_slot,
+            } => ApiError::NotFound(format!(
+                "History unavailable: slot {} is prior to the oldest slot {} held by the node",
+                requested_slot, oldest_slot
+            )),
+            e => ApiError::ServerError(format!("Database error {}: {:?}", e.error_code(), e)),
+        }
     }
 }
 
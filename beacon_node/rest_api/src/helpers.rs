@@ -3,7 +3,9 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use bls::PublicKeyBytes;
 use eth2_libp2p::{PubsubMessage, Topic};
 use eth2_libp2p::{
-    BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC, TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
+    ATTESTER_SLASHING_TOPIC, BEACON_AGGREGATE_AND_PROOF_TOPIC, BEACON_ATTESTATION_TOPIC,
+    BEACON_BLOCK_AND_WITNESS_TOPIC, BEACON_BLOCK_TOPIC, PROPOSER_SLASHING_TOPIC,
+    TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX, VOLUNTARY_EXIT_TOPIC,
 };
 use hex;
 use http::header;
@@ -12,11 +14,12 @@ use network::NetworkMessage;
 use parking_lot::RwLock;
 use ssz::{Decode, Encode};
 use std::sync::Arc;
-use store::{iter::AncestorIter, Store};
+use store::Store;
 use tokio::sync::mpsc;
 use types::{
-    Attestation, BeaconBlock, BeaconState, CommitteeIndex, Epoch, EthSpec, Hash256, RelativeEpoch,
-    Signature, Slot,
+    Attestation, AttesterSlashing, BeaconBlock, BeaconState, BlockBodyExtension, CommitteeIndex,
+    Epoch, EthSpec, Hash256, ProposerSlashing, RelativeEpoch, Signature, SignedAggregateAndProof,
+    Slot, VoluntaryExit,
 };
 
 /// Parse a slot.
@@ -80,6 +83,35 @@ pub fn parse_signature(string: &str) -> Result<Signature, ApiError> {
     }
 }
 
+/// Parse a graffiti from a `0x` prefixed string of at most 32 bytes.
+///
+/// Shorter graffitis are padded with trailing zeros.
+pub fn parse_graffiti(string: &str) -> Result<[u8; 32], ApiError> {
+    const PREFIX: &str = "0x";
+
+    if string.starts_with(PREFIX) {
+        let trimmed = string.trim_start_matches(PREFIX);
+        let bytes = hex::decode(trimmed)
+            .map_err(|e| ApiError::BadRequest(format!("Unable to parse graffiti hex: {:?}", e)))?;
+
+        let mut graffiti = [0; 32];
+        if bytes.len() > graffiti.len() {
+            return Err(ApiError::BadRequest(format!(
+                "Graffiti is {} bytes, the maximum is {}",
+                bytes.len(),
+                graffiti.len()
+            )));
+        }
+        graffiti[..bytes.len()].copy_from_slice(&bytes);
+
+        Ok(graffiti)
+    } else {
+        Err(ApiError::BadRequest(
+            "Graffiti must have a 0x prefix".to_string(),
+        ))
+    }
+}
+
 /// Parse a root from a `0x` preixed string.
 ///
 /// E.g., `"0x0000000000000000000000000000000000000000000000000000000000000000"`
@@ -118,16 +150,21 @@ pub fn parse_pubkey_bytes(string: &str) -> Result<PublicKeyBytes, ApiError> {
 /// Returns the root of the `BeaconBlock` in the canonical chain of `beacon_chain` at the given
 /// `slot`, if possible.
 ///
-/// May return a root for a previous slot, in the case of skip slots.
+/// May return a root for a previous slot, in the case of skip slots. Returns an error if `slot` is
+/// older than the anchor of the database.
 pub fn block_root_at_slot<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     target: Slot,
-) -> Option<Hash256> {
-    beacon_chain
+) -> Result<Option<Hash256>, ApiError> {
+    if let Some(anchor_info) = beacon_chain.store.get_anchor_info()? {
+        anchor_info.check_block_available(target)?;
+    }
+
+    Ok(beacon_chain
         .rev_iter_block_roots()
         .take_while(|(_root, slot)| *slot >= target)
         .find(|(_root, slot)| *slot == target)
-        .map(|(root, _slot)| root)
+        .map(|(root, _slot)| root))
 }
 
 /// Returns a `BeaconState` and it's root in the canonical chain of `beacon_chain` at the given
@@ -139,16 +176,17 @@ pub fn state_at_slot<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     slot: Slot,
 ) -> Result<(Hash256, BeaconState<T::EthSpec>), ApiError> {
-    let head_state = &beacon_chain.head().beacon_state;
-
-    if head_state.slot == slot {
-        // The request slot is the same as the best block (head) slot.
+    // The request slot is the same as the best block (head) slot.
+    let head = beacon_chain.with_head(|head| {
+        if head.beacon_state.slot == slot {
+            Some((head.beacon_state_root, head.beacon_state.clone()))
+        } else {
+            None
+        }
+    });
 
-        // I'm not sure if this `.clone()` will be optimized out. If not, it seems unnecessary.
-        Ok((
-            beacon_chain.head().beacon_state_root,
-            beacon_chain.head().beacon_state.clone(),
-        ))
+    if let Some(head) = head {
+        Ok(head)
     } else {
         let root = state_root_at_slot(beacon_chain, slot)?;
 
@@ -170,7 +208,8 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
     beacon_chain: &BeaconChain<T>,
     slot: Slot,
 ) -> Result<Hash256, ApiError> {
-    let head_state = &beacon_chain.head().beacon_state;
+    let (head_slot, head_state_root) =
+        beacon_chain.with_head(|head| (head.beacon_state.slot, head.beacon_state_root));
     let current_slot = beacon_chain
         .slot()
         .map_err(|_| ApiError::ServerError("Unable to read slot clock".to_string()))?;
@@ -190,19 +229,22 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
             "Requested slot {} is past the current slot {}",
             slot, current_slot
         )))
-    } else if head_state.slot == slot {
+    } else if head_slot == slot {
         // 2. The request slot is the same as the best block (head) slot.
         //
         // The head state root is stored in memory, return a reference.
-        Ok(beacon_chain.head().beacon_state_root)
-    } else if head_state.slot > slot {
+        Ok(head_state_root)
+    } else if head_slot > slot {
         // 3. The request slot is prior to the head slot.
         //
         // Iterate through the state roots on the head state to find the root for that
         // slot. Once the root is found, load it from the database.
-        Ok(head_state
-            .try_iter_ancestor_roots(beacon_chain.store.clone())
-            .ok_or_else(|| ApiError::ServerError("Failed to create roots iterator".to_string()))?
+        if let Some(anchor_info) = beacon_chain.store.get_anchor_info()? {
+            anchor_info.check_state_available(slot)?;
+        }
+
+        Ok(beacon_chain
+            .rev_iter_state_roots()
             .find(|(_root, s)| *s == slot)
             .map(|(root, _slot)| root)
             .ok_or_else(|| ApiError::NotFound(format!("Unable to find state at slot {}", slot)))?)
@@ -211,7 +253,7 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
         //
         // Use `per_slot_processing` to advance the head state to the present slot,
         // assuming that all slots do not contain a block (i.e., they are skipped slots).
-        let mut state = beacon_chain.head().beacon_state.clone();
+        let mut state = beacon_chain.head().beacon_state;
         let spec = &T::EthSpec::default_spec();
 
         for _ in state.slot.as_u64()..slot.as_u64() {
@@ -256,6 +298,26 @@ pub fn publish_beacon_block_to_network<T: BeaconChainTypes + 'static>(
         )));
     }
 
+    // Blocks which carry an execution witness are also published on the experimental topic for
+    // such blocks.
+    if block.body.extension.execution_witness().is_some() {
+        let topic = Topic::new(format!(
+            "/{}/{}/{}",
+            TOPIC_PREFIX, BEACON_BLOCK_AND_WITNESS_TOPIC, TOPIC_ENCODING_POSTFIX
+        ));
+        let message = PubsubMessage::BlockAndWitness(block.as_ssz_bytes());
+
+        if let Err(e) = chan.write().try_send(NetworkMessage::Publish {
+            topics: vec![topic],
+            message,
+        }) {
+            return Err(ApiError::ServerError(format!(
+                "Unable to send new block and witness to network: {:?}",
+                e
+            )));
+        }
+    }
+
     Ok(())
 }
 
@@ -285,6 +347,110 @@ pub fn publish_attestation_to_network<T: BeaconChainTypes + 'static>(
     Ok(())
 }
 
+pub fn publish_aggregate_and_proof_to_network<T: BeaconChainTypes + 'static>(
+    chan: Arc<RwLock<mpsc::UnboundedSender<NetworkMessage>>>,
+    signed_aggregate: SignedAggregateAndProof<T::EthSpec>,
+) -> Result<(), ApiError> {
+    // create the network topic to send on
+    let topic_string = format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, BEACON_AGGREGATE_AND_PROOF_TOPIC, TOPIC_ENCODING_POSTFIX
+    );
+    let topic = Topic::new(topic_string);
+    let message = PubsubMessage::AggregateAndProof(signed_aggregate.as_ssz_bytes());
+
+    // Publish the aggregate to the p2p network via gossipsub.
+    if let Err(e) = chan.write().try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        return Err(ApiError::ServerError(format!(
+            "Unable to send new aggregate to network: {:?}",
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn publish_voluntary_exit_to_network(
+    chan: Arc<RwLock<mpsc::UnboundedSender<NetworkMessage>>>,
+    exit: VoluntaryExit,
+) -> Result<(), ApiError> {
+    // create the network topic to send on
+    let topic_string = format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, VOLUNTARY_EXIT_TOPIC, TOPIC_ENCODING_POSTFIX
+    );
+    let topic = Topic::new(topic_string);
+    let message = PubsubMessage::VoluntaryExit(exit.as_ssz_bytes());
+
+    // Publish the exit to the p2p network via gossipsub.
+    if let Err(e) = chan.write().try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        return Err(ApiError::ServerError(format!(
+            "Unable to send voluntary exit to network: {:?}",
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn publish_proposer_slashing_to_network(
+    chan: Arc<RwLock<mpsc::UnboundedSender<NetworkMessage>>>,
+    slashing: ProposerSlashing,
+) -> Result<(), ApiError> {
+    // create the network topic to send on
+    let topic_string = format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, PROPOSER_SLASHING_TOPIC, TOPIC_ENCODING_POSTFIX
+    );
+    let topic = Topic::new(topic_string);
+    let message = PubsubMessage::ProposerSlashing(slashing.as_ssz_bytes());
+
+    // Publish the slashing to the p2p network via gossipsub.
+    if let Err(e) = chan.write().try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        return Err(ApiError::ServerError(format!(
+            "Unable to send proposer slashing to network: {:?}",
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn publish_attester_slashing_to_network<T: BeaconChainTypes + 'static>(
+    chan: Arc<RwLock<mpsc::UnboundedSender<NetworkMessage>>>,
+    slashing: AttesterSlashing<T::EthSpec>,
+) -> Result<(), ApiError> {
+    // create the network topic to send on
+    let topic_string = format!(
+        "/{}/{}/{}",
+        TOPIC_PREFIX, ATTESTER_SLASHING_TOPIC, TOPIC_ENCODING_POSTFIX
+    );
+    let topic = Topic::new(topic_string);
+    let message = PubsubMessage::AttesterSlashing(slashing.as_ssz_bytes());
+
+    // Publish the slashing to the p2p network via gossipsub.
+    if let Err(e) = chan.write().try_send(NetworkMessage::Publish {
+        topics: vec![topic],
+        message,
+    }) {
+        return Err(ApiError::ServerError(format!(
+            "Unable to send attester slashing to network: {:?}",
+            e
+        )));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -306,6 +472,19 @@ mod test {
         assert!(parse_root("0x00").is_err());
     }
 
+    #[test]
+    fn parse_graffiti_works() {
+        let mut expected = [0; 32];
+        expected[0] = 42;
+        assert_eq!(parse_graffiti("0x2a"), Ok(expected));
+        assert_eq!(
+            parse_graffiti(&format!("0x{}", "ff".repeat(32))),
+            Ok([255; 32])
+        );
+        assert!(parse_graffiti(&format!("0x{}", "ff".repeat(33))).is_err());
+        assert!(parse_graffiti("2a").is_err());
+    }
+
     #[test]
     fn parse_slot_works() {
         assert_eq!(parse_slot("0"), Ok(Slot::new(0)));
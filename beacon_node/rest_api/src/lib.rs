@@ -6,8 +6,10 @@ extern crate network as client_network;
 
 mod beacon;
 pub mod config;
+mod duties_cache;
 mod error;
 mod helpers;
+mod lighthouse;
 mod metrics;
 mod network;
 mod node;
@@ -21,6 +23,8 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use client_network::NetworkMessage;
 use client_network::Service as NetworkService;
 pub use config::ApiEncodingFormat;
+use duties_cache::DutiesCache;
+use environment::TaskExecutor;
 use error::{ApiError, ApiResult};
 use eth2_config::Eth2Config;
 use hyper::rt::Future;
@@ -33,14 +37,27 @@ use std::net::SocketAddr;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::runtime::TaskExecutor;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use url_query::UrlQuery;
 
 pub use crate::helpers::parse_pubkey_bytes;
-pub use beacon::{BlockResponse, HeadResponse, StateResponse};
+pub use beacon::{
+    BlockResponse, Committee, HeadResponse, StateProofResponse, StateResponse, ValidatorRequest,
+    ValidatorResponse,
+};
+pub use beacon_chain::ChainHealth;
 pub use config::Config;
-pub use validator::{BulkValidatorDutiesRequest, ValidatorDuty};
+pub use lighthouse::{
+    EpochParticipation, HealthResponse, ServiceReport, StartupReport,
+    ValidatorAttestationPerformance,
+};
+pub use network::BanRequest;
+pub use store::AnchorInfo;
+pub use validator::{
+    BroadcastValidation, BulkValidatorDutiesRequest, ValidatorDuty, ValidatorLiveness,
+    ValidatorLivenessRequest,
+};
 
 pub type BoxFut = Box<dyn Future<Item = Response<Body>, Error = ApiError> + Send>;
 pub type NetworkChannel = Arc<RwLock<mpsc::UnboundedSender<NetworkMessage>>>;
@@ -58,16 +75,23 @@ pub fn start_server<T: BeaconChainTypes>(
     db_path: PathBuf,
     freezer_db_path: PathBuf,
     eth2_config: Eth2Config,
+    startup_report: StartupReport,
     log: slog::Logger,
 ) -> Result<(exit_future::Signal, SocketAddr), hyper::Error> {
     let inner_log = log.clone();
     let eth2_config = Arc::new(eth2_config);
+    let startup_report = Arc::new(startup_report);
+    let duties_cache = Arc::new(DutiesCache::new(Duration::from_millis(
+        beacon_chain.spec.milliseconds_per_slot,
+    )));
 
     // Define the function that will build the request handler.
     let make_service = make_service_fn(move |_socket: &AddrStream| {
         let beacon_chain = beacon_chain.clone();
         let log = inner_log.clone();
         let eth2_config = eth2_config.clone();
+        let startup_report = startup_report.clone();
+        let duties_cache = duties_cache.clone();
         let network_service = network_info.network_service.clone();
         let network_channel = Arc::new(RwLock::new(network_info.network_chan.clone()));
         let db_path = db_path.clone();
@@ -80,6 +104,8 @@ pub fn start_server<T: BeaconChainTypes>(
                 network_service.clone(),
                 network_channel.clone(),
                 eth2_config.clone(),
+                startup_report.clone(),
+                duties_cache.clone(),
                 log.clone(),
                 db_path.clone(),
                 freezer_db_path.clone(),
@@ -121,7 +147,7 @@ pub fn start_server<T: BeaconChainTypes>(
         "port" => actual_listen_addr.port(),
     );
 
-    executor.spawn(server_future);
+    executor.spawn(server_future, "http_server");
 
     Ok((exit_signal, actual_listen_addr))
 }
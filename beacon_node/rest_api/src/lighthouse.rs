@@ -0,0 +1,646 @@
+use crate::config::ApiEncodingFormat;
+use crate::helpers::{
+    check_content_type_for_json, parse_epoch, parse_pubkey_bytes, parse_root, parse_slot,
+    state_at_slot,
+};
+use crate::response_builder::ResponseBuilder;
+use crate::{ApiError, ApiResult, BoxFut, NetworkService, UrlQuery};
+use beacon_chain::{
+    BeaconChain, BeaconChainError, BeaconChainTypes, CacheMemoryReport, ChainHealth, CheckPoint,
+    PersistedOperationPool,
+};
+use eth2_config::Eth2Config;
+use eth2_libp2p::address::parse_enr;
+use eth2_testnet_config::{Eth2TestnetConfig, TestnetBundle};
+use futures::{Future, Stream};
+use http::header;
+use hyper::{Body, Request};
+use serde::{Deserialize, Serialize};
+use ssz::Decode;
+use state_processing::EpochProcessingSummary;
+use std::sync::Arc;
+use store::Store;
+use types::{BeaconBlock, Epoch, EthSpec, Slot, YamlConfig};
+
+/// The prefix of the paths of the per-validator Lighthouse endpoints.
+pub const VALIDATORS_PATH_PREFIX: &str = "/lighthouse/validators/";
+/// The suffix of the path of the balance history endpoint.
+pub const BALANCES_PATH_SUFFIX: &str = "/balances";
+/// The suffix of the path of the activation and exit queue endpoint.
+pub const QUEUE_PATH_SUFFIX: &str = "/queue";
+
+/// The maximum number of epochs which may be replayed by a single participation export request.
+pub const MAX_PARTICIPATION_EXPORT_EPOCHS: u64 = 256;
+
+/// HTTP handler to return the recorded balances of the validator whose public key is in the path,
+/// e.g. `/lighthouse/validators/0x..../balances?start_epoch=0&end_epoch=10`.
+///
+/// Both epochs are optional, defaulting to genesis and the epoch of the head, respectively.
+pub fn get_validator_balances<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let pubkey = parse_pubkey_bytes(
+        req.uri()
+            .path()
+            .trim_start_matches(VALIDATORS_PATH_PREFIX)
+            .trim_end_matches(BALANCES_PATH_SUFFIX),
+    )?;
+
+    let mut start_epoch = Epoch::new(0);
+    let mut end_epoch = beacon_chain
+        .head_info()
+        .slot
+        .epoch(T::EthSpec::slots_per_epoch());
+
+    if let Some(query) = req.uri().query() {
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "start_epoch" => start_epoch = parse_epoch(&value)?,
+                "end_epoch" => end_epoch = parse_epoch(&value)?,
+                _ => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Unknown query parameter: {}",
+                        key
+                    )))
+                }
+            }
+        }
+    }
+
+    let records = beacon_chain
+        .balance_history(&pubkey, start_epoch, end_epoch)
+        .map_err(|e| ApiError::ServerError(format!("Unable to read balance history: {:?}", e)))?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "The balances of {:?} are not recorded, see --balance-history",
+                pubkey
+            ))
+        })?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&records)
+}
+
+/// HTTP handler to return an estimate of the progress of the validator whose public key is in the
+/// path through the activation and exit queues, e.g. `/lighthouse/validators/0x..../queue`.
+pub fn get_validator_queue<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let pubkey = parse_pubkey_bytes(
+        req.uri()
+            .path()
+            .trim_start_matches(VALIDATORS_PATH_PREFIX)
+            .trim_end_matches(QUEUE_PATH_SUFFIX),
+    )?;
+
+    let validator_index = beacon_chain
+        .validator_index(&pubkey)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown validator: {:?}", pubkey)))?;
+
+    let estimate = beacon_chain
+        .validator_queue_estimate(validator_index)
+        .map_err(|e| ApiError::ServerError(format!("Unable to estimate queue: {:?}", e)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&estimate)
+}
+
+/// HTTP handler to return the validators which have been added, or whose record or status has
+/// changed, since the start of an epoch, e.g. `/lighthouse/validators/diff?since_epoch=10`.
+///
+/// The `epoch` of the response may be supplied as the `since_epoch` of the next request.
+pub fn get_validators_diff<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let since_epoch = parse_epoch(&UrlQuery::from_request(&req)?.only_one("since_epoch")?)?;
+
+    let diff = beacon_chain
+        .validators_diff(since_epoch)
+        .map_err(|e| ApiError::ServerError(format!("Unable to diff validators: {:?}", e)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&diff)
+}
+
+/// HTTP handler to return the deposits which have been observed in the deposit contract but not
+/// yet included in the head state, with the epoch in which each is expected to be included.
+pub fn get_pending_deposits<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let pending_deposits = beacon_chain.pending_deposits().ok_or_else(|| {
+        ApiError::NotFound("The node is not configured with an eth1 backend".to_string())
+    })?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&pending_deposits)
+}
+
+/// HTTP handler to return the nodes of the block tree used by fork choice, with their weights,
+/// justified and finalized epochs and best descendants.
+pub fn get_fork_choice<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let nodes = beacon_chain
+        .fork_choice_nodes()
+        .map_err(|e| ApiError::ServerError(format!("Unable to read fork choice: {:?}", e)))?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&nodes)
+}
+
+/// The response to `/lighthouse/health`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// The latest assessment of the health of the chain, if it has been assessed.
+    pub chain_health: Option<ChainHealth>,
+    /// The estimated memory usage of each in-memory cache of the beacon chain.
+    pub cache_memory: CacheMemoryReport,
+}
+
+/// HTTP handler to return the health of the chain and the estimated memory usage of its caches.
+pub fn get_health<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&HealthResponse {
+        chain_health: beacon_chain.chain_health(),
+        cache_memory: beacon_chain.cache_memory_report(),
+    })
+}
+
+/// A service started by the beacon node, as it appears in a `StartupReport`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceReport {
+    pub name: String,
+    /// The configured address of the service, e.g. `127.0.0.1:5052`.
+    ///
+    /// The port may differ from that on which the service actually listens if it was configured
+    /// as `0`.
+    pub listen_address: String,
+}
+
+/// The effective configuration of a beacon node, as logged at startup and returned by
+/// `/lighthouse/config`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartupReport {
+    pub version: String,
+    /// The name of the spec constants, e.g. `mainnet` or `minimal`.
+    pub spec_constants: String,
+    /// The directory from which the testnet configuration was loaded, if any.
+    pub testnet_dir: Option<String>,
+    /// The method by which the genesis state was obtained, e.g. `deposit_contract`.
+    pub genesis_method: String,
+    pub data_dir: String,
+    pub services: Vec<ServiceReport>,
+    /// The eth1 backend used for block production: `caching`, `dummy` or `none`.
+    pub eth1_backend: String,
+    /// The scheme, host and port of the eth1 endpoint, without any credentials, path or query.
+    pub eth1_endpoint: Option<String>,
+    pub deposit_contract_address: String,
+    pub deposit_contract_deploy_block: u64,
+    /// The base64 encoding of the local ENR, if the network service has started.
+    pub enr: Option<String>,
+    /// The base64 encodings of the ENRs of the boot nodes.
+    pub boot_nodes: Vec<String>,
+}
+
+/// HTTP handler to return the effective configuration of the node, as logged at startup.
+pub fn get_config(req: Request<Body>, startup_report: Arc<StartupReport>) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(startup_report.as_ref())
+}
+
+/// HTTP handler to return the chain spec, genesis state, deposit contract and ENRs of the testnet
+/// followed by this node, as a JSON `TestnetBundle`.
+///
+/// The ENRs are those of this node and its boot nodes. A new node may join the testnet by pointing
+/// `--testnet-url` at any existing node.
+pub fn get_testnet<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network: Arc<NetworkService<T>>,
+    eth2_config: Arc<Eth2Config>,
+    startup_report: Arc<StartupReport>,
+) -> ApiResult {
+    let (_root, genesis_state) = state_at_slot(&beacon_chain, beacon_chain.spec.genesis_slot)?;
+
+    let mut boot_enr = vec![network.local_enr()];
+    for boot_node in &startup_report.boot_nodes {
+        let enr = parse_enr(boot_node).map_err(ApiError::ServerError)?;
+        if !boot_enr.contains(&enr) {
+            boot_enr.push(enr);
+        }
+    }
+
+    let testnet: Eth2TestnetConfig<T::EthSpec> = Eth2TestnetConfig {
+        deposit_contract_address: startup_report.deposit_contract_address.clone(),
+        deposit_contract_deploy_block: startup_report.deposit_contract_deploy_block,
+        boot_enr: Some(boot_enr),
+        genesis_state: Some(genesis_state),
+        yaml_config: Some(YamlConfig::from_spec::<T::EthSpec>(&beacon_chain.spec)),
+        deposit_chain_id: eth2_config.eth1_chain_id,
+        deposit_network_id: eth2_config.eth1_network_id,
+    };
+
+    let bundle = TestnetBundle::from_testnet_config(&testnet).map_err(ApiError::ServerError)?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&bundle)
+}
+
+/// HTTP handler to return the block with the given `block_root` (or the head block, if no root is
+/// supplied) and its post-state, as a snappy-compressed SSZ `CheckPoint`.
+///
+/// The response may be saved to a file and imported into another node with `post_checkpoint`.
+pub fn get_checkpoint<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let block_root = match UrlQuery::from_request(&req) {
+        Ok(query) => Some(parse_root(&query.only_one("block_root")?)?),
+        Err(_) => None,
+    };
+
+    let checkpoint = beacon_chain
+        .export_checkpoint(block_root)
+        .map_err(|e| ApiError::ServerError(format!("Unable to read checkpoint: {:?}", e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown block: {:?}", block_root)))?;
+
+    let bytes = checkpoint
+        .as_snappy_ssz_bytes()
+        .map_err(ApiError::ServerError)?;
+
+    ResponseBuilder::new(&req)?.body_bytes(bytes)
+}
+
+/// HTTP handler to store the block and state of a snappy-compressed SSZ `CheckPoint` (as returned
+/// by `get_checkpoint`) in the database, returning the root of the block.
+pub fn post_checkpoint<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> BoxFut {
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                CheckPoint::from_snappy_ssz_bytes(&chunks).map_err(ApiError::BadRequest)
+            })
+            .and_then(move |checkpoint: CheckPoint<T::EthSpec>| {
+                let block_root = checkpoint.beacon_block_root;
+
+                beacon_chain.import_checkpoint(checkpoint).map_err(|e| {
+                    ApiError::BadRequest(format!("Unable to import checkpoint: {:?}", e))
+                })?;
+
+                response_builder?.body(&block_root)
+            }),
+    )
+}
+
+/// HTTP handler to return the contents of the operation pool (the attestations for each
+/// attestation data, the attester and proposer slashings and the voluntary exits), as JSON or (if
+/// requested with the `Accept` header) SSZ.
+pub fn get_op_pool<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body(&beacon_chain.export_op_pool())
+}
+
+/// HTTP handler to replace the contents of the operation pool with a dump returned by
+/// `get_op_pool`, encoded as JSON or (if specified by the `Content-Type` header) SSZ.
+///
+/// The operations are not verified, so this should only be used to reproduce the blocks produced
+/// by another node.
+pub fn post_op_pool<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> BoxFut {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map_or(Ok(""), |h| h.to_str())
+        .map_err(|e| {
+            ApiError::BadRequest(format!(
+                "The Content-Type header contains invalid characters: {:?}",
+                e
+            ))
+        });
+    let encoding = ApiEncodingFormat::from(try_future!(content_type));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(move |chunks| match encoding {
+                ApiEncodingFormat::SSZ => {
+                    PersistedOperationPool::from_ssz_bytes(&chunks).map_err(|e| {
+                        ApiError::BadRequest(format!(
+                            "Unable to parse SSZ into an operation pool: {:?}",
+                            e
+                        ))
+                    })
+                }
+                _ => serde_json::from_slice(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to parse JSON into an operation pool: {:?}",
+                        e
+                    ))
+                }),
+            })
+            .and_then(move |op_pool: PersistedOperationPool<T::EthSpec>| {
+                beacon_chain.import_op_pool(op_pool);
+
+                response_builder?.body_no_ssz(&())
+            }),
+    )
+}
+
+/// HTTP handler to return the schema version, anchor and layout of the database, describing which
+/// blocks and states the node is able to serve.
+pub fn get_database_info<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let database_info = beacon_chain.store.get_database_info()?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&database_info)
+}
+
+/// HTTP handler to store a JSON list of blocks older than the oldest block in the database,
+/// returning the updated anchor.
+///
+/// The blocks must form a chain ending at the parent of the oldest block in the database (see
+/// `BeaconChain::import_historical_blocks`).
+pub fn post_historical_blocks<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                serde_json::from_slice(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!("Unable to parse JSON into blocks: {:?}", e))
+                })
+            })
+            .and_then(move |blocks: Vec<BeaconBlock<T::EthSpec>>| {
+                let anchor_info = beacon_chain.import_historical_blocks(blocks).map_err(|e| {
+                    let message = format!("Unable to import historical blocks: {:?}", e);
+                    match e {
+                        BeaconChainError::HistoricalBlockError(_) => ApiError::BadRequest(message),
+                        _ => ApiError::ServerError(message),
+                    }
+                })?;
+
+                response_builder?.body_no_ssz(&anchor_info)
+            }),
+    )
+}
+
+/// The participation of the active validators in the attestations of an epoch, as a fraction of
+/// the active balance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochParticipation {
+    pub epoch: Epoch,
+    pub active_balance: u64,
+    pub source_rate: f64,
+    pub target_rate: f64,
+    pub head_rate: f64,
+}
+
+/// The performance of an active validator in the attestations of an epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorAttestationPerformance {
+    pub epoch: Epoch,
+    pub validator_index: u64,
+    pub is_slashed: bool,
+    pub is_source_attester: bool,
+    pub is_target_attester: bool,
+    pub is_head_attester: bool,
+    pub inclusion_delay: Option<u64>,
+}
+
+/// A row of a participation export which may be written as CSV.
+trait CsvRow {
+    /// The comma-separated names of the columns.
+    const HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+impl CsvRow for EpochParticipation {
+    const HEADER: &'static str = "epoch,active_balance,source_rate,target_rate,head_rate";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.epoch, self.active_balance, self.source_rate, self.target_rate, self.head_rate
+        )
+    }
+}
+
+impl CsvRow for ValidatorAttestationPerformance {
+    const HEADER: &'static str = "epoch,validator_index,is_slashed,is_source_attester,\
+                                  is_target_attester,is_head_attester,inclusion_delay";
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.epoch,
+            self.validator_index,
+            self.is_slashed,
+            self.is_source_attester,
+            self.is_target_attester,
+            self.is_head_attester,
+            self.inclusion_delay
+                .map_or_else(String::new, |delay| delay.to_string())
+        )
+    }
+}
+
+/// The query of a participation export request.
+struct ParticipationQuery {
+    start_epoch: Epoch,
+    end_epoch: Epoch,
+    validator_indices: Option<Vec<u64>>,
+    csv: bool,
+}
+
+impl ParticipationQuery {
+    /// Parses the `start_slot`, `end_slot`, `validator_index` and `format` parameters of `req`.
+    ///
+    /// The slots default to genesis and the slot of the head, respectively, and are rounded to the
+    /// epochs which contain them.
+    fn from_request<T: BeaconChainTypes>(
+        req: &Request<Body>,
+        beacon_chain: &BeaconChain<T>,
+    ) -> Result<Self, ApiError> {
+        let mut start_slot = Slot::new(0);
+        let mut end_slot = beacon_chain.head_info().slot;
+        let mut validator_indices: Option<Vec<u64>> = None;
+        let mut csv = false;
+
+        if let Some(query) = req.uri().query() {
+            for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+                match key.as_ref() {
+                    "start_slot" => start_slot = parse_slot(&value)?,
+                    "end_slot" => end_slot = parse_slot(&value)?,
+                    "validator_index" => validator_indices.get_or_insert_with(Vec::new).push(
+                        value.parse::<u64>().map_err(|e| {
+                            ApiError::BadRequest(format!(
+                                "Unable to parse validator index: {:?}",
+                                e
+                            ))
+                        })?,
+                    ),
+                    "format" => {
+                        csv = match value.as_ref() {
+                            "csv" => true,
+                            "json" => false,
+                            _ => {
+                                return Err(ApiError::BadRequest(format!(
+                                    "Unknown format: {}, expected csv or json",
+                                    value
+                                )))
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(ApiError::BadRequest(format!(
+                            "Unknown query parameter: {}",
+                            key
+                        )))
+                    }
+                }
+            }
+        }
+
+        let start_epoch = start_slot.epoch(T::EthSpec::slots_per_epoch());
+        let end_epoch = end_slot.epoch(T::EthSpec::slots_per_epoch());
+
+        if end_epoch < start_epoch {
+            return Err(ApiError::BadRequest(format!(
+                "The end slot {} is prior to the start slot {}",
+                end_slot, start_slot
+            )));
+        }
+
+        if end_epoch - start_epoch >= MAX_PARTICIPATION_EXPORT_EPOCHS {
+            return Err(ApiError::BadRequest(format!(
+                "Unable to export more than {} epochs in a single request",
+                MAX_PARTICIPATION_EXPORT_EPOCHS
+            )));
+        }
+
+        Ok(Self {
+            start_epoch,
+            end_epoch,
+            validator_indices,
+            csv,
+        })
+    }
+
+    /// Replays the requested epochs of the canonical chain to summarise their participation.
+    fn replay<T: BeaconChainTypes>(
+        &self,
+        beacon_chain: &BeaconChain<T>,
+    ) -> Result<Vec<EpochProcessingSummary>, ApiError> {
+        beacon_chain
+            .replay_epoch_summaries(self.start_epoch, self.end_epoch)
+            .map_err(|e| ApiError::ServerError(format!("Unable to replay blocks: {:?}", e)))
+    }
+
+    /// Returns `rows` as CSV if it was requested, otherwise in the encoding of the `Accept`
+    /// header.
+    fn respond<R: CsvRow + Serialize>(&self, req: &Request<Body>, rows: &[R]) -> ApiResult {
+        if self.csv {
+            let mut text = String::from(R::HEADER);
+            text.push('\n');
+            for row in rows {
+                text.push_str(&row.csv_row());
+                text.push('\n');
+            }
+
+            ResponseBuilder::new(req)?.body_csv(text)
+        } else {
+            ResponseBuilder::new(req)?.body_no_ssz(&rows)
+        }
+    }
+}
+
+/// HTTP handler to return the participation rates of each epoch of the canonical chain over a
+/// range of slots, computed by replaying blocks from the database, e.g.
+/// `/lighthouse/participation/epochs?start_slot=0&end_slot=320&format=csv`.
+///
+/// The format may be either `json` (the default) or `csv`.
+pub fn get_epoch_participation<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = ParticipationQuery::from_request(&req, &beacon_chain)?;
+
+    let rows = query
+        .replay(&beacon_chain)?
+        .iter()
+        .map(|summary| EpochParticipation {
+            epoch: summary.epoch,
+            active_balance: summary.total_balances.previous_epoch,
+            source_rate: summary.source_participation_rate(),
+            target_rate: summary.target_participation_rate(),
+            head_rate: summary.head_participation_rate(),
+        })
+        .collect::<Vec<_>>();
+
+    query.respond(&req, &rows)
+}
+
+/// HTTP handler to return the attestation performance of each active validator in each epoch of
+/// the canonical chain over a range of slots, computed by replaying blocks from the database,
+/// e.g. `/lighthouse/participation/validators?start_slot=0&end_slot=320&validator_index=3`.
+///
+/// The `validator_index` parameter may be repeated, and defaults to all validators. The format
+/// may be either `json` (the default) or `csv`.
+pub fn get_validator_participation<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = ParticipationQuery::from_request(&req, &beacon_chain)?;
+
+    let rows = query
+        .replay(&beacon_chain)?
+        .iter()
+        .flat_map(|summary| {
+            summary
+                .validators
+                .iter()
+                .enumerate()
+                .filter(|(_, participation)| participation.is_active)
+                .map(
+                    move |(validator_index, participation)| ValidatorAttestationPerformance {
+                        epoch: summary.epoch,
+                        validator_index: validator_index as u64,
+                        is_slashed: participation.is_slashed,
+                        is_source_attester: participation.is_source_attester,
+                        is_target_attester: participation.is_target_attester,
+                        is_head_attester: participation.is_head_attester,
+                        inclusion_delay: participation.inclusion_delay,
+                    },
+                )
+        })
+        .filter(|row| {
+            query
+                .validator_indices
+                .as_ref()
+                .map_or(true, |indices| indices.contains(&row.validator_index))
+        })
+        .collect::<Vec<_>>();
+
+    query.respond(&req, &rows)
+}
@@ -21,6 +21,14 @@ lazy_static! {
         "http_server_success_total",
         "Total count of HTTP 200 responses sent"
     );
+    pub static ref DUTIES_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "http_server_duties_cache_hits_total",
+        "Count of duties requests served from the duties cache"
+    );
+    pub static ref DUTIES_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "http_server_duties_cache_misses_total",
+        "Count of duties requests which missed the duties cache"
+    );
 }
 
 /// Returns the full set of Prometheus metrics for the Beacon Node application.
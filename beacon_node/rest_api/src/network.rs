@@ -1,10 +1,23 @@
-use crate::error::ApiResult;
+use crate::error::{ApiError, ApiResult};
 use crate::response_builder::ResponseBuilder;
-use crate::NetworkService;
+use crate::{BoxFut, NetworkService, UrlQuery};
 use beacon_chain::BeaconChainTypes;
-use eth2_libp2p::{Multiaddr, PeerId};
+use eth2_libp2p::{BanTarget, Multiaddr, PeerId};
+use futures::{Future, Stream};
 use hyper::{Body, Request};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// A request to ban a peer id or IP address.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct BanRequest {
+    /// A base58-encoded peer id or an IP address.
+    pub target: BanTarget,
+    /// The duration of the ban in seconds. The ban is permanent if it is `None`.
+    pub duration_secs: Option<u64>,
+}
 
 /// HTTP handler to return the list of libp2p multiaddr the client is listening on.
 ///
@@ -55,6 +68,14 @@ pub fn get_peer_count<T: BeaconChainTypes>(
     ResponseBuilder::new(&req)?.body(&network.connected_peers())
 }
 
+/// HTTP handler to return the number of peers connected in each direction and the peer limits.
+pub fn get_peer_counts<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network: Arc<NetworkService<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&network.peer_counts())
+}
+
 /// HTTP handler to return the list of peers connected to the client's libp2p service.
 ///
 /// Peers are presented as a list of `PeerId::to_string()`.
@@ -69,3 +90,56 @@ pub fn get_peer_list<T: BeaconChainTypes>(
         .collect();
     ResponseBuilder::new(&req)?.body_no_ssz(&connected_peers)
 }
+
+/// HTTP handler to return the peer ids and IP addresses which are banned, including peers banned
+/// for misbehaving.
+pub fn get_bans<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network: Arc<NetworkService<T>>,
+) -> ApiResult {
+    ResponseBuilder::new(&req)?.body_no_ssz(&network.bans())
+}
+
+/// HTTP handler to ban a peer id or IP address, as described by a `BanRequest`, disconnecting
+/// the connected peers which it applies to. Returns the `Ban`.
+pub fn post_ban<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network: Arc<NetworkService<T>>,
+) -> BoxFut {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let future = req
+        .into_body()
+        .concat2()
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+        .and_then(|chunks| {
+            serde_json::from_slice::<BanRequest>(&chunks).map_err(|e| {
+                ApiError::BadRequest(format!("Unable to parse JSON into BanRequest: {:?}", e))
+            })
+        })
+        .and_then(move |request| {
+            let ban = network.ban(
+                request.target,
+                request.duration_secs.map(Duration::from_secs),
+            );
+            response_builder?.body_no_ssz(&ban)
+        });
+
+    Box::new(future)
+}
+
+/// HTTP handler to lift the ban of the peer id or IP address given by the `target` query
+/// parameter. Returns a `404` if it is not banned.
+pub fn delete_ban<T: BeaconChainTypes>(
+    req: Request<Body>,
+    network: Arc<NetworkService<T>>,
+) -> ApiResult {
+    let target = UrlQuery::from_request(&req)?.only_one("target")?;
+    let target = BanTarget::from_str(&target).map_err(ApiError::BadRequest)?;
+
+    if network.unban(&target) {
+        ResponseBuilder::new(&req)?.body_no_ssz(&target)
+    } else {
+        Err(ApiError::NotFound(format!("{} is not banned", target)))
+    }
+}
@@ -1,9 +1,49 @@
 use crate::response_builder::ResponseBuilder;
 use crate::ApiResult;
-use hyper::{Body, Request};
+use beacon_chain::{BeaconChain, BeaconChainTypes, ChainHealth};
+use hyper::{Body, Request, StatusCode};
+use std::sync::Arc;
+use store::Store;
 use version;
 
 /// Read the version string from the current Lighthouse build.
 pub fn get_version(req: Request<Body>) -> ApiResult {
     ResponseBuilder::new(&req)?.body_no_ssz(&version::version())
 }
+
+/// Returns the `AnchorInfo` of the database, or `null` if it holds the full history of the chain.
+pub fn get_anchor<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let anchor_info = beacon_chain.store.get_anchor_info()?;
+
+    ResponseBuilder::new(&req)?.body_no_ssz(&anchor_info)
+}
+
+/// Returns the latest `ChainHealth` of the node, or `null` if it has not yet been assessed.
+///
+/// The status code of the response is:
+///
+/// - `200` if the node is synced.
+/// - `206` if the node is syncing, or is synced but its eth1 node is not.
+/// - `503` if the node is stalled or has not yet been assessed.
+pub fn get_health<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let chain_health = beacon_chain.chain_health();
+
+    let status = match chain_health {
+        Some(ChainHealth::Synced) => StatusCode::OK,
+        Some(ChainHealth::Eth1Optimistic) | Some(ChainHealth::Syncing { .. }) => {
+            StatusCode::PARTIAL_CONTENT
+        }
+        Some(ChainHealth::Stalled { .. }) | None => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    let mut response = ResponseBuilder::new(&req)?.body_no_ssz(&chain_health)?;
+    *response.status_mut() = status;
+
+    Ok(response)
+}
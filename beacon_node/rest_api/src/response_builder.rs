@@ -73,6 +73,14 @@ impl ResponseBuilder {
             .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
     }
 
+    pub fn body_bytes(self, bytes: Vec<u8>) -> ApiResult {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/octet-stream")
+            .body(Body::from(bytes))
+            .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+    }
+
     pub fn body_text(self, text: String) -> ApiResult {
         Response::builder()
             .status(StatusCode::OK)
@@ -80,4 +88,12 @@ impl ResponseBuilder {
             .body(Body::from(text))
             .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
     }
+
+    pub fn body_csv(self, text: String) -> ApiResult {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "text/csv; charset=utf-8")
+            .body(Body::from(text))
+            .map_err(|e| ApiError::ServerError(format!("Failed to build response: {:?}", e)))
+    }
 }
@@ -1,16 +1,46 @@
+use crate::duties_cache::DutiesCache;
 use crate::{
-    beacon, error::ApiError, helpers, metrics, network, node, spec, validator, BoxFut,
-    NetworkChannel,
+    beacon, error::ApiError, helpers, lighthouse, metrics, network, node, spec, validator, BoxFut,
+    NetworkChannel, StartupReport,
 };
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{take_state_clones, with_request_id, BeaconChain, BeaconChainTypes, RequestId};
 use client_network::Service as NetworkService;
 use eth2_config::Eth2Config;
-use futures::{Future, IntoFuture};
+use futures::{Future, IntoFuture, Poll};
+use hyper::header::HeaderValue;
 use hyper::{Body, Error, Method, Request, Response};
-use slog::debug;
+use lighthouse_error::CodedError;
+use slog::{debug, o, warn};
 use std::path::PathBuf;
 use std::sync::Arc;
 
+/// The number of clones of the head state which a single request may make before a warning is
+/// logged (in debug builds only).
+///
+/// Handlers which only read a few fields of the head state should borrow it with
+/// `BeaconChain::with_head_state` instead.
+pub const MAX_STATE_CLONES_PER_REQUEST: usize = 1;
+
+/// The response header which carries the ID of the request, as it appears in the logs.
+pub const REQUEST_ID_HEADER: &str = "lighthouse-request-id";
+
+/// A future which is polled as part of a request, so that the lines logged by the `BeaconChain`
+/// whilst it is polled include the request ID.
+struct InRequest<F> {
+    request_id: RequestId,
+    future: F,
+}
+
+impl<F: Future> Future for InRequest<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        let future = &mut self.future;
+        with_request_id(self.request_id, || future.poll())
+    }
+}
+
 fn into_boxfut<F: IntoFuture + 'static>(item: F) -> BoxFut
 where
     F: IntoFuture<Item = Response<Body>, Error = ApiError>,
@@ -25,6 +55,8 @@ pub fn route<T: BeaconChainTypes>(
     network_service: Arc<NetworkService<T>>,
     network_channel: NetworkChannel,
     eth2_config: Arc<Eth2Config>,
+    startup_report: Arc<StartupReport>,
+    duties_cache: Arc<DutiesCache<T::EthSpec>>,
     local_log: slog::Logger,
     db_path: PathBuf,
     freezer_db_path: PathBuf,
@@ -34,14 +66,23 @@ pub fn route<T: BeaconChainTypes>(
 
     let path = req.uri().path().to_string();
 
+    let request_id = RequestId::next();
+    let local_log = local_log.new(o!("request_id" => request_id));
     let log = local_log.clone();
+
+    // Discard any clones made by previous requests on this thread.
+    take_state_clones();
     let request_result: Box<dyn Future<Item = Response<_>, Error = _> + Send> =
-        match (req.method(), path.as_ref()) {
+        with_request_id(request_id, || match (req.method(), path.as_ref()) {
             // Methods for Client
             (&Method::GET, "/node/version") => into_boxfut(node::get_version(req)),
             (&Method::GET, "/node/syncing") => {
                 into_boxfut(helpers::implementation_pending_response(req))
             }
+            (&Method::GET, "/node/anchor") => into_boxfut(node::get_anchor::<T>(req, beacon_chain)),
+            (&Method::GET, "/eth/v1/node/health") => {
+                into_boxfut(node::get_health::<T>(req, beacon_chain))
+            }
 
             // Methods for Network
             (&Method::GET, "/network/enr") => {
@@ -56,12 +97,22 @@ pub fn route<T: BeaconChainTypes>(
             (&Method::GET, "/network/peers") => {
                 into_boxfut(network::get_peer_list::<T>(req, network_service))
             }
+            (&Method::GET, "/network/peer_counts") => {
+                into_boxfut(network::get_peer_counts::<T>(req, network_service))
+            }
             (&Method::GET, "/network/listen_port") => {
                 into_boxfut(network::get_listen_port::<T>(req, network_service))
             }
             (&Method::GET, "/network/listen_addresses") => {
                 into_boxfut(network::get_listen_addresses::<T>(req, network_service))
             }
+            (&Method::GET, "/network/bans") => {
+                into_boxfut(network::get_bans::<T>(req, network_service))
+            }
+            (&Method::POST, "/network/bans") => network::post_ban::<T>(req, network_service),
+            (&Method::DELETE, "/network/bans") => {
+                into_boxfut(network::delete_ban::<T>(req, network_service))
+            }
 
             // Methods for Beacon Node
             (&Method::GET, "/beacon/head") => into_boxfut(beacon::get_head::<T>(req, beacon_chain)),
@@ -91,6 +142,15 @@ pub fn route<T: BeaconChainTypes>(
             (&Method::GET, "/beacon/validators") => {
                 into_boxfut(beacon::get_validators::<T>(req, beacon_chain))
             }
+            (&Method::POST, "/beacon/validators") => {
+                beacon::post_validators::<T>(req, beacon_chain)
+            }
+            (&Method::POST, "/beacon/proposer_slashing") => {
+                beacon::post_proposer_slashing::<T>(req, beacon_chain, network_channel, log)
+            }
+            (&Method::POST, "/beacon/attester_slashing") => {
+                beacon::post_attester_slashing::<T>(req, beacon_chain, network_channel, log)
+            }
             (&Method::GET, "/beacon/validators/indicies") => {
                 into_boxfut(helpers::implementation_pending_response(req))
             }
@@ -99,14 +159,17 @@ pub fn route<T: BeaconChainTypes>(
             }
 
             // Methods for Validator
-            (&Method::GET, "/validator/duties") => {
-                into_boxfut(validator::get_validator_duties::<T>(req, beacon_chain))
-            }
+            (&Method::GET, "/validator/duties") => into_boxfut(
+                validator::get_validator_duties::<T>(req, beacon_chain, duties_cache),
+            ),
             (&Method::POST, "/validator/duties") => {
-                validator::post_validator_duties::<T>(req, beacon_chain)
+                validator::post_validator_duties::<T>(req, beacon_chain, duties_cache)
+            }
+            (&Method::POST, "/validator/liveness") => {
+                validator::post_validator_liveness::<T>(req, beacon_chain)
             }
             (&Method::GET, "/validator/block") => {
-                into_boxfut(validator::get_new_beacon_block::<T>(req, beacon_chain, log))
+                validator::get_new_beacon_block::<T>(req, beacon_chain, log)
             }
             (&Method::POST, "/validator/block") => {
                 validator::publish_beacon_block::<T>(req, beacon_chain, network_channel, log)
@@ -117,6 +180,26 @@ pub fn route<T: BeaconChainTypes>(
             (&Method::POST, "/validator/attestation") => {
                 validator::publish_attestation::<T>(req, beacon_chain, network_channel, log)
             }
+            (&Method::GET, "/validator/attestations") => {
+                into_boxfut(validator::get_new_attestations::<T>(req, beacon_chain))
+            }
+            (&Method::POST, "/validator/attestations") => {
+                validator::publish_attestations::<T>(req, beacon_chain, network_channel, log)
+            }
+            (&Method::POST, "/validator/voluntary_exit") => {
+                validator::publish_voluntary_exit::<T>(req, beacon_chain, network_channel, log)
+            }
+            (&Method::GET, "/validator/aggregate_attestation") => {
+                into_boxfut(validator::get_aggregate_attestation::<T>(req, beacon_chain))
+            }
+            (&Method::POST, "/validator/aggregate_and_proofs") => {
+                validator::publish_aggregate_and_proofs::<T>(
+                    req,
+                    beacon_chain,
+                    network_channel,
+                    log,
+                )
+            }
 
             (&Method::GET, "/beacon/state") => {
                 into_boxfut(beacon::get_state::<T>(req, beacon_chain))
@@ -127,10 +210,19 @@ pub fn route<T: BeaconChainTypes>(
             (&Method::GET, "/beacon/state/current_finalized_checkpoint") => into_boxfut(
                 beacon::get_current_finalized_checkpoint::<T>(req, beacon_chain),
             ),
+            (&Method::GET, "/beacon/state/proof") => {
+                into_boxfut(beacon::get_state_proof::<T>(req, beacon_chain))
+            }
             (&Method::GET, "/beacon/state/genesis") => {
                 into_boxfut(beacon::get_genesis_state::<T>(req, beacon_chain))
             }
             //TODO: Add aggreggate/filtered state lookups here, e.g. /beacon/validators/balances
+            (&Method::GET, path)
+                if path.starts_with(beacon::STATES_PATH_PREFIX)
+                    && path.ends_with(beacon::COMMITTEES_PATH_SUFFIX) =>
+            {
+                into_boxfut(beacon::get_state_committees::<T>(req, beacon_chain))
+            }
 
             // Methods for bootstrap and checking configuration
             (&Method::GET, "/spec") => into_boxfut(spec::get_spec::<T>(req, beacon_chain)),
@@ -151,29 +243,115 @@ pub fn route<T: BeaconChainTypes>(
                 freezer_db_path,
             )),
 
+            // Lighthouse-specific methods
+            (&Method::GET, "/lighthouse/config") => {
+                into_boxfut(lighthouse::get_config(req, startup_report))
+            }
+            (&Method::GET, "/lighthouse/testnet") => into_boxfut(lighthouse::get_testnet::<T>(
+                req,
+                beacon_chain,
+                network_service,
+                eth2_config,
+                startup_report,
+            )),
+            (&Method::GET, "/lighthouse/fork_choice") => {
+                into_boxfut(lighthouse::get_fork_choice::<T>(req, beacon_chain))
+            }
+            (&Method::GET, "/lighthouse/health") => {
+                into_boxfut(lighthouse::get_health::<T>(req, beacon_chain))
+            }
+            (&Method::GET, "/lighthouse/checkpoint") => {
+                into_boxfut(lighthouse::get_checkpoint::<T>(req, beacon_chain))
+            }
+            (&Method::POST, "/lighthouse/checkpoint") => {
+                lighthouse::post_checkpoint::<T>(req, beacon_chain)
+            }
+            (&Method::GET, "/lighthouse/op_pool") => {
+                into_boxfut(lighthouse::get_op_pool::<T>(req, beacon_chain))
+            }
+            (&Method::POST, "/lighthouse/op_pool") => {
+                lighthouse::post_op_pool::<T>(req, beacon_chain)
+            }
+            (&Method::GET, "/lighthouse/database/info") => {
+                into_boxfut(lighthouse::get_database_info::<T>(req, beacon_chain))
+            }
+            (&Method::POST, "/lighthouse/database/historical_blocks") => {
+                lighthouse::post_historical_blocks::<T>(req, beacon_chain)
+            }
+            (&Method::GET, "/lighthouse/validators/diff") => {
+                into_boxfut(lighthouse::get_validators_diff::<T>(req, beacon_chain))
+            }
+            (&Method::GET, "/lighthouse/eth1/pending_deposits") => {
+                into_boxfut(lighthouse::get_pending_deposits::<T>(req, beacon_chain))
+            }
+            (&Method::GET, "/lighthouse/participation/epochs") => {
+                into_boxfut(lighthouse::get_epoch_participation::<T>(req, beacon_chain))
+            }
+            (&Method::GET, "/lighthouse/participation/validators") => into_boxfut(
+                lighthouse::get_validator_participation::<T>(req, beacon_chain),
+            ),
+            (&Method::GET, path)
+                if path.starts_with(lighthouse::VALIDATORS_PATH_PREFIX)
+                    && path.ends_with(lighthouse::BALANCES_PATH_SUFFIX) =>
+            {
+                into_boxfut(lighthouse::get_validator_balances::<T>(req, beacon_chain))
+            }
+            (&Method::GET, path)
+                if path.starts_with(lighthouse::VALIDATORS_PATH_PREFIX)
+                    && path.ends_with(lighthouse::QUEUE_PATH_SUFFIX) =>
+            {
+                into_boxfut(lighthouse::get_validator_queue::<T>(req, beacon_chain))
+            }
+
             _ => Box::new(futures::future::err(ApiError::NotFound(
                 "Request path and/or method not found.".to_owned(),
             ))),
-        };
+        });
+
+    // Only the clones made whilst the handler is called are counted, not those made by a future
+    // that it returns.
+    let state_clones = take_state_clones();
+    if cfg!(debug_assertions) && state_clones > MAX_STATE_CLONES_PER_REQUEST {
+        warn!(
+            local_log,
+            "HTTP API request cloned the head state";
+            "path" => &path,
+            "clones" => state_clones,
+        );
+    }
 
     // Map the Rust-friendly `Result` in to a http-friendly response. In effect, this ensures that
     // any `Err` returned from our response handlers becomes a valid http response to the client
     // (e.g., a response with a 404 or 500 status).
-    request_result.then(move |result| match result {
-        Ok(response) => {
-            debug!(local_log, "HTTP API request successful"; "path" => path);
-            metrics::inc_counter(&metrics::SUCCESS_COUNT);
-            metrics::stop_timer(timer);
-
-            Ok(response)
-        }
-        Err(e) => {
-            let error_response = e.into();
-
-            debug!(local_log, "HTTP API request failure"; "path" => path);
-            metrics::stop_timer(timer);
-
-            Ok(error_response)
-        }
+    let request_result = InRequest {
+        request_id,
+        future: request_result,
+    };
+    request_result.then(move |result| {
+        let mut response = match result {
+            Ok(response) => {
+                debug!(local_log, "HTTP API request successful"; "path" => path);
+                metrics::inc_counter(&metrics::SUCCESS_COUNT);
+                metrics::stop_timer(timer);
+
+                response
+            }
+            Err(e) => {
+                let code = e.error_code();
+                let error_response: Response<Body> = e.into();
+
+                debug!(local_log, "HTTP API request failure"; "path" => path, "code" => code.to_string());
+                metrics::stop_timer(timer);
+
+                error_response
+            }
+        };
+
+        response.headers_mut().insert(
+            REQUEST_ID_HEADER,
+            HeaderValue::from(request_id.as_u64()),
+        );
+
+        Ok(response)
     })
 }
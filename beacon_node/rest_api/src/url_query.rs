@@ -1,4 +1,7 @@
-use crate::helpers::{parse_committee_index, parse_epoch, parse_signature, parse_slot};
+use crate::helpers::{
+    parse_committee_index, parse_epoch, parse_graffiti, parse_signature, parse_slot,
+};
+use crate::validator::BroadcastValidation;
 use crate::ApiError;
 use hyper::Request;
 use types::{CommitteeIndex, Epoch, Signature, Slot};
@@ -103,6 +106,24 @@ impl<'a> UrlQuery<'a> {
         self.first_of(&["randao_reveal"])
             .and_then(|(_key, value)| parse_signature(&value))
     }
+
+    /// Returns the value of the first occurrence of the `graffiti` key, or `None` if there is no
+    /// such key.
+    pub fn graffiti(self) -> Result<Option<[u8; 32]>, ApiError> {
+        self.first_of(&["graffiti"])
+            .ok()
+            .map(|(_key, value)| parse_graffiti(&value))
+            .transpose()
+    }
+
+    /// Returns the value of the first occurrence of the `broadcast_validation` key, or `None` if
+    /// there is no such key.
+    pub fn broadcast_validation(self) -> Result<Option<BroadcastValidation>, ApiError> {
+        self.first_of(&["broadcast_validation"])
+            .ok()
+            .map(|(_key, value)| value.parse().map_err(ApiError::BadRequest))
+            .transpose()
+    }
 }
 
 #[cfg(test)]
@@ -1,35 +1,99 @@
+use crate::duties_cache::{DutiesCache, EpochDuties};
 use crate::helpers::{
-    check_content_type_for_json, parse_pubkey_bytes, publish_attestation_to_network,
-    publish_beacon_block_to_network,
+    check_content_type_for_json, parse_pubkey_bytes, publish_aggregate_and_proof_to_network,
+    publish_attestation_to_network, publish_beacon_block_to_network,
+    publish_voluntary_exit_to_network,
 };
 use crate::response_builder::ResponseBuilder;
 use crate::{ApiError, ApiResult, BoxFut, NetworkChannel, UrlQuery};
 use beacon_chain::{
     AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BlockProcessingOutcome,
+    GossipBlockOutcome, LocalMessage, ProposalObservation,
 };
 use bls::PublicKeyBytes;
 use futures::future::Future;
 use futures::stream::Stream;
 use hyper::{Body, Request};
 use serde::{Deserialize, Serialize};
-use slog::{error, info, warn, Logger};
+use slog::{debug, error, info, warn, Logger};
 use ssz_derive::{Decode, Encode};
+use state_processing::common::get_attesting_indices;
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
 use types::beacon_state::EthSpec;
-use types::{Attestation, BeaconBlock, CommitteeIndex, Epoch, RelativeEpoch, Slot};
+use types::{
+    Attestation, BeaconBlock, CommitteeIndex, Epoch, Hash256, RelativeEpoch, SelectionProof,
+    SignedAggregateAndProof, Slot, VoluntaryExit,
+};
+
+/// The validation a block must pass before it is broadcast by `publish_beacon_block`, selected by
+/// the `broadcast_validation` query parameter.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BroadcastValidation {
+    /// Only the checks required to propagate the block on gossip. The block is broadcast before
+    /// it is imported.
+    Gossip,
+    /// The block must be fully imported before it is broadcast.
+    Consensus,
+    /// As `Consensus`, but the block is also refused if a different block from the same proposer
+    /// at the same slot has already been published.
+    ConsensusAndEquivocation,
+}
+
+impl Default for BroadcastValidation {
+    fn default() -> Self {
+        BroadcastValidation::ConsensusAndEquivocation
+    }
+}
+
+impl BroadcastValidation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastValidation::Gossip => "gossip",
+            BroadcastValidation::Consensus => "consensus",
+            BroadcastValidation::ConsensusAndEquivocation => "consensus_and_equivocation",
+        }
+    }
+}
+
+impl FromStr for BroadcastValidation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gossip" => Ok(BroadcastValidation::Gossip),
+            "consensus" => Ok(BroadcastValidation::Consensus),
+            "consensus_and_equivocation" => Ok(BroadcastValidation::ConsensusAndEquivocation),
+            other => Err(format!("Unknown broadcast validation: {}", other)),
+        }
+    }
+}
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct ValidatorDuty {
     /// The validator's BLS public key, uniquely identifying them. _48-bytes, hex encoded with 0x prefix, case insensitive._
     pub validator_pubkey: PublicKeyBytes,
+    /// The validator's index in `state.validators`, if it is known to the beacon chain.
+    pub validator_index: Option<u64>,
     /// The slot at which the validator must attest.
     pub attestation_slot: Option<Slot>,
     /// The index of the committee within `slot` of which the validator is a member.
     pub attestation_committee_index: Option<CommitteeIndex>,
     /// The position of the validator in the committee.
     pub attestation_committee_position: Option<usize>,
+    /// The modulo used to determine if the validator is an aggregator for its committee (see
+    /// `SelectionProof::is_aggregator_from_modulo`).
+    pub aggregator_modulo: Option<u64>,
     /// The slots in which a validator must propose a block (can be empty).
     pub block_proposal_slots: Vec<Slot>,
+    /// The root of the block at the last slot before the epoch, upon which these duties depend.
+    ///
+    /// If a re-org changes this root, the duties may have changed and should be fetched again.
+    #[serde(default)]
+    pub dependent_root: Option<Hash256>,
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
@@ -44,6 +108,7 @@ pub struct BulkValidatorDutiesRequest {
 pub fn post_validator_duties<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
+    duties_cache: Arc<DutiesCache<T::EthSpec>>,
 ) -> BoxFut {
     let response_builder = ResponseBuilder::new(&req);
 
@@ -62,6 +127,7 @@ pub fn post_validator_duties<T: BeaconChainTypes>(
         .and_then(|bulk_request| {
             return_validator_duties(
                 beacon_chain,
+                duties_cache,
                 bulk_request.epoch,
                 bulk_request.pubkeys.into_iter().map(Into::into).collect(),
             )
@@ -71,12 +137,116 @@ pub fn post_validator_duties<T: BeaconChainTypes>(
     Box::new(future)
 }
 
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct ValidatorLivenessRequest {
+    pub epoch: Epoch,
+    pub pubkeys: Vec<PublicKeyBytes>,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ValidatorLiveness {
+    /// The validator's BLS public key.
+    pub pubkey: PublicKeyBytes,
+    pub epoch: Epoch,
+    /// True if an attestation from the validator targeting `epoch` has been included in the
+    /// canonical chain.
+    pub is_live: bool,
+}
+
+/// HTTP Handler to determine whether a set of validators have been seen attesting during a
+/// particular epoch.
+///
+/// The given `epoch` must be the current or previous epoch of the head state.
+pub fn post_validator_liveness<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> BoxFut {
+    let response_builder = ResponseBuilder::new(&req);
+
+    let future = req
+        .into_body()
+        .concat2()
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+        .and_then(|chunks| {
+            serde_json::from_slice::<ValidatorLivenessRequest>(&chunks).map_err(|e| {
+                ApiError::BadRequest(format!(
+                    "Unable to parse JSON into ValidatorLivenessRequest: {:?}",
+                    e
+                ))
+            })
+        })
+        .and_then(|request| return_validator_liveness(beacon_chain, request.epoch, request.pubkeys))
+        .and_then(|liveness| response_builder?.body_no_ssz(&liveness));
+
+    Box::new(future)
+}
+
+fn return_validator_liveness<T: BeaconChainTypes>(
+    beacon_chain: Arc<BeaconChain<T>>,
+    epoch: Epoch,
+    validator_pubkeys: Vec<PublicKeyBytes>,
+) -> Result<Vec<ValidatorLiveness>, ApiError> {
+    let mut state = beacon_chain.head().beacon_state;
+
+    let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch).map_err(|_| {
+        ApiError::BadRequest(format!(
+            "Liveness is only available for the current or previous epoch, not {}",
+            epoch
+        ))
+    })?;
+    if relative_epoch == RelativeEpoch::Next {
+        return Err(ApiError::BadRequest(
+            "Liveness is not available for the next epoch".to_string(),
+        ));
+    }
+
+    state
+        .build_committee_cache(relative_epoch, &beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+    state
+        .update_pubkey_cache()
+        .map_err(|e| ApiError::ServerError(format!("Unable to build pubkey cache: {:?}", e)))?;
+
+    let live_indices = state
+        .get_matching_source_attestations(epoch)
+        .map_err(|e| ApiError::ServerError(format!("Unable to read attestations: {:?}", e)))?
+        .iter()
+        .filter(|attestation| attestation.data.target.epoch == epoch)
+        .map(|attestation| {
+            get_attesting_indices(&state, &attestation.data, &attestation.aggregation_bits)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ApiError::ServerError(format!("Unable to get attesting indices: {:?}", e)))?
+        .into_iter()
+        .flatten()
+        .collect::<HashSet<usize>>();
+
+    validator_pubkeys
+        .into_iter()
+        .map(|pubkey| {
+            let is_live = state
+                .get_validator_index(&pubkey)
+                .map_err(|e| {
+                    ApiError::ServerError(format!("Unable to read pubkey cache: {:?}", e))
+                })?
+                .map_or(false, |index| live_indices.contains(&index));
+
+            Ok(ValidatorLiveness {
+                pubkey,
+                epoch,
+                is_live,
+            })
+        })
+        .collect()
+}
+
 /// HTTP Handler to retrieve a the duties for a set of validators during a particular epoch
 ///
 /// The given `epoch` must be within one epoch of the current epoch.
 pub fn get_validator_duties<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
+    duties_cache: Arc<DutiesCache<T::EthSpec>>,
 ) -> ApiResult {
     let query = UrlQuery::from_request(&req)?;
 
@@ -87,66 +257,30 @@ pub fn get_validator_duties<T: BeaconChainTypes>(
         .map(|validator_pubkey_str| parse_pubkey_bytes(validator_pubkey_str))
         .collect::<Result<_, _>>()?;
 
-    let duties = return_validator_duties(beacon_chain, epoch, validator_pubkeys)?;
+    let duties = return_validator_duties(beacon_chain, duties_cache, epoch, validator_pubkeys)?;
 
     ResponseBuilder::new(&req)?.body_no_ssz(&duties)
 }
 
 fn return_validator_duties<T: BeaconChainTypes>(
     beacon_chain: Arc<BeaconChain<T>>,
+    duties_cache: Arc<DutiesCache<T::EthSpec>>,
     epoch: Epoch,
     validator_pubkeys: Vec<PublicKeyBytes>,
 ) -> Result<Vec<ValidatorDuty>, ApiError> {
-    let slots_per_epoch = T::EthSpec::slots_per_epoch();
-    let head_epoch = beacon_chain.head().beacon_state.current_epoch();
+    let dependent_root = beacon_chain.duties_dependent_root(epoch);
 
-    let mut state = if RelativeEpoch::from_epoch(head_epoch, epoch).is_ok() {
-        beacon_chain.head().beacon_state
-    } else {
-        let slot = if epoch > head_epoch {
-            // Move to the first slot of the epoch prior to the request.
-            //
-            // Taking advantage of saturating epoch subtraction.
-            (epoch - 1).start_slot(slots_per_epoch)
-        } else {
-            // Move to the end of the epoch following the target.
-            //
-            // Taking advantage of saturating epoch subtraction.
-            (epoch + 2).start_slot(slots_per_epoch) - 1
-        };
-
-        beacon_chain.state_at_slot(slot).map_err(|e| {
-            ApiError::ServerError(format!("Unable to load state for epoch {}: {:?}", epoch, e))
-        })?
+    let epoch_duties = match dependent_root.and_then(|root| duties_cache.get(epoch, root)) {
+        Some(epoch_duties) => epoch_duties,
+        None => {
+            let epoch_duties = Arc::new(compute_epoch_duties(&beacon_chain, epoch)?);
+            if let Some(root) = dependent_root {
+                duties_cache.insert(epoch, root, epoch_duties.clone());
+            }
+            epoch_duties
+        }
     };
-
-    let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch)
-        .map_err(|_| ApiError::ServerError(String::from("Loaded state is in the wrong epoch")))?;
-
-    state
-        .build_committee_cache(relative_epoch, &beacon_chain.spec)
-        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
-    state
-        .update_pubkey_cache()
-        .map_err(|e| ApiError::ServerError(format!("Unable to build pubkey cache: {:?}", e)))?;
-
-    // Get a list of all validators for this epoch.
-    //
-    // Used for quickly determining the slot for a proposer.
-    let validator_proposers: Vec<(usize, Slot)> = epoch
-        .slot_iter(T::EthSpec::slots_per_epoch())
-        .map(|slot| {
-            state
-                .get_beacon_proposer_index(slot, &beacon_chain.spec)
-                .map(|i| (i, slot))
-                .map_err(|e| {
-                    ApiError::ServerError(format!(
-                        "Unable to get proposer index for validator: {:?}",
-                        e
-                    ))
-                })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let state = &epoch_duties.state;
 
     validator_pubkeys
         .into_iter()
@@ -157,7 +291,7 @@ fn return_validator_duties<T: BeaconChainTypes>(
                 })?
             {
                 let duties = state
-                    .get_attestation_duties(validator_index, relative_epoch)
+                    .get_attestation_duties(validator_index, epoch_duties.relative_epoch)
                     .map_err(|e| {
                         ApiError::ServerError(format!(
                             "Unable to obtain attestation duties: {:?}",
@@ -165,7 +299,8 @@ fn return_validator_duties<T: BeaconChainTypes>(
                         ))
                     })?;
 
-                let block_proposal_slots = validator_proposers
+                let block_proposal_slots = epoch_duties
+                    .proposers
                     .iter()
                     .filter(|(i, _slot)| validator_index == *i)
                     .map(|(_i, slot)| *slot)
@@ -173,54 +308,177 @@ fn return_validator_duties<T: BeaconChainTypes>(
 
                 Ok(ValidatorDuty {
                     validator_pubkey,
+                    validator_index: Some(validator_index as u64),
                     attestation_slot: duties.map(|d| d.slot),
                     attestation_committee_index: duties.map(|d| d.index),
                     attestation_committee_position: duties.map(|d| d.committee_position),
+                    aggregator_modulo: duties
+                        .map(|d| SelectionProof::modulo(d.committee_len, &beacon_chain.spec)),
                     block_proposal_slots,
+                    dependent_root,
                 })
             } else {
                 Ok(ValidatorDuty {
                     validator_pubkey,
+                    validator_index: None,
                     attestation_slot: None,
                     attestation_committee_index: None,
                     attestation_committee_position: None,
+                    aggregator_modulo: None,
                     block_proposal_slots: vec![],
+                    dependent_root,
                 })
             }
         })
         .collect::<Result<Vec<_>, ApiError>>()
 }
 
+/// Loads a state from which the duties of any validator in `epoch` may be read, building its
+/// committee and pubkey caches, and finds the proposers of the epoch.
+fn compute_epoch_duties<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    epoch: Epoch,
+) -> Result<EpochDuties<T::EthSpec>, ApiError> {
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+    let head_epoch = beacon_chain.with_head_state(|state| state.current_epoch());
+
+    // Duties for the next epoch are requested by every validator client at the epoch boundary,
+    // so they are served from the precomputed next epoch whenever possible.
+    let precomputed = if epoch > head_epoch {
+        beacon_chain.precomputed_epoch(epoch)
+    } else {
+        None
+    };
+
+    let mut state = if let Some(precomputed) = &precomputed {
+        precomputed.state.clone()
+    } else if RelativeEpoch::from_epoch(head_epoch, epoch).is_ok() {
+        beacon_chain.head().beacon_state
+    } else {
+        let slot = if epoch > head_epoch {
+            // Move to the first slot of the epoch prior to the request.
+            //
+            // Taking advantage of saturating epoch subtraction.
+            (epoch - 1).start_slot(slots_per_epoch)
+        } else {
+            // Move to the end of the epoch following the target.
+            //
+            // Taking advantage of saturating epoch subtraction.
+            (epoch + 2).start_slot(slots_per_epoch) - 1
+        };
+
+        beacon_chain.state_at_slot(slot).map_err(|e| {
+            ApiError::ServerError(format!("Unable to load state for epoch {}: {:?}", epoch, e))
+        })?
+    };
+
+    let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch)
+        .map_err(|_| ApiError::ServerError(String::from("Loaded state is in the wrong epoch")))?;
+
+    state
+        .build_committee_cache(relative_epoch, &beacon_chain.spec)
+        .map_err(|e| ApiError::ServerError(format!("Unable to build committee cache: {:?}", e)))?;
+    state
+        .update_pubkey_cache()
+        .map_err(|e| ApiError::ServerError(format!("Unable to build pubkey cache: {:?}", e)))?;
+
+    // Get a list of all validators for this epoch.
+    //
+    // Used for quickly determining the slot for a proposer.
+    let proposers: Vec<(usize, Slot)> = if let Some(precomputed) = &precomputed {
+        precomputed
+            .proposers
+            .iter()
+            .copied()
+            .zip(epoch.slot_iter(T::EthSpec::slots_per_epoch()))
+            .collect()
+    } else if epoch >= head_epoch {
+        beacon_chain
+            .epoch_proposers(beacon_chain.head_info().block_root, epoch)
+            .map_err(|e| ApiError::ServerError(format!("Unable to get proposers: {:?}", e)))?
+            .proposers
+            .iter()
+            .map(|(index, _)| *index)
+            .zip(epoch.slot_iter(T::EthSpec::slots_per_epoch()))
+            .collect()
+    } else {
+        epoch
+            .slot_iter(T::EthSpec::slots_per_epoch())
+            .map(|slot| {
+                state
+                    .get_beacon_proposer_index(slot, &beacon_chain.spec)
+                    .map(|i| (i, slot))
+                    .map_err(|e| {
+                        ApiError::ServerError(format!(
+                            "Unable to get proposer index for validator: {:?}",
+                            e
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    Ok(EpochDuties {
+        state,
+        relative_epoch,
+        proposers,
+    })
+}
+
 /// HTTP Handler to produce a new BeaconBlock from the current state, ready to be signed by a validator.
 pub fn get_new_beacon_block<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
     log: Logger,
-) -> ApiResult {
-    let query = UrlQuery::from_request(&req)?;
+) -> BoxFut {
+    let query = try_future!(UrlQuery::from_request(&req));
 
-    let slot = query.slot()?;
-    let randao_reveal = query.randao_reveal()?;
+    let slot = try_future!(query.slot());
+    let randao_reveal = try_future!(query.randao_reveal());
+    let graffiti = try_future!(query.graffiti());
+    let response_builder = ResponseBuilder::new(&req);
 
-    let (new_block, _state) = beacon_chain
-        .produce_block(randao_reveal, slot)
-        .map_err(|e| {
-            error!(
-                log,
-                "Error whilst producing block";
-                "error" => format!("{:?}", e)
-            );
+    // Allow late aggregates for the previous slot to arrive before packing the block.
+    let wait = beacon_chain.block_packing_wait(slot);
+    if wait > Duration::from_millis(0) {
+        debug!(
+            log,
+            "Waiting for aggregates before producing block";
+            "wait_ms" => wait.as_millis() as u64,
+            "slot" => slot,
+        );
+    }
 
-            ApiError::ServerError(format!(
-                "Beacon node is not able to produce a block: {:?}",
-                e
-            ))
-        })?;
+    Box::new(
+        Delay::new(Instant::now() + wait)
+            .map_err(|e| ApiError::ServerError(format!("Block packing delay failed: {:?}", e)))
+            .and_then(move |()| {
+                let (new_block, _state) = beacon_chain
+                    .produce_block_with_graffiti(randao_reveal, slot, graffiti)
+                    .map_err(|e| {
+                        error!(
+                            log,
+                            "Error whilst producing block";
+                            "error" => format!("{:?}", e)
+                        );
+
+                        ApiError::ServerError(format!(
+                            "Beacon node is not able to produce a block: {:?}",
+                            e
+                        ))
+                    })?;
 
-    ResponseBuilder::new(&req)?.body(&new_block)
+                beacon_chain.observe_local_block_production(slot);
+
+                response_builder?.body(&new_block)
+            }),
+    )
 }
 
 /// HTTP Handler to publish a BeaconBlock, which has been signed by a validator.
+///
+/// The block is validated according to the `broadcast_validation` query parameter (see
+/// `BroadcastValidation`) before it is broadcast to the network.
 pub fn publish_beacon_block<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
@@ -230,6 +488,13 @@ pub fn publish_beacon_block<T: BeaconChainTypes>(
     try_future!(check_content_type_for_json(&req));
     let response_builder = ResponseBuilder::new(&req);
 
+    let validation = if req.uri().query().is_some() {
+        try_future!(UrlQuery::from_request(&req).and_then(UrlQuery::broadcast_validation))
+            .unwrap_or_default()
+    } else {
+        BroadcastValidation::default()
+    };
+
     let body = req.into_body();
     Box::new(
         body.concat2()
@@ -239,82 +504,199 @@ pub fn publish_beacon_block<T: BeaconChainTypes>(
                     ApiError::BadRequest(format!("Unable to parse JSON into BeaconBlock: {:?}", e))
                 })
             })
-            .and_then(move |block: BeaconBlock<T::EthSpec>| {
-                let slot = block.slot;
-                match beacon_chain.process_block(block.clone()) {
-                    Ok(BlockProcessingOutcome::Processed { block_root }) => {
-                        // Block was processed, publish via gossipsub
-                        info!(
-                            log,
-                            "Block from local validator";
-                            "block_root" => format!("{}", block_root),
-                            "block_slot" => slot,
-                        );
-
-                        publish_beacon_block_to_network::<T>(network_chan, block)?;
-
-                        // Run the fork choice algorithm and enshrine a new canonical head, if
-                        // found.
-                        //
-                        // The new head may or may not be the block we just received.
-                        if let Err(e) = beacon_chain.fork_choice() {
-                            error!(
-                                log,
-                                "Failed to find beacon chain head";
-                                "error" => format!("{:?}", e)
-                            );
-                        } else {
-                            // In the best case, validators should produce blocks that become the
-                            // head.
-                            //
-                            // Potential reasons this may not be the case:
-                            //
-                            // - A quick re-org between block produce and publish.
-                            // - Excessive time between block produce and publish.
-                            // - A validator is using another beacon node to produce blocks and
-                            // submitting them here.
-                            if beacon_chain.head().beacon_block_root != block_root {
-                                warn!(
-                                    log,
-                                    "Block from validator is not head";
-                                    "desc" => "potential re-org",
-                                );
+            .and_then(move |block: BeaconBlock<T::EthSpec>| match validation {
+                BroadcastValidation::Gossip => {
+                    verify_block_for_gossip(&beacon_chain, &block, validation, &log)?;
+                    publish_local_block(&beacon_chain, network_chan, block.clone())?;
+                    let block_root =
+                        import_published_block(&beacon_chain, block, validation, &log)?;
+                    update_head(&beacon_chain, block_root, &log);
+                    Ok(())
+                }
+                BroadcastValidation::Consensus => {
+                    let block_root =
+                        import_published_block(&beacon_chain, block.clone(), validation, &log)?;
+                    publish_local_block(&beacon_chain, network_chan, block)?;
+                    update_head(&beacon_chain, block_root, &log);
+                    Ok(())
+                }
+                BroadcastValidation::ConsensusAndEquivocation => {
+                    verify_block_for_gossip(&beacon_chain, &block, validation, &log)?;
 
-                            }
+                    // Never publish two different blocks from the same proposer at the same slot,
+                    // even if they were produced by different validator clients.
+                    //
+                    // The gossip checks are performed first so that an invalid block cannot
+                    // prevent a valid block from being published.
+                    match beacon_chain.observe_local_block(&block) {
+                        Ok(ProposalObservation::Equivocation { .. }) => {
+                            return Err(ApiError::BadRequest(format!(
+                                "A different block has already been published at slot {}, this block would be slashable",
+                                block.slot
+                            )))
+                        }
+                        Ok(_) => (),
+                        Err(e) => {
+                            return Err(ApiError::ServerError(format!(
+                                "Unable to check block for equivocation: {:?}",
+                                e
+                            )))
                         }
-
-                        Ok(())
-                    }
-                    Ok(outcome) => {
-                        warn!(
-                            log,
-                            "Invalid block from local validator";
-                            "outcome" => format!("{:?}", outcome)
-                        );
-
-                        Err(ApiError::ProcessingError(format!(
-                            "The BeaconBlock could not be processed and has not been published: {:?}",
-                            outcome
-                        )))
                     }
-                    Err(e) => {
-                        error!(
-                            log,
-                            "Error whilst processing block";
-                            "error" => format!("{:?}", e)
-                        );
 
-                        Err(ApiError::ServerError(format!(
-                            "Error while processing block: {:?}",
-                            e
-                        )))
-                    }
+                    let block_root =
+                        import_published_block(&beacon_chain, block.clone(), validation, &log)?;
+                    publish_local_block(&beacon_chain, network_chan, block)?;
+                    update_head(&beacon_chain, block_root, &log);
+                    Ok(())
                 }
-        })
-        .and_then(|_| response_builder?.body_no_ssz(&()))
+            })
+            .and_then(|_| response_builder?.body_no_ssz(&())),
     )
 }
 
+/// Publishes `block` from a local validator to the network, recording whether it was published
+/// so that it may be reported if it does not become canonical.
+fn publish_local_block<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    network_chan: NetworkChannel,
+    block: BeaconBlock<T::EthSpec>,
+) -> Result<(), ApiError> {
+    let result = publish_beacon_block_to_network::<T>(network_chan, block.clone());
+    beacon_chain.observe_local_block_publication(&block, result.is_ok());
+    result
+}
+
+/// Performs the checks required before `block` may be propagated on gossip, returning an error
+/// describing the failure if it may not be.
+fn verify_block_for_gossip<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    block: &BeaconBlock<T::EthSpec>,
+    validation: BroadcastValidation,
+    log: &Logger,
+) -> Result<(), ApiError> {
+    match beacon_chain.verify_block_for_gossip(block) {
+        Ok(GossipBlockOutcome::Valid { .. }) => Ok(()),
+        Ok(GossipBlockOutcome::Invalid(outcome)) => {
+            warn!(
+                log,
+                "Invalid block from local validator";
+                "outcome" => format!("{:?}", outcome),
+                "broadcast_validation" => validation.as_str(),
+            );
+
+            Err(ApiError::ProcessingError(format!(
+                "The BeaconBlock failed gossip validation and has not been published: {:?}",
+                outcome
+            )))
+        }
+        Err(e) => {
+            error!(
+                log,
+                "Error whilst verifying block";
+                "error" => format!("{:?}", e)
+            );
+
+            Err(ApiError::ServerError(format!(
+                "Error while verifying block: {:?}",
+                e
+            )))
+        }
+    }
+}
+
+/// Imports a block from a local validator, returning its root or an error describing the failure
+/// if the block could not be imported.
+///
+/// When `validation` is `BroadcastValidation::Gossip` the block has already been broadcast, which
+/// is reflected in the error.
+fn import_published_block<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    block: BeaconBlock<T::EthSpec>,
+    validation: BroadcastValidation,
+    log: &Logger,
+) -> Result<Hash256, ApiError> {
+    let slot = block.slot;
+    let publication = if validation == BroadcastValidation::Gossip {
+        "has been published"
+    } else {
+        "has not been published"
+    };
+
+    match beacon_chain.process_block(block) {
+        Ok(BlockProcessingOutcome::Processed { block_root }) => {
+            info!(
+                log,
+                "Block from local validator";
+                "block_root" => format!("{}", block_root),
+                "block_slot" => slot,
+            );
+
+            Ok(block_root)
+        }
+        Ok(outcome) => {
+            warn!(
+                log,
+                "Invalid block from local validator";
+                "outcome" => format!("{:?}", outcome),
+                "broadcast_validation" => validation.as_str(),
+            );
+
+            Err(ApiError::ProcessingError(format!(
+                "The BeaconBlock could not be processed and {}: {:?}",
+                publication, outcome
+            )))
+        }
+        Err(e) => {
+            error!(
+                log,
+                "Error whilst processing block";
+                "error" => format!("{:?}", e)
+            );
+
+            Err(ApiError::ServerError(format!(
+                "Error while processing block: {:?}",
+                e
+            )))
+        }
+    }
+}
+
+/// Runs the fork choice algorithm after the block with `block_root` from a local validator has
+/// been imported and published, enshrining a new canonical head if one is found.
+///
+/// The new head may or may not be the block we just received.
+fn update_head<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    block_root: Hash256,
+    log: &Logger,
+) {
+    if let Err(e) = beacon_chain.fork_choice() {
+        error!(
+            log,
+            "Failed to find beacon chain head";
+            "error" => format!("{:?}", e)
+        );
+    } else {
+        // In the best case, validators should produce blocks that become the
+        // head.
+        //
+        // Potential reasons this may not be the case:
+        //
+        // - A quick re-org between block produce and publish.
+        // - Excessive time between block produce and publish.
+        // - A validator is using another beacon node to produce blocks and
+        // submitting them here.
+        if beacon_chain.head().beacon_block_root != block_root {
+            warn!(
+                log,
+                "Block from validator is not head";
+                "desc" => "potential re-org",
+            );
+        }
+    }
+}
+
 /// HTTP Handler to produce a new Attestation from the current state, ready to be signed by a validator.
 pub fn get_new_attestation<T: BeaconChainTypes>(
     req: Request<Body>,
@@ -329,9 +711,31 @@ pub fn get_new_attestation<T: BeaconChainTypes>(
         .produce_attestation(slot, index)
         .map_err(|e| ApiError::BadRequest(format!("Unable to produce attestation: {:?}", e)))?;
 
+    beacon_chain.observe_local_production(LocalMessage::Attestation, slot);
+
     ResponseBuilder::new(&req)?.body(&attestation)
 }
 
+/// HTTP Handler to return an unsigned, unaggregated `Attestation` for each committee at `slot`,
+/// in order of committee index.
+///
+/// Allows a validator client with validators in many committees to fetch all of their
+/// attestations in one request.
+pub fn get_new_attestations<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let slot = UrlQuery::from_request(&req)?.slot()?;
+
+    let attestations = beacon_chain
+        .produce_attestations_at_slot(slot)
+        .map_err(|e| ApiError::BadRequest(format!("Unable to produce attestations: {:?}", e)))?;
+
+    beacon_chain.observe_local_production(LocalMessage::Attestation, slot);
+
+    ResponseBuilder::new(&req)?.body(&attestations)
+}
+
 /// HTTP Handler to publish an Attestation, which has been signed by a validator.
 pub fn publish_attestation<T: BeaconChainTypes>(
     req: Request<Body>,
@@ -356,45 +760,302 @@ pub fn publish_attestation<T: BeaconChainTypes>(
                 })
             })
             .and_then(move |attestation: Attestation<T::EthSpec>| {
-                match beacon_chain.process_attestation(attestation.clone()) {
-                    Ok(AttestationProcessingOutcome::Processed) => {
-                        // Block was processed, publish via gossipsub
+                process_and_publish_attestation(&beacon_chain, network_chan, &log, attestation)
+            })
+            .and_then(|_| response_builder?.body_no_ssz(&())),
+    )
+}
+
+/// HTTP Handler to publish a list of attestations, which have been signed by validators.
+///
+/// Each valid attestation is published, even if others are invalid. If any attestation is
+/// invalid, the error describes each invalid attestation by its position in the list.
+pub fn publish_attestations<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel,
+    log: Logger,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                serde_json::from_slice(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to deserialize JSON into a list of Attestation: {:?}",
+                        e
+                    ))
+                })
+            })
+            .and_then(move |attestations: Vec<Attestation<T::EthSpec>>| {
+                let count = attestations.len();
+                let errors = attestations
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, attestation)| {
+                        process_and_publish_attestation(
+                            &beacon_chain,
+                            network_chan.clone(),
+                            &log,
+                            attestation,
+                        )
+                        .err()
+                        .map(|e| format!("{}: {:?}", i, e))
+                    })
+                    .collect::<Vec<_>>();
+
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(ApiError::ProcessingError(format!(
+                        "{} of {} attestations could not be processed and have not been published: {}",
+                        errors.len(),
+                        count,
+                        errors.join(", ")
+                    )))
+                }
+            })
+            .and_then(|_| response_builder?.body_no_ssz(&())),
+    )
+}
+
+/// Processes an attestation from a local validator, publishing it to the network if it is valid.
+fn process_and_publish_attestation<T: BeaconChainTypes>(
+    beacon_chain: &BeaconChain<T>,
+    network_chan: NetworkChannel,
+    log: &Logger,
+    attestation: Attestation<T::EthSpec>,
+) -> Result<(), ApiError> {
+    match beacon_chain.process_attestation(attestation.clone()) {
+        Ok(AttestationProcessingOutcome::Processed) => {
+            // Block was processed, publish via gossipsub
+            info!(
+                log,
+                "Attestation from local validator";
+                "target" => attestation.data.source.epoch,
+                "source" => attestation.data.source.epoch,
+                "index" => attestation.data.index,
+                "slot" => attestation.data.slot,
+            );
+            let slot = attestation.data.slot;
+            publish_attestation_to_network::<T>(network_chan, attestation)?;
+            beacon_chain.observe_local_publication(LocalMessage::Attestation, slot);
+            Ok(())
+        }
+        Ok(outcome) => {
+            warn!(
+                log,
+                "Invalid attestation from local validator";
+                "outcome" => format!("{:?}", outcome)
+            );
+
+            Err(ApiError::ProcessingError(format!(
+                "The Attestation could not be processed and has not been published: {:?}",
+                outcome
+            )))
+        }
+        Err(e) => {
+            error!(
+                log,
+                "Error whilst processing attestation";
+                "error" => format!("{:?}", e)
+            );
+
+            Err(ApiError::ServerError(format!(
+                "Error while processing attestation: {:?}",
+                e
+            )))
+        }
+    }
+}
+
+/// HTTP Handler to accept a signed `VoluntaryExit`, adding it to the operation pool and publishing
+/// it to the network.
+pub fn publish_voluntary_exit<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel,
+    log: Logger,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .and_then(|chunks| {
+                serde_json::from_slice::<VoluntaryExit>(&chunks).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to deserialize JSON into a VoluntaryExit: {:?}",
+                        e
+                    ))
+                })
+            })
+            .and_then(
+                move |exit| match beacon_chain.process_voluntary_exit(exit.clone()) {
+                    Ok(()) => {
                         info!(
                             log,
-                            "Attestation from local validator";
-                            "target" => attestation.data.source.epoch,
-                            "source" => attestation.data.source.epoch,
-                            "index" => attestation.data.index,
-                            "slot" => attestation.data.slot,
+                            "Voluntary exit from local validator";
+                            "validator_index" => exit.validator_index,
+                            "epoch" => exit.epoch,
                         );
-                        publish_attestation_to_network::<T>(network_chan, attestation)
+                        publish_voluntary_exit_to_network(network_chan, exit)
                     }
-                    Ok(outcome) => {
+                    Err(e) => {
                         warn!(
                             log,
-                            "Invalid attestation from local validator";
-                            "outcome" => format!("{:?}", outcome)
+                            "Invalid voluntary exit from local validator";
+                            "validator_index" => exit.validator_index,
+                            "error" => format!("{:?}", e)
                         );
 
                         Err(ApiError::ProcessingError(format!(
-                            "The Attestation could not be processed and has not been published: {:?}",
-                            outcome
-                        )))
+                        "The VoluntaryExit could not be processed and has not been published: {:?}",
+                        e
+                    )))
                     }
-                    Err(e) => {
-                        error!(
-                            log,
-                            "Error whilst processing attestation";
-                            "error" => format!("{:?}", e)
-                        );
+                },
+            )
+            .and_then(|_| response_builder?.body_no_ssz(&())),
+    )
+}
 
-                        Err(ApiError::ServerError(format!(
-                            "Error while processing attestation: {:?}",
-                            e
-                        )))
-                    }
-                }
+/// HTTP Handler to retrieve the best aggregate attestation known to the beacon node for the
+/// committee at the given slot and index, ready to be published by an aggregator.
+pub fn get_aggregate_attestation<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+) -> ApiResult {
+    let query = UrlQuery::from_request(&req)?;
+
+    let slot = query.slot()?;
+    let index = query.committee_index()?;
+
+    let aggregate = beacon_chain
+        .op_pool
+        .get_aggregated_attestation(slot, index)
+        .ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "No attestations known for slot {} and committee index {}",
+                slot, index
+            ))
+        })?;
+
+    beacon_chain.observe_local_production(LocalMessage::AggregateAndProof, slot);
+
+    ResponseBuilder::new(&req)?.body(&aggregate)
+}
+
+/// HTTP Handler to publish a list of `SignedAggregateAndProof`s, which have been produced and
+/// signed by aggregators.
+pub fn publish_aggregate_and_proofs<T: BeaconChainTypes>(
+    req: Request<Body>,
+    beacon_chain: Arc<BeaconChain<T>>,
+    network_chan: NetworkChannel,
+    log: Logger,
+) -> BoxFut {
+    try_future!(check_content_type_for_json(&req));
+    let response_builder = ResponseBuilder::new(&req);
+
+    Box::new(
+        req.into_body()
+            .concat2()
+            .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+            .map(|chunk| chunk.iter().cloned().collect::<Vec<u8>>())
+            .and_then(|chunks| {
+                serde_json::from_slice(&chunks.as_slice()).map_err(|e| {
+                    ApiError::BadRequest(format!(
+                        "Unable to deserialize JSON into a list of SignedAggregateAndProof: {:?}",
+                        e
+                    ))
+                })
             })
+            .and_then(
+                move |signed_aggregates: Vec<SignedAggregateAndProof<T::EthSpec>>| {
+                    let head = beacon_chain.head();
+                    let state = &head.beacon_state;
+
+                    for signed_aggregate in &signed_aggregates {
+                        let aggregator_index = signed_aggregate.message.aggregator_index;
+                        let validator = state
+                            .validators
+                            .get(aggregator_index as usize)
+                            .ok_or_else(|| {
+                                ApiError::BadRequest(format!(
+                                    "Unknown aggregator index: {}",
+                                    aggregator_index
+                                ))
+                            })?;
+
+                        if !signed_aggregate.is_valid(
+                            &validator.pubkey,
+                            &state.fork,
+                            &beacon_chain.spec,
+                        ) {
+                            warn!(
+                                log,
+                                "Invalid aggregate from local validator";
+                                "aggregator_index" => aggregator_index,
+                            );
+
+                            return Err(ApiError::BadRequest(format!(
+                                "Invalid signature or selection proof from aggregator {}",
+                                aggregator_index
+                            )));
+                        }
+                    }
+
+                    for signed_aggregate in signed_aggregates {
+                        let aggregate = &signed_aggregate.message.aggregate;
+
+                        match beacon_chain.process_attestation(aggregate.clone()) {
+                            Ok(AttestationProcessingOutcome::Processed) => info!(
+                                log,
+                                "Aggregate from local validator";
+                                "aggregator_index" => signed_aggregate.message.aggregator_index,
+                                "signers" => aggregate.aggregation_bits.num_set_bits(),
+                                "index" => aggregate.data.index,
+                                "slot" => aggregate.data.slot,
+                            ),
+                            // The aggregate may include attestations that are already known, so
+                            // it is published regardless of the outcome.
+                            Ok(outcome) => debug!(
+                                log,
+                                "Aggregate from local validator not processed";
+                                "outcome" => format!("{:?}", outcome)
+                            ),
+                            Err(e) => {
+                                error!(
+                                    log,
+                                    "Error whilst processing aggregate";
+                                    "error" => format!("{:?}", e)
+                                );
+
+                                return Err(ApiError::ServerError(format!(
+                                    "Error while processing aggregate: {:?}",
+                                    e
+                                )));
+                            }
+                        }
+
+                        let slot = aggregate.data.slot;
+                        publish_aggregate_and_proof_to_network::<T>(
+                            network_chan.clone(),
+                            signed_aggregate,
+                        )?;
+                        beacon_chain
+                            .observe_local_publication(LocalMessage::AggregateAndProof, slot);
+                    }
+
+                    Ok(())
+                },
+            )
             .and_then(|_| response_builder?.body_no_ssz(&())),
     )
 }
@@ -0,0 +1,367 @@
+#![cfg(test)]
+
+//! Checks the JSON representation of each type served by the REST API against a snapshot in
+//! `tests/snapshots`, so that a change to a field name or to the encoding of a field (e.g., a hex
+//! string losing its `0x` prefix) cannot break downstream tooling unnoticed.
+//!
+//! Snapshots record the *shape* of a response rather than its values: each number is replaced by
+//! `"number"`, each decimal string by `"quoted_number"`, each bool by `"bool"`, each hex string by
+//! `"hex(<bytes>)"` (or `"unprefixed_hex(<bytes>)"` if it lacks a `0x` prefix) and each non-empty
+//! array by the shape of its first element. Other strings (e.g., enum variants) are kept verbatim.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to rewrite the snapshots after an intentional change, and review
+//! the diff.
+
+use beacon_chain::{
+    BalanceRecord, CacheMemoryReport, CacheMemoryUsage, CachePriority, ChainHealth, ForkChoiceNode,
+    ValidatorQueueEstimate,
+};
+use eth2_libp2p::{Ban, BanTarget, PeerCounts};
+use rest_api::{
+    AnchorInfo, BanRequest, BlockResponse, Committee, HeadResponse, HealthResponse, StateResponse,
+    ValidatorDuty, ValidatorLiveness, ValidatorResponse,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use types::{
+    test_utils::TestingBeaconStateBuilder, BeaconBlock, Epoch, EthSpec, Hash256, MinimalEthSpec,
+    Slot,
+};
+
+type E = MinimalEthSpec;
+
+/// If this environment variable is set, snapshots are rewritten instead of checked.
+const UPDATE_SNAPSHOTS_ENV: &str = "UPDATE_SNAPSHOTS";
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.json", name))
+}
+
+/// Returns the number of bytes encoded by `s`, if it is a string of hex digits.
+fn hex_len(s: &str) -> Option<usize> {
+    if !s.is_empty() && s.len() % 2 == 0 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(s.len() / 2)
+    } else {
+        None
+    }
+}
+
+/// Returns the shape of `value`, as described in the module docs.
+fn shape(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::Bool(_) => json!("bool"),
+        Value::Number(_) => json!("number"),
+        Value::String(s) if s == "0x" => json!("hex(0)"),
+        Value::String(s) if s.starts_with("0x") && hex_len(&s[2..]).is_some() => {
+            json!(format!("hex({})", hex_len(&s[2..]).unwrap_or(0)))
+        }
+        Value::String(s) if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+            json!("quoted_number")
+        }
+        Value::String(s) => match hex_len(s) {
+            Some(len) => json!(format!("unprefixed_hex({})", len)),
+            None => value.clone(),
+        },
+        Value::Array(values) => Value::Array(values.first().map(shape).into_iter().collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), shape(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Asserts that the shape of the JSON encoding of `item` matches the snapshot called `name`.
+fn assert_snapshot<T: Serialize>(name: &str, item: &T) {
+    let path = snapshot_path(name);
+    let actual = shape(&serde_json::to_value(item).expect("should serialize to json"));
+    let actual_string = serde_json::to_string_pretty(&actual).expect("should pretty-print json");
+
+    if env::var(UPDATE_SNAPSHOTS_ENV).is_ok() {
+        fs::create_dir_all(path.parent().expect("snapshot path should have a parent"))
+            .expect("should create snapshot dir");
+        fs::write(&path, format!("{}\n", actual_string)).expect("should write snapshot");
+        return;
+    }
+
+    let expected: Value = fs::read(&path)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| format!("{:?}", e)))
+        .unwrap_or_else(|e| {
+            panic!(
+                "Unable to read snapshot {:?} ({}), run with {}=1 to create it",
+                path, e, UPDATE_SNAPSHOTS_ENV
+            )
+        });
+
+    assert!(
+        actual == expected,
+        "The JSON representation of {} has changed.\n\nExpected:\n{}\n\nActual:\n{}\n\n\
+         If the change is intentional, run with {}=1 to update {:?}",
+        name,
+        serde_json::to_string_pretty(&expected).expect("should pretty-print json"),
+        actual_string,
+        UPDATE_SNAPSHOTS_ENV,
+        path
+    );
+}
+
+fn root(i: u64) -> Hash256 {
+    Hash256::from_low_u64_be(i)
+}
+
+#[test]
+fn head_response() {
+    assert_snapshot(
+        "head_response",
+        &HeadResponse {
+            slot: Slot::new(9),
+            block_root: root(1),
+            state_root: root(2),
+            finalized_slot: Slot::new(0),
+            finalized_block_root: root(3),
+            justified_slot: Slot::new(8),
+            justified_block_root: root(4),
+            previous_justified_slot: Slot::new(0),
+            previous_justified_block_root: root(3),
+            duty_dependent_root: Some(root(1)),
+        },
+    );
+}
+
+#[test]
+fn block_response() {
+    let spec = E::default_spec();
+    let beacon_block = BeaconBlock::<E>::empty(&spec);
+
+    assert_snapshot(
+        "block_response",
+        &BlockResponse {
+            root: beacon_block.canonical_root(),
+            beacon_block,
+        },
+    );
+}
+
+#[test]
+fn state_response() {
+    let spec = E::default_spec();
+    let (beacon_state, _keypairs) =
+        TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(2, &spec).build();
+
+    assert_snapshot(
+        "state_response",
+        &StateResponse {
+            root: root(1),
+            beacon_state,
+        },
+    );
+}
+
+#[test]
+fn validator_response() {
+    let spec = E::default_spec();
+    let (state, _keypairs) =
+        TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(1, &spec).build();
+    let validator = state.validators[0].clone();
+
+    assert_snapshot(
+        "validator_response",
+        &ValidatorResponse {
+            pubkey: validator.pubkey.clone(),
+            validator_index: Some(0),
+            balance: Some(state.balances[0]),
+            validator: Some(validator.clone()),
+        },
+    );
+    assert_snapshot(
+        "validator_response_unknown",
+        &ValidatorResponse {
+            pubkey: validator.pubkey,
+            validator_index: None,
+            balance: None,
+            validator: None,
+        },
+    );
+}
+
+#[test]
+fn validator_duty() {
+    let spec = E::default_spec();
+    let (state, _keypairs) =
+        TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(1, &spec).build();
+
+    assert_snapshot(
+        "validator_duty",
+        &ValidatorDuty {
+            validator_pubkey: state.validators[0].pubkey.clone(),
+            validator_index: Some(0),
+            attestation_slot: Some(Slot::new(3)),
+            attestation_committee_index: Some(0),
+            attestation_committee_position: Some(1),
+            aggregator_modulo: Some(1),
+            block_proposal_slots: vec![Slot::new(5)],
+            dependent_root: Some(Hash256::from_low_u64_be(7)),
+        },
+    );
+}
+
+#[test]
+fn validator_liveness() {
+    let spec = E::default_spec();
+    let (state, _keypairs) =
+        TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(1, &spec).build();
+
+    assert_snapshot(
+        "validator_liveness",
+        &ValidatorLiveness {
+            pubkey: state.validators[0].pubkey.clone(),
+            epoch: Epoch::new(2),
+            is_live: true,
+        },
+    );
+}
+
+#[test]
+fn committee() {
+    assert_snapshot(
+        "committee",
+        &Committee {
+            index: 1,
+            slot: Slot::new(25),
+            validators: vec![12, 77, 5],
+        },
+    );
+}
+
+#[test]
+fn chain_health() {
+    assert_snapshot(
+        "chain_health",
+        &json!({
+            "synced": ChainHealth::Synced,
+            "eth1_optimistic": ChainHealth::Eth1Optimistic,
+            "syncing": ChainHealth::Syncing { distance: Slot::new(1520) },
+            "stalled": ChainHealth::Stalled { distance: Slot::new(1520) },
+        }),
+    );
+}
+
+#[test]
+fn anchor_info() {
+    assert_snapshot(
+        "anchor_info",
+        &AnchorInfo {
+            anchor_root: root(1),
+            oldest_block_slot: Slot::new(8192),
+            oldest_state_slot: Slot::new(8192),
+        },
+    );
+}
+
+#[test]
+fn balance_record() {
+    assert_snapshot(
+        "balance_record",
+        &BalanceRecord {
+            epoch: Epoch::new(1),
+            balance: 32_000_121_474,
+            effective_balance: 32_000_000_000,
+        },
+    );
+}
+
+#[test]
+fn validator_queue_estimate() {
+    assert_snapshot(
+        "validator_queue_estimate",
+        &ValidatorQueueEstimate {
+            validator_index: 16_384,
+            activation_queue_position: Some(12),
+            activation_epoch: Some(Epoch::new(1029)),
+            is_exiting: true,
+            exit_epoch: Some(Epoch::new(1100)),
+            withdrawable_epoch: Some(Epoch::new(1356)),
+        },
+    );
+}
+
+#[test]
+fn fork_choice_node() {
+    assert_snapshot(
+        "fork_choice_node",
+        &ForkChoiceNode {
+            root: root(2),
+            parent: Some(root(1)),
+            slot: Slot::new(3),
+            weight: 64,
+            justified_epoch: Epoch::new(0),
+            finalized_epoch: Epoch::new(0),
+            best_descendant: root(2),
+        },
+    );
+}
+
+#[test]
+fn peer_counts() {
+    assert_snapshot(
+        "peer_counts",
+        &PeerCounts {
+            connected: 12,
+            inbound: 7,
+            outbound: 5,
+            trusted: 1,
+            max_peers: 25,
+            max_inbound_peers: 20,
+            max_outbound_peers: 10,
+            target_outbound_peers: 5,
+        },
+    );
+}
+
+#[test]
+fn ban() {
+    let target = BanTarget::Ip("10.0.0.1".parse().expect("should parse ip"));
+
+    assert_snapshot(
+        "ban",
+        &Ban {
+            target: target.clone(),
+            expires_at: Some(1_583_020_800),
+        },
+    );
+    assert_snapshot(
+        "ban_request",
+        &BanRequest {
+            target,
+            duration_secs: Some(3600),
+        },
+    );
+}
+
+#[test]
+fn health_response() {
+    assert_snapshot(
+        "health_response",
+        &HealthResponse {
+            chain_health: Some(ChainHealth::Synced),
+            cache_memory: CacheMemoryReport {
+                budget_bytes: Some(1_073_741_824),
+                total_bytes: 402_653_184,
+                caches: vec![CacheMemoryUsage {
+                    name: "checkpoint".to_string(),
+                    priority: CachePriority::High,
+                    entries: 4,
+                    bytes: 402_653_184,
+                }],
+            },
+        },
+    );
+}
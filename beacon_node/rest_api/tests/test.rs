@@ -1,17 +1,22 @@
 #![cfg(test)]
 
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{BeaconChain, BeaconChainTypes, ChainHealth};
+use lighthouse_error::{CodedError, ErrorCode};
 use node_test_rig::{
     environment::{Environment, EnvironmentBuilder},
     testing_client_config, ClientConfig, ClientGenesis, LocalBeaconNode,
 };
-use remote_beacon_node::{PublishStatus, ValidatorDuty};
+use remote_beacon_node::{AnchorInfo, BroadcastValidation, PublishStatus, ValidatorDuty};
 use std::convert::TryInto;
 use std::sync::Arc;
-use tree_hash::TreeHash;
+use store::Store;
+use tree_hash::{SignedRoot, TreeHash};
 use types::{
-    test_utils::generate_deterministic_keypair, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec,
-    MinimalEthSpec, PublicKey, RelativeEpoch, Signature, Slot,
+    test_utils::{
+        generate_deterministic_keypair, ProposerSlashingTestTask, TestingProposerSlashingBuilder,
+    },
+    BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Hash256, MinimalEthSpec, PublicKey,
+    RelativeEpoch, SelectionProof, Signature, SignedAggregateAndProof, Slot, VoluntaryExit,
 };
 use version;
 
@@ -162,6 +167,199 @@ fn validator_produce_attestation() {
     );
 }
 
+#[test]
+fn validator_produce_attestations() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+    let state = beacon_chain.head().beacon_state.clone();
+
+    let validator_index = 0;
+    let duties = state
+        .get_attestation_duties(validator_index, RelativeEpoch::Current)
+        .expect("should have attestation duties cache")
+        .expect("should have attestation duties");
+
+    let attestations = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_attestations(duties.slot),
+        )
+        .expect("should fetch attestations from http api");
+
+    assert_eq!(
+        attestations.len() as u64,
+        state
+            .get_committee_count_at_slot(duties.slot)
+            .expect("should get committee count"),
+        "should have an attestation for each committee"
+    );
+    for (index, attestation) in attestations.iter().enumerate() {
+        assert_eq!(
+            attestation.data.index, index as u64,
+            "should be in index order"
+        );
+        assert_eq!(attestation.data.slot, duties.slot, "should have same slot");
+    }
+
+    let mut attestation = attestations[duties.index as usize].clone();
+
+    // Try publishing the attestation without a signature, ensure it is flagged as invalid.
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .publish_attestations(vec![attestation.clone()]),
+        )
+        .expect("should publish attestations");
+    assert!(
+        !publish_status.is_valid(),
+        "the unsigned published attestation should not be valid"
+    );
+
+    attestation
+        .sign(
+            &generate_deterministic_keypair(validator_index).sk,
+            duties.committee_position,
+            &state.fork,
+            spec,
+        )
+        .expect("should sign attestation");
+
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .publish_attestations(vec![attestation]),
+        )
+        .expect("should publish attestations");
+    assert!(
+        publish_status.is_valid(),
+        "the signed published attestation should be valid"
+    );
+}
+
+#[test]
+fn validator_aggregate_and_proofs() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+    let state = beacon_chain.head().beacon_state.clone();
+
+    let validator_index = 0;
+    let duties = state
+        .get_attestation_duties(validator_index, RelativeEpoch::Current)
+        .expect("should have attestation duties cache")
+        .expect("should have attestation duties");
+
+    let keypair = generate_deterministic_keypair(validator_index);
+
+    let mut attestation = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_attestation(duties.slot, duties.index),
+        )
+        .expect("should fetch attestation from http api");
+    attestation
+        .sign(&keypair.sk, duties.committee_position, &state.fork, spec)
+        .expect("should sign attestation");
+
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .publish_attestation(attestation.clone()),
+        )
+        .expect("should publish attestation");
+    assert!(
+        publish_status.is_valid(),
+        "the signed published attestation should be valid"
+    );
+
+    let aggregate = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .get_aggregate_attestation(duties.slot, duties.index),
+        )
+        .expect("should fetch aggregate from http api");
+    assert_eq!(
+        aggregate, attestation,
+        "the only attestation should be the aggregate"
+    );
+
+    // An aggregate signed by a validator other than the aggregator should be rejected.
+    let invalid_aggregate = SignedAggregateAndProof::from_aggregate(
+        validator_index as u64 + 1,
+        aggregate.clone(),
+        &keypair.sk,
+        &state.fork,
+        spec,
+    );
+    assert!(
+        env.runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .validator()
+                    .publish_aggregate_and_proofs(vec![invalid_aggregate]),
+            )
+            .is_err(),
+        "should not publish an aggregate with an invalid signature"
+    );
+
+    let signed_aggregate = SignedAggregateAndProof::from_aggregate(
+        validator_index as u64,
+        aggregate,
+        &keypair.sk,
+        &state.fork,
+        spec,
+    );
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .publish_aggregate_and_proofs(vec![signed_aggregate]),
+        )
+        .expect("should publish aggregate");
+    assert!(
+        publish_status.is_valid(),
+        "the signed aggregate should be valid"
+    );
+}
+
 #[test]
 fn validator_duties_bulk() {
     let mut env = build_env();
@@ -199,6 +397,60 @@ fn validator_duties_bulk() {
     check_duties(duties, epoch, validators, beacon_chain, spec);
 }
 
+#[test]
+fn validator_liveness() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let epoch = Epoch::new(0);
+
+    let validators = beacon_chain
+        .head()
+        .beacon_state
+        .validators
+        .iter()
+        .map(|v| (&v.pubkey).try_into().expect("pubkey should be valid"))
+        .collect::<Vec<_>>();
+
+    let liveness = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .get_liveness(epoch, &validators),
+        )
+        .expect("should fetch liveness from http api");
+
+    assert_eq!(
+        liveness.len(),
+        validators.len(),
+        "there should be a liveness result for each validator"
+    );
+    for (result, pubkey) in liveness.iter().zip(validators.iter()) {
+        assert_eq!(
+            result.pubkey,
+            pubkey.clone().into(),
+            "liveness results should be in the same order as the request"
+        );
+        assert_eq!(
+            result.epoch, epoch,
+            "liveness should be for the requested epoch"
+        );
+        assert!(
+            !result.is_live,
+            "no validator should be live before any attestations are included"
+        );
+    }
+}
+
 #[test]
 fn validator_duties() {
     let mut env = build_env();
@@ -237,13 +489,31 @@ fn validator_duties() {
         spec,
     );
 
+    // 2. Check that a repeated request for the epoch, served from the duties cache, is correct.
+    let duties = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .get_duties(epoch, &validators[..1]),
+        )
+        .expect("should fetch duties from http api");
+    check_duties(
+        duties,
+        epoch,
+        validators[..1].to_vec(),
+        beacon_chain.clone(),
+        spec,
+    );
+
     epoch += 4;
     let duties = env
         .runtime()
         .block_on(remote_node.http.validator().get_duties(epoch, &validators))
         .expect("should fetch duties from http api");
 
-    // 2. Check with a long skip forward.
+    // 3. Check with a long skip forward.
     check_duties(duties, epoch, validators, beacon_chain, spec);
 
     // TODO: test an epoch in the past. Blocked because the `LocalBeaconNode` cannot produce a
@@ -301,6 +571,24 @@ fn check_duties<T: BeaconChainTypes>(
                 "attestation index should match"
             );
 
+            assert_eq!(
+                Some(validator_index as u64),
+                duty.validator_index,
+                "validator index should match"
+            );
+
+            assert_eq!(
+                Some(SelectionProof::modulo(attestation_duty.committee_len, spec)),
+                duty.aggregator_modulo,
+                "aggregator modulo should match"
+            );
+
+            assert_eq!(
+                beacon_chain.duties_dependent_root(epoch),
+                duty.dependent_root,
+                "dependent root should match"
+            );
+
             if !duty.block_proposal_slots.is_empty() {
                 for slot in &duty.block_proposal_slots {
                     let expected_proposer = state
@@ -407,6 +695,207 @@ fn validator_block_post() {
     );
 }
 
+#[test]
+fn validator_block_post_broadcast_validation() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let mut config = testing_client_config();
+    config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time: 13_371_337,
+    };
+
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+
+    let mut block = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .produce_block(slot, randao_reveal),
+        )
+        .expect("should fetch block from http api");
+
+    // Publish the block without a signature, ensure it is flagged as invalid by each level.
+    for validation in &[
+        BroadcastValidation::Gossip,
+        BroadcastValidation::Consensus,
+        BroadcastValidation::ConsensusAndEquivocation,
+    ] {
+        let publish_status = env
+            .runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .validator()
+                    .publish_block_with_validation(block.clone(), *validation),
+            )
+            .expect("should publish block");
+        if cfg!(not(feature = "fake_crypto")) {
+            assert!(
+                !publish_status.is_valid(),
+                "the unsigned block should not be valid with {:?}",
+                validation
+            );
+        }
+    }
+
+    sign_block(beacon_chain.clone(), &mut block, spec);
+    let block_root = block.canonical_root();
+
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .validator()
+                .publish_block_with_validation(block.clone(), BroadcastValidation::Gossip),
+        )
+        .expect("should publish block");
+
+    if cfg!(not(feature = "fake_crypto")) {
+        assert_eq!(
+            publish_status,
+            PublishStatus::Valid,
+            "the signed block should be valid with gossip validation"
+        );
+    }
+
+    let head = env
+        .runtime()
+        .block_on(remote_node.http.beacon().get_head())
+        .expect("should get head");
+
+    assert_eq!(
+        head.block_root, block_root,
+        "the block should be imported after it is published"
+    );
+}
+
+#[test]
+fn validator_voluntary_exit() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let fork = beacon_chain.head().beacon_state.fork.clone();
+    let keypair = generate_deterministic_keypair(0);
+
+    let mut exit = VoluntaryExit {
+        epoch: Epoch::new(0),
+        validator_index: 0,
+        signature: Signature::empty_signature(),
+    };
+    let domain = spec.get_domain(exit.epoch, Domain::VoluntaryExit, &fork);
+    exit.signature = Signature::new(&exit.signed_root(), domain, &keypair.sk);
+
+    let publish_status = env
+        .runtime()
+        .block_on(remote_node.http.validator().publish_voluntary_exit(exit))
+        .expect("should publish voluntary exit");
+
+    // Validators must be active for `persistent_committee_period` epochs before they may exit.
+    match publish_status {
+        PublishStatus::Invalid(_) => {}
+        other => panic!(
+            "an exit from a newly activated validator should be invalid, got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn beacon_proposer_slashing() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let fork = beacon_chain.head().beacon_state.fork.clone();
+    let make_slashing = |test_task| {
+        TestingProposerSlashingBuilder::double_vote::<E, _>(
+            test_task,
+            0,
+            |validator_index, message, epoch, domain| {
+                let keypair = generate_deterministic_keypair(validator_index as usize);
+                let domain = spec.get_domain(epoch, domain, &fork);
+                Signature::new(message, domain, &keypair.sk)
+            },
+        )
+    };
+
+    let invalid_slashing = make_slashing(ProposerSlashingTestTask::ProposalsIdentical);
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .publish_proposer_slashing(invalid_slashing),
+        )
+        .expect("should publish proposer slashing");
+
+    match publish_status {
+        PublishStatus::Invalid(_) => {}
+        other => panic!(
+            "a slashing of two identical proposals should be invalid, got {:?}",
+            other
+        ),
+    }
+
+    let slashing = make_slashing(ProposerSlashingTestTask::Valid);
+    let publish_status = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .publish_proposer_slashing(slashing.clone()),
+        )
+        .expect("should publish proposer slashing");
+
+    assert_eq!(
+        publish_status,
+        PublishStatus::Valid,
+        "a valid slashing should be accepted"
+    );
+
+    let state = beacon_chain.head().beacon_state;
+    let (proposer_slashings, _) = beacon_chain.op_pool.get_slashings(&state, spec);
+    assert_eq!(
+        proposer_slashings,
+        vec![slashing],
+        "the slashing should be in the op pool"
+    );
+}
+
 #[test]
 fn validator_block_get() {
     let mut env = build_env();
@@ -447,6 +936,104 @@ fn validator_block_get() {
     );
 }
 
+#[test]
+fn validator_block_get_with_graffiti() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain, slot, spec);
+
+    let mut graffiti = [0; 32];
+    graffiti[..4].copy_from_slice(b"test");
+
+    let block = env
+        .runtime()
+        .block_on(remote_node.http.validator().produce_block_with_graffiti(
+            slot,
+            randao_reveal,
+            Some(graffiti),
+        ))
+        .expect("should fetch block from http api");
+
+    assert_eq!(
+        block.body.graffiti, graffiti,
+        "the block should include the requested graffiti"
+    );
+}
+
+#[test]
+fn beacon_validators() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let state = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain")
+        .head()
+        .beacon_state;
+
+    let mut pubkeys = state
+        .validators
+        .iter()
+        .take(2)
+        .map(|v| (&v.pubkey).try_into().expect("pubkey should be valid"))
+        .collect::<Vec<PublicKey>>();
+    let unknown_pubkey = generate_deterministic_keypair(state.validators.len()).pk;
+    pubkeys.push(unknown_pubkey);
+
+    let validators = env
+        .runtime()
+        .block_on(remote_node.http.beacon().get_validators(&pubkeys))
+        .expect("should fetch validators from http api");
+
+    assert_eq!(
+        validators.len(),
+        pubkeys.len(),
+        "there should be a result for each validator"
+    );
+    for (i, (result, pubkey)) in validators.iter().zip(pubkeys.iter()).enumerate() {
+        assert_eq!(
+            result.pubkey,
+            pubkey.clone().into(),
+            "results should be in the same order as the request"
+        );
+
+        if i < 2 {
+            assert_eq!(result.validator_index, Some(i), "index should match state");
+            assert_eq!(
+                result.balance,
+                Some(state.balances[i]),
+                "balance should match state"
+            );
+            assert_eq!(
+                result.validator.as_ref(),
+                Some(&state.validators[i]),
+                "validator should match state"
+            );
+        } else {
+            assert_eq!(
+                result.validator_index, None,
+                "unknown validator has no index"
+            );
+            assert_eq!(result.balance, None, "unknown validator has no balance");
+            assert_eq!(result.validator, None, "unknown validator has no record");
+        }
+    }
+}
+
 #[test]
 fn beacon_state() {
     let mut env = build_env();
@@ -621,3 +1208,186 @@ fn get_version() {
 
     assert_eq!(version::version(), version, "result should be as expected");
 }
+
+#[test]
+fn get_anchor() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    let anchor = env
+        .runtime()
+        .block_on(remote_node.http.node().get_anchor())
+        .expect("should fetch anchor from http api");
+
+    assert_eq!(
+        anchor, None,
+        "node synced from genesis should have no anchor"
+    );
+
+    let anchor_info = AnchorInfo {
+        anchor_root: beacon_chain.head_info().block_root,
+        oldest_block_slot: Slot::new(8),
+        oldest_state_slot: Slot::new(16),
+    };
+    beacon_chain
+        .store
+        .put_anchor_info(&anchor_info)
+        .expect("should store anchor");
+
+    let anchor = env
+        .runtime()
+        .block_on(remote_node.http.node().get_anchor())
+        .expect("should fetch anchor from http api");
+
+    assert_eq!(anchor, Some(anchor_info), "should return the stored anchor");
+}
+
+#[test]
+fn get_health() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+
+    for chain_health in &[
+        ChainHealth::Synced,
+        ChainHealth::Syncing {
+            distance: Slot::new(64),
+        },
+        ChainHealth::Stalled {
+            distance: Slot::new(64),
+        },
+    ] {
+        beacon_chain.set_chain_health(*chain_health);
+
+        let health = env
+            .runtime()
+            .block_on(remote_node.http.node().get_health())
+            .expect("should fetch health from http api");
+
+        assert_eq!(
+            health,
+            Some(*chain_health),
+            "should return the assessed health"
+        );
+    }
+}
+
+#[test]
+fn error_codes() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let error = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_block_by_root(Hash256::repeat_byte(42)),
+        )
+        .expect_err("should not find unknown block");
+
+    assert_eq!(
+        error.error_code(),
+        ErrorCode(4004),
+        "should return the not found code in the response"
+    );
+}
+
+#[test]
+fn state_committees() {
+    let mut env = build_env();
+
+    let node = build_node(&mut env, testing_client_config());
+    let remote_node = node.remote_node().expect("should produce remote node");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("node should have beacon chain");
+    let spec = &beacon_chain.spec;
+
+    let mut state = beacon_chain.head().beacon_state;
+    state
+        .build_committee_cache(RelativeEpoch::Current, spec)
+        .expect("should build current committee cache");
+    state
+        .build_committee_cache(RelativeEpoch::Next, spec)
+        .expect("should build next committee cache");
+
+    for epoch in &[Epoch::new(0), Epoch::new(1)] {
+        let committees = env
+            .runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .beacon()
+                    .get_committees("head", Some(*epoch), None, None),
+            )
+            .expect("should fetch committees from http api");
+
+        let expected = epoch
+            .slot_iter(E::slots_per_epoch())
+            .flat_map(|slot| {
+                state
+                    .get_beacon_committees_at_slot(slot)
+                    .expect("should get committees")
+            })
+            .map(|committee| {
+                (
+                    committee.slot,
+                    committee.index,
+                    committee.committee.to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            committees
+                .into_iter()
+                .map(|committee| (committee.slot, committee.index, committee.validators))
+                .collect::<Vec<_>>(),
+            expected,
+            "should return the committees of epoch {}",
+            epoch
+        );
+    }
+
+    let slot = Slot::new(3);
+    let committees = env
+        .runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_committees("0", None, Some(0), Some(slot)),
+        )
+        .expect("should fetch filtered committees from http api");
+    let expected = state
+        .get_beacon_committee(slot, 0)
+        .expect("should get committee");
+
+    assert_eq!(committees.len(), 1, "should filter by slot and index");
+    assert_eq!(committees[0].validators, expected.committee.to_vec());
+
+    env.runtime()
+        .block_on(
+            remote_node
+                .http
+                .beacon()
+                .get_committees("head", Some(Epoch::new(2)), None, None),
+        )
+        .expect_err("should not serve committees more than an epoch after the state");
+}
@@ -0,0 +1,154 @@
+use crate::Error;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::hash_map::{Entry, HashMap};
+use store::{DBColumn, SimpleDiskStore, Store};
+use types::EthSpec;
+
+/// The number of epochs stored in each chunk of a validator's target array.
+pub const CHUNK_SIZE: u64 = 16;
+
+/// Identifies one of the two arrays of target epochs stored for each validator.
+///
+/// For some validator and epoch `e`:
+///
+/// - `Min` stores the lowest target of any attestation with a source greater than `e`.
+/// - `Max` stores the highest target of any attestation with a source less than `e`.
+///
+/// An attestation with source `s` and target `t` surrounds a previous attestation if
+/// `min[s] < t`, and is surrounded by a previous attestation if `max[s] > t`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetArray {
+    Min,
+    Max,
+}
+
+impl TargetArray {
+    fn column(self) -> DBColumn {
+        match self {
+            TargetArray::Min => DBColumn::SlasherMinTargets,
+            TargetArray::Max => DBColumn::SlasherMaxTargets,
+        }
+    }
+
+    /// The value stored for an epoch for which no attestation is known.
+    pub fn empty_value(self) -> u64 {
+        match self {
+            TargetArray::Min => u64::max_value(),
+            TargetArray::Max => 0,
+        }
+    }
+}
+
+/// The target epochs for `CHUNK_SIZE` consecutive epochs.
+///
+/// Chunks are stored in a ring of `num_chunks` entries per validator, so `chunk_index` is used to
+/// detect (and ignore) a chunk which has been left behind by an earlier pass around the ring.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+struct Chunk {
+    chunk_index: u64,
+    targets: Vec<u64>,
+}
+
+impl Chunk {
+    fn empty(array: TargetArray, chunk_index: u64) -> Self {
+        Self {
+            chunk_index,
+            targets: vec![array.empty_value(); CHUNK_SIZE as usize],
+        }
+    }
+}
+
+/// Reads and writes the target arrays of many validators, holding all modified chunks in memory
+/// until `commit` is called.
+pub struct TargetArrays<'a, E: EthSpec> {
+    db: &'a SimpleDiskStore<E>,
+    num_chunks: u64,
+    chunks: HashMap<(TargetArray, u64, u64), (Chunk, bool)>,
+}
+
+impl<'a, E: EthSpec> TargetArrays<'a, E> {
+    /// Creates a view of the target arrays in `db`, which hold at least `history_length` epochs.
+    pub fn new(db: &'a SimpleDiskStore<E>, history_length: u64) -> Self {
+        Self {
+            db,
+            // Allow for a window of `history_length` epochs which does not start on a chunk
+            // boundary.
+            num_chunks: history_length / CHUNK_SIZE + 2,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Returns the value of `array` for `validator_index` at `epoch`.
+    pub fn get(
+        &mut self,
+        array: TargetArray,
+        validator_index: u64,
+        epoch: u64,
+    ) -> Result<u64, Error> {
+        let (chunk, _) = self.chunk(array, validator_index, epoch)?;
+        Ok(chunk.targets[(epoch % CHUNK_SIZE) as usize])
+    }
+
+    /// Sets the value of `array` for `validator_index` at `epoch`.
+    pub fn set(
+        &mut self,
+        array: TargetArray,
+        validator_index: u64,
+        epoch: u64,
+        target: u64,
+    ) -> Result<(), Error> {
+        let (chunk, dirty) = self.chunk(array, validator_index, epoch)?;
+        chunk.targets[(epoch % CHUNK_SIZE) as usize] = target;
+        *dirty = true;
+        Ok(())
+    }
+
+    /// Writes all modified chunks to the database.
+    pub fn commit(self) -> Result<(), Error> {
+        let num_chunks = self.num_chunks;
+        for ((array, validator_index, chunk_index), (chunk, dirty)) in self.chunks {
+            if dirty {
+                self.db.put_bytes(
+                    array.column().into(),
+                    &chunk_key(validator_index, chunk_index % num_chunks),
+                    &chunk.as_ssz_bytes(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn chunk(
+        &mut self,
+        array: TargetArray,
+        validator_index: u64,
+        epoch: u64,
+    ) -> Result<&mut (Chunk, bool), Error> {
+        let chunk_index = epoch / CHUNK_SIZE;
+
+        match self.chunks.entry((array, validator_index, chunk_index)) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let chunk = self
+                    .db
+                    .get_bytes(
+                        array.column().into(),
+                        &chunk_key(validator_index, chunk_index % self.num_chunks),
+                    )?
+                    .map(|bytes| Chunk::from_ssz_bytes(&bytes))
+                    .transpose()?
+                    .filter(|chunk| chunk.chunk_index == chunk_index)
+                    .unwrap_or_else(|| Chunk::empty(array, chunk_index));
+
+                Ok(entry.insert((chunk, false)))
+            }
+        }
+    }
+}
+
+fn chunk_key(validator_index: u64, ring_index: u64) -> Vec<u8> {
+    let mut key = validator_index.to_be_bytes().to_vec();
+    key.extend_from_slice(&ring_index.to_be_bytes());
+    key
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// The default number of epochs of attestations which are checked for slashable votes.
+///
+/// This is roughly 18 days on mainnet.
+pub const DEFAULT_HISTORY_LENGTH: u64 = 4_096;
+
+/// Slasher configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the slasher.
+    pub enabled: bool,
+    /// Name of the directory inside the data directory where the slasher database is located.
+    pub db_name: String,
+    /// The number of epochs of attestations which are checked for slashable votes.
+    ///
+    /// Attestations with a source epoch older than this are ignored.
+    pub history_length: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            db_name: "slasher_db".to_string(),
+            history_length: DEFAULT_HISTORY_LENGTH,
+        }
+    }
+}
+
+impl Config {
+    /// Returns an error if the config cannot be used to run a slasher.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.history_length == 0 {
+            Err("Slasher history length must be greater than zero".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,534 @@
+//! Detects slashable attestations over a long history of epochs, allowing a beacon node to act as a
+//! "slashing watchtower".
+//!
+//! Valid attestations are queued with `Slasher::accept_attestation` and checked in batches by
+//! `Slasher::process_queued`, which returns an `AttesterSlashing` for each double vote or surround
+//! vote that is found.
+//!
+//! Surround votes are found using a pair of arrays of target epochs for each validator (see
+//! `TargetArray`), so a new attestation is checked against all previous attestations in the history
+//! without loading them from the database. All data is kept in a database which is separate to the
+//! beacon chain database and is pruned as the history moves forward.
+#[macro_use]
+extern crate lazy_static;
+
+mod array;
+mod config;
+mod metrics;
+
+pub use config::{Config, DEFAULT_HISTORY_LENGTH};
+
+use array::{TargetArray, TargetArrays};
+use parking_lot::Mutex;
+use slog::{debug, info, Logger};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashSet;
+use std::mem;
+use std::path::Path;
+use store::{DBColumn, SimpleDiskStore, Store};
+use tree_hash::TreeHash;
+use types::{AttesterSlashing, Epoch, EthSpec, Hash256, IndexedAttestation};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    DatabaseError(store::Error),
+    SszDecodeError(DecodeError),
+}
+
+impl From<store::Error> for Error {
+    fn from(e: store::Error) -> Error {
+        Error::DatabaseError(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Error {
+        Error::SszDecodeError(e)
+    }
+}
+
+/// The attestation made by a validator for some target epoch.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+struct AttesterRecord {
+    target_epoch: u64,
+    /// The root of the `IndexedAttestation`.
+    attestation_root: Hash256,
+    /// The root of the `AttestationData`.
+    data_root: Hash256,
+}
+
+/// The roots of all attestations stored for some target epoch.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+struct EpochAttestations {
+    target_epoch: u64,
+    attestation_roots: Vec<Hash256>,
+}
+
+pub struct Slasher<E: EthSpec> {
+    db: SimpleDiskStore<E>,
+    attestation_queue: Mutex<Vec<IndexedAttestation<E>>>,
+    config: Config,
+    log: Logger,
+}
+
+impl<E: EthSpec> Slasher<E> {
+    /// Opens the slasher database at `path`, creating a new database if one does not already
+    /// exist.
+    pub fn open(config: Config, path: &Path, log: Logger) -> Result<Self, String> {
+        config.validate()?;
+
+        let db = SimpleDiskStore::open(path)
+            .map_err(|e| format!("Unable to open slasher database: {:?}", e))?;
+
+        info!(
+            log,
+            "Slasher started";
+            "history_length" => config.history_length,
+            "path" => format!("{}", path.display()),
+        );
+
+        Ok(Self {
+            db,
+            attestation_queue: Mutex::new(vec![]),
+            config,
+            log,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Queues a valid `attestation` to be checked by the next call to `process_queued`.
+    pub fn accept_attestation(&self, attestation: IndexedAttestation<E>) {
+        let mut queue = self.attestation_queue.lock();
+        queue.push(attestation);
+        metrics::set_gauge(&metrics::SLASHER_QUEUE_LENGTH, queue.len() as i64);
+    }
+
+    /// Checks all queued attestations against the history, returning the slashings that were
+    /// found.
+    ///
+    /// Attestations with a source epoch older than the history, or with a target epoch later than
+    /// `current_epoch`, are ignored.
+    pub fn process_queued(&self, current_epoch: Epoch) -> Result<Vec<AttesterSlashing<E>>, Error> {
+        let timer = metrics::start_timer(&metrics::SLASHER_PROCESS_TIMES);
+
+        let attestations = mem::replace(&mut *self.attestation_queue.lock(), vec![]);
+        metrics::set_gauge(&metrics::SLASHER_QUEUE_LENGTH, 0);
+
+        let current_epoch = current_epoch.as_u64();
+        let lowest_epoch = current_epoch.saturating_sub(self.config.history_length - 1);
+
+        let mut arrays = TargetArrays::new(&self.db, self.config.history_length);
+        let mut slashing_roots = HashSet::new();
+        let mut slashings = vec![];
+
+        for attestation in &attestations {
+            let source_epoch = attestation.data.source.epoch.as_u64();
+            let target_epoch = attestation.data.target.epoch.as_u64();
+
+            if source_epoch < lowest_epoch
+                || target_epoch > current_epoch
+                || source_epoch > target_epoch
+            {
+                metrics::inc_counter(&metrics::SLASHER_ATTESTATIONS_IGNORED);
+                debug!(
+                    self.log,
+                    "Slasher ignored attestation";
+                    "source_epoch" => source_epoch,
+                    "target_epoch" => target_epoch,
+                    "current_epoch" => current_epoch,
+                );
+                continue;
+            }
+
+            let attestation_root = Hash256::from_slice(&attestation.tree_hash_root());
+            self.store_attestation(attestation_root, attestation)?;
+
+            for &validator_index in attestation.attesting_indices.iter() {
+                let slashing = self.check_validator(
+                    &mut arrays,
+                    validator_index,
+                    attestation,
+                    attestation_root,
+                    lowest_epoch,
+                    current_epoch,
+                )?;
+
+                if let Some(slashing) = slashing {
+                    // Many validators may have made the same pair of attestations, however only a
+                    // single slashing is required.
+                    let roots = (
+                        Hash256::from_slice(&slashing.attestation_1.tree_hash_root()),
+                        Hash256::from_slice(&slashing.attestation_2.tree_hash_root()),
+                    );
+                    if slashing_roots.insert(roots) {
+                        slashings.push(slashing);
+                    }
+                }
+            }
+
+            metrics::inc_counter(&metrics::SLASHER_ATTESTATIONS_PROCESSED);
+        }
+
+        arrays.commit()?;
+
+        metrics::stop_timer(timer);
+
+        Ok(slashings)
+    }
+
+    /// Checks `attestation` for a double or surround vote by `validator_index`, then adds it to the
+    /// history of that validator.
+    fn check_validator(
+        &self,
+        arrays: &mut TargetArrays<E>,
+        validator_index: u64,
+        attestation: &IndexedAttestation<E>,
+        attestation_root: Hash256,
+        lowest_epoch: u64,
+        current_epoch: u64,
+    ) -> Result<Option<AttesterSlashing<E>>, Error> {
+        let source_epoch = attestation.data.source.epoch.as_u64();
+        let target_epoch = attestation.data.target.epoch.as_u64();
+        let data_root = Hash256::from_slice(&attestation.data.tree_hash_root());
+
+        if let Some(record) = self.get_attester_record(validator_index, target_epoch)? {
+            if record.data_root == data_root {
+                // The validator has already been checked for an attestation with the same data.
+                return Ok(None);
+            }
+
+            metrics::inc_counter(&metrics::SLASHER_DOUBLE_VOTES);
+            return Ok(self
+                .get_attestation(record.attestation_root)?
+                .map(|previous| AttesterSlashing {
+                    attestation_1: previous,
+                    attestation_2: attestation.clone(),
+                }));
+        }
+
+        self.put_attester_record(
+            validator_index,
+            &AttesterRecord {
+                target_epoch,
+                attestation_root,
+                data_root,
+            },
+        )?;
+
+        let mut slashing = None;
+
+        let min_target = arrays.get(TargetArray::Min, validator_index, source_epoch)?;
+        if min_target < target_epoch {
+            metrics::inc_counter(&metrics::SLASHER_SURROUND_VOTES);
+            slashing = self
+                .get_attestation_for_target(validator_index, min_target)?
+                .map(|surrounded| AttesterSlashing {
+                    attestation_1: attestation.clone(),
+                    attestation_2: surrounded,
+                });
+        }
+
+        let max_target = arrays.get(TargetArray::Max, validator_index, source_epoch)?;
+        if slashing.is_none() && max_target > target_epoch {
+            metrics::inc_counter(&metrics::SLASHER_SURROUND_VOTES);
+            slashing = self
+                .get_attestation_for_target(validator_index, max_target)?
+                .map(|surrounding| AttesterSlashing {
+                    attestation_1: surrounding,
+                    attestation_2: attestation.clone(),
+                });
+        }
+
+        // Both arrays are monotonic, so the updates stop at the first epoch which is already
+        // bounded by `target_epoch`.
+        for epoch in (lowest_epoch..source_epoch).rev() {
+            if arrays.get(TargetArray::Min, validator_index, epoch)? <= target_epoch {
+                break;
+            }
+            arrays.set(TargetArray::Min, validator_index, epoch, target_epoch)?;
+        }
+        for epoch in source_epoch + 1..=current_epoch {
+            if arrays.get(TargetArray::Max, validator_index, epoch)? >= target_epoch {
+                break;
+            }
+            arrays.set(TargetArray::Max, validator_index, epoch, target_epoch)?;
+        }
+
+        Ok(slashing)
+    }
+
+    /// Stores `attestation`, deleting the attestations from the epoch which previously shared its
+    /// place in the history.
+    fn store_attestation(
+        &self,
+        attestation_root: Hash256,
+        attestation: &IndexedAttestation<E>,
+    ) -> Result<(), Error> {
+        let attestation_column = DBColumn::SlasherAttestation.into();
+        let epoch_column = DBColumn::SlasherEpochAttestations.into();
+
+        if self
+            .db
+            .key_exists(attestation_column, attestation_root.as_bytes())?
+        {
+            return Ok(());
+        }
+
+        let target_epoch = attestation.data.target.epoch.as_u64();
+        let epoch_key = self.history_key(target_epoch);
+
+        let mut epoch_attestations = match self.db.get_bytes(epoch_column, &epoch_key)? {
+            Some(bytes) => EpochAttestations::from_ssz_bytes(&bytes)?,
+            None => EpochAttestations {
+                target_epoch,
+                attestation_roots: vec![],
+            },
+        };
+
+        if epoch_attestations.target_epoch != target_epoch {
+            for root in &epoch_attestations.attestation_roots {
+                self.db.key_delete(attestation_column, root.as_bytes())?;
+            }
+            epoch_attestations = EpochAttestations {
+                target_epoch,
+                attestation_roots: vec![],
+            };
+        }
+
+        epoch_attestations.attestation_roots.push(attestation_root);
+
+        self.db.put_bytes(
+            attestation_column,
+            attestation_root.as_bytes(),
+            &attestation.as_ssz_bytes(),
+        )?;
+        self.db
+            .put_bytes(epoch_column, &epoch_key, &epoch_attestations.as_ssz_bytes())?;
+
+        Ok(())
+    }
+
+    fn get_attestation(
+        &self,
+        attestation_root: Hash256,
+    ) -> Result<Option<IndexedAttestation<E>>, Error> {
+        self.db
+            .get_bytes(
+                DBColumn::SlasherAttestation.into(),
+                attestation_root.as_bytes(),
+            )?
+            .map(|bytes| IndexedAttestation::from_ssz_bytes(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Returns the attestation made by `validator_index` for `target_epoch`, if it is still in the
+    /// history.
+    fn get_attestation_for_target(
+        &self,
+        validator_index: u64,
+        target_epoch: u64,
+    ) -> Result<Option<IndexedAttestation<E>>, Error> {
+        match self.get_attester_record(validator_index, target_epoch)? {
+            Some(record) => self.get_attestation(record.attestation_root),
+            None => Ok(None),
+        }
+    }
+
+    fn get_attester_record(
+        &self,
+        validator_index: u64,
+        target_epoch: u64,
+    ) -> Result<Option<AttesterRecord>, Error> {
+        let record = self
+            .db
+            .get_bytes(
+                DBColumn::SlasherAttesterRecord.into(),
+                &self.attester_record_key(validator_index, target_epoch),
+            )?
+            .map(|bytes| AttesterRecord::from_ssz_bytes(&bytes))
+            .transpose()?;
+
+        // Ignore a record from an earlier epoch which shared the same place in the history.
+        Ok(record.filter(|record| record.target_epoch == target_epoch))
+    }
+
+    fn put_attester_record(
+        &self,
+        validator_index: u64,
+        record: &AttesterRecord,
+    ) -> Result<(), Error> {
+        self.db
+            .put_bytes(
+                DBColumn::SlasherAttesterRecord.into(),
+                &self.attester_record_key(validator_index, record.target_epoch),
+                &record.as_ssz_bytes(),
+            )
+            .map_err(Into::into)
+    }
+
+    fn attester_record_key(&self, validator_index: u64, target_epoch: u64) -> Vec<u8> {
+        let mut key = validator_index.to_be_bytes().to_vec();
+        key.append(&mut self.history_key(target_epoch));
+        key
+    }
+
+    /// Returns a key which is re-used for every `history_length` epochs, so that the database does
+    /// not grow as the history moves forward.
+    fn history_key(&self, epoch: u64) -> Vec<u8> {
+        (epoch % self.config.history_length).to_be_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use tempfile::{tempdir, TempDir};
+    use types::{AggregateSignature, AttestationData, Checkpoint, MinimalEthSpec, Slot};
+
+    type E = MinimalEthSpec;
+
+    fn slasher(history_length: u64) -> (Slasher<E>, TempDir) {
+        let dir = tempdir().expect("should create temp dir");
+        let config = Config {
+            enabled: true,
+            history_length,
+            ..Config::default()
+        };
+        let log = NullLoggerBuilder.build().expect("should build logger");
+        let slasher = Slasher::open(config, dir.path(), log).expect("should open slasher");
+        (slasher, dir)
+    }
+
+    fn attestation(
+        indices: Vec<u64>,
+        source: u64,
+        target: u64,
+        root: u64,
+    ) -> IndexedAttestation<E> {
+        IndexedAttestation {
+            attesting_indices: indices.into(),
+            data: AttestationData {
+                slot: Slot::new(target * E::slots_per_epoch()),
+                index: 0,
+                beacon_block_root: Hash256::from_low_u64_be(root),
+                source: Checkpoint {
+                    epoch: Epoch::new(source),
+                    root: Hash256::zero(),
+                },
+                target: Checkpoint {
+                    epoch: Epoch::new(target),
+                    root: Hash256::zero(),
+                },
+            },
+            signature: AggregateSignature::new(),
+        }
+    }
+
+    fn process(
+        slasher: &Slasher<E>,
+        attestations: Vec<IndexedAttestation<E>>,
+        current_epoch: u64,
+    ) -> Vec<AttesterSlashing<E>> {
+        for attestation in attestations {
+            slasher.accept_attestation(attestation);
+        }
+        slasher
+            .process_queued(Epoch::new(current_epoch))
+            .expect("should process attestations")
+    }
+
+    #[test]
+    fn no_slashing_for_honest_votes() {
+        let (slasher, _dir) = slasher(64);
+
+        let attestations = (1..10)
+            .map(|epoch| attestation(vec![0, 1, 2], epoch - 1, epoch, 0))
+            .collect();
+        assert!(process(&slasher, attestations, 10).is_empty());
+
+        // The same attestations again are not slashable.
+        let attestations = (1..10)
+            .map(|epoch| attestation(vec![0, 1, 2], epoch - 1, epoch, 0))
+            .collect();
+        assert!(process(&slasher, attestations, 10).is_empty());
+    }
+
+    #[test]
+    fn double_vote() {
+        let (slasher, _dir) = slasher(64);
+
+        let first = attestation(vec![0, 1], 2, 3, 0);
+        assert!(process(&slasher, vec![first.clone()], 3).is_empty());
+
+        let second = attestation(vec![1, 2], 2, 3, 1);
+        let slashings = process(&slasher, vec![second.clone()], 3);
+        assert_eq!(
+            slashings,
+            vec![AttesterSlashing {
+                attestation_1: first,
+                attestation_2: second,
+            }]
+        );
+    }
+
+    #[test]
+    fn surround_votes() {
+        let (slasher, _dir) = slasher(64);
+
+        let surrounded = attestation(vec![0], 5, 6, 0);
+        assert!(process(&slasher, vec![surrounded.clone()], 10).is_empty());
+
+        // A later attestation surrounds an earlier one.
+        let surrounding = attestation(vec![0], 4, 8, 0);
+        let slashings = process(&slasher, vec![surrounding.clone()], 10);
+        assert_eq!(
+            slashings,
+            vec![AttesterSlashing {
+                attestation_1: surrounding.clone(),
+                attestation_2: surrounded,
+            }]
+        );
+        assert!(slashings[0]
+            .attestation_1
+            .is_surround_vote(&slashings[0].attestation_2));
+
+        // A later attestation is surrounded by an earlier one.
+        let surrounded = attestation(vec![0], 6, 7, 0);
+        let slashings = process(&slasher, vec![surrounded.clone()], 10);
+        assert_eq!(
+            slashings,
+            vec![AttesterSlashing {
+                attestation_1: surrounding,
+                attestation_2: surrounded,
+            }]
+        );
+    }
+
+    #[test]
+    fn surround_vote_across_chunks() {
+        let (slasher, _dir) = slasher(256);
+
+        let surrounded = attestation(vec![3], 40, 41, 0);
+        assert!(process(&slasher, vec![surrounded], 100).is_empty());
+
+        let surrounding = attestation(vec![3], 2, 90, 0);
+        assert_eq!(process(&slasher, vec![surrounding], 100).len(), 1);
+    }
+
+    #[test]
+    fn ignores_attestations_outside_history() {
+        let (slasher, _dir) = slasher(16);
+
+        let surrounded = attestation(vec![0], 20, 21, 0);
+        assert!(process(&slasher, vec![surrounded], 30).is_empty());
+
+        // The source is older than the history.
+        let surrounding = attestation(vec![0], 10, 25, 0);
+        assert!(process(&slasher, vec![surrounding], 30).is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+pub use lighthouse_metrics::*;
+
+lazy_static! {
+    pub static ref SLASHER_QUEUE_LENGTH: Result<IntGauge> = try_create_int_gauge(
+        "slasher_queue_length",
+        "Count of attestations waiting to be processed by the slasher"
+    );
+    pub static ref SLASHER_PROCESS_TIMES: Result<Histogram> = try_create_histogram(
+        "slasher_process_seconds",
+        "Time taken to process a batch of queued attestations"
+    );
+    pub static ref SLASHER_ATTESTATIONS_PROCESSED: Result<IntCounter> = try_create_int_counter(
+        "slasher_attestations_processed_total",
+        "Count of attestations checked for slashable votes"
+    );
+    pub static ref SLASHER_ATTESTATIONS_IGNORED: Result<IntCounter> = try_create_int_counter(
+        "slasher_attestations_ignored_total",
+        "Count of attestations ignored because they fall outside the slasher history"
+    );
+    pub static ref SLASHER_DOUBLE_VOTES: Result<IntCounter> = try_create_int_counter(
+        "slasher_double_votes_total",
+        "Count of double votes found by the slasher"
+    );
+    pub static ref SLASHER_SURROUND_VOTES: Result<IntCounter> = try_create_int_counter(
+        "slasher_surround_votes_total",
+        "Count of surround votes found by the slasher"
+    );
+}
@@ -12,6 +12,22 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
         /*
          * Configuration directory locations.
          */
+        .arg(
+            Arg::with_name("config-file")
+                .long("config-file")
+                .value_name("FILE")
+                .help("A TOML file containing the configuration of the beacon node, with the \
+                       layout printed by --export-config. Flags on the command line take \
+                       precedence over the values in the file.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("export-config")
+                .long("export-config")
+                .help("Print the effective configuration (from the flags, the --config-file and \
+                       the datadir) as TOML, in the format read by --config-file, and exit.")
+                .takes_value(false)
+        )
         .arg(
             Arg::with_name("network-dir")
                 .long("network-dir")
@@ -28,12 +44,14 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
         )
         .arg(
-            Arg::with_name("testnet-dir")
-                .long("testnet-dir")
-                .value_name("DIR")
-                .help("Path to directory containing eth2_testnet specs. Defaults to \
-                      a hard-coded Lighthouse testnet. Only effective if there is no \
-                      existing database.")
+            Arg::with_name("testnet-url")
+                .long("testnet-url")
+                .value_name("URL")
+                .help("The HTTP API of a node of the testnet to join (e.g., http://10.0.0.2:5052). \
+                       Its chain spec, genesis state, deposit contract and ENRs are downloaded \
+                       to the testnet/ dir inside the beacon node dir and used in place of the \
+                       --testnet-dir. Only effective if there is no existing database. The \
+                       --spec must match that of the testnet.")
                 .takes_value(true)
         )
         /*
@@ -70,6 +88,49 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("10")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("max-inbound-peers")
+                .long("max-inbound-peers")
+                .value_name("COUNT")
+                .help("The maximum number of peers which connected to this node. Defaults to \
+                       --maxpeers. The lowest-scored peers are disconnected first.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-outbound-peers")
+                .long("max-outbound-peers")
+                .value_name("COUNT")
+                .help("The maximum number of peers which this node dialed. Defaults to \
+                       --maxpeers. The lowest-scored peers are disconnected first.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("target-outbound-peers")
+                .long("target-outbound-peers")
+                .value_name("COUNT")
+                .help("The number of peers which this node aims to dial. Whilst it has fewer, \
+                       discovery is queried for more peers. Defaults to --maxpeers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("trusted-peers")
+                .long("trusted-peers")
+                .value_name("PEER_IDS")
+                .help("A comma-separated list of peer ids which are never disconnected to \
+                       respect the peer limits.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ban-peer")
+                .long("ban-peer")
+                .value_name("PEER_ID/IP")
+                .help("Bans a peer id or IP address: peers are neither dialed nor accepted from it. \
+                       The ban is persisted and lasts until it is removed through the HTTP API. \
+                       May be used multiple times.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
         .arg(
             Arg::with_name("boot-nodes")
                 .long("boot-nodes")
@@ -102,6 +163,13 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        is determined automatically.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("execution-witness-topic")
+                .long("execution-witness-topic")
+                .help("Subscribe to the experimental gossip topic of blocks which carry an \
+                       execution witness. Requires a build with the `execution_witness` feature.")
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("libp2p-addresses")
                 .long("libp2p-addresses")
@@ -119,6 +187,48 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        automatically.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("enr-dump")
+                .long("enr-dump")
+                .help("Print the ENR that this node advertises (generating the network key and \
+                       ENR if they do not exist) and exit, without starting the node.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("record-gossip")
+                .long("record-gossip")
+                .value_name("DIR")
+                .help("Append each gossip message received (its topic, arrival time and bytes) \
+                       to a log in DIR, which may be replayed against a fresh chain with \
+                       `lcli replay-gossip`.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("allow-config-change")
+                .long("allow-config-change")
+                .help("Start even if consensus-critical settings (e.g., the spec or genesis \
+                       method) differ from those with which the datadir was created, replacing \
+                       the configs stored in the datadir.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Build and validate the beacon chain (checking that the spec and genesis \
+                       are consistent with the database), print the resolved configuration as \
+                       TOML and exit, without starting any services.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("revalidate-from-slot")
+                .long("revalidate-from-slot")
+                .value_name("SLOT")
+                .help("Replay the stored canonical chain from SLOT to the head through the full \
+                       state transition (including signatures), report the first block whose \
+                       state root differs from the replayed state and exit, without starting \
+                       any services. May take hours for long ranges.")
+                .takes_value(true),
+        )
         /* REST API related arguments */
         .arg(
             Arg::with_name("http")
@@ -142,6 +252,111 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("5052")
                 .takes_value(true),
         )
+        /* Prometheus metrics HTTP server related arguments */
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .help("Enable the Prometheus metrics HTTP server. Disabled by default.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("metrics-address")
+                .long("metrics-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the Prometheus metrics HTTP server.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("metrics-port")
+                .long("metrics-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the Prometheus metrics HTTP server.")
+                .default_value("5054")
+                .takes_value(true),
+        )
+        /* Slasher related arguments */
+        .arg(
+            Arg::with_name("slasher")
+                .long("slasher")
+                .help("Check all valid attestations for double and surround votes, publishing \
+                       any resulting slashings. Uses a separate database in the data directory. \
+                       Disabled by default.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("slasher-history-length")
+                .long("slasher-history-length")
+                .value_name("EPOCHS")
+                .help("Set the number of epochs of attestations which the slasher checks for \
+                       slashable votes.")
+                .default_value("4096")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("balance-history")
+                .long("balance-history")
+                .value_name("PUBKEYS")
+                .help("Record the balance of each of the given validators at each epoch, so it \
+                       can be queried via the HTTP API. Accepts a comma-separated list of \
+                       0x-prefixed public keys.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("block-packing-delay")
+                .long("block-packing-delay")
+                .value_name("MILLIS|adaptive")
+                .help("Specifies how many milliseconds into its slot a block is packed, allowing \
+                       late aggregate attestations to be included. With \"adaptive\", the delay \
+                       is learned from the arrival times of recent aggregates, up to \
+                       --block-packing-max-delay.")
+                .takes_value(true)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("block-packing-max-delay")
+                .long("block-packing-max-delay")
+                .value_name("MILLIS")
+                .help("Specifies the maximum adaptive --block-packing-delay, in milliseconds.")
+                .takes_value(true)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::with_name("debug-dump-invalid-blocks")
+                .long("debug-dump-invalid-blocks")
+                .help("Write the pre-state, block and error of each block which fails the state \
+                       transition to the \"debug\" directory in the datadir, so that it can be \
+                       replayed with `lcli transition-blocks`. At most one block is written per \
+                       minute.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("log-block-processing-breakdown")
+                .long("log-block-processing-breakdown")
+                .help("Log the time spent in each stage of the state transition (e.g., \
+                       process_attestations, process_rewards_and_penalties) for each imported \
+                       block, to localize performance regressions.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("cache-memory-budget")
+                .long("cache-memory-budget")
+                .value_name("MEGABYTES")
+                .help("Bound the total size of the in-memory caches of the beacon chain (e.g., \
+                       recent states, shufflings and proposers). When it is exceeded, the entries \
+                       which are cheapest to recompute are evicted first. Useful on machines with \
+                       little memory.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("monitoring-endpoint")
+                .long("monitoring-endpoint")
+                .value_name("URL")
+                .help("Send a JSON summary of the state of the node (sync status, peer count and \
+                       host memory and load) to this URL with a POST request every minute, for \
+                       hosted dashboards. Disabled by default.")
+                .takes_value(true),
+        )
         /* Websocket related arguments */
         .arg(
             Arg::with_name("ws")
@@ -149,6 +364,16 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Enable the websocket server. Disabled by default.")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("finality-updates")
+                .long("finality-updates")
+                .help("Publish a finality update to the websocket server at each finalization, \
+                       containing the headers of the finalized block and of a block attesting \
+                       to it, with a Merkle proof of the finalized root against the state of the \
+                       latter.")
+                .requires("ws")
+                .takes_value(false),
+        )
         .arg(
             Arg::with_name("ws-address")
                 .long("ws-address")
@@ -165,6 +390,31 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("5053")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("ws-tls-identity")
+                .long("ws-tls-identity")
+                .value_name("FILE")
+                .help("Serve the websocket server over TLS, using the PKCS #12 identity (a \
+                       certificate chain and its private key) in this file.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ws-tls-identity-password-file")
+                .long("ws-tls-identity-password-file")
+                .value_name("FILE")
+                .help("A file containing the password of the --ws-tls-identity.")
+                .requires("ws-tls-identity")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ws-auth-token-file")
+                .long("ws-auth-token-file")
+                .value_name("FILE")
+                .help("Require websocket clients to present the token in this file, either as a \
+                       bearer token in the Authorization header or as the \"token\" query \
+                       parameter.")
+                .takes_value(true),
+        )
 
         /*
          * Eth1 Integration
@@ -186,11 +436,69 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name("eth1-endpoint")
                 .long("eth1-endpoint")
-                .value_name("HTTP-ENDPOINT")
-                .help("Specifies the server for a web3 connection to the Eth1 chain.")
+                .value_name("ENDPOINT")
+                .help("Specifies the server for a web3 connection to the Eth1 chain. Either a HTTP \
+                       endpoint or the path of an IPC socket (e.g., ipc:///path/to/geth.ipc).")
                 .takes_value(true)
                 .default_value("https://goerli.public.sigp.io")
         )
+        .arg(
+            Arg::with_name("eth1-max-concurrent-requests")
+                .long("eth1-max-concurrent-requests")
+                .value_name("COUNT")
+                .help("The maximum number of requests in flight to the eth1 node at once. \
+                       Connections to the eth1 node are kept alive and reused between requests.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-verify-deposit-signatures")
+                .long("eth1-verify-deposit-signatures")
+                .help("If present, the signature of each deposit is verified as it is imported \
+                       from the eth1 node. Deposits with invalid signatures are logged and \
+                       flagged by the pending deposits API.")
+                .takes_value(false)
+        )
+        /* Genesis ceremony related arguments */
+        .arg(
+            Arg::with_name("genesis-ceremony-peer")
+                .long("genesis-ceremony-peer")
+                .value_name("URL")
+                .help("Takes part in a genesis ceremony with the node whose ceremony server is at \
+                       this URL (e.g., http://10.0.0.2:5055). Once the genesis state has been \
+                       computed from the deposit contract, its root is served to the other nodes \
+                       and the chain is not started until a quorum of them report the same root. \
+                       May be used multiple times.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("genesis-ceremony-quorum")
+                .long("genesis-ceremony-quorum")
+                .value_name("COUNT")
+                .help("The number of genesis ceremony peers which must report the same genesis \
+                       root before the chain is started. Defaults to all of them.")
+                .requires("genesis-ceremony-peer")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("genesis-ceremony-address")
+                .long("genesis-ceremony-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the genesis ceremony HTTP server. Defaults to \
+                       127.0.0.1.")
+                .requires("genesis-ceremony-peer")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("genesis-ceremony-port")
+                .long("genesis-ceremony-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the genesis ceremony HTTP server. Defaults \
+                       to 5055.")
+                .requires("genesis-ceremony-peer")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("slots-per-restore-point")
                 .long("slots-per-restore-point")
@@ -205,6 +513,74 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                     )
                 )
         )
+        .arg(
+            Arg::with_name("state-cache-size")
+                .long("state-cache-size")
+                .value_name("STATE_COUNT")
+                .help("Specifies the maximum number of states held in the in-memory state cache. \
+                       Set to 0 to disable the cache.")
+                .takes_value(true)
+                .default_value(
+                    Box::leak(
+                        format!("{}", StoreConfig::default().state_cache_max_states)
+                            .into_boxed_str()
+                    )
+                )
+        )
+        .arg(
+            Arg::with_name("state-cache-memory")
+                .long("state-cache-memory")
+                .value_name("MEGABYTES")
+                .help("Specifies the maximum memory used by the in-memory state cache, in \
+                       megabytes. The least-recently used states are evicted to stay within \
+                       this limit.")
+                .takes_value(true)
+                .default_value(
+                    Box::leak(
+                        format!("{}", StoreConfig::default().state_cache_max_bytes / (1024 * 1024))
+                            .into_boxed_str()
+                    )
+                )
+        )
+        .arg(
+            Arg::with_name("hot-full-state-epochs")
+                .long("hot-full-state-epochs")
+                .value_name("EPOCHS")
+                .help("Specifies the number of epochs past the latest finalized state for which \
+                       every state is stored in full. Later states which are not on an epoch \
+                       boundary are replayed from blocks when required, bounding the size of the \
+                       database when the chain is not finalizing.")
+                .takes_value(true)
+                .default_value(
+                    Box::leak(
+                        format!("{}", StoreConfig::default().hot_full_state_epochs)
+                            .into_boxed_str()
+                    )
+                )
+        )
+        /*
+         * Spec overrides.
+         */
+        .arg(
+            Arg::with_name("spec-overrides")
+                .long("spec-overrides")
+                .value_name("FILE")
+                .help("A YAML file containing some of the constants from the spec configs (e.g., \
+                       SECONDS_PER_SLOT: 2), which replace those of the testnet when creating a \
+                       new datadir. Constants which are fixed at compile time cannot be changed.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("spec-override")
+                .long("spec-override")
+                .value_name("NAME=VALUE")
+                .help("Replaces a single spec constant (e.g., ETH1_FOLLOW_DISTANCE=16) when \
+                       creating a new datadir. May be used multiple times, and takes precedence \
+                       over --spec-overrides.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
         /*
          * The "testnet" sub-command.
          *
@@ -236,6 +612,38 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                            attestations. This should only be used for testing networking elements. The \
                            value must like in the range 1-100. Default is 100.")
             )
+            /*
+             * Interop "quick start" genesis.
+             */
+            .arg(
+                Arg::with_name("interop-validator-count")
+                    .long("interop-validator-count")
+                    .value_name("INTEGER")
+                    .help("Creates a genesis state with this many deterministic interop \
+                           validators, as per the multi-client interop \"quick start\" \
+                           convention. Every client given the same flags derives the same \
+                           genesis state. Replaces the testnet method subcommand.")
+                    .takes_value(true)
+            )
+            .arg(
+                Arg::with_name("interop-eth1-genesis-time")
+                    .long("interop-eth1-genesis-time")
+                    .value_name("UNIX_EPOCH_SECONDS")
+                    .help("The timestamp of the mocked eth1 block from which the interop \
+                           genesis state is derived. Defaults to the start of the present minute.")
+                    .requires("interop-validator-count")
+                    .takes_value(true)
+            )
+            .arg(
+                Arg::with_name("interop-genesis-delay")
+                    .long("interop-genesis-delay")
+                    .value_name("SECONDS")
+                    .help("The delay between the mocked eth1 block and the genesis of the \
+                           interop chain.")
+                    .requires("interop-validator-count")
+                    .takes_value(true)
+                    .default_value("0")
+            )
             .arg(
                 Arg::with_name("slot-time")
                     .long("slot-time")
@@ -300,6 +708,33 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                     .required(true)
                     .help("A file from which to read the state"))
             )
+            /*
+             * `bootstrap`
+             *
+             * Start a new node, using a genesis state downloaded from another Lighthouse node.
+             */
+            .subcommand(SubCommand::with_name("bootstrap")
+                .about("Creates a new datadir where the genesis state is downloaded from the HTTP \
+                       API of another Lighthouse node. The server is untrusted: provide \
+                       --expected-state-root or --verify-server to check the state before using it.")
+                .arg(Arg::with_name("server")
+                    .value_name("URL")
+                    .required(true)
+                    .help("The HTTP API of the node from which to download the state, \
+                           e.g., http://localhost:5052"))
+                .arg(Arg::with_name("expected-state-root")
+                    .long("expected-state-root")
+                    .value_name("ROOT")
+                    .takes_value(true)
+                    .help("A 0x-prefixed state root, obtained from a trusted source. The node will \
+                           refuse to start if the downloaded state has any other root."))
+                .arg(Arg::with_name("verify-server")
+                    .long("verify-server")
+                    .value_name("URL")
+                    .takes_value(true)
+                    .help("The HTTP API of a second, independent node. The node will refuse to \
+                           start if its state root differs from that of the downloaded state."))
+            )
             /*
              * `prysm`
              *
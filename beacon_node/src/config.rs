@@ -1,21 +1,29 @@
+use crate::config_file::ConfigFile;
+use beacon_chain::BlockPackingDelay;
 use clap::ArgMatches;
 use client::{ClientConfig, ClientGenesis, Eth2Config};
 use eth2_config::{read_from_file, write_to_file};
-use eth2_libp2p::{Enr, Multiaddr};
+use eth2_libp2p::address::{parse_enr_list, parse_multiaddr_list, parse_peer_id_list};
+use eth2_libp2p::BanTarget;
 use eth2_testnet_config::Eth2TestnetConfig;
 use genesis::recent_genesis_time;
+use lighthouse_bootstrap::{Bootstrapper, StateVerification};
 use rand::{distributions::Alphanumeric, Rng};
-use slog::{crit, info, Logger};
+use slog::{crit, info, warn, Logger};
 use ssz::Encode;
 use std::fs;
+use std::mem;
 use std::net::Ipv4Addr;
-use std::path::PathBuf;
-use types::{Epoch, EthSpec, Fork};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use types::{Epoch, EthSpec, Fork, Hash256, PublicKeyBytes, EXECUTION_WITNESS_ENABLED};
 
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
-pub const BEACON_NODE_DIR: &str = "beacon";
+pub const BEACON_NODE_DIR: &str = lighthouse_dirs::BEACON_DIR;
 pub const NETWORK_DIR: &str = "network";
+pub const TESTNET_DIR: &str = "testnet";
+pub const DEBUG_DUMP_DIR: &str = "debug";
 
 type Result<T> = std::result::Result<T, String>;
 type Config = (ClientConfig, Eth2Config, Logger);
@@ -34,19 +42,38 @@ pub fn get_configs<E: EthSpec>(
 ) -> Result<Config> {
     let log = core_log.clone();
 
+    // The eth2 config selected by the flags (e.g., `--spec`), before any stored config is loaded.
+    let cli_eth2_config = eth2_config.clone();
+
     let mut client_config = ClientConfig::default();
 
     client_config.spec_constants = eth2_config.spec_constants.clone();
 
+    // Read the `--config-file` flag.
+    let config_file = cli_args
+        .value_of("config-file")
+        .map(|path| ConfigFile::open(Path::new(path)))
+        .transpose()?;
+
     // Read the `--datadir` flag.
     //
-    // If it's not present, try and find the home directory (`~`) and push the default data
-    // directory onto it.
+    // If it's not present, use the `data_dir` of the config file or the beacon node directory of
+    // the platform default (see `lighthouse_dirs`).
+    let file_data_dir = config_file
+        .as_ref()
+        .and_then(|file| file.get("data_dir"))
+        .map(|value| {
+            value
+                .as_str()
+                .map(PathBuf::from)
+                .ok_or_else(|| "Invalid value for data_dir in config file".to_string())
+        })
+        .transpose()?;
     client_config.data_dir = cli_args
         .value_of("datadir")
         .map(PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|home| home.join(".lighthouse").join(BEACON_NODE_DIR)))
-        .unwrap_or_else(|| PathBuf::from("."));
+        .or(file_data_dir)
+        .unwrap_or_else(|| lighthouse_dirs::default_dir(BEACON_NODE_DIR));
 
     // Load the client config, if it exists .
     let path = client_config.data_dir.join(CLIENT_CONFIG_FILENAME);
@@ -78,8 +105,34 @@ pub fn get_configs<E: EthSpec>(
         }
     }
 
+    // The configs resolved when the datadir was created, against which consensus-critical changes
+    // are checked (unless the `testnet` command is replacing the datadir).
+    let replaces_datadir = cli_args
+        .subcommand_matches("testnet")
+        .map_or(false, |args| {
+            args.is_present("force") || args.is_present("random-datadir")
+        });
+    let stored_configs = if !replaces_datadir
+        && client_config.data_dir.join(CLIENT_CONFIG_FILENAME).exists()
+        && client_config.data_dir.join(ETH2_CONFIG_FILENAME).exists()
+    {
+        Some((client_config.clone(), eth2_config.clone()))
+    } else {
+        None
+    };
+
+    // Apply the config file over the stored config, before the flags are applied over both.
+    if let Some(config_file) = &config_file {
+        let data_dir = client_config.data_dir.clone();
+        client_config = config_file.apply(&client_config)?;
+        client_config.data_dir = data_dir;
+    }
+
     // Read the `--testnet-dir` flag.
     if let Some(val) = cli_args.value_of("testnet-dir") {
+        if cli_args.is_present("testnet-url") {
+            return Err("Only one of --testnet-dir and --testnet-url may be supplied".into());
+        }
         client_config.testnet_dir = Some(PathBuf::from(val));
     }
 
@@ -89,6 +142,11 @@ pub fn get_configs<E: EthSpec>(
     // If a network dir has been specified, override the `datadir` definition.
     if let Some(dir) = cli_args.value_of("network-dir") {
         client_config.network.network_dir = PathBuf::from(dir);
+    } else if config_file
+        .as_ref()
+        .map_or(false, |file| file.get("network.network_dir").is_some())
+    {
+        // The network dir was set in the config file.
     } else {
         client_config.network.network_dir = client_config.data_dir.join(NETWORK_DIR);
     };
@@ -107,6 +165,48 @@ pub fn get_configs<E: EthSpec>(
             .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
     }
 
+    if let Some(count_str) = cli_args.value_of("max-inbound-peers") {
+        client_config.network.max_inbound_peers = Some(
+            count_str
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid number of max inbound peers: {}", count_str))?,
+        );
+    }
+
+    if let Some(count_str) = cli_args.value_of("max-outbound-peers") {
+        client_config.network.max_outbound_peers = Some(
+            count_str
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid number of max outbound peers: {}", count_str))?,
+        );
+    }
+
+    if let Some(count_str) = cli_args.value_of("target-outbound-peers") {
+        client_config.network.target_outbound_peers = Some(
+            count_str
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid number of target outbound peers: {}", count_str))?,
+        );
+    }
+
+    if let Some(trusted_peers_str) = cli_args.value_of("trusted-peers") {
+        client_config.network.trusted_peers = parse_peer_id_list(trusted_peers_str)
+            .map_err(|e| format!("Unable to parse --trusted-peers: {}", e))?;
+    }
+
+    if let Some(values) = cli_args.values_of("ban-peer") {
+        client_config.network.banned_peers = values
+            .map(|value| {
+                BanTarget::from_str(value).map_err(|e| format!("Unable to parse --ban-peer: {}", e))
+            })
+            .collect::<Result<_, _>>()?;
+    }
+
+    // The gossipsub parameters are only set in the `[network.gossipsub]` section of the config
+    // file.
+    client_config.network.gossipsub.validate()?;
+    client_config.network.gs_config = client_config.network.gossipsub.gs_config();
+
     if let Some(port_str) = cli_args.value_of("port") {
         let port = port_str
             .parse::<u16>()
@@ -116,27 +216,32 @@ pub fn get_configs<E: EthSpec>(
     }
 
     if let Some(boot_enr_str) = cli_args.value_of("boot-nodes") {
-        client_config.network.boot_nodes = boot_enr_str
-            .split(',')
-            .map(|enr| enr.parse().map_err(|_| format!("Invalid ENR: {}", enr)))
-            .collect::<Result<Vec<Enr>>>()?;
+        client_config.network.boot_nodes = parse_enr_list(boot_enr_str)
+            .map_err(|e| format!("Unable to parse --boot-nodes: {}", e))?;
     }
 
     if let Some(libp2p_addresses_str) = cli_args.value_of("libp2p-addresses") {
-        client_config.network.libp2p_nodes = libp2p_addresses_str
-            .split(',')
-            .map(|multiaddr| {
-                multiaddr
-                    .parse()
-                    .map_err(|_| format!("Invalid Multiaddr: {}", multiaddr))
-            })
-            .collect::<Result<Vec<Multiaddr>>>()?;
+        client_config.network.libp2p_nodes = parse_multiaddr_list(libp2p_addresses_str)
+            .map_err(|e| format!("Unable to parse --libp2p-addresses: {}", e))?;
     }
 
     if let Some(topics_str) = cli_args.value_of("topics") {
         client_config.network.topics = topics_str.split(',').map(|s| s.into()).collect();
     }
 
+    if let Some(dir) = cli_args.value_of("record-gossip") {
+        client_config.network.record_gossip_dir = Some(PathBuf::from(dir));
+    }
+
+    if cli_args.is_present("execution-witness-topic") {
+        if !EXECUTION_WITNESS_ENABLED {
+            return Err("The execution witness topic requires a build with the \
+                 `execution_witness` feature"
+                .into());
+        }
+        client_config.network.execution_witness_topic = true;
+    }
+
     if let Some(discovery_address_str) = cli_args.value_of("discovery-address") {
         client_config.network.discovery_address = discovery_address_str
             .parse()
@@ -173,6 +278,93 @@ pub fn get_configs<E: EthSpec>(
             .map_err(|_| "http-port is not a valid u16.")?;
     }
 
+    /*
+     * Prometheus metrics HTTP server
+     */
+
+    if cli_args.is_present("metrics") {
+        client_config.http_metrics.enabled = true;
+    }
+
+    if let Some(address) = cli_args.value_of("metrics-address") {
+        client_config.http_metrics.listen_address = address
+            .parse::<Ipv4Addr>()
+            .map_err(|_| "metrics-address is not a valid IPv4 address.")?;
+    }
+
+    if let Some(port) = cli_args.value_of("metrics-port") {
+        client_config.http_metrics.port = port
+            .parse::<u16>()
+            .map_err(|_| "metrics-port is not a valid u16.")?;
+    }
+
+    /*
+     * Slasher
+     */
+
+    if cli_args.is_present("slasher") {
+        client_config.slasher.enabled = true;
+    }
+
+    if let Some(history_length) = cli_args.value_of("slasher-history-length") {
+        client_config.slasher.history_length = history_length
+            .parse::<u64>()
+            .map_err(|_| "slasher-history-length is not a valid u64.")?;
+    }
+
+    /*
+     * Balance history
+     */
+
+    if let Some(pubkeys) = cli_args.value_of("balance-history") {
+        client_config.balance_history = pubkeys
+            .split(',')
+            .map(parse_pubkey)
+            .collect::<Result<_>>()?;
+    }
+
+    /*
+     * Block packing
+     */
+
+    if let Some(delay) = cli_args.value_of("block-packing-delay") {
+        client_config.block_packing_delay = if delay == "adaptive" {
+            let max_millis = cli_args
+                .value_of("block-packing-max-delay")
+                .unwrap_or("1000")
+                .parse::<u64>()
+                .map_err(|_| "block-packing-max-delay is not a valid u64.")?;
+
+            BlockPackingDelay::Adaptive { max_millis }
+        } else {
+            let millis = delay
+                .parse::<u64>()
+                .map_err(|_| "block-packing-delay must be a u64 or \"adaptive\".")?;
+
+            BlockPackingDelay::Fixed { millis }
+        };
+    }
+
+    if cli_args.is_present("debug-dump-invalid-blocks") {
+        client_config.debug_dump_dir = Some(client_config.data_dir.join(DEBUG_DUMP_DIR));
+    }
+
+    if cli_args.is_present("log-block-processing-breakdown") {
+        client_config.log_block_processing_breakdown = true;
+    }
+
+    if let Some(megabytes) = cli_args.value_of("cache-memory-budget") {
+        let megabytes = megabytes
+            .parse::<usize>()
+            .map_err(|_| "cache-memory-budget is not a valid usize.")?;
+
+        client_config.cache_memory_budget = Some(megabytes * 1024 * 1024);
+    }
+
+    if let Some(endpoint) = cli_args.value_of("monitoring-endpoint") {
+        client_config.monitoring_endpoint = Some(endpoint.to_string());
+    }
+
     /*
      * Websocket server
      */
@@ -181,6 +373,10 @@ pub fn get_configs<E: EthSpec>(
         client_config.websocket_server.enabled = true;
     }
 
+    if cli_args.is_present("finality-updates") {
+        client_config.finality_updates = true;
+    }
+
     if let Some(address) = cli_args.value_of("ws-address") {
         client_config.websocket_server.listen_address = address
             .parse::<Ipv4Addr>()
@@ -193,6 +389,18 @@ pub fn get_configs<E: EthSpec>(
             .map_err(|_| "ws-port is not a valid u16.")?;
     }
 
+    if let Some(path) = cli_args.value_of("ws-tls-identity") {
+        client_config.websocket_server.tls_identity_file = Some(PathBuf::from(path));
+    }
+
+    if let Some(path) = cli_args.value_of("ws-tls-identity-password-file") {
+        client_config.websocket_server.tls_identity_password_file = Some(PathBuf::from(path));
+    }
+
+    if let Some(path) = cli_args.value_of("ws-auth-token-file") {
+        client_config.websocket_server.auth_token_file = Some(PathBuf::from(path));
+    }
+
     /*
      * Eth1
      */
@@ -216,10 +424,67 @@ pub fn get_configs<E: EthSpec>(
         client_config.eth1.endpoint = val.to_string();
     }
 
-    match cli_args.subcommand() {
-        ("testnet", Some(sub_cmd_args)) => {
-            process_testnet_subcommand(&mut client_config, &mut eth2_config, sub_cmd_args)?
+    if let Some(count_str) = cli_args.value_of("eth1-max-concurrent-requests") {
+        client_config.eth1.max_concurrent_requests = count_str
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid eth1-max-concurrent-requests: {}", count_str))?;
+    }
+
+    if cli_args.is_present("eth1-verify-deposit-signatures") {
+        client_config.eth1.verify_deposit_signatures = true;
+    }
+
+    /*
+     * Genesis ceremony
+     */
+
+    if let Some(peers) = cli_args.values_of("genesis-ceremony-peer") {
+        let mut ceremony = client_config.genesis_ceremony.clone().unwrap_or_default();
+        ceremony.peers = peers.map(String::from).collect();
+        ceremony.quorum = ceremony.peers.len();
+
+        if let Some(quorum) = cli_args.value_of("genesis-ceremony-quorum") {
+            ceremony.quorum = quorum
+                .parse::<usize>()
+                .map_err(|_| "genesis-ceremony-quorum is not a valid usize.")?;
         }
+
+        if let Some(address) = cli_args.value_of("genesis-ceremony-address") {
+            ceremony.listen_address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "genesis-ceremony-address is not a valid IPv4 address.")?;
+        }
+
+        if let Some(port) = cli_args.value_of("genesis-ceremony-port") {
+            ceremony.port = port
+                .parse::<u16>()
+                .map_err(|_| "genesis-ceremony-port is not a valid u16.")?;
+        }
+
+        if ceremony.quorum > ceremony.peers.len() {
+            return Err(format!(
+                "genesis-ceremony-quorum ({}) exceeds the number of peers ({})",
+                ceremony.quorum,
+                ceremony.peers.len()
+            ));
+        }
+
+        client_config.genesis_ceremony = Some(ceremony);
+    }
+
+    /*
+     * Spec overrides
+     */
+
+    let spec_overrides = spec_overrides(cli_args)?;
+
+    match cli_args.subcommand() {
+        ("testnet", Some(sub_cmd_args)) => process_testnet_subcommand::<E>(
+            &mut client_config,
+            &mut eth2_config,
+            sub_cmd_args,
+            &spec_overrides,
+        )?,
         // No sub-command assumes a resume operation.
         _ => {
             // If no primary subcommand was given, start the beacon chain from an existing
@@ -234,13 +499,32 @@ pub fn get_configs<E: EthSpec>(
                     "Starting from an empty database";
                     "data_dir" => format!("{:?}", client_config.data_dir)
                 );
-                init_new_client::<E>(&mut client_config, &mut eth2_config)?
+                init_new_client::<E>(
+                    &mut client_config,
+                    &mut eth2_config,
+                    cli_args.value_of("testnet-url"),
+                    &spec_overrides,
+                    &log,
+                )?
             } else {
                 info!(
                     log,
                     "Resuming from existing datadir";
                     "data_dir" => format!("{:?}", client_config.data_dir)
                 );
+                for flag in &["testnet-dir", "testnet-url"] {
+                    if cli_args.is_present(flag) {
+                        warn!(
+                            log,
+                            "Ignoring --{}", flag;
+                            "reason" => "the testnet is only loaded when the datadir is created"
+                        );
+                    }
+                }
+                // Spec overrides are checked against the stored spec, below.
+                for spec_override in &spec_overrides {
+                    eth2_config.spec = eth2_config.spec.with_overrides::<E>(spec_override)?;
+                }
                 // If the `testnet` command was not provided, attempt to load an existing datadir and
                 // continue with an existing chain.
                 load_from_datadir(&mut client_config)?
@@ -258,6 +542,68 @@ pub fn get_configs<E: EthSpec>(
             .map_err(|_| "slots-per-restore-point is not a valid integer".to_string())?;
     }
 
+    if let Some(state_cache_size) = cli_args.value_of("state-cache-size") {
+        client_config.store.state_cache_max_states = state_cache_size
+            .parse()
+            .map_err(|_| "state-cache-size is not a valid integer".to_string())?;
+    }
+
+    if let Some(state_cache_memory) = cli_args.value_of("state-cache-memory") {
+        let megabytes: usize = state_cache_memory
+            .parse()
+            .map_err(|_| "state-cache-memory is not a valid integer".to_string())?;
+        client_config.store.state_cache_max_bytes = megabytes * 1024 * 1024;
+    }
+
+    if let Some(hot_full_state_epochs) = cli_args.value_of("hot-full-state-epochs") {
+        client_config.store.hot_full_state_epochs = hot_full_state_epochs
+            .parse()
+            .map_err(|_| "hot-full-state-epochs is not a valid integer".to_string())?;
+    }
+
+    if let Some((stored_client_config, stored_eth2_config)) = stored_configs {
+        let changes = consensus_config_changes(
+            (&stored_client_config, &stored_eth2_config),
+            (&client_config, &eth2_config),
+            &cli_eth2_config.spec_constants,
+        );
+
+        if changes.is_empty() {
+            // Re-running the `testnet` command on its existing datadir resumes its chain.
+            if let ("testnet", Some(_)) = cli_args.subcommand() {
+                load_from_datadir(&mut client_config)?;
+            }
+        } else if cli_args.is_present("allow-config-change") {
+            for change in &changes {
+                warn!(
+                    log,
+                    "Consensus-critical config changed";
+                    "change" => change,
+                );
+            }
+
+            if cli_eth2_config.spec_constants != stored_eth2_config.spec_constants {
+                client_config.spec_constants = cli_eth2_config.spec_constants.clone();
+                eth2_config = cli_eth2_config;
+            }
+
+            // Resuming does not change the genesis method of the datadir.
+            let mut new_client_config = client_config.clone();
+            if let ClientGenesis::Resume = new_client_config.genesis {
+                new_client_config.genesis = stored_client_config.genesis;
+            }
+
+            write_configs(&new_client_config, &eth2_config)?;
+        } else {
+            return Err(format!(
+                "The datadir {:?} was created with a different config ({}). Use a different \
+                 --datadir, or --allow-config-change to start anyway.",
+                client_config.data_dir,
+                changes.join("; ")
+            ));
+        }
+    }
+
     if eth2_config.spec_constants != client_config.spec_constants {
         crit!(log, "Specification constants do not match.";
               "client_config" => client_config.spec_constants.to_string(),
@@ -266,6 +612,10 @@ pub fn get_configs<E: EthSpec>(
         return Err("Specification constant mismatch".into());
     }
 
+    // Refuse an eth1 node on any network other than that of the deposit contract of the testnet.
+    client_config.eth1.chain_id = eth2_config.eth1_chain_id;
+    client_config.eth1.network_id = eth2_config.eth1_network_id;
+
     /*
      * Zero-ports
      *
@@ -276,11 +626,64 @@ pub fn get_configs<E: EthSpec>(
         client_config.network.discovery_port = 0;
         client_config.rest_api.port = 0;
         client_config.websocket_server.port = 0;
+        client_config.http_metrics.port = 0;
+        if let Some(ceremony) = client_config.genesis_ceremony.as_mut() {
+            ceremony.port = 0;
+        }
     }
 
     Ok((client_config, eth2_config, log))
 }
 
+/// Returns a description of each consensus-critical setting of the `resolved` configs which
+/// differs from the `stored` configs of the datadir.
+///
+/// The `cli_spec_constants` are those selected by the `--spec` flag, which are not otherwise
+/// reflected in the `resolved` configs.
+fn consensus_config_changes(
+    stored: (&ClientConfig, &Eth2Config),
+    resolved: (&ClientConfig, &Eth2Config),
+    cli_spec_constants: &str,
+) -> Vec<String> {
+    let (stored_client_config, stored_eth2_config) = stored;
+    let (client_config, eth2_config) = resolved;
+    let mut changes = vec![];
+
+    if cli_spec_constants != stored_eth2_config.spec_constants {
+        changes.push(format!(
+            "spec preset is {}, not {}",
+            cli_spec_constants, stored_eth2_config.spec_constants
+        ));
+    }
+
+    if eth2_config.spec != stored_eth2_config.spec {
+        changes.push("spec differs".to_string());
+    }
+
+    // Resuming is consistent with any genesis method.
+    let resuming = match client_config.genesis {
+        ClientGenesis::Resume => true,
+        _ => false,
+    };
+    if !resuming
+        && mem::discriminant(&client_config.genesis)
+            != mem::discriminant(&stored_client_config.genesis)
+    {
+        changes.push("genesis method differs".to_string());
+    }
+
+    if client_config.testnet_dir.is_some()
+        && client_config.testnet_dir != stored_client_config.testnet_dir
+    {
+        changes.push(format!(
+            "testnet dir is {:?}, not {:?}",
+            client_config.testnet_dir, stored_client_config.testnet_dir
+        ));
+    }
+
+    changes
+}
+
 /// Load from an existing database.
 fn load_from_datadir(client_config: &mut ClientConfig) -> Result<()> {
     // Check to ensure the datadir exists.
@@ -312,31 +715,60 @@ fn load_from_datadir(client_config: &mut ClientConfig) -> Result<()> {
 }
 
 /// Create a new client with the default configuration.
+///
+/// The testnet is downloaded from the HTTP API at `testnet_url`, if supplied, and written to the
+/// datadir.
 fn init_new_client<E: EthSpec>(
     client_config: &mut ClientConfig,
     eth2_config: &mut Eth2Config,
+    testnet_url: Option<&str>,
+    spec_overrides: &[String],
+    log: &Logger,
 ) -> Result<()> {
-    let eth2_testnet_config: Eth2TestnetConfig<E> =
-        if let Some(testnet_dir) = &client_config.testnet_dir {
-            Eth2TestnetConfig::load(testnet_dir.clone())
-                .map_err(|e| format!("Unable to open testnet dir at {:?}: {}", testnet_dir, e))?
-        } else {
-            Eth2TestnetConfig::hard_coded()
-                .map_err(|e| format!("Unable to load hard-coded testnet dir: {}", e))?
-        };
+    let downloaded_testnet: Option<Eth2TestnetConfig<E>> = testnet_url
+        .map(|url| {
+            let testnet = Bootstrapper::connect(url.to_string(), log)?.testnet()?;
+
+            info!(
+                log,
+                "Downloaded testnet";
+                "boot_nodes" => testnet.boot_enr.as_ref().map_or(0, Vec::len),
+                "deposit_contract" => &testnet.deposit_contract_address,
+                "url" => url,
+            );
+
+            client_config.testnet_dir = Some(client_config.data_dir.join(TESTNET_DIR));
+
+            Ok::<_, String>(testnet)
+        })
+        .transpose()?;
+
+    let eth2_testnet_config: Eth2TestnetConfig<E> = if let Some(testnet) = &downloaded_testnet {
+        testnet.clone()
+    } else if let Some(testnet_dir) = &client_config.testnet_dir {
+        Eth2TestnetConfig::load(testnet_dir.clone())
+            .map_err(|e| format!("Unable to open testnet dir at {:?}: {}", testnet_dir, e))?
+    } else {
+        Eth2TestnetConfig::hard_coded()
+            .map_err(|e| format!("Unable to load hard-coded testnet dir: {}", e))?
+    };
 
     eth2_config.spec = eth2_testnet_config
-        .yaml_config
-        .as_ref()
-        .ok_or_else(|| "The testnet directory must contain a spec config".to_string())?
-        .apply_to_chain_spec::<E>(&eth2_config.spec)
-        .ok_or_else(|| {
+        .chain_spec(&eth2_config.spec)
+        .map_err(|e| {
             format!(
-                "The loaded config is not compatible with the {} spec",
-                &eth2_config.spec_constants
+                "Invalid testnet dir for the {} spec: {}",
+                eth2_config.spec_constants, e
             )
         })?;
 
+    for spec_override in spec_overrides {
+        eth2_config.spec = eth2_config.spec.with_overrides::<E>(spec_override)?;
+    }
+
+    eth2_config.eth1_chain_id = eth2_testnet_config.deposit_chain_id;
+    eth2_config.eth1_network_id = eth2_testnet_config.deposit_network_id;
+
     let spec = &mut eth2_config.spec;
 
     client_config.eth1.deposit_contract_address =
@@ -371,6 +803,12 @@ fn init_new_client<E: EthSpec>(
 
     create_new_datadir(&client_config, &eth2_config)?;
 
+    if let (Some(testnet), Some(testnet_dir)) = (downloaded_testnet, &client_config.testnet_dir) {
+        testnet
+            .write_to_file(testnet_dir.clone())
+            .map_err(|e| format!("Unable to write testnet dir at {:?}: {}", testnet_dir, e))?;
+    }
+
     Ok(())
 }
 
@@ -386,19 +824,19 @@ pub fn create_new_datadir(client_config: &ClientConfig, eth2_config: &Eth2Config
     }
 
     // Create `datadir` and any non-existing parent directories.
-    fs::create_dir_all(&client_config.data_dir)
+    lighthouse_dirs::create_dir_restricted(&client_config.data_dir)
         .map_err(|e| format!("Failed to create data dir: {}", e))?;
 
+    write_configs(client_config, eth2_config)
+}
+
+/// Writes the configs to `client_config.data_dir`, replacing any existing configs.
+fn write_configs(client_config: &ClientConfig, eth2_config: &Eth2Config) -> Result<()> {
     macro_rules! write_to_file {
         ($file: ident, $variable: ident) => {
-            let file = client_config.data_dir.join($file);
-            if file.exists() {
-                return Err(format!("Datadir is not clean, {} exists.", $file));
-            } else {
-                // Write the onfig to a TOML file in the datadir.
-                write_to_file(client_config.data_dir.join($file), $variable)
-                    .map_err(|e| format!("Unable to write {} file: {:?}", $file, e))?;
-            }
+            // Write the config to a TOML file in the datadir.
+            write_to_file(client_config.data_dir.join($file), $variable)
+                .map_err(|e| format!("Unable to write {} file: {:?}", $file, e))?;
         };
     }
 
@@ -409,10 +847,11 @@ pub fn create_new_datadir(client_config: &ClientConfig, eth2_config: &Eth2Config
 }
 
 /// Process the `testnet` CLI subcommand arguments, updating the `builder`.
-fn process_testnet_subcommand(
+fn process_testnet_subcommand<E: EthSpec>(
     client_config: &mut ClientConfig,
     eth2_config: &mut Eth2Config,
     cli_args: &ArgMatches,
+    spec_overrides: &[String],
 ) -> Result<()> {
     // Specifies that a random datadir should be used.
     if cli_args.is_present("random-datadir") {
@@ -455,6 +894,40 @@ fn process_testnet_subcommand(
 
     // Start matching on the second subcommand (e.g., `testnet bootstrap ...`).
     match cli_args.subcommand() {
+        (cmd, _) if cli_args.is_present("interop-validator-count") => {
+            if !cmd.is_empty() {
+                return Err(format!(
+                    "The {} method cannot be used with --interop-validator-count",
+                    cmd
+                ));
+            }
+
+            let validator_count = cli_args
+                .value_of("interop-validator-count")
+                .ok_or_else(|| "No interop-validator-count specified")?
+                .parse::<usize>()
+                .map_err(|e| format!("Unable to parse interop-validator-count: {:?}", e))?;
+
+            let eth1_genesis_time = match cli_args.value_of("interop-eth1-genesis-time") {
+                Some(time) => time
+                    .parse::<u64>()
+                    .map_err(|e| format!("Unable to parse interop-eth1-genesis-time: {:?}", e))?,
+                None => recent_genesis_time(1),
+            };
+
+            let genesis_delay = cli_args
+                .value_of("interop-genesis-delay")
+                .ok_or_else(|| "No interop-genesis-delay specified")?
+                .parse::<u64>()
+                .map_err(|e| format!("Unable to parse interop-genesis-delay: {:?}", e))?;
+
+            client_config.dummy_eth1_backend = true;
+
+            client_config.genesis = ClientGenesis::Interop {
+                validator_count,
+                genesis_time: interop_genesis_time(eth1_genesis_time, genesis_delay)?,
+            };
+        }
         ("recent", Some(cli_args)) => {
             let validator_count = cli_args
                 .value_of("validator_count")
@@ -513,6 +986,26 @@ fn process_testnet_subcommand(
 
             client_config.genesis = start_method;
         }
+        ("bootstrap", Some(cli_args)) => {
+            let server = cli_args
+                .value_of("server")
+                .ok_or_else(|| "No bootstrap server specified")?
+                .to_string();
+
+            let expected_state_root = cli_args
+                .value_of("expected-state-root")
+                .map(parse_state_root)
+                .transpose()?;
+
+            client_config.genesis = ClientGenesis::RemoteNode {
+                server,
+                port: None,
+                verification: StateVerification {
+                    expected_state_root,
+                    verify_server: cli_args.value_of("verify-server").map(String::from),
+                },
+            };
+        }
         ("prysm", Some(_)) => {
             let mut spec = &mut eth2_config.spec;
 
@@ -544,14 +1037,144 @@ fn process_testnet_subcommand(
         _ => return Err("No testnet method specified. See 'testnet --help'.".into()),
     };
 
-    create_new_datadir(&client_config, &eth2_config)?;
+    for spec_override in spec_overrides {
+        eth2_config.spec = eth2_config.spec.with_overrides::<E>(spec_override)?;
+    }
+
+    // An existing datadir is resumed, if its configs are consistent (see `get_configs`).
+    if !client_config.data_dir.join(CLIENT_CONFIG_FILENAME).exists() {
+        create_new_datadir(&client_config, &eth2_config)?;
+    }
 
     Ok(())
 }
 
+/// Returns the genesis time of an interop "quick start" chain whose mocked eth1 genesis block has
+/// the timestamp `eth1_genesis_time`.
+fn interop_genesis_time(eth1_genesis_time: u64, genesis_delay: u64) -> Result<u64> {
+    eth1_genesis_time
+        .checked_add(genesis_delay)
+        .ok_or_else(|| "The interop genesis time overflows a u64".to_string())
+}
+
+/// Reads the `--spec-overrides` and `--spec-override` flags into YAML mappings, in the order in
+/// which they should be applied to the spec.
+fn spec_overrides(cli_args: &ArgMatches) -> Result<Vec<String>> {
+    let mut overrides = vec![];
+
+    if let Some(path) = cli_args.value_of("spec-overrides") {
+        overrides.push(
+            fs::read_to_string(path)
+                .map_err(|e| format!("Unable to read spec overrides at {}: {:?}", path, e))?,
+        );
+    }
+
+    if let Some(values) = cli_args.values_of("spec-override") {
+        for value in values {
+            let mut split = value.splitn(2, '=');
+            match (split.next(), split.next()) {
+                (Some(name), Some(value)) => overrides.push(format!("{}: {}", name, value)),
+                _ => {
+                    return Err(format!(
+                        "Invalid spec-override, expected NAME=VALUE: {}",
+                        value
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(overrides)
+}
+
 fn random_string(len: usize) -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
         .take(len)
         .collect::<String>()
 }
+
+/// Parses a 0x-prefixed, hex-encoded validator public key.
+fn parse_pubkey(string: &str) -> Result<PublicKeyBytes> {
+    let bytes = hex::decode(string.trim().trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid hex in public key {}: {:?}", string, e))?;
+
+    PublicKeyBytes::from_bytes(&bytes)
+        .map_err(|e| format!("Invalid public key {}: {:?}", string, e))
+}
+
+/// Parses a 0x-prefixed, hex-encoded state root.
+fn parse_state_root(string: &str) -> Result<Hash256> {
+    let bytes = hex::decode(string.trim().trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid hex in state root {}: {:?}", string, e))?;
+
+    if bytes.len() == 32 {
+        Ok(Hash256::from_slice(&bytes))
+    } else {
+        Err(format!(
+            "Invalid state root {}: expected 32 bytes, got {}",
+            string,
+            bytes.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_consensus_config_changes() {
+        let client_config = ClientConfig::default();
+        let eth2_config = Eth2Config::minimal();
+        let stored = (&client_config, &eth2_config);
+
+        assert!(
+            consensus_config_changes(stored, stored, "minimal").is_empty(),
+            "identical configs should not change"
+        );
+        assert_eq!(consensus_config_changes(stored, stored, "mainnet").len(), 1);
+
+        let mut resumed_client_config = client_config.clone();
+        resumed_client_config.genesis = ClientGenesis::Resume;
+        let mut other_eth2_config = eth2_config.clone();
+        other_eth2_config.spec.milliseconds_per_slot += 1;
+        assert_eq!(
+            consensus_config_changes(
+                stored,
+                (&resumed_client_config, &other_eth2_config),
+                "minimal"
+            ),
+            vec!["spec differs".to_string()],
+            "resuming should not change the genesis method"
+        );
+
+        let mut other_client_config = client_config.clone();
+        other_client_config.genesis = ClientGenesis::SszFile {
+            path: PathBuf::from("genesis.ssz"),
+        };
+        other_client_config.testnet_dir = Some(PathBuf::from("testnet"));
+        assert_eq!(
+            consensus_config_changes(stored, (&other_client_config, &eth2_config), "minimal").len(),
+            2
+        );
+    }
+
+    #[test]
+    fn parses_state_roots() {
+        let root = format!("0x{}", "ab".repeat(32));
+        assert_eq!(
+            parse_state_root(&root),
+            Ok(Hash256::from_slice(&[0xab; 32]))
+        );
+        assert!(parse_state_root("0xabcd").is_err());
+        assert!(parse_state_root("0xzz").is_err());
+    }
+
+    #[test]
+    fn delays_interop_genesis() {
+        assert_eq!(interop_genesis_time(1_578_009_600, 0), Ok(1_578_009_600));
+        assert_eq!(interop_genesis_time(1_578_009_600, 300), Ok(1_578_009_900));
+        assert!(interop_genesis_time(u64::max_value(), 1).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! Reads the configuration of the beacon node from a TOML file, supplied with `--config-file`.
+//!
+//! The file has the same layout as the `ClientConfig` (e.g., as printed by `--export-config`),
+//! with top-level keys such as `data_dir` and sections such as `[network]`, `[eth1]`,
+//! `[rest_api]` and `[store]`. Any key may be omitted, in which case its value is taken from the
+//! config stored in the datadir (or the defaults). Flags given on the command line take
+//! precedence over the file.
+use client::ClientConfig;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::{Table, Value};
+
+/// A parsed configuration file, which is yet to be applied to a `ClientConfig`.
+pub struct ConfigFile {
+    path: PathBuf,
+    table: Table,
+}
+
+impl ConfigFile {
+    /// Reads and parses the file at `path`.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read config file {:?}: {}", path, e))?;
+
+        Self::from_str(path, &contents)
+    }
+
+    fn from_str(path: &Path, contents: &str) -> Result<Self, String> {
+        let table = contents
+            .parse::<Value>()
+            .map_err(|e| format!("Unable to parse config file {:?}: {}", path, e))?
+            .as_table()
+            .cloned()
+            .ok_or_else(|| format!("Config file {:?} is not a TOML table", path))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    /// Returns the value of the (dotted) `key`, if it is set in the file.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let mut parts = key.split('.');
+        let first = self.table.get(parts.next()?)?;
+        parts.try_fold(first, |value, part| value.get(part))
+    }
+
+    /// Returns a copy of `config` with each of the keys in the file replaced by its value.
+    ///
+    /// Returns an error naming the offending key if a key is not a field of the `ClientConfig`,
+    /// or if its value is invalid.
+    pub fn apply(&self, config: &ClientConfig) -> Result<ClientConfig, String> {
+        let base = encode(config)?;
+        let keys = overrides(&base, &self.table, "");
+
+        let mut merged = base.clone();
+        for (key, value) in &keys {
+            set(&mut merged, key, value.clone());
+        }
+
+        let applied: ClientConfig = match Value::Table(merged).try_into() {
+            Ok(applied) => applied,
+            Err(e) => {
+                // Apply each key on its own, to find the one which is invalid.
+                for (key, value) in &keys {
+                    let mut single = base.clone();
+                    set(&mut single, key, value.clone());
+
+                    if let Err(e) = Value::Table(single).try_into::<ClientConfig>() {
+                        return Err(format!(
+                            "Invalid value for {} in config file {:?}: {}",
+                            key, self.path, e
+                        ));
+                    }
+                }
+
+                return Err(format!("Invalid config file {:?}: {}", self.path, e));
+            }
+        };
+
+        // Keys which are not fields of the `ClientConfig` are dropped when it is decoded.
+        let encoded = encode(&applied)?;
+        for (key, _) in &keys {
+            if get(&encoded, key).is_none() {
+                return Err(format!(
+                    "Unknown key {} in config file {:?}",
+                    key, self.path
+                ));
+            }
+        }
+
+        Ok(applied)
+    }
+}
+
+/// Returns `config` as a TOML table, in the format of a config file.
+pub fn encode(config: &ClientConfig) -> Result<Table, String> {
+    match Value::try_from(config) {
+        Ok(Value::Table(table)) => Ok(table),
+        Ok(_) => Err("Client config is not a TOML table".to_string()),
+        Err(e) => Err(format!("Unable to encode client config: {:?}", e)),
+    }
+}
+
+/// Returns the (dotted) key and value of each value in `overlay` which replaces a value of
+/// `base`.
+///
+/// Tables in `overlay` are merged into the corresponding tables of `base`, except for those which
+/// replace an enum (a table with a single key, e.g., `{ Fixed = { millis = 0 } }`) with a
+/// different variant, which replace the whole table.
+fn overrides(base: &Table, overlay: &Table, prefix: &str) -> Vec<(String, Value)> {
+    let mut keys = vec![];
+
+    for (name, value) in overlay {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+
+        match (base.get(name), value) {
+            (Some(Value::Table(base_table)), Value::Table(overlay_table))
+                if !is_other_variant(base_table, overlay_table) =>
+            {
+                keys.append(&mut overrides(base_table, overlay_table, &key))
+            }
+            _ => keys.push((key, value.clone())),
+        }
+    }
+
+    keys
+}
+
+/// Returns `true` if `base` is an enum variant and `overlay` is a different variant.
+fn is_other_variant(base: &Table, overlay: &Table) -> bool {
+    base.len() == 1 && overlay.keys().any(|name| !base.contains_key(name))
+}
+
+fn get<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let first = table.get(parts.next()?)?;
+    parts.try_fold(first, |value, part| value.get(part))
+}
+
+/// Sets the (dotted) `key` of `table` to `value`, creating any tables on the way.
+fn set(table: &mut Table, key: &str, value: Value) {
+    let mut parts = key.split('.').collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return,
+    };
+
+    let mut current = table;
+    for part in parts {
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        if !entry.is_table() {
+            *entry = Value::Table(Table::new());
+        }
+        current = match entry {
+            Value::Table(next) => next,
+            _ => return,
+        };
+    }
+
+    current.insert(last.to_string(), value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beacon_chain::BlockPackingDelay;
+
+    fn config_file(contents: &str) -> ConfigFile {
+        ConfigFile::from_str(Path::new("beacon.toml"), contents).expect("should parse file")
+    }
+
+    #[test]
+    fn overrides_keys_in_file() {
+        let mut config = ClientConfig::default();
+        config.network.max_peers = 10;
+        config.store.slots_per_restore_point = 64;
+
+        let file = config_file(
+            r#"
+            log_block_processing_breakdown = true
+
+            [network]
+            libp2p_port = 9500
+            max_inbound_peers = 5
+
+            [rest_api]
+            port = 5500
+
+            [block_packing_delay.Fixed]
+            millis = 250
+            "#,
+        );
+        let applied = file.apply(&config).expect("should apply file");
+
+        assert!(applied.log_block_processing_breakdown);
+        assert_eq!(applied.network.libp2p_port, 9500);
+        assert_eq!(applied.network.max_inbound_peers, Some(5));
+        assert_eq!(applied.rest_api.port, 5500);
+        assert_eq!(
+            applied.block_packing_delay,
+            BlockPackingDelay::Fixed { millis: 250 }
+        );
+        assert_eq!(applied.network.max_peers, 10, "omitted keys should be kept");
+        assert_eq!(applied.store.slots_per_restore_point, 64);
+        assert_eq!(file.get("network.libp2p_port"), Some(&Value::Integer(9500)));
+        assert_eq!(file.get("network.max_peers"), None);
+    }
+
+    #[test]
+    fn names_invalid_keys() {
+        let config = ClientConfig::default();
+
+        let unknown = config_file("[network]\nlibp2p_prot = 9500\n")
+            .apply(&config)
+            .expect_err("should reject unknown key");
+        assert!(unknown.contains("network.libp2p_prot"), "{}", unknown);
+
+        let invalid = config_file("[rest_api]\nport = \"http\"\n")
+            .apply(&config)
+            .expect_err("should reject invalid value");
+        assert!(invalid.contains("rest_api.port"), "{}", invalid);
+
+        let skipped = config_file("[network]\ntrusted_peers = []\n")
+            .apply(&config)
+            .expect_err("should reject keys which are not read from files");
+        assert!(skipped.contains("network.trusted_peers"), "{}", skipped);
+    }
+
+    #[test]
+    fn exported_config_round_trips() {
+        let mut config = ClientConfig::default();
+        config.network.max_peers = 42;
+
+        let exported = toml::to_string(&Value::Table(encode(&config).expect("should encode")))
+            .expect("should print toml");
+        let applied = config_file(&exported)
+            .apply(&ClientConfig::default())
+            .expect("should apply exported config");
+
+        assert_eq!(applied.network.max_peers, 42);
+    }
+}
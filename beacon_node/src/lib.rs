@@ -3,6 +3,7 @@ extern crate clap;
 
 mod cli;
 mod config;
+mod config_file;
 
 pub use beacon_chain;
 pub use cli::cli_app;
@@ -10,31 +11,35 @@ pub use client::{Client, ClientBuilder, ClientConfig, ClientGenesis};
 pub use eth2_config::Eth2Config;
 
 use beacon_chain::{
-    builder::Witness, eth1_chain::CachingEth1Backend, events::WebSocketSender,
+    builder::Witness, eth1_chain::CachingEth1Backend, events::EventBus,
     lmd_ghost::ThreadSafeReducedTree, slot_clock::SystemTimeSlotClock,
 };
 use clap::ArgMatches;
 use config::get_configs;
 use environment::RuntimeContext;
-use futures::{Future, IntoFuture};
-use slog::{info, warn};
+use futures::{
+    future::{self, Either},
+    Future, IntoFuture,
+};
+use slog::{crit, info, warn};
 use std::ops::{Deref, DerefMut};
 use store::{migrate::BackgroundMigrator, DiskStore};
-use types::EthSpec;
+use types::{EthSpec, Slot};
 
-/// A type-alias to the tighten the definition of a production-intended `Client`.
-pub type ProductionClient<E> = Client<
-    Witness<
-        DiskStore<E>,
-        BackgroundMigrator<E>,
-        SystemTimeSlotClock,
-        ThreadSafeReducedTree<DiskStore<E>, E>,
-        CachingEth1Backend<E, DiskStore<E>>,
-        E,
-        WebSocketSender<E>,
-    >,
+/// A type-alias to the `BeaconChainTypes` of a production-intended `Client`.
+pub type ProductionWitness<E> = Witness<
+    DiskStore<E>,
+    BackgroundMigrator<E>,
+    SystemTimeSlotClock,
+    ThreadSafeReducedTree<DiskStore<E>, E>,
+    CachingEth1Backend<E, DiskStore<E>>,
+    E,
+    EventBus<E>,
 >;
 
+/// A type-alias to the tighten the definition of a production-intended `Client`.
+pub type ProductionClient<E> = Client<ProductionWitness<E>>;
+
 /// The beacon node `Client` that will be used in production.
 ///
 /// Generic over some `EthSpec`.
@@ -45,6 +50,159 @@ pub type ProductionClient<E> = Client<
 /// demonstrates an intention, not a promise.
 pub struct ProductionBeaconNode<E: EthSpec>(ProductionClient<E>);
 
+/// Prints the ENR that a beacon node configured with `matches` would advertise, as for the
+/// `--enr-dump` flag.
+pub fn dump_enr<E: EthSpec>(
+    context: RuntimeContext<E>,
+    matches: &ArgMatches,
+) -> Result<(), String> {
+    let (client_config, _eth2_config, log) =
+        get_configs::<E>(matches, context.eth2_config.clone(), context.log.clone())?;
+
+    let enr = eth2_libp2p::load_local_enr(&client_config.network, &log)
+        .map_err(|e| format!("Unable to load the local ENR: {:?}", e))?;
+
+    println!("{}", eth2_libp2p::address::describe_enr(&enr));
+
+    Ok(())
+}
+
+/// Prints the effective client configuration for `matches` as TOML, in the format read by the
+/// `--config-file` flag, as for the `--export-config` flag.
+pub fn export_config<E: EthSpec>(
+    context: RuntimeContext<E>,
+    matches: &ArgMatches,
+) -> Result<(), String> {
+    let (client_config, _eth2_config, _log) =
+        get_configs::<E>(matches, context.eth2_config.clone(), context.log.clone())?;
+
+    let encoded = toml::to_string(&toml::Value::Table(config_file::encode(&client_config)?))
+        .map_err(|e| format!("Unable to encode client config: {:?}", e))?;
+    println!("{}", encoded);
+
+    Ok(())
+}
+
+/// Builds and validates the beacon chain that a beacon node configured with `matches` would
+/// start, then prints the resolved configuration as TOML, as for the `--dry-run` flag.
+///
+/// No services are started. The database is created (and the genesis state obtained) as it would
+/// be for a normal start.
+pub fn dry_run<E: EthSpec>(
+    context: RuntimeContext<E>,
+    matches: &ArgMatches,
+) -> impl Future<Item = (), Error = String> {
+    get_configs::<E>(matches, context.eth2_config.clone(), context.log.clone())
+        .into_future()
+        .and_then(move |(client_config, eth2_config, _log)| {
+            let db_path = client_config.create_db_path()?;
+            let freezer_db_path = client_config.create_freezer_db_path()?;
+
+            let builder: ClientBuilder<ProductionWitness<E>> =
+                ClientBuilder::new(context.eth_spec_instance.clone())
+                    .runtime_context(context)
+                    .chain_spec(eth2_config.spec.clone())
+                    .disk_store(&db_path, &freezer_db_path, &client_config.store)?
+                    .background_migrator()?;
+
+            Ok((builder, client_config, eth2_config))
+        })
+        .and_then(|(builder, client_config, eth2_config)| {
+            builder
+                .beacon_chain_builder(client_config.genesis.clone(), client_config.eth1.clone())
+                .and_then(|builder| builder.validate_beacon_chain())
+                .map(move |_| (client_config, eth2_config))
+        })
+        .and_then(|(client_config, eth2_config)| {
+            let mut config = toml::value::Table::new();
+            config.insert(
+                "client".into(),
+                toml::Value::try_from(&client_config)
+                    .map_err(|e| format!("Unable to encode client config: {:?}", e))?,
+            );
+            config.insert(
+                "eth2".into(),
+                toml::Value::try_from(&eth2_config)
+                    .map_err(|e| format!("Unable to encode eth2 config: {:?}", e))?,
+            );
+
+            let encoded = toml::to_string(&toml::Value::Table(config))
+                .map_err(|e| format!("Unable to encode config: {:?}", e))?;
+            println!("{}", encoded);
+
+            Ok(())
+        })
+}
+
+/// Builds the beacon chain from the database of a beacon node configured with `matches` and
+/// replays it from `start_slot` through the full state transition, as for the
+/// `--revalidate-from-slot` flag.
+///
+/// No services are started. Returns an error describing the first divergence, if any.
+pub fn revalidate<E: EthSpec>(
+    context: RuntimeContext<E>,
+    matches: &ArgMatches,
+    start_slot: Slot,
+) -> impl Future<Item = (), Error = String> {
+    get_configs::<E>(matches, context.eth2_config.clone(), context.log.clone())
+        .into_future()
+        .and_then(move |(client_config, eth2_config, log)| {
+            let db_path = client_config.create_db_path()?;
+            let freezer_db_path = client_config.create_freezer_db_path()?;
+
+            let builder: ClientBuilder<ProductionWitness<E>> =
+                ClientBuilder::new(context.eth_spec_instance.clone())
+                    .runtime_context(context)
+                    .chain_spec(eth2_config.spec)
+                    .disk_store(&db_path, &freezer_db_path, &client_config.store)?
+                    .background_migrator()?;
+
+            Ok((builder, client_config, log))
+        })
+        .and_then(|(builder, client_config, log)| {
+            builder
+                .beacon_chain_builder(client_config.genesis.clone(), client_config.eth1.clone())
+                .map(|builder| (builder, log))
+        })
+        .and_then(move |(builder, log)| {
+            let beacon_chain = builder
+                .no_eth1_backend()?
+                .system_time_slot_clock()?
+                .event_bus()?
+                .build_beacon_chain()?
+                .build()
+                .beacon_chain()
+                .ok_or_else(|| "Beacon chain was not built".to_string())?;
+
+            let summary = beacon_chain
+                .revalidate_from_slot(start_slot)
+                .map_err(|e| format!("Unable to read chain: {:?}", e))?;
+
+            match summary.divergence {
+                None => {
+                    info!(
+                        log,
+                        "Revalidation complete";
+                        "blocks_verified" => summary.blocks_verified,
+                        "head_slot" => summary.head_slot,
+                        "start_slot" => summary.start_slot,
+                    );
+                    Ok(())
+                }
+                Some(divergence) => {
+                    crit!(
+                        log,
+                        "Chain diverges from replayed state";
+                        "divergence" => format!("{:?}", divergence),
+                        "blocks_verified" => summary.blocks_verified,
+                        "start_slot" => summary.start_slot,
+                    );
+                    Err(format!("Chain diverges: {:?}", divergence))
+                }
+            }
+        })
+}
+
 impl<E: EthSpec> ProductionBeaconNode<E> {
     /// Starts a new beacon node `Client` in the given `environment`.
     ///
@@ -79,7 +237,13 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         let spec = context.eth2_config().spec.clone();
         let genesis_eth1_config = client_config.eth1.clone();
         let client_genesis = client_config.genesis.clone();
+        let uses_eth1_node = (client_config.sync_eth1_chain && !client_config.dummy_eth1_backend)
+            || match client_genesis {
+                ClientGenesis::DepositContract => true,
+                _ => false,
+            };
         let store_config = client_config.store.clone();
+        let genesis_ceremony = client_config.genesis_ceremony.clone();
         let log = context.log.clone();
 
         let db_path_res = client_config.create_db_path();
@@ -88,18 +252,28 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         db_path_res
             .into_future()
             .and_then(move |db_path| {
-                Ok(ClientBuilder::new(context.eth_spec_instance.clone())
+                let builder = ClientBuilder::new(context.eth_spec_instance.clone())
                     .runtime_context(context)
                     .chain_spec(spec)
-                    .disk_store(
-                        &db_path,
-                        &freezer_db_path_res?,
-                        store_config.slots_per_restore_point,
-                    )?
-                    .background_migrator()?)
+                    .disk_store(&db_path, &freezer_db_path_res?, &store_config)?
+                    .background_migrator()?;
+
+                if let Some(config) = genesis_ceremony {
+                    Ok(builder.genesis_ceremony(config))
+                } else {
+                    Ok(builder)
+                }
             })
             .and_then(move |builder| {
-                builder.beacon_chain_builder(client_genesis, genesis_eth1_config)
+                let verified_builder = if uses_eth1_node {
+                    Either::A(builder.verify_eth1_network(genesis_eth1_config.clone()))
+                } else {
+                    Either::B(future::ok(builder))
+                };
+
+                verified_builder.and_then(move |builder| {
+                    builder.beacon_chain_builder(client_genesis, genesis_eth1_config)
+                })
             })
             .and_then(move |builder| {
                 let builder = if client_config.sync_eth1_chain && !client_config.dummy_eth1_backend
@@ -127,19 +301,69 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
                     builder.no_eth1_backend()?
                 };
 
+                let builder = if client_config.slasher.enabled {
+                    builder.slasher(&client_config)?
+                } else {
+                    builder
+                };
+
+                let builder = if !client_config.balance_history.is_empty() {
+                    builder.balance_history(client_config.balance_history.clone())?
+                } else {
+                    builder
+                };
+
+                let builder = if let Some(dir) = &client_config.debug_dump_dir {
+                    builder.debug_dump_dir(dir.clone())?
+                } else {
+                    builder
+                };
+
+                let builder = if let Some(bytes) = client_config.cache_memory_budget {
+                    builder.cache_memory_budget(bytes)?
+                } else {
+                    builder
+                };
+
                 let builder = builder
+                    .block_packing_delay(client_config.block_packing_delay)?
+                    .log_block_processing_breakdown(client_config.log_block_processing_breakdown)?
                     .system_time_slot_clock()?
-                    .websocket_event_handler(client_config.websocket_server.clone())?
+                    .event_bus()?
                     .build_beacon_chain()?
+                    .websocket_server(client_config.websocket_server.clone())?
                     .libp2p_network(&client_config.network)?
+                    .startup_report(&client_config, &http_eth2_config)?
+                    .slasher_service()?
+                    .fork_choice_service()?
+                    .next_epoch_service()?
+                    .deposit_tree_service()?
                     .notifier()?;
 
+                let builder = if client_config.finality_updates {
+                    builder.finality_update_service()?
+                } else {
+                    builder
+                };
+
+                let builder = if client_config.monitoring_endpoint.is_some() {
+                    builder.monitoring_service(&client_config)?
+                } else {
+                    builder
+                };
+
                 let builder = if client_config.rest_api.enabled {
                     builder.http_server(&client_config, &http_eth2_config)?
                 } else {
                     builder
                 };
 
+                let builder = if client_config.http_metrics.enabled {
+                    builder.http_metrics_server(&client_config)?
+                } else {
+                    builder
+                };
+
                 Ok(Self(builder.build()))
             })
     }
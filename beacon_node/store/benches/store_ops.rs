@@ -0,0 +1,360 @@
+//! Benchmarks of the store operations which dominate block import and chain traversal, intended
+//! to evaluate changes to the storage layout (e.g., the hot/cold split) quantitatively.
+//!
+//! By default the benchmarks run against a `MemoryStore`, which measures the cost of encoding and
+//! decoding. Set `STORE_BENCH_BACKEND=disk` to run them against a `DiskStore` instead, in a
+//! temporary directory or in the directory given by `STORE_BENCH_DIR` (e.g., to benchmark a
+//! particular device). The state cache of the `DiskStore` is disabled, so every read hits the
+//! database.
+//!
+//! To check a change for regressions, save a baseline before making it:
+//!
+//! ```bash
+//! cargo bench -p store --bench store_ops -- --save-baseline before
+//! ```
+//!
+//! Then compare against that baseline with `STORE_BENCH_MAX_REGRESSION` set to the largest
+//! acceptable relative increase in mean time (e.g., `0.1` for 10%). The run fails if any
+//! benchmark regressed by more than that:
+//!
+//! ```bash
+//! STORE_BENCH_MAX_REGRESSION=0.1 cargo bench -p store --bench store_ops -- --baseline before
+//! ```
+use criterion::{black_box, Benchmark, Criterion};
+use rayon::prelude::*;
+use sloggers::{null::NullLoggerBuilder, Build};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use store::iter::ParentRootBlockIterator;
+use store::{DiskStore, MemoryStore, Store, StoreConfig};
+use tempfile::{tempdir, tempdir_in, TempDir};
+use types::{
+    test_utils::generate_deterministic_keypair, BeaconBlock, BeaconState, Epoch, Eth1Data, EthSpec,
+    Hash256, MainnetEthSpec, Slot, Validator,
+};
+
+type E = MainnetEthSpec;
+
+/// Selects the store backend, either `memory` (the default) or `disk`.
+const BACKEND_ENV: &str = "STORE_BENCH_BACKEND";
+/// The directory in which to create the databases of the `disk` backend.
+const DIR_ENV: &str = "STORE_BENCH_DIR";
+/// The largest acceptable relative increase in the mean time of a benchmark.
+const MAX_REGRESSION_ENV: &str = "STORE_BENCH_MAX_REGRESSION";
+
+/// The prefix of the criterion group of each benchmark in this file, which is also the prefix of
+/// the directory in which criterion records its results.
+const GROUP_PREFIX: &str = "store_";
+
+const VALIDATOR_COUNTS: &[usize] = &[1_024, 16_384, 65_536];
+/// The number of validators in the state written when importing a block.
+const IMPORT_VALIDATOR_COUNT: usize = 16_384;
+/// The length of the chain walked by the iterator benchmarks.
+const ITER_BLOCK_COUNT: u64 = 10_000;
+
+fn get_state(validator_count: usize) -> BeaconState<E> {
+    let spec = &E::default_spec();
+    let eth1_data = Eth1Data {
+        deposit_root: Hash256::zero(),
+        deposit_count: 0,
+        block_hash: Hash256::zero(),
+    };
+
+    let mut state = BeaconState::new(0, eth1_data, spec);
+
+    for i in 0..validator_count {
+        state.balances.push(i as u64).expect("should add balance");
+    }
+
+    state.validators = (0..validator_count)
+        .into_iter()
+        .collect::<Vec<_>>()
+        .par_iter()
+        .map(|&i| Validator {
+            pubkey: generate_deterministic_keypair(i).pk.into(),
+            withdrawal_credentials: Hash256::from_low_u64_le(i as u64),
+            effective_balance: spec.max_effective_balance,
+            slashed: false,
+            activation_eligibility_epoch: Epoch::new(0),
+            activation_epoch: Epoch::new(0),
+            exit_epoch: Epoch::from(u64::max_value()),
+            withdrawable_epoch: Epoch::from(u64::max_value()),
+        })
+        .collect::<Vec<_>>()
+        .into();
+
+    state.build_all_caches(spec).expect("should build caches");
+
+    state
+}
+
+/// Opens a `DiskStore` in a new temporary directory, which is deleted when the returned
+/// `TempDir` is dropped.
+fn open_disk_store() -> (DiskStore<E>, TempDir) {
+    let dir = match env::var(DIR_ENV) {
+        Ok(parent) => tempdir_in(parent),
+        Err(_) => tempdir(),
+    }
+    .expect("should create temp dir");
+
+    let hot_path = dir.path().join("chain_db");
+    let cold_path = dir.path().join("freezer_db");
+    let config = StoreConfig {
+        state_cache_max_states: 0,
+        ..StoreConfig::default()
+    };
+    let log = NullLoggerBuilder.build().expect("should build logger");
+
+    let store = DiskStore::open(&hot_path, &cold_path, &config, E::default_spec(), log)
+        .expect("should open disk store");
+
+    (store, dir)
+}
+
+/// Stores a chain of `count` empty blocks, returning the root of the last one.
+fn store_chain<S: Store<E>>(store: &S, count: u64) -> Hash256 {
+    let spec = E::default_spec();
+    let mut parent_root = Hash256::zero();
+
+    for slot in 0..count {
+        let mut block = BeaconBlock::<E>::empty(&spec);
+        block.slot = Slot::new(slot);
+        block.parent_root = parent_root;
+
+        parent_root = block.canonical_root();
+        store.put(&parent_root, &block).expect("should store block");
+        store
+            .put_block_slot(&parent_root, block.slot)
+            .expect("should index block");
+    }
+
+    parent_root
+}
+
+/// Registers each benchmark against the store returned by `open_store`.
+///
+/// The values returned alongside the store (e.g., a temporary directory) are kept alive until its
+/// benchmark completes.
+fn store_benches<S, T, F>(c: &mut Criterion, backend: &str, open_store: F)
+where
+    S: Store<E>,
+    T: 'static,
+    F: Fn() -> (S, T),
+{
+    let spec = E::default_spec();
+
+    for &validator_count in VALIDATOR_COUNTS {
+        let group = format!("{}{}_{}_validators", GROUP_PREFIX, backend, validator_count);
+        let state = get_state(validator_count);
+        let state_root = Hash256::from_low_u64_be(1);
+
+        let (store, guard) = open_store();
+        let inner_state = state.clone();
+        c.bench(
+            &group,
+            Benchmark::new("put_state", move |b| {
+                let _guard = &guard;
+                b.iter(|| {
+                    store
+                        .put_state(&state_root, &inner_state)
+                        .expect("should put state")
+                })
+            })
+            .sample_size(10),
+        );
+
+        let (store, guard) = open_store();
+        store
+            .put_state(&state_root, &state)
+            .expect("should put state");
+        c.bench(
+            &group,
+            Benchmark::new("get_state", move |b| {
+                let _guard = &guard;
+                b.iter(|| {
+                    black_box(
+                        store
+                            .get_state(&state_root, Some(Slot::new(0)))
+                            .expect("should get state")
+                            .expect("state should exist"),
+                    )
+                })
+            })
+            .sample_size(10),
+        );
+    }
+
+    // Performs the same writes as the beacon chain when it imports a block.
+    let group = format!("{}{}", GROUP_PREFIX, backend);
+    let state = get_state(IMPORT_VALIDATOR_COUNT);
+    let state_root = Hash256::from_low_u64_be(1);
+    let mut block = BeaconBlock::<E>::empty(&spec);
+    block.state_root = state_root;
+    let block_root = block.canonical_root();
+
+    let (store, guard) = open_store();
+    c.bench(
+        &group,
+        Benchmark::new(
+            format!("import_block/{}_validators", IMPORT_VALIDATOR_COUNT),
+            move |b| {
+                let _guard = &guard;
+                b.iter(|| {
+                    store
+                        .put_state(&state_root, &state)
+                        .expect("should put state");
+                    store
+                        .put_state_slot(&state_root, state.slot)
+                        .expect("should index state");
+                    store.put(&block_root, &block).expect("should put block");
+                    store
+                        .put_block_slot(&block_root, block.slot)
+                        .expect("should index block");
+                })
+            },
+        )
+        .sample_size(10),
+    );
+
+    let (store, guard) = open_store();
+    let head_root = store_chain(&store, ITER_BLOCK_COUNT);
+    let store = Arc::new(store);
+
+    let inner_store = store.clone();
+    c.bench(
+        &group,
+        Benchmark::new(
+            format!("iter/parent_root_blocks/{}", ITER_BLOCK_COUNT),
+            move |b| {
+                b.iter(|| {
+                    let count = ParentRootBlockIterator::new(inner_store.as_ref(), head_root)
+                        .count() as u64;
+                    assert_eq!(count, ITER_BLOCK_COUNT, "should walk the whole chain");
+                })
+            },
+        )
+        .sample_size(10),
+    );
+
+    c.bench(
+        &group,
+        Benchmark::new(
+            format!("iter/block_slot_index/{}", ITER_BLOCK_COUNT),
+            move |b| {
+                let _guard = &guard;
+                b.iter(|| {
+                    for block in ParentRootBlockIterator::new(store.as_ref(), head_root) {
+                        black_box(
+                            store
+                                .get_block_slot(&block.parent_root)
+                                .expect("should read index"),
+                        );
+                    }
+                })
+            },
+        )
+        .sample_size(10),
+    );
+}
+
+fn all_benches(c: &mut Criterion) {
+    match env::var(BACKEND_ENV).as_ref().map(String::as_str) {
+        Ok("disk") => store_benches(c, "disk", open_disk_store),
+        Ok("memory") | Err(_) => store_benches(c, "memory", || (MemoryStore::<E>::open(), ())),
+        Ok(other) => panic!(
+            "Unknown {}: {}, expected memory or disk",
+            BACKEND_ENV, other
+        ),
+    }
+}
+
+/// Returns the directory in which criterion writes its results.
+fn criterion_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("CRITERION_HOME") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
+        PathBuf::from(dir).join("criterion")
+    } else {
+        PathBuf::from("target/criterion")
+    }
+}
+
+/// Returns the relative change in mean time recorded in each `change/estimates.json` under `dir`
+/// whose top-level directory starts with `prefix`, i.e., the change of each benchmark since the
+/// baseline it was compared against.
+fn mean_changes(dir: &Path, prefix: &str) -> Vec<(PathBuf, f64)> {
+    let mut changes = vec![];
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return changes,
+    };
+
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let matches_prefix = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with(prefix));
+        if !path.is_dir() || !matches_prefix {
+            continue;
+        }
+
+        let estimates = path.join("change").join("estimates.json");
+        let mean = fs::read(&estimates)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+            .and_then(|json| json["Mean"]["point_estimate"].as_f64());
+
+        if let Some(mean) = mean {
+            changes.push((path.clone(), mean));
+        }
+
+        changes.append(&mut mean_changes(&path, ""));
+    }
+
+    changes
+}
+
+/// Fails if any benchmark in this file regressed by more than `STORE_BENCH_MAX_REGRESSION` since
+/// the baseline it was compared against.
+fn check_regressions() {
+    let max_regression: f64 = match env::var(MAX_REGRESSION_ENV) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid {}: {:?}", MAX_REGRESSION_ENV, e)),
+        Err(_) => return,
+    };
+
+    let changes = mean_changes(&criterion_dir(), GROUP_PREFIX);
+    if changes.is_empty() {
+        panic!(
+            "{} is set but no comparisons were found, run with --baseline <name>",
+            MAX_REGRESSION_ENV
+        );
+    }
+
+    let regressions = changes
+        .into_iter()
+        .filter(|(_, change)| *change > max_regression)
+        .map(|(path, change)| format!("{:?}: {:+.1}%", path, change * 100.0))
+        .collect::<Vec<_>>();
+
+    if !regressions.is_empty() {
+        eprintln!(
+            "Benchmarks regressed by more than {:.1}%:\n{}",
+            max_regression * 100.0,
+            regressions.join("\n")
+        );
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    all_benches(&mut criterion);
+    criterion.final_summary();
+
+    check_regressions();
+}
@@ -0,0 +1,59 @@
+use crate::{DBColumn, Error, SimpleStoreItem};
+use serde_derive::{Deserialize, Serialize};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::{Hash256, Slot};
+
+/// 32-byte key for accessing the `AnchorInfo` of the database.
+pub const ANCHOR_INFO_KEY: &str = "ANCHORINFOANCHORINFOANCHORINFOAN";
+
+/// Describes the oldest data held by a database which was not synced from genesis (e.g., one
+/// initialised from a checkpoint).
+///
+/// A database without an `AnchorInfo` holds the full history of the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct AnchorInfo {
+    /// The root of the block from which the database was initialised.
+    pub anchor_root: Hash256,
+    /// The slot of the oldest block in the database.
+    pub oldest_block_slot: Slot,
+    /// The slot of the oldest state in the database.
+    pub oldest_state_slot: Slot,
+}
+
+impl AnchorInfo {
+    /// Returns `Err(HistoryUnavailable)` if the block at `slot` is older than the anchor.
+    pub fn check_block_available(&self, slot: Slot) -> Result<(), Error> {
+        check_available(slot, self.oldest_block_slot)
+    }
+
+    /// Returns `Err(HistoryUnavailable)` if the state at `slot` is older than the anchor.
+    pub fn check_state_available(&self, slot: Slot) -> Result<(), Error> {
+        check_available(slot, self.oldest_state_slot)
+    }
+}
+
+fn check_available(requested_slot: Slot, oldest_slot: Slot) -> Result<(), Error> {
+    if requested_slot < oldest_slot {
+        Err(Error::HistoryUnavailable {
+            requested_slot,
+            oldest_slot,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+impl SimpleStoreItem for AnchorInfo {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
@@ -4,8 +4,15 @@ use types::{EthSpec, MinimalEthSpec};
 
 /// Default directory name for the freezer database under the top-level data dir.
 const DEFAULT_FREEZER_DB_DIR: &str = "freezer_db";
+/// Default maximum number of states in the state cache.
+pub const DEFAULT_STATE_CACHE_MAX_STATES: usize = 16;
+/// Default maximum memory used by the state cache (256 MiB).
+pub const DEFAULT_STATE_CACHE_MAX_BYTES: usize = 256 * 1024 * 1024;
+/// Default number of epochs past the split for which all hot states are stored in full.
+pub const DEFAULT_HOT_FULL_STATE_EPOCHS: u64 = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StoreConfig {
     /// Name of the directory inside the data directory where the main "hot" DB is located.
     pub db_name: String,
@@ -13,6 +20,17 @@ pub struct StoreConfig {
     pub freezer_db_path: Option<PathBuf>,
     /// Number of slots to wait between storing restore points in the freezer database.
     pub slots_per_restore_point: u64,
+    /// Maximum number of states held in the state cache.
+    pub state_cache_max_states: usize,
+    /// Maximum estimated memory used by the states in the state cache, in bytes.
+    pub state_cache_max_bytes: usize,
+    /// Number of epochs past the split slot (i.e., the latest finalized state) for which all hot
+    /// states are stored in full.
+    ///
+    /// Later states which are not on an epoch boundary are stored as a summary and are replayed
+    /// from their epoch boundary state when loaded, bounding the size of the hot database when
+    /// the chain is not finalizing.
+    pub hot_full_state_epochs: u64,
 }
 
 impl Default for StoreConfig {
@@ -21,6 +39,9 @@ impl Default for StoreConfig {
             db_name: "chain_db".to_string(),
             freezer_db_path: None,
             slots_per_restore_point: MinimalEthSpec::slots_per_historical_root() as u64,
+            state_cache_max_states: DEFAULT_STATE_CACHE_MAX_STATES,
+            state_cache_max_bytes: DEFAULT_STATE_CACHE_MAX_BYTES,
+            hot_full_state_epochs: DEFAULT_HOT_FULL_STATE_EPOCHS,
         }
     }
 }
@@ -0,0 +1,51 @@
+use crate::{AnchorInfo, Error, Store};
+use serde_derive::{Deserialize, Serialize};
+use types::{BeaconBlock, EthSpec, Slot};
+
+/// The version of the layout of the database, which must be incremented whenever the layout
+/// changes in a way that is incompatible with earlier versions.
+pub const CURRENT_SCHEMA_VERSION: u64 = 1;
+
+/// Describes which data a database holds and how it is laid out, so that clients (e.g., block
+/// explorers) know what the node is able to serve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseInfo {
+    pub schema_version: u64,
+    /// The anchor of the database, or `None` if it holds the full history of the chain.
+    pub anchor: Option<AnchorInfo>,
+    /// The slot of the anchor block, if known.
+    pub anchor_slot: Option<Slot>,
+    /// The slot of the oldest block in the database.
+    pub oldest_block_slot: Slot,
+    /// States prior to this slot are held in the cold (freezer) database, at restore points.
+    ///
+    /// Always zero for databases without a freezer.
+    pub split_slot: Slot,
+    /// The number of slots between the restore points of the freezer database, if any.
+    pub slots_per_restore_point: Option<u64>,
+}
+
+impl DatabaseInfo {
+    /// Returns the info of a database without a freezer.
+    pub fn without_freezer<S: Store<E>, E: EthSpec>(store: &S) -> Result<Self, Error> {
+        let anchor = store.get_anchor_info()?;
+        let anchor_slot = match anchor {
+            Some(anchor) => match store.get_block_slot(&anchor.anchor_root)? {
+                Some(slot) => Some(slot),
+                None => store
+                    .get::<BeaconBlock<E>>(&anchor.anchor_root)?
+                    .map(|block| block.slot),
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            anchor,
+            anchor_slot,
+            oldest_block_slot: anchor.map_or(Slot::new(0), |anchor| anchor.oldest_block_slot),
+            split_slot: Slot::new(0),
+            slots_per_restore_point: None,
+        })
+    }
+}
@@ -1,7 +1,8 @@
 use crate::chunked_vector::ChunkError;
 use crate::hot_cold_store::HotColdDbError;
+use lighthouse_error::{CodedError, ErrorCode};
 use ssz::DecodeError;
-use types::BeaconStateError;
+use types::{BeaconStateError, Slot};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -10,7 +11,28 @@ pub enum Error {
     BeaconStateError(BeaconStateError),
     PartialBeaconStateError,
     HotColdDbError(HotColdDbError),
-    DBError { message: String },
+    DBError {
+        message: String,
+    },
+    /// The requested data is older than the `AnchorInfo` of the database, so it is not stored.
+    HistoryUnavailable {
+        requested_slot: Slot,
+        oldest_slot: Slot,
+    },
+}
+
+impl CodedError for Error {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            Error::SszDecodeError(_) => 1000,
+            Error::VectorChunkError(_) => 1001,
+            Error::BeaconStateError(_) => 1002,
+            Error::PartialBeaconStateError => 1003,
+            Error::HotColdDbError(_) => 1004,
+            Error::DBError { .. } => 1005,
+            Error::HistoryUnavailable { .. } => 1006,
+        })
+    }
 }
 
 impl From<DecodeError> for Error {
@@ -3,17 +3,17 @@ use crate::chunked_vector::{
 };
 use crate::forwards_iter::HybridForwardsBlockRootsIterator;
 use crate::iter::{ParentRootBlockIterator, StateRootsIterator};
+use crate::metrics;
+use crate::state_cache::StateCache;
 use crate::{
-    leveldb_store::LevelDB, DBColumn, Error, PartialBeaconState, SimpleStoreItem, Store, StoreItem,
+    leveldb_store::LevelDB, AnchorInfo, DBColumn, DatabaseInfo, Error, PartialBeaconState,
+    SimpleStoreItem, Store, StoreConfig, StoreItem, ANCHOR_INFO_KEY,
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use slog::{debug, trace, warn, Logger};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
-use state_processing::{
-    per_block_processing, per_slot_processing, BlockProcessingError, BlockSignatureStrategy,
-    SlotProcessingError,
-};
+use state_processing::{BlockReplayError, BlockReplayer};
 use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::path::Path;
@@ -33,14 +33,20 @@ pub struct HotColdDB<E: EthSpec> {
     /// States with slots less than `split.slot` are in the cold DB, while states with slots
     /// greater than or equal are in the hot DB.
     split: RwLock<Split>,
+    /// The oldest block and state held by the database, if it does not hold the full history.
+    anchor_info: RwLock<Option<AnchorInfo>>,
     /// Number of slots per restore point state in the freezer database.
     slots_per_restore_point: u64,
+    /// Number of epochs past the split for which all hot states are stored in full.
+    hot_full_state_epochs: u64,
     /// Cold database containing compact historical data.
     pub(crate) cold_db: LevelDB<E>,
     /// Hot database containing duplicated but quick-to-access recent data.
     ///
     /// The hot database also contains all blocks.
     pub(crate) hot_db: LevelDB<E>,
+    /// Cache of recently read states, from both the hot and cold databases.
+    state_cache: Mutex<StateCache<E>>,
     /// Chain spec.
     spec: ChainSpec,
     /// Logger.
@@ -60,14 +66,13 @@ pub enum HotColdDbError {
     MissingRestorePoint(Hash256),
     MissingStateSlot(Hash256),
     MissingSplitState(Hash256, Slot),
+    MissingEpochBoundaryState(Hash256),
     RestorePointDecodeError(ssz::DecodeError),
     RestorePointReplayFailure {
         expected_state_root: Hash256,
         observed_state_root: Hash256,
     },
-    BlockReplayBeaconError(BeaconStateError),
-    BlockReplaySlotError(SlotProcessingError),
-    BlockReplayBlockError(BlockProcessingError),
+    BlockReplayError(BlockReplayError),
     InvalidSlotsPerRestorePoint {
         slots_per_restore_point: u64,
         slots_per_historical_root: u64,
@@ -96,38 +101,64 @@ impl<E: EthSpec> Store<E> for HotColdDB<E> {
     }
 
     /// Store a state in the store.
+    ///
+    /// Hot states far enough past the split are stored as a `HotStateSummary`, unless they lie
+    /// on an epoch boundary.
     fn put_state(&self, state_root: &Hash256, state: &BeaconState<E>) -> Result<(), Error> {
         if state.slot < self.get_split_slot() {
             self.store_archive_state(state_root, state)
+        } else if self.is_summarised_hot_state(state.slot) {
+            self.store_hot_state_summary(state_root, state)
         } else {
             self.hot_db.put_state(state_root, state)
         }
     }
 
-    /// Fetch a state from the store.
+    /// Fetch the `AnchorInfo` from memory.
+    fn get_anchor_info(&self) -> Result<Option<AnchorInfo>, Error> {
+        Ok(*self.anchor_info.read())
+    }
+
+    /// Store the `AnchorInfo` on disk and in memory.
+    fn put_anchor_info(&self, anchor_info: &AnchorInfo) -> Result<(), Error> {
+        let key = Hash256::from_slice(ANCHOR_INFO_KEY.as_bytes());
+        self.hot_db.put(&key, anchor_info)?;
+        *self.anchor_info.write() = Some(*anchor_info);
+        Ok(())
+    }
+
+    /// Includes the split slot and restore point spacing of the freezer database.
+    fn get_database_info(&self) -> Result<DatabaseInfo, Error> {
+        Ok(DatabaseInfo {
+            split_slot: self.get_split_slot(),
+            slots_per_restore_point: Some(self.slots_per_restore_point),
+            ..DatabaseInfo::without_freezer(self)?
+        })
+    }
+
+    /// Fetch a state from the store, consulting the state cache first.
+    ///
+    /// Returns `Err(HistoryUnavailable)` if `slot` is older than the anchor of the store.
     fn get_state(
         &self,
         state_root: &Hash256,
         slot: Option<Slot>,
     ) -> Result<Option<BeaconState<E>>, Error> {
-        if let Some(slot) = slot {
-            if slot < self.get_split_slot() {
-                self.load_archive_state(state_root, slot).map(Some)
-            } else {
-                self.hot_db.get_state(state_root, None)
-            }
-        } else {
-            match self.hot_db.get_state(state_root, None)? {
-                Some(state) => Ok(Some(state)),
-                None => {
-                    // Look-up the state in the freezer DB. We don't know the slot, so we must
-                    // look it up separately and then use it to reconstruct the state from a
-                    // restore point.
-                    let slot = self.load_state_slot(state_root)?;
-                    self.load_archive_state(state_root, slot).map(Some)
-                }
-            }
+        if let Some(state) = self.state_cache.lock().get(state_root) {
+            return Ok(Some(state));
+        }
+
+        if let (Some(slot), Some(anchor_info)) = (slot, *self.anchor_info.read()) {
+            anchor_info.check_state_available(slot)?;
+        }
+
+        let state = self.get_state_uncached(state_root, slot)?;
+
+        if let Some(state) = &state {
+            self.state_cache.lock().insert(*state_root, state);
         }
+
+        Ok(state)
     }
 
     /// Advance the split point of the store, moving new finalized states to the freezer.
@@ -161,8 +192,7 @@ impl<E: EthSpec> Store<E> for HotColdDB<E> {
         {
             if slot % store.slots_per_restore_point == 0 {
                 let state: BeaconState<E> = store
-                    .hot_db
-                    .get_state(&state_root, None)?
+                    .load_hot_state(&state_root)?
                     .ok_or_else(|| HotColdDbError::MissingStateToFreeze(state_root))?;
 
                 store.store_archive_state(&state_root, &state)?;
@@ -187,6 +217,9 @@ impl<E: EthSpec> Store<E> for HotColdDB<E> {
             store
                 .hot_db
                 .key_delete(DBColumn::BeaconState.into(), state_root.as_bytes())?;
+            store
+                .hot_db
+                .key_delete(DBColumn::BeaconStateSummary.into(), state_root.as_bytes())?;
         }
 
         debug!(
@@ -207,26 +240,38 @@ impl<E: EthSpec> Store<E> for HotColdDB<E> {
     ) -> Self::ForwardsBlockRootsIterator {
         HybridForwardsBlockRootsIterator::new(store, start_slot, end_state, end_block_root, spec)
     }
+
+    /// Fetch a `HistoricalBatch` from the freezer database.
+    fn get_historical_batch(&self, index: usize) -> Result<Option<HistoricalBatch<E>>, Error> {
+        HistoricalBatch::db_get(&self.cold_db, &Self::historical_batch_key(index))
+    }
 }
 
 impl<E: EthSpec> HotColdDB<E> {
     /// Open a new or existing database, with the given paths to the hot and cold DBs.
     ///
-    /// The `slots_per_restore_point` parameter must be a divisor of `SLOTS_PER_HISTORICAL_ROOT`.
+    /// The `slots_per_restore_point` of the `config` must be a divisor of
+    /// `SLOTS_PER_HISTORICAL_ROOT`.
     pub fn open(
         hot_path: &Path,
         cold_path: &Path,
-        slots_per_restore_point: u64,
+        config: &StoreConfig,
         spec: ChainSpec,
         log: Logger,
     ) -> Result<Self, Error> {
-        Self::verify_slots_per_restore_point(slots_per_restore_point)?;
+        Self::verify_slots_per_restore_point(config.slots_per_restore_point)?;
 
         let db = HotColdDB {
             split: RwLock::new(Split::default()),
-            slots_per_restore_point,
+            anchor_info: RwLock::new(None),
+            slots_per_restore_point: config.slots_per_restore_point,
+            hot_full_state_epochs: config.hot_full_state_epochs,
             cold_db: LevelDB::open(cold_path)?,
             hot_db: LevelDB::open(hot_path)?,
+            state_cache: Mutex::new(StateCache::new(
+                config.state_cache_max_states,
+                config.state_cache_max_bytes,
+            )),
             spec,
             log,
             _phantom: PhantomData,
@@ -237,9 +282,103 @@ impl<E: EthSpec> HotColdDB<E> {
         if let Some(split) = db.load_split()? {
             *db.split.write() = split;
         }
+        *db.anchor_info.write() = db
+            .hot_db
+            .get::<AnchorInfo>(&Hash256::from_slice(ANCHOR_INFO_KEY.as_bytes()))?;
         Ok(db)
     }
 
+    /// Fetch a state from the hot or cold database, bypassing the state cache.
+    fn get_state_uncached(
+        &self,
+        state_root: &Hash256,
+        slot: Option<Slot>,
+    ) -> Result<Option<BeaconState<E>>, Error> {
+        if let Some(slot) = slot {
+            if slot < self.get_split_slot() {
+                self.load_archive_state(state_root, slot).map(Some)
+            } else {
+                self.load_hot_state(state_root)
+            }
+        } else {
+            match self.load_hot_state(state_root)? {
+                Some(state) => Ok(Some(state)),
+                None => {
+                    // Look-up the state in the freezer DB. We don't know the slot, so we must
+                    // look it up separately and then use it to reconstruct the state from a
+                    // restore point.
+                    let slot = self.load_state_slot(state_root)?;
+                    self.load_archive_state(state_root, slot).map(Some)
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if a hot state at `slot` should be stored as a `HotStateSummary` rather than
+    /// in full.
+    ///
+    /// Epoch boundary states are always stored in full, so that every summarised state can be
+    /// replayed from one.
+    fn is_summarised_hot_state(&self, slot: Slot) -> bool {
+        let full_state_slots = self.hot_full_state_epochs * E::slots_per_epoch();
+
+        slot % E::slots_per_epoch() != 0 && slot >= self.get_split_slot() + full_state_slots
+    }
+
+    /// Store a hot state as a `HotStateSummary`.
+    ///
+    /// The state is added to the state cache, since it is likely to be read again soon (e.g., as
+    /// the parent of the next block).
+    fn store_hot_state_summary(
+        &self,
+        state_root: &Hash256,
+        state: &BeaconState<E>,
+    ) -> Result<(), Error> {
+        HotStateSummary::new(state_root, state)?.db_put(&self.hot_db, state_root)?;
+        self.state_cache.lock().insert(*state_root, state);
+
+        metrics::inc_counter(&metrics::HOT_STATE_SUMMARY_WRITE_COUNT);
+
+        Ok(())
+    }
+
+    /// Load a post-finalization state from the hot database.
+    ///
+    /// States stored as a `HotStateSummary` are reconstructed by replaying blocks on top of their
+    /// epoch boundary state.
+    fn load_hot_state(&self, state_root: &Hash256) -> Result<Option<BeaconState<E>>, Error> {
+        if let Some(state) = self.hot_db.get_state(state_root, None)? {
+            return Ok(Some(state));
+        }
+
+        let summary = match HotStateSummary::db_get(&self.hot_db, state_root)? {
+            Some(summary) => summary,
+            None => return Ok(None),
+        };
+
+        let timer = metrics::start_timer(&metrics::HOT_STATE_REPLAY_TIMES);
+
+        let boundary_slot = summary
+            .slot
+            .epoch(E::slots_per_epoch())
+            .start_slot(E::slots_per_epoch());
+        let boundary_state = self
+            .get_state(&summary.epoch_boundary_state_root, Some(boundary_slot))?
+            .ok_or_else(|| {
+                HotColdDbError::MissingEpochBoundaryState(summary.epoch_boundary_state_root)
+            })?;
+        let blocks = self.load_blocks_to_replay(
+            boundary_state.slot,
+            summary.slot,
+            summary.latest_block_root,
+        )?;
+        let state = self.replay_blocks(boundary_state, blocks, summary.slot)?;
+
+        metrics::stop_timer(timer);
+
+        Ok(Some(state))
+    }
+
     /// Store a pre-finalization state in the freezer database.
     ///
     /// Will return an error if the state does not lie on a restore point boundary.
@@ -280,6 +419,16 @@ impl<E: EthSpec> HotColdDB<E> {
         let restore_point_index = state.slot.as_u64() / self.slots_per_restore_point;
         self.store_restore_point_hash(restore_point_index, *state_root)?;
 
+        // 4. Store the historical batch that was accumulated into `historical_roots` on the
+        // transition to this slot, so that its block roots remain provable.
+        let slots_per_historical_root = E::SlotsPerHistoricalRoot::to_u64();
+        if state.slot > 0 && state.slot % slots_per_historical_root == 0 {
+            let index = (state.slot.as_u64() / slots_per_historical_root - 1) as usize;
+            state
+                .historical_batch()
+                .db_put(&self.cold_db, &Self::historical_batch_key(index))?;
+        }
+
         Ok(())
     }
 
@@ -414,35 +563,15 @@ impl<E: EthSpec> HotColdDB<E> {
     /// Will skip slots as necessary.
     fn replay_blocks(
         &self,
-        mut state: BeaconState<E>,
+        state: BeaconState<E>,
         blocks: Vec<BeaconBlock<E>>,
         target_slot: Slot,
     ) -> Result<BeaconState<E>, Error> {
-        state
-            .build_all_caches(&self.spec)
-            .map_err(HotColdDbError::BlockReplayBeaconError)?;
-
-        for block in blocks {
-            while state.slot < block.slot {
-                per_slot_processing(&mut state, &self.spec)
-                    .map_err(HotColdDbError::BlockReplaySlotError)?;
-            }
-            per_block_processing(
-                &mut state,
-                &block,
-                None,
-                BlockSignatureStrategy::NoVerification,
-                &self.spec,
-            )
-            .map_err(HotColdDbError::BlockReplayBlockError)?;
-        }
-
-        while state.slot < target_slot {
-            per_slot_processing(&mut state, &self.spec)
-                .map_err(HotColdDbError::BlockReplaySlotError)?;
-        }
-
-        Ok(state)
+        BlockReplayer::new(state, &self.spec)
+            .stop_at_slot(target_slot)
+            .apply_blocks(blocks)
+            .map(BlockReplayer::into_state)
+            .map_err(|e| HotColdDbError::BlockReplayError(e).into())
     }
 
     /// Fetch a copy of the current split slot from memory.
@@ -489,6 +618,11 @@ impl<E: EthSpec> HotColdDB<E> {
             .map_err(Into::into)
     }
 
+    /// Convert an index into `historical_roots` into a database key.
+    fn historical_batch_key(index: usize) -> Hash256 {
+        Hash256::from_low_u64_be(index as u64)
+    }
+
     /// Convert a `restore_point_index` into a database key.
     fn restore_point_key(restore_point_index: u64) -> Hash256 {
         Hash256::from_low_u64_be(restore_point_index)
@@ -547,6 +681,54 @@ impl SimpleStoreItem for Split {
     }
 }
 
+/// Struct for storing a hot state which is not stored in full.
+///
+/// The state is reconstructed by replaying the blocks up to `latest_block_root` on top of the
+/// state at the start of its epoch.
+#[derive(Debug, Clone, Copy, Default, Encode, Decode)]
+struct HotStateSummary {
+    slot: Slot,
+    latest_block_root: Hash256,
+    epoch_boundary_state_root: Hash256,
+}
+
+impl HotStateSummary {
+    /// Summarise `state`, which must not lie on an epoch boundary.
+    fn new<E: EthSpec>(state_root: &Hash256, state: &BeaconState<E>) -> Result<Self, Error> {
+        // The state root of the latest block header is only filled in at the next slot, so it
+        // must be set to compute the root of a block applied to this state.
+        let mut latest_block_header = state.latest_block_header.clone();
+        if latest_block_header.state_root == Hash256::zero() {
+            latest_block_header.state_root = *state_root;
+        }
+
+        let boundary_slot = state
+            .slot
+            .epoch(E::slots_per_epoch())
+            .start_slot(E::slots_per_epoch());
+
+        Ok(HotStateSummary {
+            slot: state.slot,
+            latest_block_root: latest_block_header.canonical_root(),
+            epoch_boundary_state_root: *state.get_state_root(boundary_slot)?,
+        })
+    }
+}
+
+impl SimpleStoreItem for HotStateSummary {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconStateSummary
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
+
 /// Struct for storing the slot of a state root in the database.
 #[derive(Clone, Copy, Default, Encode, Decode)]
 struct StateSlot {
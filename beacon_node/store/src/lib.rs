@@ -10,10 +10,12 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod anchor;
 mod block_at_slot;
 pub mod chunked_iter;
 pub mod chunked_vector;
 pub mod config;
+mod database_info;
 mod errors;
 mod forwards_iter;
 mod hot_cold_store;
@@ -22,18 +24,23 @@ mod leveldb_store;
 mod memory_store;
 mod metrics;
 mod partial_beacon_state;
+mod root_index;
+mod state_cache;
 
 pub mod iter;
 pub mod migrate;
 
 use std::sync::Arc;
 
+pub use self::anchor::{AnchorInfo, ANCHOR_INFO_KEY};
 pub use self::config::StoreConfig;
+pub use self::database_info::{DatabaseInfo, CURRENT_SCHEMA_VERSION};
 pub use self::hot_cold_store::HotColdDB as DiskStore;
 pub use self::leveldb_store::LevelDB as SimpleDiskStore;
 pub use self::memory_store::MemoryStore;
 pub use self::migrate::Migrate;
 pub use self::partial_beacon_state::PartialBeaconState;
+pub use self::root_index::{BlockRootSlot, RootIndexInfo, StateRootSlot, ROOT_INDEX_INFO_KEY};
 pub use errors::Error;
 pub use impls::beacon_state::StorageContainer as BeaconStateStorageContainer;
 pub use metrics::scrape_for_metrics;
@@ -89,16 +96,82 @@ pub trait Store<E: EthSpec>: Sync + Send + Sized + 'static {
         slot: Option<Slot>,
     ) -> Result<Option<BeaconState<E>>, Error>;
 
+    /// Fetch the `AnchorInfo` of the store, if it does not hold the full history of the chain.
+    fn get_anchor_info(&self) -> Result<Option<AnchorInfo>, Error> {
+        self.get(&Hash256::from_slice(ANCHOR_INFO_KEY.as_bytes()))
+    }
+
+    /// Store the `AnchorInfo` of the store, marking all data older than it as unavailable.
+    fn put_anchor_info(&self, anchor_info: &AnchorInfo) -> Result<(), Error> {
+        self.put(
+            &Hash256::from_slice(ANCHOR_INFO_KEY.as_bytes()),
+            anchor_info,
+        )
+    }
+
+    /// Returns a summary of the data held by the store.
+    fn get_database_info(&self) -> Result<DatabaseInfo, Error> {
+        DatabaseInfo::without_freezer(self)
+    }
+
+    /// Fetch the slot of the block with `block_root` from the block root index.
+    ///
+    /// Returns `None` if the block is not indexed, e.g., it was stored before the index was
+    /// introduced and has not been backfilled (see `RootIndexInfo`).
+    fn get_block_slot(&self, block_root: &Hash256) -> Result<Option<Slot>, Error> {
+        Ok(self
+            .get::<BlockRootSlot>(block_root)?
+            .map(|index| index.slot))
+    }
+
+    /// Add the block with `block_root` at `slot` to the block root index.
+    fn put_block_slot(&self, block_root: &Hash256, slot: Slot) -> Result<(), Error> {
+        self.put(block_root, &BlockRootSlot { slot })
+    }
+
+    /// Fetch the slot of the state with `state_root` from the state root index.
+    ///
+    /// Returns `None` if the state is not indexed, e.g., it was stored before the index was
+    /// introduced and has not been backfilled (see `RootIndexInfo`).
+    fn get_state_slot(&self, state_root: &Hash256) -> Result<Option<Slot>, Error> {
+        Ok(self
+            .get::<StateRootSlot>(state_root)?
+            .map(|index| index.slot))
+    }
+
+    /// Add the state with `state_root` at `slot` to the state root index.
+    fn put_state_slot(&self, state_root: &Hash256, slot: Slot) -> Result<(), Error> {
+        self.put(state_root, &StateRootSlot { slot })
+    }
+
+    /// Fetch the `RootIndexInfo` of the store, if its root indices have been backfilled.
+    fn get_root_index_info(&self) -> Result<Option<RootIndexInfo>, Error> {
+        self.get(&Hash256::from_slice(ROOT_INDEX_INFO_KEY.as_bytes()))
+    }
+
+    /// Store the `RootIndexInfo` of the store, marking its root indices as complete.
+    fn put_root_index_info(&self, root_index_info: &RootIndexInfo) -> Result<(), Error> {
+        self.put(
+            &Hash256::from_slice(ROOT_INDEX_INFO_KEY.as_bytes()),
+            root_index_info,
+        )
+    }
+
     /// Given the root of an existing block in the store (`start_block_root`), return a parent
     /// block with the specified `slot`.
     ///
     /// Returns `None` if no parent block exists at that slot, or if `slot` is greater than the
-    /// slot of `start_block_root`.
+    /// slot of `start_block_root`. Returns `Err(HistoryUnavailable)` if `slot` is older than the
+    /// anchor of the store.
     fn get_block_at_preceeding_slot(
         &self,
         start_block_root: Hash256,
         slot: Slot,
     ) -> Result<Option<(Hash256, BeaconBlock<E>)>, Error> {
+        if let Some(anchor_info) = self.get_anchor_info()? {
+            anchor_info.check_block_available(slot)?;
+        }
+
         block_at_slot::get_block_at_preceeding_slot::<_, E>(self, slot, start_block_root)
     }
 
@@ -111,6 +184,11 @@ pub trait Store<E: EthSpec>: Sync + Send + Sized + 'static {
         Ok(())
     }
 
+    /// (Optionally) Fetch the `HistoricalBatch` whose root is at `index` in `historical_roots`.
+    fn get_historical_batch(&self, _index: usize) -> Result<Option<HistoricalBatch<E>>, Error> {
+        Ok(None)
+    }
+
     /// Get a forwards (slot-ascending) iterator over the beacon block roots since `start_slot`.
     ///
     /// Will be efficient for frozen portions of the database if using `DiskStore`.
@@ -142,10 +220,30 @@ pub enum DBColumn {
     BeaconRestorePoint,
     /// For the mapping from state roots to their slots.
     BeaconStateSlot,
+    /// For the index from block roots to their slots, maintained at import time.
+    BeaconBlockRootIndex,
+    /// For the index from state roots to their slots, maintained at import time.
+    BeaconStateRootIndex,
+    /// For the summaries of hot states which are not stored in full.
+    BeaconStateSummary,
     BeaconBlockRoots,
     BeaconStateRoots,
     BeaconHistoricalRoots,
     BeaconRandaoMixes,
+    /// For the `HistoricalBatch` of each entry in `historical_roots`, keyed by its index.
+    BeaconHistoricalBatch,
+    /// For the chunks of each validator's minimum target epochs, used by the slasher.
+    SlasherMinTargets,
+    /// For the chunks of each validator's maximum target epochs, used by the slasher.
+    SlasherMaxTargets,
+    /// For the attestation made by each validator at each target epoch, used by the slasher.
+    SlasherAttesterRecord,
+    /// For the attestations seen by the slasher, keyed by their root.
+    SlasherAttestation,
+    /// For the roots of the attestations seen by the slasher at each target epoch.
+    SlasherEpochAttestations,
+    /// For the balance of each monitored validator at each epoch.
+    ValidatorBalanceHistory,
 }
 
 impl Into<&'static str> for DBColumn {
@@ -158,10 +256,20 @@ impl Into<&'static str> for DBColumn {
             DBColumn::BeaconChain => "bch",
             DBColumn::BeaconRestorePoint => "brp",
             DBColumn::BeaconStateSlot => "bss",
+            DBColumn::BeaconBlockRootIndex => "bri",
+            DBColumn::BeaconStateRootIndex => "sri",
+            DBColumn::BeaconStateSummary => "bsm",
             DBColumn::BeaconBlockRoots => "bbr",
             DBColumn::BeaconStateRoots => "bsr",
             DBColumn::BeaconHistoricalRoots => "bhr",
             DBColumn::BeaconRandaoMixes => "brm",
+            DBColumn::BeaconHistoricalBatch => "bhb",
+            DBColumn::SlasherMinTargets => "smn",
+            DBColumn::SlasherMaxTargets => "smx",
+            DBColumn::SlasherAttesterRecord => "sar",
+            DBColumn::SlasherAttestation => "sat",
+            DBColumn::SlasherEpochAttestations => "sea",
+            DBColumn::ValidatorBalanceHistory => "vbh",
         }
     }
 }
@@ -290,17 +398,10 @@ mod tests {
 
         let hot_dir = tempdir().unwrap();
         let cold_dir = tempdir().unwrap();
-        let slots_per_restore_point = MinimalEthSpec::slots_per_historical_root() as u64;
+        let config = StoreConfig::default();
         let spec = MinimalEthSpec::default_spec();
         let log = NullLoggerBuilder.build().unwrap();
-        let store = DiskStore::open(
-            &hot_dir.path(),
-            &cold_dir.path(),
-            slots_per_restore_point,
-            spec,
-            log,
-        )
-        .unwrap();
+        let store = DiskStore::open(&hot_dir.path(), &cold_dir.path(), &config, spec, log).unwrap();
 
         test_impl(store);
     }
@@ -321,6 +422,118 @@ mod tests {
         test_impl(store);
     }
 
+    #[test]
+    fn anchor_info() {
+        use sloggers::{null::NullLoggerBuilder, Build};
+
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        let config = StoreConfig::default();
+        let spec = MinimalEthSpec::default_spec();
+        let log = NullLoggerBuilder.build().unwrap();
+        let open = || {
+            DiskStore::<MinimalEthSpec>::open(
+                &hot_dir.path(),
+                &cold_dir.path(),
+                &config,
+                spec.clone(),
+                log.clone(),
+            )
+            .unwrap()
+        };
+        let anchor_info = AnchorInfo {
+            anchor_root: Hash256::random(),
+            oldest_block_slot: Slot::new(64),
+            oldest_state_slot: Slot::new(96),
+        };
+
+        let store = open();
+        assert_eq!(store.get_anchor_info(), Ok(None));
+        store.put_anchor_info(&anchor_info).unwrap();
+        drop(store);
+
+        let store = open();
+        assert_eq!(store.get_anchor_info(), Ok(Some(anchor_info)));
+        assert_eq!(
+            store.get_state(&Hash256::random(), Some(Slot::new(95))),
+            Err(Error::HistoryUnavailable {
+                requested_slot: Slot::new(95),
+                oldest_slot: Slot::new(96),
+            })
+        );
+        assert_eq!(
+            store.get_block_at_preceeding_slot(Hash256::random(), Slot::new(63)),
+            Err(Error::HistoryUnavailable {
+                requested_slot: Slot::new(63),
+                oldest_slot: Slot::new(64),
+            })
+        );
+        assert_eq!(
+            store.get_block_at_preceeding_slot(Hash256::random(), Slot::new(64)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn database_info() {
+        let store = MemoryStore::<MinimalEthSpec>::open();
+        let expected = DatabaseInfo {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            anchor: None,
+            anchor_slot: None,
+            oldest_block_slot: Slot::new(0),
+            split_slot: Slot::new(0),
+            slots_per_restore_point: None,
+        };
+        assert_eq!(store.get_database_info(), Ok(expected.clone()));
+
+        let anchor_info = AnchorInfo {
+            anchor_root: Hash256::random(),
+            oldest_block_slot: Slot::new(64),
+            oldest_state_slot: Slot::new(96),
+        };
+        store.put_anchor_info(&anchor_info).unwrap();
+        store
+            .put_block_slot(&anchor_info.anchor_root, Slot::new(128))
+            .unwrap();
+
+        assert_eq!(
+            store.get_database_info(),
+            Ok(DatabaseInfo {
+                anchor: Some(anchor_info),
+                anchor_slot: Some(Slot::new(128)),
+                oldest_block_slot: Slot::new(64),
+                ..expected
+            })
+        );
+    }
+
+    #[test]
+    fn root_index() {
+        let store = MemoryStore::<MinimalEthSpec>::open();
+        let root = Hash256::random();
+
+        assert_eq!(store.get_block_slot(&root), Ok(None));
+        assert_eq!(store.get_state_slot(&root), Ok(None));
+
+        store.put_block_slot(&root, Slot::new(3)).unwrap();
+        store.put_state_slot(&root, Slot::new(5)).unwrap();
+
+        assert_eq!(
+            store.get_block_slot(&root),
+            Ok(Some(Slot::new(3))),
+            "block and state indices should be distinct"
+        );
+        assert_eq!(store.get_state_slot(&root), Ok(Some(Slot::new(5))));
+
+        assert_eq!(store.get_root_index_info(), Ok(None));
+        let root_index_info = RootIndexInfo {
+            backfilled_to_slot: Slot::new(7),
+        };
+        store.put_root_index_info(&root_index_info).unwrap();
+        assert_eq!(store.get_root_index_info(), Ok(Some(root_index_info)));
+    }
+
     #[test]
     fn exists() {
         let store = MemoryStore::<MinimalEthSpec>::open();
@@ -105,6 +105,40 @@ lazy_static! {
         "store_beacon_block_write_bytes_total",
         "Total number of beacon block bytes written to the DB"
     );
+    /*
+     * State Cache
+     */
+    pub static ref STATE_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "store_state_cache_hits_total",
+        "Number of state reads served by the state cache"
+    );
+    pub static ref STATE_CACHE_MISSES: Result<IntCounter> = try_create_int_counter(
+        "store_state_cache_misses_total",
+        "Number of state reads not served by the state cache"
+    );
+    pub static ref STATE_CACHE_EVICTIONS: Result<IntCounter> = try_create_int_counter(
+        "store_state_cache_evictions_total",
+        "Number of states evicted from the state cache"
+    );
+    pub static ref STATE_CACHE_SIZE: Result<IntGauge> = try_create_int_gauge(
+        "store_state_cache_size",
+        "Number of states in the state cache"
+    );
+    pub static ref STATE_CACHE_BYTES: Result<IntGauge> = try_create_int_gauge(
+        "store_state_cache_bytes",
+        "Estimated memory used by the states in the state cache (bytes)"
+    );
+    /*
+     * Hot State Summaries
+     */
+    pub static ref HOT_STATE_SUMMARY_WRITE_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_hot_state_summary_write_total",
+        "Number of hot states stored as a summary rather than in full"
+    );
+    pub static ref HOT_STATE_REPLAY_TIMES: Result<Histogram> = try_create_histogram(
+        "store_hot_state_replay_seconds",
+        "Time taken to reconstruct a hot state from its epoch boundary state"
+    );
 }
 
 /// Updates the global metrics registry with store-related information.
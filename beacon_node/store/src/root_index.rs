@@ -0,0 +1,72 @@
+use crate::{DBColumn, Error, SimpleStoreItem};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::Slot;
+
+/// 32-byte key for accessing the `RootIndexInfo` of the database.
+pub const ROOT_INDEX_INFO_KEY: &str = "ROOTINDEXINFOROOTINDEXINFOROOTIN";
+
+/// The slot of a block, keyed by the block root.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct BlockRootSlot {
+    pub slot: Slot,
+}
+
+impl SimpleStoreItem for BlockRootSlot {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconBlockRootIndex
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
+
+/// The slot of a state, keyed by the state root.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct StateRootSlot {
+    pub slot: Slot,
+}
+
+impl SimpleStoreItem for StateRootSlot {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconStateRootIndex
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
+
+/// Records that the root indices of a database are complete, i.e., that the blocks and states
+/// stored before the indices were introduced have been backfilled into them.
+///
+/// A database without a `RootIndexInfo` only indexes the blocks and states imported since it was
+/// upgraded.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct RootIndexInfo {
+    /// The slot of the head when the backfill completed.
+    pub backfilled_to_slot: Slot,
+}
+
+impl SimpleStoreItem for RootIndexInfo {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::metrics;
+use ssz::Encode;
+use std::collections::{HashMap, VecDeque};
+use std::mem::size_of;
+use types::{BeaconState, EthSpec, Hash256};
+
+/// A cache of recently read `BeaconState`s, keyed by state root.
+///
+/// The cache is bounded both by the number of states and by an estimate of the memory they use.
+/// When either bound would be exceeded, the least-recently used states are evicted. A bound of
+/// zero disables the cache.
+///
+/// Only the committee caches of each state are retained; the other caches (e.g., the tree hash
+/// cache) are dropped to keep the memory usage predictable.
+pub struct StateCache<E: EthSpec> {
+    states: HashMap<Hash256, (BeaconState<E>, usize)>,
+    /// State roots ordered from least to most recently used.
+    recency: VecDeque<Hash256>,
+    max_states: usize,
+    max_bytes: usize,
+    bytes: usize,
+}
+
+impl<E: EthSpec> StateCache<E> {
+    pub fn new(max_states: usize, max_bytes: usize) -> Self {
+        Self {
+            states: HashMap::new(),
+            recency: VecDeque::new(),
+            max_states,
+            max_bytes,
+            bytes: 0,
+        }
+    }
+
+    /// Returns a copy of the state with `state_root`, if it is cached.
+    pub fn get(&mut self, state_root: &Hash256) -> Option<BeaconState<E>> {
+        let state = self.states.get(state_root).map(|(state, _)| state.clone());
+
+        if state.is_some() {
+            metrics::inc_counter(&metrics::STATE_CACHE_HITS);
+            self.touch(state_root);
+        } else {
+            metrics::inc_counter(&metrics::STATE_CACHE_MISSES);
+        }
+
+        state
+    }
+
+    /// Adds `state` to the cache, evicting the least-recently used states if required.
+    ///
+    /// A state which would not fit in the cache even if it were empty is not added.
+    pub fn insert(&mut self, state_root: Hash256, state: &BeaconState<E>) {
+        if self.states.contains_key(&state_root) {
+            self.touch(&state_root);
+            return;
+        }
+
+        let size = estimate_size(state);
+        if self.max_states == 0 || size > self.max_bytes {
+            return;
+        }
+
+        while self.states.len() >= self.max_states || self.bytes + size > self.max_bytes {
+            if !self.evict_oldest() {
+                break;
+            }
+        }
+
+        let mut cached_state = state.clone_without_caches();
+        cached_state.committee_caches = state.committee_caches.clone();
+
+        self.states.insert(state_root, (cached_state, size));
+        self.recency.push_back(state_root);
+        self.bytes += size;
+
+        self.update_metrics();
+    }
+
+    /// Marks `state_root` as the most recently used state.
+    fn touch(&mut self, state_root: &Hash256) {
+        if let Some(i) = self.recency.iter().position(|root| root == state_root) {
+            self.recency.remove(i);
+            self.recency.push_back(*state_root);
+        }
+    }
+
+    /// Evicts the least-recently used state, returning `false` if the cache is empty.
+    fn evict_oldest(&mut self) -> bool {
+        match self.recency.pop_front() {
+            Some(state_root) => {
+                if let Some((_, size)) = self.states.remove(&state_root) {
+                    self.bytes -= size;
+                }
+                metrics::inc_counter(&metrics::STATE_CACHE_EVICTIONS);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update_metrics(&self) {
+        metrics::set_gauge(&metrics::STATE_CACHE_SIZE, self.states.len() as i64);
+        metrics::set_gauge(&metrics::STATE_CACHE_BYTES, self.bytes as i64);
+    }
+}
+
+/// Estimates the memory used by `state` once it is cached: its SSZ size, plus the committee
+/// caches (which hold at most two `usize` per validator for each cached epoch).
+fn estimate_size<E: EthSpec>(state: &BeaconState<E>) -> usize {
+    state.ssz_bytes_len()
+        + state.committee_caches.len() * state.validators.len() * 2 * size_of::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{test_utils::TestingBeaconStateBuilder, ChainSpec, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    fn get_state(spec: &ChainSpec) -> BeaconState<E> {
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, spec);
+        let (state, _keypairs) = builder.build();
+        state
+    }
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_state() {
+        let spec = MinimalEthSpec::default_spec();
+        let state = get_state(&spec);
+        let mut cache = StateCache::new(2, usize::max_value());
+
+        cache.insert(root(1), &state);
+        cache.insert(root(2), &state);
+        assert!(cache.get(&root(1)).is_some());
+
+        cache.insert(root(3), &state);
+        assert_eq!(cache.states.len(), 2);
+        assert!(cache.get(&root(1)).is_some(), "recently used state is kept");
+        assert!(
+            cache.get(&root(2)).is_none(),
+            "least recently used state is evicted"
+        );
+        assert!(cache.get(&root(3)).is_some());
+    }
+
+    #[test]
+    fn respects_memory_budget() {
+        let spec = MinimalEthSpec::default_spec();
+        let state = get_state(&spec);
+        let size = estimate_size(&state);
+        let mut cache = StateCache::new(16, size * 2);
+
+        for i in 0..4 {
+            cache.insert(root(i), &state);
+        }
+        assert_eq!(cache.states.len(), 2);
+        assert_eq!(cache.bytes, size * 2);
+
+        let mut tiny_cache = StateCache::new(16, size - 1);
+        tiny_cache.insert(root(0), &state);
+        assert!(
+            tiny_cache.states.is_empty(),
+            "a state larger than the budget is not cached"
+        );
+    }
+
+    #[test]
+    fn zero_bounds_disable_cache() {
+        let spec = MinimalEthSpec::default_spec();
+        let state = get_state(&spec);
+
+        let mut cache = StateCache::new(0, usize::max_value());
+        cache.insert(root(0), &state);
+        assert!(cache.states.is_empty());
+
+        let mut cache = StateCache::new(16, 0);
+        cache.insert(root(0), &state);
+        assert!(cache.states.is_empty());
+    }
+}
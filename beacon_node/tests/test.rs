@@ -2,8 +2,9 @@
 
 use node_test_rig::{
     environment::{Environment, EnvironmentBuilder},
-    testing_client_config, LocalBeaconNode,
+    testing_client_config, testing_client_config_with, ClientGenesis, LocalBeaconNode,
 };
+use std::time::Duration;
 use types::{EthSpec, MinimalEthSpec, Slot};
 
 fn env_builder() -> EnvironmentBuilder<MinimalEthSpec> {
@@ -51,3 +52,161 @@ fn http_server_genesis_state() {
         "genesis state from api should match that from the DB"
     );
 }
+
+#[test]
+fn builder_overrides_genesis() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let validator_count = 64;
+    let context = env.core_context();
+    let node = env
+        .runtime()
+        .block_on(
+            LocalBeaconNode::builder(
+                context,
+                testing_client_config_with(8, Duration::from_secs(0)),
+            )
+            .validator_count(validator_count)
+            .spec(|spec| spec.milliseconds_per_slot = 1_000)
+            .build(),
+        )
+        .expect("should block until node created");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    assert_eq!(
+        beacon_chain.head().beacon_state.validators.len(),
+        validator_count,
+        "genesis state should have the overridden validator count"
+    );
+    assert_eq!(
+        beacon_chain.spec.milliseconds_per_slot, 1_000,
+        "beacon chain should use the overridden spec"
+    );
+}
+
+#[test]
+fn fault_injection_hooks() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let node = build_node(&mut env);
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    node.pause_slot_clock().expect("should pause slot clock");
+    assert!(beacon_chain.slot_clock.is_paused());
+
+    node.set_gossip_drop_percentage(50)
+        .expect("should set gossip drop percentage");
+    node.set_block_import_delay(Duration::from_millis(500))
+        .expect("should set block import delay");
+
+    node.clear_faults().expect("should clear faults");
+    assert!(!beacon_chain.slot_clock.is_paused());
+}
+
+#[test]
+fn restart_from_datadir() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let node = build_node(&mut env);
+    let head = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain")
+        .head();
+
+    let stopped = env
+        .runtime()
+        .block_on(node.shutdown())
+        .expect("should shutdown node");
+
+    let context = env.core_context();
+    let node = env
+        .runtime()
+        .block_on(LocalBeaconNode::restart(
+            context,
+            testing_client_config(),
+            stopped,
+        ))
+        .expect("should restart node");
+    let restarted_head = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain")
+        .head();
+
+    assert_eq!(
+        restarted_head.beacon_block_root, head.beacon_block_root,
+        "restarted node should resume from the same head"
+    );
+    assert_eq!(
+        restarted_head.beacon_state_root, head.beacon_state_root,
+        "restarted node should resume from the same head state"
+    );
+}
+
+#[test]
+fn manual_time_advances_slot_clock() {
+    let client_config = testing_client_config();
+    let genesis_time = match client_config.genesis {
+        ClientGenesis::Interop { genesis_time, .. } => genesis_time,
+        _ => panic!("testing config should use interop genesis"),
+    };
+
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .manual_time(Duration::from_secs(genesis_time))
+        .build()
+        .expect("environment should build");
+
+    let context = env.core_context();
+    let node = env
+        .runtime()
+        .block_on(LocalBeaconNode::production(context, client_config))
+        .expect("should block until node created");
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    assert_eq!(beacon_chain.slot().expect("should read slot"), Slot::new(0));
+
+    // Advance through a day of slots, without waiting for them to pass.
+    let milliseconds_per_slot = beacon_chain.spec.milliseconds_per_slot;
+    let slots = 24 * 60 * 60 * 1_000 / milliseconds_per_slot;
+    env.time_source()
+        .advance(Duration::from_millis(slots * milliseconds_per_slot));
+
+    assert_eq!(
+        beacon_chain.slot().expect("should read slot"),
+        Slot::new(slots),
+        "slot clock should follow the manual time"
+    );
+}
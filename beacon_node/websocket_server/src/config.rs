@@ -1,5 +1,6 @@
 use serde_derive::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
 /// The core configuration of a Lighthouse beacon node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +10,14 @@ pub struct Config {
     pub listen_address: Ipv4Addr,
     /// The port the REST API HTTP server will listen on.
     pub port: u16,
+    /// If set, connections are served over TLS with the PKCS #12 identity (a certificate chain
+    /// and its private key) in this file.
+    pub tls_identity_file: Option<PathBuf>,
+    /// The file containing the password of the `tls_identity_file`, if it has one.
+    pub tls_identity_password_file: Option<PathBuf>,
+    /// If set, connections must present the token in this file, either as a bearer token in the
+    /// `Authorization` header or as the `token` query parameter.
+    pub auth_token_file: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -17,6 +26,9 @@ impl Default for Config {
             enabled: false,
             listen_address: Ipv4Addr::new(127, 0, 0, 1),
             port: 5053,
+            tls_identity_file: None,
+            tls_identity_password_file: None,
+            auth_token_file: None,
         }
     }
 }
@@ -0,0 +1,151 @@
+use native_tls::{TlsAcceptor, TlsStream};
+use slog::{debug, Logger};
+use std::net::TcpStream;
+use std::sync::Arc;
+use ws::{CloseCode, ErrorKind, Handler, Handshake, Request, Response};
+
+/// The query parameter in which a client may present its token, for clients (e.g., browsers)
+/// which cannot set the `Authorization` header of a websocket request.
+pub const TOKEN_QUERY_PARAM: &str = "token";
+
+/// Handles a single websocket connection, which only receives events.
+///
+/// Rejects the handshake of a client which does not present `token`, if it is set, and terminates
+/// TLS with `tls_acceptor`, if it is set.
+pub struct Connection {
+    pub token: Option<Arc<String>>,
+    pub tls_acceptor: Option<Arc<TlsAcceptor>>,
+    pub log: Logger,
+}
+
+impl Connection {
+    /// Returns `true` if `req` presents the token, or no token is required.
+    fn is_authorized(&self, req: &Request) -> bool {
+        let token = match &self.token {
+            Some(token) => token,
+            None => return true,
+        };
+
+        let bearer = req
+            .header("Authorization")
+            .and_then(|value| std::str::from_utf8(value).ok())
+            .and_then(|value| {
+                let mut parts = value.splitn(2, ' ');
+                match (parts.next(), parts.next()) {
+                    (Some("Bearer"), Some(bearer)) => Some(bearer.trim()),
+                    _ => None,
+                }
+            });
+
+        let query_token = req.resource().splitn(2, '?').nth(1).and_then(|query| {
+            query.split('&').find_map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(TOKEN_QUERY_PARAM), Some(value)) => Some(value),
+                    _ => None,
+                }
+            })
+        });
+
+        bearer
+            .into_iter()
+            .chain(query_token)
+            .any(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+    }
+}
+
+impl Handler for Connection {
+    fn on_request(&mut self, req: &Request) -> ws::Result<Response> {
+        if self.is_authorized(req) {
+            Response::from_request(req)
+        } else {
+            debug!(self.log, "Rejected unauthorized websocket client");
+
+            let mut response = Response::new(401, "Unauthorized", b"Invalid token".to_vec());
+            response
+                .headers_mut()
+                .push(("WWW-Authenticate".into(), b"Bearer".to_vec()));
+            Ok(response)
+        }
+    }
+
+    fn on_open(&mut self, shake: Handshake) -> ws::Result<()> {
+        let address = shake
+            .remote_addr()
+            .unwrap_or(None)
+            .unwrap_or_else(|| "unknown".to_string());
+        debug!(
+            self.log,
+            "Websocket client connected";
+            "address" => address,
+        );
+        Ok(())
+    }
+
+    fn on_close(&mut self, code: CloseCode, _reason: &str) {
+        debug!(
+            self.log,
+            "Websocket client disconnected";
+            "code" => format!("{:?}", code),
+        );
+    }
+
+    fn upgrade_ssl_server(&mut self, sock: TcpStream) -> ws::Result<TlsStream<TcpStream>> {
+        let tls_acceptor = self.tls_acceptor.as_ref().ok_or_else(|| {
+            ws::Error::new(ErrorKind::Internal, "TLS is not enabled for this server")
+        })?;
+
+        tls_acceptor.accept(sock).map_err(|e| {
+            ws::Error::new(
+                ErrorKind::Internal,
+                format!("TLS handshake failed: {:?}", e),
+            )
+        })
+    }
+}
+
+/// Compares `a` and `b` in time which depends only upon their lengths, so that a token cannot be
+/// guessed byte by byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::o;
+
+    fn connection(token: Option<&str>) -> Connection {
+        Connection {
+            token: token.map(|token| Arc::new(token.to_string())),
+            tls_acceptor: None,
+            log: Logger::root(slog::Discard, o!()),
+        }
+    }
+
+    fn request(resource: &str, authorization: Option<&str>) -> Request {
+        let mut req = Request::from_url(
+            &format!("ws://127.0.0.1:5053{}", resource)
+                .parse()
+                .expect("should parse url"),
+        )
+        .expect("should build request");
+        if let Some(authorization) = authorization {
+            req.headers_mut()
+                .push(("Authorization".into(), authorization.as_bytes().to_vec()));
+        }
+        req
+    }
+
+    #[test]
+    fn authorizes_token() {
+        assert!(connection(None).is_authorized(&request("/", None)));
+
+        let connection = connection(Some("secret"));
+        assert!(!connection.is_authorized(&request("/", None)));
+        assert!(!connection.is_authorized(&request("/", Some("Bearer wrong"))));
+        assert!(!connection.is_authorized(&request("/?token=secre", None)));
+        assert!(connection.is_authorized(&request("/", Some("Bearer secret"))));
+        assert!(connection.is_authorized(&request("/?a=b&token=secret", None)));
+    }
+}
@@ -1,15 +1,23 @@
+use connection::Connection;
+use environment::TaskExecutor;
 use futures::Future;
+use native_tls::{Identity, TlsAcceptor};
+use serde::Serialize;
 use slog::{debug, error, info, warn, Logger};
+use std::fs;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
 use std::thread;
-use tokio::runtime::TaskExecutor;
 use types::EthSpec;
-use ws::{Sender, WebSocket};
+use ws::{Builder, Sender, Settings};
 
 mod config;
+mod connection;
 
 pub use config::Config;
+pub use connection::TOKEN_QUERY_PARAM;
 
 pub struct WebSocketSender<T: EthSpec> {
     sender: Option<Sender>,
@@ -34,6 +42,14 @@ impl<T: EthSpec> WebSocketSender<T> {
             Ok(())
         }
     }
+
+    /// Serializes `value` as JSON and broadcasts it to all clients.
+    pub fn send_json<S: Serialize>(&self, value: &S) -> Result<(), String> {
+        self.send_string(
+            serde_json::to_string(value)
+                .map_err(|e| format!("Unable to serialize event: {:?}", e))?,
+        )
+    }
 }
 
 pub fn start_server<T: EthSpec>(
@@ -43,8 +59,44 @@ pub fn start_server<T: EthSpec>(
 ) -> Result<(WebSocketSender<T>, exit_future::Signal, SocketAddr), String> {
     let server_string = format!("{}:{}", config.listen_address, config.port);
 
+    let token = config
+        .auth_token_file
+        .as_ref()
+        .map(|path| read_secret(path, "auth token"))
+        .transpose()?
+        .map(Arc::new);
+    let tls_acceptor = config
+        .tls_identity_file
+        .as_ref()
+        .map(|path| build_tls_acceptor(path, config.tls_identity_password_file.as_ref()))
+        .transpose()?
+        .map(Arc::new);
+
+    if token.is_none() && !config.listen_address.is_loopback() {
+        warn!(
+            log,
+            "Websocket server is exposed without authentication";
+            "info" => "consider setting an auth token file",
+            "address" => format!("{}", config.listen_address),
+        );
+    }
+
+    let settings = Settings {
+        encrypt_server: tls_acceptor.is_some(),
+        ..Settings::default()
+    };
+    let is_encrypted = settings.encrypt_server;
+    let is_authenticated = token.is_some();
+
     // Create a server that simply ignores any incoming messages.
-    let server = WebSocket::new(|_| |_| Ok(()))
+    let log_inner = log.clone();
+    let server = Builder::new()
+        .with_settings(settings)
+        .build(move |_| Connection {
+            token: token.clone(),
+            tls_acceptor: tls_acceptor.clone(),
+            log: log_inner.clone(),
+        })
         .map_err(|e| format!("Failed to initialize websocket server: {:?}", e))?
         .bind(server_string.clone())
         .map_err(|e| {
@@ -84,7 +136,7 @@ pub fn start_server<T: EthSpec>(
 
         // Place a future on the executor that will shutdown the websocket server when the
         // application exits.
-        executor.spawn(exit_future);
+        executor.spawn(exit_future, "websocket_exit");
 
         exit_signal
     };
@@ -111,6 +163,8 @@ pub fn start_server<T: EthSpec>(
         "WebSocket server started";
         "address" => format!("{}", actual_listen_addr.ip()),
         "port" => actual_listen_addr.port(),
+        "tls" => is_encrypted,
+        "auth" => is_authenticated,
     );
 
     Ok((
@@ -122,3 +176,41 @@ pub fn start_server<T: EthSpec>(
         actual_listen_addr,
     ))
 }
+
+/// Reads a secret (e.g., the token which clients must present) from the file at `path`, ignoring
+/// surrounding whitespace.
+fn read_secret(path: &Path, name: &str) -> Result<String, String> {
+    let secret = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read websocket {} file {:?}: {:?}", name, path, e))?
+        .trim()
+        .to_string();
+
+    if secret.is_empty() {
+        Err(format!("Websocket {} file {:?} is empty", name, path))
+    } else {
+        Ok(secret)
+    }
+}
+
+/// Builds a `TlsAcceptor` from the PKCS #12 identity at `identity_path`, which is decrypted with
+/// the password in `password_path` (or an empty password if it is `None`).
+fn build_tls_acceptor<P: AsRef<Path>>(
+    identity_path: &Path,
+    password_path: Option<P>,
+) -> Result<TlsAcceptor, String> {
+    let identity = fs::read(identity_path).map_err(|e| {
+        format!(
+            "Unable to read websocket TLS identity file {:?}: {:?}",
+            identity_path, e
+        )
+    })?;
+    let password = match password_path {
+        Some(path) => read_secret(path.as_ref(), "TLS identity password")?,
+        None => String::new(),
+    };
+
+    let identity = Identity::from_pkcs12(&identity, &password)
+        .map_err(|e| format!("Invalid websocket TLS identity: {:?}", e))?;
+
+    TlsAcceptor::new(identity).map_err(|e| format!("Unable to build TLS acceptor: {:?}", e))
+}
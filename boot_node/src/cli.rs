@@ -0,0 +1,58 @@
+use clap::{App, Arg};
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    App::new("boot_node")
+        .visible_aliases(&["bootnode", "boot"])
+        .about(
+            "Runs only the discv5 discovery protocol, helping the nodes of a testnet find each \
+             other. Does not run a beacon chain.",
+        )
+        .arg(
+            Arg::with_name("network-dir")
+                .long("network-dir")
+                .value_name("DIR")
+                .help(
+                    "Data directory for the network key, which determines the node ID of the \
+                     boot node. Defaults to the boot_node directory in the --datadir.",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("listen-address")
+                .long("listen-address")
+                .value_name("ADDRESS")
+                .help("The address the boot node will listen for UDP connections on.")
+                .default_value("0.0.0.0")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .value_name("PORT")
+                .help("The UDP port to listen on and to advertise in the ENR.")
+                .default_value("9000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enr-address")
+                .long("enr-address")
+                .value_name("ADDRESS")
+                .help(
+                    "The IP address to advertise in the ENR. The ENR is never updated, so this \
+                     should be the public address of the boot node.",
+                )
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("boot-nodes")
+                .long("boot-nodes")
+                .allow_hyphen_values(true)
+                .value_name("ENR-LIST")
+                .help(
+                    "One or more comma-delimited base64-encoded ENRs of other boot nodes, in \
+                     addition to those in the testnet directory.",
+                )
+                .takes_value(true),
+        )
+}
@@ -0,0 +1,64 @@
+use clap::ArgMatches;
+use eth2_libp2p::{address::parse_enr_list, NetworkConfig};
+use eth2_testnet_config::Eth2TestnetConfig;
+use std::path::PathBuf;
+use types::EthSpec;
+
+/// The name of the directory, within the `--datadir`, where the network key is stored.
+pub const BOOT_NODE_DIR: &str = "boot_node";
+
+/// Builds the `NetworkConfig` of a boot node from the CLI arguments.
+///
+/// The boot nodes listed in the testnet directory (or the hard-coded testnet, if there is no
+/// `--testnet-dir`) are added to the routing table, along with any given by `--boot-nodes`.
+pub fn get_config<E: EthSpec>(cli_args: &ArgMatches) -> Result<NetworkConfig, String> {
+    let mut config = NetworkConfig::default();
+
+    config.network_dir = if let Some(dir) = cli_args.value_of("network-dir") {
+        PathBuf::from(dir)
+    } else {
+        cli_args
+            .value_of("datadir")
+            .map(PathBuf::from)
+            .unwrap_or_else(lighthouse_dirs::default_root_dir)
+            .join(BOOT_NODE_DIR)
+    };
+
+    config.listen_address = parse_arg(cli_args, "listen-address")?;
+    config.discovery_port = parse_arg(cli_args, "port")?;
+    config.discovery_address = parse_arg(cli_args, "enr-address")?;
+
+    let testnet_config: Eth2TestnetConfig<E> =
+        if let Some(testnet_dir) = cli_args.value_of("testnet-dir") {
+            Eth2TestnetConfig::load(PathBuf::from(testnet_dir))
+                .map_err(|e| format!("Unable to open testnet dir at {}: {}", testnet_dir, e))?
+        } else {
+            Eth2TestnetConfig::hard_coded()
+                .map_err(|e| format!("Unable to load hard-coded testnet dir: {}", e))?
+        };
+
+    if let Some(mut boot_nodes) = testnet_config.boot_enr {
+        config.boot_nodes.append(&mut boot_nodes);
+    }
+
+    if let Some(boot_nodes) = cli_args.value_of("boot-nodes") {
+        config.boot_nodes.extend(
+            parse_enr_list(boot_nodes).map_err(|e| format!("Invalid --boot-nodes: {}", e))?,
+        );
+    }
+
+    Ok(config)
+}
+
+/// Parses the value of the argument `name`, which must have a default value.
+fn parse_arg<T>(cli_args: &ArgMatches, name: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    cli_args
+        .value_of(name)
+        .ok_or_else(|| format!("Expected --{} flag", name))?
+        .parse()
+        .map_err(|e| format!("Invalid --{}: {}", name, e))
+}
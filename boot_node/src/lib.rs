@@ -0,0 +1,91 @@
+//! A discovery-only boot node.
+//!
+//! Runs the discv5 protocol with a static ENR and nothing else (i.e., no beacon chain, no libp2p
+//! protocols), so that testnet operators can run lightweight nodes which only help other nodes
+//! find peers.
+mod cli;
+mod config;
+
+pub use cli::cli_app;
+pub use config::get_config;
+pub use eth2_libp2p::{Enr, NetworkConfig};
+
+use clap::ArgMatches;
+use environment::{RuntimeContext, ShutdownReason};
+use eth2_libp2p::Discv5Service;
+use futures::{future, Future, IntoFuture};
+use slog::{crit, info};
+use tokio::sync::oneshot;
+use types::EthSpec;
+
+/// A boot node running on the executor of a `RuntimeContext`.
+///
+/// Discovery is stopped when the `BootNode` is dropped.
+pub struct BootNode {
+    enr: Enr,
+    _exit: oneshot::Sender<()>,
+}
+
+impl BootNode {
+    /// Starts a boot node with a config generated from the CLI `matches`.
+    pub fn new_from_cli<E: EthSpec>(
+        context: RuntimeContext<E>,
+        matches: &ArgMatches,
+    ) -> impl Future<Item = Self, Error = String> {
+        get_config::<E>(matches)
+            .into_future()
+            .and_then(move |config| Self::new(context, config))
+    }
+
+    /// Starts a boot node which listens and advertises as described by `config`.
+    ///
+    /// The node runs until it is dropped. If discovery fails, a shutdown of the whole process is
+    /// requested.
+    ///
+    /// The UDP socket is bound on the runtime of `context`, hence the returned future.
+    pub fn new<E: EthSpec>(
+        context: RuntimeContext<E>,
+        config: NetworkConfig,
+    ) -> impl Future<Item = Self, Error = String> {
+        future::lazy(move || {
+            let log = context.log.clone();
+            let mut shutdown_sender = context.shutdown_sender.clone();
+
+            let service = Discv5Service::new(&config, log.clone())
+                .map_err(|e| format!("Failed to start discovery: {:?}", e))?;
+            let enr = service.local_enr().clone();
+
+            info!(
+                log,
+                "Boot node started";
+                "enr" => enr.to_base64(),
+                "boot_nodes" => config.boot_nodes.len(),
+            );
+
+            let (exit, exit_rx) = oneshot::channel();
+            context.executor.spawn(
+                service
+                    .select(exit_rx.then(|_| Ok(())))
+                    .then(move |result| {
+                        match result {
+                            Ok(_) => info!(log, "Boot node shutdown"),
+                            Err((e, _)) => {
+                                crit!(log, "Boot node failed"; "error" => format!("{:?}", e));
+                                let _ = shutdown_sender
+                                    .try_send(ShutdownReason::Failure("Boot node failed"));
+                            }
+                        }
+                        Ok(())
+                    }),
+                "boot_node",
+            );
+
+            Ok(Self { enr, _exit: exit })
+        })
+    }
+
+    /// Returns the ENR advertised by the boot node.
+    pub fn enr(&self) -> &Enr {
+        &self.enr
+    }
+}
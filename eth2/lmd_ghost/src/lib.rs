@@ -8,6 +8,22 @@ pub use reduced_tree::ThreadSafeReducedTree;
 
 pub type Result<T> = std::result::Result<T, String>;
 
+/// A node of the block tree maintained by an `LmdGhost` implementation, for introspection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    /// The root of the block represented by this node.
+    pub root: Hash256,
+    /// The root of the parent node, if any.
+    ///
+    /// Implementations which do not store every block may report an ancestor, rather than the
+    /// parent block.
+    pub parent: Option<Hash256>,
+    /// The weight of the node, as of the last call to `find_head`.
+    pub weight: u64,
+    /// The node that `find_head` would select as the head if it started at this node.
+    pub best_descendant: Hash256,
+}
+
 // Note: the `PartialEq` bound is only required for testing. If it becomes a serious annoyance we
 // can remove it.
 pub trait LmdGhost<S: Store<E>, E: EthSpec>: PartialEq + Send + Sync + Sized {
@@ -23,6 +39,19 @@ pub trait LmdGhost<S: Store<E>, E: EthSpec>: PartialEq + Send + Sync + Sized {
         block_slot: Slot,
     ) -> Result<()>;
 
+    /// Process a batch of attestation messages, each a `(validator_index, block_hash, block_slot)`
+    /// as in `process_attestation`.
+    ///
+    /// Implementations may override this to apply the whole batch whilst holding their locks
+    /// once, rather than once per message.
+    fn process_attestations(&self, messages: &[(usize, Hash256, Slot)]) -> Result<()> {
+        messages
+            .iter()
+            .try_for_each(|(validator_index, block_hash, block_slot)| {
+                self.process_attestation(*validator_index, *block_hash, *block_slot)
+            })
+    }
+
     /// Process a block that was seen on the network.
     fn process_block(&self, block: &BeaconBlock<E>, block_hash: Hash256) -> Result<()>;
 
@@ -49,6 +78,9 @@ pub trait LmdGhost<S: Store<E>, E: EthSpec>: PartialEq + Send + Sync + Sized {
     /// Returns the latest message for a given validator index.
     fn latest_message(&self, validator_index: usize) -> Option<(Hash256, Slot)>;
 
+    /// Returns all of the nodes in the block tree, in no particular order.
+    fn nodes(&self) -> Result<Vec<TreeNode>>;
+
     /// Runs an integrity verification function on fork choice algorithm.
     ///
     /// Returns `Ok(())` if the underlying fork choice has maintained its integrity,
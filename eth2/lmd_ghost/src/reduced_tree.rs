@@ -3,7 +3,7 @@
 //! This algorithm was conceived at IC3 Cornell, 2019.
 //!
 //! This implementation is incomplete and has known bugs. Do not use in production.
-use super::{LmdGhost, Result as SuperResult};
+use super::{LmdGhost, Result as SuperResult, TreeNode};
 use itertools::Itertools;
 use parking_lot::RwLock;
 use ssz::{Decode, Encode};
@@ -85,6 +85,17 @@ where
             .map_err(|e| format!("process_attestation failed: {:?}", e))
     }
 
+    fn process_attestations(&self, messages: &[(usize, Hash256, Slot)]) -> SuperResult<()> {
+        let mut core = self.core.write();
+
+        messages
+            .iter()
+            .try_for_each(|(validator_index, block_hash, block_slot)| {
+                core.process_message(*validator_index, *block_hash, *block_slot)
+            })
+            .map_err(|e| format!("process_attestations failed: {:?}", e))
+    }
+
     /// Process a block that was seen on the network.
     fn process_block(&self, block: &BeaconBlock<E>, block_hash: Hash256) -> SuperResult<()> {
         self.core
@@ -123,6 +134,13 @@ where
         self.core.read().latest_message(validator_index)
     }
 
+    fn nodes(&self) -> SuperResult<Vec<TreeNode>> {
+        self.core
+            .read()
+            .nodes()
+            .map_err(|e| format!("nodes failed: {:?}", e))
+    }
+
     fn verify_integrity(&self) -> SuperResult<()> {
         self.core.read().verify_integrity()
     }
@@ -341,6 +359,26 @@ where
         }
     }
 
+    /// Returns a summary of every node in the reduced tree.
+    ///
+    /// The parent of each node is its parent in the reduced tree, which may be a distant
+    /// ancestor of its block.
+    pub fn nodes(&self) -> Result<Vec<TreeNode>> {
+        let (_, root_slot) = self.root;
+
+        self.nodes
+            .values()
+            .map(|node| {
+                Ok(TreeNode {
+                    root: node.block_hash,
+                    parent: node.parent_hash,
+                    weight: node.weight,
+                    best_descendant: self.find_head_from(node, root_slot)?.block_hash,
+                })
+            })
+            .collect()
+    }
+
     // Corresponds to the loop in `get_head` in the spec.
     fn find_head_from<'a>(
         &'a self,
@@ -397,3 +397,50 @@ fn update_finalized_root_honest() {
 
     test_update_finalized_root(&harness.honest_roots)
 }
+
+/// Votes on both forks are reflected in the weights and best descendants of the tree's nodes.
+#[test]
+fn nodes_report_weights_and_best_descendants() {
+    let harness = &FORKED_HARNESS;
+    let lmd = harness.new_fork_choice();
+
+    let (honest_root, honest_slot) = *harness.honest_roots.first().unwrap();
+    let (faulty_root, faulty_slot) = *harness.faulty_roots.first().unwrap();
+
+    lmd.process_attestation(0, honest_root, honest_slot)
+        .expect("should accept honest vote");
+    lmd.process_attestation(1, honest_root, honest_slot)
+        .expect("should accept honest vote");
+    lmd.process_attestation(2, faulty_root, faulty_slot)
+        .expect("should accept faulty vote");
+
+    let head = lmd
+        .find_head(
+            Slot::new(0),
+            harness.genesis_block_root,
+            ForkedHarness::weight_function,
+        )
+        .expect("should find head");
+    assert_eq!(head, honest_root, "honest head should be selected");
+
+    let nodes = lmd.nodes().expect("should list nodes");
+    let node = |root: Hash256| {
+        nodes
+            .iter()
+            .find(|node| node.root == root)
+            .unwrap_or_else(|| panic!("{:?} should be in the tree", root))
+    };
+
+    let genesis = node(harness.genesis_block_root);
+    assert_eq!(genesis.parent, None);
+    assert_eq!(genesis.weight, 3);
+    assert_eq!(genesis.best_descendant, honest_root);
+
+    let honest = node(honest_root);
+    assert_eq!(honest.weight, 2);
+    assert_eq!(honest.best_descendant, honest_root);
+
+    let faulty = node(faulty_root);
+    assert_eq!(faulty.weight, 1);
+    assert_eq!(faulty.best_descendant, faulty_root);
+}
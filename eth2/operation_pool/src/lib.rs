@@ -21,8 +21,8 @@ use state_processing::per_block_processing::{
 use std::collections::{hash_map, HashMap, HashSet};
 use std::marker::PhantomData;
 use types::{
-    typenum::Unsigned, Attestation, AttesterSlashing, BeaconState, ChainSpec, EthSpec,
-    ProposerSlashing, Validator, VoluntaryExit,
+    typenum::Unsigned, Attestation, AttesterSlashing, BeaconState, ChainSpec, CommitteeIndex,
+    EthSpec, ProposerSlashing, Slot, Validator, VoluntaryExit,
 };
 
 #[derive(Default, Debug)]
@@ -90,6 +90,24 @@ impl<T: EthSpec> OperationPool<T> {
         self.attestations.read().values().map(Vec::len).sum()
     }
 
+    /// Returns the attestation for the committee at `slot` and `index` with the most signatures,
+    /// if any.
+    ///
+    /// This is the aggregate that should be published by an aggregator for that committee.
+    pub fn get_aggregated_attestation(
+        &self,
+        slot: Slot,
+        index: CommitteeIndex,
+    ) -> Option<Attestation<T>> {
+        self.attestations
+            .read()
+            .values()
+            .flatten()
+            .filter(|attestation| attestation.data.slot == slot && attestation.data.index == index)
+            .max_by_key(|attestation| attestation.aggregation_bits.num_set_bits())
+            .cloned()
+    }
+
     /// Get a list of attestations for inclusion in a block.
     pub fn get_attestations(
         &self,
@@ -310,6 +328,15 @@ impl<T: EthSpec> OperationPool<T> {
         self.prune_attester_slashings(finalized_state, spec);
         self.prune_voluntary_exits(finalized_state);
     }
+
+    /// Replace the contents of the pool with those of `other` (e.g., a pool loaded from a dump of
+    /// another node's pool).
+    pub fn replace(&self, other: Self) {
+        *self.attestations.write() = other.attestations.into_inner();
+        *self.attester_slashings.write() = other.attester_slashings.into_inner();
+        *self.proposer_slashings.write() = other.proposer_slashings.into_inner();
+        *self.voluntary_exits.write() = other.voluntary_exits.into_inner();
+    }
 }
 
 /// Filter up to a maximum number of operations out of an iterator.
@@ -519,6 +546,17 @@ mod release_tests {
         assert_eq!(op_pool.attestations.read().len(), committees.len());
         assert_eq!(op_pool.num_attestations(), committees.len());
 
+        // The aggregate for each committee should include every signature.
+        for bc in &committees {
+            let aggregate = op_pool
+                .get_aggregated_attestation(slot, bc.index)
+                .expect("should have aggregate");
+            assert_eq!(
+                aggregate.aggregation_bits.num_set_bits(),
+                bc.committee.len()
+            );
+        }
+
         // Before the min attestation inclusion delay, get_attestations shouldn't return anything.
         state.slot -= 1;
         assert_eq!(op_pool.get_attestations(state, spec).len(), 0);
@@ -1,14 +1,19 @@
 use crate::attestation_id::AttestationId;
 use crate::OperationPool;
 use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use types::*;
 
 /// SSZ-serializable version of `OperationPool`.
 ///
+/// Also serializable as JSON, so that the contents of a node's pool may be dumped for debugging
+/// and loaded into another node.
+///
 /// Operations are stored in arbitrary order, so it's not a good idea to compare instances
 /// of this type (or its encoded form) for equality. Convert back to an `OperationPool` first.
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode, Serialize, Deserialize)]
+#[serde(bound = "T: EthSpec")]
 pub struct PersistedOperationPool<T: EthSpec> {
     /// Mapping from attestation ID to attestation mappings.
     // We could save space by not storing the attestation ID, but it might
@@ -97,3 +102,39 @@ impl<T: EthSpec> PersistedOperationPool<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::TestingBeaconStateBuilder;
+
+    type E = MinimalEthSpec;
+
+    #[test]
+    fn json_round_trip() {
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::<E>::from_deterministic_keypairs(8, &spec).build();
+
+        let op_pool = OperationPool::<E>::new();
+        for validator_index in 0..2 {
+            op_pool.voluntary_exits.write().insert(
+                validator_index,
+                VoluntaryExit {
+                    epoch: Epoch::new(1),
+                    validator_index,
+                    signature: Signature::empty_signature(),
+                },
+            );
+        }
+
+        let json = serde_json::to_string(&PersistedOperationPool::from_operation_pool(&op_pool))
+            .expect("should encode");
+        let decoded: PersistedOperationPool<E> =
+            serde_json::from_str(&json).expect("should decode");
+
+        let loaded = OperationPool::new();
+        loaded.replace(decoded.into_operation_pool(&state, &spec));
+        assert_eq!(loaded, op_pool);
+    }
+}
@@ -2,9 +2,16 @@ extern crate env_logger;
 
 use criterion::Criterion;
 use criterion::{black_box, criterion_group, criterion_main, Benchmark};
-use ssz::Encode;
-use state_processing::{test_utils::BlockBuilder, BlockSignatureStrategy, VerifySignatures};
-use types::{BeaconBlock, BeaconState, ChainSpec, EthSpec, MainnetEthSpec, MinimalEthSpec, Slot};
+use ssz::{Decode, Encode};
+use state_processing::{
+    per_block_processing::{verify_deposit_merkle_proof, verify_deposit_merkle_proofs},
+    test_utils::BlockBuilder,
+    BlockSignatureStrategy, BlockSignatureVerifier, CommitteePubkeyCache, VerifySignatures,
+};
+use types::test_utils::{DepositTestTask, TestingBeaconBlockBuilder, TestingBeaconStateBuilder};
+use types::{
+    BeaconBlock, BeaconState, ChainSpec, EthSpec, MainnetEthSpec, MinimalEthSpec, Slot, Unsigned,
+};
 
 pub const VALIDATORS_LOW: usize = 32_768;
 pub const VALIDATORS_HIGH: usize = 300_032;
@@ -19,6 +26,8 @@ fn all_benches(c: &mut Criterion) {
     worst_bench::<MinimalEthSpec>(c, "minimal", VALIDATORS_LOW);
     worst_bench::<MainnetEthSpec>(c, "mainnet", VALIDATORS_LOW);
     worst_bench::<MainnetEthSpec>(c, "mainnet", VALIDATORS_HIGH);
+
+    deposit_bench::<MainnetEthSpec>(c, "mainnet");
 }
 
 /// Run a bench with a average complexity block.
@@ -40,6 +49,75 @@ fn worst_bench<T: EthSpec>(c: &mut Criterion, spec_desc: &str, validator_count:
     bench_block::<T>(c, block, state, spec, spec_desc, "high_complexity_block");
 }
 
+/// Run a bench of the merkle proof verification of a block with `MAX_DEPOSITS` deposits.
+fn deposit_bench<T: EthSpec>(c: &mut Criterion, spec_desc: &str) {
+    let spec = &T::default_spec();
+
+    let (mut state, _keypairs) =
+        TestingBeaconStateBuilder::<T>::from_deterministic_keypairs(16, spec).build();
+    let mut builder = TestingBeaconBlockBuilder::<T>::new(spec);
+    builder.insert_deposits(
+        spec.max_effective_balance,
+        DepositTestTask::Valid,
+        0,
+        T::MaxDeposits::to_u64(),
+        &mut state,
+        spec,
+    );
+    let deposits = builder.block.body.deposits.to_vec();
+
+    let title = &format!("{}/{}_deposits", spec_desc, deposits.len());
+
+    let local_state = state.clone();
+    let local_deposits = deposits.clone();
+    let local_spec = spec.clone();
+    c.bench(
+        &title,
+        Benchmark::new("deposit_merkle_proofs/individual", move |b| {
+            b.iter_batched_ref(
+                || (),
+                |_| {
+                    for (i, deposit) in local_deposits.iter().enumerate() {
+                        black_box(
+                            verify_deposit_merkle_proof(
+                                &local_state,
+                                deposit,
+                                local_state.eth1_deposit_index + i as u64,
+                                &local_spec,
+                            )
+                            .expect("should verify deposit"),
+                        )
+                    }
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+
+    let local_state = state.clone();
+    let local_deposits = deposits.clone();
+    let local_spec = spec.clone();
+    c.bench(
+        &title,
+        Benchmark::new("deposit_merkle_proofs/batch", move |b| {
+            b.iter_batched_ref(
+                || (),
+                |_| {
+                    assert!(black_box(verify_deposit_merkle_proofs(
+                        &local_state,
+                        &local_deposits,
+                        local_state.eth1_deposit_index,
+                        &local_spec,
+                    )))
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+}
+
 /// Return a block and state where the block has "average" complexity. I.e., the number of
 /// operations we'd generally expect to see.
 fn get_average_block<T: EthSpec>(
@@ -219,6 +297,63 @@ fn bench_block<T: EthSpec>(
         .sample_size(10),
     );
 
+    let local_block = block.clone();
+    let local_state = state.clone();
+    let local_spec = spec.clone();
+    c.bench(
+        &title,
+        Benchmark::new("verify_entire_block_signatures", move |b| {
+            b.iter_batched_ref(
+                || (local_spec.clone(), local_state.clone(), local_block.clone()),
+                |(spec, state, block)| {
+                    black_box(
+                        BlockSignatureVerifier::verify_entire_block(state, &block, &spec)
+                            .expect("verify_entire_block should succeed"),
+                    )
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+
+    let local_block = block.clone();
+    let local_state = state.clone();
+    let local_spec = spec.clone();
+    // Warm the cache, as the cache of a node would be warmed by gossip attestations.
+    let pubkey_cache = CommitteePubkeyCache::default();
+    BlockSignatureVerifier::verify_entire_block_with_pubkey_cache(
+        &state,
+        &block,
+        &pubkey_cache,
+        spec,
+    )
+    .expect("verify_entire_block_with_pubkey_cache should succeed");
+    c.bench(
+        &title,
+        Benchmark::new(
+            "verify_entire_block_signatures_with_pubkey_cache",
+            move |b| {
+                b.iter_batched_ref(
+                    || (local_spec.clone(), local_state.clone(), local_block.clone()),
+                    |(spec, state, block)| {
+                        black_box(
+                            BlockSignatureVerifier::verify_entire_block_with_pubkey_cache(
+                                state,
+                                &block,
+                                &pubkey_cache,
+                                &spec,
+                            )
+                            .expect("verify_entire_block_with_pubkey_cache should succeed"),
+                        )
+                    },
+                    criterion::BatchSize::SmallInput,
+                )
+            },
+        )
+        .sample_size(10),
+    );
+
     let local_block = block.clone();
     let local_state = state.clone();
     let local_spec = spec.clone();
@@ -421,6 +556,24 @@ fn bench_block<T: EthSpec>(
         })
         .sample_size(10),
     );
+
+    let local_block_bytes = block.as_ssz_bytes();
+    c.bench(
+        &title,
+        Benchmark::new("ssz_deserialize_block", move |b| {
+            b.iter_batched_ref(
+                || (),
+                |_| {
+                    black_box(
+                        BeaconBlock::<T>::from_ssz_bytes(&local_block_bytes)
+                            .expect("should decode block"),
+                    )
+                },
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
 }
 
 criterion_group!(benches, all_benches,);
@@ -0,0 +1,294 @@
+//! Applies a sequence of blocks to a state, skipping slots as necessary.
+//!
+//! Replaying blocks is required wherever a state is reconstructed from an earlier one (e.g., when
+//! loading states from the database, or transitioning states in `lcli`). The amount of
+//! verification required differs between the callers: blocks read from the database have already
+//! been verified, whilst blocks supplied by a user have not.
+use crate::per_block_processing::{errors::BlockProcessingError, per_block_processing};
+use crate::per_slot_processing::{per_slot_processing, Error as SlotProcessingError};
+use crate::{BlockSignatureStrategy, EpochProcessingSummary};
+use types::{BeaconBlock, BeaconState, BeaconStateError, ChainSpec, EthSpec, Hash256, Slot};
+
+#[derive(Debug, PartialEq)]
+pub enum BlockReplayError {
+    BeaconStateError(BeaconStateError),
+    SlotProcessingError(SlotProcessingError),
+    BlockProcessingError {
+        slot: Slot,
+        error: BlockProcessingError,
+    },
+    /// A block is for a slot prior to the slot of the state.
+    BlockPriorToState {
+        block_slot: Slot,
+        state_slot: Slot,
+    },
+    /// The state root of a block does not match the state produced by applying it.
+    StateRootMismatch {
+        slot: Slot,
+        expected: Hash256,
+        observed: Hash256,
+    },
+}
+
+impl From<BeaconStateError> for BlockReplayError {
+    fn from(e: BeaconStateError) -> Self {
+        BlockReplayError::BeaconStateError(e)
+    }
+}
+
+impl From<SlotProcessingError> for BlockReplayError {
+    fn from(e: SlotProcessingError) -> Self {
+        BlockReplayError::SlotProcessingError(e)
+    }
+}
+
+/// Applies blocks to a state, with a configurable level of verification.
+///
+/// By default, signatures and state roots are not verified, which is only suitable for blocks
+/// which have been verified before (e.g., those read from the database).
+pub struct BlockReplayer<'a, T: EthSpec> {
+    state: BeaconState<T>,
+    spec: &'a ChainSpec,
+    block_signature_strategy: BlockSignatureStrategy,
+    verify_state_roots: bool,
+    target_slot: Option<Slot>,
+    epoch_summaries: Option<Vec<EpochProcessingSummary>>,
+}
+
+impl<'a, T: EthSpec> BlockReplayer<'a, T> {
+    /// Instantiates a replayer which applies blocks on top of `state`.
+    pub fn new(state: BeaconState<T>, spec: &'a ChainSpec) -> Self {
+        Self {
+            state,
+            spec,
+            block_signature_strategy: BlockSignatureStrategy::NoVerification,
+            verify_state_roots: false,
+            target_slot: None,
+            epoch_summaries: None,
+        }
+    }
+
+    /// Sets the strategy used to verify the signatures of each block.
+    pub fn block_signature_strategy(mut self, strategy: BlockSignatureStrategy) -> Self {
+        self.block_signature_strategy = strategy;
+        self
+    }
+
+    /// Verifies that the state root of each block matches the state produced by applying it.
+    pub fn verify_state_roots(mut self) -> Self {
+        self.verify_state_roots = true;
+        self
+    }
+
+    /// Stops replaying at `slot`: blocks after `slot` are ignored, and the state is advanced
+    /// through any empty slots up to `slot` once the blocks are applied.
+    pub fn stop_at_slot(mut self, slot: Slot) -> Self {
+        self.target_slot = Some(slot);
+        self
+    }
+
+    /// Retains the summary produced by each epoch transition, see `into_epoch_summaries`.
+    pub fn collect_epoch_summaries(mut self) -> Self {
+        self.epoch_summaries = Some(vec![]);
+        self
+    }
+
+    /// Applies `blocks`, which must be in ascending slot order, to the state.
+    pub fn apply_blocks(mut self, blocks: Vec<BeaconBlock<T>>) -> Result<Self, BlockReplayError> {
+        for block in blocks {
+            if self.target_slot.map_or(false, |target| block.slot > target) {
+                break;
+            }
+
+            if block.slot < self.state.slot {
+                return Err(BlockReplayError::BlockPriorToState {
+                    block_slot: block.slot,
+                    state_slot: self.state.slot,
+                });
+            }
+
+            self.advance_to(block.slot)?;
+            self.state.build_all_caches(self.spec)?;
+
+            per_block_processing(
+                &mut self.state,
+                &block,
+                None,
+                self.block_signature_strategy,
+                self.spec,
+            )
+            .map_err(|error| BlockReplayError::BlockProcessingError {
+                slot: block.slot,
+                error,
+            })?;
+
+            if self.verify_state_roots {
+                let observed = self.state.update_tree_hash_cache()?;
+                if observed != block.state_root {
+                    return Err(BlockReplayError::StateRootMismatch {
+                        slot: block.slot,
+                        expected: block.state_root,
+                        observed,
+                    });
+                }
+            }
+        }
+
+        if let Some(target_slot) = self.target_slot {
+            self.advance_to(target_slot)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the state, with all the blocks applied.
+    pub fn into_state(self) -> BeaconState<T> {
+        self.state
+    }
+
+    /// Returns the summaries of the epoch transitions performed whilst applying the blocks, in
+    /// ascending epoch order.
+    ///
+    /// Always empty unless `collect_epoch_summaries` was called.
+    pub fn into_epoch_summaries(self) -> Vec<EpochProcessingSummary> {
+        self.epoch_summaries.unwrap_or_default()
+    }
+
+    /// Processes empty slots until the state is at `slot`.
+    fn advance_to(&mut self, slot: Slot) -> Result<(), BlockReplayError> {
+        if self.state.slot < slot {
+            self.state.build_all_caches(self.spec)?;
+        }
+
+        while self.state.slot < slot {
+            let summary = per_slot_processing(&mut self.state, self.spec)?;
+
+            if let (Some(summaries), Some(summary)) = (&mut self.epoch_summaries, summary) {
+                summaries.push(summary);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_hash::SignedRoot;
+    use types::test_utils::{TestingBeaconBlockBuilder, TestingBeaconStateBuilder};
+    use types::{Epoch, MinimalEthSpec};
+
+    type E = MinimalEthSpec;
+
+    /// Returns a state and an empty block which may be applied to it at `slot`, with the correct
+    /// state root.
+    fn state_and_block(slot: Slot, spec: &ChainSpec) -> (BeaconState<E>, BeaconBlock<E>) {
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(8, spec).build();
+
+        let mut advanced = BlockReplayer::new(state.clone(), spec)
+            .stop_at_slot(slot)
+            .apply_blocks(vec![])
+            .expect("should advance state")
+            .into_state();
+
+        let mut builder = TestingBeaconBlockBuilder::new(spec);
+        builder.set_slot(slot);
+        builder.set_parent_root(Hash256::from_slice(
+            &advanced.latest_block_header.signed_root(),
+        ));
+        let mut block = builder.build_without_signing();
+
+        advanced
+            .build_all_caches(spec)
+            .expect("should build caches");
+        per_block_processing(
+            &mut advanced,
+            &block,
+            None,
+            BlockSignatureStrategy::NoVerification,
+            spec,
+        )
+        .expect("should process block");
+        block.state_root = advanced
+            .update_tree_hash_cache()
+            .expect("should hash state");
+
+        (state, block)
+    }
+
+    #[test]
+    fn applies_blocks_up_to_target_slot() {
+        let spec = E::default_spec();
+        let (state, block) = state_and_block(Slot::new(2), &spec);
+
+        let replayed = BlockReplayer::new(state.clone(), &spec)
+            .verify_state_roots()
+            .stop_at_slot(Slot::new(4))
+            .apply_blocks(vec![block.clone()])
+            .expect("should replay block")
+            .into_state();
+        assert_eq!(replayed.slot, Slot::new(4));
+        assert_eq!(replayed.latest_block_header.slot, block.slot);
+
+        let stopped = BlockReplayer::new(state, &spec)
+            .stop_at_slot(Slot::new(1))
+            .apply_blocks(vec![block])
+            .expect("should ignore block after target slot")
+            .into_state();
+        assert_eq!(stopped.slot, Slot::new(1));
+        assert_eq!(stopped.latest_block_header.slot, Slot::new(0));
+    }
+
+    #[test]
+    fn verifies_state_roots() {
+        let spec = E::default_spec();
+        let (state, mut block) = state_and_block(Slot::new(1), &spec);
+        block.state_root = Hash256::zero();
+
+        assert!(BlockReplayer::new(state.clone(), &spec)
+            .apply_blocks(vec![block.clone()])
+            .is_ok());
+
+        match BlockReplayer::new(state, &spec)
+            .verify_state_roots()
+            .apply_blocks(vec![block])
+        {
+            Err(BlockReplayError::StateRootMismatch { slot, expected, .. }) => {
+                assert_eq!(slot, Slot::new(1));
+                assert_eq!(expected, Hash256::zero());
+            }
+            _ => panic!("should reject the state root"),
+        }
+    }
+
+    #[test]
+    fn collects_epoch_summaries() {
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec).build();
+        let target_slot = Slot::new(3 * E::slots_per_epoch());
+
+        let replayer = BlockReplayer::new(state.clone(), &spec)
+            .stop_at_slot(target_slot)
+            .apply_blocks(vec![])
+            .expect("should advance state");
+        assert!(replayer.into_epoch_summaries().is_empty());
+
+        let summaries = BlockReplayer::new(state, &spec)
+            .collect_epoch_summaries()
+            .stop_at_slot(target_slot)
+            .apply_blocks(vec![])
+            .expect("should advance state")
+            .into_epoch_summaries();
+        // The previous epoch of the first two transitions is the genesis epoch.
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|summary| summary.epoch)
+                .collect::<Vec<_>>(),
+            vec![Epoch::new(0), Epoch::new(0), Epoch::new(1)]
+        );
+    }
+}
@@ -1,16 +1,25 @@
 #[macro_use]
 mod macros;
 
+pub mod block_replayer;
 pub mod common;
 pub mod genesis;
 pub mod per_block_processing;
 pub mod per_epoch_processing;
 pub mod per_slot_processing;
 pub mod test_utils;
+pub mod timing;
 
+pub use block_replayer::{BlockReplayError, BlockReplayer};
 pub use genesis::{initialize_beacon_state_from_eth1, is_valid_genesis_state, process_activations};
 pub use per_block_processing::{
-    errors::BlockProcessingError, per_block_processing, BlockSignatureStrategy, VerifySignatures,
+    errors::BlockProcessingError, per_block_processing, BlockSignatureStrategy,
+    BlockSignatureVerifier, CommitteePubkeyCache, VerifySignatures,
+};
+pub use per_epoch_processing::{
+    epoch_processing_summary::{EpochProcessingSummary, ValidatorParticipation},
+    errors::EpochProcessingError,
+    per_epoch_processing,
 };
-pub use per_epoch_processing::{errors::EpochProcessingError, per_epoch_processing};
 pub use per_slot_processing::{per_slot_processing, Error as SlotProcessingError};
+pub use timing::{time_stage, TimingRecorder, TimingReport};
@@ -1,4 +1,5 @@
 use crate::common::{initiate_validator_exit, slash_validator};
+use crate::timing::time_stage;
 use errors::{BlockOperationError, BlockProcessingError, HeaderInvalid, IntoWithIndex};
 use rayon::prelude::*;
 use signature_sets::{block_proposal_signature_set, randao_signature_set};
@@ -11,17 +12,21 @@ pub use self::verify_attester_slashing::{
 };
 pub use self::verify_proposer_slashing::verify_proposer_slashing;
 pub use block_signature_verifier::BlockSignatureVerifier;
+pub use committee_pubkey_cache::{CommitteePubkeyCache, COMMITTEE_PUBKEY_CACHE_EPOCHS};
 pub use is_valid_indexed_attestation::is_valid_indexed_attestation;
 pub use verify_attestation::{
     verify_attestation_for_block_inclusion, verify_attestation_for_state,
+    verify_attestation_for_state_with_pubkey_cache,
 };
 pub use verify_deposit::{
-    get_existing_validator_index, verify_deposit_merkle_proof, verify_deposit_signature,
+    get_existing_validator_index, verify_deposit_merkle_proof, verify_deposit_merkle_proofs,
+    verify_deposit_signature,
 };
 pub use verify_exit::{verify_exit, verify_exit_time_independent_only};
 
 pub mod block_processing_builder;
 mod block_signature_verifier;
+mod committee_pubkey_cache;
 pub mod errors;
 mod is_valid_indexed_attestation;
 mod signature_sets;
@@ -80,7 +85,10 @@ pub fn per_block_processing<T: EthSpec>(
         BlockSignatureStrategy::VerifyBulk => {
             // Verify all signatures in the block at once.
             block_verify!(
-                BlockSignatureVerifier::verify_entire_block(state, block, spec).is_ok(),
+                time_stage("verify_block_signatures", || {
+                    BlockSignatureVerifier::verify_entire_block(state, block, spec)
+                })
+                .is_ok(),
                 BlockProcessingError::BulkSignatureVerificationFailed
             );
             VerifySignatures::False
@@ -89,39 +97,57 @@ pub fn per_block_processing<T: EthSpec>(
         BlockSignatureStrategy::NoVerification => VerifySignatures::False,
     };
 
-    process_block_header(state, block, block_signed_root, verify_signatures, spec)?;
+    time_stage("process_block_header", || {
+        process_block_header(state, block, block_signed_root, verify_signatures, spec)
+    })?;
 
     // Ensure the current and previous epoch caches are built.
-    state.build_committee_cache(RelativeEpoch::Previous, spec)?;
-    state.build_committee_cache(RelativeEpoch::Current, spec)?;
-
-    process_randao(&mut state, &block, verify_signatures, &spec)?;
-    process_eth1_data(&mut state, &block.body.eth1_data)?;
-    process_proposer_slashings(
-        &mut state,
-        &block.body.proposer_slashings,
-        verify_signatures,
-        spec,
-    )?;
-    process_attester_slashings(
-        &mut state,
-        &block.body.attester_slashings,
-        verify_signatures,
-        spec,
-    )?;
-    process_attestations(
-        &mut state,
-        &block.body.attestations,
-        verify_signatures,
-        spec,
-    )?;
-    process_deposits(&mut state, &block.body.deposits, spec)?;
-    process_exits(
-        &mut state,
-        &block.body.voluntary_exits,
-        verify_signatures,
-        spec,
-    )?;
+    time_stage("block_committee_caches", || {
+        state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+        state.build_committee_cache(RelativeEpoch::Current, spec)
+    })?;
+
+    time_stage("process_randao", || {
+        process_randao(&mut state, &block, verify_signatures, &spec)
+    })?;
+    time_stage("process_eth1_data", || {
+        process_eth1_data(&mut state, &block.body.eth1_data)
+    })?;
+    time_stage("process_proposer_slashings", || {
+        process_proposer_slashings(
+            &mut state,
+            &block.body.proposer_slashings,
+            verify_signatures,
+            spec,
+        )
+    })?;
+    time_stage("process_attester_slashings", || {
+        process_attester_slashings(
+            &mut state,
+            &block.body.attester_slashings,
+            verify_signatures,
+            spec,
+        )
+    })?;
+    time_stage("process_attestations", || {
+        process_attestations(
+            &mut state,
+            &block.body.attestations,
+            verify_signatures,
+            spec,
+        )
+    })?;
+    time_stage("process_deposits", || {
+        process_deposits(&mut state, &block.body.deposits, spec)
+    })?;
+    time_stage("process_exits", || {
+        process_exits(
+            &mut state,
+            &block.body.voluntary_exits,
+            verify_signatures,
+            spec,
+        )
+    })?;
 
     Ok(())
 }
@@ -396,14 +422,23 @@ pub fn process_deposits<T: EthSpec>(
         }
     );
 
-    // Verify merkle proofs in parallel.
-    deposits
-        .par_iter()
-        .enumerate()
-        .try_for_each(|(i, deposit)| {
-            verify_deposit_merkle_proof(state, deposit, state.eth1_deposit_index + i as u64, spec)
+    // Verify the merkle proofs as a batch, which shares the hashing of the upper levels of the
+    // deposit tree. If the batch is invalid, verify each proof in parallel to find the invalid
+    // deposit.
+    if !verify_deposit_merkle_proofs(state, deposits, state.eth1_deposit_index, spec) {
+        deposits
+            .par_iter()
+            .enumerate()
+            .try_for_each(|(i, deposit)| {
+                verify_deposit_merkle_proof(
+                    state,
+                    deposit,
+                    state.eth1_deposit_index + i as u64,
+                    spec,
+                )
                 .map_err(|e| e.into_with_index(i))
-        })?;
+            })?;
+    }
 
     // Update the state in series.
     for deposit in deposits {
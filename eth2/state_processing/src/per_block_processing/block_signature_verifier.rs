@@ -1,6 +1,7 @@
 use super::signature_sets::{Error as SignatureSetError, Result as SignatureSetResult, *};
 use crate::common::get_indexed_attestation;
 use crate::per_block_processing::errors::{AttestationInvalid, BlockOperationError};
+use crate::per_block_processing::CommitteePubkeyCache;
 use bls::{verify_signature_sets, SignatureSet};
 use rayon::prelude::*;
 use types::{
@@ -48,6 +49,8 @@ pub struct BlockSignatureVerifier<'a, T: EthSpec> {
     block: &'a BeaconBlock<T>,
     state: &'a BeaconState<T>,
     spec: &'a ChainSpec,
+    /// If present, the aggregate public keys of attestations are read from this cache.
+    pubkey_cache: Option<&'a CommitteePubkeyCache>,
     sets: Vec<SignatureSet<'a>>,
 }
 
@@ -59,6 +62,7 @@ impl<'a, T: EthSpec> BlockSignatureVerifier<'a, T> {
             block,
             state,
             spec,
+            pubkey_cache: None,
             sets: vec![],
         }
     }
@@ -74,20 +78,37 @@ impl<'a, T: EthSpec> BlockSignatureVerifier<'a, T> {
         state: &'a BeaconState<T>,
         block: &'a BeaconBlock<T>,
         spec: &'a ChainSpec,
+    ) -> Result<()> {
+        Self::new(state, block, spec).include_and_verify_entire_block()
+    }
+
+    /// As per `Self::verify_entire_block`, but the aggregate public keys of attestations are read
+    /// from `pubkey_cache`, which is updated with the committees of `state`.
+    pub fn verify_entire_block_with_pubkey_cache(
+        state: &'a BeaconState<T>,
+        block: &'a BeaconBlock<T>,
+        pubkey_cache: &'a CommitteePubkeyCache,
+        spec: &'a ChainSpec,
     ) -> Result<()> {
         let mut verifier = Self::new(state, block, spec);
+        verifier.pubkey_cache = Some(pubkey_cache);
+        verifier.include_and_verify_entire_block()
+    }
 
-        verifier.include_block_proposal(None)?;
-        verifier.include_randao_reveal()?;
-        verifier.include_proposer_slashings()?;
-        verifier.include_attester_slashings()?;
-        verifier.include_attestations()?;
+    /// Includes all* the signatures in `self.block` and verifies them, see
+    /// `Self::verify_entire_block`.
+    fn include_and_verify_entire_block(mut self) -> Result<()> {
+        self.include_block_proposal(None)?;
+        self.include_randao_reveal()?;
+        self.include_proposer_slashings()?;
+        self.include_attester_slashings()?;
+        self.include_attestations()?;
         /*
          * Deposits are not included because they can legally have invalid signatures.
          */
-        verifier.include_exits()?;
+        self.include_exits()?;
 
-        verifier.verify()
+        self.verify()
     }
 
     /// Verify all* the signatures that have been included in `self`, returning `Ok(())` if the
@@ -181,12 +202,21 @@ impl<'a, T: EthSpec> BlockSignatureVerifier<'a, T> {
             .map(|attestation| {
                 let indexed_attestation = get_indexed_attestation(self.state, attestation)?;
 
-                self.sets.push(indexed_attestation_signature_set(
-                    &self.state,
-                    &attestation.signature,
-                    &indexed_attestation,
-                    &self.spec,
-                )?);
+                let set = match self.pubkey_cache {
+                    Some(pubkey_cache) => attestation_signature_set_from_cache(
+                        self.state,
+                        attestation,
+                        pubkey_cache,
+                        self.spec,
+                    )?,
+                    None => indexed_attestation_signature_set(
+                        &self.state,
+                        &attestation.signature,
+                        &indexed_attestation,
+                        &self.spec,
+                    )?,
+                };
+                self.sets.push(set);
 
                 Ok(indexed_attestation)
             })
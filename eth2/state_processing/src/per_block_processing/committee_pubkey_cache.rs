@@ -0,0 +1,277 @@
+use super::signature_sets::{validator_pubkey, Error, Result};
+use bls::{AggregatePublicKey, G1Point};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use types::{AttestationData, BeaconState, BitList, Epoch, EthSpec, Slot};
+
+/// The number of epochs for which the public keys of committees are kept.
+///
+/// Attestations may be verified against the committees of the previous and current epochs.
+pub const COMMITTEE_PUBKEY_CACHE_EPOCHS: u64 = 2;
+
+/// The public keys of a single committee, and their aggregate.
+struct CommitteePubkeys {
+    /// The validator indices of the committee, used to detect a change of shuffling.
+    members: Vec<usize>,
+    /// The decompressed public key of each member of the committee.
+    pubkeys: Vec<G1Point>,
+    /// The aggregate of all the `pubkeys`.
+    aggregate: G1Point,
+}
+
+impl CommitteePubkeys {
+    fn new<T: EthSpec>(state: &BeaconState<T>, members: &[usize]) -> Result<Self> {
+        let pubkeys = members
+            .iter()
+            .map(|&validator_index| Ok(validator_pubkey(state, validator_index)?.into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut aggregate = AggregatePublicKey::new();
+        pubkeys
+            .iter()
+            .for_each(|pubkey| aggregate.add_point(pubkey));
+        aggregate.affine();
+
+        Ok(Self {
+            members: members.to_vec(),
+            pubkeys,
+            aggregate: aggregate.into_raw().point,
+        })
+    }
+
+    /// Returns the aggregate public key of the members with a set bit in `aggregation_bits`.
+    ///
+    /// When most of the committee is included, the keys of the missing members are subtracted
+    /// from the aggregate of the whole committee, rather than adding the keys of the included
+    /// members.
+    fn aggregate_of<T: EthSpec>(
+        &self,
+        aggregation_bits: &BitList<T::MaxValidatorsPerCommittee>,
+    ) -> Result<G1Point> {
+        if aggregation_bits.len() != self.pubkeys.len() {
+            return Err(Error::MismatchedPublicKeyLen {
+                pubkey_len: self.pubkeys.len(),
+                other_len: aggregation_bits.len(),
+            });
+        }
+
+        let included = |i: usize| aggregation_bits.get(i).unwrap_or(false);
+        let num_included = aggregation_bits.num_set_bits();
+
+        let mut aggregate = AggregatePublicKey::new();
+        if num_included * 2 >= self.pubkeys.len() {
+            aggregate.add_point(&self.aggregate);
+            self.pubkeys
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !included(*i))
+                .for_each(|(_, pubkey)| aggregate.sub_point(pubkey));
+        } else {
+            self.pubkeys
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| included(*i))
+                .for_each(|(_, pubkey)| aggregate.add_point(pubkey));
+        }
+        aggregate.affine();
+
+        Ok(aggregate.into_raw().point)
+    }
+}
+
+/// Caches the decompressed public keys of each committee of recent epochs, along with their
+/// aggregate, so that the aggregate public key of an attestation can be computed without
+/// decompressing and adding the key of every attester.
+///
+/// Committees are keyed by `(slot, index)`. Since a committee with the same key may differ
+/// between forks, the members of a cached committee are compared against those of the state
+/// whenever it is read, and the entry is replaced if the shuffling has changed.
+#[derive(Default)]
+pub struct CommitteePubkeyCache {
+    committees: RwLock<HashMap<(Slot, u64), Arc<CommitteePubkeys>>>,
+}
+
+impl CommitteePubkeyCache {
+    /// Returns the aggregate public key of the attesters of an attestation with the given `data`
+    /// and `aggregation_bits`, as per the committees of `state`.
+    ///
+    /// The committee caches of `state` must be built for the epoch of the attestation.
+    pub fn get_aggregate_pubkey<T: EthSpec>(
+        &self,
+        state: &BeaconState<T>,
+        data: &AttestationData,
+        aggregation_bits: &BitList<T::MaxValidatorsPerCommittee>,
+    ) -> Result<G1Point> {
+        let committee = state.get_beacon_committee(data.slot, data.index)?;
+        let key = (data.slot, data.index);
+
+        let cached = self
+            .committees
+            .read()
+            .ok()
+            .and_then(|committees| committees.get(&key).cloned())
+            .filter(|pubkeys| pubkeys.members[..] == *committee.committee);
+
+        let pubkeys = match cached {
+            Some(pubkeys) => pubkeys,
+            None => {
+                let pubkeys = Arc::new(CommitteePubkeys::new(state, committee.committee)?);
+                self.insert::<T>(key, pubkeys.clone());
+                pubkeys
+            }
+        };
+
+        pubkeys.aggregate_of::<T>(aggregation_bits)
+    }
+
+    /// Adds the `pubkeys` of the committee with the given `key`, pruning the committees of all
+    /// but the latest `COMMITTEE_PUBKEY_CACHE_EPOCHS` epochs.
+    fn insert<T: EthSpec>(&self, key: (Slot, u64), pubkeys: Arc<CommitteePubkeys>) {
+        let mut committees = match self.committees.write() {
+            Ok(committees) => committees,
+            // A panic whilst holding the lock cannot leave an entry partially written, so the
+            // cache is still usable.
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        committees.insert(key, pubkeys);
+
+        let epoch = |slot: Slot| slot.epoch(T::slots_per_epoch());
+        if let Some(latest_epoch) = committees.keys().map(|(slot, _)| epoch(*slot)).max() {
+            let earliest_epoch =
+                latest_epoch.saturating_sub(Epoch::new(COMMITTEE_PUBKEY_CACHE_EPOCHS - 1));
+            committees.retain(|(slot, _), _| epoch(*slot) >= earliest_epoch);
+        }
+    }
+
+    /// Returns the number of committees in the cache.
+    pub fn len(&self) -> usize {
+        self.committees
+            .read()
+            .map(|committees| committees.len())
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the cache holds no committees.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::per_block_processing::signature_sets::attestation_signature_set_from_cache;
+    use types::test_utils::{
+        AttestationTestTask, TestingAttestationBuilder, TestingBeaconStateBuilder,
+    };
+    use types::{Attestation, ChainSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch};
+
+    type E = MinimalEthSpec;
+
+    const VALIDATOR_COUNT: usize = 256;
+
+    fn get_state(spec: &ChainSpec) -> (BeaconState<E>, Vec<Keypair>) {
+        let (mut state, keypairs) =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(VALIDATOR_COUNT, spec).build();
+        state
+            .build_committee_cache(RelativeEpoch::Current, spec)
+            .expect("should build committee cache");
+        (state, keypairs)
+    }
+
+    fn committee(state: &BeaconState<E>) -> Vec<usize> {
+        state
+            .get_beacon_committee(Slot::new(0), 0)
+            .expect("should get committee")
+            .committee
+            .to_vec()
+    }
+
+    /// Returns an attestation to the first committee of `state`, signed by the `signers`.
+    fn signed_attestation(
+        state: &BeaconState<E>,
+        keypairs: &[Keypair],
+        signers: &[usize],
+        spec: &ChainSpec,
+    ) -> Attestation<E> {
+        let mut builder = TestingAttestationBuilder::new(
+            AttestationTestTask::Valid,
+            state,
+            &committee(state),
+            Slot::new(0),
+            0,
+            spec,
+        );
+        let secret_keys = signers.iter().map(|&i| &keypairs[i].sk).collect::<Vec<_>>();
+        builder.sign(
+            AttestationTestTask::Valid,
+            signers,
+            &secret_keys,
+            &state.fork,
+            spec,
+        );
+        builder.build()
+    }
+
+    fn is_valid(
+        state: &BeaconState<E>,
+        attestation: &Attestation<E>,
+        cache: &CommitteePubkeyCache,
+        spec: &ChainSpec,
+    ) -> bool {
+        attestation_signature_set_from_cache(state, attestation, cache, spec)
+            .expect("should get signature set")
+            .is_valid()
+    }
+
+    #[test]
+    fn aggregates_subsets_of_committee() {
+        let spec = E::default_spec();
+        let (state, keypairs) = get_state(&spec);
+        let cache = CommitteePubkeyCache::default();
+        let committee = committee(&state);
+        assert!(committee.len() > 2, "committee should have several members");
+
+        // Signed by most of the committee, a few members of the committee and the whole committee.
+        for signers in &[&committee[1..], &committee[..2], &committee[..]] {
+            let attestation = signed_attestation(&state, &keypairs, signers, &spec);
+            assert!(is_valid(&state, &attestation, &cache, &spec));
+        }
+        assert_eq!(cache.len(), 1, "should cache the committee once");
+
+        let mut attestation = signed_attestation(&state, &keypairs, &committee[1..], &spec);
+        attestation
+            .aggregation_bits
+            .set(0, true)
+            .expect("should set bit");
+        assert!(
+            !is_valid(&state, &attestation, &cache, &spec),
+            "should include the key of a member which did not sign"
+        );
+    }
+
+    #[test]
+    fn replaces_committee_when_shuffling_changes() {
+        let spec = E::default_spec();
+        let (mut state, keypairs) = get_state(&spec);
+        let cache = CommitteePubkeyCache::default();
+
+        let attestation = signed_attestation(&state, &keypairs, &committee(&state)[1..], &spec);
+        assert!(is_valid(&state, &attestation, &cache, &spec));
+
+        // Changing the randao mixes changes the seed, and therefore the shuffling.
+        let previous_committee = committee(&state);
+        for mix in state.randao_mixes[..].iter_mut() {
+            *mix = Hash256::from_low_u64_be(42);
+        }
+        state
+            .force_build_committee_cache(RelativeEpoch::Current, &spec)
+            .expect("should build committee cache");
+        assert_ne!(committee(&state), previous_committee);
+
+        let attestation = signed_attestation(&state, &keypairs, &committee(&state)[1..], &spec);
+        assert!(is_valid(&state, &attestation, &cache, &spec));
+        assert_eq!(cache.len(), 1, "should replace the stale committee");
+    }
+}
@@ -2,12 +2,13 @@
 //! validated individually, or alongside in others in a potentially cheaper bulk operation.
 //!
 //! This module exposes one function to extract each type of `SignatureSet` from a `BeaconBlock`.
+use super::committee_pubkey_cache::CommitteePubkeyCache;
 use bls::{G1Point, G1Ref, SignatureSet, SignedMessage};
 use std::borrow::Cow;
 use std::convert::TryInto;
 use tree_hash::{SignedRoot, TreeHash};
 use types::{
-    AggregateSignature, AttesterSlashing, BeaconBlock, BeaconBlockHeader, BeaconState,
+    AggregateSignature, Attestation, AttesterSlashing, BeaconBlock, BeaconBlockHeader, BeaconState,
     BeaconStateError, ChainSpec, DepositData, Domain, EthSpec, Hash256, IndexedAttestation,
     ProposerSlashing, PublicKey, Signature, VoluntaryExit,
 };
@@ -164,6 +165,38 @@ pub fn indexed_attestation_signature_set<'a, 'b, T: EthSpec>(
     Ok(SignatureSet::new(signature, vec![signed_message], domain))
 }
 
+/// Returns the signature set for the given `attestation`, reading the aggregate public key of its
+/// attesters from `pubkey_cache` rather than aggregating the key of each attester.
+///
+/// Does not check that `attestation.aggregation_bits` is valid, see `get_indexed_attestation`.
+pub fn attestation_signature_set_from_cache<'a, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    attestation: &'a Attestation<T>,
+    pubkey_cache: &CommitteePubkeyCache,
+    spec: &'a ChainSpec,
+) -> Result<SignatureSet<'a>> {
+    let message = attestation.data.tree_hash_root();
+
+    let aggregate_pubkey = pubkey_cache.get_aggregate_pubkey(
+        state,
+        &attestation.data,
+        &attestation.aggregation_bits,
+    )?;
+
+    let domain = spec.get_domain(
+        attestation.data.target.epoch,
+        Domain::BeaconAttester,
+        &state.fork,
+    );
+
+    Ok(SignatureSet::single(
+        &attestation.signature,
+        Cow::Owned(aggregate_pubkey),
+        message,
+        domain,
+    ))
+}
+
 /// Returns the signature set for the given `attester_slashing` and corresponding `pubkeys`.
 pub fn attester_slashing_signature_sets<'a, T: EthSpec>(
     state: &'a BeaconState<T>,
@@ -238,7 +271,7 @@ pub fn exit_signature_set<'a, T: EthSpec>(
 }
 
 /// Maps a validator index to a `PublicKey`.
-fn validator_pubkey<'a, T: EthSpec>(
+pub(super) fn validator_pubkey<'a, T: EthSpec>(
     state: &'a BeaconState<T>,
     validator_index: usize,
 ) -> Result<Cow<'a, G1Point>> {
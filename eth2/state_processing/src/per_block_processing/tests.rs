@@ -2,6 +2,7 @@
 
 use super::block_processing_builder::BlockProcessingBuilder;
 use super::errors::*;
+use super::{verify_deposit_merkle_proof, verify_deposit_merkle_proofs};
 use crate::{per_block_processing, BlockSignatureStrategy};
 use tree_hash::SignedRoot;
 use types::test_utils::{
@@ -248,6 +249,52 @@ fn invalid_deposit_bad_merkle_proof() {
     );
 }
 
+#[test]
+fn deposit_merkle_proofs_batch_matches_individual() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let test_task = DepositTestTask::Valid;
+
+    let (block, state) = builder.build_with_n_deposits(
+        <MainnetEthSpec as EthSpec>::MaxDeposits::to_u64(),
+        test_task,
+        None,
+        None,
+        &spec,
+    );
+    let deposits = &block.body.deposits[..];
+
+    let verify_individually = |first_index: u64, deposits: &[Deposit]| {
+        deposits.iter().enumerate().all(|(i, deposit)| {
+            verify_deposit_merkle_proof(&state, deposit, first_index + i as u64, &spec).is_ok()
+        })
+    };
+
+    // Valid proofs, and proofs checked against the wrong indices.
+    for first_index in 0..2 {
+        assert_eq!(
+            verify_deposit_merkle_proofs(&state, deposits, first_index, &spec),
+            verify_individually(first_index, deposits),
+            "first_index: {}",
+            first_index
+        );
+    }
+    assert!(verify_deposit_merkle_proofs(&state, deposits, 0, &spec));
+
+    // A single corrupt proof element anywhere in the batch.
+    for i in 0..deposits.len() {
+        let mut corrupt = deposits.to_vec();
+        corrupt[i].proof[i % (spec.deposit_contract_tree_depth as usize + 1)] = Hash256::zero();
+
+        assert!(!verify_individually(0, &corrupt));
+        assert!(
+            !verify_deposit_merkle_proofs(&state, &corrupt, 0, &spec),
+            "deposit: {}",
+            i
+        );
+    }
+}
+
 #[test]
 fn invalid_deposit_wrong_pubkey() {
     let spec = MainnetEthSpec::default_spec();
@@ -1,5 +1,6 @@
 use super::errors::{AttestationInvalid as Invalid, BlockOperationError};
-use super::VerifySignatures;
+use super::signature_sets::attestation_signature_set_from_cache;
+use super::{CommitteePubkeyCache, VerifySignatures};
 use crate::common::get_indexed_attestation;
 use crate::per_block_processing::is_valid_indexed_attestation;
 use types::*;
@@ -73,6 +74,24 @@ pub fn verify_attestation_for_state<T: EthSpec>(
     Ok(())
 }
 
+/// As per `verify_attestation_for_state` with `VerifySignatures::True`, but the aggregate public
+/// key of the attesters is read from `pubkey_cache`.
+pub fn verify_attestation_for_state_with_pubkey_cache<T: EthSpec>(
+    state: &BeaconState<T>,
+    attestation: &Attestation<T>,
+    pubkey_cache: &CommitteePubkeyCache,
+    spec: &ChainSpec,
+) -> Result<()> {
+    verify_attestation_for_state(state, attestation, VerifySignatures::False, spec)?;
+
+    verify!(
+        attestation_signature_set_from_cache(state, attestation, pubkey_cache, spec)?.is_valid(),
+        Invalid::BadSignature
+    );
+
+    Ok(())
+}
+
 /// Check target epoch and source checkpoint.
 ///
 /// Spec v0.9.1
@@ -2,7 +2,7 @@ use super::errors::{BlockOperationError, DepositInvalid};
 use crate::per_block_processing::signature_sets::{
     deposit_pubkey_signature_message, deposit_signature_set,
 };
-use merkle_proof::verify_merkle_proof;
+use merkle_proof::{verify_merkle_proof, verify_merkle_proofs_batch};
 use tree_hash::TreeHash;
 use types::*;
 
@@ -68,3 +68,32 @@ pub fn verify_deposit_merkle_proof<T: EthSpec>(
 
     Ok(())
 }
+
+/// Verify that each of the `deposits`, which have consecutive indices from `first_deposit_index`,
+/// is included in the state's eth1 deposit root.
+///
+/// Equivalent to `verify_deposit_merkle_proof` for each deposit, but the hashes shared by their
+/// proofs are only computed once. Returns `false` if any proof is invalid, without identifying it.
+pub fn verify_deposit_merkle_proofs<T: EthSpec>(
+    state: &BeaconState<T>,
+    deposits: &[Deposit],
+    first_deposit_index: u64,
+    spec: &ChainSpec,
+) -> bool {
+    let leaves = deposits
+        .iter()
+        .map(|deposit| Hash256::from_slice(&deposit.data.tree_hash_root()))
+        .collect::<Vec<_>>();
+    let branches = deposits
+        .iter()
+        .map(|deposit| &deposit.proof[..])
+        .collect::<Vec<_>>();
+
+    verify_merkle_proofs_batch(
+        &leaves,
+        &branches,
+        spec.deposit_contract_tree_depth as usize + 1,
+        first_deposit_index as usize,
+        state.eth1_data.deposit_root,
+    )
+}
@@ -1,9 +1,12 @@
+use crate::timing::time_stage;
+use epoch_processing_summary::EpochProcessingSummary;
 use errors::EpochProcessingError as Error;
 use tree_hash::TreeHash;
 use types::*;
 use validator_statuses::{TotalBalances, ValidatorStatuses};
 
 pub mod apply_rewards;
+pub mod epoch_processing_summary;
 pub mod errors;
 pub mod process_slashings;
 pub mod registry_updates;
@@ -19,41 +22,60 @@ pub use registry_updates::process_registry_updates;
 /// Mutates the given `BeaconState`, returning early if an error is encountered. If an error is
 /// returned, a state might be "half-processed" and therefore in an invalid state.
 ///
+/// Returns a summary of the validator participation in the state's previous epoch.
+///
 /// Spec v0.9.1
 pub fn per_epoch_processing<T: EthSpec>(
     state: &mut BeaconState<T>,
     spec: &ChainSpec,
-) -> Result<(), Error> {
+) -> Result<EpochProcessingSummary, Error> {
     // Ensure the committee caches are built.
-    state.build_committee_cache(RelativeEpoch::Previous, spec)?;
-    state.build_committee_cache(RelativeEpoch::Current, spec)?;
-    state.build_committee_cache(RelativeEpoch::Next, spec)?;
+    time_stage("epoch_committee_caches", || {
+        state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+        state.build_committee_cache(RelativeEpoch::Current, spec)?;
+        state.build_committee_cache(RelativeEpoch::Next, spec)
+    })?;
 
     // Load the struct we use to assign validators into sets based on their participation.
     //
     // E.g., attestation in the previous epoch, attested to the head, etc.
-    let mut validator_statuses = ValidatorStatuses::new(state, spec)?;
-    validator_statuses.process_attestations(&state, spec)?;
+    let mut validator_statuses = time_stage("validator_statuses", || {
+        let mut validator_statuses = ValidatorStatuses::new(state, spec)?;
+        validator_statuses.process_attestations(&state, spec)?;
+        Ok::<_, Error>(validator_statuses)
+    })?;
+
+    let summary = EpochProcessingSummary::new(state.previous_epoch(), &validator_statuses);
 
     // Justification and finalization.
-    process_justification_and_finalization(state, &validator_statuses.total_balances)?;
+    time_stage("process_justification_and_finalization", || {
+        process_justification_and_finalization(state, &validator_statuses.total_balances)
+    })?;
 
     // Rewards and Penalties.
-    process_rewards_and_penalties(state, &mut validator_statuses, spec)?;
+    time_stage("process_rewards_and_penalties", || {
+        process_rewards_and_penalties(state, &mut validator_statuses, spec)
+    })?;
 
     // Registry Updates.
-    process_registry_updates(state, spec)?;
+    time_stage("process_registry_updates", || {
+        process_registry_updates(state, spec)
+    })?;
 
     // Slashings.
-    process_slashings(state, validator_statuses.total_balances.current_epoch, spec)?;
+    time_stage("process_slashings", || {
+        process_slashings(state, validator_statuses.total_balances.current_epoch, spec)
+    })?;
 
     // Final updates.
-    process_final_updates(state, spec)?;
+    time_stage("process_final_updates", || {
+        process_final_updates(state, spec)
+    })?;
 
     // Rotate the epoch caches to suit the epoch transition.
-    state.advance_caches();
+    time_stage("advance_caches", || state.advance_caches());
 
-    Ok(())
+    Ok(summary)
 }
 
 /// Update the following fields on the `BeaconState`:
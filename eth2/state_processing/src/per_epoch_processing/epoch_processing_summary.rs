@@ -0,0 +1,90 @@
+use super::validator_statuses::{TotalBalances, ValidatorStatuses};
+use types::Epoch;
+
+/// The participation of a single validator in the attestations of some epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ValidatorParticipation {
+    /// True if the validator was active in the epoch.
+    pub is_active: bool,
+    /// True if the validator has been slashed, ever.
+    pub is_slashed: bool,
+    /// True if the validator had an attestation with a matching source included in a block.
+    pub is_source_attester: bool,
+    /// True if the validator had an attestation with a matching target included in a block.
+    pub is_target_attester: bool,
+    /// True if the validator had an attestation with a matching head included in a block.
+    pub is_head_attester: bool,
+    /// The distance between the slot of the validator's earliest-included attestation and the
+    /// slot of the block which included it.
+    pub inclusion_delay: Option<u64>,
+}
+
+/// A summary of the validator participation observed during per-epoch processing.
+///
+/// Allows the performance of validators to be inspected after the state has been processed,
+/// without re-running epoch processing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochProcessingSummary {
+    /// The epoch in which the summarised attestations were made.
+    ///
+    /// This is the _previous_ epoch of the state being processed, since the attestations of the
+    /// _current_ epoch may still be included in later blocks.
+    pub epoch: Epoch,
+    /// The summed balances of the participating validators.
+    pub total_balances: TotalBalances,
+    /// The participation of each validator, by validator index.
+    pub validators: Vec<ValidatorParticipation>,
+}
+
+impl EpochProcessingSummary {
+    /// Summarises the previous epoch participation in `validator_statuses`, which must have had
+    /// `process_attestations` called on it.
+    pub fn new(epoch: Epoch, validator_statuses: &ValidatorStatuses) -> Self {
+        let validators = validator_statuses
+            .statuses
+            .iter()
+            .map(|status| ValidatorParticipation {
+                is_active: status.is_active_in_previous_epoch,
+                is_slashed: status.is_slashed,
+                is_source_attester: status.is_previous_epoch_attester,
+                is_target_attester: status.is_previous_epoch_target_attester,
+                is_head_attester: status.is_previous_epoch_head_attester,
+                inclusion_delay: status.inclusion_info.map(|info| info.delay),
+            })
+            .collect();
+
+        Self {
+            epoch,
+            total_balances: validator_statuses.total_balances.clone(),
+            validators,
+        }
+    }
+
+    /// Returns the participation of the validator with `validator_index`, if it is known.
+    pub fn validator(&self, validator_index: usize) -> Option<&ValidatorParticipation> {
+        self.validators.get(validator_index)
+    }
+
+    /// Returns the fraction of the active balance which attested with a matching source.
+    pub fn source_participation_rate(&self) -> f64 {
+        self.participation_rate(self.total_balances.previous_epoch_attesters)
+    }
+
+    /// Returns the fraction of the active balance which attested with a matching target.
+    pub fn target_participation_rate(&self) -> f64 {
+        self.participation_rate(self.total_balances.previous_epoch_target_attesters)
+    }
+
+    /// Returns the fraction of the active balance which attested with a matching head.
+    pub fn head_participation_rate(&self) -> f64 {
+        self.participation_rate(self.total_balances.previous_epoch_head_attesters)
+    }
+
+    fn participation_rate(&self, attesting_balance: u64) -> f64 {
+        if self.total_balances.previous_epoch == 0 {
+            0.0
+        } else {
+            attesting_balance as f64 / self.total_balances.previous_epoch as f64
+        }
+    }
+}
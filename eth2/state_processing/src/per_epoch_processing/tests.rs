@@ -21,3 +21,33 @@ fn runs_without_error() {
 
     per_epoch_processing(&mut state, &spec).unwrap();
 }
+
+#[test]
+fn returns_previous_epoch_summary() {
+    let spec = MinimalEthSpec::default_spec();
+
+    let mut builder: TestingBeaconStateBuilder<MinimalEthSpec> =
+        TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+
+    let target_slot =
+        (MinimalEthSpec::genesis_epoch() + 4).end_slot(MinimalEthSpec::slots_per_epoch());
+    builder.teleport_to_slot(target_slot);
+
+    let (mut state, _keypairs) = builder.build();
+    let previous_epoch = state.previous_epoch();
+
+    let summary = per_epoch_processing(&mut state, &spec).unwrap();
+
+    assert_eq!(summary.epoch, previous_epoch);
+    assert_eq!(summary.validators.len(), 8);
+    assert!(summary
+        .validators
+        .iter()
+        .all(|validator| validator.is_active));
+    // No attestations were included, so no validator participated.
+    assert!(summary
+        .validators
+        .iter()
+        .all(|validator| !validator.is_source_attester && validator.inclusion_delay.is_none()));
+    assert_eq!(summary.target_participation_rate(), 0.0);
+}
@@ -9,20 +9,25 @@ pub enum Error {
 
 /// Advances a state forward by one slot, performing per-epoch processing if required.
 ///
+/// Returns the summary produced by per-epoch processing, if it was performed.
+///
 /// Spec v0.9.1
 pub fn per_slot_processing<T: EthSpec>(
     state: &mut BeaconState<T>,
     spec: &ChainSpec,
-) -> Result<(), Error> {
-    cache_state(state)?;
+) -> Result<Option<EpochProcessingSummary>, Error> {
+    time_stage("cache_state", || cache_state(state))?;
 
-    if state.slot > spec.genesis_slot && (state.slot + 1) % T::slots_per_epoch() == 0 {
-        per_epoch_processing(state, spec)?;
-    }
+    let summary = if state.slot > spec.genesis_slot && (state.slot + 1) % T::slots_per_epoch() == 0
+    {
+        Some(per_epoch_processing(state, spec)?)
+    } else {
+        None
+    };
 
     state.slot += 1;
 
-    Ok(())
+    Ok(summary)
 }
 
 fn cache_state<T: EthSpec>(state: &mut BeaconState<T>) -> Result<(), Error> {
@@ -0,0 +1,143 @@
+//! Optional instrumentation which records the time spent in each stage of the state transition,
+//! so that a performance regression can be localized to a particular stage.
+//!
+//! Recording is enabled for the current thread with `TimingRecorder::start` and stops when the
+//! recorder is finished or dropped. Whilst recording is disabled, `time_stage` only checks a
+//! thread-local flag, so the instrumentation costs almost nothing when unused.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static REPORT: RefCell<Option<TimingReport>> = RefCell::new(None);
+}
+
+/// The time spent in each stage of the state transition, in the order in which the stages were
+/// first entered.
+///
+/// A stage which is entered more than once (e.g., epoch processing whilst skipping several
+/// epochs) reports the total time spent in it.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TimingReport {
+    pub stages: Vec<(&'static str, Duration)>,
+}
+
+impl TimingReport {
+    fn record(&mut self, stage: &'static str, duration: Duration) {
+        match self.stages.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += duration,
+            None => self.stages.push((stage, duration)),
+        }
+    }
+
+    /// Returns the total time spent in `stage`, if it was entered.
+    pub fn get(&self, stage: &str) -> Option<Duration> {
+        self.stages
+            .iter()
+            .find(|(name, _)| *name == stage)
+            .map(|(_, duration)| *duration)
+    }
+}
+
+impl fmt::Display for TimingReport {
+    /// Formats the report as `stage=duration` pairs, e.g., `process_randao=1.2ms`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (stage, duration)) in self.stages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={:?}", stage, duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the stages of the state transition performed on the current thread from when it is
+/// started until it is finished or dropped.
+pub struct TimingRecorder {
+    // Prevents construction without `start`, which would leave the thread-local report unset.
+    _private: (),
+}
+
+impl TimingRecorder {
+    /// Starts recording on the current thread, discarding any report which was being recorded.
+    pub fn start() -> Self {
+        REPORT.with(|report| *report.borrow_mut() = Some(TimingReport::default()));
+        Self { _private: () }
+    }
+
+    /// Stops recording and returns the stages recorded since `start`.
+    pub fn finish(self) -> TimingReport {
+        REPORT
+            .with(|report| report.borrow_mut().take())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for TimingRecorder {
+    fn drop(&mut self) {
+        REPORT.with(|report| *report.borrow_mut() = None);
+    }
+}
+
+/// Runs `f`, adding the time it takes to `stage` if a `TimingRecorder` is running on the current
+/// thread.
+pub fn time_stage<T, F: FnOnce() -> T>(stage: &'static str, f: F) -> T {
+    let recording = REPORT.with(|report| report.borrow().is_some());
+    if !recording {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    let duration = start.elapsed();
+
+    REPORT.with(|report| {
+        if let Some(report) = report.borrow_mut().as_mut() {
+            report.record(stage, duration);
+        }
+    });
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_whilst_started() {
+        time_stage("before", || ());
+
+        let recorder = TimingRecorder::start();
+        time_stage("a", || ());
+        time_stage("b", || time_stage("nested", || ()));
+        time_stage("a", || ());
+        let report = recorder.finish();
+
+        time_stage("after", || ());
+
+        assert_eq!(
+            report
+                .stages
+                .iter()
+                .map(|(stage, _)| *stage)
+                .collect::<Vec<_>>(),
+            vec!["a", "nested", "b"]
+        );
+        assert!(report.get("b") >= report.get("nested"));
+        assert_eq!(report.get("before"), None);
+
+        let report = TimingRecorder::start().finish();
+        assert!(report.stages.is_empty());
+    }
+
+    #[test]
+    fn stops_when_dropped() {
+        {
+            let _recorder = TimingRecorder::start();
+        }
+        REPORT.with(|report| assert!(report.borrow().is_none()));
+    }
+}
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ssz::Decode;
+use types::{BeaconState, MainnetEthSpec};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = BeaconState::<MainnetEthSpec>::from_ssz_bytes(data);
+});
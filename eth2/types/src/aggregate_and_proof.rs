@@ -0,0 +1,79 @@
+use super::{
+    Attestation, ChainSpec, Domain, EthSpec, Fork, PublicKey, SecretKey, SelectionProof, Signature,
+};
+use crate::test_utils::TestRandom;
+
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use test_random_derive::TestRandom;
+use tree_hash::TreeHash;
+use tree_hash_derive::TreeHash;
+
+/// A validator's aggregate attestation, along with the proof that it was selected to aggregate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom)]
+#[serde(bound = "T: EthSpec")]
+pub struct AggregateAndProof<T: EthSpec> {
+    /// The index of the validator that created the aggregate.
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub aggregator_index: u64,
+    pub aggregate: Attestation<T>,
+    /// A signature across `aggregate.data.slot` by the aggregator.
+    pub selection_proof: Signature,
+}
+
+impl<T: EthSpec> AggregateAndProof<T> {
+    /// Produces a new `AggregateAndProof`, computing the `selection_proof` with `secret_key`.
+    pub fn from_aggregate(
+        aggregator_index: u64,
+        aggregate: Attestation<T>,
+        secret_key: &SecretKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> Self {
+        let selection_proof =
+            SelectionProof::new::<T>(aggregate.data.slot, secret_key, fork, spec).into();
+
+        Self {
+            aggregator_index,
+            aggregate,
+            selection_proof,
+        }
+    }
+
+    /// Returns `true` if `self.selection_proof` is a valid signature by `validator_pubkey`.
+    pub fn is_valid_selection_proof(
+        &self,
+        validator_pubkey: &PublicKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> bool {
+        SelectionProof::from(self.selection_proof.clone()).verify::<T>(
+            self.aggregate.data.slot,
+            validator_pubkey,
+            fork,
+            spec,
+        )
+    }
+
+    /// Returns the signing domain for `self`.
+    pub fn domain(&self, fork: &Fork, spec: &ChainSpec) -> u64 {
+        spec.get_domain(
+            self.aggregate.data.slot.epoch(T::slots_per_epoch()),
+            Domain::AggregateAndProof,
+            fork,
+        )
+    }
+
+    /// Returns the message which must be signed to produce a `SignedAggregateAndProof`.
+    pub fn signing_root(&self) -> Vec<u8> {
+        self.tree_hash_root()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    ssz_tests!(AggregateAndProof<MainnetEthSpec>);
+}
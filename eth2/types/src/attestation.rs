@@ -67,6 +67,24 @@ impl<T: EthSpec> Attestation<T> {
         committee_position: usize,
         fork: &Fork,
         spec: &ChainSpec,
+    ) -> Result<(), Error> {
+        let message = self.data.tree_hash_root();
+        let domain = spec.get_domain(self.data.target.epoch, Domain::BeaconAttester, fork);
+
+        self.add_signature(
+            &Signature::new(&message, domain, secret_key),
+            committee_position,
+        )
+    }
+
+    /// Adds a `signature` that was produced elsewhere (e.g., by a remote signer) to `self`,
+    /// setting the `committee_position`'th bit of `aggregation_bits` to `true`.
+    ///
+    /// Returns an `AlreadySigned` error if the `committee_position`'th bit is already `true`.
+    pub fn add_signature(
+        &mut self,
+        signature: &Signature,
+        committee_position: usize,
     ) -> Result<(), Error> {
         if self
             .aggregation_bits
@@ -79,11 +97,7 @@ impl<T: EthSpec> Attestation<T> {
                 .set(committee_position, true)
                 .map_err(|e| Error::SszTypesError(e))?;
 
-            let message = self.data.tree_hash_root();
-            let domain = spec.get_domain(self.data.target.epoch, Domain::BeaconAttester, fork);
-
-            self.signature
-                .add(&Signature::new(&message, domain, secret_key));
+            self.signature.add(signature);
 
             Ok(())
         }
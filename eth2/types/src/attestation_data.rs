@@ -14,6 +14,7 @@ use tree_hash_derive::TreeHash;
 )]
 pub struct AttestationData {
     pub slot: Slot,
+    #[serde(with = "serde_utils::quoted_u64")]
     pub index: u64,
 
     // LMD GHOST vote
@@ -55,6 +55,7 @@ impl<T: EthSpec> BeaconBlock<T> {
                 attestations: VariableList::empty(),
                 deposits: VariableList::empty(),
                 voluntary_exits: VariableList::empty(),
+                extension: Default::default(),
             },
             signature: Signature::empty_signature(),
         }
@@ -26,6 +26,13 @@ pub struct BeaconBlockBody<T: EthSpec> {
     pub attestations: VariableList<Attestation<T>, T::MaxAttestations>,
     pub deposits: VariableList<Deposit, T::MaxDeposits>,
     pub voluntary_exits: VariableList<VoluntaryExit, T::MaxVoluntaryExits>,
+    /// Experimental fields, only encoded when the `block_body_extension` feature is enabled.
+    #[cfg_attr(not(feature = "block_body_extension"), serde(skip))]
+    #[cfg_attr(feature = "block_body_extension", serde(default))]
+    #[cfg_attr(not(feature = "block_body_extension"), ssz(skip_serializing))]
+    #[cfg_attr(not(feature = "block_body_extension"), ssz(skip_deserializing))]
+    #[cfg_attr(not(feature = "block_body_extension"), tree_hash(skip_hashing))]
+    pub extension: T::BlockBodyExtension,
 }
 
 #[cfg(test)]
@@ -33,4 +40,90 @@ mod tests {
     use super::*;
 
     ssz_tests!(BeaconBlockBody<MainnetEthSpec>);
+
+    /// A spec whose blocks carry a `StatelessWitness`.
+    #[derive(Clone, PartialEq, Debug, Default)]
+    pub struct WitnessEthSpec;
+
+    impl EthSpec for WitnessEthSpec {
+        type BlockBodyExtension = StatelessWitness;
+
+        crate::params_from_eth_spec!(MinimalEthSpec {
+            JustificationBitsLength,
+            MaxValidatorsPerCommittee,
+            GenesisEpoch,
+            SlotsPerEpoch,
+            SlotsPerEth1VotingPeriod,
+            SlotsPerHistoricalRoot,
+            EpochsPerHistoricalVector,
+            EpochsPerSlashingsVector,
+            HistoricalRootsLimit,
+            ValidatorRegistryLimit,
+            MaxProposerSlashings,
+            MaxAttesterSlashings,
+            MaxAttestations,
+            MaxDeposits,
+            MaxVoluntaryExits,
+            MaxPendingAttestations
+        });
+
+        fn default_spec() -> ChainSpec {
+            ChainSpec::minimal()
+        }
+    }
+
+    #[cfg(feature = "block_body_extension")]
+    mod with_extension {
+        use super::*;
+
+        ssz_tests!(BeaconBlockBody<WitnessEthSpec>);
+    }
+
+    /// Returns a random body with a non-empty extension.
+    fn body_with_witness() -> BeaconBlockBody<WitnessEthSpec> {
+        use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut body = BeaconBlockBody::random_for_test(&mut rng);
+        body.extension = StatelessWitness {
+            validator_indices: VariableList::from(vec![1, 2, 3]),
+            proof: VariableList::from(vec![Hash256::from_low_u64_be(4)]),
+        };
+        body
+    }
+
+    #[test]
+    #[cfg(not(feature = "block_body_extension"))]
+    fn extension_is_not_encoded() {
+        use ssz::{Decode, Encode};
+        use tree_hash::TreeHash;
+
+        let body = body_with_witness();
+        let mut without_extension = body.clone();
+        without_extension.extension = StatelessWitness::default();
+
+        assert_eq!(body.as_ssz_bytes(), without_extension.as_ssz_bytes());
+        assert_eq!(body.tree_hash_root(), without_extension.tree_hash_root());
+        assert_eq!(
+            BeaconBlockBody::from_ssz_bytes(&body.as_ssz_bytes()),
+            Ok(without_extension)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "block_body_extension")]
+    fn extension_is_encoded() {
+        use ssz::{Decode, Encode};
+        use tree_hash::TreeHash;
+
+        let body = body_with_witness();
+        let mut without_extension = body.clone();
+        without_extension.extension = StatelessWitness::default();
+
+        assert_ne!(body.tree_hash_root(), without_extension.tree_hash_root());
+        assert_eq!(
+            BeaconBlockBody::from_ssz_bytes(&body.as_ssz_bytes()),
+            Ok(body)
+        );
+    }
 }
@@ -8,7 +8,7 @@ use eth2_hashing::hash;
 use int_to_bytes::{int_to_bytes4, int_to_bytes8};
 use pubkey_cache::PubkeyCache;
 use serde_derive::{Deserialize, Serialize};
-use ssz::ssz_encode;
+use ssz::{ssz_encode, Encode};
 use ssz_derive::{Decode, Encode};
 use ssz_types::{typenum::Unsigned, BitVector, FixedVector};
 use swap_or_not_shuffle::compute_shuffled_index;
@@ -17,12 +17,15 @@ use tree_hash::TreeHash;
 use tree_hash_derive::{CachedTreeHash, TreeHash};
 
 pub use self::committee_cache::CommitteeCache;
+pub use self::state_proof::{StateField, StateProof, StateProofTarget, STATE_FIELDS_DEPTH};
 pub use eth_spec::*;
 
 #[macro_use]
 mod committee_cache;
 mod exit_cache;
+mod partial_ssz;
 mod pubkey_cache;
+mod state_proof;
 mod tests;
 
 pub const CACHED_EPOCHS: usize = 3;
@@ -45,6 +48,8 @@ pub enum Error {
     InsufficientAttestations,
     InsufficientCommittees,
     InsufficientStateRoots,
+    /// The supplied `HistoricalBatch` does not match the root at this index of `historical_roots`.
+    HistoricalBatchMismatch(usize),
     NoCommittee {
         slot: Slot,
         index: CommitteeIndex,
@@ -95,6 +100,17 @@ impl BeaconTreeHashCache {
     pub fn is_initialized(&self) -> bool {
         self.initialized
     }
+
+    /// An estimate of the memory used by the cache, in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.block_roots.memory_size()
+            + self.state_roots.memory_size()
+            + self.historical_roots.memory_size()
+            + self.validators.memory_size()
+            + self.balances.memory_size()
+            + self.randao_mixes.memory_size()
+            + self.slashings.memory_size()
+    }
 }
 
 /// The state of the `BeaconChain` at some slot.
@@ -120,6 +136,7 @@ where
     T: EthSpec,
 {
     // Versioning
+    #[serde(with = "serde_utils::quoted_u64")]
     pub genesis_time: u64,
     pub slot: Slot,
     pub fork: Fork,
@@ -138,6 +155,7 @@ where
     // Ethereum 1.0 chain data
     pub eth1_data: Eth1Data,
     pub eth1_data_votes: VariableList<Eth1Data, T::SlotsPerEth1VotingPeriod>,
+    #[serde(with = "serde_utils::quoted_u64")]
     pub eth1_deposit_index: u64,
 
     // Registry
@@ -146,6 +164,7 @@ where
     pub validators: VariableList<Validator, T::ValidatorRegistryLimit>,
     #[compare_fields(as_slice)]
     #[cached_tree_hash(balances)]
+    #[serde(with = "ssz_types::serde_utils::quoted_u64_var_list")]
     pub balances: VariableList<u64, T::ValidatorRegistryLimit>,
 
     // Randomness
@@ -154,6 +173,7 @@ where
 
     // Slashings
     #[cached_tree_hash(slashings)]
+    #[serde(with = "ssz_types::serde_utils::quoted_u64_fixed_vec")]
     pub slashings: FixedVector<u64, T::EpochsPerSlashingsVector>,
 
     // Attestations
@@ -910,6 +930,20 @@ impl<T: EthSpec> BeaconState<T> {
         self.tree_hash_cache = BeaconTreeHashCache::default();
     }
 
+    /// An estimate of the memory used by the state and its caches, in bytes.
+    ///
+    /// The size of the state itself is approximated by the length of its SSZ encoding.
+    pub fn memory_size(&self) -> usize {
+        self.ssz_bytes_len()
+            + self
+                .committee_caches
+                .iter()
+                .map(CommitteeCache::memory_size)
+                .sum::<usize>()
+            + self.pubkey_cache.memory_size()
+            + self.tree_hash_cache.memory_size()
+    }
+
     pub fn clone_without_caches(&self) -> Self {
         BeaconState {
             genesis_time: self.genesis_time,
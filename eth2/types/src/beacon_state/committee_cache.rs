@@ -81,6 +81,13 @@ impl CommitteeCache {
         Some(epoch) == self.initialized_epoch
     }
 
+    /// An estimate of the memory used by the cache, in bytes.
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.shuffling.capacity() * std::mem::size_of::<usize>()
+            + self.shuffling_positions.capacity() * std::mem::size_of::<Option<NonZeroUsize>>()
+    }
+
     /// Returns the **shuffled** list of active validator indices for the initialized epoch.
     ///
     /// These indices are not in ascending order.
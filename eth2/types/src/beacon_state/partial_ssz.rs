@@ -0,0 +1,288 @@
+//! The SSZ encoding of a subset of the fields of a `BeaconState`, allowing the fields which change
+//! between two states (e.g., the balances and the roots of recent blocks and states) to be stored
+//! without re-encoding the whole state.
+//!
+//! A single field may also be read from the SSZ encoding of a whole state without decoding the
+//! other fields.
+use crate::*;
+use ssz::{Decode, DecodeError, Encode, BYTES_PER_LENGTH_OFFSET};
+use ssz_derive::{Decode, Encode};
+
+/// The SSZ encoding of a single field of a partially-serialized state.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct PartialField {
+    /// The `StateField::index` of the field.
+    index: u8,
+    ssz: Vec<u8>,
+}
+
+/// Implements the per-field functions of `BeaconState` for each of the listed fields, in the
+/// order of `StateField`.
+macro_rules! impl_field_ssz {
+    ($($variant: ident => $field: ident: $type: ty),* $(,)?) => {
+        impl<T: EthSpec> BeaconState<T> {
+            /// Returns the SSZ encoding of `field`.
+            pub fn field_as_ssz_bytes(&self, field: StateField) -> Vec<u8> {
+                match field {
+                    $(StateField::$variant => self.$field.as_ssz_bytes(),)*
+                }
+            }
+
+            /// Replaces the value of `field` with the value decoded from `bytes`.
+            ///
+            /// The caches of the state are not updated, see `Self::deserialize_partial`.
+            fn set_field_from_ssz_bytes(
+                &mut self,
+                field: StateField,
+                bytes: &[u8],
+            ) -> Result<(), DecodeError> {
+                match field {
+                    $(StateField::$variant => self.$field = <$type>::from_ssz_bytes(bytes)?,)*
+                }
+                Ok(())
+            }
+
+            /// Returns `true` if `field` has the same value in `self` and `other`.
+            fn field_eq(&self, other: &Self, field: StateField) -> bool {
+                match field {
+                    $(StateField::$variant => self.$field == other.$field,)*
+                }
+            }
+
+            /// Returns whether each field has a fixed length, and its length in the fixed-length
+            /// part of the SSZ encoding of a state.
+            fn field_ssz_layout() -> Vec<(StateField, bool, usize)> {
+                vec![
+                    $((
+                        StateField::$variant,
+                        <$type as Decode>::is_ssz_fixed_len(),
+                        <$type as Decode>::ssz_fixed_len(),
+                    ),)*
+                ]
+            }
+        }
+    };
+}
+
+impl_field_ssz!(
+    GenesisTime => genesis_time: u64,
+    Slot => slot: Slot,
+    Fork => fork: Fork,
+    LatestBlockHeader => latest_block_header: BeaconBlockHeader,
+    BlockRoots => block_roots: FixedVector<Hash256, T::SlotsPerHistoricalRoot>,
+    StateRoots => state_roots: FixedVector<Hash256, T::SlotsPerHistoricalRoot>,
+    HistoricalRoots => historical_roots: VariableList<Hash256, T::HistoricalRootsLimit>,
+    Eth1Data => eth1_data: Eth1Data,
+    Eth1DataVotes => eth1_data_votes: VariableList<Eth1Data, T::SlotsPerEth1VotingPeriod>,
+    Eth1DepositIndex => eth1_deposit_index: u64,
+    Validators => validators: VariableList<Validator, T::ValidatorRegistryLimit>,
+    Balances => balances: VariableList<u64, T::ValidatorRegistryLimit>,
+    RandaoMixes => randao_mixes: FixedVector<Hash256, T::EpochsPerHistoricalVector>,
+    Slashings => slashings: FixedVector<u64, T::EpochsPerSlashingsVector>,
+    PreviousEpochAttestations =>
+        previous_epoch_attestations: VariableList<PendingAttestation<T>, T::MaxPendingAttestations>,
+    CurrentEpochAttestations =>
+        current_epoch_attestations: VariableList<PendingAttestation<T>, T::MaxPendingAttestations>,
+    JustificationBits => justification_bits: BitVector<T::JustificationBitsLength>,
+    PreviousJustifiedCheckpoint => previous_justified_checkpoint: Checkpoint,
+    CurrentJustifiedCheckpoint => current_justified_checkpoint: Checkpoint,
+    FinalizedCheckpoint => finalized_checkpoint: Checkpoint,
+);
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Returns the fields whose values differ between `self` and `other`.
+    pub fn changed_fields(&self, other: &Self) -> Vec<StateField> {
+        StateField::all()
+            .filter(|field| !self.field_eq(other, *field))
+            .collect()
+    }
+
+    /// Returns the SSZ encoding of only the given `fields` of the state, which may be applied to
+    /// another state with `Self::deserialize_partial`.
+    pub fn serialize_partial(&self, fields: &[StateField]) -> Vec<u8> {
+        fields
+            .iter()
+            .map(|field| PartialField {
+                index: field.index() as u8,
+                ssz: self.field_as_ssz_bytes(*field),
+            })
+            .collect::<Vec<_>>()
+            .as_ssz_bytes()
+    }
+
+    /// Replaces the fields of `self` with those encoded in `bytes` by `Self::serialize_partial`,
+    /// returning the fields which were replaced.
+    ///
+    /// The caches which depend on the replaced fields are dropped. If an error is returned, some
+    /// fields may have been replaced.
+    pub fn deserialize_partial(&mut self, bytes: &[u8]) -> Result<Vec<StateField>, DecodeError> {
+        let fields = Vec::<PartialField>::from_ssz_bytes(bytes)?
+            .into_iter()
+            .map(|partial| {
+                let field = StateField::from_index(partial.index as usize).ok_or_else(|| {
+                    DecodeError::BytesInvalid(format!("Unknown state field: {}", partial.index))
+                })?;
+                self.set_field_from_ssz_bytes(field, &partial.ssz)?;
+                Ok(field)
+            })
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+
+        if fields.contains(&StateField::Validators) {
+            self.drop_pubkey_cache();
+            self.exit_cache = Default::default();
+        }
+
+        let shuffling_fields = [
+            StateField::Slot,
+            StateField::Validators,
+            StateField::RandaoMixes,
+        ];
+        if fields.iter().any(|field| shuffling_fields.contains(field)) {
+            self.drop_committee_cache(RelativeEpoch::Previous);
+            self.drop_committee_cache(RelativeEpoch::Current);
+            self.drop_committee_cache(RelativeEpoch::Next);
+        }
+
+        Ok(fields)
+    }
+
+    /// Returns the SSZ encoding of `field` within `bytes`, the SSZ encoding of a whole state,
+    /// without decoding the state.
+    pub fn field_ssz_bytes(bytes: &[u8], field: StateField) -> Result<&[u8], DecodeError> {
+        let layout = Self::field_ssz_layout();
+        let read_offset = |position: usize| -> Result<usize, DecodeError> {
+            let offset_bytes = bytes
+                .get(position..position + BYTES_PER_LENGTH_OFFSET)
+                .ok_or_else(|| DecodeError::InvalidByteLength {
+                    len: bytes.len(),
+                    expected: position + BYTES_PER_LENGTH_OFFSET,
+                })?;
+            u32::from_ssz_bytes(offset_bytes).map(|offset| offset as usize)
+        };
+
+        // The position of `field` in the fixed-length part of the encoding.
+        let position = layout
+            .iter()
+            .take(field.index())
+            .map(|(_, _, fixed_len)| fixed_len)
+            .sum::<usize>();
+        let (_, is_fixed_len, fixed_len) = layout[field.index()];
+
+        let (start, end) = if is_fixed_len {
+            (position, position + fixed_len)
+        } else {
+            // A variable-length field ends where the next variable-length field starts, or at the
+            // end of the encoding.
+            let next_position = layout
+                .iter()
+                .enumerate()
+                .skip(field.index() + 1)
+                .find(|(_, (_, is_fixed_len, _))| !is_fixed_len)
+                .map(|(index, _)| {
+                    layout
+                        .iter()
+                        .take(index)
+                        .map(|(_, _, fixed_len)| fixed_len)
+                        .sum::<usize>()
+                });
+            let end = match next_position {
+                Some(next_position) => read_offset(next_position)?,
+                None => bytes.len(),
+            };
+
+            (read_offset(position)?, end)
+        };
+
+        if start > end {
+            return Err(DecodeError::OutOfBoundsByte { i: start });
+        }
+
+        bytes
+            .get(start..end)
+            .ok_or_else(|| DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: end,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+
+    type E = MinimalEthSpec;
+
+    fn get_state() -> BeaconState<E> {
+        let spec = E::default_spec();
+        let (state, _keypairs) =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec).build();
+        state
+    }
+
+    #[test]
+    fn partial_round_trip() {
+        let previous = get_state();
+
+        let mut current = previous.clone();
+        current.slot += 1;
+        current.balances[3] += 1;
+        current.block_roots[0] = Hash256::from_low_u64_be(1);
+
+        let fields = current.changed_fields(&previous);
+        assert_eq!(
+            fields,
+            vec![
+                StateField::Slot,
+                StateField::BlockRoots,
+                StateField::Balances
+            ]
+        );
+
+        let bytes = current.serialize_partial(&fields);
+        assert!(bytes.len() < current.as_ssz_bytes().len());
+
+        let mut restored = previous.clone();
+        assert_eq!(restored.deserialize_partial(&bytes), Ok(fields));
+        assert!(restored.changed_fields(&current).is_empty());
+
+        let mut unknown = PartialField {
+            index: 20,
+            ssz: vec![],
+        };
+        assert!(previous
+            .clone()
+            .deserialize_partial(&vec![unknown].as_ssz_bytes())
+            .is_err());
+
+        unknown = PartialField {
+            index: StateField::Balances.index() as u8,
+            ssz: vec![1],
+        };
+        assert!(previous
+            .clone()
+            .deserialize_partial(&vec![unknown].as_ssz_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn reads_fields_from_state_bytes() {
+        let mut state = get_state();
+        state
+            .historical_roots
+            .push(Hash256::from_low_u64_be(7))
+            .unwrap();
+        let bytes = state.as_ssz_bytes();
+
+        for field in StateField::all() {
+            assert_eq!(
+                BeaconState::<E>::field_ssz_bytes(&bytes, field),
+                Ok(&state.field_as_ssz_bytes(field)[..]),
+                "{:?}",
+                field
+            );
+        }
+
+        assert!(BeaconState::<E>::field_ssz_bytes(&bytes[..8], StateField::Balances).is_err());
+    }
+}
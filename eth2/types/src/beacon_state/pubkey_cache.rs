@@ -33,6 +33,13 @@ impl PubkeyCache {
         }
     }
 
+    /// An estimate of the memory used by the cache, in bytes, ignoring the overhead of the map.
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.map.capacity()
+                * (std::mem::size_of::<PublicKeyBytes>() + std::mem::size_of::<ValidatorIndex>())
+    }
+
     /// Looks up a validator index's by their public key.
     pub fn get(&self, pubkey: &PublicKeyBytes) -> Option<ValidatorIndex> {
         self.map.get(pubkey).copied()
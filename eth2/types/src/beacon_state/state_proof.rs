@@ -0,0 +1,455 @@
+//! Merkle proofs of values within a `BeaconState`, allowing a light client which trusts a state
+//! root to verify a single value without downloading the whole state.
+use super::{BeaconState, Error};
+use crate::{EthSpec, Hash256, HistoricalBatch, Slot};
+use merkle_proof::{verify_merkle_proof, MerkleTree};
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use ssz_types::typenum::Unsigned;
+use tree_hash::TreeHash;
+
+/// The depth of the tree formed by the roots of the fields of a `BeaconState`.
+pub const STATE_FIELDS_DEPTH: usize = 5;
+
+/// Every `StateField`, in order of their index.
+const STATE_FIELDS: [StateField; 20] = [
+    StateField::GenesisTime,
+    StateField::Slot,
+    StateField::Fork,
+    StateField::LatestBlockHeader,
+    StateField::BlockRoots,
+    StateField::StateRoots,
+    StateField::HistoricalRoots,
+    StateField::Eth1Data,
+    StateField::Eth1DataVotes,
+    StateField::Eth1DepositIndex,
+    StateField::Validators,
+    StateField::Balances,
+    StateField::RandaoMixes,
+    StateField::Slashings,
+    StateField::PreviousEpochAttestations,
+    StateField::CurrentEpochAttestations,
+    StateField::JustificationBits,
+    StateField::PreviousJustifiedCheckpoint,
+    StateField::CurrentJustifiedCheckpoint,
+    StateField::FinalizedCheckpoint,
+];
+
+/// A top-level field of a `BeaconState`, in the order in which the fields are tree hashed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StateField {
+    GenesisTime,
+    Slot,
+    Fork,
+    LatestBlockHeader,
+    BlockRoots,
+    StateRoots,
+    HistoricalRoots,
+    Eth1Data,
+    Eth1DataVotes,
+    Eth1DepositIndex,
+    Validators,
+    Balances,
+    RandaoMixes,
+    Slashings,
+    PreviousEpochAttestations,
+    CurrentEpochAttestations,
+    JustificationBits,
+    PreviousJustifiedCheckpoint,
+    CurrentJustifiedCheckpoint,
+    FinalizedCheckpoint,
+}
+
+impl StateField {
+    /// Returns the field with the given name, as it appears in the spec (e.g.,
+    /// `finalized_checkpoint`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let field = match name {
+            "genesis_time" => StateField::GenesisTime,
+            "slot" => StateField::Slot,
+            "fork" => StateField::Fork,
+            "latest_block_header" => StateField::LatestBlockHeader,
+            "block_roots" => StateField::BlockRoots,
+            "state_roots" => StateField::StateRoots,
+            "historical_roots" => StateField::HistoricalRoots,
+            "eth1_data" => StateField::Eth1Data,
+            "eth1_data_votes" => StateField::Eth1DataVotes,
+            "eth1_deposit_index" => StateField::Eth1DepositIndex,
+            "validators" => StateField::Validators,
+            "balances" => StateField::Balances,
+            "randao_mixes" => StateField::RandaoMixes,
+            "slashings" => StateField::Slashings,
+            "previous_epoch_attestations" => StateField::PreviousEpochAttestations,
+            "current_epoch_attestations" => StateField::CurrentEpochAttestations,
+            "justification_bits" => StateField::JustificationBits,
+            "previous_justified_checkpoint" => StateField::PreviousJustifiedCheckpoint,
+            "current_justified_checkpoint" => StateField::CurrentJustifiedCheckpoint,
+            "finalized_checkpoint" => StateField::FinalizedCheckpoint,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    /// The position of the field's root amongst the leaves of the state tree.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the field with the given `index`, if any.
+    pub fn from_index(index: usize) -> Option<Self> {
+        STATE_FIELDS.get(index).copied()
+    }
+
+    /// Returns all of the fields, in order of their index.
+    pub fn all() -> impl Iterator<Item = Self> {
+        STATE_FIELDS.iter().copied()
+    }
+}
+
+/// A value within a `BeaconState` which can be proven against the state root.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StateProofTarget {
+    /// The root of a top-level field of the state.
+    Field(StateField),
+    /// The root of the block at a recent slot, within `block_roots`.
+    BlockRoot(Slot),
+    /// The root of the state at a recent slot, within `state_roots`.
+    StateRoot(Slot),
+    /// The root of the record of the validator with the given index, within `validators`.
+    Validator(usize),
+    /// The block root of the `finalized_checkpoint`, allowing a light client to verify the
+    /// header of the finalized block.
+    FinalizedCheckpointRoot,
+}
+
+/// A Merkle proof that `leaf` is included in the tree of a `BeaconState`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct StateProof {
+    /// The tree hash root of the proven value.
+    pub leaf: Hash256,
+    /// The sibling nodes on the path from `leaf` to the state root, ordered from the bottom up.
+    pub branch: Vec<Hash256>,
+    /// The position of `leaf` amongst the `2^branch.len()` leaves at the bottom of the proof.
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub index: u64,
+}
+
+impl StateProof {
+    /// Returns `true` if the proof shows that `leaf` is included in the state with `state_root`.
+    pub fn verify(&self, state_root: Hash256) -> bool {
+        verify_merkle_proof(
+            self.leaf,
+            &self.branch,
+            self.branch.len(),
+            self.index as usize,
+            state_root,
+        )
+    }
+}
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Produces a proof of `target` against the tree hash root of `self`.
+    ///
+    /// The proof is computed from scratch, so this function is expensive for large states.
+    pub fn compute_merkle_proof(&self, target: StateProofTarget) -> Result<StateProof, Error> {
+        match target {
+            StateProofTarget::Field(field) => {
+                let field_roots = self.field_roots();
+                Ok(StateProof {
+                    leaf: field_roots[field.index()],
+                    branch: subtree_branch(&field_roots, STATE_FIELDS_DEPTH, field.index()),
+                    index: field.index() as u64,
+                })
+            }
+            StateProofTarget::BlockRoot(slot) => {
+                let i = self.get_latest_block_roots_index(slot)?;
+                Ok(self.vector_item_proof(StateField::BlockRoots, &self.block_roots, i))
+            }
+            StateProofTarget::StateRoot(slot) => {
+                let i = self.get_latest_state_roots_index(slot)?;
+                Ok(self.vector_item_proof(StateField::StateRoots, &self.state_roots, i))
+            }
+            StateProofTarget::Validator(i) => {
+                if i >= self.validators.len() {
+                    return Err(Error::UnknownValidator);
+                }
+
+                let leaves = self
+                    .validators
+                    .iter()
+                    .map(|validator| Hash256::from_slice(&validator.tree_hash_root()))
+                    .collect::<Vec<_>>();
+                let depth = log2(T::ValidatorRegistryLimit::to_usize());
+
+                let mut branch = subtree_branch(&leaves, depth, i);
+                branch.push(length_chunk(self.validators.len()));
+
+                Ok(self.extend_to_state_root(StateField::Validators, leaves[i], branch, i))
+            }
+            StateProofTarget::FinalizedCheckpointRoot => {
+                // The root is the second of the two fields of the checkpoint.
+                let epoch_root =
+                    Hash256::from_slice(&self.finalized_checkpoint.epoch.tree_hash_root());
+                Ok(self.extend_to_state_root(
+                    StateField::FinalizedCheckpoint,
+                    self.finalized_checkpoint.root,
+                    vec![epoch_root],
+                    1,
+                ))
+            }
+        }
+    }
+
+    /// Produces a proof of the root of the block at `slot` against the tree hash root of `self`,
+    /// where `slot` is old enough that its block root is only committed to via `historical_roots`.
+    ///
+    /// The `batch` must be the `HistoricalBatch` which was accumulated into `historical_roots` for
+    /// `slot`. Since `self` does not contain the batch, it must be loaded from elsewhere (e.g., the
+    /// freezer database).
+    pub fn compute_historical_block_root_proof(
+        &self,
+        slot: Slot,
+        batch: &HistoricalBatch<T>,
+    ) -> Result<StateProof, Error> {
+        let slots_per_historical_root = T::SlotsPerHistoricalRoot::to_usize();
+        let batch_index = slot.as_usize() / slots_per_historical_root;
+
+        let batch_root = self
+            .historical_roots
+            .get(batch_index)
+            .ok_or(Error::SlotOutOfBounds)?;
+        if Hash256::from_slice(&batch.tree_hash_root()) != *batch_root {
+            return Err(Error::HistoricalBatchMismatch(batch_index));
+        }
+
+        // Prove the block root within the batch, where `block_roots` is the first of its two
+        // fields.
+        let i = slot.as_usize() % slots_per_historical_root;
+        let mut branch = subtree_branch(&batch.block_roots, log2(slots_per_historical_root), i);
+        branch.push(Hash256::from_slice(&batch.state_roots.tree_hash_root()));
+
+        // Then prove the batch root within `historical_roots`.
+        let index = i | (batch_index << branch.len());
+        branch.extend(subtree_branch(
+            &self.historical_roots,
+            log2(T::HistoricalRootsLimit::to_usize()),
+            batch_index,
+        ));
+        branch.push(length_chunk(self.historical_roots.len()));
+
+        Ok(self.extend_to_state_root(
+            StateField::HistoricalRoots,
+            batch.block_roots[i],
+            branch,
+            index,
+        ))
+    }
+
+    /// Returns the tree hash roots of each of the fields of `self`, ordered by `StateField`.
+    pub fn field_roots(&self) -> Vec<Hash256> {
+        vec![
+            self.genesis_time.tree_hash_root(),
+            self.slot.tree_hash_root(),
+            self.fork.tree_hash_root(),
+            self.latest_block_header.tree_hash_root(),
+            self.block_roots.tree_hash_root(),
+            self.state_roots.tree_hash_root(),
+            self.historical_roots.tree_hash_root(),
+            self.eth1_data.tree_hash_root(),
+            self.eth1_data_votes.tree_hash_root(),
+            self.eth1_deposit_index.tree_hash_root(),
+            self.validators.tree_hash_root(),
+            self.balances.tree_hash_root(),
+            self.randao_mixes.tree_hash_root(),
+            self.slashings.tree_hash_root(),
+            self.previous_epoch_attestations.tree_hash_root(),
+            self.current_epoch_attestations.tree_hash_root(),
+            self.justification_bits.tree_hash_root(),
+            self.previous_justified_checkpoint.tree_hash_root(),
+            self.current_justified_checkpoint.tree_hash_root(),
+            self.finalized_checkpoint.tree_hash_root(),
+        ]
+        .into_iter()
+        .map(|root| Hash256::from_slice(&root))
+        .collect()
+    }
+
+    /// Produces a proof of the `i`th item of `vector`, which is the value of `field`.
+    fn vector_item_proof(&self, field: StateField, vector: &[Hash256], i: usize) -> StateProof {
+        let branch = subtree_branch(vector, log2(vector.len()), i);
+        self.extend_to_state_root(field, vector[i], branch, i)
+    }
+
+    /// Extends a proof of `leaf` at `index` within the tree of `field` into a proof against the
+    /// state root.
+    fn extend_to_state_root(
+        &self,
+        field: StateField,
+        leaf: Hash256,
+        mut branch: Vec<Hash256>,
+        index: usize,
+    ) -> StateProof {
+        let field_depth = branch.len();
+        branch.extend(subtree_branch(
+            &self.field_roots(),
+            STATE_FIELDS_DEPTH,
+            field.index(),
+        ));
+
+        StateProof {
+            leaf,
+            branch,
+            index: (index | (field.index() << field_depth)) as u64,
+        }
+    }
+}
+
+/// Returns the bottom-up branch for the leaf at `index` of a tree of `depth` with `leaves`.
+fn subtree_branch(leaves: &[Hash256], depth: usize, index: usize) -> Vec<Hash256> {
+    let (_, branch) = MerkleTree::create(leaves, depth).generate_proof(index, depth);
+    branch
+}
+
+/// Returns the chunk which is mixed into the root of a list of `len` items.
+fn length_chunk(len: usize) -> Hash256 {
+    let mut chunk = (len as u64).to_le_bytes().to_vec();
+    chunk.resize(32, 0);
+    Hash256::from_slice(&chunk)
+}
+
+/// Returns the depth of a tree with `n` leaves, where `n` is a power of two.
+fn log2(n: usize) -> usize {
+    n.trailing_zeros() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+    use crate::{Epoch, MinimalEthSpec};
+
+    fn get_state(validator_count: usize) -> BeaconState<MinimalEthSpec> {
+        let spec = MinimalEthSpec::default_spec();
+        let builder =
+            TestingBeaconStateBuilder::from_deterministic_keypairs(validator_count, &spec);
+        let (mut state, _keypairs) = builder.build();
+        state.slot = Slot::new(17);
+        state.finalized_checkpoint.epoch = Epoch::new(1);
+        for (i, root) in state.block_roots.iter_mut().enumerate() {
+            *root = Hash256::from_low_u64_be(i as u64);
+        }
+        state
+    }
+
+    #[test]
+    fn field_roots_match_state_root() {
+        let state = get_state(8);
+        let root = MerkleTree::create(&state.field_roots(), STATE_FIELDS_DEPTH).hash();
+        assert_eq!(root, Hash256::from_slice(&state.tree_hash_root()));
+    }
+
+    #[test]
+    fn proofs_verify() {
+        let state = get_state(8);
+        let state_root = Hash256::from_slice(&state.tree_hash_root());
+
+        let targets = vec![
+            StateProofTarget::Field(StateField::FinalizedCheckpoint),
+            StateProofTarget::Field(StateField::GenesisTime),
+            StateProofTarget::BlockRoot(Slot::new(3)),
+            StateProofTarget::StateRoot(Slot::new(16)),
+            StateProofTarget::Validator(0),
+            StateProofTarget::Validator(5),
+            StateProofTarget::FinalizedCheckpointRoot,
+        ];
+
+        for target in targets {
+            let proof = state.compute_merkle_proof(target).unwrap();
+            assert!(proof.verify(state_root), "{:?} should verify", target);
+            assert!(
+                !proof.verify(Hash256::zero()),
+                "{:?} should not verify against another root",
+                target
+            );
+        }
+
+        let proof = state
+            .compute_merkle_proof(StateProofTarget::BlockRoot(Slot::new(3)))
+            .unwrap();
+        assert_eq!(proof.leaf, Hash256::from_low_u64_be(3));
+
+        let proof = state
+            .compute_merkle_proof(StateProofTarget::FinalizedCheckpointRoot)
+            .unwrap();
+        assert_eq!(proof.leaf, state.finalized_checkpoint.root);
+
+        let proof = state
+            .compute_merkle_proof(StateProofTarget::Validator(5))
+            .unwrap();
+        assert_eq!(
+            proof.leaf,
+            Hash256::from_slice(&state.validators[5].tree_hash_root())
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_targets() {
+        let state = get_state(8);
+        assert_eq!(
+            state.compute_merkle_proof(StateProofTarget::BlockRoot(Slot::new(17))),
+            Err(Error::SlotOutOfBounds)
+        );
+        assert_eq!(
+            state.compute_merkle_proof(StateProofTarget::Validator(8)),
+            Err(Error::UnknownValidator)
+        );
+    }
+
+    #[test]
+    fn historical_block_root_proofs() {
+        let mut state = get_state(8);
+        let slots_per_historical_root = MinimalEthSpec::slots_per_historical_root() as u64;
+
+        let batches = (0..3)
+            .map(|batch_index| {
+                let mut batch = state.historical_batch();
+                for (i, root) in batch.block_roots.iter_mut().enumerate() {
+                    *root = Hash256::from_low_u64_be(batch_index * 1_000 + i as u64);
+                }
+                batch
+            })
+            .collect::<Vec<_>>();
+        for batch in &batches {
+            state
+                .historical_roots
+                .push(Hash256::from_slice(&batch.tree_hash_root()))
+                .unwrap();
+        }
+        let state_root = Hash256::from_slice(&state.tree_hash_root());
+
+        for &(batch_index, i) in &[(0, 0), (1, 5), (2, slots_per_historical_root - 1)] {
+            let slot = Slot::new(batch_index * slots_per_historical_root + i);
+            let proof = state
+                .compute_historical_block_root_proof(slot, &batches[batch_index as usize])
+                .unwrap();
+            assert_eq!(
+                proof.leaf,
+                Hash256::from_low_u64_be(batch_index * 1_000 + i)
+            );
+            assert!(proof.verify(state_root), "slot {} should verify", slot);
+            assert!(!proof.verify(Hash256::zero()));
+        }
+
+        assert_eq!(
+            state.compute_historical_block_root_proof(Slot::new(5), &batches[1]),
+            Err(Error::HistoricalBatchMismatch(0))
+        );
+        assert_eq!(
+            state.compute_historical_block_root_proof(
+                Slot::new(3 * slots_per_historical_root),
+                &batches[0]
+            ),
+            Err(Error::SlotOutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Extra fields which an `EthSpec` may add to the `BeaconBlockBody`.
+//!
+//! This allows experimental payloads (e.g., the witnesses required by stateless clients) to be
+//! carried in blocks without defining a separate block type.
+//!
+//! The extension is only included in the SSZ, tree hash and serde representations of the
+//! `BeaconBlockBody` when the `block_body_extension` feature is enabled. Without the feature, the
+//! extension of a block is always `Default::default()` once decoded and blocks are identical to
+//! those of the spec.
+use crate::test_utils::TestRandom;
+use crate::*;
+use rand::RngCore;
+use serde_derive::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::typenum::{U4096, U65536};
+use ssz_types::VariableList;
+use std::fmt::Debug;
+use test_random_derive::TestRandom;
+use tree_hash::{TreeHash, TreeHashType};
+use tree_hash_derive::TreeHash;
+
+/// Extra fields in the `BeaconBlockBody`, see the module-level documentation.
+pub trait BlockBodyExtension:
+    Debug
+    + Clone
+    + PartialEq
+    + Default
+    + Send
+    + Sync
+    + serde::Serialize
+    + serde::de::DeserializeOwned
+    + Encode
+    + Decode
+    + TreeHash
+    + TestRandom
+{
+    /// Returns the execution witness carried by the extension, if any.
+    fn execution_witness(&self) -> Option<&ExecutionWitness> {
+        None
+    }
+}
+
+/// The extension of the specs defined in this crate.
+#[cfg(not(feature = "execution_witness"))]
+pub type DefaultBlockBodyExtension = NoExtension;
+/// The extension of the specs defined in this crate.
+#[cfg(feature = "execution_witness")]
+pub type DefaultBlockBodyExtension = ExecutionWitness;
+
+/// An extension without any fields.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NoExtension;
+
+impl BlockBodyExtension for NoExtension {}
+
+impl Encode for NoExtension {
+    fn is_ssz_fixed_len() -> bool {
+        true
+    }
+
+    fn ssz_fixed_len() -> usize {
+        0
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        0
+    }
+
+    fn ssz_append(&self, _buf: &mut Vec<u8>) {}
+}
+
+impl Decode for NoExtension {
+    fn is_ssz_fixed_len() -> bool {
+        true
+    }
+
+    fn ssz_fixed_len() -> usize {
+        0
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.is_empty() {
+            Ok(NoExtension)
+        } else {
+            Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: 0,
+            })
+        }
+    }
+}
+
+impl TreeHash for NoExtension {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Container
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        vec![0; 32]
+    }
+}
+
+impl TestRandom for NoExtension {
+    fn random_for_test(_rng: &mut impl RngCore) -> Self {
+        NoExtension
+    }
+}
+
+/// An example extension, carrying a witness of the parts of the parent state which are read
+/// whilst processing the block. This would allow a client which only stores the state root to
+/// verify the block.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct StatelessWitness {
+    /// The indices of the validators whose records are read whilst processing the block.
+    pub validator_indices: VariableList<u64, U4096>,
+    /// A Merkle multi-proof of those validator records against the state root of the parent
+    /// block.
+    pub proof: VariableList<Hash256, U65536>,
+}
+
+impl BlockBodyExtension for StatelessWitness {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ssz_tests!(StatelessWitness);
+
+    #[test]
+    fn no_extension_is_empty() {
+        assert_eq!(NoExtension.as_ssz_bytes(), Vec::<u8>::new());
+        assert_eq!(NoExtension::from_ssz_bytes(&[]), Ok(NoExtension));
+        assert!(NoExtension::from_ssz_bytes(&[0]).is_err());
+    }
+}
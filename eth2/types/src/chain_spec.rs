@@ -6,12 +6,15 @@ use utils::{u32_from_hex_str, u32_to_hex_str, u8_from_hex_str, u8_to_hex_str};
 /// Each of the BLS signature domains.
 ///
 /// Spec v0.9.1
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Domain {
     BeaconProposer,
     BeaconAttester,
     Randao,
     Deposit,
     VoluntaryExit,
+    SelectionProof,
+    AggregateAndProof,
 }
 
 /// Holds all the "constants" for a BeaconChain.
@@ -83,6 +86,13 @@ pub struct ChainSpec {
     domain_randao: u32,
     domain_deposit: u32,
     domain_voluntary_exit: u32,
+    domain_selection_proof: u32,
+    domain_aggregate_and_proof: u32,
+
+    /*
+     * Attestation aggregation
+     */
+    pub target_aggregators_per_committee: u64,
 
     /*
      * Fork choice
@@ -111,6 +121,8 @@ impl ChainSpec {
             Domain::Randao => self.domain_randao,
             Domain::Deposit => self.domain_deposit,
             Domain::VoluntaryExit => self.domain_voluntary_exit,
+            Domain::SelectionProof => self.domain_selection_proof,
+            Domain::AggregateAndProof => self.domain_aggregate_and_proof,
         }
     }
 
@@ -211,6 +223,13 @@ impl ChainSpec {
             domain_randao: 2,
             domain_deposit: 3,
             domain_voluntary_exit: 4,
+            domain_selection_proof: 8,
+            domain_aggregate_and_proof: 9,
+
+            /*
+             * Attestation aggregation
+             */
+            target_aggregators_per_committee: 16,
 
             /*
              * Fork choice
@@ -276,6 +295,47 @@ impl ChainSpec {
             ..ChainSpec::mainnet()
         }
     }
+
+    /// Returns a copy of `self` with the constants in `overrides` replaced, where `overrides` is a
+    /// YAML mapping of some of the constants in the configs of the Ethereum 2 spec (e.g.,
+    /// `SECONDS_PER_SLOT: 2`).
+    ///
+    /// Returns an error if `overrides` cannot be parsed, or if it changes a constant which is
+    /// fixed at compile time by `T`.
+    pub fn with_overrides<T: EthSpec>(&self, overrides: &str) -> Result<Self, String> {
+        let overrides: serde_yaml::Mapping = serde_yaml::from_str(overrides)
+            .map_err(|e| format!("Unable to parse spec overrides: {:?}", e))?;
+
+        let mut config = match serde_yaml::to_value(YamlConfig::from_spec::<T>(self)) {
+            Ok(serde_yaml::Value::Mapping(config)) => config,
+            other => return Err(format!("Unable to serialize spec: {:?}", other)),
+        };
+        for (name, value) in overrides.iter() {
+            config.insert(name.clone(), value.clone());
+        }
+
+        let yaml_config: YamlConfig = serde_yaml::from_value(serde_yaml::Value::Mapping(config))
+            .map_err(|e| format!("Invalid spec overrides: {:?}", e))?;
+
+        let mismatches = yaml_config.eth_spec_mismatches::<T>();
+        if !mismatches.is_empty() {
+            return Err(format!(
+                "Spec overrides cannot change compile-time constants: {}",
+                mismatches.join(", ")
+            ));
+        }
+
+        let mut spec = yaml_config
+            .apply_to_chain_spec::<T>(self)
+            .ok_or_else(|| "Unable to apply spec overrides".to_string())?;
+
+        // `SECONDS_PER_SLOT` has a lower resolution than `milliseconds_per_slot`.
+        if !overrides.contains_key(&serde_yaml::Value::from("SECONDS_PER_SLOT")) {
+            spec.milliseconds_per_slot = self.milliseconds_per_slot;
+        }
+
+        Ok(spec)
+    }
 }
 
 impl Default for ChainSpec {
@@ -315,6 +375,12 @@ mod tests {
         test_domain(Domain::Randao, spec.domain_randao, &spec);
         test_domain(Domain::Deposit, spec.domain_deposit, &spec);
         test_domain(Domain::VoluntaryExit, spec.domain_voluntary_exit, &spec);
+        test_domain(Domain::SelectionProof, spec.domain_selection_proof, &spec);
+        test_domain(
+            Domain::AggregateAndProof,
+            spec.domain_aggregate_and_proof,
+            &spec,
+        );
     }
 }
 
@@ -394,7 +460,7 @@ pub struct YamlConfig {
     // EthSpec
     justification_bits_length: u32,
     max_validators_per_committee: u32,
-    genesis_epoch: Epoch,
+    genesis_epoch: u64,
     slots_per_epoch: u64,
     slots_per_eth1_voting_period: usize,
     slots_per_historical_root: usize,
@@ -492,7 +558,7 @@ impl YamlConfig {
             // EthSpec
             justification_bits_length: T::JustificationBitsLength::to_u32(),
             max_validators_per_committee: T::MaxValidatorsPerCommittee::to_u32(),
-            genesis_epoch: T::genesis_epoch(),
+            genesis_epoch: T::genesis_epoch().as_u64(),
             slots_per_epoch: T::slots_per_epoch(),
             slots_per_eth1_voting_period: T::slots_per_eth1_voting_period(),
             slots_per_historical_root: T::slots_per_historical_root(),
@@ -527,24 +593,95 @@ impl YamlConfig {
         }
     }
 
+    /// Returns the names of the constants in `self` which differ from the compile-time constants of
+    /// `T`. A config may only be applied to a `ChainSpec` for `T` if this list is empty.
+    pub fn eth_spec_mismatches<T: EthSpec>(&self) -> Vec<&'static str> {
+        let checks = [
+            (
+                "JUSTIFICATION_BITS_LENGTH",
+                self.justification_bits_length == T::JustificationBitsLength::to_u32(),
+            ),
+            (
+                "MAX_VALIDATORS_PER_COMMITTEE",
+                self.max_validators_per_committee == T::MaxValidatorsPerCommittee::to_u32(),
+            ),
+            (
+                "GENESIS_EPOCH",
+                self.genesis_epoch == T::genesis_epoch().as_u64(),
+            ),
+            (
+                "SLOTS_PER_EPOCH",
+                self.slots_per_epoch == T::slots_per_epoch(),
+            ),
+            (
+                "SLOTS_PER_ETH1_VOTING_PERIOD",
+                self.slots_per_eth1_voting_period == T::slots_per_eth1_voting_period(),
+            ),
+            (
+                "SLOTS_PER_HISTORICAL_ROOT",
+                self.slots_per_historical_root == T::slots_per_historical_root(),
+            ),
+            (
+                "EPOCHS_PER_HISTORICAL_VECTOR",
+                self.epochs_per_historical_vector == T::epochs_per_historical_vector(),
+            ),
+            (
+                "EPOCHS_PER_SLASHINGS_VECTOR",
+                self.epochs_per_slashings_vector == T::EpochsPerSlashingsVector::to_usize(),
+            ),
+            (
+                "HISTORICAL_ROOTS_LIMIT",
+                self.historical_roots_limit == T::HistoricalRootsLimit::to_u64(),
+            ),
+            (
+                "VALIDATOR_REGISTRY_LIMIT",
+                self.validator_registry_limit == T::ValidatorRegistryLimit::to_u64(),
+            ),
+            (
+                "MAX_PROPOSER_SLASHINGS",
+                self.max_proposer_slashings == T::MaxProposerSlashings::to_u32(),
+            ),
+            (
+                "MAX_ATTESTER_SLASHINGS",
+                self.max_attester_slashings == T::MaxAttesterSlashings::to_u32(),
+            ),
+            (
+                "MAX_ATTESTATIONS",
+                self.max_attestations == T::MaxAttestations::to_u32(),
+            ),
+            (
+                "MAX_DEPOSITS",
+                self.max_deposits == T::MaxDeposits::to_u32(),
+            ),
+            (
+                "MAX_VOLUNTARY_EXITS",
+                self.max_voluntary_exits == T::MaxVoluntaryExits::to_u32(),
+            ),
+        ];
+
+        checks
+            .iter()
+            .filter(|(_, matches)| !matches)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Returns the `EthSpec` whose compile-time constants match `self`, if any.
+    ///
+    /// The minimal and interop specs share the same constants, so `Minimal` is returned for either.
+    pub fn eth_spec_id(&self) -> Option<EthSpecId> {
+        if self.eth_spec_mismatches::<MainnetEthSpec>().is_empty() {
+            Some(EthSpecId::Mainnet)
+        } else if self.eth_spec_mismatches::<MinimalEthSpec>().is_empty() {
+            Some(EthSpecId::Minimal)
+        } else {
+            None
+        }
+    }
+
     pub fn apply_to_chain_spec<T: EthSpec>(&self, chain_spec: &ChainSpec) -> Option<ChainSpec> {
         // Checking for EthSpec constants
-        if self.justification_bits_length != T::JustificationBitsLength::to_u32()
-            || self.max_validators_per_committee != T::MaxValidatorsPerCommittee::to_u32()
-            || self.genesis_epoch != T::genesis_epoch()
-            || self.slots_per_epoch != T::slots_per_epoch()
-            || self.slots_per_eth1_voting_period != T::slots_per_eth1_voting_period()
-            || self.slots_per_historical_root != T::slots_per_historical_root()
-            || self.epochs_per_historical_vector != T::epochs_per_historical_vector()
-            || self.epochs_per_slashings_vector != T::EpochsPerSlashingsVector::to_usize()
-            || self.historical_roots_limit != T::HistoricalRootsLimit::to_u64()
-            || self.validator_registry_limit != T::ValidatorRegistryLimit::to_u64()
-            || self.max_proposer_slashings != T::MaxProposerSlashings::to_u32()
-            || self.max_attester_slashings != T::MaxAttesterSlashings::to_u32()
-            || self.max_attestations != T::MaxAttestations::to_u32()
-            || self.max_deposits != T::MaxDeposits::to_u32()
-            || self.max_voluntary_exits != T::MaxVoluntaryExits::to_u32()
-        {
+        if !self.eth_spec_mismatches::<T>().is_empty() {
             return None;
         }
 
@@ -623,6 +760,19 @@ mod yaml_tests {
         assert_eq!(from, yamlconfig);
     }
 
+    #[test]
+    fn detects_eth_spec_id() {
+        let minimal = YamlConfig::from_spec::<MinimalEthSpec>(&ChainSpec::minimal());
+        assert_eq!(minimal.eth_spec_id(), Some(EthSpecId::Minimal));
+
+        let mainnet = YamlConfig::from_spec::<MainnetEthSpec>(&ChainSpec::mainnet());
+        assert_eq!(mainnet.eth_spec_id(), Some(EthSpecId::Mainnet));
+
+        let mut unknown = minimal;
+        unknown.slots_per_epoch = 4;
+        assert_eq!(unknown.eth_spec_id(), None);
+    }
+
     #[test]
     fn mainnet_round_trip() {
         let tmp_file = NamedTempFile::new().expect("failed to create temp file");
@@ -654,6 +804,12 @@ mod yaml_tests {
         // Applying a yaml config with incorrect EthSpec should fail
         let res = yamlconfig.apply_to_chain_spec::<MainnetEthSpec>(&spec);
         assert_eq!(res, None);
+        assert!(yamlconfig
+            .eth_spec_mismatches::<MainnetEthSpec>()
+            .contains(&"SLOTS_PER_EPOCH"));
+        assert!(yamlconfig
+            .eth_spec_mismatches::<MinimalEthSpec>()
+            .is_empty());
 
         // Applying a yaml config with correct EthSpec should NOT fail
         let new_spec = yamlconfig
@@ -661,4 +817,35 @@ mod yaml_tests {
             .expect("should have applied spec");
         assert_eq!(new_spec, ChainSpec::minimal());
     }
+
+    #[test]
+    fn with_overrides() {
+        let mut spec = ChainSpec::minimal();
+        spec.milliseconds_per_slot = 500;
+
+        let new_spec = spec
+            .with_overrides::<MinimalEthSpec>(
+                "MIN_GENESIS_ACTIVE_VALIDATOR_COUNT: 16\nETH1_FOLLOW_DISTANCE: 4",
+            )
+            .expect("should apply overrides");
+        assert_eq!(new_spec.min_genesis_active_validator_count, 16);
+        assert_eq!(new_spec.eth1_follow_distance, 4);
+        assert_eq!(new_spec.milliseconds_per_slot, 500);
+        assert_eq!(new_spec.target_committee_size, spec.target_committee_size);
+
+        let new_spec = spec
+            .with_overrides::<MinimalEthSpec>("SECONDS_PER_SLOT: 2")
+            .expect("should apply overrides");
+        assert_eq!(new_spec.milliseconds_per_slot, 2_000);
+
+        // Constants which are fixed by the `EthSpec` cannot be changed.
+        let err = spec
+            .with_overrides::<MinimalEthSpec>("SLOTS_PER_EPOCH: 4")
+            .unwrap_err();
+        assert!(err.contains("SLOTS_PER_EPOCH"));
+
+        assert!(spec
+            .with_overrides::<MinimalEthSpec>("NOT_A_CONSTANT: 1")
+            .is_err());
+    }
 }
@@ -27,6 +27,7 @@ use tree_hash_derive::{SignedRoot, TreeHash};
 pub struct DepositData {
     pub pubkey: PublicKeyBytes,
     pub withdrawal_credentials: Hash256,
+    #[serde(with = "serde_utils::quoted_u64")]
     pub amount: u64,
     #[signed_root(skip_hashing)]
     pub signature: SignatureBytes,
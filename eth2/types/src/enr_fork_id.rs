@@ -0,0 +1,123 @@
+use crate::test_utils::TestRandom;
+use crate::{Epoch, Fork, Hash256};
+
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use test_random_derive::TestRandom;
+use tree_hash::TreeHash;
+use tree_hash_derive::TreeHash;
+
+/// Identifies the fork (and chain) of a node in its ENR, under the `eth2` key, so that peers on
+/// other chains can be ignored before they are dialed.
+///
+/// Based upon the `ENRForkID` of later specs, with the genesis block root in place of the
+/// genesis validators root, which is not known to v0.9.1 states.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct EnrForkId {
+    #[serde(with = "serde_utils::bytes_4_hex")]
+    pub fork_digest: [u8; 4],
+    #[serde(with = "serde_utils::bytes_4_hex")]
+    pub next_fork_version: [u8; 4],
+    pub next_fork_epoch: Epoch,
+}
+
+/// The data from which a fork digest is computed.
+#[derive(TreeHash)]
+struct ForkData {
+    current_version: [u8; 4],
+    genesis_root: Hash256,
+}
+
+impl EnrForkId {
+    /// Returns the fork ID of a chain with the given `genesis_root`, at `epoch`.
+    ///
+    /// If `fork` is scheduled after `epoch`, it is advertised as the next fork. Otherwise, no
+    /// fork is scheduled and the next fork epoch is `far_future_epoch`.
+    pub fn new(fork: &Fork, genesis_root: Hash256, epoch: Epoch, far_future_epoch: Epoch) -> Self {
+        if epoch < fork.epoch {
+            Self {
+                fork_digest: compute_fork_digest(fork.previous_version, genesis_root),
+                next_fork_version: fork.current_version,
+                next_fork_epoch: fork.epoch,
+            }
+        } else {
+            Self {
+                fork_digest: compute_fork_digest(fork.current_version, genesis_root),
+                next_fork_version: fork.current_version,
+                next_fork_epoch: far_future_epoch,
+            }
+        }
+    }
+
+    /// Returns `true` if a node advertising `other` is on the same chain and fork as this node,
+    /// such that it is worth connecting to.
+    ///
+    /// The next fork may differ, since a node which has not been upgraded for a fork remains
+    /// useful until the fork occurs.
+    pub fn is_compatible_with(&self, other: &EnrForkId) -> bool {
+        self.fork_digest == other.fork_digest
+    }
+}
+
+/// Returns the first four bytes of the root of the `current_version` of a chain with the given
+/// `genesis_root`.
+pub fn compute_fork_digest(current_version: [u8; 4], genesis_root: Hash256) -> [u8; 4] {
+    let root = ForkData {
+        current_version,
+        genesis_root,
+    }
+    .tree_hash_root();
+
+    let mut digest = [0; 4];
+    digest.copy_from_slice(&root[0..4]);
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ssz_tests!(EnrForkId);
+
+    #[test]
+    fn compatible_with_same_chain_and_fork() {
+        let far_future_epoch = Epoch::new(u64::max_value());
+        let fork = Fork {
+            previous_version: [0, 0, 0, 0],
+            current_version: [0, 0, 0, 1],
+            epoch: Epoch::new(10),
+        };
+        let genesis_root = Hash256::from_low_u64_be(1);
+        let fork_id = |fork: &Fork, genesis_root, epoch| {
+            EnrForkId::new(fork, genesis_root, Epoch::new(epoch), far_future_epoch)
+        };
+
+        let before_fork = fork_id(&fork, genesis_root, 9);
+        assert_eq!(before_fork.next_fork_version, fork.current_version);
+        assert_eq!(before_fork.next_fork_epoch, fork.epoch);
+
+        let after_fork = fork_id(&fork, genesis_root, 10);
+        assert_eq!(after_fork.next_fork_epoch, far_future_epoch);
+        assert!(after_fork.is_compatible_with(&fork_id(&fork, genesis_root, 20)));
+
+        assert!(
+            !before_fork.is_compatible_with(&after_fork),
+            "nodes on either side of a fork are incompatible"
+        );
+        assert!(
+            !after_fork.is_compatible_with(&fork_id(&fork, Hash256::from_low_u64_be(2), 10)),
+            "nodes on different chains are incompatible"
+        );
+
+        let unscheduled = Fork {
+            epoch: Epoch::new(20),
+            ..fork.clone()
+        };
+        assert!(
+            fork_id(&unscheduled, genesis_root, 9).is_compatible_with(&before_fork),
+            "the next fork may differ"
+        );
+    }
+}
@@ -4,7 +4,46 @@ use ssz_types::typenum::{
     Unsigned, U0, U1, U1024, U1099511627776, U128, U16, U16777216, U2048, U32, U4, U4096, U64,
     U65536, U8, U8192,
 };
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+/// Identifies one of the `EthSpec` implementations which are compiled into the binary, allowing
+/// one to be selected at runtime.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EthSpecId {
+    Mainnet,
+    Minimal,
+    Interop,
+}
+
+impl EthSpecId {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EthSpecId::Mainnet => "mainnet",
+            EthSpecId::Minimal => "minimal",
+            EthSpecId::Interop => "interop",
+        }
+    }
+}
+
+impl FromStr for EthSpecId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(EthSpecId::Mainnet),
+            "minimal" => Ok(EthSpecId::Minimal),
+            "interop" => Ok(EthSpecId::Interop),
+            other => Err(format!("Unknown spec: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for EthSpecId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
 pub trait EthSpec: 'static + Default + Sync + Send + Clone + Debug + PartialEq {
     /*
@@ -49,6 +88,11 @@ pub trait EthSpec: 'static + Default + Sync + Send + Clone + Debug + PartialEq {
     // NOTE: we could safely instantiate this by using type-level arithmetic, but doing
     // so adds ~25s to the time required to type-check this crate
     type MaxPendingAttestations: Unsigned + Clone + Sync + Send + Debug + PartialEq;
+    /*
+     * Experiments
+     */
+    /// Extra fields in the `BeaconBlockBody`, see `BlockBodyExtension`.
+    type BlockBodyExtension: BlockBodyExtension;
 
     fn default_spec() -> ChainSpec;
 
@@ -143,6 +187,7 @@ impl EthSpec for MainnetEthSpec {
     type MaxDeposits = U16;
     type MaxVoluntaryExits = U16;
     type MaxPendingAttestations = U4096; // 128 max attestations * 32 slots per epoch
+    type BlockBodyExtension = DefaultBlockBodyExtension;
 
     fn default_spec() -> ChainSpec {
         ChainSpec::mainnet()
@@ -177,7 +222,8 @@ impl EthSpec for MinimalEthSpec {
         MaxAttesterSlashings,
         MaxAttestations,
         MaxDeposits,
-        MaxVoluntaryExits
+        MaxVoluntaryExits,
+        BlockBodyExtension
     });
 
     fn default_spec() -> ChainSpec {
@@ -209,7 +255,8 @@ impl EthSpec for InteropEthSpec {
         MaxAttesterSlashings,
         MaxAttestations,
         MaxDeposits,
-        MaxVoluntaryExits
+        MaxVoluntaryExits,
+        BlockBodyExtension
     });
 
     fn default_spec() -> ChainSpec {
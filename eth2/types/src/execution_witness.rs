@@ -0,0 +1,245 @@
+//! A witness of the execution state accessed by a block, allowing stateless clients to verify it.
+//!
+//! The containers follow the Verkle-tree witness proposed in EIP-6800. They are carried in the
+//! `BeaconBlockBody` of the specs in this crate when the `execution_witness` feature is enabled
+//! (see `BlockBodyExtension`).
+use crate::test_utils::TestRandom;
+use crate::*;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use ssz_types::typenum::{U1048576, U256, U31, U32, U65536, U8};
+use ssz_types::{FixedVector, VariableList};
+use test_random_derive::TestRandom;
+use tree_hash_derive::TreeHash;
+
+/// Is `true` if the specs in this crate carry an `ExecutionWitness` in each block.
+pub const EXECUTION_WITNESS_ENABLED: bool = cfg!(feature = "execution_witness");
+
+/// The maximum number of stems in a witness.
+pub type MaxStems = U65536;
+/// The maximum number of commitments in the proof of a witness.
+pub type MaxCommitmentsPerWitness = U1048576;
+
+/// The first 31 bytes of a key in the Verkle tree, which is shared by 256 values.
+pub type Stem = FixedVector<u8, U31>;
+
+/// The value of a single key, which is empty if the key is absent.
+pub type WitnessValue = VariableList<u8, U32>;
+
+#[derive(Debug, PartialEq)]
+pub enum ExecutionWitnessError {
+    /// The stems of the state diff are not in strictly ascending order.
+    StemsNotSorted,
+    /// The suffixes of a stem are not in strictly ascending order.
+    SuffixesNotSorted { stem_index: usize },
+    /// A value is neither absent nor 32 bytes long.
+    InvalidValueLength { stem_index: usize, suffix: u8 },
+    /// The proof does not have one depth for each stem of the state diff.
+    DepthsMismatch { stems: usize, depths: usize },
+}
+
+/// The values of a single key before and after the block.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct SuffixStateDiff {
+    /// The last byte of the key.
+    pub suffix: u8,
+    pub current_value: WitnessValue,
+    pub new_value: WitnessValue,
+}
+
+/// The keys accessed by a block which share `stem`.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct StemStateDiff {
+    pub stem: Stem,
+    pub suffix_diffs: VariableList<SuffixStateDiff, U256>,
+}
+
+/// An inner product argument proof.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct IpaProof {
+    pub cl: FixedVector<Hash256, U8>,
+    pub cr: FixedVector<Hash256, U8>,
+    pub final_evaluation: Hash256,
+}
+
+/// A multi-proof of the values of a `StateDiff` against the state root of the parent block.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct VerkleProof {
+    /// Stems which are in the tree where the accessed stems would otherwise be, proving their
+    /// absence.
+    pub other_stems: VariableList<Stem, MaxStems>,
+    /// The depth of each accessed stem and whether it is present, one byte per stem.
+    pub depth_extension_present: VariableList<u8, MaxStems>,
+    pub commitments_by_path: VariableList<Hash256, MaxCommitmentsPerWitness>,
+    pub d: Hash256,
+    pub ipa_proof: IpaProof,
+}
+
+/// The state accessed by a block, and a proof of it.
+///
+/// An empty `state_diff` means the block carries no witness.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct ExecutionWitness {
+    pub state_diff: VariableList<StemStateDiff, MaxStems>,
+    pub verkle_proof: VerkleProof,
+}
+
+impl ExecutionWitness {
+    /// Returns `true` if the witness does not prove any state.
+    pub fn is_empty(&self) -> bool {
+        self.state_diff.is_empty()
+    }
+
+    /// Checks that the witness is well-formed.
+    ///
+    /// This does not verify the proof against the state root, which requires the Verkle
+    /// commitment scheme.
+    pub fn verify_structure(&self) -> Result<(), ExecutionWitnessError> {
+        let stems_sorted = self
+            .state_diff
+            .iter()
+            .zip(self.state_diff.iter().skip(1))
+            .all(|(a, b)| a.stem[..] < b.stem[..]);
+        if !stems_sorted {
+            return Err(ExecutionWitnessError::StemsNotSorted);
+        }
+
+        for (stem_index, stem_diff) in self.state_diff.iter().enumerate() {
+            let suffixes_sorted = stem_diff
+                .suffix_diffs
+                .iter()
+                .zip(stem_diff.suffix_diffs.iter().skip(1))
+                .all(|(a, b)| a.suffix < b.suffix);
+            if !suffixes_sorted {
+                return Err(ExecutionWitnessError::SuffixesNotSorted { stem_index });
+            }
+
+            for diff in stem_diff.suffix_diffs.iter() {
+                let valid_length = |value: &WitnessValue| value.is_empty() || value.len() == 32;
+                if !valid_length(&diff.current_value) || !valid_length(&diff.new_value) {
+                    return Err(ExecutionWitnessError::InvalidValueLength {
+                        stem_index,
+                        suffix: diff.suffix,
+                    });
+                }
+            }
+        }
+
+        let depths = self.verkle_proof.depth_extension_present.len();
+        if depths != self.state_diff.len() {
+            return Err(ExecutionWitnessError::DepthsMismatch {
+                stems: self.state_diff.len(),
+                depths,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl BlockBodyExtension for ExecutionWitness {
+    fn execution_witness(&self) -> Option<&ExecutionWitness> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ssz_tests!(ExecutionWitness);
+
+    fn stem_diff(first_byte: u8, suffixes: &[u8]) -> StemStateDiff {
+        let mut stem = vec![0; 31];
+        stem[0] = first_byte;
+
+        StemStateDiff {
+            stem: FixedVector::from(stem),
+            suffix_diffs: VariableList::from(
+                suffixes
+                    .iter()
+                    .map(|&suffix| SuffixStateDiff {
+                        suffix,
+                        current_value: VariableList::from(vec![1; 32]),
+                        new_value: VariableList::empty(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    fn witness(state_diff: Vec<StemStateDiff>) -> ExecutionWitness {
+        ExecutionWitness {
+            verkle_proof: VerkleProof {
+                depth_extension_present: VariableList::from(vec![0; state_diff.len()]),
+                ..VerkleProof::default()
+            },
+            state_diff: VariableList::from(state_diff),
+        }
+    }
+
+    #[test]
+    fn empty_witness_is_absent() {
+        let empty = ExecutionWitness::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.execution_witness(), None);
+        assert_eq!(empty.verify_structure(), Ok(()));
+
+        let witness = witness(vec![stem_diff(1, &[0])]);
+        assert_eq!(witness.execution_witness(), Some(&witness));
+    }
+
+    #[test]
+    fn verifies_structure() {
+        assert_eq!(
+            witness(vec![stem_diff(1, &[0, 3]), stem_diff(2, &[255])]).verify_structure(),
+            Ok(())
+        );
+        assert_eq!(
+            witness(vec![stem_diff(2, &[0]), stem_diff(1, &[0])]).verify_structure(),
+            Err(ExecutionWitnessError::StemsNotSorted)
+        );
+        assert_eq!(
+            witness(vec![stem_diff(1, &[0]), stem_diff(1, &[1])]).verify_structure(),
+            Err(ExecutionWitnessError::StemsNotSorted)
+        );
+        assert_eq!(
+            witness(vec![stem_diff(1, &[3, 3])]).verify_structure(),
+            Err(ExecutionWitnessError::SuffixesNotSorted { stem_index: 0 })
+        );
+
+        let mut invalid_value = witness(vec![stem_diff(1, &[7])]);
+        invalid_value.state_diff[0].suffix_diffs[0].new_value = VariableList::from(vec![1; 31]);
+        assert_eq!(
+            invalid_value.verify_structure(),
+            Err(ExecutionWitnessError::InvalidValueLength {
+                stem_index: 0,
+                suffix: 7
+            })
+        );
+
+        let mut missing_depth = witness(vec![stem_diff(1, &[0])]);
+        missing_depth.verkle_proof.depth_extension_present = VariableList::empty();
+        assert_eq!(
+            missing_depth.verify_structure(),
+            Err(ExecutionWitnessError::DepthsMismatch {
+                stems: 1,
+                depths: 0
+            })
+        );
+    }
+}
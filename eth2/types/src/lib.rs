@@ -6,6 +6,7 @@
 #[macro_use]
 pub mod test_utils;
 
+pub mod aggregate_and_proof;
 pub mod attestation;
 pub mod attestation_data;
 pub mod attestation_duty;
@@ -15,18 +16,24 @@ pub mod beacon_block_body;
 pub mod beacon_block_header;
 pub mod beacon_committee;
 pub mod beacon_state;
+pub mod block_body_extension;
 pub mod chain_spec;
 pub mod checkpoint;
 pub mod deposit;
 pub mod deposit_data;
+pub mod enr_fork_id;
 pub mod eth1_data;
 pub mod eth_spec;
+pub mod execution_witness;
 pub mod fork;
 pub mod free_attestation;
 pub mod historical_batch;
 pub mod indexed_attestation;
 pub mod pending_attestation;
 pub mod proposer_slashing;
+pub mod safe_signer;
+pub mod selection_proof;
+pub mod signed_aggregate_and_proof;
 pub mod utils;
 pub mod voluntary_exit;
 #[macro_use]
@@ -39,6 +46,7 @@ pub mod validator;
 
 use ethereum_types::{H160, H256};
 
+pub use crate::aggregate_and_proof::AggregateAndProof;
 pub use crate::attestation::{Attestation, Error as AttestationError};
 pub use crate::attestation_data::AttestationData;
 pub use crate::attestation_duty::AttestationDuty;
@@ -48,11 +56,19 @@ pub use crate::beacon_block_body::BeaconBlockBody;
 pub use crate::beacon_block_header::BeaconBlockHeader;
 pub use crate::beacon_committee::{BeaconCommittee, OwnedBeaconCommittee};
 pub use crate::beacon_state::{Error as BeaconStateError, *};
+pub use crate::block_body_extension::{
+    BlockBodyExtension, DefaultBlockBodyExtension, NoExtension, StatelessWitness,
+};
 pub use crate::chain_spec::{ChainSpec, Domain, YamlConfig};
 pub use crate::checkpoint::Checkpoint;
 pub use crate::deposit::{Deposit, DEPOSIT_TREE_DEPTH};
 pub use crate::deposit_data::DepositData;
+pub use crate::enr_fork_id::EnrForkId;
 pub use crate::eth1_data::Eth1Data;
+pub use crate::eth_spec::EthSpecId;
+pub use crate::execution_witness::{
+    ExecutionWitness, ExecutionWitnessError, EXECUTION_WITNESS_ENABLED,
+};
 pub use crate::fork::Fork;
 pub use crate::free_attestation::FreeAttestation;
 pub use crate::historical_batch::HistoricalBatch;
@@ -60,6 +76,9 @@ pub use crate::indexed_attestation::IndexedAttestation;
 pub use crate::pending_attestation::PendingAttestation;
 pub use crate::proposer_slashing::ProposerSlashing;
 pub use crate::relative_epoch::{Error as RelativeEpochError, RelativeEpoch};
+pub use crate::safe_signer::{SafeSigner, SlashingProtectionBackend};
+pub use crate::selection_proof::SelectionProof;
+pub use crate::signed_aggregate_and_proof::SignedAggregateAndProof;
 pub use crate::slot_epoch::{Epoch, Slot};
 pub use crate::slot_height::SlotHeight;
 pub use crate::validator::Validator;
@@ -13,7 +13,9 @@ use tree_hash_derive::TreeHash;
 pub struct PendingAttestation<T: EthSpec> {
     pub aggregation_bits: BitList<T::MaxValidatorsPerCommittee>,
     pub data: AttestationData,
+    #[serde(with = "serde_utils::quoted_u64")]
     pub inclusion_delay: u64,
+    #[serde(with = "serde_utils::quoted_u64")]
     pub proposer_index: u64,
 }
 
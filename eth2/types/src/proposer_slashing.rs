@@ -11,6 +11,7 @@ use tree_hash_derive::TreeHash;
 /// Spec v0.9.1
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom)]
 pub struct ProposerSlashing {
+    #[serde(with = "serde_utils::quoted_u64")]
     pub proposer_index: u64,
     pub header_1: BeaconBlockHeader,
     pub header_2: BeaconBlockHeader,
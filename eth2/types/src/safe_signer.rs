@@ -0,0 +1,239 @@
+//! A signer which consults a slashing protection backend before signing any slashable message.
+//!
+//! `SafeSigner` never exposes its secret key, so a caller holding one (e.g., a validator client or
+//! a test rig) is unable to sign a block or attestation without the backend having first recorded
+//! it as safe.
+use crate::{
+    AttestationData, BeaconBlockHeader, ChainSpec, Domain, EthSpec, Fork, Hash256, Keypair,
+    PublicKey, Signature, Slot,
+};
+use std::fmt::Debug;
+use std::sync::Arc;
+use tree_hash::TreeHash;
+
+/// A store of the slashable messages signed by some validators, which refuses to record a message
+/// that conflicts with one recorded earlier.
+pub trait SlashingProtectionBackend {
+    /// The reason a message was deemed unsafe to sign.
+    type Error: Debug;
+
+    /// Returns `Ok` if it is safe for `pubkey` to sign the block at `slot` with `signing_root`,
+    /// recording the block before returning.
+    fn check_and_record_block(
+        &self,
+        pubkey: &PublicKey,
+        slot: Slot,
+        signing_root: Hash256,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns `Ok` if it is safe for `pubkey` to sign an attestation for `data` with
+    /// `signing_root`, recording the attestation before returning.
+    fn check_and_record_attestation(
+        &self,
+        pubkey: &PublicKey,
+        data: &AttestationData,
+        signing_root: Hash256,
+    ) -> Result<(), Self::Error>;
+}
+
+impl<P: SlashingProtectionBackend> SlashingProtectionBackend for Arc<P> {
+    type Error = P::Error;
+
+    fn check_and_record_block(
+        &self,
+        pubkey: &PublicKey,
+        slot: Slot,
+        signing_root: Hash256,
+    ) -> Result<(), Self::Error> {
+        P::check_and_record_block(self, pubkey, slot, signing_root)
+    }
+
+    fn check_and_record_attestation(
+        &self,
+        pubkey: &PublicKey,
+        data: &AttestationData,
+        signing_root: Hash256,
+    ) -> Result<(), Self::Error> {
+        P::check_and_record_attestation(self, pubkey, data, signing_root)
+    }
+}
+
+/// Signs the blocks and attestations of a single validator, provided they are deemed safe by a
+/// `SlashingProtectionBackend`.
+pub struct SafeSigner<P> {
+    keypair: Keypair,
+    protection: P,
+}
+
+impl<P: SlashingProtectionBackend> SafeSigner<P> {
+    /// Takes ownership of `keypair`, which may only be used to sign via `protection` thereafter.
+    pub fn new(keypair: Keypair, protection: P) -> Self {
+        Self {
+            keypair,
+            protection,
+        }
+    }
+
+    /// The public key of the validator.
+    pub fn pubkey(&self) -> &PublicKey {
+        &self.keypair.pk
+    }
+
+    /// Returns the signature of the block with `header`, if it is safe to sign.
+    ///
+    /// The signature of `header` is ignored, so it may be empty.
+    pub fn sign_block<T: EthSpec>(
+        &self,
+        header: &BeaconBlockHeader,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> Result<Signature, P::Error> {
+        // The canonical root of a header matches that of its block, so the history is consistent
+        // with signers which record blocks.
+        let signing_root = header.canonical_root();
+
+        self.protection
+            .check_and_record_block(self.pubkey(), header.slot, signing_root)?;
+
+        let message = signing_root.as_bytes();
+        let domain = spec.get_domain(
+            header.slot.epoch(T::slots_per_epoch()),
+            Domain::BeaconProposer,
+            fork,
+        );
+
+        Ok(Signature::new(message, domain, &self.keypair.sk))
+    }
+
+    /// Returns the signature of an attestation for `data`, if it is safe to sign.
+    pub fn sign_attestation(
+        &self,
+        data: &AttestationData,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> Result<Signature, P::Error> {
+        let message = data.tree_hash_root();
+
+        self.protection.check_and_record_attestation(
+            self.pubkey(),
+            data,
+            Hash256::from_slice(&message),
+        )?;
+
+        let domain = spec.get_domain(data.target.epoch, Domain::BeaconAttester, fork);
+
+        Ok(Signature::new(&message, domain, &self.keypair.sk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BeaconBlock, Checkpoint, Epoch, MinimalEthSpec};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    type E = MinimalEthSpec;
+
+    /// Refuses conflicting blocks and attestations with the same slot and target epoch.
+    #[derive(Default)]
+    struct TestProtection {
+        blocks: RefCell<HashMap<Slot, Hash256>>,
+        attestations: RefCell<HashMap<Epoch, Hash256>>,
+    }
+
+    impl SlashingProtectionBackend for TestProtection {
+        type Error = &'static str;
+
+        fn check_and_record_block(
+            &self,
+            _pubkey: &PublicKey,
+            slot: Slot,
+            signing_root: Hash256,
+        ) -> Result<(), Self::Error> {
+            match self.blocks.borrow_mut().insert(slot, signing_root) {
+                Some(previous) if previous != signing_root => Err("double block proposal"),
+                _ => Ok(()),
+            }
+        }
+
+        fn check_and_record_attestation(
+            &self,
+            _pubkey: &PublicKey,
+            data: &AttestationData,
+            signing_root: Hash256,
+        ) -> Result<(), Self::Error> {
+            match self
+                .attestations
+                .borrow_mut()
+                .insert(data.target.epoch, signing_root)
+            {
+                Some(previous) if previous != signing_root => Err("double vote"),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn attestation_data(target_epoch: u64, root: u64) -> AttestationData {
+        AttestationData {
+            slot: Slot::new(0),
+            index: 0,
+            beacon_block_root: Hash256::from_low_u64_be(root),
+            source: Checkpoint::default(),
+            target: Checkpoint {
+                epoch: Epoch::new(target_epoch),
+                root: Hash256::zero(),
+            },
+        }
+    }
+
+    #[test]
+    fn signs_blocks_once_per_slot() {
+        let spec = E::default_spec();
+        let fork = Fork::default();
+        let keypair = Keypair::random();
+        let signer = SafeSigner::new(keypair.clone(), TestProtection::default());
+
+        let mut block = BeaconBlock::<E>::empty(&spec);
+        block.slot = Slot::new(1);
+
+        let signature = signer
+            .sign_block::<E>(&block.block_header(), &fork, &spec)
+            .expect("should sign block");
+
+        block.sign(&keypair.sk, &fork, &spec);
+        assert_eq!(signature, block.signature);
+
+        // Re-signing the same block is safe, regardless of its signature.
+        assert!(signer
+            .sign_block::<E>(&block.block_header(), &fork, &spec)
+            .is_ok());
+
+        block.state_root = Hash256::from_low_u64_be(1);
+        assert_eq!(
+            signer.sign_block::<E>(&block.block_header(), &fork, &spec),
+            Err("double block proposal")
+        );
+    }
+
+    #[test]
+    fn refuses_double_votes() {
+        let spec = E::default_spec();
+        let fork = Fork::default();
+        let signer = SafeSigner::new(Keypair::random(), Arc::new(TestProtection::default()));
+
+        assert!(signer
+            .sign_attestation(&attestation_data(1, 1), &fork, &spec)
+            .is_ok());
+        assert!(signer
+            .sign_attestation(&attestation_data(1, 1), &fork, &spec)
+            .is_ok());
+        assert!(signer
+            .sign_attestation(&attestation_data(2, 2), &fork, &spec)
+            .is_ok());
+        assert_eq!(
+            signer.sign_attestation(&attestation_data(1, 2), &fork, &spec),
+            Err("double vote")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{ChainSpec, Domain, EthSpec, Fork, PublicKey, SecretKey, Signature, Slot};
+use eth2_hashing::hash;
+use serde_derive::{Deserialize, Serialize};
+use std::cmp;
+use std::convert::TryInto;
+use tree_hash::TreeHash;
+
+/// A signature across a slot, used to determine if a validator should aggregate the attestations
+/// of its committee.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SelectionProof(Signature);
+
+impl SelectionProof {
+    /// Signs `slot` with `secret_key`.
+    pub fn new<T: EthSpec>(
+        slot: Slot,
+        secret_key: &SecretKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> Self {
+        let message = slot.tree_hash_root();
+        let domain = spec.get_domain(
+            slot.epoch(T::slots_per_epoch()),
+            Domain::SelectionProof,
+            fork,
+        );
+
+        Self(Signature::new(&message, domain, secret_key))
+    }
+
+    /// Returns the "modulo" used to determine if a validator in a committee of size
+    /// `committee_len` is an aggregator.
+    pub fn modulo(committee_len: usize, spec: &ChainSpec) -> u64 {
+        cmp::max(
+            1,
+            committee_len as u64 / spec.target_aggregators_per_committee,
+        )
+    }
+
+    /// Returns `true` if the validator that produced `self` is an aggregator for a committee of
+    /// size `committee_len`.
+    pub fn is_aggregator(&self, committee_len: usize, spec: &ChainSpec) -> bool {
+        self.is_aggregator_from_modulo(Self::modulo(committee_len, spec))
+    }
+
+    /// Returns `true` if the validator that produced `self` is an aggregator, given a `modulo`
+    /// produced by `Self::modulo`.
+    pub fn is_aggregator_from_modulo(&self, modulo: u64) -> bool {
+        let signature_hash = hash(&self.0.as_bytes());
+        let signature_hash_int = u64::from_le_bytes(
+            signature_hash[0..8]
+                .try_into()
+                .expect("first 8 bytes of signature should always convert to fixed array"),
+        );
+
+        signature_hash_int % cmp::max(1, modulo) == 0
+    }
+
+    /// Returns `true` if `self` is a valid signature across `slot` by `pubkey`.
+    pub fn verify<T: EthSpec>(
+        &self,
+        slot: Slot,
+        pubkey: &PublicKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> bool {
+        let message = slot.tree_hash_root();
+        let domain = spec.get_domain(
+            slot.epoch(T::slots_per_epoch()),
+            Domain::SelectionProof,
+            fork,
+        );
+
+        self.0.verify(&message, domain, pubkey)
+    }
+}
+
+impl From<Signature> for SelectionProof {
+    fn from(signature: Signature) -> Self {
+        Self(signature)
+    }
+}
+
+impl From<SelectionProof> for Signature {
+    fn from(proof: SelectionProof) -> Self {
+        proof.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::generate_deterministic_keypairs, Keypair, MinimalEthSpec};
+
+    #[test]
+    fn modulo_is_never_zero() {
+        let spec = MinimalEthSpec::default_spec();
+
+        assert_eq!(SelectionProof::modulo(0, &spec), 1);
+        assert_eq!(SelectionProof::modulo(1, &spec), 1);
+        assert_eq!(
+            SelectionProof::modulo(spec.target_aggregators_per_committee as usize * 4, &spec),
+            4
+        );
+    }
+
+    #[test]
+    fn everyone_aggregates_in_small_committees() {
+        let spec = MinimalEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let proof = SelectionProof::new::<MinimalEthSpec>(
+            Slot::new(1),
+            &keypair.sk,
+            &spec.genesis_fork,
+            &spec,
+        );
+
+        assert!(proof.is_aggregator(1, &spec));
+        assert!(proof.verify::<MinimalEthSpec>(
+            Slot::new(1),
+            &keypair.pk,
+            &spec.genesis_fork,
+            &spec
+        ));
+        assert!(!proof.verify::<MinimalEthSpec>(
+            Slot::new(2),
+            &keypair.pk,
+            &spec.genesis_fork,
+            &spec
+        ));
+    }
+
+    #[test]
+    fn aggregators_are_selected_by_modulo() {
+        let spec = MinimalEthSpec::default_spec();
+        let committee_len = spec.target_aggregators_per_committee as usize * 2;
+        let modulo = SelectionProof::modulo(committee_len, &spec);
+
+        let aggregators = generate_deterministic_keypairs(64)
+            .iter()
+            .map(|keypair| {
+                SelectionProof::new::<MinimalEthSpec>(
+                    Slot::new(1),
+                    &keypair.sk,
+                    &spec.genesis_fork,
+                    &spec,
+                )
+            })
+            .filter(|proof| {
+                let is_aggregator = proof.is_aggregator(committee_len, &spec);
+                assert_eq!(is_aggregator, proof.is_aggregator_from_modulo(modulo));
+                is_aggregator
+            })
+            .count();
+
+        // Roughly half of the validators should be selected when the modulo is two.
+        assert_eq!(modulo, 2);
+        assert!(aggregators > 16 && aggregators < 48, "{}", aggregators);
+    }
+}
@@ -0,0 +1,94 @@
+use super::{
+    AggregateAndProof, Attestation, ChainSpec, EthSpec, Fork, PublicKey, SecretKey, Signature,
+};
+use crate::test_utils::TestRandom;
+
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use test_random_derive::TestRandom;
+use tree_hash_derive::TreeHash;
+
+/// An `AggregateAndProof`, signed by the aggregator.
+///
+/// This is the message that is published to the network by aggregators.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom)]
+#[serde(bound = "T: EthSpec")]
+pub struct SignedAggregateAndProof<T: EthSpec> {
+    pub message: AggregateAndProof<T>,
+    pub signature: Signature,
+}
+
+impl<T: EthSpec> SignedAggregateAndProof<T> {
+    /// Produces a new `SignedAggregateAndProof`, signing both the selection proof and the
+    /// `AggregateAndProof` with `secret_key`.
+    pub fn from_aggregate(
+        aggregator_index: u64,
+        aggregate: Attestation<T>,
+        secret_key: &SecretKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> Self {
+        let message =
+            AggregateAndProof::from_aggregate(aggregator_index, aggregate, secret_key, fork, spec);
+        let signature = Signature::new(
+            &message.signing_root(),
+            message.domain(fork, spec),
+            secret_key,
+        );
+
+        Self { message, signature }
+    }
+
+    /// Returns `true` if `self.signature` is a valid signature across `self.message` by
+    /// `validator_pubkey`.
+    ///
+    /// The selection proof is not verified.
+    pub fn is_valid_signature(
+        &self,
+        validator_pubkey: &PublicKey,
+        fork: &Fork,
+        spec: &ChainSpec,
+    ) -> bool {
+        self.signature.verify(
+            &self.message.signing_root(),
+            self.message.domain(fork, spec),
+            validator_pubkey,
+        )
+    }
+
+    /// Returns `true` if both the selection proof and the signature are valid for
+    /// `validator_pubkey`.
+    pub fn is_valid(&self, validator_pubkey: &PublicKey, fork: &Fork, spec: &ChainSpec) -> bool {
+        self.message
+            .is_valid_selection_proof(validator_pubkey, fork, spec)
+            && self.is_valid_signature(validator_pubkey, fork, spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{SeedableRng, XorShiftRng};
+    use crate::*;
+
+    ssz_tests!(SignedAggregateAndProof<MainnetEthSpec>);
+
+    #[test]
+    fn from_aggregate_is_valid() {
+        let spec = MainnetEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let aggregate = Attestation::random_for_test(&mut rng);
+
+        let signed = SignedAggregateAndProof::<MainnetEthSpec>::from_aggregate(
+            7,
+            aggregate,
+            &keypair.sk,
+            &spec.genesis_fork,
+            &spec,
+        );
+
+        assert!(signed.is_valid(&keypair.pk, &spec.genesis_fork, &spec));
+        assert!(!signed.is_valid(&Keypair::random().pk, &spec.genesis_fork, &spec));
+    }
+}
@@ -24,10 +24,11 @@ use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, Sub, SubAssi
 
 #[derive(Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Slot(u64);
+pub struct Slot(#[serde(with = "serde_utils::quoted_u64")] u64);
 
 #[derive(Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
-pub struct Epoch(u64);
+#[serde(transparent)]
+pub struct Epoch(#[serde(with = "serde_utils::quoted_u64")] u64);
 
 impl_common!(Slot);
 impl_common!(Epoch);
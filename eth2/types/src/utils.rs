@@ -1,3 +1,3 @@
 mod serde_utils;
 
-pub use serde_utils::*;
+pub use self::serde_utils::*;
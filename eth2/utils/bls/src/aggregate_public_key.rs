@@ -29,6 +29,11 @@ impl AggregatePublicKey {
         self.0.point.add(point)
     }
 
+    /// Removes `point` from the aggregate, i.e., the inverse of `Self::add_point`.
+    pub fn sub_point(&mut self, point: &G1Point) {
+        self.0.point.sub(point)
+    }
+
     /// Returns the underlying public key.
     pub fn as_raw(&self) -> &RawAggregatePublicKey {
         &self.0
@@ -41,6 +41,10 @@ impl FakeAggregatePublicKey {
         // No nothing.
     }
 
+    pub fn sub_point(&mut self, _point: &G1Point) {
+        // No nothing.
+    }
+
     pub fn as_raw(&self) -> &Self {
         &self
     }
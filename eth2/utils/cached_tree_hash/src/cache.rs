@@ -127,6 +127,19 @@ impl TreeHashCache {
     pub fn leaves(&mut self) -> &mut Vec<Hash256> {
         &mut self.layers[self.depth]
     }
+
+    /// An estimate of the memory used by the cache, in bytes.
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .layers
+                .iter()
+                .map(|layer| {
+                    std::mem::size_of::<Vec<Hash256>>()
+                        + layer.capacity() * std::mem::size_of::<Hash256>()
+                })
+                .sum::<usize>()
+    }
 }
 
 /// Compute the dirty indices for one layer up.
@@ -16,6 +16,18 @@ pub struct MultiTreeHashCache {
     value_caches: Vec<TreeHashCache>,
 }
 
+impl MultiTreeHashCache {
+    /// An estimate of the memory used by the cache, in bytes.
+    pub fn memory_size(&self) -> usize {
+        self.list_cache.memory_size()
+            + self
+                .value_caches
+                .iter()
+                .map(TreeHashCache::memory_size)
+                .sum::<usize>()
+    }
+}
+
 impl<T, N> CachedTreeHash<MultiTreeHashCache> for VariableList<T, N>
 where
     T: CachedTreeHash<TreeHashCache>,
@@ -10,6 +10,10 @@ use types::ChainSpec;
 pub struct Eth2Config {
     pub spec_constants: String,
     pub spec: ChainSpec,
+    /// The chain ID of the eth1 network of the deposit contract, if it is known.
+    pub eth1_chain_id: Option<u64>,
+    /// The network ID of the eth1 network of the deposit contract, if it is known.
+    pub eth1_network_id: Option<u64>,
 }
 
 impl Default for Eth2Config {
@@ -17,6 +21,8 @@ impl Default for Eth2Config {
         Self {
             spec_constants: "minimal".to_string(),
             spec: ChainSpec::minimal(),
+            eth1_chain_id: None,
+            eth1_network_id: None,
         }
     }
 }
@@ -26,6 +32,8 @@ impl Eth2Config {
         Self {
             spec_constants: "mainnet".to_string(),
             spec: ChainSpec::mainnet(),
+            eth1_chain_id: None,
+            eth1_network_id: None,
         }
     }
 
@@ -33,6 +41,8 @@ impl Eth2Config {
         Self {
             spec_constants: "minimal".to_string(),
             spec: ChainSpec::minimal(),
+            eth1_chain_id: None,
+            eth1_network_id: None,
         }
     }
 
@@ -40,6 +50,8 @@ impl Eth2Config {
         Self {
             spec_constants: "interop".to_string(),
             spec: ChainSpec::interop(),
+            eth1_chain_id: None,
+            eth1_network_id: None,
         }
     }
 }
@@ -0,0 +1,145 @@
+//! Defines the JSON representation of an EIP-2335 keystore.
+//!
+//! These definitions are intended to be as close to the EIP-2335 specification as possible, they
+//! perform only basic validation (e.g., they do not check that a KDF function name matches the
+//! provided KDF parameters). More complete validation is performed by the `Keystore` type.
+//!
+//! https://eips.ethereum.org/EIPS/eip-2335
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The only keystore version supported by this implementation.
+pub const KEYSTORE_VERSION: u32 = 4;
+
+pub const SCRYPT_FUNCTION: &str = "scrypt";
+pub const PBKDF2_FUNCTION: &str = "pbkdf2";
+pub const PBKDF2_PRF: &str = "hmac-sha256";
+pub const SHA256_FUNCTION: &str = "sha256";
+pub const AES_128_CTR_FUNCTION: &str = "aes-128-ctr";
+
+/// A keystore, as it is serialized to JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonKeystore {
+    pub crypto: Crypto,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub pubkey: String,
+    pub path: String,
+    pub uuid: Uuid,
+    pub version: u32,
+}
+
+/// The `crypto` section of a keystore, also shared with EIP-2386 wallets.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Crypto {
+    pub kdf: KdfModule,
+    pub checksum: ChecksumModule,
+    pub cipher: CipherModule,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KdfModule {
+    pub function: String,
+    pub params: Kdf,
+    pub message: HexBytes,
+}
+
+/// Parameters for the key derivation function.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Kdf {
+    Scrypt(Scrypt),
+    Pbkdf2(Pbkdf2),
+}
+
+impl Kdf {
+    /// Returns the name of the function, as it should appear in the `kdf.function` field.
+    pub fn function(&self) -> &'static str {
+        match self {
+            Kdf::Scrypt(_) => SCRYPT_FUNCTION,
+            Kdf::Pbkdf2(_) => PBKDF2_FUNCTION,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scrypt {
+    pub dklen: u32,
+    pub n: u32,
+    pub p: u32,
+    pub r: u32,
+    pub salt: HexBytes,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pbkdf2 {
+    pub c: u32,
+    pub dklen: u32,
+    pub prf: String,
+    pub salt: HexBytes,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChecksumModule {
+    pub function: String,
+    pub params: EmptyParams,
+    pub message: HexBytes,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CipherModule {
+    pub function: String,
+    pub params: Cipher,
+    pub message: HexBytes,
+}
+
+/// Parameters for the symmetric cipher.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cipher {
+    pub iv: HexBytes,
+}
+
+/// Used for the empty `params` object of the checksum module.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmptyParams {}
+
+/// Bytes which are serialized as a hex string _without_ a `0x` prefix, as per EIP-2335.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct HexBytes(pub Vec<u8>);
+
+impl HexBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for HexBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Serialize for HexBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for HexBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        hex::decode(&string)
+            .map(HexBytes)
+            .map_err(|e| de::Error::custom(format!("Invalid hex string: {:?}", e)))
+    }
+}
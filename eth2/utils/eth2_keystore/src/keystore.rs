@@ -0,0 +1,419 @@
+//! Provides a `Keystore` which can be used to encrypt/decrypt a BLS `Keypair` as per EIP-2335.
+use crate::json_keystore::{
+    ChecksumModule, Cipher, CipherModule, Crypto, EmptyParams, JsonKeystore, Kdf, KdfModule,
+    Pbkdf2, Scrypt, AES_128_CTR_FUNCTION, KEYSTORE_VERSION, PBKDF2_PRF, SHA256_FUNCTION,
+};
+use crate::PlainText;
+use aes_ctr::stream_cipher::generic_array::GenericArray;
+use aes_ctr::stream_cipher::{NewStreamCipher, SyncStreamCipher};
+use aes_ctr::Aes128Ctr;
+use bls::{Keypair, PublicKey, SecretKey};
+use hmac::Hmac;
+use rand::prelude::*;
+use sha2::{Digest, Sha256};
+use ssz::Encode;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// The byte-length of a BLS secret key, as it is stored in a keystore.
+pub const SECRET_KEY_LEN: usize = 32;
+/// The byte-length of a BLS secret key, as it is represented by the `bls` crate.
+pub const BLS_SECRET_KEY_LEN: usize = 48;
+/// The byte-length of the derived key, as required by the AES-128-CTR cipher and SHA-256 checksum.
+pub const DKLEN: u32 = 32;
+/// The byte-length of the AES-128-CTR initialization vector.
+pub const IV_SIZE: usize = 16;
+/// The byte-length of the KDF salt.
+pub const SALT_SIZE: usize = 32;
+/// The default scrypt cost parameter, as suggested by EIP-2335.
+pub const DEFAULT_SCRYPT_N: u32 = 262_144;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    InvalidSecretKeyLen { len: usize, expected: usize },
+    InvalidPassword,
+    InvalidSecretKeyBytes(String),
+    PublicKeyMismatch,
+    UnsupportedVersion(u32),
+    UnsupportedFunction(String),
+    InvalidDkLen(u32),
+    InvalidIvLen(usize),
+    InvalidScryptParams,
+    InvalidPbkdf2Params,
+    ReadError(String),
+    WriteError(String),
+    InvalidJson(String),
+}
+
+/// Returns the default key derivation function (scrypt), with a random salt.
+pub fn default_kdf() -> Kdf {
+    scrypt_kdf(DEFAULT_SCRYPT_N)
+}
+
+/// Returns a scrypt key derivation function with the CPU/memory cost `n` and a random salt.
+///
+/// Values of `n` below `DEFAULT_SCRYPT_N` make keystores faster to decrypt, but also faster to
+/// brute-force, so they should only be used for keys which hold no real value (e.g., on testnets).
+/// `n` must be a power of two greater than one.
+pub fn scrypt_kdf(n: u32) -> Kdf {
+    Kdf::Scrypt(Scrypt {
+        dklen: DKLEN,
+        n,
+        p: 1,
+        r: 8,
+        salt: rand::thread_rng().gen::<[u8; SALT_SIZE]>().to_vec().into(),
+    })
+}
+
+/// Returns the default cipher (AES-128-CTR), with a random initialization vector.
+pub fn default_cipher() -> Cipher {
+    Cipher {
+        iv: rand::thread_rng().gen::<[u8; IV_SIZE]>().to_vec().into(),
+    }
+}
+
+/// Constructs a `Keystore`.
+pub struct KeystoreBuilder<'a> {
+    keypair: &'a Keypair,
+    password: &'a [u8],
+    kdf: Kdf,
+    cipher: Cipher,
+    uuid: Uuid,
+    path: String,
+    description: Option<String>,
+}
+
+impl<'a> KeystoreBuilder<'a> {
+    /// Creates a new builder using the default (scrypt) KDF, a random IV and a random UUID.
+    pub fn new(keypair: &'a Keypair, password: &'a [u8], path: String) -> Self {
+        Self {
+            keypair,
+            password,
+            kdf: default_kdf(),
+            cipher: default_cipher(),
+            uuid: Uuid::new_v4(),
+            path,
+            description: None,
+        }
+    }
+
+    /// Build the keystore using the supplied `kdf` instead of the default.
+    pub fn kdf(mut self, kdf: Kdf) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// Build the keystore with the given `description`.
+    pub fn description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Consumes `self`, returning a `Keystore`.
+    pub fn build(self) -> Result<Keystore, Error> {
+        Keystore::encrypt(
+            self.keypair,
+            self.password,
+            self.kdf,
+            self.cipher,
+            self.uuid,
+            self.path,
+            self.description,
+        )
+    }
+}
+
+/// Provides a BLS keystore as defined in [EIP-2335](https://eips.ethereum.org/EIPS/eip-2335).
+///
+/// Use `KeystoreBuilder` to create a new keystore.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Keystore {
+    json: JsonKeystore,
+}
+
+impl Keystore {
+    /// Generate a keystore for the `keypair` given the provided parameters.
+    fn encrypt(
+        keypair: &Keypair,
+        password: &[u8],
+        kdf: Kdf,
+        cipher: Cipher,
+        uuid: Uuid,
+        path: String,
+        description: Option<String>,
+    ) -> Result<Self, Error> {
+        let secret = secret_key_to_bytes(&keypair.sk)?;
+
+        Ok(Keystore {
+            json: JsonKeystore {
+                crypto: encrypt(secret.as_bytes(), password, kdf, cipher)?,
+                description,
+                pubkey: hex::encode(keypair.pk.as_ssz_bytes()),
+                path,
+                uuid,
+                version: KEYSTORE_VERSION,
+            },
+        })
+    }
+
+    /// Regenerate a BLS `Keypair` from `self` and the supplied `password`.
+    ///
+    /// ## Errors
+    ///
+    /// - The `password` is incorrect.
+    /// - The keystore is badly formed.
+    pub fn decrypt_keypair(&self, password: &[u8]) -> Result<Keypair, Error> {
+        let plain_text = decrypt(password, &self.json.crypto)?;
+
+        if plain_text.len() != SECRET_KEY_LEN {
+            return Err(Error::InvalidSecretKeyLen {
+                len: plain_text.len(),
+                expected: SECRET_KEY_LEN,
+            });
+        }
+
+        let sk = secret_key_from_bytes(plain_text.as_bytes())?;
+        let pk = PublicKey::from_secret_key(&sk);
+
+        if hex::encode(pk.as_ssz_bytes()) != self.json.pubkey {
+            return Err(Error::PublicKeyMismatch);
+        }
+
+        Ok(Keypair { sk, pk })
+    }
+
+    /// Returns the UUID for the keystore.
+    pub fn uuid(&self) -> &Uuid {
+        &self.json.uuid
+    }
+
+    /// Returns the EIP-2334 derivation path for the keystore.
+    pub fn path(&self) -> &str {
+        &self.json.path
+    }
+
+    /// Returns the hex-encoded pubkey for the keystore (without a `0x` prefix).
+    pub fn pubkey(&self) -> &str {
+        &self.json.pubkey
+    }
+
+    /// Encodes `self` as a JSON object.
+    pub fn to_json_string(&self) -> Result<String, Error> {
+        serde_json::to_string(&self.json).map_err(|e| Error::InvalidJson(format!("{}", e)))
+    }
+
+    /// Returns `self` from an encoded JSON object.
+    pub fn from_json_str(json_string: &str) -> Result<Self, Error> {
+        let json: JsonKeystore =
+            serde_json::from_str(json_string).map_err(|e| Error::InvalidJson(format!("{}", e)))?;
+        Self::from_json(json)
+    }
+
+    /// Encodes `self` as a JSON object and writes it to `path`.
+    pub fn to_json_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        File::create(path)
+            .map_err(|e| Error::WriteError(format!("{}", e)))?
+            .write_all(self.to_json_string()?.as_bytes())
+            .map_err(|e| Error::WriteError(format!("{}", e)))
+    }
+
+    /// Instantiates `self` by reading a JSON object from the file at `path`.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut string = String::new();
+
+        File::open(path)
+            .map_err(|e| Error::ReadError(format!("{}", e)))?
+            .read_to_string(&mut string)
+            .map_err(|e| Error::ReadError(format!("{}", e)))?;
+
+        Self::from_json_str(&string)
+    }
+
+    fn from_json(json: JsonKeystore) -> Result<Self, Error> {
+        if json.version != KEYSTORE_VERSION {
+            return Err(Error::UnsupportedVersion(json.version));
+        }
+
+        Ok(Self { json })
+    }
+}
+
+/// Encrypts `plain_text` with `password`, returning the EIP-2335 `crypto` section.
+///
+/// This function is shared with EIP-2386 wallets, which use an identical `crypto` section.
+pub fn encrypt(
+    plain_text: &[u8],
+    password: &[u8],
+    kdf: Kdf,
+    cipher: Cipher,
+) -> Result<Crypto, Error> {
+    let derived_key = derive_key(password, &kdf)?;
+
+    if cipher.iv.as_bytes().len() != IV_SIZE {
+        return Err(Error::InvalidIvLen(cipher.iv.as_bytes().len()));
+    }
+
+    let mut cipher_text = plain_text.to_vec();
+    apply_aes_128_ctr(&derived_key, cipher.iv.as_bytes(), &mut cipher_text);
+
+    Ok(Crypto {
+        kdf: KdfModule {
+            function: kdf.function().to_string(),
+            params: kdf,
+            message: vec![].into(),
+        },
+        checksum: ChecksumModule {
+            function: SHA256_FUNCTION.to_string(),
+            params: EmptyParams::default(),
+            message: checksum(&derived_key, &cipher_text).into(),
+        },
+        cipher: CipherModule {
+            function: AES_128_CTR_FUNCTION.to_string(),
+            params: cipher,
+            message: cipher_text.into(),
+        },
+    })
+}
+
+/// Decrypts the `crypto` section with `password`, returning the plain-text secret.
+///
+/// ## Errors
+///
+/// - The `password` is incorrect (i.e., the checksum does not match).
+/// - The `crypto` section uses an unsupported function.
+pub fn decrypt(password: &[u8], crypto: &Crypto) -> Result<PlainText, Error> {
+    if crypto.kdf.function != crypto.kdf.params.function() {
+        return Err(Error::UnsupportedFunction(crypto.kdf.function.clone()));
+    }
+    if crypto.checksum.function != SHA256_FUNCTION {
+        return Err(Error::UnsupportedFunction(crypto.checksum.function.clone()));
+    }
+    if crypto.cipher.function != AES_128_CTR_FUNCTION {
+        return Err(Error::UnsupportedFunction(crypto.cipher.function.clone()));
+    }
+
+    let iv = crypto.cipher.params.iv.as_bytes();
+    if iv.len() != IV_SIZE {
+        return Err(Error::InvalidIvLen(iv.len()));
+    }
+
+    let cipher_text = crypto.cipher.message.as_bytes();
+    let derived_key = derive_key(password, &crypto.kdf.params)?;
+
+    if checksum(&derived_key, cipher_text) != crypto.checksum.message.as_bytes() {
+        return Err(Error::InvalidPassword);
+    }
+
+    let mut plain_text = PlainText::from(cipher_text.to_vec());
+    apply_aes_128_ctr(&derived_key, iv, plain_text.as_mut_bytes());
+
+    Ok(plain_text)
+}
+
+/// Derive a key from `password` using the given `kdf`.
+fn derive_key(password: &[u8], kdf: &Kdf) -> Result<PlainText, Error> {
+    let mut derived_key = PlainText::zero(DKLEN as usize);
+
+    match kdf {
+        Kdf::Pbkdf2(params) => {
+            if params.dklen != DKLEN {
+                return Err(Error::InvalidDkLen(params.dklen));
+            }
+            if params.prf != PBKDF2_PRF || params.c == 0 {
+                return Err(Error::InvalidPbkdf2Params);
+            }
+
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(
+                password,
+                params.salt.as_bytes(),
+                params.c as usize,
+                derived_key.as_mut_bytes(),
+            );
+        }
+        Kdf::Scrypt(params) => {
+            if params.dklen != DKLEN {
+                return Err(Error::InvalidDkLen(params.dklen));
+            }
+            if !params.n.is_power_of_two() || params.n < 2 {
+                return Err(Error::InvalidScryptParams);
+            }
+
+            let log_n = params.n.trailing_zeros() as u8;
+            let scrypt_params = scrypt::ScryptParams::new(log_n, params.r, params.p)
+                .map_err(|_| Error::InvalidScryptParams)?;
+
+            scrypt::scrypt(
+                password,
+                params.salt.as_bytes(),
+                &scrypt_params,
+                derived_key.as_mut_bytes(),
+            )
+            .map_err(|_| Error::InvalidScryptParams)?;
+        }
+    }
+
+    Ok(derived_key)
+}
+
+/// Returns `SHA256(derived_key[16..32] ++ cipher_text)`.
+fn checksum(derived_key: &PlainText, cipher_text: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(&derived_key.as_bytes()[16..32]);
+    hasher.input(cipher_text);
+    hasher.result().to_vec()
+}
+
+/// Applies the AES-128-CTR keystream to `data` in-place, using `derived_key[0..16]` as the key.
+fn apply_aes_128_ctr(derived_key: &PlainText, iv: &[u8], data: &mut [u8]) {
+    let mut cipher = Aes128Ctr::new(
+        GenericArray::from_slice(&derived_key.as_bytes()[0..16]),
+        GenericArray::from_slice(iv),
+    );
+    cipher.apply_keystream(data);
+}
+
+/// Returns the 32-byte representation of `sk`, as stored in a keystore.
+pub fn secret_key_to_bytes(sk: &SecretKey) -> Result<PlainText, Error> {
+    let bytes = PlainText::from(sk.as_ssz_bytes());
+
+    if bytes.len() != BLS_SECRET_KEY_LEN {
+        return Err(Error::InvalidSecretKeyLen {
+            len: bytes.len(),
+            expected: BLS_SECRET_KEY_LEN,
+        });
+    }
+
+    Ok(PlainText::from(
+        bytes.as_bytes()[BLS_SECRET_KEY_LEN - SECRET_KEY_LEN..].to_vec(),
+    ))
+}
+
+/// Instantiates a `SecretKey` from the 32-byte representation stored in a keystore.
+pub fn secret_key_from_bytes(bytes: &[u8]) -> Result<SecretKey, Error> {
+    if bytes.len() != SECRET_KEY_LEN {
+        return Err(Error::InvalidSecretKeyLen {
+            len: bytes.len(),
+            expected: SECRET_KEY_LEN,
+        });
+    }
+
+    let mut padded = PlainText::zero(BLS_SECRET_KEY_LEN);
+    padded.as_mut_bytes()[BLS_SECRET_KEY_LEN - SECRET_KEY_LEN..].copy_from_slice(bytes);
+
+    SecretKey::from_bytes(padded.as_bytes())
+        .map_err(|e| Error::InvalidSecretKeyBytes(format!("{:?}", e)))
+}
+
+impl From<Pbkdf2> for Kdf {
+    fn from(params: Pbkdf2) -> Self {
+        Kdf::Pbkdf2(params)
+    }
+}
+
+impl From<Scrypt> for Kdf {
+    fn from(params: Scrypt) -> Self {
+        Kdf::Scrypt(params)
+    }
+}
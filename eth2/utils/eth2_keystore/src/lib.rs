@@ -0,0 +1,17 @@
+//! Provides encryption and decryption of BLS secret keys, as per
+//! [EIP-2335](https://eips.ethereum.org/EIPS/eip-2335).
+//!
+//! The `crypto` module of a keystore is also used by EIP-2386 wallets, so the underlying
+//! `encrypt` and `decrypt` functions are exposed for use by the `eth2_wallet` crate.
+mod keystore;
+mod plain_text;
+
+pub mod json_keystore;
+
+pub use keystore::{
+    decrypt, default_cipher, default_kdf, encrypt, scrypt_kdf, secret_key_from_bytes,
+    secret_key_to_bytes, Error, Keystore, KeystoreBuilder, DEFAULT_SCRYPT_N, DKLEN, IV_SIZE,
+    SALT_SIZE,
+};
+pub use plain_text::PlainText;
+pub use uuid::Uuid;
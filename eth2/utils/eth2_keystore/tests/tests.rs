@@ -0,0 +1,106 @@
+#![cfg(test)]
+
+use bls::Keypair;
+use eth2_keystore::{
+    json_keystore::{Kdf, Pbkdf2, PBKDF2_PRF},
+    scrypt_kdf, Error, Keystore, KeystoreBuilder, DKLEN,
+};
+use tempdir::TempDir;
+
+const GOOD_PASSWORD: &[u8] = &[42, 42, 42];
+const BAD_PASSWORD: &[u8] = &[43, 43, 43];
+
+/// A cheap KDF, to avoid spending a long time running scrypt in tests.
+fn insecure_kdf() -> Kdf {
+    Kdf::Pbkdf2(Pbkdf2 {
+        dklen: DKLEN,
+        c: 2,
+        prf: PBKDF2_PRF.to_string(),
+        salt: vec![1; 32].into(),
+    })
+}
+
+#[test]
+fn string_round_trip() {
+    let keypair = Keypair::random();
+
+    let keystore = KeystoreBuilder::new(&keypair, GOOD_PASSWORD, "".into())
+        .kdf(insecure_kdf())
+        .build()
+        .unwrap();
+
+    let json = keystore.to_json_string().unwrap();
+    let decoded = Keystore::from_json_str(&json).unwrap();
+
+    assert_eq!(
+        decoded.decrypt_keypair(BAD_PASSWORD).err().unwrap(),
+        Error::InvalidPassword,
+        "should not decrypt with bad password"
+    );
+
+    assert_eq!(
+        decoded.decrypt_keypair(GOOD_PASSWORD).unwrap(),
+        keypair,
+        "should decrypt with good password"
+    );
+}
+
+#[test]
+fn file_round_trip() {
+    let keypair = Keypair::random();
+    let dir = TempDir::new("keystore").unwrap();
+    let path = dir.path().join("keystore.json");
+
+    let keystore = KeystoreBuilder::new(&keypair, GOOD_PASSWORD, "m/12381/3600/0/0/0".into())
+        .kdf(insecure_kdf())
+        .build()
+        .unwrap();
+
+    keystore.to_json_file(&path).unwrap();
+
+    let decoded = Keystore::from_json_file(&path).unwrap();
+
+    assert_eq!(decoded, keystore, "should decode identical keystore");
+    assert_eq!(decoded.path(), "m/12381/3600/0/0/0");
+    assert_eq!(decoded.decrypt_keypair(GOOD_PASSWORD).unwrap(), keypair);
+}
+
+#[test]
+fn unsupported_version() {
+    let keypair = Keypair::random();
+
+    let keystore = KeystoreBuilder::new(&keypair, GOOD_PASSWORD, "".into())
+        .kdf(insecure_kdf())
+        .build()
+        .unwrap();
+
+    let json = keystore
+        .to_json_string()
+        .unwrap()
+        .replace("\"version\":4", "\"version\":3");
+
+    assert_eq!(
+        Keystore::from_json_str(&json),
+        Err(Error::UnsupportedVersion(3))
+    );
+}
+
+#[test]
+fn reduced_cost_scrypt() {
+    let keypair = Keypair::random();
+
+    let keystore = KeystoreBuilder::new(&keypair, GOOD_PASSWORD, "".into())
+        .kdf(scrypt_kdf(16))
+        .build()
+        .unwrap();
+
+    assert_eq!(keystore.decrypt_keypair(GOOD_PASSWORD).unwrap(), keypair);
+
+    assert_eq!(
+        KeystoreBuilder::new(&keypair, GOOD_PASSWORD, "".into())
+            .kdf(scrypt_kdf(15))
+            .build(),
+        Err(Error::InvalidScryptParams),
+        "n must be a power of two"
+    );
+}
@@ -8,23 +8,29 @@
 //! https://github.com/sigp/lighthouse/pull/605
 
 use eth2_libp2p::Enr;
+use serde_derive::{Deserialize, Serialize};
 use ssz::{Decode, Encode};
 use std::fs::{create_dir_all, File};
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use types::{Address, BeaconState, EthSpec, YamlConfig};
+use std::path::{Path, PathBuf};
+use types::{Address, BeaconState, ChainSpec, EthSpec, EthSpecId, YamlConfig};
 
 pub const ADDRESS_FILE: &str = "deposit_contract.txt";
 pub const DEPLOY_BLOCK_FILE: &str = "deploy_block.txt";
 pub const BOOT_ENR_FILE: &str = "boot_enr.yaml";
 pub const GENESIS_STATE_FILE: &str = "genesis.ssz";
 pub const YAML_CONFIG_FILE: &str = "config.yaml";
+pub const DEPOSIT_CHAIN_ID_FILE: &str = "deposit_chain_id.txt";
+pub const DEPOSIT_NETWORK_ID_FILE: &str = "deposit_network_id.txt";
 
 pub const HARDCODED_YAML_CONFIG: &[u8] = include_bytes!("../testnet/config.yaml");
 pub const HARDCODED_DEPLOY_BLOCK: &[u8] = include_bytes!("../testnet/deploy_block.txt");
 pub const HARDCODED_DEPOSIT_CONTRACT: &[u8] = include_bytes!("../testnet/deposit_contract.txt");
 pub const HARDCODED_GENESIS_STATE: &[u8] = include_bytes!("../testnet/genesis.ssz");
 pub const HARDCODED_BOOT_ENR: &[u8] = include_bytes!("../testnet/boot_enr.yaml");
+pub const HARDCODED_DEPOSIT_CHAIN_ID: &[u8] = include_bytes!("../testnet/deposit_chain_id.txt");
+pub const HARDCODED_DEPOSIT_NETWORK_ID: &[u8] =
+    include_bytes!("../testnet/deposit_network_id.txt");
 
 /// Specifies an Eth2 testnet.
 ///
@@ -36,6 +42,10 @@ pub struct Eth2TestnetConfig<E: EthSpec> {
     pub boot_enr: Option<Vec<Enr>>,
     pub genesis_state: Option<BeaconState<E>>,
     pub yaml_config: Option<YamlConfig>,
+    /// The chain ID of the eth1 network on which the deposit contract is deployed, if known.
+    pub deposit_chain_id: Option<u64>,
+    /// The network ID of the eth1 network on which the deposit contract is deployed, if known.
+    pub deposit_network_id: Option<u64>,
 }
 
 impl<E: EthSpec> Eth2TestnetConfig<E> {
@@ -61,6 +71,14 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
                 serde_yaml::from_reader(HARDCODED_YAML_CONFIG)
                     .map_err(|e| format!("Unable to parse genesis state: {:?}", e))?,
             ),
+            deposit_chain_id: Some(
+                serde_yaml::from_reader(HARDCODED_DEPOSIT_CHAIN_ID)
+                    .map_err(|e| format!("Unable to parse deposit chain id: {:?}", e))?,
+            ),
+            deposit_network_id: Some(
+                serde_yaml::from_reader(HARDCODED_DEPOSIT_NETWORK_ID)
+                    .map_err(|e| format!("Unable to parse deposit network id: {:?}", e))?,
+            ),
         })
     }
 
@@ -113,6 +131,14 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
             write_to_yaml_file!(YAML_CONFIG_FILE, yaml_config);
         }
 
+        if let Some(deposit_chain_id) = self.deposit_chain_id {
+            write_to_yaml_file!(DEPOSIT_CHAIN_ID_FILE, deposit_chain_id);
+        }
+
+        if let Some(deposit_network_id) = self.deposit_network_id {
+            write_to_yaml_file!(DEPOSIT_NETWORK_ID_FILE, deposit_network_id);
+        }
+
         // The genesis state is a special case because it uses SSZ, not YAML.
         if let Some(genesis_state) = &self.genesis_state {
             let file = base_dir.join(GENESIS_STATE_FILE);
@@ -129,6 +155,19 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
     }
 
     pub fn load(base_dir: PathBuf) -> Result<Self, String> {
+        if !base_dir.is_dir() {
+            return Err(format!("Testnet directory {:?} does not exist", base_dir));
+        }
+
+        for file in &[ADDRESS_FILE, DEPLOY_BLOCK_FILE] {
+            if !base_dir.join(file).exists() {
+                return Err(format!(
+                    "Testnet directory {:?} is missing the required {} file",
+                    base_dir, file
+                ));
+            }
+        }
+
         macro_rules! load_from_file {
             ($file: ident) => {
                 File::open(base_dir.join($file))
@@ -154,6 +193,8 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
         let deposit_contract_deploy_block = load_from_file!(DEPLOY_BLOCK_FILE);
         let boot_enr = optional_load_from_file!(BOOT_ENR_FILE);
         let yaml_config = optional_load_from_file!(YAML_CONFIG_FILE);
+        let deposit_chain_id = optional_load_from_file!(DEPOSIT_CHAIN_ID_FILE);
+        let deposit_network_id = optional_load_from_file!(DEPOSIT_NETWORK_ID_FILE);
 
         // The genesis state is a special case because it uses SSZ, not YAML.
         let file = base_dir.join(GENESIS_STATE_FILE);
@@ -180,9 +221,46 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
             boot_enr,
             genesis_state,
             yaml_config,
+            deposit_chain_id,
+            deposit_network_id,
         })
     }
 
+    /// Returns `base_spec` with the values from the `config.yaml` of this testnet applied.
+    ///
+    /// Returns an error if there is no `config.yaml`, or if it or the genesis state are
+    /// inconsistent with the compiled `EthSpec`.
+    pub fn chain_spec(&self, base_spec: &ChainSpec) -> Result<ChainSpec, String> {
+        let yaml_config = self
+            .yaml_config
+            .as_ref()
+            .ok_or_else(|| format!("The testnet directory must contain a {}", YAML_CONFIG_FILE))?;
+
+        let mismatches = yaml_config.eth_spec_mismatches::<E>();
+        if !mismatches.is_empty() {
+            return Err(format!(
+                "The {} is not compatible with the compiled spec, these constants differ: {}",
+                YAML_CONFIG_FILE,
+                mismatches.join(", ")
+            ));
+        }
+
+        let spec = yaml_config
+            .apply_to_chain_spec::<E>(base_spec)
+            .ok_or_else(|| format!("Unable to apply {}", YAML_CONFIG_FILE))?;
+
+        if let Some(genesis_state) = &self.genesis_state {
+            if genesis_state.slot != spec.genesis_slot {
+                return Err(format!(
+                    "The {} is not a genesis state, it has slot {} (expected {})",
+                    GENESIS_STATE_FILE, genesis_state.slot, spec.genesis_slot
+                ));
+            }
+        }
+
+        Ok(spec)
+    }
+
     pub fn deposit_contract_address(&self) -> Result<Address, String> {
         if self.deposit_contract_address.starts_with("0x") {
             self.deposit_contract_address[2..]
@@ -194,11 +272,95 @@ impl<E: EthSpec> Eth2TestnetConfig<E> {
     }
 }
 
+/// A testnet as served by the `/lighthouse/testnet` endpoint of the HTTP API, so that new nodes may
+/// join it from any of its existing nodes.
+///
+/// Unlike `Eth2TestnetConfig`, the genesis state and `config.yaml` are required.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestnetBundle {
+    pub deposit_contract_address: String,
+    pub deposit_contract_deploy_block: u64,
+    pub boot_enr: Vec<Enr>,
+    /// The SSZ encoding of the genesis state.
+    #[serde(with = "serde_utils::hex_bytes")]
+    pub genesis_state: Vec<u8>,
+    pub yaml_config: YamlConfig,
+    pub deposit_chain_id: Option<u64>,
+    pub deposit_network_id: Option<u64>,
+}
+
+impl TestnetBundle {
+    /// Returns an error if `testnet` has no genesis state or `config.yaml`.
+    pub fn from_testnet_config<E: EthSpec>(testnet: &Eth2TestnetConfig<E>) -> Result<Self, String> {
+        Ok(Self {
+            deposit_contract_address: testnet.deposit_contract_address.clone(),
+            deposit_contract_deploy_block: testnet.deposit_contract_deploy_block,
+            boot_enr: testnet.boot_enr.clone().unwrap_or_default(),
+            genesis_state: testnet
+                .genesis_state
+                .as_ref()
+                .ok_or_else(|| "A testnet bundle requires a genesis state".to_string())?
+                .as_ssz_bytes(),
+            yaml_config: testnet
+                .yaml_config
+                .clone()
+                .ok_or_else(|| format!("A testnet bundle requires a {}", YAML_CONFIG_FILE))?,
+            deposit_chain_id: testnet.deposit_chain_id,
+            deposit_network_id: testnet.deposit_network_id,
+        })
+    }
+
+    /// Decodes the genesis state, returning the testnet in the form in which it is written to a
+    /// testnet directory.
+    pub fn into_testnet_config<E: EthSpec>(self) -> Result<Eth2TestnetConfig<E>, String> {
+        Ok(Eth2TestnetConfig {
+            deposit_contract_address: self.deposit_contract_address,
+            deposit_contract_deploy_block: self.deposit_contract_deploy_block,
+            boot_enr: Some(self.boot_enr),
+            genesis_state: Some(
+                BeaconState::from_ssz_bytes(&self.genesis_state)
+                    .map_err(|e| format!("Unable to SSZ decode genesis state: {:?}", e))?,
+            ),
+            yaml_config: Some(self.yaml_config),
+            deposit_chain_id: self.deposit_chain_id,
+            deposit_network_id: self.deposit_network_id,
+        })
+    }
+}
+
+/// Returns the `EthSpec` of the testnet in `testnet_dir` (or of the hard-coded testnet, if
+/// `testnet_dir` is `None`) as determined by its `config.yaml`, without decoding its genesis state.
+///
+/// Returns `None` if there is no `config.yaml` or it does not match any compiled `EthSpec`.
+pub fn eth_spec_id(testnet_dir: Option<&Path>) -> Result<Option<EthSpecId>, String> {
+    let yaml_config: Option<YamlConfig> = match testnet_dir {
+        Some(testnet_dir) => {
+            let path = testnet_dir.join(YAML_CONFIG_FILE);
+            if path.exists() {
+                let file =
+                    File::open(&path).map_err(|e| format!("Unable to open {:?}: {:?}", path, e))?;
+                Some(
+                    serde_yaml::from_reader(file)
+                        .map_err(|e| format!("Unable to parse {:?}: {:?}", path, e))?,
+                )
+            } else {
+                None
+            }
+        }
+        None => Some(
+            serde_yaml::from_reader(HARDCODED_YAML_CONFIG)
+                .map_err(|e| format!("Unable to parse {}: {:?}", YAML_CONFIG_FILE, e))?,
+        ),
+    };
+
+    Ok(yaml_config.and_then(|yaml_config| yaml_config.eth_spec_id()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempdir::TempDir;
-    use types::{Eth1Data, Hash256, MainnetEthSpec, YamlConfig};
+    use types::{Eth1Data, Hash256, MainnetEthSpec, MinimalEthSpec, YamlConfig};
 
     type E = MainnetEthSpec;
 
@@ -210,6 +372,34 @@ mod tests {
         assert!(dir.boot_enr.is_some());
         assert!(dir.genesis_state.is_some());
         assert!(dir.yaml_config.is_some());
+        assert_eq!(dir.deposit_chain_id, Some(5), "should be goerli");
+        assert_eq!(dir.deposit_network_id, Some(5), "should be goerli");
+    }
+
+    #[test]
+    fn detects_eth_spec_id() {
+        assert_eq!(eth_spec_id(None), Ok(Some(EthSpecId::Mainnet)));
+
+        let temp_dir = TempDir::new("eth2_testnet_test").expect("should create temp dir");
+        let base_dir = temp_dir.path().join("my_testnet");
+        assert_eq!(eth_spec_id(Some(&base_dir)), Ok(None));
+
+        let testnet: Eth2TestnetConfig<MinimalEthSpec> = Eth2TestnetConfig {
+            deposit_contract_address: "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string(),
+            deposit_contract_deploy_block: 0,
+            boot_enr: None,
+            genesis_state: None,
+            yaml_config: Some(YamlConfig::from_spec::<MinimalEthSpec>(
+                &MinimalEthSpec::default_spec(),
+            )),
+            deposit_chain_id: None,
+            deposit_network_id: None,
+        };
+        testnet
+            .write_to_file(base_dir.clone())
+            .expect("should write to file");
+
+        assert_eq!(eth_spec_id(Some(&base_dir)), Ok(Some(EthSpecId::Minimal)));
     }
 
     #[test]
@@ -227,14 +417,77 @@ mod tests {
         let genesis_state = Some(BeaconState::new(42, eth1_data, spec));
         let yaml_config = Some(YamlConfig::from_spec::<E>(spec));
 
-        do_test::<E>(boot_enr, genesis_state.clone(), yaml_config.clone());
-        do_test::<E>(None, None, None);
+        do_test::<E>(boot_enr, genesis_state.clone(), yaml_config.clone(), Some(5));
+        do_test::<E>(None, None, None, None);
+    }
+
+    #[test]
+    fn bundle_round_trip() {
+        let testnet: Eth2TestnetConfig<E> =
+            Eth2TestnetConfig::hard_coded().expect("should decode hard_coded params");
+
+        let bundle = TestnetBundle::from_testnet_config(&testnet).expect("should bundle");
+        let json = serde_json::to_string(&bundle).expect("should encode bundle");
+        let decoded: TestnetBundle = serde_json::from_str(&json).expect("should decode bundle");
+
+        assert_eq!(
+            decoded.into_testnet_config::<E>(),
+            Ok(testnet.clone()),
+            "should decode as encoded"
+        );
+
+        let mut incomplete = testnet;
+        incomplete.genesis_state = None;
+        assert!(TestnetBundle::from_testnet_config(&incomplete).is_err());
+    }
+
+    #[test]
+    fn load_missing_files() {
+        let temp_dir = TempDir::new("eth2_testnet_test").expect("should create temp dir");
+
+        let missing_dir = temp_dir.path().join("missing");
+        let err = Eth2TestnetConfig::<E>::load(missing_dir).expect_err("should not load");
+        assert!(err.contains("does not exist"), "{}", err);
+
+        let err = Eth2TestnetConfig::<E>::load(temp_dir.path().to_path_buf())
+            .expect_err("should not load");
+        assert!(err.contains(ADDRESS_FILE), "{}", err);
+    }
+
+    #[test]
+    fn chain_spec_consistency() {
+        let spec = E::default_spec();
+        let mut testnet: Eth2TestnetConfig<E> = Eth2TestnetConfig {
+            deposit_contract_address: "0xBB9bc244D798123fDe783fCc1C72d3Bb8C189413".to_string(),
+            deposit_contract_deploy_block: 0,
+            boot_enr: None,
+            genesis_state: None,
+            yaml_config: None,
+            deposit_chain_id: None,
+            deposit_network_id: None,
+        };
+        assert!(
+            testnet.chain_spec(&spec).is_err(),
+            "should require a config"
+        );
+
+        testnet.yaml_config = Some(YamlConfig::from_spec::<MinimalEthSpec>(
+            &MinimalEthSpec::default_spec(),
+        ));
+        let err = testnet
+            .chain_spec(&spec)
+            .expect_err("should detect minimal config");
+        assert!(err.contains("SLOTS_PER_EPOCH"), "{}", err);
+
+        testnet.yaml_config = Some(YamlConfig::from_spec::<E>(&spec));
+        assert_eq!(testnet.chain_spec(&spec), Ok(spec));
     }
 
     fn do_test<E: EthSpec>(
         boot_enr: Option<Vec<Enr>>,
         genesis_state: Option<BeaconState<E>>,
         yaml_config: Option<YamlConfig>,
+        deposit_chain_id: Option<u64>,
     ) {
         let temp_dir = TempDir::new("eth2_testnet_test").expect("should create temp dir");
         let base_dir = PathBuf::from(temp_dir.path().join("my_testnet"));
@@ -247,6 +500,8 @@ mod tests {
             boot_enr,
             genesis_state,
             yaml_config,
+            deposit_chain_id,
+            deposit_network_id: deposit_chain_id,
         };
 
         testnet
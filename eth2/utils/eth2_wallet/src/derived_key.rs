@@ -0,0 +1,181 @@
+//! Provides BLS secret key derivation, as per [EIP-2333](https://eips.ethereum.org/EIPS/eip-2333).
+use crate::Error;
+use eth2_keystore::PlainText;
+use hkdf::Hkdf;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+lazy_static! {
+    static ref CURVE_ORDER: BigUint =
+        "52435875175126190479447740508185965837690552500527637822603658699938581184513"
+            .parse::<BigUint>()
+            .expect("Curve order should be valid");
+}
+
+/// The byte size of a SHA256 hash.
+pub const HASH_SIZE: usize = 32;
+/// The size of the lamport array.
+pub const LAMPORT_ARRAY_SIZE: usize = 255;
+/// The `L` value used in the `hdkf_mod_r` function.
+pub const MOD_R_L: usize = 48;
+/// The minimum byte-length of a seed, as required by EIP-2333.
+pub const MIN_SEED_LEN: usize = 32;
+/// The initial salt used in the `hkdf_mod_r` function.
+const SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+
+/// A BLS secret key that is derived from some `seed`, or from some parent secret key.
+///
+/// The key is stored as a 32-byte big-endian integer and is zeroized on drop.
+pub struct DerivedKey(PlainText);
+
+impl DerivedKey {
+    /// Instantiates `Self` from some seed of any length.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Err(())` if the seed is less than 32 bytes in length.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < MIN_SEED_LEN {
+            Err(Error::InvalidSeedLength(seed.len()))
+        } else {
+            Ok(Self(derive_master_sk(seed)))
+        }
+    }
+
+    /// Derives a child key from the secret `Self` at some `index`.
+    pub fn child(&self, index: u32) -> DerivedKey {
+        Self(derive_child_sk(self.0.as_bytes(), index))
+    }
+
+    /// Returns the secret BLS key in `self`, as a 32-byte big-endian integer.
+    pub fn secret(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+/// Derives the "master" BLS secret key from some `seed` bytes.
+///
+/// Equivalent to `derive_master_SK` in EIP-2333.
+fn derive_master_sk(seed: &[u8]) -> PlainText {
+    hkdf_mod_r(seed)
+}
+
+/// From the given `parent_sk`, derives a child key at index`.
+///
+/// Equivalent to `derive_child_SK` in EIP-2333.
+fn derive_child_sk(parent_sk: &[u8], index: u32) -> PlainText {
+    let compressed_lamport_pk = parent_sk_to_lamport_pk(parent_sk, index);
+    hkdf_mod_r(compressed_lamport_pk.as_bytes())
+}
+
+/// From the `ikm` (initial key material), performs a HKDF-Extract and HKDF-Expand to generate a
+/// BLS private key within the order of the BLS-381 curve.
+///
+/// Equivalent to `HKDF_mod_r` in EIP-2333.
+fn hkdf_mod_r(ikm: &[u8]) -> PlainText {
+    let mut ikm_with_postfix = PlainText::zero(ikm.len() + 1);
+    ikm_with_postfix.as_mut_bytes()[..ikm.len()].copy_from_slice(ikm);
+
+    let info = (MOD_R_L as u16).to_be_bytes();
+    let mut salt = SALT.to_vec();
+
+    loop {
+        salt = Sha256::digest(&salt).to_vec();
+
+        let mut okm = PlainText::zero(MOD_R_L);
+        Hkdf::<Sha256>::new(Some(&salt), ikm_with_postfix.as_bytes())
+            .expand(&info, okm.as_mut_bytes())
+            .expect("OKM length is less than the maximum HKDF output length");
+
+        let sk = BigUint::from_bytes_be(okm.as_bytes()) % &*CURVE_ORDER;
+
+        if sk != BigUint::from(0_u8) {
+            return mod_r_to_bytes(sk);
+        }
+    }
+}
+
+/// Encodes `sk` as a 32-byte big-endian integer.
+fn mod_r_to_bytes(sk: BigUint) -> PlainText {
+    let bytes = PlainText::from(sk.to_bytes_be());
+
+    let mut output = PlainText::zero(HASH_SIZE);
+    output.as_mut_bytes()[HASH_SIZE - bytes.len()..].copy_from_slice(bytes.as_bytes());
+    output
+}
+
+/// Generates a Lamport public key from the given `ikm` (which is assumed to be a BLS secret key).
+///
+/// Equivalent to `parent_SK_to_lamport_PK` in EIP-2333.
+fn parent_sk_to_lamport_pk(ikm: &[u8], index: u32) -> PlainText {
+    let salt = index.to_be_bytes();
+    let not_ikm = PlainText::from(ikm.iter().map(|byte| !byte).collect::<Vec<u8>>());
+
+    let lamports = [
+        ikm_to_lamport_sk(&salt, ikm),
+        ikm_to_lamport_sk(&salt, not_ikm.as_bytes()),
+    ];
+
+    let mut lamport_pk = PlainText::zero(HASH_SIZE * LAMPORT_ARRAY_SIZE * 2);
+
+    lamports
+        .iter()
+        .flat_map(|lamport| lamport.as_bytes().chunks(HASH_SIZE))
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let hash = Sha256::digest(chunk);
+            lamport_pk.as_mut_bytes()[i * HASH_SIZE..(i + 1) * HASH_SIZE].copy_from_slice(&hash);
+        });
+
+    PlainText::from(Sha256::digest(lamport_pk.as_bytes()).to_vec())
+}
+
+/// Generates a Lamport secret key from the `ikm` (initial key material).
+///
+/// Equivalent to `IKM_to_lamport_SK` in EIP-2333.
+fn ikm_to_lamport_sk(salt: &[u8], ikm: &[u8]) -> PlainText {
+    let mut okm = PlainText::zero(HASH_SIZE * LAMPORT_ARRAY_SIZE);
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(&[], okm.as_mut_bytes())
+        .expect("OKM length is equal to the maximum HKDF output length");
+    okm
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test vector 0 from EIP-2333.
+    #[test]
+    fn eip2333_test_case_0() {
+        let seed = hex::decode(
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+             1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        )
+        .unwrap();
+
+        let master = DerivedKey::from_seed(&seed).unwrap();
+        let child = master.child(0);
+
+        assert_eq!(
+            BigUint::from_bytes_be(master.secret()),
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096"
+                .parse::<BigUint>()
+                .unwrap()
+        );
+        assert_eq!(
+            BigUint::from_bytes_be(child.secret()),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+                .parse::<BigUint>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn short_seed() {
+        assert_eq!(
+            DerivedKey::from_seed(&[42; MIN_SEED_LEN - 1]).err(),
+            Some(Error::InvalidSeedLength(MIN_SEED_LEN - 1))
+        );
+    }
+}
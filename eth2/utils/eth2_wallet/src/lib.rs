@@ -0,0 +1,42 @@
+//! Provides hierarchical deterministic wallets for Eth2 validators.
+//!
+//! - [EIP-2386](https://eips.ethereum.org/EIPS/eip-2386): the JSON wallet format.
+//! - [EIP-2333](https://eips.ethereum.org/EIPS/eip-2333): BLS key derivation.
+//! - [EIP-2334](https://eips.ethereum.org/EIPS/eip-2334): validator key paths.
+//!
+//! Wallet seeds are generated from [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+//! mnemonics.
+#[macro_use]
+extern crate lazy_static;
+
+mod derived_key;
+mod validator_path;
+mod wallet;
+
+pub use bip39;
+pub use derived_key::DerivedKey;
+pub use eth2_keystore::{scrypt_kdf, Keystore, PlainText, Uuid, DEFAULT_SCRYPT_N};
+pub use validator_path::{KeyType, ValidatorPath, COIN_TYPE, PURPOSE};
+pub use wallet::{
+    keypair_from_seed, JsonWallet, ValidatorKeystores, Wallet, WalletBuilder, WALLET_TYPE,
+    WALLET_VERSION,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    KeystoreError(eth2_keystore::Error),
+    InvalidSeedLength(usize),
+    EmptyPassword,
+    PathExhausted,
+    UnsupportedVersion(u32),
+    UnsupportedType(String),
+    ReadError(String),
+    WriteError(String),
+    InvalidJson(String),
+}
+
+impl From<eth2_keystore::Error> for Error {
+    fn from(e: eth2_keystore::Error) -> Error {
+        Error::KeystoreError(e)
+    }
+}
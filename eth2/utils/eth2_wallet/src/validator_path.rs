@@ -0,0 +1,65 @@
+//! Provides the validator key derivation paths defined in
+//! [EIP-2334](https://eips.ethereum.org/EIPS/eip-2334).
+use std::fmt;
+
+pub const PURPOSE: u32 = 12381;
+pub const COIN_TYPE: u32 = 3600;
+
+/// The type of validator key to derive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyType {
+    Voting,
+    Withdrawal,
+}
+
+/// A derivation path for some validator key, e.g., `m/12381/3600/0/0/0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorPath(Vec<u32>);
+
+impl ValidatorPath {
+    /// Returns the path for the key of `key_type` for the validator at `index` in the wallet.
+    pub fn new(index: u32, key_type: KeyType) -> Self {
+        let mut vec = vec![PURPOSE, COIN_TYPE, index, 0];
+
+        match key_type {
+            KeyType::Voting => vec.push(0),
+            KeyType::Withdrawal => {}
+        }
+
+        Self(vec)
+    }
+
+    /// Returns each node (i.e., child index) in the path, starting at the master node.
+    pub fn iter_nodes(&self) -> impl Iterator<Item = &u32> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for ValidatorPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+
+        for node in self.iter_nodes() {
+            write!(f, "/{}", node)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            format!("{}", ValidatorPath::new(0, KeyType::Voting)),
+            "m/12381/3600/0/0/0"
+        );
+        assert_eq!(
+            format!("{}", ValidatorPath::new(42, KeyType::Withdrawal)),
+            "m/12381/3600/42/0"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    derived_key::DerivedKey,
+    validator_path::{KeyType, ValidatorPath},
+    Error,
+};
+use bip39::{Mnemonic, Seed as Bip39Seed};
+use bls::{Keypair, PublicKey};
+use eth2_keystore::{
+    decrypt, default_cipher, default_kdf, encrypt,
+    json_keystore::{Cipher, Crypto, Kdf},
+    secret_key_from_bytes, Keystore, KeystoreBuilder, PlainText, Uuid,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The only wallet version supported by this implementation.
+pub const WALLET_VERSION: u32 = 1;
+/// The only wallet type supported by this implementation.
+pub const WALLET_TYPE: &str = "hierarchical deterministic";
+
+/// A wallet, as it is serialized to JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonWallet {
+    pub crypto: Crypto,
+    pub name: String,
+    pub nextaccount: u32,
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub uuid: Uuid,
+    pub version: u32,
+}
+
+/// The keystores for a single validator, derived from a `Wallet`.
+pub struct ValidatorKeystores {
+    /// The wallet index used to derive the keystores.
+    pub index: u32,
+    pub voting: Keystore,
+    pub withdrawal: Keystore,
+}
+
+/// Constructs a `Wallet`.
+pub struct WalletBuilder<'a> {
+    seed: PlainText,
+    password: &'a [u8],
+    kdf: Kdf,
+    cipher: Cipher,
+    uuid: Uuid,
+    name: String,
+    nextaccount: u32,
+}
+
+impl<'a> WalletBuilder<'a> {
+    /// Creates a new builder for a wallet encrypting the seed derived from `mnemonic`.
+    pub fn from_mnemonic(
+        mnemonic: &Mnemonic,
+        password: &'a [u8],
+        name: String,
+    ) -> Result<Self, Error> {
+        let seed = Bip39Seed::new(mnemonic, "");
+        Self::from_seed_bytes(seed.as_bytes(), password, name)
+    }
+
+    /// Creates a new builder for a wallet encrypting `seed`.
+    pub fn from_seed_bytes(seed: &[u8], password: &'a [u8], name: String) -> Result<Self, Error> {
+        if password.is_empty() {
+            return Err(Error::EmptyPassword);
+        }
+
+        Ok(Self {
+            seed: PlainText::from(seed.to_vec()),
+            password,
+            kdf: default_kdf(),
+            cipher: default_cipher(),
+            uuid: Uuid::new_v4(),
+            name,
+            nextaccount: 0,
+        })
+    }
+
+    /// Build the wallet using the supplied `kdf` instead of the default.
+    pub fn kdf(mut self, kdf: Kdf) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    /// Consumes `self`, returning a `Wallet`.
+    pub fn build(self) -> Result<Wallet, Error> {
+        // Ensure the seed is suitable for key derivation before storing it.
+        DerivedKey::from_seed(self.seed.as_bytes())?;
+
+        Ok(Wallet {
+            json: JsonWallet {
+                crypto: encrypt(self.seed.as_bytes(), self.password, self.kdf, self.cipher)?,
+                name: self.name,
+                nextaccount: self.nextaccount,
+                type_field: WALLET_TYPE.to_string(),
+                uuid: self.uuid,
+                version: WALLET_VERSION,
+            },
+        })
+    }
+}
+
+/// Provides a hierarchical deterministic wallet, as defined in
+/// [EIP-2386](https://eips.ethereum.org/EIPS/eip-2386).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Wallet {
+    json: JsonWallet,
+}
+
+impl Wallet {
+    /// Produces the voting and withdrawal keystores for the next validator in the wallet,
+    /// incrementing `self.nextaccount`.
+    ///
+    /// The keystores are encrypted with the `voting_password` and `withdrawal_password`,
+    /// respectively.
+    ///
+    /// ## Notes
+    ///
+    /// The caller must persist `self` after calling this function, otherwise the same keys may be
+    /// derived again in the future.
+    pub fn next_validator(
+        &mut self,
+        wallet_password: &[u8],
+        voting_password: &[u8],
+        withdrawal_password: &[u8],
+    ) -> Result<ValidatorKeystores, Error> {
+        self.next_validator_with_kdf(
+            wallet_password,
+            voting_password,
+            withdrawal_password,
+            default_kdf,
+        )
+    }
+
+    /// As per `Self::next_validator`, but the keystores are encrypted using the key derivation
+    /// functions returned by `kdf` (e.g., `eth2_keystore::scrypt_kdf` with a reduced cost).
+    pub fn next_validator_with_kdf<F: Fn() -> Kdf>(
+        &mut self,
+        wallet_password: &[u8],
+        voting_password: &[u8],
+        withdrawal_password: &[u8],
+        kdf: F,
+    ) -> Result<ValidatorKeystores, Error> {
+        let index = self.json.nextaccount;
+        let seed = self.decrypt_seed(wallet_password)?;
+
+        let keystore = |key_type: KeyType, password: &[u8]| -> Result<Keystore, Error> {
+            let path = ValidatorPath::new(index, key_type);
+            let keypair = keypair_from_seed(seed.as_bytes(), &path)?;
+
+            KeystoreBuilder::new(&keypair, password, format!("{}", path))
+                .kdf(kdf())
+                .build()
+                .map_err(Into::into)
+        };
+
+        let keystores = ValidatorKeystores {
+            index,
+            voting: keystore(KeyType::Voting, voting_password)?,
+            withdrawal: keystore(KeyType::Withdrawal, withdrawal_password)?,
+        };
+
+        self.json.nextaccount = index.checked_add(1).ok_or_else(|| Error::PathExhausted)?;
+
+        Ok(keystores)
+    }
+
+    /// Returns the seed encrypted in `self`.
+    pub fn decrypt_seed(&self, password: &[u8]) -> Result<PlainText, Error> {
+        decrypt(password, &self.json.crypto).map_err(Into::into)
+    }
+
+    /// Returns the name of the wallet.
+    pub fn name(&self) -> &str {
+        &self.json.name
+    }
+
+    /// Returns the UUID of the wallet.
+    pub fn uuid(&self) -> &Uuid {
+        &self.json.uuid
+    }
+
+    /// Returns the index of the next validator to be derived from the wallet.
+    pub fn nextaccount(&self) -> u32 {
+        self.json.nextaccount
+    }
+
+    /// Encodes `self` as a JSON object.
+    pub fn to_json_string(&self) -> Result<String, Error> {
+        serde_json::to_string(&self.json).map_err(|e| Error::InvalidJson(format!("{}", e)))
+    }
+
+    /// Returns `self` from an encoded JSON object.
+    pub fn from_json_str(json_string: &str) -> Result<Self, Error> {
+        let json: JsonWallet =
+            serde_json::from_str(json_string).map_err(|e| Error::InvalidJson(format!("{}", e)))?;
+
+        if json.version != WALLET_VERSION {
+            return Err(Error::UnsupportedVersion(json.version));
+        }
+
+        if json.type_field != WALLET_TYPE {
+            return Err(Error::UnsupportedType(json.type_field));
+        }
+
+        Ok(Self { json })
+    }
+
+    /// Encodes `self` as a JSON object and writes it to `path`.
+    pub fn to_json_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        File::create(path)
+            .map_err(|e| Error::WriteError(format!("{}", e)))?
+            .write_all(self.to_json_string()?.as_bytes())
+            .map_err(|e| Error::WriteError(format!("{}", e)))
+    }
+
+    /// Instantiates `self` by reading a JSON object from the file at `path`.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut string = String::new();
+
+        File::open(path)
+            .map_err(|e| Error::ReadError(format!("{}", e)))?
+            .read_to_string(&mut string)
+            .map_err(|e| Error::ReadError(format!("{}", e)))?;
+
+        Self::from_json_str(&string)
+    }
+}
+
+/// Derives the `Keypair` at `path` from the given wallet `seed`.
+pub fn keypair_from_seed(seed: &[u8], path: &ValidatorPath) -> Result<Keypair, Error> {
+    let master = DerivedKey::from_seed(seed)?;
+    let derived = path
+        .iter_nodes()
+        .fold(master, |parent, index| parent.child(*index));
+
+    let sk = secret_key_from_bytes(derived.secret())?;
+    let pk = PublicKey::from_secret_key(&sk);
+
+    Ok(Keypair { sk, pk })
+}
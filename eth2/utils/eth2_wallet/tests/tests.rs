@@ -0,0 +1,94 @@
+#![cfg(test)]
+
+use eth2_keystore::json_keystore::{Kdf, Pbkdf2, PBKDF2_PRF};
+use eth2_wallet::{
+    bip39::{Language, Mnemonic, MnemonicType},
+    keypair_from_seed, KeyType, ValidatorPath, Wallet, WalletBuilder,
+};
+
+const WALLET_PASSWORD: &[u8] = &[43; 43];
+const VOTING_PASSWORD: &[u8] = &[44; 44];
+const WITHDRAWAL_PASSWORD: &[u8] = &[45; 45];
+
+/// A cheap KDF, to avoid spending a long time running scrypt in tests.
+fn insecure_kdf() -> Kdf {
+    Kdf::Pbkdf2(Pbkdf2 {
+        dklen: 32,
+        c: 2,
+        prf: PBKDF2_PRF.to_string(),
+        salt: vec![1; 32].into(),
+    })
+}
+
+fn wallet_from_mnemonic(mnemonic: &Mnemonic) -> Wallet {
+    WalletBuilder::from_mnemonic(mnemonic, WALLET_PASSWORD, "test".into())
+        .expect("should create builder")
+        .kdf(insecure_kdf())
+        .build()
+        .expect("should build wallet")
+}
+
+#[test]
+fn json_round_trip() {
+    let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+    let wallet = wallet_from_mnemonic(&mnemonic);
+
+    let json = wallet.to_json_string().unwrap();
+    let decoded = Wallet::from_json_str(&json).unwrap();
+
+    assert_eq!(decoded, wallet);
+    assert_eq!(decoded.name(), "test");
+    assert_eq!(decoded.nextaccount(), 0);
+}
+
+#[test]
+fn validator_keys_match_paths() {
+    let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+    let mut wallet = wallet_from_mnemonic(&mnemonic);
+    let seed = wallet.decrypt_seed(WALLET_PASSWORD).unwrap();
+
+    for index in 0..1 {
+        let keystores = wallet
+            .next_validator(WALLET_PASSWORD, VOTING_PASSWORD, WITHDRAWAL_PASSWORD)
+            .expect("should derive validator");
+
+        assert_eq!(keystores.index, index);
+        assert_eq!(wallet.nextaccount(), index + 1);
+
+        let voting_path = ValidatorPath::new(index, KeyType::Voting);
+        let withdrawal_path = ValidatorPath::new(index, KeyType::Withdrawal);
+
+        assert_eq!(keystores.voting.path(), format!("{}", voting_path));
+        assert_eq!(keystores.withdrawal.path(), format!("{}", withdrawal_path));
+
+        assert_eq!(
+            keystores.voting.decrypt_keypair(VOTING_PASSWORD).unwrap(),
+            keypair_from_seed(seed.as_bytes(), &voting_path).unwrap()
+        );
+        assert_eq!(
+            keystores
+                .withdrawal
+                .decrypt_keypair(WITHDRAWAL_PASSWORD)
+                .unwrap(),
+            keypair_from_seed(seed.as_bytes(), &withdrawal_path).unwrap()
+        );
+    }
+}
+
+#[test]
+fn mnemonic_recovery() {
+    let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+    let recovered = Mnemonic::from_phrase(mnemonic.phrase(), Language::English).unwrap();
+
+    let seed_a = wallet_from_mnemonic(&mnemonic)
+        .decrypt_seed(WALLET_PASSWORD)
+        .unwrap();
+    let seed_b = wallet_from_mnemonic(&recovered)
+        .decrypt_seed(WALLET_PASSWORD)
+        .unwrap();
+
+    assert!(
+        seed_a == seed_b,
+        "recovered wallet should have the same seed"
+    );
+}
@@ -3,9 +3,10 @@ use eth2_libp2p::{
     multiaddr::{Multiaddr, Protocol},
     Enr,
 };
+use eth2_testnet_config::{Eth2TestnetConfig, TestnetBundle};
 use reqwest::{Error as HttpError, Url};
-use serde::Deserialize;
-use slog::{error, Logger};
+use serde::{Deserialize, Serialize};
+use slog::{error, info, warn, Logger};
 use std::borrow::Cow;
 use std::net::Ipv4Addr;
 use std::time::Duration;
@@ -27,6 +28,23 @@ impl From<HttpError> for Error {
     }
 }
 
+/// Defines how a state downloaded from an untrusted bootstrap server is verified before the node
+/// anchors upon it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateVerification {
+    /// The root which the state must have, as obtained out-of-band by the user.
+    pub expected_state_root: Option<Hash256>,
+    /// A second, independent server whose state root at the same slot must match.
+    pub verify_server: Option<String>,
+}
+
+impl StateVerification {
+    /// Returns `true` if the state will be checked against some other source.
+    pub fn is_enabled(&self) -> bool {
+        self.expected_state_root.is_some() || self.verify_server.is_some()
+    }
+}
+
 /// Used to load "bootstrap" information from the HTTP API of another Lighthouse beacon node.
 ///
 /// Bootstrapping information includes things like genesis and finalized states and blocks, and
@@ -109,6 +127,13 @@ impl Bootstrapper {
         get_eth2_config(self.url.clone()).map_err(|e| format!("Unable to get Eth2Config: {:?}", e))
     }
 
+    /// Returns the testnet followed by the server, including its genesis state and boot ENRs.
+    pub fn testnet<T: EthSpec>(&self) -> Result<Eth2TestnetConfig<T>, String> {
+        get_testnet(self.url.clone())
+            .map_err(|e| format!("Unable to get testnet: {:?}", e))?
+            .into_testnet_config()
+    }
+
     /// Returns the servers ENR address.
     pub fn enr(&self) -> Result<Enr, String> {
         get_enr(self.url.clone()).map_err(|e| format!("Unable to get ENR: {:?}", e))
@@ -133,6 +158,78 @@ impl Bootstrapper {
         Ok((state, block))
     }
 
+    /// Returns the genesis block and state, having verified the state as per `verification`.
+    ///
+    /// Returns an error if the state root does not match the root of the genesis block, or any of
+    /// the sources in `verification`.
+    pub fn verified_genesis<T: EthSpec>(
+        &self,
+        verification: &StateVerification,
+        log: &Logger,
+    ) -> Result<(BeaconState<T>, BeaconBlock<T>), String> {
+        let (state, block) = self.genesis::<T>()?;
+
+        // Compute the root locally, rather than trusting the root supplied by the server.
+        let state_root = state.canonical_root();
+        if block.state_root != state_root {
+            return Err(format!(
+                "Bootstrap state root {:?} does not match the state root of the block {:?}",
+                state_root, block.state_root
+            ));
+        }
+
+        if let Some(expected_state_root) = verification.expected_state_root {
+            if expected_state_root != state_root {
+                return Err(format!(
+                    "Bootstrap state root {:?} does not match the expected state root {:?}",
+                    state_root, expected_state_root
+                ));
+            }
+        }
+
+        if let Some(verify_server) = &verification.verify_server {
+            let url = Url::parse(verify_server)
+                .map_err(|e| format!("Invalid verification server url: {}", e))?;
+            let verified_root = get_state_root(url, state.slot).map_err(|e| {
+                format!("Unable to get state root from verification server: {:?}", e)
+            })?;
+
+            if verified_root != state_root {
+                return Err(format!(
+                    "Bootstrap state root {:?} does not match the state root {:?} of the \
+                     verification server",
+                    state_root, verified_root
+                ));
+            }
+        }
+
+        if verification.is_enabled() {
+            let verify_server = verification
+                .verify_server
+                .as_ref()
+                .map_or("none", String::as_str);
+
+            info!(
+                log,
+                "Verified bootstrap state";
+                "state_root" => format!("{:?}", state_root),
+                "slot" => state.slot.as_u64(),
+                "expected_state_root" => verification.expected_state_root.is_some(),
+                "verify_server" => verify_server,
+            );
+        } else {
+            warn!(
+                log,
+                "Bootstrap state is unverified";
+                "info" => "check the state root against a trusted source",
+                "state_root" => format!("{:?}", state_root),
+                "slot" => state.slot.as_u64(),
+            );
+        }
+
+        Ok((state, block))
+    }
+
     /// Returns the most recent finalized state and block.
     pub fn finalized<T: EthSpec>(&self) -> Result<(BeaconState<T>, BeaconBlock<T>), String> {
         let slots_per_epoch = get_slots_per_epoch(self.url.clone())
@@ -177,6 +274,19 @@ fn get_eth2_config(mut url: Url) -> Result<Eth2Config, Error> {
         .map_err(Into::into)
 }
 
+fn get_testnet(mut url: Url) -> Result<TestnetBundle, Error> {
+    url.path_segments_mut()
+        .map(|mut url| {
+            url.push("lighthouse").push("testnet");
+        })
+        .map_err(|_| Error::InvalidUrl)?;
+
+    reqwest::get(url)?
+        .error_for_status()?
+        .json()
+        .map_err(Into::into)
+}
+
 fn get_finalized_slot(mut url: Url, slots_per_epoch: u64) -> Result<Slot, Error> {
     url.path_segments_mut()
         .map(|mut url| {
@@ -212,6 +322,22 @@ fn get_state<T: EthSpec>(mut url: Url, slot: Slot) -> Result<StateResponse<T>, E
         .map_err(Into::into)
 }
 
+fn get_state_root(mut url: Url, slot: Slot) -> Result<Hash256, Error> {
+    url.path_segments_mut()
+        .map(|mut url| {
+            url.push("beacon").push("state_root");
+        })
+        .map_err(|_| Error::InvalidUrl)?;
+
+    url.query_pairs_mut()
+        .append_pair("slot", &format!("{}", slot.as_u64()));
+
+    reqwest::get(url)?
+        .error_for_status()?
+        .json()
+        .map_err(Into::into)
+}
+
 #[derive(Deserialize)]
 #[serde(bound = "T: EthSpec")]
 pub struct BlockResponse<T: EthSpec> {
@@ -0,0 +1,121 @@
+//! The default locations of the directories in which Lighthouse stores its data.
+//!
+//! All data lives beneath a single root directory, laid out as:
+//!
+//! - `beacon/`: the beacon node database, network keys and configs.
+//! - `validators/`: one directory per validator.
+//! - `secrets/`: the passwords of validator keystores.
+//! - `wallets/`: EIP-2386 wallets.
+//!
+//! The root is platform-specific:
+//!
+//! - Linux: `$XDG_DATA_HOME/lighthouse` (usually `~/.local/share/lighthouse`).
+//! - macOS: `~/Library/Application Support/Lighthouse`.
+//! - Windows: `%APPDATA%\Lighthouse`.
+//!
+//! An existing `~/.lighthouse` directory (the layout used by earlier releases) is preferred over
+//! the platform directory, so that upgrading does not strand existing data.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory containing the beacon node data.
+pub const BEACON_DIR: &str = "beacon";
+/// The directory containing validator directories.
+pub const VALIDATORS_DIR: &str = "validators";
+/// The directory containing the passwords of validator keystores.
+pub const SECRETS_DIR: &str = "secrets";
+/// The directory containing wallets.
+pub const WALLETS_DIR: &str = "wallets";
+
+/// The name of the root directory used by earlier releases, within the home directory.
+pub const LEGACY_ROOT_DIR: &str = ".lighthouse";
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const ROOT_DIR_NAME: &str = "Lighthouse";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const ROOT_DIR_NAME: &str = "lighthouse";
+
+/// Returns the root directory beneath which all Lighthouse data is stored by default.
+///
+/// This is `~/.lighthouse` if it exists, otherwise the platform data directory (see the module
+/// docs). If neither the home nor the data directory are known, the present directory is used.
+pub fn default_root_dir() -> PathBuf {
+    root_dir(dirs::home_dir(), dirs::data_dir())
+}
+
+/// Returns the default location of the `name` directory (e.g., `VALIDATORS_DIR`).
+pub fn default_dir(name: &str) -> PathBuf {
+    default_root_dir().join(name)
+}
+
+fn root_dir(home_dir: Option<PathBuf>, data_dir: Option<PathBuf>) -> PathBuf {
+    home_dir
+        .map(|home| home.join(LEGACY_ROOT_DIR))
+        .filter(|legacy| legacy.is_dir())
+        .or_else(|| data_dir.map(|data| data.join(ROOT_DIR_NAME)))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Creates the directory at `path` and any non-existing parents.
+///
+/// On unix, each directory created is only accessible by the present user (i.e., mode `0o700`).
+/// The permissions of existing directories are not changed.
+pub fn create_dir_restricted<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder
+        .create(path)
+        .map_err(|e| format!("Unable to create {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn prefers_legacy_root_dir() {
+        let home = TempDir::new("home").expect("should create temp dir");
+        let data = home.path().join("data");
+
+        assert_eq!(
+            root_dir(Some(home.path().into()), Some(data.clone())),
+            data.join(ROOT_DIR_NAME)
+        );
+
+        fs::create_dir(home.path().join(LEGACY_ROOT_DIR)).expect("should create legacy dir");
+        assert_eq!(
+            root_dir(Some(home.path().into()), Some(data)),
+            home.path().join(LEGACY_ROOT_DIR)
+        );
+        assert_eq!(root_dir(None, None), PathBuf::from("."));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn creates_restricted_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = TempDir::new("root").expect("should create temp dir");
+        let path = root.path().join("lighthouse").join(SECRETS_DIR);
+
+        create_dir_restricted(&path).expect("should create dirs");
+        create_dir_restricted(&path).expect("should tolerate existing dirs");
+
+        for dir in &[path.parent().expect("has parent"), &path] {
+            let mode = fs::metadata(dir)
+                .expect("should read metadata")
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o700, "{:?}", dir);
+        }
+    }
+}
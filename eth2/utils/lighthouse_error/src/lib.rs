@@ -0,0 +1,171 @@
+//! A shared taxonomy of errors, allowing errors from any Lighthouse service to be identified by a
+//! stable numeric code.
+//!
+//! Codes are allocated to services in ranges:
+//!
+//! - `1000..2000`: the database (`store`).
+//! - `2000..3000`: the beacon chain (`beacon_chain`).
+//! - `3000..4000`: networking (`eth2-libp2p`).
+//! - `4000..5000`: the HTTP API server (`rest_api`).
+//! - `5000..6000`: the HTTP API client (`remote_beacon_node`).
+//! - `9000`: errors which have no more specific code (see `Error::other`).
+//!
+//! Once allocated, a code must never be reused for a different error.
+use std::fmt;
+
+/// The code of errors which have no more specific code.
+pub const OTHER: ErrorCode = ErrorCode(9000);
+
+/// The name of the HTTP header which carries the code of an error response.
+pub const HTTP_HEADER: &str = "lighthouse-error-code";
+
+/// A stable, machine-readable identifier for a kind of error, displayed as `E<code>` (e.g.,
+/// `E1005`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErrorCode(pub u16);
+
+impl ErrorCode {
+    /// Parses a code in the form produced by `Display` (e.g., from the `HTTP_HEADER` of a
+    /// response).
+    pub fn parse(string: &str) -> Option<Self> {
+        let digits = if string.starts_with('E') {
+            &string[1..]
+        } else {
+            string
+        };
+
+        digits.parse().ok().map(ErrorCode)
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "E{:04}", self.0)
+    }
+}
+
+/// An error type with a stable `ErrorCode` for each of its variants.
+pub trait CodedError: fmt::Debug {
+    fn error_code(&self) -> ErrorCode;
+
+    /// Converts `self` into an `Error`, using its `Debug` representation as the message.
+    fn into_error(self) -> Error
+    where
+        Self: Sized,
+    {
+        Error::new(self.error_code(), format!("{:?}", self))
+    }
+}
+
+/// A coded error with a chain of context describing what was being done when it occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    code: ErrorCode,
+    message: String,
+    /// Context descriptions, innermost first.
+    context: Vec<String>,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(code: ErrorCode, message: S) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            context: vec![],
+        }
+    }
+
+    /// An error which has no more specific code than `OTHER`.
+    pub fn other<S: Into<String>>(message: S) -> Self {
+        Self::new(OTHER, message)
+    }
+
+    /// Adds a description of what was being done when `self` occurred.
+    pub fn context<S: Into<String>>(mut self, context: S) -> Self {
+        self.context.push(context.into());
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl CodedError for Error {
+    fn error_code(&self) -> ErrorCode {
+        self.code
+    }
+
+    fn into_error(self) -> Error {
+        self
+    }
+}
+
+/// Displays as `<code>: <outermost context>: ... : <message>`.
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        for context in self.context.iter().rev() {
+            write!(f, ": {}", context)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Adds context to the `CodedError` of a `Result`.
+pub trait ResultExt<T> {
+    fn context<S: Into<String>>(self, context: S) -> Result<T, Error>;
+}
+
+impl<T, E: CodedError> ResultExt<T> for Result<T, E> {
+    fn context<S: Into<String>>(self, context: S) -> Result<T, Error> {
+        self.map_err(|e| e.into_error().context(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Inner;
+
+    impl CodedError for Inner {
+        fn error_code(&self) -> ErrorCode {
+            ErrorCode(1234)
+        }
+    }
+
+    #[test]
+    fn displays_context_chain() {
+        let result: Result<(), _> = Err(Inner);
+        let error = result
+            .context("reading block")
+            .context("loading head")
+            .unwrap_err();
+
+        assert_eq!(error.code(), ErrorCode(1234));
+        assert_eq!(
+            error.to_string(),
+            "E1234: loading head: reading block: Inner"
+        );
+        assert_eq!(Error::other("oops").to_string(), "E9000: oops");
+    }
+
+    #[test]
+    fn parses_codes() {
+        assert_eq!(ErrorCode::parse("E0042"), Some(ErrorCode(42)));
+        assert_eq!(ErrorCode::parse("4004"), Some(ErrorCode(4004)));
+        assert_eq!(ErrorCode::parse("E"), None);
+        assert_eq!(
+            ErrorCode::parse(&ErrorCode(5001).to_string()),
+            Some(ErrorCode(5001))
+        );
+    }
+}
@@ -1,3 +1,9 @@
+mod module_level_filter;
+mod rotating_file;
+
+pub use module_level_filter::{parse_module_levels, ModuleLevelFilter};
+pub use rotating_file::RotatingFile;
+
 use std::io::{Result, Write};
 
 pub const MAX_MESSAGE_WIDTH: usize = 40;
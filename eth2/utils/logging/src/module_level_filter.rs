@@ -0,0 +1,124 @@
+use slog::{Drain, Level, OwnedKVList, Record};
+
+/// A `Drain` which discards records below a minimum level, where the minimum level may be
+/// overridden for particular modules.
+///
+/// Modules are matched by prefix on their path, so an override for `network` also applies to
+/// `network::service`, but not to `network_utils`. The most specific matching override is used.
+pub struct ModuleLevelFilter<D> {
+    drain: D,
+    default_level: Level,
+    module_levels: Vec<(String, Level)>,
+}
+
+impl<D> ModuleLevelFilter<D> {
+    pub fn new(drain: D, default_level: Level, module_levels: Vec<(String, Level)>) -> Self {
+        Self {
+            drain,
+            default_level,
+            module_levels,
+        }
+    }
+
+    /// Returns the minimum level for records logged from `module`.
+    pub fn level_for(&self, module: &str) -> Level {
+        self.module_levels
+            .iter()
+            .filter(|(prefix, _)| {
+                module == prefix
+                    || (module.starts_with(prefix.as_str())
+                        && module[prefix.len()..].starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default_level)
+    }
+
+    /// Returns the most verbose level which is emitted by any module.
+    fn max_level(&self) -> Level {
+        self.module_levels
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default_level, |max, level| {
+                if level.is_at_least(max) {
+                    max
+                } else {
+                    level
+                }
+            })
+    }
+}
+
+impl<D: Drain> Drain for ModuleLevelFilter<D> {
+    type Ok = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if record.level().is_at_least(self.level_for(record.module())) {
+            self.drain.log(record, values).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn is_enabled(&self, level: Level) -> bool {
+        level.is_at_least(self.max_level()) && self.drain.is_enabled(level)
+    }
+}
+
+/// Parses a comma-separated list of module level overrides, e.g.,
+/// `network=debug,eth2_libp2p::discovery=warn`.
+///
+/// Module paths use underscores, even for crates whose names contain hyphens.
+pub fn parse_module_levels(string: &str) -> Result<Vec<(String, Level)>, String> {
+    string
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            let mut parts = item.trim().splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(module), Some(level)) if !module.is_empty() => level
+                    .parse::<Level>()
+                    .map(|level| (module.replace('-', "_"), level))
+                    .map_err(|_| format!("Unknown level {} for module {}", level, module)),
+                _ => Err(format!("Module levels must be MODULE=LEVEL, not {}", item)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::Discard;
+
+    #[test]
+    fn module_levels() {
+        let module_levels =
+            parse_module_levels("network=debug, network::service=trace,eth2-libp2p=crit")
+                .expect("should parse levels");
+        assert_eq!(
+            module_levels,
+            vec![
+                ("network".to_string(), Level::Debug),
+                ("network::service".to_string(), Level::Trace),
+                ("eth2_libp2p".to_string(), Level::Critical),
+            ]
+        );
+
+        let filter = ModuleLevelFilter::new(Discard, Level::Info, module_levels);
+        assert_eq!(filter.level_for("beacon_chain"), Level::Info);
+        assert_eq!(filter.level_for("network"), Level::Debug);
+        assert_eq!(filter.level_for("network::sync"), Level::Debug);
+        assert_eq!(filter.level_for("network::service"), Level::Trace);
+        assert_eq!(filter.level_for("network_utils"), Level::Info);
+        assert_eq!(filter.level_for("eth2_libp2p::behaviour"), Level::Critical);
+
+        assert!(filter.is_enabled(Level::Trace));
+        assert!(ModuleLevelFilter::new(Discard, Level::Info, vec![]).is_enabled(Level::Info));
+        assert!(!ModuleLevelFilter::new(Discard, Level::Info, vec![]).is_enabled(Level::Debug));
+
+        assert!(parse_module_levels("network").is_err());
+        assert!(parse_module_levels("network=loud").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use flate2::{write::GzEncoder, Compression};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A file which is rotated once it exceeds a maximum size.
+///
+/// When rotated, the file at `path` is renamed to `path.1` (or compressed to `path.1.gz`), any
+/// existing `path.1` becomes `path.2`, and so on. At most `max_files` rotated files are kept.
+///
+/// Rotation only happens directly after a newline has been written, so that a single log record is
+/// never split across files.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    compress: bool,
+    file: File,
+    size: u64,
+    at_line_start: bool,
+}
+
+impl RotatingFile {
+    /// Opens the file at `path`, appending to it if it already exists.
+    pub fn new(path: PathBuf, max_size: u64, max_files: usize, compress: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            compress,
+            file,
+            size,
+            at_line_start: true,
+        })
+    }
+
+    /// Returns the path of the `n`th rotated file.
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        if self.compress {
+            name.push(".gz");
+        }
+        PathBuf::from(name)
+    }
+
+    /// Moves each rotated file along by one, then rotates the present file and starts a new one.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for n in (1..self.max_files).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+
+        let first = self.rotated_path(1);
+        if self.compress {
+            compress_file(&self.path, &first)?;
+            fs::remove_file(&self.path)?;
+        } else {
+            fs::rename(&self.path, &first)?;
+        }
+
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.at_line_start && self.size >= self.max_size && self.size > 0 {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        if written > 0 {
+            self.size += written as u64;
+            self.at_line_start = buf[written - 1] == b'\n';
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Writes a gzip-compressed copy of the file at `from` to `to`.
+fn compress_file(from: &Path, to: &Path) -> io::Result<()> {
+    let mut input = File::open(from)?;
+    let mut encoder = GzEncoder::new(File::create(to)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempdir::TempDir;
+
+    fn write_lines(file: &mut RotatingFile, count: usize) {
+        for i in 0..count {
+            // Write each line in two parts, like a logger which writes each field separately.
+            write!(file, "line {}", i).expect("should write");
+            writeln!(file, " of {}", count).expect("should write");
+        }
+    }
+
+    #[test]
+    fn rotation() {
+        let dir = TempDir::new("rotating_file").expect("should create temp dir");
+        let path = dir.path().join("beacon.log");
+
+        let mut file = RotatingFile::new(path.clone(), 100, 2, false).expect("should open");
+        write_lines(&mut file, 50);
+
+        let current = fs::read_to_string(&path).expect("should read current file");
+        let rotated =
+            fs::read_to_string(dir.path().join("beacon.log.1")).expect("should read rotated file");
+
+        assert!(rotated.len() >= 100, "should rotate once full");
+        assert!(rotated.ends_with('\n'), "should not split lines");
+        assert!(current.ends_with("line 49 of 50\n"));
+        assert!(dir.path().join("beacon.log.2").exists());
+        assert!(
+            !dir.path().join("beacon.log.3").exists(),
+            "should only keep max_files"
+        );
+    }
+
+    #[test]
+    fn compressed_rotation() {
+        let dir = TempDir::new("rotating_file").expect("should create temp dir");
+        let path = dir.path().join("beacon.log");
+
+        let mut file = RotatingFile::new(path.clone(), 100, 1, true).expect("should open");
+        write_lines(&mut file, 10);
+
+        let mut rotated = String::new();
+        GzDecoder::new(File::open(dir.path().join("beacon.log.1.gz")).expect("should open"))
+            .read_to_string(&mut rotated)
+            .expect("should decompress rotated file");
+
+        assert!(rotated.starts_with("line 0 of 10\n"));
+        assert!(rotated.ends_with('\n'), "should not split lines");
+    }
+}
@@ -212,6 +212,82 @@ pub fn verify_merkle_proof(
     }
 }
 
+/// Verify proofs that each of `leaves` exists in a Merkle tree rooted at `root`, at consecutive
+/// indices from `start_index`, where `branches[i]` is the proof of `leaves[i]`.
+///
+/// Equivalent to calling `verify_merkle_proof` for each leaf, but each node shared by the proofs
+/// is only hashed once: the proofs of `n` consecutive leaves need roughly `n + 2 * depth` hashes,
+/// rather than `n * depth`. Each branch is checked against the nodes computed from the leaves
+/// (and from the outermost branches), so a proof is only accepted if it would be accepted alone.
+pub fn verify_merkle_proofs_batch(
+    leaves: &[H256],
+    branches: &[&[H256]],
+    depth: usize,
+    start_index: usize,
+    root: H256,
+) -> bool {
+    if leaves.len() != branches.len() || branches.iter().any(|branch| branch.len() != depth) {
+        return false;
+    }
+    if leaves.is_empty() {
+        return true;
+    }
+
+    // Only the lowest `depth` bits of an index are significant, so leaves which are not all
+    // within the same subtree of `depth` cannot share their hashes.
+    let end_index = match start_index.checked_add(leaves.len() - 1) {
+        Some(end_index) => end_index,
+        None => return false,
+    };
+    if depth >= std::mem::size_of::<usize>() * 8 || start_index >> depth != end_index >> depth {
+        return leaves
+            .iter()
+            .zip(branches)
+            .enumerate()
+            .all(|(i, (leaf, branch))| {
+                verify_merkle_proof(*leaf, branch, depth, start_index + i, root)
+            });
+    }
+
+    // The nodes of the current level from index `first`, which are the ancestors of the leaves.
+    let mut level = leaves.to_vec();
+    let mut first = start_index;
+
+    for i in 0..depth {
+        let last = first + level.len() - 1;
+        // The siblings outside of `level` can only be read from the branches.
+        let node = |index: usize| {
+            if index < first {
+                branches[0][i]
+            } else if index > last {
+                branches[branches.len() - 1][i]
+            } else {
+                level[index - first]
+            }
+        };
+
+        for (j, branch) in branches.iter().enumerate() {
+            let sibling = ((start_index + j) >> i) ^ 1;
+            if branch[i] != node(sibling) {
+                return false;
+            }
+        }
+
+        let parents = ((first >> 1)..=(last >> 1))
+            .map(|parent| {
+                let left = node(parent << 1);
+                let right = node((parent << 1) | 1);
+                H256::from_slice(&hash_concat(left.as_bytes(), right.as_bytes()))
+            })
+            .collect();
+
+        level = parents;
+        first >>= 1;
+    }
+
+    level.len() == 1 && level[0] == root
+}
+
 /// Compute a root hash from a leaf and a Merkle proof.
 fn merkle_root_from_branch(leaf: H256, branch: &[H256], depth: usize, index: usize) -> H256 {
     assert_eq!(branch.len(), depth, "proof length should equal depth");
@@ -278,6 +354,87 @@ mod tests {
         TestResult::from_bool(proofs_ok)
     }
 
+    /// Check that the batch verifier accepts a range of proofs if and only if each of them is
+    /// accepted by `verify_merkle_proof`, including when one leaf or branch has been corrupted.
+    #[quickcheck]
+    fn quickcheck_batch_matches_individual(
+        int_leaves: Vec<u64>,
+        depth: usize,
+        start: usize,
+        count: usize,
+        corruption: Option<(usize, usize)>,
+    ) -> TestResult {
+        if depth > MAX_TREE_DEPTH
+            || int_leaves.is_empty()
+            || int_leaves.len() > 2usize.pow(depth as u32)
+        {
+            return TestResult::discard();
+        }
+
+        let leaves: Vec<_> = int_leaves.into_iter().map(H256::from_low_u64_be).collect();
+        let merkle_tree = MerkleTree::create(&leaves, depth);
+        let merkle_root = merkle_tree.hash();
+
+        let start = start % leaves.len();
+        let count = 1 + count % (leaves.len() - start);
+        let (mut batch_leaves, mut branches): (Vec<_>, Vec<_>) = (start..start + count)
+            .map(|i| merkle_tree.generate_proof(i, depth))
+            .unzip();
+
+        if let Some((j, level)) = corruption {
+            let j = j % count;
+            let level = level % (depth + 1);
+            if level == depth {
+                batch_leaves[j].0[0] ^= 1;
+            } else {
+                branches[j][level].0[0] ^= 1;
+            }
+        }
+
+        let individual_ok = (0..count).all(|j| {
+            verify_merkle_proof(batch_leaves[j], &branches[j], depth, start + j, merkle_root)
+        });
+        let branches: Vec<&[H256]> = branches.iter().map(Vec::as_slice).collect();
+        let batch_ok =
+            verify_merkle_proofs_batch(&batch_leaves, &branches, depth, start, merkle_root);
+
+        TestResult::from_bool(batch_ok == individual_ok)
+    }
+
+    #[test]
+    fn batch_rejects_corrupt_middle_proof() {
+        let depth = 4;
+        let leaves: Vec<_> = (0..12).map(H256::from_low_u64_be).collect();
+        let merkle_tree = MerkleTree::create(&leaves, depth);
+        let merkle_root = merkle_tree.hash();
+
+        let (batch_leaves, mut branches): (Vec<_>, Vec<_>) = (3..10)
+            .map(|i| merkle_tree.generate_proof(i, depth))
+            .unzip();
+        {
+            let branches: Vec<&[H256]> = branches.iter().map(Vec::as_slice).collect();
+            assert!(verify_merkle_proofs_batch(
+                &batch_leaves,
+                &branches,
+                depth,
+                3,
+                merkle_root
+            ));
+        }
+
+        // The branch of a middle proof is never hashed, only compared to the nodes computed from
+        // the leaves.
+        branches[3][depth - 1].0[0] ^= 1;
+        let branches: Vec<&[H256]> = branches.iter().map(Vec::as_slice).collect();
+        assert!(!verify_merkle_proofs_batch(
+            &batch_leaves,
+            &branches,
+            depth,
+            3,
+            merkle_root
+        ));
+    }
+
     #[test]
     fn sparse_zero_correct() {
         let depth = 2;
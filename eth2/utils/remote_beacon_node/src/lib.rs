@@ -5,6 +5,7 @@
 
 use eth2_config::Eth2Config;
 use futures::{future, Future, IntoFuture};
+use lighthouse_error::{CodedError, ErrorCode, ResultExt};
 use reqwest::{
     r#async::{Client, ClientBuilder, Response},
     StatusCode,
@@ -14,12 +15,16 @@ use ssz::Encode;
 use std::marker::PhantomData;
 use std::time::Duration;
 use types::{
-    Attestation, BeaconBlock, BeaconState, CommitteeIndex, Epoch, EthSpec, Fork, Hash256,
-    PublicKey, Signature, Slot,
+    Attestation, AttesterSlashing, BeaconBlock, BeaconState, CommitteeIndex, Epoch, EthSpec, Fork,
+    Hash256, ProposerSlashing, PublicKey, Signature, SignedAggregateAndProof, Slot, VoluntaryExit,
 };
 use url::Url;
 
-pub use rest_api::{BulkValidatorDutiesRequest, HeadResponse, ValidatorDuty};
+pub use rest_api::{
+    AnchorInfo, BroadcastValidation, BulkValidatorDutiesRequest, ChainHealth, Committee,
+    HeadResponse, ValidatorDuty, ValidatorLiveness, ValidatorLivenessRequest, ValidatorRequest,
+    ValidatorResponse,
+};
 
 // Setting a long timeout for debug ensures that crypto-heavy operations can still succeed.
 #[cfg(debug_assertions)]
@@ -36,14 +41,17 @@ pub struct RemoteBeaconNode<E: EthSpec> {
 
 impl<E: EthSpec> RemoteBeaconNode<E> {
     /// Uses the default HTTP timeout.
-    pub fn new(http_endpoint: String) -> Result<Self, String> {
+    pub fn new(http_endpoint: String) -> Result<Self, lighthouse_error::Error> {
         Self::new_with_timeout(http_endpoint, Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
     }
 
-    pub fn new_with_timeout(http_endpoint: String, timeout: Duration) -> Result<Self, String> {
+    pub fn new_with_timeout(
+        http_endpoint: String,
+        timeout: Duration,
+    ) -> Result<Self, lighthouse_error::Error> {
         Ok(Self {
             http: HttpClient::new(http_endpoint, timeout)
-                .map_err(|e| format!("Unable to create http client: {:?}", e))?,
+                .context("Unable to create http client")?,
         })
     }
 }
@@ -57,7 +65,24 @@ pub enum Error {
     /// There was an error when encoding/decoding an object using serde.
     SerdeJsonError(serde_json::Error),
     /// The server responded to the request, however it did not return a 200-type success code.
-    DidNotSucceed { status: StatusCode, body: String },
+    ///
+    /// The `code` is that of the server's error, if it provided one.
+    DidNotSucceed {
+        status: StatusCode,
+        code: Option<ErrorCode>,
+        body: String,
+    },
+}
+
+impl CodedError for Error {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            Error::UrlParseError(_) => ErrorCode(5000),
+            Error::ReqwestError(_) => ErrorCode(5001),
+            Error::SerdeJsonError(_) => ErrorCode(5002),
+            Error::DidNotSucceed { code, .. } => code.unwrap_or(ErrorCode(5003)),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -144,9 +169,15 @@ fn error_for_status(
     if status.is_success() {
         Box::new(future::ok(response))
     } else {
+        let code = response
+            .headers()
+            .get(lighthouse_error::HTTP_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(ErrorCode::parse);
+
         Box::new(response.text().then(move |text_result| match text_result {
             Err(e) => Err(Error::ReqwestError(e)),
-            Ok(body) => Err(Error::DidNotSucceed { status, body }),
+            Ok(body) => Err(Error::DidNotSucceed { status, code, body }),
         }))
     }
 }
@@ -223,6 +254,117 @@ impl<E: EthSpec> Validator<E> {
             })
     }
 
+    /// Produces an unsigned attestation for each committee at `slot`, in order of committee
+    /// index.
+    pub fn produce_attestations(
+        &self,
+        slot: Slot,
+    ) -> impl Future<Item = Vec<Attestation<E>>, Error = Error> {
+        let query_params = vec![("slot".into(), format!("{}", slot))];
+
+        let client = self.0.clone();
+        self.url("attestations")
+            .into_future()
+            .and_then(move |url| client.json_get(url, query_params))
+    }
+
+    /// Posts a list of attestations to the beacon node, expecting it to verify them and publish
+    /// them to the network. The status is `Invalid` if any of them is invalid, in which case the
+    /// valid attestations are still published.
+    pub fn publish_attestations(
+        &self,
+        attestations: Vec<Attestation<E>>,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        let client = self.0.clone();
+        self.url("attestations")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, attestations))
+            .and_then(|mut response| {
+                response
+                    .text()
+                    .map(|text| (response, text))
+                    .map_err(Error::from)
+            })
+            .and_then(|(response, text)| match response.status() {
+                StatusCode::OK => Ok(PublishStatus::Valid),
+                StatusCode::ACCEPTED => Ok(PublishStatus::Invalid(text)),
+                _ => response
+                    .error_for_status()
+                    .map_err(Error::from)
+                    .map(|_| PublishStatus::Unknown),
+            })
+    }
+
+    /// Posts a signed voluntary exit to the beacon node, expecting it to verify it and publish it
+    /// to the network.
+    pub fn publish_voluntary_exit(
+        &self,
+        exit: VoluntaryExit,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        let client = self.0.clone();
+        self.url("voluntary_exit")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, exit))
+            .and_then(|mut response| {
+                response
+                    .text()
+                    .map(|text| (response, text))
+                    .map_err(Error::from)
+            })
+            .and_then(|(response, text)| match response.status() {
+                StatusCode::OK => Ok(PublishStatus::Valid),
+                StatusCode::ACCEPTED => Ok(PublishStatus::Invalid(text)),
+                _ => response
+                    .error_for_status()
+                    .map_err(Error::from)
+                    .map(|_| PublishStatus::Unknown),
+            })
+    }
+
+    /// Requests the best aggregate attestation known to the beacon node for the committee at
+    /// `slot` and `committee_index`.
+    pub fn get_aggregate_attestation(
+        &self,
+        slot: Slot,
+        committee_index: CommitteeIndex,
+    ) -> impl Future<Item = Attestation<E>, Error = Error> {
+        let query_params = vec![
+            ("slot".into(), format!("{}", slot)),
+            ("committee_index".into(), format!("{}", committee_index)),
+        ];
+
+        let client = self.0.clone();
+        self.url("aggregate_attestation")
+            .into_future()
+            .and_then(move |url| client.json_get(url, query_params))
+    }
+
+    /// Posts a list of signed aggregates to the beacon node, expecting it to verify them and
+    /// publish them to the network.
+    pub fn publish_aggregate_and_proofs(
+        &self,
+        signed_aggregates: Vec<SignedAggregateAndProof<E>>,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        let client = self.0.clone();
+        self.url("aggregate_and_proofs")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, signed_aggregates))
+            .and_then(|mut response| {
+                response
+                    .text()
+                    .map(|text| (response, text))
+                    .map_err(Error::from)
+            })
+            .and_then(|(response, text)| match response.status() {
+                StatusCode::OK => Ok(PublishStatus::Valid),
+                StatusCode::ACCEPTED => Ok(PublishStatus::Invalid(text)),
+                _ => response
+                    .error_for_status()
+                    .map_err(Error::from)
+                    .map(|_| PublishStatus::Unknown),
+            })
+    }
+
     /// Returns the duties required of the given validator pubkeys in the given epoch.
     ///
     /// ## Warning
@@ -273,15 +415,53 @@ impl<E: EthSpec> Validator<E> {
             .and_then(|mut success| success.json().map_err(Error::from))
     }
 
+    /// Returns whether or not each of the given validator pubkeys has been seen attesting in the
+    /// given `epoch`.
+    pub fn get_liveness(
+        &self,
+        epoch: Epoch,
+        validator_pubkeys: &[PublicKey],
+    ) -> impl Future<Item = Vec<ValidatorLiveness>, Error = Error> {
+        let client = self.0.clone();
+
+        let request = ValidatorLivenessRequest {
+            epoch,
+            pubkeys: validator_pubkeys
+                .iter()
+                .map(|pubkey| pubkey.clone().into())
+                .collect(),
+        };
+
+        self.url("liveness")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, request))
+            .and_then(|response| error_for_status(response).map_err(Error::from))
+            .and_then(|mut success| success.json().map_err(Error::from))
+    }
+
     /// Posts a block to the beacon node, expecting it to verify it and publish it to the network.
     pub fn publish_block(
         &self,
         block: BeaconBlock<E>,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        self.publish_block_with_validation(block, BroadcastValidation::default())
+    }
+
+    /// Posts a block to the beacon node, expecting it to perform the given `validation` before
+    /// publishing it to the network.
+    pub fn publish_block_with_validation(
+        &self,
+        block: BeaconBlock<E>,
+        validation: BroadcastValidation,
     ) -> impl Future<Item = PublishStatus, Error = Error> {
         let client = self.0.clone();
         self.url("block")
             .into_future()
-            .and_then(move |url| client.json_post::<_>(url, block))
+            .and_then(move |mut url| {
+                url.query_pairs_mut()
+                    .append_pair("broadcast_validation", validation.as_str());
+                client.json_post::<_>(url, block)
+            })
             .and_then(|mut response| {
                 response
                     .text()
@@ -303,16 +483,30 @@ impl<E: EthSpec> Validator<E> {
         &self,
         slot: Slot,
         randao_reveal: Signature,
+    ) -> impl Future<Item = BeaconBlock<E>, Error = Error> {
+        self.produce_block_with_graffiti(slot, randao_reveal, None)
+    }
+
+    /// Requests a new block, including the given `graffiti` instead of the beacon node's default
+    /// (if it is not `None`).
+    pub fn produce_block_with_graffiti(
+        &self,
+        slot: Slot,
+        randao_reveal: Signature,
+        graffiti: Option<[u8; 32]>,
     ) -> impl Future<Item = BeaconBlock<E>, Error = Error> {
         let client = self.0.clone();
         self.url("block").into_future().and_then(move |url| {
-            client.json_get::<BeaconBlock<E>>(
-                url,
-                vec![
-                    ("slot".into(), format!("{}", slot.as_u64())),
-                    ("randao_reveal".into(), signature_as_string(&randao_reveal)),
-                ],
-            )
+            let mut query_params = vec![
+                ("slot".into(), format!("{}", slot.as_u64())),
+                ("randao_reveal".into(), signature_as_string(&randao_reveal)),
+            ];
+
+            if let Some(graffiti) = graffiti {
+                query_params.push(("graffiti".into(), format!("0x{}", hex::encode(graffiti))));
+            }
+
+            client.json_get::<BeaconBlock<E>>(url, query_params)
         })
     }
 }
@@ -350,6 +544,27 @@ impl<E: EthSpec> Beacon<E> {
             .and_then(move |url| client.json_get::<HeadResponse>(url, vec![]))
     }
 
+    /// Returns the index, balance and status of each of the given validators in the head state.
+    pub fn get_validators(
+        &self,
+        validator_pubkeys: &[PublicKey],
+    ) -> impl Future<Item = Vec<ValidatorResponse>, Error = Error> {
+        let client = self.0.clone();
+
+        let request = ValidatorRequest {
+            pubkeys: validator_pubkeys
+                .iter()
+                .map(|pubkey| pubkey.clone().into())
+                .collect(),
+        };
+
+        self.url("validators")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, request))
+            .and_then(|response| error_for_status(response).map_err(Error::from))
+            .and_then(|mut success| success.json().map_err(Error::from))
+    }
+
     /// Returns the block and block root at the given slot.
     pub fn get_block_by_slot(
         &self,
@@ -397,6 +612,34 @@ impl<E: EthSpec> Beacon<E> {
         self.get_state("root".to_string(), root_as_string(root))
     }
 
+    /// Returns the committees of `epoch` (or the epoch of the state, if `None`) for the state
+    /// identified by `state_id` (e.g., `head` or a slot), optionally filtered by `index` and
+    /// `slot`.
+    pub fn get_committees(
+        &self,
+        state_id: &str,
+        epoch: Option<Epoch>,
+        index: Option<CommitteeIndex>,
+        slot: Option<Slot>,
+    ) -> impl Future<Item = Vec<Committee>, Error = Error> {
+        let mut query_params = vec![];
+        if let Some(epoch) = epoch {
+            query_params.push(("epoch".into(), format!("{}", epoch)));
+        }
+        if let Some(index) = index {
+            query_params.push(("index".into(), format!("{}", index)));
+        }
+        if let Some(slot) = slot {
+            query_params.push(("slot".into(), format!("{}", slot)));
+        }
+
+        let client = self.0.clone();
+        self.0
+            .url(&format!("eth/v1/beacon/states/{}/committees", state_id))
+            .into_future()
+            .and_then(move |url| client.json_get(url, query_params))
+    }
+
     /// Returns the state and state root at the given slot.
     fn get_state(
         &self,
@@ -411,6 +654,58 @@ impl<E: EthSpec> Beacon<E> {
             })
             .map(|response| (response.beacon_state, response.root))
     }
+
+    /// Posts a proposer slashing to the beacon node, expecting it to verify it and publish it to
+    /// the network.
+    pub fn publish_proposer_slashing(
+        &self,
+        slashing: ProposerSlashing,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        let client = self.0.clone();
+        self.url("proposer_slashing")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, slashing))
+            .and_then(|mut response| {
+                response
+                    .text()
+                    .map(|text| (response, text))
+                    .map_err(Error::from)
+            })
+            .and_then(|(response, text)| match response.status() {
+                StatusCode::OK => Ok(PublishStatus::Valid),
+                StatusCode::ACCEPTED => Ok(PublishStatus::Invalid(text)),
+                _ => response
+                    .error_for_status()
+                    .map_err(Error::from)
+                    .map(|_| PublishStatus::Unknown),
+            })
+    }
+
+    /// Posts an attester slashing to the beacon node, expecting it to verify it and publish it to
+    /// the network.
+    pub fn publish_attester_slashing(
+        &self,
+        slashing: AttesterSlashing<E>,
+    ) -> impl Future<Item = PublishStatus, Error = Error> {
+        let client = self.0.clone();
+        self.url("attester_slashing")
+            .into_future()
+            .and_then(move |url| client.json_post::<_>(url, slashing))
+            .and_then(|mut response| {
+                response
+                    .text()
+                    .map(|text| (response, text))
+                    .map_err(Error::from)
+            })
+            .and_then(|(response, text)| match response.status() {
+                StatusCode::OK => Ok(PublishStatus::Valid),
+                StatusCode::ACCEPTED => Ok(PublishStatus::Invalid(text)),
+                _ => response
+                    .error_for_status()
+                    .map_err(Error::from)
+                    .map(|_| PublishStatus::Unknown),
+            })
+    }
 }
 
 /// Provides the functions on the `/spec` endpoint of the node.
@@ -451,6 +746,43 @@ impl<E: EthSpec> Node<E> {
             .into_future()
             .and_then(move |url| client.json_get(url, vec![]))
     }
+
+    /// Returns the `AnchorInfo` of the node's database, or `None` if it holds the full history
+    /// of the chain.
+    pub fn get_anchor(&self) -> impl Future<Item = Option<AnchorInfo>, Error = Error> {
+        let client = self.0.clone();
+        self.url("anchor")
+            .into_future()
+            .and_then(move |url| client.json_get(url, vec![]))
+    }
+
+    /// Returns the `ChainHealth` of the node, or `None` if it has not yet been assessed.
+    ///
+    /// The node responds with a `503` status whilst it is stalled or not yet assessed, which is
+    /// not treated as an error.
+    pub fn get_health(&self) -> impl Future<Item = Option<ChainHealth>, Error = Error> {
+        let client = self.0.clone();
+        self.0
+            .url("eth/v1/node/health")
+            .into_future()
+            .and_then(move |url| {
+                client
+                    .client
+                    .get(&url.to_string())
+                    .send()
+                    .map_err(Error::from)
+            })
+            .and_then(
+                |response| -> Box<dyn Future<Item = Response, Error = Error> + Send> {
+                    if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+                        Box::new(future::ok(response))
+                    } else {
+                        error_for_status(response)
+                    }
+                },
+            )
+            .and_then(|mut success| success.json().map_err(Error::from))
+    }
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,53 @@
+//! Serializes a `[u8; 4]` (e.g., a fork version) as a `0x`-prefixed hex string.
+
+use crate::hex_bytes::PrefixedHexVisitor;
+use serde::de::Error;
+use serde::{Deserializer, Serializer};
+
+const BYTES_LEN: usize = 4;
+
+pub fn serialize<S>(bytes: &[u8; BYTES_LEN], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::hex_bytes::serialize(bytes, serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; BYTES_LEN], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let decoded = deserializer.deserialize_str(PrefixedHexVisitor)?;
+
+    if decoded.len() != BYTES_LEN {
+        return Err(D::Error::custom(format!(
+            "expected {} bytes, got {}",
+            BYTES_LEN,
+            decoded.len()
+        )));
+    }
+
+    let mut array = [0; BYTES_LEN];
+    array.copy_from_slice(&decoded);
+    Ok(array)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct Version(#[serde(with = "super")] [u8; 4]);
+
+    #[test]
+    fn round_trips() {
+        let version = Version([0, 1, 2, 255]);
+        let json = serde_json::to_string(&version).unwrap();
+
+        assert_eq!(json, "\"0x000102ff\"");
+        assert_eq!(serde_json::from_str::<Version>(&json).unwrap(), version);
+        assert!(serde_json::from_str::<Version>("\"0x0001\"").is_err());
+        assert!(serde_json::from_str::<Version>("\"000102ff\"").is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! Serde helpers for the representations used by the Ethereum 2.0 APIs, for use with
+//! `#[serde(with = "...")]`.
+//!
+//! Integers are serialized as decimal strings (e.g., `"32000000000"`), since many JSON
+//! implementations cannot represent a `u64` exactly. Plain numbers are still accepted when
+//! deserializing, so that YAML test vectors and older clients continue to work.
+
+pub mod bytes_4_hex;
+pub mod hex_bytes;
+pub mod quoted_u64;
+pub mod quoted_u64_vec;
+
+pub use quoted_u64::QuotedU64Visitor;
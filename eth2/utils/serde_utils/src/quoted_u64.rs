@@ -0,0 +1,85 @@
+//! Serializes a `u64` as a decimal string, accepting either a string or a number when
+//! deserializing.
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Serializer;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub struct QuotedU64Visitor;
+
+impl<'de> Visitor<'de> for QuotedU64Visitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a quoted or unquoted u64")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        value
+            .parse()
+            .map_err(|e| de::Error::custom(format!("invalid u64 {:?} ({:?})", value, e)))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(value)
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u64::try_from(value).map_err(|_| de::Error::custom(format!("negative u64 {}", value)))
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // Serde requires the `value` to be a ref.
+pub fn serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(QuotedU64Visitor)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct Quoted(#[serde(with = "super")] u64);
+
+    #[test]
+    fn serializes_as_string() {
+        assert_eq!(
+            serde_json::to_string(&Quoted(u64::max_value())).unwrap(),
+            "\"18446744073709551615\""
+        );
+    }
+
+    #[test]
+    fn deserializes_string_or_number() {
+        assert_eq!(
+            serde_json::from_str::<Quoted>("\"42\"").unwrap(),
+            Quoted(42)
+        );
+        assert_eq!(serde_json::from_str::<Quoted>("42").unwrap(), Quoted(42));
+        assert_eq!(serde_yaml::from_str::<Quoted>("42").unwrap(), Quoted(42));
+        assert_eq!(serde_yaml::from_str::<Quoted>("'42'").unwrap(), Quoted(42));
+        assert!(serde_json::from_str::<Quoted>("\"0x2a\"").is_err());
+        assert!(serde_json::from_str::<Quoted>("-1").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Serializes a list of `u64` as a list of decimal strings, accepting either strings or numbers
+//! when deserializing.
+
+use serde::de::{Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct QuotedU64(#[serde(with = "crate::quoted_u64")] u64);
+
+pub struct QuotedU64VecVisitor;
+
+impl<'de> Visitor<'de> for QuotedU64VecVisitor {
+    type Value = Vec<u64>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of quoted or unquoted u64s")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(QuotedU64(value)) = seq.next_element()? {
+            vec.push(value);
+        }
+
+        Ok(vec)
+    }
+}
+
+pub fn serialize<S>(values: &[u64], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(values.len()))?;
+    for value in values {
+        seq.serialize_element(&QuotedU64(*value))?;
+    }
+    seq.end()
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(QuotedU64VecVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Values {
+        #[serde(with = "super")]
+        values: Vec<u64>,
+    }
+
+    #[test]
+    fn round_trips() {
+        let values = Values {
+            values: vec![0, 1, u64::max_value()],
+        };
+        let json = serde_json::to_string(&values).unwrap();
+
+        assert_eq!(json, r#"{"values":["0","1","18446744073709551615"]}"#);
+        assert_eq!(serde_json::from_str::<Values>(&json).unwrap(), values);
+    }
+
+    #[test]
+    fn deserializes_mixed() {
+        assert_eq!(
+            serde_json::from_str::<Values>(r#"{"values":[1,"2"]}"#)
+                .unwrap()
+                .values,
+            vec![1, 2]
+        );
+        assert!(serde_json::from_str::<Values>(r#"{"values":["a"]}"#).is_err());
+    }
+}
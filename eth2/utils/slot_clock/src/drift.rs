@@ -0,0 +1,63 @@
+use crate::{metrics, SlotClock};
+use std::time::Duration;
+use types::Slot;
+
+/// If a block arrives more than this long before the start of its slot, the local clock is
+/// probably behind the clocks of the rest of the network.
+pub const CLOCK_DRIFT_WARNING_THRESHOLD: Duration = Duration::from_millis(1_000);
+
+/// Compares the local clock with the slot of a block which was just received from the network.
+///
+/// Blocks are produced at the start of their slot, so a block which arrives before its slot has
+/// started suggests that the local clock is behind (e.g., NTP is not running). Records how early
+/// the block arrived and returns that duration if it exceeds `threshold`, so that the caller may
+/// warn the user.
+///
+/// Returns `None` for a block which arrives after the start of its slot, or if the clock is not
+/// based on time.
+pub fn check_block_arrival<S: SlotClock>(
+    clock: &S,
+    block_slot: Slot,
+    threshold: Duration,
+) -> Option<Duration> {
+    let early_by = clock
+        .duration_to_slot(block_slot)
+        .unwrap_or_else(|| Duration::from_secs(0));
+
+    metrics::set_gauge(&metrics::BLOCK_EARLY_ARRIVAL, early_by.as_millis() as i64);
+
+    if early_by > threshold {
+        metrics::inc_counter(&metrics::CLOCK_DRIFT_WARNINGS);
+        Some(early_by)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SystemTimeSlotClock, TimeSource};
+
+    #[test]
+    fn detects_early_blocks() {
+        let genesis = Duration::from_secs(1_000);
+        let time_source = TimeSource::manual(genesis + Duration::from_millis(10_500));
+        let clock = SystemTimeSlotClock::new(Slot::new(0), genesis, Duration::from_secs(2))
+            .with_time_source(time_source);
+        let threshold = Duration::from_millis(1_000);
+
+        // The present slot is 5, which started 500ms ago.
+        assert_eq!(check_block_arrival(&clock, Slot::new(4), threshold), None);
+        assert_eq!(check_block_arrival(&clock, Slot::new(5), threshold), None);
+        // Slot 6 starts in 1.5s, slot 7 in 3.5s.
+        assert_eq!(
+            check_block_arrival(&clock, Slot::new(6), threshold),
+            Some(Duration::from_millis(1_500))
+        );
+        assert_eq!(
+            check_block_arrival(&clock, Slot::new(7), Duration::from_secs(4)),
+            None
+        );
+    }
+}
@@ -1,14 +1,18 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod drift;
 mod metrics;
 mod system_time_slot_clock;
 mod testing_slot_clock;
+mod time_source;
 
 use std::time::Duration;
 
+pub use crate::drift::{check_block_arrival, CLOCK_DRIFT_WARNING_THRESHOLD};
 pub use crate::system_time_slot_clock::SystemTimeSlotClock;
 pub use crate::testing_slot_clock::TestingSlotClock;
+pub use crate::time_source::TimeSource;
 pub use metrics::scrape_for_metrics;
 pub use types::Slot;
 
@@ -23,6 +27,24 @@ pub trait SlotClock: Send + Sync + Sized {
     /// Returns the slot at this present time.
     fn now(&self) -> Option<Slot>;
 
+    /// Returns the slot at this present time if the clock were `tolerance` ahead.
+    ///
+    /// Useful for accepting messages from nodes with a slightly faster clock than ours.
+    fn now_with_future_tolerance(&self, tolerance: Duration) -> Option<Slot>;
+
+    /// Returns the slot at this present time if the clock were `tolerance` behind.
+    ///
+    /// Useful for accepting messages from nodes with a slightly slower clock than ours.
+    fn now_with_past_tolerance(&self, tolerance: Duration) -> Option<Slot>;
+
+    /// Returns the present time as a duration since the `UNIX_EPOCH`, if the clock is based on
+    /// time.
+    fn now_duration(&self) -> Option<Duration>;
+
+    /// Returns the time at which `slot` starts, as a duration since the `UNIX_EPOCH`, if the clock
+    /// is based on time.
+    fn start_of(&self, slot: Slot) -> Option<Duration>;
+
     /// Returns the duration between slots
     fn slot_duration(&self) -> Duration;
 
@@ -31,4 +53,12 @@ pub trait SlotClock: Send + Sync + Sized {
 
     /// Returns the duration until the first slot of the next epoch.
     fn duration_to_next_epoch(&self, slots_per_epoch: u64) -> Option<Duration>;
+
+    /// Returns the duration since the start of `slot`, or `None` if `slot` has not yet started.
+    fn duration_since_start_of(&self, slot: Slot) -> Option<Duration>;
+
+    /// Returns the duration until the start of `slot`, or `None` if `slot` has already started.
+    fn duration_to_slot(&self, slot: Slot) -> Option<Duration> {
+        self.start_of(slot)?.checked_sub(self.now_duration()?)
+    }
 }
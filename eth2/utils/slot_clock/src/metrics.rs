@@ -13,6 +13,14 @@ lazy_static! {
         "slotclock_slot_time_milliseconds",
         "The duration in milliseconds between each slot"
     );
+    pub static ref BLOCK_EARLY_ARRIVAL: Result<IntGauge> = try_create_int_gauge(
+        "slotclock_block_early_arrival_milliseconds",
+        "How long before the start of its slot the last gossip block arrived (0 if it was not early)"
+    );
+    pub static ref CLOCK_DRIFT_WARNINGS: Result<IntCounter> = try_create_int_counter(
+        "slotclock_drift_warnings_total",
+        "Count of blocks which arrived so early that the local clock is probably behind"
+    );
 }
 
 /// Update the global metrics `DEFAULT_REGISTRY` with info from the slot clock.
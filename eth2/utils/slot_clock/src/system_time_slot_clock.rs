@@ -1,15 +1,77 @@
-use super::SlotClock;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::{SlotClock, TimeSource};
+use parking_lot::RwLock;
+use std::sync::Arc;
+use std::time::Duration;
 use types::Slot;
 
 pub use std::time::SystemTimeError;
 
 /// Determines the present slot based upon the present system time.
+///
+/// The time is read from a `TimeSource`, which may be advanced manually (e.g., to run through many
+/// slots in a test) and is typically shared by all clocks in a process.
+///
+/// The clock may also be paused (e.g., to simulate a stalled node in testing), in which case it
+/// continues to report the time at which it was paused. Clones of a clock share its paused state.
 #[derive(Clone)]
 pub struct SystemTimeSlotClock {
     genesis_slot: Slot,
     genesis_duration: Duration,
     slot_duration: Duration,
+    time_source: TimeSource,
+    /// If `Some`, the clock is paused at this duration since the `UNIX_EPOCH`. Shared between
+    /// clones, but not with other clocks using the same `TimeSource`.
+    paused_at: Arc<RwLock<Option<Duration>>>,
+}
+
+impl SystemTimeSlotClock {
+    /// Reads the time from `time_source`, instead of the system time.
+    pub fn with_time_source(mut self, time_source: TimeSource) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Returns the source from which the clock reads the time.
+    pub fn time_source(&self) -> &TimeSource {
+        &self.time_source
+    }
+
+    /// Stops the clock at the present time. Has no effect if the clock is already paused.
+    pub fn pause(&self) {
+        let mut paused_at = self.paused_at.write();
+        if paused_at.is_none() {
+            *paused_at = self.time_source.now();
+        }
+    }
+
+    /// Resumes the clock, which immediately jumps forward to the present time.
+    pub fn resume(&self) {
+        *self.paused_at.write() = None;
+    }
+
+    /// Returns `true` if the clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.read().is_some()
+    }
+
+    /// Returns the slot at `time` (a duration since the `UNIX_EPOCH`), or `None` if `time` is
+    /// before genesis.
+    ///
+    /// Computed with millisecond precision.
+    fn slot_of(&self, time: Duration) -> Option<Slot> {
+        let since_genesis = time.checked_sub(self.genesis_duration)?;
+        let slot = (since_genesis.as_millis() / self.slot_duration.as_millis()) as u64;
+
+        Some(self.genesis_slot + slot)
+    }
+
+    /// Returns the duration since the `UNIX_EPOCH`, or the time at which the clock was paused.
+    fn time_now(&self) -> Option<Duration> {
+        match *self.paused_at.read() {
+            Some(paused_at) => Some(paused_at),
+            None => self.time_source.now(),
+        }
+    }
 }
 
 impl SlotClock for SystemTimeSlotClock {
@@ -22,78 +84,67 @@ impl SlotClock for SystemTimeSlotClock {
             genesis_slot,
             genesis_duration,
             slot_duration,
+            time_source: TimeSource::system(),
+            paused_at: Arc::new(RwLock::new(None)),
         }
     }
 
     fn now(&self) -> Option<Slot> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
-        let genesis = self.genesis_duration;
-
-        if now >= genesis {
-            let since_genesis = now
-                .checked_sub(genesis)
-                .expect("Control flow ensures now is greater than or equal to genesis");
-            let slot =
-                Slot::from((since_genesis.as_millis() / self.slot_duration.as_millis()) as u64);
-            Some(slot + self.genesis_slot)
-        } else {
-            None
-        }
+        self.slot_of(self.time_now()?)
     }
 
-    fn duration_to_next_slot(&self) -> Option<Duration> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
-        let genesis = self.genesis_duration;
+    fn now_with_future_tolerance(&self, tolerance: Duration) -> Option<Slot> {
+        self.slot_of(self.time_now()?.checked_add(tolerance)?)
+    }
 
-        let slot_start = |slot: Slot| -> Duration {
-            let slot = slot.as_u64() as u32;
-            genesis + slot * self.slot_duration
-        };
+    fn now_with_past_tolerance(&self, tolerance: Duration) -> Option<Slot> {
+        // A clock which is behind may not have reached genesis yet, in which case it is at the
+        // genesis slot (as a node which has not reached genesis would not be sending messages).
+        self.time_now()?
+            .checked_sub(tolerance)
+            .and_then(|time| self.slot_of(time))
+            .or_else(|| self.now().map(|_| self.genesis_slot))
+    }
 
-        if now >= genesis {
-            Some(
-                slot_start(self.now()? + 1)
-                    .checked_sub(now)
-                    .expect("The next slot cannot start before now"),
-            )
-        } else {
-            Some(
-                genesis
-                    .checked_sub(now)
-                    .expect("Control flow ensures genesis is greater than or equal to now"),
-            )
-        }
+    fn now_duration(&self) -> Option<Duration> {
+        self.time_now()
     }
 
-    fn duration_to_next_epoch(&self, slots_per_epoch: u64) -> Option<Duration> {
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
-        let genesis = self.genesis_duration;
+    fn start_of(&self, slot: Slot) -> Option<Duration> {
+        let slots_since_genesis = slot.as_u64().checked_sub(self.genesis_slot.as_u64())?;
 
-        let slot_start = |slot: Slot| -> Duration {
-            let slot = slot.as_u64() as u32;
-            genesis + slot * self.slot_duration
-        };
+        (self.slot_duration.as_millis() as u64)
+            .checked_mul(slots_since_genesis)
+            .map(Duration::from_millis)?
+            .checked_add(self.genesis_duration)
+    }
 
-        let epoch_start_slot = self
-            .now()
-            .map(|slot| slot.epoch(slots_per_epoch))
-            .map(|epoch| (epoch + 1).start_slot(slots_per_epoch))?;
+    fn duration_to_next_slot(&self) -> Option<Duration> {
+        let now = self.time_now()?;
 
-        if now >= genesis {
-            Some(
-                slot_start(epoch_start_slot)
-                    .checked_sub(now)
-                    .expect("The next epoch cannot start before now"),
-            )
-        } else {
-            Some(
-                genesis
+        match self.now() {
+            Some(slot) => self.start_of(slot + 1)?.checked_sub(now),
+            None => self.genesis_duration.checked_sub(now),
+        }
+    }
+
+    fn duration_to_next_epoch(&self, slots_per_epoch: u64) -> Option<Duration> {
+        let now = self.time_now()?;
+
+        match self.now() {
+            Some(slot) => {
+                let next_epoch = slot.epoch(slots_per_epoch) + 1;
+                self.start_of(next_epoch.start_slot(slots_per_epoch))?
                     .checked_sub(now)
-                    .expect("Control flow ensures genesis is greater than or equal to now"),
-            )
+            }
+            None => self.genesis_duration.checked_sub(now),
         }
     }
 
+    fn duration_since_start_of(&self, slot: Slot) -> Option<Duration> {
+        self.time_now()?.checked_sub(self.start_of(slot)?)
+    }
+
     fn slot_duration(&self) -> Duration {
         self.slot_duration
     }
@@ -102,6 +153,7 @@ impl SlotClock for SystemTimeSlotClock {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     /*
      * Note: these tests are using actual system times and could fail if they are executed on a
@@ -137,6 +189,138 @@ mod tests {
         assert!(clock.duration_to_next_slot().unwrap() <= Duration::from_millis(500));
     }
 
+    #[test]
+    fn pause_and_resume() {
+        let genesis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("should get system time")
+            - Duration::from_millis(2_500);
+        let clock = SystemTimeSlotClock::new(Slot::new(0), genesis, Duration::from_millis(1_000));
+        let clone = clock.clone();
+
+        clock.pause();
+        assert!(clone.is_paused(), "clones should share the paused state");
+        assert_eq!(clone.now(), Some(Slot::new(2)));
+
+        // The paused clock should not advance, even once the next slot has begun.
+        let to_next_slot = clock.duration_to_next_slot().expect("should get duration");
+        std::thread::sleep(to_next_slot + Duration::from_millis(100));
+        assert_eq!(clock.now(), Some(Slot::new(2)));
+        assert_eq!(clock.duration_to_next_slot(), Some(to_next_slot));
+
+        clone.resume();
+        assert!(!clock.is_paused());
+        assert_eq!(clock.now(), Some(Slot::new(3)));
+    }
+
+    #[test]
+    fn manual_time_source() {
+        let genesis = Duration::from_secs(1_000);
+        let slot_duration = Duration::from_secs(6);
+        let time_source = TimeSource::manual(genesis);
+        let clock = SystemTimeSlotClock::new(Slot::new(0), genesis, slot_duration)
+            .with_time_source(time_source.clone());
+
+        assert_eq!(clock.now(), Some(Slot::new(0)));
+        assert_eq!(clock.duration_to_next_slot(), Some(slot_duration));
+
+        time_source.advance(slot_duration * 100 + Duration::from_secs(1));
+        assert_eq!(clock.now(), Some(Slot::new(100)));
+        assert_eq!(
+            clock.duration_to_next_slot(),
+            Some(slot_duration - Duration::from_secs(1))
+        );
+
+        clock.pause();
+        time_source.advance(slot_duration * 7_100);
+        assert_eq!(clock.now(), Some(Slot::new(100)));
+        clock.resume();
+        assert_eq!(clock.now(), Some(Slot::new(7_200)));
+    }
+
+    #[test]
+    fn duration_since_start_of() {
+        let genesis_slot = Slot::new(0);
+        let genesis_duration = Duration::from_secs(100);
+        let slot_duration = Duration::from_secs(6);
+        let time_source = TimeSource::manual(Duration::from_secs(115));
+        let clock = SystemTimeSlotClock::new(genesis_slot, genesis_duration, slot_duration)
+            .with_time_source(time_source);
+
+        assert_eq!(
+            clock.duration_since_start_of(Slot::new(0)),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            clock.duration_since_start_of(Slot::new(2)),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(clock.duration_since_start_of(Slot::new(3)), None);
+    }
+
+    #[test]
+    fn millisecond_slots() {
+        let genesis = Duration::from_secs(1_000);
+        let slot_duration = Duration::from_millis(1_500);
+        let time_source = TimeSource::manual(genesis + Duration::from_millis(4_600));
+        let clock = SystemTimeSlotClock::new(Slot::new(0), genesis, slot_duration)
+            .with_time_source(time_source);
+
+        assert_eq!(clock.now(), Some(Slot::new(3)));
+        assert_eq!(
+            clock.duration_to_next_slot(),
+            Some(Duration::from_millis(1_400))
+        );
+        assert_eq!(
+            clock.duration_to_next_epoch(8),
+            Some(Duration::from_millis(7_400))
+        );
+        assert_eq!(
+            clock.start_of(Slot::new(4)),
+            Some(genesis + Duration::from_millis(6_000))
+        );
+        assert_eq!(
+            clock.duration_to_slot(Slot::new(5)),
+            Some(Duration::from_millis(2_900))
+        );
+        assert_eq!(clock.duration_to_slot(Slot::new(3)), None);
+    }
+
+    #[test]
+    fn clock_skew_tolerance() {
+        let genesis = Duration::from_secs(1_000);
+        let slot_duration = Duration::from_secs(6);
+        let time_source = TimeSource::manual(genesis + Duration::from_millis(5_800));
+        let clock = SystemTimeSlotClock::new(Slot::new(0), genesis, slot_duration)
+            .with_time_source(time_source.clone());
+        let tolerance = Duration::from_millis(500);
+
+        assert_eq!(clock.now(), Some(Slot::new(0)));
+        assert_eq!(
+            clock.now_with_future_tolerance(tolerance),
+            Some(Slot::new(1))
+        );
+        assert_eq!(clock.now_with_past_tolerance(tolerance), Some(Slot::new(0)));
+
+        time_source.set(genesis + Duration::from_millis(6_200));
+        assert_eq!(clock.now(), Some(Slot::new(1)));
+        assert_eq!(clock.now_with_past_tolerance(tolerance), Some(Slot::new(0)));
+
+        // Before genesis, a clock which is ahead may have started, but one which is behind has
+        // not.
+        time_source.set(genesis - Duration::from_millis(200));
+        assert_eq!(clock.now(), None);
+        assert_eq!(
+            clock.now_with_future_tolerance(tolerance),
+            Some(Slot::new(0))
+        );
+        assert_eq!(clock.now_with_past_tolerance(tolerance), None);
+
+        // Just after genesis, a clock which is behind is still at the genesis slot.
+        time_source.set(genesis + Duration::from_millis(200));
+        assert_eq!(clock.now_with_past_tolerance(tolerance), Some(Slot::new(0)));
+    }
+
     #[test]
     #[should_panic]
     fn zero_seconds() {
@@ -32,6 +32,26 @@ impl SlotClock for TestingSlotClock {
         Some(slot)
     }
 
+    /// Ignores the tolerance, since the clock is not based on time.
+    fn now_with_future_tolerance(&self, _tolerance: Duration) -> Option<Slot> {
+        self.now()
+    }
+
+    /// Ignores the tolerance, since the clock is not based on time.
+    fn now_with_past_tolerance(&self, _tolerance: Duration) -> Option<Slot> {
+        self.now()
+    }
+
+    /// Always returns `None`, since the clock is not based on time.
+    fn now_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Always returns `None`, since the clock is not based on time.
+    fn start_of(&self, _slot: Slot) -> Option<Duration> {
+        None
+    }
+
     /// Always returns a duration of 1 second.
     fn duration_to_next_slot(&self) -> Option<Duration> {
         Some(Duration::from_secs(1))
@@ -42,6 +62,15 @@ impl SlotClock for TestingSlotClock {
         Some(Duration::from_secs(slots_per_epoch))
     }
 
+    /// Returns a duration of 0 seconds for any slot which is not after the present slot.
+    fn duration_since_start_of(&self, slot: Slot) -> Option<Duration> {
+        if slot <= self.now()? {
+            Some(Duration::from_secs(0))
+        } else {
+            None
+        }
+    }
+
     /// Always returns a slot duration of 0 seconds.
     fn slot_duration(&self) -> Duration {
         Duration::from_secs(0)
@@ -0,0 +1,94 @@
+use parking_lot::RwLock;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Provides the present time to a `SystemTimeSlotClock`.
+///
+/// By default, this is the system time. Alternatively, the time may be frozen and then advanced
+/// manually, allowing tests to progress through many slots without waiting for them to pass.
+///
+/// Clones share the same time, so a single `TimeSource` may be given to each service in a process
+/// (e.g., via the `RuntimeContext`) and advanced from one place.
+#[derive(Clone, Default)]
+pub struct TimeSource {
+    /// The manually-set duration since the `UNIX_EPOCH`. If `None`, the system time is used.
+    manual_time: Arc<RwLock<Option<Duration>>>,
+}
+
+impl TimeSource {
+    /// Returns a source which follows the system time.
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    /// Returns a source which is frozen at `time` (a duration since the `UNIX_EPOCH`) until it is
+    /// advanced.
+    pub fn manual(time: Duration) -> Self {
+        Self {
+            manual_time: Arc::new(RwLock::new(Some(time))),
+        }
+    }
+
+    /// Returns the present time, as a duration since the `UNIX_EPOCH`.
+    pub fn now(&self) -> Option<Duration> {
+        match *self.manual_time.read() {
+            Some(time) => Some(time),
+            None => SystemTime::now().duration_since(UNIX_EPOCH).ok(),
+        }
+    }
+
+    /// Returns `true` if the time is frozen (i.e., it only changes when it is set or advanced).
+    pub fn is_frozen(&self) -> bool {
+        self.manual_time.read().is_some()
+    }
+
+    /// Freezes the time at the present time. Has no effect if the time is already frozen.
+    pub fn freeze(&self) {
+        let mut manual_time = self.manual_time.write();
+        if manual_time.is_none() {
+            *manual_time = SystemTime::now().duration_since(UNIX_EPOCH).ok();
+        }
+    }
+
+    /// Returns to following the system time.
+    pub fn unfreeze(&self) {
+        *self.manual_time.write() = None;
+    }
+
+    /// Freezes the time at `time`, which may be in the past or future.
+    pub fn set(&self, time: Duration) {
+        *self.manual_time.write() = Some(time);
+    }
+
+    /// Freezes the time (if it is not already frozen) then advances it by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.freeze();
+        if let Some(time) = self.manual_time.write().as_mut() {
+            *time += duration;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_time() {
+        let source = TimeSource::manual(Duration::from_secs(100));
+        let clone = source.clone();
+
+        assert!(source.is_frozen());
+        assert_eq!(source.now(), Some(Duration::from_secs(100)));
+
+        clone.advance(Duration::from_secs(12));
+        assert_eq!(source.now(), Some(Duration::from_secs(112)));
+
+        clone.set(Duration::from_secs(50));
+        assert_eq!(source.now(), Some(Duration::from_secs(50)));
+
+        source.unfreeze();
+        assert!(!clone.is_frozen());
+        assert!(clone.now() > Some(Duration::from_secs(50)));
+    }
+}
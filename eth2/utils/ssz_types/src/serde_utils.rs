@@ -0,0 +1,87 @@
+//! Serde helpers for lists of `u64`, which are serialized as lists of decimal strings (see the
+//! `serde_utils` crate).
+
+/// For use with `#[serde(with = "ssz_types::serde_utils::quoted_u64_fixed_vec")]`.
+pub mod quoted_u64_fixed_vec {
+    use crate::FixedVector;
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+    use typenum::Unsigned;
+
+    pub fn serialize<S, N>(values: &FixedVector<u64, N>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        N: Unsigned,
+    {
+        ::serde_utils::quoted_u64_vec::serialize(&values[..], serializer)
+    }
+
+    pub fn deserialize<'de, D, N>(deserializer: D) -> Result<FixedVector<u64, N>, D::Error>
+    where
+        D: Deserializer<'de>,
+        N: Unsigned,
+    {
+        let vec = ::serde_utils::quoted_u64_vec::deserialize(deserializer)?;
+        FixedVector::new(vec).map_err(|e| D::Error::custom(format!("{:?}", e)))
+    }
+}
+
+/// For use with `#[serde(with = "ssz_types::serde_utils::quoted_u64_var_list")]`.
+pub mod quoted_u64_var_list {
+    use crate::VariableList;
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+    use typenum::Unsigned;
+
+    pub fn serialize<S, N>(values: &VariableList<u64, N>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        N: Unsigned,
+    {
+        ::serde_utils::quoted_u64_vec::serialize(&values[..], serializer)
+    }
+
+    pub fn deserialize<'de, D, N>(deserializer: D) -> Result<VariableList<u64, N>, D::Error>
+    where
+        D: Deserializer<'de>,
+        N: Unsigned,
+    {
+        let vec = ::serde_utils::quoted_u64_vec::deserialize(deserializer)?;
+        VariableList::new(vec).map_err(|e| D::Error::custom(format!("{:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{typenum::U2, FixedVector, VariableList};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Lists {
+        #[serde(with = "super::quoted_u64_fixed_vec")]
+        fixed: FixedVector<u64, U2>,
+        #[serde(with = "super::quoted_u64_var_list")]
+        variable: VariableList<u64, U2>,
+    }
+
+    #[test]
+    fn round_trips() {
+        let lists = Lists {
+            fixed: FixedVector::new(vec![1, 2]).unwrap(),
+            variable: VariableList::new(vec![3]).unwrap(),
+        };
+        let yaml = serde_yaml::to_string(&lists).unwrap();
+
+        assert_eq!(serde_yaml::from_str::<Lists>(&yaml).unwrap(), lists);
+        assert_eq!(
+            serde_yaml::from_str::<Lists>("fixed: [1, 2]\nvariable: [3]").unwrap(),
+            lists
+        );
+    }
+
+    #[test]
+    fn checks_lengths() {
+        assert!(serde_yaml::from_str::<Lists>("fixed: [1]\nvariable: []").is_err());
+        assert!(serde_yaml::from_str::<Lists>("fixed: [1, 2]\nvariable: [1, 2, 3]").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Collects the results of `cargo bench` into a single machine-readable report, optionally
+//! comparing them against the report of an earlier run (e.g., the base of a PR).
+use clap::ArgMatches;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// A benchmark is considered to have regressed if its mean time grows by more than this
+/// percentage, unless `--threshold` is supplied.
+const DEFAULT_THRESHOLD_PERCENT: f64 = 10.0;
+
+/// The mean, median and standard deviation of a single benchmark, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Estimates {
+    mean: f64,
+    median: f64,
+    std_dev: f64,
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let criterion_dir = matches
+        .value_of("criterion-dir")
+        .ok_or_else(|| "No criterion directory supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse criterion directory: {}", e))?;
+    let threshold = matches
+        .value_of("threshold")
+        .map(|s| {
+            s.parse::<f64>()
+                .map_err(|e| format!("Failed to parse threshold: {}", e))
+        })
+        .transpose()?
+        .unwrap_or(DEFAULT_THRESHOLD_PERCENT);
+
+    let results = read_criterion_dir(&criterion_dir)?;
+
+    if results.is_empty() {
+        return Err(format!(
+            "No benchmark results found in {:?}, run `cargo bench` first",
+            criterion_dir
+        ));
+    }
+
+    info!("Found {} benchmark results", results.len());
+
+    let report = Value::Array(
+        results
+            .iter()
+            .map(|(id, estimates)| {
+                json!({
+                    "id": id,
+                    "mean_ns": estimates.mean,
+                    "median_ns": estimates.median,
+                    "std_dev_ns": estimates.std_dev,
+                })
+            })
+            .collect(),
+    );
+
+    let output = matches
+        .value_of("output")
+        .ok_or_else(|| "No output file supplied".to_string())?;
+    let file = File::create(output).map_err(|e| format!("Unable to create {}: {}", output, e))?;
+    serde_json::to_writer_pretty(file, &report)
+        .map_err(|e| format!("Unable to write report: {}", e))?;
+    info!("Wrote report to {}", output);
+
+    if let Some(baseline) = matches.value_of("baseline") {
+        let baseline = read_report(Path::new(baseline))?;
+        let regressions = compare(&results, &baseline, threshold);
+
+        if !regressions.is_empty() {
+            return Err(format!(
+                "{} benchmarks regressed by more than {}%",
+                regressions.len(),
+                threshold
+            ));
+        }
+
+        info!("No benchmarks regressed by more than {}%", threshold);
+    }
+
+    Ok(())
+}
+
+/// Logs each benchmark in `results` which is slower than in `baseline` by more than `threshold`
+/// percent, returning their IDs.
+///
+/// Benchmarks which are missing from either report are ignored.
+fn compare(
+    results: &BTreeMap<String, Estimates>,
+    baseline: &BTreeMap<String, f64>,
+    threshold: f64,
+) -> Vec<String> {
+    results
+        .iter()
+        .filter_map(|(id, estimates)| {
+            let baseline_mean = *baseline.get(id)?;
+            let change = (estimates.mean - baseline_mean) / baseline_mean * 100.0;
+
+            info!("{}: {:.2}ns ({:+.2}%)", id, estimates.mean, change);
+
+            if change > threshold {
+                warn!(
+                    "{} regressed from {:.2}ns to {:.2}ns ({:+.2}%)",
+                    id, baseline_mean, estimates.mean, change
+                );
+                Some(id.clone())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reads the mean of each benchmark from a report previously written by this command.
+fn read_report(path: &Path) -> Result<BTreeMap<String, f64>, String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+    let report: Value =
+        serde_json::from_reader(file).map_err(|e| format!("Unable to parse {:?}: {}", path, e))?;
+
+    report
+        .as_array()
+        .ok_or_else(|| format!("{:?} is not a benchmark report", path))?
+        .iter()
+        .map(|result| {
+            let id = result["id"]
+                .as_str()
+                .ok_or_else(|| format!("Benchmark in {:?} has no id", path))?;
+            let mean = result["mean_ns"]
+                .as_f64()
+                .ok_or_else(|| format!("Benchmark {} in {:?} has no mean", id, path))?;
+            Ok((id.to_string(), mean))
+        })
+        .collect()
+}
+
+/// Reads the latest results of each benchmark from the output directory of `criterion`
+/// (typically `target/criterion`).
+///
+/// `criterion` stores the results of each benchmark in a `new` directory, containing a
+/// `benchmark.json` which identifies the benchmark and an `estimates.json` with its statistics.
+fn read_criterion_dir(dir: &Path) -> Result<BTreeMap<String, Estimates>, String> {
+    let mut results = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("Unable to read {:?}: {}", dir, e))?;
+
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Unable to read entry in {:?}: {}", dir, e))?
+                .path();
+
+            if !path.is_dir() {
+                continue;
+            }
+
+            if path.file_name().map_or(false, |name| name == "new") {
+                let (id, estimates) = read_benchmark(&path)?;
+                results.insert(id, estimates);
+            } else {
+                dirs.push(path);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Reads the ID and statistics of the benchmark stored in `dir`.
+fn read_benchmark(dir: &Path) -> Result<(String, Estimates), String> {
+    let benchmark = read_json(&dir.join("benchmark.json"))?;
+    let estimates = read_json(&dir.join("estimates.json"))?;
+
+    let id = benchmark["full_id"]
+        .as_str()
+        .ok_or_else(|| format!("Benchmark in {:?} has no full_id", dir))?
+        .to_string();
+
+    // Older versions of `criterion` capitalize the names of the statistics.
+    let point_estimate = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| estimates[*name]["point_estimate"].as_f64())
+            .ok_or_else(|| format!("Benchmark {} has no {} estimate", id, names[0]))
+    };
+
+    let estimates = Estimates {
+        mean: point_estimate(&["mean", "Mean"])?,
+        median: point_estimate(&["median", "Median"])?,
+        std_dev: point_estimate(&["std_dev", "StdDev"])?,
+    };
+
+    Ok((id, estimates))
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+    serde_json::from_reader(file).map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+}
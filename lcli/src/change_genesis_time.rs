@@ -0,0 +1,79 @@
+use crate::transition_blocks::{load_from_ssz, write_to_ssz};
+use clap::ArgMatches;
+use std::path::PathBuf;
+use types::{BeaconState, EthSpec};
+
+/// Rewrites the `genesis_time` (and optionally the fork) of a state, writing the resulting state
+/// to `output`, or back to the input file if no output is supplied.
+pub fn run_change_genesis_time<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let state_path = matches
+        .value_of("ssz-state")
+        .ok_or_else(|| "No state file supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse state path: {}", e))?;
+
+    let genesis_time = matches
+        .value_of("genesis-time")
+        .ok_or_else(|| "No genesis-time supplied".to_string())?
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse genesis-time: {}", e))?;
+
+    let fork_version = matches
+        .value_of("fork-version")
+        .map(parse_fork_version)
+        .transpose()?;
+
+    let output_path = match matches.value_of("output") {
+        Some(path) => path
+            .parse::<PathBuf>()
+            .map_err(|e| format!("Failed to parse output path: {}", e))?,
+        None => state_path.clone(),
+    };
+
+    info!("State path: {:?}", state_path);
+
+    let mut state: BeaconState<T> = load_from_ssz(state_path)?;
+
+    info!("Pre-state root: {:?}", state.canonical_root());
+
+    change_genesis_time(&mut state, genesis_time, fork_version);
+
+    info!("Genesis time: {}", state.genesis_time);
+    info!("Fork: {:?}", state.fork);
+    info!("Post-state root: {:?}", state.canonical_root());
+
+    write_to_ssz(output_path, &state)
+}
+
+/// Sets the `genesis_time` of `state` and, if `fork_version` is supplied, forks it to that version
+/// at its current epoch.
+fn change_genesis_time<T: EthSpec>(
+    state: &mut BeaconState<T>,
+    genesis_time: u64,
+    fork_version: Option<[u8; 4]>,
+) {
+    state.genesis_time = genesis_time;
+
+    if let Some(fork_version) = fork_version {
+        state.fork.previous_version = state.fork.current_version;
+        state.fork.current_version = fork_version;
+        state.fork.epoch = state.current_epoch();
+    }
+}
+
+/// Parses a 4-byte fork version from a (optionally 0x-prefixed) hex string.
+fn parse_fork_version(string: &str) -> Result<[u8; 4], String> {
+    let bytes = hex::decode(string.trim_start_matches("0x"))
+        .map_err(|e| format!("Failed to parse fork-version as hex: {:?}", e))?;
+
+    if bytes.len() != 4 {
+        return Err(format!(
+            "fork-version must be 4 bytes, not {} bytes",
+            bytes.len()
+        ));
+    }
+
+    let mut fork_version = [0; 4];
+    fork_version.copy_from_slice(&bytes);
+    Ok(fork_version)
+}
@@ -33,11 +33,7 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
         .value_of("output")
         .ok_or_else(|| ())
         .and_then(|output| output.parse::<PathBuf>().map_err(|_| ()))
-        .unwrap_or_else(|_| {
-            dirs::home_dir()
-                .map(|home| home.join(".lighthouse").join("testnet"))
-                .expect("should locate home directory")
-        });
+        .unwrap_or_else(|_| lighthouse_dirs::default_dir("testnet"));
 
     let password = parse_password(matches)?;
 
@@ -69,6 +65,15 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
 
     info!("Present eth1 block number is {}", deploy_block);
 
+    let network_id = env
+        .runtime()
+        .block_on(web3.net().version())
+        .map_err(|e| format!("Failed to get network id: {}", e))?
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse network id: {}", e))?;
+
+    info!("Eth1 network id is {}", network_id);
+
     info!("Deploying the bytecode at https://github.com/sigp/unsafe-eth2-deposit-contract",);
 
     info!(
@@ -104,6 +109,8 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
         boot_enr: None,
         genesis_state: None,
         yaml_config: Some(YamlConfig::from_spec::<T>(&spec)),
+        deposit_chain_id: None,
+        deposit_network_id: Some(network_id),
     };
 
     testnet_config.write_to_file(output_dir)?;
@@ -19,26 +19,12 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
         .value_of("testnet-dir")
         .ok_or_else(|| ())
         .and_then(|dir| dir.parse::<PathBuf>().map_err(|_| ()))
-        .unwrap_or_else(|_| {
-            dirs::home_dir()
-                .map(|home| home.join(".lighthouse").join("testnet"))
-                .expect("should locate home directory")
-        });
+        .unwrap_or_else(|_| lighthouse_dirs::default_dir("testnet"));
 
     let mut eth2_testnet_config: Eth2TestnetConfig<T> =
         Eth2TestnetConfig::load(testnet_dir.clone())?;
 
-    let spec = eth2_testnet_config
-        .yaml_config
-        .as_ref()
-        .ok_or_else(|| "The testnet directory must contain a spec config".to_string())?
-        .apply_to_chain_spec::<T>(&env.core_context().eth2_config.spec)
-        .ok_or_else(|| {
-            format!(
-                "The loaded config is not compatible with the {} spec",
-                &env.core_context().eth2_config.spec_constants
-            )
-        })?;
+    let spec = eth2_testnet_config.chain_spec(&env.core_context().eth2_config.spec)?;
 
     let mut config = Eth1Config::default();
     config.endpoint = endpoint.to_string();
@@ -47,7 +33,8 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
     config.lowest_cached_block_number = eth2_testnet_config.deposit_contract_deploy_block;
     config.follow_distance = spec.eth1_follow_distance / 2;
 
-    let genesis_service = Eth1GenesisService::new(config, env.core_context().log.clone());
+    let genesis_service =
+        Eth1GenesisService::new(config, env.core_context().log.clone(), spec.clone());
 
     let future = genesis_service
         .wait_for_genesis_state(ETH1_GENESIS_UPDATE_INTERVAL, spec)
@@ -1,16 +1,27 @@
 #[macro_use]
 extern crate log;
 
+mod bench_report;
+mod change_genesis_time;
 mod deploy_deposit_contract;
 mod eth1_genesis;
+mod new_testnet;
 mod parse_hex;
+mod pretty_ssz;
 mod refund_deposit_contract;
+mod replay_gossip;
+mod skip_slots;
+mod state_diff;
 mod transition_blocks;
 
+use change_genesis_time::run_change_genesis_time;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use environment::EnvironmentBuilder;
 use log::Level;
 use parse_hex::run_parse_hex;
+use pretty_ssz::run_pretty_ssz;
+use skip_slots::run_skip_slots;
+use state_diff::run_state_diff;
 use std::fs::File;
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -88,6 +99,68 @@ fn main() {
                         .help("Path to output a SSZ file."),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("skip-slots")
+                .about("Performs a state transition of empty slots on a state")
+                .arg(
+                    Arg::with_name("pre-state")
+                        .value_name("BEACON_STATE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a SSZ file of the pre-state."),
+                )
+                .arg(
+                    Arg::with_name("num-slots")
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Number of empty slots to apply to the pre-state."),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("SSZ_FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./output.ssz")
+                        .help("Path to output a SSZ file."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("change-genesis-time")
+                .about("Rewrites the genesis time and fork of a state")
+                .arg(
+                    Arg::with_name("ssz-state")
+                        .value_name("BEACON_STATE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a SSZ file of the state."),
+                )
+                .arg(
+                    Arg::with_name("genesis-time")
+                        .value_name("UNIX_EPOCH_SECONDS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The new genesis time."),
+                )
+                .arg(
+                    Arg::with_name("fork-version")
+                        .long("fork-version")
+                        .value_name("HEX")
+                        .takes_value(true)
+                        .help(
+                            "If present, forks the state to this 4-byte version at its current \
+                            epoch, so that it is not confused with the stalled network.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("SSZ_FILE")
+                        .takes_value(true)
+                        .help("Path to output a SSZ file. Defaults to overwriting the state file."),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("pretty-hex")
                 .about("Parses SSZ encoded as ASCII 0x-prefixed hex")
@@ -96,7 +169,7 @@ fn main() {
                         .value_name("TYPE")
                         .takes_value(true)
                         .required(true)
-                        .possible_values(&["block"])
+                        .possible_values(pretty_ssz::TYPES)
                         .help("The schema of the supplied SSZ."),
                 )
                 .arg(
@@ -107,11 +180,58 @@ fn main() {
                         .help("SSZ encoded as 0x-prefixed hex"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("pretty-ssz")
+                .about("Parses a file of SSZ bytes and prints it as YAML or JSON")
+                .arg(
+                    Arg::with_name("type")
+                        .value_name("TYPE")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(pretty_ssz::TYPES)
+                        .help("The schema of the supplied SSZ."),
+                )
+                .arg(
+                    Arg::with_name("ssz-file")
+                        .value_name("SSZ_FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a file of SSZ bytes."),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["yaml", "json"])
+                        .default_value("yaml")
+                        .help("The output format."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("state-diff")
+                .about("Prints the fields which differ between two BeaconStates")
+                .arg(
+                    Arg::with_name("state-a")
+                        .value_name("BEACON_STATE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a SSZ file of the first state."),
+                )
+                .arg(
+                    Arg::with_name("state-b")
+                        .value_name("BEACON_STATE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to a SSZ file of the second state."),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("deploy-deposit-contract")
                 .about(
-                    "Deploy an eth1 deposit contract and create a ~/.lighthouse/testnet directory \
-                    (unless another directory is specified).",
+                    "Deploy an eth1 deposit contract and create a `testnet` directory in the \
+                    Lighthouse data directory (unless another directory is specified).",
                 )
                 .arg(
                     Arg::with_name("output")
@@ -119,7 +239,7 @@ fn main() {
                         .long("output")
                         .value_name("PATH")
                         .takes_value(true)
-                        .help("The output directory. Defaults to ~/.lighthouse/testnet"),
+                        .help("The output directory. Defaults to `testnet` in the Lighthouse data directory"),
                 )
                 .arg(
                     Arg::with_name("min-genesis-time")
@@ -175,7 +295,7 @@ fn main() {
                         .long("testnet-dir")
                         .value_name("PATH")
                         .takes_value(true)
-                        .help("The testnet dir. Defaults to ~/.lighthouse/testnet"),
+                        .help("The testnet dir. Defaults to `testnet` in the Lighthouse data directory"),
                 )
                 .arg(
                     Arg::with_name("eth1-endpoint")
@@ -214,7 +334,7 @@ fn main() {
                         .long("testnet-dir")
                         .value_name("PATH")
                         .takes_value(true)
-                        .help("The testnet dir. Defaults to ~/.lighthouse/testnet"),
+                        .help("The testnet dir. Defaults to `testnet` in the Lighthouse data directory"),
                 )
                 .arg(
                     Arg::with_name("eth1-endpoint")
@@ -226,6 +346,185 @@ fn main() {
                         .help("The URL to the eth1 JSON-RPC http API."),
                 )
         )
+        .subcommand(
+            SubCommand::with_name("new-testnet")
+                .about(
+                    "Produces a testnet directory which may be used by the beacon node via \
+                    --testnet-dir.",
+                )
+                .arg(
+                    Arg::with_name("testnet-dir")
+                        .short("d")
+                        .long("testnet-dir")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("The output directory. Defaults to `testnet` in the Lighthouse data directory"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("Overwrite any existing files in the testnet directory."),
+                )
+                .arg(
+                    Arg::with_name("min-genesis-time")
+                        .long("min-genesis-time")
+                        .value_name("UNIX_SECONDS")
+                        .takes_value(true)
+                        .help("The MIN_GENESIS_TIME constant."),
+                )
+                .arg(
+                    Arg::with_name("min-genesis-active-validator-count")
+                        .long("min-genesis-active-validator-count")
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .help("The MIN_GENESIS_ACTIVE_VALIDATOR_COUNT constant."),
+                )
+                .arg(
+                    Arg::with_name("milliseconds-per-slot")
+                        .long("milliseconds-per-slot")
+                        .value_name("MILLISECONDS")
+                        .takes_value(true)
+                        .help("The slot duration, in milliseconds."),
+                )
+                .arg(
+                    Arg::with_name("eth1-follow-distance")
+                        .long("eth1-follow-distance")
+                        .value_name("ETH1_BLOCKS")
+                        .takes_value(true)
+                        .help("The ETH1_FOLLOW_DISTANCE constant."),
+                )
+                .arg(
+                    Arg::with_name("deposit-contract-address")
+                        .long("deposit-contract-address")
+                        .value_name("ETH1_ADDRESS")
+                        .takes_value(true)
+                        .help("The address of the deposit contract. Defaults to the zero address."),
+                )
+                .arg(
+                    Arg::with_name("deposit-contract-deploy-block")
+                        .long("deposit-contract-deploy-block")
+                        .value_name("ETH1_BLOCK_NUMBER")
+                        .takes_value(true)
+                        .help("The block at which the deposit contract was deployed. Defaults to 0."),
+                )
+                .arg(
+                    Arg::with_name("deposit-chain-id")
+                        .long("deposit-chain-id")
+                        .value_name("CHAIN_ID")
+                        .takes_value(true)
+                        .help("The chain ID of the eth1 network of the deposit contract. If \
+                               supplied, beacon nodes refuse eth1 nodes on any other chain."),
+                )
+                .arg(
+                    Arg::with_name("deposit-network-id")
+                        .long("deposit-network-id")
+                        .value_name("NETWORK_ID")
+                        .takes_value(true)
+                        .help("The network ID of the eth1 network of the deposit contract. If \
+                               supplied, beacon nodes refuse eth1 nodes on any other network."),
+                )
+                .arg(
+                    Arg::with_name("boot-enr")
+                        .long("boot-enr")
+                        .value_name("ENR")
+                        .takes_value(true)
+                        .multiple(true)
+                        .help("The ENR of a boot node. May be supplied multiple times."),
+                )
+                .arg(
+                    Arg::with_name("mnemonic-phrase")
+                        .long("mnemonic-phrase")
+                        .value_name("MNEMONIC_PHRASE")
+                        .takes_value(true)
+                        .requires("validator-count")
+                        .help(
+                            "The BIP-39 mnemonic of the wallet from which the genesis validators \
+                            are derived. If not supplied, no genesis state is produced and the \
+                            beacon node will find genesis from the deposit contract.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("validator-count")
+                        .long("validator-count")
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .requires("mnemonic-phrase")
+                        .help("The number of genesis validators to derive from the mnemonic."),
+                )
+                .arg(
+                    Arg::with_name("genesis-time")
+                        .long("genesis-time")
+                        .value_name("UNIX_SECONDS")
+                        .takes_value(true)
+                        .requires("mnemonic-phrase")
+                        .help("The genesis time of the genesis state. Defaults to now."),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("replay-gossip")
+                .about(
+                    "Replays a log of gossip messages recorded by a beacon node with \
+                    --record-gossip against a fresh chain, started from the genesis state of \
+                    the testnet.",
+                )
+                .arg(
+                    Arg::with_name("gossip-log")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the gossip log."),
+                )
+                .arg(
+                    Arg::with_name("testnet-dir")
+                        .short("d")
+                        .long("testnet-dir")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("The testnet dir. Defaults to `testnet` in the Lighthouse data directory"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("bench-report")
+                .about(
+                    "Collects the results of `cargo bench` into a JSON report, optionally \
+                    failing if any benchmark has regressed against an earlier report.",
+                )
+                .arg(
+                    Arg::with_name("criterion-dir")
+                        .long("criterion-dir")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .default_value("./target/criterion")
+                        .help("The directory in which criterion stores benchmark results."),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("JSON_FILE")
+                        .takes_value(true)
+                        .default_value("./bench_report.json")
+                        .help("Path to output the JSON report."),
+                )
+                .arg(
+                    Arg::with_name("baseline")
+                        .long("baseline")
+                        .value_name("JSON_FILE")
+                        .takes_value(true)
+                        .help("A report from an earlier run to compare the results against."),
+                )
+                .arg(
+                    Arg::with_name("threshold")
+                        .long("threshold")
+                        .value_name("PERCENT")
+                        .takes_value(true)
+                        .requires("baseline")
+                        .help(
+                            "The percentage by which a benchmark may slow down relative to the \
+                            baseline before it is considered a regression. Defaults to 10.",
+                        ),
+                )
+        )
         .get_matches();
 
     macro_rules! run_with_spec {
@@ -294,8 +593,18 @@ fn run<T: EthSpec>(env_builder: EnvironmentBuilder<T>, matches: &ArgMatches) {
         }
         ("transition-blocks", Some(matches)) => run_transition_blocks::<T>(matches)
             .unwrap_or_else(|e| error!("Failed to transition blocks: {}", e)),
+        ("skip-slots", Some(matches)) => {
+            run_skip_slots::<T>(matches).unwrap_or_else(|e| error!("Failed to skip slots: {}", e))
+        }
+        ("change-genesis-time", Some(matches)) => run_change_genesis_time::<T>(matches)
+            .unwrap_or_else(|e| error!("Failed to change genesis time: {}", e)),
         ("pretty-hex", Some(matches)) => run_parse_hex::<T>(matches)
             .unwrap_or_else(|e| error!("Failed to pretty print hex: {}", e)),
+        ("pretty-ssz", Some(matches)) => run_pretty_ssz::<T>(matches)
+            .unwrap_or_else(|e| error!("Failed to pretty print SSZ: {}", e)),
+        ("state-diff", Some(matches)) => {
+            run_state_diff::<T>(matches).unwrap_or_else(|e| error!("Failed to diff states: {}", e))
+        }
         ("deploy-deposit-contract", Some(matches)) => {
             deploy_deposit_contract::run::<T>(env, matches)
                 .unwrap_or_else(|e| error!("Failed to run deploy-deposit-contract command: {}", e))
@@ -306,6 +615,14 @@ fn run<T: EthSpec>(env_builder: EnvironmentBuilder<T>, matches: &ArgMatches) {
         }
         ("eth1-genesis", Some(matches)) => eth1_genesis::run::<T>(env, matches)
             .unwrap_or_else(|e| error!("Failed to run eth1-genesis command: {}", e)),
+        ("new-testnet", Some(matches)) => new_testnet::run::<T>(env, matches)
+            .unwrap_or_else(|e| error!("Failed to run new-testnet command: {}", e)),
+        ("replay-gossip", Some(matches)) => replay_gossip::run::<T>(env, matches)
+            .unwrap_or_else(|e| error!("Failed to run replay-gossip command: {}", e)),
+        ("bench-report", Some(matches)) => bench_report::run(matches).unwrap_or_else(|e| {
+            error!("Failed to run bench-report command: {}", e);
+            std::process::exit(1)
+        }),
         (other, _) => error!("Unknown subcommand {}. See --help.", other),
     }
 }
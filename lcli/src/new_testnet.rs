@@ -0,0 +1,144 @@
+use clap::ArgMatches;
+use environment::Environment;
+use eth2_libp2p::address::parse_enr;
+use eth2_testnet_config::Eth2TestnetConfig;
+use eth2_wallet::{
+    bip39::{Language, Mnemonic, Seed as Bip39Seed},
+    keypair_from_seed, KeyType, ValidatorPath,
+};
+use genesis::interop_genesis_state;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use types::{Address, EthSpec, Keypair, YamlConfig};
+
+pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<(), String> {
+    let testnet_dir = matches
+        .value_of("testnet-dir")
+        .ok_or_else(|| ())
+        .and_then(|dir| dir.parse::<PathBuf>().map_err(|_| ()))
+        .unwrap_or_else(|_| lighthouse_dirs::default_dir("testnet"));
+
+    let overwrite_files = matches.is_present("force");
+
+    if testnet_dir.exists() && !overwrite_files {
+        return Err(format!(
+            "{:?} already exists, use --force to overwrite",
+            testnet_dir
+        ));
+    }
+
+    let mut spec = env.core_context().eth2_config.spec.clone();
+
+    if let Some(value) = parse_optional::<u64>(matches, "min-genesis-time")? {
+        spec.min_genesis_time = value;
+    }
+    if let Some(value) = parse_optional::<u64>(matches, "min-genesis-active-validator-count")? {
+        spec.min_genesis_active_validator_count = value;
+    }
+    if let Some(value) = parse_optional::<u64>(matches, "milliseconds-per-slot")? {
+        spec.milliseconds_per_slot = value;
+    }
+    if let Some(value) = parse_optional::<u64>(matches, "eth1-follow-distance")? {
+        spec.eth1_follow_distance = value;
+    }
+
+    let deposit_contract_address = matches
+        .value_of("deposit-contract-address")
+        .map(|address| {
+            address
+                .trim_start_matches("0x")
+                .parse::<Address>()
+                .map_err(|e| format!("Unable to parse --deposit-contract-address: {:?}", e))
+        })
+        .transpose()?
+        .unwrap_or_else(Address::zero);
+    let deposit_contract_deploy_block =
+        parse_optional::<u64>(matches, "deposit-contract-deploy-block")?.unwrap_or(0);
+    let deposit_chain_id = parse_optional::<u64>(matches, "deposit-chain-id")?;
+    let deposit_network_id = parse_optional::<u64>(matches, "deposit-network-id")?;
+
+    let boot_enr = matches
+        .values_of("boot-enr")
+        .map(|values| {
+            values
+                .map(parse_enr)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Unable to parse --boot-enr: {}", e))
+        })
+        .transpose()?;
+
+    let genesis_state = if let Some(phrase) = matches.value_of("mnemonic-phrase") {
+        let validator_count = parse_optional::<usize>(matches, "validator-count")?
+            .ok_or_else(|| "--validator-count is required with --mnemonic-phrase".to_string())?;
+        let genesis_time = parse_optional::<u64>(matches, "genesis-time")?.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("should obtain time since unix epoch")
+                .as_secs()
+        });
+
+        info!(
+            "Deriving {} validator keypairs from the mnemonic",
+            validator_count
+        );
+        let keypairs = keypairs_from_mnemonic(phrase, validator_count)?;
+
+        info!(
+            "Creating genesis state with {} validators and genesis time {}",
+            validator_count, genesis_time
+        );
+        let genesis_state = interop_genesis_state::<T>(&keypairs, genesis_time, &spec)?;
+        info!("Genesis state root: {:?}", genesis_state.canonical_root());
+
+        Some(genesis_state)
+    } else {
+        info!("No mnemonic supplied, genesis will be found from the deposit contract");
+        None
+    };
+
+    let testnet_config: Eth2TestnetConfig<T> = Eth2TestnetConfig {
+        deposit_contract_address: format!("{:?}", deposit_contract_address),
+        deposit_contract_deploy_block,
+        boot_enr,
+        genesis_state,
+        yaml_config: Some(YamlConfig::from_spec::<T>(&spec)),
+        deposit_chain_id,
+        deposit_network_id,
+    };
+
+    info!("Writing testnet to {:?}", testnet_dir);
+
+    testnet_config.force_write_to_file(testnet_dir)
+}
+
+/// Derives the voting keypairs for the first `count` validators of the EIP-2334 wallet with the
+/// given BIP-39 `phrase`.
+///
+/// These are the same keys as those derived by the `account_manager` from a wallet created with
+/// the same mnemonic.
+fn keypairs_from_mnemonic(phrase: &str, count: usize) -> Result<Vec<Keypair>, String> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+        .map_err(|e| format!("Invalid mnemonic: {}", e))?;
+    let seed = Bip39Seed::new(&mnemonic, "");
+
+    (0..count as u32)
+        .map(|index| {
+            keypair_from_seed(seed.as_bytes(), &ValidatorPath::new(index, KeyType::Voting))
+                .map_err(|e| format!("Unable to derive keypair {}: {:?}", index, e))
+        })
+        .collect()
+}
+
+fn parse_optional<T: std::str::FromStr>(
+    matches: &ArgMatches,
+    name: &str,
+) -> Result<Option<T>, String> {
+    matches
+        .value_of(name)
+        .map(|value| {
+            value
+                .parse::<T>()
+                .map_err(|_| format!("Unable to parse --{}: {}", name, value))
+        })
+        .transpose()
+}
@@ -1,7 +1,6 @@
+use crate::pretty_ssz::{decode_and_print_type, OutputFormat};
 use clap::ArgMatches;
-use serde::Serialize;
-use ssz::Decode;
-use types::{BeaconBlock, BeaconState, EthSpec};
+use types::EthSpec;
 
 pub fn run_parse_hex<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
     let type_str = matches
@@ -21,23 +20,5 @@ pub fn run_parse_hex<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
     info!("Using minimal spec");
     info!("Type: {:?}", type_str);
 
-    match type_str {
-        "block" => decode_and_print::<BeaconBlock<T>>(&hex)?,
-        "state" => decode_and_print::<BeaconState<T>>(&hex)?,
-        other => return Err(format!("Unknown type: {}", other)),
-    };
-
-    Ok(())
-}
-
-fn decode_and_print<T: Decode + Serialize>(bytes: &[u8]) -> Result<(), String> {
-    let item = T::from_ssz_bytes(&bytes).map_err(|e| format!("Ssz decode failed: {:?}", e))?;
-
-    println!(
-        "{}",
-        serde_yaml::to_string(&item)
-            .map_err(|e| format!("Unable to write object to YAML: {:?}", e))?
-    );
-
-    Ok(())
+    decode_and_print_type::<T>(type_str, &hex, OutputFormat::Yaml)
 }
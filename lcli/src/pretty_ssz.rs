@@ -0,0 +1,111 @@
+use clap::ArgMatches;
+use serde::Serialize;
+use ssz::Decode;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+use types::{
+    Attestation, AttesterSlashing, BeaconBlock, BeaconBlockHeader, BeaconState, Deposit, EthSpec,
+    IndexedAttestation, ProposerSlashing, SignedAggregateAndProof, VoluntaryExit,
+};
+
+/// The names of the types which may be pretty-printed.
+pub const TYPES: &[&str] = &[
+    "block",
+    "block_header",
+    "state",
+    "attestation",
+    "indexed_attestation",
+    "signed_aggregate_and_proof",
+    "attester_slashing",
+    "proposer_slashing",
+    "deposit",
+    "voluntary_exit",
+];
+
+/// The format in which a decoded object is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Yaml,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, String> {
+        match string {
+            "yaml" => Ok(OutputFormat::Yaml),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("Unknown output format: {}", other)),
+        }
+    }
+}
+
+pub fn run_pretty_ssz<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let type_str = matches
+        .value_of("type")
+        .ok_or_else(|| "No type supplied".to_string())?;
+    let path = matches
+        .value_of("ssz-file")
+        .ok_or_else(|| "No SSZ file supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse SSZ file path: {}", e))?;
+    let format = matches
+        .value_of("format")
+        .ok_or_else(|| "No output format supplied".to_string())?
+        .parse::<OutputFormat>()?;
+
+    info!("Type: {:?}", type_str);
+    info!("SSZ file path: {:?}", path);
+
+    let mut file =
+        File::open(path.clone()).map_err(|e| format!("Unable to open file {:?}: {:?}", path, e))?;
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)
+        .map_err(|e| format!("Unable to read from file {:?}: {:?}", path, e))?;
+
+    decode_and_print_type::<T>(type_str, &bytes, format)
+}
+
+/// Decodes `bytes` as the type with the name `type_str` (see `TYPES`) and prints it to stdout.
+pub fn decode_and_print_type<T: EthSpec>(
+    type_str: &str,
+    bytes: &[u8],
+    format: OutputFormat,
+) -> Result<(), String> {
+    match type_str {
+        "block" => decode_and_print::<BeaconBlock<T>>(bytes, format),
+        "block_header" => decode_and_print::<BeaconBlockHeader>(bytes, format),
+        "state" => decode_and_print::<BeaconState<T>>(bytes, format),
+        "attestation" => decode_and_print::<Attestation<T>>(bytes, format),
+        "indexed_attestation" => decode_and_print::<IndexedAttestation<T>>(bytes, format),
+        "signed_aggregate_and_proof" => {
+            decode_and_print::<SignedAggregateAndProof<T>>(bytes, format)
+        }
+        "attester_slashing" => decode_and_print::<AttesterSlashing<T>>(bytes, format),
+        "proposer_slashing" => decode_and_print::<ProposerSlashing>(bytes, format),
+        "deposit" => decode_and_print::<Deposit>(bytes, format),
+        "voluntary_exit" => decode_and_print::<VoluntaryExit>(bytes, format),
+        other => Err(format!("Unknown type: {}", other)),
+    }
+}
+
+fn decode_and_print<T: Decode + Serialize>(
+    bytes: &[u8],
+    format: OutputFormat,
+) -> Result<(), String> {
+    let item = T::from_ssz_bytes(&bytes).map_err(|e| format!("Ssz decode failed: {:?}", e))?;
+
+    let string = match format {
+        OutputFormat::Yaml => serde_yaml::to_string(&item)
+            .map_err(|e| format!("Unable to write object to YAML: {:?}", e))?,
+        OutputFormat::Json => serde_json::to_string_pretty(&item)
+            .map_err(|e| format!("Unable to write object to JSON: {:?}", e))?,
+    };
+
+    println!("{}", string);
+
+    Ok(())
+}
@@ -31,11 +31,7 @@ pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<
         .value_of("testnet-dir")
         .ok_or_else(|| ())
         .and_then(|dir| dir.parse::<PathBuf>().map_err(|_| ()))
-        .unwrap_or_else(|_| {
-            dirs::home_dir()
-                .map(|home| home.join(".lighthouse").join("testnet"))
-                .expect("should locate home directory")
-        });
+        .unwrap_or_else(|_| lighthouse_dirs::default_dir("testnet"));
 
     let eth2_testnet_config: Eth2TestnetConfig<T> = Eth2TestnetConfig::load(testnet_dir)?;
 
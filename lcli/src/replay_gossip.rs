@@ -0,0 +1,84 @@
+use beacon_chain::builder::BeaconChainBuilder;
+use beacon_chain::store::{migrate::NullMigrator, MemoryStore};
+use beacon_chain::test_utils::HarnessType;
+use clap::ArgMatches;
+use environment::Environment;
+use eth2_testnet_config::Eth2TestnetConfig;
+use network::{read_gossip_log, replay_gossip};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use types::EthSpec;
+
+/// Replays a log recorded with `beacon_node --record-gossip` against a fresh chain, started from
+/// the genesis state of the testnet.
+///
+/// The slot clock of the chain is set to the slot in which each message was received before the
+/// message is processed, so that each replay of a log produces the same chain.
+pub fn run<T: EthSpec>(mut env: Environment<T>, matches: &ArgMatches) -> Result<(), String> {
+    let log_path = matches
+        .value_of("gossip-log")
+        .ok_or_else(|| "No gossip log supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse gossip log path: {}", e))?;
+
+    let testnet_dir = matches
+        .value_of("testnet-dir")
+        .ok_or_else(|| ())
+        .and_then(|dir| dir.parse::<PathBuf>().map_err(|_| ()))
+        .unwrap_or_else(|_| lighthouse_dirs::default_dir("testnet"));
+
+    let context = env.core_context();
+    let eth2_testnet_config: Eth2TestnetConfig<T> = Eth2TestnetConfig::load(testnet_dir)?;
+    let spec = eth2_testnet_config.chain_spec(&context.eth2_config.spec)?;
+    let genesis_state = eth2_testnet_config
+        .genesis_state
+        .ok_or_else(|| "The testnet dir does not contain a genesis state".to_string())?;
+    let genesis_time = genesis_state.genesis_time;
+
+    let records = read_gossip_log(&log_path)?;
+    info!(
+        "Replaying {} gossip messages from {:?}",
+        records.len(),
+        log_path
+    );
+
+    let chain = BeaconChainBuilder::<HarnessType<T>>::new(T::default())
+        .logger(context.log.clone())
+        .custom_spec(spec.clone())
+        .store(Arc::new(MemoryStore::open()))
+        .store_migrator(NullMigrator)
+        .genesis_state(genesis_state)?
+        .no_eth1_backend()
+        .null_event_handler()
+        .testing_slot_clock(Duration::from_millis(spec.milliseconds_per_slot))?
+        .reduced_tree_fork_choice()?
+        .build()?;
+    let chain = Arc::new(chain);
+
+    let slot_chain = chain.clone();
+    let records = records.into_iter().inspect(move |record| {
+        let slots_since_genesis =
+            record.timestamp.saturating_sub(genesis_time * 1_000) / spec.milliseconds_per_slot;
+        slot_chain
+            .slot_clock
+            .set_slot(spec.genesis_slot.as_u64() + slots_since_genesis);
+    });
+
+    let processed = replay_gossip(
+        chain.clone(),
+        records,
+        &context.executor,
+        context.log.clone(),
+    );
+
+    let head = chain.head_info();
+    info!("Processed {} gossip messages", processed);
+    info!("Head slot: {}, root: {:?}", head.slot, head.block_root);
+    info!(
+        "Finalized epoch: {}, root: {:?}",
+        head.finalized_checkpoint.epoch, head.finalized_checkpoint.root
+    );
+
+    Ok(())
+}
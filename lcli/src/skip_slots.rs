@@ -0,0 +1,61 @@
+use crate::transition_blocks::{load_from_ssz, write_to_ssz};
+use clap::ArgMatches;
+use state_processing::per_slot_processing;
+use std::path::PathBuf;
+use std::time::Instant;
+use types::{BeaconState, EthSpec};
+
+/// Applies `num-slots` empty slots to a state, writing the resulting state to `output`.
+pub fn run_skip_slots<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let pre_state_path = matches
+        .value_of("pre-state")
+        .ok_or_else(|| "No pre-state file supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse pre-state path: {}", e))?;
+
+    let num_slots = matches
+        .value_of("num-slots")
+        .ok_or_else(|| "No num-slots supplied".to_string())?
+        .parse::<u64>()
+        .map_err(|e| format!("Failed to parse num-slots: {}", e))?;
+
+    let output_path = matches
+        .value_of("output")
+        .ok_or_else(|| "No output file supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse output path: {}", e))?;
+
+    info!("Pre-state path: {:?}", pre_state_path);
+
+    let pre_state: BeaconState<T> = load_from_ssz(pre_state_path)?;
+
+    info!("Pre-state slot: {}", pre_state.slot);
+    info!("Pre-state root: {:?}", pre_state.canonical_root());
+
+    let post_state = skip_slots(pre_state, num_slots)?;
+
+    info!("Post-state slot: {}", post_state.slot);
+    info!("Post-state root: {:?}", post_state.canonical_root());
+
+    write_to_ssz(output_path, &post_state)
+}
+
+fn skip_slots<T: EthSpec>(
+    mut state: BeaconState<T>,
+    num_slots: u64,
+) -> Result<BeaconState<T>, String> {
+    let spec = &T::default_spec();
+
+    state
+        .build_all_caches(spec)
+        .map_err(|e| format!("Unable to build caches: {:?}", e))?;
+
+    let t = Instant::now();
+    for i in 0..num_slots {
+        per_slot_processing(&mut state, spec)
+            .map_err(|e| format!("Failed to advance slot on iteration {}: {:?}", i, e))?;
+    }
+    info!("Slot processing: {:?}", t.elapsed());
+
+    Ok(state)
+}
@@ -0,0 +1,80 @@
+use crate::transition_blocks::load_from_ssz;
+use clap::ArgMatches;
+use compare_fields::{CompareFields, Comparison, FieldComparison};
+use std::path::PathBuf;
+use types::{BeaconState, EthSpec};
+
+pub fn run_state_diff<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let path_a = matches
+        .value_of("state-a")
+        .ok_or_else(|| "No first state supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse first state path: {}", e))?;
+    let path_b = matches
+        .value_of("state-b")
+        .ok_or_else(|| "No second state supplied".to_string())?
+        .parse::<PathBuf>()
+        .map_err(|e| format!("Failed to parse second state path: {}", e))?;
+
+    info!("State a path: {:?}", path_a);
+    info!("State b path: {:?}", path_b);
+
+    let state_a: BeaconState<T> = load_from_ssz(path_a)?;
+    let state_b: BeaconState<T> = load_from_ssz(path_b)?;
+
+    info!("State a root: {:?}", state_a.canonical_root());
+    info!("State b root: {:?}", state_b.canonical_root());
+
+    let differences = diff_states(state_a, state_b);
+
+    if differences.is_empty() {
+        info!("States are equal");
+    } else {
+        for comparison in &differences {
+            print_comparison(comparison);
+        }
+        info!("{} fields differ", differences.len());
+    }
+
+    Ok(())
+}
+
+/// Returns the fields of `a` and `b` which differ. For list fields, only the differing elements
+/// are included.
+///
+/// Caches are ignored, they are not part of the consensus state.
+fn diff_states<T: EthSpec>(mut a: BeaconState<T>, mut b: BeaconState<T>) -> Vec<Comparison> {
+    a.drop_all_caches();
+    b.drop_all_caches();
+
+    let mut differences: Vec<Comparison> = a
+        .compare_fields(&b)
+        .into_iter()
+        .filter(Comparison::not_equal)
+        .collect();
+
+    differences
+        .iter_mut()
+        .for_each(|c| c.retain_children(FieldComparison::not_equal));
+
+    differences
+}
+
+fn print_comparison(comparison: &Comparison) {
+    match comparison {
+        Comparison::Child(field) => print_field(&field.field_name, field),
+        Comparison::Parent {
+            field_name,
+            children,
+            ..
+        } => children
+            .iter()
+            .for_each(|child| print_field(&format!("{}[{}]", field_name, child.field_name), child)),
+    }
+}
+
+fn print_field(name: &str, field: &FieldComparison) {
+    println!("{}:", name);
+    println!("  a: {}", field.a);
+    println!("  b: {}", field.b);
+}
@@ -1,9 +1,10 @@
 use clap::ArgMatches;
 use ssz::{Decode, Encode};
-use state_processing::{per_block_processing, per_slot_processing, BlockSignatureStrategy};
+use state_processing::{BlockReplayer, BlockSignatureStrategy};
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
+use std::time::Instant;
 use types::{BeaconBlock, BeaconState, EthSpec};
 
 pub fn run_transition_blocks<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
@@ -25,58 +26,40 @@ pub fn run_transition_blocks<T: EthSpec>(matches: &ArgMatches) -> Result<(), Str
         .parse::<PathBuf>()
         .map_err(|e| format!("Failed to parse output path: {}", e))?;
 
-    info!("Using minimal spec");
     info!("Pre-state path: {:?}", pre_state_path);
     info!("Block path: {:?}", block_path);
 
     let pre_state: BeaconState<T> = load_from_ssz(pre_state_path)?;
     let block: BeaconBlock<T> = load_from_ssz(block_path)?;
 
-    let post_state = do_transition(pre_state, block)?;
+    info!("Pre-state root: {:?}", pre_state.canonical_root());
+    info!("Block root: {:?}", block.canonical_root());
 
-    let mut output_file = File::create(output_path.clone())
-        .map_err(|e| format!("Unable to create output file: {:?}", e))?;
+    let post_state = do_transition(pre_state, block)?;
 
-    output_file
-        .write_all(&post_state.as_ssz_bytes())
-        .map_err(|e| format!("Unable to write to output file: {:?}", e))?;
+    info!("Post-state root: {:?}", post_state.canonical_root());
 
-    Ok(())
+    write_to_ssz(output_path, &post_state)
 }
 
 fn do_transition<T: EthSpec>(
-    mut pre_state: BeaconState<T>,
+    pre_state: BeaconState<T>,
     block: BeaconBlock<T>,
 ) -> Result<BeaconState<T>, String> {
     let spec = &T::default_spec();
 
-    pre_state
-        .build_all_caches(spec)
-        .map_err(|e| format!("Unable to build caches: {:?}", e))?;
-
-    // Transition the parent state to the block slot.
-    for i in pre_state.slot.as_u64()..block.slot.as_u64() {
-        per_slot_processing(&mut pre_state, spec)
-            .map_err(|e| format!("Failed to advance slot on iteration {}: {:?}", i, e))?;
-    }
-
-    pre_state
-        .build_all_caches(spec)
-        .map_err(|e| format!("Unable to build caches: {:?}", e))?;
+    let t = Instant::now();
+    let post_state = BlockReplayer::new(pre_state, spec)
+        .block_signature_strategy(BlockSignatureStrategy::VerifyIndividual)
+        .apply_blocks(vec![block])
+        .map_err(|e| format!("State transition failed: {:?}", e))?
+        .into_state();
+    info!("Slot and block processing: {:?}", t.elapsed());
 
-    per_block_processing(
-        &mut pre_state,
-        &block,
-        None,
-        BlockSignatureStrategy::VerifyIndividual,
-        spec,
-    )
-    .map_err(|e| format!("State transition failed: {:?}", e))?;
-
-    Ok(pre_state)
+    Ok(post_state)
 }
 
-fn load_from_ssz<T: Decode>(path: PathBuf) -> Result<T, String> {
+pub fn load_from_ssz<T: Decode>(path: PathBuf) -> Result<T, String> {
     let mut file =
         File::open(path.clone()).map_err(|e| format!("Unable to open file {:?}: {:?}", path, e))?;
     let mut bytes = vec![];
@@ -84,3 +67,10 @@ fn load_from_ssz<T: Decode>(path: PathBuf) -> Result<T, String> {
         .map_err(|e| format!("Unable to read from file {:?}: {:?}", path, e))?;
     T::from_ssz_bytes(&bytes).map_err(|e| format!("Ssz decode failed: {:?}", e))
 }
+
+pub fn write_to_ssz<T: Encode>(path: PathBuf, item: &T) -> Result<(), String> {
+    let mut file = File::create(path.clone())
+        .map_err(|e| format!("Unable to create file {:?}: {:?}", path, e))?;
+    file.write_all(&item.as_ssz_bytes())
+        .map_err(|e| format!("Unable to write to file {:?}: {:?}", path, e))
+}
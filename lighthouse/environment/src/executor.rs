@@ -0,0 +1,202 @@
+use crate::metrics;
+use crate::ShutdownReason;
+use futures::{sync::mpsc, Future};
+use futures_cpupool::{Builder as CpuPoolBuilder, CpuPool};
+use lighthouse_metrics::{IntGaugeVec, Result as MetricsResult};
+use slog::{crit, Logger};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The prefix given to the names of the threads in the blocking thread pool.
+const BLOCKING_THREAD_PREFIX: &str = "blocking-";
+
+/// Spawns tasks on a `tokio` runtime, or on a bounded pool of threads for blocking work.
+///
+/// Each task is given a name, which is used to count the running tasks in the `async_tasks_count`
+/// and `blocking_tasks_count` metrics.
+///
+/// A panic in a spawned task is logged and requests that the process shuts down, rather than
+/// silently stopping the task whilst the rest of the process continues.
+#[derive(Clone)]
+pub struct TaskExecutor {
+    executor: tokio::runtime::TaskExecutor,
+    blocking_pool: CpuPool,
+    shutdown_sender: mpsc::Sender<ShutdownReason>,
+    log: Logger,
+}
+
+impl TaskExecutor {
+    /// Creates a new executor which spawns async tasks on `executor` and blocking tasks on
+    /// `blocking_pool`.
+    pub fn new(
+        executor: tokio::runtime::TaskExecutor,
+        blocking_pool: CpuPool,
+        shutdown_sender: mpsc::Sender<ShutdownReason>,
+        log: Logger,
+    ) -> Self {
+        Self {
+            executor,
+            blocking_pool,
+            shutdown_sender,
+            log,
+        }
+    }
+
+    /// Returns a bounded pool of `size` threads, suitable for `TaskExecutor::new`.
+    pub fn blocking_pool(size: usize) -> CpuPool {
+        CpuPoolBuilder::new()
+            .pool_size(size)
+            .name_prefix(BLOCKING_THREAD_PREFIX)
+            .create()
+    }
+
+    /// Spawns `task` on the `tokio` runtime, counting it under `name` until it completes.
+    pub fn spawn(
+        &self,
+        task: impl Future<Item = (), Error = ()> + Send + 'static,
+        name: &'static str,
+    ) {
+        let log = self.log.clone();
+        let shutdown_sender = self.shutdown_sender.clone();
+        let gauge = TaskGauge::new(&metrics::ASYNC_TASKS_COUNT, name);
+
+        let future = AssertUnwindSafe(task).catch_unwind().then(move |result| {
+            drop(gauge);
+            if let Err(panic) = result {
+                handle_panic(panic, name, &log, shutdown_sender);
+            }
+            Ok(())
+        });
+
+        self.executor.spawn(future)
+    }
+
+    /// Runs `task` on the blocking thread pool, returning a future which resolves to its result.
+    ///
+    /// Intended for CPU-heavy work (e.g., signature verification) which would otherwise stall the
+    /// `tokio` runtime. The pool is bounded, so tasks wait in a queue when all of its threads are
+    /// busy. Tasks are counted under `name` whilst they are queued and whilst they run.
+    ///
+    /// The future resolves to an error if `task` panics.
+    pub fn spawn_blocking<F, R>(
+        &self,
+        task: F,
+        name: &'static str,
+    ) -> impl Future<Item = R, Error = String>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let log = self.log.clone();
+        let shutdown_sender = self.shutdown_sender.clone();
+        let queued_gauge = TaskGauge::new(&metrics::BLOCKING_TASKS_QUEUED, name);
+
+        self.blocking_pool.spawn_fn(move || {
+            drop(queued_gauge);
+            let _running_gauge = TaskGauge::new(&metrics::BLOCKING_TASKS_COUNT, name);
+
+            panic::catch_unwind(AssertUnwindSafe(task)).map_err(|panic| {
+                handle_panic(panic, name, &log, shutdown_sender);
+                format!("Blocking task {} panicked", name)
+            })
+        })
+    }
+}
+
+/// Increments the gauge for a task when created and decrements it when dropped, so tasks which
+/// are dropped without completing (e.g., when the runtime shuts down) are not counted forever.
+struct TaskGauge {
+    gauge_vec: &'static MetricsResult<IntGaugeVec>,
+    name: &'static str,
+}
+
+impl TaskGauge {
+    fn new(gauge_vec: &'static MetricsResult<IntGaugeVec>, name: &'static str) -> Self {
+        metrics::inc_gauge_vec(gauge_vec, &[name]);
+        Self { gauge_vec, name }
+    }
+}
+
+impl Drop for TaskGauge {
+    fn drop(&mut self) {
+        metrics::dec_gauge_vec(self.gauge_vec, &[self.name]);
+    }
+}
+
+/// Logs the panic of the task with `name` and requests that the process shuts down.
+fn handle_panic(
+    panic: Box<dyn Any + Send>,
+    name: &'static str,
+    log: &Logger,
+    mut shutdown_sender: mpsc::Sender<ShutdownReason>,
+) {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    metrics::inc_counter_vec(&metrics::TASK_PANICS, &[name]);
+    crit!(
+        log,
+        "Task panicked";
+        "task_name" => name,
+        "message" => message,
+    );
+
+    let _ = shutdown_sender.try_send(ShutdownReason::Failure("Panic (fatal error)"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future, Stream};
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use tokio::runtime::Runtime;
+
+    fn executor(runtime: &Runtime) -> (TaskExecutor, mpsc::Receiver<ShutdownReason>) {
+        let (shutdown_sender, shutdown_receiver) = mpsc::channel(1);
+        let log = NullLoggerBuilder.build().expect("should build logger");
+        let executor = TaskExecutor::new(
+            runtime.executor(),
+            TaskExecutor::blocking_pool(2),
+            shutdown_sender,
+            log,
+        );
+        (executor, shutdown_receiver)
+    }
+
+    #[test]
+    fn spawn_blocking_returns_result() {
+        let mut runtime = Runtime::new().expect("should create runtime");
+        let (executor, _shutdown_receiver) = executor(&runtime);
+
+        let result = runtime.block_on(executor.spawn_blocking(|| 21 * 2, "test_blocking"));
+        assert_eq!(result, Ok(42));
+    }
+
+    #[test]
+    fn panic_requests_shutdown() {
+        let mut runtime = Runtime::new().expect("should create runtime");
+        let (executor, shutdown_receiver) = executor(&runtime);
+
+        executor.spawn(
+            future::lazy(|| -> Result<(), ()> { panic!("test panic") }),
+            "test_panic",
+        );
+
+        let (reason, _) = runtime
+            .block_on(shutdown_receiver.into_future())
+            .map_err(|_| ())
+            .expect("should receive shutdown reason");
+        assert_eq!(reason, Some(ShutdownReason::Failure("Panic (fatal error)")));
+
+        let result = runtime.block_on(
+            executor.spawn_blocking(|| -> u8 { panic!("test panic") }, "test_blocking_panic"),
+        );
+        assert!(
+            result.is_err(),
+            "should return an error when the task panics"
+        );
+    }
+}
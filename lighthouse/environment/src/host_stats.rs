@@ -0,0 +1,75 @@
+//! Statistics describing the host on which a Lighthouse service is running.
+//!
+//! These are read from `/proc` and are only available on Linux. On other platforms each statistic
+//! is `None`.
+
+/// A snapshot of the load and memory usage of the host and of this process.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HostStats {
+    /// The load average of the host over the last minute.
+    pub load_average_1m: Option<f64>,
+    pub memory_total_bytes: Option<u64>,
+    /// The memory which is available to start new processes, including reclaimable caches.
+    pub memory_available_bytes: Option<u64>,
+    /// The resident memory size of this process.
+    pub process_resident_memory_bytes: Option<u64>,
+}
+
+impl HostStats {
+    /// Reads the present statistics of the host.
+    #[cfg(target_os = "linux")]
+    pub fn observe() -> Self {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok();
+        let meminfo_bytes = |key| meminfo.as_ref().and_then(|s| read_kb_field(s, key));
+
+        Self {
+            load_average_1m: std::fs::read_to_string("/proc/loadavg")
+                .ok()
+                .and_then(|s| s.split_whitespace().next()?.parse().ok()),
+            memory_total_bytes: meminfo_bytes("MemTotal:"),
+            memory_available_bytes: meminfo_bytes("MemAvailable:"),
+            process_resident_memory_bytes: std::fs::read_to_string("/proc/self/status")
+                .ok()
+                .and_then(|s| read_kb_field(&s, "VmRSS:")),
+        }
+    }
+
+    /// Host statistics are not supported on this platform.
+    #[cfg(not(target_os = "linux"))]
+    pub fn observe() -> Self {
+        Self::default()
+    }
+}
+
+/// Reads the value (in kB) of the line starting with `key` in a `/proc` file, in bytes.
+#[cfg(target_os = "linux")]
+fn read_kb_field(contents: &str, key: &str) -> Option<u64> {
+    contents
+        .lines()
+        .find(|line| line.starts_with(key))?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u64>()
+        .ok()
+        .map(|kb| kb * 1024)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_proc() {
+        let stats = HostStats::observe();
+
+        assert!(stats.load_average_1m.is_some(), "should read load average");
+        assert!(
+            stats.memory_available_bytes.unwrap() <= stats.memory_total_bytes.unwrap(),
+            "should read memory"
+        );
+        assert!(
+            stats.process_resident_memory_bytes.unwrap() > 0,
+            "should use some memory"
+        );
+    }
+}
@@ -7,17 +7,35 @@
 //! `Context` which can be handed to any service that wishes to start async tasks or perform
 //! logging.
 
+#[macro_use]
+extern crate lazy_static;
+
+mod executor;
+mod host_stats;
+mod metrics;
+mod thread_pools;
+
+pub use executor::TaskExecutor;
+pub use host_stats::HostStats;
+pub use thread_pools::{parse_cpu_list, ThreadPoolConfig};
+
 use eth2_config::Eth2Config;
-use futures::{sync::oneshot, Future};
+use futures::{
+    sync::{mpsc, oneshot},
+    Future, Stream,
+};
+use futures_cpupool::CpuPool;
+use logging::{ModuleLevelFilter, RotatingFile};
 use slog::{info, o, Drain, Level, Logger};
 use sloggers::{null::NullLoggerBuilder, Build};
+use slot_clock::TimeSource;
 use std::cell::RefCell;
-use std::ffi::OsStr;
-use std::fs::{rename as FsRename, OpenOptions};
+use std::io;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::runtime::{Builder as RuntimeBuilder, Runtime, TaskExecutor};
+use std::time::Duration;
+use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
 use types::{EthSpec, InteropEthSpec, MainnetEthSpec, MinimalEthSpec};
 
 /// Builds an `Environment`.
@@ -26,6 +44,8 @@ pub struct EnvironmentBuilder<E: EthSpec> {
     log: Option<Logger>,
     eth_spec_instance: E,
     eth2_config: Eth2Config,
+    time_source: TimeSource,
+    thread_pools: ThreadPoolConfig,
 }
 
 impl EnvironmentBuilder<MinimalEthSpec> {
@@ -36,6 +56,8 @@ impl EnvironmentBuilder<MinimalEthSpec> {
             log: None,
             eth_spec_instance: MinimalEthSpec,
             eth2_config: Eth2Config::minimal(),
+            time_source: TimeSource::system(),
+            thread_pools: ThreadPoolConfig::default(),
         }
     }
 }
@@ -48,6 +70,8 @@ impl EnvironmentBuilder<MainnetEthSpec> {
             log: None,
             eth_spec_instance: MainnetEthSpec,
             eth2_config: Eth2Config::mainnet(),
+            time_source: TimeSource::system(),
+            thread_pools: ThreadPoolConfig::default(),
         }
     }
 }
@@ -60,17 +84,38 @@ impl EnvironmentBuilder<InteropEthSpec> {
             log: None,
             eth_spec_instance: InteropEthSpec,
             eth2_config: Eth2Config::interop(),
+            time_source: TimeSource::system(),
+            thread_pools: ThreadPoolConfig::default(),
         }
     }
 }
 
 impl<E: EthSpec> EnvironmentBuilder<E> {
+    /// Specifies the sizes of the thread pools and the CPU affinity of the process.
+    ///
+    /// Must be called before any threads are spawned (i.e., before the runtime and logger are
+    /// specified), see `ThreadPoolConfig::apply_to_process`.
+    pub fn thread_pools(mut self, config: ThreadPoolConfig) -> Result<Self, String> {
+        config.apply_to_process()?;
+        self.thread_pools = config;
+        Ok(self)
+    }
+
     /// Specifies that a multi-threaded tokio runtime should be used. Ideal for production uses.
     ///
-    /// The `Runtime` used is just the standard tokio runtime.
+    /// The `Runtime` used is the standard tokio runtime, with a thread per core unless otherwise
+    /// specified by `Self::thread_pools`.
     pub fn multi_threaded_tokio_runtime(mut self) -> Result<Self, String> {
-        self.runtime =
-            Some(Runtime::new().map_err(|e| format!("Failed to start runtime: {:?}", e))?);
+        let mut builder = RuntimeBuilder::new();
+        if let Some(threads) = self.thread_pools.worker_threads {
+            builder.core_threads(threads);
+        }
+
+        self.runtime = Some(
+            builder
+                .build()
+                .map_err(|e| format!("Failed to start runtime: {:?}", e))?,
+        );
         Ok(self)
     }
 
@@ -99,29 +144,46 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
     /// The logger is "async" because it has a dedicated thread that accepts logs and then
     /// asynchronously flushes them to stdout/files/etc. This means the thread that raised the log
     /// does not have to wait for the logs to be flushed.
-    pub fn async_logger(mut self, debug_level: &str) -> Result<Self, String> {
-        // Build the initial logger.
-        let decorator = slog_term::TermDecorator::new().build();
-        let decorator = logging::AlignedTermDecorator::new(decorator, logging::MAX_MESSAGE_WIDTH);
-        let drain = slog_term::FullFormat::new(decorator).build().fuse();
-        let drain = slog_async::Async::new(drain).build();
+    pub fn async_logger(self, debug_level: &str) -> Result<Self, String> {
+        self.logger(LoggerConfig::new(debug_level)?)
+    }
 
-        let drain = match debug_level {
-            "info" => drain.filter_level(Level::Info),
-            "debug" => drain.filter_level(Level::Debug),
-            "trace" => drain.filter_level(Level::Trace),
-            "warn" => drain.filter_level(Level::Warning),
-            "error" => drain.filter_level(Level::Error),
-            "crit" => drain.filter_level(Level::Critical),
-            unknown => return Err(format!("Unknown debug-level: {}", unknown)),
+    /// Specifies that the `slog` asynchronous logger should be used, with the format and levels in
+    /// `config`.
+    pub fn logger(mut self, config: LoggerConfig) -> Result<Self, String> {
+        let drain = match config.format {
+            LogFormat::Terminal => {
+                let decorator = slog_term::TermDecorator::new().build();
+                let decorator =
+                    logging::AlignedTermDecorator::new(decorator, logging::MAX_MESSAGE_WIDTH);
+                let drain = slog_term::FullFormat::new(decorator).build().fuse();
+                slog_async::Async::new(drain).build()
+            }
+            LogFormat::Json => {
+                let drain = Mutex::new(slog_json::Json::default(io::stdout())).fuse();
+                slog_async::Async::new(drain).build()
+            }
         };
 
-        self.log = Some(Logger::root(drain.fuse(), o!()));
+        self.log = Some(Logger::root(config.filter(drain).fuse(), o!()));
         Ok(self)
     }
 
+    /// Specifies that all slot clocks created from this environment should be frozen at `time` (a
+    /// duration since the `UNIX_EPOCH`), only advancing when the `Environment::time_source` is
+    /// advanced. Ideal for testing.
+    ///
+    /// Only slot clocks are affected, timers (e.g., the per-slot notifier) still use the system
+    /// time.
+    pub fn manual_time(mut self, time: Duration) -> Self {
+        self.time_source = TimeSource::manual(time);
+        self
+    }
+
     /// Consumes the builder, returning an `Environment`.
     pub fn build(self) -> Result<Environment<E>, String> {
+        let (shutdown_sender, shutdown_receiver) = mpsc::channel(1);
+
         Ok(Environment {
             runtime: self
                 .runtime
@@ -131,10 +193,117 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
                 .ok_or_else(|| "Cannot build environment without log".to_string())?,
             eth_spec_instance: self.eth_spec_instance,
             eth2_config: self.eth2_config,
+            time_source: self.time_source,
+            shutdown_sender,
+            shutdown_receiver: Some(shutdown_receiver),
+            blocking_pool: TaskExecutor::blocking_pool(
+                self.thread_pools
+                    .blocking_threads
+                    .unwrap_or_else(num_cpus::get),
+            ),
         })
     }
 }
 
+/// The format of the log records emitted by a logger.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable, aligned text.
+    Terminal,
+    /// One JSON object per record, suitable for log aggregation systems.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, String> {
+        match string {
+            "terminal" => Ok(LogFormat::Terminal),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("Unknown log format: {}", other)),
+        }
+    }
+}
+
+/// Configures the format and levels of the logs emitted by an `Environment`.
+#[derive(Debug, Clone)]
+pub struct LoggerConfig {
+    /// Records below this level are not logged, unless overridden in `module_levels`.
+    pub debug_level: Level,
+    /// Overrides `debug_level` for the given modules (see `logging::ModuleLevelFilter`).
+    pub module_levels: Vec<(String, Level)>,
+    pub format: LogFormat,
+}
+
+impl LoggerConfig {
+    /// Returns a config for terminal output at the given `debug_level` (e.g., `info`).
+    pub fn new(debug_level: &str) -> Result<Self, String> {
+        Ok(Self {
+            debug_level: parse_debug_level(debug_level)?,
+            module_levels: vec![],
+            format: LogFormat::Terminal,
+        })
+    }
+
+    fn filter<D: Drain>(&self, drain: D) -> ModuleLevelFilter<D> {
+        ModuleLevelFilter::new(drain, self.debug_level, self.module_levels.clone())
+    }
+}
+
+/// Configures the rotation of a log file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogFileConfig {
+    /// The file is rotated once it exceeds this many bytes.
+    pub max_size: u64,
+    /// The number of rotated files which are kept.
+    pub max_files: usize,
+    /// If `true`, rotated files are gzip-compressed.
+    pub compress: bool,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 200 * 1024 * 1024,
+            max_files: 5,
+            compress: false,
+        }
+    }
+}
+
+/// Parses a `--debug-level` value (e.g., `info`).
+pub fn parse_debug_level(debug_level: &str) -> Result<Level, String> {
+    match debug_level {
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        "warn" => Ok(Level::Warning),
+        "error" => Ok(Level::Error),
+        "crit" => Ok(Level::Critical),
+        unknown => Err(format!("Unknown debug-level: {}", unknown)),
+    }
+}
+
+/// The reason the process is shutting down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShutdownReason {
+    /// The shutdown was expected (e.g., the user pressed Ctrl+C).
+    Success(&'static str),
+    /// A service encountered an error it could not recover from.
+    Failure(&'static str),
+}
+
+impl ShutdownReason {
+    /// Returns the message describing the reason for the shutdown.
+    pub fn message(&self) -> &'static str {
+        match self {
+            ShutdownReason::Success(msg) => msg,
+            ShutdownReason::Failure(msg) => msg,
+        }
+    }
+}
+
 /// An execution context that can be used by a service.
 ///
 /// Distinct from an `Environment` because a `Context` is not able to give a mutable reference to a
@@ -145,6 +314,10 @@ pub struct RuntimeContext<E: EthSpec> {
     pub log: Logger,
     pub eth_spec_instance: E,
     pub eth2_config: Eth2Config,
+    /// The source of time for all slot clocks created by this service.
+    pub time_source: TimeSource,
+    /// Used to request that the whole process shuts down (e.g., after a fatal error in a service).
+    pub shutdown_sender: mpsc::Sender<ShutdownReason>,
 }
 
 impl<E: EthSpec> RuntimeContext<E> {
@@ -157,6 +330,8 @@ impl<E: EthSpec> RuntimeContext<E> {
             log: self.log.new(o!("service" => service_name)),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            time_source: self.time_source.clone(),
+            shutdown_sender: self.shutdown_sender.clone(),
         }
     }
 
@@ -173,6 +348,10 @@ pub struct Environment<E: EthSpec> {
     log: Logger,
     eth_spec_instance: E,
     pub eth2_config: Eth2Config,
+    time_source: TimeSource,
+    shutdown_sender: mpsc::Sender<ShutdownReason>,
+    shutdown_receiver: Option<mpsc::Receiver<ShutdownReason>>,
+    blocking_pool: CpuPool,
 }
 
 impl<E: EthSpec> Environment<E> {
@@ -187,25 +366,47 @@ impl<E: EthSpec> Environment<E> {
     /// Returns a `Context` where no "service" has been added to the logger output.
     pub fn core_context(&mut self) -> RuntimeContext<E> {
         RuntimeContext {
-            executor: self.runtime.executor(),
+            executor: self.task_executor(),
             log: self.log.clone(),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            time_source: self.time_source.clone(),
+            shutdown_sender: self.shutdown_sender.clone(),
         }
     }
 
     /// Returns a `Context` where the `service_name` is added to the logger output.
     pub fn service_context(&mut self, service_name: String) -> RuntimeContext<E> {
         RuntimeContext {
-            executor: self.runtime.executor(),
+            executor: self.task_executor(),
             log: self.log.new(o!("service" => service_name)),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            time_source: self.time_source.clone(),
+            shutdown_sender: self.shutdown_sender.clone(),
         }
     }
 
-    /// Block the current thread until Ctrl+C is received.
-    pub fn block_until_ctrl_c(&mut self) -> Result<(), String> {
+    /// Returns a `TaskExecutor` which spawns tasks on this environment's runtime.
+    fn task_executor(&self) -> TaskExecutor {
+        TaskExecutor::new(
+            self.runtime.executor(),
+            self.blocking_pool.clone(),
+            self.shutdown_sender.clone(),
+            self.log.clone(),
+        )
+    }
+
+    /// Block the current thread until either SIGINT (Ctrl+C) or SIGTERM is received, or a service
+    /// requests a shutdown via the `shutdown_sender` in its `RuntimeContext`.
+    ///
+    /// May only be called once.
+    pub fn block_until_shutdown_requested(&mut self) -> Result<ShutdownReason, String> {
+        let shutdown_receiver = self
+            .shutdown_receiver
+            .take()
+            .ok_or_else(|| "Shutdown has already been awaited".to_string())?;
+
         let (ctrlc_send, ctrlc_oneshot) = oneshot::channel();
         let ctrlc_send_c = RefCell::new(Some(ctrlc_send));
         ctrlc::set_handler(move || {
@@ -215,10 +416,19 @@ impl<E: EthSpec> Environment<E> {
         })
         .map_err(|e| format!("Could not set ctrlc handler: {:?}", e))?;
 
-        // Block this thread until Crtl+C is pressed.
+        let signal = ctrlc_oneshot
+            .map(|()| ShutdownReason::Success("Received SIGINT or SIGTERM"))
+            .map_err(|e| format!("Ctrlc oneshot failed: {:?}", e));
+        let requested = shutdown_receiver
+            .into_future()
+            .map_err(|_| "Shutdown channel failed".to_string())
+            .and_then(|(reason, _)| reason.ok_or_else(|| "Shutdown channel closed".to_string()));
+
+        // Block this thread until a signal is received or a shutdown is requested.
         self.runtime()
-            .block_on(ctrlc_oneshot)
-            .map_err(|e| format!("Ctrlc oneshot failed: {:?}", e))
+            .block_on(signal.select(requested))
+            .map(|(reason, _)| reason)
+            .map_err(|(e, _)| e)
     }
 
     /// Shutdown the `tokio` runtime when all tasks are idle.
@@ -229,52 +439,36 @@ impl<E: EthSpec> Environment<E> {
             .map_err(|e| format!("Tokio runtime shutdown returned an error: {:?}", e))
     }
 
-    /// Sets the logger (and all child loggers) to log to a file.
-    pub fn log_to_json_file(&mut self, path: PathBuf, debug_level: &str) -> Result<(), String> {
-        // Creating a backup if the logfile already exists.
-        if path.exists() {
-            let start = SystemTime::now();
-            let timestamp = start
-                .duration_since(UNIX_EPOCH)
-                .map_err(|e| e.to_string())?
-                .as_secs();
-            let file_stem = path
-                .file_stem()
-                .ok_or_else(|| "Invalid file name".to_string())?
-                .to_str()
-                .ok_or_else(|| "Failed to create str from filename".to_string())?;
-            let file_ext = path.extension().unwrap_or_else(|| OsStr::new(""));
-            let backup_name = format!("{}_backup_{}", file_stem, timestamp);
-            let backup_path = path.with_file_name(backup_name).with_extension(file_ext);
-            FsRename(&path, &backup_path).map_err(|e| e.to_string())?;
-        }
-
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)
-            .map_err(|e| format!("Unable to open logfile: {:?}", e))?;
+    /// Sets the logger (and all child loggers) to log structured JSON records to a file, which is
+    /// rotated as described by `file_config`.
+    ///
+    /// If the file already exists, records are appended to it.
+    pub fn log_to_json_file(
+        &mut self,
+        path: PathBuf,
+        config: &LoggerConfig,
+        file_config: LogFileConfig,
+    ) -> Result<(), String> {
+        let file = RotatingFile::new(
+            path.clone(),
+            file_config.max_size,
+            file_config.max_files,
+            file_config.compress,
+        )
+        .map_err(|e| format!("Unable to open logfile: {:?}", e))?;
 
         let drain = Mutex::new(slog_json::Json::default(file)).fuse();
         let drain = slog_async::Async::new(drain).build();
 
-        let drain = match debug_level {
-            "info" => drain.filter_level(Level::Info),
-            "debug" => drain.filter_level(Level::Debug),
-            "trace" => drain.filter_level(Level::Trace),
-            "warn" => drain.filter_level(Level::Warning),
-            "error" => drain.filter_level(Level::Error),
-            "crit" => drain.filter_level(Level::Critical),
-            unknown => return Err(format!("Unknown debug-level: {}", unknown)),
-        };
-
-        self.log = Logger::root(drain.fuse(), o!());
+        self.log = Logger::root(config.filter(drain).fuse(), o!());
 
         info!(
             self.log,
             "Logging to JSON file";
-            "path" => format!("{:?}", path)
+            "path" => format!("{:?}", path),
+            "max_size" => file_config.max_size,
+            "max_files" => file_config.max_files,
+            "compress" => file_config.compress,
         );
 
         Ok(())
@@ -287,6 +481,13 @@ impl<E: EthSpec> Environment<E> {
     pub fn eth2_config(&self) -> &Eth2Config {
         &self.eth2_config
     }
+
+    /// Returns the source of time shared by all services in this environment.
+    ///
+    /// Unless `EnvironmentBuilder::manual_time` was used, this follows the system time.
+    pub fn time_source(&self) -> &TimeSource {
+        &self.time_source
+    }
 }
 
 pub fn null_logger() -> Result<Logger, String> {
@@ -0,0 +1,24 @@
+pub use lighthouse_metrics::*;
+
+lazy_static! {
+    pub static ref ASYNC_TASKS_COUNT: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "async_tasks_count",
+        "Count of async tasks spawned in the tokio runtime which have not yet completed",
+        &["task_name"]
+    );
+    pub static ref BLOCKING_TASKS_COUNT: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "blocking_tasks_count",
+        "Count of blocking tasks presently running in the blocking thread pool",
+        &["task_name"]
+    );
+    pub static ref BLOCKING_TASKS_QUEUED: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "blocking_tasks_queued",
+        "Count of blocking tasks waiting for a thread in the blocking thread pool",
+        &["task_name"]
+    );
+    pub static ref TASK_PANICS: Result<IntCounterVec> = try_create_int_counter_vec(
+        "task_panics_total",
+        "Count of spawned tasks which have panicked",
+        &["task_name"]
+    );
+}
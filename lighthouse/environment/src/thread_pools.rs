@@ -0,0 +1,151 @@
+//! Configures the sizes of the thread pools of an `Environment` and the cores on which their
+//! threads run, so that CPU-heavy work (e.g., epoch processing) can be prevented from starving
+//! other work (e.g., networking) on shared machines.
+
+/// The environment variable read by `rayon` to determine the size of its global thread pool.
+const RAYON_NUM_THREADS: &str = "RAYON_NUM_THREADS";
+
+/// The maximum number of cores supported by `set_cpu_affinity`, as for the `cpu_set_t` of Linux.
+const MAX_CORES: usize = 1024;
+
+/// The sizes of the thread pools of an `Environment`. Each pool has a thread per core unless
+/// otherwise specified.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadPoolConfig {
+    /// The number of threads in the `tokio` runtime which runs async tasks (e.g., networking).
+    pub worker_threads: Option<usize>,
+    /// The number of threads in the pool for blocking tasks (e.g., database reads).
+    pub blocking_threads: Option<usize>,
+    /// The number of threads in the global `rayon` pool, used for parallel work such as signature
+    /// verification and tree hashing.
+    pub rayon_threads: Option<usize>,
+    /// The cores to which all threads of the process are pinned, if any.
+    ///
+    /// Only supported on Linux.
+    pub cpu_affinity: Option<Vec<usize>>,
+}
+
+impl ThreadPoolConfig {
+    /// Pins the current thread to the `cpu_affinity` cores and sets the size of the global `rayon`
+    /// pool.
+    ///
+    /// Must be called before any other threads are spawned (e.g., by the `tokio` runtime or by
+    /// `rayon`), since only threads spawned afterwards inherit the affinity, and the `rayon` pool
+    /// is sized when it is first used.
+    pub fn apply_to_process(&self) -> Result<(), String> {
+        for (name, threads) in &[
+            ("worker", self.worker_threads),
+            ("blocking", self.blocking_threads),
+            ("rayon", self.rayon_threads),
+        ] {
+            if *threads == Some(0) {
+                return Err(format!("The number of {} threads must be non-zero", name));
+            }
+        }
+
+        if let Some(cores) = &self.cpu_affinity {
+            set_cpu_affinity(cores)?;
+        }
+
+        if let Some(threads) = self.rayon_threads {
+            std::env::set_var(RAYON_NUM_THREADS, threads.to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a list of cores, where each item is a core or an inclusive range of cores (e.g.,
+/// `0-3,6`).
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, String> {
+    let parse_core = |core: &str| {
+        core.trim()
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid core {:?}: {}", core, e))
+    };
+
+    let mut cores = vec![];
+    for item in list.split(',') {
+        let mut bounds = item.splitn(2, '-');
+        let first = parse_core(bounds.next().unwrap_or(""))?;
+        let last = bounds.next().map(parse_core).transpose()?.unwrap_or(first);
+
+        if last < first {
+            return Err(format!("Invalid range of cores: {}", item));
+        }
+
+        cores.extend(first..=last);
+    }
+
+    cores.sort();
+    cores.dedup();
+
+    match cores.last() {
+        Some(core) if *core >= MAX_CORES => Err(format!(
+            "Core {} exceeds the maximum of {}",
+            core,
+            MAX_CORES - 1
+        )),
+        _ => Ok(cores),
+    }
+}
+
+/// Restricts the current thread (and any threads it spawns) to run on `cores`.
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(cores: &[usize]) -> Result<(), String> {
+    extern "C" {
+        fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    }
+
+    let mut mask = [0_u64; MAX_CORES / 64];
+    for &core in cores {
+        let word = mask
+            .get_mut(core / 64)
+            .ok_or_else(|| format!("Core {} exceeds the maximum of {}", core, MAX_CORES - 1))?;
+        *word |= 1 << (core % 64);
+    }
+
+    // A `pid` of zero refers to the calling thread.
+    let result = unsafe { sched_setaffinity(0, std::mem::size_of_val(&mask), mask.as_ptr()) };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unable to set CPU affinity to {:?}: {}",
+            cores,
+            std::io::Error::last_os_error()
+        ))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_cores: &[usize]) -> Result<(), String> {
+    Err("Setting the CPU affinity is only supported on Linux".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("3"), Ok(vec![3]));
+        assert_eq!(parse_cpu_list("0-3,6"), Ok(vec![0, 1, 2, 3, 6]));
+        assert_eq!(parse_cpu_list("6, 2-3,3"), Ok(vec![2, 3, 6]));
+        assert!(parse_cpu_list("").is_err());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("1-2-3").is_err());
+        assert!(parse_cpu_list("1024").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_pools() {
+        let config = ThreadPoolConfig {
+            blocking_threads: Some(0),
+            ..ThreadPoolConfig::default()
+        };
+        assert!(config.apply_to_process().is_err());
+    }
+}
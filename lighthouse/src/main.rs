@@ -2,16 +2,20 @@
 extern crate clap;
 
 use beacon_node::ProductionBeaconNode;
+use boot_node::BootNode;
 use clap::{App, Arg, ArgMatches};
 use env_logger::{Builder, Env};
-use environment::EnvironmentBuilder;
+use environment::{
+    parse_cpu_list, EnvironmentBuilder, LogFileConfig, LoggerConfig, ShutdownReason,
+    ThreadPoolConfig,
+};
+use logging::parse_module_levels;
 use slog::{crit, info, warn};
 use std::path::PathBuf;
 use std::process::exit;
-use types::EthSpec;
+use types::{EthSpec, EthSpecId};
 use validator_client::ProductionValidatorClient;
 
-pub const DEFAULT_DATA_DIR: &str = ".lighthouse";
 pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
 
@@ -32,40 +36,154 @@ fn main() {
                 .short("s")
                 .long("spec")
                 .value_name("TITLE")
-                .help("Specifies the default eth2 spec type.")
+                .help(
+                    "Specifies the eth2 spec type. Defaults to the spec of the config.yaml in \
+                     the --testnet-dir, if any, otherwise mainnet.",
+                )
                 .takes_value(true)
                 .possible_values(&["mainnet", "minimal", "interop"])
                 .global(true)
-                .default_value("mainnet"),
+                .env("LIGHTHOUSE_SPEC"),
         )
         .arg(
             Arg::with_name("logfile")
                 .long("logfile")
                 .value_name("FILE")
                 .help("File path where output will be written.")
-                .takes_value(true),
+                .takes_value(true)
+                .global(true)
+                .env("LIGHTHOUSE_LOGFILE"),
+        )
+        .arg(
+            Arg::with_name("logfile-max-size")
+                .long("logfile-max-size")
+                .value_name("MEGABYTES")
+                .help("The size at which the --logfile is rotated.")
+                .takes_value(true)
+                .global(true)
+                .default_value("200"),
+        )
+        .arg(
+            Arg::with_name("logfile-max-number")
+                .long("logfile-max-number")
+                .value_name("COUNT")
+                .help("The number of rotated --logfile files to keep.")
+                .takes_value(true)
+                .global(true)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("logfile-compress")
+                .long("logfile-compress")
+                .help("Compress rotated --logfile files with gzip.")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("The format of the logs written to the terminal.")
+                .takes_value(true)
+                .possible_values(&["terminal", "json"])
+                .global(true)
+                .env("LIGHTHOUSE_LOG_FORMAT")
+                .default_value("terminal"),
+        )
+        .arg(
+            Arg::with_name("module-debug-levels")
+                .long("module-debug-levels")
+                .value_name("MODULE=LEVEL,...")
+                .help(
+                    "Overrides --debug-level for the given modules, and any modules beneath them \
+                     (e.g., network=debug,eth2_libp2p::discovery=trace).",
+                )
+                .takes_value(true)
+                .global(true)
+                .env("LIGHTHOUSE_MODULE_DEBUG_LEVELS"),
         )
         .arg(
             Arg::with_name("debug-level")
                 .long("debug-level")
                 .value_name("LEVEL")
-                .help("The title of the spec constants for chain config.")
+                .help("The verbosity level for emitting logs.")
                 .takes_value(true)
                 .possible_values(&["info", "debug", "trace", "warn", "error", "crit"])
+                .global(true)
+                .env("LIGHTHOUSE_DEBUG_LEVEL")
                 .default_value("info"),
         )
+        .arg(
+            Arg::with_name("worker-threads")
+                .long("worker-threads")
+                .value_name("COUNT")
+                .help(
+                    "The number of threads which run async tasks (e.g., networking). Defaults \
+                     to the number of cores.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("blocking-threads")
+                .long("blocking-threads")
+                .value_name("COUNT")
+                .help(
+                    "The number of threads which run blocking tasks (e.g., database reads). \
+                     Defaults to the number of cores.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("rayon-threads")
+                .long("rayon-threads")
+                .value_name("COUNT")
+                .help(
+                    "The number of threads which run parallel, CPU-heavy work (e.g., signature \
+                     verification). Defaults to the number of cores.",
+                )
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("cpu-affinity")
+                .long("cpu-affinity")
+                .value_name("CORES")
+                .help(
+                    "Pins all threads to the given cores (e.g., 0-3,6). Only supported on \
+                     Linux.",
+                )
+                .takes_value(true)
+                .global(true)
+                .env("LIGHTHOUSE_CPU_AFFINITY"),
+        )
         .arg(
             Arg::with_name("datadir")
                 .long("datadir")
                 .short("d")
                 .value_name("DIR")
                 .global(true)
+                .env("LIGHTHOUSE_DATADIR")
                 .help("Data directory for lighthouse keys and databases.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("testnet-dir")
+                .long("testnet-dir")
+                .value_name("DIR")
+                .global(true)
+                .env("LIGHTHOUSE_TESTNET_DIR")
+                .help(
+                    "Path to directory containing eth2_testnet specs. Defaults to a hard-coded \
+                     Lighthouse testnet. For the beacon node, only effective if there is no \
+                     existing database.",
+                )
+                .takes_value(true),
+        )
         .subcommand(beacon_node::cli_app())
         .subcommand(validator_client::cli_app())
         .subcommand(account_manager::cli_app())
+        .subcommand(boot_node::cli_app())
         .get_matches();
 
     macro_rules! run_with_spec {
@@ -80,14 +198,46 @@ fn main() {
         };
     }
 
-    match matches.value_of("spec") {
-        Some("minimal") => run_with_spec!(EnvironmentBuilder::minimal()),
-        Some("mainnet") => run_with_spec!(EnvironmentBuilder::mainnet()),
-        Some("interop") => run_with_spec!(EnvironmentBuilder::interop()),
-        spec => {
-            // This path should be unreachable due to slog having a `default_value`
-            unreachable!("Unknown spec configuration: {:?}", spec);
+    let eth_spec_id = match eth_spec_id(&matches) {
+        Ok(eth_spec_id) => eth_spec_id,
+        Err(e) => {
+            println!("Failed to start Lighthouse: {}", e);
+            exit(1)
         }
+    };
+
+    match eth_spec_id {
+        EthSpecId::Minimal => run_with_spec!(EnvironmentBuilder::minimal()),
+        EthSpecId::Mainnet => run_with_spec!(EnvironmentBuilder::mainnet()),
+        EthSpecId::Interop => run_with_spec!(EnvironmentBuilder::interop()),
+    }
+}
+
+/// Returns the spec selected by the `--spec` flag or, if it is not supplied, the spec of the
+/// `config.yaml` in the `--testnet-dir`. Defaults to mainnet.
+fn eth_spec_id(matches: &ArgMatches) -> Result<EthSpecId, String> {
+    if let Some(spec) = matches.value_of("spec") {
+        return spec.parse();
+    }
+
+    let testnet_dir = match matches.value_of("testnet-dir") {
+        Some(testnet_dir) => PathBuf::from(testnet_dir),
+        None => return Ok(EthSpecId::Mainnet),
+    };
+
+    match eth2_testnet_config::eth_spec_id(Some(&testnet_dir))? {
+        Some(eth_spec_id) => Ok(eth_spec_id),
+        None if testnet_dir
+            .join(eth2_testnet_config::YAML_CONFIG_FILE)
+            .exists() =>
+        {
+            Err(format!(
+                "The {} in {:?} does not match a known spec, use --spec to select one",
+                eth2_testnet_config::YAML_CONFIG_FILE,
+                testnet_dir
+            ))
+        }
+        None => Ok(EthSpecId::Mainnet),
     }
 }
 
@@ -99,20 +249,59 @@ fn run<E: EthSpec>(
         .value_of("debug-level")
         .ok_or_else(|| "Expected --debug-level flag".to_string())?;
 
+    let mut logger_config = LoggerConfig::new(debug_level)?;
+    logger_config.format = matches
+        .value_of("log-format")
+        .ok_or_else(|| "Expected --log-format flag".to_string())?
+        .parse()?;
+    if let Some(module_levels) = matches.value_of("module-debug-levels") {
+        logger_config.module_levels = parse_module_levels(module_levels)?;
+    }
+
+    let parse_threads = |name: &str| -> Result<Option<usize>, String> {
+        matches
+            .value_of(name)
+            .map(|threads| {
+                threads
+                    .parse()
+                    .map_err(|e| format!("Invalid --{}: {}", name, e))
+            })
+            .transpose()
+    };
+    let thread_pool_config = ThreadPoolConfig {
+        worker_threads: parse_threads("worker-threads")?,
+        blocking_threads: parse_threads("blocking-threads")?,
+        rayon_threads: parse_threads("rayon-threads")?,
+        cpu_affinity: matches
+            .value_of("cpu-affinity")
+            .map(parse_cpu_list)
+            .transpose()?,
+    };
+
     let mut environment = environment_builder
-        .async_logger(debug_level)?
+        .thread_pools(thread_pool_config)?
+        .logger(logger_config.clone())?
         .multi_threaded_tokio_runtime()?
         .build()?;
 
-    let log = environment.core_context().log;
-
     if let Some(log_path) = matches.value_of("logfile") {
         let path = log_path
             .parse::<PathBuf>()
             .map_err(|e| format!("Failed to parse log path: {:?}", e))?;
-        environment.log_to_json_file(path, debug_level)?;
+        let file_config = LogFileConfig {
+            max_size: value_t!(matches, "logfile-max-size", u64)
+                .map_err(|e| format!("Invalid --logfile-max-size: {}", e))?
+                * 1024
+                * 1024,
+            max_files: value_t!(matches, "logfile-max-number", usize)
+                .map_err(|e| format!("Invalid --logfile-max-number: {}", e))?,
+            compress: matches.is_present("logfile-compress"),
+        };
+        environment.log_to_json_file(path, &logger_config, file_config)?;
     }
 
+    let log = environment.core_context().log;
+
     if std::mem::size_of::<usize>() != 8 {
         crit!(
             log,
@@ -143,6 +332,53 @@ fn run<E: EthSpec>(
         return Ok(());
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches("beacon_node") {
+        if sub_matches.is_present("enr-dump") {
+            beacon_node::dump_enr(environment.core_context(), sub_matches)?;
+
+            // Exit as soon as the ENR has been printed.
+            return Ok(());
+        }
+
+        if sub_matches.is_present("export-config") {
+            beacon_node::export_config(environment.core_context(), sub_matches)?;
+
+            // Exit as soon as the config has been printed.
+            return Ok(());
+        }
+
+        if let Some(start_slot) = sub_matches.value_of("revalidate-from-slot") {
+            let start_slot = start_slot
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid --revalidate-from-slot: {}", e))?;
+
+            environment
+                .runtime()
+                .block_on(beacon_node::revalidate(
+                    environment.core_context(),
+                    sub_matches,
+                    start_slot.into(),
+                ))
+                .map_err(|e| format!("Revalidation failed: {}", e))?;
+
+            // Exit without starting the beacon node.
+            return Ok(());
+        }
+
+        if sub_matches.is_present("dry-run") {
+            environment
+                .runtime()
+                .block_on(beacon_node::dry_run(
+                    environment.core_context(),
+                    sub_matches,
+                ))
+                .map_err(|e| format!("Dry run failed: {}", e))?;
+
+            // Exit without starting the beacon node.
+            return Ok(());
+        }
+    }
+
     let beacon_node = if let Some(sub_matches) = matches.subcommand_matches("beacon_node") {
         let runtime_context = environment.core_context();
 
@@ -159,6 +395,15 @@ fn run<E: EthSpec>(
         None
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches("validator_client") {
+        if let Some(exit_matches) = sub_matches.subcommand_matches("exit") {
+            validator_client::exit::run(sub_matches, exit_matches, environment)?;
+
+            // Exit as soon as the exit command returns control.
+            return Ok(());
+        }
+    }
+
     let validator_client = if let Some(sub_matches) = matches.subcommand_matches("validator_client")
     {
         let runtime_context = environment.core_context();
@@ -180,19 +425,40 @@ fn run<E: EthSpec>(
         None
     };
 
-    if beacon_node.is_none() && validator_client.is_none() {
+    let boot_node = if let Some(sub_matches) = matches.subcommand_matches("boot_node") {
+        let runtime_context = environment.core_context();
+
+        let boot_node = environment
+            .runtime()
+            .block_on(BootNode::new_from_cli(runtime_context, sub_matches))
+            .map_err(|e| format!("Failed to start boot node: {}", e))?;
+
+        Some(boot_node)
+    } else {
+        None
+    };
+
+    if beacon_node.is_none() && validator_client.is_none() && boot_node.is_none() {
         crit!(log, "No subcommand supplied. See --help .");
         return Err("No subcommand supplied.".into());
     }
 
-    // Block this thread until Crtl+C is pressed.
-    environment.block_until_ctrl_c()?;
+    // Block this thread until SIGINT or SIGTERM is received, or a service requests a shutdown.
+    let shutdown_reason = environment.block_until_shutdown_requested()?;
 
-    info!(log, "Shutting down..");
+    info!(log, "Shutting down.."; "reason" => shutdown_reason.message());
 
-    drop(beacon_node);
+    // The validator client is stopped first so that it does not try to produce blocks or
+    // attestations whilst the beacon node is shutting down.
     drop(validator_client);
+    drop(beacon_node);
+    drop(boot_node);
 
     // Shutdown the environment once all tasks have completed.
-    environment.shutdown_on_idle()
+    environment.shutdown_on_idle()?;
+
+    match shutdown_reason {
+        ShutdownReason::Success(_) => Ok(()),
+        ShutdownReason::Failure(msg) => Err(msg.to_string()),
+    }
 }
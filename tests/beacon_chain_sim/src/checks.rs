@@ -1,8 +1,8 @@
-use crate::local_network::LocalNetwork;
 use futures::{stream, Future, IntoFuture, Stream};
+use node_test_rig::{HeadResponse, LocalNetwork};
 use std::time::{Duration, Instant};
 use tokio::timer::Delay;
-use types::{Epoch, EthSpec, Slot, Unsigned};
+use types::{BeaconState, ChainSpec, Epoch, EthSpec, Hash256, Slot, Unsigned, Validator};
 
 /// Checks that all of the validators have on-boarded by the start of the second eth1 voting
 /// period.
@@ -40,8 +40,311 @@ pub fn verify_first_finalization<E: EthSpec>(
         .and_then(|()| verify_all_finalized_at(network, Epoch::new(2)))
 }
 
+/// Checks the state of the network at the end of the simulation: all nodes must have finalized
+/// `finalized_epoch` (or later) on a single canonical chain, and none of the validators with
+/// `honest_validator_indices` may have been slashed.
+pub fn verify_end_of_run<E: EthSpec>(
+    network: LocalNetwork<E>,
+    slot_duration: Duration,
+    end_epoch: Epoch,
+    finalized_epoch: Epoch,
+    honest_validator_indices: Vec<usize>,
+) -> impl Future<Item = (), Error = String> {
+    epoch_delay(end_epoch, slot_duration, E::slots_per_epoch())
+        .and_then(move |()| network.heads().map(|heads| (network, heads)))
+        .and_then(move |(network, heads)| {
+            let heads = heads.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(head) = heads
+                .iter()
+                .find(|head| head.finalized_slot.epoch(E::slots_per_epoch()) < finalized_epoch)
+            {
+                return Err(format!(
+                    "Nodes have not all finalized epoch {}. Lowest finalized slot: {}",
+                    finalized_epoch, head.finalized_slot
+                ));
+            }
+
+            verify_single_canonical_chain(&heads)?;
+
+            Ok((network, heads[0].state_root))
+        })
+        .and_then(move |(network, state_root)| {
+            verify_no_slashings(network, state_root, honest_validator_indices)
+        })
+}
+
+/// Checks that, by `epoch`, the validators with `validator_indices` have been deposited and
+/// activated, no earlier than the activation delay allows and without exceeding the churn limit.
+pub fn verify_activations<E: EthSpec>(
+    network: LocalNetwork<E>,
+    slot_duration: Duration,
+    epoch: Epoch,
+    validator_indices: Vec<usize>,
+    spec: ChainSpec,
+) -> impl Future<Item = (), Error = String> {
+    epoch_delay(epoch, slot_duration, E::slots_per_epoch())
+        .and_then(|()| head_state(network))
+        .and_then(move |state| {
+            let current_epoch = state.current_epoch();
+
+            for &index in &validator_indices {
+                let validator = get_validator(&state, index)?;
+
+                if validator.activation_eligibility_epoch == spec.far_future_epoch {
+                    return Err(format!(
+                        "Validator {} is not eligible for activation at epoch {}",
+                        index, current_epoch
+                    ));
+                }
+
+                let earliest_epoch = state
+                    .compute_activation_exit_epoch(validator.activation_eligibility_epoch, &spec);
+                if validator.activation_epoch < earliest_epoch {
+                    return Err(format!(
+                        "Validator {} activated at epoch {}, before epoch {}",
+                        index, validator.activation_epoch, earliest_epoch
+                    ));
+                }
+
+                if !validator.is_active_at(current_epoch) {
+                    return Err(format!(
+                        "Validator {} is not active at epoch {}. Activation epoch: {}",
+                        index, current_epoch, validator.activation_epoch
+                    ));
+                }
+            }
+
+            verify_churn(
+                &state,
+                &spec,
+                "activated",
+                |validator| validator.activation_epoch,
+                |a, b| {
+                    a.activation_eligibility_epoch < b.activation_eligibility_epoch
+                        && a.activation_epoch > b.activation_epoch
+                },
+            )
+        })
+}
+
+/// Checks that, by `epoch`, the validators with `validator_indices` have exited after
+/// publishing voluntary exits at `exit_message_epoch`, no earlier than the exit delay allows and
+/// without exceeding the churn limit, and that they become withdrawable after
+/// `spec.min_validator_withdrawability_delay`.
+pub fn verify_exits<E: EthSpec>(
+    network: LocalNetwork<E>,
+    slot_duration: Duration,
+    epoch: Epoch,
+    validator_indices: Vec<usize>,
+    exit_message_epoch: Epoch,
+    spec: ChainSpec,
+) -> impl Future<Item = (), Error = String> {
+    epoch_delay(epoch, slot_duration, E::slots_per_epoch())
+        .and_then(|()| head_state(network))
+        .and_then(move |state| {
+            let current_epoch = state.current_epoch();
+            let earliest_epoch = state.compute_activation_exit_epoch(exit_message_epoch, &spec);
+
+            for &index in &validator_indices {
+                let validator = get_validator(&state, index)?;
+
+                if validator.exit_epoch == spec.far_future_epoch {
+                    return Err(format!(
+                        "Validator {} has not exited at epoch {}",
+                        index, current_epoch
+                    ));
+                }
+
+                if validator.exit_epoch < earliest_epoch {
+                    return Err(format!(
+                        "Validator {} exited at epoch {}, before epoch {}",
+                        index, validator.exit_epoch, earliest_epoch
+                    ));
+                }
+
+                if !validator.is_exited_at(current_epoch) {
+                    return Err(format!(
+                        "Validator {} is still active at epoch {}. Exit epoch: {}",
+                        index, current_epoch, validator.exit_epoch
+                    ));
+                }
+
+                let withdrawable_epoch =
+                    validator.exit_epoch + spec.min_validator_withdrawability_delay;
+                if validator.withdrawable_epoch != withdrawable_epoch {
+                    return Err(format!(
+                        "Validator {} is withdrawable at epoch {}, expected epoch {}",
+                        index, validator.withdrawable_epoch, withdrawable_epoch
+                    ));
+                }
+            }
+
+            verify_churn(
+                &state,
+                &spec,
+                "exited",
+                |validator| validator.exit_epoch,
+                |_, _| false,
+            )
+        })
+}
+
+/// Verifies that no more validators in `state` than the churn limit share any epoch returned by
+/// `churn_epoch` (ignoring the genesis epoch and the far future epoch), and that no pair of
+/// validators is `out_of_order`.
+fn verify_churn<E: EthSpec>(
+    state: &BeaconState<E>,
+    spec: &ChainSpec,
+    description: &str,
+    churn_epoch: impl Fn(&Validator) -> Epoch,
+    out_of_order: impl Fn(&Validator, &Validator) -> bool,
+) -> Result<(), String> {
+    let genesis_epoch = spec.genesis_slot.epoch(E::slots_per_epoch());
+    let active_validator_count = state
+        .validators
+        .iter()
+        .filter(|validator| validator.is_active_at(state.current_epoch()))
+        .count() as u64;
+    let churn_limit = std::cmp::max(
+        spec.min_per_epoch_churn_limit,
+        active_validator_count / spec.churn_limit_quotient,
+    );
+
+    let churned = state
+        .validators
+        .iter()
+        .filter(|validator| {
+            let epoch = churn_epoch(validator);
+            epoch != genesis_epoch && epoch != spec.far_future_epoch
+        })
+        .collect::<Vec<_>>();
+
+    for validator in &churned {
+        let epoch = churn_epoch(validator);
+        let count = churned
+            .iter()
+            .filter(|other| churn_epoch(other) == epoch)
+            .count() as u64;
+
+        if count > churn_limit {
+            return Err(format!(
+                "{} validators {} at epoch {}, exceeding the churn limit of {}",
+                count, description, epoch, churn_limit
+            ));
+        }
+
+        if churned.iter().any(|other| out_of_order(validator, other)) {
+            return Err(format!(
+                "Validators {} out of order at epoch {}",
+                description, epoch
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn get_validator<E: EthSpec>(state: &BeaconState<E>, index: usize) -> Result<&Validator, String> {
+    state.validators.get(index).ok_or_else(|| {
+        format!(
+            "Validator {} is not in the state at epoch {}",
+            index,
+            state.current_epoch()
+        )
+    })
+}
+
+/// Reads the head state of the first beacon node in `network`.
+fn head_state<E: EthSpec>(
+    network: LocalNetwork<E>,
+) -> impl Future<Item = BeaconState<E>, Error = String> {
+    network
+        .remote_nodes()
+        .and_then(|remote_nodes| {
+            remote_nodes
+                .into_iter()
+                .next()
+                .ok_or_else(|| "Network has no beacon nodes".to_string())
+        })
+        .into_future()
+        .and_then(|remote_node| {
+            let beacon = remote_node.http.beacon();
+            beacon
+                .get_head()
+                .map_err(|e| format!("Get head via http failed: {:?}", e))
+                .and_then(move |head| {
+                    beacon
+                        .get_state_by_root(head.state_root)
+                        .map(|(state, _root)| state)
+                        .map_err(|e| format!("Get state root via http failed: {:?}", e))
+                })
+        })
+}
+
+/// Verifies that all of the `heads` which have finalized the latest finalized slot agree upon the
+/// finalized block. Conflicts at earlier slots are detected by `LocalNetwork::wait_for_fault`
+/// whilst the simulation runs.
+fn verify_single_canonical_chain(heads: &[HeadResponse]) -> Result<(), String> {
+    let best = heads
+        .iter()
+        .max_by_key(|head| head.finalized_slot)
+        .ok_or_else(|| "Network has no beacon nodes".to_string())?;
+
+    for head in heads {
+        if head.finalized_slot == best.finalized_slot
+            && head.finalized_block_root != best.finalized_block_root
+        {
+            return Err(format!(
+                "Nodes finalized conflicting blocks at slot {}: {:?} and {:?}",
+                best.finalized_slot, best.finalized_block_root, head.finalized_block_root
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies that none of the validators with `validator_indices` are slashed in the state with
+/// `state_root`, as read from the first beacon node in `network`.
+fn verify_no_slashings<E: EthSpec>(
+    network: LocalNetwork<E>,
+    state_root: Hash256,
+    validator_indices: Vec<usize>,
+) -> impl Future<Item = (), Error = String> {
+    network
+        .remote_nodes()
+        .and_then(|remote_nodes| {
+            remote_nodes
+                .into_iter()
+                .next()
+                .ok_or_else(|| "Network has no beacon nodes".to_string())
+        })
+        .into_future()
+        .and_then(move |remote_node| {
+            remote_node
+                .http
+                .beacon()
+                .get_state_by_root(state_root)
+                .map(|(state, _root)| state)
+                .map_err(|e| format!("Get state root via http failed: {:?}", e))
+        })
+        .and_then(move |state: BeaconState<E>| {
+            let slashed = validator_indices
+                .into_iter()
+                .filter(|i| state.validators.get(*i).map_or(false, |v| v.slashed))
+                .collect::<Vec<_>>();
+
+            if slashed.is_empty() {
+                Ok(())
+            } else {
+                Err(format!("Honest validators were slashed: {:?}", slashed))
+            }
+        })
+}
+
 /// Delays for `epochs`, plus half a slot extra.
-fn epoch_delay(
+pub fn epoch_delay(
     epochs: Epoch,
     slot_duration: Duration,
     slots_per_epoch: u64,
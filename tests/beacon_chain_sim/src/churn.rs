@@ -0,0 +1,111 @@
+//! Changes the validator set whilst the simulation runs: new validators deposit via the eth1
+//! chain and existing validators voluntarily exit.
+use crate::checks::epoch_delay;
+use futures::{future, Future, IntoFuture};
+use node_test_rig::{LocalNetwork, ValidatorConfig};
+use std::time::Duration;
+use types::{
+    test_utils::{generate_deterministic_keypair, TestingVoluntaryExitBuilder},
+    ChainSpec, Epoch, EthSpec,
+};
+
+/// At `epoch`, submits a deposit of `amount` gwei to the eth1 chain of the `network` for each of
+/// the deterministic validators with `validator_indices`, then starts a validator client for them
+/// on the beacon node at index `node`.
+pub fn deposit_at<E: EthSpec>(
+    network: LocalNetwork<E>,
+    slot_duration: Duration,
+    epoch: Epoch,
+    node: usize,
+    validator_indices: Vec<usize>,
+    amount: u64,
+) -> impl Future<Item = (), Error = String> {
+    epoch_delay(epoch, slot_duration, E::slots_per_epoch())
+        .and_then(move |()| {
+            network
+                .eth1_chain()
+                .ok_or_else(|| "Network has no eth1 chain".to_string())
+                .map(|eth1_chain| {
+                    eth1_chain.deposit_deterministic::<E>(validator_indices.clone(), amount)
+                })
+                .into_future()
+                .flatten()
+                .map(move |()| (network, validator_indices))
+        })
+        .and_then(move |(network, validator_indices)| {
+            println!(
+                "Submitted deposits for validators {:?} at epoch {}",
+                validator_indices, epoch
+            );
+
+            network.add_validator_client(ValidatorConfig::default(), node, validator_indices)
+        })
+}
+
+/// At `epoch`, publishes a voluntary exit for each of the deterministic validators with
+/// `validator_indices` via the beacon node at index `node`.
+///
+/// The exits are valid from `epoch`, so the validators must have been active for at least
+/// `spec.persistent_committee_period` epochs.
+pub fn exit_at<E: EthSpec>(
+    network: LocalNetwork<E>,
+    slot_duration: Duration,
+    epoch: Epoch,
+    node: usize,
+    validator_indices: Vec<usize>,
+    spec: ChainSpec,
+) -> impl Future<Item = (), Error = String> {
+    epoch_delay(epoch, slot_duration, E::slots_per_epoch())
+        .and_then(move |()| {
+            network
+                .remote_nodes()
+                .and_then(|remote_nodes| {
+                    remote_nodes
+                        .into_iter()
+                        .nth(node)
+                        .ok_or_else(|| format!("No beacon node for index {}", node))
+                })
+                .into_future()
+        })
+        .and_then(|remote_node| {
+            remote_node
+                .http
+                .beacon()
+                .get_fork()
+                .map_err(|e| format!("Get fork via http failed: {:?}", e))
+                .map(|fork| (remote_node, fork))
+        })
+        .and_then(move |(remote_node, fork)| {
+            let validator = remote_node.http.validator();
+
+            future::join_all(
+                validator_indices
+                    .into_iter()
+                    .map(|index| {
+                        let mut builder = TestingVoluntaryExitBuilder::new(epoch, index as u64);
+                        builder.sign(&generate_deterministic_keypair(index).sk, &fork, &spec);
+
+                        validator
+                            .publish_voluntary_exit(builder.build())
+                            .map_err(|e| format!("Publish voluntary exit via http failed: {:?}", e))
+                            .and_then(move |status| {
+                                if status.is_valid() {
+                                    Ok(index)
+                                } else {
+                                    Err(format!(
+                                        "Voluntary exit of validator {} was not accepted: {:?}",
+                                        index, status
+                                    ))
+                                }
+                            })
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .map(move |exited| {
+            println!(
+                "Published voluntary exits for validators {:?} at epoch {}",
+                exited, epoch
+            )
+        })
+}
@@ -2,9 +2,21 @@
 //! `v` validators. A deposit contract is deployed at the start of the simulation using a local
 //! `ganache-cli` instance (you must have `ganache-cli` installed and avaliable on your path). All
 //! beacon nodes independently listen for genesis from the deposit contract, then start operating.
+//! Besides the first beacon node, the beacon nodes also discover each other via a discovery-only
+//! boot node.
+//!
+//! Each beacon node follows a `NodeBehaviour`: honest, offline for some epochs, publishing
+//! conflicting blocks or attesting late. The network must tolerate the misbehaving nodes.
+//!
+//! The validator set changes as the simulation runs: new validators deposit via the eth1 chain
+//! after genesis and some of the genesis validators voluntarily exit. The new validators must be
+//! activated, and the exiting validators must exit and become withdrawable, at the epochs allowed
+//! by the activation and exit queues.
 //!
 //! As the simulation runs, there are checks made to ensure that all components are running
-//! correctly. If any of these checks fail, the simulation will exit immediately.
+//! correctly. If any of these checks fail, the simulation will exit immediately. At the end of the
+//! run, the network must have finalized on a single canonical chain without slashing any honest
+//! validators.
 //!
 //! By default, the simulation will end as soon as all checks have finished. It may be configured
 //! to run indefinitely by setting `end_after_checks = false`.
@@ -19,18 +31,18 @@
 //! changed without a recompile.
 
 mod checks;
-mod local_network;
+mod churn;
+mod strategies;
 
 use env_logger::{Builder, Env};
-use eth1_test_rig::GanacheEth1Instance;
 use futures::{future, stream, Future, Stream};
-use local_network::LocalNetwork;
 use node_test_rig::{
-    environment::EnvironmentBuilder, testing_client_config, ClientGenesis, ValidatorConfig,
+    environment::EnvironmentBuilder, testing_client_config, LocalEth1Chain, LocalNetwork,
+    ValidatorConfig,
 };
-use std::time::{Duration, Instant};
-use tokio::timer::Interval;
-use types::MinimalEthSpec;
+use std::time::Duration;
+use strategies::NodeBehaviour;
+use types::{Epoch, MinimalEthSpec};
 
 pub type E = MinimalEthSpec;
 
@@ -38,14 +50,26 @@ fn main() {
     // Debugging output for libp2p and external crates.
     Builder::from_env(Env::default()).init();
 
-    let nodes = 4;
     let validators_per_node = 20;
     let log_level = "debug";
     let speed_up_factor = 4;
     let end_after_checks = true;
+    // One behaviour per beacon node. Misbehaving nodes must hold less than a third of the stake
+    // for the chain to keep finalizing.
+    let behaviours = vec![
+        NodeBehaviour::Honest,
+        NodeBehaviour::LateAttester {
+            delay: Duration::from_millis(1_000),
+        },
+        NodeBehaviour::EquivocatingProposer,
+        NodeBehaviour::Offline {
+            from_epoch: Epoch::new(5),
+            epochs: 2,
+        },
+    ];
 
     match async_sim(
-        nodes,
+        behaviours,
         validators_per_node,
         speed_up_factor,
         log_level,
@@ -60,7 +84,7 @@ fn main() {
 }
 
 fn async_sim(
-    node_count: usize,
+    behaviours: Vec<NodeBehaviour>,
     validators_per_node: usize,
     speed_up_factor: u64,
     log_level: &str,
@@ -80,85 +104,75 @@ fn async_sim(
     spec.seconds_per_day = eth1_block_time.as_secs() * spec.eth1_follow_distance * 2;
     spec.min_genesis_time = 0;
     spec.min_genesis_active_validator_count = 64;
+    // Allow the genesis validators to exit early in the simulation.
+    spec.persistent_committee_period = 0;
 
+    let node_count = behaviours.len();
     let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
     let initial_validator_count = spec.min_genesis_active_validator_count as usize;
     let total_validator_count = validators_per_node * node_count;
     let deposit_amount = env.eth2_config.spec.max_effective_balance;
+    let spec = env.eth2_config.spec.clone();
+    let max_head_lag = strategies::max_head_lag(&behaviours, E::slots_per_epoch());
+    let validator_indices = move |node: usize| {
+        (node * validators_per_node..(node + 1) * validators_per_node).collect::<Vec<_>>()
+    };
+    let honest_validator_indices = behaviours
+        .iter()
+        .enumerate()
+        .filter(|(_, behaviour)| behaviour.is_honest())
+        .flat_map(|(node, _)| validator_indices(node))
+        .collect::<Vec<_>>();
+    // Validators which deposit after genesis. There are no more than the churn limit, so that
+    // they may all be activated in the same epoch.
+    let deposit_epoch = Epoch::new(1);
+    let new_validator_indices = (total_validator_count
+        ..total_validator_count + spec.min_per_epoch_churn_limit as usize)
+        .collect::<Vec<_>>();
+    // Validators of the first node which voluntarily exit.
+    let exit_epoch = Epoch::new(2);
+    let exiting_validator_indices = validator_indices(0).into_iter().take(2).collect::<Vec<_>>();
 
     let context = env.core_context();
     let executor = context.executor.clone();
 
-    let future = GanacheEth1Instance::new()
+    let future = LocalEth1Chain::start(&context, eth1_block_time)
         /*
-         * Deploy the deposit contract, spawn tasks to keep creating new blocks and deposit
-         * validators.
+         * Start an eth1 chain with the deposit contract deployed, then submit the deposits for
+         * all validators.
          */
-        .map(move |ganache_eth1_instance| {
-            let deposit_contract = ganache_eth1_instance.deposit_contract;
-            let ganache = ganache_eth1_instance.ganache;
-            let eth1_endpoint = ganache.endpoint();
-            let deposit_contract_address = deposit_contract.address();
-
-            // Start a timer that produces eth1 blocks on an interval.
+        .map(move |eth1_chain| {
             executor.spawn(
-                Interval::new(Instant::now(), eth1_block_time)
-                    .map_err(|_| eprintln!("Eth1 block timer failed"))
-                    .for_each(move |_| ganache.evm_mine().map_err(|_| ()))
-                    .map_err(|_| eprintln!("Eth1 evm_mine failed"))
-                    .map(|_| ()),
+                eth1_chain
+                    .deposit_deterministic::<E>(
+                        (0..total_validator_count).collect(),
+                        deposit_amount,
+                    )
+                    .map_err(|e| eprintln!("Error submitting deposit: {}", e)),
+                "eth1_deposits",
             );
 
-            // Submit deposits to the deposit contract.
-            executor.spawn(
-                stream::unfold(0..total_validator_count, move |mut iter| {
-                    iter.next().map(|i| {
-                        println!("Submitting deposit for validator {}...", i);
-                        deposit_contract
-                            .deposit_deterministic_async::<E>(i, deposit_amount)
-                            .map(|_| ((), iter))
-                    })
-                })
-                .collect()
-                .map(|_| ())
-                .map_err(|e| eprintln!("Error submitting deposit: {}", e)),
-            );
-
-            let mut beacon_config = testing_client_config();
-
-            beacon_config.genesis = ClientGenesis::DepositContract;
-            beacon_config.eth1.endpoint = eth1_endpoint;
-            beacon_config.eth1.deposit_contract_address = deposit_contract_address;
-            beacon_config.eth1.deposit_contract_deploy_block = 0;
-            beacon_config.eth1.lowest_cached_block_number = 0;
-            beacon_config.eth1.follow_distance = 1;
-            beacon_config.dummy_eth1_backend = false;
-            beacon_config.sync_eth1_chain = true;
-
-            beacon_config
+            eth1_chain
         })
         /*
-         * Create a new `LocalNetwork` with one beacon node.
+         * Create a new `LocalNetwork` with one beacon node, following the eth1 chain.
          */
-        .and_then(move |beacon_config| {
-            LocalNetwork::new(context, beacon_config.clone())
+        .and_then(move |eth1_chain| {
+            let beacon_config = testing_client_config();
+
+            LocalNetwork::new_with_eth1(context, beacon_config.clone(), eth1_chain)
                 .map(|network| (network, beacon_config))
         })
         /*
-         * One by one, add beacon nodes to the network.
+         * Start a discovery-only boot node, then one by one, add beacon nodes to the network.
          */
         .and_then(move |(network, beacon_config)| {
             let network_1 = network.clone();
 
-            stream::unfold(0..node_count - 1, move |mut iter| {
-                iter.next().map(|_| {
-                    network_1
-                        .add_beacon_node(beacon_config.clone())
-                        .map(|()| ((), iter))
-                })
-            })
-            .collect()
-            .map(|_| network)
+            network
+                .add_boot_node()
+                .and_then(move |()| network_1.add_beacon_nodes(beacon_config, node_count - 1))
+                .map(|()| network)
         })
         /*
          * One by one, add validator clients to the network. Each validator client is attached to
@@ -176,11 +190,8 @@ fn async_sim(
 
             stream::unfold(0..node_count, move |mut iter| {
                 iter.next().map(|i| {
-                    let indices = (i * validators_per_node..(i + 1) * validators_per_node)
-                        .collect::<Vec<_>>();
-
                     network_1
-                        .add_validator_client(ValidatorConfig::default(), i, indices)
+                        .add_validator_client(ValidatorConfig::default(), i, validator_indices(i))
                         .map(|()| ((), iter))
                 })
             })
@@ -200,6 +211,21 @@ fn async_sim(
                     Box::new(future::empty().map_err(|()| "".to_string()))
                 };
 
+            // Each of these futures never completes, unless a behaviour cannot be applied.
+            let behaviour_futures = behaviours
+                .into_iter()
+                .enumerate()
+                .map(|(node, behaviour)| {
+                    strategies::apply(
+                        network.clone(),
+                        node,
+                        behaviour,
+                        validator_indices(node),
+                        slot_duration,
+                    )
+                })
+                .collect::<Vec<_>>();
+
             future::ok(())
                 // Check that the chain finalizes at the first given opportunity.
                 .join(checks::verify_first_finalization(
@@ -219,9 +245,73 @@ fn async_sim(
                     slot_duration,
                     total_validator_count,
                 ))
+                // Deposit for new validators after genesis, then check that they are activated
+                // once the deposits have been included and the activation queue allows.
+                .join(churn::deposit_at(
+                    network.clone(),
+                    slot_duration,
+                    deposit_epoch,
+                    0,
+                    new_validator_indices.clone(),
+                    deposit_amount,
+                ))
+                .join(checks::verify_activations(
+                    network.clone(),
+                    slot_duration,
+                    Epoch::new(20),
+                    new_validator_indices,
+                    spec.clone(),
+                ))
+                // Exit some of the genesis validators, then check that they exit and become
+                // withdrawable at the epochs allowed by the exit queue.
+                .join(churn::exit_at(
+                    network.clone(),
+                    slot_duration,
+                    exit_epoch,
+                    0,
+                    exiting_validator_indices.clone(),
+                    spec.clone(),
+                ))
+                .join(checks::verify_exits(
+                    network.clone(),
+                    slot_duration,
+                    Epoch::new(12),
+                    exiting_validator_indices,
+                    exit_epoch,
+                    spec,
+                ))
+                // Check that the network recovers from the misbehaving nodes, finalizing a single
+                // canonical chain without slashing any honest validators.
+                .join(checks::verify_end_of_run(
+                    network.clone(),
+                    slot_duration,
+                    Epoch::new(10),
+                    Epoch::new(8),
+                    honest_validator_indices,
+                ))
                 // End now or run forever, depending on the `end_after_checks` flag.
                 .join(final_future)
+                // Exit immediately if any node becomes unresponsive, falls behind or finalizes a
+                // conflicting block.
+                .select(network.wait_for_fault(max_head_lag).and_then(|faults| {
+                    Err(format!(
+                        "Network fault: {}",
+                        faults
+                            .iter()
+                            .map(|fault| fault.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                }))
                 .map(|_| network)
+                .map_err(|(e, _)| e)
+                // Exit immediately if the behaviour of any node cannot be applied.
+                .select(
+                    future::join_all(behaviour_futures)
+                        .and_then(|_| Err("Node behaviours ended unexpectedly".to_string())),
+                )
+                .map(|(network, _)| network)
+                .map_err(|(e, _)| e)
         })
         /*
          * End the simulation by dropping the network. This will kill all running beacon nodes and
@@ -0,0 +1,198 @@
+use crate::checks::epoch_delay;
+use futures::{
+    future::{self, loop_fn, Loop},
+    Future, IntoFuture,
+};
+use node_test_rig::{LocalBeaconNode, LocalNetwork};
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use types::{test_utils::generate_deterministic_keypair, BeaconBlock, Epoch, EthSpec, Slot};
+
+/// The graffiti included in the conflicting blocks published by an `EquivocatingProposer`.
+const EQUIVOCATION_GRAFFITI: [u8; 32] = *b"equivocating-proposer-simulation";
+
+/// The behaviour of a beacon node (and the validator client attached to it) during the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeBehaviour {
+    /// Follows the protocol.
+    Honest,
+    /// Ignores all messages to and from the other nodes for `epochs`, starting at `from_epoch`.
+    Offline { from_epoch: Epoch, epochs: u64 },
+    /// Publishes a second, conflicting block for each block proposed by its validators.
+    EquivocatingProposer,
+    /// Holds each attestation for `delay` before publishing it.
+    LateAttester { delay: Duration },
+}
+
+impl NodeBehaviour {
+    /// Returns `true` if the validators of a node with this behaviour never sign conflicting
+    /// messages, and so must never be slashed.
+    pub fn is_honest(self) -> bool {
+        match self {
+            NodeBehaviour::EquivocatingProposer => false,
+            _ => true,
+        }
+    }
+}
+
+/// Returns the number of slots the head of a node may lag behind the best head in the network
+/// before it is considered faulty, allowing for any nodes which go offline.
+pub fn max_head_lag(behaviours: &[NodeBehaviour], slots_per_epoch: u64) -> u64 {
+    behaviours
+        .iter()
+        .map(|behaviour| match behaviour {
+            NodeBehaviour::Offline { epochs, .. } => (epochs + 1) * slots_per_epoch,
+            _ => slots_per_epoch,
+        })
+        .max()
+        .unwrap_or(slots_per_epoch)
+}
+
+/// Returns a future which makes the beacon node at index `node` (which manages the validators
+/// with `validator_indices`) follow `behaviour`.
+///
+/// Intended to be started at genesis. The future never resolves, unless it encounters an error.
+pub fn apply<E: EthSpec>(
+    network: LocalNetwork<E>,
+    node: usize,
+    behaviour: NodeBehaviour,
+    validator_indices: Vec<usize>,
+    slot_duration: Duration,
+) -> Box<dyn Future<Item = (), Error = String> + Send> {
+    let future: Box<dyn Future<Item = (), Error = String> + Send> = match behaviour {
+        NodeBehaviour::Honest => Box::new(future::ok(())),
+        NodeBehaviour::Offline { from_epoch, epochs } => {
+            let network_1 = network.clone();
+            let peers = (0..network.beacon_node_count())
+                .filter(|peer| *peer != node)
+                .collect::<Vec<_>>();
+            let peers_1 = peers.clone();
+
+            Box::new(
+                epoch_delay(from_epoch, slot_duration, E::slots_per_epoch())
+                    .and_then(move |()| {
+                        peers
+                            .iter()
+                            .try_for_each(|peer| network.partition(node, *peer))
+                    })
+                    .and_then(move |()| {
+                        epoch_delay(Epoch::new(epochs), slot_duration, E::slots_per_epoch())
+                    })
+                    .and_then(move |()| {
+                        peers_1
+                            .iter()
+                            .try_for_each(|peer| network_1.heal(node, *peer))
+                    }),
+            )
+        }
+        NodeBehaviour::EquivocatingProposer => {
+            Box::new(equivocate(network, node, validator_indices, slot_duration))
+        }
+        NodeBehaviour::LateAttester { delay } => Box::new(
+            network
+                .with_beacon_node(node, |beacon_node| {
+                    beacon_node.set_attestation_publish_delay(delay)
+                })
+                .into_future(),
+        ),
+    };
+
+    Box::new(future.and_then(|()| future::empty()))
+}
+
+/// Twice per slot, checks whether one of `validator_indices` has proposed the head block of the
+/// beacon node at index `node` in the current slot. If so, a conflicting block is produced,
+/// signed by the same proposer and published via the next beacon node in the network.
+fn equivocate<E: EthSpec>(
+    network: LocalNetwork<E>,
+    node: usize,
+    validator_indices: Vec<usize>,
+    slot_duration: Duration,
+) -> impl Future<Item = (), Error = String> {
+    loop_fn(Slot::new(0), move |last_slot| {
+        let network = network.clone();
+        let validator_indices = validator_indices.clone();
+
+        Delay::new(Instant::now() + slot_duration / 2)
+            .map_err(|e| format!("Equivocation delay failed: {:?}", e))
+            .and_then(move |()| {
+                let block = network.with_beacon_node(node, |beacon_node| {
+                    conflicting_block(beacon_node, &validator_indices, last_slot)
+                })?;
+                let remote_nodes = network.remote_nodes()?;
+
+                Ok((block, remote_nodes))
+            })
+            .and_then(move |(block, remote_nodes)| {
+                let future: Box<dyn Future<Item = Slot, Error = String> + Send> = match block {
+                    Some(block) => {
+                        let slot = block.slot;
+                        let remote_node = &remote_nodes[(node + 1) % remote_nodes.len()];
+
+                        Box::new(
+                            remote_node
+                                .http
+                                .validator()
+                                .publish_block(block)
+                                .map(move |_| slot)
+                                .map_err(|e| format!("Unable to publish block: {:?}", e)),
+                        )
+                    }
+                    None => Box::new(future::ok(last_slot)),
+                };
+
+                future.map(Loop::<(), _>::Continue)
+            })
+    })
+}
+
+/// Returns a block which conflicts with the head block of `beacon_node`, if the head block is
+/// from a slot later than `last_slot` and was proposed by one of `validator_indices`.
+fn conflicting_block<E: EthSpec>(
+    beacon_node: &LocalBeaconNode<E>,
+    validator_indices: &[usize],
+    last_slot: Slot,
+) -> Result<Option<BeaconBlock<E>>, String> {
+    let beacon_chain = beacon_node
+        .client
+        .beacon_chain()
+        .ok_or_else(|| "Node does not have a beacon chain".to_string())?;
+
+    let head = beacon_chain.head();
+    let slot = head.beacon_block.slot;
+
+    if slot <= last_slot || slot == 0 {
+        return Ok(None);
+    }
+
+    let proposer = beacon_chain
+        .block_proposer(slot)
+        .map_err(|e| format!("Unable to get proposer: {:?}", e))?;
+
+    if !validator_indices.contains(&proposer) {
+        return Ok(None);
+    }
+
+    let parent_state = beacon_chain
+        .state_at_slot(slot - 1)
+        .map_err(|e| format!("Unable to get parent state: {:?}", e))?;
+    let fork = parent_state.fork.clone();
+
+    // The randao reveal of the proposer is the same for any block it proposes in this epoch.
+    let (mut block, _state) = beacon_chain
+        .produce_block_on_state(
+            parent_state,
+            slot,
+            head.beacon_block.body.randao_reveal.clone(),
+            Some(EQUIVOCATION_GRAFFITI),
+        )
+        .map_err(|e| format!("Unable to produce conflicting block: {:?}", e))?;
+
+    block.sign(
+        &generate_deterministic_keypair(proposer).sk,
+        &fork,
+        &beacon_chain.spec,
+    );
+
+    Ok(Some(block))
+}
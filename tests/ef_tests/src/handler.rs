@@ -35,7 +35,13 @@ pub trait Handler {
 
         // Iterate through test suites
         let test_cases = fs::read_dir(&handler_path)
-            .expect("handler dir exists")
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Unable to read handler dir {}: {:?}",
+                    handler_path.display(),
+                    e
+                )
+            })
             .flat_map(|entry| {
                 entry
                     .ok()
@@ -45,14 +51,21 @@ pub trait Handler {
             .flat_map(Result::ok)
             .map(|test_case_dir| {
                 let path = test_case_dir.path();
-                let case = Self::Case::load_from_dir(&path).expect("test should load");
+                let case = Self::Case::load_from_dir(&path).unwrap_or_else(|e| {
+                    panic!("Unable to load test from {}: {:?}", path.display(), e)
+                });
                 (path, case)
             })
             .collect();
 
         let results = Cases { test_cases }.test_results();
 
-        let name = format!("{}/{}", Self::runner_name(), Self::handler_name());
+        let name = format!(
+            "{}/{}/{}",
+            Self::config_name(),
+            Self::runner_name(),
+            Self::handler_name()
+        );
         crate::results::assert_tests_pass(&name, &handler_path, &results);
     }
 }
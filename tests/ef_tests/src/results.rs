@@ -14,7 +14,17 @@ pub fn assert_tests_pass(handler_name: &str, path: &Path, results: &[CaseResult]
             &results,
         );
         if !failed.is_empty() {
-            panic!("Tests failed (see above)");
+            let failed_paths = failed
+                .iter()
+                .map(|case| case.path.display().to_string())
+                .collect::<Vec<_>>();
+            panic!(
+                "{} of {} tests failed in {} (see above): {}",
+                failed.len(),
+                results.len(),
+                path.display(),
+                failed_paths.join(", ")
+            );
         }
     } else {
         println!("Passed {} tests in {}", results.len(), path.display());
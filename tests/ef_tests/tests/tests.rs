@@ -9,53 +9,50 @@ fn shuffling() {
     ShufflingHandler::<MainnetEthSpec>::run();
 }
 
-#[test]
-fn operations_deposit() {
-    OperationsHandler::<MinimalEthSpec, Deposit>::run();
-    OperationsHandler::<MainnetEthSpec, Deposit>::run();
-}
-
-#[test]
-fn operations_exit() {
-    OperationsHandler::<MinimalEthSpec, VoluntaryExit>::run();
-    OperationsHandler::<MainnetEthSpec, VoluntaryExit>::run();
-}
-
-#[test]
-fn operations_proposer_slashing() {
-    OperationsHandler::<MinimalEthSpec, ProposerSlashing>::run();
-    OperationsHandler::<MainnetEthSpec, ProposerSlashing>::run();
-}
-
-#[test]
-fn operations_attester_slashing() {
-    OperationsHandler::<MinimalEthSpec, AttesterSlashing<_>>::run();
-    OperationsHandler::<MainnetEthSpec, AttesterSlashing<_>>::run();
-}
+/// Defines a module named `$test_name` containing one test per preset, so that each
+/// handler/preset pair passes or fails as a separate test case (e.g.,
+/// `operations_deposit::minimal`).
+///
+/// Any type parameters after the handler are passed to it after the `EthSpec`.
+macro_rules! state_transition_test {
+    ($test_name:ident, $handler:ident$(, $param:ty)*; $($preset:ident => $spec:ty),+) => {
+        mod $test_name {
+            use super::*;
 
-#[test]
-fn operations_attestation() {
-    OperationsHandler::<MinimalEthSpec, Attestation<_>>::run();
-    OperationsHandler::<MainnetEthSpec, Attestation<_>>::run();
+            $(
+                #[test]
+                fn $preset() {
+                    $handler::<$spec$(, $param)*>::run();
+                }
+            )+
+        }
+    };
+    ($test_name:ident, $handler:ident$(, $param:ty)*) => {
+        state_transition_test!(
+            $test_name, $handler$(, $param)*;
+            minimal => MinimalEthSpec,
+            mainnet => MainnetEthSpec
+        );
+    };
 }
 
-#[test]
-fn operations_block_header() {
-    OperationsHandler::<MinimalEthSpec, BeaconBlock<_>>::run();
-    OperationsHandler::<MainnetEthSpec, BeaconBlock<_>>::run();
-}
+state_transition_test!(operations_deposit, OperationsHandler, Deposit);
+state_transition_test!(operations_exit, OperationsHandler, VoluntaryExit);
+state_transition_test!(
+    operations_proposer_slashing,
+    OperationsHandler,
+    ProposerSlashing
+);
+state_transition_test!(
+    operations_attester_slashing,
+    OperationsHandler,
+    AttesterSlashing<_>
+);
+state_transition_test!(operations_attestation, OperationsHandler, Attestation<_>);
+state_transition_test!(operations_block_header, OperationsHandler, BeaconBlock<_>);
 
-#[test]
-fn sanity_blocks() {
-    SanityBlocksHandler::<MinimalEthSpec>::run();
-    SanityBlocksHandler::<MainnetEthSpec>::run();
-}
-
-#[test]
-fn sanity_slots() {
-    SanitySlotsHandler::<MinimalEthSpec>::run();
-    SanitySlotsHandler::<MainnetEthSpec>::run();
-}
+state_transition_test!(sanity_blocks, SanityBlocksHandler);
+state_transition_test!(sanity_slots, SanitySlotsHandler);
 
 #[test]
 #[cfg(not(feature = "fake_crypto"))]
@@ -167,43 +164,42 @@ fn ssz_generic() {
     SszGenericHandler::<Containers>::run();
 }
 
-#[test]
-fn epoch_processing_justification_and_finalization() {
-    EpochProcessingHandler::<MinimalEthSpec, JustificationAndFinalization>::run();
-    EpochProcessingHandler::<MainnetEthSpec, JustificationAndFinalization>::run();
-}
-
-#[test]
-fn epoch_processing_rewards_and_penalties() {
-    EpochProcessingHandler::<MinimalEthSpec, RewardsAndPenalties>::run();
-    // Note: there are no reward and penalty tests for mainnet yet
-}
-
-#[test]
-fn epoch_processing_registry_updates() {
-    EpochProcessingHandler::<MinimalEthSpec, RegistryUpdates>::run();
-    EpochProcessingHandler::<MainnetEthSpec, RegistryUpdates>::run();
-}
-
-#[test]
-fn epoch_processing_slashings() {
-    EpochProcessingHandler::<MinimalEthSpec, Slashings>::run();
-    EpochProcessingHandler::<MainnetEthSpec, Slashings>::run();
-}
-
-#[test]
-fn epoch_processing_final_updates() {
-    EpochProcessingHandler::<MainnetEthSpec, FinalUpdates>::run();
-    EpochProcessingHandler::<MainnetEthSpec, FinalUpdates>::run();
-}
-
-#[test]
-fn genesis_initialization() {
-    GenesisInitializationHandler::<MinimalEthSpec>::run();
-}
-
-#[test]
-fn genesis_validity() {
-    GenesisValidityHandler::<MinimalEthSpec>::run();
-    // Note: there are no genesis validity tests for mainnet
-}
+state_transition_test!(
+    epoch_processing_justification_and_finalization,
+    EpochProcessingHandler,
+    JustificationAndFinalization
+);
+// Note: there are no reward and penalty tests for mainnet yet
+state_transition_test!(
+    epoch_processing_rewards_and_penalties,
+    EpochProcessingHandler,
+    RewardsAndPenalties;
+    minimal => MinimalEthSpec
+);
+state_transition_test!(
+    epoch_processing_registry_updates,
+    EpochProcessingHandler,
+    RegistryUpdates
+);
+state_transition_test!(
+    epoch_processing_slashings,
+    EpochProcessingHandler,
+    Slashings
+);
+state_transition_test!(
+    epoch_processing_final_updates,
+    EpochProcessingHandler,
+    FinalUpdates
+);
+
+// Note: there are no genesis tests for mainnet
+state_transition_test!(
+    genesis_initialization,
+    GenesisInitializationHandler;
+    minimal => MinimalEthSpec
+);
+state_transition_test!(
+    genesis_validity,
+    GenesisValidityHandler;
+    minimal => MinimalEthSpec
+);
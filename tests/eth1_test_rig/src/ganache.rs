@@ -1,4 +1,5 @@
-use futures::Future;
+use crate::DEPLOYER_ACCOUNTS_INDEX;
+use futures::{stream, Future, Stream};
 use serde_json::json;
 use std::io::prelude::*;
 use std::io::BufReader;
@@ -8,6 +9,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use web3::{
     transports::{EventLoopHandle, Http},
+    types::{Address, TransactionRequest, U256},
     Transport, Web3,
 };
 
@@ -117,17 +119,63 @@ impl GanacheInstance {
 
     /// Mines a single block.
     pub fn evm_mine(&self) -> impl Future<Item = (), Error = String> {
+        evm_mine(&self.web3)
+    }
+
+    /// Mines `count` blocks, one after the other.
+    pub fn fast_forward_blocks(&self, count: u64) -> impl Future<Item = (), Error = String> {
+        let web3 = self.web3.clone();
+
+        stream::iter_ok(0..count).for_each(move |_| evm_mine(&web3))
+    }
+
+    /// Transfers `amount` wei to `to` from the account with index `DEPLOYER_ACCOUNTS_INDEX`.
+    pub fn fund_account(
+        &self,
+        to: Address,
+        amount: U256,
+    ) -> impl Future<Item = (), Error = String> {
+        let web3 = self.web3.clone();
+
         self.web3
-            .transport()
-            .execute("evm_mine", vec![])
-            .map(|_| ())
-            .map_err(|_| {
-                "utils should mine new block with evm_mine (only works with ganache-cli!)"
-                    .to_string()
+            .eth()
+            .accounts()
+            .map_err(|e| format!("Failed to get accounts: {:?}", e))
+            .and_then(|accounts| {
+                accounts
+                    .get(DEPLOYER_ACCOUNTS_INDEX)
+                    .cloned()
+                    .ok_or_else(|| "Insufficient accounts for funding".to_string())
             })
+            .and_then(move |from| {
+                let tx_request = TransactionRequest {
+                    from,
+                    to: Some(to),
+                    gas: None,
+                    gas_price: None,
+                    value: Some(amount),
+                    data: None,
+                    nonce: None,
+                    condition: None,
+                };
+
+                web3.eth()
+                    .send_transaction(tx_request)
+                    .map_err(|e| format!("Failed to fund account: {:?}", e))
+            })
+            .map(|_| ())
     }
 }
 
+fn evm_mine(web3: &Web3<Http>) -> impl Future<Item = (), Error = String> {
+    web3.transport()
+        .execute("evm_mine", vec![])
+        .map(|_| ())
+        .map_err(|_| {
+            "utils should mine new block with evm_mine (only works with ganache-cli!)".to_string()
+        })
+}
+
 fn endpoint(port: u16) -> String {
     format!("http://localhost:{}", port)
 }
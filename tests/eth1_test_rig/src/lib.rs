@@ -30,12 +30,24 @@ pub struct GanacheEth1Instance {
 
 impl GanacheEth1Instance {
     pub fn new() -> impl Future<Item = Self, Error = String> {
-        GanacheInstance::new().into_future().and_then(|ganache| {
-            DepositContract::deploy(ganache.web3.clone(), 0, None).map(|deposit_contract| Self {
-                ganache,
-                deposit_contract,
+        Self::new_with_contract(BYTECODE.to_vec(), ABI.to_vec())
+    }
+
+    /// Starts a ganache instance with a custom deposit contract (e.g., a modified testnet contract
+    /// with a lower minimum deposit) deployed from `bytecode` and `abi`.
+    pub fn new_with_contract(
+        bytecode: Vec<u8>,
+        abi: Vec<u8>,
+    ) -> impl Future<Item = Self, Error = String> {
+        GanacheInstance::new()
+            .into_future()
+            .and_then(move |ganache| {
+                DepositContract::deploy_bytecode(ganache.web3.clone(), 0, &bytecode, &abi, None)
+                    .map(|deposit_contract| Self {
+                        ganache,
+                        deposit_contract,
+                    })
             })
-        })
     }
 
     pub fn endpoint(&self) -> String {
@@ -77,7 +89,11 @@ impl DepositContract {
         )
     }
 
-    fn deploy_bytecode(
+    /// Deploys a deposit contract from custom `bytecode` and `abi`, rather than the contract
+    /// bundled with `deposit_contract`.
+    ///
+    /// The contract must expose the same `deposit` function as the bundled contract.
+    pub fn deploy_bytecode(
         web3: Web3<Http>,
         confirmations: usize,
         bytecode: &[u8],
@@ -85,6 +101,7 @@ impl DepositContract {
         password: Option<String>,
     ) -> impl Future<Item = Self, Error = String> {
         let web3_1 = web3.clone();
+        let abi_1 = abi.to_vec();
 
         deploy_deposit_contract(
             web3.clone(),
@@ -100,7 +117,7 @@ impl DepositContract {
             )
         })
         .and_then(move |address| {
-            Contract::from_json(web3_1.eth(), address, ABI)
+            Contract::from_json(web3_1.eth(), address, &abi_1)
                 .map_err(|e| format!("Failed to init contract: {:?}", e))
         })
         .map(|contract| Self { contract, web3 })
@@ -2,20 +2,41 @@
 //!
 //! Intended to be used for testing and simulation purposes. Not for production.
 
+mod local_eth1;
+mod local_network;
+
 use beacon_node::ProductionBeaconNode;
 use environment::RuntimeContext;
-use futures::Future;
+use eth2_libp2p::PeerId;
+use futures::{
+    future::{self, loop_fn, Loop},
+    Future,
+};
+use network::FaultInjector;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tempdir::TempDir;
-use types::EthSpec;
+use tokio::timer::Delay;
+use types::{ChainSpec, EthSpec};
 use validator_client::{KeySource, ProductionValidatorClient};
 
 pub use beacon_node::{ClientConfig, ClientGenesis, ProductionClient};
 pub use environment;
-pub use remote_beacon_node::RemoteBeaconNode;
+pub use local_eth1::LocalEth1Chain;
+pub use local_network::{Fault, LocalNetwork};
+pub use remote_beacon_node::{HeadResponse, RemoteBeaconNode};
 pub use validator_client::Config as ValidatorConfig;
 
+/// The number of deterministic validators in the genesis state created by
+/// `testing_client_config`.
+pub const DEFAULT_VALIDATOR_COUNT: usize = 8;
+
+/// The maximum time to wait for the services of a beacon node to exit during `shutdown`.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often to check whether the services of a beacon node have exited during `shutdown`.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Provids a beacon node that is running in the current process on a given tokio executor (it
 /// is _local_ to this process).
 ///
@@ -31,12 +52,35 @@ impl<E: EthSpec> LocalBeaconNode<E> {
     /// The node created is using the same types as the node we use in production.
     pub fn production(
         context: RuntimeContext<E>,
-        mut client_config: ClientConfig,
+        client_config: ClientConfig,
     ) -> impl Future<Item = Self, Error = String> {
         // Creates a temporary directory that will be deleted once this `TempDir` is dropped.
         let datadir = TempDir::new("lighthouse_node_test_rig")
             .expect("should create temp directory for client datadir");
 
+        Self::production_in(context, client_config, datadir)
+    }
+
+    /// Starts a new node from the database of a node which was stopped via `shutdown`.
+    ///
+    /// The genesis in `client_config` is ignored, the node resumes from the head, fork choice and
+    /// operation pool persisted by the previous node.
+    pub fn restart(
+        context: RuntimeContext<E>,
+        mut client_config: ClientConfig,
+        stopped: StoppedBeaconNode,
+    ) -> impl Future<Item = Self, Error = String> {
+        client_config.genesis = ClientGenesis::Resume;
+
+        Self::production_in(context, client_config, stopped.datadir)
+    }
+
+    /// Starts a new, production beacon node which stores all its data in `datadir`.
+    fn production_in(
+        context: RuntimeContext<E>,
+        mut client_config: ClientConfig,
+        datadir: TempDir,
+    ) -> impl Future<Item = Self, Error = String> {
         client_config.data_dir = datadir.path().into();
         client_config.network.network_dir = PathBuf::from(datadir.path()).join("network");
 
@@ -45,25 +89,213 @@ impl<E: EthSpec> LocalBeaconNode<E> {
             datadir,
         })
     }
+
+    /// Gracefully shuts down the node, persisting its state to the database.
+    ///
+    /// Resolves once all services have released the database, returning the data directory so
+    /// that a new node may be started from it via `restart`.
+    pub fn shutdown(self) -> impl Future<Item = StoppedBeaconNode, Error = String> {
+        let LocalBeaconNode { client, datadir } = self;
+        let beacon_chain = client
+            .beacon_chain()
+            .map(|beacon_chain| Arc::downgrade(&beacon_chain));
+
+        // Dropping the client persists the beacon chain and signals each service to exit.
+        drop(client);
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        loop_fn((), move |()| {
+            let released = beacon_chain
+                .as_ref()
+                .map_or(true, |beacon_chain| beacon_chain.upgrade().is_none());
+
+            if released {
+                future::Either::A(future::ok(Loop::Break(())))
+            } else if Instant::now() >= deadline {
+                future::Either::A(future::err(
+                    "Timed out waiting for the beacon node services to exit".to_string(),
+                ))
+            } else {
+                future::Either::B(
+                    Delay::new(Instant::now() + SHUTDOWN_POLL_INTERVAL)
+                        .map_err(|e| format!("Shutdown delay failed: {:?}", e))
+                        .map(|()| Loop::Continue(())),
+                )
+            }
+        })
+        .map(move |()| StoppedBeaconNode { datadir })
+    }
+}
+
+/// The data directory of a `LocalBeaconNode` which has been shut down.
+///
+/// The directory is deleted when this struct is dropped, unless it is passed to
+/// `LocalBeaconNode::restart`.
+pub struct StoppedBeaconNode {
+    pub datadir: TempDir,
 }
 
 impl<E: EthSpec> LocalBeaconNode<E> {
+    /// Returns a builder which starts a node with the given `client_config`, allowing the genesis
+    /// parameters and the spec to be modified first.
+    pub fn builder(
+        context: RuntimeContext<E>,
+        client_config: ClientConfig,
+    ) -> LocalBeaconNodeBuilder<E> {
+        LocalBeaconNodeBuilder {
+            context,
+            client_config,
+        }
+    }
+
+    /// Stops the node's slot clock, so that it does not progress beyond the present slot until
+    /// `resume_slot_clock` is called.
+    pub fn pause_slot_clock(&self) -> Result<(), String> {
+        self.client
+            .beacon_chain()
+            .ok_or_else(|| "Cannot pause slot clock without a beacon chain".to_string())?
+            .slot_clock
+            .pause();
+        Ok(())
+    }
+
+    /// Resumes the node's slot clock, which immediately jumps forward to the present slot.
+    pub fn resume_slot_clock(&self) -> Result<(), String> {
+        self.client
+            .beacon_chain()
+            .ok_or_else(|| "Cannot resume slot clock without a beacon chain".to_string())?
+            .slot_clock
+            .resume();
+        Ok(())
+    }
+
+    /// Drops `percentage` percent of the gossip messages received by this node.
+    pub fn set_gossip_drop_percentage(&self, percentage: u8) -> Result<(), String> {
+        self.fault_injector()?
+            .set_gossip_drop_percentage(percentage);
+        Ok(())
+    }
+
+    /// Holds each block received via gossip for `delay` before importing it.
+    pub fn set_block_import_delay(&self, delay: Duration) -> Result<(), String> {
+        self.fault_injector()?.set_block_import_delay(delay);
+        Ok(())
+    }
+
+    /// Holds each attestation published by this node for `delay` before sending it to peers, so
+    /// that its validators appear to attest late.
+    pub fn set_attestation_publish_delay(&self, delay: Duration) -> Result<(), String> {
+        self.fault_injector()?.set_attestation_publish_delay(delay);
+        Ok(())
+    }
+
+    /// Ignores all messages received from `other`. Messages from this node will still reach
+    /// `other`, unless it is also partitioned from this node.
+    pub fn partition_from(&self, other: &Self) -> Result<(), String> {
+        self.fault_injector()?.partition(other.peer_id()?);
+        Ok(())
+    }
+
+    /// Reverses a previous call to `partition_from`.
+    pub fn heal_partition_from(&self, other: &Self) -> Result<(), String> {
+        self.fault_injector()?.heal(&other.peer_id()?);
+        Ok(())
+    }
+
+    /// Removes all faults from the node's network and resumes its slot clock.
+    pub fn clear_faults(&self) -> Result<(), String> {
+        self.fault_injector()?.clear();
+        self.resume_slot_clock()
+    }
+
+    fn fault_injector(&self) -> Result<Arc<FaultInjector>, String> {
+        self.client
+            .network_fault_injector()
+            .ok_or_else(|| "Cannot inject network faults without a network".to_string())
+    }
+
+    fn peer_id(&self) -> Result<PeerId, String> {
+        self.client
+            .libp2p_peer_id()
+            .ok_or_else(|| "Node does not have a libp2p network".to_string())
+    }
+
     /// Returns a `RemoteBeaconNode` that can connect to `self`. Useful for testing the node as if
     /// it were external this process.
-    pub fn remote_node(&self) -> Result<RemoteBeaconNode<E>, String> {
-        let socket_addr = self
-            .client
-            .http_listen_addr()
-            .ok_or_else(|| "A remote beacon node must have a http server".to_string())?;
-        Ok(RemoteBeaconNode::new(format!(
+    pub fn remote_node(&self) -> Result<RemoteBeaconNode<E>, lighthouse_error::Error> {
+        let socket_addr = self.client.http_listen_addr().ok_or_else(|| {
+            lighthouse_error::Error::other("A remote beacon node must have a http server")
+        })?;
+        RemoteBeaconNode::new(format!(
             "http://{}:{}",
             socket_addr.ip(),
             socket_addr.port()
-        ))?)
+        ))
     }
 }
 
+/// Builds a `LocalBeaconNode` with an interop genesis state.
+///
+/// Created via `LocalBeaconNode::builder`.
+pub struct LocalBeaconNodeBuilder<E: EthSpec> {
+    context: RuntimeContext<E>,
+    client_config: ClientConfig,
+}
+
+impl<E: EthSpec> LocalBeaconNodeBuilder<E> {
+    /// Start with `validator_count` deterministic validators in the genesis state.
+    pub fn validator_count(mut self, validator_count: usize) -> Self {
+        let genesis_time = match self.client_config.genesis {
+            ClientGenesis::Interop { genesis_time, .. } => genesis_time,
+            _ => now(),
+        };
+        self.client_config.genesis = ClientGenesis::Interop {
+            validator_count,
+            genesis_time,
+        };
+        self
+    }
+
+    /// Set the genesis time to `genesis_delay` from now.
+    pub fn genesis_delay(mut self, genesis_delay: Duration) -> Self {
+        let validator_count = match self.client_config.genesis {
+            ClientGenesis::Interop {
+                validator_count, ..
+            } => validator_count,
+            _ => DEFAULT_VALIDATOR_COUNT,
+        };
+        self.client_config.genesis = ClientGenesis::Interop {
+            validator_count,
+            genesis_time: now() + genesis_delay.as_secs(),
+        };
+        self
+    }
+
+    /// Modify the `ChainSpec` used by the node (e.g., to shorten slots or lower the genesis
+    /// validator count). Only affects this node, not the rest of the environment.
+    pub fn spec<F: FnOnce(&mut ChainSpec)>(mut self, modify: F) -> Self {
+        modify(&mut self.context.eth2_config.spec);
+        self
+    }
+
+    /// Starts the node.
+    pub fn build(self) -> impl Future<Item = LocalBeaconNode<E>, Error = String> {
+        LocalBeaconNode::production(self.context, self.client_config)
+    }
+}
+
+/// Returns a config for a node with `DEFAULT_VALIDATOR_COUNT` validators which reaches genesis
+/// immediately.
 pub fn testing_client_config() -> ClientConfig {
+    testing_client_config_with(DEFAULT_VALIDATOR_COUNT, Duration::from_secs(0))
+}
+
+/// Returns a config for a node with `validator_count` validators which reaches genesis after
+/// `genesis_delay`.
+///
+/// Changes to the spec are applied via `LocalBeaconNode::builder`, since the spec is defined by
+/// the `RuntimeContext` rather than the `ClientConfig`.
+pub fn testing_client_config_with(validator_count: usize, genesis_delay: Duration) -> ClientConfig {
     let mut client_config = ClientConfig::default();
 
     // Setting ports to `0` means that the OS will choose some available port.
@@ -76,21 +308,22 @@ pub fn testing_client_config() -> ClientConfig {
 
     client_config.dummy_eth1_backend = true;
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("should get system time")
-        .as_secs();
-
     client_config.genesis = ClientGenesis::Interop {
-        validator_count: 8,
-        genesis_time: now,
+        validator_count,
+        genesis_time: now() + genesis_delay.as_secs(),
     };
 
-    client_config.dummy_eth1_backend = true;
-
     client_config
 }
 
+/// Returns the current unix time, in seconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("should get system time")
+        .as_secs()
+}
+
 /// Provids a validator client that is running in the current process on a given tokio executor (it
 /// is _local_ to this process).
 ///
@@ -0,0 +1,110 @@
+//! Provides a `LocalEth1Chain`, a simulated eth1 chain with the deposit contract deployed, which
+//! beacon nodes in the current process can use for genesis, eth1 voting and deposit inclusion.
+//!
+//! The chain is provided by a dedicated `ganache-cli` instance, which must be installed and
+//! available on the `PATH`.
+use crate::{ClientConfig, ClientGenesis};
+use environment::RuntimeContext;
+use eth1_test_rig::GanacheEth1Instance;
+use exit_future::Signal;
+use futures::{stream, Future, Stream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::EthSpec;
+
+/// A simulated eth1 chain which produces a new block every `block_time`.
+///
+/// The chain stops producing blocks and the `ganache-cli` process is killed when this struct is
+/// dropped.
+pub struct LocalEth1Chain {
+    eth1: Arc<GanacheEth1Instance>,
+    block_time: Duration,
+    _exit_signal: Signal,
+}
+
+impl LocalEth1Chain {
+    /// Starts `ganache-cli`, deploys the deposit contract and spawns a task on the `context`
+    /// executor which mines a block every `block_time`.
+    pub fn start<E: EthSpec>(
+        context: &RuntimeContext<E>,
+        block_time: Duration,
+    ) -> impl Future<Item = Self, Error = String> {
+        let executor = context.executor.clone();
+
+        GanacheEth1Instance::new().map(move |eth1| {
+            let eth1 = Arc::new(eth1);
+            let (exit_signal, exit) = exit_future::signal();
+
+            let miner = eth1.clone();
+            executor.spawn(
+                exit.until(
+                    Interval::new(Instant::now(), block_time)
+                        .map_err(|e| eprintln!("Eth1 block timer failed: {:?}", e))
+                        .for_each(move |_| {
+                            miner
+                                .ganache
+                                .evm_mine()
+                                .map_err(|e| eprintln!("Eth1 evm_mine failed: {}", e))
+                        }),
+                )
+                .map(|_| ()),
+                "eth1_miner",
+            );
+
+            Self {
+                eth1,
+                block_time,
+                _exit_signal: exit_signal,
+            }
+        })
+    }
+
+    /// Returns the HTTP JSON-RPC endpoint of the eth1 node.
+    pub fn endpoint(&self) -> String {
+        self.eth1.endpoint()
+    }
+
+    /// Returns the address of the deposit contract, in `0x00ab...` format.
+    pub fn deposit_contract_address(&self) -> String {
+        self.eth1.deposit_contract.address()
+    }
+
+    /// Returns the time between eth1 blocks.
+    pub fn block_time(&self) -> Duration {
+        self.block_time
+    }
+
+    /// Configures `client_config` to follow this chain from the first block, using it for both
+    /// genesis and block production.
+    pub fn configure_beacon_node(&self, client_config: &mut ClientConfig) {
+        client_config.genesis = ClientGenesis::DepositContract;
+        client_config.eth1.endpoint = self.endpoint();
+        client_config.eth1.deposit_contract_address = self.deposit_contract_address();
+        client_config.eth1.deposit_contract_deploy_block = 0;
+        client_config.eth1.lowest_cached_block_number = 0;
+        client_config.eth1.follow_distance = 1;
+        client_config.dummy_eth1_backend = false;
+        client_config.sync_eth1_chain = true;
+    }
+
+    /// Submits a deposit of `amount` gwei for each of the deterministic validators with the given
+    /// `indices`, one after the other.
+    pub fn deposit_deterministic<E: EthSpec>(
+        &self,
+        indices: Vec<usize>,
+        amount: u64,
+    ) -> impl Future<Item = (), Error = String> {
+        let deposit_contract = self.eth1.deposit_contract.clone();
+
+        stream::unfold(indices.into_iter(), move |mut iter| {
+            iter.next().map(|index| {
+                deposit_contract
+                    .deposit_deterministic_async::<E>(index, amount)
+                    .map(|()| ((), iter))
+            })
+        })
+        .collect()
+        .map(|_| ())
+    }
+}
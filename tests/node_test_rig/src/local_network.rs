@@ -0,0 +1,549 @@
+//! Provides a `LocalNetwork`, which runs a set of beacon nodes and validator clients in the
+//! current process.
+//!
+//! The beacon nodes discover each other via the first node's ENR (and the ENRs of any
+//! discovery-only boot nodes) and communicate using their real libp2p stacks on localhost. The
+//! network can be watched until it reaches some epoch, or until a fault (e.g., an unresponsive
+//! node or conflicting finality) is detected.
+use crate::{
+    ClientConfig, HeadResponse, LocalBeaconNode, LocalEth1Chain, LocalValidatorClient,
+    RemoteBeaconNode, ValidatorConfig,
+};
+use boot_node::{BootNode, NetworkConfig};
+use environment::RuntimeContext;
+use futures::{
+    future::{self, loop_fn, Loop},
+    stream, Future, IntoFuture, Stream,
+};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::UdpSocket;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempdir::TempDir;
+use tokio::timer::Delay;
+use types::{Epoch, EthSpec, Slot};
+
+/// A problem with the network which means it is not operating correctly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// The beacon node with the given index did not respond to a HTTP request.
+    Unresponsive { node: usize, error: String },
+    /// The head of the beacon node with the given index is too far behind the best head in the
+    /// network.
+    HeadLag {
+        node: usize,
+        head_slot: Slot,
+        best_slot: Slot,
+    },
+    /// Two beacon nodes have finalized different blocks at the same slot.
+    FinalityConflict { nodes: (usize, usize), slot: Slot },
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::Unresponsive { node, error } => {
+                write!(f, "node {} is unresponsive: {}", node, error)
+            }
+            Fault::HeadLag {
+                node,
+                head_slot,
+                best_slot,
+            } => write!(
+                f,
+                "node {} has head at slot {} whilst the best head is at slot {}",
+                node, head_slot, best_slot
+            ),
+            Fault::FinalityConflict { nodes, slot } => write!(
+                f,
+                "nodes {} and {} finalized different blocks at slot {}",
+                nodes.0, nodes.1, slot
+            ),
+        }
+    }
+}
+
+/// Helper struct to reduce `Arc` usage.
+pub struct Inner<E: EthSpec> {
+    context: RuntimeContext<E>,
+    beacon_nodes: RwLock<Vec<LocalBeaconNode<E>>>,
+    validator_clients: RwLock<Vec<LocalValidatorClient<E>>>,
+    /// Each boot node, along with the directory holding its network key.
+    boot_nodes: RwLock<Vec<(BootNode, TempDir)>>,
+    eth1_chain: Option<LocalEth1Chain>,
+}
+
+/// Represents a set of interconnected `LocalBeaconNode` and `LocalValidatorClient`.
+///
+/// Provides functions to allow adding new beacon nodes and validators.
+pub struct LocalNetwork<E: EthSpec> {
+    inner: Arc<Inner<E>>,
+}
+
+impl<E: EthSpec> Clone for LocalNetwork<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<E: EthSpec> Deref for LocalNetwork<E> {
+    type Target = Inner<E>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
+
+impl<E: EthSpec> LocalNetwork<E> {
+    /// Creates a new network with a single `BeaconNode`.
+    pub fn new(
+        context: RuntimeContext<E>,
+        beacon_config: ClientConfig,
+    ) -> impl Future<Item = Self, Error = String> {
+        Self::build(context, beacon_config, None)
+    }
+
+    /// Creates a new network with a single `BeaconNode`, where all beacon nodes follow the given
+    /// `eth1_chain` for genesis and eth1 voting.
+    ///
+    /// The `eth1_chain` runs until the network is dropped.
+    pub fn new_with_eth1(
+        context: RuntimeContext<E>,
+        mut beacon_config: ClientConfig,
+        eth1_chain: LocalEth1Chain,
+    ) -> impl Future<Item = Self, Error = String> {
+        eth1_chain.configure_beacon_node(&mut beacon_config);
+        Self::build(context, beacon_config, Some(eth1_chain))
+    }
+
+    fn build(
+        context: RuntimeContext<E>,
+        beacon_config: ClientConfig,
+        eth1_chain: Option<LocalEth1Chain>,
+    ) -> impl Future<Item = Self, Error = String> {
+        LocalBeaconNode::production(context.service_context("boot_node".into()), beacon_config).map(
+            |beacon_node| Self {
+                inner: Arc::new(Inner {
+                    context,
+                    beacon_nodes: RwLock::new(vec![beacon_node]),
+                    validator_clients: RwLock::new(vec![]),
+                    boot_nodes: RwLock::new(vec![]),
+                    eth1_chain,
+                }),
+            },
+        )
+    }
+
+    /// Returns the simulated eth1 chain followed by the beacon nodes, if any.
+    pub fn eth1_chain(&self) -> Option<&LocalEth1Chain> {
+        self.eth1_chain.as_ref()
+    }
+
+    /// Returns the number of beacon nodes in the network.
+    ///
+    /// Note: does not count nodes that are external to this `LocalNetwork` that may have connected
+    /// (e.g., another Lighthouse process on the same machine.)
+    pub fn beacon_node_count(&self) -> usize {
+        self.beacon_nodes.read().len()
+    }
+
+    /// Returns the number of validator clients in the network.
+    ///
+    /// Note: does not count nodes that are external to this `LocalNetwork` that may have connected
+    /// (e.g., another Lighthouse process on the same machine.)
+    pub fn validator_client_count(&self) -> usize {
+        self.validator_clients.read().len()
+    }
+
+    /// Starts a discovery-only boot node on localhost.
+    ///
+    /// Beacon nodes added after the boot node use it (in addition to the 0'th beacon node) to
+    /// discover their peers.
+    pub fn add_boot_node(&self) -> impl Future<Item = (), Error = String> {
+        let index = self.boot_nodes.read().len();
+        let context = self
+            .context
+            .service_context(format!("discv5_boot_node_{}", index));
+        let self_1 = self.clone();
+
+        let network_dir = TempDir::new("lighthouse_boot_node")
+            .expect("should create temp directory for boot node network dir");
+
+        unused_udp_port()
+            .into_future()
+            .and_then(move |port| {
+                let mut config = NetworkConfig::default();
+                config.network_dir = network_dir.path().to_path_buf();
+                config.listen_address = "127.0.0.1".parse().expect("valid ip address");
+                config.discovery_port = port;
+
+                BootNode::new(context, config).map(|boot_node| (boot_node, network_dir))
+            })
+            .map(move |boot_node| self_1.boot_nodes.write().push(boot_node))
+    }
+
+    /// Adds a beacon node to the network, connecting to the 0'th beacon node and any boot nodes via
+    /// ENR.
+    ///
+    /// If the network has an eth1 chain, the node is configured to follow it.
+    pub fn add_beacon_node(
+        &self,
+        mut beacon_config: ClientConfig,
+    ) -> impl Future<Item = (), Error = String> {
+        let self_1 = self.clone();
+
+        if let Some(eth1_chain) = self.eth1_chain() {
+            eth1_chain.configure_beacon_node(&mut beacon_config);
+        }
+
+        self.beacon_nodes
+            .read()
+            .first()
+            .map(|boot_node| {
+                beacon_config.network.boot_nodes.push(
+                    boot_node
+                        .client
+                        .enr()
+                        .expect("bootnode must have a network"),
+                );
+            })
+            .expect("should have atleast one node");
+
+        beacon_config.network.boot_nodes.extend(
+            self.boot_nodes
+                .read()
+                .iter()
+                .map(|(boot_node, _)| boot_node.enr().clone()),
+        );
+
+        let index = self.beacon_nodes.read().len();
+
+        LocalBeaconNode::production(
+            self.context.service_context(format!("node_{}", index)),
+            beacon_config,
+        )
+        .map(move |beacon_node| {
+            self_1.beacon_nodes.write().push(beacon_node);
+        })
+    }
+
+    /// Adds `count` beacon nodes to the network, one after the other.
+    pub fn add_beacon_nodes(
+        &self,
+        beacon_config: ClientConfig,
+        count: usize,
+    ) -> impl Future<Item = (), Error = String> {
+        let self_1 = self.clone();
+
+        stream::unfold(0..count, move |mut iter| {
+            iter.next().map(|_| {
+                self_1
+                    .add_beacon_node(beacon_config.clone())
+                    .map(|()| ((), iter))
+            })
+        })
+        .collect()
+        .map(|_| ())
+    }
+
+    /// Adds a validator client to the network, connecting it to the beacon node with index
+    /// `beacon_node`.
+    pub fn add_validator_client(
+        &self,
+        mut validator_config: ValidatorConfig,
+        beacon_node: usize,
+        keypair_indices: Vec<usize>,
+    ) -> impl Future<Item = (), Error = String> {
+        let index = self.validator_clients.read().len();
+        let context = self.context.service_context(format!("validator_{}", index));
+        let self_1 = self.clone();
+
+        self.beacon_nodes
+            .read()
+            .get(beacon_node)
+            .map(move |beacon_node| {
+                let socket_addr = beacon_node
+                    .client
+                    .http_listen_addr()
+                    .expect("Must have http started");
+
+                validator_config.http_server =
+                    format!("http://{}:{}", socket_addr.ip(), socket_addr.port());
+
+                validator_config
+            })
+            .ok_or_else(|| format!("No beacon node for index {}", beacon_node))
+            .into_future()
+            .and_then(move |validator_config| {
+                LocalValidatorClient::production_with_insecure_keypairs(
+                    context,
+                    validator_config,
+                    &keypair_indices,
+                )
+            })
+            .map(move |validator_client| self_1.validator_clients.write().push(validator_client))
+    }
+
+    /// For all beacon nodes in `Self`, return a HTTP client to access each nodes HTTP API.
+    pub fn remote_nodes(&self) -> Result<Vec<RemoteBeaconNode<E>>, String> {
+        let beacon_nodes = self.beacon_nodes.read();
+
+        beacon_nodes
+            .iter()
+            .map(|beacon_node| beacon_node.remote_node().map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    /// Partitions the beacon nodes with indices `a` and `b`, so that neither processes any message
+    /// received from the other.
+    pub fn partition(&self, a: usize, b: usize) -> Result<(), String> {
+        let beacon_nodes = self.beacon_nodes.read();
+        let (node_a, node_b) = get_pair(&beacon_nodes, a, b)?;
+
+        node_a.partition_from(node_b)?;
+        node_b.partition_from(node_a)
+    }
+
+    /// Reverses a previous call to `partition`.
+    pub fn heal(&self, a: usize, b: usize) -> Result<(), String> {
+        let beacon_nodes = self.beacon_nodes.read();
+        let (node_a, node_b) = get_pair(&beacon_nodes, a, b)?;
+
+        node_a.heal_partition_from(node_b)?;
+        node_b.heal_partition_from(node_a)
+    }
+
+    /// Calls `f` with the beacon node at `index`, e.g., to inject a fault into that node.
+    pub fn with_beacon_node<F, T>(&self, index: usize, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&LocalBeaconNode<E>) -> Result<T, String>,
+    {
+        self.beacon_nodes
+            .read()
+            .get(index)
+            .ok_or_else(|| format!("No beacon node for index {}", index))
+            .and_then(f)
+    }
+
+    /// Returns the duration of a slot, according to the spec of the network.
+    pub fn slot_duration(&self) -> Duration {
+        Duration::from_millis(self.context.eth2_config.spec.milliseconds_per_slot)
+    }
+
+    /// Returns the head of each beacon node (or the error encountered when requesting it), in the
+    /// order the nodes were added.
+    pub fn heads(&self) -> impl Future<Item = Vec<Result<HeadResponse, String>>, Error = String> {
+        self.remote_nodes().into_future().and_then(|remote_nodes| {
+            future::join_all(remote_nodes.into_iter().map(|remote_node| {
+                remote_node.http.beacon().get_head().then(|result| {
+                    Ok::<_, String>(
+                        result.map_err(|e| format!("Get head via http failed: {:?}", e)),
+                    )
+                })
+            }))
+        })
+    }
+
+    /// Checks the network once, returning any faults which are present.
+    ///
+    /// A node is considered to be lagging if its head is more than `max_head_lag` slots behind the
+    /// best head in the network.
+    pub fn faults(&self, max_head_lag: u64) -> impl Future<Item = Vec<Fault>, Error = String> {
+        self.heads()
+            .map(move |heads| find_faults(&heads, max_head_lag))
+    }
+
+    /// Checks the network once per slot, resolving with the faults as soon as any are detected.
+    pub fn wait_for_fault(
+        &self,
+        max_head_lag: u64,
+    ) -> impl Future<Item = Vec<Fault>, Error = String> {
+        let slot_duration = self.slot_duration();
+
+        loop_fn(self.clone(), move |network| {
+            slot_delay(slot_duration)
+                .and_then(move |()| network.faults(max_head_lag).map(|faults| (network, faults)))
+                .map(|(network, faults)| {
+                    if faults.is_empty() {
+                        Loop::Continue(network)
+                    } else {
+                        Loop::Break(faults)
+                    }
+                })
+        })
+    }
+
+    /// Checks the network once per slot, resolving once the head of every beacon node is in
+    /// `epoch` or later.
+    ///
+    /// Returns an error describing the faults if any are detected before `epoch` is reached.
+    pub fn wait_for_epoch(
+        &self,
+        epoch: Epoch,
+        max_head_lag: u64,
+    ) -> impl Future<Item = (), Error = String> {
+        let slot_duration = self.slot_duration();
+        let target_slot = epoch.start_slot(E::slots_per_epoch());
+
+        loop_fn(self.clone(), move |network| {
+            slot_delay(slot_duration)
+                .and_then(move |()| network.heads().map(|heads| (network, heads)))
+                .and_then(move |(network, heads)| {
+                    let faults = find_faults(&heads, max_head_lag);
+
+                    if !faults.is_empty() {
+                        Err(format!(
+                            "Network faults detected before epoch {}: {}",
+                            epoch,
+                            faults
+                                .iter()
+                                .map(|fault| fault.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ))
+                    } else if heads.iter().flatten().all(|head| head.slot >= target_slot) {
+                        Ok(Loop::Break(()))
+                    } else {
+                        Ok(Loop::Continue(network))
+                    }
+                })
+        })
+    }
+}
+
+/// Returns the beacon nodes with indices `a` and `b`, which must be distinct.
+fn get_pair<E: EthSpec>(
+    beacon_nodes: &[LocalBeaconNode<E>],
+    a: usize,
+    b: usize,
+) -> Result<(&LocalBeaconNode<E>, &LocalBeaconNode<E>), String> {
+    if a == b {
+        return Err("Cannot partition a beacon node from itself".to_string());
+    }
+
+    let get = |index: usize| {
+        beacon_nodes
+            .get(index)
+            .ok_or_else(|| format!("No beacon node for index {}", index))
+    };
+
+    Ok((get(a)?, get(b)?))
+}
+
+/// Finds a UDP port which is unused at the time of the call (it is not reserved).
+fn unused_udp_port() -> Result<u16, String> {
+    UdpSocket::bind("127.0.0.1:0")
+        .and_then(|socket| socket.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find an unused UDP port: {:?}", e))
+}
+
+/// Delays for a single slot.
+fn slot_delay(slot_duration: Duration) -> impl Future<Item = (), Error = String> {
+    Delay::new(Instant::now() + slot_duration).map_err(|e| format!("Slot delay failed: {:?}", e))
+}
+
+/// Returns the faults which are evident from the `heads` of each beacon node.
+fn find_faults(heads: &[Result<HeadResponse, String>], max_head_lag: u64) -> Vec<Fault> {
+    let mut faults = vec![];
+
+    let best_slot = heads
+        .iter()
+        .flatten()
+        .map(|head| head.slot)
+        .max()
+        .unwrap_or_else(|| Slot::new(0));
+
+    // Maps each finalized slot to the first node to finalize it and the block it finalized.
+    let mut finalized: HashMap<Slot, (usize, _)> = HashMap::new();
+
+    for (node, head) in heads.iter().enumerate() {
+        let head = match head {
+            Ok(head) => head,
+            Err(error) => {
+                faults.push(Fault::Unresponsive {
+                    node,
+                    error: error.clone(),
+                });
+                continue;
+            }
+        };
+
+        if head.slot + max_head_lag < best_slot {
+            faults.push(Fault::HeadLag {
+                node,
+                head_slot: head.slot,
+                best_slot,
+            });
+        }
+
+        let (other, root) = *finalized
+            .entry(head.finalized_slot)
+            .or_insert((node, head.finalized_block_root));
+        if root != head.finalized_block_root {
+            faults.push(Fault::FinalityConflict {
+                nodes: (other, node),
+                slot: head.finalized_slot,
+            });
+        }
+    }
+
+    faults
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Hash256;
+
+    fn head(slot: u64, finalized_slot: u64, finalized_block_root: u64) -> HeadResponse {
+        HeadResponse {
+            slot: Slot::new(slot),
+            block_root: Hash256::zero(),
+            state_root: Hash256::zero(),
+            finalized_slot: Slot::new(finalized_slot),
+            finalized_block_root: Hash256::from_low_u64_be(finalized_block_root),
+            justified_slot: Slot::new(0),
+            justified_block_root: Hash256::zero(),
+            previous_justified_slot: Slot::new(0),
+            previous_justified_block_root: Hash256::zero(),
+            duty_dependent_root: None,
+        }
+    }
+
+    #[test]
+    fn detects_faults() {
+        let healthy = vec![Ok(head(10, 8, 1)), Ok(head(9, 8, 1)), Ok(head(10, 0, 0))];
+        assert_eq!(find_faults(&healthy, 2), vec![]);
+
+        let faulty = vec![
+            Ok(head(10, 8, 1)),
+            Err("timeout".to_string()),
+            Ok(head(4, 0, 0)),
+            Ok(head(10, 8, 2)),
+        ];
+        assert_eq!(
+            find_faults(&faulty, 2),
+            vec![
+                Fault::Unresponsive {
+                    node: 1,
+                    error: "timeout".to_string()
+                },
+                Fault::HeadLag {
+                    node: 2,
+                    head_slot: Slot::new(4),
+                    best_slot: Slot::new(10)
+                },
+                Fault::FinalityConflict {
+                    nodes: (0, 3),
+                    slot: Slot::new(8)
+                },
+            ]
+        );
+    }
+}
@@ -1,26 +1,27 @@
 use crate::{
-    duties_service::{DutiesService, ValidatorDuty},
+    beacon_node_fallback::BeaconNodeFallback,
+    duties_service::{duration_into_slot, DutiesService, DutyTiming, ValidatorDuty},
+    http_metrics::metrics,
     validator_store::ValidatorStore,
 };
 use environment::RuntimeContext;
 use exit_future::Signal;
-use futures::{Future, Stream};
-use remote_beacon_node::{PublishStatus, RemoteBeaconNode};
-use slog::{crit, info, trace};
+use futures::{future, Future, Stream};
+use remote_beacon_node::PublishStatus;
+use slog::{crit, debug, info, trace, warn};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::timer::Interval;
-use types::{ChainSpec, CommitteeIndex, EthSpec, Slot};
+use std::time::Duration;
+use types::{Attestation, ChainSpec, CommitteeIndex, EthSpec, Slot};
 
 /// Builds an `AttestationService`.
 pub struct AttestationServiceBuilder<T, E: EthSpec> {
     duties_service: Option<DutiesService<T, E>>,
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<T>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<Arc<BeaconNodeFallback<T, E>>>,
     context: Option<RuntimeContext<E>>,
 }
 
@@ -30,7 +31,7 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
             duties_service: None,
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
         }
     }
@@ -50,8 +51,8 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: Arc<BeaconNodeFallback<T, E>>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -72,9 +73,9 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build AttestationService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build AttestationService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build AttestationService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build AttestationService without runtime_context")?,
@@ -88,7 +89,7 @@ pub struct Inner<T, E: EthSpec> {
     duties_service: DutiesService<T, E>,
     validator_store: ValidatorStore<T, E>,
     slot_clock: T,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
     context: RuntimeContext<E>,
 }
 
@@ -97,6 +98,9 @@ pub struct Inner<T, E: EthSpec> {
 /// If any validators are on the same committee, a single attestation will be downloaded and
 /// returned to the beacon node. This attestation will have a signature from each of the
 /// validators.
+///
+/// Validators which have been selected as aggregators then publish the best aggregate known to the
+/// beacon node 2/3rds of the way through the slot.
 pub struct AttestationService<T, E: EthSpec> {
     inner: Arc<Inner<T, E>>,
 }
@@ -119,22 +123,14 @@ impl<T, E: EthSpec> Deref for AttestationService<T, E> {
 
 impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
     /// Starts the service which periodically produces attestations.
-    pub fn start_update_service(&self, spec: &ChainSpec) -> Result<Signal, String> {
+    pub fn start_update_service(&self, _spec: &ChainSpec) -> Result<Signal, String> {
         let context = &self.context;
         let log = context.log.clone();
 
-        let duration_to_next_slot = self
-            .slot_clock
-            .duration_to_next_slot()
-            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
-
-        let interval = {
-            let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
-            Interval::new(
-                Instant::now() + duration_to_next_slot + slot_duration / 3,
-                slot_duration,
-            )
-        };
+        let interval =
+            DutyTiming::Attestation.interval(&self.slot_clock, Duration::from_secs(0))?;
+        let aggregate_interval =
+            DutyTiming::Aggregate.interval(&self.slot_clock, Duration::from_secs(0))?;
 
         let (exit_signal, exit_fut) = exit_future::signal();
         let service = self.clone();
@@ -144,6 +140,7 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
 
         context.executor.spawn(
             exit_fut
+                .clone()
                 .until(
                     interval
                         .map_err(move |e| {
@@ -154,6 +151,11 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
                             }
                         })
                         .for_each(move |_| {
+                            metrics::observe_duty_lateness(
+                                DutyTiming::Attestation,
+                                &service.slot_clock,
+                            );
+
                             if let Err(e) = service.spawn_attestation_tasks() {
                                 crit!(
                                     log_2,
@@ -171,13 +173,55 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
                         }),
                 )
                 .map(move |_| info!(log_3, "Shutdown complete")),
+            "attestation_service",
+        );
+
+        let service = self.clone();
+        let log_1 = log.clone();
+        let log_2 = log.clone();
+
+        context.executor.spawn(
+            exit_fut
+                .until(
+                    aggregate_interval
+                        .map_err(move |e| {
+                            crit! {
+                                log_1,
+                                "Timer thread failed";
+                                "error" => format!("{}", e)
+                            }
+                        })
+                        .for_each(move |_| {
+                            metrics::observe_duty_lateness(
+                                DutyTiming::Aggregate,
+                                &service.slot_clock,
+                            );
+
+                            if let Err(e) = service.spawn_aggregation_tasks() {
+                                crit!(
+                                    log_2,
+                                    "Failed to spawn aggregation tasks";
+                                    "error" => e
+                                )
+                            } else {
+                                trace!(
+                                    log_2,
+                                    "Spawned aggregation tasks";
+                                )
+                            }
+
+                            Ok(())
+                        }),
+                )
+                .map(|_| ()),
+            "aggregation_service",
         );
 
         Ok(exit_signal)
     }
 
-    /// For each each required attestation, spawn a new task that downloads, signs and uploads the
-    /// attestation to the beacon node.
+    /// Spawn a task that downloads the attestations of each committee with a local validator at
+    /// the current slot, signs them and uploads them to the beacon node.
     fn spawn_attestation_tasks(&self) -> Result<(), String> {
         let service = self.clone();
 
@@ -186,10 +230,58 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             .now()
             .ok_or_else(|| "Failed to read slot clock".to_string())?;
 
+        if !service.validator_store.signing_enabled() {
+            debug!(
+                service.context.log,
+                "Skipping attestations whilst signing is disabled";
+                "slot" => slot.as_u64()
+            );
+            return Ok(());
+        }
+
+        if service.duties_service.attesters(slot).is_empty() {
+            return Ok(());
+        }
+
+        let log = service.context.log.clone();
+
+        // A single task produces and publishes the attestations of every committee, so that a
+        // validator client with validators in many committees makes two requests per slot.
+        //
+        // The duties are re-downloaded first if a re-org has changed the shuffling since they
+        // were last polled.
+        service.context.executor.spawn(
+            self.duties_service
+                .refresh_reorged_duties(slot)
+                .then(move |result| {
+                    if let Err(e) = result {
+                        warn!(
+                            log,
+                            "Unable to check duties for re-org";
+                            "error" => e,
+                            "slot" => slot.as_u64(),
+                        )
+                    }
+
+                    let committee_indices = service.committee_indices(slot);
+
+                    if committee_indices.is_empty() {
+                        future::Either::A(future::ok(()))
+                    } else {
+                        future::Either::B(service.do_attestations(slot, committee_indices))
+                    }
+                }),
+            "attestation_producer",
+        );
+
+        Ok(())
+    }
+
+    /// Returns the duties of the validators which must attest at `slot`, grouped by committee.
+    fn committee_indices(&self, slot: Slot) -> HashMap<CommitteeIndex, Vec<ValidatorDuty>> {
         let mut committee_indices: HashMap<CommitteeIndex, Vec<ValidatorDuty>> = HashMap::new();
 
-        service
-            .duties_service
+        self.duties_service
             .attesters(slot)
             .into_iter()
             .for_each(|duty| {
@@ -202,104 +294,274 @@ impl<T: SlotClock + 'static, E: EthSpec> AttestationService<T, E> {
             });
 
         committee_indices
+    }
+
+    /// Download the attestations of each committee at `slot`, have the attestation of each
+    /// committee in `committee_indices` signed by the validators in it, then upload them.
+    fn do_attestations(
+        &self,
+        slot: Slot,
+        committee_indices: HashMap<CommitteeIndex, Vec<ValidatorDuty>>,
+    ) -> impl Future<Item = (), Error = ()> {
+        let service_1 = self.clone();
+        let service_2 = self.clone();
+        let service_3 = self.clone();
+        let log_1 = self.context.log.clone();
+        let log_2 = self.context.log.clone();
+
+        self.beacon_nodes
+            .first_success(move |beacon_node| {
+                beacon_node.http.validator().produce_attestations(slot)
+            })
+            .map_err(|e| format!("Failed to produce attestations: {:?}", e))
+            .map(move |attestations| {
+                attestations
+                    .into_iter()
+                    .filter_map(|attestation| {
+                        let committee_index = attestation.data.index;
+                        committee_indices
+                            .get(&committee_index)
+                            .map(|validator_duties| {
+                                service_1.sign_attestation(
+                                    slot,
+                                    committee_index,
+                                    validator_duties,
+                                    attestation,
+                                )
+                            })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .and_then(move |attestations| {
+                let signed_attestations = attestations.clone();
+                service_2
+                    .beacon_nodes
+                    .first_success(move |beacon_node| {
+                        beacon_node
+                            .http
+                            .validator()
+                            .publish_attestations(signed_attestations.clone())
+                    })
+                    .map(|publish_status| (attestations, publish_status))
+                    .map_err(|e| format!("Failed to publish attestations: {:?}", e))
+            })
+            .map(move |(attestations, publish_status)| match publish_status {
+                PublishStatus::Valid => {
+                    if let Some(delay) = duration_into_slot(&service_3.slot_clock, slot) {
+                        metrics::observe(&metrics::ATTESTATION_PUBLISH_DELAY, delay.as_secs_f64());
+                    }
+                    metrics::inc_counter_by(
+                        &metrics::ATTESTATIONS_PUBLISHED,
+                        attestations.len() as i64,
+                    );
+
+                    for attestation in &attestations {
+                        info!(
+                            log_1,
+                            "Successfully published attestation";
+                            "signatures" => attestation.aggregation_bits.num_set_bits(),
+                            "head_block" => format!("{}", attestation.data.beacon_block_root),
+                            "committee_index" => attestation.data.index,
+                            "slot" => attestation.data.slot.as_u64(),
+                        )
+                    }
+                }
+                PublishStatus::Invalid(msg) => crit!(
+                    log_1,
+                    "Published attestations were invalid";
+                    "message" => msg,
+                    "attestations" => attestations.len(),
+                    "slot" => slot.as_u64(),
+                ),
+                PublishStatus::Unknown => {
+                    crit!(log_1, "Unknown condition when publishing attestations")
+                }
+            })
+            .map_err(move |e| {
+                crit!(
+                    log_2,
+                    "Error during attestation production";
+                    "error" => e
+                )
+            })
+    }
+
+    /// Have `attestation`, for the committee at `slot` and `committee_index`, signed by all
+    /// validators in `validator_duties`.
+    fn sign_attestation(
+        &self,
+        slot: Slot,
+        committee_index: CommitteeIndex,
+        validator_duties: &[ValidatorDuty],
+        attestation: Attestation<E>,
+    ) -> Attestation<E> {
+        let log = &self.context.log;
+
+        validator_duties
+            .iter()
+            .fold(attestation, |mut attestation, duty| {
+                if let Some((duty_slot, duty_committee_index, validator_committee_position)) =
+                    attestation_duties(duty)
+                {
+                    if duty_slot == slot && duty_committee_index == committee_index {
+                        if self
+                            .validator_store
+                            .sign_attestation(
+                                &duty.validator_pubkey,
+                                validator_committee_position,
+                                &mut attestation,
+                            )
+                            .is_none()
+                        {
+                            crit!(log, "Failed to sign attestation");
+                        }
+                    } else {
+                        crit!(log, "Inconsistent validator duties during signing");
+                    }
+                } else {
+                    crit!(log, "Missing validator duties when signing");
+                }
+
+                attestation
+            })
+    }
+
+    /// For each committee with at least one aggregator, spawn a new task that downloads the best
+    /// aggregate from the beacon node, has it signed by each aggregator and then publishes it.
+    fn spawn_aggregation_tasks(&self) -> Result<(), String> {
+        let service = self.clone();
+
+        let slot = service
+            .slot_clock
+            .now()
+            .ok_or_else(|| "Failed to read slot clock".to_string())?;
+
+        if !service.validator_store.signing_enabled() {
+            debug!(
+                service.context.log,
+                "Skipping aggregation whilst signing is disabled";
+                "slot" => slot.as_u64()
+            );
+            return Ok(());
+        }
+
+        let mut committee_indices: HashMap<CommitteeIndex, Vec<ValidatorDuty>> = HashMap::new();
+
+        service
+            .duties_service
+            .attesters(slot)
             .into_iter()
-            .for_each(|(committee_index, validator_duties)| {
-                // Spawn a separate task for each attestation.
-                service.context.executor.spawn(self.clone().do_attestation(
-                    slot,
-                    committee_index,
-                    validator_duties,
-                ));
+            .filter(ValidatorDuty::is_aggregator)
+            .for_each(|duty| {
+                if let Some(committee_index) = duty.attestation_committee_index {
+                    committee_indices
+                        .entry(committee_index)
+                        .or_insert_with(Vec::new)
+                        .push(duty);
+                }
+            });
+
+        committee_indices
+            .into_iter()
+            .for_each(|(committee_index, aggregator_duties)| {
+                service.context.executor.spawn(
+                    self.clone()
+                        .do_aggregation(slot, committee_index, aggregator_duties),
+                    "aggregate_producer",
+                );
             });
 
         Ok(())
     }
 
-    /// For a given `committee_index`, download the attestation, have it signed by all validators
-    /// in `validator_duties` then upload it.
-    fn do_attestation(
+    /// For a given `committee_index`, download the best aggregate attestation, wrap it in a
+    /// `SignedAggregateAndProof` for each of the `aggregator_duties` and publish them.
+    fn do_aggregation(
         &self,
         slot: Slot,
         committee_index: CommitteeIndex,
-        validator_duties: Vec<ValidatorDuty>,
+        aggregator_duties: Vec<ValidatorDuty>,
     ) -> impl Future<Item = (), Error = ()> {
         let service_1 = self.clone();
         let service_2 = self.clone();
         let log_1 = self.context.log.clone();
         let log_2 = self.context.log.clone();
 
-        self.beacon_node
-            .http
-            .validator()
-            .produce_attestation(slot, committee_index)
-            .map_err(|e| format!("Failed to produce attestation: {:?}", e))
-            .map(move |attestation| {
-                validator_duties
+        self.beacon_nodes
+            .first_success(move |beacon_node| {
+                beacon_node
+                    .http
+                    .validator()
+                    .get_aggregate_attestation(slot, committee_index)
+            })
+            .map_err(|e| format!("Failed to produce aggregate: {:?}", e))
+            .map(move |aggregate| {
+                aggregator_duties
                     .iter()
-                    .fold(attestation, |mut attestation, duty| {
-                        let log = service_1.context.log.clone();
-
-                        if let Some((
-                            duty_slot,
-                            duty_committee_index,
-                            validator_committee_position,
-                        )) = attestation_duties(duty)
-                        {
-                            if duty_slot == slot && duty_committee_index == committee_index {
-                                if service_1
+                    .filter_map(|duty| {
+                        let log = &service_1.context.log;
+
+                        match (duty.validator_index, duty.selection_proof.clone()) {
+                            (Some(validator_index), Some(selection_proof)) => {
+                                let signed_aggregate = service_1
                                     .validator_store
-                                    .sign_attestation(
+                                    .produce_signed_aggregate_and_proof(
                                         &duty.validator_pubkey,
-                                        validator_committee_position,
-                                        &mut attestation,
-                                    )
-                                    .is_none()
-                                {
-                                    crit!(log, "Failed to sign attestation");
+                                        validator_index,
+                                        aggregate.clone(),
+                                        selection_proof,
+                                    );
+
+                                if signed_aggregate.is_none() {
+                                    crit!(log, "Failed to sign aggregate");
                                 }
-                            } else {
-                                crit!(log, "Inconsistent validator duties during signing");
+
+                                signed_aggregate
+                            }
+                            _ => {
+                                crit!(log, "Missing validator duties when aggregating");
+                                None
                             }
-                        } else {
-                            crit!(log, "Missing validator duties when signing");
                         }
-
-                        attestation
                     })
+                    .collect::<Vec<_>>()
             })
-            .and_then(move |attestation| {
+            .and_then(move |signed_aggregates| {
+                let count = signed_aggregates.len();
                 service_2
-                    .beacon_node
-                    .http
-                    .validator()
-                    .publish_attestation(attestation.clone())
-                    .map(|publish_status| (attestation, publish_status))
-                    .map_err(|e| format!("Failed to publish attestation: {:?}", e))
+                    .beacon_nodes
+                    .first_success(move |beacon_node| {
+                        beacon_node
+                            .http
+                            .validator()
+                            .publish_aggregate_and_proofs(signed_aggregates.clone())
+                    })
+                    .map(move |publish_status| (count, publish_status))
+                    .map_err(|e| format!("Failed to publish aggregates: {:?}", e))
             })
-            .map(move |(attestation, publish_status)| match publish_status {
+            .map(move |(count, publish_status)| match publish_status {
                 PublishStatus::Valid => info!(
                     log_1,
-                    "Successfully published attestation";
-                    "signatures" => attestation.aggregation_bits.num_set_bits(),
-                    "head_block" => format!("{}", attestation.data.beacon_block_root),
-                    "committee_index" => attestation.data.index,
-                    "slot" => attestation.data.slot.as_u64(),
+                    "Successfully published aggregates";
+                    "aggregators" => count,
+                    "committee_index" => committee_index,
+                    "slot" => slot.as_u64(),
                 ),
                 PublishStatus::Invalid(msg) => crit!(
                     log_1,
-                    "Published attestation was invalid";
+                    "Published aggregate was invalid";
                     "message" => msg,
-                    "committee_index" => attestation.data.index,
-                    "slot" => attestation.data.slot.as_u64(),
+                    "committee_index" => committee_index,
+                    "slot" => slot.as_u64(),
                 ),
                 PublishStatus::Unknown => {
-                    crit!(log_1, "Unknown condition when publishing attestation")
+                    crit!(log_1, "Unknown condition when publishing aggregates")
                 }
             })
             .map_err(move |e| {
                 crit!(
                     log_2,
-                    "Error during attestation production";
+                    "Error during aggregation";
                     "error" => e
                 )
             })
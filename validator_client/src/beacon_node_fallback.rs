@@ -0,0 +1,572 @@
+//! Allows the validator client to connect to multiple beacon nodes, using the healthiest node for
+//! each request and falling back to the others whenever a request fails.
+use crate::http_metrics::metrics;
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{
+    future::{self, loop_fn, Loop},
+    stream, Future, IntoFuture, Stream,
+};
+use parking_lot::RwLock;
+use remote_beacon_node::RemoteBeaconNode;
+use slog::{crit, debug, warn, Logger};
+use slot_clock::SlotClock;
+use std::fmt::{self, Debug};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::{Interval, Timeout};
+use types::EthSpec;
+
+/// The reason a candidate beacon node is not considered healthy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateError {
+    /// The node has not been contacted yet.
+    Uninitialized,
+    /// The node could not be contacted.
+    Offline,
+    /// The node is online, but its head is too far behind the wall-clock slot.
+    NotSynced,
+}
+
+/// A summary of the health of a single beacon node, suitable for logging or metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    pub status: Result<(), CandidateError>,
+    pub latency: Option<Duration>,
+    pub successes: usize,
+    pub failures: usize,
+}
+
+/// A request made to a single beacon node by `BeaconNodeFallback::try_in_turn`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestAttempt {
+    pub endpoint: String,
+    /// The time from sending the request until it succeeded, failed or timed out.
+    pub latency: Duration,
+    /// The error, or `None` if the request succeeded.
+    pub error: Option<String>,
+}
+
+impl fmt::Display for RequestAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(
+                f,
+                "{} failed after {}ms: {}",
+                self.endpoint,
+                self.latency.as_millis(),
+                error
+            ),
+            None => write!(
+                f,
+                "{} succeeded after {}ms",
+                self.endpoint,
+                self.latency.as_millis()
+            ),
+        }
+    }
+}
+
+/// A beacon node which may be used by the validator client.
+pub struct CandidateBeaconNode<E: EthSpec> {
+    pub beacon_node: RemoteBeaconNode<E>,
+    endpoint: String,
+    status: RwLock<Result<(), CandidateError>>,
+    latency: RwLock<Option<Duration>>,
+    successes: AtomicUsize,
+    failures: AtomicUsize,
+}
+
+impl<E: EthSpec> CandidateBeaconNode<E> {
+    pub fn new(endpoint: String, beacon_node: RemoteBeaconNode<E>) -> Self {
+        Self {
+            beacon_node,
+            endpoint,
+            status: RwLock::new(Err(CandidateError::Uninitialized)),
+            latency: RwLock::new(None),
+            successes: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the URL of the beacon node.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns the result of the most recent health check.
+    pub fn status(&self) -> Result<(), CandidateError> {
+        *self.status.read()
+    }
+
+    fn record_success(&self) {
+        self.successes.fetch_add(1, Ordering::Relaxed);
+        metrics::inc_counter_vec(
+            &metrics::BEACON_NODE_REQUESTS,
+            &[&self.endpoint, metrics::SUCCESS],
+        );
+    }
+
+    fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        metrics::inc_counter_vec(
+            &metrics::BEACON_NODE_REQUESTS,
+            &[&self.endpoint, metrics::FAILURE],
+        );
+        *self.status.write() = Err(CandidateError::Offline);
+    }
+
+    fn health(&self) -> EndpointHealth {
+        EndpointHealth {
+            endpoint: self.endpoint.clone(),
+            status: self.status(),
+            latency: *self.latency.read(),
+            successes: self.successes.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Contacts the beacon node, updating its status and latency.
+    ///
+    /// A node is considered synced if its head is no more than one epoch behind the wall-clock
+    /// epoch.
+    fn refresh_status<T: SlotClock>(
+        self: Arc<Self>,
+        slot_clock: &T,
+        log: Logger,
+    ) -> impl Future<Item = (), Error = ()> {
+        let current_epoch = slot_clock
+            .now()
+            .map(|slot| slot.epoch(E::slots_per_epoch()));
+        let start = Instant::now();
+
+        self.beacon_node
+            .http
+            .beacon()
+            .get_head()
+            .then(move |result| {
+                let status = match result {
+                    Ok(head) => {
+                        *self.latency.write() = Some(Instant::now().duration_since(start));
+
+                        let head_epoch = head.slot.epoch(E::slots_per_epoch());
+                        match current_epoch {
+                            Some(current_epoch) if head_epoch + 1 < current_epoch => {
+                                warn!(
+                                    log,
+                                    "Beacon node is not synced";
+                                    "endpoint" => &self.endpoint,
+                                    "node_head_epoch" => head_epoch.as_u64(),
+                                    "current_epoch" => current_epoch.as_u64(),
+                                );
+                                Err(CandidateError::NotSynced)
+                            }
+                            _ => Ok(()),
+                        }
+                    }
+                    Err(e) => {
+                        *self.latency.write() = None;
+                        warn!(
+                            log,
+                            "Unable to contact beacon node";
+                            "endpoint" => &self.endpoint,
+                            "error" => format!("{:?}", e),
+                        );
+                        Err(CandidateError::Offline)
+                    }
+                };
+
+                *self.status.write() = status;
+
+                Ok(())
+            })
+    }
+}
+
+/// A collection of beacon nodes, ordered by preference.
+///
+/// Requests are sent to the healthy node with the lowest latency. If the request fails, it is
+/// retried on each of the remaining nodes in turn.
+pub struct BeaconNodeFallback<T, E: EthSpec> {
+    candidates: Vec<Arc<CandidateBeaconNode<E>>>,
+    slot_clock: T,
+    log: Logger,
+}
+
+impl<T: SlotClock + 'static, E: EthSpec> BeaconNodeFallback<T, E> {
+    pub fn new(candidates: Vec<CandidateBeaconNode<E>>, slot_clock: T, log: Logger) -> Self {
+        Self {
+            candidates: candidates.into_iter().map(Arc::new).collect(),
+            slot_clock,
+            log,
+        }
+    }
+
+    /// Returns the number of candidates which passed their most recent health check.
+    pub fn num_available(&self) -> usize {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.status().is_ok())
+            .count()
+    }
+
+    /// Returns the total number of candidates.
+    pub fn num_total(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Returns a health summary for each candidate, in the order they were configured.
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.candidates
+            .iter()
+            .map(|candidate| candidate.health())
+            .collect()
+    }
+
+    /// Restores the health of the candidate with `endpoint`, as persisted by a previous run of the
+    /// validator client.
+    ///
+    /// The status is only restored if the candidate has not been contacted by this run.
+    pub fn restore_health(
+        &self,
+        endpoint: &str,
+        status: Result<(), CandidateError>,
+        successes: usize,
+        failures: usize,
+    ) {
+        if let Some(candidate) = self
+            .candidates
+            .iter()
+            .find(|candidate| candidate.endpoint == endpoint)
+        {
+            let mut current_status = candidate.status.write();
+            if *current_status == Err(CandidateError::Uninitialized) {
+                *current_status = status;
+            }
+
+            candidate.successes.fetch_add(successes, Ordering::Relaxed);
+            candidate.failures.fetch_add(failures, Ordering::Relaxed);
+        }
+    }
+
+    /// Refreshes the status of all candidates concurrently.
+    pub fn update_all_candidates(&self) -> impl Future<Item = (), Error = ()> {
+        let futures = self
+            .candidates
+            .iter()
+            .map(|candidate| {
+                candidate
+                    .clone()
+                    .refresh_status(&self.slot_clock, self.log.clone())
+            })
+            .collect::<Vec<_>>();
+
+        stream::futures_unordered(futures).for_each(|_| Ok(()))
+    }
+
+    /// Returns the candidates in the order in which they should be tried.
+    ///
+    /// Healthy nodes come first, sorted by latency. Unhealthy nodes follow in the order they were
+    /// configured, so that requests may still succeed if the health checks are stale.
+    fn ordered_candidates(&self) -> Vec<Arc<CandidateBeaconNode<E>>> {
+        let (mut healthy, unhealthy): (Vec<_>, Vec<_>) = self
+            .candidates
+            .iter()
+            .cloned()
+            .partition(|candidate| candidate.status().is_ok());
+
+        healthy.sort_by_key(|candidate| candidate.latency.read().unwrap_or(Duration::max_value()));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    /// Runs `func` against each beacon node in turn, returning the first successful result.
+    ///
+    /// If all nodes fail, the errors from each node are returned.
+    pub fn first_success<F, R, O, Err>(&self, func: F) -> impl Future<Item = O, Error = String>
+    where
+        F: Fn(&RemoteBeaconNode<E>) -> R + Send + 'static,
+        R: IntoFuture<Item = O, Error = Err>,
+        R::Future: Send + 'static,
+        O: Send + 'static,
+        Err: Debug + Send + 'static,
+    {
+        self.try_in_turn(None, func)
+            .map(|(output, _)| output)
+            .map_err(|attempts| {
+                let errors = attempts
+                    .iter()
+                    .map(|attempt| {
+                        format!(
+                            "{}: {}",
+                            attempt.endpoint,
+                            attempt.error.as_ref().map_or("", String::as_str)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("All beacon nodes failed: [{}]", errors.join(", "))
+            })
+    }
+
+    /// Runs `func` against each beacon node in turn until one succeeds, returning its result along
+    /// with each request that was made.
+    ///
+    /// If `deadline` is set, the request in flight is abandoned when it is reached and no further
+    /// nodes are tried, so that each fallback node receives whatever remains of the time budget.
+    /// If no node succeeds, the requests that were made are returned as the error.
+    pub fn try_in_turn<F, R, O, Err>(
+        &self,
+        deadline: Option<Instant>,
+        func: F,
+    ) -> impl Future<Item = (O, Vec<RequestAttempt>), Error = Vec<RequestAttempt>>
+    where
+        F: Fn(&RemoteBeaconNode<E>) -> R + Send + 'static,
+        R: IntoFuture<Item = O, Error = Err>,
+        R::Future: Send + 'static,
+        O: Send + 'static,
+        Err: Debug + Send + 'static,
+    {
+        let log = self.log.clone();
+        let candidates = self.ordered_candidates().into_iter();
+
+        loop_fn(
+            (candidates, func, vec![]),
+            move |(mut candidates, func, mut attempts)| {
+                let log = log.clone();
+
+                let past_deadline = deadline.map_or(false, |deadline| Instant::now() >= deadline);
+                let candidate = candidates.next().filter(|_| !past_deadline);
+
+                let future: Box<dyn Future<Item = Loop<_, _>, Error = Vec<RequestAttempt>> + Send> =
+                    match candidate {
+                        Some(candidate) => {
+                            let start = Instant::now();
+                            let request =
+                                request_until(func(&candidate.beacon_node).into_future(), deadline);
+
+                            Box::new(request.then(move |result| {
+                                let mut attempt = RequestAttempt {
+                                    endpoint: candidate.endpoint().to_string(),
+                                    latency: Instant::now().duration_since(start),
+                                    error: None,
+                                };
+
+                                match result {
+                                    Ok(output) => {
+                                        candidate.record_success();
+                                        attempts.push(attempt);
+                                        Ok(Loop::Break((output, attempts)))
+                                    }
+                                    Err(e) => {
+                                        debug!(
+                                            log,
+                                            "Request to beacon node failed";
+                                            "endpoint" => candidate.endpoint(),
+                                            "error" => &e,
+                                        );
+                                        candidate.record_failure();
+                                        attempt.error = Some(e);
+                                        attempts.push(attempt);
+                                        Ok(Loop::Continue((candidates, func, attempts)))
+                                    }
+                                }
+                            }))
+                        }
+                        None => Box::new(future::err(attempts)),
+                    };
+
+                future
+            },
+        )
+    }
+}
+
+/// Returns `request` with its error formatted as a string, abandoning it at `deadline` (if any).
+fn request_until<F, O, Err>(
+    request: F,
+    deadline: Option<Instant>,
+) -> Box<dyn Future<Item = O, Error = String> + Send>
+where
+    F: Future<Item = O, Error = Err> + Send + 'static,
+    O: Send + 'static,
+    Err: Debug + Send + 'static,
+{
+    match deadline {
+        Some(deadline) => Box::new(Timeout::new_at(request, deadline).map_err(|e| {
+            if e.is_elapsed() {
+                "Timed out".to_string()
+            } else {
+                e.into_inner()
+                    .map_or_else(|| "Timer failed".to_string(), |e| format!("{:?}", e))
+            }
+        })),
+        None => Box::new(request.map_err(|e| format!("{:?}", e))),
+    }
+}
+
+/// Starts a service which refreshes the health of all `beacon_nodes` once per slot.
+pub fn start_fallback_updater_service<T: SlotClock + 'static, E: EthSpec>(
+    context: RuntimeContext<E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+) -> Result<Signal, String> {
+    let log = context.log.clone();
+    let interval = Interval::new(Instant::now(), beacon_nodes.slot_clock.slot_duration());
+
+    let (exit_signal, exit_fut) = exit_future::signal();
+
+    context.executor.spawn(
+        exit_fut
+            .until(
+                interval
+                    .map_err(move |e| {
+                        crit! {
+                            log,
+                            "Timer thread failed";
+                            "error" => format!("{}", e)
+                        }
+                    })
+                    .for_each(move |_| beacon_nodes.update_all_candidates()),
+            )
+            .map(|_| ()),
+        "beacon_node_fallback",
+    );
+
+    Ok(exit_signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::{o, Discard};
+    use slot_clock::TestingSlotClock;
+    use tokio::runtime::Runtime;
+    use types::{MinimalEthSpec, Slot};
+
+    type E = MinimalEthSpec;
+
+    fn candidate(
+        endpoint: &str,
+        status: Result<(), CandidateError>,
+        latency_millis: u64,
+    ) -> CandidateBeaconNode<E> {
+        let candidate = CandidateBeaconNode::new(
+            endpoint.to_string(),
+            RemoteBeaconNode::new(endpoint.to_string()).expect("should create beacon node"),
+        );
+        *candidate.status.write() = status;
+        *candidate.latency.write() = Some(Duration::from_millis(latency_millis));
+        candidate
+    }
+
+    #[test]
+    fn healthy_candidates_ordered_by_latency() {
+        let fallback = BeaconNodeFallback::new(
+            vec![
+                candidate("http://a", Err(CandidateError::Offline), 1),
+                candidate("http://b", Ok(()), 300),
+                candidate("http://c", Err(CandidateError::NotSynced), 2),
+                candidate("http://d", Ok(()), 100),
+            ],
+            TestingSlotClock::new(Slot::new(0), Duration::from_secs(0), Duration::from_secs(1)),
+            slog::Logger::root(Discard, o!()),
+        );
+
+        let order = fallback
+            .ordered_candidates()
+            .iter()
+            .map(|candidate| candidate.endpoint().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(order, vec!["http://d", "http://b", "http://a", "http://c"]);
+        assert_eq!(fallback.num_available(), 2);
+        assert_eq!(fallback.num_total(), 4);
+    }
+
+    #[test]
+    fn restores_health_of_uninitialized_candidates() {
+        let fallback = BeaconNodeFallback::new(
+            vec![
+                candidate("http://a", Err(CandidateError::Uninitialized), 1),
+                candidate("http://b", Err(CandidateError::Offline), 1),
+            ],
+            TestingSlotClock::new(Slot::new(0), Duration::from_secs(0), Duration::from_secs(1)),
+            slog::Logger::root(Discard, o!()),
+        );
+
+        fallback.restore_health("http://a", Ok(()), 3, 1);
+        fallback.restore_health("http://b", Ok(()), 5, 0);
+        fallback.restore_health("http://unknown", Ok(()), 1, 1);
+
+        let health = fallback.health();
+        assert_eq!(health[0].status, Ok(()));
+        assert_eq!((health[0].successes, health[0].failures), (3, 1));
+        assert_eq!(
+            health[1].status,
+            Err(CandidateError::Offline),
+            "should not override a status learned by this run"
+        );
+        assert_eq!((health[1].successes, health[1].failures), (5, 0));
+    }
+
+    #[test]
+    fn try_in_turn_records_each_attempt() {
+        let fallback = BeaconNodeFallback::new(
+            vec![
+                candidate("http://a", Ok(()), 1),
+                candidate("http://b", Ok(()), 2),
+                candidate("http://c", Ok(()), 3),
+            ],
+            TestingSlotClock::new(Slot::new(0), Duration::from_secs(0), Duration::from_secs(1)),
+            slog::Logger::root(Discard, o!()),
+        );
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let (output, attempts) = Runtime::new()
+            .expect("should start runtime")
+            .block_on(fallback.try_in_turn(Some(deadline), move |_| {
+                if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                    Err("offline")
+                } else {
+                    Ok(42)
+                }
+            }))
+            .expect("should succeed on the second node");
+
+        assert_eq!(output, 42);
+        assert_eq!(attempts.len(), 2, "should not try the third node");
+        assert_eq!(attempts[0].endpoint, "http://a");
+        assert_eq!(attempts[0].error, Some("\"offline\"".to_string()));
+        assert_eq!(attempts[1].endpoint, "http://b");
+        assert_eq!(attempts[1].error, None);
+        assert_eq!(fallback.health()[0].status, Err(CandidateError::Offline));
+    }
+
+    #[test]
+    fn try_in_turn_stops_at_deadline() {
+        let fallback = BeaconNodeFallback::new(
+            vec![
+                candidate("http://a", Ok(()), 1),
+                candidate("http://b", Ok(()), 2),
+            ],
+            TestingSlotClock::new(Slot::new(0), Duration::from_secs(0), Duration::from_secs(1)),
+            slog::Logger::root(Discard, o!()),
+        );
+
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let attempts = Runtime::new()
+            .expect("should start runtime")
+            .block_on(fallback.try_in_turn(Some(deadline), |_| future::empty::<(), ()>()))
+            .expect_err("should time out");
+
+        assert_eq!(
+            attempts.len(),
+            1,
+            "should not try a node after the deadline"
+        );
+        assert_eq!(attempts[0].endpoint, "http://a");
+        assert_eq!(attempts[0].error, Some("Timed out".to_string()));
+    }
+}
@@ -1,26 +1,102 @@
-use crate::{duties_service::DutiesService, validator_store::ValidatorStore};
+use crate::{
+    beacon_node_fallback::{BeaconNodeFallback, RequestAttempt},
+    duties_service::{duration_into_slot, DutiesService, DutyTiming},
+    graffiti_file::GraffitiFile,
+    http_metrics::metrics,
+    validator_settings::graffiti_bytes,
+    validator_store::ValidatorStore,
+};
 use environment::RuntimeContext;
 use exit_future::Signal;
 use futures::{stream, Future, IntoFuture, Stream};
-use remote_beacon_node::{PublishStatus, RemoteBeaconNode};
-use slog::{crit, error, info, trace};
+use parking_lot::Mutex;
+use remote_beacon_node::PublishStatus;
+use slog::{crit, debug, error, info, trace, warn};
 use slot_clock::SlotClock;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::timer::Interval;
-use types::{ChainSpec, EthSpec};
+use types::{ChainSpec, EthSpec, PublicKey, Slot};
 
 /// Delay this period of time after the slot starts. This allows the node to process the new slot.
 const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(100);
 
+/// A stage of a block proposal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProposalStage {
+    Randao,
+    Produce,
+    Sign,
+    Publish,
+}
+
+impl ProposalStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProposalStage::Randao => "randao",
+            ProposalStage::Produce => "produce",
+            ProposalStage::Sign => "sign",
+            ProposalStage::Publish => "publish",
+        }
+    }
+}
+
+/// The requests made to beacon nodes during a block proposal, which are logged as a post-mortem
+/// of the proposal if any of them failed.
+#[derive(Default)]
+struct ProposalPostMortem {
+    requests: Mutex<Vec<(ProposalStage, RequestAttempt)>>,
+}
+
+impl ProposalPostMortem {
+    /// Records the requests made during `stage`, returning the output of the stage or, if no beacon
+    /// node succeeded, an error.
+    fn observe<O>(
+        &self,
+        stage: ProposalStage,
+        result: Result<(O, Vec<RequestAttempt>), Vec<RequestAttempt>>,
+    ) -> Result<O, (ProposalStage, String)> {
+        let mut requests = self.requests.lock();
+        match result {
+            Ok((output, attempts)) => {
+                requests.extend(attempts.into_iter().map(|attempt| (stage, attempt)));
+                Ok(output)
+            }
+            Err(attempts) => {
+                requests.extend(attempts.into_iter().map(|attempt| (stage, attempt)));
+                Err((stage, "No beacon node succeeded".to_string()))
+            }
+        }
+    }
+
+    /// Returns `true` if any request to a beacon node failed.
+    fn has_failures(&self) -> bool {
+        self.requests
+            .lock()
+            .iter()
+            .any(|(_, attempt)| attempt.error.is_some())
+    }
+
+    /// Returns each request, prefixed by its stage, in the order in which they were made.
+    fn summary(&self) -> String {
+        self.requests
+            .lock()
+            .iter()
+            .map(|(stage, attempt)| format!("{} {}", stage.as_str(), attempt))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
 /// Builds a `BlockService`.
 pub struct BlockServiceBuilder<T, E: EthSpec> {
     duties_service: Option<DutiesService<T, E>>,
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<Arc<T>>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<Arc<BeaconNodeFallback<T, E>>>,
     context: Option<RuntimeContext<E>>,
+    graffiti: Option<String>,
+    graffiti_file: Option<GraffitiFile>,
 }
 
 impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
@@ -29,8 +105,10 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
             duties_service: None,
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
+            graffiti: None,
+            graffiti_file: None,
         }
     }
 
@@ -49,8 +127,8 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: Arc<BeaconNodeFallback<T, E>>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -59,6 +137,17 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
         self
     }
 
+    /// The graffiti to use for validators without graffiti in their settings or graffiti file.
+    pub fn graffiti(mut self, graffiti: Option<String>) -> Self {
+        self.graffiti = graffiti;
+        self
+    }
+
+    pub fn graffiti_file(mut self, graffiti_file: Option<GraffitiFile>) -> Self {
+        self.graffiti_file = graffiti_file;
+        self
+    }
+
     pub fn build(self) -> Result<BlockService<T, E>, String> {
         Ok(BlockService {
             inner: Arc::new(Inner {
@@ -71,12 +160,14 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build BlockService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build BlockService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build BlockService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build BlockService without runtime_context")?,
+                graffiti: self.graffiti,
+                graffiti_file: self.graffiti_file,
             }),
         })
     }
@@ -87,8 +178,10 @@ pub struct Inner<T, E: EthSpec> {
     duties_service: DutiesService<T, E>,
     validator_store: ValidatorStore<T, E>,
     slot_clock: Arc<T>,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
     context: RuntimeContext<E>,
+    graffiti: Option<String>,
+    graffiti_file: Option<GraffitiFile>,
 }
 
 /// Attempts to produce attestations for any block producer(s) at the start of the epoch.
@@ -114,21 +207,11 @@ impl<T, E: EthSpec> Deref for BlockService<T, E> {
 
 impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
     /// Starts the service that periodically attempts to produce blocks.
-    pub fn start_update_service(&self, spec: &ChainSpec) -> Result<Signal, String> {
+    pub fn start_update_service(&self, _spec: &ChainSpec) -> Result<Signal, String> {
         let log = self.context.log.clone();
 
-        let duration_to_next_slot = self
-            .slot_clock
-            .duration_to_next_slot()
-            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
-
-        let interval = {
-            let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
-            Interval::new(
-                Instant::now() + duration_to_next_slot + TIME_DELAY_FROM_SLOT,
-                slot_duration,
-            )
-        };
+        let interval =
+            DutyTiming::Block.interval(self.slot_clock.as_ref(), TIME_DELAY_FROM_SLOT)?;
 
         let (exit_signal, exit_fut) = exit_future::signal();
         let service = self.clone();
@@ -146,9 +229,16 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
                                 "error" => format!("{}", e)
                             }
                         })
-                        .for_each(move |_| service.clone().do_update().then(|_| Ok(()))),
+                        .for_each(move |_| {
+                            metrics::observe_duty_lateness(
+                                DutyTiming::Block,
+                                service.slot_clock.as_ref(),
+                            );
+                            service.clone().do_update().then(|_| Ok(()))
+                        }),
                 )
                 .map(move |_| info!(log_2, "Shutdown complete")),
+            "block_service",
         );
 
         Ok(exit_signal)
@@ -167,7 +257,17 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
             })
             .into_future()
             .and_then(move |slot| {
-                let iter = service.duties_service.block_producers(slot).into_iter();
+                let iter = if service.validator_store.signing_enabled() {
+                    service.duties_service.block_producers(slot)
+                } else {
+                    debug!(
+                        log_2,
+                        "Skipping block production whilst signing is disabled";
+                        "slot" => slot.as_u64()
+                    );
+                    vec![]
+                }
+                .into_iter();
 
                 if iter.len() == 0 {
                     trace!(
@@ -186,76 +286,10 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
                 }
 
                 stream::unfold(iter, move |mut block_producers| {
-                    let log_1 = service.context.log.clone();
-                    let log_2 = service.context.log.clone();
-                    let service_1 = service.clone();
-                    let service_2 = service.clone();
-                    let service_3 = service.clone();
-
+                    let service = service.clone();
                     block_producers.next().map(move |validator_pubkey| {
-                        service_1
-                            .validator_store
-                            .randao_reveal(&validator_pubkey, slot.epoch(E::slots_per_epoch()))
-                            .ok_or_else(|| "Unable to produce randao reveal".to_string())
-                            .into_future()
-                            .and_then(move |randao_reveal| {
-                                service_1
-                                    .beacon_node
-                                    .http
-                                    .validator()
-                                    .produce_block(slot, randao_reveal)
-                                    .map_err(|e| {
-                                        format!(
-                                            "Error from beacon node when producing block: {:?}",
-                                            e
-                                        )
-                                    })
-                            })
-                            .and_then(move |block| {
-                                service_2
-                                    .validator_store
-                                    .sign_block(&validator_pubkey, block)
-                                    .ok_or_else(|| "Unable to sign block".to_string())
-                            })
-                            .and_then(move |block| {
-                                service_3
-                                    .beacon_node
-                                    .http
-                                    .validator()
-                                    .publish_block(block.clone())
-                                    .map(|publish_status| (block, publish_status))
-                                    .map_err(|e| {
-                                        format!(
-                                            "Error from beacon node when publishing block: {:?}",
-                                            e
-                                        )
-                                    })
-                            })
-                            .map(move |(block, publish_status)| match publish_status {
-                                PublishStatus::Valid => info!(
-                                    log_1,
-                                    "Successfully published block";
-                                    "deposits" => block.body.deposits.len(),
-                                    "attestations" => block.body.attestations.len(),
-                                    "slot" => block.slot.as_u64(),
-                                ),
-                                PublishStatus::Invalid(msg) => crit!(
-                                    log_1,
-                                    "Published block was invalid";
-                                    "message" => msg,
-                                    "slot" => block.slot.as_u64(),
-                                ),
-                                PublishStatus::Unknown => {
-                                    crit!(log_1, "Unknown condition when publishing block")
-                                }
-                            })
-                            .map_err(move |e| {
-                                crit!(
-                                    log_2,
-                                    "Error whilst producing block";
-                                    "message" => e
-                                )
-                            })
+                        service
+                            .propose_block(slot, validator_pubkey)
                             .then(|_| Ok(((), block_producers)))
                     })
                 })
@@ -263,4 +297,163 @@ impl<T: SlotClock + 'static, E: EthSpec> BlockService<T, E> {
                 .map(|_| ())
             })
     }
+
+    /// Produces, signs and publishes a block at `slot` for `validator_pubkey`.
+    ///
+    /// Block production is retried on each beacon node in turn until attestations are due (a
+    /// third of the way through the slot), since a block produced any later is likely to be
+    /// orphaned. If any request to a beacon node fails, a post-mortem of the proposal is logged,
+    /// listing the endpoint, latency and error of each request by stage.
+    fn propose_block(
+        self,
+        slot: Slot,
+        validator_pubkey: PublicKey,
+    ) -> impl Future<Item = (), Error = ()> {
+        let log = self.context.log.clone();
+        let post_mortem = Arc::new(ProposalPostMortem::default());
+        let post_mortem_1 = post_mortem.clone();
+        let post_mortem_2 = post_mortem.clone();
+        let service_1 = self.clone();
+        let service_2 = self.clone();
+        let service_3 = self.clone();
+        let graffiti = self.graffiti(&validator_pubkey);
+        let deadline = self.production_deadline(slot);
+        let proposer = validator_pubkey.clone();
+
+        self.validator_store
+            .randao_reveal(&validator_pubkey, slot.epoch(E::slots_per_epoch()))
+            .ok_or_else(|| {
+                (
+                    ProposalStage::Randao,
+                    "Unable to produce randao reveal".to_string(),
+                )
+            })
+            .into_future()
+            .and_then(move |randao_reveal| {
+                service_1
+                    .beacon_nodes
+                    .try_in_turn(deadline, move |beacon_node| {
+                        beacon_node.http.validator().produce_block_with_graffiti(
+                            slot,
+                            randao_reveal.clone(),
+                            graffiti,
+                        )
+                    })
+                    .then(move |result| post_mortem_1.observe(ProposalStage::Produce, result))
+            })
+            .and_then(move |block| {
+                service_2
+                    .validator_store
+                    .sign_block(&validator_pubkey, block)
+                    .ok_or_else(|| (ProposalStage::Sign, "Unable to sign block".to_string()))
+            })
+            .and_then(move |block| {
+                let signed_block = block.clone();
+                service_3
+                    .beacon_nodes
+                    .try_in_turn(None, move |beacon_node| {
+                        beacon_node
+                            .http
+                            .validator()
+                            .publish_block(signed_block.clone())
+                    })
+                    .then(move |result| post_mortem_2.observe(ProposalStage::Publish, result))
+                    .map(|publish_status| (block, publish_status))
+            })
+            .then(move |result| {
+                let failed_stage = match result {
+                    Ok((block, PublishStatus::Valid)) => {
+                        metrics::inc_counter(&metrics::BLOCKS_PUBLISHED);
+                        info!(
+                            log,
+                            "Successfully published block";
+                            "deposits" => block.body.deposits.len(),
+                            "attestations" => block.body.attestations.len(),
+                            "slot" => block.slot.as_u64(),
+                        );
+                        None
+                    }
+                    Ok((block, PublishStatus::Invalid(msg))) => {
+                        crit!(
+                            log,
+                            "Published block was invalid";
+                            "message" => msg,
+                            "slot" => block.slot.as_u64(),
+                        );
+                        None
+                    }
+                    Ok((_, PublishStatus::Unknown)) => {
+                        crit!(log, "Unknown condition when publishing block");
+                        None
+                    }
+                    Err((stage, e)) => {
+                        crit!(
+                            log,
+                            "Error whilst producing block";
+                            "stage" => stage.as_str(),
+                            "message" => e,
+                        );
+                        Some(stage)
+                    }
+                };
+
+                if failed_stage.is_some() || post_mortem.has_failures() {
+                    warn!(
+                        log,
+                        "Block proposal post-mortem";
+                        "requests" => post_mortem.summary(),
+                        "failed_stage" => failed_stage.map_or("none", ProposalStage::as_str),
+                        "validator" => format!("{:?}", proposer),
+                        "slot" => slot.as_u64(),
+                    );
+                }
+
+                Ok(())
+            })
+    }
+
+    /// Returns the time by which a block for `slot` must be produced, which is when attestations
+    /// for `slot` are due.
+    fn production_deadline(&self, slot: Slot) -> Option<Instant> {
+        let slot_clock = self.slot_clock.as_ref();
+        let elapsed = duration_into_slot(slot_clock, slot)?;
+        let remaining = DutyTiming::Attestation
+            .offset(slot_clock.slot_duration())
+            .checked_sub(elapsed)
+            .unwrap_or_default();
+
+        Some(Instant::now() + remaining)
+    }
+
+    /// Returns the graffiti to include in a block proposed by `validator_pubkey`.
+    ///
+    /// In order of precedence, the graffiti is read from the validator's settings (e.g., as set
+    /// via the HTTP API), the graffiti file, then the global graffiti. If none is set, `None` is
+    /// returned and the beacon node uses its default graffiti.
+    fn graffiti(&self, validator_pubkey: &PublicKey) -> Option<[u8; 32]> {
+        let log = &self.context.log;
+
+        let graffiti = self
+            .validator_store
+            .validator_settings(validator_pubkey)
+            .and_then(|settings| settings.graffiti)
+            .or_else(|| {
+                self.graffiti_file.as_ref().and_then(|graffiti_file| {
+                    if let Err(e) = graffiti_file.reload_if_changed() {
+                        warn!(
+                            log,
+                            "Unable to reload graffiti file";
+                            "error" => e,
+                            "msg" => "using the previous contents of the file",
+                        );
+                    }
+                    graffiti_file.graffiti(validator_pubkey)
+                })
+            })
+            .or_else(|| self.graffiti.clone())?;
+
+        graffiti_bytes(&graffiti)
+            .map_err(|e| warn!(log, "Invalid graffiti"; "error" => e))
+            .ok()
+    }
 }
@@ -14,6 +14,146 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value(&DEFAULT_HTTP_SERVER)
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("fallback-servers")
+                .long("fallback-servers")
+                .value_name("NETWORK_ADDRESSES")
+                .help("A comma-separated list of additional beacon node HTTP endpoints. The \
+                       validator client uses the healthiest (synced, lowest latency) node for \
+                       each request and fails over to the others if it is unavailable.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("secrets-dir")
+                .long("secrets-dir")
+                .value_name("SECRETS_DIRECTORY")
+                .help("The directory which contains the password to unlock the validator \
+                       voting keypairs. Each password should be contained in a file where the \
+                       name is the 0x-prefixed hex representation of the validators voting \
+                       public key. Defaults to the `secrets` directory beside the default \
+                       `--datadir`.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("key-cache-password-file")
+                .long("key-cache-password-file")
+                .value_name("FILE")
+                .help("A file containing a passphrase which encrypts a cache of the decrypted \
+                       voting keys in the `--datadir`. If present, the validator client decrypts \
+                       the cache at startup rather than each keystore, and updates the cache \
+                       whenever its validators change.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("enable-doppelganger-protection")
+                .long("enable-doppelganger-protection")
+                .help("If present, the validator client will not sign any messages for the first \
+                       few epochs after startup whilst it checks the network for attestations \
+                       from its own validators. If any are seen, the process exits. Use this \
+                       when migrating validators between hosts to avoid double-signing.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("remote-signers")
+                .long("remote-signers")
+                .value_name("FILE")
+                .help("A JSON file which lists the validators that should be signed for by a \
+                       remote signer, along with the URL of the signer for each validator.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("remote-signer-timeout")
+                .long("remote-signer-timeout")
+                .value_name("MILLISECONDS")
+                .help("The timeout for each request to a remote signer. Overrides any value in \
+                       the --remote-signers file.")
+                .requires("remote-signers")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("graffiti")
+                .long("graffiti")
+                .value_name("GRAFFITI")
+                .help("The graffiti (at most 32 bytes) to include in proposed blocks. Overridden \
+                       by --graffiti-file and the graffiti set for each validator via the HTTP \
+                       API.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("graffiti-file")
+                .long("graffiti-file")
+                .value_name("FILE")
+                .help("A JSON file which specifies the graffiti for each validator, keyed by \
+                       voting pubkey, along with a default for unlisted validators. The file \
+                       is reloaded whenever it changes.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("interop-validators")
+                .long("interop-validators")
+                .value_name("START:COUNT")
+                .help("INSECURE: manage COUNT of the deterministic interop validators, starting \
+                       at index START (e.g., 0:8). The keypairs are derived from publicly known \
+                       private keys, so no keystores are required. ONLY USE FOR TESTING.")
+                .takes_value(true),
+        )
+        /* HTTP API related arguments */
+        .arg(
+            Arg::with_name("http")
+                .long("http")
+                .help("Enable the key management HTTP API. Requests must include the token \
+                       stored in the api-token.txt file in the data directory.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("http-address")
+                .long("http-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the HTTP API.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("http-port")
+                .long("http-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the HTTP API.")
+                .default_value("5062")
+                .takes_value(true),
+        )
+        /* Prometheus metrics HTTP server related arguments */
+        .arg(
+            Arg::with_name("metrics")
+                .long("metrics")
+                .help("Enable the Prometheus metrics HTTP server.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("metrics-address")
+                .long("metrics-address")
+                .value_name("ADDRESS")
+                .help("Set the listen address for the Prometheus metrics HTTP server.")
+                .default_value("127.0.0.1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("metrics-port")
+                .long("metrics-port")
+                .value_name("PORT")
+                .help("Set the listen TCP port for the Prometheus metrics HTTP server.")
+                .default_value("5064")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("monitoring-endpoint")
+                .long("monitoring-endpoint")
+                .value_name("URL")
+                .help("Send a JSON summary of the validator client (validator counts, published \
+                       blocks and attestations, beacon node availability and host memory and \
+                       load) to this URL with a POST request every minute, for hosted \
+                       dashboards. Disabled by default.")
+                .takes_value(true),
+        )
         /*
          * The "testnet" sub-command.
          *
@@ -52,4 +192,26 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("The pubkey of the validator that should sign the block.")
             )
         )
+        .subcommand(SubCommand::with_name("exit")
+            .about("Submits a voluntary exit for a validator, permanently removing it from the \
+            validator set once included in the chain. This cannot be undone.")
+            .arg(Arg::with_name("validator")
+                .long("validator")
+                .value_name("PUBKEY")
+                .required(true)
+                .help("The 0x-prefixed voting pubkey of the validator to exit.")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("no-confirmation")
+                .long("no-confirmation")
+                .help("Submit the exit without asking for confirmation.")
+                .takes_value(false)
+            )
+            .arg(Arg::with_name("no-wait")
+                .long("no-wait")
+                .help("Exit as soon as the beacon node accepts the exit, rather than waiting \
+                until it is included in the chain.")
+                .takes_value(false)
+            )
+        )
 }
@@ -1,9 +1,16 @@
+use crate::http_api;
+use crate::http_metrics;
+use crate::signing_method::RemoteSignersConfig;
+use crate::validator_settings::check_graffiti;
 use clap::ArgMatches;
 use serde_derive::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
 pub const DEFAULT_HTTP_SERVER: &str = "http://localhost:5052/";
-pub const DEFAULT_DATA_DIR: &str = ".lighthouse/validators";
+/// The name of the remote signers file which is loaded from the data directory if no other file
+/// is specified.
+pub const DEFAULT_REMOTE_SIGNERS_FILE: &str = "remote_signers.json";
 
 /// Specifies a method for obtaining validator keypairs.
 #[derive(Clone)]
@@ -25,6 +32,8 @@ impl Default for KeySource {
 pub struct Config {
     /// The data directory, which stores all validator databases
     pub data_dir: PathBuf,
+    /// The directory containing the passwords for any EIP-2335 keystores in the `data_dir`.
+    pub secrets_dir: PathBuf,
     /// Specifies how the validator client should load keypairs.
     #[serde(skip)]
     pub key_source: KeySource,
@@ -32,23 +41,62 @@ pub struct Config {
     ///
     /// Should be similar to `http://localhost:8080`
     pub http_server: String,
+    /// Additional beacon node http endpoints which are used when the `http_server` is unavailable
+    /// or not synced.
+    pub fallback_http_servers: Vec<String>,
+    /// If `true`, refuse to sign any messages until the validators have been observed to be
+    /// offline for a number of epochs.
+    pub enable_doppelganger_protection: bool,
+    /// Validators which should be signed for by a remote signer, rather than a local keypair.
+    pub remote_signers: Option<RemoteSignersConfig>,
+    /// The file to which remote signers added via the HTTP API are persisted.
+    pub remote_signers_path: Option<PathBuf>,
+    /// A file containing the passphrase of the decrypted-key cache. If `None`, no cache is used
+    /// and each keystore is decrypted at startup.
+    pub key_cache_password_file: Option<PathBuf>,
+    /// Configuration for the key management HTTP API.
+    pub http_api: http_api::Config,
+    /// Configuration for the Prometheus metrics HTTP server.
+    pub http_metrics: http_metrics::Config,
+    /// A URL to which a summary of the validator client is periodically sent, if any.
+    #[serde(default)]
+    pub monitoring_endpoint: Option<String>,
+    /// The graffiti to include in blocks, unless overridden for a particular validator.
+    pub graffiti: Option<String>,
+    /// A file mapping validator pubkeys to their graffiti, which is reloaded whenever it changes.
+    pub graffiti_file: Option<PathBuf>,
 }
 
 impl Default for Config {
     /// Build a new configuration from defaults.
     fn default() -> Self {
-        let mut data_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        data_dir.push(".lighthouse");
-        data_dir.push("validators");
         Self {
-            data_dir,
+            data_dir: lighthouse_dirs::default_dir(lighthouse_dirs::VALIDATORS_DIR),
+            secrets_dir: lighthouse_dirs::default_dir(lighthouse_dirs::SECRETS_DIR),
             key_source: <_>::default(),
             http_server: DEFAULT_HTTP_SERVER.to_string(),
+            fallback_http_servers: vec![],
+            enable_doppelganger_protection: false,
+            remote_signers: None,
+            remote_signers_path: None,
+            key_cache_password_file: None,
+            http_api: <_>::default(),
+            http_metrics: <_>::default(),
+            monitoring_endpoint: None,
+            graffiti: None,
+            graffiti_file: None,
         }
     }
 }
 
 impl Config {
+    /// Returns the `http_server` followed by each of the `fallback_http_servers`.
+    pub fn beacon_node_endpoints(&self) -> Vec<String> {
+        let mut endpoints = vec![self.http_server.clone()];
+        endpoints.extend(self.fallback_http_servers.iter().cloned());
+        endpoints
+    }
+
     /// Returns a `Default` implementation of `Self` with some parameters modified by the supplied
     /// `cli_args`.
     pub fn from_cli(cli_args: &ArgMatches) -> Result<Config, String> {
@@ -56,22 +104,116 @@ impl Config {
 
         // Read the `--datadir` flag.
         //
-        // If it's not present, try and find the home directory (`~`) and push the default data
-        // directory onto it. If the home directory is not available, use the present directory.
-        config.data_dir = cli_args
-            .value_of("datadir")
-            .map(PathBuf::from)
-            .unwrap_or_else(|| {
-                dirs::home_dir()
-                    .map(|home| home.join(DEFAULT_DATA_DIR))
-                    .unwrap_or_else(|| PathBuf::from("."))
-            });
+        // If it's not present, use the validators directory of the platform default (see
+        // `lighthouse_dirs`).
+        if let Some(data_dir) = cli_args.value_of("datadir") {
+            config.data_dir = PathBuf::from(data_dir);
+        }
+
+        if let Some(secrets_dir) = cli_args.value_of("secrets-dir") {
+            config.secrets_dir = PathBuf::from(secrets_dir);
+        }
 
         if let Some(server) = cli_args.value_of("server") {
             config.http_server = server.to_string();
         }
 
+        config.enable_doppelganger_protection =
+            cli_args.is_present("enable-doppelganger-protection");
+
+        if let Some(servers) = cli_args.value_of("fallback-servers") {
+            config.fallback_http_servers = servers
+                .split(',')
+                .map(|server| server.trim().to_string())
+                .filter(|server| !server.is_empty())
+                .collect();
+        }
+
+        let remote_signers_path = cli_args
+            .value_of("remote-signers")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| config.data_dir.join(DEFAULT_REMOTE_SIGNERS_FILE));
+
+        if remote_signers_path.exists() {
+            let mut remote_signers = RemoteSignersConfig::from_file(&remote_signers_path)?;
+
+            if let Some(timeout) = cli_args.value_of("remote-signer-timeout") {
+                remote_signers.timeout_millis = Some(
+                    timeout
+                        .parse::<u64>()
+                        .map_err(|e| format!("Unable to parse remote-signer-timeout: {:?}", e))?,
+                );
+            }
+
+            config.remote_signers = Some(remote_signers);
+        } else if cli_args.is_present("remote-signers") {
+            return Err(format!(
+                "Remote signers file does not exist: {:?}",
+                remote_signers_path
+            ));
+        }
+
+        config.remote_signers_path = Some(remote_signers_path);
+
+        config.key_cache_password_file = cli_args
+            .value_of("key-cache-password-file")
+            .map(PathBuf::from);
+
+        if let Some(graffiti) = cli_args.value_of("graffiti") {
+            check_graffiti(graffiti)?;
+            config.graffiti = Some(graffiti.to_string());
+        }
+
+        config.graffiti_file = cli_args.value_of("graffiti-file").map(PathBuf::from);
+
+        /*
+         * HTTP API
+         */
+        if cli_args.is_present("http") {
+            config.http_api.enabled = true;
+        }
+
+        if let Some(address) = cli_args.value_of("http-address") {
+            config.http_api.listen_address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "http-address is not a valid IPv4 address.")?;
+        }
+
+        if let Some(port) = cli_args.value_of("http-port") {
+            config.http_api.port = port
+                .parse::<u16>()
+                .map_err(|_| "http-port is not a valid u16.")?;
+        }
+
+        /*
+         * Prometheus metrics HTTP server
+         */
+        if cli_args.is_present("metrics") {
+            config.http_metrics.enabled = true;
+        }
+
+        if let Some(address) = cli_args.value_of("metrics-address") {
+            config.http_metrics.listen_address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "metrics-address is not a valid IPv4 address.")?;
+        }
+
+        if let Some(port) = cli_args.value_of("metrics-port") {
+            config.http_metrics.port = port
+                .parse::<u16>()
+                .map_err(|_| "metrics-port is not a valid u16.")?;
+        }
+
+        if let Some(endpoint) = cli_args.value_of("monitoring-endpoint") {
+            config.monitoring_endpoint = Some(endpoint.to_string());
+        }
+
         let config = match cli_args.subcommand() {
+            ("testnet", Some(_)) if cli_args.is_present("interop-validators") => {
+                return Err(
+                    "Cannot specify --interop-validators with the testnet subcommand".into(),
+                );
+            }
             ("testnet", Some(sub_cli_args)) => {
                 if cli_args.is_present("eth2-config") && sub_cli_args.is_present("bootstrap") {
                     return Err(
@@ -83,7 +225,10 @@ impl Config {
                 process_testnet_subcommand(sub_cli_args, config)?
             }
             _ => {
-                config.key_source = KeySource::Disk;
+                config.key_source = match cli_args.value_of("interop-validators") {
+                    Some(range) => KeySource::InsecureKeypairs(parse_interop_validators(range)?),
+                    None => KeySource::Disk,
+                };
                 config
             }
         };
@@ -92,6 +237,28 @@ impl Config {
     }
 }
 
+/// Parses a `start:count` range of interop validator indices (e.g., `8:4` is validators 8 to 11).
+fn parse_interop_validators(range: &str) -> Result<Vec<usize>, String> {
+    let mut parts = range.split(':');
+
+    let (start, count) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(start), Some(count), None) => (start, count),
+        _ => return Err("--interop-validators must be of the form start:count".to_string()),
+    };
+    let start = start
+        .parse::<usize>()
+        .map_err(|e| format!("Unable to parse interop validator start: {:?}", e))?;
+    let count = count
+        .parse::<usize>()
+        .map_err(|e| format!("Unable to parse interop validator count: {:?}", e))?;
+
+    if count == 0 {
+        return Err("--interop-validators must specify at least one validator".to_string());
+    }
+
+    Ok((start..start + count).collect())
+}
+
 /// Parses the `testnet` CLI subcommand, modifying the `config` based upon the parameters in
 /// `cli_args`.
 fn process_testnet_subcommand(cli_args: &ArgMatches, mut config: Config) -> Result<Config, String> {
@@ -119,3 +286,19 @@ fn process_testnet_subcommand(cli_args: &ArgMatches, mut config: Config) -> Resu
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interop_validators_range() {
+        assert_eq!(parse_interop_validators("0:4"), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(parse_interop_validators("8:2"), Ok(vec![8, 9]));
+
+        assert!(parse_interop_validators("8:0").is_err());
+        assert!(parse_interop_validators("8").is_err());
+        assert!(parse_interop_validators("0:4:1").is_err());
+        assert!(parse_interop_validators("a:4").is_err());
+    }
+}
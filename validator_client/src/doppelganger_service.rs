@@ -0,0 +1,305 @@
+//! Provides an opt-in "doppelganger" detection mode.
+//!
+//! When enabled, the validator client refuses to sign any messages after startup until it has
+//! watched `detection_epochs` full epochs pass without the beacon node reporting any attestations
+//! from its validators. If any attestations are seen, another instance must be running with the
+//! same keys and the process exits immediately to avoid being slashed.
+use crate::beacon_node_fallback::BeaconNodeFallback;
+use crate::validator_store::ValidatorStore;
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{future, stream, Future, Stream};
+use parking_lot::RwLock;
+use slog::{crit, debug, error, info};
+use slot_clock::SlotClock;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::{ChainSpec, Epoch, EthSpec};
+
+/// The default number of full epochs which must pass without a doppelganger being detected.
+pub const DEFAULT_DOPPELGANGER_DETECTION_EPOCHS: u64 = 2;
+
+/// Run the check this far into each slot, giving the beacon node time to import the latest block.
+const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(500);
+
+/// Builds a `DoppelgangerService`.
+pub struct DoppelgangerServiceBuilder<T, E: EthSpec> {
+    validator_store: Option<ValidatorStore<T, E>>,
+    slot_clock: Option<T>,
+    beacon_nodes: Option<Arc<BeaconNodeFallback<T, E>>>,
+    context: Option<RuntimeContext<E>>,
+    detection_epochs: u64,
+}
+
+impl<T: SlotClock + 'static, E: EthSpec> DoppelgangerServiceBuilder<T, E> {
+    pub fn new() -> Self {
+        Self {
+            validator_store: None,
+            slot_clock: None,
+            beacon_nodes: None,
+            context: None,
+            detection_epochs: DEFAULT_DOPPELGANGER_DETECTION_EPOCHS,
+        }
+    }
+
+    pub fn validator_store(mut self, store: ValidatorStore<T, E>) -> Self {
+        self.validator_store = Some(store);
+        self
+    }
+
+    pub fn slot_clock(mut self, slot_clock: T) -> Self {
+        self.slot_clock = Some(slot_clock);
+        self
+    }
+
+    pub fn beacon_nodes(mut self, beacon_nodes: Arc<BeaconNodeFallback<T, E>>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
+        self
+    }
+
+    pub fn runtime_context(mut self, context: RuntimeContext<E>) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn detection_epochs(mut self, detection_epochs: u64) -> Self {
+        self.detection_epochs = detection_epochs;
+        self
+    }
+
+    pub fn build(self) -> Result<DoppelgangerService<T, E>, String> {
+        let slot_clock = self
+            .slot_clock
+            .ok_or_else(|| "Cannot build DoppelgangerService without slot_clock")?;
+        let start_epoch = slot_clock
+            .now()
+            .ok_or_else(|| "Unable to read slot clock")?
+            .epoch(E::slots_per_epoch());
+
+        Ok(DoppelgangerService {
+            inner: Arc::new(Inner {
+                validator_store: self
+                    .validator_store
+                    .ok_or_else(|| "Cannot build DoppelgangerService without validator_store")?,
+                slot_clock,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build DoppelgangerService without beacon_nodes")?,
+                context: self
+                    .context
+                    .ok_or_else(|| "Cannot build DoppelgangerService without runtime_context")?,
+                start_epoch,
+                progress: RwLock::new(Progress {
+                    remaining_epochs: self.detection_epochs,
+                    last_complete_epoch: start_epoch,
+                }),
+            }),
+        })
+    }
+}
+
+/// Tracks how many full epochs have been checked.
+struct Progress {
+    remaining_epochs: u64,
+    last_complete_epoch: Epoch,
+}
+
+/// Helper to minimise `Arc` usage.
+pub struct Inner<T, E: EthSpec> {
+    validator_store: ValidatorStore<T, E>,
+    slot_clock: T,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+    context: RuntimeContext<E>,
+    /// The epoch in which the validator client started.
+    ///
+    /// Attestations in this epoch are ignored, since they may have been produced by this validator
+    /// client prior to a restart.
+    start_epoch: Epoch,
+    progress: RwLock<Progress>,
+}
+
+/// Watches for attestations from the validators in the `validator_store`, enabling signing once
+/// none have been seen for the required number of epochs.
+pub struct DoppelgangerService<T, E: EthSpec> {
+    inner: Arc<Inner<T, E>>,
+}
+
+impl<T, E: EthSpec> Clone for DoppelgangerService<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, E: EthSpec> Deref for DoppelgangerService<T, E> {
+    type Target = Inner<T, E>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
+
+impl<T: SlotClock + 'static, E: EthSpec> DoppelgangerService<T, E> {
+    /// Starts the service which checks for doppelgangers each slot.
+    pub fn start_update_service(&self, spec: &ChainSpec) -> Result<Signal, String> {
+        let log = self.context.log.clone();
+
+        self.validator_store.set_signing_enabled(false);
+
+        info!(
+            log,
+            "Doppelganger protection enabled";
+            "msg" => "signing is disabled until no doppelganger has been detected",
+            "detection_epochs" => self.progress.read().remaining_epochs,
+        );
+
+        let duration_to_next_slot = self
+            .slot_clock
+            .duration_to_next_slot()
+            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
+
+        let interval = Interval::new(
+            Instant::now() + duration_to_next_slot + TIME_DELAY_FROM_SLOT,
+            Duration::from_millis(spec.milliseconds_per_slot),
+        );
+
+        let (exit_signal, exit_fut) = exit_future::signal();
+        let service = self.clone();
+        let log_1 = log.clone();
+        let log_2 = log.clone();
+
+        self.context.executor.spawn(
+            exit_fut
+                .until(
+                    interval
+                        .map_err(move |e| {
+                            crit! {
+                                log_1,
+                                "Timer thread failed";
+                                "error" => format!("{}", e)
+                            }
+                        })
+                        .take_while(move |_| Ok(!service.validator_store.signing_enabled()))
+                        .for_each({
+                            let service = self.clone();
+                            move |_| service.do_update().then(|_| Ok(()))
+                        }),
+                )
+                .map(move |_| info!(log_2, "Shutdown complete")),
+            "doppelganger_service",
+        );
+
+        Ok(exit_signal)
+    }
+
+    /// Checks the liveness of all validators in the current and previous epochs.
+    fn do_update(&self) -> impl Future<Item = (), Error = ()> {
+        let service = self.clone();
+        let log = self.context.log.clone();
+
+        let current_epoch = match self.slot_clock.now() {
+            Some(slot) => slot.epoch(E::slots_per_epoch()),
+            None => {
+                error!(log, "Unable to read slot clock");
+                return future::Either::A(future::err(()));
+            }
+        };
+        let previous_epoch = current_epoch - 1;
+
+        // Only epochs after the start epoch are checked. The previous epoch is checked as a
+        // "complete" epoch, whilst the current epoch is checked to detect doppelgangers sooner.
+        let epochs = [previous_epoch, current_epoch]
+            .iter()
+            .copied()
+            .filter(|epoch| *epoch > self.start_epoch)
+            .collect::<Vec<_>>();
+
+        let pubkeys = self.validator_store.voting_pubkeys();
+
+        future::Either::B(
+            stream::iter_ok(epochs)
+                .and_then(move |epoch| {
+                    let pubkeys = pubkeys.clone();
+                    service
+                        .beacon_nodes
+                        .first_success(move |beacon_node| {
+                            beacon_node.http.validator().get_liveness(epoch, &pubkeys)
+                        })
+                        .map(move |liveness| (epoch, liveness))
+                })
+                .collect()
+                .map_err({
+                    let log = log.clone();
+                    move |e| {
+                        error!(
+                            log,
+                            "Unable to check for doppelgangers";
+                            "error" => e,
+                        )
+                    }
+                })
+                .map({
+                    let service = self.clone();
+                    move |results| {
+                        for (epoch, liveness) in &results {
+                            for live in liveness.iter().filter(|live| live.is_live) {
+                                crit!(
+                                    log,
+                                    "Doppelganger detected";
+                                    "msg" => "another instance is signing with this validator's \
+                                              keys. Shutting down to avoid being slashed.",
+                                    "epoch" => epoch.as_u64(),
+                                    "voting_pubkey" => format!("{:?}", live.pubkey),
+                                );
+                            }
+                        }
+
+                        if results
+                            .iter()
+                            .any(|(_, liveness)| liveness.iter().any(|live| live.is_live))
+                        {
+                            // There is no way to safely continue, so exit the whole process
+                            // rather than just this service.
+                            std::process::exit(1);
+                        }
+
+                        let checked_previous_epoch =
+                            results.iter().any(|(epoch, _)| *epoch == previous_epoch);
+
+                        service.record_complete_epoch(checked_previous_epoch, previous_epoch);
+                    }
+                }),
+        )
+    }
+
+    /// Records that `epoch` has been checked in full, enabling signing if the required number of
+    /// epochs has been reached.
+    fn record_complete_epoch(&self, checked: bool, epoch: Epoch) {
+        let log = &self.context.log;
+        let mut progress = self.progress.write();
+
+        if checked && epoch > progress.last_complete_epoch {
+            progress.last_complete_epoch = epoch;
+            progress.remaining_epochs = progress.remaining_epochs.saturating_sub(1);
+
+            debug!(
+                log,
+                "No doppelganger detected";
+                "epoch" => epoch.as_u64(),
+                "remaining_epochs" => progress.remaining_epochs,
+            );
+        }
+
+        if progress.remaining_epochs == 0 && !self.validator_store.signing_enabled() {
+            info!(
+                log,
+                "Doppelganger protection complete";
+                "msg" => "no doppelganger detected, signing is enabled",
+            );
+            self.validator_store.set_signing_enabled(true);
+        }
+    }
+}
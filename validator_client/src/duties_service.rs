@@ -1,18 +1,20 @@
+use crate::beacon_node_fallback::BeaconNodeFallback;
+use crate::duty_plugins::{DutyPlugin, DutyRegistry};
 use crate::validator_store::ValidatorStore;
 use environment::RuntimeContext;
-use exit_future::Signal;
+use exit_future::{Exit, Signal};
 use futures::{future, Future, IntoFuture, Stream};
 use parking_lot::RwLock;
-use remote_beacon_node::RemoteBeaconNode;
-use slog::{crit, debug, error, info, trace, warn};
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, debug, error, info, trace, warn, Logger};
 use slot_clock::SlotClock;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::timer::Interval;
-use types::{ChainSpec, CommitteeIndex, Epoch, EthSpec, PublicKey, Slot};
+use types::{ChainSpec, CommitteeIndex, Epoch, EthSpec, Hash256, PublicKey, SelectionProof, Slot};
 
 /// Delay this period of time after the slot starts. This allows the node to process the new slot.
 const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(100);
@@ -22,19 +24,115 @@ const PRUNE_DEPTH: u64 = 4;
 
 type BaseHashMap = HashMap<PublicKey, HashMap<Epoch, ValidatorDuty>>;
 
+/// The point within a slot at which some validator duty should be performed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DutyTiming {
+    /// Blocks are produced at the start of the slot.
+    Block,
+    /// Attestations are produced 1/3rd of the way through the slot.
+    Attestation,
+    /// Aggregates are produced 2/3rds of the way through the slot.
+    Aggregate,
+}
+
+impl DutyTiming {
+    /// Returns the duration after the start of a slot at which this duty should be performed.
+    pub fn offset(self, slot_duration: Duration) -> Duration {
+        match self {
+            DutyTiming::Block => Duration::from_secs(0),
+            DutyTiming::Attestation => slot_duration / 3,
+            DutyTiming::Aggregate => slot_duration * 2 / 3,
+        }
+    }
+
+    /// Returns the name of the duty, as used in metrics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DutyTiming::Block => "block",
+            DutyTiming::Attestation => "attestation",
+            DutyTiming::Aggregate => "aggregate",
+        }
+    }
+
+    /// Returns how long after `self.offset()` in the current slot this duty is being performed.
+    pub fn lateness<T: SlotClock>(self, slot_clock: &T) -> Option<Duration> {
+        let slot = slot_clock.now()?;
+        let elapsed = duration_into_slot(slot_clock, slot)?;
+
+        Some(
+            elapsed
+                .checked_sub(self.offset(slot_clock.slot_duration()))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns an `Interval` which fires once per slot, `self.offset() + delay` after the start
+    /// of each slot (starting at the next slot).
+    ///
+    /// The `delay` may be used to allow the beacon node some time to process the new slot.
+    pub fn interval<T: SlotClock>(
+        self,
+        slot_clock: &T,
+        delay: Duration,
+    ) -> Result<Interval, String> {
+        let duration_to_next_slot = slot_clock
+            .duration_to_next_slot()
+            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
+        let slot_duration = slot_clock.slot_duration();
+
+        Ok(Interval::new(
+            Instant::now() + duration_to_next_slot + self.offset(slot_duration) + delay,
+            slot_duration,
+        ))
+    }
+}
+
+/// Returns the time elapsed since the start of `slot`, or `None` if `slot` has not yet started.
+pub fn duration_into_slot<T: SlotClock>(slot_clock: &T, slot: Slot) -> Option<Duration> {
+    let current_slot = slot_clock.now()?;
+    let slot_duration = slot_clock.slot_duration();
+    let elapsed_slots = current_slot.as_u64().checked_sub(slot.as_u64())?;
+    let into_current_slot = slot_duration.checked_sub(slot_clock.duration_to_next_slot()?)?;
+
+    Some(slot_duration * elapsed_slots as u32 + into_current_slot)
+}
+
 /// Stores the duties for some validator for an epoch.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct ValidatorDuty {
     /// The validator's BLS public key, uniquely identifying them. _48-bytes, hex encoded with 0x prefix, case insensitive._
     pub validator_pubkey: PublicKey,
+    /// The validator's index in `state.validators`.
+    pub validator_index: Option<u64>,
     /// The slot at which the validator must attest.
     pub attestation_slot: Option<Slot>,
     /// The index of the committee within `slot` of which the validator is a member.
     pub attestation_committee_index: Option<CommitteeIndex>,
     /// The position of the validator in the committee.
     pub attestation_committee_position: Option<usize>,
+    /// The modulo used to determine if the validator is an aggregator for its committee.
+    pub aggregator_modulo: Option<u64>,
+    /// The validator's signature across `attestation_slot`, used to determine if it is an
+    /// aggregator.
+    pub selection_proof: Option<SelectionProof>,
     /// The slots in which a validator must propose a block (can be empty).
     pub block_proposal_slots: Vec<Slot>,
+    /// The root of the block upon which these duties depend, if provided by the beacon node.
+    pub dependent_root: Option<Hash256>,
+}
+
+impl ValidatorDuty {
+    /// Returns `true` if the validator must aggregate the attestations of its committee.
+    ///
+    /// Always returns `false` if the `selection_proof` has not yet been computed.
+    pub fn is_aggregator(&self) -> bool {
+        match (&self.selection_proof, self.aggregator_modulo) {
+            (Some(selection_proof), Some(modulo)) => {
+                selection_proof.is_aggregator_from_modulo(modulo)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl TryInto<ValidatorDuty> for remote_beacon_node::ValidatorDuty {
@@ -45,10 +143,14 @@ impl TryInto<ValidatorDuty> for remote_beacon_node::ValidatorDuty {
             validator_pubkey: (&self.validator_pubkey)
                 .try_into()
                 .map_err(|e| format!("Invalid pubkey bytes from server: {:?}", e))?,
+            validator_index: self.validator_index,
             attestation_slot: self.attestation_slot,
             attestation_committee_index: self.attestation_committee_index,
             attestation_committee_position: self.attestation_committee_position,
+            aggregator_modulo: self.aggregator_modulo,
+            selection_proof: None,
             block_proposal_slots: self.block_proposal_slots,
+            dependent_root: self.dependent_root,
         })
     }
 }
@@ -71,6 +173,8 @@ enum InsertOutcome {
 #[derive(Default)]
 pub struct DutiesStore {
     store: RwLock<BaseHashMap>,
+    /// The root upon which the duties of each epoch in `store` depend.
+    dependent_roots: RwLock<HashMap<Epoch, Hash256>>,
 }
 
 impl DutiesStore {
@@ -143,6 +247,61 @@ impl DutiesStore {
             .collect()
     }
 
+    /// Returns the duties of every validator for `epoch` and all later epochs, ordered by epoch
+    /// and then by validator index.
+    fn duties_from(&self, epoch: Epoch) -> Vec<(Epoch, ValidatorDuty)> {
+        let mut duties = self
+            .store
+            .read()
+            .values()
+            .flat_map(|validator_map| {
+                validator_map
+                    .iter()
+                    .filter(|(duties_epoch, _)| **duties_epoch >= epoch)
+                    .map(|(duties_epoch, duties)| (*duties_epoch, duties.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        duties.sort_by_key(|(duties_epoch, duties)| (*duties_epoch, duties.validator_index));
+        duties
+    }
+
+    /// Returns the index of the validator with `pubkey`, if it is known from any of its duties.
+    fn validator_index(&self, pubkey: &PublicKey) -> Option<u64> {
+        self.store
+            .read()
+            .get(pubkey)?
+            .values()
+            .find_map(|duties| duties.validator_index)
+    }
+
+    /// Returns the selection proof already computed for `duties`, if the validator's known duties
+    /// in `epoch` are for the same attestation slot.
+    fn known_selection_proof(
+        &self,
+        epoch: Epoch,
+        duties: &ValidatorDuty,
+    ) -> Option<SelectionProof> {
+        self.store
+            .read()
+            .get(&duties.validator_pubkey)?
+            .get(&epoch)
+            .filter(|known_duties| known_duties.attestation_slot == duties.attestation_slot)?
+            .selection_proof
+            .clone()
+    }
+
+    /// Returns the root upon which the known duties for `epoch` depend.
+    fn dependent_root(&self, epoch: Epoch) -> Option<Hash256> {
+        self.dependent_roots.read().get(&epoch).copied()
+    }
+
+    /// Records that the duties for `epoch` depend upon `root`, returning the root previously
+    /// recorded for `epoch`.
+    fn set_dependent_root(&self, epoch: Epoch, root: Hash256) -> Option<Hash256> {
+        self.dependent_roots.write().insert(epoch, root)
+    }
+
     fn insert(&self, epoch: Epoch, duties: ValidatorDuty, slots_per_epoch: u64) -> InsertOutcome {
         let mut store = self.store.write();
 
@@ -182,14 +341,18 @@ impl DutiesStore {
                 validator_map.retain(|epoch, _duties| *epoch >= prior_to);
                 !validator_map.is_empty()
             });
+        self.dependent_roots
+            .write()
+            .retain(|epoch, _root| *epoch >= prior_to);
     }
 }
 
 pub struct DutiesServiceBuilder<T, E: EthSpec> {
     validator_store: Option<ValidatorStore<T, E>>,
     slot_clock: Option<T>,
-    beacon_node: Option<RemoteBeaconNode<E>>,
+    beacon_nodes: Option<Arc<BeaconNodeFallback<T, E>>>,
     context: Option<RuntimeContext<E>>,
+    duty_registry: DutyRegistry<T, E>,
 }
 
 impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
@@ -197,8 +360,9 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         Self {
             validator_store: None,
             slot_clock: None,
-            beacon_node: None,
+            beacon_nodes: None,
             context: None,
+            duty_registry: DutyRegistry::default(),
         }
     }
 
@@ -212,8 +376,8 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         self
     }
 
-    pub fn beacon_node(mut self, beacon_node: RemoteBeaconNode<E>) -> Self {
-        self.beacon_node = Some(beacon_node);
+    pub fn beacon_nodes(mut self, beacon_nodes: Arc<BeaconNodeFallback<T, E>>) -> Self {
+        self.beacon_nodes = Some(beacon_nodes);
         self
     }
 
@@ -222,6 +386,12 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
         self
     }
 
+    /// The plugins which will be polled in addition to the block and attestation duties.
+    pub fn duty_registry(mut self, duty_registry: DutyRegistry<T, E>) -> Self {
+        self.duty_registry = duty_registry;
+        self
+    }
+
     pub fn build(self) -> Result<DutiesService<T, E>, String> {
         Ok(DutiesService {
             inner: Arc::new(Inner {
@@ -232,12 +402,13 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesServiceBuilder<T, E> {
                 slot_clock: self
                     .slot_clock
                     .ok_or_else(|| "Cannot build DutiesService without slot_clock")?,
-                beacon_node: self
-                    .beacon_node
-                    .ok_or_else(|| "Cannot build DutiesService without beacon_node")?,
+                beacon_nodes: self
+                    .beacon_nodes
+                    .ok_or_else(|| "Cannot build DutiesService without beacon_nodes")?,
                 context: self
                     .context
                     .ok_or_else(|| "Cannot build DutiesService without runtime_context")?,
+                duty_registry: self.duty_registry,
             }),
         })
     }
@@ -248,8 +419,9 @@ pub struct Inner<T, E: EthSpec> {
     store: Arc<DutiesStore>,
     validator_store: ValidatorStore<T, E>,
     pub(crate) slot_clock: T,
-    beacon_node: RemoteBeaconNode<E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
     context: RuntimeContext<E>,
+    duty_registry: DutyRegistry<T, E>,
 }
 
 /// Maintains a store of the duties for all voting validators in the `validator_store`.
@@ -307,22 +479,65 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
         self.store.attesters(slot, E::slots_per_epoch())
     }
 
-    /// Start the service that periodically polls the beacon node for validator duties.
-    pub fn start_update_service(&self, spec: &ChainSpec) -> Result<Signal, String> {
-        let log = self.context.log.clone();
-
-        let duration_to_next_slot = self
+    /// Returns the known duties for the current and all later epochs, ordered by epoch and then
+    /// by validator index.
+    pub fn upcoming_duties(&self) -> Vec<(Epoch, ValidatorDuty)> {
+        let current_epoch = self
             .slot_clock
-            .duration_to_next_slot()
-            .ok_or_else(|| "Unable to determine duration to next slot".to_string())?;
+            .now()
+            .map(|slot| slot.epoch(E::slots_per_epoch()))
+            .unwrap_or_else(|| Epoch::new(0));
 
-        let interval = {
-            let slot_duration = Duration::from_millis(spec.milliseconds_per_slot);
-            Interval::new(
-                Instant::now() + duration_to_next_slot + TIME_DELAY_FROM_SLOT,
-                slot_duration,
-            )
+        self.store.duties_from(current_epoch)
+    }
+
+    /// Stores duties which were persisted by a previous run of the validator client.
+    ///
+    /// Duties for past epochs or for validators which are no longer voting are ignored. The
+    /// restored duties are replaced by the first successful duties poll.
+    pub fn restore_duties(&self, duties: Vec<(Epoch, ValidatorDuty)>) {
+        let current_epoch = match self.slot_clock.now() {
+            Some(slot) => slot.epoch(E::slots_per_epoch()),
+            None => return,
         };
+        let voting_pubkeys = self
+            .validator_store
+            .voting_pubkeys()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        for (epoch, duties) in duties {
+            if epoch < current_epoch || !voting_pubkeys.contains(&duties.validator_pubkey) {
+                continue;
+            }
+
+            if let Some(root) = duties.dependent_root {
+                self.store.set_dependent_root(epoch, root);
+            }
+            self.store.insert(epoch, duties, E::slots_per_epoch());
+        }
+    }
+
+    /// Returns the index of the validator with `pubkey`, if it is known from its duties.
+    pub fn validator_index(&self, pubkey: &PublicKey) -> Option<u64> {
+        self.store.validator_index(pubkey)
+    }
+
+    pub fn validator_store(&self) -> &ValidatorStore<T, E> {
+        &self.validator_store
+    }
+
+    pub fn log(&self) -> &Logger {
+        &self.context.log
+    }
+
+    /// Start the service that periodically polls the beacon node for validator duties.
+    pub fn start_update_service(&self, _spec: &ChainSpec) -> Result<Signal, String> {
+        let log = self.context.log.clone();
+
+        // Poll for duties at the start of each slot, ensuring that the duties for the current and
+        // next epoch are known before any block or attestation needs to be produced.
+        let interval = DutyTiming::Block.interval(&self.slot_clock, TIME_DELAY_FROM_SLOT)?;
 
         let (exit_signal, exit_fut) = exit_future::signal();
         let service = self.clone();
@@ -330,7 +545,13 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
         let log_2 = log.clone();
 
         // Run an immediate update before starting the updater service.
-        self.context.executor.spawn(service.clone().do_update());
+        self.context
+            .executor
+            .spawn(service.clone().do_update(), "duties_service_first_update");
+
+        for plugin in self.duty_registry.plugins() {
+            self.start_plugin_service(plugin.clone(), exit_fut.clone())?;
+        }
 
         self.context.executor.spawn(
             exit_fut
@@ -346,11 +567,76 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
                         .for_each(move |_| service.clone().do_update().then(|_| Ok(()))),
                 )
                 .map(move |_| info!(log_2, "Shutdown complete")),
+            "duties_service",
         );
 
         Ok(exit_signal)
     }
 
+    /// Starts a timer which polls `plugin` according to its cadence, until `exit_fut` resolves.
+    fn start_plugin_service(
+        &self,
+        plugin: Arc<dyn DutyPlugin<T, E>>,
+        exit_fut: Exit,
+    ) -> Result<(), String> {
+        let log = self.context.log.clone();
+        let cadence = plugin.cadence(E::slots_per_epoch());
+        let interval = cadence
+            .timing()
+            .interval(&self.slot_clock, TIME_DELAY_FROM_SLOT)?;
+
+        let service = self.clone();
+        let log_1 = log.clone();
+        let plugin_name = plugin.name();
+
+        self.context.executor.spawn(
+            exit_fut
+                .until(
+                    interval
+                        .map_err(move |e| {
+                            crit! {
+                                log_1,
+                                "Timer thread failed";
+                                "error" => format!("{}", e)
+                            }
+                        })
+                        .for_each(move |_| {
+                            let slot = match service.slot_clock.now() {
+                                Some(slot) => slot,
+                                None => {
+                                    error!(log, "Duties manager failed to read slot clock");
+                                    return Ok(());
+                                }
+                            };
+
+                            if cadence.is_due(slot, E::slots_per_epoch()) {
+                                let log = log.clone();
+
+                                // Spawn a separate task so a slow plugin cannot delay its timer.
+                                service.context.executor.spawn(
+                                    plugin.poll(&service, slot).map_err(move |e| {
+                                        error!(
+                                            log,
+                                            "Duty plugin failed";
+                                            "plugin" => plugin_name,
+                                            "slot" => slot.as_u64(),
+                                            "error" => e,
+                                        )
+                                    }),
+                                    "duty_plugin",
+                                );
+                            }
+
+                            Ok(())
+                        }),
+                )
+                .map(|_| ()),
+            "duty_plugin_service",
+        );
+
+        Ok(())
+    }
+
     /// Attempt to download the duties of all managed validators for this epoch and the next.
     fn do_update(&self) -> impl Future<Item = (), Error = ()> {
         let service_1 = self.clone();
@@ -388,10 +674,8 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
                 let log = service_2.context.log.clone();
 
                 service_2
-                    .beacon_node
-                    .http
-                    .beacon()
-                    .get_head()
+                    .beacon_nodes
+                    .first_success(|beacon_node| beacon_node.http.beacon().get_head())
                     .map(move |head| (epoch, head.slot.epoch(E::slots_per_epoch())))
                     .map_err(move |e| {
                         error!(
@@ -440,6 +724,49 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
             .map(|_| ())
     }
 
+    /// Re-downloads the duties for the epoch of `slot` if the head of the beacon node shows that
+    /// a re-org has changed the root upon which the known duties depend.
+    ///
+    /// Resolves once the duties are up-to-date, so that duties computed from a shuffling which is
+    /// no longer canonical are not performed.
+    pub fn refresh_reorged_duties(&self, slot: Slot) -> impl Future<Item = (), Error = String> {
+        let service = self.clone();
+        let epoch = slot.epoch(E::slots_per_epoch());
+        let known_root = self.store.dependent_root(epoch);
+
+        self.beacon_nodes
+            .first_success(|beacon_node| beacon_node.http.beacon().get_head())
+            .map_err(|e| format!("Failed to get head: {:?}", e))
+            .and_then(move |head| {
+                let head_root = if head.slot.epoch(E::slots_per_epoch()) == epoch {
+                    head.duty_dependent_root
+                } else if head.slot < epoch.start_slot(E::slots_per_epoch()) {
+                    // The head is prior to the epoch, so the duties depend upon the head itself.
+                    Some(head.block_root)
+                } else {
+                    None
+                };
+
+                let future: Box<dyn Future<Item = (), Error = String> + Send> =
+                    match (known_root, head_root) {
+                        (Some(known_root), Some(head_root)) if known_root != head_root => {
+                            debug!(
+                                service.context.log,
+                                "Re-downloading duties after re-org";
+                                "known_root" => format!("{}", known_root),
+                                "head_root" => format!("{}", head_root),
+                                "epoch" => format!("{}", epoch)
+                            );
+
+                            Box::new(service.update_epoch(epoch))
+                        }
+                        _ => Box::new(future::ok(())),
+                    };
+
+                future
+            })
+    }
+
     /// Attempt to download the duties of all managed validators for the given `epoch`.
     fn update_epoch(self, epoch: Epoch) -> impl Future<Item = (), Error = String> {
         let service_1 = self.clone();
@@ -447,14 +774,18 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
 
         let pubkeys = service_1.validator_store.voting_pubkeys();
         service_1
-            .beacon_node
-            .http
-            .validator()
-            .get_duties_bulk(epoch, pubkeys.as_slice())
+            .beacon_nodes
+            .first_success(move |beacon_node| {
+                beacon_node
+                    .http
+                    .validator()
+                    .get_duties_bulk(epoch, pubkeys.as_slice())
+            })
             .map(move |all_duties| (epoch, all_duties))
             .map_err(move |e| format!("Failed to get duties for epoch {}: {:?}", epoch, e))
             .and_then(move |(epoch, all_duties)| {
                 let log = service_2.context.log.clone();
+                let dependent_root = all_duties.iter().find_map(|duties| duties.dependent_root);
 
                 let mut new_validator = 0;
                 let mut new_epoch = 0;
@@ -463,7 +794,19 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
                 let mut invalid = 0;
 
                 all_duties.into_iter().try_for_each::<_, Result<_, String>>(|remote_duties| {
-                    let duties: ValidatorDuty = remote_duties.try_into()?;
+                    let mut duties: ValidatorDuty = remote_duties.try_into()?;
+
+                    // The selection proof is only computed once per attestation slot, since it
+                    // may require a request to a remote signer.
+                    duties.selection_proof = service_2
+                        .store
+                        .known_selection_proof(epoch, &duties)
+                        .or_else(|| {
+                            service_2.validator_store.produce_selection_proof(
+                                &duties.validator_pubkey,
+                                duties.attestation_slot?,
+                            )
+                        });
 
                     match service_2
                         .store
@@ -515,6 +858,31 @@ impl<T: SlotClock + 'static, E: EthSpec> DutiesService<T, E> {
                     )
                 }
 
+                if let Some(root) = dependent_root {
+                    let previous_root = service_2.store.set_dependent_root(epoch, root);
+
+                    // The dependent root of a future epoch changes with each new head, until the
+                    // last slot before the epoch has passed. Only then is a change a re-org.
+                    let dependent_slot_passed = service_2
+                        .slot_clock
+                        .now()
+                        .map_or(false, |slot| slot >= epoch.start_slot(E::slots_per_epoch()));
+
+                    match previous_root {
+                        Some(previous_root) if previous_root != root && dependent_slot_passed => {
+                            warn!(
+                                log,
+                                "Re-org changed duties dependent root";
+                                "info" => "Duties have been re-downloaded.",
+                                "previous_root" => format!("{}", previous_root),
+                                "new_root" => format!("{}", root),
+                                "epoch" => format!("{}", epoch)
+                            )
+                        }
+                        _ => (),
+                    }
+                }
+
                 Ok(())
             })
     }
@@ -530,3 +898,129 @@ fn duties_match_epoch(duties: &ValidatorDuty, epoch: Epoch, slots_per_epoch: u64
             .iter()
             .all(|slot| slot.epoch(slots_per_epoch) == epoch)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Keypair, MinimalEthSpec};
+
+    fn duty(slot: Slot, selection_proof: Option<SelectionProof>) -> ValidatorDuty {
+        ValidatorDuty {
+            validator_pubkey: Keypair::random().pk,
+            validator_index: Some(0),
+            attestation_slot: Some(slot),
+            attestation_committee_index: Some(0),
+            attestation_committee_position: Some(0),
+            aggregator_modulo: Some(1),
+            selection_proof,
+            block_proposal_slots: vec![],
+            dependent_root: None,
+        }
+    }
+
+    #[test]
+    fn selection_proof_reused_for_same_slot() {
+        let spec = MinimalEthSpec::default_spec();
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+        let keypair = Keypair::random();
+        let slot = Slot::new(1);
+        let epoch = slot.epoch(slots_per_epoch);
+        let proof =
+            SelectionProof::new::<MinimalEthSpec>(slot, &keypair.sk, &spec.genesis_fork, &spec);
+
+        let store = DutiesStore::default();
+        let known = duty(slot, Some(proof.clone()));
+        let pubkey = known.validator_pubkey.clone();
+        store.insert(epoch, known, slots_per_epoch);
+
+        let mut new_duty = duty(slot, None);
+        new_duty.validator_pubkey = pubkey.clone();
+        assert_eq!(store.known_selection_proof(epoch, &new_duty), Some(proof));
+
+        let mut moved_duty = duty(slot + 1, None);
+        moved_duty.validator_pubkey = pubkey;
+        assert_eq!(store.known_selection_proof(epoch, &moved_duty), None);
+    }
+
+    #[test]
+    fn is_aggregator_requires_selection_proof() {
+        let spec = MinimalEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let slot = Slot::new(1);
+        let proof =
+            SelectionProof::new::<MinimalEthSpec>(slot, &keypair.sk, &spec.genesis_fork, &spec);
+
+        assert!(!duty(slot, None).is_aggregator());
+        // With a modulo of 1, every validator is an aggregator.
+        assert!(duty(slot, Some(proof)).is_aggregator());
+    }
+
+    #[test]
+    fn duties_from_epoch() {
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+        let store = DutiesStore::default();
+
+        let mut a = duty(Slot::new(1), None);
+        a.validator_index = Some(3);
+        let mut b = duty(Slot::new(2), None);
+        b.validator_index = Some(1);
+        let mut a_next = a.clone();
+        a_next.attestation_slot = Some(Slot::new(slots_per_epoch + 1));
+
+        store.insert(Epoch::new(0), a.clone(), slots_per_epoch);
+        store.insert(Epoch::new(0), b.clone(), slots_per_epoch);
+        store.insert(Epoch::new(1), a_next.clone(), slots_per_epoch);
+
+        assert_eq!(
+            store.duties_from(Epoch::new(0)),
+            vec![
+                (Epoch::new(0), b.clone()),
+                (Epoch::new(0), a),
+                (Epoch::new(1), a_next.clone())
+            ]
+        );
+        assert_eq!(
+            store.duties_from(Epoch::new(1)),
+            vec![(Epoch::new(1), a_next)]
+        );
+        assert_eq!(store.validator_index(&b.validator_pubkey), Some(1));
+        assert_eq!(store.validator_index(&Keypair::random().pk), None);
+    }
+
+    #[test]
+    fn dependent_roots_pruned() {
+        let store = DutiesStore::default();
+        let root = Hash256::from_low_u64_be(1);
+        let reorged_root = Hash256::from_low_u64_be(2);
+
+        assert_eq!(store.set_dependent_root(Epoch::new(1), root), None);
+        assert_eq!(
+            store.set_dependent_root(Epoch::new(1), reorged_root),
+            Some(root)
+        );
+        assert_eq!(store.set_dependent_root(Epoch::new(2), root), None);
+        assert_eq!(store.dependent_root(Epoch::new(1)), Some(reorged_root));
+
+        store.prune(Epoch::new(2));
+        assert_eq!(store.dependent_root(Epoch::new(1)), None);
+        assert_eq!(store.dependent_root(Epoch::new(2)), Some(root));
+    }
+
+    #[test]
+    fn duty_timing_offsets() {
+        let slot_duration = Duration::from_secs(12);
+
+        assert_eq!(
+            DutyTiming::Block.offset(slot_duration),
+            Duration::from_secs(0)
+        );
+        assert_eq!(
+            DutyTiming::Attestation.offset(slot_duration),
+            Duration::from_secs(4)
+        );
+        assert_eq!(
+            DutyTiming::Aggregate.offset(slot_duration),
+            Duration::from_secs(8)
+        );
+    }
+}
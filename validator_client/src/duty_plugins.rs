@@ -0,0 +1,190 @@
+//! Provides the `DutyPlugin` trait, which allows new categories of validator duty to be added to
+//! the `DutiesService` without modifying it.
+//!
+//! Each plugin declares a `DutyCadence` and is polled by the `DutiesService` on its own timer,
+//! independently of the block and attestation duties.
+//!
+//! One plugin is provided:
+//!
+//! - `RandaoPrecompute`: produces the randao reveal for each upcoming block proposal during the
+//!   final slot of the preceding epoch.
+use crate::duties_service::{DutiesService, DutyTiming};
+use futures::{future, Future};
+use slog::{debug, warn};
+use slot_clock::SlotClock;
+use std::collections::HashSet;
+use std::sync::Arc;
+use types::{EthSpec, Slot};
+
+/// The future returned when a plugin is polled.
+pub type DutyFuture = Box<dyn Future<Item = (), Error = String> + Send>;
+
+/// Describes how often a `DutyPlugin` should be polled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DutyCadence {
+    /// Poll during every slot, at the given point within the slot.
+    EverySlot(DutyTiming),
+    /// Poll once per epoch, during the `slot_in_epoch`th slot of the epoch, at the given point
+    /// within the slot.
+    EveryEpoch {
+        slot_in_epoch: u64,
+        timing: DutyTiming,
+    },
+}
+
+impl DutyCadence {
+    /// Returns the point within a slot at which the plugin should be polled.
+    pub fn timing(self) -> DutyTiming {
+        match self {
+            DutyCadence::EverySlot(timing) => timing,
+            DutyCadence::EveryEpoch { timing, .. } => timing,
+        }
+    }
+
+    /// Returns `true` if the plugin should be polled during `slot`.
+    pub fn is_due(self, slot: Slot, slots_per_epoch: u64) -> bool {
+        match self {
+            DutyCadence::EverySlot(_) => true,
+            DutyCadence::EveryEpoch { slot_in_epoch, .. } => {
+                slot.as_u64() % slots_per_epoch == slot_in_epoch
+            }
+        }
+    }
+}
+
+/// A category of validator duty which is performed by the `DutiesService`.
+pub trait DutyPlugin<T, E: EthSpec>: Send + Sync {
+    /// A short name which uniquely identifies the plugin in logs.
+    fn name(&self) -> &'static str;
+
+    /// How often the plugin should be polled.
+    fn cadence(&self, slots_per_epoch: u64) -> DutyCadence;
+
+    /// Perform the duty for `slot`.
+    ///
+    /// The returned future is spawned on its own task, so it may take as long as required without
+    /// delaying other duties.
+    fn poll(&self, duties_service: &DutiesService<T, E>, slot: Slot) -> DutyFuture;
+}
+
+/// The set of plugins which are polled by the `DutiesService`.
+pub struct DutyRegistry<T, E: EthSpec> {
+    plugins: Vec<Arc<dyn DutyPlugin<T, E>>>,
+}
+
+impl<T, E: EthSpec> Default for DutyRegistry<T, E> {
+    fn default() -> Self {
+        Self { plugins: vec![] }
+    }
+}
+
+impl<T, E: EthSpec> DutyRegistry<T, E> {
+    /// Adds `plugin` to the registry, returning an error if a plugin with the same name has
+    /// already been registered.
+    pub fn register(&mut self, plugin: Arc<dyn DutyPlugin<T, E>>) -> Result<(), String> {
+        if self
+            .plugins
+            .iter()
+            .any(|known| known.name() == plugin.name())
+        {
+            return Err(format!(
+                "Duty plugin {} is already registered",
+                plugin.name()
+            ));
+        }
+
+        self.plugins.push(plugin);
+
+        Ok(())
+    }
+
+    /// Returns all registered plugins, in the order they were registered.
+    pub fn plugins(&self) -> &[Arc<dyn DutyPlugin<T, E>>] {
+        &self.plugins
+    }
+}
+
+/// Produces the randao reveals for any block proposals in the next epoch during the final slot of
+/// the current epoch.
+///
+/// This avoids waiting on the signer (which may be remote) at the start of the proposal slot.
+pub struct RandaoPrecompute;
+
+impl<T: SlotClock + 'static, E: EthSpec> DutyPlugin<T, E> for RandaoPrecompute {
+    fn name(&self) -> &'static str {
+        "randao_precompute"
+    }
+
+    fn cadence(&self, slots_per_epoch: u64) -> DutyCadence {
+        DutyCadence::EveryEpoch {
+            slot_in_epoch: slots_per_epoch - 1,
+            timing: DutyTiming::Aggregate,
+        }
+    }
+
+    fn poll(&self, duties_service: &DutiesService<T, E>, slot: Slot) -> DutyFuture {
+        let duties_service = duties_service.clone();
+        let next_epoch = slot.epoch(E::slots_per_epoch()) + 1;
+
+        Box::new(future::lazy(move || {
+            let log = duties_service.log();
+
+            let proposers = next_epoch
+                .slot_iter(E::slots_per_epoch())
+                .flat_map(|slot| duties_service.block_producers(slot))
+                .collect::<HashSet<_>>();
+
+            let failures = proposers
+                .iter()
+                .filter(|pubkey| {
+                    !duties_service
+                        .validator_store()
+                        .precompute_randao_reveal(pubkey, next_epoch)
+                })
+                .count();
+
+            if failures > 0 {
+                warn!(
+                    log,
+                    "Unable to precompute randao reveals";
+                    "failures" => failures,
+                    "epoch" => next_epoch.as_u64(),
+                );
+            } else if !proposers.is_empty() {
+                debug!(
+                    log,
+                    "Precomputed randao reveals";
+                    "proposers" => proposers.len(),
+                    "epoch" => next_epoch.as_u64(),
+                );
+            }
+
+            Ok(())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cadence_is_due() {
+        let slots_per_epoch = 8;
+
+        let every_slot = DutyCadence::EverySlot(DutyTiming::Block);
+        assert!((0..16).all(|slot| every_slot.is_due(Slot::new(slot), slots_per_epoch)));
+
+        let every_epoch = DutyCadence::EveryEpoch {
+            slot_in_epoch: 7,
+            timing: DutyTiming::Aggregate,
+        };
+        assert_eq!(every_epoch.timing(), DutyTiming::Aggregate);
+        assert_eq!(
+            (0..24)
+                .filter(|slot| every_epoch.is_due(Slot::new(*slot), slots_per_epoch))
+                .collect::<Vec<_>>(),
+            vec![7, 15, 23]
+        );
+    }
+}
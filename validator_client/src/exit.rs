@@ -0,0 +1,326 @@
+//! Provides the `validator_client exit` command, which voluntarily exits a validator from the
+//! validator set.
+//!
+//! The command checks that the validator is eligible to exit, asks the user to confirm, signs a
+//! `VoluntaryExit`, submits it to the beacon node and then waits until it has been included in the
+//! chain.
+use crate::{Config, ProductionValidatorClient};
+use clap::ArgMatches;
+use environment::Environment;
+use futures::{
+    future::{loop_fn, Loop},
+    Future,
+};
+use remote_beacon_node::{PublishStatus, ValidatorResponse};
+use slog::{info, Logger};
+use slot_clock::SlotClock;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use types::{ChainSpec, Epoch, EthSpec, PublicKey, Signature, Validator, VoluntaryExit};
+
+/// The phrase the user must type to confirm the exit.
+const CONFIRMATION_PHRASE: &str = "Exit my validator";
+
+/// The explanation shown to the user before they confirm the exit.
+const EXIT_WARNING: &str = "WARNING: a voluntary exit is IRREVERSIBLE.
+
+Once the exit is included in the chain, the validator will stop being assigned duties and can \
+never be activated again. The deposited funds cannot be withdrawn until some future upgrade to \
+the protocol, and cannot be used to re-activate this or any other validator.
+
+The validator must continue to perform its duties until its exit epoch has passed, so do not \
+stop the validator client until then.";
+
+/// Runs the `exit` subcommand of the validator client.
+///
+/// `vc_matches` are the arguments to the `validator_client` command (e.g., the beacon node
+/// endpoints and data directory) and `exit_matches` are the arguments to the `exit` subcommand.
+pub fn run<E: EthSpec>(
+    vc_matches: &ArgMatches,
+    exit_matches: &ArgMatches,
+    mut environment: Environment<E>,
+) -> Result<(), String> {
+    let log = environment.core_context().log;
+    let config = Config::from_cli(vc_matches)?;
+
+    let pubkey = exit_matches
+        .value_of("validator")
+        .ok_or_else(|| "--validator is required".to_string())
+        .and_then(parse_pubkey)?;
+    let no_confirmation = exit_matches.is_present("no-confirmation");
+    let no_wait = exit_matches.is_present("no-wait");
+
+    let context = environment.core_context();
+    let client = environment
+        .runtime()
+        .block_on(ProductionValidatorClient::new(context, config))?;
+    let spec = client.context.eth2_config.spec.clone();
+
+    if !client.validator_store.voting_pubkeys().contains(&pubkey) {
+        return Err(format!(
+            "Validator {:?} is not managed by this validator client",
+            pubkey
+        ));
+    }
+
+    let current_epoch = client
+        .duties_service
+        .slot_clock
+        .now()
+        .ok_or_else(|| "Unable to read slot clock".to_string())?
+        .epoch(E::slots_per_epoch());
+
+    let (validator_index, validator) = environment
+        .runtime()
+        .block_on(get_validator(&client, pubkey.clone()))
+        .and_then(
+            |response| match (response.validator_index, response.validator) {
+                (Some(validator_index), Some(validator)) => Ok((validator_index, validator)),
+                _ => Err(format!(
+                    "Validator {:?} is not known to the beacon node",
+                    pubkey
+                )),
+            },
+        )?;
+
+    check_exit_eligibility(&validator, current_epoch, &spec)?;
+
+    if !no_confirmation {
+        confirm_exit(&pubkey, validator_index)?;
+    }
+
+    environment
+        .runtime()
+        .block_on(client.fork_service.update())
+        .map_err(|e| format!("Unable to get fork from beacon node: {}", e))?;
+
+    let exit = VoluntaryExit {
+        epoch: current_epoch,
+        validator_index: validator_index as u64,
+        signature: Signature::empty_signature(),
+    };
+    let signed_exit = client
+        .validator_store
+        .sign_voluntary_exit(&pubkey, exit)
+        .ok_or_else(|| "Unable to sign voluntary exit".to_string())?;
+
+    let publish_status = environment
+        .runtime()
+        .block_on(client.beacon_nodes.first_success(move |beacon_node| {
+            beacon_node
+                .http
+                .validator()
+                .publish_voluntary_exit(signed_exit.clone())
+        }))?;
+
+    match publish_status {
+        PublishStatus::Valid => info!(
+            log,
+            "Voluntary exit published";
+            "validator_index" => validator_index,
+            "epoch" => current_epoch.as_u64(),
+        ),
+        PublishStatus::Invalid(msg) => {
+            return Err(format!(
+                "Voluntary exit was rejected by beacon node: {}",
+                msg
+            ))
+        }
+        PublishStatus::Unknown => {
+            return Err("Unknown condition when publishing voluntary exit".to_string())
+        }
+    }
+
+    if no_wait {
+        return Ok(());
+    }
+
+    let slot_duration = client.duties_service.slot_clock.slot_duration();
+    let validator = environment.runtime().block_on(wait_for_exit(
+        client,
+        pubkey,
+        slot_duration,
+        log.clone(),
+    ))?;
+
+    info!(
+        log,
+        "Voluntary exit included in chain";
+        "exit_epoch" => validator.exit_epoch.as_u64(),
+        "withdrawable_epoch" => validator.withdrawable_epoch.as_u64(),
+        "info" => "Keep the validator client running until the exit epoch",
+    );
+
+    Ok(())
+}
+
+/// Returns `Ok` if `validator` may submit a voluntary exit during `current_epoch`.
+///
+/// These checks are repeated by the beacon node, but checking them here provides a clearer
+/// explanation to the user.
+fn check_exit_eligibility(
+    validator: &Validator,
+    current_epoch: Epoch,
+    spec: &ChainSpec,
+) -> Result<(), String> {
+    if validator.exit_epoch != spec.far_future_epoch {
+        return Err(format!(
+            "Validator is already exiting, with exit epoch {}",
+            validator.exit_epoch
+        ));
+    }
+
+    if !validator.is_active_at(current_epoch) {
+        return Err(format!(
+            "Validator is not active in the current epoch ({})",
+            current_epoch
+        ));
+    }
+
+    let eligible_epoch = validator.activation_epoch + spec.persistent_committee_period;
+    if current_epoch < eligible_epoch {
+        return Err(format!(
+            "Validator must be active for {} epochs before it may exit, which is epoch {}",
+            spec.persistent_committee_period, eligible_epoch
+        ));
+    }
+
+    Ok(())
+}
+
+/// Explains the consequences of the exit and requires the user to type the
+/// `CONFIRMATION_PHRASE`.
+fn confirm_exit(pubkey: &PublicKey, validator_index: usize) -> Result<(), String> {
+    eprintln!("{}", EXIT_WARNING);
+    eprintln!();
+    eprintln!("Validator: {:?} (index {})", pubkey, validator_index);
+    eprint!(
+        "Enter the phrase \"{}\" to submit the exit: ",
+        CONFIRMATION_PHRASE
+    );
+    io::stderr()
+        .flush()
+        .map_err(|e| format!("Unable to write to terminal: {}", e))?;
+
+    let mut input = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut input)
+        .map_err(|e| format!("Unable to read confirmation: {}", e))?;
+
+    if input.trim() == CONFIRMATION_PHRASE {
+        Ok(())
+    } else {
+        Err("Confirmation phrase did not match, the exit was not submitted".to_string())
+    }
+}
+
+/// Returns the validator with `pubkey`, as known to the beacon node.
+fn get_validator<E: EthSpec>(
+    client: &ProductionValidatorClient<E>,
+    pubkey: PublicKey,
+) -> impl Future<Item = ValidatorResponse, Error = String> {
+    client
+        .beacon_nodes
+        .first_success(move |beacon_node| {
+            beacon_node.http.beacon().get_validators(&[pubkey.clone()])
+        })
+        .and_then(|mut validators| {
+            validators
+                .pop()
+                .ok_or_else(|| "Beacon node returned no validators".to_string())
+        })
+}
+
+/// Polls the beacon node once per slot until the validator's exit epoch has been set.
+fn wait_for_exit<E: EthSpec>(
+    client: ProductionValidatorClient<E>,
+    pubkey: PublicKey,
+    slot_duration: Duration,
+    log: Logger,
+) -> impl Future<Item = Validator, Error = String> {
+    let far_future_epoch = client.context.eth2_config.spec.far_future_epoch;
+
+    info!(log, "Waiting for voluntary exit to be included in chain");
+
+    loop_fn(client, move |client| {
+        let log = log.clone();
+
+        Delay::new(Instant::now() + slot_duration)
+            .map_err(|e| format!("Unable to create delay: {:?}", e))
+            .and_then({
+                let pubkey = pubkey.clone();
+                move |_| get_validator(&client, pubkey).map(|response| (client, response))
+            })
+            .map(move |(client, response)| match response.validator {
+                Some(validator) if validator.exit_epoch != far_future_epoch => {
+                    Loop::Break(validator)
+                }
+                _ => {
+                    info!(log, "Voluntary exit not yet included");
+                    Loop::Continue(client)
+                }
+            })
+    })
+}
+
+fn parse_pubkey(string: &str) -> Result<PublicKey, String> {
+    const PREFIX: &str = "0x";
+
+    if string.starts_with(PREFIX) {
+        let bytes = hex::decode(string.trim_start_matches(PREFIX))
+            .map_err(|e| format!("Invalid hex string: {:?}", e))?;
+        PublicKey::from_bytes(&bytes)
+            .map_err(|e| format!("Unable to deserialize public key: {:?}", e))
+    } else {
+        Err("Public key must have a 0x prefix".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Hash256, MinimalEthSpec};
+
+    fn validator(activation_epoch: u64, spec: &ChainSpec) -> Validator {
+        Validator {
+            pubkey: PublicKey::default().into(),
+            withdrawal_credentials: Hash256::zero(),
+            effective_balance: spec.max_effective_balance,
+            slashed: false,
+            activation_eligibility_epoch: Epoch::new(0),
+            activation_epoch: Epoch::new(activation_epoch),
+            exit_epoch: spec.far_future_epoch,
+            withdrawable_epoch: spec.far_future_epoch,
+        }
+    }
+
+    #[test]
+    fn exit_eligibility() {
+        let spec = MinimalEthSpec::default_spec();
+        let period = spec.persistent_committee_period;
+
+        let eligible = validator(0, &spec);
+        assert_eq!(
+            check_exit_eligibility(&eligible, Epoch::new(period), &spec),
+            Ok(())
+        );
+
+        assert!(
+            check_exit_eligibility(&eligible, Epoch::new(period - 1), &spec).is_err(),
+            "should not exit before persistent_committee_period"
+        );
+        assert!(
+            check_exit_eligibility(&validator(period, &spec), Epoch::new(period), &spec).is_err(),
+            "should not exit immediately after activation"
+        );
+
+        let mut exiting = eligible.clone();
+        exiting.exit_epoch = Epoch::new(period + 10);
+        assert!(
+            check_exit_eligibility(&exiting, Epoch::new(period), &spec).is_err(),
+            "should not exit twice"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Provides a `GraffitiFile`, which maps validator pubkeys to their `ValidatorSettings` (e.g.,
+//! graffiti) and is reloaded whenever it changes on disk.
+//!
+//! The file is JSON, for example:
+//!
+//! ```json
+//! {
+//!   "default": { "graffiti": "my node" },
+//!   "validators": {
+//!     "0xa1b2...": { "graffiti": "validator one" }
+//!   }
+//! }
+//! ```
+use crate::validator_settings::{check_graffiti, ValidatorSettings};
+use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use types::PublicKey;
+
+/// The contents of a graffiti file.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraffitiFileContents {
+    /// Settings for validators which have no entry in `validators`.
+    #[serde(default)]
+    pub default: ValidatorSettings,
+    #[serde(default)]
+    pub validators: HashMap<PublicKey, ValidatorSettings>,
+}
+
+impl GraffitiFileContents {
+    /// Reads and validates the contents of the file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Unable to open graffiti file {:?}: {}", path, e))?;
+        let contents: Self = serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse graffiti file {:?}: {}", path, e))?;
+
+        contents
+            .validators
+            .values()
+            .chain(std::iter::once(&contents.default))
+            .filter_map(|settings| settings.graffiti.as_ref())
+            .try_for_each(|graffiti| check_graffiti(graffiti))
+            .map_err(|e| format!("Invalid graffiti file {:?}: {}", path, e))?;
+
+        Ok(contents)
+    }
+
+    /// Returns the graffiti for the validator with `pubkey`, falling back to the default.
+    pub fn graffiti(&self, pubkey: &PublicKey) -> Option<&String> {
+        self.validators
+            .get(pubkey)
+            .and_then(|settings| settings.graffiti.as_ref())
+            .or_else(|| self.default.graffiti.as_ref())
+    }
+}
+
+/// Identifies a version of a file, such that a change to the file is (almost certainly) detected.
+type FileVersion = (Option<SystemTime>, u64);
+
+/// A graffiti file which is reloaded each time it is modified.
+pub struct GraffitiFile {
+    path: PathBuf,
+    contents: RwLock<(FileVersion, GraffitiFileContents)>,
+}
+
+impl GraffitiFile {
+    /// Loads the graffiti file at `path`.
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let version = file_version(&path)?;
+        let contents = GraffitiFileContents::from_file(&path)?;
+
+        Ok(Self {
+            path,
+            contents: RwLock::new((version, contents)),
+        })
+    }
+
+    /// Reloads the file if it has been modified since it was last loaded.
+    ///
+    /// If the file cannot be read or is invalid, the previous contents are retained.
+    pub fn reload_if_changed(&self) -> Result<(), String> {
+        let version = file_version(&self.path)?;
+
+        if self.contents.read().0 == version {
+            return Ok(());
+        }
+
+        let contents = GraffitiFileContents::from_file(&self.path)?;
+        *self.contents.write() = (version, contents);
+
+        Ok(())
+    }
+
+    /// Returns the graffiti for the validator with `pubkey`, as of the last time the file was
+    /// loaded.
+    pub fn graffiti(&self, pubkey: &PublicKey) -> Option<String> {
+        self.contents.read().1.graffiti(pubkey).cloned()
+    }
+}
+
+fn file_version(path: &Path) -> Result<FileVersion, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Unable to read graffiti file {:?}: {}", path, e))?;
+
+    Ok((metadata.modified().ok(), metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+    use types::Keypair;
+
+    fn write_file(path: &Path, contents: &GraffitiFileContents) {
+        serde_json::to_writer(File::create(path).expect("should create file"), contents)
+            .expect("should write file");
+    }
+
+    #[test]
+    fn graffiti_falls_back_to_default() {
+        let pubkey = Keypair::random().pk;
+        let other_pubkey = Keypair::random().pk;
+
+        let mut contents = GraffitiFileContents::default();
+        assert_eq!(contents.graffiti(&pubkey), None);
+
+        contents.default.graffiti = Some("default".into());
+        contents.validators.insert(
+            pubkey.clone(),
+            ValidatorSettings {
+                graffiti: Some("validator".into()),
+            },
+        );
+        contents
+            .validators
+            .insert(other_pubkey.clone(), ValidatorSettings::default());
+
+        assert_eq!(contents.graffiti(&pubkey), Some(&"validator".to_string()));
+        assert_eq!(
+            contents.graffiti(&other_pubkey),
+            Some(&"default".to_string())
+        );
+        assert_eq!(
+            contents.graffiti(&Keypair::random().pk),
+            Some(&"default".to_string())
+        );
+    }
+
+    #[test]
+    fn reloads_on_change() {
+        let temp_dir = TempDir::new("graffiti_file").expect("should create test dir");
+        let path = temp_dir.path().join("graffiti.json");
+        let pubkey = Keypair::random().pk;
+
+        let mut contents = GraffitiFileContents::default();
+        contents.default.graffiti = Some("before".into());
+        write_file(&path, &contents);
+
+        let file = GraffitiFile::new(path.clone()).expect("should load file");
+        assert_eq!(file.graffiti(&pubkey), Some("before".into()));
+
+        contents.default.graffiti = Some("after the change".into());
+        write_file(&path, &contents);
+        file.reload_if_changed().expect("should reload");
+        assert_eq!(file.graffiti(&pubkey), Some("after the change".into()));
+
+        // An invalid file is rejected, retaining the previous contents.
+        contents.default.graffiti = Some("a".repeat(33));
+        write_file(&path, &contents);
+        assert!(file.reload_if_changed().is_err());
+        assert_eq!(file.graffiti(&pubkey), Some("after the change".into()));
+    }
+}
@@ -0,0 +1,177 @@
+//! The request and response bodies used by the HTTP API.
+use serde_derive::{Deserialize, Serialize};
+use types::{CommitteeIndex, Epoch, PublicKey, Slot};
+
+/// Wraps the data returned by every successful request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericResponse<T> {
+    pub data: T,
+}
+
+impl<T> From<T> for GenericResponse<T> {
+    fn from(data: T) -> Self {
+        Self { data }
+    }
+}
+
+/// The result of an operation on a single validator, along with an optional explanation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status<T> {
+    pub status: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl<T> Status<T> {
+    pub fn ok(status: T) -> Self {
+        Self {
+            status,
+            message: None,
+        }
+    }
+
+    pub fn error(status: T, message: String) -> Self {
+        Self {
+            status,
+            message: Some(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Imported,
+    Duplicate,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteStatus {
+    Deleted,
+    NotFound,
+    Error,
+}
+
+/// A validator which signs with a local keystore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeystoreData {
+    pub validating_pubkey: PublicKey,
+}
+
+/// The body of a `POST /eth/v1/keystores` request.
+///
+/// Each entry in `keystores` is a JSON-encoded EIP-2335 keystore, which is decrypted with the
+/// entry at the same index in `passwords`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportKeystoresRequest {
+    pub keystores: Vec<String>,
+    pub passwords: Vec<String>,
+}
+
+/// The body of a `DELETE /eth/v1/keystores` or `DELETE /eth/v1/remotekeys` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeleteKeysRequest {
+    pub pubkeys: Vec<PublicKey>,
+}
+
+/// A validator which signs with a remote signer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteKeyData {
+    pub pubkey: PublicKey,
+    pub url: String,
+}
+
+/// The body of a `POST /eth/v1/remotekeys` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRemoteKeysRequest {
+    pub remote_keys: Vec<RemoteKeyData>,
+}
+
+/// The response to a `GET /eth/v1/validator/{pubkey}/graffiti` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraffitiData {
+    pub pubkey: PublicKey,
+    pub graffiti: Option<String>,
+}
+
+/// The body of a `POST /eth/v1/validator/{pubkey}/graffiti` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetGraffitiRequest {
+    pub graffiti: String,
+}
+
+/// How a validator signs its messages.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerKind {
+    Local,
+    Remote,
+}
+
+/// A validator managed by the validator client, as returned by `GET /lighthouse/validators`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorData {
+    pub pubkey: PublicKey,
+    /// The index of the validator in the beacon state, if it is known (i.e., the validator is
+    /// in the state and its duties have been fetched).
+    pub index: Option<u64>,
+    pub signer: SignerKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+    pub graffiti: Option<String>,
+    /// The slot of the latest block signed by the validator, including before a restart.
+    pub last_signed_block_slot: Option<Slot>,
+    /// The target epoch of the latest attestation signed by the validator, including before a
+    /// restart.
+    pub last_signed_attestation_target_epoch: Option<Epoch>,
+}
+
+/// The duties of a single validator for an epoch, as returned by `GET /lighthouse/duties`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DutyData {
+    pub pubkey: PublicKey,
+    pub validator_index: Option<u64>,
+    pub epoch: Epoch,
+    pub attestation_slot: Option<Slot>,
+    pub attestation_committee_index: Option<CommitteeIndex>,
+    /// Always `false` until the validator's selection proof has been computed.
+    pub is_aggregator: bool,
+    pub block_proposal_slots: Vec<Slot>,
+}
+
+/// The health of a beacon node, as seen by its most recent health check.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeaconNodeStatus {
+    Synced,
+    NotSynced,
+    Offline,
+    Uninitialized,
+}
+
+/// A beacon node used by the validator client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BeaconNodeHealthData {
+    pub endpoint: String,
+    pub status: BeaconNodeStatus,
+    pub latency_ms: Option<u64>,
+    /// The number of successful requests since the validator client started.
+    pub successes: usize,
+    /// The number of failed requests since the validator client started.
+    pub failures: usize,
+}
+
+/// The response to a `GET /lighthouse/health` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthData {
+    /// The wall-clock slot, or `None` if it is before genesis.
+    pub current_slot: Option<Slot>,
+    /// `false` whilst doppelganger protection prevents the validators from signing.
+    pub signing_enabled: bool,
+    pub validator_count: usize,
+    /// The number of beacon nodes which are online and synced.
+    pub beacon_nodes_available: usize,
+    pub beacon_nodes: Vec<BeaconNodeHealthData>,
+}
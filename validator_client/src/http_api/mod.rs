@@ -0,0 +1,685 @@
+//! Provides an authenticated HTTP API which allows the validators managed by this validator client
+//! to be listed, imported and deleted at runtime (e.g., by a staking dashboard), and allows the
+//! validator client to be monitored separately from its beacon nodes.
+//!
+//! The following endpoints are provided:
+//!
+//! - `GET|POST|DELETE /eth/v1/keystores`: local EIP-2335 keystores.
+//! - `GET|POST|DELETE /eth/v1/remotekeys`: remote signer definitions.
+//! - `GET|POST|DELETE /eth/v1/validator/{pubkey}/graffiti`: per-validator graffiti.
+//! - `GET /lighthouse/validators`: all validators, with their index and signer.
+//! - `GET /lighthouse/duties`: the known duties for the current and later epochs.
+//! - `GET /lighthouse/health`: the wall-clock slot and the status of each beacon node.
+//!
+//! Every request must include an `Authorization: Bearer <token>` header, where the token is read
+//! from the `api-token.txt` file in the validator client's data directory.
+mod api_types;
+
+pub use api_types::*;
+
+use crate::beacon_node_fallback::{BeaconNodeFallback, CandidateError};
+use crate::duties_service::DutiesService;
+use crate::signing_method::RemoteSignerDefinition;
+use crate::validator_directory::create_with_600_perms;
+use crate::validator_settings::check_graffiti;
+use crate::validator_store::{DeleteOutcome, ImportOutcome, ValidatorStore};
+use environment::RuntimeContext;
+use eth2_keystore::Keystore;
+use exit_future::Signal;
+use futures::{Future, IntoFuture, Stream};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_derive::{Deserialize, Serialize};
+use slog::{debug, info, warn, Logger};
+use slot_clock::SlotClock;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use types::{EthSpec, PublicKey};
+
+/// The name of the file in the data directory which contains the API token.
+pub const API_TOKEN_FILE: &str = "api-token.txt";
+
+/// The number of random bytes in a newly generated API token.
+const API_TOKEN_BYTES: usize = 32;
+
+const KEYSTORES_PATH: &str = "/eth/v1/keystores";
+const REMOTE_KEYS_PATH: &str = "/eth/v1/remotekeys";
+const VALIDATOR_PATH_PREFIX: &str = "/eth/v1/validator/";
+const GRAFFITI_PATH_SUFFIX: &str = "/graffiti";
+const LIGHTHOUSE_VALIDATORS_PATH: &str = "/lighthouse/validators";
+const LIGHTHOUSE_DUTIES_PATH: &str = "/lighthouse/duties";
+const LIGHTHOUSE_HEALTH_PATH: &str = "/lighthouse/health";
+
+/// HTTP API Configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the HTTP API server.
+    pub enabled: bool,
+    /// The IPv4 address the HTTP API server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the HTTP API server will listen on.
+    pub port: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5062,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ApiError {
+    MethodNotAllowed(String),
+    ServerError(String),
+    BadRequest(String),
+    NotFound(String),
+    Unauthorized(String),
+}
+
+impl ApiError {
+    pub fn status_code(self) -> (StatusCode, String) {
+        match self {
+            ApiError::MethodNotAllowed(desc) => (StatusCode::METHOD_NOT_ALLOWED, desc),
+            ApiError::ServerError(desc) => (StatusCode::INTERNAL_SERVER_ERROR, desc),
+            ApiError::BadRequest(desc) => (StatusCode::BAD_REQUEST, desc),
+            ApiError::NotFound(desc) => (StatusCode::NOT_FOUND, desc),
+            ApiError::Unauthorized(desc) => (StatusCode::UNAUTHORIZED, desc),
+        }
+    }
+}
+
+impl Into<Response<Body>> for ApiError {
+    fn into(self) -> Response<Body> {
+        let status_code = self.status_code();
+        Response::builder()
+            .status(status_code.0)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Body::from(status_code.1))
+            .expect("Response should always be created.")
+    }
+}
+
+pub type ApiResult = Result<Response<Body>, ApiError>;
+type BoxFut = Box<dyn Future<Item = Response<Body>, Error = ApiError> + Send>;
+
+/// The components which are read or modified by the requests.
+struct Shared<T, E: EthSpec> {
+    validator_store: ValidatorStore<T, E>,
+    duties_service: DutiesService<T, E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+    api_token: String,
+}
+
+/// Starts the HTTP API server, serving requests for the validators in `validator_store`.
+///
+/// The API token is read from (or generated in) `data_dir`.
+pub fn start_server<T: SlotClock + 'static, E: EthSpec>(
+    config: &Config,
+    context: &RuntimeContext<E>,
+    validator_store: ValidatorStore<T, E>,
+    duties_service: DutiesService<T, E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+    data_dir: &Path,
+) -> Result<(Signal, SocketAddr), String> {
+    let log = context.log.clone();
+    let inner_log = log.clone();
+    let shared = Arc::new(Shared {
+        validator_store,
+        duties_service,
+        beacon_nodes,
+        api_token: load_or_create_api_token(data_dir)?,
+    });
+
+    // Define the function that will build the request handler.
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let shared = shared.clone();
+        let log = inner_log.clone();
+
+        service_fn(move |req: Request<Body>| route(req, &shared, log.clone()))
+    });
+
+    let bind_addr = (config.listen_address, config.port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind HTTP API to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    // Determine the address the server is actually listening on.
+    //
+    // This may be different to `bind_addr` if bind port was 0 (this allows the OS to choose a free
+    // port).
+    let actual_listen_addr = server.local_addr();
+
+    // Build a channel to kill the HTTP server.
+    let (exit_signal, exit) = exit_future::signal();
+    let inner_log = log.clone();
+    let server_exit = exit.and_then(move |_| {
+        info!(inner_log, "HTTP API shutdown");
+        Ok(())
+    });
+    // Configure the `hyper` server to gracefully shutdown when the shutdown channel is triggered.
+    let inner_log = log.clone();
+    let server_future = server
+        .with_graceful_shutdown(server_exit)
+        .map_err(move |e| warn!(inner_log, "HTTP API failed"; "error" => format!("{:?}", e)));
+
+    info!(
+        log,
+        "HTTP API started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+        "api_token_file" => format!("{:?}", data_dir.join(API_TOKEN_FILE)),
+    );
+
+    context.executor.spawn(server_future, "http_api");
+
+    Ok((exit_signal, actual_listen_addr))
+}
+
+/// Reads the API token from `data_dir`, generating a new random token if the file does not exist.
+pub fn load_or_create_api_token(data_dir: &Path) -> Result<String, String> {
+    let path = data_dir.join(API_TOKEN_FILE);
+
+    if path.exists() {
+        let token = fs::read_to_string(&path)
+            .map_err(|e| format!("Unable to read API token file {:?}: {}", path, e))?;
+        let token = token.trim();
+
+        if token.is_empty() {
+            Err(format!("API token file {:?} is empty", path))
+        } else {
+            Ok(token.to_string())
+        }
+    } else {
+        let token = hex::encode(rand::thread_rng().gen::<[u8; API_TOKEN_BYTES]>());
+
+        fs::create_dir_all(data_dir)
+            .map_err(|e| format!("Unable to create {:?}: {}", data_dir, e))?;
+        create_with_600_perms(&path, token.as_bytes())?;
+
+        Ok(token)
+    }
+}
+
+/// Returns `Ok` if `req` has an `Authorization` header containing the `api_token`.
+fn check_auth(req: &Request<Body>, api_token: &str) -> Result<(), ApiError> {
+    const BEARER_PREFIX: &str = "Bearer ";
+
+    let header = req
+        .headers()
+        .get(AUTHORIZATION)
+        .ok_or_else(|| ApiError::Unauthorized("Missing Authorization header".to_string()))?
+        .to_str()
+        .map_err(|_| ApiError::Unauthorized("Invalid Authorization header".to_string()))?;
+
+    if !header.starts_with(BEARER_PREFIX) {
+        return Err(ApiError::Unauthorized(
+            "Authorization header must use the Bearer scheme".to_string(),
+        ));
+    }
+
+    if constant_time_eq(
+        header[BEARER_PREFIX.len()..].as_bytes(),
+        api_token.as_bytes(),
+    ) {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized("Invalid API token".to_string()))
+    }
+}
+
+/// Compares `a` and `b` in time that depends only on their lengths, so the API token cannot be
+/// guessed byte-by-byte by timing responses.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn into_boxfut<F: IntoFuture + 'static>(item: F) -> BoxFut
+where
+    F: IntoFuture<Item = Response<Body>, Error = ApiError>,
+    F::Future: Send,
+{
+    Box::new(item.into_future())
+}
+
+fn route<T: SlotClock + 'static, E: EthSpec>(
+    req: Request<Body>,
+    shared: &Shared<T, E>,
+    log: Logger,
+) -> impl Future<Item = Response<Body>, Error = hyper::Error> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let validator_store = shared.validator_store.clone();
+
+    let request_result: BoxFut = match check_auth(&req, &shared.api_token) {
+        Err(e) => into_boxfut(Err(e)),
+        Ok(()) => match (&method, path.as_ref()) {
+            (&Method::GET, LIGHTHOUSE_VALIDATORS_PATH) => into_boxfut(list_validators(shared)),
+            (&Method::GET, LIGHTHOUSE_DUTIES_PATH) => into_boxfut(list_duties(shared)),
+            (&Method::GET, LIGHTHOUSE_HEALTH_PATH) => into_boxfut(get_health(shared)),
+            (&Method::GET, KEYSTORES_PATH) => into_boxfut(list_keystores(&validator_store)),
+            (&Method::POST, KEYSTORES_PATH) => Box::new(
+                json_body(req).and_then(move |body| import_keystores(body, &validator_store)),
+            ),
+            (&Method::DELETE, KEYSTORES_PATH) => Box::new(
+                json_body(req).and_then(move |body| delete_keystores(body, &validator_store)),
+            ),
+            (&Method::GET, REMOTE_KEYS_PATH) => into_boxfut(list_remote_keys(&validator_store)),
+            (&Method::POST, REMOTE_KEYS_PATH) => Box::new(
+                json_body(req).and_then(move |body| import_remote_keys(body, &validator_store)),
+            ),
+            (&Method::DELETE, REMOTE_KEYS_PATH) => Box::new(
+                json_body(req).and_then(move |body| delete_remote_keys(body, &validator_store)),
+            ),
+            (method, path) => match graffiti_path_pubkey(path) {
+                Some(pubkey) => match (method, parse_pubkey(pubkey)) {
+                    (_, Err(e)) => into_boxfut(Err(e)),
+                    (&Method::GET, Ok(pubkey)) => {
+                        into_boxfut(get_graffiti(pubkey, &validator_store))
+                    }
+                    (&Method::POST, Ok(pubkey)) => {
+                        Box::new(json_body(req).and_then(move |body: SetGraffitiRequest| {
+                            set_graffiti(pubkey, Some(body.graffiti), &validator_store)
+                        }))
+                    }
+                    (&Method::DELETE, Ok(pubkey)) => {
+                        into_boxfut(set_graffiti(pubkey, None, &validator_store))
+                    }
+                    _ => into_boxfut(Err(ApiError::MethodNotAllowed(path.to_string()))),
+                },
+                None => into_boxfut(Err(ApiError::NotFound(
+                    "Request path and/or method not found.".to_string(),
+                ))),
+            },
+        },
+    };
+
+    request_result.then(move |result| match result {
+        Ok(response) => {
+            debug!(log, "HTTP API request successful"; "method" => format!("{}", method), "path" => path);
+            Ok(response)
+        }
+        Err(e) => {
+            debug!(log, "HTTP API request failure"; "method" => format!("{}", method), "path" => path);
+            Ok(e.into())
+        }
+    })
+}
+
+/// Returns the (unparsed) pubkey in a `/eth/v1/validator/{pubkey}/graffiti` path.
+fn graffiti_path_pubkey(path: &str) -> Option<&str> {
+    if path.starts_with(VALIDATOR_PATH_PREFIX)
+        && path.ends_with(GRAFFITI_PATH_SUFFIX)
+        && path.len() > VALIDATOR_PATH_PREFIX.len() + GRAFFITI_PATH_SUFFIX.len()
+    {
+        Some(&path[VALIDATOR_PATH_PREFIX.len()..path.len() - GRAFFITI_PATH_SUFFIX.len()])
+    } else {
+        None
+    }
+}
+
+/// Parses a 0x-prefixed, hex-encoded public key.
+fn parse_pubkey(string: &str) -> Result<PublicKey, ApiError> {
+    const PREFIX: &str = "0x";
+
+    if string.starts_with(PREFIX) {
+        let bytes = hex::decode(string.trim_start_matches(PREFIX))
+            .map_err(|e| ApiError::BadRequest(format!("Invalid hex string: {:?}", e)))?;
+        PublicKey::from_bytes(&bytes).map_err(|e| {
+            ApiError::BadRequest(format!("Unable to deserialize public key: {:?}.", e))
+        })
+    } else {
+        Err(ApiError::BadRequest(
+            "Public key must have a 0x prefix".to_string(),
+        ))
+    }
+}
+
+/// Reads the body of `req` and decodes it as JSON.
+fn json_body<T: DeserializeOwned + Send + 'static>(
+    req: Request<Body>,
+) -> impl Future<Item = T, Error = ApiError> {
+    req.into_body()
+        .concat2()
+        .map_err(|e| ApiError::ServerError(format!("Unable to get request body: {:?}", e)))
+        .and_then(|chunks| {
+            serde_json::from_slice(&chunks)
+                .map_err(|e| ApiError::BadRequest(format!("Unable to parse JSON body: {:?}", e)))
+        })
+}
+
+/// Returns a `200 OK` response containing `data`, encoded as JSON.
+fn json_response<T: Serialize>(data: T) -> ApiResult {
+    let body = serde_json::to_vec(&GenericResponse::from(data))
+        .map_err(|e| ApiError::ServerError(format!("Unable to serialize response: {:?}", e)))?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("Response should always be created."))
+}
+
+fn import_status(result: Result<ImportOutcome, String>) -> Status<ImportStatus> {
+    match result {
+        Ok(ImportOutcome::Imported) => Status::ok(ImportStatus::Imported),
+        Ok(ImportOutcome::Duplicate) => Status::ok(ImportStatus::Duplicate),
+        Err(e) => Status::error(ImportStatus::Error, e),
+    }
+}
+
+fn delete_status(result: Result<DeleteOutcome, String>) -> Status<DeleteStatus> {
+    match result {
+        Ok(DeleteOutcome::Deleted) => Status::ok(DeleteStatus::Deleted),
+        Ok(DeleteOutcome::NotFound) => Status::ok(DeleteStatus::NotFound),
+        Err(e) => Status::error(DeleteStatus::Error, e),
+    }
+}
+
+/// HTTP handler to list the validators which sign with a local keystore.
+fn list_keystores<T: SlotClock + 'static, E: EthSpec>(
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let keystores = validator_store
+        .local_pubkeys()
+        .into_iter()
+        .map(|validating_pubkey| KeystoreData { validating_pubkey })
+        .collect::<Vec<_>>();
+
+    json_response(keystores)
+}
+
+/// HTTP handler to import EIP-2335 keystores, returning a status for each keystore.
+fn import_keystores<T: SlotClock + 'static, E: EthSpec>(
+    request: ImportKeystoresRequest,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    if request.keystores.len() != request.passwords.len() {
+        return Err(ApiError::BadRequest(format!(
+            "Got {} keystores but {} passwords",
+            request.keystores.len(),
+            request.passwords.len()
+        )));
+    }
+
+    let statuses = request
+        .keystores
+        .iter()
+        .zip(request.passwords.into_iter())
+        .map(|(keystore, password)| {
+            import_status(
+                Keystore::from_json_str(keystore)
+                    .map_err(|e| format!("Invalid keystore: {:?}", e))
+                    .and_then(|keystore| {
+                        validator_store.import_keystore(keystore, password.into_bytes())
+                    }),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    json_response(statuses)
+}
+
+/// HTTP handler to delete local keystores, returning a status for each pubkey.
+///
+/// The slashing protection history of each validator is retained on disk.
+fn delete_keystores<T: SlotClock + 'static, E: EthSpec>(
+    request: DeleteKeysRequest,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let statuses = request
+        .pubkeys
+        .iter()
+        .map(|pubkey| delete_status(validator_store.delete_keystore(pubkey)))
+        .collect::<Vec<_>>();
+
+    json_response(statuses)
+}
+
+/// HTTP handler to list the validators which sign with a remote signer.
+fn list_remote_keys<T: SlotClock + 'static, E: EthSpec>(
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let remote_keys = validator_store
+        .remote_signers()
+        .into_iter()
+        .map(|definition| RemoteKeyData {
+            pubkey: definition.pubkey,
+            url: definition.url,
+        })
+        .collect::<Vec<_>>();
+
+    json_response(remote_keys)
+}
+
+/// HTTP handler to add remote signers, returning a status for each signer.
+fn import_remote_keys<T: SlotClock + 'static, E: EthSpec>(
+    request: ImportRemoteKeysRequest,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let statuses = request
+        .remote_keys
+        .into_iter()
+        .map(|remote_key| {
+            import_status(
+                validator_store.import_remote_signer(RemoteSignerDefinition {
+                    pubkey: remote_key.pubkey,
+                    url: remote_key.url,
+                }),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    json_response(statuses)
+}
+
+/// HTTP handler to remove remote signers, returning a status for each pubkey.
+fn delete_remote_keys<T: SlotClock + 'static, E: EthSpec>(
+    request: DeleteKeysRequest,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let statuses = request
+        .pubkeys
+        .iter()
+        .map(|pubkey| delete_status(validator_store.delete_remote_signer(pubkey)))
+        .collect::<Vec<_>>();
+
+    json_response(statuses)
+}
+
+/// HTTP handler to return the graffiti of a single validator.
+fn get_graffiti<T: SlotClock + 'static, E: EthSpec>(
+    pubkey: PublicKey,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    let settings = validator_store
+        .validator_settings(&pubkey)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown validator: {:?}", pubkey)))?;
+
+    json_response(GraffitiData {
+        pubkey,
+        graffiti: settings.graffiti,
+    })
+}
+
+/// HTTP handler to set (or, if `graffiti` is `None`, clear) the graffiti of a single validator.
+fn set_graffiti<T: SlotClock + 'static, E: EthSpec>(
+    pubkey: PublicKey,
+    graffiti: Option<String>,
+    validator_store: &ValidatorStore<T, E>,
+) -> ApiResult {
+    if let Some(graffiti) = &graffiti {
+        check_graffiti(graffiti).map_err(ApiError::BadRequest)?;
+    }
+
+    let mut settings = validator_store
+        .validator_settings(&pubkey)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown validator: {:?}", pubkey)))?;
+    settings.graffiti = graffiti;
+
+    validator_store
+        .set_validator_settings(&pubkey, settings)
+        .map_err(ApiError::ServerError)?
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown validator: {:?}", pubkey)))?;
+
+    json_response(())
+}
+
+/// HTTP handler to list all validators, whether they sign locally or remotely.
+fn list_validators<T: SlotClock + 'static, E: EthSpec>(shared: &Shared<T, E>) -> ApiResult {
+    let validator_store = &shared.validator_store;
+    let local = validator_store
+        .local_pubkeys()
+        .into_iter()
+        .map(|pubkey| (pubkey, SignerKind::Local, None));
+    let remote = validator_store
+        .remote_signers()
+        .into_iter()
+        .map(|definition| (definition.pubkey, SignerKind::Remote, Some(definition.url)));
+
+    let validators = local
+        .chain(remote)
+        .map(|(pubkey, signer, remote_url)| {
+            let last_signed = validator_store.last_signed_by(&pubkey);
+
+            ValidatorData {
+                index: shared.duties_service.validator_index(&pubkey),
+                graffiti: validator_store
+                    .validator_settings(&pubkey)
+                    .and_then(|settings| settings.graffiti),
+                last_signed_block_slot: last_signed
+                    .as_ref()
+                    .and_then(|last_signed| last_signed.block_slot),
+                last_signed_attestation_target_epoch: last_signed
+                    .and_then(|last_signed| last_signed.attestation_target_epoch),
+                pubkey,
+                signer,
+                remote_url,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    json_response(validators)
+}
+
+/// HTTP handler to list the known duties for the current and later epochs.
+fn list_duties<T: SlotClock + 'static, E: EthSpec>(shared: &Shared<T, E>) -> ApiResult {
+    let duties = shared
+        .duties_service
+        .upcoming_duties()
+        .into_iter()
+        .map(|(epoch, duties)| DutyData {
+            is_aggregator: duties.is_aggregator(),
+            pubkey: duties.validator_pubkey,
+            validator_index: duties.validator_index,
+            epoch,
+            attestation_slot: duties.attestation_slot,
+            attestation_committee_index: duties.attestation_committee_index,
+            block_proposal_slots: duties.block_proposal_slots,
+        })
+        .collect::<Vec<_>>();
+
+    json_response(duties)
+}
+
+/// HTTP handler to report the wall-clock slot and the health of each beacon node.
+fn get_health<T: SlotClock + 'static, E: EthSpec>(shared: &Shared<T, E>) -> ApiResult {
+    let beacon_nodes = shared
+        .beacon_nodes
+        .health()
+        .into_iter()
+        .map(|health| BeaconNodeHealthData {
+            endpoint: health.endpoint,
+            status: match health.status {
+                Ok(()) => BeaconNodeStatus::Synced,
+                Err(CandidateError::NotSynced) => BeaconNodeStatus::NotSynced,
+                Err(CandidateError::Offline) => BeaconNodeStatus::Offline,
+                Err(CandidateError::Uninitialized) => BeaconNodeStatus::Uninitialized,
+            },
+            latency_ms: health.latency.map(|latency| latency.as_millis() as u64),
+            successes: health.successes,
+            failures: health.failures,
+        })
+        .collect::<Vec<_>>();
+
+    json_response(HealthData {
+        current_slot: shared.duties_service.slot_clock.now(),
+        signing_enabled: shared.validator_store.signing_enabled(),
+        validator_count: shared.validator_store.num_voting_validators(),
+        beacon_nodes_available: shared.beacon_nodes.num_available(),
+        beacon_nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+    use types::Keypair;
+
+    fn request_with_auth(header: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder();
+        builder.uri(KEYSTORES_PATH);
+        if let Some(header) = header {
+            builder.header(AUTHORIZATION, header);
+        }
+        builder.body(Body::empty()).expect("should build request")
+    }
+
+    #[test]
+    fn api_token_is_persisted() {
+        let temp_dir = TempDir::new("http_api").expect("should create test dir");
+
+        let token = load_or_create_api_token(temp_dir.path()).expect("should create token");
+        assert_eq!(token.len(), API_TOKEN_BYTES * 2);
+        assert_eq!(
+            load_or_create_api_token(temp_dir.path()),
+            Ok(token),
+            "should load the existing token"
+        );
+    }
+
+    #[test]
+    fn auth_requires_token() {
+        let token = "secret";
+
+        assert_eq!(
+            check_auth(&request_with_auth(Some("Bearer secret")), token),
+            Ok(())
+        );
+        for header in &[
+            None,
+            Some("Bearer wrong"),
+            Some("Basic secret"),
+            Some("secret"),
+        ] {
+            match check_auth(&request_with_auth(*header), token) {
+                Err(ApiError::Unauthorized(_)) => {}
+                other => panic!("{:?} should be unauthorized, got {:?}", header, other),
+            }
+        }
+    }
+
+    #[test]
+    fn graffiti_path() {
+        let pubkey = Keypair::random().pk;
+        let path = format!("/eth/v1/validator/{:?}/graffiti", pubkey);
+
+        assert_eq!(
+            graffiti_path_pubkey(&path).map(parse_pubkey),
+            Some(Ok(pubkey))
+        );
+        assert_eq!(graffiti_path_pubkey("/eth/v1/validator//graffiti"), None);
+        assert_eq!(graffiti_path_pubkey(KEYSTORES_PATH), None);
+    }
+}
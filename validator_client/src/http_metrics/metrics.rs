@@ -0,0 +1,77 @@
+use crate::duties_service::DutyTiming;
+use slot_clock::SlotClock;
+
+pub use lighthouse_metrics::*;
+
+pub const SUCCESS: &str = "success";
+pub const FAILURE: &str = "failure";
+
+lazy_static! {
+    /*
+     * Duties
+     */
+    pub static ref DUTY_LATENESS: Result<HistogramVec> = try_create_histogram_vec(
+        "vc_duty_lateness_seconds",
+        "Time between the scheduled start of a duty and the validator client starting it",
+        &["duty"]
+    );
+    pub static ref ATTESTATION_PUBLISH_DELAY: Result<Histogram> = try_create_histogram(
+        "vc_attestation_publish_delay_seconds",
+        "Time between the start of the slot and an attestation being accepted by the beacon node"
+    );
+
+    pub static ref ATTESTATIONS_PUBLISHED: Result<IntCounter> = try_create_int_counter(
+        "vc_attestations_published_total",
+        "Count of attestations accepted by a beacon node"
+    );
+    pub static ref BLOCKS_PUBLISHED: Result<IntCounter> = try_create_int_counter(
+        "vc_blocks_published_total",
+        "Count of blocks accepted by a beacon node"
+    );
+
+    /*
+     * Signing
+     */
+    pub static ref SIGNING_TIMES: Result<HistogramVec> = try_create_histogram_vec(
+        "vc_signing_duration_seconds",
+        "Time taken to sign a message, including any request to a remote signer",
+        &["domain"]
+    );
+
+    /*
+     * Beacon nodes
+     */
+    pub static ref BEACON_NODE_REQUESTS: Result<IntCounterVec> = try_create_int_counter_vec(
+        "vc_beacon_node_requests_total",
+        "Count of requests to each beacon node, by outcome",
+        &["endpoint", "outcome"]
+    );
+    pub static ref BEACON_NODES_AVAILABLE: Result<IntGauge> = try_create_int_gauge(
+        "vc_beacon_nodes_available_count",
+        "Number of beacon nodes which passed their most recent health check"
+    );
+    pub static ref BEACON_NODES_TOTAL: Result<IntGauge> = try_create_int_gauge(
+        "vc_beacon_nodes_total_count",
+        "Total number of configured beacon nodes"
+    );
+
+    /*
+     * Validators
+     */
+    pub static ref VALIDATORS_TOTAL: Result<IntGauge> = try_create_int_gauge(
+        "vc_validators_total_count",
+        "Number of validators managed by the validator client"
+    );
+    pub static ref VALIDATOR_BALANCE: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "vc_validator_balance_gwei",
+        "Balance of each validator in the beacon node's head state, updated each epoch",
+        &["pubkey"]
+    );
+}
+
+/// Records how late the given `duty` is being started in the current slot.
+pub fn observe_duty_lateness<T: SlotClock>(duty: DutyTiming, slot_clock: &T) {
+    if let Some(lateness) = duty.lateness(slot_clock) {
+        observe_vec(&DUTY_LATENESS, &[duty.as_str()], lateness.as_secs_f64());
+    }
+}
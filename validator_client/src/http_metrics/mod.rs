@@ -0,0 +1,247 @@
+//! Provides an unauthenticated HTTP server which serves the validator client's Prometheus metrics
+//! at `GET /metrics`.
+//!
+//! The server also runs a task which fetches the balance of each validator from the beacon node
+//! once per epoch.
+pub mod metrics;
+
+use crate::beacon_node_fallback::BeaconNodeFallback;
+use crate::validator_store::ValidatorStore;
+use environment::RuntimeContext;
+use exit_future::Signal;
+use futures::{future, Future, Stream};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use metrics::{Encoder, TextEncoder};
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, debug, info, warn, Logger};
+use slot_clock::SlotClock;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::EthSpec;
+
+/// The path at which metrics are served.
+const METRICS_PATH: &str = "/metrics";
+
+/// Delay this period of time after the epoch starts before fetching validator balances. This
+/// allows the beacon node to process the epoch transition.
+const BALANCE_UPDATE_DELAY: Duration = Duration::from_secs(1);
+
+/// Metrics HTTP server configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the metrics HTTP server.
+    pub enabled: bool,
+    /// The IPv4 address the metrics HTTP server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the metrics HTTP server will listen on.
+    pub port: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5064,
+        }
+    }
+}
+
+/// The components which are read each time the metrics are scraped.
+struct Shared<T, E: EthSpec> {
+    validator_store: ValidatorStore<T, E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+    slot_clock: Arc<T>,
+}
+
+/// Starts the metrics HTTP server and the per-epoch validator balance updater.
+///
+/// The returned `Signal` stops both.
+pub fn start_server<T: SlotClock + 'static, E: EthSpec>(
+    config: &Config,
+    context: &RuntimeContext<E>,
+    validator_store: ValidatorStore<T, E>,
+    beacon_nodes: Arc<BeaconNodeFallback<T, E>>,
+    slot_clock: Arc<T>,
+) -> Result<(Signal, SocketAddr), String> {
+    let log = context.log.clone();
+    let shared = Arc::new(Shared {
+        validator_store,
+        beacon_nodes,
+        slot_clock,
+    });
+
+    // Define the function that will build the request handler.
+    let inner_shared = shared.clone();
+    let make_service = make_service_fn(move |_socket: &AddrStream| {
+        let shared = inner_shared.clone();
+
+        service_fn(move |req: Request<Body>| future::ok::<_, hyper::Error>(route(req, &shared)))
+    });
+
+    let bind_addr = (config.listen_address, config.port).into();
+    let server = Server::try_bind(&bind_addr)
+        .map_err(|e| format!("Unable to bind metrics server to {}: {:?}", bind_addr, e))?
+        .serve(make_service);
+
+    // Determine the address the server is actually listening on.
+    //
+    // This may be different to `bind_addr` if bind port was 0 (this allows the OS to choose a free
+    // port).
+    let actual_listen_addr = server.local_addr();
+
+    // Build a channel to kill the HTTP server and the balance updater.
+    let (exit_signal, exit) = exit_future::signal();
+    let inner_log = log.clone();
+    let server_exit = exit.clone().and_then(move |_| {
+        info!(inner_log, "Metrics HTTP server shutdown");
+        Ok(())
+    });
+    // Configure the `hyper` server to gracefully shutdown when the shutdown channel is triggered.
+    let inner_log = log.clone();
+    let server_future = server.with_graceful_shutdown(server_exit).map_err(
+        move |e| warn!(inner_log, "Metrics HTTP server failed"; "error" => format!("{:?}", e)),
+    );
+
+    info!(
+        log,
+        "Metrics HTTP server started";
+        "address" => format!("{}", actual_listen_addr.ip()),
+        "port" => actual_listen_addr.port(),
+    );
+
+    context.executor.spawn(server_future, "http_metrics");
+
+    let balance_updater = balance_updater(shared, log)?;
+    context.executor.spawn(
+        exit.until(balance_updater).map(|_| ()),
+        "metrics_balance_updater",
+    );
+
+    Ok((exit_signal, actual_listen_addr))
+}
+
+fn route<T: SlotClock + 'static, E: EthSpec>(
+    req: Request<Body>,
+    shared: &Shared<T, E>,
+) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, METRICS_PATH) => match gather_metrics(shared) {
+            Ok(body) => response(StatusCode::OK, body),
+            Err(e) => response(StatusCode::INTERNAL_SERVER_ERROR, e),
+        },
+        _ => response(StatusCode::NOT_FOUND, "Request path not found".to_string()),
+    }
+}
+
+fn response(status: StatusCode, body: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(body))
+        .expect("Response should always be created.")
+}
+
+/// Updates the metrics which are only read at the time of the scrape, then returns all metrics
+/// in the Prometheus text format.
+fn gather_metrics<T: SlotClock + 'static, E: EthSpec>(
+    shared: &Shared<T, E>,
+) -> Result<String, String> {
+    slot_clock::scrape_for_metrics::<E, T>(&shared.slot_clock);
+
+    metrics::set_gauge(
+        &metrics::VALIDATORS_TOTAL,
+        shared.validator_store.num_voting_validators() as i64,
+    );
+    metrics::set_gauge(
+        &metrics::BEACON_NODES_AVAILABLE,
+        shared.beacon_nodes.num_available() as i64,
+    );
+    metrics::set_gauge(
+        &metrics::BEACON_NODES_TOTAL,
+        shared.beacon_nodes.num_total() as i64,
+    );
+
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&metrics::gather(), &mut buffer)
+        .map_err(|e| format!("Unable to encode metrics: {:?}", e))?;
+
+    String::from_utf8(buffer).map_err(|e| format!("Unable to encode metrics as UTF-8: {:?}", e))
+}
+
+/// Returns a future which fetches the balances of all validators immediately and then shortly
+/// after the start of each epoch.
+fn balance_updater<T: SlotClock + 'static, E: EthSpec>(
+    shared: Arc<Shared<T, E>>,
+    log: Logger,
+) -> Result<impl Future<Item = (), Error = ()>, String> {
+    let duration_to_next_epoch = shared
+        .slot_clock
+        .duration_to_next_epoch(E::slots_per_epoch())
+        .ok_or_else(|| "Unable to determine duration to next epoch".to_string())?;
+    let epoch_duration = shared.slot_clock.slot_duration() * E::slots_per_epoch() as u32;
+
+    let interval = Interval::new(
+        Instant::now() + duration_to_next_epoch + BALANCE_UPDATE_DELAY,
+        epoch_duration,
+    );
+
+    let log_1 = log.clone();
+    let immediate_update = update_balances(shared.clone(), log.clone());
+
+    Ok(immediate_update.and_then(move |_| {
+        interval
+            .map_err(move |e| {
+                crit! {
+                    log_1,
+                    "Timer thread failed";
+                    "error" => format!("{}", e)
+                }
+            })
+            .for_each(move |_| update_balances(shared.clone(), log.clone()))
+    }))
+}
+
+/// Fetches the balance of each validator from the beacon nodes, updating the balance metrics.
+fn update_balances<T: SlotClock + 'static, E: EthSpec>(
+    shared: Arc<Shared<T, E>>,
+    log: Logger,
+) -> impl Future<Item = (), Error = ()> {
+    let pubkeys = shared.validator_store.voting_pubkeys();
+    let request_pubkeys = pubkeys.clone();
+
+    shared
+        .beacon_nodes
+        .first_success(move |beacon_node| {
+            beacon_node.http.beacon().get_validators(&request_pubkeys)
+        })
+        .map(move |validators| {
+            // The beacon node returns the validators in the same order as the request.
+            pubkeys
+                .iter()
+                .zip(validators.iter())
+                .for_each(|(pubkey, validator)| {
+                    if let Some(balance) = validator.balance {
+                        metrics::set_gauge_vec(
+                            &metrics::VALIDATOR_BALANCE,
+                            &[&format!("{:?}", pubkey)],
+                            balance as i64,
+                        );
+                    }
+                })
+        })
+        .or_else(move |e| {
+            debug!(
+                log,
+                "Unable to update validator balances";
+                "error" => e
+            );
+            Ok(())
+        })
+}
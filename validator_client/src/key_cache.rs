@@ -0,0 +1,164 @@
+//! An encrypted file which holds the voting keypairs of all local validators, so that the
+//! validator client may start by decrypting a single file with a single passphrase, rather than
+//! each EIP-2335 keystore with its own password.
+//!
+//! The cache holds the same secrets as the keystores, so it should be encrypted with the default
+//! (full-cost) scrypt parameters, and it is written with `600` permissions.
+use crate::validator_directory::create_with_600_perms;
+use eth2_keystore::{
+    decrypt, default_cipher, encrypt,
+    json_keystore::{Crypto, Kdf},
+    secret_key_from_bytes, secret_key_to_bytes, PlainText,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use types::{Keypair, PublicKey};
+
+/// The name of the key cache file, within the validators directory.
+pub const KEY_CACHE_FILE: &str = "key_cache.json";
+/// The only key cache version supported by this implementation.
+const KEY_CACHE_VERSION: u32 = 1;
+/// The byte-length of each secret key in the plain text of the cache.
+const SECRET_KEY_LEN: usize = 32;
+
+/// The JSON representation of the key cache file.
+#[derive(Serialize, Deserialize)]
+struct JsonKeyCache {
+    version: u32,
+    /// The concatenation of the 32-byte secret keys, encrypted as per the `crypto` section of an
+    /// EIP-2335 keystore.
+    crypto: Crypto,
+}
+
+/// The keypairs decrypted from a key cache file.
+#[derive(Default)]
+pub struct KeyCache {
+    keypairs: HashMap<PublicKey, Keypair>,
+}
+
+impl KeyCache {
+    /// Decrypts the key cache at `path` with `password`.
+    ///
+    /// Returns an empty cache if the file does not exist.
+    pub fn open(path: &Path, password: &[u8]) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file =
+            File::open(path).map_err(|e| format!("Unable to open key cache {:?}: {}", path, e))?;
+        let json: JsonKeyCache = serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse key cache {:?}: {}", path, e))?;
+
+        if json.version != KEY_CACHE_VERSION {
+            return Err(format!(
+                "Unsupported key cache version {} in {:?}",
+                json.version, path
+            ));
+        }
+
+        let plain_text = decrypt(password, &json.crypto)
+            .map_err(|e| format!("Unable to decrypt key cache {:?}: {:?}", path, e))?;
+
+        if plain_text.len() % SECRET_KEY_LEN != 0 {
+            return Err(format!("Invalid key cache length: {}", plain_text.len()));
+        }
+
+        let keypairs = plain_text
+            .as_bytes()
+            .chunks(SECRET_KEY_LEN)
+            .map(|bytes| {
+                let sk = secret_key_from_bytes(bytes)
+                    .map_err(|e| format!("Invalid secret key in key cache: {:?}", e))?;
+                let pk = PublicKey::from_secret_key(&sk);
+                Ok((pk.clone(), Keypair { sk, pk }))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { keypairs })
+    }
+
+    /// Encrypts `keypairs` with `password` and `kdf`, replacing any existing key cache at `path`.
+    pub fn save<'a, I>(path: &Path, password: &[u8], kdf: Kdf, keypairs: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = &'a Keypair>,
+    {
+        let keypairs = keypairs.into_iter().collect::<Vec<_>>();
+
+        let mut plain_text = PlainText::zero(keypairs.len() * SECRET_KEY_LEN);
+        for (keypair, chunk) in keypairs
+            .iter()
+            .zip(plain_text.as_mut_bytes().chunks_mut(SECRET_KEY_LEN))
+        {
+            let bytes = secret_key_to_bytes(&keypair.sk)
+                .map_err(|e| format!("Unable to encode secret key: {:?}", e))?;
+            chunk.copy_from_slice(bytes.as_bytes());
+        }
+
+        let json = JsonKeyCache {
+            version: KEY_CACHE_VERSION,
+            crypto: encrypt(plain_text.as_bytes(), password, kdf, default_cipher())
+                .map_err(|e| format!("Unable to encrypt key cache: {:?}", e))?,
+        };
+        let bytes =
+            serde_json::to_vec(&json).map_err(|e| format!("Unable to encode key cache: {}", e))?;
+
+        create_with_600_perms(path, &bytes)
+    }
+
+    /// Returns the keypair for `pubkey`, if it is in the cache.
+    pub fn get(&self, pubkey: &PublicKey) -> Option<Keypair> {
+        self.keypairs.get(pubkey).cloned()
+    }
+
+    /// Returns the number of keypairs in the cache.
+    pub fn len(&self) -> usize {
+        self.keypairs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_keystore::{
+        json_keystore::{Pbkdf2, PBKDF2_PRF},
+        DKLEN,
+    };
+    use tempdir::TempDir;
+
+    /// A cheap KDF, to avoid spending a long time running scrypt in tests.
+    fn insecure_kdf() -> Kdf {
+        Kdf::Pbkdf2(Pbkdf2 {
+            dklen: DKLEN,
+            c: 2,
+            prf: PBKDF2_PRF.to_string(),
+            salt: vec![1; 32].into(),
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        let temp_dir = TempDir::new("key_cache").expect("should create test dir");
+        let path = temp_dir.path().join(KEY_CACHE_FILE);
+        let keypairs = (0..4).map(|_| Keypair::random()).collect::<Vec<_>>();
+
+        let empty = KeyCache::open(&path, b"password").expect("should open missing cache");
+        assert_eq!(empty.len(), 0);
+
+        KeyCache::save(&path, b"password", insecure_kdf(), &keypairs).expect("should save");
+
+        let cache = KeyCache::open(&path, b"password").expect("should open cache");
+        assert_eq!(cache.len(), keypairs.len());
+        for keypair in &keypairs {
+            assert_eq!(cache.get(&keypair.pk).as_ref(), Some(keypair));
+        }
+        assert!(cache.get(&Keypair::random().pk).is_none());
+
+        assert!(
+            KeyCache::open(&path, b"wrong password").is_err(),
+            "should not open with the wrong password"
+        );
+    }
+}
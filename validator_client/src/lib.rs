@@ -1,21 +1,42 @@
+#[macro_use]
+extern crate lazy_static;
+
 mod attestation_service;
+mod beacon_node_fallback;
 mod block_service;
 mod cli;
 mod config;
+mod doppelganger_service;
 mod duties_service;
+mod duty_plugins;
 mod fork_service;
+mod graffiti_file;
+mod http_api;
+mod http_metrics;
+mod key_cache;
+mod monitoring_service;
 mod notifier;
+mod persisted_state;
+mod signing_method;
+mod slashing_protection;
+mod validator_settings;
 mod validator_store;
 
+pub mod exit;
 pub mod validator_directory;
 
 pub use cli::cli_app;
 pub use config::{Config, KeySource};
 
 use attestation_service::{AttestationService, AttestationServiceBuilder};
+use beacon_node_fallback::{
+    start_fallback_updater_service, BeaconNodeFallback, CandidateBeaconNode,
+};
 use block_service::{BlockService, BlockServiceBuilder};
 use clap::ArgMatches;
+use doppelganger_service::{DoppelgangerService, DoppelgangerServiceBuilder};
 use duties_service::{DutiesService, DutiesServiceBuilder};
+use duty_plugins::{DutyRegistry, RandaoPrecompute};
 use environment::RuntimeContext;
 use exit_future::Signal;
 use fork_service::{ForkService, ForkServiceBuilder};
@@ -23,11 +44,15 @@ use futures::{
     future::{self, loop_fn, Loop},
     Future, IntoFuture,
 };
+use graffiti_file::GraffitiFile;
+use monitoring_service::spawn_monitoring_service;
 use notifier::spawn_notifier;
+use persisted_state::{restore_from_disk, spawn_persistence_service};
 use remote_beacon_node::RemoteBeaconNode;
-use slog::{error, info, Logger};
+use slog::{error, info, warn, Logger};
 use slot_clock::SlotClock;
 use slot_clock::SystemTimeSlotClock;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::timer::Delay;
@@ -46,6 +71,10 @@ pub struct ProductionValidatorClient<T: EthSpec> {
     fork_service: ForkService<SystemTimeSlotClock, T>,
     block_service: BlockService<SystemTimeSlotClock, T>,
     attestation_service: AttestationService<SystemTimeSlotClock, T>,
+    beacon_nodes: Arc<BeaconNodeFallback<SystemTimeSlotClock, T>>,
+    doppelganger_service: Option<DoppelgangerService<SystemTimeSlotClock, T>>,
+    validator_store: ValidatorStore<SystemTimeSlotClock, T>,
+    config: Config,
     exit_signals: Vec<Signal>,
 }
 
@@ -76,31 +105,38 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
         info!(
             log_1,
             "Starting validator client";
-            "beacon_node" => &config.http_server,
+            "beacon_nodes" => format!("{:?}", config.beacon_node_endpoints()),
             "datadir" => format!("{:?}", config.data_dir),
         );
 
-        RemoteBeaconNode::new_with_timeout(config.http_server.clone(), HTTP_TIMEOUT)
+        config
+            .beacon_node_endpoints()
+            .into_iter()
+            .map(|endpoint| {
+                RemoteBeaconNode::new_with_timeout(endpoint.clone(), HTTP_TIMEOUT)
+                    .map(|beacon_node| CandidateBeaconNode::new(endpoint, beacon_node))
+            })
+            .collect::<Result<Vec<_>, _>>()
             .map_err(|e| format!("Unable to init beacon node http client: {}", e))
             .into_future()
-            .and_then(move |beacon_node| wait_for_node(beacon_node, log_2))
-            .and_then(|beacon_node| {
+            .and_then(move |candidates| wait_for_node(candidates, log_2))
+            .and_then(|(beacon_node, candidates)| {
                 beacon_node
                     .http
                     .spec()
                     .get_eth2_config()
-                    .map(|eth2_config| (beacon_node, eth2_config))
+                    .map(|eth2_config| (candidates, beacon_node, eth2_config))
                     .map_err(|e| format!("Unable to read eth2 config from beacon node: {:?}", e))
             })
-            .and_then(|(beacon_node, eth2_config)| {
+            .and_then(|(candidates, beacon_node, eth2_config)| {
                 beacon_node
                     .http
                     .beacon()
                     .get_genesis_time()
-                    .map(|genesis_time| (beacon_node, eth2_config, genesis_time))
+                    .map(|genesis_time| (candidates, eth2_config, genesis_time))
                     .map_err(|e| format!("Unable to read genesis time from beacon node: {:?}", e))
             })
-            .and_then(move |(beacon_node, remote_eth2_config, genesis_time)| {
+            .and_then(move |(candidates, remote_eth2_config, genesis_time)| {
                 SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .into_future()
@@ -128,7 +164,7 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                                     .map_err(|e| {
                                         format!("Unable to create genesis wait delay: {:?}", e)
                                     })
-                                    .map(move |_| (beacon_node, remote_eth2_config, genesis_time)),
+                                    .map(move |_| (candidates, remote_eth2_config, genesis_time)),
                             )
                         } else {
                             info!(
@@ -137,13 +173,13 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                                 "seconds_ago" => (now - genesis).as_secs()
                             );
 
-                            Box::new(future::ok((beacon_node, remote_eth2_config, genesis_time)))
+                            Box::new(future::ok((candidates, remote_eth2_config, genesis_time)))
                         };
 
                         box_future
                     })
             })
-            .and_then(move |(beacon_node, remote_eth2_config, genesis_time)| {
+            .and_then(move |(candidates, remote_eth2_config, genesis_time)| {
                 let log = log_4.clone();
 
                 // Do not permit a connection to a beacon node using different spec constants.
@@ -167,11 +203,18 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                     context.eth2_config.spec.genesis_slot,
                     Duration::from_secs(genesis_time),
                     Duration::from_millis(context.eth2_config.spec.milliseconds_per_slot),
-                );
+                )
+                .with_time_source(context.time_source.clone());
+
+                let beacon_nodes = Arc::new(BeaconNodeFallback::new(
+                    candidates,
+                    slot_clock.clone(),
+                    context.service_context("fallback".into()).log,
+                ));
 
                 let fork_service = ForkServiceBuilder::new()
                     .slot_clock(slot_clock.clone())
-                    .beacon_node(beacon_node.clone())
+                    .beacon_nodes(beacon_nodes.clone())
                     .runtime_context(context.service_context("fork".into()))
                     .build()?;
 
@@ -181,7 +224,7 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                         //
                         // Use the `account_manager` to generate these files.
                         KeySource::Disk => ValidatorStore::load_from_disk(
-                            config.data_dir.clone(),
+                            &config,
                             context.eth2_config.spec.clone(),
                             fork_service.clone(),
                             log.clone(),
@@ -190,6 +233,12 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                         //
                         // Do not use in production.
                         KeySource::InsecureKeypairs(indices) => {
+                            warn!(
+                                log,
+                                "Using insecure interop keypairs";
+                                "info" => "only use for testing",
+                                "validators" => indices.len(),
+                            );
                             ValidatorStore::insecure_ephemeral_validators(
                                 &indices,
                                 context.eth2_config.spec.clone(),
@@ -205,41 +254,99 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
                     "voting_validators" => validator_store.num_voting_validators()
                 );
 
+                let mut duty_registry = DutyRegistry::default();
+                duty_registry.register(Arc::new(RandaoPrecompute))?;
+
                 let duties_service = DutiesServiceBuilder::new()
                     .slot_clock(slot_clock.clone())
                     .validator_store(validator_store.clone())
-                    .beacon_node(beacon_node.clone())
+                    .beacon_nodes(beacon_nodes.clone())
                     .runtime_context(context.service_context("duties".into()))
+                    .duty_registry(duty_registry)
                     .build()?;
 
+                let graffiti_file = config
+                    .graffiti_file
+                    .clone()
+                    .map(GraffitiFile::new)
+                    .transpose()?;
+
                 let block_service = BlockServiceBuilder::new()
                     .duties_service(duties_service.clone())
                     .slot_clock(slot_clock.clone())
                     .validator_store(validator_store.clone())
-                    .beacon_node(beacon_node.clone())
+                    .beacon_nodes(beacon_nodes.clone())
                     .runtime_context(context.service_context("block".into()))
+                    .graffiti(config.graffiti.clone())
+                    .graffiti_file(graffiti_file)
                     .build()?;
 
+                let doppelganger_service = if config.enable_doppelganger_protection {
+                    // Disable signing immediately, before any other service is started.
+                    validator_store.set_signing_enabled(false);
+
+                    Some(
+                        DoppelgangerServiceBuilder::new()
+                            .slot_clock(slot_clock.clone())
+                            .validator_store(validator_store.clone())
+                            .beacon_nodes(beacon_nodes.clone())
+                            .runtime_context(context.service_context("doppelganger".into()))
+                            .build()?,
+                    )
+                } else {
+                    None
+                };
+
                 let attestation_service = AttestationServiceBuilder::new()
                     .duties_service(duties_service.clone())
                     .slot_clock(slot_clock)
-                    .validator_store(validator_store)
-                    .beacon_node(beacon_node)
+                    .validator_store(validator_store.clone())
+                    .beacon_nodes(beacon_nodes.clone())
                     .runtime_context(context.service_context("attestation".into()))
                     .build()?;
 
+                // Restore the duties and statistics persisted before the last shutdown, so that
+                // duties may be performed before the first duties poll succeeds.
+                restore_from_disk(
+                    &config.data_dir,
+                    &duties_service,
+                    &validator_store,
+                    &beacon_nodes,
+                    &log,
+                );
+
                 Ok(Self {
                     context,
                     duties_service,
                     fork_service,
                     block_service,
                     attestation_service,
+                    beacon_nodes,
+                    doppelganger_service,
+                    validator_store,
+                    config,
                     exit_signals: vec![],
                 })
             })
     }
 
     pub fn start_service(&mut self) -> Result<(), String> {
+        let fallback_exit = start_fallback_updater_service(
+            self.context.service_context("fallback".into()),
+            self.beacon_nodes.clone(),
+        )
+        .map_err(|e| format!("Unable to start beacon node fallback service: {}", e))?;
+
+        let doppelganger_exit = self
+            .doppelganger_service
+            .as_ref()
+            .map(|service| {
+                service
+                    .start_update_service(&self.context.eth2_config.spec)
+                    .map_err(|e| format!("Unable to start doppelganger service: {}", e))
+            })
+            .transpose()?;
+
         let duties_exit = self
             .duties_service
             .start_update_service(&self.context.eth2_config.spec)
@@ -263,62 +370,133 @@ impl<T: EthSpec> ProductionValidatorClient<T> {
         let notifier_exit =
             spawn_notifier(self).map_err(|e| format!("Failed to start notifier: {}", e))?;
 
-        self.exit_signals = vec![
+        let persistence_exit = spawn_persistence_service(self, self.config.data_dir.clone())
+            .map_err(|e| format!("Failed to start persistence service: {}", e))?;
+
+        let http_api_exit = if self.config.http_api.enabled {
+            let (exit_signal, _listen_addr) = http_api::start_server(
+                &self.config.http_api,
+                &self.context.service_context("http_api".into()),
+                self.validator_store.clone(),
+                self.duties_service.clone(),
+                self.beacon_nodes.clone(),
+                &self.config.data_dir,
+            )
+            .map_err(|e| format!("Unable to start HTTP API: {}", e))?;
+
+            Some(exit_signal)
+        } else {
+            None
+        };
+
+        let http_metrics_exit = if self.config.http_metrics.enabled {
+            let (exit_signal, _listen_addr) = http_metrics::start_server(
+                &self.config.http_metrics,
+                &self.context.service_context("http_metrics".into()),
+                self.validator_store.clone(),
+                self.beacon_nodes.clone(),
+                Arc::new(self.duties_service.slot_clock.clone()),
+            )
+            .map_err(|e| format!("Unable to start metrics HTTP server: {}", e))?;
+
+            Some(exit_signal)
+        } else {
+            None
+        };
+
+        let monitoring_exit = if self.config.monitoring_endpoint.is_some() {
+            Some(
+                spawn_monitoring_service(self)
+                    .map_err(|e| format!("Failed to start monitoring service: {}", e))?,
+            )
+        } else {
+            None
+        };
+
+        self.exit_signals.extend(doppelganger_exit);
+        self.exit_signals.extend(http_api_exit);
+        self.exit_signals.extend(http_metrics_exit);
+        self.exit_signals.extend(monitoring_exit);
+        self.exit_signals.extend(vec![
+            fallback_exit,
             duties_exit,
             fork_exit,
             block_exit,
             attestation_exit,
             notifier_exit,
-        ];
+            persistence_exit,
+        ]);
 
         Ok(())
     }
 }
 
-/// Request the version from the node, looping back and trying again on failure. Exit once the node
-/// has been contacted.
+/// Request the version from each of the `candidates` in turn, looping back and trying again on
+/// failure. Exit once any node has been contacted, returning that node along with all candidates.
 fn wait_for_node<E: EthSpec>(
-    beacon_node: RemoteBeaconNode<E>,
+    candidates: Vec<CandidateBeaconNode<E>>,
     log: Logger,
-) -> impl Future<Item = RemoteBeaconNode<E>, Error = String> {
-    // Try to get the version string from the node, looping until success is returned.
-    loop_fn(beacon_node.clone(), move |beacon_node| {
+) -> impl Future<Item = (RemoteBeaconNode<E>, Vec<CandidateBeaconNode<E>>), Error = String> {
+    // Try to get the version string from each node, looping until success is returned.
+    loop_fn((candidates, 0), move |(candidates, index)| {
         let log = log.clone();
-        beacon_node
-            .clone()
-            .http
-            .node()
-            .get_version()
-            .map_err(|e| format!("{:?}", e))
-            .then(move |result| {
-                let future: Box<dyn Future<Item = Loop<_, _>, Error = String> + Send> = match result
-                {
-                    Ok(version) => {
-                        info!(
-                            log,
-                            "Connected to beacon node";
-                            "version" => version,
-                        );
-
-                        Box::new(future::ok(Loop::Break(beacon_node)))
-                    }
-                    Err(e) => {
-                        error!(
-                            log,
-                            "Unable to connect to beacon node";
-                            "error" => format!("{:?}", e),
-                        );
-
-                        Box::new(
-                            Delay::new(Instant::now() + RETRY_DELAY)
-                                .map_err(|e| format!("Failed to trigger delay: {:?}", e))
-                                .and_then(|_| future::ok(Loop::Continue(beacon_node))),
-                        )
-                    }
-                };
 
-                future
-            })
+        let future: Box<dyn Future<Item = Loop<_, _>, Error = String> + Send> = match candidates
+            .get(index % candidates.len().max(1))
+        {
+            Some(candidate) => {
+                let endpoint = candidate.endpoint().to_string();
+                let beacon_node = candidate.beacon_node.clone();
+
+                Box::new(
+                    beacon_node
+                        .http
+                        .node()
+                        .get_version()
+                        .map_err(|e| format!("{:?}", e))
+                        .then(move |result| {
+                            let future: Box<dyn Future<Item = Loop<_, _>, Error = String> + Send> =
+                                match result {
+                                    Ok(version) => {
+                                        info!(
+                                            log,
+                                            "Connected to beacon node";
+                                            "version" => version,
+                                            "endpoint" => endpoint,
+                                        );
+
+                                        Box::new(future::ok(Loop::Break((beacon_node, candidates))))
+                                    }
+                                    Err(e) => {
+                                        error!(
+                                            log,
+                                            "Unable to connect to beacon node";
+                                            "error" => format!("{:?}", e),
+                                            "endpoint" => endpoint,
+                                        );
+
+                                        Box::new(
+                                            Delay::new(Instant::now() + RETRY_DELAY)
+                                                .map_err(|e| {
+                                                    format!("Failed to trigger delay: {:?}", e)
+                                                })
+                                                .and_then(move |_| {
+                                                    future::ok(Loop::Continue((
+                                                        candidates,
+                                                        index + 1,
+                                                    )))
+                                                }),
+                                        )
+                                    }
+                                };
+
+                            future
+                        }),
+                )
+            }
+            None => Box::new(future::err("No beacon nodes configured".to_string())),
+        };
+
+        future
     })
-    .map(|_| beacon_node)
 }
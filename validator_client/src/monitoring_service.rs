@@ -0,0 +1,135 @@
+use crate::http_metrics::metrics;
+use crate::ProductionValidatorClient;
+use environment::HostStats;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use reqwest::{r#async::ClientBuilder, Url};
+use serde_derive::Serialize;
+use slog::{debug, error, info, warn};
+use slot_clock::SlotClock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::timer::Interval;
+use types::{Epoch, EthSpec};
+
+/// The interval between the summaries sent to the monitoring endpoint.
+pub const MONITORING_INTERVAL: Duration = Duration::from_secs(60);
+/// The timeout of each request to the monitoring endpoint.
+const MONITORING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The summary of the state of the validator client which is sent to the monitoring endpoint.
+#[derive(Debug, Serialize)]
+pub struct ValidatorClientSummary {
+    /// Always `validator_client`, to distinguish this summary from those of beacon nodes.
+    pub process: &'static str,
+    /// The time at which the summary was taken, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub epoch: Option<Epoch>,
+    pub validators_total: usize,
+    /// The number of validators with an attestation duty in `epoch`.
+    pub validators_active: usize,
+    /// The number of validators with a block proposal duty in `epoch`.
+    pub validators_proposing: usize,
+    /// The number of attestations accepted by a beacon node since the validator client started.
+    pub attestations_published: Option<u64>,
+    /// The number of blocks accepted by a beacon node since the validator client started.
+    pub blocks_published: Option<u64>,
+    pub beacon_nodes_available: usize,
+    pub beacon_nodes_total: usize,
+    pub load_average_1m: Option<f64>,
+    pub memory_total_bytes: Option<u64>,
+    pub memory_available_bytes: Option<u64>,
+    pub process_resident_memory_bytes: Option<u64>,
+}
+
+/// Spawns a service which sends a `ValidatorClientSummary` to the `monitoring_endpoint` of the
+/// client with a `POST` request every `MONITORING_INTERVAL`.
+///
+/// A failed request is logged and not retried; the next summary is sent at the next interval.
+pub fn spawn_monitoring_service<T: EthSpec>(
+    client: &ProductionValidatorClient<T>,
+) -> Result<Signal, String> {
+    let context = client.context.service_context("monitoring".into());
+
+    let endpoint = client
+        .config
+        .monitoring_endpoint
+        .as_ref()
+        .ok_or_else(|| "monitoring service requires a monitoring endpoint")?;
+    let endpoint = Url::parse(endpoint)
+        .map_err(|e| format!("Invalid monitoring endpoint {}: {}", endpoint, e))?;
+    let http = ClientBuilder::new()
+        .timeout(MONITORING_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+
+    let duties_service = client.duties_service.clone();
+    let beacon_nodes = client.beacon_nodes.clone();
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+
+    info!(
+        context.log,
+        "Monitoring enabled";
+        "interval_secs" => MONITORING_INTERVAL.as_secs(),
+        "endpoint" => endpoint.as_str(),
+    );
+
+    let interval_future = Interval::new(Instant::now() + MONITORING_INTERVAL, MONITORING_INTERVAL)
+        .map_err(move |e| error!(log_1, "Monitoring timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let epoch = duties_service
+                .slot_clock
+                .now()
+                .map(|slot| slot.epoch(T::slots_per_epoch()));
+            let host = HostStats::observe();
+            let summary = ValidatorClientSummary {
+                process: "validator_client",
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0),
+                epoch,
+                validators_total: duties_service.total_validator_count(),
+                validators_active: epoch.map_or(0, |epoch| duties_service.attester_count(epoch)),
+                validators_proposing: epoch.map_or(0, |epoch| duties_service.proposer_count(epoch)),
+                attestations_published: counter_value(&metrics::ATTESTATIONS_PUBLISHED),
+                blocks_published: counter_value(&metrics::BLOCKS_PUBLISHED),
+                beacon_nodes_available: beacon_nodes.num_available(),
+                beacon_nodes_total: beacon_nodes.num_total(),
+                load_average_1m: host.load_average_1m,
+                memory_total_bytes: host.memory_total_bytes,
+                memory_available_bytes: host.memory_available_bytes,
+                process_resident_memory_bytes: host.process_resident_memory_bytes,
+            };
+
+            let log = log_2.clone();
+            http.post(endpoint.clone())
+                .json(&summary)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .then(move |result| {
+                    match result {
+                        Ok(_) => debug!(log, "Sent monitoring summary"),
+                        Err(e) => warn!(
+                            log,
+                            "Unable to send monitoring summary";
+                            "error" => format!("{}", e),
+                        ),
+                    }
+                    Ok(())
+                })
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    client
+        .context
+        .executor
+        .spawn(exit.until(interval_future).map(|_| ()), "monitoring");
+
+    Ok(exit_signal)
+}
+
+/// Returns the value of `counter`, or `None` if it could not be registered.
+fn counter_value(counter: &metrics::Result<metrics::IntCounter>) -> Option<u64> {
+    counter.as_ref().ok().map(|counter| counter.get() as u64)
+}
@@ -1,7 +1,7 @@
 use crate::ProductionValidatorClient;
 use exit_future::Signal;
 use futures::{Future, Stream};
-use slog::{error, info};
+use slog::{error, info, warn};
 use slot_clock::SlotClock;
 use std::time::{Duration, Instant};
 use tokio::timer::Interval;
@@ -25,6 +25,7 @@ pub fn spawn_notifier<T: EthSpec>(client: &ProductionValidatorClient<T>) -> Resu
     let interval_duration = slot_duration;
 
     let duties_service = client.duties_service.clone();
+    let beacon_nodes = client.beacon_nodes.clone();
     let log_1 = context.log.clone();
     let log_2 = context.log.clone();
 
@@ -35,6 +36,29 @@ pub fn spawn_notifier<T: EthSpec>(client: &ProductionValidatorClient<T>) -> Resu
         .for_each(move |_| {
             let log = log_2.clone();
 
+            let available_nodes = beacon_nodes.num_available();
+            if available_nodes == 0 {
+                error!(
+                    log,
+                    "No synced beacon nodes";
+                    "total" => beacon_nodes.num_total(),
+                );
+            } else if available_nodes < beacon_nodes.num_total() {
+                for health in beacon_nodes
+                    .health()
+                    .into_iter()
+                    .filter(|health| health.status.is_err())
+                {
+                    warn!(
+                        log,
+                        "Beacon node unavailable";
+                        "endpoint" => health.endpoint,
+                        "status" => format!("{:?}", health.status),
+                        "failed_requests" => health.failures,
+                    );
+                }
+            }
+
             if let Some(slot) = duties_service.slot_clock.now() {
                 let epoch = slot.epoch(T::slots_per_epoch());
 
@@ -85,6 +109,7 @@ pub fn spawn_notifier<T: EthSpec>(client: &ProductionValidatorClient<T>) -> Resu
     client.context.executor.spawn(
         exit.until(interval_future)
             .map(move |_| info!(log, "Shutdown complete")),
+        "notifier",
     );
 
     Ok(exit_signal)
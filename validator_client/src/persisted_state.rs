@@ -0,0 +1,310 @@
+//! Persists the state which the validator client otherwise learns over time (its duties, the
+//! messages signed by each validator and the health of each beacon node) in its datadir.
+//!
+//! The state is restored at startup, so that a restart mid-epoch can perform duties immediately
+//! rather than waiting for the first duties poll to succeed, and so that statistics survive
+//! restarts.
+use crate::beacon_node_fallback::{BeaconNodeFallback, CandidateError};
+use crate::duties_service::{DutiesService, ValidatorDuty};
+use crate::validator_store::ValidatorStore;
+use crate::ProductionValidatorClient;
+use exit_future::Signal;
+use futures::{Future, Stream};
+use serde_derive::{Deserialize, Serialize};
+use slog::{error, info, warn, Logger};
+use slot_clock::SlotClock;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+use types::{Epoch, EthSpec, PublicKey, Slot};
+
+/// The name of the file in the datadir which stores the state of the validator client.
+pub const PERSISTED_STATE_FILE: &str = "validator_client_state.json";
+
+/// The duties of a single validator for `epoch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistedDuty {
+    pub epoch: Epoch,
+    #[serde(flatten)]
+    pub duties: ValidatorDuty,
+}
+
+/// The latest messages signed by a single validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastSigned {
+    pub pubkey: PublicKey,
+    /// The slot of the latest block signed by the validator.
+    pub block_slot: Option<Slot>,
+    /// The target epoch of the latest attestation signed by the validator.
+    pub attestation_target_epoch: Option<Epoch>,
+}
+
+/// The health of a single beacon node, as of its most recent health check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistedBeaconNode {
+    pub endpoint: String,
+    pub synced: bool,
+    pub successes: usize,
+    pub failures: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    #[serde(default)]
+    pub duties: Vec<PersistedDuty>,
+    #[serde(default)]
+    pub last_signed: Vec<LastSigned>,
+    #[serde(default)]
+    pub beacon_nodes: Vec<PersistedBeaconNode>,
+}
+
+impl PersistedState {
+    /// Reads the state of the given services.
+    pub fn from_services<T: SlotClock + 'static, E: EthSpec>(
+        duties_service: &DutiesService<T, E>,
+        validator_store: &ValidatorStore<T, E>,
+        beacon_nodes: &BeaconNodeFallback<T, E>,
+    ) -> Self {
+        Self {
+            duties: duties_service
+                .upcoming_duties()
+                .into_iter()
+                .map(|(epoch, duties)| PersistedDuty { epoch, duties })
+                .collect(),
+            last_signed: validator_store.last_signed(),
+            beacon_nodes: beacon_nodes
+                .health()
+                .into_iter()
+                .map(|health| PersistedBeaconNode {
+                    endpoint: health.endpoint,
+                    synced: health.status.is_ok(),
+                    successes: health.successes,
+                    failures: health.failures,
+                })
+                .collect(),
+        }
+    }
+
+    /// Restores the state into the given services.
+    ///
+    /// Duties are only restored for validators which are still voting, in the current and later
+    /// epochs.
+    pub fn restore<T: SlotClock + 'static, E: EthSpec>(
+        self,
+        duties_service: &DutiesService<T, E>,
+        validator_store: &ValidatorStore<T, E>,
+        beacon_nodes: &BeaconNodeFallback<T, E>,
+    ) {
+        duties_service.restore_duties(
+            self.duties
+                .into_iter()
+                .map(|persisted| (persisted.epoch, persisted.duties))
+                .collect(),
+        );
+        validator_store.restore_last_signed(self.last_signed);
+
+        for node in self.beacon_nodes {
+            let status = if node.synced {
+                Ok(())
+            } else {
+                Err(CandidateError::NotSynced)
+            };
+            beacon_nodes.restore_health(&node.endpoint, status, node.successes, node.failures);
+        }
+    }
+
+    /// Loads the state stored in `directory`, returning the default state if none exists.
+    pub fn load(directory: &Path) -> Result<Self, String> {
+        let path = directory.join(PERSISTED_STATE_FILE);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = File::open(&path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+        serde_json::from_reader(file).map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+    }
+
+    /// Writes the state into `directory`, replacing any existing state.
+    pub fn save(&self, directory: &Path) -> Result<(), String> {
+        let path = directory.join(PERSISTED_STATE_FILE);
+        let temp_path = path.with_extension("tmp");
+
+        let file = File::create(&temp_path)
+            .map_err(|e| format!("Unable to create {:?}: {}", temp_path, e))?;
+        serde_json::to_writer(file, self)
+            .map_err(|e| format!("Unable to write validator client state: {}", e))?;
+
+        fs::rename(&temp_path, &path).map_err(|e| format!("Unable to save {:?}: {}", path, e))
+    }
+}
+
+/// Saves the state of the given services into `data_dir`, logging any error.
+fn persist<T: SlotClock + 'static, E: EthSpec>(
+    duties_service: &DutiesService<T, E>,
+    validator_store: &ValidatorStore<T, E>,
+    beacon_nodes: &BeaconNodeFallback<T, E>,
+    data_dir: &Path,
+    log: &Logger,
+) {
+    let state = PersistedState::from_services(duties_service, validator_store, beacon_nodes);
+
+    if let Err(e) = state.save(data_dir) {
+        error!(
+            log,
+            "Failed to persist validator client state";
+            "error" => e
+        )
+    }
+}
+
+/// Spawns a service which saves the state of the validator client once per epoch, and again at
+/// shutdown.
+pub fn spawn_persistence_service<E: EthSpec>(
+    client: &ProductionValidatorClient<E>,
+    data_dir: PathBuf,
+) -> Result<Signal, String> {
+    let context = client.context.service_context("persistence".into());
+
+    let slot_duration = Duration::from_millis(context.eth2_config.spec.milliseconds_per_slot);
+    let duration_to_next_slot = client
+        .duties_service
+        .slot_clock
+        .duration_to_next_slot()
+        .ok_or_else(|| "persistence service unable to determine time to next slot")?;
+
+    // Run this three quarters of the way through each slot, after the slot's duties are done.
+    let start_instant = Instant::now() + duration_to_next_slot + (slot_duration * 3 / 4);
+
+    let duties_service = client.duties_service.clone();
+    let validator_store = client.validator_store.clone();
+    let beacon_nodes = client.beacon_nodes.clone();
+    let log_1 = context.log.clone();
+    let log_2 = context.log.clone();
+
+    let shutdown_duties_service = duties_service.clone();
+    let shutdown_validator_store = validator_store.clone();
+    let shutdown_beacon_nodes = beacon_nodes.clone();
+    let shutdown_data_dir = data_dir.clone();
+    let shutdown_log = context.log.clone();
+
+    let interval_future = Interval::new(start_instant, slot_duration)
+        .map_err(move |e| error!(log_1, "Persistence timer failed"; "error" => format!("{:?}", e)))
+        .for_each(move |_| {
+            let is_last_slot_of_epoch = duties_service
+                .slot_clock
+                .now()
+                .map_or(false, |slot| (slot + 1) % E::slots_per_epoch() == 0);
+
+            // Duties are persisted before the epoch ends, so that they may be restored if the
+            // validator client restarts during the next epoch.
+            if is_last_slot_of_epoch {
+                persist(
+                    &duties_service,
+                    &validator_store,
+                    &beacon_nodes,
+                    &data_dir,
+                    &log_2,
+                );
+            }
+
+            Ok(())
+        });
+
+    let (exit_signal, exit) = exit_future::signal();
+    client.context.executor.spawn(
+        exit.until(interval_future).map(move |_| {
+            persist(
+                &shutdown_duties_service,
+                &shutdown_validator_store,
+                &shutdown_beacon_nodes,
+                &shutdown_data_dir,
+                &shutdown_log,
+            );
+            info!(shutdown_log, "Shutdown complete")
+        }),
+        "persistence",
+    );
+
+    Ok(exit_signal)
+}
+
+/// Loads the state stored in `data_dir` into the given services, logging the outcome.
+///
+/// A missing or corrupt file is not fatal: the state is learned again over time.
+pub fn restore_from_disk<T: SlotClock + 'static, E: EthSpec>(
+    data_dir: &Path,
+    duties_service: &DutiesService<T, E>,
+    validator_store: &ValidatorStore<T, E>,
+    beacon_nodes: &BeaconNodeFallback<T, E>,
+    log: &Logger,
+) {
+    match PersistedState::load(data_dir) {
+        Ok(state) => {
+            info!(
+                log,
+                "Restored validator client state";
+                "duties" => state.duties.len(),
+                "validators_with_history" => state.last_signed.len(),
+            );
+            state.restore(duties_service, validator_store, beacon_nodes);
+        }
+        Err(e) => warn!(
+            log,
+            "Unable to restore validator client state";
+            "error" => e,
+            "info" => "Duties will be fetched from the beacon node."
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+    use types::{Hash256, Keypair};
+
+    #[test]
+    fn state_round_trip() {
+        let temp_dir = TempDir::new("persisted_state").expect("should create test dir");
+
+        assert_eq!(
+            PersistedState::load(temp_dir.path()),
+            Ok(PersistedState::default()),
+            "should load defaults when no file exists"
+        );
+
+        let pubkey = Keypair::random().pk;
+        let state = PersistedState {
+            duties: vec![PersistedDuty {
+                epoch: Epoch::new(3),
+                duties: ValidatorDuty {
+                    validator_pubkey: pubkey.clone(),
+                    validator_index: Some(7),
+                    attestation_slot: Some(Slot::new(25)),
+                    attestation_committee_index: Some(1),
+                    attestation_committee_position: Some(2),
+                    aggregator_modulo: Some(1),
+                    selection_proof: None,
+                    block_proposal_slots: vec![Slot::new(26)],
+                    dependent_root: Some(Hash256::from_low_u64_be(1)),
+                },
+            }],
+            last_signed: vec![LastSigned {
+                pubkey,
+                block_slot: Some(Slot::new(20)),
+                attestation_target_epoch: Some(Epoch::new(3)),
+            }],
+            beacon_nodes: vec![PersistedBeaconNode {
+                endpoint: "http://localhost:5052".into(),
+                synced: true,
+                successes: 10,
+                failures: 1,
+            }],
+        };
+        state.save(temp_dir.path()).expect("should save");
+
+        assert_eq!(PersistedState::load(temp_dir.path()), Ok(state));
+    }
+}
@@ -0,0 +1,299 @@
+//! Provides the `SignableProvider` trait, which abstracts the production of BLS signatures away
+//! from the `ValidatorStore`.
+//!
+//! Two providers are implemented:
+//!
+//! - `LocalKeypair`: signs with a secret key held in memory.
+//! - `RemoteSigner`: requests signatures from a remote HTTP service so that secret keys may be kept
+//!   off the validator host.
+use serde_derive::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use types::{ChainSpec, Domain, Epoch, Fork, Keypair, PublicKey, Signature};
+
+/// The default timeout for requests to a remote signer.
+pub const DEFAULT_REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The remote signer could not be contacted, or returned an error.
+    RemoteSigner(String),
+    /// The remote signer returned a signature that does not verify against the validator's pubkey.
+    InvalidRemoteSignature,
+}
+
+/// The information required to compute the signing domain for some message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningContext {
+    pub domain: Domain,
+    pub epoch: Epoch,
+    pub fork: Fork,
+}
+
+impl SigningContext {
+    /// Returns the domain for the message, as used for signing.
+    pub fn domain(&self, spec: &ChainSpec) -> u64 {
+        spec.get_domain(self.epoch, self.domain, &self.fork)
+    }
+}
+
+/// Produces a signature across a `message` for a single validator.
+pub trait SignableProvider: Send + Sync {
+    /// Sign `message` (typically a tree hash root) with the given `context`.
+    fn sign(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+        spec: &ChainSpec,
+    ) -> Result<Signature, Error>;
+}
+
+/// Signs messages with a keypair that is held in memory.
+pub struct LocalKeypair {
+    keypair: Keypair,
+}
+
+impl LocalKeypair {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+}
+
+impl SignableProvider for LocalKeypair {
+    fn sign(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+        spec: &ChainSpec,
+    ) -> Result<Signature, Error> {
+        Ok(Signature::new(
+            message,
+            context.domain(spec),
+            &self.keypair.sk,
+        ))
+    }
+}
+
+/// Describes a single validator which should be signed for by a remote signer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSignerDefinition {
+    /// The voting public key of the validator.
+    pub pubkey: PublicKey,
+    /// The URL to which signing requests for this validator should be sent.
+    pub url: String,
+}
+
+/// The configuration for all remote signers used by the validator client.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RemoteSignersConfig {
+    pub signers: Vec<RemoteSignerDefinition>,
+    /// The timeout for each signing request, in milliseconds.
+    #[serde(default)]
+    pub timeout_millis: Option<u64>,
+    /// If `true`, a validator which also has a local keypair will use it to sign whenever the
+    /// remote signer fails.
+    #[serde(default)]
+    pub fallback_to_local: bool,
+}
+
+impl RemoteSignersConfig {
+    /// Loads the configuration from the JSON file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Unable to open remote signers file {:?}: {}", path, e))?;
+        serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse remote signers file {:?}: {}", path, e))
+    }
+
+    /// Writes the configuration to the JSON file at `path`, replacing any existing file.
+    pub fn to_file(&self, path: &Path) -> Result<(), String> {
+        let temp_path = path.with_extension("tmp");
+
+        let file = File::create(&temp_path)
+            .map_err(|e| format!("Unable to create {:?}: {}", temp_path, e))?;
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|e| format!("Unable to write remote signers file: {}", e))?;
+
+        fs::rename(&temp_path, path)
+            .map_err(|e| format!("Unable to save remote signers file {:?}: {}", path, e))
+    }
+
+    /// Returns the configured timeout, or the default if none was supplied.
+    pub fn timeout(&self) -> Duration {
+        self.timeout_millis
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_REMOTE_SIGNER_TIMEOUT)
+    }
+
+    /// Returns the signer definition for `pubkey`, if any.
+    pub fn get(&self, pubkey: &PublicKey) -> Option<&RemoteSignerDefinition> {
+        self.signers.iter().find(|signer| signer.pubkey == *pubkey)
+    }
+}
+
+/// The body of a request sent to a remote signer.
+#[derive(Debug, Serialize)]
+struct SigningRequest<'a> {
+    pubkey: &'a PublicKey,
+    /// The 0x-prefixed, hex-encoded message (signing root).
+    signing_root: String,
+    /// The name of the signature domain, e.g., `beacon_proposer`.
+    domain_type: &'static str,
+    /// The 0x-prefixed, hex-encoded (little-endian) domain, including the fork version.
+    domain: String,
+    epoch: Epoch,
+    fork: &'a Fork,
+}
+
+/// The body of a successful response from a remote signer.
+#[derive(Debug, Deserialize)]
+struct SigningResponse {
+    signature: Signature,
+}
+
+/// Signs messages by sending them to a remote HTTP service.
+///
+/// The request is blocking, so a short `timeout` should be used to avoid delaying other duties.
+pub struct RemoteSigner {
+    url: String,
+    pubkey: PublicKey,
+    client: reqwest::Client,
+    fallback: Option<Arc<dyn SignableProvider>>,
+}
+
+impl RemoteSigner {
+    /// Creates a new signer for the validator with `pubkey` which sends requests to `url`.
+    ///
+    /// If `fallback` is supplied, it will be used to sign messages whenever the remote signer
+    /// fails.
+    pub fn new(
+        url: String,
+        pubkey: PublicKey,
+        timeout: Duration,
+        fallback: Option<Arc<dyn SignableProvider>>,
+    ) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| format!("Unable to build remote signer client: {:?}", e))?;
+
+        Ok(Self {
+            url,
+            pubkey,
+            client,
+            fallback,
+        })
+    }
+
+    fn request_signature(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+        spec: &ChainSpec,
+    ) -> Result<Signature, Error> {
+        let request = SigningRequest {
+            pubkey: &self.pubkey,
+            signing_root: format!("0x{}", hex::encode(message)),
+            domain_type: domain_type(context.domain),
+            domain: format!("0x{}", hex::encode(context.domain(spec).to_le_bytes())),
+            epoch: context.epoch,
+            fork: &context.fork,
+        };
+
+        let response: SigningResponse = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json())
+            .map_err(|e| Error::RemoteSigner(format!("{:?}", e)))?;
+
+        // Never trust the remote signer to have signed the correct message.
+        if response
+            .signature
+            .verify(message, context.domain(spec), &self.pubkey)
+        {
+            Ok(response.signature)
+        } else {
+            Err(Error::InvalidRemoteSignature)
+        }
+    }
+}
+
+impl SignableProvider for RemoteSigner {
+    fn sign(
+        &self,
+        message: &[u8],
+        context: &SigningContext,
+        spec: &ChainSpec,
+    ) -> Result<Signature, Error> {
+        match (
+            self.request_signature(message, context, spec),
+            &self.fallback,
+        ) {
+            (Err(_), Some(fallback)) => fallback.sign(message, context, spec),
+            (result, _) => result,
+        }
+    }
+}
+
+/// Returns the name of the `domain`, as it is sent to a remote signer.
+pub(crate) fn domain_type(domain: Domain) -> &'static str {
+    match domain {
+        Domain::BeaconProposer => "beacon_proposer",
+        Domain::BeaconAttester => "beacon_attester",
+        Domain::Randao => "randao",
+        Domain::Deposit => "deposit",
+        Domain::VoluntaryExit => "voluntary_exit",
+        Domain::SelectionProof => "selection_proof",
+        Domain::AggregateAndProof => "aggregate_and_proof",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{EthSpec, MinimalEthSpec};
+
+    #[test]
+    fn local_keypair_signature_verifies() {
+        let spec = MinimalEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let context = SigningContext {
+            domain: Domain::Randao,
+            epoch: Epoch::new(3),
+            fork: spec.genesis_fork.clone(),
+        };
+        let message = [42; 32];
+
+        let signature = LocalKeypair::new(keypair.clone())
+            .sign(&message, &context, &spec)
+            .expect("should sign");
+
+        assert!(signature.verify(&message, context.domain(&spec), &keypair.pk));
+    }
+
+    #[test]
+    fn remote_signers_config_from_json() {
+        let pubkey = Keypair::random().pk;
+        let json = format!(
+            r#"{{"signers": [{{"pubkey": "{}", "url": "http://localhost:9000/sign"}}]}}"#,
+            serde_json::to_value(&pubkey)
+                .expect("should encode pubkey")
+                .as_str()
+                .expect("pubkey should be a string")
+        );
+
+        let config: RemoteSignersConfig = serde_json::from_str(&json).expect("should decode");
+
+        assert_eq!(config.timeout(), DEFAULT_REMOTE_SIGNER_TIMEOUT);
+        assert!(!config.fallback_to_local);
+        assert_eq!(
+            config.get(&pubkey).map(|signer| signer.url.as_str()),
+            Some("http://localhost:9000/sign")
+        );
+    }
+}
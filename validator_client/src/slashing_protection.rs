@@ -0,0 +1,405 @@
+//! Provides a simple, file-backed slashing protection database for the validator client.
+//!
+//! A history of every block and attestation signed by a validator is stored as an SSZ file inside
+//! that validator's directory. The history is consulted (and updated) whilst holding a lock
+//! _before_ any signature is produced, so two conflicting messages can never be signed by the same
+//! validator, even if signing requests arrive concurrently.
+use parking_lot::Mutex;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use types::{AttestationData, Epoch, Hash256, PublicKey, SlashingProtectionBackend, Slot};
+
+/// The name of the file which stores the slashing protection history in each validator directory.
+pub const SLASHING_PROTECTION_FILE: &str = "slashing_protection.ssz";
+
+/// The reason a message was deemed unsafe to sign.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotSafe {
+    /// The validator is not known to the slashing protection database.
+    UnregisteredValidator,
+    /// A different block has already been signed at this slot.
+    DoubleBlockProposal { slot: Slot },
+    /// A different attestation has already been signed with this target epoch.
+    DoubleVote { target_epoch: Epoch },
+    /// The attestation would surround an attestation that was previously signed.
+    SurroundingVote {
+        prev_source_epoch: Epoch,
+        prev_target_epoch: Epoch,
+    },
+    /// The attestation would be surrounded by an attestation that was previously signed.
+    SurroundedVote {
+        prev_source_epoch: Epoch,
+        prev_target_epoch: Epoch,
+    },
+    /// The attestation source is later than its target.
+    InvalidAttestation {
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+    },
+    /// The history could not be read from or written to disk.
+    IOError(String),
+}
+
+/// The outcome of a successful slashing protection check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Safe {
+    /// The message is new and has been recorded in the history.
+    Valid,
+    /// An identical message has already been signed, it is safe to sign it again.
+    SameData,
+}
+
+/// A record of a block signed by some validator.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct SignedBlock {
+    pub slot: Slot,
+    pub signing_root: Hash256,
+}
+
+/// A record of an attestation signed by some validator.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct SignedAttestation {
+    pub source_epoch: Epoch,
+    pub target_epoch: Epoch,
+    pub signing_root: Hash256,
+}
+
+/// The complete signing history of a single validator.
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct ValidatorHistory {
+    pub blocks: Vec<SignedBlock>,
+    pub attestations: Vec<SignedAttestation>,
+}
+
+impl ValidatorHistory {
+    /// Checks if it is safe to sign a block at `slot` with the given `signing_root`.
+    pub fn check_block(&self, slot: Slot, signing_root: Hash256) -> Result<Safe, NotSafe> {
+        match self.blocks.iter().find(|block| block.slot == slot) {
+            Some(block) if block.signing_root == signing_root => Ok(Safe::SameData),
+            Some(_) => Err(NotSafe::DoubleBlockProposal { slot }),
+            None => Ok(Safe::Valid),
+        }
+    }
+
+    /// Checks if it is safe to sign an attestation with the given source, target and
+    /// `signing_root`.
+    pub fn check_attestation(
+        &self,
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+        signing_root: Hash256,
+    ) -> Result<Safe, NotSafe> {
+        if source_epoch > target_epoch {
+            return Err(NotSafe::InvalidAttestation {
+                source_epoch,
+                target_epoch,
+            });
+        }
+
+        for prev in &self.attestations {
+            if prev.target_epoch == target_epoch {
+                if prev.signing_root == signing_root {
+                    return Ok(Safe::SameData);
+                } else {
+                    return Err(NotSafe::DoubleVote { target_epoch });
+                }
+            }
+
+            if source_epoch < prev.source_epoch && prev.target_epoch < target_epoch {
+                return Err(NotSafe::SurroundingVote {
+                    prev_source_epoch: prev.source_epoch,
+                    prev_target_epoch: prev.target_epoch,
+                });
+            }
+
+            if prev.source_epoch < source_epoch && target_epoch < prev.target_epoch {
+                return Err(NotSafe::SurroundedVote {
+                    prev_source_epoch: prev.source_epoch,
+                    prev_target_epoch: prev.target_epoch,
+                });
+            }
+        }
+
+        Ok(Safe::Valid)
+    }
+
+    /// Loads the history from `path`, returning an empty history if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut bytes = vec![];
+
+        File::open(path)
+            .map_err(|e| format!("Unable to open slashing protection file: {}", e))?
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Unable to read slashing protection file: {}", e))?;
+
+        Self::from_ssz_bytes(&bytes)
+            .map_err(|e| format!("Unable to decode slashing protection file: {:?}", e))
+    }
+
+    /// Writes the history to `path`.
+    ///
+    /// The history is first written to a temporary file which is then moved into place, so a
+    /// crash part-way through writing cannot corrupt the existing history.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let temp_path = path.with_extension("tmp");
+
+        File::create(&temp_path)
+            .map_err(|e| format!("Unable to create slashing protection file: {}", e))?
+            .write_all(&self.as_ssz_bytes())
+            .map_err(|e| format!("Unable to write slashing protection file: {}", e))?;
+
+        fs::rename(&temp_path, path)
+            .map_err(|e| format!("Unable to move slashing protection file: {}", e))
+    }
+}
+
+/// Stores the signing history for all validators managed by this validator client.
+#[derive(Default)]
+pub struct SlashingProtection {
+    validators: Mutex<HashMap<PublicKey, (PathBuf, ValidatorHistory)>>,
+}
+
+impl SlashingProtection {
+    /// Loads (or creates) the signing history for the validator with the given `pubkey`, stored
+    /// within `directory`.
+    pub fn register_validator(&self, pubkey: PublicKey, directory: &Path) -> Result<(), String> {
+        let path = directory.join(SLASHING_PROTECTION_FILE);
+        let history = ValidatorHistory::load(&path)?;
+
+        self.validators.lock().insert(pubkey, (path, history));
+
+        Ok(())
+    }
+
+    /// Returns `Ok` if it is safe for `pubkey` to sign a block at `slot` with `signing_root`.
+    ///
+    /// If the block is new it is persisted to disk _before_ this function returns.
+    pub fn check_and_insert_block(
+        &self,
+        pubkey: &PublicKey,
+        slot: Slot,
+        signing_root: Hash256,
+    ) -> Result<Safe, NotSafe> {
+        let mut validators = self.validators.lock();
+        let (path, history) = validators
+            .get_mut(pubkey)
+            .ok_or_else(|| NotSafe::UnregisteredValidator)?;
+
+        let safe = history.check_block(slot, signing_root)?;
+
+        if safe == Safe::Valid {
+            let mut new_history = history.clone();
+            new_history.blocks.push(SignedBlock { slot, signing_root });
+            new_history.save(path).map_err(NotSafe::IOError)?;
+            *history = new_history;
+        }
+
+        Ok(safe)
+    }
+
+    /// Returns `Ok` if it is safe for `pubkey` to sign an attestation for `data`.
+    ///
+    /// If the attestation is new it is persisted to disk _before_ this function returns.
+    pub fn check_and_insert_attestation(
+        &self,
+        pubkey: &PublicKey,
+        data: &AttestationData,
+        signing_root: Hash256,
+    ) -> Result<Safe, NotSafe> {
+        let mut validators = self.validators.lock();
+        let (path, history) = validators
+            .get_mut(pubkey)
+            .ok_or_else(|| NotSafe::UnregisteredValidator)?;
+
+        let source_epoch = data.source.epoch;
+        let target_epoch = data.target.epoch;
+
+        let safe = history.check_attestation(source_epoch, target_epoch, signing_root)?;
+
+        if safe == Safe::Valid {
+            let mut new_history = history.clone();
+            new_history.attestations.push(SignedAttestation {
+                source_epoch,
+                target_epoch,
+                signing_root,
+            });
+            new_history.save(path).map_err(NotSafe::IOError)?;
+            *history = new_history;
+        }
+
+        Ok(safe)
+    }
+}
+
+impl SlashingProtectionBackend for SlashingProtection {
+    type Error = NotSafe;
+
+    fn check_and_record_block(
+        &self,
+        pubkey: &PublicKey,
+        slot: Slot,
+        signing_root: Hash256,
+    ) -> Result<(), NotSafe> {
+        self.check_and_insert_block(pubkey, slot, signing_root)
+            .map(|_| ())
+    }
+
+    fn check_and_record_attestation(
+        &self,
+        pubkey: &PublicKey,
+        data: &AttestationData,
+        signing_root: Hash256,
+    ) -> Result<(), NotSafe> {
+        self.check_and_insert_attestation(pubkey, data, signing_root)
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+    use types::EthSpec;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    fn history_with_attestation(source: u64, target: u64) -> ValidatorHistory {
+        ValidatorHistory {
+            blocks: vec![],
+            attestations: vec![SignedAttestation {
+                source_epoch: Epoch::new(source),
+                target_epoch: Epoch::new(target),
+                signing_root: root(0),
+            }],
+        }
+    }
+
+    #[test]
+    fn double_block_proposal() {
+        let history = ValidatorHistory {
+            blocks: vec![SignedBlock {
+                slot: Slot::new(1),
+                signing_root: root(1),
+            }],
+            attestations: vec![],
+        };
+
+        assert_eq!(
+            history.check_block(Slot::new(1), root(1)),
+            Ok(Safe::SameData)
+        );
+        assert_eq!(
+            history.check_block(Slot::new(1), root(2)),
+            Err(NotSafe::DoubleBlockProposal { slot: Slot::new(1) })
+        );
+        assert_eq!(history.check_block(Slot::new(2), root(2)), Ok(Safe::Valid));
+    }
+
+    #[test]
+    fn attestation_votes() {
+        let history = history_with_attestation(2, 4);
+        let check = |source, target, r| {
+            history.check_attestation(Epoch::new(source), Epoch::new(target), root(r))
+        };
+
+        assert_eq!(check(2, 4, 0), Ok(Safe::SameData));
+        assert_eq!(
+            check(3, 4, 1),
+            Err(NotSafe::DoubleVote {
+                target_epoch: Epoch::new(4)
+            })
+        );
+        assert_eq!(
+            check(1, 5, 1),
+            Err(NotSafe::SurroundingVote {
+                prev_source_epoch: Epoch::new(2),
+                prev_target_epoch: Epoch::new(4)
+            })
+        );
+        assert_eq!(
+            check(3, 3, 1),
+            Err(NotSafe::SurroundedVote {
+                prev_source_epoch: Epoch::new(2),
+                prev_target_epoch: Epoch::new(4)
+            })
+        );
+        assert_eq!(
+            check(6, 5, 1),
+            Err(NotSafe::InvalidAttestation {
+                source_epoch: Epoch::new(6),
+                target_epoch: Epoch::new(5)
+            })
+        );
+        assert_eq!(check(4, 5, 1), Ok(Safe::Valid));
+    }
+
+    #[test]
+    fn history_persists_across_restarts() {
+        let dir = TempDir::new("slashing_protection").expect("should create temp dir");
+        let pubkey = types::Keypair::random().pk;
+
+        let protection = SlashingProtection::default();
+        protection
+            .register_validator(pubkey.clone(), dir.path())
+            .expect("should register validator");
+        assert_eq!(
+            protection.check_and_insert_block(&pubkey, Slot::new(1), root(1)),
+            Ok(Safe::Valid)
+        );
+
+        let protection = SlashingProtection::default();
+        protection
+            .register_validator(pubkey.clone(), dir.path())
+            .expect("should register validator");
+        assert_eq!(
+            protection.check_and_insert_block(&pubkey, Slot::new(1), root(2)),
+            Err(NotSafe::DoubleBlockProposal { slot: Slot::new(1) })
+        );
+    }
+    #[test]
+    fn safe_signer_refuses_slashable_attestations() {
+        let dir = TempDir::new("slashing_protection").expect("should create temp dir");
+        let keypair = types::Keypair::random();
+        let spec = types::MinimalEthSpec::default_spec();
+        let fork = types::Fork::default();
+
+        let protection = SlashingProtection::default();
+        protection
+            .register_validator(keypair.pk.clone(), dir.path())
+            .expect("should register validator");
+        let signer = types::SafeSigner::new(keypair, protection);
+
+        let data = |source, target| AttestationData {
+            slot: Slot::new(0),
+            index: 0,
+            beacon_block_root: Hash256::zero(),
+            source: types::Checkpoint {
+                epoch: Epoch::new(source),
+                root: Hash256::zero(),
+            },
+            target: types::Checkpoint {
+                epoch: Epoch::new(target),
+                root: Hash256::zero(),
+            },
+        };
+
+        assert!(signer.sign_attestation(&data(2, 4), &fork, &spec).is_ok());
+        assert_eq!(
+            signer.sign_attestation(&data(1, 5), &fork, &spec),
+            Err(NotSafe::SurroundingVote {
+                prev_source_epoch: Epoch::new(2),
+                prev_target_epoch: Epoch::new(4)
+            })
+        );
+    }
+}
@@ -1,13 +1,15 @@
 use bls::get_withdrawal_credentials;
 use deposit_contract::eth1_tx_data;
+use eth2_keystore::Keystore;
 use hex;
+use rand::{distributions::Alphanumeric, Rng};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use types::{
     test_utils::generate_deterministic_keypair, ChainSpec, DepositData, Hash256, Keypair,
     PublicKey, SecretKey, Signature,
@@ -16,20 +18,33 @@ use types::{
 const VOTING_KEY_PREFIX: &str = "voting";
 const WITHDRAWAL_KEY_PREFIX: &str = "withdrawal";
 const ETH1_DEPOSIT_DATA_FILE: &str = "eth1_deposit_data.rlp";
+/// The length of the randomly generated keystore passwords.
+const KEYSTORE_PASSWORD_LEN: usize = 48;
 
 /// Returns the filename of a keypair file.
 fn keypair_file(prefix: &str) -> String {
     format!("{}_keypair", prefix)
 }
 
+/// Returns the filename of an EIP-2335 keystore file.
+fn keystore_file(prefix: &str) -> String {
+    format!("{}_keystore.json", prefix)
+}
+
 /// Returns the name of the folder to be generated for a validator with the given voting key.
-fn dir_name(voting_pubkey: &PublicKey) -> String {
+pub fn dir_name(voting_pubkey: &PublicKey) -> String {
     format!("0x{}", hex::encode(voting_pubkey.as_ssz_bytes()))
 }
 
+/// Returns the name of the file in the secrets directory which stores the password for the
+/// keystore with the given (hex-encoded, without `0x`) pubkey.
+fn password_file_name(keystore_pubkey: &str) -> String {
+    format!("0x{}", keystore_pubkey)
+}
+
 /// Represents the files/objects for each dedicated lighthouse validator directory.
 ///
-/// Generally lives in `~/.lighthouse/validators/`.
+/// Generally lives in the default `validators` directory (see `lighthouse_dirs`).
 #[derive(Debug, Clone, PartialEq)]
 pub struct ValidatorDirectory {
     pub directory: PathBuf,
@@ -41,7 +56,10 @@ pub struct ValidatorDirectory {
 impl ValidatorDirectory {
     /// Attempts to load a validator from the given directory, requiring only components necessary
     /// for signing messages.
-    pub fn load_for_signing(directory: PathBuf) -> Result<Self, String> {
+    ///
+    /// Keypairs are read from the (unencrypted) keypair files if they exist, otherwise they are
+    /// decrypted from EIP-2335 keystores using the password files in `secrets_dir`.
+    pub fn load_for_signing(directory: PathBuf, secrets_dir: &Path) -> Result<Self, String> {
         if !directory.exists() {
             return Err(format!(
                 "Validator directory does not exist: {:?}",
@@ -49,16 +67,76 @@ impl ValidatorDirectory {
             ));
         }
 
+        let load = |prefix| {
+            load_keypair(directory.clone(), prefix).or_else(|keypair_error| {
+                if directory.join(keystore_file(prefix)).exists() {
+                    load_keystore_keypair(directory.clone(), prefix, secrets_dir)
+                } else {
+                    Err(keypair_error)
+                }
+            })
+        };
+
         Ok(Self {
             voting_keypair: Some(
-                load_keypair(directory.clone(), VOTING_KEY_PREFIX)
+                load(VOTING_KEY_PREFIX)
                     .map_err(|e| format!("Unable to get voting keypair: {}", e))?,
             ),
-            withdrawal_keypair: load_keypair(directory.clone(), WITHDRAWAL_KEY_PREFIX).ok(),
+            withdrawal_keypair: load(WITHDRAWAL_KEY_PREFIX).ok(),
             deposit_data: load_eth1_deposit_data(directory.clone()).ok(),
             directory,
         })
     }
+
+    /// Loads only the voting keypair from the given directory, as per `Self::load_for_signing`.
+    ///
+    /// Unlike `Self::load_for_signing`, the withdrawal keystore is not decrypted, which halves the
+    /// time taken to load validators with EIP-2335 keystores.
+    pub fn load_voting_keypair(directory: &Path, secrets_dir: &Path) -> Result<Keypair, String> {
+        load_keypair(directory.to_path_buf(), VOTING_KEY_PREFIX)
+            .or_else(|keypair_error| {
+                if directory.join(keystore_file(VOTING_KEY_PREFIX)).exists() {
+                    load_keystore_keypair(directory.to_path_buf(), VOTING_KEY_PREFIX, secrets_dir)
+                } else {
+                    Err(keypair_error)
+                }
+            })
+            .map_err(|e| format!("Unable to get voting keypair: {}", e))
+    }
+
+    /// Returns the public key of the voting keystore in the given directory, without decrypting
+    /// it, or `None` if the directory has no (valid) voting keystore.
+    pub fn voting_keystore_pubkey(directory: &Path) -> Option<PublicKey> {
+        let keystore =
+            Keystore::from_json_file(directory.join(keystore_file(VOTING_KEY_PREFIX))).ok()?;
+        let bytes = hex::decode(keystore.pubkey()).ok()?;
+        PublicKey::from_ssz_bytes(&bytes).ok()
+    }
+
+    /// Returns the signed `DepositData` for `self`, for a deposit of `amount` Gwei.
+    ///
+    /// Requires both the voting and withdrawal keypairs to be present.
+    pub fn deposit_data_struct(
+        &self,
+        amount: u64,
+        spec: &ChainSpec,
+    ) -> Result<DepositData, String> {
+        let voting_keypair = self
+            .voting_keypair
+            .as_ref()
+            .ok_or_else(|| "deposit_data_struct requires a voting_keypair")?;
+        let withdrawal_keypair = self
+            .withdrawal_keypair
+            .as_ref()
+            .ok_or_else(|| "deposit_data_struct requires a withdrawal_keypair")?;
+
+        Ok(signed_deposit_data(
+            voting_keypair,
+            withdrawal_keypair,
+            amount,
+            spec,
+        ))
+    }
 }
 
 /// Load a `Keypair` from a file.
@@ -81,6 +159,26 @@ fn load_keypair(base_path: PathBuf, file_prefix: &str) -> Result<Keypair, String
         .map_err(|e| format!("Unable to decode keypair: {:?}", e))
 }
 
+/// Load a `Keypair` from an EIP-2335 keystore, reading the password from `secrets_dir`.
+fn load_keystore_keypair(
+    base_path: PathBuf,
+    file_prefix: &str,
+    secrets_dir: &Path,
+) -> Result<Keypair, String> {
+    let path = base_path.join(keystore_file(file_prefix));
+
+    let keystore = Keystore::from_json_file(&path)
+        .map_err(|e| format!("Unable to read keystore {:?}: {:?}", path, e))?;
+
+    let password_path = secrets_dir.join(password_file_name(keystore.pubkey()));
+    let password = fs::read(&password_path)
+        .map_err(|e| format!("Unable to read password file {:?}: {}", password_path, e))?;
+
+    keystore
+        .decrypt_keypair(&password)
+        .map_err(|e| format!("Unable to decrypt keystore {:?}: {:?}", path, e))
+}
+
 /// Load eth1_deposit_data from file.
 fn load_eth1_deposit_data(base_path: PathBuf) -> Result<Vec<u8>, String> {
     let path = base_path.join(ETH1_DEPOSIT_DATA_FILE);
@@ -136,6 +234,8 @@ pub struct ValidatorDirectoryBuilder {
     directory: Option<PathBuf>,
     voting_keypair: Option<Keypair>,
     withdrawal_keypair: Option<Keypair>,
+    voting_keystore: Option<(Keystore, Vec<u8>)>,
+    withdrawal_keystore: Option<(Keystore, Vec<u8>)>,
     amount: Option<u64>,
     deposit_data: Option<Vec<u8>>,
     spec: Option<ChainSpec>,
@@ -174,7 +274,58 @@ impl ValidatorDirectoryBuilder {
         self
     }
 
-    /// Creates a validator directory in the given `base_path` (e.g., `~/.lighthouse/validators/`).
+    /// Use the keypairs encrypted in the given EIP-2335 keystores (e.g., those derived from a
+    /// wallet).
+    ///
+    /// The keystores are decrypted with the given passwords and may later be written to disk with
+    /// `write_keystore_files`.
+    pub fn keystores(
+        mut self,
+        voting: (Keystore, Vec<u8>),
+        withdrawal: (Keystore, Vec<u8>),
+    ) -> Result<Self, String> {
+        self.voting_keypair = Some(
+            voting
+                .0
+                .decrypt_keypair(&voting.1)
+                .map_err(|e| format!("Unable to decrypt voting keystore: {:?}", e))?,
+        );
+        self.withdrawal_keypair = Some(
+            withdrawal
+                .0
+                .decrypt_keypair(&withdrawal.1)
+                .map_err(|e| format!("Unable to decrypt withdrawal keystore: {:?}", e))?,
+        );
+        self.voting_keystore = Some(voting);
+        self.withdrawal_keystore = Some(withdrawal);
+        Ok(self)
+    }
+
+    /// Use the keypair encrypted in the given EIP-2335 voting keystore, without a withdrawal
+    /// keypair (e.g., when importing a keystore for an existing validator).
+    ///
+    /// The keystore is decrypted with the given password and may later be written to disk with
+    /// `write_keystore_files`.
+    pub fn voting_keystore(
+        mut self,
+        keystore: Keystore,
+        password: Vec<u8>,
+    ) -> Result<Self, String> {
+        self.voting_keypair = Some(
+            keystore
+                .decrypt_keypair(&password)
+                .map_err(|e| format!("Unable to decrypt voting keystore: {:?}", e))?,
+        );
+        self.voting_keystore = Some((keystore, password));
+        Ok(self)
+    }
+
+    /// Returns the public key of the voting keypair, if one has been supplied.
+    pub fn voting_pubkey(&self) -> Option<&PublicKey> {
+        self.voting_keypair.as_ref().map(|keypair| &keypair.pk)
+    }
+
+    /// Creates a validator directory in the given `base_path` (e.g., the default `validators` directory).
     pub fn create_directory(mut self, base_path: PathBuf) -> Result<Self, String> {
         let voting_keypair = self
             .voting_keypair
@@ -190,7 +341,27 @@ impl ValidatorDirectoryBuilder {
             ));
         }
 
-        fs::create_dir_all(&directory)
+        lighthouse_dirs::create_dir_restricted(&directory)
+            .map_err(|e| format!("Unable to create validator directory: {}", e))?;
+
+        self.directory = Some(directory);
+
+        Ok(self)
+    }
+
+    /// Uses the validator directory in the given `base_path`, creating it if it does not exist.
+    ///
+    /// Unlike `create_directory`, an existing directory is permitted since it may contain the
+    /// slashing protection history of a validator whose keys were previously deleted.
+    pub fn create_or_reuse_directory(mut self, base_path: PathBuf) -> Result<Self, String> {
+        let voting_keypair = self
+            .voting_keypair
+            .as_ref()
+            .ok_or_else(|| "directory requires a voting_keypair")?;
+
+        let directory = base_path.join(dir_name(&voting_keypair.pk));
+
+        lighthouse_dirs::create_dir_restricted(&directory)
             .map_err(|e| format!("Unable to create validator directory: {}", e))?;
 
         self.directory = Some(directory);
@@ -213,6 +384,64 @@ impl ValidatorDirectoryBuilder {
         Ok(self)
     }
 
+    /// Writes the voting keystore and, if present, the withdrawal keystore into the validator
+    /// directory and their passwords into `secrets_dir`.
+    pub fn write_keystore_files(self, secrets_dir: &Path) -> Result<Self, String> {
+        let voting = self
+            .voting_keystore
+            .as_ref()
+            .ok_or_else(|| "write_keystore_files requires a voting_keystore")?;
+
+        lighthouse_dirs::create_dir_restricted(secrets_dir)
+            .map_err(|e| format!("Unable to create secrets directory: {}", e))?;
+
+        self.save_keystore(&voting.0, &voting.1, VOTING_KEY_PREFIX, secrets_dir)?;
+
+        if let Some(withdrawal) = self.withdrawal_keystore.as_ref() {
+            self.save_keystore(
+                &withdrawal.0,
+                &withdrawal.1,
+                WITHDRAWAL_KEY_PREFIX,
+                secrets_dir,
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    fn save_keystore(
+        &self,
+        keystore: &Keystore,
+        password: &[u8],
+        file_prefix: &str,
+        secrets_dir: &Path,
+    ) -> Result<(), String> {
+        let path = self
+            .directory
+            .as_ref()
+            .map(|directory| directory.join(keystore_file(file_prefix)))
+            .ok_or_else(|| "save_keystore requires a directory")?;
+
+        if path.exists() {
+            return Err(format!("Keystore file already exists at: {:?}", path));
+        }
+
+        keystore
+            .to_json_file(&path)
+            .map_err(|e| format!("Unable to write keystore: {:?}", e))?;
+
+        let password_path = secrets_dir.join(password_file_name(keystore.pubkey()));
+
+        if password_path.exists() {
+            return Err(format!(
+                "Password file already exists at: {:?}",
+                password_path
+            ));
+        }
+
+        create_with_600_perms(&password_path, password)
+    }
+
     fn save_keypair(&self, keypair: Keypair, file_prefix: &str) -> Result<(), String> {
         let path = self
             .directory
@@ -224,21 +453,7 @@ impl ValidatorDirectoryBuilder {
             return Err(format!("Keypair file already exists at: {:?}", path));
         }
 
-        let mut file = File::create(&path).map_err(|e| format!("Unable to create file: {}", e))?;
-
-        // Ensure file has correct permissions.
-        let mut perm = file
-            .metadata()
-            .map_err(|e| format!("Unable to get file metadata: {}", e))?
-            .permissions();
-        perm.set_mode((libc::S_IWUSR | libc::S_IRUSR) as u32);
-        file.set_permissions(perm)
-            .map_err(|e| format!("Unable to set file permissions: {}", e))?;
-
-        file.write_all(&SszEncodableKeypair::from(keypair).as_ssz_bytes())
-            .map_err(|e| format!("Unable to write keypair to file: {}", e))?;
-
-        Ok(())
+        create_with_600_perms(&path, &SszEncodableKeypair::from(keypair).as_ssz_bytes())
     }
 
     pub fn write_eth1_data_file(mut self) -> Result<Self, String> {
@@ -260,24 +475,13 @@ impl ValidatorDirectoryBuilder {
             .map(|directory| directory.join(ETH1_DEPOSIT_DATA_FILE))
             .ok_or_else(|| "write_eth1_data_filer requires a directory")?;
 
-        let deposit_data = {
-            let withdrawal_credentials = Hash256::from_slice(&get_withdrawal_credentials(
-                &withdrawal_keypair.pk,
-                spec.bls_withdrawal_prefix_byte,
-            ));
-
-            let mut deposit_data = DepositData {
-                pubkey: voting_keypair.pk.clone().into(),
-                withdrawal_credentials,
-                amount,
-                signature: Signature::empty_signature().into(),
-            };
-
-            deposit_data.signature = deposit_data.create_signature(&voting_keypair.sk, &spec);
-
-            eth1_tx_data(&deposit_data)
-                .map_err(|e| format!("Unable to encode eth1 deposit tx data: {:?}", e))?
-        };
+        let deposit_data = eth1_tx_data(&signed_deposit_data(
+            voting_keypair,
+            withdrawal_keypair,
+            amount,
+            spec,
+        ))
+        .map_err(|e| format!("Unable to encode eth1 deposit tx data: {:?}", e))?;
 
         if path.exists() {
             return Err(format!("Eth1 data file already exists at: {:?}", path));
@@ -303,9 +507,93 @@ impl ValidatorDirectoryBuilder {
     }
 }
 
+/// Produces a `DepositData` for the given keypairs, signed by the `voting_keypair`.
+fn signed_deposit_data(
+    voting_keypair: &Keypair,
+    withdrawal_keypair: &Keypair,
+    amount: u64,
+    spec: &ChainSpec,
+) -> DepositData {
+    let withdrawal_credentials = Hash256::from_slice(&get_withdrawal_credentials(
+        &withdrawal_keypair.pk,
+        spec.bls_withdrawal_prefix_byte,
+    ));
+
+    let mut deposit_data = DepositData {
+        pubkey: voting_keypair.pk.clone().into(),
+        withdrawal_credentials,
+        amount,
+        signature: Signature::empty_signature().into(),
+    };
+
+    deposit_data.signature = deposit_data.create_signature(&voting_keypair.sk, spec);
+
+    deposit_data
+}
+
+/// Deletes the voting keypair file, voting keystore and voting keystore password (if any) of the
+/// validator in `directory`.
+///
+/// The directory itself is retained, since it holds the validator's slashing protection history.
+pub fn delete_voting_keys(directory: &Path, secrets_dir: &Path) -> Result<(), String> {
+    let keystore_path = directory.join(keystore_file(VOTING_KEY_PREFIX));
+
+    if keystore_path.exists() {
+        let keystore = Keystore::from_json_file(&keystore_path)
+            .map_err(|e| format!("Unable to read keystore {:?}: {:?}", keystore_path, e))?;
+
+        let password_path = secrets_dir.join(password_file_name(keystore.pubkey()));
+        if password_path.exists() {
+            fs::remove_file(&password_path)
+                .map_err(|e| format!("Unable to delete {:?}: {}", password_path, e))?;
+        }
+
+        fs::remove_file(&keystore_path)
+            .map_err(|e| format!("Unable to delete {:?}: {}", keystore_path, e))?;
+    }
+
+    let keypair_path = directory.join(keypair_file(VOTING_KEY_PREFIX));
+    if keypair_path.exists() {
+        fs::remove_file(&keypair_path)
+            .map_err(|e| format!("Unable to delete {:?}: {}", keypair_path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Returns a random password, suitable for encrypting a keystore.
+pub fn random_password() -> Vec<u8> {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(KEYSTORE_PASSWORD_LEN)
+        .map(|c| c as u8)
+        .collect()
+}
+
+/// Creates a file with `600 (-rw-------)` permissions and writes `bytes` to it.
+pub(crate) fn create_with_600_perms(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut file = File::create(&path).map_err(|e| format!("Unable to create file: {}", e))?;
+
+    // Ensure file has correct permissions.
+    let mut perm = file
+        .metadata()
+        .map_err(|e| format!("Unable to get file metadata: {}", e))?
+        .permissions();
+    perm.set_mode((libc::S_IWUSR | libc::S_IRUSR) as u32);
+    file.set_permissions(perm)
+        .map_err(|e| format!("Unable to set file permissions: {}", e))?;
+
+    file.write_all(bytes)
+        .map_err(|e| format!("Unable to write to file: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use eth2_keystore::{
+        json_keystore::{Kdf, Pbkdf2, PBKDF2_PRF},
+        KeystoreBuilder, DKLEN,
+    };
     use tempdir::TempDir;
     use types::{EthSpec, MinimalEthSpec};
 
@@ -330,8 +618,9 @@ mod tests {
             .build()
             .expect("should build dir");
 
-        let loaded_dir = ValidatorDirectory::load_for_signing(created_dir.directory.clone())
-            .expect("should load directory");
+        let loaded_dir =
+            ValidatorDirectory::load_for_signing(created_dir.directory.clone(), temp_dir.path())
+                .expect("should load directory");
 
         assert_eq!(
             created_dir, loaded_dir,
@@ -393,12 +682,118 @@ mod tests {
             "should have some deposit data"
         );
 
-        let loaded_dir = ValidatorDirectory::load_for_signing(created_dir.directory.clone())
-            .expect("should load directory");
+        let loaded_dir =
+            ValidatorDirectory::load_for_signing(created_dir.directory.clone(), temp_dir.path())
+                .expect("should load directory");
+
+        assert_eq!(
+            created_dir, loaded_dir,
+            "the directory created should match the one loaded"
+        );
+    }
+
+    #[test]
+    fn keystores_round_trip() {
+        let spec = E::default_spec();
+        let temp_dir = TempDir::new("acc_manager").expect("should create test dir");
+        let secrets_dir = temp_dir.path().join("secrets");
+
+        let keystore = |password: &[u8]| {
+            let keystore = KeystoreBuilder::new(&Keypair::random(), password, "".into())
+                .kdf(Kdf::Pbkdf2(Pbkdf2 {
+                    dklen: DKLEN,
+                    c: 2,
+                    prf: PBKDF2_PRF.to_string(),
+                    salt: vec![1; 32].into(),
+                }))
+                .build()
+                .expect("should build keystore");
+            (keystore, password.to_vec())
+        };
+
+        let created_dir = ValidatorDirectoryBuilder::default()
+            .spec(spec)
+            .full_deposit_amount()
+            .expect("should set full deposit amount")
+            .keystores(keystore(&random_password()), keystore(&random_password()))
+            .expect("should decrypt keystores")
+            .create_directory(temp_dir.path().into())
+            .expect("should create directory")
+            .write_keystore_files(&secrets_dir)
+            .expect("should write keystore files")
+            .write_eth1_data_file()
+            .expect("should write eth1 data file")
+            .build()
+            .expect("should build dir");
+
+        let loaded_dir =
+            ValidatorDirectory::load_for_signing(created_dir.directory.clone(), &secrets_dir)
+                .expect("should load directory");
 
         assert_eq!(
             created_dir, loaded_dir,
             "the directory created should match the one loaded"
         );
+
+        let voting_keypair = created_dir
+            .voting_keypair
+            .expect("should have voting keypair");
+        assert_eq!(
+            ValidatorDirectory::load_voting_keypair(&created_dir.directory, &secrets_dir),
+            Ok(voting_keypair.clone()),
+            "should load the voting keypair alone"
+        );
+        assert_eq!(
+            ValidatorDirectory::voting_keystore_pubkey(&created_dir.directory),
+            Some(voting_keypair.pk),
+            "should read the pubkey without decrypting the keystore"
+        );
+    }
+
+    #[test]
+    fn voting_keystore_import_and_delete() {
+        let temp_dir = TempDir::new("acc_manager").expect("should create test dir");
+        let secrets_dir = temp_dir.path().join("secrets");
+        let password = random_password();
+        let keystore = KeystoreBuilder::new(&Keypair::random(), &password, "".into())
+            .kdf(Kdf::Pbkdf2(Pbkdf2 {
+                dklen: DKLEN,
+                c: 2,
+                prf: PBKDF2_PRF.to_string(),
+                salt: vec![1; 32].into(),
+            }))
+            .build()
+            .expect("should build keystore");
+
+        let import = || {
+            ValidatorDirectoryBuilder::default()
+                .voting_keystore(keystore.clone(), password.clone())
+                .expect("should decrypt keystore")
+                .create_or_reuse_directory(temp_dir.path().into())
+                .expect("should create directory")
+                .write_keystore_files(&secrets_dir)
+                .expect("should write keystore files")
+                .build()
+                .expect("should build dir")
+        };
+
+        let created_dir = import();
+        assert!(created_dir.withdrawal_keypair.is_none());
+
+        let loaded_dir =
+            ValidatorDirectory::load_for_signing(created_dir.directory.clone(), &secrets_dir)
+                .expect("should load directory");
+        assert_eq!(created_dir, loaded_dir);
+
+        delete_voting_keys(&created_dir.directory, &secrets_dir).expect("should delete keys");
+        assert!(
+            ValidatorDirectory::load_for_signing(created_dir.directory.clone(), &secrets_dir)
+                .is_err(),
+            "should not load deleted keys"
+        );
+        assert!(created_dir.directory.exists(), "should retain directory");
+
+        // The same keystore may be imported again into the existing directory.
+        assert_eq!(import(), created_dir);
     }
 }
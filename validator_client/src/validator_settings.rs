@@ -0,0 +1,104 @@
+//! Provides `ValidatorSettings`, the per-validator options which may be modified at runtime (e.g.,
+//! via the HTTP API) and are persisted alongside the validator's keys.
+use serde_derive::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::Path;
+
+/// The name of the file in each validator directory which stores its settings.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The maximum length of a graffiti, in bytes.
+pub const GRAFFITI_MAX_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ValidatorSettings {
+    /// The graffiti to include in blocks proposed by this validator, overriding any global
+    /// graffiti.
+    #[serde(default)]
+    pub graffiti: Option<String>,
+}
+
+impl ValidatorSettings {
+    /// Loads the settings stored in `directory`, returning the default settings if none exist.
+    pub fn load(directory: &Path) -> Result<Self, String> {
+        let path = directory.join(SETTINGS_FILE);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = File::open(&path).map_err(|e| format!("Unable to open {:?}: {}", path, e))?;
+        serde_json::from_reader(file).map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+    }
+
+    /// Writes the settings into `directory`, replacing any existing settings.
+    pub fn save(&self, directory: &Path) -> Result<(), String> {
+        let path = directory.join(SETTINGS_FILE);
+        let temp_path = path.with_extension("tmp");
+
+        let file = File::create(&temp_path)
+            .map_err(|e| format!("Unable to create {:?}: {}", temp_path, e))?;
+        serde_json::to_writer(file, self)
+            .map_err(|e| format!("Unable to write validator settings: {}", e))?;
+
+        fs::rename(&temp_path, &path).map_err(|e| format!("Unable to save {:?}: {}", path, e))
+    }
+}
+
+/// Returns `Ok` if `graffiti` fits within the graffiti field of a block.
+pub fn check_graffiti(graffiti: &str) -> Result<(), String> {
+    if graffiti.len() > GRAFFITI_MAX_LEN {
+        Err(format!(
+            "Graffiti is {} bytes, the maximum is {}",
+            graffiti.len(),
+            GRAFFITI_MAX_LEN
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns `graffiti` as it is included in a block, padded with trailing zeros.
+pub fn graffiti_bytes(graffiti: &str) -> Result<[u8; GRAFFITI_MAX_LEN], String> {
+    check_graffiti(graffiti)?;
+
+    let mut bytes = [0; GRAFFITI_MAX_LEN];
+    bytes[..graffiti.len()].copy_from_slice(graffiti.as_bytes());
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn settings_round_trip() {
+        let temp_dir = TempDir::new("validator_settings").expect("should create test dir");
+
+        assert_eq!(
+            ValidatorSettings::load(temp_dir.path()),
+            Ok(ValidatorSettings::default()),
+            "should load defaults when no file exists"
+        );
+
+        let settings = ValidatorSettings {
+            graffiti: Some("lighthouse".into()),
+        };
+        settings.save(temp_dir.path()).expect("should save");
+
+        assert_eq!(ValidatorSettings::load(temp_dir.path()), Ok(settings));
+    }
+
+    #[test]
+    fn graffiti_length() {
+        assert!(check_graffiti(&"a".repeat(GRAFFITI_MAX_LEN)).is_ok());
+        assert!(check_graffiti(&"a".repeat(GRAFFITI_MAX_LEN + 1)).is_err());
+
+        let mut expected = [0; GRAFFITI_MAX_LEN];
+        expected[..3].copy_from_slice(b"abc");
+        assert_eq!(graffiti_bytes("abc"), Ok(expected));
+        assert!(graffiti_bytes(&"a".repeat(GRAFFITI_MAX_LEN + 1)).is_err());
+    }
+}
@@ -1,24 +1,116 @@
+use crate::config::Config;
 use crate::fork_service::ForkService;
-use crate::validator_directory::{ValidatorDirectory, ValidatorDirectoryBuilder};
+use crate::http_metrics::metrics;
+use crate::key_cache::{KeyCache, KEY_CACHE_FILE};
+use crate::persisted_state::LastSigned;
+use crate::signing_method::{
+    domain_type, LocalKeypair, RemoteSigner, RemoteSignerDefinition, RemoteSignersConfig,
+    SignableProvider, SigningContext,
+};
+use crate::slashing_protection::SlashingProtection;
+use crate::validator_directory::{
+    delete_voting_keys, dir_name, ValidatorDirectory, ValidatorDirectoryBuilder,
+};
+use crate::validator_settings::ValidatorSettings;
+use eth2_keystore::{default_kdf, Keystore};
 use parking_lot::RwLock;
 use rayon::prelude::*;
-use slog::{error, Logger};
+use slog::{crit, error, info, Logger};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
-use std::fs::read_dir;
-use std::iter::FromIterator;
+use std::fs::{self, create_dir_all, read_dir};
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tempdir::TempDir;
-use tree_hash::TreeHash;
+use tree_hash::{SignedRoot, TreeHash};
 use types::{
-    Attestation, BeaconBlock, ChainSpec, Domain, Epoch, EthSpec, Fork, PublicKey, Signature,
+    test_utils::generate_deterministic_keypair, AggregateAndProof, Attestation, BeaconBlock,
+    ChainSpec, Domain, Epoch, EthSpec, Fork, Hash256, Keypair, PublicKey, SelectionProof,
+    Signature, SignedAggregateAndProof, Slot, VoluntaryExit,
 };
 
+/// The number of times progress is logged whilst loading validator keys.
+const KEY_LOADING_PROGRESS_STEPS: usize = 10;
+
+/// The outcome of importing a validator into the store.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportOutcome {
+    Imported,
+    /// The validator was already known to the store and has not been modified.
+    Duplicate,
+}
+
+/// The outcome of deleting a validator from the store.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeleteOutcome {
+    Deleted,
+    /// The validator is not known to the store (or does not use the requested signing method).
+    NotFound,
+}
+
+/// A validator which is able to produce signatures, either locally or via a remote signer.
+#[derive(Clone)]
+struct SigningValidator {
+    /// The directory in which the validator's slashing protection history is stored.
+    directory: PathBuf,
+    signer: Arc<dyn SignableProvider>,
+    /// The URL of the remote signer, if one is used.
+    remote_url: Option<String>,
+    settings: ValidatorSettings,
+}
+
+impl SigningValidator {
+    fn local(directory: PathBuf, keypair: Keypair) -> Result<Self, String> {
+        Ok(Self {
+            settings: ValidatorSettings::load(&directory)?,
+            directory,
+            signer: Arc::new(LocalKeypair::new(keypair)),
+            remote_url: None,
+        })
+    }
+
+    fn remote(
+        directory: PathBuf,
+        definition: &RemoteSignerDefinition,
+        config: &RemoteSignersConfig,
+        fallback: Option<Arc<dyn SignableProvider>>,
+    ) -> Result<Self, String> {
+        let signer = RemoteSigner::new(
+            definition.url.clone(),
+            definition.pubkey.clone(),
+            config.timeout(),
+            fallback,
+        )?;
+
+        Ok(Self {
+            settings: ValidatorSettings::load(&directory)?,
+            directory,
+            signer: Arc::new(signer),
+            remote_url: Some(definition.url.clone()),
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct ValidatorStore<T, E: EthSpec> {
-    validators: Arc<RwLock<HashMap<PublicKey, ValidatorDirectory>>>,
+    validators: Arc<RwLock<HashMap<PublicKey, SigningValidator>>>,
+    slashing_protection: Arc<SlashingProtection>,
+    /// The directory containing a sub-directory for each validator.
+    base_dir: PathBuf,
+    /// The directory containing the passwords for any EIP-2335 keystores in `base_dir`.
+    secrets_dir: PathBuf,
+    remote_signers: Arc<RwLock<RemoteSignersConfig>>,
+    /// The file to which changes to `remote_signers` are persisted, if any.
+    remote_signers_path: Option<PathBuf>,
+    /// If `false`, the store will refuse to sign any messages (e.g., whilst checking for
+    /// doppelgangers).
+    signing_enabled: Arc<AtomicBool>,
+    /// Randao reveals which were produced ahead of the epoch in which they are required.
+    randao_reveals: Arc<RwLock<HashMap<(PublicKey, Epoch), Signature>>>,
+    /// The latest messages signed by each validator.
+    last_signed: Arc<RwLock<HashMap<PublicKey, LastSigned>>>,
     spec: Arc<ChainSpec>,
     log: Logger,
     temp_dir: Option<Arc<TempDir>>,
@@ -27,43 +119,114 @@ pub struct ValidatorStore<T, E: EthSpec> {
 }
 
 impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
+    /// Loads the validators in the `data_dir` of `config`, along with any remote signers.
     pub fn load_from_disk(
-        base_dir: PathBuf,
+        config: &Config,
         spec: ChainSpec,
         fork_service: ForkService<T, E>,
         log: Logger,
     ) -> Result<Self, String> {
-        let validator_iter = read_dir(&base_dir)
+        let base_dir = config.data_dir.clone();
+        let secrets_dir = config.secrets_dir.clone();
+        let remote_signers = config.remote_signers.clone().unwrap_or_default();
+        let remote_signers_path = config.remote_signers_path.clone();
+
+        let key_cache_password = config
+            .key_cache_password_file
+            .as_ref()
+            .map(|path| {
+                fs::read(&path)
+                    .map_err(|e| format!("Unable to read key cache password {:?}: {}", path, e))
+            })
+            .transpose()?;
+        let key_cache_path = base_dir.join(KEY_CACHE_FILE);
+        let key_cache = match &key_cache_password {
+            Some(password) => KeyCache::open(&key_cache_path, password)?,
+            None => KeyCache::default(),
+        };
+
+        let directories = read_dir(&base_dir)
             .map_err(|e| format!("Failed to read base directory {:?}: {:?}", base_dir, e))?
-            .filter_map(|validator_dir| {
-                let path = validator_dir.ok()?.path();
-
-                if path.is_dir() {
-                    match ValidatorDirectory::load_for_signing(path.clone()) {
-                        Ok(validator_directory) => Some(validator_directory),
-                        Err(e) => {
-                            error!(
-                                log,
-                                "Failed to load a validator directory";
-                                "error" => e,
-                                "path" => path.to_str(),
-                            );
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
+            .filter_map(|validator_dir| Some(validator_dir.ok()?.path()))
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+
+        let keypairs =
+            load_voting_keypairs(directories, &secrets_dir, &key_cache, &remote_signers, &log);
+
+        if let Some(password) = &key_cache_password {
+            let cache_is_stale = keypairs.len() != key_cache.len()
+                || keypairs
+                    .iter()
+                    .any(|(_, keypair)| key_cache.get(&keypair.pk).is_none());
+
+            if cache_is_stale {
+                KeyCache::save(
+                    &key_cache_path,
+                    password,
+                    default_kdf(),
+                    keypairs.iter().map(|(_, keypair)| keypair),
+                )?;
+
+                info!(
+                    log,
+                    "Updated validator key cache";
+                    "path" => format!("{:?}", key_cache_path),
+                    "validators" => keypairs.len(),
+                );
+            }
+        }
+
+        let mut validators = keypairs
+            .into_iter()
+            .map(|(directory, keypair)| {
+                Ok((
+                    keypair.pk.clone(),
+                    SigningValidator::local(directory, keypair)?,
+                ))
             })
-            .filter_map(|validator_directory| {
-                validator_directory
-                    .voting_keypair
-                    .clone()
-                    .map(|voting_keypair| (voting_keypair.pk, validator_directory))
-            });
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        for definition in &remote_signers.signers {
+            let local = validators.remove(&definition.pubkey);
+            let fallback = if remote_signers.fallback_to_local {
+                local.as_ref().map(|validator| validator.signer.clone())
+            } else {
+                None
+            };
+            let directory = local
+                .map(|validator| validator.directory)
+                .unwrap_or_else(|| base_dir.join(dir_name(&definition.pubkey)));
+
+            create_dir_all(&directory)
+                .map_err(|e| format!("Unable to create dir {:?}: {:?}", directory, e))?;
+
+            info!(
+                log,
+                "Using remote signer";
+                "url" => &definition.url,
+                "voting_pubkey" => format!("{:?}", definition.pubkey),
+                "local_fallback" => fallback.is_some(),
+            );
+
+            validators.insert(
+                definition.pubkey.clone(),
+                SigningValidator::remote(directory, definition, &remote_signers, fallback)?,
+            );
+        }
+
+        let slashing_protection = load_slashing_protection(&validators)?;
 
         Ok(Self {
-            validators: Arc::new(RwLock::new(HashMap::from_iter(validator_iter))),
+            validators: Arc::new(RwLock::new(validators)),
+            slashing_protection: Arc::new(slashing_protection),
+            base_dir,
+            secrets_dir,
+            remote_signers: Arc::new(RwLock::new(remote_signers)),
+            remote_signers_path,
+            signing_enabled: Arc::new(AtomicBool::new(true)),
+            randao_reveals: Arc::new(RwLock::new(HashMap::new())),
+            last_signed: Arc::new(RwLock::new(HashMap::new())),
             spec: Arc::new(spec),
             log,
             temp_dir: None,
@@ -72,6 +235,13 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         })
     }
 
+    /// Creates a store for the deterministic "interop" validators with the given indices.
+    ///
+    /// The keypairs are derived in memory (as per `generate_deterministic_keypairs`) and no
+    /// keystores are read or written. A temporary directory holds the slashing protection history,
+    /// so it is lost when the store is dropped.
+    ///
+    /// The private keys are publicly known, so these validators must only be used for testing.
     pub fn insecure_ephemeral_validators(
         validator_indices: &[usize],
         spec: ChainSpec,
@@ -85,26 +255,29 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         let validators = validator_indices
             .par_iter()
             .map(|index| {
-                ValidatorDirectoryBuilder::default()
-                    .spec(spec.clone())
-                    .full_deposit_amount()?
-                    .insecure_keypairs(*index)
-                    .create_directory(data_dir.clone())?
-                    .write_keypair_files()?
-                    .write_eth1_data_file()?
-                    .build()
+                let keypair = generate_deterministic_keypair(*index);
+                let directory = data_dir.join(dir_name(&keypair.pk));
+                create_dir_all(&directory)
+                    .map_err(|e| format!("Unable to create {:?}: {}", directory, e))?;
+
+                Ok((
+                    keypair.pk.clone(),
+                    SigningValidator::local(directory, keypair)?,
+                ))
             })
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .filter_map(|validator_directory| {
-                validator_directory
-                    .voting_keypair
-                    .clone()
-                    .map(|voting_keypair| (voting_keypair.pk, validator_directory))
-            });
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        let slashing_protection = load_slashing_protection(&validators)?;
 
         Ok(Self {
-            validators: Arc::new(RwLock::new(HashMap::from_iter(validators))),
+            validators: Arc::new(RwLock::new(validators)),
+            slashing_protection: Arc::new(slashing_protection),
+            secrets_dir: data_dir.clone(),
+            base_dir: data_dir,
+            remote_signers: Arc::new(RwLock::new(RemoteSignersConfig::default())),
+            remote_signers_path: None,
+            signing_enabled: Arc::new(AtomicBool::new(true)),
+            randao_reveals: Arc::new(RwLock::new(HashMap::new())),
+            last_signed: Arc::new(RwLock::new(HashMap::new())),
             spec: Arc::new(spec),
             log,
             temp_dir: Some(Arc::new(temp_dir)),
@@ -125,6 +298,264 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         self.validators.read().len()
     }
 
+    /// Returns the voting pubkeys of all validators which sign with a local keypair.
+    pub fn local_pubkeys(&self) -> Vec<PublicKey> {
+        self.validators
+            .read()
+            .iter()
+            .filter(|(_, validator)| validator.remote_url.is_none())
+            .map(|(pubkey, _)| pubkey.clone())
+            .collect()
+    }
+
+    /// Returns the definitions of all validators which sign with a remote signer.
+    pub fn remote_signers(&self) -> Vec<RemoteSignerDefinition> {
+        self.validators
+            .read()
+            .iter()
+            .filter_map(|(pubkey, validator)| {
+                validator
+                    .remote_url
+                    .as_ref()
+                    .map(|url| RemoteSignerDefinition {
+                        pubkey: pubkey.clone(),
+                        url: url.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Decrypts `keystore` and adds it to the store, writing the keystore into the validator's
+    /// directory and its password into the secrets directory.
+    ///
+    /// The validator will begin performing duties once they are next polled by the duties service.
+    pub fn import_keystore(
+        &self,
+        keystore: Keystore,
+        password: Vec<u8>,
+    ) -> Result<ImportOutcome, String> {
+        let builder = ValidatorDirectoryBuilder::default().voting_keystore(keystore, password)?;
+
+        // Hold the lock for the entire import to prevent the same keystore from being imported
+        // twice concurrently.
+        let mut validators = self.validators.write();
+
+        if builder
+            .voting_pubkey()
+            .map_or(false, |pubkey| validators.contains_key(pubkey))
+        {
+            return Ok(ImportOutcome::Duplicate);
+        }
+
+        let validator_directory = builder
+            .create_or_reuse_directory(self.base_dir.clone())?
+            .write_keystore_files(&self.secrets_dir)?
+            .build()?;
+        let voting_keypair = validator_directory
+            .voting_keypair
+            .ok_or_else(|| "Imported validator has no voting keypair")?;
+
+        self.slashing_protection
+            .register_validator(voting_keypair.pk.clone(), &validator_directory.directory)?;
+
+        info!(
+            self.log,
+            "Imported validator keystore";
+            "voting_pubkey" => format!("{:?}", voting_keypair.pk),
+        );
+
+        validators.insert(
+            voting_keypair.pk.clone(),
+            SigningValidator::local(validator_directory.directory, voting_keypair)?,
+        );
+
+        Ok(ImportOutcome::Imported)
+    }
+
+    /// Removes the validator with the local keypair for `pubkey` from the store, deleting its
+    /// keys from disk.
+    ///
+    /// The validator's directory (and therefore its slashing protection history) is retained.
+    pub fn delete_keystore(&self, pubkey: &PublicKey) -> Result<DeleteOutcome, String> {
+        let mut validators = self.validators.write();
+
+        let directory = match validators.get(pubkey) {
+            Some(validator) if validator.remote_url.is_none() => validator.directory.clone(),
+            _ => return Ok(DeleteOutcome::NotFound),
+        };
+
+        delete_voting_keys(&directory, &self.secrets_dir)?;
+        validators.remove(pubkey);
+
+        info!(
+            self.log,
+            "Deleted validator keystore";
+            "voting_pubkey" => format!("{:?}", pubkey),
+        );
+
+        Ok(DeleteOutcome::Deleted)
+    }
+
+    /// Adds a validator which signs with the remote signer described by `definition`, persisting
+    /// the definition to the remote signers file.
+    pub fn import_remote_signer(
+        &self,
+        definition: RemoteSignerDefinition,
+    ) -> Result<ImportOutcome, String> {
+        let mut validators = self.validators.write();
+
+        if validators.contains_key(&definition.pubkey) {
+            return Ok(ImportOutcome::Duplicate);
+        }
+
+        let directory = self.base_dir.join(dir_name(&definition.pubkey));
+        create_dir_all(&directory)
+            .map_err(|e| format!("Unable to create dir {:?}: {:?}", directory, e))?;
+
+        let mut remote_signers = self.remote_signers.write();
+        let validator =
+            SigningValidator::remote(directory.clone(), &definition, &remote_signers, None)?;
+
+        let mut new_remote_signers = remote_signers.clone();
+        new_remote_signers.signers.push(definition.clone());
+        self.save_remote_signers(&new_remote_signers)?;
+        *remote_signers = new_remote_signers;
+
+        self.slashing_protection
+            .register_validator(definition.pubkey.clone(), &directory)?;
+
+        info!(
+            self.log,
+            "Imported remote signer";
+            "url" => &definition.url,
+            "voting_pubkey" => format!("{:?}", definition.pubkey),
+        );
+
+        validators.insert(definition.pubkey, validator);
+
+        Ok(ImportOutcome::Imported)
+    }
+
+    /// Removes the remote signer for `pubkey`, persisting the change to the remote signers file.
+    ///
+    /// If the validator's directory contains a local keypair it will be used for signing
+    /// henceforth, as if the validator client had been restarted.
+    pub fn delete_remote_signer(&self, pubkey: &PublicKey) -> Result<DeleteOutcome, String> {
+        let mut validators = self.validators.write();
+
+        let directory = match validators.get(pubkey) {
+            Some(validator) if validator.remote_url.is_some() => validator.directory.clone(),
+            _ => return Ok(DeleteOutcome::NotFound),
+        };
+
+        let mut remote_signers = self.remote_signers.write();
+        let mut new_remote_signers = remote_signers.clone();
+        new_remote_signers
+            .signers
+            .retain(|signer| signer.pubkey != *pubkey);
+        self.save_remote_signers(&new_remote_signers)?;
+        *remote_signers = new_remote_signers;
+
+        match ValidatorDirectory::load_for_signing(directory.clone(), &self.secrets_dir)
+            .ok()
+            .and_then(|validator_directory| validator_directory.voting_keypair)
+        {
+            Some(keypair) => {
+                validators.insert(pubkey.clone(), SigningValidator::local(directory, keypair)?);
+            }
+            None => {
+                validators.remove(pubkey);
+            }
+        }
+
+        info!(
+            self.log,
+            "Deleted remote signer";
+            "voting_pubkey" => format!("{:?}", pubkey),
+        );
+
+        Ok(DeleteOutcome::Deleted)
+    }
+
+    /// Writes `remote_signers` to the remote signers file, if one is configured.
+    fn save_remote_signers(&self, remote_signers: &RemoteSignersConfig) -> Result<(), String> {
+        match &self.remote_signers_path {
+            Some(path) => remote_signers.to_file(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the settings for the validator with `pubkey`, if it is known to the store.
+    pub fn validator_settings(&self, pubkey: &PublicKey) -> Option<ValidatorSettings> {
+        self.validators
+            .read()
+            .get(pubkey)
+            .map(|validator| validator.settings.clone())
+    }
+
+    /// Replaces the settings for the validator with `pubkey`, persisting them to its directory.
+    ///
+    /// Returns `Ok(None)` if the validator is not known to the store.
+    pub fn set_validator_settings(
+        &self,
+        pubkey: &PublicKey,
+        settings: ValidatorSettings,
+    ) -> Result<Option<()>, String> {
+        let mut validators = self.validators.write();
+
+        match validators.get_mut(pubkey) {
+            Some(validator) => {
+                settings.save(&validator.directory)?;
+                validator.settings = settings;
+                Ok(Some(()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns `true` if the store is permitted to sign messages.
+    pub fn signing_enabled(&self) -> bool {
+        self.signing_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Enables or disables the signing of all messages.
+    pub fn set_signing_enabled(&self, enabled: bool) {
+        self.signing_enabled.store(enabled, Ordering::SeqCst)
+    }
+
+    /// Returns the latest messages signed by each validator which has signed any.
+    pub fn last_signed(&self) -> Vec<LastSigned> {
+        self.last_signed.read().values().cloned().collect()
+    }
+
+    /// Returns the latest messages signed by the validator with `pubkey`, if any.
+    pub fn last_signed_by(&self, pubkey: &PublicKey) -> Option<LastSigned> {
+        self.last_signed.read().get(pubkey).cloned()
+    }
+
+    /// Stores the latest signed messages persisted by a previous run of the validator client.
+    ///
+    /// Any messages signed by this run are kept in preference to the restored ones.
+    pub fn restore_last_signed(&self, last_signed: Vec<LastSigned>) {
+        let mut map = self.last_signed.write();
+
+        for restored in last_signed {
+            map.entry(restored.pubkey.clone()).or_insert(restored);
+        }
+    }
+
+    /// Updates the latest messages signed by the validator with `pubkey`.
+    fn record_signed<F: FnOnce(&mut LastSigned)>(&self, pubkey: &PublicKey, update: F) {
+        let mut map = self.last_signed.write();
+        let last_signed = map.entry(pubkey.clone()).or_insert_with(|| LastSigned {
+            pubkey: pubkey.clone(),
+            block_slot: None,
+            attestation_target_epoch: None,
+        });
+
+        update(last_signed)
+    }
+
     fn fork(&self) -> Option<Fork> {
         if self.fork_service.fork().is_none() {
             error!(
@@ -135,18 +566,100 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         self.fork_service.fork()
     }
 
+    /// Signs `message` with the signer for `validator_pubkey`, logging any error.
+    fn sign(
+        &self,
+        validator_pubkey: &PublicKey,
+        message: &[u8],
+        domain: Domain,
+        epoch: Epoch,
+    ) -> Option<Signature> {
+        if !self.signing_enabled() {
+            return None;
+        }
+
+        let signer = self.validators.read().get(validator_pubkey)?.signer.clone();
+        let context = SigningContext {
+            domain,
+            epoch,
+            fork: self.fork()?,
+        };
+
+        let _timer = metrics::start_timer_vec(&metrics::SIGNING_TIMES, &[domain_type(domain)]);
+
+        signer
+            .sign(message, &context, &self.spec)
+            .map_err(|e| {
+                error!(
+                    self.log,
+                    "Unable to sign message";
+                    "error" => format!("{:?}", e),
+                    "domain" => format!("{:?}", domain),
+                    "voting_pubkey" => format!("{:?}", validator_pubkey),
+                )
+            })
+            .ok()
+    }
+
     pub fn randao_reveal(&self, validator_pubkey: &PublicKey, epoch: Epoch) -> Option<Signature> {
+        if let Some(randao_reveal) = self
+            .randao_reveals
+            .read()
+            .get(&(validator_pubkey.clone(), epoch))
+        {
+            return Some(randao_reveal.clone());
+        }
+
         // TODO: check this against the slot clock to make sure it's not an early reveal?
-        self.validators
+        self.sign(
+            validator_pubkey,
+            &epoch.tree_hash_root(),
+            Domain::Randao,
+            epoch,
+        )
+    }
+
+    /// Produces the randao reveal for `epoch` ahead of time, so that it is immediately available
+    /// to `Self::randao_reveal` (e.g., to avoid waiting on a remote signer whilst proposing).
+    ///
+    /// Reveals for epochs prior to `epoch` are discarded.
+    pub fn precompute_randao_reveal(&self, validator_pubkey: &PublicKey, epoch: Epoch) -> bool {
+        self.randao_reveals
+            .write()
+            .retain(|(_, reveal_epoch), _| *reveal_epoch >= epoch);
+
+        if self
+            .randao_reveals
             .read()
-            .get(validator_pubkey)
-            .and_then(|validator_dir| {
-                let voting_keypair = validator_dir.voting_keypair.as_ref()?;
-                let message = epoch.tree_hash_root();
-                let domain = self.spec.get_domain(epoch, Domain::Randao, &self.fork()?);
+            .contains_key(&(validator_pubkey.clone(), epoch))
+        {
+            return true;
+        }
 
-                Some(Signature::new(&message, domain, &voting_keypair.sk))
+        self.randao_reveal(validator_pubkey, epoch)
+            .map(|randao_reveal| {
+                self.randao_reveals
+                    .write()
+                    .insert((validator_pubkey.clone(), epoch), randao_reveal);
             })
+            .is_some()
+    }
+
+    /// Signs `exit`, which permanently exits the validator from the validator set once included in
+    /// the chain.
+    pub fn sign_voluntary_exit(
+        &self,
+        validator_pubkey: &PublicKey,
+        mut exit: VoluntaryExit,
+    ) -> Option<VoluntaryExit> {
+        exit.signature = self.sign(
+            validator_pubkey,
+            &exit.signed_root(),
+            Domain::VoluntaryExit,
+            exit.epoch,
+        )?;
+
+        Some(exit)
     }
 
     pub fn sign_block(
@@ -154,15 +667,35 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         validator_pubkey: &PublicKey,
         mut block: BeaconBlock<E>,
     ) -> Option<BeaconBlock<E>> {
-        // TODO: check for slashing.
-        self.validators
-            .read()
-            .get(validator_pubkey)
-            .and_then(|validator_dir| {
-                let voting_keypair = validator_dir.voting_keypair.as_ref()?;
-                block.sign(&voting_keypair.sk, &self.fork()?, &self.spec);
-                Some(block)
+        if !self.signing_enabled() || !self.validators.read().contains_key(validator_pubkey) {
+            return None;
+        }
+
+        self.slashing_protection
+            .check_and_insert_block(validator_pubkey, block.slot, block.canonical_root())
+            .map_err(|e| {
+                crit!(
+                    self.log,
+                    "Refusing to sign slashable block";
+                    "error" => format!("{:?}", e),
+                    "slot" => block.slot.as_u64(),
+                )
             })
+            .ok()?;
+
+        block.signature = self.sign(
+            validator_pubkey,
+            &block.signed_root(),
+            Domain::BeaconProposer,
+            block.epoch(),
+        )?;
+
+        let slot = block.slot;
+        self.record_signed(validator_pubkey, |last_signed| {
+            last_signed.block_slot = last_signed.block_slot.max(Some(slot))
+        });
+
+        Some(block)
     }
 
     pub fn sign_attestation(
@@ -171,30 +704,177 @@ impl<T: SlotClock + 'static, E: EthSpec> ValidatorStore<T, E> {
         validator_committee_position: usize,
         attestation: &mut Attestation<E>,
     ) -> Option<()> {
-        // TODO: check for slashing.
-        self.validators
-            .read()
-            .get(validator_pubkey)
-            .and_then(|validator_dir| {
-                let voting_keypair = validator_dir.voting_keypair.as_ref()?;
-
-                attestation
-                    .sign(
-                        &voting_keypair.sk,
-                        validator_committee_position,
-                        &self.fork()?,
-                        &self.spec,
-                    )
-                    .map_err(|e| {
-                        error!(
-                            self.log,
-                            "Error whilst signing attestation";
-                            "error" => format!("{:?}", e)
-                        )
-                    })
-                    .ok()?;
+        if !self.signing_enabled() || !self.validators.read().contains_key(validator_pubkey) {
+            return None;
+        }
+
+        self.slashing_protection
+            .check_and_insert_attestation(
+                validator_pubkey,
+                &attestation.data,
+                Hash256::from_slice(&attestation.data.tree_hash_root()),
+            )
+            .map_err(|e| {
+                crit!(
+                    self.log,
+                    "Refusing to sign slashable attestation";
+                    "error" => format!("{:?}", e),
+                    "slot" => attestation.data.slot.as_u64(),
+                )
+            })
+            .ok()?;
+
+        let signature = self.sign(
+            validator_pubkey,
+            &attestation.data.tree_hash_root(),
+            Domain::BeaconAttester,
+            attestation.data.target.epoch,
+        )?;
+
+        let target_epoch = attestation.data.target.epoch;
+        self.record_signed(validator_pubkey, |last_signed| {
+            last_signed.attestation_target_epoch =
+                last_signed.attestation_target_epoch.max(Some(target_epoch))
+        });
 
-                Some(())
+        attestation
+            .add_signature(&signature, validator_committee_position)
+            .map_err(|e| {
+                error!(
+                    self.log,
+                    "Error whilst signing attestation";
+                    "error" => format!("{:?}", e)
+                )
             })
+            .ok()
+    }
+
+    /// Produces the proof used to determine if the validator is an aggregator at `slot`.
+    pub fn produce_selection_proof(
+        &self,
+        validator_pubkey: &PublicKey,
+        slot: Slot,
+    ) -> Option<SelectionProof> {
+        self.sign(
+            validator_pubkey,
+            &slot.tree_hash_root(),
+            Domain::SelectionProof,
+            slot.epoch(E::slots_per_epoch()),
+        )
+        .map(SelectionProof::from)
+    }
+
+    /// Wraps `aggregate` in a `SignedAggregateAndProof`, signed by the aggregator.
+    ///
+    /// Aggregates are not slashable, so slashing protection is not consulted.
+    pub fn produce_signed_aggregate_and_proof(
+        &self,
+        validator_pubkey: &PublicKey,
+        aggregator_index: u64,
+        aggregate: Attestation<E>,
+        selection_proof: SelectionProof,
+    ) -> Option<SignedAggregateAndProof<E>> {
+        let message = AggregateAndProof {
+            aggregator_index,
+            selection_proof: selection_proof.into(),
+            aggregate,
+        };
+
+        let signature = self.sign(
+            validator_pubkey,
+            &message.signing_root(),
+            Domain::AggregateAndProof,
+            message.aggregate.data.slot.epoch(E::slots_per_epoch()),
+        )?;
+
+        Some(SignedAggregateAndProof { message, signature })
+    }
+}
+
+/// Returns `true` if the directory at `path` belongs to a validator with a remote signer.
+/// Loads the voting keypair of each of the validator `directories`, using the keypairs in
+/// `key_cache` in place of decrypting the keystores where possible.
+///
+/// Keystores are decrypted in parallel, and progress is logged since decrypting hundreds of
+/// keystores may take several minutes. Directories which fail to load are logged and skipped.
+fn load_voting_keypairs(
+    directories: Vec<PathBuf>,
+    secrets_dir: &Path,
+    key_cache: &KeyCache,
+    remote_signers: &RemoteSignersConfig,
+    log: &Logger,
+) -> Vec<(PathBuf, Keypair)> {
+    let total = directories.len();
+    let log_interval = std::cmp::max(total / KEY_LOADING_PROGRESS_STEPS, 1);
+    let loaded = AtomicUsize::new(0);
+
+    if total > 0 {
+        info!(
+            log,
+            "Loading validator keys";
+            "validators" => total,
+            "threads" => rayon::current_num_threads(),
+        );
     }
+
+    directories
+        .into_par_iter()
+        .filter_map(|path| {
+            let cached = ValidatorDirectory::voting_keystore_pubkey(&path)
+                .and_then(|pubkey| key_cache.get(&pubkey));
+            let result = match cached {
+                Some(keypair) => Ok(keypair),
+                None => ValidatorDirectory::load_voting_keypair(&path, secrets_dir),
+            };
+
+            let count = loaded.fetch_add(1, Ordering::Relaxed) + 1;
+            if count % log_interval == 0 || count == total {
+                info!(
+                    log,
+                    "Loading validator keys";
+                    "loaded" => count,
+                    "total" => total,
+                );
+            }
+
+            match result {
+                Ok(keypair) => Some((path, keypair)),
+                // A validator which uses a remote signer is not required to have any keys in its
+                // directory.
+                Err(_) if is_remote_signer_dir(&path, remote_signers) => None,
+                Err(e) => {
+                    error!(
+                        log,
+                        "Failed to load a validator directory";
+                        "error" => e,
+                        "path" => path.to_str(),
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn is_remote_signer_dir(path: &Path, remote_signers: &RemoteSignersConfig) -> bool {
+    remote_signers
+        .signers
+        .iter()
+        .any(|signer| path.file_name() == Some(dir_name(&signer.pubkey).as_ref()))
+}
+
+/// Loads the slashing protection history for each of the given `validators`.
+fn load_slashing_protection(
+    validators: &HashMap<PublicKey, SigningValidator>,
+) -> Result<SlashingProtection, String> {
+    let slashing_protection = SlashingProtection::default();
+
+    validators
+        .iter()
+        .try_for_each(|(pubkey, validator_dir)| {
+            slashing_protection.register_validator(pubkey.clone(), &validator_dir.directory)
+        })
+        .map_err(|e| format!("Unable to load slashing protection: {}", e))?;
+
+    Ok(slashing_protection)
 }
@@ -0,0 +1,208 @@
+#![cfg(test)]
+
+use node_test_rig::{
+    environment::{Environment, EnvironmentBuilder},
+    testing_client_config, ClientConfig, LocalBeaconNode, LocalValidatorClient, ValidatorConfig,
+    DEFAULT_VALIDATOR_COUNT,
+};
+use std::collections::HashSet;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use types::{Epoch, EthSpec, Hash256, MinimalEthSpec, Slot};
+
+type E = MinimalEthSpec;
+
+/// Slots are shortened so that several epochs pass within a few seconds.
+const MILLISECONDS_PER_SLOT: u64 = 1_000;
+
+/// How often to check whether the node has reached some slot.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn build_env() -> Environment<E> {
+    let mut env = EnvironmentBuilder::minimal()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    // Both the beacon node and the validator client read the spec from the environment.
+    env.eth2_config.spec.milliseconds_per_slot = MILLISECONDS_PER_SLOT;
+
+    env
+}
+
+/// Returns a config for a beacon node whose HTTP server listens on a fixed port, so that a
+/// validator client can reconnect to it after a restart.
+fn client_config() -> ClientConfig {
+    let mut config = testing_client_config();
+    config.rest_api.port = TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("should find unused port")
+        .port();
+    config
+}
+
+fn build_node(env: &mut Environment<E>, config: ClientConfig) -> LocalBeaconNode<E> {
+    let context = env.core_context();
+    env.runtime()
+        .block_on(LocalBeaconNode::production(context, config))
+        .expect("should block until node created")
+}
+
+/// Starts a validator client with all the genesis validators, attached to `node`.
+fn build_validator_client(
+    env: &mut Environment<E>,
+    node: &LocalBeaconNode<E>,
+) -> LocalValidatorClient<E> {
+    let socket_addr = node
+        .client
+        .http_listen_addr()
+        .expect("node should have http server");
+
+    let mut config = ValidatorConfig::default();
+    config.http_server = format!("http://{}:{}", socket_addr.ip(), socket_addr.port());
+
+    let context = env.service_context("validator".into());
+    env.runtime()
+        .block_on(LocalValidatorClient::production_with_insecure_keypairs(
+            context,
+            config,
+            &(0..DEFAULT_VALIDATOR_COUNT).collect::<Vec<_>>(),
+        ))
+        .expect("should start validator client")
+}
+
+/// Blocks until the slot clock of `node` reaches `slot`.
+fn wait_until_slot(env: &mut Environment<E>, node: &LocalBeaconNode<E>, slot: Slot) {
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    while beacon_chain.slot().expect("should read slot") < slot {
+        env.runtime()
+            .block_on(Delay::new(Instant::now() + POLL_INTERVAL))
+            .expect("should wait for slot");
+    }
+}
+
+/// Returns the slots of the blocks in the canonical chain of `node`, excluding genesis, and the
+/// total number of attestations they include.
+fn canonical_blocks(node: &LocalBeaconNode<E>) -> (Vec<Slot>, usize) {
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let mut seen = HashSet::<Hash256>::new();
+    let roots = beacon_chain
+        .rev_iter_block_roots()
+        .filter(|(root, slot)| *slot > Slot::new(0) && seen.insert(*root))
+        .collect::<Vec<_>>();
+
+    let attestations = roots
+        .iter()
+        .map(|(root, _)| {
+            beacon_chain
+                .get_block(root)
+                .expect("should read block")
+                .expect("block should exist")
+                .body
+                .attestations
+                .len()
+        })
+        .sum();
+
+    (
+        roots.into_iter().map(|(_, slot)| slot).collect(),
+        attestations,
+    )
+}
+
+fn justified_epoch(node: &LocalBeaconNode<E>) -> Epoch {
+    node.client
+        .beacon_chain()
+        .expect("client should have beacon chain")
+        .head()
+        .beacon_state
+        .current_justified_checkpoint
+        .epoch
+}
+
+#[test]
+fn proposes_blocks_and_includes_attestations() {
+    let mut env = build_env();
+    let node = build_node(&mut env, client_config());
+    let _validator_client = build_validator_client(&mut env, &node);
+
+    let epochs = 4;
+    let end_slot = Epoch::new(epochs).start_slot(E::slots_per_epoch());
+    wait_until_slot(&mut env, &node, end_slot);
+
+    let (block_slots, attestations) = canonical_blocks(&node);
+
+    // Allow for a few slots to be missed while the validator client starts.
+    assert!(
+        block_slots.len() as u64 >= end_slot.as_u64() / 2,
+        "should propose most blocks, proposed {} of {}",
+        block_slots.len(),
+        end_slot
+    );
+    assert!(attestations > 0, "blocks should include attestations");
+    assert!(
+        justified_epoch(&node) > Epoch::new(0),
+        "attestations should justify an epoch"
+    );
+}
+
+#[test]
+fn survives_beacon_node_restart_mid_epoch() {
+    let mut env = build_env();
+    let config = client_config();
+    let node = build_node(&mut env, config.clone());
+    let _validator_client = build_validator_client(&mut env, &node);
+
+    let slots_per_epoch = E::slots_per_epoch();
+    let restart_slot = Epoch::new(1).start_slot(slots_per_epoch) + slots_per_epoch / 2;
+    wait_until_slot(&mut env, &node, restart_slot);
+
+    let stopped = env
+        .runtime()
+        .block_on(node.shutdown())
+        .expect("should shutdown node");
+
+    // Leave the validator client without a beacon node for a couple of slots.
+    env.runtime()
+        .block_on(Delay::new(
+            Instant::now() + Duration::from_millis(2 * MILLISECONDS_PER_SLOT),
+        ))
+        .expect("should wait while node is offline");
+
+    let context = env.core_context();
+    let node = env
+        .runtime()
+        .block_on(LocalBeaconNode::restart(context, config, stopped))
+        .expect("should restart node");
+
+    let end_slot = Epoch::new(5).start_slot(slots_per_epoch);
+    wait_until_slot(&mut env, &node, end_slot);
+
+    let (block_slots, _attestations) = canonical_blocks(&node);
+    let blocks_after_restart = block_slots
+        .iter()
+        .filter(|slot| **slot > restart_slot + 2)
+        .count();
+
+    assert!(
+        blocks_after_restart as u64 >= (end_slot - restart_slot).as_u64() / 2,
+        "validator client should resume proposing after the restart, proposed {}",
+        blocks_after_restart
+    );
+    assert!(
+        justified_epoch(&node) >= Epoch::new(2),
+        "validator client should resume attesting after the restart"
+    );
+}